@@ -46,6 +46,7 @@ fn insert_op(table_id: TableId, table_name: &str, row: ProductValue) -> Database
         table_name: table_name.into(),
         inserts: [row].into(),
         deletes: [].into(),
+        truncated: false,
     }
 }
 
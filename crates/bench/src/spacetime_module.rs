@@ -1,5 +1,6 @@
 use std::{marker::PhantomData, path::Path};
 
+use spacetimedb::config::{DurabilityConfig, MemoryConfig, QuotaConfig};
 use spacetimedb::db::{Config, Storage};
 use spacetimedb_lib::{
     sats::{product, ArrayValue},
@@ -57,6 +58,9 @@ impl<L: ModuleLanguage> BenchDatabase for SpacetimeModule<L> {
         let runtime = start_runtime();
         let config = Config {
             storage: if in_memory { Storage::Memory } else { Storage::Disk },
+            quotas: QuotaConfig::UNLIMITED,
+            durability: DurabilityConfig::DEFAULT,
+            memory: MemoryConfig::DEFAULT,
         };
 
         let module = runtime.block_on(async {
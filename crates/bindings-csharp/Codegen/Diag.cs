@@ -132,4 +132,20 @@ internal static class ErrorDescriptor
             ctx => $"Could not find the specified column {ctx.columnName} in {ctx.typeName}.",
             ctx => ctx.attr
         );
+
+    public static readonly ErrorDescriptor<MethodDeclarationSyntax> ViewReturnType =
+        new(
+            group,
+            "[SpacetimeDB.View] methods must return a value",
+            method => $"View method {method.Identifier} returns void.",
+            method => method.ReturnType
+        );
+
+    public static readonly ErrorDescriptor<MethodDeclarationSyntax> ViewContextParam =
+        new(
+            group,
+            "Views must have a first argument of type ViewContext",
+            method => $"View method {method.Identifier} does not have a ViewContext parameter.",
+            method => method.ParameterList
+        );
 }
@@ -438,7 +438,14 @@ record TableDeclaration : BaseTypeDeclaration<ColumnDeclaration>
         }
     }
 
-    public record struct View(string viewName, string tableName, string view, string getter);
+    public record struct View(
+        string viewName,
+        string tableName,
+        string view,
+        string getter,
+        string readOnlyView,
+        string readOnlyGetter
+    );
 
     public IEnumerable<View> GenerateViews()
     {
@@ -508,7 +515,14 @@ record TableDeclaration : BaseTypeDeclaration<ColumnDeclaration>
                 {{string.Join("\n", GenerateViewFilters(v))}}
             }
             """,
-                $"{SyntaxFacts.GetText(Visibility)} Internal.TableHandles.{v.Name} {v.Name} => new();"
+                $"{SyntaxFacts.GetText(Visibility)} Internal.TableHandles.{v.Name} {v.Name} => new();",
+                $$"""
+            {{SyntaxFacts.GetText(Visibility)}} readonly struct {{v.Name}} {
+                public ulong Count => {{iTable}}.DoCount();
+                public IEnumerable<{{globalName}}> Iter() => {{iTable}}.DoIter();
+            }
+            """,
+                $"{SyntaxFacts.GetText(Visibility)} Internal.ReadOnlyTableHandles.{v.Name} {v.Name} => new();"
             );
         }
     }
@@ -665,6 +679,79 @@ record ReducerDeclaration
     }
 }
 
+record ViewDeclaration
+{
+    public readonly string Name;
+    public readonly string FullName;
+    public readonly EquatableArray<MemberDeclaration> Args;
+    public readonly string ReturnTypeInfo;
+    public readonly string? HttpGet;
+    private readonly bool HasWrongSignature;
+
+    public ViewDeclaration(GeneratorAttributeSyntaxContext context, DiagReporter diag)
+    {
+        var methodSyntax = (MethodDeclarationSyntax)context.TargetNode;
+        var method = (IMethodSymbol)context.TargetSymbol;
+        var attr = context.Attributes.Single().ParseAs<ViewAttribute>();
+
+        if (method.ReturnsVoid)
+        {
+            diag.Report(ErrorDescriptor.ViewReturnType, methodSyntax);
+            HasWrongSignature = true;
+        }
+
+        if (
+            method.Parameters.FirstOrDefault()?.Type
+            is not INamedTypeSymbol { Name: "ViewContext" }
+        )
+        {
+            diag.Report(ErrorDescriptor.ViewContextParam, methodSyntax);
+            HasWrongSignature = true;
+        }
+
+        Name = method.Name;
+        FullName = SymbolToName(method);
+        HttpGet = attr.HttpGet;
+        Args = new(
+            method
+                .Parameters.Skip(1)
+                .Select(p => new MemberDeclaration(p, p.Type, diag))
+                .ToImmutableArray()
+        );
+        // Dummy BSATN implementation for void methods, we already reported an error above.
+        ReturnTypeInfo = method.ReturnsVoid
+            ? "SpacetimeDB.BSATN.Unsupported<object>"
+            : new MemberDeclaration(method, method.ReturnType, diag).TypeInfo;
+    }
+
+    public string GenerateClass()
+    {
+        var invocation = HasWrongSignature
+            ? "throw new System.InvalidOperationException()"
+            : $"__ReturnType.Write(writer, {FullName}({string.Join(
+                ", ",
+                Args.Select(a => $"{a.Name}.Read(reader)").Prepend("(SpacetimeDB.ViewContext)ctx")
+            )}))";
+
+        return $$"""
+            class {{Name}}: SpacetimeDB.Internal.IView {
+                {{MemberDeclaration.GenerateBsatnFields(Accessibility.Private, Args)}}
+                private static readonly {{ReturnTypeInfo}} __ReturnType = new();
+
+                public SpacetimeDB.Internal.RawViewDefV9 MakeViewDef(SpacetimeDB.BSATN.ITypeRegistrar registrar) => new (
+                    nameof({{Name}}),
+                    [{{MemberDeclaration.GenerateDefs(Args)}}],
+                    __ReturnType.GetAlgebraicType(registrar)
+                );
+
+                public void Invoke(BinaryReader reader, BinaryWriter writer, SpacetimeDB.Internal.IViewContext ctx) {
+                    {{invocation}};
+                }
+            }
+            """;
+    }
+}
+
 [Generator]
 public class Module : IIncrementalGenerator
 {
@@ -786,6 +873,26 @@ public class Module : IIncrementalGenerator
             r => r.FullName
         );
 
+        var views = context
+            .SyntaxProvider.ForAttributeWithMetadataName(
+                fullyQualifiedMetadataName: typeof(ViewAttribute).FullName,
+                predicate: (node, ct) => true, // already covered by attribute restrictions
+                transform: (context, ct) =>
+                    context.ParseWithDiags(diag => new ViewDeclaration(context, diag))
+            )
+            .ReportDiagnostics(context)
+            .WithTrackingName("SpacetimeDB.View.Parse");
+
+        var addViews = CollectDistinct(
+            "View",
+            context,
+            views
+                .Select((v, ct) => (v.Name, v.FullName, v.HttpGet, Class: v.GenerateClass()))
+                .WithTrackingName("SpacetimeDB.View.GenerateClass"),
+            v => v.Name,
+            v => v.FullName
+        );
+
         var tableViews = CollectDistinct(
             "Table",
             context,
@@ -797,15 +904,79 @@ public class Module : IIncrementalGenerator
         );
 
         context.RegisterSourceOutput(
-            tableViews.Combine(addReducers),
+            tableViews.Combine(addReducers).Combine(addViews),
             (context, tuple) =>
             {
-                var (tableViews, addReducers) = tuple;
-                // Don't generate the FFI boilerplate if there are no tables or reducers.
-                if (tableViews.Array.IsEmpty && addReducers.Array.IsEmpty)
+                var ((tableViews, addReducers), addViews) = tuple;
+                // Don't generate the FFI boilerplate if there are no tables, reducers or views.
+                if (
+                    tableViews.Array.IsEmpty
+                    && addReducers.Array.IsEmpty
+                    && addViews.Array.IsEmpty
+                )
                 {
                     return;
                 }
+                // The read-only context is only emitted when the module declares views,
+                // to keep the generated code of modules without views unchanged.
+                var viewContext = addViews.Array.IsEmpty
+                    ? ""
+                    : $$"""
+
+                        public sealed record ViewContext : DbContext<ReadOnlyLocal>, Internal.IViewContext {
+                            public readonly Identity CallerIdentity;
+                            public readonly Address? CallerAddress;
+                            public readonly DateTimeOffset Timestamp;
+
+                            // We need this property to be non-static for parity with client SDK.
+                            public Identity Identity => Internal.IReducerContext.GetIdentity();
+
+                            internal ViewContext(Identity identity, Address? address, DateTimeOffset time) {
+                                CallerIdentity = identity;
+                                CallerAddress = address;
+                                Timestamp = time;
+                            }
+                        }
+
+                        namespace Internal.ReadOnlyTableHandles {
+                            {{string.Join("\n", tableViews.Select(v => v.readOnlyView))}}
+                        }
+
+                        public sealed class ReadOnlyLocal {
+                            {{string.Join("\n", tableViews.Select(v => v.readOnlyGetter))}}
+                        }
+                    """;
+                var viewExport = addViews.Array.IsEmpty
+                    ? ""
+                    : """
+
+                        [UnmanagedCallersOnly(EntryPoint = "__call_view__")]
+                        public static SpacetimeDB.Internal.Errno __call_view__(
+                            uint id,
+                            ulong sender_0,
+                            ulong sender_1,
+                            ulong sender_2,
+                            ulong sender_3,
+                            ulong address_0,
+                            ulong address_1,
+                            SpacetimeDB.Internal.DateTimeOffsetRepr timestamp,
+                            SpacetimeDB.Internal.BytesSource args,
+                            SpacetimeDB.Internal.BytesSink result,
+                            SpacetimeDB.Internal.BytesSink error
+                        ) => SpacetimeDB.Internal.Module.__call_view__(
+                            id,
+                            sender_0,
+                            sender_1,
+                            sender_2,
+                            sender_3,
+                            address_0,
+                            address_1,
+                            timestamp,
+                            args,
+                            result,
+                            error
+                        );
+                    """;
                 context.AddSource(
                     "FFI.cs",
                     $$"""
@@ -840,11 +1011,14 @@ public class Module : IIncrementalGenerator
 
                         public sealed class Local {
                             {{string.Join("\n", tableViews.Select(v => v.getter))}}
-                        }
+                        }{{viewContext}}
                     }
 
                     static class ModuleRegistration {
-                        {{string.Join("\n", addReducers.Select(r => r.Class))}}
+                        {{string.Join(
+                            "\n",
+                            addReducers.Select(r => r.Class).Concat(addViews.Select(v => v.Class))
+                        )}}
 
                     #if EXPERIMENTAL_WASM_AOT
                         // In AOT mode we're building a library.
@@ -866,6 +1040,24 @@ public class Module : IIncrementalGenerator
                             {{string.Join(
                                 "\n",
                                 tableViews.Select(t => $"SpacetimeDB.Internal.Module.RegisterTable<{t.tableName}, SpacetimeDB.Internal.TableHandles.{t.viewName}>();")
+                            )}}{{(
+                                addViews.Array.IsEmpty
+                                ? ""
+                                : "\nSpacetimeDB.Internal.Module.SetViewContextConstructor((identity, address, time) => new SpacetimeDB.ViewContext(identity, address, time));\n"
+                                    + string.Join(
+                                        "\n",
+                                        addViews.Select(v =>
+                                            $"SpacetimeDB.Internal.Module.RegisterView<{v.Name}>();"
+                                        )
+                                    )
+                                    + string.Join(
+                                        "",
+                                        addViews
+                                            .Where(v => v.HttpGet is not null)
+                                            .Select(v =>
+                                                $"\nSpacetimeDB.Internal.Module.RegisterHttpRoute(SpacetimeDB.Internal.HttpMethod.Get, {SymbolDisplay.FormatLiteral(v.HttpGet!, true)}, nameof({v.Name}));"
+                                            )
+                                    )
                             )}}
                         }
 
@@ -897,7 +1089,7 @@ public class Module : IIncrementalGenerator
                             timestamp,
                             args,
                             error
-                        );
+                        );{{viewExport}}
                     #endif
                     }
                     """
@@ -108,4 +108,24 @@
     {
         public ReducerKind Kind => kind;
     }
+
+    /// <summary>
+    /// Registers a method as a SpacetimeDB view: a read-only function of the database state and its arguments.
+    ///
+    /// <para>
+    /// The first parameter must be a <c>ViewContext</c>, which only provides read access to the tables.
+    /// The method must return a value of a type supported by SpacetimeDB serialization.
+    /// </para>
+    /// </summary>
+    [AttributeUsage(AttributeTargets.Method, Inherited = false)]
+    public sealed class ViewAttribute : Attribute
+    {
+        /// <summary>
+        /// If set, the view also serves <c>GET</c> requests to this path, e.g. <c>/leaderboard</c>,
+        /// under the HTTP root of the database.
+        ///
+        /// <para>Query parameters are passed as the view's arguments by name, and its result is returned as JSON.</para>
+        /// </summary>
+        public string? HttpGet { get; init; }
+    }
 }
@@ -10,7 +10,20 @@ using SpacetimeDB;
 namespace SpacetimeDB.Internal
 {
 	[SpacetimeDB.Type]
-	public enum RawMiscModuleExportV9
-	{
-	}
+	public partial record RawMiscModuleExportV9 : SpacetimeDB.TaggedEnum<(
+		SpacetimeDB.Internal.RawViewDefV9 View,
+		SpacetimeDB.Internal.RawReducerErrorTypeV9 ReducerErrorType,
+		SpacetimeDB.Internal.RawGeneratedColumnDefV9 GeneratedColumn,
+		SpacetimeDB.Internal.RawTableDurabilityDefV9 TableDurability,
+		SpacetimeDB.Internal.RawHttpRouteDefV9 HttpRoute,
+		SpacetimeDB.Internal.RawReducerArgConstraintDefV9 ReducerArgConstraint,
+		SpacetimeDB.Internal.RawReducerPriorityDefV9 ReducerPriority,
+		SpacetimeDB.Internal.RawScheduleCatchUpDefV9 ScheduleCatchUp,
+		SpacetimeDB.Internal.RawReducerAliasDefV9 ReducerAlias,
+		SpacetimeDB.Internal.RawCounterDefV9 Counter,
+		SpacetimeDB.Internal.RawSoftDeleteDefV9 SoftDelete,
+		SpacetimeDB.Internal.RawCoalesceDefV9 Coalesce,
+		SpacetimeDB.Internal.RawBoundToConnectionDefV9 BoundToConnection,
+		SpacetimeDB.Internal.RawTopicAuthorizerDefV9 TopicAuthorizer
+	)>;
 }
@@ -36,6 +36,13 @@ public enum Errno : short
     SCHEDULE_AT_DELAY_TOO_LONG = 13,
     INDEX_NOT_UNIQUE = 14,
     NO_SUCH_ROW = 15,
+    NO_SUCH_SAVEPOINT = 16,
+    HOST_CALL_FAILURE_VALUE = 17,
+    NOT_A_BLOB_TABLE = 18,
+    BLOB_OUT_OF_BOUNDS = 19,
+    NO_SUCH_SEQUENCE = 20,
+    NO_SUCH_ASSET = 21,
+    NO_SUCH_SESSION_VARIABLE = 22,
 }
 
 #pragma warning disable IDE1006 // Naming Styles - Not applicable to FFI stuff.
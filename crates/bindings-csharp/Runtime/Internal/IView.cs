@@ -0,0 +1,13 @@
+namespace SpacetimeDB.Internal;
+
+using SpacetimeDB.BSATN;
+
+public interface IViewContext { }
+
+public interface IView
+{
+    RawViewDefV9 MakeViewDef(ITypeRegistrar registrar);
+
+    // This one is not static because we need to be able to store IView in a list.
+    void Invoke(BinaryReader reader, BinaryWriter writer, IViewContext ctx);
+}
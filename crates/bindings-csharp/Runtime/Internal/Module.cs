@@ -35,20 +35,33 @@ partial class RawModuleDefV9
     internal void RegisterReducer(RawReducerDefV9 reducer) => Reducers.Add(reducer);
 
     internal void RegisterTable(RawTableDefV9 table) => Tables.Add(table);
+
+    internal void RegisterView(RawViewDefV9 view) =>
+        MiscExports.Add(new RawMiscModuleExportV9.View(view));
+
+    internal void RegisterHttpRoute(RawHttpRouteDefV9 route) =>
+        MiscExports.Add(new RawMiscModuleExportV9.HttpRoute(route));
 }
 
 public static class Module
 {
     private static readonly RawModuleDefV9 moduleDef = new();
     private static readonly List<IReducer> reducers = [];
+    private static readonly List<IView> views = [];
 
     private static Func<Identity, Address?, Random, DateTimeOffset, IReducerContext>? newContext =
         null;
 
+    private static Func<Identity, Address?, DateTimeOffset, IViewContext>? newViewContext = null;
+
     public static void SetReducerContextConstructor(
         Func<Identity, Address?, Random, DateTimeOffset, IReducerContext> ctor
     ) => newContext = ctor;
 
+    public static void SetViewContextConstructor(
+        Func<Identity, Address?, DateTimeOffset, IViewContext> ctor
+    ) => newViewContext = ctor;
+
     readonly struct TypeRegistrar() : ITypeRegistrar
     {
         private readonly Dictionary<Type, AlgebraicType.Ref> types = [];
@@ -88,6 +101,17 @@ public static class Module
         moduleDef.RegisterReducer(reducer.MakeReducerDef(typeRegistrar));
     }
 
+    public static void RegisterView<V>()
+        where V : IView, new()
+    {
+        var view = new V();
+        views.Add(view);
+        moduleDef.RegisterView(view.MakeViewDef(typeRegistrar));
+    }
+
+    public static void RegisterHttpRoute(HttpMethod method, string path, string view) =>
+        moduleDef.RegisterHttpRoute(new(method, path, view));
+
     public static void RegisterTable<T, View>()
         where T : IStructuralReadWrite, new()
         where View : ITableView<View, T>, new()
@@ -207,4 +231,51 @@ public static class Module
             return Errno.HOST_CALL_FAILURE;
         }
     }
+
+    public static Errno __call_view__(
+        uint id,
+        ulong sender_0,
+        ulong sender_1,
+        ulong sender_2,
+        ulong sender_3,
+        ulong address_0,
+        ulong address_1,
+        DateTimeOffsetRepr timestamp,
+        BytesSource args,
+        BytesSink result,
+        BytesSink error
+    )
+    {
+        try
+        {
+            var senderIdentity = Identity.From(
+                MemoryMarshal.AsBytes([sender_0, sender_1, sender_2, sender_3]).ToArray()
+            );
+            var senderAddress = Address.From(
+                MemoryMarshal.AsBytes([address_0, address_1]).ToArray()
+            );
+            var time = timestamp.ToStd();
+
+            var ctx = newViewContext!(senderIdentity, senderAddress, time);
+
+            using var stream = new MemoryStream(args.Consume());
+            using var reader = new BinaryReader(stream);
+            using var output = new MemoryStream();
+            using var writer = new BinaryWriter(output);
+            views[(int)id].Invoke(reader, writer, ctx);
+            if (stream.Position != stream.Length)
+            {
+                throw new Exception("Unrecognised extra bytes in the view arguments");
+            }
+            result.Write(output.ToArray());
+            return Errno.OK; /* no exception */
+        }
+        catch (Exception e)
+        {
+            var error_str = e.ToString();
+            var error_bytes = System.Text.Encoding.UTF8.GetBytes(error_str);
+            error.Write(error_bytes);
+            return Errno.HOST_CALL_FAILURE;
+        }
+    }
 }
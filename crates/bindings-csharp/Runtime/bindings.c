@@ -139,6 +139,16 @@ EXPORT(int16_t, __call_reducer__,
        &sender_0, &sender_1, &sender_2, &sender_3,
        &address_0, &address_1,
        &timestamp, &args, &error);
+
+EXPORT(int16_t, __call_view__,
+       (uint32_t id,
+        uint64_t sender_0, uint64_t sender_1, uint64_t sender_2, uint64_t sender_3,
+        uint64_t address_0, uint64_t address_1,
+        uint64_t timestamp, BytesSource args, BytesSink result, BytesSink error),
+       &id,
+       &sender_0, &sender_1, &sender_2, &sender_3,
+       &address_0, &address_1,
+       &timestamp, &args, &result, &error);
 #endif
 
 // Shims to avoid dependency on WASI in the generated Wasm file.
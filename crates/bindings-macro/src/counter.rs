@@ -0,0 +1,79 @@
+use crate::sym;
+use crate::trigger::table_handle_path;
+use crate::util::{check_duplicate, ident_to_litstr, match_meta};
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::parse::Parser as _;
+use syn::{Ident, Path, Token};
+
+pub(crate) struct CounterArgs {
+    table: Path,
+    group_by: Ident,
+    into_table: Ident,
+    into_column: Ident,
+}
+
+impl CounterArgs {
+    pub(crate) fn parse(input: TokenStream) -> syn::Result<Self> {
+        let mut table = None::<Path>;
+        let mut group_by = None;
+        let mut into = None;
+        syn::meta::parser(|meta| {
+            match_meta!(match meta {
+                sym::table => {
+                    check_duplicate(&table, &meta)?;
+                    table = Some(meta.value()?.parse()?);
+                }
+                sym::group_by => {
+                    check_duplicate(&group_by, &meta)?;
+                    group_by = Some(meta.value()?.parse()?);
+                }
+                sym::into => {
+                    check_duplicate(&into, &meta)?;
+                    let value = meta.value()?;
+                    let into_table = value.parse()?;
+                    value.parse::<Token![.]>()?;
+                    into = Some((into_table, value.parse()?));
+                }
+            });
+            Ok(())
+        })
+        .parse2(input)?;
+        let missing = |arg| syn::Error::new(Span::call_site(), format!("a counter must specify `{arg}`"));
+        let table = table.ok_or_else(|| missing("table"))?;
+        let group_by = group_by.ok_or_else(|| missing("group_by"))?;
+        let (into_table, into_column) = into.ok_or_else(|| missing("into = table.column"))?;
+        Ok(Self {
+            table,
+            group_by,
+            into_table,
+            into_column,
+        })
+    }
+}
+
+pub(crate) fn counter_impl(args: CounterArgs) -> syn::Result<TokenStream> {
+    let CounterArgs {
+        table,
+        group_by,
+        into_table,
+        into_column,
+    } = args;
+
+    let table_handle = table_handle_path(&table);
+    let table_name = &table.segments.last().unwrap().ident;
+    let register_counter_symbol =
+        format!("__preinit__20_register_counter_{table_name}_{group_by}_into_{into_table}_{into_column}");
+    let group_by = ident_to_litstr(&group_by);
+    let into_table = ident_to_litstr(&into_table);
+    let into_column = ident_to_litstr(&into_column);
+
+    Ok(quote! {
+        const _: () = {
+            #[export_name = #register_counter_symbol]
+            extern "C" fn __register_counter() {
+                spacetimedb::rt::register_counter::<#table_handle>(#group_by, #into_table, #into_column)
+            }
+        };
+    })
+}
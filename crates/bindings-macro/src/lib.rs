@@ -1,9 +1,11 @@
 //! Defines procedural macros like `#[spacetimedb::table]`,
 //! simplifying writing SpacetimeDB modules in Rust.
 
+mod counter;
 mod reducer;
 mod sats;
 mod table;
+mod trigger;
 mod util;
 
 use proc_macro::TokenStream as StdTokenStream;
@@ -30,23 +32,48 @@ mod sym {
         };
     }
 
+    symbol!(alias);
     symbol!(at);
+    symbol!(authorize_topic);
     symbol!(auto_inc);
+    symbol!(bound_to_connection);
     symbol!(btree);
+    symbol!(bump);
+    symbol!(catch_up);
     symbol!(client_connected);
     symbol!(client_disconnected);
+    symbol!(coalesce_ms);
     symbol!(columns);
     symbol!(crate_, crate);
+    symbol!(deprecated);
+    symbol!(durability);
+    symbol!(ephemeral);
+    symbol!(generated);
+    symbol!(group_by);
     symbol!(index);
     symbol!(init);
+    symbol!(into);
+    symbol!(max);
+    symbol!(max_len);
+    symbol!(min);
+    symbol!(min_len);
     symbol!(name);
+    symbol!(on_delete);
+    symbol!(on_insert);
+    symbol!(one_of);
     symbol!(primary_key);
+    symbol!(priority);
     symbol!(private);
     symbol!(public);
     symbol!(sats);
     symbol!(scheduled);
+    symbol!(soft_delete);
+    symbol!(start);
+    symbol!(step);
+    symbol!(table);
     symbol!(unique);
     symbol!(update);
+    symbol!(validate);
 
     impl PartialEq<Symbol> for syn::Ident {
         fn eq(&self, sym: &Symbol) -> bool {
@@ -150,11 +177,139 @@ mod sym {
 ///
 /// The reducer cannot be called manually and may not have any parameters.
 /// If an error occurs when initializing, the module will not be published.
+///
+/// # Topic authorizer
+///
+/// `#[spacetimedb::reducer(authorize_topic)]` marks the reducer deciding which clients may subscribe to which topics,
+/// the targets of `ReducerContext::broadcast`.
+/// It must take the topic, a `String`, as its only parameter besides `ReducerContext`,
+/// and is run whenever a client subscribes to a topic, with the client as the sender.
+/// The subscription is refused if the reducer returns an error, which is sent to the client.
+/// There can be at most one per module, and it cannot be called manually.
+/// Without one, any client may subscribe to any topic.
+///
+/// # Priority
+///
+/// `#[spacetimedb::reducer(priority = high)]` or `#[spacetimedb::reducer(priority = low)]`
+/// sets how urgently calls of the reducer are run when the database is busy.
+/// Waiting calls run in order of priority, and in the order they arrived within a priority,
+/// so latency-sensitive gameplay reducers can jump ahead of batch jobs like cleanup or autosave.
+/// A call which is already running is never interrupted.
+/// Scheduled calls use the priority of their reducer.
+/// The default is `priority = normal`.
+///
+/// # Aliases
+///
+/// `#[spacetimedb::reducer(alias = "old_name")]` lets clients keep calling a renamed reducer by its old name,
+/// so the server can be renamed before every client has been upgraded.
+/// `alias` may be given more than once.
+/// Adding `deprecated = "use new_name"` marks the aliases as deprecated:
+/// each call made under one logs the note as a warning, and `spacetime describe` lists it with the reducer.
+///
+/// # Argument constraints
+///
+/// Parameters may be annotated with `#[validate(...)]` to constrain the values a reducer accepts:
+///
+/// ```rust,ignore
+/// #[spacetimedb::reducer]
+/// pub fn set_profile(
+///     ctx: &ReducerContext,
+///     #[validate(min_len = 1, max_len = 32)] name: String,
+///     #[validate(min = 13, max = 150)] age: u16,
+///     #[validate(one_of("light", "dark"))] theme: String,
+/// ) {
+///     // ...
+/// }
+/// ```
+///
+/// * `min_len` and `max_len` bound the length of a string, in characters, or of a `Vec`.
+/// * `min` and `max` bound the value of an integer.
+/// * `one_of` lists the permitted values of a string.
+///
+/// All bounds are inclusive.
+/// Calls whose arguments violate a constraint are rejected without running the reducer,
+/// and generated client code checks the same constraints before sending a call.
 #[proc_macro_attribute]
 pub fn reducer(args: StdTokenStream, item: StdTokenStream) -> StdTokenStream {
     cvt_attr::<ItemFn>(args, item, quote!(), |args, original_function| {
+        // `#[validate]` isn't a real attribute, so it must be removed from the function we emit.
+        let arg_constraints = reducer::take_arg_constraints(original_function);
         let args = reducer::ReducerArgs::parse(args)?;
-        reducer::reducer_impl(args, original_function)
+        reducer::reducer_impl(args, original_function, arg_constraints?)
+    })
+}
+
+/// Marks a function as a trigger, which runs whenever a row of a table is inserted or deleted.
+///
+/// ```ignore
+/// #[spacetimedb::trigger(on_insert = element)]
+/// fn audit(ctx: &ReducerContext, row: &Element) {
+///     ctx.db.audit_log().insert(AuditLog { element_id: row.id, by: ctx.sender });
+/// }
+/// ```
+///
+/// The table is named by its accessor, i.e., the `name` given to `#[spacetimedb::table]`,
+/// which may be a path if the table is defined in another module.
+/// The function must take a `&ReducerContext` and a reference to a row of the table.
+///
+/// * `on_insert = my_table` runs the trigger after each row inserted into `my_table`,
+///   with the row as actually inserted.
+/// * `on_delete = my_table` runs the trigger after each row deleted from `my_table`.
+///
+/// Both may be given to run the same function on either.
+/// An update through a unique column counts as deleting the old row and inserting the new one.
+///
+/// Triggers run within the transaction of the reducer that made the write,
+/// receiving that reducer's context, so a trigger that panics fails the whole reducer.
+/// Triggers may write to tables themselves, including ones which have triggers.
+///
+/// Triggers run only for writes made by module code through table handles.
+/// Writes made by the host or directly through the system calls don't run them, namely:
+///
+/// * SQL `INSERT`, `UPDATE` and `DELETE` statements.
+/// * Rows of `bound_to_connection` tables deleted when their connection disconnects.
+/// * Soft-deleted rows purged once they are older than the table's retention.
+/// * Steps replayed by `Journal::undo` and `Journal::redo`.
+#[proc_macro_attribute]
+pub fn trigger(args: StdTokenStream, item: StdTokenStream) -> StdTokenStream {
+    cvt_attr::<ItemFn>(args, item, quote!(), |args, original_function| {
+        let args = trigger::TriggerArgs::parse(args)?;
+        trigger::trigger_impl(args, original_function)
+    })
+}
+
+/// Declares a count of the rows of a table, grouped by one of its columns,
+/// which the database keeps up to date in a column of another table.
+///
+/// ```ignore
+/// #[spacetimedb::counter(table = element, group_by = canvas_id, into = canvas_stats.element_count)]
+/// #[spacetimedb::table(name = canvas_stats)]
+/// pub struct CanvasStats {
+///     #[primary_key]
+///     canvas_id: u64,
+///     element_count: u32,
+/// }
+/// ```
+///
+/// Whenever a row is inserted into, deleted from or updated in `element`,
+/// the `element_count` of the row of `canvas_stats` whose primary key is the row's `canvas_id` is adjusted
+/// within the same transaction, so the count can be read without scanning `element`.
+/// Rows whose `canvas_id` matches no row of `canvas_stats` aren't counted,
+/// so the row holding a count should be inserted, with a count of zero, before the rows it counts.
+///
+/// * `table` is the accessor of the counted table, which may be a path if it is defined in another module.
+/// * `group_by` is the column of the counted table holding the primary key of the row to count each row under.
+///   It must have the type of that primary key.
+/// * `into` names the table and column holding the counts,
+///   which must be a `u32`, `u64`, `i32` or `i64` column that is neither the primary key nor auto-incremented.
+///
+/// The attribute may be placed on any item, such as the definition of either table.
+/// Changing the counters of a table once it holds rows requires a manual migration.
+#[proc_macro_attribute]
+pub fn counter(args: StdTokenStream, item: StdTokenStream) -> StdTokenStream {
+    cvt_attr::<syn::Item>(args, item, quote!(), |args, _item| {
+        let args = counter::CounterArgs::parse(args)?;
+        counter::counter_impl(args)
     })
 }
 
@@ -216,6 +371,57 @@ fn derive_table_helper_attr() -> Attribute {
 ///    Specify the name of the table in the database, if you want it to be different from
 ///    the name of the struct.
 ///
+/// * `durability = relaxed`
+///
+///    Updates to a table with relaxed durability are broadcast to subscribers as usual,
+///    but are not written to the commitlog, so committing them never waits on the disk.
+///    When the database restarts, the table holds its rows as of the latest snapshot,
+///    and the updates committed since are lost,
+///    so this suits state which is cheap to recompute like cursor positions.
+///    A transaction which only updates relaxed tables takes no offset in the commitlog.
+///    The default is `durability = durable`.
+///
+/// * `ephemeral`
+///
+///    Short for `durability = ephemeral`.
+///    An ephemeral table lives only in memory: like a relaxed one, its updates are not written
+///    to the commitlog, and its rows are also left out of snapshots,
+///    so the table is empty whenever the database restarts.
+///    Subscribers see its updates as usual,
+///    which suits presence, typing indicators, and matchmaking queues.
+///
+/// * `soft_delete = 1000`
+///
+///    The database keeps the history of the table's last 1000 transactions,
+///    so that SQL queries can read the table as it was after any of them,
+///    e.g. `SELECT * FROM my_table AS OF 1234`, where `1234` is the offset of the transaction.
+///    This lets a module offer undo, or show past versions of its data, without copying the table.
+///    By default, a table keeps no history.
+///
+/// * `coalesce_ms = 50`
+///
+///    Subscribers are sent the updates to the table at most once every 50 milliseconds,
+///    with the updates made to a row in the meantime merged into one, by primary key.
+///    This saves bandwidth for rows updated many times a second, like cursor positions,
+///    at the cost of delaying their updates by up to the window.
+///    The table must have a primary key. By default, updates are sent right away.
+///
+/// * `bound_to_connection` or `bound_to_connection = owner`
+///
+///    The rows of the table are bound to client connections: when a client disconnects,
+///    the rows whose `owner` column holds the `Address` of its connection, the `ctx.address` of its calls,
+///    are deleted in the same transaction which records the disconnection,
+///    after the `client_disconnected` reducer, if any, has run.
+///    The column can be left out if it is the table's only column of type `Address`.
+///
+/// * `scheduled(my_reducer, catch_up = fire_once)`
+///
+///    For a scheduled table, `catch_up` decides what happens to the rows whose `ScheduleAt::Time`
+///    passed while the database was offline: `skip_missed` deletes them without calling the reducer,
+///    `fire_once` calls the reducer for the latest of them only, and `fire_all` calls it for each of them.
+///    Rows with a `ScheduleAt::Interval` resume one period after the database starts, whatever the policy.
+///    The default is `catch_up = fire_all`.
+///
 /// # Column (field) attributes
 ///
 /// * `#[auto_inc]`
@@ -229,6 +435,20 @@ fn derive_table_helper_attr() -> Attribute {
 ///    Note that using `#[auto_inc]` on a field does not also imply `#[primary_key]` or `#[unique]`.
 ///    If those semantics are desired, those attributes should also be used.
 ///
+///    The table handle gets a `{field}_sequence()` method returning a [`Sequence`],
+///    which can inspect and advance the sequence.
+///
+/// * `#[auto_inc(start = 1000, step = 10, bump)]`
+///
+///    Like `#[auto_inc]`, but configures the sequence. All options are optional.
+///
+///    - `start`: the first value the sequence generates.
+///    - `step`: how much the sequence moves by for each generated value; may be negative, but not zero.
+///    - `bump`: when a row is inserted or updated with a nonzero value for the field,
+///      the sequence is moved past that value, so it will not later generate it.
+///      Without `bump`, explicitly inserting values ahead of the sequence
+///      can later cause unique constraint violations on insertion of a `0`.
+///
 /// * `#[unique]`
 ///
 ///    Creates an index and unique constraint for the annotated field.
@@ -241,10 +461,23 @@ fn derive_table_helper_attr() -> Attribute {
 ///
 ///    Creates a single-column index with the specified algorithm.
 ///
+/// * `#[generated("lower(name)")]`
+///
+///    Makes the field a generated column, which the database computes from the row's other fields
+///    whenever the row is inserted or updated. Whatever value the reducer writes into the field is ignored.
+///    The expression may name other fields and string literals (`'like this'`),
+///    and call the string functions `lower`, `upper`, `trim` and `concat`.
+///
+///    Generated columns may be combined with `#[unique]` and `#[index(btree)]`,
+///    e.g., to enforce case-insensitive uniqueness of a name.
+///    Note that the row returned by `insert` holds the value the reducer wrote into the field;
+///    read the row back to see the computed value.
+///
 /// [`Serialize`]: https://docs.rs/spacetimedb/latest/spacetimedb/trait.Serialize.html
 /// [`Deserialize`]: https://docs.rs/spacetimedb/latest/spacetimedb/trait.Deserialize.html
 /// [`SpacetimeType`]: https://docs.rs/spacetimedb/latest/spacetimedb/trait.SpacetimeType.html
 /// [`TableType`]: https://docs.rs/spacetimedb/latest/spacetimedb/trait.TableType.html
+/// [`Sequence`]: https://docs.rs/spacetimedb/latest/spacetimedb/table/struct.Sequence.html
 #[proc_macro_attribute]
 pub fn table(args: StdTokenStream, item: StdTokenStream) -> StdTokenStream {
     // put this on the struct so we don't get unknown attribute errors
@@ -285,7 +518,7 @@ pub fn table(args: StdTokenStream, item: StdTokenStream) -> StdTokenStream {
 ///
 /// Provides helper attributes for `#[spacetimedb::table]`, so that we don't get unknown attribute errors.
 #[doc(hidden)]
-#[proc_macro_derive(__TableHelper, attributes(sats, unique, auto_inc, primary_key, index))]
+#[proc_macro_derive(__TableHelper, attributes(sats, unique, auto_inc, primary_key, index, generated))]
 pub fn table_helper(input: StdTokenStream) -> StdTokenStream {
     schema_type(input)
 }
@@ -2,14 +2,50 @@ use crate::sym;
 use crate::util::{check_duplicate, check_duplicate_msg, ident_to_litstr, match_meta};
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, quote_spanned};
-use syn::parse::Parser as _;
+use syn::ext::IdentExt;
+use syn::parse::{ParseStream, Parser as _};
+use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
-use syn::{FnArg, Ident, ItemFn, LitStr};
+use syn::{FnArg, Ident, ItemFn, LitInt, LitStr, Token};
 
 #[derive(Default)]
 pub(crate) struct ReducerArgs {
     name: Option<LitStr>,
     lifecycle: Option<LifecycleReducer>,
+    authorize_topic: Option<Span>,
+    priority: Option<ReducerPriority>,
+    aliases: Vec<LitStr>,
+    deprecated: Option<LitStr>,
+}
+
+enum ReducerPriority {
+    High(Span),
+    Normal(Span),
+    Low(Span),
+}
+
+impl ReducerPriority {
+    fn parse_meta(meta: syn::meta::ParseNestedMeta) -> syn::Result<Self> {
+        let ident: Ident = meta.value()?.parse()?;
+        let span = ident.span();
+        match &*ident.to_string() {
+            "high" => Ok(ReducerPriority::High(span)),
+            "normal" => Ok(ReducerPriority::Normal(span)),
+            "low" => Ok(ReducerPriority::Low(span)),
+            _ => Err(syn::Error::new(span, "expected `high`, `normal` or `low`")),
+        }
+    }
+
+    fn to_value(&self) -> TokenStream {
+        let (ReducerPriority::High(span) | ReducerPriority::Normal(span) | ReducerPriority::Low(span)) = *self;
+        let name = match self {
+            ReducerPriority::High(_) => "High",
+            ReducerPriority::Normal(_) => "Normal",
+            ReducerPriority::Low(_) => "Low",
+        };
+        let ident = Ident::new(name, span);
+        quote_spanned!(span => spacetimedb::rt::ReducerPriority::#ident)
+    }
 }
 
 enum LifecycleReducer {
@@ -47,19 +83,136 @@ impl ReducerArgs {
                 sym::client_connected => set_lifecycle(LifecycleReducer::ClientConnected)?,
                 sym::client_disconnected => set_lifecycle(LifecycleReducer::ClientDisconnected)?,
                 sym::update => set_lifecycle(LifecycleReducer::Update)?,
+                sym::authorize_topic => {
+                    check_duplicate(&args.authorize_topic, &meta)?;
+                    args.authorize_topic = Some(meta.path.span());
+                }
                 sym::name => {
                     check_duplicate(&args.name, &meta)?;
                     args.name = Some(meta.value()?.parse()?);
                 }
+                sym::priority => {
+                    check_duplicate(&args.priority, &meta)?;
+                    args.priority = Some(ReducerPriority::parse_meta(meta)?);
+                }
+                sym::alias => args.aliases.push(meta.value()?.parse()?),
+                sym::deprecated => {
+                    check_duplicate(&args.deprecated, &meta)?;
+                    args.deprecated = Some(meta.value()?.parse()?);
+                }
             });
             Ok(())
         })
         .parse2(input)?;
+        if let (Some(span), Some(_)) = (args.authorize_topic, &args.lifecycle) {
+            return Err(syn::Error::new(span, "a lifecycle reducer cannot authorize topics"));
+        }
+        if let (Some(deprecated), []) = (&args.deprecated, &*args.aliases) {
+            return Err(syn::Error::new(
+                deprecated.span(),
+                "`deprecated` applies to the reducer's aliases, but it has no `alias`",
+            ));
+        }
         Ok(args)
     }
 }
 
-pub(crate) fn reducer_impl(args: ReducerArgs, original_function: &ItemFn) -> syn::Result<TokenStream> {
+/// A constraint on a reducer argument, declared with `#[validate(...)]`.
+pub(crate) struct ArgConstraint {
+    /// The position of the argument, not counting the `ReducerContext`.
+    arg: u16,
+    kind: ArgConstraintKind,
+}
+
+enum ArgConstraintKind {
+    MinLength(u32),
+    MaxLength(u32),
+    Min(i128),
+    Max(i128),
+    OneOf(Vec<LitStr>),
+}
+
+impl ArgConstraint {
+    fn to_desc(&self) -> TokenStream {
+        let arg = self.arg;
+        let kind = match &self.kind {
+            ArgConstraintKind::MinLength(n) => quote!(MinLength(#n)),
+            ArgConstraintKind::MaxLength(n) => quote!(MaxLength(#n)),
+            ArgConstraintKind::Min(n) => quote!(Min(#n)),
+            ArgConstraintKind::Max(n) => quote!(Max(#n)),
+            ArgConstraintKind::OneOf(values) => quote!(OneOf(&[#(#values),*])),
+        };
+        quote!(spacetimedb::rt::ArgConstraintDesc {
+            arg: #arg,
+            kind: spacetimedb::rt::ArgConstraintKind::#kind,
+        })
+    }
+}
+
+/// Parses an integer literal, which may be negated.
+fn parse_signed_int(input: ParseStream) -> syn::Result<i128> {
+    let negative = input.parse::<Option<Token![-]>>()?.is_some();
+    let lit = input.parse::<LitInt>()?;
+    let value = lit.base10_parse::<i128>()?;
+    Ok(if negative { -value } else { value })
+}
+
+/// Removes the `#[validate(...)]` attributes from the parameters of `original_function`,
+/// returning the constraints they declare.
+pub(crate) fn take_arg_constraints(original_function: &mut ItemFn) -> syn::Result<Vec<ArgConstraint>> {
+    let mut constraints = vec![];
+    let mut errors = vec![];
+    for (i, arg) in original_function.sig.inputs.iter_mut().enumerate() {
+        let FnArg::Typed(arg) = arg else { continue };
+        let (validate_attrs, attrs): (Vec<_>, Vec<_>) =
+            arg.attrs.drain(..).partition(|attr| attr.path() == sym::validate);
+        arg.attrs = attrs;
+        for attr in validate_attrs {
+            let Some(arg) = i.checked_sub(1) else {
+                errors.push(syn::Error::new_spanned(
+                    attr,
+                    "the `ReducerContext` argument cannot be validated",
+                ));
+                continue;
+            };
+            let arg = arg as u16;
+            let mut push = |kind| constraints.push(ArgConstraint { arg, kind });
+            let result = attr.parse_nested_meta(|meta| {
+                match_meta!(match meta {
+                    sym::min_len => push(ArgConstraintKind::MinLength(
+                        meta.value()?.parse::<LitInt>()?.base10_parse()?
+                    )),
+                    sym::max_len => push(ArgConstraintKind::MaxLength(
+                        meta.value()?.parse::<LitInt>()?.base10_parse()?
+                    )),
+                    sym::min => push(ArgConstraintKind::Min(parse_signed_int(meta.value()?)?)),
+                    sym::max => push(ArgConstraintKind::Max(parse_signed_int(meta.value()?)?)),
+                    sym::one_of => {
+                        let content;
+                        syn::parenthesized!(content in meta.input);
+                        let values = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                        push(ArgConstraintKind::OneOf(values.into_iter().collect()));
+                    }
+                });
+                Ok(())
+            });
+            errors.extend(result.err());
+        }
+    }
+    match errors.into_iter().reduce(|mut a, b| {
+        a.combine(b);
+        a
+    }) {
+        Some(err) => Err(err),
+        None => Ok(constraints),
+    }
+}
+
+pub(crate) fn reducer_impl(
+    args: ReducerArgs,
+    original_function: &ItemFn,
+    arg_constraints: Vec<ArgConstraint>,
+) -> syn::Result<TokenStream> {
     let func_name = &original_function.sig.ident;
     let vis = &original_function.vis;
 
@@ -90,7 +243,7 @@ pub(crate) fn reducer_impl(args: ReducerArgs, original_function: &ItemFn) -> syn
     // Extract all function parameter names.
     let opt_arg_names = typed_args.iter().map(|arg| {
         if let syn::Pat::Ident(i) = &*arg.pat {
-            let name = i.ident.to_string();
+            let name = i.ident.unraw().to_string();
             quote!(Some(#name))
         } else {
             quote!(None)
@@ -108,6 +261,12 @@ pub(crate) fn reducer_impl(args: ReducerArgs, original_function: &ItemFn) -> syn
     }
     .into_iter();
 
+    let arg_constraint_descs = arg_constraints.iter().map(ArgConstraint::to_desc);
+    let priority = args.priority.iter().map(ReducerPriority::to_value);
+    let aliases = &args.aliases;
+    let deprecated = args.deprecated.iter();
+    let authorizes_topics = args.authorize_topic.is_some();
+
     let register_describer_symbol = format!("__preinit__20_register_describer_{}", reducer_name.value());
 
     let lt_params = &original_function.sig.generics;
@@ -116,7 +275,7 @@ pub(crate) fn reducer_impl(args: ReducerArgs, original_function: &ItemFn) -> syn
     let generated_describe_function = quote! {
         #[export_name = #register_describer_symbol]
         pub extern "C" fn __register_describer() {
-            spacetimedb::rt::register_reducer::<_, #func_name>(#func_name)
+            spacetimedb::rt::register_reducer::<_, #func_name, _>(#func_name)
         }
     };
 
@@ -134,7 +293,7 @@ pub(crate) fn reducer_impl(args: ReducerArgs, original_function: &ItemFn) -> syn
             }
         };
         impl #func_name {
-            fn invoke(__ctx: spacetimedb::ReducerContext, __args: &[u8]) -> spacetimedb::ReducerResult {
+            fn invoke(__ctx: spacetimedb::ReducerContext, __args: &[u8]) -> Result<(), spacetimedb::rt::ReducerFailure> {
                 spacetimedb::rt::invoke_reducer(#func_name, __ctx, __args)
             }
         }
@@ -143,6 +302,11 @@ pub(crate) fn reducer_impl(args: ReducerArgs, original_function: &ItemFn) -> syn
             const NAME: &'static str = #reducer_name;
             #(const LIFECYCLE: Option<spacetimedb::rt::LifecycleReducer> = Some(#lifecycle);)*
             const ARG_NAMES: &'static [Option<&'static str>] = &[#(#opt_arg_names),*];
+            const ARG_CONSTRAINTS: &'static [spacetimedb::rt::ArgConstraintDesc] = &[#(#arg_constraint_descs),*];
+            #(const PRIORITY: spacetimedb::rt::ReducerPriority = #priority;)*
+            const ALIASES: &'static [&'static str] = &[#(#aliases),*];
+            #(const DEPRECATED: Option<&'static str> = Some(#deprecated);)*
+            const AUTHORIZES_TOPICS: bool = #authorizes_topics;
             const INVOKE: spacetimedb::rt::ReducerFn = #func_name::invoke;
         }
     })
@@ -3,6 +3,7 @@ extern crate proc_macro;
 
 use proc_macro2::{Span, TokenStream};
 use quote::{quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
 use syn::punctuated::Pair;
 use syn::spanned::Spanned;
 use syn::{LitStr, Token};
@@ -54,7 +55,7 @@ pub(crate) fn sats_type_from_derive(
             let fields = struc.fields.iter().map(|field| SatsField {
                 ident: field.ident.as_ref(),
                 vis: &field.vis,
-                name: field.ident.as_ref().map(syn::Ident::to_string),
+                name: field.ident.as_ref().map(|ident| ident.unraw().to_string()),
                 ty: &field.ty,
                 original_attrs: &field.attrs,
             });
@@ -65,7 +66,7 @@ pub(crate) fn sats_type_from_derive(
                 let (member, ty) = variant_data(var)?.unzip();
                 Ok(SatsVariant {
                     ident: &var.ident,
-                    name: var.ident.to_string(),
+                    name: var.ident.unraw().to_string(),
                     ty,
                     member,
                     original_attrs: &var.attrs,
@@ -255,6 +256,21 @@ pub(crate) fn derive_deserialize(ty: &SatsType<'_>) -> TokenStream {
             let field_strings = fields.iter().map(|f| f.name.as_deref().unwrap()).collect::<Vec<_>>();
             let field_types = fields.iter().map(|f| &f.ty);
             let field_types2 = field_types.clone();
+            let iter_n4 = 0usize..;
+
+            // The in-place visitor borrows the value being deserialized into.
+            let place_lifetime = syn::Lifetime::new("'__place", Span::call_site());
+            let mut place_generics = ty.generics.clone();
+            place_generics
+                .params
+                .insert(0, syn::LifetimeParam::new(place_lifetime.clone()).into());
+            let (place_impl_generics, place_ty_generics, _) = place_generics.split_for_impl();
+            let mut de_place_generics = de_generics.clone();
+            de_place_generics
+                .params
+                .insert(1, syn::LifetimeParam::new(place_lifetime.clone()).into());
+            let (de_place_impl_generics, _, _) = de_place_generics.split_for_impl();
+
             quote! {
                 #[allow(non_camel_case_types)]
                 #[allow(clippy::all)]
@@ -265,6 +281,41 @@ pub(crate) fn derive_deserialize(ty: &SatsType<'_>) -> TokenStream {
                                 _marker: std::marker::PhantomData::<fn() -> #name #ty_generics>,
                             })
                         }
+
+                        fn deserialize_in_place<D: #spacetimedb_lib::de::Deserializer<'de>>(deserializer: D, __place: &mut Self) -> Result<(), D::Error> {
+                            deserializer.deserialize_product(__ProductInPlaceVisitor { place: __place })
+                        }
+                    }
+
+                    struct __ProductInPlaceVisitor #place_impl_generics #where_clause {
+                        place: &#place_lifetime mut #name #ty_generics,
+                    }
+
+                    impl #de_place_impl_generics #spacetimedb_lib::de::ProductVisitor<'de> for __ProductInPlaceVisitor #place_ty_generics #de_where_clause {
+                        type Output = ();
+
+                        fn product_name(&self) -> Option<&str> {
+                            Some(#tuple_name)
+                        }
+                        fn product_len(&self) -> usize {
+                            #n_fields
+                        }
+
+                        fn visit_seq_product<A: #spacetimedb_lib::de::SeqProductAccess<'de>>(mut self, mut tup: A) -> Result<Self::Output, A::Error> {
+                            #(
+                                if tup.next_element_seed(#spacetimedb_lib::de::InPlace(&mut self.place.#field_names))?.is_none() {
+                                    return Err(#spacetimedb_lib::de::Error::invalid_product_length(#iter_n4, &self));
+                                }
+                            )*
+                            Ok(())
+                        }
+                        fn visit_named_product<A: #spacetimedb_lib::de::NamedProductAccess<'de>>(mut self, __prod: A) -> Result<Self::Output, A::Error> {
+                            // Fields may come in any order, so just deserialize a fresh value.
+                            *self.place = __ProductVisitor {
+                                _marker: std::marker::PhantomData,
+                            }.visit_named_product(__prod)?;
+                            Ok(())
+                        }
                     }
 
                     struct __ProductVisitor #impl_generics #where_clause {
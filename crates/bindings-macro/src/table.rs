@@ -14,6 +14,10 @@ use syn::{parse_quote, Ident, Path, Token};
 
 pub(crate) struct TableArgs {
     access: Option<TableAccess>,
+    durability: Option<TableDurability>,
+    soft_delete: Option<u64>,
+    coalesce_ms: Option<u64>,
+    bound_to_connection: Option<Option<Ident>>,
     scheduled: Option<ScheduledArg>,
     name: Ident,
     indices: Vec<IndexArg>,
@@ -36,10 +40,75 @@ impl TableAccess {
     }
 }
 
+enum TableDurability {
+    Durable(Span),
+    Relaxed(Span),
+    Ephemeral(Span),
+}
+
+impl TableDurability {
+    fn parse_meta(meta: ParseNestedMeta) -> syn::Result<Self> {
+        let ident: Ident = meta.value()?.parse()?;
+        let span = ident.span();
+        match &*ident.to_string() {
+            "durable" => Ok(TableDurability::Durable(span)),
+            "relaxed" => Ok(TableDurability::Relaxed(span)),
+            "ephemeral" => Ok(TableDurability::Ephemeral(span)),
+            _ => Err(syn::Error::new(span, "expected `durable`, `relaxed`, or `ephemeral`")),
+        }
+    }
+
+    fn to_value(&self) -> TokenStream {
+        let (TableDurability::Durable(span) | TableDurability::Relaxed(span) | TableDurability::Ephemeral(span)) =
+            *self;
+        let name = match self {
+            TableDurability::Durable(_) => "Durable",
+            TableDurability::Relaxed(_) => "Relaxed",
+            TableDurability::Ephemeral(_) => "Ephemeral",
+        };
+        let ident = Ident::new(name, span);
+        quote_spanned!(span => spacetimedb::table::TableDurability::#ident)
+    }
+}
+
 struct ScheduledArg {
     span: Span,
     reducer: Path,
     at: Option<Ident>,
+    catch_up: Option<CatchUpPolicy>,
+}
+
+enum CatchUpPolicy {
+    SkipMissed(Span),
+    FireOnce(Span),
+    FireAll(Span),
+}
+
+impl CatchUpPolicy {
+    fn parse_meta(meta: ParseNestedMeta) -> syn::Result<Self> {
+        let ident: Ident = meta.value()?.parse()?;
+        let span = ident.span();
+        match &*ident.to_string() {
+            "skip_missed" => Ok(CatchUpPolicy::SkipMissed(span)),
+            "fire_once" => Ok(CatchUpPolicy::FireOnce(span)),
+            "fire_all" => Ok(CatchUpPolicy::FireAll(span)),
+            _ => Err(syn::Error::new(
+                span,
+                "expected `skip_missed`, `fire_once` or `fire_all`",
+            )),
+        }
+    }
+
+    fn to_value(&self) -> TokenStream {
+        let (CatchUpPolicy::SkipMissed(span) | CatchUpPolicy::FireOnce(span) | CatchUpPolicy::FireAll(span)) = *self;
+        let name = match self {
+            CatchUpPolicy::SkipMissed(_) => "SkipMissed",
+            CatchUpPolicy::FireOnce(_) => "FireOnce",
+            CatchUpPolicy::FireAll(_) => "FireAll",
+        };
+        let ident = Ident::new(name, span);
+        quote_spanned!(span => spacetimedb::table::CatchUpPolicy::#ident)
+    }
 }
 
 struct IndexArg {
@@ -55,6 +124,10 @@ enum IndexType {
 impl TableArgs {
     pub(crate) fn parse(input: TokenStream, struct_ident: &Ident) -> syn::Result<Self> {
         let mut access = None;
+        let mut durability = None;
+        let mut soft_delete = None;
+        let mut coalesce_ms = None;
+        let mut bound_to_connection = None;
         let mut scheduled = None;
         let mut name = None;
         let mut indices = Vec::new();
@@ -73,6 +146,33 @@ impl TableArgs {
                     let value = meta.value()?;
                     name = Some(value.parse()?);
                 }
+                sym::durability => {
+                    check_duplicate_msg(&durability, &meta, "already specified durability")?;
+                    durability = Some(TableDurability::parse_meta(meta)?);
+                }
+                sym::ephemeral => {
+                    check_duplicate_msg(&durability, &meta, "already specified durability")?;
+                    durability = Some(TableDurability::Ephemeral(meta.path.span()));
+                }
+                sym::soft_delete => {
+                    check_duplicate(&soft_delete, &meta)?;
+                    let retention = meta.value()?.parse::<syn::LitInt>()?;
+                    soft_delete = Some(retention.base10_parse()?);
+                }
+                sym::coalesce_ms => {
+                    check_duplicate(&coalesce_ms, &meta)?;
+                    let window_ms = meta.value()?.parse::<syn::LitInt>()?;
+                    coalesce_ms = Some(window_ms.base10_parse()?);
+                }
+                sym::bound_to_connection => {
+                    check_duplicate(&bound_to_connection, &meta)?;
+                    let column = if meta.input.peek(Token![=]) {
+                        Some(meta.value()?.parse()?)
+                    } else {
+                        None
+                    };
+                    bound_to_connection = Some(column);
+                }
                 sym::index => indices.push(IndexArg::parse_meta(meta)?),
                 sym::scheduled => {
                     check_duplicate(&scheduled, &meta)?;
@@ -91,6 +191,10 @@ impl TableArgs {
         })?;
         Ok(TableArgs {
             access,
+            durability,
+            soft_delete,
+            coalesce_ms,
+            bound_to_connection,
             scheduled,
             name,
             indices,
@@ -103,6 +207,7 @@ impl ScheduledArg {
         let span = meta.path.span();
         let mut reducer = None;
         let mut at = None;
+        let mut catch_up = None;
 
         meta.parse_nested_meta(|meta| {
             if meta.input.peek(syn::Token![=]) || meta.input.peek(syn::token::Paren) {
@@ -112,6 +217,10 @@ impl ScheduledArg {
                         let ident = meta.value()?.parse()?;
                         at = Some(ident);
                     }
+                    sym::catch_up => {
+                        check_duplicate(&catch_up, &meta)?;
+                        catch_up = Some(CatchUpPolicy::parse_meta(meta)?);
+                    }
                 })
             } else {
                 check_duplicate_msg(&reducer, &meta, "can only specify one scheduled reducer")?;
@@ -123,7 +232,12 @@ impl ScheduledArg {
         let reducer = reducer.ok_or_else(|| {
             meta.error("must specify scheduled reducer associated with the table: scheduled(reducer_name)")
         })?;
-        Ok(Self { span, reducer, at })
+        Ok(Self {
+            span,
+            reducer,
+            at,
+            catch_up,
+        })
     }
 }
 
@@ -212,13 +326,13 @@ impl IndexArg {
             ValidatedIndexType::BTree { cols } => {
                 let cols = cols
                     .iter()
-                    .map(|col| col.field.ident.unwrap().to_string())
+                    .map(|col| col.field.ident.unwrap().unraw().to_string())
                     .collect::<Vec<_>>();
                 let cols = cols.join("_");
                 format!("{table_name}_{cols}_idx_btree")
             }
             ValidatedIndexType::UniqueBTree { col } => {
-                let col = col.field.ident.unwrap().to_string();
+                let col = col.field.ident.unwrap().unraw().to_string();
                 format!("{table_name}_{col}_idx_btree")
             }
         };
@@ -396,9 +510,10 @@ fn find_column<'a, 'b>(cols: &'a [Column<'b>], name: &Ident) -> syn::Result<&'a
 
 enum ColumnAttr {
     Unique(Span),
-    AutoInc(Span),
+    AutoInc(AutoIncArg),
     PrimaryKey(Span),
     Index(IndexArg),
+    Generated(Span, syn::LitStr),
 }
 
 impl ColumnAttr {
@@ -413,17 +528,81 @@ impl ColumnAttr {
             attr.meta.require_path_only()?;
             Some(ColumnAttr::Unique(ident.span()))
         } else if ident == sym::auto_inc {
-            attr.meta.require_path_only()?;
-            Some(ColumnAttr::AutoInc(ident.span()))
+            let auto_inc = AutoIncArg::parse_auto_inc_attr(ident.span(), attr)?;
+            Some(ColumnAttr::AutoInc(auto_inc))
         } else if ident == sym::primary_key {
             attr.meta.require_path_only()?;
             Some(ColumnAttr::PrimaryKey(ident.span()))
+        } else if ident == sym::generated {
+            let expr = attr.parse_args::<syn::LitStr>()?;
+            Some(ColumnAttr::Generated(ident.span(), expr))
         } else {
             None
         })
     }
 }
 
+/// The options of an `#[auto_inc]` column attribute.
+struct AutoIncArg {
+    span: Span,
+    start: Option<i128>,
+    step: i128,
+    bump: bool,
+}
+
+impl AutoIncArg {
+    fn parse_auto_inc_attr(span: Span, attr: &syn::Attribute) -> syn::Result<Self> {
+        let mut start = None;
+        let mut step = None;
+        let mut bump = None;
+        if let syn::Meta::List(_) = &attr.meta {
+            attr.parse_nested_meta(|meta| {
+                match_meta!(match meta {
+                    sym::start => {
+                        check_duplicate(&start, &meta)?;
+                        start = Some(parse_i128(&meta)?);
+                    }
+                    sym::step => {
+                        check_duplicate(&step, &meta)?;
+                        let value = parse_i128(&meta)?;
+                        if value == 0 {
+                            return Err(meta.error("the step of a sequence must not be zero"));
+                        }
+                        step = Some(value);
+                    }
+                    sym::bump => {
+                        check_duplicate(&bump, &meta)?;
+                        bump = Some(());
+                    }
+                });
+                Ok(())
+            })?;
+        } else {
+            attr.meta.require_path_only()?;
+        }
+        Ok(AutoIncArg {
+            span,
+            start,
+            step: step.unwrap_or(1),
+            bump: bump.is_some(),
+        })
+    }
+}
+
+/// Parses `= <integer>`, where the integer may be negative.
+fn parse_i128(meta: &ParseNestedMeta) -> syn::Result<i128> {
+    let value = meta.value()?;
+    let neg = value.parse::<Option<Token![-]>>()?.is_some();
+    let lit = value.parse::<syn::LitInt>()?;
+    let n = lit.base10_parse::<i128>()?;
+    if neg {
+        n.checked_neg()
+            .ok_or_else(|| syn::Error::new(lit.span(), "number too small to fit in an `i128`"))
+    } else {
+        Ok(n)
+    }
+}
+
 pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::Result<TokenStream> {
     let vis = &item.vis;
     let sats_ty = sats::sats_type_from_derive(item, quote!(spacetimedb::spacetimedb_lib))?;
@@ -463,6 +642,7 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
     let mut columns = vec![];
     let mut unique_columns = vec![];
     let mut sequenced_columns = vec![];
+    let mut generated_columns = vec![];
     let mut primary_key_column = None;
 
     for (i, field) in fields.iter().enumerate() {
@@ -472,6 +652,7 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
         let mut unique = None;
         let mut auto_inc = None;
         let mut primary_key = None;
+        let mut generated = None;
         for attr in field.original_attrs {
             let Some(attr) = ColumnAttr::parse(attr, field_ident)? else {
                 continue;
@@ -481,15 +662,19 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
                     check_duplicate(&unique, span)?;
                     unique = Some(span);
                 }
-                ColumnAttr::AutoInc(span) => {
-                    check_duplicate(&auto_inc, span)?;
-                    auto_inc = Some(span);
+                ColumnAttr::AutoInc(arg) => {
+                    check_duplicate(&auto_inc, arg.span)?;
+                    auto_inc = Some(arg);
                 }
                 ColumnAttr::PrimaryKey(span) => {
                     check_duplicate(&primary_key, span)?;
                     primary_key = Some(span);
                 }
                 ColumnAttr::Index(index_arg) => args.indices.push(index_arg),
+                ColumnAttr::Generated(span, expr) => {
+                    check_duplicate(&generated, span)?;
+                    generated = Some((span, expr));
+                }
             }
         }
 
@@ -508,8 +693,14 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
                 },
             });
         }
-        if auto_inc.is_some() {
-            sequenced_columns.push(column);
+        if let Some((span, expr)) = generated {
+            if auto_inc.is_some() {
+                return Err(syn::Error::new(span, "a generated column cannot also be `#[auto_inc]`"));
+            }
+            generated_columns.push((column, expr));
+        }
+        if let Some(auto_inc) = auto_inc {
+            sequenced_columns.push((column, auto_inc));
         }
         if let Some(span) = primary_key {
             check_duplicate_msg(&primary_key_column, span, "can only have one primary key per table")?;
@@ -543,7 +734,7 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
 
     // Generate `integrate_generated_columns`
     // which will integrate all generated auto-inc col values into `_row`.
-    let integrate_gen_col = sequenced_columns.iter().map(|col| {
+    let integrate_gen_col = sequenced_columns.iter().map(|(col, _)| {
         let field = col.field.ident.unwrap();
         quote_spanned!(field.span()=>
             spacetimedb::table::SequenceTrigger::maybe_decode_into(&mut __row.#field, &mut __generated_cols);
@@ -555,10 +746,66 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
         }
     );
 
+    // Generate `bump_sequences`
+    // which will move the sequences of `#[auto_inc(bump)]` columns past the values in `_row`.
+    let bump_seq = sequenced_columns.iter().filter(|(_, seq)| seq.bump).map(|(col, _)| {
+        let field = col.field.ident.unwrap();
+        let col_id = col.index;
+        quote_spanned!(field.span()=>
+            spacetimedb::table::bump_sequence::<Self>(#col_id, &__row.#field);
+        )
+    });
+    let bump_sequences = quote_spanned!(item.span() =>
+        fn bump_sequences(__row: &#row_type) {
+            #(#bump_seq)*
+        }
+    );
+
+    // Generate a `{column}_sequence` accessor for each `#[auto_inc]` column.
+    let sequence_accessors = sequenced_columns.iter().map(|(col, _)| {
+        let vis = col.field.vis;
+        let column_ident = col.field.ident.unwrap();
+        let accessor = format_ident!("{}_sequence", column_ident.unraw());
+        let col_id = col.index;
+        let doc = format!(
+            "Gets the [`Sequence`][spacetimedb::Sequence] for the \
+             [`{column_ident}`][{original_struct_ident}::{column_ident}] column."
+        );
+        quote! {
+            #[doc = #doc]
+            #vis fn #accessor(&self) -> spacetimedb::Sequence<Self> {
+                spacetimedb::Sequence::__new(#col_id)
+            }
+        }
+    });
+
     let table_access = args.access.iter().map(|acc| acc.to_value());
+    let table_durability = args.durability.iter().map(|dur| dur.to_value());
+    let soft_delete = args.soft_delete.iter();
+    let coalesce_ms = args.coalesce_ms.iter();
     let unique_col_ids = unique_columns.iter().map(|col| col.index);
     let primary_col_id = primary_key_column.iter().map(|col| col.index);
-    let sequence_col_ids = sequenced_columns.iter().map(|col| col.index);
+    let sequence_descs = sequenced_columns.iter().map(|(col, seq)| {
+        let col_id = col.index;
+        let start = match seq.start {
+            Some(start) => quote!(Some(#start)),
+            None => quote!(None),
+        };
+        let increment = seq.step;
+        quote!(spacetimedb::table::SequenceDesc {
+            column: #col_id,
+            start: #start,
+            increment: #increment,
+        })
+    });
+
+    let generated_column_descs = generated_columns.iter().map(|(col, expr)| {
+        let col_id = col.index;
+        quote!(spacetimedb::table::GeneratedColumnDesc {
+            column: #col_id,
+            expr: #expr,
+        })
+    });
 
     let (schedule, schedule_typecheck) = args
         .scheduled
@@ -596,9 +843,14 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
 
             let reducer = &sched.reducer;
             let scheduled_at_id = scheduled_at_column.index;
+            let catch_up = sched.catch_up.as_ref().map_or_else(
+                || quote!(spacetimedb::table::CatchUpPolicy::FireAll),
+                |catch_up| catch_up.to_value(),
+            );
             let desc = quote!(spacetimedb::table::ScheduleDesc {
                 reducer_name: <#reducer as spacetimedb::rt::ReducerInfo>::NAME,
                 scheduled_at_column: #scheduled_at_id,
+                catch_up: #catch_up,
             });
 
             let primary_key_ty = primary_key_column.ty;
@@ -615,6 +867,28 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
         .unzip();
     let schedule = schedule.into_iter();
 
+    let (bound_to_connection, bound_to_connection_typecheck) = args
+        .bound_to_connection
+        .as_ref()
+        .map(|column| {
+            // Without a named column, the module's validation picks the table's only `Address` column.
+            let Some(column) = column else {
+                let desc = quote!(spacetimedb::table::BoundToConnectionDesc { column: None });
+                return Ok((desc, None));
+            };
+            let column = find_column(&columns, column)?;
+            let col_id = column.index;
+            let desc = quote!(spacetimedb::table::BoundToConnectionDesc { column: Some(#col_id) });
+            let ty = column.ty;
+            let typecheck = quote! {
+                let _ = |x: #ty| { let _: spacetimedb::Address = x; };
+            };
+            Ok::<_, syn::Error>((desc, Some(typecheck)))
+        })
+        .transpose()?
+        .unzip();
+    let bound_to_connection = bound_to_connection.into_iter();
+
     let unique_err = if !unique_columns.is_empty() {
         quote!(spacetimedb::UniqueConstraintViolation)
     } else {
@@ -636,16 +910,25 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
             type AutoIncOverflow = #autoinc_err;
 
             #integrate_generated_columns
+
+            #bump_sequences
         }
         impl spacetimedb::table::TableInternal for #tablehandle_ident {
             const TABLE_NAME: &'static str = #table_name;
             // the default value if not specified is Private
             #(const TABLE_ACCESS: spacetimedb::table::TableAccess = #table_access;)*
+            // the default value if not specified is Durable
+            #(const DURABILITY: spacetimedb::table::TableDurability = #table_durability;)*
+            #(const SOFT_DELETE: Option<u64> = Some(#soft_delete);)*
+            #(const COALESCE_MS: Option<u64> = Some(#coalesce_ms);)*
+            #(const BOUND_TO_CONNECTION: Option<spacetimedb::table::BoundToConnectionDesc> = Some(#bound_to_connection);)*
             const UNIQUE_COLUMNS: &'static [u16] = &[#(#unique_col_ids),*];
             const INDEXES: &'static [spacetimedb::table::IndexDesc<'static>] = &[#(#index_descs),*];
             #(const PRIMARY_KEY: Option<u16> = Some(#primary_col_id);)*
-            const SEQUENCES: &'static [u16] = &[#(#sequence_col_ids),*];
+            const SEQUENCES: &'static [spacetimedb::table::SequenceDesc] = &[#(#sequence_descs),*];
             #(const SCHEDULE: Option<spacetimedb::table::ScheduleDesc<'static>> = Some(#schedule);)*
+            const GENERATED_COLUMNS: &'static [spacetimedb::table::GeneratedColumnDesc<'static>] =
+                &[#(#generated_column_descs),*];
 
             #table_id_from_name_func
         }
@@ -683,6 +966,7 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
         const _: () = {
             #(let _ = <#field_types as spacetimedb::rt::TableColumn>::_ITEM;)*
             #schedule_typecheck
+            #bound_to_connection_typecheck
         };
 
         #trait_def
@@ -692,6 +976,7 @@ pub(crate) fn table_impl(mut args: TableArgs, item: &syn::DeriveInput) -> syn::R
         const _: () = {
             impl #tablehandle_ident {
                 #(#index_accessors)*
+                #(#sequence_accessors)*
             }
 
             #tabletype_impl
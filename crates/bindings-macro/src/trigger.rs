@@ -0,0 +1,83 @@
+use crate::sym;
+use crate::util::{check_duplicate, match_meta};
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::parse::Parser as _;
+use syn::{ItemFn, Path};
+
+#[derive(Default)]
+pub(crate) struct TriggerArgs {
+    on_insert: Option<Path>,
+    on_delete: Option<Path>,
+}
+
+impl TriggerArgs {
+    pub(crate) fn parse(input: TokenStream) -> syn::Result<Self> {
+        let mut args = Self::default();
+        syn::meta::parser(|meta| {
+            match_meta!(match meta {
+                sym::on_insert => {
+                    check_duplicate(&args.on_insert, &meta)?;
+                    args.on_insert = Some(meta.value()?.parse()?);
+                }
+                sym::on_delete => {
+                    check_duplicate(&args.on_delete, &meta)?;
+                    args.on_delete = Some(meta.value()?.parse()?);
+                }
+            });
+            Ok(())
+        })
+        .parse2(input)?;
+        if args.on_insert.is_none() && args.on_delete.is_none() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "a trigger must specify the table it runs on with `on_insert` or `on_delete`",
+            ));
+        }
+        Ok(args)
+    }
+}
+
+/// Returns the path to the table handle of the table accessed as `table`,
+/// e.g., `tables::element__TableHandle` for `tables::element`.
+pub(crate) fn table_handle_path(table: &Path) -> Path {
+    let mut handle = table.clone();
+    if let Some(last) = handle.segments.last_mut() {
+        last.ident = format_ident!("{}__TableHandle", last.ident);
+    }
+    handle
+}
+
+pub(crate) fn trigger_impl(args: TriggerArgs, original_function: &ItemFn) -> syn::Result<TokenStream> {
+    let func_name = &original_function.sig.ident;
+
+    if let Some(param) = original_function.sig.generics.params.first() {
+        return Err(syn::Error::new_spanned(
+            param,
+            "generic parameters are not allowed on triggers",
+        ));
+    }
+
+    let events = [
+        ("insert", "Insert", &args.on_insert),
+        ("delete", "Delete", &args.on_delete),
+    ];
+    let registrations = events.into_iter().filter_map(|(name, variant, table)| {
+        let table_handle = table_handle_path(table.as_ref()?);
+        let variant = format_ident!("{variant}");
+        let register_trigger_symbol = format!("__preinit__20_register_trigger_{func_name}_on_{name}");
+        Some(quote! {
+            const _: () = {
+                #[export_name = #register_trigger_symbol]
+                extern "C" fn __register_trigger() {
+                    spacetimedb::rt::register_trigger::<#table_handle>(
+                        spacetimedb::rt::TriggerEvent::#variant,
+                        #func_name,
+                    )
+                }
+            };
+        })
+    });
+
+    Ok(quote!(#(#registrations)*))
+}
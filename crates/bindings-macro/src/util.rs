@@ -1,24 +1,26 @@
 use proc_macro::TokenStream as StdTokenStream;
 use proc_macro2::{Span, TokenStream};
+use syn::ext::IdentExt;
 use syn::parse::Parse;
 use syn::Ident;
 
 /// Parses `item`, passing it and `args` to `f`,
 /// which should return only whats newly added, excluding the `item`.
+/// `f` may modify the `item`, e.g., to remove helper attributes.
 /// Returns the full token stream `extra_attr item newly_added`.
 pub(crate) fn cvt_attr<Item: Parse + quote::ToTokens>(
     args: StdTokenStream,
     item: StdTokenStream,
     extra_attr: TokenStream,
-    f: impl FnOnce(TokenStream, &Item) -> syn::Result<TokenStream>,
+    f: impl FnOnce(TokenStream, &mut Item) -> syn::Result<TokenStream>,
 ) -> StdTokenStream {
     let item: TokenStream = item.into();
-    let parsed_item = match syn::parse2::<Item>(item.clone()) {
+    let mut parsed_item = match syn::parse2::<Item>(item.clone()) {
         Ok(i) => i,
         Err(e) => return TokenStream::from_iter([item, e.into_compile_error()]).into(),
     };
-    let generated = f(args.into(), &parsed_item).unwrap_or_else(syn::Error::into_compile_error);
-    TokenStream::from_iter([extra_attr, item, generated]).into()
+    let generated = f(args.into(), &mut parsed_item).unwrap_or_else(syn::Error::into_compile_error);
+    TokenStream::from_iter([extra_attr, parsed_item.into_token_stream(), generated]).into()
 }
 
 /// Run `f`, converting `Err` returns into a compile error.
@@ -31,8 +33,9 @@ pub(crate) fn ok_or_compile_error<Res: Into<StdTokenStream>>(f: impl FnOnce() ->
     }
 }
 
+/// Returns the name of `ident` as a string literal, without any `r#` prefix.
 pub(crate) fn ident_to_litstr(ident: &Ident) -> syn::LitStr {
-    syn::LitStr::new(&ident.to_string(), ident.span())
+    syn::LitStr::new(&ident.unraw().to_string(), ident.span())
 }
 
 pub(crate) trait ErrorSource {
@@ -558,6 +558,354 @@ pub mod raw {
         pub fn identity(out_ptr: *mut u8);
     }
 
+    #[link(wasm_import_module = "spacetime_10.1")]
+    extern "C" {
+        /// Takes a savepoint in the current transaction,
+        /// writing a handle to it to `out`.
+        ///
+        /// The transaction can later be rolled back to the savepoint with [`savepoint_rollback`],
+        /// discarding only the writes made after it was taken.
+        /// Savepoints nest, and their handles are only valid until the end of the current reducer.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `out` is NULL or `out[..size_of::<u32>()]` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+        pub fn savepoint_begin(out: *mut u32) -> u16;
+
+        /// Rolls the current transaction back to the savepoint `savepoint`,
+        /// discarding the writes made since it was taken.
+        ///
+        /// The savepoint is released, along with any savepoints nested within it.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+        /// - `NO_SUCH_SAVEPOINT`, when `savepoint` is not a valid savepoint.
+        pub fn savepoint_rollback(savepoint: u32) -> u16;
+
+        /// Releases the savepoint `savepoint`, along with any savepoints nested within it,
+        /// keeping the writes made since it was taken.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NO_SUCH_SAVEPOINT`, when `savepoint` is not a valid savepoint.
+        pub fn savepoint_release(savepoint: u32) -> u16;
+    }
+
+    #[link(wasm_import_module = "spacetime_10.2")]
+    extern "C" {
+        /// Writes the length in bytes of the blob `blob_id`
+        /// in the blob table identified by `table_id` to `out`.
+        ///
+        /// A blob table has exactly the columns `(blob_id: u64, chunk: u32, data: Vec<u8>)`.
+        /// A blob which was never written has length `0`.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `out` is NULL or `out[..size_of::<u64>()]` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+        /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+        /// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+        pub fn blob_len(table_id: TableId, blob_id: u64, out: *mut u64) -> u16;
+
+        /// Reads up to `buffer_len` bytes of the blob `blob_id`
+        /// in the blob table identified by `table_id`, starting at `offset`,
+        /// into `buffer = buffer_ptr[..buffer_len]`.
+        ///
+        /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+        /// On success (`0` is returned),
+        /// `buffer_len` is set to the number of bytes read,
+        /// which is less than the capacity only if the end of the blob was reached.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+        /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+        /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+        /// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+        pub fn blob_read(
+            table_id: TableId,
+            blob_id: u64,
+            offset: u64,
+            buffer_ptr: *mut u8,
+            buffer_len_ptr: *mut usize,
+        ) -> u16;
+
+        /// Writes `data = data_ptr[..data_len]` into the blob `blob_id`
+        /// in the blob table identified by `table_id`, starting at `offset`.
+        ///
+        /// Existing bytes are overwritten, and the blob grows as needed.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `data_ptr` is NULL or `data` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+        /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+        /// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+        /// - `BLOB_OUT_OF_BOUNDS`, when `offset` is past the end of the blob.
+        pub fn blob_write(table_id: TableId, blob_id: u64, offset: u64, data_ptr: *const u8, data_len: usize) -> u16;
+
+        /// Deletes the blob `blob_id` in the blob table identified by `table_id`,
+        /// writing the number of chunks deleted to `out`.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `out` is NULL or `out[..size_of::<u32>()]` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+        /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+        /// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+        pub fn blob_delete(table_id: TableId, blob_id: u64, out: *mut u32) -> u16;
+    }
+
+    #[link(wasm_import_module = "spacetime_10.3")]
+    extern "C" {
+        /// Deletes all rows in the table identified by `table_id`,
+        /// clearing the table wholesale rather than deleting each row individually.
+        ///
+        /// The number of rows deleted is written to the WASM pointer `out`.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `out` is NULL or `out[..size_of::<u64>()]` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+        /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+        pub fn datastore_table_truncate(table_id: TableId, out: *mut u64) -> u16;
+    }
+
+    #[link(wasm_import_module = "spacetime_10.4")]
+    extern "C" {
+        /// Writes the value which the sequence on the column `col_id` of the table `table_id`
+        /// will generate next to `out`, without consuming it.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `out` is NULL or `out[..size_of::<i128>()]` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+        /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+        /// - `NO_SUCH_SEQUENCE`, when `col_id` is not a column of the table with a sequence.
+        pub fn sequence_peek(table_id: TableId, col_id: ColId, out: *mut i128) -> u16;
+
+        /// Moves the sequence on the column `col_id` of the table `table_id`
+        /// past the value `*value_ptr`,
+        /// so that the sequence won't later generate that value.
+        ///
+        /// Writes `1` to `out` if the sequence moved,
+        /// or `0` if it had already passed the value or the value is out of the sequence's range.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `value_ptr` is NULL or `value_ptr[..size_of::<i128>()]` is not in bounds of WASM memory.
+        /// - `out` is NULL or `out[..size_of::<u32>()]` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+        /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+        /// - `NO_SUCH_SEQUENCE`, when `col_id` is not a column of the table with a sequence.
+        pub fn sequence_advance_past(table_id: TableId, col_id: ColId, value_ptr: *const i128, out: *mut u32) -> u16;
+    }
+
+    #[link(wasm_import_module = "spacetime_10.5")]
+    extern "C" {
+        /// Broadcasts the message `payload = payload_ptr[..payload_len]`
+        /// on the topic `topic = topic_ptr[..topic_len]`.
+        ///
+        /// The message is sent to the clients subscribed to `topic`
+        /// once the current reducer's transaction commits,
+        /// and is dropped if it doesn't, or if it was broadcast after a savepoint
+        /// which is then rolled back to.
+        /// Broadcast messages are never written to tables or to the commitlog.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `topic_ptr` is NULL or `topic` is not in bounds of WASM memory.
+        /// - `topic` is not valid UTF-8.
+        /// - `payload_ptr` is NULL or `payload` is not in bounds of WASM memory.
+        pub fn broadcast(topic_ptr: *const u8, topic_len: usize, payload_ptr: *const u8, payload_len: usize);
+    }
+
+    #[link(wasm_import_module = "spacetime_10.6")]
+    extern "C" {
+        /// Queries the length of the asset at the UTF-8 `path = path_ptr[..path_len]`
+        /// in the module's asset bundle, writing it to `out`.
+        ///
+        /// Assets are read-only, so this may be called outside of a transaction.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `path_ptr` is NULL or `path` is not in bounds of WASM memory.
+        /// - `path` is not valid UTF-8.
+        /// - `out` is NULL or `out[..size_of::<u64>()]` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NO_SUCH_ASSET`, when there is no asset at `path`.
+        pub fn asset_len(path_ptr: *const u8, path_len: usize, out: *mut u64) -> u16;
+
+        /// Reads up to `buffer_len` bytes of the asset at the UTF-8 `path = path_ptr[..path_len]`,
+        /// starting at `offset`, into `buffer = buffer_ptr[..buffer_len]`.
+        ///
+        /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+        /// On success (`0` is returned),
+        /// `buffer_len` is set to the number of bytes read,
+        /// which is less than the capacity only if the end of the asset was reached.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `path_ptr` is NULL or `path` is not in bounds of WASM memory.
+        /// - `path` is not valid UTF-8.
+        /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+        /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NO_SUCH_ASSET`, when there is no asset at `path`.
+        pub fn asset_read(
+            path_ptr: *const u8,
+            path_len: usize,
+            offset: u64,
+            buffer_ptr: *mut u8,
+            buffer_len_ptr: *mut usize,
+        ) -> u16;
+    }
+
+    #[link(wasm_import_module = "spacetime_10.7")]
+    extern "C" {
+        /// Writes what the host learned about the caller of the current reducer when it connected,
+        /// as a BSATN-encoded `Option<ClientMetadata>`, to `buffer = buffer_ptr[..buffer_len]`.
+        ///
+        /// The metadata is `None` unless the reducer was called by a connected client.
+        ///
+        /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+        /// On success (`0` is returned), `buffer_len` is set to the length of the encoded metadata.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+        /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `BUFFER_TOO_SMALL`, when the encoded metadata cannot fit in `buffer`.
+        ///   When this occurs, `buffer_len` is set to the length of the encoded metadata.
+        pub fn caller_metadata(buffer_ptr: *mut u8, buffer_len_ptr: *mut usize) -> u16;
+    }
+
+    #[link(wasm_import_module = "spacetime_10.8")]
+    extern "C" {
+        /// Writes the value of the variable `key = key_ptr[..key_len]`
+        /// in the session of the caller of the current reducer, as UTF-8, to `buffer = buffer_ptr[..buffer_len]`.
+        ///
+        /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+        /// On success (`0` is returned), `buffer_len` is set to the length of the value.
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `key_ptr` is NULL or `key` is not in bounds of WASM memory.
+        /// - `key` is not valid UTF-8.
+        /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+        /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `NO_SUCH_SESSION_VARIABLE`, when the caller has no session or has not set `key` in it.
+        /// - `BUFFER_TOO_SMALL`, when the value cannot fit in `buffer`.
+        ///   When this occurs, `buffer_len` is set to the length of the value.
+        pub fn session_get(key_ptr: *const u8, key_len: usize, buffer_ptr: *mut u8, buffer_len_ptr: *mut usize) -> u16;
+    }
+
+    #[link(wasm_import_module = "spacetime_10.9")]
+    extern "C" {
+        /// Runs the batch of operations `ops = ops_ptr[..ops_len]` in WASM memory in one call,
+        /// where `ops` is a BSATN-encoded `Vec<BatchOp>`.
+        ///
+        /// The operations run in order, each behaving as the host call of the same name would.
+        /// An operation failing with an error that its host call would return
+        /// doesn't stop the batch, but is reported in its result instead.
+        ///
+        /// On success, the handle of an iterator over the BSATN-encoded `Vec<BatchResult>`,
+        /// holding one result per operation, is written to `out`.
+        /// The iterator can be advanced by [`row_iter_bsatn_advance`].
+        ///
+        /// # Traps
+        ///
+        /// Traps if:
+        /// - `ops_ptr` is NULL or `ops` is not in bounds of WASM memory.
+        /// - `out` is NULL or `out[..size_of::<RowIter>()]` is not in bounds of WASM memory.
+        /// - an operation fails with an error that would trap its host call.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error:
+        ///
+        /// - `BSATN_DECODE_ERROR`, when `ops` cannot be decoded to a `Vec<BatchOp>`.
+        pub fn datastore_batch_bsatn(ops_ptr: *const u8, ops_len: usize, out: *mut RowIter) -> u16;
+    }
+
     /// What strategy does the database index use?
     ///
     /// See also: https://www.postgresql.org/docs/current/sql-createindex.html
@@ -851,6 +1199,45 @@ pub fn datastore_delete_all_by_eq_bsatn(table_id: TableId, relation: &[u8]) -> R
     unsafe { call(|out| raw::datastore_delete_all_by_eq_bsatn(table_id, relation.as_ptr(), relation.len(), out)) }
 }
 
+/// Deletes all rows in the table identified by `table_id`,
+/// clearing the table wholesale rather than deleting each row individually.
+///
+/// The number of rows deleted is returned.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+/// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+#[inline]
+pub fn datastore_table_truncate(table_id: TableId) -> Result<u64, Errno> {
+    unsafe { call(|out| raw::datastore_table_truncate(table_id, out)) }
+}
+
+/// Runs the batch of operations `ops`, a BSATN-encoded `Vec<BatchOp>`, in one call,
+/// returning the BSATN-encoded `Vec<BatchResult>` with one result per operation.
+///
+/// The operations run in order, each behaving as the host call of the same name would.
+/// An operation failing with an error that its host call would return
+/// doesn't stop the batch, but is reported in its result instead.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `BSATN_DECODE_ERROR`, when `ops` cannot be decoded to a `Vec<BatchOp>`.
+#[inline]
+pub fn datastore_batch_bsatn(ops: &[u8]) -> Result<Vec<u8>, Errno> {
+    let raw = unsafe { call(|out| raw::datastore_batch_bsatn(ops.as_ptr(), ops.len(), out))? };
+    let mut iter = RowIter { raw };
+    let mut results = Vec::new();
+    while !iter.is_exhausted() {
+        iter.read(&mut results);
+    }
+    Ok(results)
+}
+
 /// Starts iteration on each row, as BSATN-encoded, of a table identified by `table_id`.
 /// Returns iterator handle is written to the `out` pointer.
 /// This handle can be advanced by [`row_iter_bsatn_advance`].
@@ -1057,6 +1444,223 @@ pub fn identity() -> [u8; 32] {
     buf
 }
 
+/// Takes a savepoint in the current transaction, returning a handle to it.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+#[inline]
+pub fn savepoint_begin() -> Result<u32, Errno> {
+    unsafe { call(|out| raw::savepoint_begin(out)) }
+}
+
+/// Rolls the current transaction back to `savepoint`,
+/// discarding the writes made since it was taken.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+/// - `NO_SUCH_SAVEPOINT`, when `savepoint` is not a valid savepoint.
+#[inline]
+pub fn savepoint_rollback(savepoint: u32) -> Result<(), Errno> {
+    cvt(unsafe { raw::savepoint_rollback(savepoint) })
+}
+
+/// Releases `savepoint`, keeping the writes made since it was taken.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NO_SUCH_SAVEPOINT`, when `savepoint` is not a valid savepoint.
+#[inline]
+pub fn savepoint_release(savepoint: u32) -> Result<(), Errno> {
+    cvt(unsafe { raw::savepoint_release(savepoint) })
+}
+
+/// Returns the length in bytes of the blob `blob_id` in the blob table identified by `table_id`.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+/// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+/// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+#[inline]
+pub fn blob_len(table_id: TableId, blob_id: u64) -> Result<u64, Errno> {
+    unsafe { call(|out| raw::blob_len(table_id, blob_id, out)) }
+}
+
+/// Reads bytes of the blob `blob_id` in the blob table identified by `table_id`,
+/// starting at `offset`, into `buf`.
+///
+/// Returns the number of bytes read,
+/// which is less than `buf.len()` only if the end of the blob was reached.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+/// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+/// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+#[inline]
+pub fn blob_read(table_id: TableId, blob_id: u64, offset: u64, buf: &mut [u8]) -> Result<usize, Errno> {
+    let mut len = buf.len();
+    cvt(unsafe { raw::blob_read(table_id, blob_id, offset, buf.as_mut_ptr(), &mut len) })?;
+    Ok(len)
+}
+
+/// Writes `data` into the blob `blob_id` in the blob table identified by `table_id`,
+/// starting at `offset`.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+/// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+/// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+/// - `BLOB_OUT_OF_BOUNDS`, when `offset` is past the end of the blob.
+#[inline]
+pub fn blob_write(table_id: TableId, blob_id: u64, offset: u64, data: &[u8]) -> Result<(), Errno> {
+    cvt(unsafe { raw::blob_write(table_id, blob_id, offset, data.as_ptr(), data.len()) })
+}
+
+/// Deletes the blob `blob_id` in the blob table identified by `table_id`,
+/// returning the number of chunks deleted.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+/// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+/// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+#[inline]
+pub fn blob_delete(table_id: TableId, blob_id: u64) -> Result<u32, Errno> {
+    unsafe { call(|out| raw::blob_delete(table_id, blob_id, out)) }
+}
+
+/// Returns the value which the sequence on the column `col_id` of the table `table_id`
+/// will generate next, without consuming it.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+/// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+/// - `NO_SUCH_SEQUENCE`, when `col_id` is not a column of the table with a sequence.
+#[inline]
+pub fn sequence_peek(table_id: TableId, col_id: ColId) -> Result<i128, Errno> {
+    unsafe { call(|out| raw::sequence_peek(table_id, col_id, out)) }
+}
+
+/// Moves the sequence on the column `col_id` of the table `table_id` past `value`,
+/// so that it won't later generate `value`,
+/// returning whether the sequence moved.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+/// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+/// - `NO_SUCH_SEQUENCE`, when `col_id` is not a column of the table with a sequence.
+#[inline]
+pub fn sequence_advance_past(table_id: TableId, col_id: ColId, value: i128) -> Result<bool, Errno> {
+    let moved = unsafe { call(|out| raw::sequence_advance_past(table_id, col_id, &value, out))? };
+    Ok(moved != 0)
+}
+
+/// Returns the length of the asset at `path` in the module's asset bundle.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NO_SUCH_ASSET`, when there is no asset at `path`.
+#[inline]
+pub fn asset_len(path: &str) -> Result<u64, Errno> {
+    unsafe { call(|out| raw::asset_len(path.as_ptr(), path.len(), out)) }
+}
+
+/// Reads bytes of the asset at `path`, starting at `offset`, into `buf`.
+///
+/// Returns the number of bytes read,
+/// which is less than `buf.len()` only if the end of the asset was reached.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NO_SUCH_ASSET`, when there is no asset at `path`.
+#[inline]
+pub fn asset_read(path: &str, offset: u64, buf: &mut [u8]) -> Result<usize, Errno> {
+    let mut len = buf.len();
+    cvt(unsafe { raw::asset_read(path.as_ptr(), path.len(), offset, buf.as_mut_ptr(), &mut len) })?;
+    Ok(len)
+}
+
+/// Returns what the host learned about the caller of the current reducer when it connected,
+/// as a BSATN-encoded `Option<ClientMetadata>`.
+#[inline]
+pub fn caller_metadata() -> Vec<u8> {
+    read_to_vec(|buf_ptr, buf_len| unsafe { raw::caller_metadata(buf_ptr, buf_len) })
+        .unwrap_or_else(|e| panic!("unexpected error from `caller_metadata`: {e}"))
+}
+
+/// Returns the value of the variable `key` in the session of the caller of the current reducer.
+///
+/// # Errors
+///
+/// Returns an error:
+///
+/// - `NO_SUCH_SESSION_VARIABLE`, when the caller has no session or has not set `key` in it.
+#[inline]
+pub fn session_get(key: &str) -> Result<Vec<u8>, Errno> {
+    read_to_vec(|buf_ptr, buf_len| unsafe { raw::session_get(key.as_ptr(), key.len(), buf_ptr, buf_len) })
+}
+
+/// Reads the output of a host call which writes to a buffer of a given capacity,
+/// growing the buffer and calling `read` again whenever it returns `BUFFER_TOO_SMALL`.
+///
+/// `read` must write at most the capacity passed to it,
+/// and set it to the number of bytes written on success
+/// or to the number of bytes needed on `BUFFER_TOO_SMALL`.
+fn read_to_vec(mut read: impl FnMut(*mut u8, &mut usize) -> u16) -> Result<Vec<u8>, Errno> {
+    const TOO_SMALL: u16 = errno::BUFFER_TOO_SMALL.get();
+    let mut buf = Vec::new();
+    loop {
+        let buf_ptr = buf.spare_capacity_mut();
+        let mut buf_len = buf_ptr.len();
+        match read(buf_ptr.as_mut_ptr().cast(), &mut buf_len) {
+            TOO_SMALL => buf.reserve(buf_len),
+            code => {
+                cvt(code)?;
+                // SAFETY: `read` just wrote `buf_len` bytes into `buf`.
+                unsafe { buf.set_len(buf_len) };
+                return Ok(buf);
+            }
+        }
+    }
+}
+
+/// Broadcasts `payload` on `topic` to the clients subscribed to it,
+/// once the current reducer's transaction commits.
+///
+/// The message is never written to tables or to the commitlog.
+#[inline]
+pub fn broadcast(topic: &str, payload: &[u8]) {
+    unsafe { raw::broadcast(topic.as_ptr(), topic.len(), payload.as_ptr(), payload.len()) }
+}
+
 pub struct RowIter {
     raw: raw::RowIter,
 }
@@ -1068,9 +1672,15 @@ impl RowIter {
     /// When the iterator has been exhausted,
     /// `self.is_exhausted()` will return `true`.
     pub fn read(&mut self, buf: &mut Vec<u8>) -> usize {
+        self.read_at_most(buf, usize::MAX)
+    }
+
+    /// Like [`Self::read`], but reads at most `max_len` bytes,
+    /// unless the next chunk of rows from the host is larger than that on its own.
+    pub fn read_at_most(&mut self, buf: &mut Vec<u8>, mut max_len: usize) -> usize {
         loop {
             let buf_ptr = buf.spare_capacity_mut();
-            let mut buf_len = buf_ptr.len();
+            let mut buf_len = buf_ptr.len().min(max_len);
             let ret = unsafe { raw::row_iter_bsatn_advance(self.raw, buf_ptr.as_mut_ptr().cast(), &mut buf_len) };
             if let -1 | 0 = ret {
                 // SAFETY: `_row_iter_bsatn_advance` just wrote `buf_len` bytes into the end of `buf`.
@@ -1084,7 +1694,10 @@ impl RowIter {
                     return buf_len;
                 }
                 0 => return buf_len,
-                TOO_SMALL => buf.reserve(buf_len),
+                TOO_SMALL => {
+                    max_len = max_len.max(buf_len);
+                    buf.reserve(buf_len);
+                }
                 e => panic!("unexpected error from `_row_iter_bsatn_advance`: {e}"),
             }
         }
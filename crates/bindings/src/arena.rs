@@ -0,0 +1,360 @@
+//! An optional bump allocator for data that lives no longer than a single reducer call.
+//!
+//! With the `arena` feature enabled, the module's global allocator hands out memory
+//! for every allocation made while a reducer runs from a chain of large chunks,
+//! simply bumping a pointer rather than searching free lists.
+//! Freeing such memory is a no-op; instead, all of it is reclaimed at once
+//! when the reducer returns to the host, just before its transaction commits.
+//! The most recent chunk is kept around for the next reducer call,
+//! so a steady workload settles into reusing the same memory over and over,
+//! instead of fragmenting the module's linear memory.
+//!
+//! Memory allocated outside of a reducer, e.g. in `init`-time describers,
+//! or before the arena was entered, comes from the system allocator as usual,
+//! and may be freely grown and freed during a reducer.
+//!
+//! # Caveats
+//!
+//! Anything that should outlive the reducer call it was created in,
+//! e.g. a lazily-initialized `static` or a cache in a `thread_local!`,
+//! must be allocated within [`suspend`], or it will be overwritten by the next reducer call.
+//! Lazy statics should therefore be initialized like so:
+//!
+//! ```ignore
+//! static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+//!
+//! let names = NAMES.get_or_init(|| spacetimedb::arena::suspend(load_names));
+//! ```
+//!
+//! The arena is only used on `wasm32`, where modules are single-threaded.
+//! On other targets, this module's functions do nothing.
+
+pub use imp::suspend;
+pub(crate) use imp::{owns, reducer_scope};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod imp {
+    /// Runs `f` with the arena suspended,
+    /// so that anything `f` allocates comes from the system allocator
+    /// and may safely outlive the current reducer call,
+    /// e.g. the value of a lazily-initialized `static`.
+    pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    pub(crate) fn reducer_scope<R: Copy>(f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    pub(crate) fn owns(_ptr: *const u8) -> bool {
+        false
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod imp {
+    use super::bump::ReducerArena;
+
+    #[global_allocator]
+    static ALLOC: ReducerArena = ReducerArena::new();
+
+    // SAFETY: wasm32 modules are single-threaded.
+    unsafe impl Sync for ReducerArena {}
+
+    /// Runs `f` with the arena suspended,
+    /// so that anything `f` allocates comes from the system allocator
+    /// and may safely outlive the current reducer call,
+    /// e.g. the value of a lazily-initialized `static`.
+    pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+        let was_active = ALLOC.active.replace(false);
+        let res = f();
+        ALLOC.active.set(was_active);
+        res
+    }
+
+    /// Runs `f`, which should be the body of a reducer call,
+    /// with allocations going to the arena, and then reclaims them all.
+    pub(crate) fn reducer_scope<R: Copy>(f: impl FnOnce() -> R) -> R {
+        ALLOC.active.set(true);
+        let res = f();
+        ALLOC.active.set(false);
+        // SAFETY: `R: Copy`, so `res` owns no allocations,
+        // and nothing else allocated in `f` is meant to outlive it.
+        unsafe { ALLOC.reset() };
+        res
+    }
+
+    /// Returns whether `ptr` points into memory owned by the arena.
+    ///
+    /// Such memory must not be retained past the end of the current reducer call.
+    pub(crate) fn owns(ptr: *const u8) -> bool {
+        ALLOC.owns(ptr)
+    }
+}
+
+/// The arena itself, also built for tests on the host.
+#[cfg(any(target_arch = "wasm32", test))]
+mod bump {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+    use std::mem::{align_of, size_of};
+    use std::ptr;
+
+    /// The size of the first chunk, one wasm page.
+    const MIN_CHUNK_SIZE: usize = 64 * 1024;
+
+    /// Chunks double in size up to this limit,
+    /// past which only allocations larger than it get bigger chunks.
+    const MAX_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+    /// Sits at the start of every chunk.
+    struct ChunkHeader {
+        /// The chunk allocated before this one, or null.
+        prev: *mut ChunkHeader,
+        /// The size of this chunk in bytes, including the header.
+        size: usize,
+    }
+
+    pub(super) struct ReducerArena {
+        /// Whether new allocations go to the arena rather than the system allocator.
+        pub(super) active: Cell<bool>,
+        /// The most recent chunk, from which we bump-allocate.
+        /// Older chunks are reachable through [`ChunkHeader::prev`].
+        chunk: Cell<*mut ChunkHeader>,
+        /// The address of the first free byte in `chunk`.
+        next: Cell<usize>,
+        /// The address one past the last byte of `chunk`.
+        end: Cell<usize>,
+    }
+
+    impl ReducerArena {
+        pub(super) const fn new() -> Self {
+            Self {
+                active: Cell::new(false),
+                chunk: Cell::new(ptr::null_mut()),
+                next: Cell::new(0),
+                end: Cell::new(0),
+            }
+        }
+
+        fn chunk_layout(size: usize) -> Option<Layout> {
+            Layout::from_size_align(size, align_of::<ChunkHeader>()).ok()
+        }
+
+        pub(super) fn owns(&self, ptr: *const u8) -> bool {
+            let addr = ptr as usize;
+            let mut chunk = self.chunk.get();
+            while !chunk.is_null() {
+                // SAFETY: every chunk in the chain is live and starts with an initialized header.
+                let ChunkHeader { prev, size } = unsafe { chunk.read() };
+                let start = chunk as usize;
+                if (start..start + size).contains(&addr) {
+                    return true;
+                }
+                chunk = prev;
+            }
+            false
+        }
+
+        /// Carves `layout` out of the current chunk, if it fits.
+        fn try_bump(&self, layout: Layout) -> Option<*mut u8> {
+            if self.chunk.get().is_null() {
+                return None;
+            }
+            let start = self.next.get().checked_add(layout.align() - 1)? & !(layout.align() - 1);
+            let end = start.checked_add(layout.size())?;
+            (end <= self.end.get()).then(|| {
+                self.next.set(end);
+                start as *mut u8
+            })
+        }
+
+        /// Starts a new chunk large enough to fit `layout`.
+        unsafe fn grow(&self, layout: Layout) -> bool {
+            let prev = self.chunk.get();
+            let prev_size = if prev.is_null() { 0 } else { (*prev).size };
+            let Some(needed) = (size_of::<ChunkHeader>() + layout.align()).checked_add(layout.size()) else {
+                return false;
+            };
+            let size = (prev_size * 2).clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE).max(needed);
+            let Some(chunk_layout) = Self::chunk_layout(size) else {
+                return false;
+            };
+            let chunk = System.alloc(chunk_layout).cast::<ChunkHeader>();
+            if chunk.is_null() {
+                return false;
+            }
+            chunk.write(ChunkHeader { prev, size });
+            self.chunk.set(chunk);
+            self.next.set(chunk as usize + size_of::<ChunkHeader>());
+            self.end.set(chunk as usize + size);
+            true
+        }
+
+        unsafe fn bump(&self, layout: Layout) -> *mut u8 {
+            if let Some(ptr) = self.try_bump(layout) {
+                return ptr;
+            }
+            if !self.grow(layout) {
+                return ptr::null_mut();
+            }
+            self.try_bump(layout).unwrap_or(ptr::null_mut())
+        }
+
+        /// Frees every chunk but the most recent one, and rewinds that one.
+        ///
+        /// # Safety
+        ///
+        /// No memory handed out by the arena may be used afterwards.
+        pub(super) unsafe fn reset(&self) {
+            let chunk = self.chunk.get();
+            if chunk.is_null() {
+                return;
+            }
+            let mut prev = ptr::replace(&mut (*chunk).prev, ptr::null_mut());
+            while !prev.is_null() {
+                let ChunkHeader { prev: older, size } = prev.read();
+                System.dealloc(prev.cast(), Self::chunk_layout(size).unwrap());
+                prev = older;
+            }
+            self.next.set(chunk as usize + size_of::<ChunkHeader>());
+        }
+    }
+
+    unsafe impl GlobalAlloc for ReducerArena {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            if self.active.get() {
+                self.bump(layout)
+            } else {
+                System.alloc(layout)
+            }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            // Arena memory is only reclaimed wholesale, in `reset`.
+            if !self.owns(ptr) {
+                System.dealloc(ptr, layout)
+            }
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            if !self.owns(ptr) {
+                return System.realloc(ptr, layout, new_size);
+            }
+
+            // The most recent allocation can grow or shrink in place.
+            let addr = ptr as usize;
+            if addr + layout.size() == self.next.get() && addr + new_size <= self.end.get() {
+                self.next.set(addr + new_size);
+                return ptr;
+            }
+            if new_size <= layout.size() {
+                return ptr;
+            }
+
+            let new_ptr = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
+            if !new_ptr.is_null() {
+                ptr::copy_nonoverlapping(ptr, new_ptr, layout.size());
+            }
+            new_ptr
+        }
+    }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Returns an arena which allocations go to.
+        fn active_arena() -> ReducerArena {
+            let arena = ReducerArena::new();
+            arena.active.set(true);
+            arena
+        }
+
+        #[test]
+        fn bumps_within_a_chunk() {
+            let arena = active_arena();
+            let first = Layout::from_size_align(3, 1).unwrap();
+            let second = Layout::from_size_align(16, 8).unwrap();
+            unsafe {
+                let a = arena.alloc(first);
+                let b = arena.alloc(second);
+                assert!(arena.owns(a) && arena.owns(b));
+                assert_eq!(b as usize % 8, 0);
+                assert_eq!(b as usize, (a as usize + 3).next_multiple_of(8));
+                // Freeing arena memory does nothing.
+                arena.dealloc(a, first);
+                assert_ne!(arena.alloc(first), a);
+                arena.reset();
+            }
+        }
+
+        #[test]
+        fn grows_into_new_chunks() {
+            let arena = active_arena();
+            let big = Layout::from_size_align(MIN_CHUNK_SIZE, 1).unwrap();
+            unsafe {
+                let a = arena.alloc(big);
+                let b = arena.alloc(big);
+                assert!(!a.is_null() && !b.is_null());
+                assert!(arena.owns(a) && arena.owns(b));
+                assert!(arena.owns(a.add(MIN_CHUNK_SIZE - 1)));
+                arena.reset();
+            }
+        }
+
+        #[test]
+        fn reallocs_the_latest_allocation_in_place() {
+            let arena = active_arena();
+            let layout = Layout::from_size_align(16, 8).unwrap();
+            unsafe {
+                let a = arena.alloc(layout);
+                a.write_bytes(7, 16);
+                assert_eq!(arena.realloc(a, layout, 64), a);
+                assert_eq!(arena.realloc(a, Layout::from_size_align(64, 8).unwrap(), 8), a);
+
+                // Once something else was allocated, growing copies to a new allocation.
+                let b = arena.alloc(layout);
+                let c = arena.realloc(a, Layout::from_size_align(8, 8).unwrap(), 32);
+                assert!(c != a && c > b);
+                assert_eq!(std::slice::from_raw_parts(c, 8), [7; 8]);
+                // But shrinking still happens in place.
+                assert_eq!(arena.realloc(b, layout, 4), b);
+                arena.reset();
+            }
+        }
+
+        #[test]
+        fn reset_keeps_only_the_latest_chunk() {
+            let arena = active_arena();
+            let small = Layout::from_size_align(8, 8).unwrap();
+            let big = Layout::from_size_align(MIN_CHUNK_SIZE, 8).unwrap();
+            unsafe {
+                let old = arena.alloc(small);
+                let latest = arena.alloc(big);
+                arena.reset();
+                assert!(!arena.owns(old));
+                assert!(arena.owns(latest));
+                // The latest chunk is reused from its start.
+                assert_eq!(arena.alloc(big), latest);
+                arena.reset();
+            }
+        }
+
+        #[test]
+        fn owns_only_arena_memory() {
+            let arena = ReducerArena::new();
+            let layout = Layout::from_size_align(8, 8).unwrap();
+            unsafe {
+                let system = arena.alloc(layout);
+                assert!(!arena.owns(system));
+                arena.dealloc(system, layout);
+
+                arena.active.set(true);
+                let bumped = arena.alloc(layout);
+                assert!(arena.owns(bumped));
+                assert!(!arena.owns(&layout as *const Layout as *const u8));
+                arena.reset();
+            }
+        }
+    }
+}
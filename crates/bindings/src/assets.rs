@@ -0,0 +1,59 @@
+//! Read-only static files published alongside the module,
+//! e.g., game configuration or level data which would otherwise have to be baked into tables.
+//!
+//! An asset bundle is a directory of files, attached with `spacetime publish --assets <dir>`.
+//! Each file is addressed by its path relative to that directory, with `/` as the separator.
+//! The bundle is replaced whenever the module is published again.
+//!
+//! Clients can download an asset over HTTP from `/database/assets/:name_or_identity/*path`.
+//! Assets are public, so they should not contain secrets.
+//!
+//! ```ignore
+//! #[spacetimedb::reducer(init)]
+//! fn init(ctx: &ReducerContext) {
+//!     let config = ctx.assets().read("config/items.json").expect("missing item config");
+//!     // ...
+//! }
+//! ```
+
+use crate::{sys, Errno};
+
+/// The asset bundle published with the module, returned by [`ReducerContext::assets`](crate::ReducerContext::assets).
+#[non_exhaustive]
+pub struct Assets {}
+
+impl Assets {
+    /// Returns the length in bytes of the asset at `path`, or `None` if there is no such asset.
+    pub fn len(&self, path: &str) -> Option<u64> {
+        not_found_to_none(sys::asset_len(path)).expect("asset_len() call failed")
+    }
+
+    /// Returns whether there is an asset at `path`.
+    pub fn exists(&self, path: &str) -> bool {
+        self.len(path).is_some()
+    }
+
+    /// Reads bytes of the asset at `path` starting at `offset` into `buf`,
+    /// returning how many bytes were read, or `None` if there is no such asset.
+    ///
+    /// Fewer than `buf.len()` bytes are read only if the end of the asset was reached.
+    pub fn read_at(&self, path: &str, offset: u64, buf: &mut [u8]) -> Option<usize> {
+        not_found_to_none(sys::asset_read(path, offset, buf)).expect("asset_read() call failed")
+    }
+
+    /// Reads the whole asset at `path` into memory, or returns `None` if there is no such asset.
+    pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+        let mut buf = vec![0; self.len(path)? as usize];
+        let len = self.read_at(path, 0, &mut buf)?;
+        buf.truncate(len);
+        Some(buf)
+    }
+}
+
+fn not_found_to_none<T>(res: Result<T, Errno>) -> Result<Option<T>, Errno> {
+    match res {
+        Ok(x) => Ok(Some(x)),
+        Err(e) if e == Errno::NO_SUCH_ASSET => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,278 @@
+//! Submitting many database operations to the host in a single call.
+//!
+//! Every call from a module into the host has a fixed cost,
+//! which dominates the run time of reducers that touch many rows one at a time.
+//! A [`Batch`] instead collects operations, and then [runs](Batch::run) them all at once,
+//! handing out a [`Pending`] for each operation, which is redeemed for its result
+//! from the [`BatchResults`].
+//!
+//! ```ignore
+//! let mut batch = Batch::new();
+//! let alice = batch.find(&ctx.db.person().id(), 1);
+//! let bob = batch.insert(&ctx.db.person(), Person { id: 0, name: "Bob".into() });
+//! let mut results = batch.run();
+//! let alice: Option<Person> = results.take(alice);
+//! let bob: Person = results.take(bob).unwrap();
+//! ```
+//!
+//! The operations run in the order they were added to the batch,
+//! so each sees the effects of those before it,
+//! and behave as the methods of [`Table`], [`UniqueColumn`] and [`BTreeIndex`] they are named after,
+//! including running any triggers.
+
+use std::any::Any;
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+use std::vec;
+
+use spacetimedb_lib::batch::{BatchDeleteAllByEq, BatchFind, BatchInsert, BatchOp, BatchResult, BatchUpdate};
+use spacetimedb_primitives::IndexId;
+
+use crate::rt::{has_triggers, run_triggers, TriggerEvent};
+use crate::table::{
+    insert_error, BTreeIndex, BTreeIndexBounds, BTreeScanArgs, Column, Index, Table, TryInsertError, UniqueColumn,
+};
+use crate::{bsatn, sys, DeserializeOwned};
+
+/// Turns the results of the operations added for one [`Pending`] into the value it resolves to.
+type Finish = Box<dyn FnOnce(&mut vec::IntoIter<BatchResult>) -> Box<dyn Any>>;
+
+/// A batch of database operations, to be submitted to the host in a single call.
+///
+/// See the [module-level documentation](self) for more.
+#[derive(Default)]
+pub struct Batch {
+    ops: Vec<BatchOp>,
+    finishers: Vec<Finish>,
+}
+
+/// The result of an operation added to a [`Batch`],
+/// which will be available from [`BatchResults::take`] once the batch has run.
+pub struct Pending<T> {
+    index: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+/// The results of the operations in a [`Batch`] that has run.
+pub struct BatchResults {
+    values: Vec<Option<Box<dyn Any>>>,
+}
+
+impl Batch {
+    /// Returns a new, empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of operations in this batch.
+    pub fn len(&self) -> usize {
+        self.finishers.len()
+    }
+
+    /// Returns whether this batch has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.finishers.is_empty()
+    }
+
+    fn push<T: 'static>(
+        &mut self,
+        ops: impl IntoIterator<Item = BatchOp>,
+        finish: impl FnOnce(&mut vec::IntoIter<BatchResult>) -> T + 'static,
+    ) -> Pending<T> {
+        self.ops.extend(ops);
+        let index = self.finishers.len();
+        self.finishers.push(Box::new(move |results| Box::new(finish(results))));
+        Pending {
+            index,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Finds the row where the value in the unique column `col` matches `col_val`,
+    /// as [`UniqueColumn::find`] does.
+    pub fn find<Tbl: Table, Col: Index + Column<Table = Tbl>>(
+        &mut self,
+        col: &UniqueColumn<Tbl, Col::ColType, Col>,
+        col_val: impl Borrow<Col::ColType>,
+    ) -> Pending<Option<Tbl::Row>> {
+        let op = BatchOp::Find(find_args(Col::index_id(), col.get_args(col_val.borrow())));
+        self.push([op], |results| decode_rows(next(results)).next())
+    }
+
+    /// Finds all rows in `index` matching the bounds `b`, as [`BTreeIndex::filter`] does.
+    pub fn filter<Tbl: Table, IndexType, Idx: Index, B, K>(
+        &mut self,
+        _index: &BTreeIndex<Tbl, IndexType, Idx>,
+        b: B,
+    ) -> Pending<Vec<Tbl::Row>>
+    where
+        B: BTreeIndexBounds<IndexType, K>,
+    {
+        let op = BatchOp::Find(find_args(Idx::index_id(), b.get_args()));
+        self.push([op], |results| decode_rows(next(results)).collect())
+    }
+
+    /// Inserts `row` into `table`, as [`Table::try_insert`] does.
+    pub fn insert<Tbl: Table + 'static>(
+        &mut self,
+        _table: &Tbl,
+        row: Tbl::Row,
+    ) -> Pending<Result<Tbl::Row, TryInsertError<Tbl>>> {
+        let op = BatchOp::Insert(BatchInsert {
+            table_id: Tbl::table_id(),
+            row: bsatn::to_vec(&row).unwrap(),
+        });
+        self.push([op], finish_insert::<Tbl>(row))
+    }
+
+    /// Updates the row matching `new_row` in the unique column `col` to `new_row`,
+    /// as [`UniqueColumn::update`] does.
+    ///
+    /// Running the batch panics if there is no such row.
+    pub fn update<Tbl: Table + 'static, Col: Index + Column<Table = Tbl>>(
+        &mut self,
+        col: &UniqueColumn<Tbl, Col::ColType, Col>,
+        new_row: Tbl::Row,
+    ) -> Pending<Tbl::Row> {
+        let index_id = Col::index_id();
+        // For the `on_delete` triggers, find the old row as part of the batch, just before the update.
+        let find_old = has_triggers::<Tbl>(TriggerEvent::Delete)
+            .then(|| BatchOp::Find(find_args(index_id, col.get_args(Col::get_field(&new_row)))));
+        let has_old = find_old.is_some();
+        let op = BatchOp::Update(BatchUpdate {
+            table_id: Tbl::table_id(),
+            index_id,
+            row: bsatn::to_vec(&new_row).unwrap(),
+        });
+        self.push(find_old.into_iter().chain([op]), finish_update::<Tbl>(has_old, new_row))
+    }
+
+    /// Deletes a row equal to `row` from `table`, as [`Table::delete`] does.
+    pub fn delete<Tbl: Table + 'static>(&mut self, _table: &Tbl, row: Tbl::Row) -> Pending<bool> {
+        let op = BatchOp::DeleteAllByEq(BatchDeleteAllByEq {
+            table_id: Tbl::table_id(),
+            relation: bsatn::to_vec(std::slice::from_ref(&row)).unwrap(),
+        });
+        self.push([op], finish_delete::<Tbl>(row))
+    }
+
+    /// Runs all operations in this batch, in a single call to the host.
+    pub fn run(self) -> BatchResults {
+        let ops = bsatn::to_vec(&self.ops).unwrap();
+        let results = sys::datastore_batch_bsatn(&ops)
+            .unwrap_or_else(|e| panic!("unexpected error from datastore_batch_bsatn: {e}"));
+        let results: Vec<BatchResult> = bsatn::from_slice(&results).expect("the host sent invalid batch results");
+        let mut results = results.into_iter();
+        let values = self
+            .finishers
+            .into_iter()
+            .map(|finish| Some(finish(&mut results)))
+            .collect();
+        BatchResults { values }
+    }
+}
+
+impl BatchResults {
+    /// Takes the result of the operation for which `pending` was handed out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pending` was handed out by a different batch.
+    pub fn take<T: 'static>(&mut self, pending: Pending<T>) -> T {
+        self.values
+            .get_mut(pending.index)
+            .and_then(Option::take)
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+            .expect("`Pending` was handed out by a different batch")
+    }
+}
+
+fn find_args(index_id: IndexId, args: BTreeScanArgs) -> BatchFind {
+    let (prefix, prefix_elems, rstart, rend) = args.args_for_syscall();
+    BatchFind {
+        index_id,
+        prefix: prefix.to_vec(),
+        prefix_elems,
+        rstart: rstart.to_vec(),
+        rend: rend.to_vec(),
+    }
+}
+
+fn next(results: &mut vec::IntoIter<BatchResult>) -> BatchResult {
+    results.next().expect("the host sent too few batch results")
+}
+
+#[track_caller]
+fn unexpected(result: BatchResult, op: &str) -> ! {
+    match result {
+        BatchResult::Error(code) => match sys::Errno::from_code(code) {
+            Some(e) => panic!("unexpected error from batched {op}: {e}"),
+            None => panic!("unexpected error from batched {op}: {code}"),
+        },
+        result => panic!("unexpected result from batched {op}: {result:?}"),
+    }
+}
+
+fn decode_rows<Row: DeserializeOwned>(result: BatchResult) -> impl Iterator<Item = Row> {
+    let BatchResult::Rows(rows) = result else {
+        unexpected(result, "find")
+    };
+    let mut reader = &rows[..];
+    let mut decoded = Vec::new();
+    while !reader.is_empty() {
+        decoded.push(bsatn::from_reader(&mut reader).expect("Failed to decode row!"));
+    }
+    decoded.into_iter()
+}
+
+fn finish_insert<Tbl: Table + 'static>(
+    mut row: Tbl::Row,
+) -> impl FnOnce(&mut vec::IntoIter<BatchResult>) -> Result<Tbl::Row, TryInsertError<Tbl>> {
+    move |results| match next(results) {
+        BatchResult::GeneratedColumns(gen_cols) => {
+            Tbl::bump_sequences(&row);
+            Tbl::integrate_generated_columns(&mut row, &gen_cols);
+            run_triggers::<Tbl>(TriggerEvent::Insert, &row);
+            Ok(row)
+        }
+        BatchResult::Error(code) => match sys::Errno::from_code(code) {
+            Some(e) => Err(insert_error::<Tbl>(e)),
+            None => unexpected(BatchResult::Error(code), "insert"),
+        },
+        result => unexpected(result, "insert"),
+    }
+}
+
+fn finish_update<Tbl: Table + 'static>(
+    has_old: bool,
+    mut new_row: Tbl::Row,
+) -> impl FnOnce(&mut vec::IntoIter<BatchResult>) -> Tbl::Row {
+    move |results| {
+        let old_row = has_old.then(|| decode_rows::<Tbl::Row>(next(results)).next()).flatten();
+        match next(results) {
+            BatchResult::GeneratedColumns(gen_cols) => {
+                Tbl::bump_sequences(&new_row);
+                Tbl::integrate_generated_columns(&mut new_row, &gen_cols);
+            }
+            result => unexpected(result, "update"),
+        }
+        if let Some(old_row) = old_row {
+            run_triggers::<Tbl>(TriggerEvent::Delete, &old_row);
+        }
+        run_triggers::<Tbl>(TriggerEvent::Insert, &new_row);
+        new_row
+    }
+}
+
+fn finish_delete<Tbl: Table + 'static>(row: Tbl::Row) -> impl FnOnce(&mut vec::IntoIter<BatchResult>) -> bool {
+    move |results| match next(results) {
+        BatchResult::Deleted(count) => {
+            if count > 0 {
+                run_triggers::<Tbl>(TriggerEvent::Delete, &row);
+            }
+            count > 0
+        }
+        result => unexpected(result, "delete"),
+    }
+}
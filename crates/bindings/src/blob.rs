@@ -0,0 +1,167 @@
+//! Large objects ("blobs"), stored in chunks in a dedicated blob table.
+//!
+//! A blob table is a table whose rows have exactly the columns
+//! `(blob_id: u64, chunk: u32, data: Vec<u8>)`, in that order.
+//! Rows of other tables refer to a blob by its `blob_id`,
+//! so that they stay small and cheap to send to subscribed clients.
+//! The chunks of a blob are managed by the host,
+//! and should only be accessed through [`Blob`] rather than inserted or deleted directly.
+//!
+//! Clients can download a blob over HTTP,
+//! including just a part of it with a `Range` request,
+//! from `/database/blob/:name_or_identity/:table/:blob_id`.
+//!
+//! ```ignore
+//! #[spacetimedb::table(name = asset_chunks, index(name = by_blob, btree(columns = [blob_id, chunk])))]
+//! pub struct AssetChunk {
+//!     blob_id: u64,
+//!     chunk: u32,
+//!     data: Vec<u8>,
+//! }
+//!
+//! #[spacetimedb::reducer]
+//! fn upload_part(ctx: &ReducerContext, blob_id: u64, offset: u64, part: Vec<u8>) -> Result<(), String> {
+//!     Blob::new(&ctx.db.asset_chunks(), blob_id)
+//!         .write_at(offset, &part)
+//!         .map_err(|e| e.to_string())
+//! }
+//! ```
+
+use std::io;
+use std::marker::PhantomData;
+
+use crate::{sys, Errno, Table};
+
+/// A handle to the blob `blob_id` in the blob table `Tbl`.
+///
+/// Creating a handle does not touch the database;
+/// a blob which was never written behaves as if it were empty.
+pub struct Blob<Tbl> {
+    blob_id: u64,
+    _table: PhantomData<Tbl>,
+}
+
+impl<Tbl: Table> Blob<Tbl> {
+    /// Returns a handle to the blob `blob_id` in `table`.
+    pub fn new(_table: &Tbl, blob_id: u64) -> Self {
+        Self {
+            blob_id,
+            _table: PhantomData,
+        }
+    }
+
+    /// Returns the ID of this blob.
+    pub fn id(&self) -> u64 {
+        self.blob_id
+    }
+
+    /// Returns the length of this blob in bytes.
+    ///
+    /// Panics if `Tbl` is not a blob table.
+    pub fn len(&self) -> u64 {
+        sys::blob_len(Tbl::table_id(), self.blob_id).expect("blob_len() call failed")
+    }
+
+    /// Returns whether this blob is empty, e.g., because it was never written.
+    ///
+    /// Panics if `Tbl` is not a blob table.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads bytes of this blob starting at `offset` into `buf`,
+    /// returning how many bytes were read.
+    ///
+    /// Fewer than `buf.len()` bytes are read only if the end of the blob was reached.
+    ///
+    /// Panics if `Tbl` is not a blob table.
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> usize {
+        sys::blob_read(Tbl::table_id(), self.blob_id, offset, buf).expect("blob_read() call failed")
+    }
+
+    /// Reads this whole blob into memory.
+    ///
+    /// Panics if `Tbl` is not a blob table.
+    pub fn read_to_vec(&self) -> Vec<u8> {
+        let mut buf = vec![0; self.len() as usize];
+        let len = self.read_at(0, &mut buf);
+        buf.truncate(len);
+        buf
+    }
+
+    /// Writes `data` into this blob starting at `offset`,
+    /// overwriting existing bytes and growing the blob as needed.
+    ///
+    /// Blobs cannot have holes, so this fails with `BLOB_OUT_OF_BOUNDS`
+    /// if `offset` is past the end of the blob.
+    /// To replace the contents of a blob with shorter ones, [`Blob::delete`] it first.
+    pub fn write_at(&self, offset: u64, data: &[u8]) -> Result<(), Errno> {
+        sys::blob_write(Tbl::table_id(), self.blob_id, offset, data)
+    }
+
+    /// Deletes all of this blob's chunks, leaving it empty.
+    ///
+    /// Panics if `Tbl` is not a blob table.
+    pub fn delete(&self) {
+        sys::blob_delete(Tbl::table_id(), self.blob_id).expect("blob_delete() call failed");
+    }
+
+    /// Returns a cursor which reads this blob from the start.
+    pub fn reader(&self) -> BlobCursor<'_, Tbl> {
+        BlobCursor { blob: self, pos: 0 }
+    }
+
+    /// Returns a cursor which writes after the current end of this blob.
+    pub fn appender(&self) -> BlobCursor<'_, Tbl> {
+        BlobCursor {
+            blob: self,
+            pos: self.len(),
+        }
+    }
+}
+
+/// A position within a [`Blob`], for use with [`std::io`].
+///
+/// Implements [`io::Read`] and [`io::Write`], which advance the position,
+/// and [`io::Seek`].
+pub struct BlobCursor<'a, Tbl> {
+    blob: &'a Blob<Tbl>,
+    pos: u64,
+}
+
+impl<Tbl: Table> io::Read for BlobCursor<'_, Tbl> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = sys::blob_read(Tbl::table_id(), self.blob.blob_id, self.pos, buf).map_err(io::Error::other)?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl<Tbl: Table> io::Write for BlobCursor<'_, Tbl> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.blob.write_at(self.pos, buf).map_err(io::Error::other)?;
+        self.pos += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<Tbl: Table> io::Seek for BlobCursor<'_, Tbl> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            io::SeekFrom::Start(pos) => Some(pos),
+            io::SeekFrom::End(delta) => self.blob.len().checked_add_signed(delta),
+            io::SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        self.pos = new_pos.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
@@ -0,0 +1,294 @@
+//! Undo and redo of the changes made by reducers, recorded in a capped journal table.
+//!
+//! The journal table is defined in the module by invoking [`undo_journal_table!`](crate::undo_journal_table),
+//! which declares a private table named `undo_journal` with the columns
+//! `(journal_id: u64, step: u64, op: u32, table_id: u32, inserted: bool, undone: bool, row: Vec<u8>)`
+//! and a btree index on `journal_id`.
+//! Its rows are managed by [`Journal`], and should not be inserted or deleted directly.
+//!
+//! A module keeps one journal per thing its users can undo changes to, e.g., one per document.
+//! The inserts and deletes made through a [`Journal`] handle form one step of that journal,
+//! which is undone and redone as a whole.
+//! Recording a new step discards the steps which were undone,
+//! and the oldest steps are discarded once the journal holds [`Journal::capacity`] steps.
+//!
+//! ```ignore
+//! spacetimedb::undo_journal_table!();
+//!
+//! #[spacetimedb::reducer]
+//! fn move_shape(ctx: &ReducerContext, canvas_id: u64, shape: Shape, x: i32, y: i32) {
+//!     let journal = ctx.journal(canvas_id);
+//!     if journal.delete(&ctx.db.shape(), shape.clone()) {
+//!         journal.insert(&ctx.db.shape(), Shape { x, y, ..shape });
+//!     }
+//! }
+//!
+//! #[spacetimedb::reducer]
+//! fn undo(ctx: &ReducerContext, canvas_id: u64) {
+//!     ctx.undo(canvas_id);
+//! }
+//! ```
+
+use std::cell::Cell;
+use std::sync::OnceLock;
+
+use spacetimedb_lib::bsatn;
+use spacetimedb_primitives::{ColId, IndexId};
+
+use crate::table::TableIter;
+use crate::{sys, Deserialize, Serialize, Table, TableId};
+
+/// Defines the table holding the journals of the module, which [`Journal`] reads and writes.
+///
+/// Must be invoked once, at the top level of the module, for [`ReducerContext::journal`](crate::ReducerContext::journal)
+/// and the functions using it to work.
+#[macro_export]
+macro_rules! undo_journal_table {
+    () => {
+        #[$crate::table(name = undo_journal)]
+        pub struct UndoJournal {
+            #[index(btree)]
+            journal_id: u64,
+            step: u64,
+            op: u32,
+            table_id: u32,
+            inserted: bool,
+            undone: bool,
+            row: ::std::vec::Vec<u8>,
+        }
+    };
+}
+
+/// The name of the journal table, defined by [`undo_journal_table!`].
+const JOURNAL_TABLE_NAME: &str = "undo_journal";
+
+/// The name of the index on `journal_id` of the journal table.
+const JOURNAL_INDEX_NAME: &str = "undo_journal_journal_id_idx_btree";
+
+/// A row of the journal table: the `op`th insert or, if not `inserted`, delete of `row`
+/// from the table `table_id` which the step `step` of the journal `journal_id` made.
+#[derive(Clone, Serialize, Deserialize)]
+#[sats(crate = spacetimedb_lib)]
+struct JournalEntry {
+    journal_id: u64,
+    step: u64,
+    op: u32,
+    table_id: u32,
+    inserted: bool,
+    undone: bool,
+    row: Vec<u8>,
+}
+
+/// A handle to the journal `journal_id`, returned by [`ReducerContext::journal`](crate::ReducerContext::journal).
+///
+/// Creating a handle does not touch the database;
+/// the step it records is started by its first insert or delete.
+pub struct Journal {
+    journal_id: u64,
+    capacity: u64,
+    step: Cell<Option<u64>>,
+    ops: Cell<u32>,
+}
+
+impl Journal {
+    /// The number of steps a journal keeps, unless set with [`Journal::with_capacity`].
+    pub const DEFAULT_CAPACITY: u64 = 100;
+
+    pub(crate) fn new(journal_id: u64) -> Self {
+        Self {
+            journal_id,
+            capacity: Self::DEFAULT_CAPACITY,
+            step: Cell::new(None),
+            ops: Cell::new(0),
+        }
+    }
+
+    /// Keep at most the last `capacity` steps of the journal, rather than [`Journal::DEFAULT_CAPACITY`].
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(self, capacity: u64) -> Self {
+        assert!(capacity > 0, "a journal must keep at least one step");
+        Self { capacity, ..self }
+    }
+
+    /// Returns the ID of this journal.
+    pub fn id(&self) -> u64 {
+        self.journal_id
+    }
+
+    /// Returns the number of steps this journal keeps.
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Inserts `row` into `table`, as [`Table::insert`] does, and records the insert in this step.
+    pub fn insert<T: Table>(&self, table: &T, row: T::Row) -> T::Row {
+        let row = table.insert(row);
+        self.record(T::table_id(), true, bsatn::to_vec(&row).unwrap());
+        row
+    }
+
+    /// Deletes `row` from `table`, as [`Table::delete`] does, and records the delete in this step.
+    ///
+    /// Nothing is recorded if `row` was not present.
+    pub fn delete<T: Table>(&self, table: &T, row: T::Row) -> bool {
+        let bytes = bsatn::to_vec(&row).unwrap();
+        let deleted = table.delete(row);
+        if deleted {
+            self.record(T::table_id(), false, bytes);
+        }
+        deleted
+    }
+
+    /// Undoes the latest step of this journal which has not been undone,
+    /// returning whether there was such a step.
+    ///
+    /// The rows of the step are written directly, so the triggers of their tables are not run.
+    /// Panics if restoring a row violates a constraint,
+    /// e.g., because a row with the same primary key was inserted since, outside of the journal.
+    pub fn undo(&self) -> bool {
+        let entries = self.entries();
+        let Some(step) = entries.iter().filter(|e| !e.undone).map(|e| e.step).max() else {
+            return false;
+        };
+        let mut ops = entries.into_iter().filter(|e| e.step == step).collect::<Vec<_>>();
+        ops.sort_by_key(|e| std::cmp::Reverse(e.op));
+        for entry in ops {
+            apply(&entry, !entry.inserted);
+            replace_entry(
+                &entry,
+                JournalEntry {
+                    undone: true,
+                    ..entry.clone()
+                },
+            );
+        }
+        true
+    }
+
+    /// Redoes the step of this journal which was undone last,
+    /// i.e., the earliest of its undone steps, returning whether there was such a step.
+    ///
+    /// Like [`Journal::undo`], this does not run triggers, and panics if a row violates a constraint.
+    pub fn redo(&self) -> bool {
+        let entries = self.entries();
+        let Some(step) = entries.iter().filter(|e| e.undone).map(|e| e.step).min() else {
+            return false;
+        };
+        let mut ops = entries.into_iter().filter(|e| e.step == step).collect::<Vec<_>>();
+        ops.sort_by_key(|e| e.op);
+        for entry in ops {
+            apply(&entry, entry.inserted);
+            replace_entry(
+                &entry,
+                JournalEntry {
+                    undone: false,
+                    ..entry.clone()
+                },
+            );
+        }
+        true
+    }
+
+    /// Records in this step that `row` was inserted into or, if not `inserted`, deleted from `table_id`.
+    fn record(&self, table_id: TableId, inserted: bool, row: Vec<u8>) {
+        let step = match self.step.get() {
+            Some(step) => step,
+            None => {
+                let step = self.begin_step();
+                self.step.set(Some(step));
+                step
+            }
+        };
+        let op = self.ops.get();
+        self.ops.set(op + 1);
+        insert_entry(&JournalEntry {
+            journal_id: self.journal_id,
+            step,
+            op,
+            table_id: table_id.0,
+            inserted,
+            undone: false,
+            row,
+        });
+    }
+
+    /// Starts a new step of this journal, discarding the steps which were undone
+    /// and those which no longer fit in its capacity.
+    fn begin_step(&self) -> u64 {
+        let entries = self.entries();
+        let step = entries
+            .iter()
+            .filter(|e| !e.undone)
+            .map(|e| e.step + 1)
+            .max()
+            .unwrap_or(0);
+        for entry in entries {
+            if entry.undone || entry.step + self.capacity <= step {
+                delete_entry(&entry);
+            }
+        }
+        step
+    }
+
+    /// Returns all the entries of this journal.
+    fn entries(&self) -> Vec<JournalEntry> {
+        let bound = bsatn::to_vec(&std::ops::Bound::Included(self.journal_id)).unwrap();
+        let iter = sys::datastore_btree_scan_bsatn(journal_index_id(), &[], ColId(0), &bound, &bound)
+            .unwrap_or_else(|e| panic!("unexpected error from datastore_btree_scan_bsatn: {e}"));
+        TableIter::new(iter).collect()
+    }
+}
+
+/// Inserts the row of `entry` if `insert`, or deletes it otherwise.
+fn apply(entry: &JournalEntry, insert: bool) {
+    let table_id = TableId(entry.table_id);
+    if insert {
+        let mut row = entry.row.clone();
+        sys::datastore_insert_bsatn(table_id, &mut row)
+            .unwrap_or_else(|e| panic!("failed to restore a row of table {table_id} from the journal: {e}"));
+    } else {
+        delete_by_eq(table_id, &entry.row);
+    }
+}
+
+fn insert_entry(entry: &JournalEntry) {
+    let mut row = bsatn::to_vec(entry).unwrap();
+    sys::datastore_insert_bsatn(journal_table_id(), &mut row).expect("failed to insert into the journal table");
+}
+
+fn delete_entry(entry: &JournalEntry) {
+    delete_by_eq(journal_table_id(), &bsatn::to_vec(entry).unwrap());
+}
+
+fn replace_entry(old: &JournalEntry, new: JournalEntry) {
+    delete_entry(old);
+    insert_entry(&new);
+}
+
+/// Deletes the row whose BSATN encoding is `row` from `table_id`.
+fn delete_by_eq(table_id: TableId, row: &[u8]) {
+    // The host expects a BSATN-encoded array of rows, i.e., their number followed by the rows.
+    let mut relation = Vec::with_capacity(4 + row.len());
+    relation.extend_from_slice(&1u32.to_le_bytes());
+    relation.extend_from_slice(row);
+    sys::datastore_delete_all_by_eq_bsatn(table_id, &relation).expect("datastore_delete_all_by_eq_bsatn() call failed");
+}
+
+fn journal_table_id() -> TableId {
+    static TABLE_ID: OnceLock<TableId> = OnceLock::new();
+    *TABLE_ID.get_or_init(|| {
+        sys::table_id_from_name(JOURNAL_TABLE_NAME).unwrap_or_else(|_| {
+            panic!("the journal table is missing; define it with `spacetimedb::undo_journal_table!()`")
+        })
+    })
+}
+
+fn journal_index_id() -> IndexId {
+    static INDEX_ID: OnceLock<IndexId> = OnceLock::new();
+    *INDEX_ID.get_or_init(|| {
+        sys::index_id_from_name(JOURNAL_INDEX_NAME).unwrap_or_else(|_| {
+            panic!("the `{JOURNAL_TABLE_NAME}` table wasn't defined by `spacetimedb::undo_journal_table!()`")
+        })
+    })
+}
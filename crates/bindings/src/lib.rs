@@ -1,13 +1,35 @@
 //! Provides safe abstractions around `bindings-sys`
 //! and re-exports `#[spacetimedb]` and `#[duration]`.
 
+#[cfg(any(feature = "arena", test))]
+pub mod arena;
+#[cfg(not(any(feature = "arena", test)))]
+mod arena {
+    pub(crate) fn suspend<R>(f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    pub(crate) fn reducer_scope<R: Copy>(f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    pub(crate) fn owns(_ptr: *const u8) -> bool {
+        false
+    }
+}
+pub mod assets;
+pub mod batch;
+pub mod blob;
 mod client_visibility_filter;
+pub mod journal;
 pub mod log_stopwatch;
 mod logger;
 #[cfg(feature = "rand")]
 mod rng;
 #[doc(hidden)]
 pub mod rt;
+pub mod session;
+pub mod system_tables;
 #[doc(hidden)]
 pub mod table;
 mod timestamp;
@@ -20,31 +42,72 @@ pub use log;
 #[cfg(feature = "rand")]
 pub use rand;
 
+pub use assets::Assets;
+pub use blob::Blob;
 #[doc(hidden)]
 pub use client_visibility_filter::Filter;
+pub use journal::Journal;
 #[cfg(feature = "rand")]
 pub use rng::StdbRng;
 pub use sats::SpacetimeType;
+pub use session::Session;
 #[doc(hidden)]
 // TODO: move `client_visibility_filter` out of `doc(hidden)` once RLS is implemented.
 pub use spacetimedb_bindings_macro::{__TableHelper, client_visibility_filter};
-pub use spacetimedb_bindings_macro::{duration, reducer, table};
+pub use spacetimedb_bindings_macro::{counter, duration, reducer, table, trigger};
 pub use spacetimedb_bindings_sys as sys;
 pub use spacetimedb_lib;
+pub use spacetimedb_lib::client_metadata::{ClientMetadata, RemoteAddress, RemoteAddressCategory};
 pub use spacetimedb_lib::de::{Deserialize, DeserializeOwned};
 pub use spacetimedb_lib::sats;
 pub use spacetimedb_lib::ser::Serialize;
 pub use spacetimedb_lib::Address;
 pub use spacetimedb_lib::AlgebraicValue;
 pub use spacetimedb_lib::Identity;
+pub use spacetimedb_lib::ParseIdentityError;
 pub use spacetimedb_lib::ScheduleAt;
 pub use spacetimedb_primitives::TableId;
 pub use sys::Errno;
-pub use table::{AutoIncOverflow, BTreeIndex, Table, TryInsertError, UniqueColumn, UniqueConstraintViolation};
+pub use system_tables::{Connection, Subscription, SystemTable};
+pub use table::{
+    AutoIncOverflow, BTreeIndex, IterOptions, RowRefIter, Sequence, Table, TryInsertError, UniqueColumn,
+    UniqueConstraintViolation,
+};
 pub use timestamp::Timestamp;
 
 pub type ReducerResult = core::result::Result<(), Box<str>>;
 
+/// An error which a reducer fails with, delivered to clients as a typed value rather than as a string.
+///
+/// A reducer returning `Result<(), ReducerError<E>>` records `E` as its error type in the module's schema,
+/// so client SDKs can generate a type for it, and clients can match on the errors a reducer fails with
+/// instead of parsing error messages.
+///
+/// ```no_run
+/// # use spacetimedb::{reducer, ReducerContext, ReducerError, SpacetimeType};
+/// #[derive(SpacetimeType)]
+/// pub enum PaintError {
+///     CanvasIsPrivate,
+///     OutOfBounds(u32),
+/// }
+///
+/// #[reducer]
+/// pub fn paint(ctx: &ReducerContext, pixel: u32) -> Result<(), ReducerError<PaintError>> {
+///     if pixel >= 10_000 {
+///         return Err(PaintError::OutOfBounds(pixel).into());
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReducerError<E>(pub E);
+
+impl<E> From<E> for ReducerError<E> {
+    fn from(error: E) -> Self {
+        Self(error)
+    }
+}
+
 /// A context that any reducer is provided with.
 #[non_exhaustive]
 pub struct ReducerContext {
@@ -90,6 +153,71 @@ impl ReducerContext {
         // which reads the module identity out of the `InstanceEnv`.
         Identity::from_byte_array(spacetimedb_bindings_sys::identity())
     }
+
+    /// Returns the clients currently connected to the database.
+    ///
+    /// The list is read from the `st_connection` system table, which the host maintains
+    /// as clients connect and disconnect, so it is consistent with the rest of the reducer's transaction.
+    /// A client is listed once its `client_connected` reducer has committed,
+    /// and until its `client_disconnected` reducer has run,
+    /// so `client_connected` does not see the calling client in the list, but `client_disconnected` does.
+    pub fn connections(&self) -> impl Iterator<Item = Connection> {
+        SystemTable::iter(&system_tables::StConnectionHandle {})
+    }
+
+    /// Returns what the host learned about the client that invoked the reducer when it connected:
+    /// the protocol it speaks, its SDK, if it said, and an anonymized form of its address.
+    ///
+    /// This is available in `client_connected` and `client_disconnected`,
+    /// and in reducers called by a connected client.
+    /// It is `None` for `init`, scheduled reducers,
+    /// and reducers called via the `/database/call` HTTP endpoint.
+    pub fn caller_metadata(&self) -> Option<ClientMetadata> {
+        bsatn::from_slice(&sys::caller_metadata()).expect("the host sent invalid caller metadata")
+    }
+
+    /// Broadcasts `payload` on `topic` to the clients subscribed to that topic.
+    ///
+    /// The message is ephemeral: it is never written to a table or to the commitlog,
+    /// and clients which subscribe later will not receive it.
+    /// It is only sent if this reducer's transaction commits,
+    /// after the transaction's updates have been sent to subscribed clients.
+    pub fn broadcast(&self, topic: &str, payload: impl AsRef<[u8]>) {
+        sys::broadcast(topic, payload.as_ref())
+    }
+
+    /// Returns the asset bundle published with the module.
+    ///
+    /// See [the `assets` module](crate::assets) for how assets are published.
+    pub fn assets(&self) -> &Assets {
+        &Assets {}
+    }
+
+    /// Returns the session of the client that invoked the reducer,
+    /// i.e., the variables it set on its connection.
+    ///
+    /// See [the `session` module](crate::session) for how clients set session variables.
+    pub fn session(&self) -> &Session {
+        &Session {}
+    }
+
+    /// Returns a handle to the undo journal `journal_id`,
+    /// which records the inserts and deletes made through it as one undoable step.
+    ///
+    /// See [the `journal` module](crate::journal) for the table which holds the journals.
+    pub fn journal(&self, journal_id: u64) -> Journal {
+        Journal::new(journal_id)
+    }
+
+    /// Undoes the latest step of the journal `journal_id`, returning whether there was a step to undo.
+    pub fn undo(&self, journal_id: u64) -> bool {
+        self.journal(journal_id).undo()
+    }
+
+    /// Redoes the step of the journal `journal_id` which was undone last, returning whether there was a step to redo.
+    pub fn redo(&self, journal_id: u64) -> bool {
+        self.journal(journal_id).redo()
+    }
 }
 
 /// A handle on a database with a particular table schema.
@@ -121,6 +249,36 @@ impl DbContext for ReducerContext {
 #[non_exhaustive]
 pub struct Local {}
 
+impl Local {
+    /// Runs `f` within a savepoint of the current transaction.
+    ///
+    /// If `f` returns `Err`, the writes it made to the database are rolled back,
+    /// leaving those made by the reducer before the savepoint in place.
+    /// If `f` returns `Ok`, its writes are kept, and will be committed along with the rest of the reducer's.
+    ///
+    /// Savepoints can be nested, so `f` may itself call `savepoint`.
+    ///
+    /// ```no_run
+    /// # use spacetimedb::ReducerContext;
+    /// # fn try_reserve(ctx: &ReducerContext) -> Result<(), String> { Ok(()) }
+    /// # fn example(ctx: &ReducerContext) {
+    /// // If the reservation fails, none of its writes are committed,
+    /// // but the reducer carries on.
+    /// let reserved = ctx.db.savepoint(|| try_reserve(ctx)).is_ok();
+    /// # }
+    /// ```
+    pub fn savepoint<T, E>(&self, f: impl FnOnce() -> Result<T, E>) -> Result<T, E> {
+        let savepoint = sys::savepoint_begin().expect("savepoint taken outside of a transaction");
+        let res = f();
+        match &res {
+            Ok(_) => sys::savepoint_release(savepoint),
+            Err(_) => sys::savepoint_rollback(savepoint),
+        }
+        .expect("savepoint should be valid until it is released");
+        res
+    }
+}
+
 // #[cfg(target_arch = "wasm32")]
 // #[global_allocator]
 // static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
@@ -172,9 +330,15 @@ impl IterBuf {
 
 impl Drop for IterBuf {
     fn drop(&mut self) {
+        // Buffers from the reducer arena are reclaimed with it, so they can't be pooled.
+        if arena::owns(self.buf.as_ptr()) {
+            return;
+        }
+
         self.buf.clear();
         let buf = std::mem::take(&mut self.buf);
-        ITER_BUFS.with_borrow_mut(|v| v.push_back(buf));
+        // The pool outlives the reducer, so it mustn't grow into the arena.
+        arena::suspend(|| ITER_BUFS.with_borrow_mut(|v| v.push_back(buf)));
     }
 }
 
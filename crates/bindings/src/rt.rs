@@ -1,15 +1,19 @@
 #![deny(unsafe_op_in_unsafe_fn)]
 
 use crate::timestamp::with_timestamp_set;
-use crate::{sys, IterBuf, ReducerContext, ReducerResult, SpacetimeType, Table, Timestamp};
+use crate::{sys, IterBuf, ReducerContext, ReducerError, SpacetimeType, Table, Timestamp};
 pub use spacetimedb_lib::db::raw_def::v9::Lifecycle as LifecycleReducer;
-use spacetimedb_lib::db::raw_def::v9::{RawIndexAlgorithm, RawModuleDefV9Builder, TableType};
+pub use spacetimedb_lib::db::raw_def::v9::ReducerPriority;
+use spacetimedb_lib::db::raw_def::v9::{
+    ArgConstraint, CatchUpPolicy, RawIndexAlgorithm, RawModuleDefV9Builder, TableDurability, TableType,
+};
 use spacetimedb_lib::de::{self, Deserialize, SeqProductAccess};
 use spacetimedb_lib::sats::typespace::TypespaceBuilder;
 use spacetimedb_lib::sats::{impl_deserialize, impl_serialize, ProductTypeElement};
 use spacetimedb_lib::ser::{Serialize, SerializeSeqProduct};
-use spacetimedb_lib::{bsatn, Address, Identity, ProductType, RawModuleDef};
+use spacetimedb_lib::{bsatn, Address, AlgebraicType, Identity, ProductType, RawModuleDef};
 use spacetimedb_primitives::*;
+use std::any::Any;
 use std::fmt;
 use std::marker::PhantomData;
 use std::sync::{Mutex, OnceLock};
@@ -24,12 +28,19 @@ pub fn invoke_reducer<'a, A: Args<'a>>(
     reducer: impl Reducer<'a, A>,
     ctx: ReducerContext,
     args: &'a [u8],
-) -> Result<(), Box<str>> {
+) -> Result<(), ReducerFailure> {
     // Deserialize the arguments from a bsatn encoding.
     let SerDeArgs(args) = bsatn::from_slice(args).expect("unable to decode args");
 
     // Run the reducer with the environment all set up.
-    with_timestamp_set(ctx.timestamp, || reducer.invoke(&ctx, args))
+    CURRENT_CONTEXT.set(&ctx, || {
+        with_timestamp_set(ctx.timestamp, || reducer.invoke(&ctx, args))
+    })
+}
+
+scoped_tls::scoped_thread_local! {
+    /// The context of the reducer currently running, which is passed on to triggers.
+    static CURRENT_CONTEXT: ReducerContext
 }
 /// A trait for types representing the *execution logic* of a reducer.
 #[diagnostic::on_unimplemented(
@@ -38,11 +49,15 @@ pub fn invoke_reducer<'a, A: Args<'a>>(
     note = "",
     note = "reducer signatures must match the following pattern:",
     note = "    `Fn(&ReducerContext, [T1, ...]) [-> Result<(), impl Display>]`",
+    note = "or `Fn(&ReducerContext, [T1, ...]) -> Result<(), ReducerError<impl SpacetimeType>>`,",
     note = "where each `Ti` type implements `SpacetimeType`.",
     note = ""
 )]
 pub trait Reducer<'de, A: Args<'de>> {
-    fn invoke(&self, ctx: &ReducerContext, args: A) -> ReducerResult;
+    fn invoke(&self, ctx: &ReducerContext, args: A) -> Result<(), ReducerFailure>;
+
+    /// Returns the type of the values the reducer fails with, if it has one.
+    fn error_type(typespace: &mut impl TypespaceBuilder) -> Option<AlgebraicType>;
 }
 
 /// A trait for types that can *describe* a reducer.
@@ -56,10 +71,55 @@ pub trait ReducerInfo {
     /// A description of the parameter names of the reducer.
     const ARG_NAMES: &'static [Option<&'static str>];
 
+    /// The constraints declared on the reducer's arguments with `#[validate(...)]`.
+    const ARG_CONSTRAINTS: &'static [ArgConstraintDesc] = &[];
+
+    /// How urgently calls of the reducer are run when the database is busy.
+    const PRIORITY: ReducerPriority = ReducerPriority::Normal;
+
+    /// The additional names under which the reducer may be called.
+    const ALIASES: &'static [&'static str] = &[];
+
+    /// The note logged when the reducer is called under one of its [`Self::ALIASES`], if they are deprecated.
+    const DEPRECATED: Option<&'static str> = None;
+
+    /// Whether the reducer decides which clients may subscribe to which topics.
+    const AUTHORIZES_TOPICS: bool = false;
+
     /// The function to call to invoke the reducer.
     const INVOKE: ReducerFn;
 }
 
+/// Describes a constraint declared on a reducer argument with `#[validate(...)]`.
+#[derive(Clone, Copy)]
+pub struct ArgConstraintDesc {
+    /// The position of the argument, not counting the `ReducerContext`.
+    pub arg: u16,
+    pub kind: ArgConstraintKind,
+}
+
+/// The kinds of constraints which may be declared on a reducer argument.
+#[derive(Clone, Copy)]
+pub enum ArgConstraintKind {
+    MinLength(u32),
+    MaxLength(u32),
+    Min(i128),
+    Max(i128),
+    OneOf(&'static [&'static str]),
+}
+
+impl From<ArgConstraintKind> for ArgConstraint {
+    fn from(kind: ArgConstraintKind) -> ArgConstraint {
+        match kind {
+            ArgConstraintKind::MinLength(n) => ArgConstraint::MinLength(n),
+            ArgConstraintKind::MaxLength(n) => ArgConstraint::MaxLength(n),
+            ArgConstraintKind::Min(n) => ArgConstraint::Min(n),
+            ArgConstraintKind::Max(n) => ArgConstraint::Max(n),
+            ArgConstraintKind::OneOf(values) => ArgConstraint::OneOf(values.iter().map(|&v| v.into()).collect()),
+        }
+    }
+}
+
 /// A trait of types representing the arguments of a reducer.
 pub trait Args<'de>: Sized {
     /// How many arguments does the reducer accept?
@@ -75,26 +135,50 @@ pub trait Args<'de>: Sized {
     fn schema<I: ReducerInfo>(typespace: &mut impl TypespaceBuilder) -> ProductType;
 }
 
+/// The ways in which a reducer can fail.
+pub enum ReducerFailure {
+    /// The reducer failed with an error message.
+    Message(Box<str>),
+    /// The reducer failed with a value of its error type, encoded as BSATN.
+    Value(Vec<u8>),
+}
+
 /// A trait of types representing the result of executing a reducer.
 #[diagnostic::on_unimplemented(
     message = "`{Self}` is not a valid reducer return type",
-    note = "reducers cannot return values -- you can only return `()` or `Result<(), impl Display>`"
+    note = "reducers cannot return values -- you can only return `()`, `Result<(), impl Display>`, \
+            or `Result<(), ReducerError<impl SpacetimeType>>`"
 )]
 pub trait IntoReducerResult {
     /// Convert the result into form where there is no value
-    /// and the error message is a string.
-    fn into_result(self) -> Result<(), Box<str>>;
+    /// and the error is either a message or an encoded value.
+    fn into_result(self) -> Result<(), ReducerFailure>;
+
+    /// Returns the type of the error values, if the error is not a message.
+    fn error_type(_typespace: &mut impl TypespaceBuilder) -> Option<AlgebraicType> {
+        None
+    }
 }
 impl IntoReducerResult for () {
     #[inline]
-    fn into_result(self) -> Result<(), Box<str>> {
+    fn into_result(self) -> Result<(), ReducerFailure> {
         Ok(self)
     }
 }
 impl<E: fmt::Display> IntoReducerResult for Result<(), E> {
     #[inline]
-    fn into_result(self) -> Result<(), Box<str>> {
-        self.map_err(|e| e.to_string().into())
+    fn into_result(self) -> Result<(), ReducerFailure> {
+        self.map_err(|e| ReducerFailure::Message(e.to_string().into()))
+    }
+}
+impl<E: SpacetimeType + Serialize> IntoReducerResult for Result<(), ReducerError<E>> {
+    #[inline]
+    fn into_result(self) -> Result<(), ReducerFailure> {
+        self.map_err(|ReducerError(e)| ReducerFailure::Value(bsatn::to_vec(&e).expect("unable to encode error")))
+    }
+
+    fn error_type(typespace: &mut impl TypespaceBuilder) -> Option<AlgebraicType> {
+        Some(E::make_type(typespace))
     }
 }
 
@@ -260,10 +344,14 @@ macro_rules! impl_reducer {
             Ret: IntoReducerResult
         {
             #[allow(non_snake_case)]
-            fn invoke(&self, ctx: &ReducerContext, args: ($($T,)*)) -> Result<(), Box<str>> {
+            fn invoke(&self, ctx: &ReducerContext, args: ($($T,)*)) -> Result<(), ReducerFailure> {
                 let ($($T,)*) = args;
                 self(ctx, $($T),*).into_result()
             }
+
+            fn error_type(typespace: &mut impl TypespaceBuilder) -> Option<AlgebraicType> {
+                Ret::error_type(typespace)
+            }
         }
 
     };
@@ -353,11 +441,29 @@ pub fn register_table<T: Table>() {
         if let Some(primary_key) = T::PRIMARY_KEY {
             table = table.with_primary_key(primary_key);
         }
-        for &col in T::SEQUENCES {
-            table = table.with_column_sequence(col);
+        for &seq in T::SEQUENCES {
+            table = table.with_column_sequence_config(seq.column, seq.start, seq.increment);
+        }
+        for &generated in T::GENERATED_COLUMNS {
+            table = table.with_generated_column(generated.column, generated.expr);
+        }
+        if T::DURABILITY != TableDurability::Durable {
+            table = table.with_durability(T::DURABILITY);
+        }
+        if let Some(retention) = T::SOFT_DELETE {
+            table = table.with_soft_delete(retention);
+        }
+        if let Some(window_ms) = T::COALESCE_MS {
+            table = table.with_coalesce(window_ms);
+        }
+        if let Some(bound) = T::BOUND_TO_CONNECTION {
+            table = table.with_bound_to_connection(bound.column.map(ColId::from));
         }
         if let Some(schedule) = T::SCHEDULE {
             table = table.with_schedule(schedule.reducer_name, schedule.scheduled_at_column);
+            if schedule.catch_up != CatchUpPolicy::FireAll {
+                table = table.with_catch_up(schedule.catch_up);
+            }
         }
 
         table.finish();
@@ -375,10 +481,29 @@ impl From<crate::table::IndexAlgo<'_>> for RawIndexAlgorithm {
 }
 
 /// Registers a describer for the reducer `I` with arguments `A`.
-pub fn register_reducer<'a, A: Args<'a>, I: ReducerInfo>(_: impl Reducer<'a, A>) {
+pub fn register_reducer<'a, A: Args<'a>, I: ReducerInfo, R: Reducer<'a, A>>(_: R) {
     register_describer(|module| {
         let params = A::schema::<I>(&mut module.inner);
         module.inner.add_reducer(I::NAME, params, I::LIFECYCLE);
+        if let Some(error_type) = R::error_type(&mut module.inner) {
+            module.inner.add_reducer_error_type(I::NAME, error_type);
+        }
+        for constraint in I::ARG_CONSTRAINTS {
+            module
+                .inner
+                .add_reducer_arg_constraint(I::NAME, constraint.arg, constraint.kind.into());
+        }
+        if I::PRIORITY != ReducerPriority::Normal {
+            module.inner.add_reducer_priority(I::NAME, I::PRIORITY);
+        }
+        for alias in I::ALIASES {
+            module
+                .inner
+                .add_reducer_alias(I::NAME, *alias, I::DEPRECATED.map(Into::into));
+        }
+        if I::AUTHORIZES_TOPICS {
+            module.inner.set_topic_authorizer(I::NAME);
+        }
         module.reducers.push(I::INVOKE);
     })
 }
@@ -390,6 +515,91 @@ pub fn register_row_level_security(sql: &'static str) {
     })
 }
 
+/// Registers a counter of the rows of the table `T`, grouped by their column named `group_by`,
+/// which the database keeps in the column `into_column` of the table `into_table`.
+pub fn register_counter<T: Table>(group_by: &'static str, into_table: &'static str, into_column: &'static str) {
+    register_describer(move |module| {
+        let row_type = T::Row::make_type(&mut module.inner);
+        let group_by_col = module
+            .inner
+            .typespace()
+            .get(*row_type.as_ref().unwrap())
+            .and_then(AlgebraicType::as_product)
+            .and_then(|row| row.elements.iter().position(|elem| elem.has_name(group_by)))
+            .unwrap_or_else(|| panic!("table `{}` has no column `{group_by}` to count by", T::TABLE_NAME));
+        module
+            .inner
+            .add_counter(T::TABLE_NAME, ColId::from(group_by_col), into_table, into_column);
+    })
+}
+
+/// The kind of write to a table on which a trigger runs.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TriggerEvent {
+    /// A row was inserted, including as the new row of an update.
+    Insert,
+    /// A row was deleted, including as the old row of an update.
+    Delete,
+}
+
+/// A trigger registered with [`register_trigger`].
+struct Trigger {
+    /// The name of the table whose writes run the trigger.
+    table: &'static str,
+    event: TriggerEvent,
+    /// The trigger function, a `fn(&ReducerContext, &T::Row)` for the table `T`.
+    func: Box<dyn Any + Send>,
+}
+
+// Like `DESCRIBERS`, this is only ever accessed from the single WASM thread.
+static TRIGGERS: Mutex<Vec<Trigger>> = Mutex::new(Vec::new());
+
+/// Registers `func` to run whenever a row of the table `T` is written as per `event`.
+pub fn register_trigger<T: Table>(event: TriggerEvent, func: fn(&ReducerContext, &T::Row)) {
+    TRIGGERS.lock().unwrap().push(Trigger {
+        table: T::TABLE_NAME,
+        event,
+        func: Box::new(func),
+    })
+}
+
+/// Returns the triggers registered for `event` on the table `T`.
+fn triggers_for<T: Table>(event: TriggerEvent) -> Vec<fn(&ReducerContext, &T::Row)> {
+    TRIGGERS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|trigger| trigger.table == T::TABLE_NAME && trigger.event == event)
+        .filter_map(|trigger| trigger.func.downcast_ref().copied())
+        .collect()
+}
+
+/// Returns whether any triggers are registered for `event` on the table `T`.
+///
+/// Used to avoid reading rows back from the datastore only for triggers to see them.
+pub(crate) fn has_triggers<T: Table>(event: TriggerEvent) -> bool {
+    TRIGGERS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|trigger| trigger.table == T::TABLE_NAME && trigger.event == event)
+}
+
+/// Runs the triggers registered for `event` on the table `T` with `row`,
+/// within the transaction of the reducer currently running.
+pub(crate) fn run_triggers<T: Table>(event: TriggerEvent, row: &T::Row) {
+    // Collect the triggers first, so that they may themselves write to tables with triggers.
+    let triggers = triggers_for::<T>(event);
+    if triggers.is_empty() {
+        return;
+    }
+    CURRENT_CONTEXT.with(|ctx| {
+        for trigger in triggers {
+            trigger(ctx, row)
+        }
+    })
+}
+
 /// A builder for a module.
 #[derive(Default)]
 struct ModuleBuilder {
@@ -403,8 +613,8 @@ struct ModuleBuilder {
 static DESCRIBERS: Mutex<Vec<Box<dyn DescriberFn>>> = Mutex::new(Vec::new());
 
 /// A reducer function takes in `(Sender, Timestamp, Args)`
-/// and returns a result with a possible error message.
-pub type ReducerFn = fn(ReducerContext, &[u8]) -> ReducerResult;
+/// and returns a result with a possible error message or value.
+pub type ReducerFn = fn(ReducerContext, &[u8]) -> Result<(), ReducerFailure>;
 static REDUCERS: OnceLock<Vec<ReducerFn>> = OnceLock::new();
 
 /// Called by the host when the module is initialized
@@ -476,7 +686,7 @@ extern "C" fn __describe_module__(description: BytesSink) {
 /// Note that in the future, more failure codes could be supported.
 #[no_mangle]
 extern "C" fn __call_reducer__(
-    id: usize,
+    id: u32,
     sender_0: u64,
     sender_1: u64,
     sender_2: u64,
@@ -509,18 +719,25 @@ extern "C" fn __call_reducer__(
         rng: std::cell::OnceCell::new(),
     };
 
-    // Fetch reducer function.
-    let reducers = REDUCERS.get().unwrap();
-    // Dispatch to it with the arguments read.
-    let res = with_read_args(args, |args| reducers[id](ctx, args));
-    // Convert any error message to an error code and writes to the `error` sink.
-    match res {
-        Ok(()) => 0,
-        Err(msg) => {
-            write_to_sink(error, msg.as_bytes());
-            errno::HOST_CALL_FAILURE.get() as i16
+    // Everything the reducer allocated is dead by the time it returns to the host to commit.
+    crate::arena::reducer_scope(|| {
+        // Fetch reducer function.
+        let reducers = REDUCERS.get().unwrap();
+        // Dispatch to it with the arguments read.
+        let res = with_read_args(args, |args| reducers[id as usize](ctx, args));
+        // Convert any error to an error code and write it to the `error` sink.
+        match res {
+            Ok(()) => 0,
+            Err(ReducerFailure::Message(msg)) => {
+                write_to_sink(error, msg.as_bytes());
+                errno::HOST_CALL_FAILURE.get() as i16
+            }
+            Err(ReducerFailure::Value(value)) => {
+                write_to_sink(error, &value);
+                errno::HOST_CALL_FAILURE_VALUE.get() as i16
+            }
         }
-    }
+    })
 }
 
 /// Run `logic` with `args` read from the host into a `&[u8]`.
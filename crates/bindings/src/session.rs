@@ -0,0 +1,39 @@
+//! Small per-connection key-value state, e.g., the client's locale or build,
+//! which a client sets on its connection and which the reducers it calls may read,
+//! without a round trip through a table for values that are never queried relationally.
+//!
+//! Clients set and remove variables with the `SetSessionVariable` message.
+//! Variables live as long as the connection, and the host bounds their number and size.
+//!
+//! ```ignore
+//! #[spacetimedb::reducer]
+//! fn greet(ctx: &ReducerContext) {
+//!     let locale = ctx.session().get("locale").unwrap_or_else(|| "en".into());
+//!     // ...
+//! }
+//! ```
+
+use crate::{sys, Errno};
+
+/// The session of the client that invoked the reducer, returned by [`ReducerContext::session`](crate::ReducerContext::session).
+///
+/// The session is empty when the reducer was not called by a connected client,
+/// and in `client_connected`, as the client can't have set any variables yet.
+#[non_exhaustive]
+pub struct Session {}
+
+impl Session {
+    /// Returns the value of the variable `key`, or `None` if the client has not set it.
+    pub fn get(&self, key: &str) -> Option<String> {
+        match sys::session_get(key) {
+            Ok(value) => Some(String::from_utf8(value).expect("session variables are UTF-8")),
+            Err(e) if e == Errno::NO_SUCH_SESSION_VARIABLE => None,
+            Err(e) => panic!("session_get() call failed: {e}"),
+        }
+    }
+
+    /// Returns whether the client has set the variable `key`.
+    pub fn contains(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+}
@@ -0,0 +1,125 @@
+//! Read-only handles on the system tables which the host maintains,
+//! reachable from module code as `ctx.db.st_connection()` and `ctx.db.st_subscription()`.
+//!
+//! Unlike the handles generated for a module's own tables,
+//! these offer no way to insert, update or delete rows;
+//! the host alone writes to the system tables.
+//!
+//! There are no `st_reducer_stats` or `st_table_stats` handles,
+//! as the host keeps no such system tables.
+//! Reducer and table usage statistics live in the host's metrics,
+//! which are neither transactional nor persisted across restarts,
+//! so a reducer could not read them consistently with the rest of its transaction.
+//! They are reported by `spacetime describe` instead.
+
+use std::sync::OnceLock;
+
+use crate::table::TableIter;
+use crate::{sys, Address, Deserialize, DeserializeOwned, Identity, Local, TableId, Timestamp};
+
+/// Implemented for the handles on system tables which modules may read.
+pub trait SystemTable {
+    /// The type of rows stored in this table.
+    type Row: DeserializeOwned;
+
+    /// The name of the system table, e.g. `st_connection`.
+    const TABLE_NAME: &'static str;
+
+    #[doc(hidden)]
+    fn table_id() -> TableId;
+
+    /// Returns the number of rows of this table in the TX state.
+    fn count(&self) -> u64 {
+        sys::datastore_table_row_count(Self::table_id()).expect("datastore_table_row_count() call failed")
+    }
+
+    /// Iterate over all rows of this table in the TX state.
+    #[inline]
+    fn iter(&self) -> impl Iterator<Item = Self::Row> {
+        let table_id = Self::table_id();
+        let iter = sys::datastore_table_scan_bsatn(table_id).expect("datastore_table_scan_bsatn() call failed");
+        TableIter::new(iter)
+    }
+}
+
+fn system_table_id(cell: &'static OnceLock<TableId>, name: &str) -> TableId {
+    *cell.get_or_init(|| crate::table_id_from_name(name))
+}
+
+/// A client connected to the database, as listed in `st_connection`.
+///
+/// A client is listed once its `client_connected` reducer has committed,
+/// and until its `client_disconnected` reducer has run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[sats(crate = spacetimedb_lib)]
+#[non_exhaustive]
+pub struct Connection {
+    /// The `Identity` of the client.
+    pub identity: Identity,
+    /// The `Address` of the client's connection.
+    pub address: Address,
+    /// The time at which the client connected.
+    pub connected_at: Timestamp,
+}
+
+/// A query to which a client is subscribed, as listed in `st_subscription`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[sats(crate = spacetimedb_lib)]
+#[non_exhaustive]
+pub struct Subscription {
+    /// The `Identity` of the subscribed client.
+    pub identity: Identity,
+    /// The `Address` of the subscribed client's connection.
+    pub address: Address,
+    /// The SQL text of the query.
+    pub query: Box<str>,
+    /// The time at which the client subscribed to the query.
+    pub created_at: Timestamp,
+    /// The time spent evaluating the query against transactions, in microseconds.
+    ///
+    /// The query is evaluated once for all of its subscribers,
+    /// so they all report the same cost.
+    pub eval_cost_micros: u64,
+}
+
+/// A read-only handle on the `st_connection` system table, returned by [`Local::st_connection`].
+#[non_exhaustive]
+pub struct StConnectionHandle {}
+
+impl SystemTable for StConnectionHandle {
+    type Row = Connection;
+    const TABLE_NAME: &'static str = "st_connection";
+
+    fn table_id() -> TableId {
+        static TABLE_ID: OnceLock<TableId> = OnceLock::new();
+        system_table_id(&TABLE_ID, Self::TABLE_NAME)
+    }
+}
+
+/// A read-only handle on the `st_subscription` system table, returned by [`Local::st_subscription`].
+#[non_exhaustive]
+pub struct StSubscriptionHandle {}
+
+impl SystemTable for StSubscriptionHandle {
+    type Row = Subscription;
+    const TABLE_NAME: &'static str = "st_subscription";
+
+    fn table_id() -> TableId {
+        static TABLE_ID: OnceLock<TableId> = OnceLock::new();
+        system_table_id(&TABLE_ID, Self::TABLE_NAME)
+    }
+}
+
+impl Local {
+    /// Returns a read-only handle on the `st_connection` system table,
+    /// which lists the clients currently connected to the database and when they connected.
+    pub fn st_connection(&self) -> &StConnectionHandle {
+        &StConnectionHandle {}
+    }
+
+    /// Returns a read-only handle on the `st_subscription` system table,
+    /// which lists the queries to which connected clients are subscribed.
+    pub fn st_subscription(&self) -> &StSubscriptionHandle {
+        &StSubscriptionHandle {}
+    }
+}
@@ -6,10 +6,11 @@ use std::{fmt, ops};
 use spacetimedb_lib::buffer::{BufReader, Cursor, DecodeError};
 use spacetimedb_lib::sats::{i256, u256};
 
-pub use spacetimedb_lib::db::raw_def::v9::TableAccess;
+pub use spacetimedb_lib::db::raw_def::v9::{CatchUpPolicy, TableAccess, TableDurability};
 use spacetimedb_lib::Hash;
 pub use spacetimedb_primitives::{ColId, IndexId};
 
+use crate::rt::{has_triggers, run_triggers, TriggerEvent};
 use crate::{bsatn, sys, Address, DeserializeOwned, Identity, IterBuf, Serialize, SpacetimeType, TableId};
 
 /// Implemented for every `TableHandle` struct generated in the client `module_bindings`
@@ -31,9 +32,27 @@ pub trait Table: TableInternal {
     /// i.e. committed_state ∪ insert_table ∖ delete_table.
     #[inline]
     fn iter(&self) -> impl Iterator<Item = Self::Row> {
+        self.iter_with(IterOptions::default())
+    }
+
+    /// Like [`Self::iter`], but fetches rows from the host as configured by `options`.
+    #[inline]
+    fn iter_with(&self, options: IterOptions) -> impl Iterator<Item = Self::Row> {
         let table_id = Self::table_id();
         let iter = sys::datastore_table_scan_bsatn(table_id).expect("datastore_table_scan_bsatn() call failed");
-        TableIter::new(iter)
+        TableIter::new(iter).with_options(options)
+    }
+
+    /// Like [`Self::iter`], but decodes every row into the same reused allocation,
+    /// avoiding per-row `String` and `Vec` allocations in scan-heavy reducers.
+    ///
+    /// The returned [`RowRefIter`] lends out each row only until the next call to
+    /// [`RowRefIter::next_row`], so it is driven with `while let` rather than `for`.
+    #[inline]
+    fn iter_ref(&self) -> RowRefIter<Self::Row> {
+        let table_id = Self::table_id();
+        let iter = sys::datastore_table_scan_bsatn(table_id).expect("datastore_table_scan_bsatn() call failed");
+        RowRefIter::new(TableIter::new(iter))
     }
 
     /// Inserts `row` into the TX state,
@@ -110,23 +129,53 @@ pub trait Table: TableInternal {
         let relation = std::slice::from_ref(&row);
         let buf = IterBuf::serialize(relation).unwrap();
         let count = sys::datastore_delete_all_by_eq_bsatn(Self::table_id(), &buf).unwrap();
+        if count > 0 {
+            run_triggers::<Self>(TriggerEvent::Delete, &row);
+        }
         count > 0
     }
 
+    /// Deletes all rows from this table, returning how many were deleted.
+    ///
+    /// This is much cheaper than deleting each row in turn,
+    /// as the host clears the table's storage and indices wholesale
+    /// and tells subscribed clients that the table was cleared
+    /// rather than sending them every deleted row.
+    ///
+    /// If the table has `on_delete` triggers, the rows are read before the table is cleared
+    /// so that the triggers can run for each of them, which forgoes most of that advantage.
+    fn truncate(&self) -> u64 {
+        let deleted = has_triggers::<Self>(TriggerEvent::Delete).then(|| self.iter().collect::<Vec<_>>());
+        let count = sys::datastore_table_truncate(Self::table_id()).expect("datastore_table_truncate() call failed");
+        for row in deleted.iter().flatten() {
+            run_triggers::<Self>(TriggerEvent::Delete, row);
+        }
+        count
+    }
+
     // Re-integrates the BSATN of the `generated_cols` into `row`.
     #[doc(hidden)]
     fn integrate_generated_columns(row: &mut Self::Row, generated_cols: &[u8]);
+
+    // Moves the sequences of `#[auto_inc(bump)]` columns past the values in `row`.
+    #[doc(hidden)]
+    fn bump_sequences(row: &Self::Row);
 }
 
 #[doc(hidden)]
 pub trait TableInternal: Sized {
     const TABLE_NAME: &'static str;
     const TABLE_ACCESS: TableAccess = TableAccess::Private;
+    const DURABILITY: TableDurability = TableDurability::Durable;
+    const SOFT_DELETE: Option<u64> = None;
+    const COALESCE_MS: Option<u64> = None;
+    const BOUND_TO_CONNECTION: Option<BoundToConnectionDesc> = None;
     const UNIQUE_COLUMNS: &'static [u16];
     const INDEXES: &'static [IndexDesc<'static>];
     const PRIMARY_KEY: Option<u16> = None;
-    const SEQUENCES: &'static [u16];
+    const SEQUENCES: &'static [SequenceDesc];
     const SCHEDULE: Option<ScheduleDesc<'static>> = None;
+    const GENERATED_COLUMNS: &'static [GeneratedColumnDesc<'static>] = &[];
 
     /// Returns the ID of this table.
     fn table_id() -> TableId;
@@ -147,6 +196,28 @@ pub enum IndexAlgo<'a> {
 pub struct ScheduleDesc<'a> {
     pub reducer_name: &'a str,
     pub scheduled_at_column: u16,
+    pub catch_up: CatchUpPolicy,
+}
+
+/// Describes the column binding the rows of a table to connections, if it was named.
+#[derive(Clone, Copy)]
+pub struct BoundToConnectionDesc {
+    pub column: Option<u16>,
+}
+
+/// Describes the sequence of an `#[auto_inc]` column.
+#[derive(Clone, Copy)]
+pub struct SequenceDesc {
+    pub column: u16,
+    pub start: Option<i128>,
+    pub increment: i128,
+}
+
+/// Describes a `#[generated]` column, computed by the database from `expr`.
+#[derive(Clone, Copy)]
+pub struct GeneratedColumnDesc<'a> {
+    pub column: u16,
+    pub expr: &'a str,
 }
 
 /// A UNIQUE constraint violation on a table was attempted.
@@ -272,7 +343,7 @@ impl<Tbl: Table, Col: Index + Column<Table = Tbl>> UniqueColumn<Tbl, Col::ColTyp
     pub const __NEW: Self = Self { _marker: PhantomData };
 
     #[inline]
-    fn get_args(&self, col_val: &Col::ColType) -> BTreeScanArgs {
+    pub(crate) fn get_args(&self, col_val: &Col::ColType) -> BTreeScanArgs {
         BTreeScanArgs {
             data: IterBuf::serialize(&std::ops::Bound::Included(col_val)).unwrap(),
             prefix_elems: 0,
@@ -323,7 +394,15 @@ impl<Tbl: Table, Col: Index + Column<Table = Tbl>> UniqueColumn<Tbl, Col::ColTyp
     /// though as of proposing no such constraints exist.
     #[inline]
     pub fn delete(&self, col_val: impl Borrow<Col::ColType>) -> bool {
-        self._delete(col_val.borrow()).0
+        let col_val = col_val.borrow();
+        let deleted = has_triggers::<Tbl>(TriggerEvent::Delete)
+            .then(|| self._find(col_val))
+            .flatten();
+        let (found, _) = self._delete(col_val);
+        if let Some(row) = deleted.filter(|_| found) {
+            run_triggers::<Tbl>(TriggerEvent::Delete, &row);
+        }
+        found
     }
 
     fn _delete(&self, col_val: &Col::ColType) -> (bool, IterBuf) {
@@ -347,8 +426,16 @@ impl<Tbl: Table, Col: Index + Column<Table = Tbl>> UniqueColumn<Tbl, Col::ColTyp
     /// or if either the delete or the insertion would violate a constraint.
     #[track_caller]
     pub fn update(&self, new_row: Tbl::Row) -> Tbl::Row {
+        let old_row = has_triggers::<Tbl>(TriggerEvent::Delete)
+            .then(|| self._find(Col::get_field(&new_row)))
+            .flatten();
         let buf = IterBuf::take();
-        update::<Tbl>(Col::index_id(), new_row, buf)
+        let new_row = update::<Tbl>(Col::index_id(), new_row, buf);
+        if let Some(old_row) = old_row {
+            run_triggers::<Tbl>(TriggerEvent::Delete, &old_row);
+        }
+        run_triggers::<Tbl>(TriggerEvent::Insert, &new_row);
+        new_row
     }
 }
 
@@ -371,6 +458,14 @@ impl<Tbl: Table, IndexType, Idx: Index> BTreeIndex<Tbl, IndexType, Idx> {
     /// - A range of values for the first indexed column.
     /// - A tuple of values for any prefix of the indexed columns, optionally terminated by a range for the next.
     pub fn filter<B, K>(&self, b: B) -> impl Iterator<Item = Tbl::Row>
+    where
+        B: BTreeIndexBounds<IndexType, K>,
+    {
+        self.filter_with(b, IterOptions::default())
+    }
+
+    /// Like [`Self::filter`], but fetches rows from the host as configured by `options`.
+    pub fn filter_with<B, K>(&self, b: B, options: IterOptions) -> impl Iterator<Item = Tbl::Row>
     where
         B: BTreeIndexBounds<IndexType, K>,
     {
@@ -379,7 +474,21 @@ impl<Tbl: Table, IndexType, Idx: Index> BTreeIndex<Tbl, IndexType, Idx> {
         let (prefix, prefix_elems, rstart, rend) = args.args_for_syscall();
         let iter = sys::datastore_btree_scan_bsatn(index_id, prefix, prefix_elems, rstart, rend)
             .unwrap_or_else(|e| panic!("unexpected error from datastore_btree_scan_bsatn: {e}"));
-        TableIter::new(iter)
+        TableIter::new(iter).with_options(options)
+    }
+
+    /// Like [`Self::filter`], but decodes every row into the same reused allocation.
+    /// See [`Table::iter_ref`].
+    pub fn filter_ref<B, K>(&self, b: B) -> RowRefIter<Tbl::Row>
+    where
+        B: BTreeIndexBounds<IndexType, K>,
+    {
+        let index_id = Idx::index_id();
+        let args = b.get_args();
+        let (prefix, prefix_elems, rstart, rend) = args.args_for_syscall();
+        let iter = sys::datastore_btree_scan_bsatn(index_id, prefix, prefix_elems, rstart, rend)
+            .unwrap_or_else(|e| panic!("unexpected error from datastore_btree_scan_bsatn: {e}"));
+        RowRefIter::new(TableIter::new(iter))
     }
 
     /// Deletes all rows in the database state where the indexed column(s) match the bounds `b`.
@@ -398,9 +507,17 @@ impl<Tbl: Table, IndexType, Idx: Index> BTreeIndex<Tbl, IndexType, Idx> {
         let index_id = Idx::index_id();
         let args = b.get_args();
         let (prefix, prefix_elems, rstart, rend) = args.args_for_syscall();
-        sys::datastore_delete_by_btree_scan_bsatn(index_id, prefix, prefix_elems, rstart, rend)
-            .unwrap_or_else(|e| panic!("unexpected error from datastore_delete_by_btree_scan_bsatn: {e}"))
-            .into()
+        let deleted = has_triggers::<Tbl>(TriggerEvent::Delete).then(|| {
+            let iter = sys::datastore_btree_scan_bsatn(index_id, prefix, prefix_elems, rstart, rend)
+                .unwrap_or_else(|e| panic!("unexpected error from datastore_btree_scan_bsatn: {e}"));
+            TableIter::<Tbl::Row>::new(iter).collect::<Vec<_>>()
+        });
+        let count = sys::datastore_delete_by_btree_scan_bsatn(index_id, prefix, prefix_elems, rstart, rend)
+            .unwrap_or_else(|e| panic!("unexpected error from datastore_delete_by_btree_scan_bsatn: {e}"));
+        for row in deleted.iter().flatten() {
+            run_triggers::<Tbl>(TriggerEvent::Delete, row);
+        }
+        count.into()
     }
 }
 
@@ -727,6 +844,9 @@ pub trait SequenceTrigger: Sized {
     fn is_sequence_trigger(&self) -> bool;
     /// BufReader::get_[< self >]
     fn decode(reader: &mut &[u8]) -> Result<Self, DecodeError>;
+    /// Converts this value to the `i128` domain of sequences,
+    /// or `None` if it doesn't fit.
+    fn to_sequence_value(&self) -> Option<i128>;
     /// Read a generated column from the slice, if this row was a sequence trigger.
     #[inline(always)]
     fn maybe_decode_into(&mut self, gen_cols: &mut &[u8]) {
@@ -752,6 +872,10 @@ macro_rules! impl_seq_trigger {
                 fn decode(reader: &mut &[u8]) -> Result<Self, DecodeError> {
                     reader.$get()
                 }
+                #[inline(always)]
+                fn to_sequence_value(&self) -> Option<i128> {
+                    i128::try_from(*self).ok()
+                }
             }
         )*
     };
@@ -779,6 +903,10 @@ impl SequenceTrigger for crate::sats::i256 {
     fn decode(reader: &mut &[u8]) -> Result<Self, DecodeError> {
         reader.get_i256()
     }
+    #[inline(always)]
+    fn to_sequence_value(&self) -> Option<i128> {
+        i128::try_from(*self).ok()
+    }
 }
 
 impl SequenceTrigger for crate::sats::u256 {
@@ -790,6 +918,58 @@ impl SequenceTrigger for crate::sats::u256 {
     fn decode(reader: &mut &[u8]) -> Result<Self, DecodeError> {
         reader.get_u256()
     }
+    #[inline(always)]
+    fn to_sequence_value(&self) -> Option<i128> {
+        i128::try_from(*self).ok()
+    }
+}
+
+/// A handle to the sequence of an `#[auto_inc]` column of the table `Tbl`.
+///
+/// Obtained from the `{column}_sequence` accessor which `#[spacetimedb::table]`
+/// generates on the table handle for each `#[auto_inc]` column.
+pub struct Sequence<Tbl> {
+    column: u16,
+    _table: PhantomData<Tbl>,
+}
+
+impl<Tbl: Table> Sequence<Tbl> {
+    #[doc(hidden)]
+    pub const fn __new(column: u16) -> Self {
+        Self {
+            column,
+            _table: PhantomData,
+        }
+    }
+
+    /// Returns the value which the sequence will generate next.
+    pub fn peek(&self) -> i128 {
+        sys::sequence_peek(Tbl::table_id(), self.column.into()).expect("sequence_peek() call failed")
+    }
+
+    /// Moves the sequence past `value`, so that it won't later generate `value`,
+    /// returning whether the sequence moved.
+    ///
+    /// This is useful after inserting rows with explicit values for the column,
+    /// which would otherwise collide with values the sequence generates later.
+    /// The sequence does not move if it has already passed `value`
+    /// or if `value` is outside the sequence's range.
+    pub fn advance_past(&self, value: i128) -> bool {
+        sys::sequence_advance_past(Tbl::table_id(), self.column.into(), value)
+            .expect("sequence_advance_past() call failed")
+    }
+}
+
+/// Moves the sequence on the column `col` of `T` past `value`,
+/// unless `value` triggers the sequence itself.
+#[doc(hidden)]
+pub fn bump_sequence<T: Table>(col: u16, value: &impl SequenceTrigger) {
+    if value.is_sequence_trigger() {
+        return;
+    }
+    if let Some(value) = value.to_sequence_value() {
+        sys::sequence_advance_past(T::table_id(), col.into(), value).expect("sequence_advance_past() call failed");
+    }
 }
 
 /// Insert a row of type `T` into the table identified by `table_id`.
@@ -803,20 +983,27 @@ fn insert<T: Table>(mut row: T::Row, mut buf: IterBuf) -> Result<T::Row, TryInse
     // Insert row into table.
     // When table has an auto-incrementing column, we must re-decode the changed `buf`.
     let res = sys::datastore_insert_bsatn(table_id, &mut buf).map(|gen_cols| {
+        T::bump_sequences(&row);
         // Let the caller handle any generated columns written back by `sys::datastore_insert_bsatn` to `buf`.
         T::integrate_generated_columns(&mut row, gen_cols);
         row
     });
-    res.map_err(|e| {
-        let err = match e {
-            sys::Errno::UNIQUE_ALREADY_EXISTS => {
-                T::UniqueConstraintViolation::get().map(TryInsertError::UniqueConstraintViolation)
-            }
-            // sys::Errno::AUTO_INC_OVERFLOW => Tbl::AutoIncOverflow::get().map(TryInsertError::AutoIncOverflow),
-            _ => None,
-        };
-        err.unwrap_or_else(|| panic!("unexpected insertion error: {e}"))
-    })
+    res.map_err(insert_error::<T>)
+        .inspect(|row| run_triggers::<T>(TriggerEvent::Insert, row))
+}
+
+/// Converts the error `e` from inserting into `T` to a [`TryInsertError`],
+/// panicking if it's not one that insertion may be expected to fail with.
+#[track_caller]
+pub(crate) fn insert_error<T: Table>(e: sys::Errno) -> TryInsertError<T> {
+    let err = match e {
+        sys::Errno::UNIQUE_ALREADY_EXISTS => {
+            T::UniqueConstraintViolation::get().map(TryInsertError::UniqueConstraintViolation)
+        }
+        // sys::Errno::AUTO_INC_OVERFLOW => Tbl::AutoIncOverflow::get().map(TryInsertError::AutoIncOverflow),
+        _ => None,
+    };
+    err.unwrap_or_else(|| panic!("unexpected insertion error: {e}"))
 }
 
 /// Update a row of type `T` to `row` using the index identified by `index_id`.
@@ -830,6 +1017,7 @@ fn update<T: Table>(index_id: IndexId, mut row: T::Row, mut buf: IterBuf) -> T::
     // Insert row into table.
     // When table has an auto-incrementing column, we must re-decode the changed `buf`.
     let res = sys::datastore_update_bsatn(table_id, index_id, &mut buf).map(|gen_cols| {
+        T::bump_sequences(&row);
         // Let the caller handle any generated columns written back by `sys::datastore_update_bsatn` to `buf`.
         T::integrate_generated_columns(&mut row, gen_cols);
         row
@@ -839,20 +1027,48 @@ fn update<T: Table>(index_id: IndexId, mut row: T::Row, mut buf: IterBuf) -> T::
     res.unwrap_or_else(|e| panic!("unexpected update error: {e}"))
 }
 
+/// How a row iterator, e.g. from [`Table::iter_with`] or [`BTreeIndex::filter_with`],
+/// fetches rows from the host.
+///
+/// There is no option to prefetch the next chunk into a second buffer while decoding the current one.
+/// A module runs on a single thread, and a host call blocks it until the chunk is copied,
+/// so a prefetch could only happen before decoding, not alongside it.
+/// To stall less between chunks, fetch fewer and larger chunks with `chunk_size` instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IterOptions {
+    /// The number of bytes of rows to fetch from the host per call.
+    ///
+    /// The host hands rows over in chunks of about
+    /// [`ROW_ITER_CHUNK_SIZE`](spacetimedb_primitives::ROW_ITER_CHUNK_SIZE) bytes,
+    /// and fetches at least one chunk per call, however small `chunk_size` is.
+    /// Tables with wide rows may benefit from a larger `chunk_size`, which means fewer calls.
+    pub chunk_size: usize,
+}
+
+impl Default for IterOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: crate::DEFAULT_BUFFER_CAPACITY,
+        }
+    }
+}
+
 /// A table iterator which yields values of the `TableType` corresponding to the table.
-struct TableIter<T: DeserializeOwned> {
+pub(crate) struct TableIter<T: DeserializeOwned> {
     /// The underlying source of our `Buffer`s.
     inner: sys::RowIter,
 
     /// The current position in the buffer, from which `deserializer` can read.
     reader: Cursor<IterBuf>,
 
+    options: IterOptions,
+
     _marker: PhantomData<T>,
 }
 
 impl<T: DeserializeOwned> TableIter<T> {
     #[inline]
-    fn new(iter: sys::RowIter) -> Self {
+    pub(crate) fn new(iter: sys::RowIter) -> Self {
         TableIter::new_with_buf(iter, IterBuf::take())
     }
 
@@ -862,35 +1078,109 @@ impl<T: DeserializeOwned> TableIter<T> {
         TableIter {
             inner: iter,
             reader: Cursor::new(buf),
+            options: IterOptions::default(),
             _marker: PhantomData,
         }
     }
 
+    #[inline]
+    fn with_options(self, options: IterOptions) -> Self {
+        Self { options, ..self }
+    }
+
     fn is_exhausted(&self) -> bool {
         (&self.reader).remaining() == 0 && self.inner.is_exhausted()
     }
 }
 
-impl<T: DeserializeOwned> Iterator for TableIter<T> {
-    type Item = T;
-
-    fn next(&mut self) -> Option<Self::Item> {
+impl<T: DeserializeOwned> TableIter<T> {
+    /// Ensure there are bytes of at least one row in the buffer to decode,
+    /// fetching the next chunk if necessary.
+    ///
+    /// Returns `false` if there are no more rows.
+    fn fill(&mut self) -> bool {
         loop {
             // If we currently have some bytes in the buffer to still decode, do that.
             if (&self.reader).remaining() > 0 {
-                let row = bsatn::from_reader(&mut &self.reader).expect("Failed to decode row!");
-                return Some(row);
+                return true;
             }
 
             // Don't fetch the next chunk if there is none.
             if self.inner.is_exhausted() {
-                return None;
+                return false;
             }
 
             // Otherwise, try to fetch the next chunk while reusing the buffer.
             self.reader.buf.clear();
             self.reader.pos.set(0);
-            self.inner.read(&mut self.reader.buf);
+            self.reader.buf.reserve(self.options.chunk_size);
+            self.inner.read_at_most(&mut self.reader.buf, self.options.chunk_size);
+        }
+    }
+
+    /// Decode the next row into `place`, reusing its allocations.
+    ///
+    /// Returns `false`, leaving `place` untouched, if there are no more rows.
+    fn next_into(&mut self, place: &mut T) -> bool {
+        if !self.fill() {
+            return false;
+        }
+        bsatn::from_reader_in_place(&mut &self.reader, place).expect("Failed to decode row!");
+        true
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for TableIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.fill()
+            .then(|| bsatn::from_reader(&mut &self.reader).expect("Failed to decode row!"))
+    }
+}
+
+/// A row iterator, e.g. from [`Table::iter_ref`] or [`BTreeIndex::filter_ref`],
+/// which decodes every row into the same allocation.
+///
+/// As each row is only borrowed until the next one is decoded,
+/// this is not an [`Iterator`]. Use it like so:
+///
+/// ```ignore
+/// let mut rows = ctx.db.person().iter_ref();
+/// while let Some(person) = rows.next_row() {
+///     log::info!("{}", person.name);
+/// }
+/// ```
+pub struct RowRefIter<T: DeserializeOwned> {
+    inner: TableIter<T>,
+
+    /// The most recently decoded row, whose allocations are reused for the next one.
+    row: Option<T>,
+}
+
+impl<T: DeserializeOwned> RowRefIter<T> {
+    #[inline]
+    fn new(inner: TableIter<T>) -> Self {
+        Self { inner, row: None }
+    }
+
+    /// Decodes and returns the next row, or `None` if there are no more rows.
+    pub fn next_row(&mut self) -> Option<&T> {
+        match &mut self.row {
+            Some(row) => {
+                if !self.inner.next_into(row) {
+                    return None;
+                }
+            }
+            None => self.row = Some(self.inner.next()?),
         }
+        self.row.as_ref()
+    }
+
+    /// Takes ownership of the most recently decoded row, if any.
+    ///
+    /// The next row will then be decoded into a fresh allocation.
+    pub fn take_row(&mut self) -> Option<T> {
+        self.row.take()
     }
 }
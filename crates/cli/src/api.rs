@@ -78,6 +78,18 @@ pub struct StmtResultJson<'a> {
     pub rows: Vec<&'a RawValue>,
 }
 
+/// A line of the newline-delimited JSON response to a streamed SQL query.
+#[derive(Debug, Clone, Deserialize)]
+pub enum StmtResultLine<'a> {
+    Schema(ProductType),
+    #[serde(borrow)]
+    Row(&'a RawValue),
+    Done {
+        rows: u64,
+        truncated: bool,
+    },
+}
+
 pub fn from_json_seed<'de, T: serde::de::DeserializeSeed<'de>>(
     s: &'de str,
     seed: T,
@@ -23,10 +23,17 @@ pub fn get_subcommands() -> Vec<Command> {
         version::cli(),
         publish::cli(),
         delete::cli(),
+        clone::cli(),
+        export::cli(),
+        export_sqlite::cli(),
+        import::cli(),
+        advise_indexes::cli(),
         logs::cli(),
+        maintenance::cli(),
         call::cli(),
         describe::cli(),
         energy::cli(),
+        versions::cli(),
         sql::cli(),
         dns::cli(),
         generate::cli(),
@@ -38,6 +45,7 @@ pub fn get_subcommands() -> Vec<Command> {
         server::cli(),
         upgrade::cli(),
         subscribe::cli(),
+        bench::cli(),
         start::cli(),
     ]
 }
@@ -53,9 +61,16 @@ pub async fn exec_subcommand(
         "call" => call::exec(config, args).await,
         "describe" => describe::exec(config, args).await,
         "energy" => energy::exec(config, args).await,
+        "versions" => versions::exec(config, args).await,
         "publish" => publish::exec(config, args).await,
         "delete" => delete::exec(config, args).await,
+        "clone" => clone::exec(config, args).await,
+        "export" => export::exec(config, args).await,
+        "export-sqlite" => export_sqlite::exec(paths, args).await,
+        "import" => import::exec(config, args).await,
+        "advise-indexes" => advise_indexes::exec(config, args).await,
         "logs" => logs::exec(config, args).await,
+        "maintenance" => maintenance::exec(config, args).await,
         "sql" => sql::exec(config, args).await,
         "rename" => dns::exec(config, args).await,
         "generate" => generate::exec(config, args).await,
@@ -64,6 +79,7 @@ pub async fn exec_subcommand(
         "build" => build::exec(config, args).await.map(drop),
         "server" => server::exec(config, paths, args).await,
         "subscribe" => subscribe::exec(config, args).await,
+        "bench" => bench::exec(config, args).await,
         "start" => return start::exec(paths, args).await,
         "login" => login::exec(config, args).await,
         "logout" => logout::exec(config, args).await,
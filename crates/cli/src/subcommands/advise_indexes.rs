@@ -0,0 +1,68 @@
+use crate::common_args;
+use crate::config::Config;
+use crate::util::{add_auth_header_opt, database_identity, get_auth_header};
+use clap::{Arg, ArgMatches};
+use spacetimedb_client_api_messages::name::IndexSuggestion;
+use tabled::settings::Style;
+use tabled::{Table, Tabled};
+
+pub fn cli() -> clap::Command {
+    clap::Command::new("advise-indexes")
+        .about("Suggests indexes for the columns which queries of a SpacetimeDB database filter on without one")
+        .arg(
+            Arg::new("database")
+                .required(true)
+                .help("The name or identity of the database to advise on"),
+        )
+        .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))
+        .after_help("Run `spacetime help advise-indexes` for more detailed information.\n")
+}
+
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+struct SuggestionRow {
+    table: String,
+    column: String,
+    scans: u64,
+    #[tabled(rename = "ROWS SCANNED")]
+    rows_scanned: u64,
+    #[tabled(rename = "EST. ROWS SAVED")]
+    rows_saved: u64,
+}
+
+pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let server = args.get_one::<String>("server").map(|s| s.as_ref());
+    let database = args.get_one::<String>("database").unwrap();
+
+    let identity = database_identity(&config, database, server).await?;
+    let host_url = config.get_host_url(server)?;
+
+    let builder = reqwest::Client::new().get(format!("{host_url}/database/advise_indexes/{identity}"));
+    let auth_header = get_auth_header(&config, false)?;
+    let builder = add_auth_header_opt(builder, &auth_header);
+    let res = builder.send().await?;
+    if res.status().is_client_error() || res.status().is_server_error() {
+        let err = res.text().await?;
+        anyhow::bail!(err)
+    }
+
+    let suggestions: Vec<IndexSuggestion> = res.json().await?;
+    if suggestions.is_empty() {
+        println!("No queries of {database} have scanned a table where an index would help.");
+        return Ok(());
+    }
+
+    let rows = suggestions.into_iter().map(|s| SuggestionRow {
+        table: s.table,
+        column: s.column,
+        scans: s.scans,
+        rows_scanned: s.rows_scanned,
+        rows_saved: s.rows_saved,
+    });
+    let mut table = Table::new(rows);
+    table.with(Style::psql());
+    println!("{table}");
+    println!("\nAdd an index with `#[index(btree)]` on the column in your module.");
+
+    Ok(())
+}
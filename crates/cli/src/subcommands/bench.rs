@@ -0,0 +1,511 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use clap::{value_parser, Arg, ArgAction, ArgGroup, ArgMatches};
+use futures::TryStreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use spacetimedb_client_api_messages::websocket as ws;
+use tokio::task::JoinSet;
+
+use crate::api::{build_client, Connection};
+use crate::common_args;
+use crate::publish;
+use crate::sql::parse_req;
+use crate::subscribe;
+use crate::util::auth_header_for_token;
+use crate::Config;
+
+pub fn cli() -> clap::Command {
+    clap::Command::new("bench")
+        .about("Drives a load of reducer calls against a database and reports throughput and latency")
+        .arg(
+            Arg::new("database")
+                .required(true)
+                .help("The name or identity of the database to benchmark"),
+        )
+        .arg(
+            Arg::new("project_path")
+                .long("project-path")
+                .short('p')
+                .value_parser(value_parser!(PathBuf))
+                .help("Build and publish the module at this path to the database before benchmarking it"),
+        )
+        .arg(
+            Arg::new("workload")
+                .long("workload")
+                .short('w')
+                .value_parser(value_parser!(Workload))
+                .help("A workload of the standard benchmark module to run"),
+        )
+        .arg(
+            Arg::new("reducer")
+                .long("reducer")
+                .short('r')
+                .action(ArgAction::Append)
+                .help(
+                    "A reducer to call, as `NAME` or `NAME:ARGS` where ARGS is a JSON array of arguments. \
+                     Repeat to call a mix of reducers, in round-robin order",
+                ),
+        )
+        .group(ArgGroup::new("load").args(["workload", "reducer"]).required(true))
+        .arg(
+            Arg::new("row_size")
+                .long("row-size")
+                .default_value("64")
+                .value_parser(value_parser!(u32))
+                .requires("workload")
+                .help("The size in bytes of the rows written by the workload"),
+        )
+        .arg(
+            Arg::new("rows")
+                .long("rows")
+                .default_value("1000")
+                .value_parser(value_parser!(u32).range(1..))
+                .requires("workload")
+                .help("The number of rows updated and read by the `update`, `read` and `mixed` workloads"),
+        )
+        .arg(
+            Arg::new("clients")
+                .long("clients")
+                .short('c')
+                .default_value("1")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("The number of concurrent clients making calls"),
+        )
+        .arg(
+            Arg::new("calls")
+                .long("calls")
+                .short('n')
+                .default_value("1000")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("The total number of reducer calls to make, across all clients"),
+        )
+        .arg(
+            Arg::new("subscribers")
+                .long("subscribers")
+                .default_value("0")
+                .value_parser(value_parser!(u32))
+                .help("The number of websocket clients subscribed to `--query` while the calls are made"),
+        )
+        .arg(
+            Arg::new("query")
+                .long("query")
+                .action(ArgAction::Append)
+                .help("A SQL query for the subscribers, by default on the table written by the workload"),
+        )
+        .arg(
+            Arg::new("shared_identity")
+                .long("shared-identity")
+                .action(ArgAction::SetTrue)
+                .help("Make every call and subscription with your identity, instead of a new identity for each client"),
+        )
+        .arg(common_args::anonymous())
+        .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))
+        .arg(common_args::yes())
+        .after_help(
+            "The workloads run against `modules/bench-load` in the SpacetimeDB repository, \
+             which `--project-path` can publish. `--reducer` calls the reducers of any module.\n",
+        )
+}
+
+/// A workload of the standard benchmark module, `modules/bench-load`.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Workload {
+    /// Append new rows.
+    Insert,
+    /// Overwrite existing rows.
+    Update,
+    /// Read existing rows.
+    Read,
+    /// Read existing rows, overwriting one in every four calls.
+    Mixed,
+}
+
+impl Workload {
+    /// Returns the table written by this workload.
+    fn table(self) -> &'static str {
+        match self {
+            Workload::Insert => "log_row",
+            Workload::Update | Workload::Read | Workload::Mixed => "keyed_row",
+        }
+    }
+
+    /// Returns the call populating the `rows` rows used by this workload, if it uses any.
+    fn setup(self, rows: u32, row_size: u32) -> Option<ReducerCall> {
+        (self != Workload::Insert).then(|| ReducerCall::new("fill_rows", json!([rows, row_size])))
+    }
+
+    /// Returns the `i`th call of this workload.
+    fn call(self, i: u32, rows: u32, row_size: u32) -> ReducerCall {
+        let id = i % rows;
+        match self {
+            Workload::Insert => ReducerCall::new("insert_row", json!([row_size])),
+            Workload::Update => ReducerCall::new("update_row", json!([id, row_size])),
+            Workload::Mixed if i % 4 == 0 => ReducerCall::new("update_row", json!([id, row_size])),
+            Workload::Read | Workload::Mixed => ReducerCall::new("read_row", json!([id])),
+        }
+    }
+}
+
+/// The calls made by the clients.
+enum Load {
+    Workload {
+        workload: Workload,
+        rows: u32,
+        row_size: u32,
+    },
+    /// Calls to these reducers, in round-robin order.
+    Reducers(Vec<ReducerCall>),
+}
+
+impl Load {
+    /// Returns the `i`th call to make.
+    fn call(&self, i: u32) -> ReducerCall {
+        match self {
+            Load::Workload {
+                workload,
+                rows,
+                row_size,
+            } => workload.call(i, *rows, *row_size),
+            Load::Reducers(reducers) => reducers[i as usize % reducers.len()].clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ReducerCall {
+    name: String,
+    /// The arguments, as a JSON array.
+    args: String,
+}
+
+impl ReducerCall {
+    fn new(name: &str, args: serde_json::Value) -> Self {
+        Self {
+            name: name.to_owned(),
+            args: args.to_string(),
+        }
+    }
+}
+
+fn parse_reducer_call(s: &str) -> ReducerCall {
+    match s.split_once(':') {
+        Some((name, args)) => ReducerCall {
+            name: name.to_owned(),
+            args: args.to_owned(),
+        },
+        None => ReducerCall {
+            name: s.to_owned(),
+            args: "[]".to_owned(),
+        },
+    }
+}
+
+/// Returns the `p`th percentile of the non-empty `sorted` latencies.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    sorted[((sorted.len() - 1) as f64 * p).round() as usize]
+}
+
+pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let load = match args.get_one::<Workload>("workload") {
+        Some(&workload) => Load::Workload {
+            workload,
+            rows: *args.get_one::<u32>("rows").unwrap(),
+            row_size: *args.get_one::<u32>("row_size").unwrap(),
+        },
+        None => Load::Reducers(
+            args.get_many::<String>("reducer")
+                .unwrap()
+                .map(|r| parse_reducer_call(r))
+                .collect(),
+        ),
+    };
+    let clients = *args.get_one::<u32>("clients").unwrap();
+    let calls = *args.get_one::<u32>("calls").unwrap();
+    let subscribers = *args.get_one::<u32>("subscribers").unwrap();
+    let shared_identity = args.get_flag("shared_identity");
+    let mut queries = args
+        .get_many::<String>("query")
+        .unwrap_or_default()
+        .map(|q| q.as_str().into())
+        .collect::<Box<[Box<str>]>>();
+    if queries.is_empty() {
+        if let Load::Workload { workload, .. } = &load {
+            queries = [format!("SELECT * FROM {}", workload.table()).into()].into();
+        }
+    }
+    if subscribers > 0 && queries.is_empty() {
+        anyhow::bail!("`--subscribers` requires at least one `--query`");
+    }
+
+    if let Some(project_path) = args.get_one::<PathBuf>("project_path") {
+        publish::exec(config.clone(), &publish_args(args, project_path)).await?;
+    }
+    let con = parse_req(config, args).await?;
+
+    if let Load::Workload {
+        workload,
+        rows,
+        row_size,
+    } = &load
+    {
+        if let Some(setup) = workload.setup(*rows, *row_size) {
+            call_reducer(&build_client(&con), &con, &setup)
+                .await
+                .with_context(|| format!("failed to set up the `{workload:?}` workload"))?;
+        }
+    }
+
+    // Connect the subscribers before making any calls, so that they observe all of them.
+    let updates_received = Arc::new(AtomicU64::new(0));
+    let mut subscriber_tasks = JoinSet::new();
+    for _ in 0..subscribers {
+        let con = client_connection(&con, shared_identity).await?;
+        let mut ws = subscribe::connect(&con).await?;
+        subscribe::subscribe(&mut ws, queries.clone(), None).await?;
+        subscribe::await_initial_update(&mut ws, None).await?;
+        let updates_received = updates_received.clone();
+        subscriber_tasks.spawn(async move {
+            while let Ok(Some(msg)) = ws.try_next().await {
+                if let Some(ws::ServerMessage::TransactionUpdate(_) | ws::ServerMessage::TransactionUpdateLight(_)) =
+                    subscribe::parse_msg_json(&msg)
+                {
+                    updates_received.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        });
+    }
+
+    // Create the identities of the clients up front, so that doing so isn't measured.
+    let mut client_cons = Vec::with_capacity(clients as usize);
+    for _ in 0..clients {
+        client_cons.push(client_connection(&con, shared_identity).await?);
+    }
+
+    let load = Arc::new(load);
+    let next_call = Arc::new(AtomicU32::new(0));
+    let start = Instant::now();
+    let mut client_tasks = JoinSet::new();
+    for con in client_cons {
+        let load = load.clone();
+        let next_call = next_call.clone();
+        client_tasks.spawn(run_client(con, load, next_call, calls));
+    }
+
+    let mut latencies = Vec::with_capacity(calls as usize);
+    let mut failures = 0;
+    while let Some(res) = client_tasks.join_next().await {
+        let (client_latencies, client_failures) = res.context("client task panicked")?;
+        latencies.extend(client_latencies);
+        failures += client_failures;
+    }
+    let elapsed = start.elapsed();
+
+    // Give the subscribers a moment to receive the updates for the last calls.
+    if subscribers > 0 {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        subscriber_tasks.abort_all();
+    }
+
+    latencies.sort_unstable();
+
+    println!("calls:       {} ({} failed)", latencies.len(), failures);
+    println!("clients:     {clients}");
+    println!("elapsed:     {elapsed:.2?}");
+    println!(
+        "throughput:  {:.1} calls/s",
+        latencies.len() as f64 / elapsed.as_secs_f64()
+    );
+    println!(
+        "latency:     p50 {:.2?}  p90 {:.2?}  p99 {:.2?}  max {:.2?}",
+        percentile(&latencies, 0.5),
+        percentile(&latencies, 0.9),
+        percentile(&latencies, 0.99),
+        percentile(&latencies, 1.0),
+    );
+    if subscribers > 0 {
+        let updates_received = updates_received.load(Ordering::Relaxed);
+        println!(
+            "subscribers: {subscribers} received {updates_received} updates ({:.1} updates/s)",
+            updates_received as f64 / elapsed.as_secs_f64()
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the arguments of `spacetime publish` publishing `project_path` to the database of `args`.
+fn publish_args(args: &ArgMatches, project_path: &Path) -> ArgMatches {
+    let mut publish_args: Vec<OsString> = vec!["publish".into(), "--project-path".into(), project_path.into()];
+    publish_args.push(args.get_one::<String>("database").unwrap().into());
+    if let Some(server) = args.get_one::<String>("server") {
+        publish_args.extend(["--server".into(), server.into()]);
+    }
+    if args.get_flag("anon_identity") {
+        publish_args.push("--anonymous".into());
+    }
+    if args.get_flag("force") {
+        publish_args.push("--yes".into());
+    }
+    publish::cli().get_matches_from(publish_args)
+}
+
+#[derive(Deserialize)]
+struct NewIdentity {
+    token: String,
+}
+
+/// Returns `con`, or a copy of it authenticated as a new identity unless `shared_identity` is set.
+async fn client_connection(con: &Connection, shared_identity: bool) -> anyhow::Result<Connection> {
+    if shared_identity {
+        return Ok(con.clone());
+    }
+    let NewIdentity { token } = reqwest::Client::new()
+        .post(format!("{}/identity", con.host))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("failed to create an identity for a client")?;
+    Ok(Connection {
+        auth_header: Some(auth_header_for_token(&token)),
+        ..con.clone()
+    })
+}
+
+async fn call_reducer(client: &reqwest::Client, con: &Connection, call: &ReducerCall) -> reqwest::Result<()> {
+    client
+        .post(format!(
+            "{}/database/call/{}/{}",
+            con.host, con.database_identity, call.name
+        ))
+        .body(call.args.clone())
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Make calls until `calls` have been made across all clients,
+/// returning the latency of each call and the number of calls which failed.
+async fn run_client(con: Connection, load: Arc<Load>, next_call: Arc<AtomicU32>, calls: u32) -> (Vec<Duration>, u32) {
+    let client = build_client(&con);
+    let mut latencies = Vec::new();
+    let mut failures = 0;
+    loop {
+        let i = next_call.fetch_add(1, Ordering::Relaxed);
+        if i >= calls {
+            break;
+        }
+        let call = load.call(i);
+
+        let started = Instant::now();
+        let res = call_reducer(&client, &con, &call).await;
+        latencies.push(started.elapsed());
+        if res.is_err() {
+            failures += 1;
+        }
+    }
+    (latencies, failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_reducer_calls() {
+        assert_eq!(parse_reducer_call("empty"), ReducerCall::new("empty", json!([])));
+        assert_eq!(
+            parse_reducer_call(r#"insert:[1, "a"]"#),
+            ReducerCall {
+                name: "insert".to_owned(),
+                args: r#"[1, "a"]"#.to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn workloads_write_rows_of_the_requested_size() {
+        let names = |workload: Workload| (0..8).map(|i| workload.call(i, 3, 256).name).collect::<Vec<_>>();
+        assert_eq!(names(Workload::Insert), ["insert_row"; 8]);
+        assert_eq!(names(Workload::Update), ["update_row"; 8]);
+        assert_eq!(names(Workload::Read), ["read_row"; 8]);
+        assert_eq!(
+            names(Workload::Mixed),
+            ["update_row", "read_row", "read_row", "read_row"].repeat(2)
+        );
+
+        assert_eq!(Workload::Insert.call(5, 3, 256).args, "[256]");
+        assert_eq!(Workload::Update.call(5, 3, 256).args, "[2,256]");
+        assert_eq!(Workload::Read.call(5, 3, 256).args, "[2]");
+
+        assert_eq!(Workload::Insert.setup(3, 256), None);
+        assert_eq!(
+            Workload::Mixed.setup(3, 256),
+            Some(ReducerCall::new("fill_rows", json!([3, 256])))
+        );
+    }
+
+    #[test]
+    fn reducer_mixes_are_called_in_round_robin_order() {
+        let load = Load::Reducers(vec![
+            ReducerCall::new("a", json!([])),
+            ReducerCall::new("b", json!([1])),
+        ]);
+        let names = (0..5).map(|i| load.call(i).name).collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "a", "b", "a"]);
+    }
+
+    #[test]
+    fn percentiles() {
+        let latencies = (1..=100).map(Duration::from_millis).collect::<Vec<_>>();
+        assert_eq!(percentile(&latencies, 0.0), Duration::from_millis(1));
+        assert_eq!(percentile(&latencies, 0.5), Duration::from_millis(51));
+        assert_eq!(percentile(&latencies, 0.99), Duration::from_millis(99));
+        assert_eq!(percentile(&latencies, 1.0), Duration::from_millis(100));
+        assert_eq!(percentile(&latencies[..1], 0.99), Duration::from_millis(1));
+    }
+
+    #[test]
+    fn requires_a_workload_or_reducers() {
+        assert!(cli().try_get_matches_from(["bench", "db"]).is_err());
+        assert!(cli()
+            .try_get_matches_from(["bench", "db", "--workload", "mixed", "--reducer", "empty"])
+            .is_err());
+        assert!(cli()
+            .try_get_matches_from(["bench", "db", "--reducer", "empty", "--row-size", "1024"])
+            .is_err());
+
+        let args = cli()
+            .try_get_matches_from(["bench", "db", "-w", "update", "--row-size", "1024", "--rows", "10"])
+            .unwrap();
+        assert_eq!(args.get_one::<Workload>("workload"), Some(&Workload::Update));
+        assert_eq!(args.get_one::<u32>("row_size"), Some(&1024));
+        assert_eq!(args.get_one::<u32>("rows"), Some(&10));
+    }
+
+    #[test]
+    fn publishes_to_the_benchmarked_database() {
+        let args = cli()
+            .try_get_matches_from(["bench", "db", "-w", "insert", "-p", "modules/bench-load", "-s", "local"])
+            .unwrap();
+        let publish_args = publish_args(&args, args.get_one::<PathBuf>("project_path").unwrap());
+        assert_eq!(
+            publish_args.get_one::<PathBuf>("project_path"),
+            Some(&PathBuf::from("modules/bench-load"))
+        );
+        assert_eq!(
+            publish_args.get_one::<String>("name|identity").map(|s| &**s),
+            Some("db")
+        );
+        assert_eq!(publish_args.get_one::<String>("server").map(|s| &**s), Some("local"));
+        assert!(!publish_args.get_flag("anon_identity"));
+    }
+}
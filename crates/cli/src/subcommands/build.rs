@@ -27,7 +27,7 @@ pub fn cli() -> clap::Command {
                 .long("debug")
                 .short('d')
                 .action(SetTrue)
-                .help("Builds the module using debug instead of release (intended to speed up local iteration, not recommended for CI)"),
+                .help("Builds the module using debug instead of release, retaining debug info so that panic backtraces include source locations (intended to speed up local iteration, not recommended for CI)"),
         )
 }
 
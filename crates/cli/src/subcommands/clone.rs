@@ -0,0 +1,52 @@
+use crate::common_args;
+use crate::config::Config;
+use crate::util::{add_auth_header_opt, database_identity, get_auth_header};
+use clap::{Arg, ArgMatches};
+use spacetimedb_client_api_messages::name::CloneResult;
+
+pub fn cli() -> clap::Command {
+    clap::Command::new("clone")
+        .about("Creates a new SpacetimeDB database as a copy of an existing one")
+        .arg(
+            Arg::new("database")
+                .required(true)
+                .help("The name or identity of the database to clone"),
+        )
+        .arg(
+            Arg::new("name")
+                .long("name")
+                .short('n')
+                .help("A name to register for the new database"),
+        )
+        .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))
+        .after_help("Run `spacetime help clone` for more detailed information.\n")
+}
+
+pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let server = args.get_one::<String>("server").map(|s| s.as_ref());
+    let database = args.get_one::<String>("database").unwrap();
+    let name = args.get_one::<String>("name");
+
+    let identity = database_identity(&config, database, server).await?;
+    let host_url = config.get_host_url(server)?;
+
+    let mut builder = reqwest::Client::new().post(format!("{host_url}/database/clone/{identity}"));
+    if let Some(name) = name {
+        builder = builder.query(&[("name", name)]);
+    }
+    let auth_header = get_auth_header(&config, false)?;
+    let builder = add_auth_header_opt(builder, &auth_header);
+    let res = builder.send().await?.error_for_status()?;
+
+    let CloneResult {
+        domain,
+        database_identity,
+    } = res.json().await?;
+    if let Some(domain) = domain {
+        println!("Cloned {database} into new database with name: {domain}, identity: {database_identity}");
+    } else {
+        println!("Cloned {database} into new database with identity: {database_identity}");
+    }
+
+    Ok(())
+}
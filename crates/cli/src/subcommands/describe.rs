@@ -1,11 +1,20 @@
+use crate::api::{ClientApi, Connection};
 use crate::common_args;
 use crate::config::Config;
+use crate::sql::{parse_req, run_sql};
 use crate::util::{add_auth_header_opt, database_identity, get_auth_header};
-use clap::{Arg, ArgMatches};
+use clap::{Arg, ArgAction, ArgMatches};
+use itertools::Itertools;
+use spacetimedb_lib::sats::algebraic_type::fmt::fmt_algebraic_type;
+use spacetimedb_lib::sats::WithTypespace;
+use spacetimedb_schema::auto_migrate::{ponder_auto_migrate_partial, AutoMigratePlan, AutoMigrateStep};
+use spacetimedb_schema::def::{ModuleDef, ReducerDef, TableDef};
+use spacetimedb_schema::identifier::Identifier;
+use std::fmt::Debug;
 
 pub fn cli() -> clap::Command {
     clap::Command::new("describe")
-        .about("Describe the structure of a database or entities within it")
+        .about("Describe the structure of a database or entities within it, along with live usage statistics")
         .arg(
             Arg::new("database")
                 .required(true)
@@ -21,17 +30,50 @@ pub fn cli() -> clap::Command {
                 .requires("entity_type")
                 .help("The name of the entity to describe"),
         )
+        .arg(
+            Arg::new("subscriptions")
+                .long("subscriptions")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("entity_type")
+                .help("List the queries connected clients are subscribed to, and the time spent evaluating them"),
+        )
+        .arg(
+            Arg::new("compare")
+                .long("compare")
+                .value_name("OTHER_DATABASE")
+                .conflicts_with_all(["entity_type", "subscriptions"])
+                .help("List how the schema and reducers of this database differ from those of another database"),
+        )
         .arg(common_args::anonymous())
         .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))
         .after_help("Run `spacetime help describe` for more detailed information.\n")
 }
 
 pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    if args.get_flag("subscriptions") {
+        let api = ClientApi::new(parse_req(config, args).await?);
+        return run_sql(api.sql(), "SELECT * FROM st_subscription", false).await;
+    }
+
     let database = args.get_one::<String>("database").unwrap();
     let entity_name = args.get_one::<String>("entity_name");
     let entity_type = args.get_one::<String>("entity_type");
     let server = args.get_one::<String>("server").map(|s| s.as_ref());
 
+    if let Some(other) = args.get_one::<String>("compare") {
+        let other_identity = database_identity(&config, other, server).await?;
+        let con = parse_req(config, args).await?;
+        let other_con = Connection {
+            database_identity: other_identity,
+            database: other.clone(),
+            ..con.clone()
+        };
+        let new = ModuleDef::try_from(ClientApi::new(con).module_def().await?)?;
+        let old = ModuleDef::try_from(ClientApi::new(other_con).module_def().await?)?;
+        print_comparison(other, &old, database, &new);
+        return Ok(());
+    }
+
     let anon_identity = args.get_flag("anon_identity");
 
     let database_identity = database_identity(&config, database, server).await?;
@@ -54,3 +96,102 @@ pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error
 
     Ok(())
 }
+
+/// Prints how the module `new` of the database `new_name`
+/// differs from the module `old` of the database `old_name`.
+///
+/// Schema differences are found by the auto-migration planner,
+/// and include those which it could not migrate automatically.
+fn print_comparison(old_name: &str, old: &ModuleDef, new_name: &str, new: &ModuleDef) {
+    let (plan, errors) = ponder_auto_migrate_partial(old, new);
+    let reducers = reducer_changes(old, new);
+
+    if plan.steps.is_empty() && errors.is_empty() && reducers.is_empty() {
+        println!("No differences between `{old_name}` and `{new_name}`.");
+        return;
+    }
+    println!("Changes from `{old_name}` to `{new_name}`:");
+
+    if !plan.steps.is_empty() || !errors.is_empty() {
+        println!("\nSchema:");
+        for step in &plan.steps {
+            println!("  {}", render_step(&plan, step));
+        }
+        if !errors.is_empty() {
+            println!("  Requiring a manual migration:");
+            for error in errors.iter().sorted().dedup() {
+                println!("    {error}");
+            }
+        }
+    }
+
+    if !reducers.is_empty() {
+        println!("\nReducers:");
+        for change in reducers {
+            println!("  {change}");
+        }
+    }
+}
+
+fn render_step(plan: &AutoMigratePlan<'_>, step: &AutoMigrateStep<'_>) -> String {
+    match *step {
+        AutoMigrateStep::AddTable(table) => format!("+ table `{table}`"),
+        AutoMigrateStep::AddIndex(index) => format!("+ index `{index}`"),
+        AutoMigrateStep::AddSequence(sequence) => format!("+ sequence `{sequence}`"),
+        AutoMigrateStep::AddSchedule(schedule) => format!("+ schedule `{schedule}`"),
+        AutoMigrateStep::AddRowLevelSecurity(sql) => format!("+ row-level security `{sql}`"),
+        AutoMigrateStep::RemoveIndex(index) => format!("- index `{index}`"),
+        AutoMigrateStep::RemoveConstraint(constraint) => format!("- constraint `{constraint}`"),
+        AutoMigrateStep::RemoveSequence(sequence) => format!("- sequence `{sequence}`"),
+        AutoMigrateStep::RemoveSchedule(schedule) => format!("- schedule `{schedule}`"),
+        AutoMigrateStep::RemoveRowLevelSecurity(sql) => format!("- row-level security `{sql}`"),
+        AutoMigrateStep::ChangeAccess(table) => render_table_change(plan, table, "access", |t| t.table_access),
+        AutoMigrateStep::ChangeDurability(table) => render_table_change(plan, table, "durability", |t| t.durability),
+    }
+}
+
+fn render_table_change<T: Debug>(
+    plan: &AutoMigratePlan<'_>,
+    table: &Identifier,
+    what: &str,
+    get: impl Fn(&TableDef) -> T,
+) -> String {
+    let old = get(plan.old.lookup_expect::<TableDef>(table));
+    let new = get(plan.new.lookup_expect::<TableDef>(table));
+    format!("~ table `{table}`: {what} {old:?} -> {new:?}")
+}
+
+/// Returns the reducers added to, removed from, or with a changed signature in `new` compared to `old`.
+fn reducer_changes(old: &ModuleDef, new: &ModuleDef) -> Vec<String> {
+    let removed_or_changed = old.reducers().filter_map(|old_reducer| {
+        let old_signature = reducer_signature(old, old_reducer);
+        match new.reducer(&old_reducer.name) {
+            None => Some(format!("- {old_signature}")),
+            Some(new_reducer) => {
+                let new_signature = reducer_signature(new, new_reducer);
+                (old_signature != new_signature).then(|| format!("~ {old_signature} -> {new_signature}"))
+            }
+        }
+    });
+    let added = new
+        .reducers()
+        .filter(|new_reducer| old.reducer(&new_reducer.name).is_none())
+        .map(|new_reducer| format!("+ {}", reducer_signature(new, new_reducer)));
+    // Order by reducer name, which follows the sign.
+    removed_or_changed
+        .chain(added)
+        .sorted_by(|a, b| a[2..].cmp(&b[2..]))
+        .collect()
+}
+
+/// Formats the name and parameters of `reducer`, with all type references resolved,
+/// so that signatures from different modules can be compared.
+fn reducer_signature(module: &ModuleDef, reducer: &ReducerDef) -> String {
+    let params = reducer.params.elements.iter().map(|param| {
+        let ty = WithTypespace::new(module.typespace(), &param.algebraic_type)
+            .resolve_refs()
+            .unwrap_or_else(|_| param.algebraic_type.clone());
+        format!("{}: {}", param.name().unwrap_or("_"), fmt_algebraic_type(&ty))
+    });
+    format!("{}({})", reducer.name, params.format(", "))
+}
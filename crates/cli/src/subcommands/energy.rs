@@ -1,9 +1,13 @@
 // use clap::Arg;
 use crate::common_args;
-use clap::ArgMatches;
+use clap::{Arg, ArgAction, ArgMatches};
+use std::collections::BTreeMap;
 
 use crate::config::Config;
-use crate::util;
+use crate::util::{self, add_auth_header_opt, get_auth_header};
+use spacetimedb_client_api_messages::energy::{EnergyLedgerEntry, LedgerDay};
+use tabled::settings::Style;
+use tabled::{Table, Tabled};
 
 pub fn cli() -> clap::Command {
     clap::Command::new("energy")
@@ -14,24 +18,51 @@ pub fn cli() -> clap::Command {
 }
 
 fn get_energy_subcommands() -> Vec<clap::Command> {
-    vec![clap::Command::new("balance")
-        .about("Show current energy balance for an identity")
-        .arg(
-            common_args::identity()
-                .help("The identity to check the balance for")
-                .long_help(
+    vec![
+        clap::Command::new("balance")
+            .about("Show current energy balance for an identity")
+            .arg(
+                common_args::identity()
+                    .help("The identity to check the balance for")
+                    .long_help(
                     "The identity to check the balance for. If no identity is provided, the default one will be used.",
                 ),
-        )
-        .arg(
-            common_args::server()
-                .help("The nickname, host name or URL of the server from which to request balance information"),
-        )]
+            )
+            .arg(
+                common_args::server()
+                    .help("The nickname, host name or URL of the server from which to request balance information"),
+            ),
+        clap::Command::new("report")
+            .about("Show the energy used per day by the reducers and storage of your databases")
+            .arg(
+                Arg::new("from")
+                    .long("from")
+                    .value_parser(parse_date)
+                    .help("The first day to report, as YYYY-MM-DD (UTC)"),
+            )
+            .arg(
+                Arg::new("to")
+                    .long("to")
+                    .value_parser(parse_date)
+                    .help("The last day to report, as YYYY-MM-DD (UTC)"),
+            )
+            .arg(
+                Arg::new("csv")
+                    .long("csv")
+                    .action(ArgAction::SetTrue)
+                    .help("Print the ledger as CSV, e.g. for importing into a billing system"),
+            )
+            .arg(
+                common_args::server()
+                    .help("The nickname, host name or URL of the server from which to request the energy ledger"),
+            ),
+    ]
 }
 
 async fn exec_subcommand(config: Config, cmd: &str, args: &ArgMatches) -> Result<(), anyhow::Error> {
     match cmd {
         "balance" => exec_status(config, args).await,
+        "report" => exec_report(config, args).await,
         unknown => Err(anyhow::anyhow!("Invalid subcommand: {}", unknown)),
     }
 }
@@ -64,3 +95,94 @@ async fn exec_status(config: Config, args: &ArgMatches) -> Result<(), anyhow::Er
 
     Ok(())
 }
+
+fn parse_date(date: &str) -> Result<chrono::NaiveDate, String> {
+    date.parse().map_err(|e| format!("{e}"))
+}
+
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+struct LedgerRow {
+    day: LedgerDay,
+    database: String,
+    resource: String,
+    calls: u64,
+    #[tabled(rename = "ENERGY (eV)")]
+    energy_used: u128,
+}
+
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+struct DailyTotalRow {
+    day: LedgerDay,
+    calls: u64,
+    #[tabled(rename = "ENERGY (eV)")]
+    energy_used: u128,
+}
+
+async fn exec_report(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let server = args.get_one::<String>("server").map(|s| s.as_ref());
+    let from = args.get_one::<chrono::NaiveDate>("from");
+    let to = args.get_one::<chrono::NaiveDate>("to");
+    let csv = args.get_flag("csv");
+    let identity = util::decode_identity(&config)?;
+
+    let mut query = vec![("format", if csv { "csv" } else { "json" }.to_owned())];
+    query.extend(from.map(|from| ("from", from.to_string())));
+    query.extend(to.map(|to| ("to", to.to_string())));
+
+    let builder = reqwest::Client::new()
+        .get(format!("{}/energy/{}/ledger", config.get_host_url(server)?, identity))
+        .query(&query);
+    let auth_header = get_auth_header(&config, false)?;
+    let res = add_auth_header_opt(builder, &auth_header).send().await?;
+    if res.status().is_client_error() || res.status().is_server_error() {
+        let err = res.text().await?;
+        anyhow::bail!(err)
+    }
+
+    if csv {
+        print!("{}", res.text().await?);
+        return Ok(());
+    }
+
+    let entries: Vec<EnergyLedgerEntry> = res.json().await?;
+    if entries.is_empty() {
+        println!("No energy usage recorded for {identity} in this period.");
+        return Ok(());
+    }
+
+    let mut totals = BTreeMap::<LedgerDay, (u64, u128)>::new();
+    for entry in &entries {
+        let total = totals.entry(entry.day).or_default();
+        total.0 += entry.calls;
+        total.1 += entry.energy_used.get();
+    }
+
+    let rows = entries.into_iter().map(|entry| LedgerRow {
+        day: entry.day,
+        database: entry.database_identity.to_string(),
+        resource: match entry.reducer {
+            Some(reducer) => format!("{} {reducer}", entry.resource),
+            None => entry.resource.to_string(),
+        },
+        calls: entry.calls,
+        energy_used: entry.energy_used.get(),
+    });
+    let mut table = Table::new(rows);
+    table.with(Style::psql());
+    println!("{table}");
+
+    let total: u128 = totals.values().map(|(_, energy_used)| energy_used).sum();
+    let rows = totals.into_iter().map(|(day, (calls, energy_used))| DailyTotalRow {
+        day,
+        calls,
+        energy_used,
+    });
+    let mut table = Table::new(rows);
+    table.with(Style::psql());
+    println!("\nDaily totals:\n{table}");
+    println!("\nTotal: {total}eV");
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use crate::common_args;
+use crate::config::Config;
+use crate::util::{add_auth_header_opt, database_identity, get_auth_header};
+use anyhow::Context;
+use clap::{Arg, ArgMatches};
+use std::io::Write;
+use std::path::PathBuf;
+
+pub fn cli() -> clap::Command {
+    clap::Command::new("export")
+        .about("Exports the rows of a table in a SpacetimeDB database to a file")
+        .arg(
+            Arg::new("database")
+                .required(true)
+                .help("The name or identity of the database to export from"),
+        )
+        .arg(
+            Arg::new("table")
+                .long("table")
+                .short('t')
+                .required(true)
+                .help("The name of the table to export"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_parser(["parquet"])
+                .default_value("parquet")
+                .help("The file format to export to"),
+        )
+        .arg(
+            Arg::new("out_file")
+                .long("out-file")
+                .short('o')
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("The path of the file to write. Defaults to `<table>.<format>`"),
+        )
+        .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))
+        .after_help("Run `spacetime help export` for more detailed information.\n")
+}
+
+pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let server = args.get_one::<String>("server").map(|s| s.as_ref());
+    let database = args.get_one::<String>("database").unwrap();
+    let table = args.get_one::<String>("table").unwrap();
+    let format = args.get_one::<String>("format").unwrap();
+    let out_file = args
+        .get_one::<PathBuf>("out_file")
+        .cloned()
+        .unwrap_or_else(|| format!("{table}.{format}").into());
+
+    let identity = database_identity(&config, database, server).await?;
+    let host_url = config.get_host_url(server)?;
+
+    let builder = reqwest::Client::new()
+        .get(format!("{host_url}/database/export/{identity}"))
+        .query(&[("table", table), ("format", format)]);
+    let auth_header = get_auth_header(&config, false)?;
+    let builder = add_auth_header_opt(builder, &auth_header);
+    let mut res = builder.send().await?.error_for_status()?;
+
+    let mut file =
+        std::fs::File::create(&out_file).with_context(|| format!("could not create file {}", out_file.display()))?;
+    let mut len = 0;
+    let written = async {
+        while let Some(chunk) = res.chunk().await? {
+            file.write_all(&chunk)?;
+            len += chunk.len();
+        }
+        file.flush()?;
+        anyhow::Ok(())
+    }
+    .await;
+    if let Err(e) = written {
+        // Don't leave a truncated file behind.
+        drop(file);
+        let _ = std::fs::remove_file(&out_file);
+        return Err(e.context("export failed"));
+    }
+
+    println!(
+        "Exported table {table} of {database} to {} ({len} bytes)",
+        out_file.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use anyhow::Context;
+use clap::{Arg, ArgMatches};
+use spacetimedb::db::export::export_sqlite;
+use spacetimedb::Identity;
+use spacetimedb_paths::server::ServerDataDir;
+use spacetimedb_paths::SpacetimePaths;
+use std::path::PathBuf;
+
+pub fn cli() -> clap::Command {
+    clap::Command::new("export-sqlite")
+        .about("Replays the history of a local database into a SQLite file for offline analysis")
+        .arg(
+            Arg::new("identity")
+                .required(true)
+                .value_parser(clap::value_parser!(Identity))
+                .help("The identity of the database to export"),
+        )
+        .arg(
+            Arg::new("replica_id")
+                .long("replica-id")
+                .required(true)
+                .value_parser(clap::value_parser!(u64))
+                .help("The id of the replica of the database whose data to read"),
+        )
+        .arg(
+            Arg::new("data_dir")
+                .long("data-dir")
+                .value_parser(clap::value_parser!(ServerDataDir))
+                .help("The path to the server data directory holding the replica [default: that of the selected spacetime instance]"),
+        )
+        .arg(
+            Arg::new("to_offset")
+                .long("to-offset")
+                .value_parser(clap::value_parser!(u64))
+                .help("The offset of the last transaction to include [default: the latest transaction]"),
+        )
+        .arg(
+            Arg::new("out_file")
+                .long("out-file")
+                .short('o')
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("The path of the SQLite file to write. Defaults to `<identity>.sqlite`"),
+        )
+        .after_help("Run `spacetime help export-sqlite` for more detailed information.\n")
+}
+
+pub async fn exec(paths: &SpacetimePaths, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let identity = *args.get_one::<Identity>("identity").unwrap();
+    let replica_id = *args.get_one::<u64>("replica_id").unwrap();
+    let data_dir = args.get_one::<ServerDataDir>("data_dir").unwrap_or(&paths.data_dir);
+    let to_offset = args.get_one::<u64>("to_offset").copied();
+    let out_file = args
+        .get_one::<PathBuf>("out_file")
+        .cloned()
+        .unwrap_or_else(|| format!("{identity}.sqlite").into());
+
+    let replica_dir = data_dir.replica(replica_id);
+    anyhow::ensure!(
+        replica_dir.0.exists(),
+        "no replica {replica_id} in {}",
+        data_dir.display()
+    );
+
+    let export = tokio::task::spawn_blocking({
+        let out_file = out_file.clone();
+        move || export_sqlite(&replica_dir, identity, replica_id, to_offset, &out_file)
+    })
+    .await?
+    .context("export failed")?;
+
+    match export.tx_offset {
+        Some(tx_offset) => println!(
+            "Exported {} tables ({} rows) of {identity}, as of transaction {tx_offset}, to {}",
+            export.tables,
+            export.rows,
+            out_file.display()
+        ),
+        None => println!(
+            "Database {identity} has no transactions; wrote an empty {}",
+            out_file.display()
+        ),
+    }
+
+    Ok(())
+}
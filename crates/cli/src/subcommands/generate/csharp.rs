@@ -384,6 +384,95 @@ fn indented_block<R>(output: &mut CodeIndenter<String>, f: impl FnOnce(&mut Code
     res
 }
 
+/// Keywords of C#, excluding contextual keywords, which may be used as identifiers.
+const CSHARP_KEYWORDS: &[&str] = &[
+    "abstract",
+    "as",
+    "base",
+    "bool",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "checked",
+    "class",
+    "const",
+    "continue",
+    "decimal",
+    "default",
+    "delegate",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "event",
+    "explicit",
+    "extern",
+    "false",
+    "finally",
+    "fixed",
+    "float",
+    "for",
+    "foreach",
+    "goto",
+    "if",
+    "implicit",
+    "in",
+    "int",
+    "interface",
+    "internal",
+    "is",
+    "lock",
+    "long",
+    "namespace",
+    "new",
+    "null",
+    "object",
+    "operator",
+    "out",
+    "override",
+    "params",
+    "private",
+    "protected",
+    "public",
+    "readonly",
+    "ref",
+    "return",
+    "sbyte",
+    "sealed",
+    "short",
+    "sizeof",
+    "stackalloc",
+    "static",
+    "string",
+    "struct",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "uint",
+    "ulong",
+    "unchecked",
+    "unsafe",
+    "ushort",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// Returns `name`, as a verbatim identifier, e.g. `@class`, if it is a C# keyword.
+fn escape_keyword(name: String) -> String {
+    if CSHARP_KEYWORDS.contains(&&*name) {
+        return format!("@{name}");
+    }
+    name
+}
+
 fn csharp_field_type(field_type: &AlgebraicType) -> Option<&str> {
     match field_type {
         AlgebraicType::Product(product) => {
@@ -669,7 +758,7 @@ pub fn autogen_csharp_globals(ctx: &GenCtx, items: &[GenItem], namespace: &str)
                     .as_deref()
                     .unwrap_or_else(|| panic!("reducer args should have names: {func_name}"));
                 let arg_type_str = ty_fmt(ctx, &arg.algebraic_type, namespace);
-                let arg_name = name.to_case(Case::Camel);
+                let arg_name = escape_keyword(name.to_case(Case::Camel));
 
                 write!(func_params, "{arg_type_str} {arg_name}").unwrap();
                 write!(func_args, "{arg_name}").unwrap();
@@ -19,6 +19,7 @@ use spacetimedb_schema;
 use spacetimedb_schema::def::{ModuleDef, ReducerDef, ScopedTypeName, TableDef, TypeDef};
 use spacetimedb_schema::identifier::Identifier;
 use spacetimedb_schema::schema::{Schema, TableSchema};
+use spacetimedb_schema::type_for_generate::AlgebraicTypeDef;
 use std::fs;
 use std::path::{Path, PathBuf};
 use wasmtime::{Caller, StoreContextMut};
@@ -88,6 +89,20 @@ pub fn cli() -> clap::Command {
                 .value_parser(clap::value_parser!(Language))
                 .help("The language to generate"),
         )
+        .arg(
+            Arg::new("tables")
+                .long("tables")
+                .value_delimiter(',')
+                .num_args(1..)
+                .help("Only generate bindings for these tables, and the types they use. Only supported with --lang rust"),
+        )
+        .arg(
+            Arg::new("reducers")
+                .long("reducers")
+                .value_delimiter(',')
+                .num_args(1..)
+                .help("Only generate bindings for these reducers, and the types they use. Only supported with --lang rust"),
+        )
         .arg(
             Arg::new("build_options")
                 .long("build-options")
@@ -109,10 +124,16 @@ pub async fn exec(config: Config, args: &clap::ArgMatches) -> anyhow::Result<()>
     let namespace = args.get_one::<String>("namespace").unwrap();
     let force = args.get_flag("force");
     let build_options = args.get_one::<String>("build_options").unwrap();
+    let subset = ModuleSubset::from_args(args);
 
     if args.value_source("namespace") == Some(ValueSource::CommandLine) && lang != Language::Csharp {
         return Err(anyhow::anyhow!("--namespace is only supported with --lang csharp"));
     }
+    if !subset.is_everything() && lang != Language::Rust {
+        return Err(anyhow::anyhow!(
+            "--tables and --reducers are only supported with --lang rust"
+        ));
+    }
 
     let module = if let Some(mut json_module) = json_module {
         let DeserializeWrapper(module) = if let Some(path) = json_module.next() {
@@ -139,7 +160,7 @@ pub async fn exec(config: Config, args: &clap::ArgMatches) -> anyhow::Result<()>
     fs::create_dir_all(out_dir)?;
 
     let mut paths = vec![];
-    for (fname, code) in generate(module, lang, namespace.as_str())? {
+    for (fname, code) in generate_subset(module, lang, namespace.as_str(), &subset)? {
         let fname = Path::new(&fname);
         // If a generator asks for a file in a subdirectory, create the subdirectory first.
         if let Some(parent) = fname.parent().filter(|p| !p.as_os_str().is_empty()) {
@@ -218,11 +239,126 @@ pub struct GenCtx {
     names: Vec<Option<String>>,
 }
 
+/// The tables and reducers of a module to generate client code for.
+///
+/// Types are generated only if used by a generated table or reducer.
+#[derive(Default)]
+pub struct ModuleSubset {
+    /// The names of the tables to generate, or `None` for all of them.
+    pub tables: Option<HashSet<String>>,
+    /// The names of the reducers to generate, or `None` for all of them.
+    pub reducers: Option<HashSet<String>>,
+}
+
+impl ModuleSubset {
+    /// Read the `--tables` and `--reducers` allowlists.
+    /// Passing either of them excludes everything not listed.
+    fn from_args(args: &clap::ArgMatches) -> Self {
+        let tables = args.get_many::<String>("tables");
+        let reducers = args.get_many::<String>("reducers");
+        if tables.is_none() && reducers.is_none() {
+            return Self::default();
+        }
+        Self {
+            tables: Some(tables.into_iter().flatten().cloned().collect()),
+            reducers: Some(reducers.into_iter().flatten().cloned().collect()),
+        }
+    }
+
+    fn is_everything(&self) -> bool {
+        self.tables.is_none() && self.reducers.is_none()
+    }
+
+    /// Returns an error for each name in the allowlists which `module` doesn't define.
+    fn ensure_defined_in(&self, module: &ModuleDef) -> anyhow::Result<()> {
+        for name in self.tables.iter().flatten() {
+            anyhow::ensure!(module.table(&**name).is_some(), "No such table: `{name}`");
+        }
+        for name in self.reducers.iter().flatten() {
+            anyhow::ensure!(module.reducer(&**name).is_some(), "No such reducer: `{name}`");
+        }
+        Ok(())
+    }
+
+    fn tables<'a>(&'a self, module: &'a ModuleDef) -> impl Iterator<Item = &'a TableDef> {
+        module.tables().filter(move |table| {
+            self.tables
+                .as_ref()
+                .map_or(true, |tables| tables.contains(&*table.name))
+        })
+    }
+
+    fn reducers<'a>(&'a self, module: &'a ModuleDef) -> impl Iterator<Item = &'a ReducerDef> {
+        module.reducers().filter(move |reducer| {
+            self.reducers
+                .as_ref()
+                .map_or(true, |reducers| reducers.contains(&*reducer.name))
+        })
+    }
+
+    /// The types used, directly or not, by the tables and reducers in the subset.
+    fn types<'a>(&'a self, module: &'a ModuleDef) -> impl Iterator<Item = &'a TypeDef> {
+        let used = (!self.is_everything()).then(|| self.used_types(module));
+        module
+            .types()
+            .filter(move |typ| used.as_ref().map_or(true, |used| used.contains(&typ.ty)))
+    }
+
+    fn used_types(&self, module: &ModuleDef) -> HashSet<AlgebraicTypeRef> {
+        let mut to_visit = self.tables(module).map(|table| table.product_type_ref).collect_vec();
+        for reducer in self.reducers(module) {
+            let uses = reducer.params_for_generate.elements.iter().map(|(_, ty)| ty);
+            for ty in uses.chain(&reducer.error_type_for_generate) {
+                ty.for_each_ref(|r| to_visit.push(r));
+            }
+        }
+
+        let typespace = module.typespace_for_generate();
+        let mut used = HashSet::default();
+        while let Some(r) = to_visit.pop() {
+            if !used.insert(r) {
+                continue;
+            }
+            let elements = match typespace.get(r) {
+                Some(AlgebraicTypeDef::Product(def)) => &def.elements[..],
+                Some(AlgebraicTypeDef::Sum(def)) => &def.variants[..],
+                Some(AlgebraicTypeDef::PlainEnum(_)) | None => &[],
+            };
+            for (_, ty) in elements {
+                ty.for_each_ref(|r| to_visit.push(r));
+            }
+        }
+        used
+    }
+}
+
 pub fn generate(module: RawModuleDef, lang: Language, namespace: &str) -> anyhow::Result<Vec<(String, String)>> {
+    generate_subset(module, lang, namespace, &ModuleSubset::default())
+}
+
+/// Like [`generate`], but only for the tables, reducers and types in `subset`,
+/// which is only supported for [`Language::Rust`].
+pub fn generate_subset(
+    module: RawModuleDef,
+    lang: Language,
+    namespace: &str,
+    subset: &ModuleSubset,
+) -> anyhow::Result<Vec<(String, String)>> {
     let module = ModuleDef::try_from(module)?;
+    subset.ensure_defined_in(&module)?;
+    anyhow::ensure!(
+        subset.is_everything() || lang == Language::Rust,
+        "Only Rust client codegen supports generating a subset of a module"
+    );
+    let everything = &ModuleSubset::default();
     Ok(match lang {
-        Language::Rust => generate_lang(&module, rust::Rust, namespace),
-        Language::TypeScript => generate_lang(&module, typescript::TypeScript, namespace),
+        Language::Rust => generate_lang(&module, rust::Rust, namespace, subset),
+        Language::TypeScript => {
+            // The TypeScript SDK builds the `AlgebraicType` of each type eagerly,
+            // which recurses forever for recursive types.
+            ensure_no_recursive_types(&module, "TypeScript")?;
+            generate_lang(&module, typescript::TypeScript, namespace, everything)
+        }
         Language::Csharp => {
             let ctx = GenCtx {
                 typespace: module.typespace().clone(),
@@ -283,27 +419,45 @@ pub fn generate(module: RawModuleDef, lang: Language, namespace: &str) -> anyhow
     })
 }
 
-fn generate_lang(module: &ModuleDef, lang: impl Lang, namespace: &str) -> Vec<(String, String)> {
+/// Returns an error naming the types of a cycle in `module`, if any,
+/// for languages `lang` which cannot represent recursive types.
+fn ensure_no_recursive_types(module: &ModuleDef, lang: &str) -> anyhow::Result<()> {
+    let Some(cycle) = module.typespace_for_generate().cycles().first() else {
+        return Ok(());
+    };
+    let names = cycle
+        .iter()
+        .map(|&ref_| match module.type_def_from_ref(ref_) {
+            Some((name, _)) => format!("`{name}`"),
+            None => format!("`{ref_}`"),
+        })
+        .join(", ");
+    anyhow::bail!(
+        "{lang} client codegen does not support recursive types, but these types refer to themselves: {names}"
+    )
+}
+
+fn generate_lang(module: &ModuleDef, lang: impl Lang, namespace: &str, subset: &ModuleSubset) -> Vec<(String, String)> {
     itertools::chain!(
-        module.tables().map(|tbl| {
+        subset.tables(module).map(|tbl| {
             (
                 lang.table_filename(module, tbl),
                 lang.generate_table(module, namespace, tbl),
             )
         }),
-        module.types().map(|typ| {
+        subset.types(module).map(|typ| {
             (
                 lang.type_filename(&typ.name),
                 lang.generate_type(module, namespace, typ),
             )
         }),
-        module.reducers().map(|reducer| {
+        subset.reducers(module).map(|reducer| {
             (
                 lang.reducer_filename(&reducer.name),
                 lang.generate_reducer(module, namespace, reducer),
             )
         }),
-        lang.generate_globals(module, namespace),
+        lang.generate_globals(module, namespace, subset),
     )
     .collect()
 }
@@ -316,7 +470,7 @@ trait Lang {
     fn generate_table(&self, module: &ModuleDef, namespace: &str, tbl: &TableDef) -> String;
     fn generate_type(&self, module: &ModuleDef, namespace: &str, typ: &TypeDef) -> String;
     fn generate_reducer(&self, module: &ModuleDef, namespace: &str, reducer: &ReducerDef) -> String;
-    fn generate_globals(&self, module: &ModuleDef, namespace: &str) -> Vec<(String, String)>;
+    fn generate_globals(&self, module: &ModuleDef, namespace: &str, subset: &ModuleSubset) -> Vec<(String, String)>;
 }
 
 /// Backwards-compatibible imitation of `TableDesc` that should be removed once the generators are updated to rely on `ModuleDef`.
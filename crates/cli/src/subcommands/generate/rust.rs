@@ -1,6 +1,6 @@
 use super::code_indenter::{CodeIndenter, Indenter};
 use super::util::{collect_case, is_type_filterable, print_lines, type_ref_name};
-use super::Lang;
+use super::{Lang, ModuleSubset};
 use crate::generate::util::{namespace_is_empty_or_default, print_auto_generated_file_comment};
 use convert_case::{Case, Casing};
 use itertools::Itertools;
@@ -59,12 +59,12 @@ Requested namespace: {namespace}",
             AlgebraicTypeDef::Product(product) => {
                 gen_and_print_imports(module, out, &product.elements, &[typ.ty]);
                 out.newline();
-                define_struct_for_product(module, out, &type_name, &product.elements, "pub");
+                define_struct_for_product(module, out, &type_name, &product.elements, Some(typ.ty), "pub");
             }
             AlgebraicTypeDef::Sum(sum) => {
                 gen_and_print_imports(module, out, &sum.variants, &[typ.ty]);
                 out.newline();
-                define_enum_for_sum(module, out, &type_name, &sum.variants);
+                define_enum_for_sum(module, out, &type_name, &sum.variants, Some(typ.ty));
             }
             AlgebraicTypeDef::PlainEnum(plain_enum) => {
                 let variants = plain_enum
@@ -73,7 +73,7 @@ Requested namespace: {namespace}",
                     .cloned()
                     .map(|var| (var, AlgebraicTypeUse::Unit))
                     .collect::<Vec<_>>();
-                define_enum_for_sum(module, out, &type_name, &variants);
+                define_enum_for_sum(module, out, &type_name, &variants, None);
             }
         }
         out.newline();
@@ -216,10 +216,11 @@ pub(super) fn register_table(client_cache: &mut __sdk::ClientCache<super::Remote
                 writeln!(out, "let _table = client_cache.get_or_make_table::<{row_type}>({table_name:?});");
                 for (unique_field_ident, unique_field_type_use) in iter_unique_cols(&schema, product_def) {
                     let unique_field_name = unique_field_ident.deref().to_case(Case::Snake);
+                    let unique_field = escape_keyword(unique_field_name.clone());
                     let unique_field_type = type_name(module, unique_field_type_use);
                     writeln!(
                         out,
-                        "_table.add_unique_constraint::<{unique_field_type}>({unique_field_name:?}, |row| &row.{unique_field_name});",
+                        "_table.add_unique_constraint::<{unique_field_type}>({unique_field_name:?}, |row| &row.{unique_field});",
                     );
                 }
             },
@@ -230,7 +231,7 @@ pub(super) fn register_table(client_cache: &mut __sdk::ClientCache<super::Remote
             let update_callback_id = table_name_pascalcase.clone() + "UpdateCallbackId";
 
             let (pk_field_ident, pk_field_type_use) = &product_def.elements[pk_field.col_pos.idx()];
-            let pk_field_name = pk_field_ident.deref().to_case(Case::Snake);
+            let pk_field_name = escape_keyword(pk_field_ident.deref().to_case(Case::Snake));
             let pk_field_type = type_name(module, pk_field_type_use);
 
             write!(
@@ -282,6 +283,7 @@ pub(super) fn parse_table_update(
         for (unique_field_ident, unique_field_type_use) in iter_unique_cols(&schema, product_def) {
             let unique_field_name = unique_field_ident.deref().to_case(Case::Snake);
             let unique_field_name_pascalcase = unique_field_name.to_case(Case::Pascal);
+            let unique_field_method = escape_keyword(unique_field_name.clone());
 
             let unique_constraint = table_name_pascalcase.clone() + &unique_field_name_pascalcase + "Unique";
             let unique_field_type = type_name(module, unique_field_type_use);
@@ -295,7 +297,7 @@ pub(super) fn parse_table_update(
         ///
         /// Users are encouraged not to explicitly reference this type,
         /// but to directly chain method calls,
-        /// like `ctx.db.{accessor_method}().{unique_field_name}().find(...)`.
+        /// like `ctx.db.{accessor_method}().{unique_field_method}().find(...)`.
         pub struct {unique_constraint}<'ctx> {{
             imp: __sdk::UniqueConstraintHandle<{row_type}, {unique_field_type}>,
             phantom: std::marker::PhantomData<&'ctx super::RemoteTables>,
@@ -303,7 +305,7 @@ pub(super) fn parse_table_update(
 
         impl<'ctx> {table_handle}<'ctx> {{
             /// Get a handle on the `{unique_field_name}` unique index on the table `{table_name}`.
-            pub fn {unique_field_name}(&self) -> {unique_constraint}<'ctx> {{
+            pub fn {unique_field_method}(&self) -> {unique_constraint}<'ctx> {{
                 {unique_constraint} {{
                     imp: self.imp.get_unique_constraint::<{unique_field_type}>({unique_field_name:?}),
                     phantom: std::marker::PhantomData,
@@ -341,10 +343,15 @@ Requested namespace: {namespace}",
 
         out.newline();
 
+        // The reducer's error type, if it declares one, may reference types which need importing.
+        let mut import_roots = reducer.params_for_generate.elements.to_vec();
+        if let Some(error_ty) = &reducer.error_type_for_generate {
+            import_roots.push((reducer.name.clone(), error_ty.clone()));
+        }
         gen_and_print_imports(
             module,
             out,
-            &reducer.params_for_generate.elements,
+            &import_roots,
             // No need to skip any imports; we're not emitting a type that other modules can import.
             &[],
         );
@@ -372,6 +379,7 @@ Requested namespace: {namespace}",
             out,
             &args_type,
             &reducer.params_for_generate.elements,
+            None,
             "pub(super)",
         );
 
@@ -382,7 +390,7 @@ Requested namespace: {namespace}",
         // The reducer arguments as `ident: ty, ident: ty, ident: ty,`,
         // like an argument list.
         let mut arglist = String::new();
-        write_arglist_no_delimiters(module, &mut arglist, &reducer.params_for_generate.elements, None).unwrap();
+        write_arglist_no_delimiters(module, &mut arglist, &reducer.params_for_generate.elements, None, None).unwrap();
 
         // The reducer argument types as `&ty, &ty, &ty`,
         // for use as the params in a `FnMut` closure type.
@@ -395,7 +403,7 @@ Requested namespace: {namespace}",
             write_type(module, &mut arg_types_ref_list, arg_ty).unwrap();
             arg_types_ref_list += ", ";
 
-            let arg_name = arg_ident.deref().to_case(Case::Snake);
+            let arg_name = escape_keyword(arg_ident.deref().to_case(Case::Snake));
             arg_names_list += &arg_name;
             arg_names_list += ", ";
         }
@@ -417,7 +425,7 @@ Requested namespace: {namespace}",
                                 " {",
                                 |out| {
                                     for (arg_ident, _ty) in &reducer.params_for_generate.elements[..] {
-                                        let arg_name = arg_ident.deref().to_case(Case::Snake);
+                                        let arg_name = escape_keyword(arg_ident.deref().to_case(Case::Snake));
                                         writeln!(out, "{arg_name}: args.{arg_name},");
                                     }
                                 },
@@ -521,10 +529,25 @@ impl {set_reducer_flags_trait} for super::SetReducerFlags {{
 "
         );
 
+        if let Some(error_ty) = &reducer.error_type_for_generate {
+            let mut error_ty_name = String::new();
+            write_type(module, &mut error_ty_name, error_ty).unwrap();
+            writeln!(
+                out,
+                "
+/// Decode the error value which the reducer `{reducer_name}` failed with.
+///
+/// Returns `None` if `status` is not [`__sdk::Status::FailedWithValue`].
+pub fn {func_name}_error(status: &__sdk::Status) -> Option<__anyhow::Result<{error_ty_name}>> {{
+    status.error_value::<{error_ty_name}>()
+}}"
+            );
+        }
+
         output.into_inner()
     }
 
-    fn generate_globals(&self, module: &ModuleDef, namespace: &str) -> Vec<(String, String)> {
+    fn generate_globals(&self, module: &ModuleDef, namespace: &str, subset: &ModuleSubset) -> Vec<(String, String)> {
         assert!(
             namespace_is_empty_or_default(namespace),
             "Rust codegen does not support namespaces, as Rust equates namespaces with `mod`s.
@@ -540,22 +563,22 @@ Requested namespace: {namespace}",
         out.newline();
 
         // Declare `pub mod` for each of the files generated.
-        print_module_decls(module, out);
+        print_module_decls(module, subset, out);
 
         out.newline();
 
         // Re-export all the modules for the generated files.
-        print_module_reexports(module, out);
+        print_module_reexports(module, subset, out);
 
         out.newline();
 
         // Define `enum Reducer`.
-        print_reducer_enum_defn(module, out);
+        print_reducer_enum_defn(module, subset, out);
 
         out.newline();
 
         // Define `DbUpdate`.
-        print_db_update_defn(module, out);
+        print_db_update_defn(module, subset, out);
 
         out.newline();
 
@@ -567,7 +590,7 @@ Requested namespace: {namespace}",
 
         // Implement `SpacetimeModule` for `RemoteModule`.
         // This includes a method for initializing the tables in the client cache.
-        print_impl_spacetime_module(module, out);
+        print_impl_spacetime_module(module, subset, out);
 
         vec![("mod.rs".to_string(), (output.into_inner()))]
     }
@@ -662,11 +685,14 @@ fn print_enum_derives(output: &mut Indenter) {
 }
 
 /// Generate a file which defines an `enum` corresponding to the `sum_type`.
+///
+/// `def` is the definition of the `sum_type`, if it has one in the typespace.
 pub fn define_enum_for_sum(
     module: &ModuleDef,
     out: &mut Indenter,
     name: &str,
     variants: &[(Identifier, AlgebraicTypeUse)],
+    def: Option<AlgebraicTypeRef>,
 ) {
     print_enum_derives(out);
     write!(out, "pub enum {name} ");
@@ -675,7 +701,7 @@ pub fn define_enum_for_sum(
         "{",
         |out| {
             for (ident, ty) in variants {
-                write_enum_variant(module, out, ident, ty);
+                write_enum_variant(module, out, ident, ty, def);
                 out.newline();
             }
         },
@@ -685,7 +711,13 @@ pub fn define_enum_for_sum(
     out.newline()
 }
 
-fn write_enum_variant(module: &ModuleDef, out: &mut Indenter, ident: &Identifier, ty: &AlgebraicTypeUse) {
+fn write_enum_variant(
+    module: &ModuleDef,
+    out: &mut Indenter,
+    ident: &Identifier,
+    ty: &AlgebraicTypeUse,
+    def: Option<AlgebraicTypeRef>,
+) {
     let name = ident.deref().to_case(Case::Pascal);
     write!(out, "{name}");
 
@@ -698,7 +730,7 @@ fn write_enum_variant(module: &ModuleDef, out: &mut Indenter, ident: &Identifier
         // If the contained type is not a product, i.e. this variant has a single
         // member, write it tuple-style, with parens.
         write!(out, "(");
-        write_type(module, out, ty).unwrap();
+        write_element_type(module, out, def, ty).unwrap();
         write!(out, ")");
     }
     writeln!(out, ",");
@@ -709,13 +741,16 @@ fn write_struct_type_fields_in_braces(
     out: &mut Indenter,
     elements: &[(Identifier, AlgebraicTypeUse)],
 
+    // The definition which has these fields, if any. Used to box recursive fields.
+    def: Option<AlgebraicTypeRef>,
+
     // Whether to print a `pub` qualifier on the fields. Necessary for `struct` defns,
     // disallowed for `enum` defns.
     pub_qualifier: bool,
 ) {
     out.delimited_block(
         "{",
-        |out| write_arglist_no_delimiters(module, out, elements, pub_qualifier.then_some("pub")).unwrap(),
+        |out| write_arglist_no_delimiters(module, out, elements, def, pub_qualifier.then_some("pub")).unwrap(),
         "}",
     );
 }
@@ -725,6 +760,9 @@ fn write_arglist_no_delimiters(
     out: &mut impl Write,
     elements: &[(Identifier, AlgebraicTypeUse)],
 
+    // The definition which has these elements, if any. Used to box recursive elements.
+    def: Option<AlgebraicTypeRef>,
+
     // Written before each line. Useful for `pub`.
     prefix: Option<&str>,
 ) -> anyhow::Result<()> {
@@ -733,16 +771,33 @@ fn write_arglist_no_delimiters(
             write!(out, "{prefix} ")?;
         }
 
-        let name = ident.deref().to_case(Case::Snake);
+        let name = escape_keyword(ident.deref().to_case(Case::Snake));
 
         write!(out, "{name}: ")?;
-        write_type(module, out, ty)?;
+        write_element_type(module, out, def, ty)?;
         writeln!(out, ",")?;
     }
 
     Ok(())
 }
 
+/// Write the type of an element or variant of the definition `def`,
+/// in a `Box` if it would otherwise contain `def` itself, making the type infinitely large.
+fn write_element_type(
+    module: &ModuleDef,
+    out: &mut impl Write,
+    def: Option<AlgebraicTypeRef>,
+    ty: &AlgebraicTypeUse,
+) -> fmt::Result {
+    if def.is_some_and(|def| module.typespace_for_generate().needs_indirection(def, ty)) {
+        write!(out, "Box<")?;
+        write_type(module, out, ty)?;
+        write!(out, ">")
+    } else {
+        write_type(module, out, ty)
+    }
+}
+
 // TODO: figure out if/when product types should derive:
 // - Clone
 // - Debug
@@ -766,6 +821,7 @@ fn define_struct_for_product(
     out: &mut Indenter,
     name: &str,
     elements: &[(Identifier, AlgebraicTypeUse)],
+    def: Option<AlgebraicTypeRef>,
     vis: &str,
 ) {
     print_struct_derives(out);
@@ -774,12 +830,33 @@ fn define_struct_for_product(
 
     // TODO: if elements is empty, define a unit struct with no brace-delimited list of fields.
     write_struct_type_fields_in_braces(
-        module, out, elements, true, // `pub`-qualify fields.
+        module, out, elements, def, true, // `pub`-qualify fields.
     );
 
     out.newline();
 }
 
+/// Keywords of the Rust language, including those reserved for future use,
+/// as of the 2021 edition.
+const RUST_KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate", "do", "dyn", "else",
+    "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod",
+    "move", "mut", "override", "priv", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+    "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Returns `name`, escaped if it is a Rust keyword,
+/// so that e.g. a column named `type` becomes the field `r#type`.
+///
+/// `self`, `Self`, `super` and `crate` cannot be raw identifiers, so they get a trailing underscore instead.
+fn escape_keyword(name: String) -> String {
+    match &*name {
+        "self" | "Self" | "super" | "crate" => name + "_",
+        _ if RUST_KEYWORDS.contains(&&*name) => format!("r#{name}"),
+        _ => name,
+    }
+}
+
 fn type_ref_module_name(module: &ModuleDef, type_ref: AlgebraicTypeRef) -> String {
     let (name, _) = module.type_def_from_ref(type_ref).unwrap();
     type_module_name(name)
@@ -794,7 +871,7 @@ fn table_module_name(table_name: &Identifier) -> String {
 }
 
 fn table_method_name(table_name: &Identifier) -> String {
-    table_name.deref().to_case(Case::Snake)
+    escape_keyword(table_name.deref().to_case(Case::Snake))
 }
 
 fn table_access_trait_name(table_name: &Identifier) -> String {
@@ -825,30 +902,30 @@ fn reducer_flags_trait_name(reducer: &ReducerDef) -> String {
     format!("set_flags_for_{}", reducer_function_name(reducer))
 }
 
-/// Iterate over all of the Rust `mod`s for types, reducers and tables in the `module`.
-fn iter_module_names(module: &ModuleDef) -> impl Iterator<Item = String> + '_ {
+/// Iterate over all of the Rust `mod`s for types, reducers and tables in the `subset` of the `module`.
+fn iter_module_names<'a>(module: &'a ModuleDef, subset: &'a ModuleSubset) -> impl Iterator<Item = String> + 'a {
     itertools::chain!(
-        module.types().map(|ty| type_module_name(&ty.name)).sorted(),
-        module.reducers().map(|r| reducer_module_name(&r.name)).sorted(),
-        module.tables().map(|tbl| table_module_name(&tbl.name)).sorted(),
+        subset.types(module).map(|ty| type_module_name(&ty.name)).sorted(),
+        subset.reducers(module).map(|r| reducer_module_name(&r.name)).sorted(),
+        subset.tables(module).map(|tbl| table_module_name(&tbl.name)).sorted(),
     )
 }
 
 /// Print `pub mod` declarations for all the files that will be generated for `items`.
-fn print_module_decls(module: &ModuleDef, out: &mut Indenter) {
-    for module_name in iter_module_names(module) {
+fn print_module_decls(module: &ModuleDef, subset: &ModuleSubset, out: &mut Indenter) {
+    for module_name in iter_module_names(module, subset) {
         writeln!(out, "pub mod {module_name};");
     }
 }
 
 /// Print appropriate reexports for all the files that will be generated for `items`.
-fn print_module_reexports(module: &ModuleDef, out: &mut Indenter) {
-    for ty in module.types().sorted_by_key(|ty| &ty.name) {
+fn print_module_reexports(module: &ModuleDef, subset: &ModuleSubset, out: &mut Indenter) {
+    for ty in subset.types(module).sorted_by_key(|ty| &ty.name) {
         let mod_name = type_module_name(&ty.name);
         let type_name = collect_case(Case::Pascal, ty.name.name_segments());
         writeln!(out, "pub use {mod_name}::{type_name};")
     }
-    for table in iter_tables(module) {
+    for table in iter_tables(module, subset) {
         let mod_name = table_module_name(&table.name);
         // TODO: More precise reexport: we want:
         // - The trait name.
@@ -857,7 +934,7 @@ fn print_module_reexports(module: &ModuleDef, out: &mut Indenter) {
         // - The table handle.
         writeln!(out, "pub use {mod_name}::*;");
     }
-    for reducer in iter_reducers(module) {
+    for reducer in iter_reducers(module, subset) {
         let mod_name = reducer_module_name(&reducer.name);
         let reducer_trait_name = reducer_function_name(reducer);
         let flags_trait_name = reducer_flags_trait_name(reducer);
@@ -866,21 +943,24 @@ fn print_module_reexports(module: &ModuleDef, out: &mut Indenter) {
             out,
             "pub use {mod_name}::{{{reducer_trait_name}, {flags_trait_name}, {callback_id_name}}};"
         );
+        if reducer.error_type_for_generate.is_some() {
+            writeln!(out, "pub use {mod_name}::{reducer_trait_name}_error;");
+        }
     }
 }
 
-/// Iterate over all the [`ReducerDef`]s defined by the module, in alphabetical order by name.
+/// Iterate over the [`ReducerDef`]s in the `subset` of the module, in alphabetical order by name.
 ///
 /// Sorting is necessary to have deterministic reproducable codegen.
-fn iter_reducers(module: &ModuleDef) -> impl Iterator<Item = &ReducerDef> {
-    module.reducers().sorted_by_key(|reducer| &reducer.name)
+fn iter_reducers<'a>(module: &'a ModuleDef, subset: &'a ModuleSubset) -> impl Iterator<Item = &'a ReducerDef> {
+    subset.reducers(module).sorted_by_key(|reducer| &reducer.name)
 }
 
-/// Iterate over all the [`TableDef`]s defined by the module, in alphabetical order by name.
+/// Iterate over the [`TableDef`]s in the `subset` of the module, in alphabetical order by name.
 ///
 /// Sorting is necessary to have deterministic reproducable codegen.
-fn iter_tables(module: &ModuleDef) -> impl Iterator<Item = &TableDef> {
-    module.tables().sorted_by_key(|table| &table.name)
+fn iter_tables<'a>(module: &'a ModuleDef, subset: &'a ModuleSubset) -> impl Iterator<Item = &'a TableDef> {
+    subset.tables(module).sorted_by_key(|table| &table.name)
 }
 
 fn iter_unique_cols<'a>(
@@ -899,7 +979,7 @@ fn iter_unique_cols<'a>(
     })
 }
 
-fn print_reducer_enum_defn(module: &ModuleDef, out: &mut Indenter) {
+fn print_reducer_enum_defn(module: &ModuleDef, subset: &ModuleSubset, out: &mut Indenter) {
     // Don't derive ser/de on this enum;
     // it's not a proper SATS enum and the derive will fail.
     writeln!(out, "#[derive(Clone, PartialEq, Debug)]");
@@ -915,14 +995,14 @@ fn print_reducer_enum_defn(module: &ModuleDef, out: &mut Indenter) {
     out.delimited_block(
         "pub enum Reducer {",
         |out| {
-            for reducer in iter_reducers(module) {
+            for reducer in iter_reducers(module, subset) {
                 write!(out, "{} ", reducer_variant_name(&reducer.name));
                 if !reducer.params_for_generate.elements.is_empty() {
                     // If the reducer has any arguments, generate a "struct variant,"
                     // like `Foo { bar: Baz, }`.
                     // If it doesn't, generate a "unit variant" instead,
                     // like `Foo,`.
-                    write_struct_type_fields_in_braces(module, out, &reducer.params_for_generate.elements, false);
+                    write_struct_type_fields_in_braces(module, out, &reducer.params_for_generate.elements, None, false);
                 }
                 writeln!(out, ",");
             }
@@ -948,7 +1028,7 @@ impl __sdk::InModule for Reducer {{
                     out.delimited_block(
                         "match self {",
                         |out| {
-                            for reducer in iter_reducers(module) {
+                            for reducer in iter_reducers(module, subset) {
                                 write!(out, "Reducer::{}", reducer_variant_name(&reducer.name));
                                 if !reducer.params_for_generate.elements.is_empty() {
                                     // Because we're emitting unit variants when the payload is empty,
@@ -996,7 +1076,7 @@ impl __sdk::InModule for Reducer {{
                     out.delimited_block(
                         "match &value.reducer_name[..] {",
                         |out| {
-                            for reducer in iter_reducers(module) {
+                            for reducer in iter_reducers(module, subset) {
                                 writeln!(
                                     out,
                                     "{:?} => Ok(__sdk::parse_reducer_args::<{}::{}>({:?}, &value.args)?.into()),",
@@ -1021,14 +1101,14 @@ impl __sdk::InModule for Reducer {{
     )
 }
 
-fn print_db_update_defn(module: &ModuleDef, out: &mut Indenter) {
+fn print_db_update_defn(module: &ModuleDef, subset: &ModuleSubset, out: &mut Indenter) {
     writeln!(out, "#[derive(Default)]");
     writeln!(out, "#[allow(non_snake_case)]");
     writeln!(out, "#[doc(hidden)]");
     out.delimited_block(
         "pub struct DbUpdate {",
         |out| {
-            for table in iter_tables(module) {
+            for table in iter_tables(module, subset) {
                 writeln!(
                     out,
                     "{}: __sdk::TableUpdate<{}>,",
@@ -1052,7 +1132,7 @@ impl TryFrom<__ws::DatabaseUpdate<__ws::BsatnFormat>> for DbUpdate {
             match &table_update.table_name[..] {
 ",
         |out| {
-            for table in iter_tables(module) {
+            for table in iter_tables(module, subset) {
                 writeln!(
                     out,
                     "{:?} => db_update.{} = {}::parse_table_update(table_update)?,",
@@ -1086,12 +1166,12 @@ impl __sdk::InModule for DbUpdate {{
         "impl __sdk::DbUpdate for DbUpdate {",
         |out| {
             out.delimited_block(
-                "fn apply_to_client_cache(&self, cache: &mut __sdk::ClientCache<RemoteModule>) {",
+                "fn apply_to_client_cache(&mut self, cache: &mut __sdk::ClientCache<RemoteModule>) {",
                 |out| {
-                    for table in iter_tables(module) {
+                    for table in iter_tables(module, subset) {
                         writeln!(
                             out,
-                            "cache.apply_diff_to_table::<{}>({:?}, &self.{});",
+                            "cache.apply_diff_to_table::<{}>({:?}, &mut self.{});",
                             type_ref_name(module, table.product_type_ref),
                             table.name.deref(),
                             table_method_name(&table.name),
@@ -1104,7 +1184,7 @@ impl __sdk::InModule for DbUpdate {{
             out.delimited_block(
                 "fn invoke_row_callbacks(&self, event: &EventContext, callbacks: &mut __sdk::DbCallbacks<RemoteModule>) {",
                 |out| {
-                    for table in iter_tables(module) {
+                    for table in iter_tables(module, subset) {
                         writeln!(
                             out,
                             "callbacks.invoke_table_row_callbacks::<{}>({:?}, &self.{}, event);",
@@ -1121,7 +1201,7 @@ impl __sdk::InModule for DbUpdate {{
     );
 }
 
-fn print_impl_spacetime_module(module: &ModuleDef, out: &mut Indenter) {
+fn print_impl_spacetime_module(module: &ModuleDef, subset: &ModuleSubset, out: &mut Indenter) {
     out.delimited_block(
         "impl __sdk::SpacetimeModule for RemoteModule {",
         |out| {
@@ -1141,7 +1221,7 @@ type SubscriptionHandle = SubscriptionHandle;
             out.delimited_block(
                 "fn register_tables(client_cache: &mut __sdk::ClientCache<Self>) {",
                 |out| {
-                    for table in iter_tables(module) {
+                    for table in iter_tables(module, subset) {
                         writeln!(out, "{}::register_table(client_cache);", table_module_name(&table.name));
                     }
                 },
@@ -1349,6 +1429,10 @@ impl __sdk::DbConnection for DbConnection {{
             imp,
         }}
     }}
+
+    fn imp(&self) -> &__sdk::DbContextImpl<RemoteModule> {{
+        &self.imp
+    }}
 }}
 
 /// A [`DbConnection`] augmented with an [`__sdk::Event`],
@@ -9,15 +9,17 @@ use std::ops::Deref;
 
 use convert_case::{Case, Casing};
 use itertools::Itertools;
+use spacetimedb_lib::db::raw_def::v9::ArgConstraint;
 use spacetimedb_lib::sats::AlgebraicTypeRef;
-use spacetimedb_primitives::ColList;
-use spacetimedb_schema::def::{ModuleDef, ReducerDef, ScopedTypeName, TableDef, TypeDef};
+use spacetimedb_primitives::{ColId, ColList};
+use spacetimedb_schema::arg_constraint;
+use spacetimedb_schema::def::{ModuleDef, ReducerArgConstraintDef, ReducerDef, ScopedTypeName, TableDef, TypeDef};
 use spacetimedb_schema::identifier::Identifier;
 use spacetimedb_schema::schema::{Schema, TableSchema};
 use spacetimedb_schema::type_for_generate::{AlgebraicTypeDef, AlgebraicTypeUse, PrimitiveType};
 
 use super::code_indenter::{CodeIndenter, Indenter};
-use super::Lang;
+use super::{Lang, ModuleSubset};
 
 type Imports = BTreeSet<AlgebraicTypeRef>;
 
@@ -61,7 +63,18 @@ Requested namespace: {namespace}",
         match &module.typespace_for_generate()[typ.ty] {
             AlgebraicTypeDef::Product(product) => {
                 gen_and_print_imports(module, out, &product.elements, &[typ.ty]);
-                define_namespace_and_object_type_for_product(module, out, &type_name, &product.elements);
+                let branded_key = branded_key(module, typ.ty);
+                if let Some(branded_key) = &branded_key {
+                    define_branded_key_type(out, branded_key);
+                }
+                define_namespace_and_object_type_for_product(
+                    module,
+                    out,
+                    &type_name,
+                    &product.elements,
+                    branded_key.as_ref(),
+                    &[],
+                );
             }
             AlgebraicTypeDef::Sum(sum) => {
                 gen_and_print_imports(module, out, &sum.variants, &[typ.ty]);
@@ -103,7 +116,15 @@ Requested namespace: {namespace}",
         let row_type = type_ref_name(module, type_ref);
         let row_type_module = type_ref_module_name(module, type_ref);
 
-        writeln!(out, "import {{ {row_type} }} from \"./{row_type_module}\";");
+        let branded_key = branded_key(module, type_ref);
+        match &branded_key {
+            Some(branded_key) => writeln!(
+                out,
+                "import {{ {row_type}, {} }} from \"./{row_type_module}\";",
+                branded_key.type_name
+            ),
+            None => writeln!(out, "import {{ {row_type} }} from \"./{row_type_module}\";"),
+        }
 
         let product_def = module.typespace_for_generate()[type_ref].as_product().unwrap();
 
@@ -173,7 +194,10 @@ export class {table_handle} {{
                 let unique_field_name_pascalcase = unique_field_name.to_case(Case::Pascal);
 
                 let unique_constraint = table_name_pascalcase.clone() + &unique_field_name_pascalcase + "Unique";
-                let unique_field_type = type_name(module, unique_field_type_use);
+                let unique_field_type = match &branded_key {
+                    Some(branded_key) if branded_key.col == field.col_pos => branded_key.type_name.clone(),
+                    _ => type_name(module, unique_field_type_use),
+                };
 
                 writeln!(
                     out,
@@ -285,12 +309,19 @@ Requested namespace: {namespace}",
 
         let args_type = reducer_args_type_name(&reducer.name);
 
-        define_namespace_and_object_type_for_product(module, out, &args_type, &reducer.params_for_generate.elements);
+        define_namespace_and_object_type_for_product(
+            module,
+            out,
+            &args_type,
+            &reducer.params_for_generate.elements,
+            None,
+            &reducer.arg_constraints,
+        );
 
         output.into_inner()
     }
 
-    fn generate_globals(&self, module: &ModuleDef, namespace: &str) -> Vec<(String, String)> {
+    fn generate_globals(&self, module: &ModuleDef, namespace: &str, _subset: &ModuleSubset) -> Vec<(String, String)> {
         assert!(
             namespace_is_empty_or_default(namespace),
             "TypeScript codegen does not support namespaces, as TypeScript equates namespaces with files.
@@ -332,6 +363,11 @@ Requested namespace: {namespace}",
             let type_module_name = type_module_name(&ty.name) + ".ts";
             writeln!(out, "import {{ {type_name} }} from \"./{type_module_name}\";");
             writeln!(out, "export {{ {type_name} }};");
+            if let Some(branded_key) = branded_key(module, ty.ty) {
+                let key_type = branded_key.type_name;
+                writeln!(out, "import {{ {key_type} }} from \"./{type_module_name}\";");
+                writeln!(out, "export {{ {key_type} }};");
+            }
         }
 
         out.newline();
@@ -445,8 +481,13 @@ fn print_remote_reducers(module: &ModuleDef, out: &mut Indenter) {
         let mut arg_list = "".to_string();
         let mut arg_name_list = "".to_string();
         for (arg_ident, arg_ty) in &reducer.params_for_generate.elements[..] {
-            let arg_name = arg_ident.deref().to_case(Case::Camel);
-            arg_name_list += &arg_name;
+            let field_name = arg_ident.deref().to_case(Case::Camel);
+            let arg_name = typescript_variable_name(field_name.clone());
+            if arg_name == field_name {
+                arg_name_list += &arg_name;
+            } else {
+                arg_name_list += &format!("{field_name}: {arg_name}");
+            }
             arg_list += &arg_name;
             arg_list += ": ";
             write_type(module, &mut arg_list, arg_ty, None).unwrap();
@@ -651,18 +692,70 @@ fn write_get_algebraic_type_for_product(
     writeln!(out, "}}");
 }
 
+/// A `u64` primary key column of a table,
+/// whose TypeScript type is branded so that the keys of different tables can't be mixed up.
+struct BrandedKey {
+    /// The primary key column of the table's row type.
+    col: ColId,
+    /// The name of the branded type, like `FooId` for the row type `Foo`.
+    type_name: String,
+}
+
+/// Returns the [`BrandedKey`] of the row type `type_ref`,
+/// if it is the row type of a table with a `u64` primary key.
+fn branded_key(module: &ModuleDef, type_ref: AlgebraicTypeRef) -> Option<BrandedKey> {
+    let product_def = module.typespace_for_generate()[type_ref].as_product()?;
+    module
+        .tables()
+        .filter(|table| table.product_type_ref == type_ref)
+        .find_map(|table| table.primary_key)
+        .filter(|pk| {
+            matches!(
+                product_def.elements[pk.idx()].1,
+                AlgebraicTypeUse::Primitive(PrimitiveType::U64)
+            )
+        })
+        .map(|col| BrandedKey {
+            col,
+            type_name: type_ref_name(module, type_ref) + "Id",
+        })
+}
+
+fn define_branded_key_type(out: &mut Indenter, branded_key: &BrandedKey) {
+    let key_type = &branded_key.type_name;
+    writeln!(
+        out,
+        "/**
+ * The primary key of this table, branded so that it can't be mixed up
+ * with a plain `bigint` or with the primary keys of other tables.
+ */"
+    );
+    writeln!(
+        out,
+        "export type {key_type} = bigint & {{ readonly __brand: \"{key_type}\" }};"
+    );
+    writeln!(out, "// Brands a `bigint` as a `{key_type}`.");
+    writeln!(
+        out,
+        "export const {key_type} = (value: bigint): {key_type} => value as {key_type};"
+    );
+    out.newline();
+}
+
 fn define_namespace_and_object_type_for_product(
     module: &ModuleDef,
     out: &mut Indenter,
     name: &str,
     elements: &[(Identifier, AlgebraicTypeUse)],
+    branded_key: Option<&BrandedKey>,
+    arg_constraints: &[ReducerArgConstraintDef],
 ) {
     write!(out, "export type {name} = {{");
     if elements.is_empty() {
         writeln!(out, "}};");
     } else {
         writeln!(out);
-        out.with_indent(|out| write_arglist_no_delimiters(module, out, elements, None, true).unwrap());
+        out.with_indent(|out| write_arglist_no_delimiters(module, out, elements, None, true, branded_key).unwrap());
         writeln!(out, "}};");
     }
 
@@ -696,20 +789,83 @@ fn define_namespace_and_object_type_for_product(
     writeln!(out, "}}");
     writeln!(out);
 
+    if !arg_constraints.is_empty() {
+        write_validate_for_reducer_args(out, name, elements, arg_constraints);
+        writeln!(out);
+    }
+
     out.dedent(1);
     writeln!(out, "}}");
 
     out.newline();
 }
 
+/// Write a `validate` function, which checks reducer arguments against the constraints the module declares,
+/// returning the same messages as the host would reject the call with.
+fn write_validate_for_reducer_args(
+    out: &mut Indenter,
+    name: &str,
+    elements: &[(Identifier, AlgebraicTypeUse)],
+    arg_constraints: &[ReducerArgConstraintDef],
+) {
+    writeln!(
+        out,
+        "// Returns a message for each argument constraint `value` violates, so an empty array means the module will accept it."
+    );
+    writeln!(out, "export function validate(value: {name}): string[] {{");
+    out.with_indent(|out| {
+        writeln!(out, "const errors: string[] = [];");
+        for def in arg_constraints {
+            let (ident, ty) = &elements[def.arg as usize];
+            let field = format!("value.{}", ident.deref().to_case(Case::Camel));
+            let is_bigint = matches!(
+                ty,
+                AlgebraicTypeUse::Primitive(
+                    PrimitiveType::I64
+                        | PrimitiveType::U64
+                        | PrimitiveType::I128
+                        | PrimitiveType::U128
+                        | PrimitiveType::I256
+                        | PrimitiveType::U256
+                )
+            );
+            let length = match ty {
+                AlgebraicTypeUse::String => format!("[...{field}].length"),
+                _ => format!("{field}.length"),
+            };
+            let integer = |n: i128| if is_bigint { format!("{n}n") } else { n.to_string() };
+            let violated = match &def.constraint {
+                ArgConstraint::MinLength(min) => format!("{length} < {min}"),
+                ArgConstraint::MaxLength(max) => format!("{length} > {max}"),
+                ArgConstraint::Min(min) => format!("{field} < {}", integer(*min)),
+                ArgConstraint::Max(max) => format!("{field} > {}", integer(*max)),
+                ArgConstraint::OneOf(allowed) => format!("!{}.includes({field})", json_string_array(allowed)),
+            };
+            let message = arg_constraint::violation(&def.constraint, ident).to_string();
+            writeln!(out, "if ({violated}) errors.push({});", json_string(&message));
+        }
+        writeln!(out, "return errors;");
+    });
+    writeln!(out, "}}");
+}
+
+fn json_string(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+fn json_string_array(strings: &[Box<str>]) -> String {
+    serde_json::to_string(strings).unwrap()
+}
+
 fn write_arglist_no_delimiters(
     module: &ModuleDef,
     out: &mut impl Write,
     elements: &[(Identifier, AlgebraicTypeUse)],
     prefix: Option<&str>,
     convert_case: bool,
+    branded_key: Option<&BrandedKey>,
 ) -> anyhow::Result<()> {
-    for (ident, ty) in elements {
+    for (i, (ident, ty)) in elements.iter().enumerate() {
         if let Some(prefix) = prefix {
             write!(out, "{prefix} ")?;
         }
@@ -721,7 +877,10 @@ fn write_arglist_no_delimiters(
         };
 
         write!(out, "{name}: ")?;
-        write_type(module, out, ty, Some("__"))?;
+        match branded_key {
+            Some(branded_key) if branded_key.col.idx() == i => write!(out, "{}", branded_key.type_name)?,
+            _ => write_type(module, out, ty, Some("__"))?,
+        }
         writeln!(out, ",")?;
     }
 
@@ -779,25 +938,78 @@ fn write_variant_constructors(
     name: &str,
     variants: &[(Identifier, AlgebraicTypeUse)],
 ) {
-    // Write all the variant constructors.
     // Write all of the variant constructors.
     for (ident, ty) in variants {
+        let variant_name = ident.deref().to_case(Case::Pascal);
         if matches!(ty, AlgebraicTypeUse::Unit) {
-            // If the variant has no members, we can export a simple object.
+            // If the variant has no members, we can export a simple object,
+            // typed at the variant so that its tag isn't widened to `string`.
             // ```
-            // export const Foo = { tag: "Foo" };
+            // export const Foo: Foo = { tag: "Foo" };
             // ```
-            write!(out, "export const {ident} = ");
-            writeln!(out, "{{ tag: \"{ident}\" }};");
+            writeln!(
+                out,
+                "export const {variant_name}: {variant_name} = {{ tag: \"{variant_name}\" }};"
+            );
             continue;
         }
-        let variant_name = ident.deref().to_case(Case::Pascal);
         write!(out, "export const {variant_name} = (value: ");
         write_type(module, out, ty, Some("__")).unwrap();
         writeln!(out, "): {name} => ({{ tag: \"{variant_name}\", value }});");
     }
 }
 
+/// Write a `match` function, which calls the case for the variant of a value of the sum type.
+///
+/// The cases are an object with a function per variant, so a `match` which misses a variant,
+/// e.g. because one was added to the module, is a type error rather than silently falling through.
+fn write_match_for_sum(
+    module: &ModuleDef,
+    out: &mut Indenter,
+    name: &str,
+    variants: &[(Identifier, AlgebraicTypeUse)],
+) {
+    writeln!(
+        out,
+        "// Calls the case of `cases` for the variant of `value`, returning its result.
+// ```
+// const n = Foo.match(foo, {{ A: (value) => value, B: () => 0 }});
+// ```"
+    );
+    writeln!(out, "export function match<R>(value: {name}, cases: {{");
+    out.with_indent(|out| {
+        for (ident, ty) in variants {
+            let variant_name = ident.deref().to_case(Case::Pascal);
+            if matches!(ty, AlgebraicTypeUse::Unit) {
+                writeln!(out, "{variant_name}: () => R,");
+            } else {
+                write!(out, "{variant_name}: (value: ");
+                write_type(module, out, ty, Some("__")).unwrap();
+                writeln!(out, ") => R,");
+            }
+        }
+    });
+    writeln!(out, "}}): R {{");
+    out.with_indent(|out| {
+        writeln!(out, "switch (value.tag) {{");
+        out.with_indent(|out| {
+            for (ident, ty) in variants {
+                let variant_name = ident.deref().to_case(Case::Pascal);
+                if matches!(ty, AlgebraicTypeUse::Unit) {
+                    writeln!(out, "case \"{variant_name}\": return cases.{variant_name}();");
+                } else {
+                    writeln!(
+                        out,
+                        "case \"{variant_name}\": return cases.{variant_name}(value.value);"
+                    );
+                }
+            }
+        });
+        writeln!(out, "}}");
+    });
+    writeln!(out, "}}");
+}
+
 fn write_get_algebraic_type_for_sum(
     module: &ModuleDef,
     out: &mut Indenter,
@@ -866,6 +1078,9 @@ fn define_namespace_and_types_for_sum(
     );
     writeln!(out);
 
+    write_match_for_sum(module, out, name, variants);
+    writeln!(out);
+
     out.dedent(1);
 
     writeln!(out, "}}");
@@ -1115,54 +1330,65 @@ fn gen_and_print_imports(
     }
 }
 
-// const RESERVED_KEYWORDS: [&str; 36] = [
-//     "break",
-//     "case",
-//     "catch",
-//     "class",
-//     "const",
-//     "continue",
-//     "debugger",
-//     "default",
-//     "delete",
-//     "do",
-//     "else",
-//     "enum",
-//     "export",
-//     "extends",
-//     "false",
-//     "finally",
-//     "for",
-//     "function",
-//     "if",
-//     "import",
-//     "in",
-//     "instanceof",
-//     "new",
-//     "null",
-//     "return",
-//     "super",
-//     "switch",
-//     "this",
-//     "throw",
-//     "true",
-//     "try",
-//     "typeof",
-//     "var",
-//     "void",
-//     "while",
-//     "with",
-// ];
-
-// fn typescript_field_name(field_name: String) -> String {
-//     if RESERVED_KEYWORDS
-//         .into_iter()
-//         .map(String::from)
-//         .collect::<Vec<String>>()
-//         .contains(&field_name)
-//     {
-//         return format!("_{field_name}");
-//     }
-
-//     field_name
-// }
+/// Reserved words of JavaScript, including those reserved only in strict mode,
+/// which cannot be used as the names of variables or parameters.
+const RESERVED_KEYWORDS: &[&str] = &[
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Returns `name`, prefixed with an underscore if it is a reserved word,
+/// for use as the name of a variable or parameter.
+///
+/// Property names may be reserved words, so this is not needed for fields.
+fn typescript_variable_name(name: String) -> String {
+    if RESERVED_KEYWORDS.contains(&&*name) {
+        return format!("_{name}");
+    }
+
+    name
+}
@@ -0,0 +1,124 @@
+use crate::common_args;
+use crate::config::Config;
+use crate::util::{add_auth_header_opt, database_identity, get_auth_header};
+use anyhow::Context;
+use clap::{Arg, ArgAction, ArgMatches};
+use itertools::Itertools;
+use spacetimedb_client_api_messages::name::ImportResult;
+use std::path::PathBuf;
+
+pub fn cli() -> clap::Command {
+    clap::Command::new("import")
+        .about("Imports the rows of a Parquet or CSV file into a table in a SpacetimeDB database")
+        .arg(
+            Arg::new("database")
+                .required(true)
+                .help("The name or identity of the database to import into"),
+        )
+        .arg(
+            Arg::new("file")
+                .required(true)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("The file to import"),
+        )
+        .arg(
+            Arg::new("table")
+                .long("table")
+                .short('t')
+                .required(true)
+                .help("The name of the table to import into"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_parser(["parquet", "csv"])
+                .help("The format of the file. Defaults to the file's extension"),
+        )
+        .arg(
+            Arg::new("map")
+                .long("map")
+                .short('m')
+                .action(ArgAction::Append)
+                .value_name("FILE_COLUMN=TABLE_COLUMN")
+                .help("Import a column of the file into a table column of a different name. May be repeated"),
+        )
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Check that the file can be imported, without inserting any rows"),
+        )
+        .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))
+        .after_help("Run `spacetime help import` for more detailed information.\n")
+}
+
+pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let server = args.get_one::<String>("server").map(|s| s.as_ref());
+    let database = args.get_one::<String>("database").unwrap();
+    let file = args.get_one::<PathBuf>("file").unwrap();
+    let table = args.get_one::<String>("table").unwrap();
+    let dry_run = args.get_flag("dry_run");
+
+    let format = match args.get_one::<String>("format") {
+        Some(format) => format.clone(),
+        None => match file.extension().and_then(|ext| ext.to_str()) {
+            Some(ext @ ("parquet" | "csv")) => ext.to_owned(),
+            _ => anyhow::bail!(
+                "cannot tell the format of {} from its extension, use --format",
+                file.display()
+            ),
+        },
+    };
+    let map = args.get_many::<String>("map").unwrap_or_default().join(",");
+
+    let data = std::fs::read(file).with_context(|| format!("could not read file {}", file.display()))?;
+
+    let identity = database_identity(&config, database, server).await?;
+    let host_url = config.get_host_url(server)?;
+
+    let mut builder = reqwest::Client::new()
+        .post(format!("{host_url}/database/import/{identity}"))
+        .query(&[("table", table), ("format", &format)]);
+    if !map.is_empty() {
+        builder = builder.query(&[("map", map)]);
+    }
+    if dry_run {
+        builder = builder.query(&[("dry_run", "true")]);
+    }
+    let auth_header = get_auth_header(&config, false)?;
+    let builder = add_auth_header_opt(builder, &auth_header);
+    let res = builder.body(data).send().await?;
+    // Report the server's explanation of why the file can't be imported.
+    if res.status().is_client_error() || res.status().is_server_error() {
+        let err = res.text().await?;
+        anyhow::bail!(err)
+    }
+
+    let ImportResult {
+        dry_run,
+        rows,
+        transactions,
+        columns,
+        ignored,
+    } = res.json().await?;
+    for (column, source) in columns {
+        match source {
+            Some(source) if source == column => println!("{column}"),
+            Some(source) => println!("{column} <- {source}"),
+            None => println!("{column} <- (default)"),
+        }
+    }
+    if !ignored.is_empty() {
+        println!("Ignored columns of the file: {}", ignored.join(", "));
+    }
+    if dry_run {
+        println!(
+            "Dry run: {rows} rows of {} can be imported into {table}",
+            file.display()
+        );
+    } else {
+        println!("Imported {rows} rows into {table} of {database} in {transactions} transactions");
+    }
+
+    Ok(())
+}
@@ -83,6 +83,10 @@ pub struct BacktraceFrame<'a> {
     pub module_name: Option<Cow<'a, str>>,
     #[serde(borrow)]
     pub func_name: Option<Cow<'a, str>>,
+    #[serde(borrow)]
+    pub filename: Option<Cow<'a, str>>,
+    pub line_number: Option<u32>,
+    pub column: Option<u32>,
 }
 
 #[derive(serde::Serialize)]
@@ -217,8 +221,21 @@ pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error
                 }
                 if let Some(function) = &frame.func_name {
                     out.set_color(&dimmed)?;
-                    writeln!(out, "{function}")?;
+                    write!(out, "{function}")?;
+                    out.reset()?;
+                }
+                writeln!(out)?;
+                if let Some(filename) = &frame.filename {
+                    out.set_color(&dimmed)?;
+                    write!(out, "        at {filename}")?;
+                    if let Some(line) = frame.line_number {
+                        write!(out, ":{line}")?;
+                        if let Some(column) = frame.column {
+                            write!(out, ":{column}")?;
+                        }
+                    }
                     out.reset()?;
+                    writeln!(out)?;
                 }
             }
         }
@@ -0,0 +1,127 @@
+use std::io;
+use std::time::Duration;
+
+use crate::common_args;
+use crate::config::Config;
+use crate::util::{add_auth_header_opt, database_identity, get_auth_header};
+use clap::{Arg, ArgMatches};
+use futures::{AsyncBufReadExt, TryStreamExt};
+use spacetimedb_client_api_messages::name::{SnapshotCompressionProgress, SnapshotCompressionStats};
+
+pub fn cli() -> clap::Command {
+    clap::Command::new("maintenance")
+        .about("Runs maintenance tasks on a SpacetimeDB database, e.g. during off-peak hours")
+        .args_conflicts_with_subcommands(true)
+        .subcommand_required(true)
+        .subcommands(get_maintenance_subcommands())
+}
+
+fn get_maintenance_subcommands() -> Vec<clap::Command> {
+    vec![clap::Command::new("compress")
+        .about("Compresses the old snapshots of a database to save disk space")
+        .arg(
+            Arg::new("database")
+                .long("database")
+                .required(true)
+                .help("The name or identity of the database whose snapshots to compress"),
+        )
+        .arg(
+            Arg::new("older_than")
+                .long("older-than")
+                .default_value("7d")
+                .value_parser(parse_duration)
+                .help("Only compress snapshots older than this, e.g. 12h, 7d or 2w"),
+        )
+        .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))]
+}
+
+async fn exec_subcommand(config: Config, cmd: &str, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    match cmd {
+        "compress" => exec_compress(config, args).await,
+        unknown => Err(anyhow::anyhow!("Invalid subcommand: {}", unknown)),
+    }
+}
+
+pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let (cmd, subcommand_args) = args.subcommand().expect("Subcommand required");
+    exec_subcommand(config, cmd, subcommand_args).await
+}
+
+/// Parse a duration like `90s`, `30m`, `12h`, `7d` or `2w`.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (amount, unit) = s.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("expected a number followed by a unit, e.g. 7d, got `{s}`"))?;
+    let secs_per_unit = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("unknown unit `{unit}`, expected one of s, m, h, d or w")),
+    };
+    amount
+        .checked_mul(secs_per_unit)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("duration `{s}` is too long"))
+}
+
+fn print_stats(stats: &SnapshotCompressionStats) {
+    println!(
+        "{} snapshots, {} objects compressed from {} to {} bytes, {} objects skipped",
+        stats.snapshots_compressed,
+        stats.objects_compressed,
+        stats.bytes_before,
+        stats.bytes_after,
+        stats.objects_skipped,
+    );
+}
+
+async fn exec_compress(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let server = args.get_one::<String>("server").map(|s| s.as_ref());
+    let database = args.get_one::<String>("database").unwrap();
+    let older_than = *args.get_one::<Duration>("older_than").unwrap();
+
+    let identity = database_identity(&config, database, server).await?;
+    let host_url = config.get_host_url(server)?;
+
+    let builder = reqwest::Client::new()
+        .post(format!("{host_url}/database/compress_snapshots/{identity}"))
+        .query(&[("older_than_secs", older_than.as_secs())]);
+    let auth_header = get_auth_header(&config, false)?;
+    let builder = add_auth_header_opt(builder, &auth_header);
+    let res = builder.send().await?;
+    if res.status().is_client_error() || res.status().is_server_error() {
+        let err = res.text().await?;
+        anyhow::bail!(err)
+    }
+
+    let mut rdr = res
+        .bytes_stream()
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        .into_async_read();
+    let mut line = String::new();
+    while rdr.read_line(&mut line).await? != 0 {
+        match serde_json::from_str::<SnapshotCompressionProgress>(&line)? {
+            SnapshotCompressionProgress::Snapshot { tx_offset, stats } => {
+                print!("Compressed snapshot {tx_offset}: ");
+                print_stats(&stats);
+            }
+            SnapshotCompressionProgress::Done(stats) => {
+                if stats.snapshots_compressed == 0 {
+                    println!("No snapshots of {database} are old enough to compress.");
+                } else {
+                    print!("Done: ");
+                    print_stats(&stats);
+                }
+                return Ok(());
+            }
+            SnapshotCompressionProgress::Error(err) => anyhow::bail!(err),
+        }
+        line.clear();
+    }
+
+    anyhow::bail!("The server stopped responding before compressing the snapshots of {database} finished")
+}
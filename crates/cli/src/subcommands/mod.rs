@@ -1,15 +1,22 @@
+pub mod advise_indexes;
+pub mod bench;
 pub mod build;
 pub mod call;
+pub mod clone;
 pub mod delete;
 pub mod describe;
 pub mod dns;
 pub mod energy;
+pub mod export;
+pub mod export_sqlite;
 pub mod generate;
+pub mod import;
 pub mod init;
 pub mod list;
 pub mod login;
 pub mod logout;
 pub mod logs;
+pub mod maintenance;
 pub mod publish;
 pub mod repl;
 pub mod server;
@@ -17,3 +24,4 @@ pub mod sql;
 pub mod subscribe;
 pub mod upgrade;
 pub mod version;
+pub mod versions;
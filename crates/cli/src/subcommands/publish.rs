@@ -1,12 +1,13 @@
-use anyhow::bail;
+use anyhow::{bail, Context};
 use clap::Arg;
 use clap::ArgAction::{Set, SetTrue};
 use clap::ArgMatches;
 use reqwest::{StatusCode, Url};
 use spacetimedb_client_api_messages::name::PublishOp;
 use spacetimedb_client_api_messages::name::{is_identity, parse_domain_name, PublishResult};
+use spacetimedb_lib::assets::{AssetBundle, AssetFile};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::config::Config;
 use crate::util::{add_auth_header_opt, get_auth_header};
@@ -49,6 +50,12 @@ pub fn cli() -> clap::Command {
                 .conflicts_with("build_options")
                 .help("The system path (absolute or relative) to the compiled wasm binary we should publish, instead of building the project."),
         )
+        .arg(
+            Arg::new("assets")
+                .value_parser(clap::value_parser!(PathBuf))
+                .long("assets")
+                .help("A directory of static files to publish alongside the module, readable by the module with `ctx.assets()` and by clients over HTTP"),
+        )
         .arg(
             common_args::anonymous()
         )
@@ -73,6 +80,7 @@ pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error
     let force = args.get_flag("force");
     let anon_identity = args.get_flag("anon_identity");
     let wasm_file = args.get_one::<PathBuf>("wasm_file");
+    let assets_dir = args.get_one::<PathBuf>("assets");
     let database_host = config.get_host_url(server)?;
     let build_options = args.get_one::<String>("build_options").unwrap();
 
@@ -108,7 +116,12 @@ pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error
     } else {
         build::exec_with_argstring(config.clone(), path_to_project, build_options).await?
     };
-    let program_bytes = fs::read(path_to_wasm)?;
+    let mut program_bytes = fs::read(path_to_wasm)?;
+    if let Some(assets_dir) = assets_dir {
+        let bundle = read_asset_bundle(assets_dir)?;
+        println!("Bundling {} assets from {}", bundle.files.len(), assets_dir.display());
+        bundle.append_to_program(&mut program_bytes);
+    }
 
     let server_address = {
         let url = Url::parse(&database_host)?;
@@ -225,3 +238,29 @@ pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error
 
     Ok(())
 }
+
+/// Reads every file under `dir` into an asset bundle,
+/// addressed by its path relative to `dir` with `/` as the separator.
+fn read_asset_bundle(dir: &Path) -> anyhow::Result<AssetBundle> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir)?;
+        let path = relative
+            .components()
+            .map(|component| {
+                component
+                    .as_os_str()
+                    .to_str()
+                    .with_context(|| format!("asset path is not valid UTF-8: {}", relative.display()))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .join("/");
+        let data = fs::read(entry.path())?;
+        files.push(AssetFile { path, data });
+    }
+    Ok(AssetBundle { files })
+}
@@ -1,8 +1,11 @@
+use std::io;
 use std::time::Instant;
 
-use crate::api::{from_json_seed, ClientApi, Connection, StmtResultJson};
+use crate::api::{from_json_seed, ClientApi, Connection, StmtResultJson, StmtResultLine};
 use crate::common_args;
+use anyhow::Context;
 use clap::{Arg, ArgAction, ArgMatches};
+use futures::{AsyncBufReadExt, TryStreamExt};
 use itertools::Itertools;
 use reqwest::RequestBuilder;
 use spacetimedb_lib::de::serde::SeedWrapper;
@@ -24,9 +27,10 @@ pub fn cli() -> clap::Command {
         .arg(
             Arg::new("query")
                 .action(ArgAction::Set)
+                .num_args(1..)
                 .required(true)
                 .conflicts_with("interactive")
-                .help("The SQL query to execute"),
+                .help("The SQL query to execute. Several queries are executed as a single transaction"),
         )
         .arg(
             Arg::new("interactive")
@@ -35,6 +39,20 @@ pub fn cli() -> clap::Command {
                 .conflicts_with("query")
                 .help("Instead of using a query, run an interactive command prompt for `SQL` expressions"),
         )
+        .arg(
+            Arg::new("stream")
+                .long("stream")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("interactive")
+                .help("Print the rows as the server streams them, rather than as an aligned table. For queries with large results"),
+        )
+        .arg(
+            Arg::new("max_rows")
+                .long("max-rows")
+                .value_parser(clap::value_parser!(u64))
+                .requires("stream")
+                .help("Have the server stop streaming after this many rows"),
+        )
         .arg(common_args::anonymous())
         .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))
 }
@@ -100,6 +118,57 @@ pub(crate) async fn run_sql(builder: RequestBuilder, sql: &str, with_stats: bool
     Ok(())
 }
 
+/// Like [`run_sql`], but has the server stream the results of the read-only `sql`,
+/// printing each row as soon as it arrives, so that results of any size can be printed.
+pub(crate) async fn stream_sql(builder: RequestBuilder, sql: &str, max_rows: Option<u64>) -> Result<(), anyhow::Error> {
+    let mut builder = builder.query(&[("stream", true)]);
+    if let Some(max_rows) = max_rows {
+        builder = builder.query(&[("max_rows", max_rows)]);
+    }
+    let res = error_for_status(builder.body(sql.to_owned()).send().await?).await?;
+
+    let mut rdr = res
+        .bytes_stream()
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        .into_async_read();
+    let mut line = String::new();
+    let mut schema = None;
+    while rdr.read_line(&mut line).await? != 0 {
+        match serde_json::from_str::<StmtResultLine<'_>>(&line)? {
+            StmtResultLine::Schema(next) => {
+                if schema.is_some() {
+                    println!();
+                }
+                let names = next
+                    .elements
+                    .iter()
+                    .enumerate()
+                    .map(|(i, e)| e.name.clone().unwrap_or_else(|| format!("column {i}").into()));
+                println!("{}", names.format(" | "));
+                schema = Some(next);
+            }
+            StmtResultLine::Row(row) => {
+                let ty = Typespace::EMPTY.with_type(schema.as_ref().context("row without a schema")?);
+                let row = from_json_seed(row.get(), SeedWrapper(ty))?;
+                let values = ty
+                    .with_values(&row)
+                    .map(|value| satn::PsqlWrapper { ty: ty.ty(), value }.to_string());
+                println!("{}", values.format(" | "));
+            }
+            StmtResultLine::Done { rows, truncated } => {
+                println!("{}", print_row_count(rows as usize));
+                if truncated {
+                    println!("Stopped at the limit of {rows} rows, more rows were left out.");
+                }
+                return Ok(());
+            }
+        }
+        line.clear();
+    }
+
+    anyhow::bail!("The server stopped responding before sending all the results")
+}
+
 fn stmt_result_to_table(stmt_result: &StmtResultJson) -> anyhow::Result<tabled::Table> {
     let StmtResultJson { schema, rows } = stmt_result;
 
@@ -134,12 +203,22 @@ pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error
 
         crate::repl::exec(con).await?;
     } else {
-        let query = args.get_one::<String>("query").unwrap();
+        let queries = args.get_many::<String>("query").unwrap().collect::<Vec<_>>();
 
         let con = parse_req(config, args).await?;
         let api = ClientApi::new(con);
 
-        run_sql(api.sql(), query, false).await?;
+        let sql = match &queries[..] {
+            [query] => query.to_string(),
+            // The server accepts a JSON array of statements to execute as a single transaction.
+            queries => serde_json::to_string(queries)?,
+        };
+        if args.get_flag("stream") {
+            let max_rows = args.get_one::<u64>("max_rows").copied();
+            stream_sql(api.sql(), &sql, max_rows).await?;
+        } else {
+            run_sql(api.sql(), &sql, false).await?;
+        }
     }
     Ok(())
 }
@@ -11,10 +11,12 @@ use spacetimedb_lib::de::serde::{DeserializeWrapper, SeedWrapper};
 use spacetimedb_lib::ser::serde::SerializeWrapper;
 use std::time::Duration;
 use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
-use crate::api::ClientApi;
+use crate::api::{ClientApi, Connection};
 use crate::common_args;
 use crate::sql::parse_req;
 use crate::Config;
@@ -62,11 +64,19 @@ pub fn cli() -> clap::Command {
                 .action(ArgAction::SetTrue)
                 .help("Print the initial update for the queries."),
         )
+        .arg(
+            Arg::new("chunk_rows")
+                .required(false)
+                .long("chunk-rows")
+                .action(ArgAction::Set)
+                .value_parser(value_parser!(u32).range(1..))
+                .help("Receive the initial update in messages of at most this many rows"),
+        )
         .arg(common_args::anonymous())
         .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))
 }
 
-fn parse_msg_json(msg: &WsMessage) -> Option<ws::ServerMessage<JsonFormat>> {
+pub(crate) fn parse_msg_json(msg: &WsMessage) -> Option<ws::ServerMessage<JsonFormat>> {
     let WsMessage::Text(msg) = msg else { return None };
     serde_json::from_str::<DeserializeWrapper<ws::ServerMessage<JsonFormat>>>(msg)
         .inspect_err(|e| eprintln!("couldn't parse message from server: {e}"))
@@ -124,37 +134,16 @@ pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error
     let num = args.get_one::<u32>("num-updates").copied();
     let timeout = args.get_one::<u32>("timeout").copied();
     let print_initial_update = args.get_flag("print_initial_update");
+    let chunk_rows = args.get_one::<u32>("chunk_rows").copied();
 
     let conn = parse_req(config, args).await?;
     let api = ClientApi::new(conn);
     let module_def = api.module_def().await?;
 
-    // Change the URI scheme from `http(s)` to `ws(s)`.
-    let mut uri = http::Uri::try_from(api.con.db_uri("subscribe"))?.into_parts();
-    uri.scheme = uri.scheme.map(|s| {
-        if s == Scheme::HTTP {
-            "ws".parse().unwrap()
-        } else if s == Scheme::HTTPS {
-            "wss".parse().unwrap()
-        } else {
-            s
-        }
-    });
-
-    // Create the websocket request.
-    let mut req = http::Uri::from_parts(uri)?.into_client_request()?;
-    req.headers_mut().insert(
-        header::SEC_WEBSOCKET_PROTOCOL,
-        http::HeaderValue::from_static(ws::TEXT_PROTOCOL),
-    );
-    //  Add the authorization header, if any.
-    if let Some(auth_header) = &api.con.auth_header {
-        req.headers_mut().insert(header::AUTHORIZATION, auth_header.try_into()?);
-    }
-    let (mut ws, _) = tokio_tungstenite::connect_async(req).await?;
+    let mut ws = connect(&api.con).await?;
 
     let task = async {
-        subscribe(&mut ws, queries.cloned().map(Into::into).collect()).await?;
+        subscribe(&mut ws, queries.cloned().map(Into::into).collect(), chunk_rows).await?;
         await_initial_update(&mut ws, print_initial_update.then_some(&module_def)).await?;
         consume_transaction_updates(&mut ws, num, &module_def).await
     };
@@ -176,8 +165,41 @@ pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error
     Ok(())
 }
 
+/// Open a websocket connection to the database, speaking the JSON protocol.
+pub(crate) async fn connect(con: &Connection) -> anyhow::Result<WebSocketStream<MaybeTlsStream<TcpStream>>> {
+    // Change the URI scheme from `http(s)` to `ws(s)`.
+    let mut uri = http::Uri::try_from(con.db_uri("subscribe"))?.into_parts();
+    uri.scheme = uri.scheme.map(|s| {
+        if s == Scheme::HTTP {
+            "ws".parse().unwrap()
+        } else if s == Scheme::HTTPS {
+            "wss".parse().unwrap()
+        } else {
+            s
+        }
+    });
+
+    // Create the websocket request.
+    let mut req = http::Uri::from_parts(uri)?.into_client_request()?;
+    req.headers_mut().insert(
+        header::SEC_WEBSOCKET_PROTOCOL,
+        http::HeaderValue::from_static(ws::TEXT_PROTOCOL),
+    );
+    //  Add the authorization header, if any.
+    if let Some(auth_header) = &con.auth_header {
+        req.headers_mut().insert(header::AUTHORIZATION, auth_header.try_into()?);
+    }
+    let (ws, _) = tokio_tungstenite::connect_async(req).await?;
+    Ok(ws)
+}
+
 /// Send the subscribe message.
-async fn subscribe<S>(ws: &mut S, query_strings: Box<[Box<str>]>) -> Result<(), S::Error>
+/// If `chunk_rows` is `Some`, ask for the initial update in messages of at most that many rows.
+pub(crate) async fn subscribe<S>(
+    ws: &mut S,
+    query_strings: Box<[Box<str>]>,
+    chunk_rows: Option<u32>,
+) -> Result<(), S::Error>
 where
     S: Sink<WsMessage> + Unpin,
 {
@@ -185,6 +207,8 @@ where
         ws::Subscribe {
             query_strings,
             request_id: 0,
+            min_tx_offset: None,
+            chunk_rows,
         },
     )))
     .unwrap();
@@ -192,8 +216,9 @@ where
 }
 
 /// Await the initial [`ServerMessage::SubscriptionUpdate`].
-/// If `module_def` is `Some`, print a JSON representation to stdout.
-async fn await_initial_update<S>(ws: &mut S, module_def: Option<&RawModuleDefV9>) -> anyhow::Result<()>
+/// If `module_def` is `Some`, print a JSON representation to stdout,
+/// one line per chunk if the update was requested in chunks.
+pub(crate) async fn await_initial_update<S>(ws: &mut S, module_def: Option<&RawModuleDefV9>) -> anyhow::Result<()>
 where
     S: TryStream<Ok = WsMessage> + Unpin,
     S::Error: std::error::Error + Send + Sync + 'static,
@@ -203,6 +228,13 @@ where
     while let Some(msg) = ws.try_next().await? {
         let Some(msg) = parse_msg_json(&msg) else { continue };
         match msg {
+            ws::ServerMessage::InitialSubscriptionChunk(chunk) => {
+                if let Some(module_def) = module_def {
+                    let formatted = reformat_update(&chunk.database_update, module_def)?;
+                    let output = serde_json::to_string(&formatted)? + "\n";
+                    tokio::io::stdout().write_all(output.as_bytes()).await?
+                }
+            }
             ws::ServerMessage::InitialSubscription(sub) => {
                 if let Some(module_def) = module_def {
                     let formatted = reformat_update(&sub.database_update, module_def)?;
@@ -213,6 +245,7 @@ where
             }
             ws::ServerMessage::TransactionUpdate(ws::TransactionUpdate { status, .. }) => anyhow::bail!(match status {
                 ws::UpdateStatus::Failed(msg) => msg,
+                ws::UpdateStatus::FailedWithValue(err) => err.message,
                 _ => RECV_TX_UPDATE.into(),
             }),
             ws::ServerMessage::TransactionUpdateLight(ws::TransactionUpdateLight { .. }) => {
@@ -249,7 +282,7 @@ where
 
         let Some(msg) = parse_msg_json(&msg) else { continue };
         match msg {
-            ws::ServerMessage::InitialSubscription(_) => {
+            ws::ServerMessage::InitialSubscription(_) | ws::ServerMessage::InitialSubscriptionChunk(_) => {
                 anyhow::bail!("protocol error: received a second initial subscription update")
             }
             ws::ServerMessage::TransactionUpdateLight(ws::TransactionUpdateLight { update, .. })
@@ -0,0 +1,102 @@
+use crate::common_args;
+use crate::config::Config;
+use crate::util::{add_auth_header_opt, database_identity, get_auth_header};
+use clap::{Arg, ArgMatches};
+use serde::Deserialize;
+use spacetimedb_lib::{Hash, Identity};
+use tabled::settings::Style;
+use tabled::{Table, Tabled};
+
+pub fn cli() -> clap::Command {
+    clap::Command::new("versions")
+        .about("Invokes commands related to the published module versions of a database")
+        .args_conflicts_with_subcommands(true)
+        .subcommand_required(true)
+        .subcommands(get_versions_subcommands())
+}
+
+fn get_versions_subcommands() -> Vec<clap::Command> {
+    vec![clap::Command::new("list")
+        .about("Lists the module versions published to a database, oldest first")
+        .arg(
+            Arg::new("database")
+                .required(true)
+                .help("The name or identity of the database to list versions for"),
+        )
+        .arg(common_args::server().help("The nickname, host name or URL of the server hosting the database"))
+        .arg(common_args::anonymous())]
+}
+
+async fn exec_subcommand(config: Config, cmd: &str, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    match cmd {
+        "list" => exec_list(config, args).await,
+        unknown => Err(anyhow::anyhow!("Invalid subcommand: {}", unknown)),
+    }
+}
+
+pub async fn exec(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let (cmd, subcommand_args) = args.subcommand().expect("Subcommand required");
+    exec_subcommand(config, cmd, subcommand_args).await
+}
+
+#[derive(Deserialize)]
+struct PublishedAt {
+    microseconds: u64,
+}
+
+#[derive(Deserialize)]
+struct ModuleVersion {
+    version: u64,
+    program_hash: Hash,
+    published_at: PublishedAt,
+    publisher: Identity,
+}
+
+#[derive(Tabled)]
+#[tabled(rename_all = "UPPERCASE")]
+struct VersionRow {
+    version: u64,
+    published: String,
+    publisher: Identity,
+    #[tabled(rename = "PROGRAM HASH")]
+    program_hash: Hash,
+}
+
+async fn exec_list(config: Config, args: &ArgMatches) -> Result<(), anyhow::Error> {
+    let database = args.get_one::<String>("database").unwrap();
+    let server = args.get_one::<String>("server").map(|s| s.as_ref());
+    let anon_identity = args.get_flag("anon_identity");
+
+    let database_identity = database_identity(&config, database, server).await?;
+
+    let builder = reqwest::Client::new().get(format!(
+        "{}/database/versions/{}",
+        config.get_host_url(server)?,
+        database_identity
+    ));
+    let auth_header = get_auth_header(&config, anon_identity)?;
+    let res = add_auth_header_opt(builder, &auth_header).send().await?;
+    if res.status().is_client_error() || res.status().is_server_error() {
+        let err = res.text().await?;
+        anyhow::bail!(err)
+    }
+
+    let versions: Vec<ModuleVersion> = res.json().await?;
+    if versions.is_empty() {
+        println!("No module versions recorded for {database_identity}.");
+        return Ok(());
+    }
+
+    let rows = versions.into_iter().map(|version| VersionRow {
+        version: version.version,
+        published: chrono::DateTime::from_timestamp_micros(version.published_at.microseconds as i64)
+            .map_or_else(|| version.published_at.microseconds.to_string(), |ts| ts.to_rfc3339()),
+        publisher: version.publisher,
+        program_hash: version.program_hash,
+    });
+    let mut table = Table::new(rows);
+    table.with(Style::psql());
+    println!("{table}");
+
+    Ok(())
+}
@@ -175,13 +175,16 @@ pub fn get_auth_header(config: &Config, anon_identity: bool) -> anyhow::Result<O
         Ok(None)
     } else {
         let token = config.spacetimedb_token_or_error()?;
-        // The current form is: Authorization: Basic base64("token:<token>")
-        let mut auth_header = String::new();
-        auth_header.push_str(format!("Basic {}", BASE_64_STD.encode(format!("token:{}", token))).as_str());
-        Ok(Some(auth_header))
+        Ok(Some(auth_header_for_token(token)))
     }
 }
 
+/// Returns the `Authorization` header authenticating with `token`.
+pub fn auth_header_for_token(token: &str) -> String {
+    // The current form is: Authorization: Basic base64("token:<token>")
+    format!("Basic {}", BASE_64_STD.encode(format!("token:{}", token)))
+}
+
 pub const VALID_PROTOCOLS: [&str; 2] = ["http", "https"];
 
 #[derive(Clone, Copy, PartialEq, Debug)]
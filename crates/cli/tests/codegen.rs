@@ -40,3 +40,20 @@ declare_tests! {
     test_codegen_typescript => TypeScript,
     test_codegen_rust => Rust,
 }
+
+#[test]
+fn test_codegen_rust_subset() {
+    let module = generate::extract_descriptions(compiled_module()).unwrap();
+    // `test_f` uses the type `TestFoobar`, which uses `Foobar`, which uses `Baz`.
+    let subset = generate::ModuleSubset {
+        tables: Some(["test_f".to_owned()].into_iter().collect()),
+        reducers: Some(["add_player".to_owned()].into_iter().collect()),
+    };
+    let outfiles: HashMap<_, _> = generate::generate_subset(module, generate::Language::Rust, "", &subset)
+        .unwrap()
+        .into_iter()
+        .collect();
+    insta::with_settings!({ sort_maps => true }, {
+        insta::assert_toml_snapshot!(outfiles);
+    });
+}
@@ -1,7 +1,9 @@
+use chrono::NaiveDate;
 use derive_more::{Add, AddAssign, From, Sub, SubAssign};
+use spacetimedb_lib::Identity;
 use spacetimedb_sats::SpacetimeType;
 use std::fmt;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 /// [EnergyQuanta] represents an amount of energy in a canonical unit.
 /// It represents the smallest unit of energy that can be used to pay for
@@ -58,6 +60,20 @@ impl fmt::Debug for EnergyQuanta {
     }
 }
 
+/// Serialized as a string of the number of quanta, to avoid truncation in JSON.
+impl serde::Serialize for EnergyQuanta {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.quanta)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for EnergyQuanta {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let quanta = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        quanta.parse().map(Self::new).map_err(serde::de::Error::custom)
+    }
+}
+
 /// [`EnergyBalance`] same unit as [`EnergyQuanta`], but representing a user account's energy balance.
 ///
 /// NOTE: This is represented by a signed integer, because it is possible
@@ -162,3 +178,135 @@ impl fmt::Debug for ReducerBudget {
             .finish()
     }
 }
+
+/// A day in UTC, by which the energy ledger rolls up energy usage.
+///
+/// Serialized as a date, e.g. `2024-10-17`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(into = "NaiveDate", try_from = "NaiveDate")]
+pub struct LedgerDay(u32);
+
+impl LedgerDay {
+    /// The number of days from 0001-01-01 to the unix epoch, as counted by [`NaiveDate::num_days_from_ce`].
+    const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+    /// The day `days` days after the unix epoch.
+    pub fn new(days: u32) -> Self {
+        Self(days)
+    }
+
+    /// The day on which `time` falls.
+    pub fn of(time: SystemTime) -> Self {
+        let secs = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self((secs / (24 * 60 * 60)) as u32)
+    }
+
+    /// The current day.
+    pub fn today() -> Self {
+        Self::of(SystemTime::now())
+    }
+
+    /// The number of days after the unix epoch.
+    pub fn days(self) -> u32 {
+        self.0
+    }
+
+    /// This day as a date.
+    pub fn date(self) -> NaiveDate {
+        NaiveDate::from_num_days_from_ce_opt(Self::UNIX_EPOCH_DAYS_FROM_CE.saturating_add_unsigned(self.0))
+            .unwrap_or(NaiveDate::MAX)
+    }
+}
+
+impl From<LedgerDay> for NaiveDate {
+    fn from(day: LedgerDay) -> Self {
+        day.date()
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("{0} is before the unix epoch")]
+pub struct DateBeforeEpoch(NaiveDate);
+
+impl TryFrom<NaiveDate> for LedgerDay {
+    type Error = DateBeforeEpoch;
+
+    fn try_from(date: NaiveDate) -> Result<Self, Self::Error> {
+        let days = date.num_days_from_ce() - Self::UNIX_EPOCH_DAYS_FROM_CE;
+        days.try_into().map(Self).map_err(|_| DateBeforeEpoch(date))
+    }
+}
+
+impl fmt::Display for LedgerDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.date().fmt(f)
+    }
+}
+
+impl fmt::Debug for LedgerDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LedgerDay").field(&self.date()).finish()
+    }
+}
+
+/// What the energy of an [`EnergyLedgerEntry`] was used for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnergyResource {
+    /// Calling a reducer or view.
+    Reducer,
+    /// Holding a database's data in memory.
+    Memory,
+    /// Storing a database's commitlog and logs on disk.
+    Disk,
+}
+
+impl fmt::Display for EnergyResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Reducer => "reducer",
+            Self::Memory => "memory",
+            Self::Disk => "disk",
+        })
+    }
+}
+
+/// The energy used for one [`EnergyResource`] of a database on one day,
+/// as recorded in the energy ledger.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EnergyLedgerEntry {
+    pub day: LedgerDay,
+    /// The identity billed for the energy, i.e. the owner of the database.
+    pub identity: Identity,
+    pub database_identity: Identity,
+    pub resource: EnergyResource,
+    /// The name of the reducer called, if `resource` is [`EnergyResource::Reducer`].
+    pub reducer: Option<String>,
+    /// The number of reducer calls, or zero for storage.
+    pub calls: u64,
+    pub energy_used: EnergyQuanta,
+}
+
+impl EnergyLedgerEntry {
+    /// The header of the CSV export of the ledger, matching [`Self::to_csv_record`].
+    pub const CSV_HEADER: &'static str = "day,identity,database_identity,resource,reducer,calls,energy_used";
+
+    /// This entry as a line of the CSV export of the ledger, without the trailing newline.
+    ///
+    /// Reducer names are identifiers, so none of the fields need quoting.
+    pub fn to_csv_record(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}",
+            self.day,
+            self.identity,
+            self.database_identity,
+            self.resource,
+            self.reducer.as_deref().unwrap_or_default(),
+            self.calls,
+            self.energy_used.get(),
+        )
+    }
+}
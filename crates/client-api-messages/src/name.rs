@@ -78,6 +78,75 @@ pub enum PublishResult {
     PermissionDenied { domain: DomainName },
 }
 
+/// The result of cloning a database into a new one.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CloneResult {
+    /// The name of the new database, if one was given.
+    pub domain: Option<String>,
+    /// The identity of the new database.
+    pub database_identity: Identity,
+}
+
+/// The result of importing the rows of a file into a table.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ImportResult {
+    /// Whether this was a dry run, in which no rows were inserted.
+    pub dry_run: bool,
+    /// The number of rows imported, or that would have been imported in a dry run.
+    pub rows: u64,
+    /// The number of transactions the rows were committed in.
+    pub transactions: u64,
+    /// The name of each column of the table,
+    /// with the name of the column of the file it was imported from,
+    /// or `None` if it was filled in with a default value.
+    pub columns: Vec<(String, Option<String>)>,
+    /// The columns of the file which were not imported.
+    pub ignored: Vec<String>,
+}
+
+/// A suggestion to add a btree index on a column,
+/// because queries have filtered on it while scanning the entire table.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct IndexSuggestion {
+    pub table: String,
+    pub column: String,
+    /// The number of times queries scanned the table, filtering on the column.
+    pub scans: u64,
+    /// The number of rows read by those scans.
+    pub rows_scanned: u64,
+    /// The estimated number of rows an index would have saved those scans from reading.
+    pub rows_saved: u64,
+}
+
+/// The totals of compressing the snapshots of a database so far.
+#[derive(Debug, Clone, Copy, Default, serde::Serialize, serde::Deserialize)]
+pub struct SnapshotCompressionStats {
+    /// The number of snapshots whose objects have been compressed.
+    pub snapshots_compressed: u64,
+    /// The number of object files compressed.
+    pub objects_compressed: u64,
+    /// The number of object files left as they were.
+    pub objects_skipped: u64,
+    /// The size in bytes of the compressed objects before compression.
+    pub bytes_before: u64,
+    /// The size in bytes of the compressed objects after compression.
+    pub bytes_after: u64,
+}
+
+/// A line of the newline-delimited JSON response to compressing the snapshots of a database.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum SnapshotCompressionProgress {
+    /// The snapshot of `tx_offset` has been compressed, bringing the totals to `stats`.
+    Snapshot {
+        tx_offset: u64,
+        stats: SnapshotCompressionStats,
+    },
+    /// All the snapshots old enough have been compressed. Always the last line if no error occurred.
+    Done(SnapshotCompressionStats),
+    /// Compressing failed. Snapshots compressed before the failure stay compressed.
+    Error(String),
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum DnsLookupResponse {
     /// The lookup was successful and the domain and identity are returned.
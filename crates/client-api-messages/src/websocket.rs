@@ -13,6 +13,10 @@
 //!
 //! Changes to the Rust SDK are not necessarily required, as it depends on this crate
 //! rather than using an external mirror of this schema.
+//!
+//! The messages of older versions of the protocol which have since changed layout
+//! are kept in their own modules, e.g., [`v1`],
+//! so that the host can keep serving clients which speak those versions.
 
 use crate::energy::EnergyQuanta;
 use crate::timestamp::Timestamp;
@@ -38,8 +42,13 @@ use std::{
     sync::Arc,
 };
 
-pub const TEXT_PROTOCOL: &str = "v1.json.spacetimedb";
-pub const BIN_PROTOCOL: &str = "v1.bsatn.spacetimedb";
+pub mod v1;
+
+#[cfg(test)]
+mod tests;
+
+pub const TEXT_PROTOCOL: &str = "v2.json.spacetimedb";
+pub const BIN_PROTOCOL: &str = "v2.bsatn.spacetimedb";
 
 pub trait RowListLen {
     /// Returns the length of the list.
@@ -72,6 +81,9 @@ pub trait WebsocketFormat: Sized {
     /// Encodes the `elems` to a list in the format and also returns the length of the list.
     fn encode_list<R: ToBsatn + Serialize>(elems: impl Iterator<Item = R>) -> (Self::List, u64);
 
+    /// Splits `list` into its first `at` elements and the rest.
+    fn split_list_at(list: Self::List, at: usize) -> (Self::List, Self::List);
+
     /// The type used to encode query updates.
     /// This type exists so that some formats, e.g., BSATN, can compress an update.
     type QueryUpdate: SpacetimeType + for<'de> Deserialize<'de> + Serialize + Debug + Clone + Send;
@@ -79,6 +91,9 @@ pub trait WebsocketFormat: Sized {
     /// Convert a `QueryUpdate` into `Self::QueryUpdate`.
     /// This allows some formats to e.g., compress the update.
     fn into_query_update(qu: QueryUpdate<Self>, compression: Compression) -> Self::QueryUpdate;
+
+    /// Convert a `Self::QueryUpdate` back into a `QueryUpdate`, decompressing it if need be.
+    fn into_uncompressed(qu: Self::QueryUpdate) -> QueryUpdate<Self>;
 }
 
 /// Messages sent from the client to the server.
@@ -97,6 +112,17 @@ pub enum ClientMessage<Args> {
     SubscribeSingle(SubscribeSingle),
     /// Remove a subscription to a SQL query that was added with SubscribeSingle.
     Unsubscribe(Unsubscribe),
+    /// Register a subscription to the result of a view, called with the given arguments.
+    SubscribeView(SubscribeView<Args>),
+    /// Remove a subscription to a view that was added with SubscribeView.
+    /// The server responds with a `ViewUpdate` deleting every row of the view's result.
+    UnsubscribeView(Unsubscribe),
+    /// Start receiving the ephemeral messages reducers broadcast on a topic.
+    SubscribeTopic(SubscribeTopic),
+    /// Stop receiving the ephemeral messages broadcast on a topic.
+    UnsubscribeTopic(SubscribeTopic),
+    /// Set or remove a variable of the connection's session, which reducers the client calls may read.
+    SetSessionVariable(SetSessionVariable),
 }
 
 impl<Args> ClientMessage<Args> {
@@ -117,6 +143,41 @@ impl<Args> ClientMessage<Args> {
             ClientMessage::SubscribeSingle(x) => ClientMessage::SubscribeSingle(x),
             ClientMessage::Unsubscribe(x) => ClientMessage::Unsubscribe(x),
             ClientMessage::Subscribe(x) => ClientMessage::Subscribe(x),
+            ClientMessage::SubscribeView(SubscribeView {
+                view,
+                args,
+                request_id,
+                query_id,
+                page,
+                min_tx_offset,
+            }) => ClientMessage::SubscribeView(SubscribeView {
+                view,
+                args: f(args),
+                request_id,
+                query_id,
+                page,
+                min_tx_offset,
+            }),
+            ClientMessage::UnsubscribeView(x) => ClientMessage::UnsubscribeView(x),
+            ClientMessage::SubscribeTopic(x) => ClientMessage::SubscribeTopic(x),
+            ClientMessage::UnsubscribeTopic(x) => ClientMessage::UnsubscribeTopic(x),
+            ClientMessage::SetSessionVariable(x) => ClientMessage::SetSessionVariable(x),
+        }
+    }
+
+    /// The offset of the transaction the server must have committed before handling this message, if any.
+    pub fn min_tx_offset(&self) -> Option<u64> {
+        match self {
+            ClientMessage::Subscribe(x) => x.min_tx_offset,
+            ClientMessage::OneOffQuery(x) => x.min_tx_offset,
+            ClientMessage::SubscribeSingle(x) => x.min_tx_offset,
+            ClientMessage::SubscribeView(x) => x.min_tx_offset,
+            ClientMessage::CallReducer(_)
+            | ClientMessage::Unsubscribe(_)
+            | ClientMessage::UnsubscribeView(_)
+            | ClientMessage::SubscribeTopic(_)
+            | ClientMessage::UnsubscribeTopic(_)
+            | ClientMessage::SetSessionVariable(_) => None,
         }
     }
 }
@@ -205,6 +266,16 @@ pub struct Subscribe {
     /// A sequence of SQL queries.
     pub query_strings: Box<[Box<str>]>,
     pub request_id: u32,
+    /// If set, the server waits until it has committed the transaction at this offset
+    /// before evaluating the queries, so that they observe it.
+    ///
+    /// Added in version 2 of the protocol.
+    pub min_tx_offset: Option<u64>,
+    /// If set, the server sends the initial matching rows in messages of at most this many rows:
+    /// zero or more [`InitialSubscriptionChunk`]s followed by the [`InitialSubscription`] holding the last rows.
+    ///
+    /// Added in version 2 of the protocol.
+    pub chunk_rows: Option<u32>,
 }
 
 /// Sent by client to register a subscription to single query, for which the client should receive
@@ -229,6 +300,11 @@ pub struct SubscribeSingle {
     /// This is used to refer to this subscription in Unsubscribe messages from the client and errors sent from the server.
     /// These only have meaning given a ConnectionId.
     pub query_id: QueryId,
+    /// If set, the server waits until it has committed the transaction at this offset
+    /// before evaluating the query, so that it observes it.
+    ///
+    /// Added in version 2 of the protocol.
+    pub min_tx_offset: Option<u64>,
 }
 
 /// Client request for removing a query from a subscription.
@@ -242,6 +318,93 @@ pub struct Unsubscribe {
     pub query_id: QueryId,
 }
 
+/// Sent by client to subscribe to the result of a view.
+///
+/// After issuing a `SubscribeView` message, the client will receive a `ViewUpdate` message
+/// containing the rows of the view's current result selected by `page`. Then, any time a reducer changes
+/// the view's result, the client will receive a `ViewUpdate` containing the changed rows.
+///
+/// The view is called with the identity and address of the subscribing client.
+///
+/// Parametric over the argument type to enable [`ClientMessage::map_args`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct SubscribeView<Args> {
+    /// The name of the view to subscribe to.
+    pub view: Box<str>,
+    /// The arguments to the view.
+    ///
+    /// In the wire format, this will be a [`Bytes`], BSATN or JSON encoded according to the view's argument schema
+    /// and the enclosing message format.
+    pub args: Args,
+    /// An identifier for a client request.
+    pub request_id: u32,
+    /// An identifier for this subscription, which should not be used for any other view subscriptions
+    /// on the same connection.
+    /// This is used to refer to this subscription in `UnsubscribeView` messages and in errors and updates
+    /// sent from the server.
+    pub query_id: QueryId,
+    /// Which rows of the view's result to send to the client.
+    pub page: ViewPage,
+    /// If set, the server waits until it has committed the transaction at this offset
+    /// before calling the view, so that it observes it.
+    pub min_tx_offset: Option<u64>,
+}
+
+/// Sent by client to start receiving the ephemeral messages reducers broadcast on `topic`,
+/// or, as `UnsubscribeTopic`, to stop receiving them.
+///
+/// Subscribing to a topic the client is already subscribed to, or unsubscribing from one it isn't, does nothing.
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct SubscribeTopic {
+    /// The topic to subscribe to.
+    pub topic: Box<str>,
+}
+
+/// Sent by a client to set `key` to `value` in the session of its connection,
+/// or to remove `key` when `value` is `None`.
+///
+/// Session variables are small strings, e.g., the client's locale or build,
+/// which live as long as the connection and which reducers the client calls may read,
+/// without a round trip through a table.
+/// A request exceeding the limits on the number or size of variables fails, leaving the session unchanged.
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct SetSessionVariable {
+    /// The name of the variable.
+    pub key: Box<str>,
+    /// The new value of the variable, or `None` to remove it.
+    pub value: Option<Box<str>>,
+}
+
+/// Restricts the rows of a view's result which a client is sent.
+///
+/// The host sorts the view's rows by `order_by`, skips `offset` rows, and keeps at most `limit`.
+/// Updates sent to the client describe changes to this window of the result,
+/// so rows may be inserted or deleted because they moved into or out of the window.
+#[derive(SpacetimeType, Clone, Default, Debug)]
+#[sats(crate = spacetimedb_lib)]
+pub struct ViewPage {
+    /// The columns to sort the view's rows by, most significant first.
+    /// If empty, rows are kept in the order the view returned them.
+    pub order_by: Box<[ViewOrderBy]>,
+    /// The number of rows to skip.
+    pub offset: u32,
+    /// The maximum number of rows to send, if any.
+    pub limit: Option<u32>,
+}
+
+/// A column to sort a view's rows by.
+#[derive(SpacetimeType, Clone, Copy, Debug)]
+#[sats(crate = spacetimedb_lib)]
+pub struct ViewOrderBy {
+    /// The position of the column within the view's rows.
+    pub column: u16,
+    /// Whether to sort in descending, rather than ascending, order.
+    pub descending: bool,
+}
+
 /// A one-off query submission.
 ///
 /// Query should be a "SELECT * FROM Table WHERE ...". Other types of queries will be rejected.
@@ -255,6 +418,11 @@ pub struct Unsubscribe {
 pub struct OneOffQuery {
     pub message_id: Box<[u8]>,
     pub query_string: Box<str>,
+    /// If set, the server waits until it has committed the transaction at this offset
+    /// before evaluating the query, so that it observes it.
+    ///
+    /// Added in version 2 of the protocol.
+    pub min_tx_offset: Option<u64>,
 }
 
 /// The tag recognized by the host and SDKs to mean no compression of a [`ServerMessage`].
@@ -287,6 +455,12 @@ pub enum ServerMessage<F: WebsocketFormat> {
     UnsubscribeApplied(UnsubscribeApplied<F>),
     /// Communicate an error in the subscription lifecycle.
     SubscriptionError(SubscriptionError),
+    /// Sent in response to a `SubscribeView` message, and whenever the view's result changes.
+    ViewUpdate(ViewUpdate<F>),
+    /// An ephemeral message a reducer broadcast on a topic the client subscribed to with `SubscribeTopic`.
+    TopicMessage(TopicMessage),
+    /// Part of the initial matching rows of a `Subscribe` which asked for them in chunks.
+    InitialSubscriptionChunk(InitialSubscriptionChunk<F>),
 }
 
 /// The matching rows of a subscription query.
@@ -333,6 +507,44 @@ pub struct UnsubscribeApplied<F: WebsocketFormat> {
     pub rows: SubscribeRows<F>,
 }
 
+/// Changes to the result of a view subscribed to with [`SubscribeView`].
+///
+/// A view's result is treated as a list of rows:
+/// a view returning a list has one row per element, and any other view has a single row.
+/// Rows which are not products are sent as a product with a single element.
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct ViewUpdate<F: WebsocketFormat> {
+    /// The request_id of the `SubscribeView` or `UnsubscribeView` message this update responds to.
+    pub request_id: u32,
+    /// The overall time between the server receiving a request and sending the response.
+    /// Zero for updates caused by a reducer.
+    pub total_host_execution_duration_micros: u64,
+    /// The ID included in the corresponding `SubscribeView` message.
+    pub query_id: QueryId,
+    /// The name of the view.
+    pub view: Box<str>,
+    /// Rows added to the view's result.
+    /// For the first update sent for a subscription, this is the entire result.
+    pub inserts: F::List,
+    /// Rows removed from the view's result.
+    pub deletes: F::List,
+}
+
+/// An ephemeral message broadcast by a reducer on a topic.
+///
+/// Topic messages are never written to tables or the commitlog.
+/// They are sent only to the clients subscribed to the topic when the reducer commits,
+/// and are dropped if the reducer fails.
+#[derive(SpacetimeType, Debug, Clone)]
+#[sats(crate = spacetimedb_lib)]
+pub struct TopicMessage {
+    /// The topic the message was broadcast on.
+    pub topic: Box<str>,
+    /// The payload of the message, as passed by the reducer.
+    pub payload: Bytes,
+}
+
 /// Server response to an error at any point of the subscription lifecycle.
 /// If this error doesn't have a request_id, the client should drop all subscriptions.
 #[derive(SpacetimeType)]
@@ -390,6 +602,23 @@ pub struct InitialSubscription<F: WebsocketFormat> {
     pub total_host_execution_duration_micros: u64,
 }
 
+/// Part of the response to a [`Subscribe`] with [`Subscribe::chunk_rows`] set.
+///
+/// Clients should apply each chunk to their cache as it arrives.
+/// The subscription is applied once the [`InitialSubscription`] with the last rows arrives.
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct InitialSubscriptionChunk<F: WebsocketFormat> {
+    /// A [`DatabaseUpdate`] containing only inserts, some of the rows which match the subscription queries.
+    pub database_update: DatabaseUpdate<F>,
+    /// The request_id of the corresponding `Subscribe` message.
+    pub request_id: u32,
+    /// The number of rows sent so far, including those in this chunk.
+    pub rows_sent: u64,
+    /// The number of rows the initial subscription holds in total.
+    pub total_rows: u64,
+}
+
 /// Received by database from client to inform of user's identity, token and client address.
 ///
 /// The database will always send an `IdentityToken` message
@@ -423,6 +652,10 @@ pub struct TransactionUpdate<F: WebsocketFormat> {
     pub timestamp: Timestamp,
     /// The identity of the user who requested the reducer run. For event-driven and
     /// scheduled reducers, it is the identity of the database owner.
+    ///
+    /// If the database hides callers, by setting the system variable `hide_callers`,
+    /// clients other than the caller receive the all-zeros identity,
+    /// the all-zeros `caller_address` and a `request_id` of zero instead.
     pub caller_identity: Identity,
     /// The 16-byte address of the user who requested the reducer run.
     /// The all-zeros address is a sentinel which denotes no address.
@@ -439,6 +672,13 @@ pub struct TransactionUpdate<F: WebsocketFormat> {
     pub energy_quanta_used: EnergyQuanta,
     /// How long the reducer took to run.
     pub host_execution_duration_micros: u64,
+    /// The offset of the transaction committed by the reducer, if it committed any changes.
+    ///
+    /// Pass this as `min_tx_offset` in later subscriptions and queries
+    /// to make sure they observe the reducer's changes.
+    ///
+    /// Added in version 2 of the protocol.
+    pub tx_offset: Option<u64>,
 }
 
 /// Received by client from database upon a reducer run.
@@ -491,6 +731,23 @@ pub enum UpdateStatus<F: WebsocketFormat> {
     /// The reducer was interrupted due to insufficient energy/funds,
     /// and any changes it attempted to make were rolled back.
     OutOfEnergy,
+    /// The reducer failed with a value of its error type,
+    /// and any changes it attempted to make were rolled back.
+    ///
+    /// Added in version 2 of the protocol.
+    /// Version 1 clients are instead sent [`UpdateStatus::Failed`] with the value's `message`.
+    FailedWithValue(ReducerErrorValue<F>),
+}
+
+/// Contained in [`UpdateStatus::FailedWithValue`], the value a reducer failed with.
+#[derive(SpacetimeType, Debug)]
+#[sats(crate = spacetimedb_lib)]
+pub struct ReducerErrorValue<F: WebsocketFormat> {
+    /// The value, formatted as a string, for clients which don't understand the reducer's error type.
+    pub message: Box<str>,
+    /// The value, encoded as BSATN or JSON according to the reducer's error type
+    /// and the client's requested protocol.
+    pub value: F::Single,
 }
 
 /// A collection of inserted and deleted rows, contained in a [`TransactionUpdate`] or [`SubscriptionUpdate`].
@@ -508,6 +765,52 @@ impl<F: WebsocketFormat> DatabaseUpdate<F> {
     pub fn num_rows(&self) -> usize {
         self.tables.iter().map(|t| t.num_rows()).sum()
     }
+
+    /// Splits this update into updates of at most `max_rows` rows each, in order,
+    /// compressing the query updates of each according to `compression`.
+    ///
+    /// Always returns at least one update, which is empty if `self` is.
+    pub fn into_chunks(self, max_rows: usize, compression: Compression) -> Vec<Self> {
+        let max_rows = max_rows.max(1);
+        let mut chunks = Vec::new();
+        let mut chunk = Vec::<TableUpdate<F>>::new();
+        let mut chunk_rows = 0;
+        for table in self.tables {
+            for update in table.updates {
+                let QueryUpdate {
+                    mut deletes,
+                    mut inserts,
+                } = F::into_uncompressed(update);
+                while !deletes.is_empty() || !inserts.is_empty() {
+                    if chunk_rows == max_rows {
+                        chunks.push(chunk.drain(..).collect());
+                        chunk_rows = 0;
+                    }
+                    let room = max_rows - chunk_rows;
+                    let (piece_deletes, rest) = F::split_list_at(deletes, room);
+                    deletes = rest;
+                    let (piece_inserts, rest) = F::split_list_at(inserts, room - piece_deletes.len());
+                    inserts = rest;
+
+                    let num_rows = piece_deletes.len() + piece_inserts.len();
+                    chunk_rows += num_rows;
+                    let piece = QueryUpdate {
+                        deletes: piece_deletes,
+                        inserts: piece_inserts,
+                    };
+                    let piece = (F::into_query_update(piece, compression), num_rows as u64);
+                    match chunk.last_mut() {
+                        Some(last) if last.table_id == table.table_id => last.push(piece),
+                        _ => chunk.push(TableUpdate::new(table.table_id, table.table_name.clone(), piece)),
+                    }
+                }
+            }
+        }
+        if !chunk.is_empty() || chunks.is_empty() {
+            chunks.push(chunk.into_iter().collect());
+        }
+        chunks
+    }
 }
 
 impl<F: WebsocketFormat> FromIterator<TableUpdate<F>> for DatabaseUpdate<F> {
@@ -538,6 +841,14 @@ pub struct TableUpdate<F: WebsocketFormat> {
     pub num_rows: u64,
     /// The actual insert and delete updates for this table.
     pub updates: SmallVec<[F::QueryUpdate; 1]>,
+    /// Whether the table was truncated by the transaction.
+    ///
+    /// If set, clients should drop all the rows they have cached for this table
+    /// before applying `updates`, which then contain no deletes.
+    ///
+    /// Added in version 2 of the protocol.
+    /// Clients speaking version 1 are instead sent every deleted row.
+    pub cleared: bool,
 }
 
 impl<F: WebsocketFormat> TableUpdate<F> {
@@ -547,6 +858,7 @@ impl<F: WebsocketFormat> TableUpdate<F> {
             table_name,
             num_rows,
             updates: [update].into(),
+            cleared: false,
         }
     }
 
@@ -556,6 +868,7 @@ impl<F: WebsocketFormat> TableUpdate<F> {
             table_name,
             num_rows: 0,
             updates: SmallVec::new(),
+            cleared: false,
         }
     }
 
@@ -679,11 +992,20 @@ impl WebsocketFormat for JsonFormat {
         (list, count)
     }
 
+    fn split_list_at(mut list: Self::List, at: usize) -> (Self::List, Self::List) {
+        let rest = list.split_off(at.min(list.len()));
+        (list, rest)
+    }
+
     type QueryUpdate = QueryUpdate<Self>;
 
     fn into_query_update(qu: QueryUpdate<Self>, _: Compression) -> Self::QueryUpdate {
         qu
     }
+
+    fn into_uncompressed(qu: Self::QueryUpdate) -> QueryUpdate<Self> {
+        qu
+    }
 }
 
 #[derive(Clone, Copy, Default, Debug, SpacetimeType)]
@@ -722,6 +1044,10 @@ impl WebsocketFormat for BsatnFormat {
         (list.finish(), count)
     }
 
+    fn split_list_at(list: Self::List, at: usize) -> (Self::List, Self::List) {
+        list.split_at(at)
+    }
+
     type QueryUpdate = CompressableQueryUpdate<Self>;
 
     fn into_query_update(qu: QueryUpdate<Self>, compression: Compression) -> Self::QueryUpdate {
@@ -743,6 +1069,10 @@ impl WebsocketFormat for BsatnFormat {
             }
         }
     }
+
+    fn into_uncompressed(qu: Self::QueryUpdate) -> QueryUpdate<Self> {
+        qu.maybe_decompress()
+    }
 }
 
 /// A specification of either a desired or decided compression algorithm.
@@ -906,6 +1236,33 @@ impl BsatnRowList {
         let data_range = self.size_hint.index_to_range(index, data_end)?;
         Some(self.rows_data.slice(data_range))
     }
+
+    /// Splits the list into its first `at` rows and the rest.
+    pub fn split_at(self, at: usize) -> (Self, Self) {
+        let at = at.min(self.len());
+        let data_end = self.rows_data.len();
+        let data_at = self
+            .size_hint
+            .index_to_range(at, data_end)
+            .map_or(data_end, |range| range.start);
+        let (head_hint, rest_hint) = match &self.size_hint {
+            RowSizeHint::FixedSize(size) => (RowSizeHint::FixedSize(*size), RowSizeHint::FixedSize(*size)),
+            RowSizeHint::RowOffsets(offsets) => {
+                let (head, rest) = offsets.split_at(at);
+                let rest = rest.iter().map(|offset| offset - data_at as RowOffset).collect();
+                (RowSizeHint::RowOffsets(head.into()), RowSizeHint::RowOffsets(rest))
+            }
+        };
+        let head = Self {
+            size_hint: head_hint,
+            rows_data: self.rows_data.slice(..data_at),
+        };
+        let rest = Self {
+            size_hint: rest_hint,
+            rows_data: self.rows_data.slice(data_at..),
+        };
+        (head, rest)
+    }
 }
 
 /// An iterator over all the elements in a [`BsatnRowList`].
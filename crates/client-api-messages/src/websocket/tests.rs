@@ -0,0 +1,171 @@
+use spacetimedb_sats::{bsatn, product, ProductValue};
+
+use super::*;
+
+fn rows(range: Range<u32>) -> Vec<ProductValue> {
+    range.map(|i| product![i, format!("row {i}")]).collect()
+}
+
+fn table_update(table_id: u32, deletes: &[ProductValue], inserts: &[ProductValue]) -> TableUpdate<BsatnFormat> {
+    let (deletes, num_deletes) = BsatnFormat::encode_list(deletes.iter());
+    let (inserts, num_inserts) = BsatnFormat::encode_list(inserts.iter());
+    let update = QueryUpdate { deletes, inserts };
+    let update = BsatnFormat::into_query_update(update, Compression::None);
+    TableUpdate::new(
+        TableId(table_id),
+        format!("table {table_id}").into(),
+        (update, num_deletes + num_inserts),
+    )
+}
+
+fn row_bytes(list: &BsatnRowList) -> Vec<Bytes> {
+    list.into_iter().collect()
+}
+
+/// Returns the rows of each chunk, as `(table_id, deletes, inserts)` per query update.
+fn chunk_rows(chunks: Vec<DatabaseUpdate<BsatnFormat>>) -> Vec<Vec<(u32, usize, usize)>> {
+    chunks
+        .into_iter()
+        .map(|chunk| {
+            chunk
+                .tables
+                .into_iter()
+                .flat_map(|table| {
+                    let table_id = table.table_id.0;
+                    table.updates.into_iter().map(move |update| {
+                        let update = BsatnFormat::into_uncompressed(update);
+                        (table_id, update.deletes.len(), update.inserts.len())
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn split_at_splits_variable_sized_rows() {
+    let (list, _) = BsatnFormat::encode_list(rows(0..5).iter());
+    let all = row_bytes(&list);
+
+    let (head, rest) = list.clone().split_at(2);
+    assert_eq!(row_bytes(&head), all[..2]);
+    assert_eq!(row_bytes(&rest), all[2..]);
+
+    let (head, rest) = list.clone().split_at(0);
+    assert!(head.is_empty());
+    assert_eq!(row_bytes(&rest), all);
+
+    let (head, rest) = list.split_at(10);
+    assert_eq!(row_bytes(&head), all);
+    assert!(rest.is_empty());
+}
+
+#[test]
+fn split_at_splits_fixed_size_rows() {
+    let mut list = BsatnRowListBuilder::fixed(4);
+    for i in 0u32..5 {
+        list.push(&i.to_le_bytes());
+    }
+    let list = list.finish();
+    let all = row_bytes(&list);
+
+    let (head, rest) = list.split_at(3);
+    assert_eq!(row_bytes(&head), all[..3]);
+    assert_eq!(row_bytes(&rest), all[3..]);
+}
+
+#[test]
+fn into_chunks_splits_across_tables() {
+    let update: DatabaseUpdate<BsatnFormat> = [
+        table_update(1, &rows(0..2), &rows(2..5)),
+        table_update(2, &[], &rows(0..4)),
+    ]
+    .into_iter()
+    .collect();
+    let chunks = update.into_chunks(3, Compression::None);
+    assert_eq!(
+        chunk_rows(chunks),
+        [vec![(1, 2, 1)], vec![(1, 0, 2), (2, 0, 1)], vec![(2, 0, 3)]]
+    );
+}
+
+#[test]
+fn into_chunks_keeps_rows_in_order() {
+    let update: DatabaseUpdate<BsatnFormat> = [table_update(1, &[], &rows(0..5))].into_iter().collect();
+    let (all, _) = BsatnFormat::encode_list(rows(0..5).iter());
+    let chunked = update
+        .into_chunks(2, Compression::None)
+        .into_iter()
+        .flat_map(|chunk| chunk.tables)
+        .flat_map(|table| table.updates)
+        .flat_map(|update| row_bytes(&BsatnFormat::into_uncompressed(update).inserts))
+        .collect::<Vec<_>>();
+    assert_eq!(chunked, row_bytes(&all));
+}
+
+#[test]
+fn into_chunks_of_nothing_is_one_empty_update() {
+    let chunks = DatabaseUpdate::<BsatnFormat>::default().into_chunks(3, Compression::None);
+    assert_eq!(chunks.len(), 1);
+    assert!(chunks[0].tables.is_empty());
+}
+
+#[test]
+fn into_chunks_treats_zero_as_one_row() {
+    let update: DatabaseUpdate<BsatnFormat> = [table_update(1, &[], &rows(0..2))].into_iter().collect();
+    assert_eq!(
+        chunk_rows(update.into_chunks(0, Compression::None)),
+        [vec![(1, 0, 1)], vec![(1, 0, 1)]]
+    );
+}
+
+#[test]
+fn v1_table_update_is_v2_without_cleared() {
+    let update = table_update(4, &rows(0..2), &rows(2..5));
+    let v2 = bsatn::to_vec(&update).unwrap();
+    let v1 = bsatn::to_vec(&v1::TableUpdate::from(update)).unwrap();
+    // `cleared` is the last field of the v2 layout.
+    assert_eq!([&v1[..], &[0]].concat(), v2);
+}
+
+#[test]
+fn v1_clients_see_failed_with_value_as_failed() {
+    let status = UpdateStatus::<BsatnFormat>::FailedWithValue(ReducerErrorValue {
+        message: "NotFound(3)".into(),
+        value: bsatn::to_vec(&product![3u32]).unwrap().into(),
+    });
+    match v1::UpdateStatus::from(status) {
+        v1::UpdateStatus::Failed(message) => assert_eq!(&*message, "NotFound(3)"),
+        status => panic!("expected `Failed`, got {status:?}"),
+    }
+}
+
+#[test]
+fn v2_only_messages_have_no_v1_form() {
+    let msg = ServerMessage::<BsatnFormat>::TopicMessage(TopicMessage {
+        topic: "chat".into(),
+        payload: Bytes::from_static(b"hello"),
+    });
+    assert!(v1::ServerMessage::try_from(msg).is_err());
+}
+
+#[test]
+fn v1_client_messages_decode_without_v2_fields() {
+    let msg = v1::ClientMessage::<Bytes>::Subscribe(v1::Subscribe {
+        query_strings: ["SELECT * FROM t".into()].into(),
+        request_id: 7,
+    });
+    let bytes = bsatn::to_vec(&msg).unwrap();
+    // A v1 message is a prefix of the v2 layout, so decoding it as v2 would fail.
+    assert!(bsatn::from_slice::<ClientMessage<Bytes>>(&bytes).is_err());
+
+    let msg: ClientMessage<Bytes> = bsatn::from_slice::<v1::ClientMessage<Bytes>>(&bytes).unwrap().into();
+    let ClientMessage::Subscribe(msg) = msg else {
+        panic!("expected a `Subscribe`");
+    };
+    assert_eq!(msg.query_strings.len(), 1);
+    assert_eq!(&*msg.query_strings[0], "SELECT * FROM t");
+    assert_eq!(msg.request_id, 7);
+    assert_eq!(msg.min_tx_offset, None);
+    assert_eq!(msg.chunk_rows, None);
+}
@@ -0,0 +1,320 @@
+//! Messages of version 1 of the WebSocket protocol,
+//! selected by the [`TEXT_PROTOCOL`] and [`BIN_PROTOCOL`] identifiers,
+//! whose layouts have since changed.
+//!
+//! Messages which kept their layout are shared with the current version of the protocol.
+//! The host converts each message it sends to a version 1 client from the current version.
+//!
+//! Clients speaking version 1 depend on the layouts reachable from here never changing.
+//! When changing the layout of a shared message, keep its old layout here instead.
+
+use super::{
+    CallReducer, IdentityToken, OneOffQueryResponse, QueryId, ReducerCallInfo, SubscriptionError, Unsubscribe,
+    WebsocketFormat,
+};
+use crate::energy::EnergyQuanta;
+use crate::timestamp::Timestamp;
+use smallvec::SmallVec;
+use spacetimedb_lib::{Address, Identity};
+use spacetimedb_primitives::TableId;
+use spacetimedb_sats::SpacetimeType;
+
+pub const TEXT_PROTOCOL: &str = "v1.json.spacetimedb";
+pub const BIN_PROTOCOL: &str = "v1.bsatn.spacetimedb";
+
+/// Messages sent from a version 1 client to the server.
+///
+/// See [`super::ClientMessage`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub enum ClientMessage<Args> {
+    CallReducer(CallReducer<Args>),
+    Subscribe(Subscribe),
+    OneOffQuery(OneOffQuery),
+    SubscribeSingle(SubscribeSingle),
+    Unsubscribe(Unsubscribe),
+}
+
+/// See [`super::Subscribe`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct Subscribe {
+    pub query_strings: Box<[Box<str>]>,
+    pub request_id: u32,
+}
+
+/// See [`super::SubscribeSingle`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct SubscribeSingle {
+    pub query: Box<str>,
+    pub request_id: u32,
+    pub query_id: QueryId,
+}
+
+/// See [`super::OneOffQuery`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct OneOffQuery {
+    pub message_id: Box<[u8]>,
+    pub query_string: Box<str>,
+}
+
+/// Messages sent from the server to a version 1 client.
+///
+/// See [`super::ServerMessage`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub enum ServerMessage<F: WebsocketFormat> {
+    InitialSubscription(InitialSubscription<F>),
+    TransactionUpdate(TransactionUpdate<F>),
+    TransactionUpdateLight(TransactionUpdateLight<F>),
+    IdentityToken(IdentityToken),
+    OneOffQueryResponse(OneOffQueryResponse<F>),
+    SubscribeApplied(SubscribeApplied<F>),
+    UnsubscribeApplied(UnsubscribeApplied<F>),
+    SubscriptionError(SubscriptionError),
+}
+
+/// See [`super::SubscribeRows`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct SubscribeRows<F: WebsocketFormat> {
+    pub table_id: TableId,
+    pub table_name: Box<str>,
+    pub table_rows: TableUpdate<F>,
+}
+
+/// See [`super::SubscribeApplied`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct SubscribeApplied<F: WebsocketFormat> {
+    pub request_id: u32,
+    pub total_host_execution_duration_micros: u64,
+    pub query_id: QueryId,
+    pub rows: SubscribeRows<F>,
+}
+
+/// See [`super::UnsubscribeApplied`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct UnsubscribeApplied<F: WebsocketFormat> {
+    pub request_id: u32,
+    pub total_host_execution_duration_micros: u64,
+    pub query_id: QueryId,
+    pub rows: SubscribeRows<F>,
+}
+
+/// See [`super::InitialSubscription`].
+#[derive(SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct InitialSubscription<F: WebsocketFormat> {
+    pub database_update: DatabaseUpdate<F>,
+    pub request_id: u32,
+    pub total_host_execution_duration_micros: u64,
+}
+
+/// See [`super::TransactionUpdate`].
+#[derive(SpacetimeType, Debug)]
+#[sats(crate = spacetimedb_lib)]
+pub struct TransactionUpdate<F: WebsocketFormat> {
+    pub status: UpdateStatus<F>,
+    pub timestamp: Timestamp,
+    pub caller_identity: Identity,
+    pub caller_address: Address,
+    pub reducer_call: ReducerCallInfo<F>,
+    pub energy_quanta_used: EnergyQuanta,
+    pub host_execution_duration_micros: u64,
+}
+
+/// See [`super::TransactionUpdateLight`].
+#[derive(SpacetimeType, Debug)]
+#[sats(crate = spacetimedb_lib)]
+pub struct TransactionUpdateLight<F: WebsocketFormat> {
+    pub request_id: u32,
+    pub update: DatabaseUpdate<F>,
+}
+
+/// See [`super::UpdateStatus`].
+#[derive(SpacetimeType, Debug)]
+#[sats(crate = spacetimedb_lib)]
+pub enum UpdateStatus<F: WebsocketFormat> {
+    Committed(DatabaseUpdate<F>),
+    Failed(Box<str>),
+    OutOfEnergy,
+}
+
+/// See [`super::DatabaseUpdate`].
+#[derive(SpacetimeType, Debug)]
+#[sats(crate = spacetimedb_lib)]
+pub struct DatabaseUpdate<F: WebsocketFormat> {
+    pub tables: Vec<TableUpdate<F>>,
+}
+
+/// See [`super::TableUpdate`].
+#[derive(SpacetimeType, Debug)]
+#[sats(crate = spacetimedb_lib)]
+pub struct TableUpdate<F: WebsocketFormat> {
+    pub table_id: TableId,
+    pub table_name: Box<str>,
+    pub num_rows: u64,
+    pub updates: SmallVec<[F::QueryUpdate; 1]>,
+}
+
+impl<Args> From<ClientMessage<Args>> for super::ClientMessage<Args> {
+    fn from(msg: ClientMessage<Args>) -> Self {
+        match msg {
+            ClientMessage::CallReducer(msg) => Self::CallReducer(msg),
+            ClientMessage::Subscribe(Subscribe {
+                query_strings,
+                request_id,
+            }) => Self::Subscribe(super::Subscribe {
+                query_strings,
+                request_id,
+                min_tx_offset: None,
+                chunk_rows: None,
+            }),
+            ClientMessage::OneOffQuery(OneOffQuery {
+                message_id,
+                query_string,
+            }) => Self::OneOffQuery(super::OneOffQuery {
+                message_id,
+                query_string,
+                min_tx_offset: None,
+            }),
+            ClientMessage::SubscribeSingle(SubscribeSingle {
+                query,
+                request_id,
+                query_id,
+            }) => Self::SubscribeSingle(super::SubscribeSingle {
+                query,
+                request_id,
+                query_id,
+                min_tx_offset: None,
+            }),
+            ClientMessage::Unsubscribe(msg) => Self::Unsubscribe(msg),
+        }
+    }
+}
+
+impl<F: WebsocketFormat> TryFrom<super::ServerMessage<F>> for ServerMessage<F> {
+    type Error = super::ServerMessage<F>;
+
+    /// Converts `msg` to version 1 of the protocol,
+    /// or returns it back if it was added in a later version.
+    ///
+    /// Those messages are only ever sent in response to requests
+    /// which version 1 clients can't make.
+    fn try_from(msg: super::ServerMessage<F>) -> Result<Self, Self::Error> {
+        use super::ServerMessage as V2;
+        Ok(match msg {
+            V2::InitialSubscription(msg) => Self::InitialSubscription(msg.into()),
+            V2::TransactionUpdate(msg) => Self::TransactionUpdate(msg.into()),
+            V2::TransactionUpdateLight(msg) => Self::TransactionUpdateLight(msg.into()),
+            V2::IdentityToken(msg) => Self::IdentityToken(msg),
+            V2::OneOffQueryResponse(msg) => Self::OneOffQueryResponse(msg),
+            V2::SubscribeApplied(msg) => Self::SubscribeApplied(msg.into()),
+            V2::UnsubscribeApplied(msg) => Self::UnsubscribeApplied(msg.into()),
+            V2::SubscriptionError(msg) => Self::SubscriptionError(msg),
+            msg @ (V2::ViewUpdate(_) | V2::TopicMessage(_) | V2::InitialSubscriptionChunk(_)) => return Err(msg),
+        })
+    }
+}
+
+impl<F: WebsocketFormat> From<super::SubscribeRows<F>> for SubscribeRows<F> {
+    fn from(rows: super::SubscribeRows<F>) -> Self {
+        Self {
+            table_id: rows.table_id,
+            table_name: rows.table_name,
+            table_rows: rows.table_rows.into(),
+        }
+    }
+}
+
+impl<F: WebsocketFormat> From<super::SubscribeApplied<F>> for SubscribeApplied<F> {
+    fn from(msg: super::SubscribeApplied<F>) -> Self {
+        Self {
+            request_id: msg.request_id,
+            total_host_execution_duration_micros: msg.total_host_execution_duration_micros,
+            query_id: msg.query_id,
+            rows: msg.rows.into(),
+        }
+    }
+}
+
+impl<F: WebsocketFormat> From<super::UnsubscribeApplied<F>> for UnsubscribeApplied<F> {
+    fn from(msg: super::UnsubscribeApplied<F>) -> Self {
+        Self {
+            request_id: msg.request_id,
+            total_host_execution_duration_micros: msg.total_host_execution_duration_micros,
+            query_id: msg.query_id,
+            rows: msg.rows.into(),
+        }
+    }
+}
+
+impl<F: WebsocketFormat> From<super::InitialSubscription<F>> for InitialSubscription<F> {
+    fn from(msg: super::InitialSubscription<F>) -> Self {
+        Self {
+            database_update: msg.database_update.into(),
+            request_id: msg.request_id,
+            total_host_execution_duration_micros: msg.total_host_execution_duration_micros,
+        }
+    }
+}
+
+impl<F: WebsocketFormat> From<super::TransactionUpdate<F>> for TransactionUpdate<F> {
+    fn from(msg: super::TransactionUpdate<F>) -> Self {
+        Self {
+            status: msg.status.into(),
+            timestamp: msg.timestamp,
+            caller_identity: msg.caller_identity,
+            caller_address: msg.caller_address,
+            reducer_call: msg.reducer_call,
+            energy_quanta_used: msg.energy_quanta_used,
+            host_execution_duration_micros: msg.host_execution_duration_micros,
+        }
+    }
+}
+
+impl<F: WebsocketFormat> From<super::TransactionUpdateLight<F>> for TransactionUpdateLight<F> {
+    fn from(msg: super::TransactionUpdateLight<F>) -> Self {
+        Self {
+            request_id: msg.request_id,
+            update: msg.update.into(),
+        }
+    }
+}
+
+impl<F: WebsocketFormat> From<super::UpdateStatus<F>> for UpdateStatus<F> {
+    fn from(status: super::UpdateStatus<F>) -> Self {
+        match status {
+            super::UpdateStatus::Committed(update) => Self::Committed(update.into()),
+            super::UpdateStatus::Failed(message) => Self::Failed(message),
+            super::UpdateStatus::OutOfEnergy => Self::OutOfEnergy,
+            super::UpdateStatus::FailedWithValue(error) => Self::Failed(error.message),
+        }
+    }
+}
+
+impl<F: WebsocketFormat> From<super::DatabaseUpdate<F>> for DatabaseUpdate<F> {
+    fn from(update: super::DatabaseUpdate<F>) -> Self {
+        Self {
+            tables: update.tables.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl<F: WebsocketFormat> From<super::TableUpdate<F>> for TableUpdate<F> {
+    fn from(update: super::TableUpdate<F>) -> Self {
+        // Version 1 can't tell clients that a table was cleared,
+        // so the host sends version 1 clients every deleted row instead.
+        debug_assert!(!update.cleared, "table cleared in an update for a version 1 client");
+        Self {
+            table_id: update.table_id,
+            table_name: update.table_name,
+            num_rows: update.num_rows,
+            updates: update.updates,
+        }
+    }
+}
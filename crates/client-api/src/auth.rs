@@ -437,6 +437,42 @@ impl headers::Header for SpacetimeExecutionDurationMicros {
     }
 }
 
+/// The offset of the transaction committed by a reducer call,
+/// which clients can pass as `min_tx_offset` to later reads to observe its effects.
+pub struct SpacetimeTxOffset(pub u64);
+impl headers::Header for SpacetimeTxOffset {
+    fn name() -> &'static http::HeaderName {
+        static NAME: http::HeaderName = http::HeaderName::from_static("spacetime-tx-offset");
+        &NAME
+    }
+
+    fn decode<'i, I: Iterator<Item = &'i HeaderValue>>(_values: &mut I) -> Result<Self, headers::Error> {
+        unimplemented!()
+    }
+
+    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+        values.extend([self.0.into()])
+    }
+}
+
+/// Marks the response to a reducer call as the result of an earlier call with the same `Idempotency-Key`,
+/// rather than of running the reducer again.
+pub struct IdempotentReplayed;
+impl headers::Header for IdempotentReplayed {
+    fn name() -> &'static http::HeaderName {
+        static NAME: http::HeaderName = http::HeaderName::from_static("idempotent-replayed");
+        &NAME
+    }
+
+    fn decode<'i, I: Iterator<Item = &'i HeaderValue>>(_values: &mut I) -> Result<Self, headers::Error> {
+        unimplemented!()
+    }
+
+    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
+        values.extend([HeaderValue::from_static("true")])
+    }
+}
+
 pub async fn anon_auth_middleware<S: ControlStateDelegate + NodeDelegate>(
     State(worker_ctx): State<S>,
     auth: SpacetimeAuthHeader,
@@ -1,22 +1,31 @@
+use std::io;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use axum::response::ErrorResponse;
+use bytes::Bytes;
 use http::StatusCode;
 
 use spacetimedb::client::ClientActorIndex;
-use spacetimedb::energy::{EnergyBalance, EnergyQuanta};
+use spacetimedb::db::blob;
+use spacetimedb::db::import::{ImportError, ImportOptions, ImportSummary};
+use spacetimedb::db::index_advisor::IndexAdvice;
+use spacetimedb::db::relational_db::{CompressionStats, SnapshotPolicy, SnapshotStatus};
+use spacetimedb::energy::{EnergyBalance, EnergyLedgerEntry, EnergyQuanta, LedgerDay};
+use spacetimedb::error::DBError;
 use spacetimedb::execution_context::Workload;
-use spacetimedb::host::{HostController, ModuleHost, NoSuchModule, UpdateDatabaseResult};
+use spacetimedb::host::{HostController, ModuleHost, NoSuchModule, ReplicaStatus, UpdateDatabaseResult};
 use spacetimedb::identity::{AuthCtx, Identity};
-use spacetimedb::json::client_api::StmtResultJson;
-use spacetimedb::messages::control_db::{Database, HostType, Node, Replica};
+use spacetimedb::json::client_api::{StmtResultJson, StmtResultLine};
+use spacetimedb::messages::control_db::{Database, HostType, ModuleVersion, Node, Replica};
 use spacetimedb::sql;
 use spacetimedb::sql::execute::translate_col;
 use spacetimedb_client_api_messages::name::{DomainName, InsertDomainResult, RegisterTldResult, Tld};
+use spacetimedb_lib::db::auth::StAccess;
 use spacetimedb_lib::ProductTypeElement;
 use spacetimedb_paths::server::ModuleLogsDir;
-use tokio::sync::watch;
+use tokio::sync::{oneshot, watch};
 
 pub mod auth;
 pub mod routes;
@@ -39,7 +48,14 @@ pub trait NodeDelegate: Send + Sync {
     /// Returns `None` if the current leader is not hosted by this node.
     /// The [`Host`] is spawned implicitly if not already running.
     async fn leader(&self, database_id: u64) -> anyhow::Result<Option<Host>>;
+    /// Return the status of the leader replica of `database`.
+    ///
+    /// Returns `None` if the current leader is not hosted by this node.
+    /// Unlike [`Self::leader`], this does not launch the replica's module host.
+    async fn leader_status(&self, database: &Database) -> anyhow::Result<Option<ReplicaStatus>>;
     fn module_logs_dir(&self, replica_id: u64) -> ModuleLogsDir;
+    /// Whether the node has finished starting up and is ready to serve the databases it hosts.
+    fn is_ready(&self) -> bool;
 }
 
 /// Client view of a running module.
@@ -60,6 +76,65 @@ impl Host {
         self.host_controller.get_module_host(self.replica_id).await
     }
 
+    pub async fn advance_time(&self, by: Duration) -> Result<(), NoSuchModule> {
+        self.host_controller.advance_time(self.replica_id, by).await
+    }
+
+    pub async fn take_snapshot(&self) -> anyhow::Result<Option<u64>> {
+        self.host_controller.take_snapshot(self.replica_id).await
+    }
+
+    pub async fn latest_snapshot(&self) -> anyhow::Result<Option<u64>> {
+        self.host_controller.latest_snapshot(self.replica_id).await
+    }
+
+    pub async fn snapshot_status(&self) -> anyhow::Result<Option<SnapshotStatus>> {
+        self.host_controller.snapshot_status(self.replica_id).await
+    }
+
+    pub async fn set_snapshot_policy(&self, policy: SnapshotPolicy) -> anyhow::Result<()> {
+        self.host_controller.set_snapshot_policy(self.replica_id, policy).await
+    }
+
+    pub async fn compress_snapshots(
+        &self,
+        older_than: Duration,
+        progress: impl FnMut(u64, &CompressionStats) + Send + 'static,
+    ) -> anyhow::Result<Option<CompressionStats>> {
+        self.host_controller
+            .compress_snapshots(self.replica_id, older_than, progress)
+            .await
+    }
+
+    pub async fn export_table_parquet(
+        &self,
+        table_name: String,
+        out: impl io::Write + Send + 'static,
+    ) -> anyhow::Result<u64> {
+        self.host_controller
+            .export_table_parquet(self.replica_id, table_name, out)
+            .await
+    }
+
+    pub async fn import_table(
+        &self,
+        table_name: String,
+        data: Bytes,
+        options: ImportOptions,
+    ) -> anyhow::Result<Result<ImportSummary, ImportError>> {
+        let module = self.module().await?;
+        Ok(tokio::task::spawn_blocking(move || module.import_table(&table_name, data, &options)).await?)
+    }
+
+    pub async fn advise_indexes(&self) -> anyhow::Result<Vec<IndexAdvice>> {
+        let module = self.module().await?;
+        Ok(tokio::task::spawn_blocking(move || module.advise_indexes()).await??)
+    }
+
+    pub async fn restore_snapshot(&self, tx_offset: u64) -> anyhow::Result<()> {
+        self.host_controller.restore_snapshot(self.replica_id, tx_offset).await
+    }
+
     pub async fn module_watcher(&self) -> Result<watch::Receiver<ModuleHost>, NoSuchModule> {
         self.host_controller.watch_module_host(self.replica_id).await
     }
@@ -82,15 +157,8 @@ impl Host {
                 self.replica_id,
                 move |db| -> axum::response::Result<_, (StatusCode, String)> {
                     tracing::info!(sql = body);
-                    let results =
-                        sql::execute::run(db, &body, auth, Some(&module_host.info().subscriptions)).map_err(|e| {
-                            log::warn!("{}", e);
-                            if let Some(auth_err) = e.get_auth_error() {
-                                (StatusCode::UNAUTHORIZED, auth_err.to_string())
-                            } else {
-                                (StatusCode::BAD_REQUEST, e.to_string())
-                            }
-                        })?;
+                    let results = sql::execute::run(db, &body, auth, Some(&module_host.info().subscriptions))
+                        .map_err(sql_error)?;
 
                     let json = db.with_read_only(Workload::Sql, |tx| {
                         results
@@ -121,6 +189,98 @@ impl Host {
         Ok(json)
     }
 
+    /// Like [`Self::exec_sql`], but for read-only SQL,
+    /// writing the results to `out` as newline-delimited JSON [`StmtResultLine`]s a page at a time,
+    /// so that they are never buffered as a whole, and no read transaction waits on `out`.
+    ///
+    /// See [`sql::execute::run_streaming`].
+    pub async fn exec_sql_streaming(
+        &self,
+        auth: AuthCtx,
+        database: Database,
+        body: String,
+        max_rows: Option<u64>,
+        mut out: impl io::Write + Send + 'static,
+    ) -> Result<(), (StatusCode, String)> {
+        self.host_controller
+            .using_database(database, self.replica_id, move |db| {
+                tracing::info!(sql = body);
+                sql::execute::run_streaming(db, &body, auth, max_rows, |line| {
+                    serde_json::to_writer(&mut out, &line)?;
+                    out.write_all(b"\n")?;
+                    Ok(())
+                })
+                .map_err(sql_error)?;
+                out.flush()
+                    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+            })
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{e:#}")))?
+    }
+
+    /// Write the part of the blob `blob_id` in the blob table `table_name` selected by `range`,
+    /// or the whole blob if `range` is `None`, to `out`.
+    /// Which part that is gets sent to `head` before anything is written.
+    ///
+    /// The part is copied out of the database first,
+    /// so that a slow reader of `out` never holds the read transaction open.
+    ///
+    /// Private blob tables can only be read by the database owner.
+    /// Blobs in tables with row level security can only be read by callers who see every chunk of them.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn read_blob(
+        &self,
+        auth: AuthCtx,
+        database: Database,
+        table_name: String,
+        blob_id: u64,
+        range: Option<BlobRange>,
+        head: oneshot::Sender<BlobHead>,
+        mut out: impl io::Write + Send + 'static,
+    ) -> axum::response::Result<()> {
+        self.host_controller
+            .using_database(database, self.replica_id, move |db| -> axum::response::Result<()> {
+                let (blob_head, data) = db.with_read_only(Workload::Sql, |tx| -> axum::response::Result<_> {
+                    let not_found = || (StatusCode::NOT_FOUND, format!("no such blob table `{table_name}`"));
+                    let table_id = db
+                        .table_id_from_name(tx, &table_name)
+                        .map_err(log_and_500)?
+                        .ok_or_else(not_found)?;
+                    let schema = db.schema_for_table(tx, table_id).map_err(log_and_500)?;
+                    if schema.table_access != StAccess::Public && auth.caller != auth.owner {
+                        return Err(not_found().into());
+                    }
+                    blob::check_blob_table(&schema).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+                    if !blob::is_blob_visible(db, tx, auth, &schema, blob_id).map_err(log_and_500)? {
+                        return Err((StatusCode::NOT_FOUND, format!("no such blob {blob_id}")).into());
+                    }
+
+                    let total_len = blob::blob_len(db, tx, table_id, blob_id).map_err(log_and_500)?;
+                    let range = match range {
+                        None => 0..total_len,
+                        Some(range) => range.resolve(total_len).ok_or_else(|| {
+                            (
+                                StatusCode::RANGE_NOT_SATISFIABLE,
+                                [(http::header::CONTENT_RANGE, format!("bytes */{total_len}"))],
+                            )
+                        })?,
+                    };
+                    let len = usize::try_from(range.end - range.start).map_err(log_and_500)?;
+                    let data = blob::read_blob(db, tx, table_id, blob_id, range.start, len).map_err(log_and_500)?;
+                    Ok((BlobHead { total_len, range }, data))
+                })?;
+                // The client went away if nobody waits for the head.
+                if head.send(blob_head).is_err() {
+                    return Ok(());
+                }
+                out.write_all(&data).map_err(log_and_500)?;
+                out.flush().map_err(log_and_500)?;
+                Ok(())
+            })
+            .await
+            .map_err(log_and_500)?
+    }
+
     pub async fn update(
         &self,
         database: Database,
@@ -133,6 +293,38 @@ impl Host {
     }
 }
 
+/// A byte range of a blob, as requested by an HTTP `Range` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlobRange {
+    /// The bytes from the given offset to the end, as in `bytes=10-`.
+    From(u64),
+    /// The bytes between the given offsets, inclusive, as in `bytes=10-19`.
+    FromTo(u64, u64),
+    /// The given number of bytes at the end, as in `bytes=-10`.
+    Suffix(u64),
+}
+
+impl BlobRange {
+    /// Returns the offsets selected by this range in a blob of `len` bytes,
+    /// or `None` if the range is not satisfiable.
+    pub fn resolve(self, len: u64) -> Option<std::ops::Range<u64>> {
+        let range = match self {
+            Self::From(start) => start..len,
+            Self::FromTo(start, end) => start..end.saturating_add(1).min(len),
+            Self::Suffix(n) => len.saturating_sub(n)..len,
+        };
+        (range.start < range.end).then_some(range)
+    }
+}
+
+/// Which part of a blob [`Host::read_blob`] reads.
+pub struct BlobHead {
+    /// The length of the whole blob.
+    pub total_len: u64,
+    /// The offsets within the blob of the bytes read.
+    pub range: std::ops::Range<u64>,
+}
+
 /// Parameters for publishing a database.
 ///
 /// See [`ControlStateDelegate::publish_database`].
@@ -182,6 +374,8 @@ pub trait ControlStateReadAccess {
     fn get_database_by_id(&self, id: u64) -> anyhow::Result<Option<Database>>;
     fn get_database_by_identity(&self, database_identity: &Identity) -> anyhow::Result<Option<Database>>;
     fn get_databases(&self) -> anyhow::Result<Vec<Database>>;
+    /// The modules published to the database `database_id`, oldest first.
+    fn get_module_versions(&self, database_id: u64) -> anyhow::Result<Vec<ModuleVersion>>;
 
     // Replicas
     fn get_replica_by_id(&self, id: u64) -> anyhow::Result<Option<Replica>>;
@@ -190,6 +384,13 @@ pub trait ControlStateReadAccess {
 
     // Energy
     fn get_energy_balance(&self, identity: &Identity) -> anyhow::Result<Option<EnergyBalance>>;
+    /// The energy ledger entries billed to `identity` for the days from `from` up to and including `to`.
+    fn get_energy_ledger(
+        &self,
+        identity: &Identity,
+        from: Option<LedgerDay>,
+        to: Option<LedgerDay>,
+    ) -> anyhow::Result<Vec<EnergyLedgerEntry>>;
 
     // DNS
     fn lookup_identity(&self, domain: &DomainName) -> anyhow::Result<Option<Identity>>;
@@ -215,6 +416,17 @@ pub trait ControlStateWriteAccess: Send + Sync {
 
     async fn delete_database(&self, caller_identity: &Identity, database_identity: &Identity) -> anyhow::Result<()>;
 
+    /// Fork the database `source_identity` into a new database `target_identity`,
+    /// owned by `caller_identity`, starting from a copy of its latest state.
+    ///
+    /// Only the owner of the source database may fork it.
+    async fn fork_database(
+        &self,
+        caller_identity: &Identity,
+        source_identity: &Identity,
+        target_identity: Identity,
+    ) -> anyhow::Result<()>;
+
     // Energy
     async fn add_energy(&self, identity: &Identity, amount: EnergyQuanta) -> anyhow::Result<()>;
     async fn withdraw_energy(&self, identity: &Identity, amount: EnergyQuanta) -> anyhow::Result<()>;
@@ -251,6 +463,9 @@ impl<T: ControlStateReadAccess + ?Sized> ControlStateReadAccess for Arc<T> {
     fn get_databases(&self) -> anyhow::Result<Vec<Database>> {
         (**self).get_databases()
     }
+    fn get_module_versions(&self, database_id: u64) -> anyhow::Result<Vec<ModuleVersion>> {
+        (**self).get_module_versions(database_id)
+    }
 
     // Replicas
     fn get_replica_by_id(&self, id: u64) -> anyhow::Result<Option<Replica>> {
@@ -264,6 +479,14 @@ impl<T: ControlStateReadAccess + ?Sized> ControlStateReadAccess for Arc<T> {
     fn get_energy_balance(&self, identity: &Identity) -> anyhow::Result<Option<EnergyBalance>> {
         (**self).get_energy_balance(identity)
     }
+    fn get_energy_ledger(
+        &self,
+        identity: &Identity,
+        from: Option<LedgerDay>,
+        to: Option<LedgerDay>,
+    ) -> anyhow::Result<Vec<EnergyLedgerEntry>> {
+        (**self).get_energy_ledger(identity, from, to)
+    }
 
     // DNS
     fn lookup_identity(&self, domain: &DomainName) -> anyhow::Result<Option<Identity>> {
@@ -293,6 +516,17 @@ impl<T: ControlStateWriteAccess + ?Sized> ControlStateWriteAccess for Arc<T> {
         (**self).delete_database(caller_identity, database_identity).await
     }
 
+    async fn fork_database(
+        &self,
+        caller_identity: &Identity,
+        source_identity: &Identity,
+        target_identity: Identity,
+    ) -> anyhow::Result<()> {
+        (**self)
+            .fork_database(caller_identity, source_identity, target_identity)
+            .await
+    }
+
     async fn add_energy(&self, identity: &Identity, amount: EnergyQuanta) -> anyhow::Result<()> {
         (**self).add_energy(identity, amount).await
     }
@@ -333,9 +567,27 @@ impl<T: NodeDelegate + ?Sized> NodeDelegate for Arc<T> {
         (**self).leader(database_id).await
     }
 
+    async fn leader_status(&self, database: &Database) -> anyhow::Result<Option<ReplicaStatus>> {
+        (**self).leader_status(database).await
+    }
+
     fn module_logs_dir(&self, replica_id: u64) -> ModuleLogsDir {
         (**self).module_logs_dir(replica_id)
     }
+
+    fn is_ready(&self) -> bool {
+        (**self).is_ready()
+    }
+}
+
+/// Map an error executing SQL to the status code to respond with.
+fn sql_error(e: DBError) -> (StatusCode, String) {
+    log::warn!("{}", e);
+    if let Some(auth_err) = e.get_auth_error() {
+        (StatusCode::UNAUTHORIZED, auth_err.to_string())
+    } else {
+        (StatusCode::BAD_REQUEST, e.to_string())
+    }
 }
 
 pub fn log_and_500(e: impl std::fmt::Display) -> ErrorResponse {
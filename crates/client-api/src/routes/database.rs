@@ -1,10 +1,10 @@
 use crate::auth::{
-    anon_auth_middleware, SpacetimeAuth, SpacetimeAuthHeader, SpacetimeEnergyUsed, SpacetimeExecutionDurationMicros,
-    SpacetimeIdentity, SpacetimeIdentityToken,
+    anon_auth_middleware, IdempotentReplayed, SpacetimeAuth, SpacetimeAuthHeader, SpacetimeEnergyUsed,
+    SpacetimeExecutionDurationMicros, SpacetimeIdentity, SpacetimeIdentityToken, SpacetimeTxOffset,
 };
 use crate::routes::subscribe::generate_random_address;
 use crate::util::{ByteStringBody, NameOrIdentity};
-use crate::{log_and_500, ControlStateDelegate, DatabaseDef, NodeDelegate};
+use crate::{log_and_500, BlobRange, ControlStateDelegate, DatabaseDef, Host, NodeDelegate};
 use axum::body::{Body, Bytes};
 use axum::extract::{DefaultBodyLimit, Path, Query, State};
 use axum::response::{ErrorResponse, IntoResponse};
@@ -15,23 +15,38 @@ use http::StatusCode;
 use serde::{Deserialize, Serialize};
 use spacetimedb::address::Address;
 use spacetimedb::database_logger::DatabaseLogger;
-use spacetimedb::host::ReducerCallError;
+use spacetimedb::db::db_metrics::{DatabaseStats, ReducerStats, TableStats};
+use spacetimedb::db::export::ExportError;
+use spacetimedb::db::import::{self, ImportError, ImportOptions};
+use spacetimedb::db::relational_db::{CompressionStats, SnapshotPolicy, SnapshotStatus};
+use spacetimedb::host::wasmtime::{ProgramUpload, ProgramUploadError};
 use spacetimedb::host::ReducerOutcome;
-use spacetimedb::host::{DescribedEntityType, UpdateDatabaseResult};
+use spacetimedb::host::{CallFingerprint, HttpRouteCallError, IdempotencyKey, ReducerCallError, ViewCallError};
+use spacetimedb::host::{DescribedEntityType, ModuleStatus, ReplicaStatus, UpdateDatabaseResult};
 use spacetimedb::host::{ModuleHost, ReducerArgs};
 use spacetimedb::identity::Identity;
 use spacetimedb::messages::control_db::{Database, HostType};
-use spacetimedb_client_api_messages::name::{self, DnsLookupResponse, DomainName, PublishOp, PublishResult};
+use spacetimedb::replica_context::QuotaExceeded;
+use spacetimedb_client_api_messages::name::{
+    self, CloneResult, DnsLookupResponse, DomainName, ImportResult, IndexSuggestion, PublishOp, PublishResult,
+    SnapshotCompressionProgress, SnapshotCompressionStats,
+};
+use spacetimedb_client_api_messages::timestamp::Timestamp;
 use spacetimedb_data_structures::map::HashMap;
 use spacetimedb_lib::address::AddressForUrl;
-use spacetimedb_lib::db::raw_def::v9::RawModuleDefV9;
+use spacetimedb_lib::db::raw_def::v9::{HttpMethod, RawModuleDefV9};
 use spacetimedb_lib::identity::AuthCtx;
 use spacetimedb_lib::sats::{self, WithTypespace};
 use spacetimedb_lib::{ProductType, ProductTypeElement};
 use spacetimedb_schema::def::{ReducerDef, TableDef};
+use std::io;
+use std::time::Duration;
 
 use super::identity::IdentityForUrl;
 
+/// The request header identifying retries of the same reducer call.
+const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
 pub(crate) struct DomainParsingRejection;
 
 impl IntoResponse for DomainParsingRejection {
@@ -59,11 +74,18 @@ pub async fn call<S: ControlStateDelegate + NodeDelegate>(
         reducer,
     }): Path<CallParams>,
     Query(CallQueryParams { client_address }): Query<CallQueryParams>,
+    headers: http::HeaderMap,
     ByteStringBody(body): ByteStringBody,
 ) -> axum::response::Result<impl IntoResponse> {
     let caller_identity = auth.identity;
 
     let args = ReducerArgs::Json(body);
+    // Retries of a call with the same key get the result of the first call rather than running the reducer again.
+    let idempotency_key = headers
+        .get(IDEMPOTENCY_KEY)
+        .map(|key| key.to_str().map(|key| IdempotencyKey::Header(key.into())))
+        .transpose()
+        .map_err(|_| (StatusCode::BAD_REQUEST, "Invalid `Idempotency-Key` header."))?;
 
     let address = name_or_address.resolve(&worker_ctx).await?.into();
     let database = worker_ctx_find_database(&worker_ctx, &address).await?.ok_or_else(|| {
@@ -86,15 +108,36 @@ pub async fn call<S: ControlStateDelegate + NodeDelegate>(
         .unwrap_or_else(generate_random_address);
 
     if let Err(e) = module
-        .call_identity_connected_disconnected(caller_identity, client_address, true)
+        .call_identity_connected_disconnected(caller_identity, client_address, true, None, None)
         .await
     {
         return Err((StatusCode::NOT_FOUND, format!("{:#}", anyhow::anyhow!(e))).into());
     }
-    let result = match module
-        .call_reducer(caller_identity, Some(client_address), None, None, None, &reducer, args)
-        .await
-    {
+    let fingerprint = idempotency_key.as_ref().map(|_| CallFingerprint::new(&reducer, &args));
+    // The call owns what it needs, so that it runs to completion when its result is kept for retries.
+    let call = {
+        let (module, reducer) = (module.clone(), reducer.clone());
+        async move {
+            module
+                .call_reducer(
+                    caller_identity,
+                    Some(client_address),
+                    None,
+                    None,
+                    None,
+                    None,
+                    None,
+                    &reducer,
+                    args,
+                )
+                .await
+        }
+    };
+    let result = match idempotency_key.zip(fingerprint) {
+        Some((key, fingerprint)) => module.call_reducer_once(caller_identity, key, fingerprint, call).await,
+        None => call.await.map(|rcr| (rcr, false)),
+    };
+    let result = match result {
         Ok(rcr) => Ok(rcr),
         Err(e) => {
             let status_code = match e {
@@ -111,6 +154,17 @@ pub async fn call<S: ControlStateDelegate + NodeDelegate>(
                     log::debug!("Attempt to call {lifecycle:?} lifeycle reducer {}", reducer);
                     StatusCode::BAD_REQUEST
                 }
+                ReducerCallError::TopicAuthorizer => {
+                    log::debug!("Attempt to call topic authorizer {}", reducer);
+                    StatusCode::BAD_REQUEST
+                }
+                ReducerCallError::QuotaExceeded(
+                    QuotaExceeded::Connections(_) | QuotaExceeded::ReducerQueue(_) | QuotaExceeded::Cpu(_),
+                ) => StatusCode::TOO_MANY_REQUESTS,
+                ReducerCallError::QuotaExceeded(QuotaExceeded::Memory(_) | QuotaExceeded::Disk(_)) => {
+                    StatusCode::INSUFFICIENT_STORAGE
+                }
+                ReducerCallError::IdempotencyKeyReused => StatusCode::UNPROCESSABLE_ENTITY,
             };
 
             log::debug!("Error while invoking reducer {:#}", e);
@@ -119,19 +173,21 @@ pub async fn call<S: ControlStateDelegate + NodeDelegate>(
     };
 
     if let Err(e) = module
-        .call_identity_connected_disconnected(caller_identity, client_address, false)
+        .call_identity_connected_disconnected(caller_identity, client_address, false, None, None)
         .await
     {
         return Err((StatusCode::NOT_FOUND, format!("{:#}", anyhow::anyhow!(e))).into());
     }
 
     match result {
-        Ok(result) => {
+        Ok((result, replayed)) => {
             let (status, body) = reducer_outcome_response(&identity, &reducer, result.outcome);
             Ok((
                 status,
                 TypedHeader(SpacetimeEnergyUsed(result.energy_used)),
                 TypedHeader(SpacetimeExecutionDurationMicros(result.execution_duration)),
+                result.tx_offset.map(|offset| TypedHeader(SpacetimeTxOffset(offset))),
+                replayed.then_some(TypedHeader(IdempotentReplayed)),
                 body,
             ))
         }
@@ -157,6 +213,10 @@ fn reducer_outcome_response(identity: &Identity, reducer: &str, outcome: Reducer
                 "Module energy budget exhausted.".to_owned(),
             )
         }
+        ReducerOutcome::TimedOut => (
+            StatusCode::GATEWAY_TIMEOUT,
+            format!("Reducer `{reducer}` exceeded its maximum duration."),
+        ),
     }
 }
 
@@ -194,6 +254,29 @@ struct EntityDescription<'a> {
     r#type: DescribedEntityType,
     arity: usize,
     schema: EntityDescriptionSchema<'a>,
+    /// The other names under which a reducer may be called.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<EntityDescriptionAlias<'a>>,
+    /// Live usage statistics, accumulated since the host started.
+    stats: EntityStats,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum EntityStats {
+    Reducer {
+        #[serde(flatten)]
+        stats: ReducerStats,
+        avg_rows_touched: f64,
+    },
+    Table(TableStats),
+}
+
+#[derive(Serialize)]
+struct EntityDescriptionAlias<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecated: Option<&'a str>,
 }
 
 #[derive(Serialize)]
@@ -203,7 +286,10 @@ struct EntityDescriptionSchema<'a> {
     elements: Box<[ProductTypeElement]>,
 }
 
-fn entity_description_json<'a>(description: WithTypespace<'_, EntityDef<'a>>) -> Option<EntityDescription<'a>> {
+fn entity_description_json<'a>(
+    description: WithTypespace<'_, EntityDef<'a>>,
+    stats: &DatabaseStats,
+) -> Option<EntityDescription<'a>> {
     let typ = description.ty().described_entity_ty();
     let len = match description.ty() {
         EntityDef::Table(t) => description
@@ -231,10 +317,33 @@ fn entity_description_json<'a>(description: WithTypespace<'_, EntityDef<'a>>) ->
             elements: r.params.elements.clone(),
         },
     };
+    let aliases = match description.ty() {
+        EntityDef::Table(_) => Vec::new(),
+        EntityDef::Reducer(r) => r
+            .aliases
+            .iter()
+            .map(|alias| EntityDescriptionAlias {
+                name: &alias.name,
+                deprecated: alias.deprecated.as_deref(),
+            })
+            .collect(),
+    };
+    let stats = match description.ty() {
+        EntityDef::Table(t) => EntityStats::Table(stats.tables.get(&*t.name).copied().unwrap_or_default()),
+        EntityDef::Reducer(r) => {
+            let stats = stats.reducers.get(&*r.name).copied().unwrap_or_default();
+            EntityStats::Reducer {
+                stats,
+                avg_rows_touched: stats.avg_rows_touched(),
+            }
+        }
+    };
     Some(EntityDescription {
         r#type: typ,
         arity: len,
         schema,
+        aliases,
+        stats,
     })
 }
 
@@ -279,8 +388,9 @@ where
     let description = get_entity(&module, &entity, entity_type)
         .ok_or_else(|| (StatusCode::NOT_FOUND, format!("{entity_type} {entity:?} not found")))?;
     let description = WithTypespace::new(module.info().module_def.typespace(), &description);
+    let stats = DatabaseStats::collect(database.database_identity);
 
-    let response_json: SchemaEntities = HashMap::from_iter([(&*entity, entity_description_json(description))]);
+    let response_json: SchemaEntities = HashMap::from_iter([(&*entity, entity_description_json(description, &stats))]);
 
     Ok((
         TypedHeader(SpacetimeIdentity(auth.identity)),
@@ -300,7 +410,11 @@ fn get_catalog(host: &ModuleHost) -> impl Iterator<Item = EntityDef> {
 fn get_entity<'a>(host: &'a ModuleHost, entity: &'_ str, entity_type: DescribedEntityType) -> Option<EntityDef<'a>> {
     match entity_type {
         DescribedEntityType::Table => host.info().module_def.table(entity).map(EntityDef::Table),
-        DescribedEntityType::Reducer => host.info().module_def.reducer(entity).map(EntityDef::Reducer),
+        DescribedEntityType::Reducer => host
+            .info()
+            .module_def
+            .reducer_or_alias_full(entity)
+            .map(|(_, def, _)| EntityDef::Reducer(def)),
     }
 }
 
@@ -349,8 +463,14 @@ where
         axum::Json(sats::serde::SerdeWrapper(raw)).into_response()
     } else {
         let typespace = module.info.module_def.typespace();
+        let stats = DatabaseStats::collect(database.database_identity);
         let entities: HashMap<_, _> = get_catalog(&module)
-            .map(|entity| (entity.name(), entity_description_json(typespace.with_type(&entity))))
+            .map(|entity| {
+                (
+                    entity.name(),
+                    entity_description_json(typespace.with_type(&entity), &stats),
+                )
+            })
             .collect();
         axum::Json(CatalogResponse { entities, typespace }).into_response()
     };
@@ -404,6 +524,138 @@ pub async fn info<S: ControlStateDelegate>(
     Ok(axum::Json(response))
 }
 
+#[derive(Deserialize)]
+pub struct StatusParams {
+    name_or_identity: NameOrIdentity,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+enum ModuleStatusResponse {
+    NotLaunched,
+    Launching {
+        replayed_tx_offset: u64,
+        /// The offset replay ends at, if known.
+        replay_max_tx_offset: Option<u64>,
+    },
+    Running,
+    Updating,
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Serialize)]
+pub struct StatusResponse {
+    module: ModuleStatusResponse,
+    committed_tx_offset: Option<u64>,
+    durable_tx_offset: Option<u64>,
+    /// The number of committed transactions not yet persisted to the commitlog, if both offsets are known.
+    commitlog_lag_txs: Option<u64>,
+    snapshots: Option<SnapshotPolicyResponse>,
+}
+
+/// Reports whether a database is running, how far its recovery has progressed if it is being launched,
+/// and how far its commitlog and snapshots are behind if it is running.
+///
+/// Unlike most other routes, this does not launch the database, so it responds right away during recovery.
+pub async fn status<S: ControlStateDelegate + NodeDelegate>(
+    State(worker_ctx): State<S>,
+    Path(StatusParams { name_or_identity }): Path<StatusParams>,
+) -> axum::response::Result<impl IntoResponse> {
+    let database_identity = name_or_identity.resolve(&worker_ctx).await?.into();
+    let database = worker_ctx_find_database(&worker_ctx, &database_identity)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+    let status = worker_ctx
+        .leader_status(&database)
+        .await
+        .map_err(log_and_500)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(axum::Json(StatusResponse::from(status)))
+}
+
+impl From<ReplicaStatus> for StatusResponse {
+    fn from(status: ReplicaStatus) -> Self {
+        let module = match status.module {
+            ModuleStatus::NotLaunched => ModuleStatusResponse::NotLaunched,
+            ModuleStatus::Launching { replay } => ModuleStatusResponse::Launching {
+                replayed_tx_offset: replay.tx_offset,
+                replay_max_tx_offset: replay.max_tx_offset,
+            },
+            ModuleStatus::Running => ModuleStatusResponse::Running,
+            ModuleStatus::Updating => ModuleStatusResponse::Updating,
+            ModuleStatus::Failed { error } => ModuleStatusResponse::Failed { error },
+        };
+        let (committed_tx_offset, durable_tx_offset, snapshots) = match status.database {
+            Some(db) => (db.committed_tx_offset, db.durable_tx_offset, db.snapshots),
+            None => (None, None, None),
+        };
+        let commitlog_lag_txs = committed_tx_offset
+            .zip(durable_tx_offset)
+            .map(|(committed, durable)| committed.saturating_sub(durable));
+        Self {
+            module,
+            committed_tx_offset,
+            durable_tx_offset,
+            commitlog_lag_txs,
+            snapshots: snapshots.map(SnapshotPolicyResponse::from),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct VersionsParams {
+    name_or_identity: NameOrIdentity,
+}
+
+#[derive(Deserialize)]
+pub struct VersionsQueryParams {
+    #[serde(default)]
+    module_def: bool,
+}
+
+#[derive(Serialize)]
+struct VersionResponse {
+    version: u64,
+    program_hash: spacetimedb_lib::Hash,
+    host_type: &'static str,
+    published_at: Timestamp,
+    publisher: Identity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    module_def: Option<sats::serde::SerdeWrapper<RawModuleDefV9>>,
+}
+
+/// Lists the modules published to a database, oldest first.
+pub async fn versions<S: ControlStateDelegate>(
+    State(worker_ctx): State<S>,
+    Path(VersionsParams { name_or_identity }): Path<VersionsParams>,
+    Query(VersionsQueryParams { module_def }): Query<VersionsQueryParams>,
+) -> axum::response::Result<impl IntoResponse> {
+    let database_identity = name_or_identity.resolve(&worker_ctx).await?.into();
+    let database = worker_ctx_find_database(&worker_ctx, &database_identity)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+
+    let response: Vec<_> = worker_ctx
+        .get_module_versions(database.id)
+        .map_err(log_and_500)?
+        .into_iter()
+        .map(|version| VersionResponse {
+            version: version.version,
+            program_hash: version.program_hash,
+            host_type: match version.host_type {
+                HostType::Wasm => "wasm",
+            },
+            published_at: version.published_at,
+            publisher: version.publisher,
+            module_def: module_def.then_some(sats::serde::SerdeWrapper(version.module_def)),
+        })
+        .collect();
+    Ok(axum::Json(response))
+}
+
 #[derive(Deserialize)]
 pub struct LogsParams {
     name_or_identity: NameOrIdentity,
@@ -515,12 +767,40 @@ pub struct SqlParams {
 }
 
 #[derive(Deserialize)]
-pub struct SqlQueryParams {}
+pub struct SqlQueryParams {
+    /// Wait until the transaction at this offset has been committed before executing the query,
+    /// as returned by a reducer call in the `Spacetime-Tx-Offset` header.
+    min_tx_offset: Option<u64>,
+    /// Respond with the results as they are read, as newline-delimited JSON,
+    /// rather than buffering them. Only read-only SQL can be streamed.
+    #[serde(default)]
+    stream: bool,
+    /// When streaming, stop after this many rows.
+    max_rows: Option<u64>,
+}
+
+/// Reads the body of a `/sql` request,
+/// which is either SQL text or a JSON array of statements.
+///
+/// All the statements of a request run in a single transaction,
+/// so an array is equivalent to its statements separated by `;`.
+fn sql_batch(body: String) -> axum::response::Result<String> {
+    if !body.trim_start().starts_with('[') {
+        return Ok(body);
+    }
+    let statements: Vec<String> = serde_json::from_str(&body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid array of SQL statements: {e}")))?;
+    Ok(statements.join(";\n"))
+}
 
 pub async fn sql<S>(
     State(worker_ctx): State<S>,
     Path(SqlParams { name_or_identity }): Path<SqlParams>,
-    Query(SqlQueryParams {}): Query<SqlQueryParams>,
+    Query(SqlQueryParams {
+        min_tx_offset,
+        stream,
+        max_rows,
+    }): Query<SqlQueryParams>,
     Extension(auth): Extension<SpacetimeAuth>,
     body: String,
 ) -> axum::response::Result<impl IntoResponse>
@@ -543,9 +823,612 @@ where
         .await
         .map_err(log_and_500)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(tx_offset) = min_tx_offset {
+        let module = host.module().await.map_err(log_and_500)?;
+        module
+            .wait_for_tx_offset(tx_offset)
+            .await
+            .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))?;
+    }
+    let body = sql_batch(body)?;
+    if stream {
+        return sql_streaming(host, auth, database, body, max_rows).await;
+    }
     let json = host.exec_sql(auth, database, body).await?;
 
-    Ok(axum::Json(json))
+    Ok(axum::Json(json).into_response())
+}
+
+/// Respond with the results of the read-only `sql` as they are read,
+/// as newline-delimited [`StmtResultLine`](spacetimedb::json::client_api::StmtResultLine)s.
+async fn sql_streaming(
+    host: Host,
+    auth: AuthCtx,
+    database: Database,
+    sql: String,
+    max_rows: Option<u64>,
+) -> axum::response::Result<axum::response::Response> {
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let out = io::BufWriter::with_capacity(EXPORT_CHUNK_SIZE, ChannelWriter(tx));
+    let query = tokio::spawn(async move { host.exec_sql_streaming(auth, database, sql, max_rows, out).await });
+
+    let respond = |body| {
+        (
+            TypedHeader(headers::CacheControl::new().with_no_cache()),
+            TypedHeader(headers::ContentType::from(mime_ndjson())),
+            body,
+        )
+            .into_response()
+    };
+
+    // If the query fails before writing anything, e.g. because it doesn't compile,
+    // we can still respond with an error status.
+    let Some(first) = rx.recv().await else {
+        query.await.map_err(log_and_500)??;
+        return Ok(respond(Body::empty()));
+    };
+
+    // Otherwise, fail the body if the query fails midway,
+    // so that the client can't mistake truncated results for complete ones.
+    let outcome = futures::stream::once(async move {
+        match query.await {
+            Ok(Ok(())) => None,
+            Ok(Err((_, e))) => Some(Err(io::Error::other(e))),
+            Err(e) => Some(Err(io::Error::other(e))),
+        }
+    })
+    .filter_map(std::future::ready);
+    let body = futures::stream::once(std::future::ready(Ok(first)))
+        .chain(tokio_stream::wrappers::ReceiverStream::new(rx).map(Ok))
+        .chain(outcome);
+
+    Ok(respond(Body::from_stream(body)))
+}
+
+#[derive(Deserialize)]
+pub struct BlobParams {
+    name_or_identity: NameOrIdentity,
+    table: String,
+    blob_id: u64,
+}
+
+/// Parse the value of an HTTP `Range` header which selects a single byte range.
+///
+/// Returns `None` for anything else, including multiple ranges,
+/// in which case the header is ignored and the whole blob is served, as RFC 9110 allows.
+fn parse_blob_range(header: &str) -> Option<BlobRange> {
+    let (start, end) = header.strip_prefix("bytes=")?.trim().split_once('-')?;
+    match (start, end) {
+        ("", suffix) => Some(BlobRange::Suffix(suffix.parse().ok()?)),
+        (start, "") => Some(BlobRange::From(start.parse().ok()?)),
+        (start, end) => {
+            let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+            (start <= end).then_some(BlobRange::FromTo(start, end))
+        }
+    }
+}
+
+/// Download a blob stored in a blob table, supporting single-range `Range` requests.
+pub async fn blob<S>(
+    State(worker_ctx): State<S>,
+    Path(BlobParams {
+        name_or_identity,
+        table,
+        blob_id,
+    }): Path<BlobParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+    headers: http::HeaderMap,
+) -> axum::response::Result<impl IntoResponse>
+where
+    S: NodeDelegate + ControlStateDelegate,
+{
+    let range = headers
+        .get(http::header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_blob_range);
+
+    let address = name_or_identity.resolve(&worker_ctx).await?.into();
+    let database = worker_ctx_find_database(&worker_ctx, &address)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+
+    let auth = AuthCtx::new(database.owner_identity, auth.identity);
+
+    let host = worker_ctx
+        .leader(database.id)
+        .await
+        .map_err(log_and_500)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let (head_tx, head_rx) = tokio::sync::oneshot::channel();
+    let (tx, rx) = tokio::sync::mpsc::channel(16);
+    let out = io::BufWriter::with_capacity(EXPORT_CHUNK_SIZE, ChannelWriter(tx));
+    let read = tokio::spawn(async move {
+        host.read_blob(auth, database, table, blob_id, range, head_tx, out)
+            .await
+    });
+
+    // If the read fails before it starts, e.g. because there is no such blob table,
+    // we can still respond with an error status.
+    let Ok(head) = head_rx.await else {
+        return Err(match read.await.map_err(log_and_500)? {
+            Err(e) => e,
+            Ok(()) => log_and_500("blob read ended before it started"),
+        });
+    };
+
+    // Otherwise, fail the body if the read fails midway,
+    // so that the client can't mistake a truncated blob for a complete one.
+    let outcome = futures::stream::once(async move {
+        match read.await {
+            Ok(Ok(())) => None,
+            Ok(Err(_)) => Some(Err(io::Error::other("failed to read the blob"))),
+            Err(e) => Some(Err(io::Error::other(e))),
+        }
+    })
+    .filter_map(std::future::ready);
+    let body = tokio_stream::wrappers::ReceiverStream::new(rx).map(Ok).chain(outcome);
+
+    let mut response = Body::from_stream(body).into_response();
+    let response_headers = response.headers_mut();
+    response_headers.insert(http::header::ACCEPT_RANGES, http::HeaderValue::from_static("bytes"));
+    response_headers.insert(http::header::CONTENT_LENGTH, (head.range.end - head.range.start).into());
+    if range.is_some() {
+        let content_range = format!("bytes {}-{}/{}", head.range.start, head.range.end - 1, head.total_len);
+        response_headers.insert(http::header::CONTENT_RANGE, content_range.parse().unwrap());
+        *response.status_mut() = StatusCode::PARTIAL_CONTENT;
+    }
+    Ok(response)
+}
+
+#[derive(Deserialize)]
+pub struct AssetParams {
+    name_or_identity: NameOrIdentity,
+    path: String,
+}
+
+/// Download a file from the asset bundle published with a database's module.
+///
+/// Assets are public.
+/// Responses carry the hash of the file as their `ETag`, and ask caches to revalidate before reusing them,
+/// so a request with a matching `If-None-Match` gets `304 Not Modified`
+/// until the module is published with a different version of the file.
+pub async fn asset<S>(
+    State(worker_ctx): State<S>,
+    Path(AssetParams { name_or_identity, path }): Path<AssetParams>,
+    headers: http::HeaderMap,
+) -> axum::response::Result<impl IntoResponse>
+where
+    S: NodeDelegate + ControlStateDelegate,
+{
+    let address = name_or_identity.resolve(&worker_ctx).await?.into();
+    let database = worker_ctx_find_database(&worker_ctx, &address)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+
+    let host = worker_ctx
+        .leader(database.id)
+        .await
+        .map_err(log_and_500)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let module = host.module().await.map_err(log_and_500)?;
+    let asset = module
+        .info()
+        .assets
+        .get(&path)
+        .ok_or((StatusCode::NOT_FOUND, "No such asset."))?;
+
+    let etag = format!("\"{}\"", asset.hash.to_hex());
+    let not_modified = headers
+        .get(http::header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|tags| {
+            tags.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == "*" || tag == etag)
+        });
+    let caching = [
+        (http::header::ETAG, etag),
+        (http::header::CACHE_CONTROL, "public, no-cache".to_owned()),
+    ];
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, caching).into_response());
+    }
+    let content_type = [(http::header::CONTENT_TYPE, asset_content_type(&path))];
+    Ok((caching, content_type, asset.data.clone()).into_response())
+}
+
+/// Guess the media type of an asset from the extension of its `path`.
+fn asset_content_type(path: &str) -> &'static str {
+    let extension = path.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("json") => "application/json",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("wasm") => "application/wasm",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    name_or_identity: NameOrIdentity,
+}
+
+#[derive(Deserialize)]
+pub struct ExportQueryParams {
+    table: String,
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Parquet,
+}
+
+/// The size of the chunks in which an export is streamed to the client.
+const EXPORT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// An [`io::Write`] which sends the bytes written to it through a channel,
+/// so that a response body can be written from a blocking task.
+struct ChannelWriter(tokio::sync::mpsc::Sender<Bytes>);
+
+impl io::Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Bytes::copy_from_slice(buf))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client went away"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn export_error(e: anyhow::Error) -> ErrorResponse {
+    match e.downcast_ref::<ExportError>() {
+        Some(ExportError::NoSuchTable(_)) => (StatusCode::NOT_FOUND, e.to_string()).into(),
+        Some(ExportError::NoSnapshot) => (StatusCode::BAD_REQUEST, e.to_string()).into(),
+        _ => log_and_500(e),
+    }
+}
+
+/// Export all rows of a table as a Parquet file.
+///
+/// The rows are read from the latest snapshot of the database,
+/// rather than the live database, and streamed to the client as they are read.
+pub async fn export<S>(
+    State(worker_ctx): State<S>,
+    Path(ExportParams { name_or_identity }): Path<ExportParams>,
+    Query(ExportQueryParams { table, format }): Query<ExportQueryParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<impl IntoResponse>
+where
+    S: NodeDelegate + ControlStateDelegate,
+{
+    let database_identity: Identity = name_or_identity.resolve(&worker_ctx).await?.into();
+    let database = worker_ctx_find_database(&worker_ctx, &database_identity)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+
+    // Exports include private tables, so only the owner may export.
+    if database.owner_identity != auth.identity {
+        return Err((StatusCode::UNAUTHORIZED, "Identity does not own database.").into());
+    }
+
+    let host = worker_ctx
+        .leader(database.id)
+        .await
+        .map_err(log_and_500)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Parquet is the only format so far.
+    let ExportFormat::Parquet = format;
+    let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+    let out = io::BufWriter::with_capacity(EXPORT_CHUNK_SIZE, ChannelWriter(tx));
+    let export = tokio::spawn(async move { host.export_table_parquet(table, out).await });
+
+    // If the export fails before writing anything, e.g. because the table doesn't exist,
+    // we can still respond with an error status.
+    let Some(first) = rx.recv().await else {
+        let err = match export.await.map_err(log_and_500)? {
+            Err(e) => e,
+            Ok(_) => anyhow::anyhow!("export wrote no data"),
+        };
+        return Err(export_error(err));
+    };
+
+    // Otherwise, fail the body if the export fails midway,
+    // so that the client can't mistake a truncated file for a complete one.
+    let outcome = futures::stream::once(async move {
+        match export.await {
+            Ok(Ok(_)) => None,
+            Ok(Err(e)) => Some(Err(io::Error::other(e))),
+            Err(e) => Some(Err(io::Error::other(e))),
+        }
+    })
+    .filter_map(std::future::ready);
+    let body = futures::stream::once(std::future::ready(Ok(first)))
+        .chain(tokio_stream::wrappers::ReceiverStream::new(rx).map(Ok))
+        .chain(outcome);
+
+    Ok((
+        TypedHeader(headers::ContentType::from(mime_parquet())),
+        Body::from_stream(body),
+    ))
+}
+
+fn mime_parquet() -> mime::Mime {
+    "application/vnd.apache.parquet".parse().unwrap()
+}
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    name_or_identity: NameOrIdentity,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQueryParams {
+    table: String,
+    #[serde(default)]
+    format: ImportFormat,
+    /// Renames of columns of the file,
+    /// as a comma-separated list of `file_column=table_column`.
+    #[serde(default)]
+    map: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    #[default]
+    Parquet,
+    Csv,
+}
+
+impl From<ImportFormat> for import::ImportFormat {
+    fn from(format: ImportFormat) -> Self {
+        match format {
+            ImportFormat::Parquet => Self::Parquet,
+            ImportFormat::Csv => Self::Csv,
+        }
+    }
+}
+
+fn parse_renames(map: &str) -> Result<Vec<(String, String)>, ErrorResponse> {
+    map.split(',')
+        .map(|rename| match rename.split_once('=') {
+            Some((from, to)) => Ok((from.trim().to_owned(), to.trim().to_owned())),
+            None => Err((
+                StatusCode::BAD_REQUEST,
+                format!("invalid column mapping `{rename}`, expected `file_column=table_column`"),
+            )
+                .into()),
+        })
+        .collect()
+}
+
+fn import_error(e: ImportError) -> ErrorResponse {
+    match e {
+        ImportError::NoSuchTable(_) => (StatusCode::NOT_FOUND, e.to_string()).into(),
+        ImportError::DB(_) => log_and_500(e),
+        _ => (StatusCode::BAD_REQUEST, e.to_string()).into(),
+    }
+}
+
+/// Import the rows of a Parquet or CSV file, sent as the request body, into a table.
+///
+/// The rows are inserted directly, without calling any reducers,
+/// in transactions of many rows each.
+/// With `dry_run`, the file is only validated against the table.
+pub async fn import<S>(
+    State(worker_ctx): State<S>,
+    Path(ImportParams { name_or_identity }): Path<ImportParams>,
+    Query(ImportQueryParams {
+        table,
+        format,
+        map,
+        dry_run,
+    }): Query<ImportQueryParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+    body: Bytes,
+) -> axum::response::Result<axum::Json<ImportResult>>
+where
+    S: NodeDelegate + ControlStateDelegate,
+{
+    let database_identity: Identity = name_or_identity.resolve(&worker_ctx).await?.into();
+    let database = worker_ctx_find_database(&worker_ctx, &database_identity)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+
+    // Imports bypass the module's reducers, so only the owner may import.
+    if database.owner_identity != auth.identity {
+        return Err((StatusCode::UNAUTHORIZED, "Identity does not own database.").into());
+    }
+
+    let options = ImportOptions {
+        format: format.into(),
+        renames: map.as_deref().map(parse_renames).transpose()?.unwrap_or_default(),
+        dry_run,
+    };
+
+    let host = worker_ctx
+        .leader(database.id)
+        .await
+        .map_err(log_and_500)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let summary = host
+        .import_table(table, body, options)
+        .await
+        .map_err(log_and_500)?
+        .map_err(import_error)?;
+
+    Ok(axum::Json(ImportResult {
+        dry_run,
+        rows: summary.rows,
+        transactions: summary.transactions,
+        columns: summary.columns,
+        ignored: summary.ignored,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct AdviseIndexesParams {
+    name_or_identity: NameOrIdentity,
+}
+
+/// Suggest btree indexes for the columns which queries have filtered on
+/// while scanning entire tables, ordered by their estimated benefit.
+///
+/// Queries are observed while the database runs, so the advice reflects its actual workload.
+pub async fn advise_indexes<S>(
+    State(worker_ctx): State<S>,
+    Path(AdviseIndexesParams { name_or_identity }): Path<AdviseIndexesParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<axum::Json<Vec<IndexSuggestion>>>
+where
+    S: NodeDelegate + ControlStateDelegate,
+{
+    let database_identity: Identity = name_or_identity.resolve(&worker_ctx).await?.into();
+    let database = worker_ctx_find_database(&worker_ctx, &database_identity)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+
+    // The advice covers queries of private tables, so only the owner may see it.
+    if database.owner_identity != auth.identity {
+        return Err((StatusCode::UNAUTHORIZED, "Identity does not own database.").into());
+    }
+
+    let host = worker_ctx
+        .leader(database.id)
+        .await
+        .map_err(log_and_500)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let advice = host.advise_indexes().await.map_err(log_and_500)?;
+
+    Ok(axum::Json(
+        advice
+            .into_iter()
+            .map(|advice| IndexSuggestion {
+                table: advice.table_name.into(),
+                column: advice.column_name.into(),
+                scans: advice.scans,
+                rows_scanned: advice.rows_scanned,
+                rows_saved: advice.rows_saved,
+            })
+            .collect(),
+    ))
+}
+
+#[derive(Deserialize)]
+pub struct HttpRouteParams {
+    name_or_identity: NameOrIdentity,
+    path: String,
+}
+
+/// Serve a `GET` request to an HTTP endpoint declared by the module,
+/// responding with the result of the view serving it as JSON.
+pub async fn http_get<S>(
+    State(worker_ctx): State<S>,
+    Path(HttpRouteParams { name_or_identity, path }): Path<HttpRouteParams>,
+    Query(query): Query<Vec<(String, String)>>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<impl IntoResponse>
+where
+    S: NodeDelegate + ControlStateDelegate,
+{
+    let address = name_or_identity.resolve(&worker_ctx).await?.into();
+    let database = worker_ctx_find_database(&worker_ctx, &address)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+
+    let leader = worker_ctx
+        .leader(database.id)
+        .await
+        .map_err(log_and_500)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let module = leader.module().await.map_err(log_and_500)?;
+
+    // The wildcard segment doesn't include the leading `/` of module paths.
+    let path = format!("/{path}");
+    let rows = module
+        .call_http_route(auth.identity, generate_random_address(), HttpMethod::Get, &path, query)
+        .await
+        .map_err(|e| {
+            let status = match e {
+                HttpRouteCallError::NoSuchRoute | HttpRouteCallError::NoSuchModule(_) => StatusCode::NOT_FOUND,
+                HttpRouteCallError::Args(_) => StatusCode::BAD_REQUEST,
+                HttpRouteCallError::View(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            log::debug!("Error while serving {path}: {e:#}");
+            (status, e.to_string())
+        })?;
+
+    Ok(axum::Json(rows))
+}
+
+#[derive(Deserialize)]
+pub struct ViewParams {
+    name_or_identity: NameOrIdentity,
+    view: String,
+}
+
+/// Call a view of the module with the JSON arguments in the request body,
+/// responding with the rows it produced as JSON.
+///
+/// The arguments are checked against the parameters of the view,
+/// so that callers without a generated SDK get a `400` rather than a failed call.
+pub async fn call_view<S>(
+    State(worker_ctx): State<S>,
+    Extension(auth): Extension<SpacetimeAuth>,
+    Path(ViewParams { name_or_identity, view }): Path<ViewParams>,
+    Query(CallQueryParams { client_address }): Query<CallQueryParams>,
+    ByteStringBody(body): ByteStringBody,
+) -> axum::response::Result<impl IntoResponse>
+where
+    S: NodeDelegate + ControlStateDelegate,
+{
+    let address = name_or_identity.resolve(&worker_ctx).await?.into();
+    let database = worker_ctx_find_database(&worker_ctx, &address)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+
+    let leader = worker_ctx
+        .leader(database.id)
+        .await
+        .map_err(log_and_500)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let module = leader.module().await.map_err(log_and_500)?;
+
+    let client_address = client_address
+        .map(Address::from)
+        .unwrap_or_else(generate_random_address);
+    let rows = module
+        .call_view(auth.identity, client_address, &view, ReducerArgs::Json(body))
+        .await
+        .map_err(|e| {
+            let status = match e {
+                ViewCallError::NoSuchView | ViewCallError::NoSuchModule(_) => StatusCode::NOT_FOUND,
+                ViewCallError::Args(_) => StatusCode::BAD_REQUEST,
+                ViewCallError::View(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            log::debug!("Error while calling view {view}: {e:#}");
+            (status, e.to_string())
+        })?;
+
+    Ok(axum::Json(rows))
 }
 
 #[derive(Deserialize)]
@@ -631,13 +1514,23 @@ pub async fn publish<S: NodeDelegate + ControlStateDelegate>(
     Path(PublishDatabaseParams {}): Path<PublishDatabaseParams>,
     Query(query_params): Query<PublishDatabaseQueryParams>,
     Extension(auth): Extension<SpacetimeAuth>,
-    body: Bytes,
+    body: Body,
 ) -> axum::response::Result<axum::Json<PublishResult>> {
     let PublishDatabaseQueryParams {
         name_or_identity,
         clear,
     } = query_params;
 
+    // Check the program as it arrives, so that one which could never be published
+    // is rejected without waiting for the whole upload.
+    let mut upload = ProgramUpload::default();
+    let mut body = body.into_data_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        upload.push(&chunk).map_err(program_upload_error)?;
+    }
+    let program_bytes = upload.finish().map_err(program_upload_error)?;
+
     // You should not be able to publish to a database that you do not own
     // so, unless you are the owner, this will fail.
 
@@ -688,7 +1581,7 @@ pub async fn publish<S: NodeDelegate + ControlStateDelegate>(
             &auth.identity,
             DatabaseDef {
                 database_identity,
-                program_bytes: body.into(),
+                program_bytes,
                 num_replicas: 1,
                 host_type: HostType::Wasm,
             },
@@ -719,6 +1612,10 @@ pub async fn publish<S: NodeDelegate + ControlStateDelegate>(
     }))
 }
 
+fn program_upload_error(err: ProgramUploadError) -> ErrorResponse {
+    (StatusCode::BAD_REQUEST, format!("Invalid module: {err}")).into()
+}
+
 #[derive(Deserialize)]
 pub struct DeleteDatabaseParams {
     database_identity: IdentityForUrl,
@@ -740,6 +1637,58 @@ pub async fn delete_database<S: ControlStateDelegate>(
     Ok(())
 }
 
+#[derive(Deserialize)]
+pub struct CloneDatabaseParams {
+    name_or_identity: NameOrIdentity,
+}
+
+#[derive(Deserialize)]
+pub struct CloneDatabaseQueryParams {
+    name: Option<String>,
+}
+
+/// Fork a database into a new database, optionally named `name`,
+/// which starts from a copy of the latest state of the original.
+pub async fn clone_database<S: NodeDelegate + ControlStateDelegate>(
+    State(ctx): State<S>,
+    Path(CloneDatabaseParams { name_or_identity }): Path<CloneDatabaseParams>,
+    Query(CloneDatabaseQueryParams { name }): Query<CloneDatabaseQueryParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<axum::Json<CloneResult>> {
+    let source_identity: Identity = name_or_identity.resolve(&ctx).await?.into();
+    let source = worker_ctx_find_database(&ctx, &source_identity)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+    if source.owner_identity != auth.identity {
+        return Err((StatusCode::UNAUTHORIZED, "Identity does not own database.").into());
+    }
+
+    let domain = name
+        .map(|name| name.parse::<DomainName>())
+        .transpose()
+        .map_err(|_| DomainParsingRejection)?;
+    if let Some(domain) = &domain {
+        if ctx.lookup_identity(domain).map_err(log_and_500)?.is_some() {
+            return Err((StatusCode::CONFLICT, "A database with that name already exists.").into());
+        }
+    }
+
+    let database_identity = SpacetimeAuth::alloc(&ctx).await?.identity;
+    ctx.fork_database(&auth.identity, &source_identity, database_identity)
+        .await
+        .map_err(log_and_500)?;
+    if let Some(domain) = &domain {
+        ctx.create_dns_record(&auth.identity, domain, &database_identity)
+            .await
+            .map_err(log_and_500)?;
+    }
+
+    Ok(axum::Json(CloneResult {
+        domain: domain.as_ref().map(ToString::to_string),
+        database_identity,
+    }))
+}
+
 #[derive(Deserialize)]
 pub struct SetNameQueryParams {
     domain: String,
@@ -775,6 +1724,235 @@ pub async fn set_name<S: ControlStateDelegate>(
     Ok(axum::Json(response))
 }
 
+#[derive(Deserialize)]
+pub struct TestRouteParams {
+    name_or_identity: NameOrIdentity,
+}
+
+/// Resolve the leader of the database targeted by a route which only the database owner may use,
+/// like the [`test_routes`].
+async fn owner_route_leader<S: ControlStateDelegate + NodeDelegate>(
+    worker_ctx: &S,
+    name_or_identity: NameOrIdentity,
+    auth: &SpacetimeAuth,
+) -> axum::response::Result<Host> {
+    let database_identity: Identity = name_or_identity.resolve(worker_ctx).await?.into();
+    let database = worker_ctx_find_database(worker_ctx, &database_identity)
+        .await?
+        .ok_or((StatusCode::NOT_FOUND, "No such database."))?;
+
+    if database.owner_identity != auth.identity {
+        return Err((StatusCode::UNAUTHORIZED, "Identity does not own database.").into());
+    }
+
+    let leader = worker_ctx
+        .leader(database.id)
+        .await
+        .map_err(log_and_500)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(leader)
+}
+
+#[derive(Deserialize)]
+pub struct AdvanceTimeQueryParams {
+    micros: u64,
+}
+
+/// Move the clock of a database forward, so that tests of scheduled reducers
+/// with long delays don't have to wait for them in real time.
+///
+/// Responds once the scheduled reducers which came due have run.
+pub async fn advance_time<S: ControlStateDelegate + NodeDelegate>(
+    State(worker_ctx): State<S>,
+    Path(TestRouteParams { name_or_identity }): Path<TestRouteParams>,
+    Query(AdvanceTimeQueryParams { micros }): Query<AdvanceTimeQueryParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<impl IntoResponse> {
+    let leader = owner_route_leader(&worker_ctx, name_or_identity, &auth).await?;
+    leader
+        .advance_time(Duration::from_micros(micros))
+        .await
+        .map_err(|_| (StatusCode::NOT_FOUND, "module not found"))?;
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct SnapshotResponse {
+    tx_offset: Option<u64>,
+}
+
+/// Capture a snapshot of a database right away, and return its TX offset.
+pub async fn take_snapshot<S: ControlStateDelegate + NodeDelegate>(
+    State(worker_ctx): State<S>,
+    Path(TestRouteParams { name_or_identity }): Path<TestRouteParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<impl IntoResponse> {
+    let leader = owner_route_leader(&worker_ctx, name_or_identity, &auth).await?;
+    let tx_offset = leader.take_snapshot().await.map_err(log_and_500)?;
+
+    Ok(axum::Json(SnapshotResponse { tx_offset }))
+}
+
+/// Return the TX offset of the latest snapshot of a database.
+pub async fn latest_snapshot<S: ControlStateDelegate + NodeDelegate>(
+    State(worker_ctx): State<S>,
+    Path(TestRouteParams { name_or_identity }): Path<TestRouteParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<impl IntoResponse> {
+    let leader = owner_route_leader(&worker_ctx, name_or_identity, &auth).await?;
+    let tx_offset = leader.latest_snapshot().await.map_err(log_and_500)?;
+
+    Ok(axum::Json(SnapshotResponse { tx_offset }))
+}
+
+#[derive(Deserialize)]
+pub struct RestoreSnapshotQueryParams {
+    tx_offset: u64,
+}
+
+/// Restart a database from the latest snapshot at or before `tx_offset`,
+/// replaying the rest of its history from the commitlog.
+pub async fn restore_snapshot<S: ControlStateDelegate + NodeDelegate>(
+    State(worker_ctx): State<S>,
+    Path(TestRouteParams { name_or_identity }): Path<TestRouteParams>,
+    Query(RestoreSnapshotQueryParams { tx_offset }): Query<RestoreSnapshotQueryParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<impl IntoResponse> {
+    let leader = owner_route_leader(&worker_ctx, name_or_identity, &auth).await?;
+    leader.restore_snapshot(tx_offset).await.map_err(log_and_500)?;
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct SnapshotPolicyParams {
+    name_or_identity: NameOrIdentity,
+}
+
+#[derive(Serialize)]
+struct SnapshotInfoResponse {
+    tx_offset: u64,
+    captured_at: Timestamp,
+    duration_micros: u64,
+}
+
+#[derive(Serialize)]
+pub struct SnapshotPolicyResponse {
+    policy: SnapshotPolicy,
+    /// The TX offset of the latest snapshot on disk.
+    latest_tx_offset: Option<u64>,
+    /// The snapshot captured most recently since the database was last launched.
+    last_captured: Option<SnapshotInfoResponse>,
+}
+
+impl From<SnapshotStatus> for SnapshotPolicyResponse {
+    fn from(status: SnapshotStatus) -> Self {
+        Self {
+            policy: status.policy,
+            latest_tx_offset: status.latest_tx_offset,
+            last_captured: status.last_captured.map(|info| SnapshotInfoResponse {
+                tx_offset: info.tx_offset,
+                captured_at: Timestamp::from_systemtime(info.captured_at),
+                duration_micros: info.duration.as_micros() as u64,
+            }),
+        }
+    }
+}
+
+/// Return when a database captures snapshots by itself, and which snapshots it has captured.
+pub async fn snapshot_policy<S: ControlStateDelegate + NodeDelegate>(
+    State(worker_ctx): State<S>,
+    Path(SnapshotPolicyParams { name_or_identity }): Path<SnapshotPolicyParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<impl IntoResponse> {
+    let leader = owner_route_leader(&worker_ctx, name_or_identity, &auth).await?;
+    let status = leader
+        .snapshot_status()
+        .await
+        .map_err(log_and_500)?
+        .ok_or((StatusCode::NOT_FOUND, "Database does not keep snapshots."))?;
+
+    Ok(axum::Json(SnapshotPolicyResponse::from(status)))
+}
+
+/// Change when a database captures snapshots by itself,
+/// e.g. to `{"every-txs": 1000000}`, `{"every-minutes": 60}` or `"on-demand"`.
+///
+/// The change lasts until the database is next launched,
+/// after which the policy from the server config applies again.
+pub async fn set_snapshot_policy<S: ControlStateDelegate + NodeDelegate>(
+    State(worker_ctx): State<S>,
+    Path(SnapshotPolicyParams { name_or_identity }): Path<SnapshotPolicyParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+    axum::Json(policy): axum::Json<SnapshotPolicy>,
+) -> axum::response::Result<impl IntoResponse> {
+    let leader = owner_route_leader(&worker_ctx, name_or_identity, &auth).await?;
+    leader.set_snapshot_policy(policy).await.map_err(log_and_500)?;
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct CompressSnapshotsParams {
+    name_or_identity: NameOrIdentity,
+}
+
+#[derive(Deserialize)]
+pub struct CompressSnapshotsQueryParams {
+    older_than_secs: u64,
+}
+
+fn compression_stats(stats: &CompressionStats) -> SnapshotCompressionStats {
+    SnapshotCompressionStats {
+        snapshots_compressed: stats.snapshots_compressed,
+        objects_compressed: stats.objects_compressed,
+        objects_skipped: stats.objects_skipped,
+        bytes_before: stats.bytes_before,
+        bytes_after: stats.bytes_after,
+    }
+}
+
+/// Compress the objects of the snapshots of a database captured more than `older_than_secs` ago.
+///
+/// Responds with newline-delimited [`SnapshotCompressionProgress`],
+/// a line after each snapshot and a final line with the totals or the error.
+pub async fn compress_snapshots<S: ControlStateDelegate + NodeDelegate>(
+    State(worker_ctx): State<S>,
+    Path(CompressSnapshotsParams { name_or_identity }): Path<CompressSnapshotsParams>,
+    Query(CompressSnapshotsQueryParams { older_than_secs }): Query<CompressSnapshotsQueryParams>,
+    Extension(auth): Extension<SpacetimeAuth>,
+) -> axum::response::Result<impl IntoResponse> {
+    let leader = owner_route_leader(&worker_ctx, name_or_identity, &auth).await?;
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let progress_tx = tx.clone();
+    let progress = move |tx_offset, stats: &CompressionStats| {
+        let stats = compression_stats(stats);
+        let _ = progress_tx.send(SnapshotCompressionProgress::Snapshot { tx_offset, stats });
+    };
+    tokio::spawn(async move {
+        let older_than = Duration::from_secs(older_than_secs);
+        let last = match leader.compress_snapshots(older_than, progress).await {
+            Ok(Some(stats)) => SnapshotCompressionProgress::Done(compression_stats(&stats)),
+            Ok(None) => SnapshotCompressionProgress::Error("Database does not keep snapshots.".into()),
+            Err(e) => SnapshotCompressionProgress::Error(format!("{e:#}")),
+        };
+        let _ = tx.send(last);
+    });
+
+    let body = tokio_stream::wrappers::UnboundedReceiverStream::new(rx).map(|line| {
+        let mut json = serde_json::to_string(&line).unwrap();
+        json.push('\n');
+        Ok::<_, std::convert::Infallible>(json)
+    });
+    Ok((
+        TypedHeader(headers::CacheControl::new().with_no_cache()),
+        TypedHeader(headers::ContentType::from(mime_ndjson())),
+        Body::from_stream(body),
+    ))
+}
+
 /// This API call is just designed to allow clients to determine whether or not they can
 /// establish a connection to SpacetimeDB. This API call doesn't actually do anything.
 pub async fn ping<S>(State(_ctx): State<S>, _auth: SpacetimeAuthHeader) -> axum::response::Result<impl IntoResponse> {
@@ -794,6 +1972,7 @@ where
         .route("/register_tld", get(register_tld::<S>))
         .route("/publish", post(publish::<S>).layer(DefaultBodyLimit::disable()))
         .route("/delete/:database_identity", post(delete_database::<S>))
+        .route("/clone/:name_or_identity", post(clone_database::<S>))
         .route_layer(axum::middleware::from_fn_with_state(ctx, anon_auth_middleware::<S>))
 }
 
@@ -808,10 +1987,116 @@ where
             get(super::subscribe::handle_websocket::<S>),
         )
         .route("/call/:name_or_identity/:reducer", post(call::<S>))
+        // There is no `/procedure` route to go with `/view`:
+        // modules can't define procedures yet, only reducers, which `/call` serves, and views.
+        .route("/view/:name_or_identity/:view", post(call_view::<S>))
         .route("/schema/:name_or_identity/:entity_type/:entity", get(describe::<S>))
         .route("/schema/:name_or_identity", get(catalog::<S>))
         .route("/info/:name_or_identity", get(info::<S>))
+        .route("/status/:name_or_identity", get(status::<S>))
+        .route("/versions/:name_or_identity", get(versions::<S>))
         .route("/logs/:name_or_identity", get(logs::<S>))
         .route("/sql/:name_or_identity", post(sql::<S>))
+        .route("/blob/:name_or_identity/:table/:blob_id", get(blob::<S>))
+        .route("/assets/:name_or_identity/*path", get(asset::<S>))
+        .route("/export/:name_or_identity", get(export::<S>))
+        .route(
+            "/import/:name_or_identity",
+            post(import::<S>).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/advise_indexes/:name_or_identity", get(advise_indexes::<S>))
+        .route(
+            "/snapshot_policy/:name_or_identity",
+            get(snapshot_policy::<S>).put(set_snapshot_policy::<S>),
+        )
+        .route("/compress_snapshots/:name_or_identity", post(compress_snapshots::<S>))
+        .route("/http/:name_or_identity/*path", get(http_get::<S>))
+        .route_layer(axum::middleware::from_fn_with_state(ctx, anon_auth_middleware::<S>))
+}
+
+/// Routes which let tests manipulate a database in ways that are not safe in production,
+/// like [`advance_time`] or [`restore_snapshot`].
+/// These should only be mounted when the server is explicitly started in test mode.
+pub fn test_routes<S>(ctx: S) -> axum::Router<S>
+where
+    S: NodeDelegate + ControlStateDelegate + Clone + 'static,
+{
+    use axum::routing::{get, post};
+    axum::Router::new()
+        .route("/advance_time/:name_or_identity", post(advance_time::<S>))
+        .route(
+            "/snapshot/:name_or_identity",
+            get(latest_snapshot::<S>).post(take_snapshot::<S>),
+        )
+        .route("/restore_snapshot/:name_or_identity", post(restore_snapshot::<S>))
         .route_layer(axum::middleware::from_fn_with_state(ctx, anon_auth_middleware::<S>))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use spacetimedb::host::{DatabaseStatus, ReplayProgress};
+
+    fn status_json(status: ReplicaStatus) -> serde_json::Value {
+        serde_json::to_value(StatusResponse::from(status)).unwrap()
+    }
+
+    #[test]
+    fn status_reports_replay_progress_while_launching() {
+        let replay = ReplayProgress {
+            tx_offset: 5,
+            max_tx_offset: Some(10),
+        };
+        let status = ReplicaStatus {
+            module: ModuleStatus::Launching { replay },
+            database: None,
+        };
+        assert_eq!(
+            status_json(status),
+            json!({
+                "module": { "state": "launching", "replayed_tx_offset": 5, "replay_max_tx_offset": 10 },
+                "committed_tx_offset": null,
+                "durable_tx_offset": null,
+                "commitlog_lag_txs": null,
+                "snapshots": null,
+            })
+        );
+    }
+
+    #[test]
+    fn status_reports_failed_launches() {
+        let status = ReplicaStatus {
+            module: ModuleStatus::Failed {
+                error: "program not found".into(),
+            },
+            database: None,
+        };
+        assert_eq!(
+            status_json(status)["module"],
+            json!({ "state": "failed", "error": "program not found" })
+        );
+    }
+
+    #[test]
+    fn status_reports_commitlog_lag_while_running() {
+        let running = |durable_tx_offset| ReplicaStatus {
+            module: ModuleStatus::Running,
+            database: Some(DatabaseStatus {
+                committed_tx_offset: Some(10),
+                durable_tx_offset,
+                snapshots: None,
+            }),
+        };
+
+        let status = status_json(running(Some(7)));
+        assert_eq!(status["module"], json!({ "state": "running" }));
+        assert_eq!(status["committed_tx_offset"], 10);
+        assert_eq!(status["durable_tx_offset"], 7);
+        assert_eq!(status["commitlog_lag_txs"], 3);
+
+        // The lag is unknown as long as nothing is known to be durable.
+        let status = status_json(running(None));
+        assert_eq!(status["commitlog_lag_txs"], serde_json::Value::Null);
+    }
+}
@@ -3,7 +3,7 @@ use axum::response::IntoResponse;
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 
-use spacetimedb::energy::EnergyQuanta;
+use spacetimedb::energy::{EnergyLedgerEntry, EnergyQuanta, LedgerDay};
 use spacetimedb_lib::Identity;
 
 use crate::auth::SpacetimeAuthRequired;
@@ -119,16 +119,66 @@ pub async fn set_energy_balance<S: ControlStateDelegate>(
     }))
 }
 
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize)]
+pub struct EnergyLedgerQueryParams {
+    /// The first day to report, inclusive.
+    from: Option<LedgerDay>,
+    /// The last day to report, inclusive.
+    to: Option<LedgerDay>,
+    #[serde(default)]
+    format: LedgerFormat,
+}
+
+/// Get the energy used per database, reducer, and day by the databases owned by an identity.
+///
+/// Only the identity itself may see its ledger.
+pub async fn get_energy_ledger<S: ControlStateDelegate>(
+    State(ctx): State<S>,
+    Path(IdentityParams { identity }): Path<IdentityParams>,
+    Query(EnergyLedgerQueryParams { from, to, format }): Query<EnergyLedgerQueryParams>,
+    SpacetimeAuthRequired(auth): SpacetimeAuthRequired,
+) -> axum::response::Result<axum::response::Response> {
+    let identity = Identity::from(identity);
+    if auth.identity != identity {
+        return Err(StatusCode::UNAUTHORIZED.into());
+    }
+
+    let entries = ctx.get_energy_ledger(&identity, from, to).map_err(log_and_500)?;
+
+    Ok(match format {
+        LedgerFormat::Json => axum::Json(entries).into_response(),
+        LedgerFormat::Csv => {
+            let mut csv = String::from(EnergyLedgerEntry::CSV_HEADER);
+            csv.push('\n');
+            for entry in &entries {
+                csv.push_str(&entry.to_csv_record());
+                csv.push('\n');
+            }
+            ([(http::header::CONTENT_TYPE, "text/csv")], csv).into_response()
+        }
+    })
+}
+
 pub fn router<S>() -> axum::Router<S>
 where
     S: NodeDelegate + ControlStateDelegate + Clone + 'static,
 {
     use axum::routing::get;
     // TODO: rework this. probably no path param.
-    axum::Router::new().route(
-        "/:identity",
-        get(get_energy_balance::<S>)
-            .post(set_energy_balance::<S>)
-            .put(add_energy::<S>),
-    )
+    axum::Router::new()
+        .route(
+            "/:identity",
+            get(get_energy_balance::<S>)
+                .post(set_energy_balance::<S>)
+                .put(add_energy::<S>),
+        )
+        .route("/:identity/ledger", get(get_energy_ledger::<S>))
 }
@@ -0,0 +1,33 @@
+use axum::extract::State;
+use axum::response::IntoResponse;
+use http::StatusCode;
+
+use crate::NodeDelegate;
+
+/// Responds as long as the server is up, for liveness probes.
+///
+/// This stays successful while databases are being recovered,
+/// so that orchestrators don't restart the server in the middle of a long recovery.
+pub async fn health() -> impl IntoResponse {
+    (StatusCode::OK, "ok")
+}
+
+/// Responds with `503 Service Unavailable` until the server has finished starting up,
+/// including launching the databases it hosts, for readiness probes.
+pub async fn ready<S: NodeDelegate>(State(ctx): State<S>) -> impl IntoResponse {
+    if ctx.is_ready() {
+        (StatusCode::OK, "ready")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "starting")
+    }
+}
+
+pub fn router<S>() -> axum::Router<S>
+where
+    S: NodeDelegate + Clone + 'static,
+{
+    use axum::routing::get;
+    axum::Router::new()
+        .route("/health", get(health))
+        .route("/ready", get(ready::<S>))
+}
@@ -1,5 +1,6 @@
 pub mod database;
 pub mod energy;
+pub mod health;
 pub mod identity;
 pub mod metrics;
 pub mod prometheus;
@@ -6,34 +6,41 @@ use std::time::Duration;
 use axum::extract::{Path, Query, State};
 use axum::response::IntoResponse;
 use axum::Extension;
-use axum_extra::TypedHeader;
 use futures::future::MaybeDone;
 use futures::{Future, FutureExt, SinkExt, StreamExt};
 use http::{HeaderValue, StatusCode};
 use scopeguard::ScopeGuard;
 use serde::Deserialize;
 use spacetimedb::client::messages::{serialize, IdentityTokenMessage, SerializableMessage};
-use spacetimedb::client::{ClientActorId, ClientConfig, ClientConnection, DataMessage, MessageHandleError, Protocol};
-use spacetimedb::host::NoSuchModule;
+use spacetimedb::client::{
+    ClientActorId, ClientConfig, ClientConnection, DataMessage, MessageHandleError, Protocol, ProtocolVersion,
+};
+use spacetimedb::host::{NoSuchModule, ReducerCallError};
 use spacetimedb::util::also_poll;
 use spacetimedb::worker_metrics::WORKER_METRICS;
 use spacetimedb_client_api_messages::websocket::{self as ws_api, Compression};
 use spacetimedb_lib::address::AddressForUrl;
+use spacetimedb_lib::client_metadata::{ClientMetadata, RemoteAddress};
 use spacetimedb_lib::Address;
 use std::time::Instant;
 use tokio::sync::mpsc;
 
 use crate::auth::SpacetimeAuth;
+use crate::util::client_ip::ClientIp;
 use crate::util::websocket::{
     CloseCode, CloseFrame, Message as WsMessage, WebSocketConfig, WebSocketStream, WebSocketUpgrade,
 };
-use crate::util::{NameOrIdentity, XForwardedFor};
+use crate::util::NameOrIdentity;
 use crate::{log_and_500, ControlStateDelegate, NodeDelegate};
 
 #[allow(clippy::declare_interior_mutable_const)]
 pub const TEXT_PROTOCOL: HeaderValue = HeaderValue::from_static(ws_api::TEXT_PROTOCOL);
 #[allow(clippy::declare_interior_mutable_const)]
 pub const BIN_PROTOCOL: HeaderValue = HeaderValue::from_static(ws_api::BIN_PROTOCOL);
+#[allow(clippy::declare_interior_mutable_const)]
+pub const TEXT_PROTOCOL_V1: HeaderValue = HeaderValue::from_static(ws_api::v1::TEXT_PROTOCOL);
+#[allow(clippy::declare_interior_mutable_const)]
+pub const BIN_PROTOCOL_V1: HeaderValue = HeaderValue::from_static(ws_api::v1::BIN_PROTOCOL);
 
 #[derive(Deserialize)]
 pub struct SubscribeParams {
@@ -49,6 +56,14 @@ pub struct SubscribeQueryParams {
     /// This knob works by setting other, more specifc, knobs to the value.
     #[serde(default)]
     pub light: bool,
+    /// The language of the client's SDK, e.g., `rust`, which reducers may read.
+    pub sdk_language: Option<String>,
+    /// The version of the client's SDK, which reducers may read.
+    pub sdk_version: Option<String>,
+    /// Whether reducer calls repeating the `request_id` of a recent call are answered with its result
+    /// rather than run again, so that clients reconnecting with the same `client_address` can safely retry calls.
+    #[serde(default)]
+    pub replay_request_ids: bool,
 }
 
 // TODO: is this a reasonable way to generate client addresses?
@@ -66,14 +81,21 @@ pub async fn handle_websocket<S>(
         client_address,
         compression,
         light,
+        sdk_language,
+        sdk_version,
+        replay_request_ids,
     }): Query<SubscribeQueryParams>,
-    forwarded_for: Option<TypedHeader<XForwardedFor>>,
+    ClientIp(client_ip): ClientIp,
     Extension(auth): Extension<SpacetimeAuth>,
     ws: WebSocketUpgrade,
 ) -> axum::response::Result<impl IntoResponse>
 where
     S: NodeDelegate + ControlStateDelegate,
 {
+    if ctx.client_actor_index().is_shutting_down() {
+        Err((StatusCode::SERVICE_UNAVAILABLE, "The server is shutting down."))?;
+    }
+
     let client_address = client_address
         .map(Address::from)
         .unwrap_or_else(generate_random_address);
@@ -87,14 +109,21 @@ where
 
     let db_address = name_or_identity.resolve(&ctx).await?.into();
 
-    let (res, ws_upgrade, protocol) =
-        ws.select_protocol([(BIN_PROTOCOL, Protocol::Binary), (TEXT_PROTOCOL, Protocol::Text)]);
+    // Prefer the current version of the protocol when the client offers several.
+    let (res, ws_upgrade, protocol) = ws.select_protocol([
+        (BIN_PROTOCOL, (Protocol::Binary, ProtocolVersion::V2)),
+        (TEXT_PROTOCOL, (Protocol::Text, ProtocolVersion::V2)),
+        (BIN_PROTOCOL_V1, (Protocol::Binary, ProtocolVersion::V1)),
+        (TEXT_PROTOCOL_V1, (Protocol::Text, ProtocolVersion::V1)),
+    ]);
 
-    let protocol = protocol.ok_or((StatusCode::BAD_REQUEST, "no valid protocol selected"))?;
+    let (protocol, version) = protocol.ok_or((StatusCode::BAD_REQUEST, "no valid protocol selected"))?;
     let client_config = ClientConfig {
         protocol,
+        version,
         compression,
         tx_update_full: !light,
+        replay_request_ids,
     };
 
     // TODO: Should also maybe refactor the code and the protocol to allow a single websocket
@@ -114,6 +143,10 @@ where
     let identity_token = auth.creds.token().into();
 
     let module_rx = leader.module_watcher().await.map_err(log_and_500)?;
+    module_rx
+        .borrow()
+        .check_connection_quota()
+        .map_err(|e| (StatusCode::TOO_MANY_REQUESTS, e.to_string()))?;
 
     let client_id = ClientActorId {
         identity: auth.identity,
@@ -128,6 +161,9 @@ where
         ..Default::default()
     };
 
+    let running = ctx.client_actor_index().register_actor();
+    let shutdown = ctx.client_actor_index().shutdown_signal();
+
     tokio::spawn(async move {
         let ws = match ws_upgrade.upgrade(ws_config).await {
             Ok(ws) => ws,
@@ -137,17 +173,57 @@ where
             }
         };
 
-        match forwarded_for {
-            Some(TypedHeader(XForwardedFor(ip))) => {
-                log::debug!("New client connected from ip {}", ip)
+        let remote_address = match client_ip {
+            Some(ip) => {
+                log::debug!("New client connected from ip {}", ip);
+                RemoteAddress::anonymize(ip, db_address)
             }
-            None => log::debug!("New client connected from unknown ip"),
-        }
+            None => {
+                log::debug!("New client connected from unknown ip");
+                RemoteAddress::UNKNOWN
+            }
+        };
+        let metadata = ClientMetadata {
+            protocol: protocol.subprotocol(version).into(),
+            sdk_language,
+            sdk_version,
+            remote_address,
+        };
 
-        let actor = |client, sendrx| ws_client_actor(client, ws, sendrx);
-        let client = match ClientConnection::spawn(client_id, client_config, leader.replica_id, module_rx, actor).await
+        let mut ws = Some(ws);
+        let actor = |client, sendrx| {
+            let ws = ws.take().unwrap();
+            async move {
+                // Keep the server from shutting down until the connection is closed.
+                let _running = running;
+                ws_client_actor(client, ws, sendrx, shutdown).await
+            }
+        };
+        let client = match ClientConnection::spawn(
+            client_id,
+            client_config,
+            metadata,
+            leader.replica_id,
+            module_rx,
+            actor,
+        )
+        .await
         {
             Ok(s) => s,
+            Err(ReducerCallError::QuotaExceeded(e)) => {
+                log::info!("Refusing client connection: {e}");
+                // Tell the client why it was refused, so that it can try again later.
+                if let Some(mut ws) = ws {
+                    let close = CloseFrame {
+                        code: CloseCode::Again,
+                        reason: e.to_string().into(),
+                    };
+                    if let Err(e) = ws.close(Some(close)).await {
+                        log::debug!("Error closing refused websocket: {e}");
+                    }
+                }
+                return;
+            }
             Err(e) => {
                 log::warn!("ModuleHost died while we were connecting: {e:#}");
                 return;
@@ -174,13 +250,18 @@ where
 
 const LIVELINESS_TIMEOUT: Duration = Duration::from_secs(60);
 
-async fn ws_client_actor(client: ClientConnection, ws: WebSocketStream, sendrx: mpsc::Receiver<SerializableMessage>) {
+async fn ws_client_actor(
+    client: ClientConnection,
+    ws: WebSocketStream,
+    sendrx: mpsc::Receiver<SerializableMessage>,
+    shutdown: impl Future<Output = ()>,
+) {
     // ensure that even if this task gets cancelled, we always cleanup the connection
     let mut client = scopeguard::guard(client, |client| {
         tokio::spawn(client.disconnect());
     });
 
-    ws_client_actor_inner(&mut client, ws, sendrx).await;
+    ws_client_actor_inner(&mut client, ws, sendrx, shutdown).await;
 
     ScopeGuard::into_inner(client).disconnect().await;
 }
@@ -197,7 +278,9 @@ async fn ws_client_actor_inner(
     client: &mut ClientConnection,
     mut ws: WebSocketStream,
     mut sendrx: mpsc::Receiver<SerializableMessage>,
+    shutdown: impl Future<Output = ()>,
 ) {
+    let mut shutdown = pin!(shutdown);
     let mut liveness_check_interval = tokio::time::interval(LIVELINESS_TIMEOUT);
     let mut got_pong = true;
 
@@ -329,6 +412,22 @@ async fn ws_client_actor_inner(
                 continue;
             }
 
+            // If the server is shutting down, tell the client so, and close the websocket.
+            // Once the client acknowledges the close, `ws.next()` returns `None` and we disconnect.
+            _ = &mut shutdown, if !closed => {
+                // Send a close frame while continuing to poll the `handle_queue`,
+                // so that the message being handled, e.g. a reducer call, runs to completion.
+                let close = also_poll(
+                    ws.close(Some(CloseFrame { code: CloseCode::Away, reason: "server shutting down".into() })),
+                    make_progress(&mut current_message),
+                );
+                if let Err(e) = close.await {
+                    log::warn!("error closing: {e:#}")
+                }
+                closed = true;
+                continue;
+            }
+
             // If it's time to send a ping...
             _ = liveness_check_interval.tick() => {
                 // If we received a pong at some point, send a fresh ping.
@@ -1,14 +1,14 @@
+pub mod client_ip;
 mod flat_csv;
 pub mod websocket;
 
 use core::fmt;
-use std::net::IpAddr;
 
 use axum::body::Bytes;
 use axum::extract::{FromRequest, Request};
 use axum::response::IntoResponse;
 use bytestring::ByteString;
-use http::{HeaderName, HeaderValue, StatusCode};
+use http::StatusCode;
 
 use spacetimedb::Identity;
 use spacetimedb_client_api_messages::name::DomainName;
@@ -36,27 +36,6 @@ impl<S: Send + Sync> FromRequest<S> for ByteStringBody {
     }
 }
 
-pub struct XForwardedFor(pub IpAddr);
-
-impl headers::Header for XForwardedFor {
-    fn name() -> &'static HeaderName {
-        static NAME: HeaderName = HeaderName::from_static("x-forwarded-for");
-        &NAME
-    }
-
-    fn decode<'i, I: Iterator<Item = &'i HeaderValue>>(values: &mut I) -> Result<Self, headers::Error> {
-        let val = values.next().ok_or_else(headers::Error::invalid)?;
-        let val = val.to_str().map_err(|_| headers::Error::invalid())?;
-        let (first, _) = val.split_once(',').ok_or_else(headers::Error::invalid)?;
-        let ip = first.trim().parse().map_err(|_| headers::Error::invalid())?;
-        Ok(XForwardedFor(ip))
-    }
-
-    fn encode<E: Extend<HeaderValue>>(&self, values: &mut E) {
-        values.extend([self.0.to_string().try_into().unwrap()])
-    }
-}
-
 #[derive(Clone, Debug)]
 pub enum NameOrIdentity {
     Identity(IdentityForUrl),
@@ -0,0 +1,160 @@
+//! Determining the address of the client which made a request, when the server may be behind reverse proxies.
+
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, FromRequestParts, Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use http::header::FORWARDED;
+use http::request::Parts;
+use http::{HeaderMap, HeaderName};
+use ipnet::IpNet;
+
+static X_FORWARDED_FOR: HeaderName = HeaderName::from_static("x-forwarded-for");
+
+/// The IP address of the client which made a request, if known.
+///
+/// This is the address of the peer the request came from, unless that is a [trusted proxy](TrustedProxies),
+/// in which case it's the client address forwarded in the `Forwarded` or `X-Forwarded-For` header.
+///
+/// It is only known for requests that went through [`resolve_client_ip`].
+#[derive(Clone, Copy, Debug)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get().copied().unwrap_or(ClientIp(None)))
+    }
+}
+
+/// The reverse proxies whose `Forwarded` and `X-Forwarded-For` headers are trusted to name the client.
+///
+/// Peers connected over a unix socket, which have no IP address, are always trusted,
+/// as only processes on the same machine can connect to it.
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies(pub Vec<IpNet>);
+
+impl TrustedProxies {
+    fn trusts(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Returns the address of the client which sent a request with `headers`, which came from `peer`.
+    pub fn client_ip(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        if peer.is_some_and(|peer| !self.trusts(peer)) {
+            return peer;
+        }
+        // Each proxy appends the address it received the request from,
+        // so the client is the last address which isn't a trusted proxy.
+        let mut client = peer;
+        for hop in forwarded_for(headers).into_iter().rev() {
+            // If a proxy didn't reveal who it received the request from, that's the best we can know.
+            let Some(ip) = hop else { break };
+            client = Some(ip);
+            if !self.trusts(ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Middleware which determines the [`ClientIp`] of each request.
+pub async fn resolve_client_ip(State(trusted): State<Arc<TrustedProxies>>, mut req: Request, next: Next) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let client_ip = trusted.client_ip(peer, req.headers());
+    req.extensions_mut().insert(ClientIp(client_ip));
+    next.run(req).await
+}
+
+/// Returns the client addresses in the `Forwarded` header, or if there is none, in the `X-Forwarded-For` header,
+/// with `None` for those which aren't IP addresses, e.g. because the proxy obfuscated them.
+fn forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    if headers.contains_key(FORWARDED) {
+        header_list(headers, &FORWARDED)
+            .map(|element| {
+                element.split(';').find_map(|pair| {
+                    let (key, value) = pair.split_once('=')?;
+                    key.trim().eq_ignore_ascii_case("for").then(|| parse_node(value))?
+                })
+            })
+            .collect()
+    } else {
+        header_list(headers, &X_FORWARDED_FOR).map(parse_node).collect()
+    }
+}
+
+/// Returns the comma-separated elements of all `name` headers, in order.
+fn header_list<'a>(headers: &'a HeaderMap, name: &HeaderName) -> impl Iterator<Item = &'a str> {
+    headers
+        .get_all(name)
+        .into_iter()
+        .flat_map(|value| value.to_str().unwrap_or_default().split(','))
+}
+
+/// Parses a node forwarded by a proxy, an IP address which may be quoted, bracketed, or have a port.
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+    let unbracketed = node.strip_prefix('[').and_then(|n| n.strip_suffix(']'));
+    match unbracketed.unwrap_or(node).parse() {
+        Ok(ip) => Some(ip),
+        Err(_) => node.parse::<SocketAddr>().ok().map(|addr| addr.ip()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::HeaderValue;
+
+    fn trusted() -> TrustedProxies {
+        TrustedProxies(vec!["127.0.0.0/8".parse().unwrap(), "10.0.0.0/8".parse().unwrap()])
+    }
+
+    fn headers(name: &HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn ip(ip: &str) -> Option<IpAddr> {
+        Some(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn ignores_headers_from_untrusted_peers() {
+        let headers = headers(&X_FORWARDED_FOR, "1.2.3.4");
+        assert_eq!(trusted().client_ip(ip("5.6.7.8"), &headers), ip("5.6.7.8"));
+    }
+
+    #[test]
+    fn skips_trusted_proxies() {
+        let headers = headers(&X_FORWARDED_FOR, "9.9.9.9, 1.2.3.4, 10.0.0.2");
+        assert_eq!(trusted().client_ip(ip("127.0.0.1"), &headers), ip("1.2.3.4"));
+        assert_eq!(trusted().client_ip(None, &headers), ip("1.2.3.4"));
+        assert_eq!(trusted().client_ip(ip("127.0.0.1"), &HeaderMap::new()), ip("127.0.0.1"));
+    }
+
+    #[test]
+    fn parses_forwarded() {
+        let headers = headers(
+            &FORWARDED,
+            r#"for=9.9.9.9, For="[2001:db8:cafe::17]:4711";proto=https, for=10.0.0.2:80;by=10.0.0.1"#,
+        );
+        assert_eq!(trusted().client_ip(ip("127.0.0.1"), &headers), ip("2001:db8:cafe::17"));
+    }
+
+    #[test]
+    fn stops_at_unknown_hops() {
+        let headers = headers(&FORWARDED, "for=1.2.3.4, for=_hidden, for=10.0.0.2");
+        assert_eq!(trusted().client_ip(ip("127.0.0.1"), &headers), ip("10.0.0.2"));
+    }
+}
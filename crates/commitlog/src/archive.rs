@@ -0,0 +1,31 @@
+use std::{fmt, io, path::Path};
+
+/// Storage for sealed log segments, typically on a remote object store.
+///
+/// Segments are identified by their minimum transaction offset, just like
+/// in a [`crate::repo::Repo`]. Once a segment is sealed, it is never written
+/// to again, so it can be removed from the local disk after it has been
+/// uploaded, and be downloaded again when it is needed for a traversal.
+///
+/// The methods of this trait are blocking.
+pub trait Archive: fmt::Debug + Send + Sync {
+    /// Store the segment with the minimum transaction offset `offset`,
+    /// reading its contents from the file at `path`.
+    ///
+    /// If the segment is already archived, it is overwritten.
+    fn upload(&self, offset: u64, path: &Path) -> io::Result<()>;
+
+    /// Write the archived segment with the minimum transaction offset `offset`
+    /// to a new file at `path`.
+    ///
+    /// Must return [`io::ErrorKind::NotFound`] if no such segment is archived.
+    fn download(&self, offset: u64, path: &Path) -> io::Result<()>;
+
+    /// Remove the archived segment with the minimum transaction offset `offset`.
+    ///
+    /// Must return [`io::ErrorKind::NotFound`] if no such segment is archived.
+    fn remove(&self, offset: u64) -> io::Result<()>;
+
+    /// Return the offsets of all archived segments, sorted in ascending order.
+    fn archived_offsets(&self) -> io::Result<Vec<u64>>;
+}
@@ -32,7 +32,7 @@ pub struct Generic<R: Repo, T> {
     ///
     /// This is a `Vec`, not a linked list, so the last element is the newest
     /// segment (after `head`).
-    tail: Vec<u64>,
+    pub(crate) tail: Vec<u64>,
     /// Configuration options.
     opts: Options,
     /// Type of a single record in this log's [`Commit::records`].
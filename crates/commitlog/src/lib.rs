@@ -1,12 +1,13 @@
 use std::{
     io,
     num::{NonZeroU16, NonZeroU64},
-    sync::RwLock,
+    sync::{Arc, RwLock},
 };
 
 use log::trace;
 use spacetimedb_paths::server::CommitLogDir;
 
+pub mod archive;
 pub mod commit;
 pub mod commitlog;
 mod index;
@@ -121,6 +122,19 @@ impl<T> Commitlog<T> {
         })
     }
 
+    /// Open the log at root directory `root` with [`Options`], moving sealed
+    /// segments to `archive` when [`Self::archive_sealed_segments`] is called.
+    ///
+    /// Segments which have been archived and removed from `root` are
+    /// downloaded again when a traversal needs them.
+    pub fn open_archived(root: CommitLogDir, opts: Options, archive: Arc<dyn archive::Archive>) -> io::Result<Self> {
+        let inner = commitlog::Generic::open(repo::Fs::with_archive(root, archive)?, opts)?;
+
+        Ok(Self {
+            inner: RwLock::new(inner),
+        })
+    }
+
     /// Determine the maximum transaction offset considered durable.
     ///
     /// The offset is `None` if the log hasn't been flushed to disk yet.
@@ -291,6 +305,24 @@ impl<T> Commitlog<T> {
         let inner = self.inner.read().unwrap();
         inner.repo.size_on_disk()
     }
+
+    /// Upload all sealed segments which are not archived yet to the archive
+    /// this log was opened with, and remove all but the newest `keep_local`
+    /// sealed segments from the local disk.
+    ///
+    /// The segment currently being written to is never archived.
+    /// Does nothing if the log was not opened via [`Self::open_archived`].
+    ///
+    /// Returns the number of segments uploaded.
+    pub fn archive_sealed_segments(&self, keep_local: usize) -> io::Result<usize> {
+        // Don't hold the lock while uploading,
+        // sealed segments are not modified anymore.
+        let (repo, sealed) = {
+            let inner = self.inner.read().unwrap();
+            (inner.repo.clone(), inner.tail.clone())
+        };
+        repo.archive_segments(&sealed, keep_local)
+    }
 }
 
 impl<T: Encode> Commitlog<T> {
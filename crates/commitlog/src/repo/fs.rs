@@ -1,10 +1,13 @@
+use std::collections::BTreeSet;
 use std::fs::{self, File};
 use std::io::{self, Seek};
+use std::sync::Arc;
 
-use log::{debug, warn};
+use log::{debug, info, warn};
 use spacetimedb_paths::server::{CommitLogDir, SegmentFile};
 
 use super::{Repo, Segment, TxOffset, TxOffsetIndex, TxOffsetIndexMut};
+use crate::archive::Archive;
 
 const SEGMENT_FILE_EXT: &str = ".stdb.log";
 
@@ -25,6 +28,11 @@ const SEGMENT_FILE_EXT: &str = ".stdb.log";
 pub struct Fs {
     /// The base directory within which segment files will be stored.
     root: CommitLogDir,
+    /// Where sealed segments are archived, if anywhere.
+    ///
+    /// Archived segments which are not present in `root` are downloaded when
+    /// they are opened.
+    archive: Option<Arc<dyn Archive>>,
 }
 
 impl Fs {
@@ -33,7 +41,19 @@ impl Fs {
     /// `root` must name an extant, accessible, writeable directory.
     pub fn new(root: CommitLogDir) -> io::Result<Self> {
         root.create()?;
-        Ok(Self { root })
+        Ok(Self { root, archive: None })
+    }
+
+    /// Create a commitlog repository which stores segments in the directory `root`,
+    /// and can move sealed segments to `archive` via [`Self::archive_segments`].
+    ///
+    /// The segments of the repository are those in `root` and those in `archive`.
+    pub fn with_archive(root: CommitLogDir, archive: Arc<dyn Archive>) -> io::Result<Self> {
+        root.create()?;
+        Ok(Self {
+            root,
+            archive: Some(archive),
+        })
     }
 
     /// Get the filename for a segment starting with `offset` within this
@@ -45,9 +65,10 @@ impl Fs {
     /// Determine the size on disk as the sum of the sizes of all segments.
     ///
     /// Note that the actively written-to segment (if any) is included.
+    /// Archived segments which are not present locally are not included.
     pub fn size_on_disk(&self) -> io::Result<u64> {
         let mut sz = 0;
-        for offset in self.existing_offsets()? {
+        for offset in self.local_offsets()? {
             sz += self.segment_path(offset).metadata()?.len();
             // Add the size of the offset index file if present
             sz += self.root.index(offset).metadata().map(|m| m.len()).unwrap_or(0);
@@ -55,6 +76,119 @@ impl Fs {
 
         Ok(sz)
     }
+
+    /// Copy all segments of this repository into a new repository at `target`,
+    /// which must not contain any segments yet.
+    ///
+    /// All segments but the last are immutable, so they are hardlinked
+    /// together with their offset indexes.
+    /// The last segment may still be written to, so it is copied.
+    /// If a commit is being appended to it while it is being copied,
+    /// the copy ends in a partial commit, which is discarded when the copy is opened.
+    pub fn fork(&self, target: CommitLogDir) -> io::Result<Self> {
+        let target = Self::new(target)?;
+        let offsets = self.existing_offsets()?;
+        if let Some((&last, sealed)) = offsets.split_last() {
+            for &offset in sealed {
+                self.hydrate(offset)?;
+                fs::hard_link(self.segment_path(offset), target.segment_path(offset))?;
+                let index = self.root.index(offset);
+                if index.0.is_file() {
+                    fs::hard_link(index, target.root.index(offset))?;
+                }
+            }
+            fs::copy(self.segment_path(last), target.segment_path(last))?;
+        }
+
+        Ok(target)
+    }
+
+    /// Upload the segments at `sealed` which are not archived yet to the archive,
+    /// and remove all but the last `keep_local` of them from the local disk.
+    ///
+    /// `sealed` must be sorted in ascending order, and only contain the
+    /// offsets of segments which are no longer written to.
+    ///
+    /// Returns the number of segments uploaded.
+    /// Does nothing if this repository has no archive.
+    pub fn archive_segments(&self, sealed: &[u64], keep_local: usize) -> io::Result<usize> {
+        let Some(archive) = &self.archive else {
+            return Ok(0);
+        };
+        let mut archived = archive.archived_offsets()?.into_iter().collect::<BTreeSet<_>>();
+        let local = self.local_offsets()?.into_iter().collect::<BTreeSet<_>>();
+
+        let mut uploaded = 0;
+        for &offset in sealed {
+            if !archived.contains(&offset) && local.contains(&offset) {
+                debug!("archiving segment {offset}");
+                archive.upload(offset, self.segment_path(offset).as_ref())?;
+                archived.insert(offset);
+                uploaded += 1;
+            }
+        }
+
+        let evict = sealed.len().saturating_sub(keep_local);
+        for &offset in &sealed[..evict] {
+            if archived.contains(&offset) && local.contains(&offset) {
+                debug!("removing archived segment {offset} from local disk");
+                let _ = self.remove_offset_index(offset).map_err(|e| {
+                    warn!("failed to remove offset index for segment {offset}, error: {e}");
+                });
+                match fs::remove_file(self.segment_path(offset)) {
+                    Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(uploaded)
+    }
+
+    /// Download the segment at `offset` from the archive if it is not present
+    /// on the local disk.
+    fn hydrate(&self, offset: u64) -> io::Result<()> {
+        let path = self.segment_path(offset);
+        match (&self.archive, path.0.try_exists()?) {
+            (Some(archive), false) => {
+                info!("downloading archived segment {offset}");
+                // Download to a temporary file first,
+                // so that an interrupted download doesn't leave a truncated segment.
+                let tmp = path.0.with_extension("log.part");
+                archive.download(offset, &tmp).inspect_err(|_| {
+                    let _ = fs::remove_file(&tmp);
+                })?;
+                fs::rename(&tmp, &path)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The offsets of the segments present on the local disk,
+    /// sorted in ascending order.
+    fn local_offsets(&self) -> io::Result<Vec<u64>> {
+        let mut segments = Vec::new();
+
+        for entry in fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                let path = entry.path();
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let Some(file_name) = name.strip_suffix(SEGMENT_FILE_EXT) else {
+                    continue;
+                };
+                let Ok(offset) = file_name.parse::<u64>() else {
+                    continue;
+                };
+
+                segments.push(offset);
+            }
+        }
+
+        segments.sort_unstable();
+
+        Ok(segments)
+    }
 }
 
 impl Segment for File {
@@ -94,6 +228,7 @@ impl Repo for Fs {
     }
 
     fn open_segment(&self, offset: u64) -> io::Result<Self::Segment> {
+        self.hydrate(offset)?;
         File::options().read(true).append(true).open(self.segment_path(offset))
     }
 
@@ -101,30 +236,27 @@ impl Repo for Fs {
         let _ = self.remove_offset_index(offset).map_err(|e| {
             warn!("failed to remove offset index for segment {offset}, error: {e}");
         });
-        fs::remove_file(self.segment_path(offset))
+        let removed = fs::remove_file(self.segment_path(offset));
+        let Some(archive) = &self.archive else {
+            return removed;
+        };
+        // The segment may have been archived and removed from the local disk.
+        // Remove the archived copy too, so that it doesn't reappear.
+        match (removed, archive.remove(offset)) {
+            (Ok(()), Ok(())) => Ok(()),
+            (Ok(()), Err(e)) | (Err(e), Ok(())) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            (Err(e), _) | (_, Err(e)) => Err(e),
+        }
     }
 
     fn existing_offsets(&self) -> io::Result<Vec<u64>> {
-        let mut segments = Vec::new();
-
-        for entry in fs::read_dir(&self.root)? {
-            let entry = entry?;
-            if entry.file_type()?.is_file() {
-                let path = entry.path();
-                let name = path.file_name().unwrap_or_default().to_string_lossy();
-                let Some(file_name) = name.strip_suffix(SEGMENT_FILE_EXT) else {
-                    continue;
-                };
-                let Ok(offset) = file_name.parse::<u64>() else {
-                    continue;
-                };
-
-                segments.push(offset);
-            }
+        let mut segments = self.local_offsets()?;
+        if let Some(archive) = &self.archive {
+            segments.extend(archive.archived_offsets()?);
+            segments.sort_unstable();
+            segments.dedup();
         }
 
-        segments.sort_unstable();
-
         Ok(segments)
     }
 
@@ -1,7 +1,11 @@
+use std::fs;
+use std::io;
 use std::num::NonZeroU16;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use rand::Rng;
-use spacetimedb_commitlog::{payload, Commitlog, Options};
+use spacetimedb_commitlog::{archive::Archive, payload, Commitlog, Options};
 use spacetimedb_paths::server::CommitLogDir;
 use spacetimedb_paths::FromPathUnchecked;
 use tempfile::tempdir;
@@ -78,3 +82,81 @@ fn resets() {
         );
     }
 }
+
+/// An [`Archive`] which stores segments as files in a directory.
+#[derive(Debug)]
+struct DirArchive(PathBuf);
+
+impl DirArchive {
+    fn path(&self, offset: u64) -> PathBuf {
+        self.0.join(offset.to_string())
+    }
+}
+
+impl Archive for DirArchive {
+    fn upload(&self, offset: u64, path: &Path) -> io::Result<()> {
+        fs::copy(path, self.path(offset)).map(drop)
+    }
+
+    fn download(&self, offset: u64, path: &Path) -> io::Result<()> {
+        fs::copy(self.path(offset), path).map(drop)
+    }
+
+    fn remove(&self, offset: u64) -> io::Result<()> {
+        fs::remove_file(self.path(offset))
+    }
+
+    fn archived_offsets(&self) -> io::Result<Vec<u64>> {
+        let mut offsets = fs::read_dir(&self.0)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().parse().unwrap()))
+            .collect::<io::Result<Vec<u64>>>()?;
+        offsets.sort_unstable();
+        Ok(offsets)
+    }
+}
+
+#[test]
+fn archives() {
+    let root = tempdir().unwrap();
+    let archive_root = tempdir().unwrap();
+    let archive = Arc::new(DirArchive(archive_root.path().to_path_buf()));
+    let opts = Options {
+        max_segment_size: 8 * 1024,
+        max_records_in_commit: NonZeroU16::MIN,
+        ..Options::default()
+    };
+    let open = || Commitlog::open_archived(CommitLogDir::from_path_unchecked(root.path()), opts, archive.clone());
+    let local_segments = || {
+        fs::read_dir(root.path())
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().to_string_lossy().ends_with(".stdb.log"))
+            .count()
+    };
+
+    let clog = open().unwrap();
+    let n_txs = 500;
+    let payload = gen_payload();
+    for _ in 0..n_txs {
+        clog.append_maybe_flush(payload).unwrap();
+    }
+    clog.flush_and_sync().unwrap();
+
+    let n_segments = local_segments();
+    assert!(n_segments > 3);
+    // All but the segment being written to are uploaded,
+    // and all but the newest sealed one are removed from the local disk.
+    assert_eq!(n_segments - 1, clog.archive_sealed_segments(1).unwrap());
+    assert_eq!(n_segments - 1, archive.archived_offsets().unwrap().len());
+    assert_eq!(2, local_segments());
+    // Nothing left to upload.
+    assert_eq!(0, clog.archive_sealed_segments(1).unwrap());
+    drop(clog);
+
+    // Archived segments are downloaded when traversing the log.
+    let clog = open().unwrap();
+    assert_eq!(
+        n_txs,
+        clog.transactions(&payload::ArrayDecoder).map(Result::unwrap).count()
+    );
+    assert_eq!(n_segments, local_segments());
+}
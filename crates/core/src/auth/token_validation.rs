@@ -9,6 +9,7 @@ use jsonwebtoken::{decode, Validation};
 pub use jsonwebtoken::{DecodingKey, EncodingKey};
 use jwks::Jwks;
 use lazy_static::lazy_static;
+use parking_lot::RwLock;
 use serde::Serialize;
 use std::sync::Arc;
 use std::time::Duration;
@@ -88,6 +89,27 @@ pub struct FullTokenValidator<T: TokenValidator + Send + Sync> {
     pub local_key: DecodingKey,
     pub local_issuer: String,
     pub oidc_validator: T,
+    /// The issuers of the tokens which are passed on to the `oidc_validator`.
+    pub allowed_issuers: AllowedIssuers,
+}
+
+/// The issuers whose tokens a [`FullTokenValidator`] accepts,
+/// which may be changed while the server is running.
+#[derive(Clone, Default)]
+pub struct AllowedIssuers(Arc<RwLock<Option<Vec<String>>>>);
+
+impl AllowedIssuers {
+    /// Accept tokens from `issuers`, or from any issuer if `None`.
+    pub fn set(&self, issuers: Option<Vec<String>>) {
+        *self.0.write() = issuers;
+    }
+
+    fn allows(&self, issuer: &str) -> bool {
+        self.0
+            .read()
+            .as_ref()
+            .map_or(true, |issuers| issuers.iter().any(|allowed| allowed == issuer))
+    }
 }
 
 #[async_trait]
@@ -114,6 +136,11 @@ where
         if issuer == self.local_issuer {
             return Err(local_key_error);
         }
+        if !self.allowed_issuers.allows(&issuer) {
+            return Err(TokenValidationError::Other(anyhow::anyhow!(
+                "Tokens issued by {issuer:?} are not accepted"
+            )));
+        }
         self.oidc_validator.validate_token(token).await
     }
 }
@@ -125,6 +152,7 @@ pub fn new_validator(local_key: DecodingKey, local_issuer: String) -> FullTokenV
         local_key,
         local_issuer,
         oidc_validator: CachingOidcTokenValidator::get_default(),
+        allowed_issuers: AllowedIssuers::default(),
     }
 }
 
@@ -328,8 +356,8 @@ mod tests {
 
     use crate::auth::identity::{IncomingClaims, SpacetimeIdentityClaims};
     use crate::auth::token_validation::{
-        BasicTokenValidator, CachingOidcTokenValidator, FullTokenValidator, OidcTokenValidator, TokenSigner,
-        TokenValidator,
+        AllowedIssuers, BasicTokenValidator, CachingOidcTokenValidator, FullTokenValidator, OidcTokenValidator,
+        TokenSigner, TokenValidator,
     };
     use crate::auth::JwtKeys;
     use base64::Engine;
@@ -432,6 +460,37 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn rejects_disallowed_issuers() -> anyhow::Result<()> {
+        let local_kp = JwtKeys::generate()?;
+        let external_kp = JwtKeys::generate()?;
+        let external_issuer = "external_issuer";
+        let claims = IncomingClaims {
+            identity: None,
+            subject: "test_subject".to_string(),
+            issuer: external_issuer.to_string(),
+            audience: vec![],
+            iat: std::time::SystemTime::now(),
+            exp: None,
+        };
+        let token = external_kp.private.sign(&claims)?;
+
+        let validator = FullTokenValidator {
+            local_key: local_kp.public,
+            local_issuer: "local_issuer".to_string(),
+            oidc_validator: external_kp.public,
+            allowed_issuers: AllowedIssuers::default(),
+        };
+        validator.validate_token(&token).await?;
+
+        validator.allowed_issuers.set(Some(vec!["another_issuer".to_string()]));
+        assert_validation_fails(&validator, &token).await?;
+
+        validator.allowed_issuers.set(Some(vec![external_issuer.to_string()]));
+        validator.validate_token(&token).await?;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn resigned_token_ignores_issuer() -> anyhow::Result<()> {
         // Test that the decoding key must work for LocalTokenValidator.
@@ -456,6 +515,7 @@ mod tests {
                 local_key: kp.public.clone(),
                 local_issuer: local_issuer.to_string(),
                 oidc_validator: OidcTokenValidator,
+                allowed_issuers: AllowedIssuers::default(),
             };
 
             let parsed_claims: SpacetimeIdentityClaims = validator.validate_token(&token).await?;
@@ -644,6 +704,7 @@ mod tests {
             local_key: kp.public,
             local_issuer: "local_issuer".to_string(),
             oidc_validator: OidcTokenValidator,
+            allowed_issuers: AllowedIssuers::default(),
         };
         run_oidc_test(v).await
     }
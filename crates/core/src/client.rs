@@ -5,12 +5,14 @@ mod client_connection;
 mod client_connection_index;
 mod message_handlers;
 pub mod messages;
+mod session;
 
 pub use client_connection::{
-    ClientConfig, ClientConnection, ClientConnectionSender, ClientSendError, DataMessage, Protocol,
+    ClientConfig, ClientConnection, ClientConnectionSender, ClientSendError, DataMessage, Protocol, ProtocolVersion,
 };
-pub use client_connection_index::ClientActorIndex;
+pub use client_connection_index::{ClientActorGuard, ClientActorIndex};
 pub use message_handlers::MessageHandleError;
+pub use session::{SessionVariableError, SessionVariables};
 use spacetimedb_lib::Address;
 
 #[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
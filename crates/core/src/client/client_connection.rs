@@ -3,18 +3,27 @@ use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
 use std::sync::Arc;
 use std::time::Instant;
 
-use super::messages::{OneOffQueryResponseMessage, SerializableMessage};
-use super::{message_handlers, ClientActorId, MessageHandleError};
+use super::messages::{
+    OneOffQueryResponseMessage, SerializableMessage, SubscriptionUpdateMessage, TransactionUpdateMessage,
+};
+use super::{message_handlers, ClientActorId, MessageHandleError, SessionVariableError, SessionVariables};
 use crate::error::DBError;
-use crate::host::{ModuleHost, NoSuchModule, ReducerArgs, ReducerCallError, ReducerCallResult};
+use crate::host::module_host::{DatabaseUpdate, EventStatus, ModuleEvent, ModuleFunctionCall};
+use crate::host::{
+    ArgsTuple, CallFingerprint, IdempotencyKey, ModuleHost, NoSuchModule, ReducerArgs, ReducerCallError,
+    ReducerCallResult, ReducerOutcome, Timestamp,
+};
 use crate::messages::websocket::Subscribe;
 use crate::util::prometheus_handle::IntGaugeExt;
 use crate::worker_metrics::WORKER_METRICS;
 use derive_more::From;
 use futures::prelude::*;
+use parking_lot::Mutex;
 use spacetimedb_client_api_messages::websocket::{
-    BsatnFormat, CallReducerFlags, Compression, FormatSwitch, JsonFormat, SubscribeSingle, Unsubscribe, WebsocketFormat,
+    self as ws, BsatnFormat, CallReducerFlags, Compression, FormatSwitch, JsonFormat, SubscribeSingle, SubscribeView,
+    Unsubscribe, WebsocketFormat,
 };
+use spacetimedb_lib::client_metadata::{ClientMetadata, RemoteAddress};
 use spacetimedb_lib::identity::RequestId;
 use tokio::sync::{mpsc, oneshot, watch};
 use tokio::task::AbortHandle;
@@ -32,26 +41,53 @@ impl Protocol {
             _ => unreachable!("requested protocol does not match output format"),
         }
     }
+
+    /// Returns the websocket subprotocol which selects this protocol at `version`.
+    pub fn subprotocol(self, version: ProtocolVersion) -> &'static str {
+        match (version, self) {
+            (ProtocolVersion::V1, Protocol::Text) => ws::v1::TEXT_PROTOCOL,
+            (ProtocolVersion::V1, Protocol::Binary) => ws::v1::BIN_PROTOCOL,
+            (ProtocolVersion::V2, Protocol::Text) => ws::TEXT_PROTOCOL,
+            (ProtocolVersion::V2, Protocol::Binary) => ws::BIN_PROTOCOL,
+        }
+    }
+}
+
+/// The version of the websocket protocol a client speaks,
+/// which decides the layout of the messages it sends and receives.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Debug)]
+pub enum ProtocolVersion {
+    /// The messages in [`ws::v1`].
+    V1,
+    /// The current messages in [`ws`].
+    V2,
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct ClientConfig {
     /// The client's desired protocol (format) when the host replies.
     pub protocol: Protocol,
+    /// The version of the protocol the client speaks.
+    pub version: ProtocolVersion,
     /// The client's desired (conditional) compression algorithm, if any.
     pub compression: Compression,
     /// Whether the client prefers full [`TransactionUpdate`]s
     /// rather than  [`TransactionUpdateLight`]s on a successful update.
     // TODO(centril): As more knobs are added, make this into a bitfield (when there's time).
     pub tx_update_full: bool,
+    /// Whether a reducer call repeating the `request_id` of a recent call from the same client address,
+    /// e.g. a retry after reconnecting, is answered with the result of that call rather than run again.
+    pub replay_request_ids: bool,
 }
 
 impl ClientConfig {
     pub fn for_test() -> ClientConfig {
         Self {
             protocol: Protocol::Binary,
+            version: ProtocolVersion::V2,
             compression: <_>::default(),
             tx_update_full: true,
+            replay_request_ids: false,
         }
     }
 }
@@ -60,6 +96,13 @@ impl ClientConfig {
 pub struct ClientConnectionSender {
     pub id: ClientActorId,
     pub config: ClientConfig,
+    /// What the host learned about the client when it connected.
+    pub metadata: Arc<ClientMetadata>,
+    /// The variables the client has set in its session.
+    ///
+    /// Reducers take a snapshot of the session when they are called,
+    /// so the variables are shared and cloned only when changed.
+    session: Mutex<Arc<SessionVariables>>,
     sendtx: mpsc::Sender<SerializableMessage>,
     abort_handle: AbortHandle,
     cancelled: AtomicBool,
@@ -85,6 +128,13 @@ impl ClientConnectionSender {
             Self {
                 id,
                 config,
+                metadata: Arc::new(ClientMetadata {
+                    protocol: config.protocol.subprotocol(config.version).into(),
+                    sdk_language: None,
+                    sdk_version: None,
+                    remote_address: RemoteAddress::UNKNOWN,
+                }),
+                session: <_>::default(),
                 sendtx,
                 abort_handle,
                 cancelled: AtomicBool::new(false),
@@ -97,6 +147,16 @@ impl ClientConnectionSender {
         Self::dummy_with_channel(id, config).0
     }
 
+    /// Returns a snapshot of the variables the client has set in its session.
+    pub fn session(&self) -> Arc<SessionVariables> {
+        self.session.lock().clone()
+    }
+
+    /// Sets the variable `key` in the client's session to `value`, or removes it when `value` is `None`.
+    pub fn set_session_variable(&self, key: Box<str>, value: Option<Box<str>>) -> Result<(), SessionVariableError> {
+        Arc::make_mut(&mut self.session.lock()).set(key, value)
+    }
+
     pub fn send_message(&self, message: impl Into<SerializableMessage>) -> Result<(), ClientSendError> {
         self.send(message.into())
     }
@@ -167,6 +227,7 @@ impl ClientConnection {
     pub async fn spawn<Fut>(
         id: ClientActorId,
         config: ClientConfig,
+        metadata: ClientMetadata,
         replica_id: u64,
         mut module_rx: watch::Receiver<ModuleHost>,
         actor: impl FnOnce(ClientConnection, mpsc::Receiver<SerializableMessage>) -> Fut,
@@ -179,8 +240,10 @@ impl ClientConnection {
         // logically subscribed to the database, not any particular replica. We should handle failover for
         // them and stuff. Not right now though.
         let module = module_rx.borrow_and_update().clone();
+        let connection_permit = module.replica_ctx().try_connect()?;
+        let metadata = Arc::new(metadata);
         module
-            .call_identity_connected_disconnected(id.identity, id.address, true)
+            .call_identity_connected_disconnected(id.identity, id.address, true, Some(metadata.clone()), None)
             .await?;
 
         let (sendtx, sendrx) = mpsc::channel::<SerializableMessage>(CLIENT_CHANNEL_CAPACITY);
@@ -190,6 +253,7 @@ impl ClientConnection {
         let (fut_tx, fut_rx) = oneshot::channel::<Fut>();
         // weird dance so that we can get an abort_handle into ClientConnection
         let abort_handle = tokio::spawn(async move {
+            let _connection_permit = connection_permit;
             let Ok(fut) = fut_rx.await else { return };
 
             let _gauge_guard = WORKER_METRICS.connected_clients.with_label_values(&db).inc_scope();
@@ -201,6 +265,8 @@ impl ClientConnection {
         let sender = Arc::new(ClientConnectionSender {
             id,
             config,
+            metadata,
+            session: <_>::default(),
             sendtx,
             abort_handle,
             cancelled: AtomicBool::new(false),
@@ -219,19 +285,30 @@ impl ClientConnection {
         Ok(this)
     }
 
-    pub fn dummy(
+    pub fn dummy_with_channel(
         id: ClientActorId,
         config: ClientConfig,
         replica_id: u64,
         mut module_rx: watch::Receiver<ModuleHost>,
-    ) -> Self {
+    ) -> (Self, mpsc::Receiver<SerializableMessage>) {
         let module = module_rx.borrow_and_update().clone();
-        Self {
-            sender: Arc::new(ClientConnectionSender::dummy(id, config)),
+        let (sender, rx) = ClientConnectionSender::dummy_with_channel(id, config);
+        let this = Self {
+            sender: Arc::new(sender),
             replica_id,
             module,
             module_rx,
-        }
+        };
+        (this, rx)
+    }
+
+    pub fn dummy(
+        id: ClientActorId,
+        config: ClientConfig,
+        replica_id: u64,
+        module_rx: watch::Receiver<ModuleHost>,
+    ) -> Self {
+        Self::dummy_with_channel(id, config, replica_id, module_rx).0
     }
 
     pub fn sender(&self) -> Arc<ClientConnectionSender> {
@@ -272,17 +349,87 @@ impl ClientConnection {
             CallReducerFlags::NoSuccessNotify => None,
         };
 
-        self.module
-            .call_reducer(
-                self.id.identity,
-                Some(self.id.address),
-                caller,
-                Some(request_id),
-                Some(timer),
-                reducer,
-                args,
-            )
-            .await
+        let fingerprint = self
+            .config
+            .replay_request_ids
+            .then(|| CallFingerprint::new(reducer, &args));
+        // The call owns what it needs, so that it can outlive this connection when its result is kept for retries.
+        let call = {
+            let (module, id, reducer) = (self.module.clone(), self.id, reducer.to_owned());
+            let (metadata, session) = (self.sender.metadata.clone(), self.sender.session());
+            async move {
+                module
+                    .call_reducer(
+                        id.identity,
+                        Some(id.address),
+                        caller,
+                        Some(metadata),
+                        Some(session),
+                        Some(request_id),
+                        Some(timer),
+                        &reducer,
+                        args,
+                    )
+                    .await
+            }
+        };
+        let Some(fingerprint) = fingerprint else {
+            return call.await;
+        };
+
+        let key = IdempotencyKey::Request(self.id.address, request_id);
+        let (result, replayed) = self
+            .module
+            .call_reducer_once(self.id.identity, key, fingerprint, call)
+            .await?;
+        if replayed {
+            // The update was sent for the first call, so only tell the client how it went.
+            let _ = self.send_message(self.replayed_update(reducer, request_id, &result));
+        }
+        Ok(result)
+    }
+
+    /// The message telling the client the outcome of its call to `reducer` with `request_id`,
+    /// when that is the `result` of an earlier call with the same `request_id`.
+    fn replayed_update(
+        &self,
+        reducer: &str,
+        request_id: RequestId,
+        result: &ReducerCallResult,
+    ) -> TransactionUpdateMessage {
+        let reducer_id = self
+            .module
+            .info()
+            .module_def
+            .reducer_or_alias_full(reducer)
+            .map(|(id, ..)| id);
+        let status = match &result.outcome {
+            ReducerOutcome::Committed => EventStatus::Committed(DatabaseUpdate::default()),
+            ReducerOutcome::Failed(errmsg) => EventStatus::Failed(errmsg.clone()),
+            ReducerOutcome::BudgetExceeded => EventStatus::OutOfEnergy,
+            ReducerOutcome::TimedOut => EventStatus::TimedOut,
+        };
+        let event = ModuleEvent {
+            timestamp: Timestamp::now(),
+            caller_identity: self.id.identity,
+            caller_address: Some(self.id.address),
+            function_call: ModuleFunctionCall {
+                reducer: reducer.to_owned(),
+                reducer_id: reducer_id.unwrap_or(u32::MAX.into()),
+                args: ArgsTuple::default(),
+            },
+            status,
+            energy_quanta_used: result.energy_used,
+            host_execution_duration: result.execution_duration,
+            tx_offset: result.tx_offset,
+            request_id: Some(request_id),
+            timer: None,
+        };
+        TransactionUpdateMessage {
+            event: Some(Arc::new(event)),
+            database_update: SubscriptionUpdateMessage::default_for_protocol(self.config.protocol, Some(request_id)),
+            hide_caller: false,
+        }
     }
 
     pub async fn subscribe_single(&self, subscription: SubscribeSingle, timer: Instant) -> Result<(), DBError> {
@@ -303,6 +450,33 @@ impl ClientConnection {
             .unwrap() // TODO: is unwrapping right here?
     }
 
+    pub async fn subscribe_view(
+        &self,
+        subscription: SubscribeView<ReducerArgs>,
+        timer: Instant,
+    ) -> Result<(), NoSuchModule> {
+        self.module.subscribe_view(self.sender(), subscription, timer).await
+    }
+
+    pub async fn unsubscribe_view(&self, request: Unsubscribe, timer: Instant) {
+        let me = self.clone();
+        tokio::task::spawn_blocking(move || me.module.subscriptions().views().remove(&me.sender, request, timer))
+            .await
+            .unwrap()
+    }
+
+    /// Subscribe the client to `topic`, if the module's topic authorizer lets it.
+    pub async fn subscribe_topic(&self, topic: Box<str>) -> Result<(), ReducerCallError> {
+        if self.module.authorize_topic(self.sender(), &topic).await? {
+            self.module.subscriptions().topics().subscribe(self.sender(), topic);
+        }
+        Ok(())
+    }
+
+    pub fn unsubscribe_topic(&self, topic: &str) {
+        self.module.subscriptions().topics().unsubscribe(&self.id, topic)
+    }
+
     pub async fn subscribe(&self, subscription: Subscribe, timer: Instant) -> Result<(), DBError> {
         let me = self.clone();
         tokio::task::spawn_blocking(move || {
@@ -352,6 +526,8 @@ impl ClientConnection {
     }
 
     pub async fn disconnect(self) {
-        self.module.disconnect_client(self.id).await
+        self.module
+            .disconnect_client(self.id, Some(self.sender.metadata.clone()), Some(self.sender.session()))
+            .await
     }
 }
@@ -1,10 +1,27 @@
 use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+use std::sync::Arc;
+
+use tokio::sync::watch;
+use tokio_util::sync::{CancellationToken, WaitForCancellationFutureOwned};
 
 use super::ClientName;
 
-#[derive(Default)]
 pub struct ClientActorIndex {
     client_name_auto_increment_state: AtomicU64,
+    /// Cancelled once the server starts shutting down.
+    shutdown: CancellationToken,
+    /// The number of client actors which are running.
+    running: Arc<watch::Sender<usize>>,
+}
+
+impl Default for ClientActorIndex {
+    fn default() -> Self {
+        Self {
+            client_name_auto_increment_state: <_>::default(),
+            shutdown: <_>::default(),
+            running: Arc::new(watch::channel(0).0),
+        }
+    }
 }
 
 impl ClientActorIndex {
@@ -14,4 +31,37 @@ impl ClientActorIndex {
     pub fn next_client_name(&self) -> ClientName {
         ClientName(self.client_name_auto_increment_state.fetch_add(1, Relaxed))
     }
+
+    /// Register a client actor, which runs until the returned guard is dropped.
+    pub fn register_actor(&self) -> ClientActorGuard {
+        self.running.send_modify(|running| *running += 1);
+        ClientActorGuard(self.running.clone())
+    }
+
+    /// Resolves once the server starts shutting down,
+    /// at which point client actors should close their connections.
+    pub fn shutdown_signal(&self) -> WaitForCancellationFutureOwned {
+        self.shutdown.clone().cancelled_owned()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// Tell all client actors to close their connections,
+    /// and wait until every registered actor has finished.
+    pub async fn shut_down(&self) {
+        self.shutdown.cancel();
+        let mut running = self.running.subscribe();
+        let _ = running.wait_for(|running| *running == 0).await;
+    }
+}
+
+/// Marks a client actor as running while it is held, see [`ClientActorIndex::register_actor`].
+pub struct ClientActorGuard(Arc<watch::Sender<usize>>);
+
+impl Drop for ClientActorGuard {
+    fn drop(&mut self) {
+        self.0.send_modify(|running| *running -= 1);
+    }
 }
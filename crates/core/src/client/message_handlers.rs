@@ -1,11 +1,11 @@
 use super::messages::{SubscriptionUpdateMessage, SwitchedServerMessage, ToProtocol, TransactionUpdateMessage};
-use super::{ClientConnection, DataMessage, Protocol};
+use super::{ClientConnection, DataMessage, Protocol, ProtocolVersion};
 use crate::energy::EnergyQuanta;
 use crate::execution_context::WorkloadType;
 use crate::host::module_host::{EventStatus, ModuleEvent, ModuleFunctionCall};
 use crate::host::{ReducerArgs, ReducerId, Timestamp};
 use crate::identity::Identity;
-use crate::messages::websocket::{CallReducer, ClientMessage, OneOffQuery};
+use crate::messages::websocket::{self as ws, CallReducer, ClientMessage, OneOffQuery};
 use crate::worker_metrics::WORKER_METRICS;
 use bytes::Bytes;
 use bytestring::ByteString;
@@ -44,20 +44,57 @@ pub async fn handle(client: &ClientConnection, message: DataMessage, timer: Inst
         .with_label_values(&client.replica_id, message_kind)
         .inc();
 
+    let version = client.config.version;
     let message = match message {
         DataMessage::Text(message) => {
             let message = ByteString::from(message);
             // TODO: update json clients and use the ws version
-            serde_json::from_str::<DeserializeWrapper<ClientMessage<ByteString>>>(&message)?
-                .0
-                .map_args(ReducerArgs::Json)
+            let message = match version {
+                ProtocolVersion::V1 => {
+                    serde_json::from_str::<DeserializeWrapper<ws::v1::ClientMessage<ByteString>>>(&message)?
+                        .0
+                        .into()
+                }
+                ProtocolVersion::V2 => {
+                    serde_json::from_str::<DeserializeWrapper<ClientMessage<ByteString>>>(&message)?.0
+                }
+            };
+            message.map_args(ReducerArgs::Json)
         }
         DataMessage::Binary(message_buf) => {
             let message_buf = Bytes::from(message_buf);
-            bsatn::from_slice::<ClientMessage<Bytes>>(&message_buf)?.map_args(ReducerArgs::Bsatn)
+            let message = match version {
+                ProtocolVersion::V1 => bsatn::from_slice::<ws::v1::ClientMessage<Bytes>>(&message_buf)?.into(),
+                ProtocolVersion::V2 => bsatn::from_slice::<ClientMessage<Bytes>>(&message_buf)?,
+            };
+            message.map_args(ReducerArgs::Bsatn)
         }
     };
 
+    // A failed reducer call is reported to the caller as a failed transaction update,
+    // which the caller matches to its call by the request id.
+    let request_id = match &message {
+        ClientMessage::CallReducer(call) => Some(call.request_id),
+        _ => None,
+    };
+
+    // Hold off until the transaction the client has observed is visible,
+    // so that it reads its own writes.
+    if let Some(tx_offset) = message.min_tx_offset() {
+        client
+            .module
+            .wait_for_tx_offset(tx_offset)
+            .await
+            .map_err(|e| MessageExecutionError {
+                reducer: None,
+                reducer_id: None,
+                request_id,
+                caller_identity: client.id.identity,
+                caller_address: Some(client.id.address),
+                err: e.into(),
+            })?;
+    }
+
     let address = client.module.info().database_identity;
     let res = match message {
         ClientMessage::CallReducer(CallReducer {
@@ -78,8 +115,8 @@ pub async fn handle(client: &ClientConnection, message: DataMessage, timer: Inst
                         .module
                         .info()
                         .module_def
-                        .reducer_full(&**reducer)
-                        .map(|(id, _)| id),
+                        .reducer_or_alias_full(&**reducer)
+                        .map(|(id, ..)| id),
                     e.into(),
                 )
             })
@@ -100,6 +137,38 @@ pub async fn handle(client: &ClientConnection, message: DataMessage, timer: Inst
                 .observe(timer.elapsed().as_secs_f64());
             res.map_err(|e| (None, None, e.into()))
         }
+        ClientMessage::SubscribeView(subscription) => {
+            let view = subscription.view.clone();
+            let res = client.subscribe_view(subscription, timer).await;
+            WORKER_METRICS
+                .request_round_trip
+                .with_label_values(&WorkloadType::Subscribe, &address, &view)
+                .observe(timer.elapsed().as_secs_f64());
+            res.map_err(|e| (None, None, e.into()))
+        }
+        ClientMessage::UnsubscribeView(request) => {
+            client.unsubscribe_view(request, timer).await;
+            WORKER_METRICS
+                .request_round_trip
+                .with_label_values(&WorkloadType::Unsubscribe, &address, "")
+                .observe(timer.elapsed().as_secs_f64());
+            Ok(())
+        }
+        ClientMessage::SubscribeTopic(request) => {
+            let res = client.subscribe_topic(request.topic).await;
+            WORKER_METRICS
+                .request_round_trip
+                .with_label_values(&WorkloadType::Subscribe, &address, "")
+                .observe(timer.elapsed().as_secs_f64());
+            res.map_err(|e| (None, None, e.into()))
+        }
+        ClientMessage::UnsubscribeTopic(request) => {
+            client.unsubscribe_topic(&request.topic);
+            Ok(())
+        }
+        ClientMessage::SetSessionVariable(request) => client
+            .set_session_variable(request.key, request.value)
+            .map_err(|e| (None, None, e.into())),
         ClientMessage::Subscribe(subscription) => {
             let res = client.subscribe(subscription, timer).await;
             WORKER_METRICS
@@ -111,6 +180,7 @@ pub async fn handle(client: &ClientConnection, message: DataMessage, timer: Inst
         ClientMessage::OneOffQuery(OneOffQuery {
             query_string: query,
             message_id,
+            min_tx_offset: _,
         }) => {
             let res = match client.config.protocol {
                 Protocol::Binary => client.one_off_query_bsatn(&query, &message_id, timer),
@@ -126,6 +196,7 @@ pub async fn handle(client: &ClientConnection, message: DataMessage, timer: Inst
     res.map_err(|(reducer, reducer_id, err)| MessageExecutionError {
         reducer: reducer.cloned(),
         reducer_id,
+        request_id,
         caller_identity: client.id.identity,
         caller_address: Some(client.id.address),
         err,
@@ -139,6 +210,7 @@ pub async fn handle(client: &ClientConnection, message: DataMessage, timer: Inst
 pub struct MessageExecutionError {
     pub reducer: Option<Box<str>>,
     pub reducer_id: Option<ReducerId>,
+    pub request_id: Option<RequestId>,
     pub caller_identity: Identity,
     pub caller_address: Option<Address>,
     #[source]
@@ -159,7 +231,8 @@ impl MessageExecutionError {
             status: EventStatus::Failed(format!("{:#}", self.err)),
             energy_quanta_used: EnergyQuanta::ZERO,
             host_execution_duration: Duration::ZERO,
-            request_id: Some(RequestId::default()),
+            tx_offset: None,
+            request_id: Some(self.request_id.unwrap_or_default()),
             timer: None,
         }
     }
@@ -168,9 +241,11 @@ impl MessageExecutionError {
 impl ToProtocol for MessageExecutionError {
     type Encoded = SwitchedServerMessage;
     fn to_protocol(self, protocol: super::Protocol) -> Self::Encoded {
+        let request_id = self.request_id;
         TransactionUpdateMessage {
             event: Some(Arc::new(self.into_event())),
-            database_update: SubscriptionUpdateMessage::default_for_protocol(protocol, None),
+            database_update: SubscriptionUpdateMessage::default_for_protocol(protocol, request_id),
+            hide_caller: false,
         }
         .to_protocol(protocol)
     }
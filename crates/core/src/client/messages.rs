@@ -1,6 +1,6 @@
-use super::{ClientConfig, DataMessage, Protocol};
+use super::{ClientConfig, DataMessage, Protocol, ProtocolVersion};
 use crate::execution_context::WorkloadType;
-use crate::host::module_host::{EventStatus, ModuleEvent};
+use crate::host::module_host::{EventStatus, ModuleEvent, ReducerErrorValue};
 use crate::host::ArgsTuple;
 use crate::messages::websocket as ws;
 use derive_more::From;
@@ -10,9 +10,9 @@ use spacetimedb_client_api_messages::websocket::{
 };
 use spacetimedb_lib::identity::RequestId;
 use spacetimedb_lib::ser::serde::SerializeWrapper;
-use spacetimedb_lib::Address;
+use spacetimedb_lib::{Address, Identity};
 use spacetimedb_primitives::TableId;
-use spacetimedb_sats::bsatn;
+use spacetimedb_sats::{bsatn, ser::Serialize, ProductValue};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -27,38 +27,54 @@ pub trait ToProtocol {
 pub(super) type SwitchedServerMessage = FormatSwitch<ws::ServerMessage<BsatnFormat>, ws::ServerMessage<JsonFormat>>;
 pub(super) type SwitchedDbUpdate = FormatSwitch<ws::DatabaseUpdate<BsatnFormat>, ws::DatabaseUpdate<JsonFormat>>;
 
-/// Serialize `msg` into a [`DataMessage`] containing a [`ws::ServerMessage`].
+/// Serialize `msg` into a [`DataMessage`] containing a [`ws::ServerMessage`],
+/// laid out according to the version of the protocol the client speaks.
 ///
 /// If `protocol` is [`Protocol::Binary`],
 /// the message will be conditionally compressed by this method according to `compression`.
 pub fn serialize(msg: impl ToProtocol<Encoded = SwitchedServerMessage>, config: ClientConfig) -> DataMessage {
+    match (msg.to_protocol(config.protocol), config.version) {
+        (FormatSwitch::Json(msg), ProtocolVersion::V2) => serialize_json(msg),
+        (FormatSwitch::Json(msg), ProtocolVersion::V1) => serialize_json(into_v1(msg)),
+        (FormatSwitch::Bsatn(msg), ProtocolVersion::V2) => serialize_bsatn(msg, config.compression),
+        (FormatSwitch::Bsatn(msg), ProtocolVersion::V1) => serialize_bsatn(into_v1(msg), config.compression),
+    }
+}
+
+/// Converts `msg` to version 1 of the protocol.
+fn into_v1<F: WebsocketFormat>(msg: ws::ServerMessage<F>) -> ws::v1::ServerMessage<F> {
+    ws::v1::ServerMessage::try_from(msg)
+        .unwrap_or_else(|_| unreachable!("version 1 clients can't request messages added in later versions"))
+}
+
+fn serialize_json(msg: impl Serialize) -> DataMessage {
+    serde_json::to_string(&SerializeWrapper::new(msg)).unwrap().into()
+}
+
+fn serialize_bsatn(msg: impl Serialize, compression: Compression) -> DataMessage {
     // TODO(centril, perf): here we are allocating buffers only to throw them away eventually.
     // Consider pooling these allocations so that we reuse them.
-    match msg.to_protocol(config.protocol) {
-        FormatSwitch::Json(msg) => serde_json::to_string(&SerializeWrapper::new(msg)).unwrap().into(),
-        FormatSwitch::Bsatn(msg) => {
-            // First write the tag so that we avoid shifting the entire message at the end.
-            let mut msg_bytes = vec![SERVER_MSG_COMPRESSION_TAG_NONE];
-            bsatn::to_writer(&mut msg_bytes, &msg).unwrap();
-
-            // Conditionally compress the message.
-            let srv_msg = &msg_bytes[1..];
-            let msg_bytes = match ws::decide_compression(srv_msg.len(), config.compression) {
-                Compression::None => msg_bytes,
-                Compression::Brotli => {
-                    let mut out = vec![SERVER_MSG_COMPRESSION_TAG_BROTLI];
-                    ws::brotli_compress(srv_msg, &mut out);
-                    out
-                }
-                Compression::Gzip => {
-                    let mut out = vec![SERVER_MSG_COMPRESSION_TAG_GZIP];
-                    ws::gzip_compress(srv_msg, &mut out);
-                    out
-                }
-            };
-            msg_bytes.into()
+
+    // First write the tag so that we avoid shifting the entire message at the end.
+    let mut msg_bytes = vec![SERVER_MSG_COMPRESSION_TAG_NONE];
+    bsatn::to_writer(&mut msg_bytes, &msg).unwrap();
+
+    // Conditionally compress the message.
+    let srv_msg = &msg_bytes[1..];
+    let msg_bytes = match ws::decide_compression(srv_msg.len(), compression) {
+        Compression::None => msg_bytes,
+        Compression::Brotli => {
+            let mut out = vec![SERVER_MSG_COMPRESSION_TAG_BROTLI];
+            ws::brotli_compress(srv_msg, &mut out);
+            out
         }
-    }
+        Compression::Gzip => {
+            let mut out = vec![SERVER_MSG_COMPRESSION_TAG_GZIP];
+            ws::gzip_compress(srv_msg, &mut out);
+            out
+        }
+    };
+    msg_bytes.into()
 }
 
 #[derive(Debug, From)]
@@ -67,8 +83,11 @@ pub enum SerializableMessage {
     QueryText(OneOffQueryResponseMessage<JsonFormat>),
     Identity(IdentityTokenMessage),
     Subscribe(SubscriptionUpdateMessage),
+    SubscribeChunk(SubscriptionChunkMessage),
     Subscription(SubscriptionMessage),
     TxUpdate(TransactionUpdateMessage),
+    ViewUpdate(ViewUpdateMessage),
+    Topic(TopicMessage),
 }
 
 impl SerializableMessage {
@@ -77,23 +96,29 @@ impl SerializableMessage {
             Self::QueryBinary(msg) => Some(msg.num_rows()),
             Self::QueryText(msg) => Some(msg.num_rows()),
             Self::Subscribe(msg) => Some(msg.num_rows()),
+            Self::SubscribeChunk(msg) => Some(msg.num_rows()),
             Self::Subscription(msg) => Some(msg.num_rows()),
             Self::TxUpdate(msg) => Some(msg.num_rows()),
-            Self::Identity(_) => None,
+            Self::ViewUpdate(msg) => Some(msg.num_rows()),
+            Self::Identity(_) | Self::Topic(_) => None,
         }
     }
 
     pub fn workload(&self) -> Option<WorkloadType> {
         match self {
             Self::QueryBinary(_) | Self::QueryText(_) => Some(WorkloadType::Sql),
-            Self::Subscribe(_) => Some(WorkloadType::Subscribe),
+            Self::Subscribe(_) | Self::SubscribeChunk(_) => Some(WorkloadType::Subscribe),
             Self::Subscription(msg) => match &msg.result {
                 SubscriptionResult::Subscribe(_) => Some(WorkloadType::Subscribe),
                 SubscriptionResult::Unsubscribe(_) => Some(WorkloadType::Unsubscribe),
                 SubscriptionResult::Error(_) => None,
             },
             Self::TxUpdate(_) => Some(WorkloadType::Update),
-            Self::Identity(_) => None,
+            Self::ViewUpdate(msg) => match msg.timer {
+                Some(_) => Some(WorkloadType::Subscribe),
+                None => Some(WorkloadType::Update),
+            },
+            Self::Identity(_) | Self::Topic(_) => None,
         }
     }
 }
@@ -106,8 +131,11 @@ impl ToProtocol for SerializableMessage {
             SerializableMessage::QueryText(msg) => msg.to_protocol(protocol),
             SerializableMessage::Identity(msg) => msg.to_protocol(protocol),
             SerializableMessage::Subscribe(msg) => msg.to_protocol(protocol),
+            SerializableMessage::SubscribeChunk(msg) => msg.to_protocol(protocol),
             SerializableMessage::TxUpdate(msg) => msg.to_protocol(protocol),
             SerializableMessage::Subscription(msg) => msg.to_protocol(protocol),
+            SerializableMessage::ViewUpdate(msg) => msg.to_protocol(protocol),
+            SerializableMessage::Topic(msg) => msg.to_protocol(protocol),
         }
     }
 }
@@ -124,12 +152,27 @@ impl ToProtocol for IdentityTokenMessage {
     }
 }
 
+pub type TopicMessage = ws::TopicMessage;
+
+impl ToProtocol for TopicMessage {
+    type Encoded = SwitchedServerMessage;
+    fn to_protocol(self, protocol: Protocol) -> Self::Encoded {
+        match protocol {
+            Protocol::Text => FormatSwitch::Json(ws::ServerMessage::TopicMessage(self)),
+            Protocol::Binary => FormatSwitch::Bsatn(ws::ServerMessage::TopicMessage(self)),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TransactionUpdateMessage {
     /// The event that caused this update.
     /// When `None`, this is a light update.
     pub event: Option<Arc<ModuleEvent>>,
     pub database_update: SubscriptionUpdateMessage,
+    /// Whether to withhold who called the reducer from the recipient,
+    /// who is then not the caller.
+    pub hide_caller: bool,
 }
 
 impl TransactionUpdateMessage {
@@ -143,9 +186,11 @@ impl ToProtocol for TransactionUpdateMessage {
     fn to_protocol(self, protocol: Protocol) -> Self::Encoded {
         fn convert<F: WebsocketFormat>(
             event: Option<Arc<ModuleEvent>>,
+            hide_caller: bool,
             request_id: u32,
             update: ws::DatabaseUpdate<F>,
             conv_args: impl FnOnce(&ArgsTuple) -> F::Single,
+            conv_error: impl FnOnce(&ReducerErrorValue) -> F::Single,
         ) -> ws::ServerMessage<F> {
             let Some(event) = event else {
                 return ws::ServerMessage::TransactionUpdateLight(ws::TransactionUpdateLight { request_id, update });
@@ -154,15 +199,26 @@ impl ToProtocol for TransactionUpdateMessage {
             let status = match &event.status {
                 EventStatus::Committed(_) => ws::UpdateStatus::Committed(update),
                 EventStatus::Failed(errmsg) => ws::UpdateStatus::Failed(errmsg.clone().into()),
+                EventStatus::FailedWithValue(error) => ws::UpdateStatus::FailedWithValue(ws::ReducerErrorValue {
+                    message: error.message.clone().into(),
+                    value: conv_error(error),
+                }),
                 EventStatus::OutOfEnergy => ws::UpdateStatus::OutOfEnergy,
+                // Clients learn of timeouts as failures, so the protocol stays the same.
+                EventStatus::TimedOut => ws::UpdateStatus::Failed("reducer exceeded its maximum duration".into()),
             };
 
             let args = conv_args(&event.function_call.args);
+            let (caller_identity, caller_address) = if hide_caller {
+                (Identity::ZERO, None)
+            } else {
+                (event.caller_identity, event.caller_address)
+            };
 
             let tx_update = ws::TransactionUpdate {
                 timestamp: event.timestamp,
                 status,
-                caller_identity: event.caller_identity,
+                caller_identity,
                 reducer_call: ws::ReducerCallInfo {
                     reducer_name: event.function_call.reducer.to_owned().into(),
                     reducer_id: event.function_call.reducer_id.into(),
@@ -171,23 +227,42 @@ impl ToProtocol for TransactionUpdateMessage {
                 },
                 energy_quanta_used: event.energy_quanta_used,
                 host_execution_duration_micros: event.host_execution_duration.as_micros() as u64,
-                caller_address: event.caller_address.unwrap_or(Address::ZERO),
+                caller_address: caller_address.unwrap_or(Address::ZERO),
+                tx_offset: event.tx_offset,
             };
 
             ws::ServerMessage::TransactionUpdate(tx_update)
         }
 
-        let TransactionUpdateMessage { event, database_update } = self;
+        let TransactionUpdateMessage {
+            event,
+            database_update,
+            hide_caller,
+        } = self;
         let update = database_update.database_update;
         protocol.assert_matches_format_switch(&update);
-        let request_id = database_update.request_id.unwrap_or(0);
+        let request_id = if hide_caller {
+            0
+        } else {
+            database_update.request_id.unwrap_or(0)
+        };
         match update {
-            FormatSwitch::Bsatn(update) => FormatSwitch::Bsatn(convert(event, request_id, update, |args| {
-                Vec::from(args.get_bsatn().clone()).into()
-            })),
-            FormatSwitch::Json(update) => {
-                FormatSwitch::Json(convert(event, request_id, update, |args| args.get_json().clone()))
-            }
+            FormatSwitch::Bsatn(update) => FormatSwitch::Bsatn(convert(
+                event,
+                hide_caller,
+                request_id,
+                update,
+                |args| Vec::from(args.get_bsatn().clone()).into(),
+                |error| Vec::from(error.get_bsatn().clone()).into(),
+            )),
+            FormatSwitch::Json(update) => FormatSwitch::Json(convert(
+                event,
+                hide_caller,
+                request_id,
+                update,
+                |args| args.get_json().clone(),
+                |error| error.get_json().clone(),
+            )),
         }
     }
 }
@@ -253,6 +328,58 @@ impl ToProtocol for SubscriptionUpdateMessage {
     }
 }
 
+/// A part of the initial rows of a legacy subscription, which the client asked to receive in chunks.
+/// The last part is sent as a [`SubscriptionUpdateMessage`].
+#[derive(Debug, Clone)]
+pub struct SubscriptionChunkMessage {
+    pub database_update: SwitchedDbUpdate,
+    pub request_id: RequestId,
+    /// The number of rows sent so far, including those of this chunk.
+    pub rows_sent: u64,
+    pub total_rows: u64,
+}
+
+impl SubscriptionChunkMessage {
+    fn num_rows(&self) -> usize {
+        match &self.database_update {
+            FormatSwitch::Bsatn(x) => x.num_rows(),
+            FormatSwitch::Json(x) => x.num_rows(),
+        }
+    }
+}
+
+impl ToProtocol for SubscriptionChunkMessage {
+    type Encoded = SwitchedServerMessage;
+    fn to_protocol(self, protocol: Protocol) -> Self::Encoded {
+        let Self {
+            request_id,
+            rows_sent,
+            total_rows,
+            ..
+        } = self;
+
+        protocol.assert_matches_format_switch(&self.database_update);
+        match self.database_update {
+            FormatSwitch::Bsatn(database_update) => FormatSwitch::Bsatn(ws::ServerMessage::InitialSubscriptionChunk(
+                ws::InitialSubscriptionChunk {
+                    database_update,
+                    request_id,
+                    rows_sent,
+                    total_rows,
+                },
+            )),
+            FormatSwitch::Json(database_update) => FormatSwitch::Json(ws::ServerMessage::InitialSubscriptionChunk(
+                ws::InitialSubscriptionChunk {
+                    database_update,
+                    request_id,
+                    rows_sent,
+                    total_rows,
+                },
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SubscriptionRows {
     pub table_id: TableId,
@@ -404,6 +531,47 @@ impl ToProtocol for SubscriptionMessage {
     }
 }
 
+/// Changes to the result of a view a client is subscribed to.
+#[derive(Debug)]
+pub struct ViewUpdateMessage {
+    /// Set when this update responds to a request from the client.
+    pub timer: Option<Instant>,
+    pub request_id: RequestId,
+    pub query_id: ws::QueryId,
+    pub view: Box<str>,
+    pub inserts: Vec<ProductValue>,
+    pub deletes: Vec<ProductValue>,
+}
+
+impl ViewUpdateMessage {
+    fn num_rows(&self) -> usize {
+        self.inserts.len() + self.deletes.len()
+    }
+
+    fn into_format<F: WebsocketFormat>(self) -> ws::ServerMessage<F> {
+        let (inserts, _) = F::encode_list(self.inserts.iter());
+        let (deletes, _) = F::encode_list(self.deletes.iter());
+        ws::ServerMessage::ViewUpdate(ws::ViewUpdate {
+            request_id: self.request_id,
+            total_host_execution_duration_micros: self.timer.map_or(0, |t| t.elapsed().as_micros() as u64),
+            query_id: self.query_id,
+            view: self.view,
+            inserts,
+            deletes,
+        })
+    }
+}
+
+impl ToProtocol for ViewUpdateMessage {
+    type Encoded = SwitchedServerMessage;
+    fn to_protocol(self, protocol: Protocol) -> Self::Encoded {
+        match protocol {
+            Protocol::Binary => FormatSwitch::Bsatn(self.into_format()),
+            Protocol::Text => FormatSwitch::Json(self.into_format()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct OneOffQueryResponseMessage<F: WebsocketFormat> {
     pub message_id: Vec<u8>,
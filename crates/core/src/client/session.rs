@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+/// The variables a client has set in the session of its connection,
+/// which the reducers it calls may read.
+///
+/// Sessions are meant for a few small strings, e.g., the client's locale or build,
+/// so their number and size are bounded.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SessionVariables {
+    vars: BTreeMap<Box<str>, Box<str>>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum SessionVariableError {
+    #[error("session variable key is {0} bytes long, but may be at most {max} bytes long", max = SessionVariables::MAX_KEY_LEN)]
+    KeyTooLong(usize),
+    #[error("session variable value is {0} bytes long, but may be at most {max} bytes long", max = SessionVariables::MAX_VALUE_LEN)]
+    ValueTooLong(usize),
+    #[error("a session may have at most {max} variables", max = SessionVariables::MAX_VARIABLES)]
+    TooManyVariables,
+}
+
+impl SessionVariables {
+    /// The maximum number of variables in a session.
+    pub const MAX_VARIABLES: usize = 32;
+    /// The maximum length in bytes of the key of a variable.
+    pub const MAX_KEY_LEN: usize = 64;
+    /// The maximum length in bytes of the value of a variable.
+    pub const MAX_VALUE_LEN: usize = 1024;
+
+    /// Returns the value of the variable `key`, if set.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.vars.get(key).map(|value| &**value)
+    }
+
+    /// Sets the variable `key` to `value`, or removes it when `value` is `None`.
+    ///
+    /// Fails, leaving the session unchanged, if the variable would exceed the session's limits.
+    pub fn set(&mut self, key: Box<str>, value: Option<Box<str>>) -> Result<(), SessionVariableError> {
+        let Some(value) = value else {
+            self.vars.remove(&key);
+            return Ok(());
+        };
+        if key.len() > Self::MAX_KEY_LEN {
+            return Err(SessionVariableError::KeyTooLong(key.len()));
+        }
+        if value.len() > Self::MAX_VALUE_LEN {
+            return Err(SessionVariableError::ValueTooLong(value.len()));
+        }
+        if self.vars.len() >= Self::MAX_VARIABLES && !self.vars.contains_key(&key) {
+            return Err(SessionVariableError::TooManyVariables);
+        }
+        self.vars.insert(key, value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_get_and_remove() {
+        let mut session = SessionVariables::default();
+        assert_eq!(session.get("locale"), None);
+
+        session.set("locale".into(), Some("fr".into())).unwrap();
+        assert_eq!(session.get("locale"), Some("fr"));
+
+        session.set("locale".into(), Some("de".into())).unwrap();
+        assert_eq!(session.get("locale"), Some("de"));
+
+        session.set("locale".into(), None).unwrap();
+        assert_eq!(session.get("locale"), None);
+    }
+
+    #[test]
+    fn limits() {
+        let mut session = SessionVariables::default();
+        let long = "x".repeat(SessionVariables::MAX_VALUE_LEN + 1);
+        assert_eq!(
+            session.set(long[..SessionVariables::MAX_KEY_LEN + 1].into(), Some("".into())),
+            Err(SessionVariableError::KeyTooLong(SessionVariables::MAX_KEY_LEN + 1))
+        );
+        assert_eq!(
+            session.set("key".into(), Some(long.into())),
+            Err(SessionVariableError::ValueTooLong(SessionVariables::MAX_VALUE_LEN + 1))
+        );
+
+        for i in 0..SessionVariables::MAX_VARIABLES {
+            session.set(i.to_string().into(), Some("".into())).unwrap();
+        }
+        assert_eq!(
+            session.set("one_too_many".into(), Some("".into())),
+            Err(SessionVariableError::TooManyVariables)
+        );
+        // Existing variables can still be overwritten.
+        session.set("0".into(), Some("value".into())).unwrap();
+        assert_eq!(session.get("0"), Some("value"));
+        assert_eq!(session.get("one_too_many"), None);
+    }
+}
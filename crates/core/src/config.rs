@@ -1,10 +1,19 @@
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use std::{fmt, io};
 
 use toml;
 use toml_edit;
 
-use spacetimedb_lib::Address;
+use crate::db::relational_db::SnapshotPolicy;
+use anyhow::Context as _;
+use ipnet::IpNet;
+use spacetimedb_durability::local::ArchiveOptions;
+use spacetimedb_durability::s3::{S3Archive, S3Config};
+use spacetimedb_lib::{Address, Identity};
 use spacetimedb_paths::cli::{ConfigDir, PrivKeyPath, PubKeyPath};
 use spacetimedb_paths::server::{ConfigToml, MetadataTomlPath};
 
@@ -60,13 +69,35 @@ impl fmt::Display for MetadataFile {
     }
 }
 
+/// The contents of a server's `config.toml`.
+///
+/// When the server is told to reload its configuration, e.g. by `SIGHUP`,
+/// the settings of `logs`, `quotas`, `auth`, `durability.snapshot-policy`
+/// and `memory` take effect immediately, including for running databases,
+/// and the certificate files named by `tls` are read again.
+/// Other `durability` settings apply to databases launched afterwards,
+/// and the remaining settings only take effect when the server is restarted.
 #[derive(serde::Deserialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct ConfigFile {
+    #[serde(default)]
+    pub server: ServerConfig,
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
     #[serde(default)]
     pub certificate_authority: Option<CertificateAuthority>,
     #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
     pub logs: LogConfig,
+    #[serde(default)]
+    pub quotas: QuotaConfig,
+    #[serde(default)]
+    pub durability: DurabilityConfig,
+    #[serde(default)]
+    pub memory: MemoryConfig,
 }
 
 impl ConfigFile {
@@ -75,6 +106,134 @@ impl ConfigFile {
     }
 }
 
+/// Settings for how the server accepts connections.
+///
+/// Command-line arguments take precedence over these.
+#[serde_with::serde_as]
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct ServerConfig {
+    /// The address and port to listen for connections on, e.g. `"0.0.0.0:3000"`,
+    /// or `unix:` followed by the path of a unix socket to listen on, e.g. `"unix:/run/spacetimedb.sock"`.
+    pub listen_addr: Option<String>,
+    /// The networks of the reverse proxies trusted to name the client
+    /// in the `Forwarded` and `X-Forwarded-For` headers of the requests they pass on, e.g. `["10.0.0.0/8"]`.
+    ///
+    /// Clients connected over a unix socket are always trusted.
+    /// Defaults to the loopback addresses, so that a proxy on the same machine is trusted.
+    #[serde_as(as = "Vec<serde_with::DisplayFromStr>")]
+    #[serde(default = "ServerConfig::default_trusted_proxies")]
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+impl ServerConfig {
+    fn default_trusted_proxies() -> Vec<IpNet> {
+        vec!["127.0.0.0/8".parse().unwrap(), "::1/128".parse().unwrap()]
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_addr: None,
+            trusted_proxies: Self::default_trusted_proxies(),
+        }
+    }
+}
+
+/// Settings for how the server shuts down when it receives `SIGTERM` or `SIGINT`.
+///
+/// The server stops accepting connections, asks its WebSocket clients to disconnect,
+/// waits for the requests and reducer calls in progress to finish,
+/// and waits for the committed transactions to be written to the commitlog before exiting.
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct ShutdownConfig {
+    /// How long to wait for clients to disconnect and requests to finish,
+    /// and again for the commitlog to be written, before exiting regardless.
+    #[serde(default = "ShutdownConfig::default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Capture a snapshot of each running database before exiting,
+    /// so that less of the commitlog needs to be replayed when it is next launched.
+    #[serde(default)]
+    pub snapshot: bool,
+}
+
+impl ShutdownConfig {
+    fn default_timeout_secs() -> u64 {
+        30
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: Self::default_timeout_secs(),
+            snapshot: false,
+        }
+    }
+}
+
+/// Settings for serving HTTPS and secure WebSockets directly,
+/// rather than behind a reverse proxy which terminates TLS.
+///
+/// The certificate is either read from `cert_path` and `key_path`,
+/// or obtained and renewed automatically through `acme`.
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct TlsConfig {
+    /// The PEM file with the certificate chain to serve, leaf certificate first.
+    pub cert_path: Option<PathBuf>,
+    /// The PEM file with the private key of the certificate.
+    pub key_path: Option<PathBuf>,
+    pub acme: Option<AcmeConfig>,
+}
+
+/// Settings for obtaining a certificate from an ACME certificate authority, such as Let's Encrypt.
+///
+/// The certificate authority verifies that the server controls each domain
+/// by requesting a token from it over plain HTTP, on port 80 of that domain.
+#[derive(serde::Deserialize, Clone, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct AcmeConfig {
+    /// The domains the certificate is for, which must all resolve to this server.
+    pub domains: Vec<String>,
+    /// How the certificate authority can reach the server's operator, e.g. `["mailto:admin@example.com"]`.
+    #[serde(default)]
+    pub contact: Vec<String>,
+    /// The directory URL of the certificate authority.
+    #[serde(default = "AcmeConfig::default_directory_url")]
+    pub directory_url: String,
+    /// The address and port to answer the certificate authority's challenges on.
+    #[serde(default = "AcmeConfig::default_challenge_listen_addr")]
+    pub challenge_listen_addr: String,
+}
+
+impl AcmeConfig {
+    fn default_directory_url() -> String {
+        "https://acme-v02.api.letsencrypt.org/directory".to_owned()
+    }
+
+    fn default_challenge_listen_addr() -> String {
+        "0.0.0.0:80".to_owned()
+    }
+}
+
+/// Settings for which clients the server accepts.
+#[derive(serde::Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct AuthConfig {
+    /// The OpenID Connect issuers whose tokens are accepted, e.g. `["https://auth.example.com"]`.
+    ///
+    /// Tokens signed by the server itself are always accepted.
+    /// If not set, tokens from any issuer are accepted.
+    pub allowed_issuers: Option<Vec<String>>,
+}
+
 #[derive(serde::Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct CertificateAuthority {
@@ -105,6 +264,300 @@ pub struct LogConfig {
     pub directives: Vec<String>,
 }
 
+/// Per-database resource limits, applied to every database hosted by this server.
+///
+/// A limit which is not set is unlimited.
+#[serde_with::serde_as]
+#[derive(serde::Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct QuotaConfig {
+    /// The maximum number of websocket clients connected to a database at once.
+    ///
+    /// Further clients are refused until one of the connected clients disconnects.
+    pub max_connections: Option<u32>,
+    /// The maximum number of reducer calls from clients which may be waiting for or running in a database at once.
+    ///
+    /// Further calls are refused until one of the pending calls finishes.
+    pub max_reducer_queue_depth: Option<u32>,
+    /// The maximum size in bytes of a database's in-memory data.
+    ///
+    /// Reducer calls from clients are refused,
+    /// and transactions which insert rows fail,
+    /// while the database is over this limit.
+    pub max_memory_bytes: Option<u64>,
+    /// The maximum size in bytes of a database's commitlog and module logs.
+    ///
+    /// Reducer calls from clients are refused,
+    /// and transactions which insert rows fail,
+    /// while the database is over this limit.
+    pub max_disk_bytes: Option<u64>,
+    /// The maximum total size in bytes of a database's tables, commitlog, and snapshots.
+    ///
+    /// Transactions which insert rows fail while the database is over this limit,
+    /// so that it can still shrink by deleting rows.
+    pub max_size_bytes: Option<u64>,
+    /// The maximum energy a single reducer call may use, which bounds its CPU time.
+    ///
+    /// A reducer which runs out of energy is aborted and its transaction rolled back.
+    pub max_reducer_energy: Option<u64>,
+    /// The maximum wall-clock time a single reducer or view call may run for, in milliseconds.
+    ///
+    /// A reducer which runs for longer is aborted and its transaction rolled back,
+    /// as if it had run out of energy.
+    pub max_reducer_duration_ms: Option<u64>,
+    /// The maximum time in milliseconds a database may spend running reducers and views per minute,
+    /// which bounds its share of the host's CPUs.
+    ///
+    /// Reducer calls from clients are refused, and scheduled reducers are put off,
+    /// while the database is over this limit.
+    /// Time used beyond the limit is paid back at the rate of the limit,
+    /// so a database can exceed it briefly, but not on average.
+    pub max_cpu_ms_per_minute: Option<u64>,
+    /// Limits for individual databases, keyed by database identity,
+    /// which take precedence over the limits above.
+    #[serde_as(as = "BTreeMap<serde_with::DisplayFromStr, _>")]
+    #[serde(default)]
+    pub databases: BTreeMap<Identity, DatabaseQuotaConfig>,
+}
+
+/// Resource limits for a single database.
+#[derive(serde::Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct DatabaseQuotaConfig {
+    /// See [`QuotaConfig::max_connections`].
+    pub max_connections: Option<u32>,
+    /// See [`QuotaConfig::max_reducer_queue_depth`].
+    pub max_reducer_queue_depth: Option<u32>,
+    /// See [`QuotaConfig::max_cpu_ms_per_minute`].
+    pub max_cpu_ms_per_minute: Option<u64>,
+}
+
+impl QuotaConfig {
+    /// No limits at all.
+    pub const UNLIMITED: Self = Self {
+        max_connections: None,
+        max_reducer_queue_depth: None,
+        max_memory_bytes: None,
+        max_disk_bytes: None,
+        max_size_bytes: None,
+        max_reducer_energy: None,
+        max_reducer_duration_ms: None,
+        max_cpu_ms_per_minute: None,
+        databases: BTreeMap::new(),
+    };
+
+    /// The limits which apply to the database `database_identity`.
+    pub fn for_database(&self, database_identity: &Identity) -> Self {
+        let db = self.databases.get(database_identity).copied().unwrap_or_default();
+        Self {
+            max_connections: db.max_connections.or(self.max_connections),
+            max_reducer_queue_depth: db.max_reducer_queue_depth.or(self.max_reducer_queue_depth),
+            max_cpu_ms_per_minute: db.max_cpu_ms_per_minute.or(self.max_cpu_ms_per_minute),
+            databases: BTreeMap::new(),
+            ..self.clone()
+        }
+    }
+
+    /// The maximum time a single reducer call may run for, if limited.
+    pub fn max_reducer_duration(&self) -> Option<Duration> {
+        self.max_reducer_duration_ms.map(Duration::from_millis)
+    }
+
+    /// The maximum time a database may spend running reducers and views per minute, if limited.
+    pub fn max_cpu_per_minute(&self) -> Option<Duration> {
+        self.max_cpu_ms_per_minute.map(Duration::from_millis)
+    }
+}
+
+/// Durability settings for the databases hosted by this server.
+#[serde_with::serde_as]
+#[derive(serde::Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct DurabilityConfig {
+    /// The longest a committed transaction waits before being synced to disk, in milliseconds.
+    ///
+    /// Transactions committed within this window are synced together by a single fsync,
+    /// so a longer window trades commit latency for throughput.
+    /// If not set, the commitlog's default is used.
+    pub group_commit_window_ms: Option<u64>,
+    /// When databases capture snapshots of their state by themselves,
+    /// e.g. `{ every-txs = 1000000 }`, `{ every-minutes = 60 }` or `"on-demand"`.
+    ///
+    /// If not set, a snapshot is captured every [`SNAPSHOT_FREQUENCY`] transactions.
+    ///
+    /// [`SNAPSHOT_FREQUENCY`]: crate::db::relational_db::SNAPSHOT_FREQUENCY
+    pub snapshot_policy: Option<SnapshotPolicy>,
+    /// The name of the durability backend which databases persist their transactions with,
+    /// among those registered with the server, e.g. `"local"` or `"local-nfs"`.
+    ///
+    /// If not set, [`Self::DEFAULT_BACKEND`] is used.
+    pub backend: Option<Box<str>>,
+    /// Settings for individual databases, keyed by database identity,
+    /// which take precedence over the settings above.
+    #[serde_as(as = "BTreeMap<serde_with::DisplayFromStr, _>")]
+    #[serde(default)]
+    pub databases: BTreeMap<Identity, DatabaseDurabilityConfig>,
+    /// Where to archive sealed commitlog segments, if anywhere.
+    ///
+    /// If not set, the whole commitlog is kept on local disk.
+    pub archive: Option<ArchiveConfig>,
+}
+
+/// Durability settings for a single database.
+#[derive(serde::Deserialize, Clone, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct DatabaseDurabilityConfig {
+    /// See [`DurabilityConfig::group_commit_window_ms`].
+    pub group_commit_window_ms: Option<u64>,
+    /// See [`DurabilityConfig::snapshot_policy`].
+    pub snapshot_policy: Option<SnapshotPolicy>,
+    /// See [`DurabilityConfig::backend`].
+    pub backend: Option<Box<str>>,
+}
+
+impl DurabilityConfig {
+    /// Use the defaults for every database.
+    pub const DEFAULT: Self = Self {
+        group_commit_window_ms: None,
+        snapshot_policy: None,
+        backend: None,
+        databases: BTreeMap::new(),
+        archive: None,
+    };
+
+    /// The durability backend databases use unless configured otherwise,
+    /// which keeps their commitlog on local disk.
+    pub const DEFAULT_BACKEND: &'static str = "local";
+
+    /// The group commit window configured for the database `database_identity`, if any.
+    pub fn group_commit_window(&self, database_identity: &Identity) -> Option<Duration> {
+        self.databases
+            .get(database_identity)
+            .and_then(|db| db.group_commit_window_ms)
+            .or(self.group_commit_window_ms)
+            .map(Duration::from_millis)
+    }
+
+    /// The snapshot policy configured for the database `database_identity`.
+    pub fn snapshot_policy(&self, database_identity: &Identity) -> SnapshotPolicy {
+        self.databases
+            .get(database_identity)
+            .and_then(|db| db.snapshot_policy)
+            .or(self.snapshot_policy)
+            .unwrap_or_default()
+    }
+
+    /// The name of the durability backend configured for the database `database_identity`.
+    pub fn backend(&self, database_identity: &Identity) -> &str {
+        self.databases
+            .get(database_identity)
+            .and_then(|db| db.backend.as_deref())
+            .or(self.backend.as_deref())
+            .unwrap_or(Self::DEFAULT_BACKEND)
+    }
+
+    /// Where to archive the commitlog of the replica `replica_id` of the database `database_identity`,
+    /// if archival is configured.
+    ///
+    /// Must be called from within a tokio runtime, which performs the archive's requests.
+    pub fn archive(&self, database_identity: &Identity, replica_id: u64) -> anyhow::Result<Option<ArchiveOptions>> {
+        self.archive
+            .as_ref()
+            .map(|archive| archive.options(database_identity, replica_id))
+            .transpose()
+    }
+}
+
+/// Settings for archiving sealed commitlog segments to an S3-compatible object store.
+///
+/// Sealed segments are uploaded in the background, after which they are removed from local disk,
+/// and downloaded again when they are needed to replay the commitlog.
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ArchiveConfig {
+    /// The base URL of the object store, e.g. `https://s3.us-east-1.amazonaws.com`.
+    pub endpoint: String,
+    /// The region of the bucket, e.g. `us-east-1`.
+    pub region: String,
+    /// The bucket to store segments in.
+    pub bucket: String,
+    /// The prefix of the keys of archived segments.
+    ///
+    /// The segments of each database replica are stored under `{prefix}{database_identity}/{replica_id}/`.
+    #[serde(default)]
+    pub prefix: String,
+    /// The access key to use. If not set, the `AWS_ACCESS_KEY_ID` environment variable is used.
+    pub access_key_id: Option<String>,
+    /// The secret key to use. If not set, the `AWS_SECRET_ACCESS_KEY` environment variable is used.
+    pub secret_access_key: Option<String>,
+    /// How often to check for sealed segments which are not archived yet, in seconds.
+    #[serde(default = "ArchiveConfig::default_interval_secs")]
+    pub interval_secs: u64,
+    /// How many of the newest sealed segments to keep on local disk after archiving them.
+    #[serde(default)]
+    pub keep_local_segments: usize,
+}
+
+impl ArchiveConfig {
+    fn default_interval_secs() -> u64 {
+        60
+    }
+
+    fn options(&self, database_identity: &Identity, replica_id: u64) -> anyhow::Result<ArchiveOptions> {
+        let credential = |value: &Option<String>, var: &str| match value {
+            Some(value) => Ok(value.clone()),
+            None => std::env::var(var).with_context(|| format!("commitlog archive: {var} is not set")),
+        };
+        let config = S3Config {
+            endpoint: self.endpoint.clone(),
+            region: self.region.clone(),
+            bucket: self.bucket.clone(),
+            prefix: format!("{}{database_identity}/{replica_id}/", self.prefix),
+            access_key_id: credential(&self.access_key_id, "AWS_ACCESS_KEY_ID")?,
+            secret_access_key: credential(&self.secret_access_key, "AWS_SECRET_ACCESS_KEY")?,
+        };
+        Ok(ArchiveOptions {
+            archive: Arc::new(S3Archive::new(config, tokio::runtime::Handle::current())),
+            interval: Duration::from_secs(self.interval_secs),
+            keep_local_segments: self.keep_local_segments,
+        })
+    }
+}
+
+impl fmt::Debug for ArchiveConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArchiveConfig")
+            .field("endpoint", &self.endpoint)
+            .field("region", &self.region)
+            .field("bucket", &self.bucket)
+            .field("prefix", &self.prefix)
+            .field("access_key_id", &self.access_key_id)
+            .field("interval_secs", &self.interval_secs)
+            .field("keep_local_segments", &self.keep_local_segments)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Settings for how the databases hosted by this server hold their data in memory.
+#[derive(serde::Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub struct MemoryConfig {
+    /// Compress the pages of tables which have not been modified in this many transactions.
+    ///
+    /// Idle tables are compressed in the background every few seconds, not as transactions commit.
+    /// Compressed pages are decompressed again when they are next accessed,
+    /// trading CPU time for memory, so that large, mostly idle databases fit on smaller hosts.
+    /// If not set, pages are never compressed.
+    pub compress_idle_tables_after_txs: Option<NonZeroU64>,
+}
+
+impl MemoryConfig {
+    /// Never compress pages.
+    pub const DEFAULT: Self = Self {
+        compress_idle_tables_after_txs: None,
+    };
+}
+
 /// Update the value of a key in a `TOML` document, preserving the formatting and comments of the original value.
 ///
 /// ie:
@@ -151,3 +604,82 @@ pub fn set_table_opt_value(table: &mut toml_edit::Table, key: &str, value: Optio
         table.remove(key);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_commit_window_per_database() {
+        let overridden = Identity::from_byte_array([1; 32]);
+        let other_settings = Identity::from_byte_array([2; 32]);
+        let config: ConfigFile = toml::from_str(&format!(
+            "[durability]
+            group-commit-window-ms = 20
+
+            [durability.databases.{overridden}]
+            group-commit-window-ms = 5
+
+            [durability.databases.{other_settings}]
+            backend = \"local\""
+        ))
+        .unwrap();
+        let durability = config.durability;
+        assert_eq!(
+            durability.group_commit_window(&overridden),
+            Some(Duration::from_millis(5))
+        );
+        for database_identity in [other_settings, Identity::ZERO] {
+            assert_eq!(
+                durability.group_commit_window(&database_identity),
+                Some(Duration::from_millis(20))
+            );
+        }
+
+        // Without any setting, the commitlog's default applies.
+        assert_eq!(DurabilityConfig::DEFAULT.group_commit_window(&overridden), None);
+        let config: ConfigFile = toml::from_str("").unwrap();
+        assert_eq!(config.durability.group_commit_window(&overridden), None);
+    }
+
+    #[test]
+    fn cpu_quota_per_database() {
+        let overridden = Identity::from_byte_array([1; 32]);
+        let config: ConfigFile = toml::from_str(&format!(
+            "[quotas]
+            max-cpu-ms-per-minute = 30000
+
+            [quotas.databases.{overridden}]
+            max-cpu-ms-per-minute = 60000"
+        ))
+        .unwrap();
+        assert_eq!(
+            config.quotas.for_database(&overridden).max_cpu_per_minute(),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            config.quotas.for_database(&Identity::ZERO).max_cpu_per_minute(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(QuotaConfig::UNLIMITED.max_cpu_per_minute(), None);
+    }
+
+    #[test]
+    fn backend_per_database() {
+        let overridden = Identity::from_byte_array([1; 32]);
+        let config: ConfigFile = toml::from_str(&format!(
+            "[durability]
+            backend = \"remote\"
+
+            [durability.databases.{overridden}]
+            backend = \"local-nfs\""
+        ))
+        .unwrap();
+        assert_eq!(config.durability.backend(&overridden), "local-nfs");
+        assert_eq!(config.durability.backend(&Identity::ZERO), "remote");
+        assert_eq!(
+            DurabilityConfig::DEFAULT.backend(&overridden),
+            DurabilityConfig::DEFAULT_BACKEND
+        );
+    }
+}
@@ -81,6 +81,10 @@ pub struct BacktraceFrame<'a> {
     pub module_name: Option<&'a str>,
     #[serde_as(as = "Option<DemangleSymbol>")]
     pub func_name: Option<&'a str>,
+    /// The source file of the frame, if the module retains DWARF debug info.
+    pub filename: Option<&'a str>,
+    pub line_number: Option<u32>,
+    pub column: Option<u32>,
 }
 
 struct DemangleSymbol;
@@ -0,0 +1,545 @@
+//! Large objects ("blobs") stored in dedicated blob tables.
+//!
+//! A blob table is an ordinary module table with exactly the columns
+//! `(blob_id: u64, chunk: u32, data: Vec<u8>)`.
+//! Each blob is stored as a run of rows sharing a `blob_id`,
+//! one per [`BLOB_CHUNK_SIZE`]d chunk, numbered from `0`.
+//! Every chunk but the last is exactly [`BLOB_CHUNK_SIZE`] bytes long.
+//!
+//! Rows referring to a blob store only its `blob_id`,
+//! so they stay small and cheap to send to subscribers,
+//! while the blob itself is written and read piecewise by the functions in this module.
+//! Declaring a btree index on `(blob_id, chunk)` makes chunk lookups fast,
+//! but is not required.
+
+use super::datastore::locking_tx_datastore::state_view::StateView;
+use super::relational_db::{MutTx, RelationalDB, Tx};
+use crate::error::DBError;
+use crate::sql::compiler::compile_sql_for_caller;
+use crate::sql::execute::execute_sql_tx;
+use spacetimedb_lib::identity::AuthCtx;
+use spacetimedb_primitives::{col_list, ColId, TableId};
+use spacetimedb_sats::{bsatn, product, AlgebraicType, AlgebraicValue, ArrayValue};
+use spacetimedb_schema::schema::TableSchema;
+use spacetimedb_table::table::RowRef;
+use std::io;
+use thiserror::Error;
+
+/// The number of bytes stored in each full chunk of a blob.
+pub const BLOB_CHUNK_SIZE: usize = 64 * 1024;
+
+const BLOB_ID_COL: ColId = ColId(0);
+const CHUNK_COL: ColId = ColId(1);
+const DATA_COL: ColId = ColId(2);
+
+#[derive(Error, Debug)]
+pub enum BlobError {
+    #[error("table `{0}` is not a blob table; expected columns `(blob_id: u64, chunk: u32, data: Vec<u8>)`")]
+    NotABlobTable(Box<str>),
+    #[error("offset {offset} is past the end of blob {blob_id}, which is {len} bytes long")]
+    OutOfBounds { blob_id: u64, offset: u64, len: u64 },
+    #[error(transparent)]
+    Db(#[from] DBError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Checks that `schema` has the column layout of a blob table.
+pub fn check_blob_table(schema: &TableSchema) -> Result<(), BlobError> {
+    let expected = [AlgebraicType::U64, AlgebraicType::U32, AlgebraicType::bytes()];
+    let columns = schema.columns();
+    if columns.len() == expected.len() && columns.iter().zip(&expected).all(|(col, ty)| col.col_type == *ty) {
+        Ok(())
+    } else {
+        Err(BlobError::NotABlobTable(schema.table_name.clone()))
+    }
+}
+
+/// Read-side access to the chunks of the blobs in one blob table,
+/// abstracting over the kind of transaction.
+trait ChunkSource {
+    /// Returns the number of chunks stored for `blob_id`.
+    fn chunk_count(&self, blob_id: u64) -> Result<u64, DBError>;
+
+    /// Returns the contents of chunk number `chunk` of `blob_id`, if it exists.
+    fn chunk(&self, blob_id: u64, chunk: u32) -> Result<Option<Box<[u8]>>, DBError>;
+}
+
+struct Chunks<'a, T> {
+    stdb: &'a RelationalDB,
+    tx: &'a T,
+    table_id: TableId,
+}
+
+fn chunk_key(blob_id: u64, chunk: u32) -> AlgebraicValue {
+    AlgebraicValue::product([AlgebraicValue::U64(blob_id), AlgebraicValue::U32(chunk)])
+}
+
+fn read_chunk_data(row: RowRef<'_>) -> Box<[u8]> {
+    match row.read_col::<ArrayValue>(DATA_COL) {
+        Ok(ArrayValue::U8(data)) => data,
+        _ => unreachable!("blob table layout was checked"),
+    }
+}
+
+impl ChunkSource for Chunks<'_, MutTx> {
+    fn chunk_count(&self, blob_id: u64) -> Result<u64, DBError> {
+        let blob_id = AlgebraicValue::U64(blob_id);
+        let iter = self
+            .stdb
+            .iter_by_col_eq_mut(self.tx, self.table_id, BLOB_ID_COL, &blob_id)?;
+        Ok(iter.count() as u64)
+    }
+
+    fn chunk(&self, blob_id: u64, chunk: u32) -> Result<Option<Box<[u8]>>, DBError> {
+        let key = chunk_key(blob_id, chunk);
+        let mut iter = self
+            .stdb
+            .iter_by_col_eq_mut(self.tx, self.table_id, col_list![BLOB_ID_COL, CHUNK_COL], &key)?;
+        Ok(iter.next().map(read_chunk_data))
+    }
+}
+
+impl ChunkSource for Chunks<'_, Tx> {
+    fn chunk_count(&self, blob_id: u64) -> Result<u64, DBError> {
+        let blob_id = AlgebraicValue::U64(blob_id);
+        let iter = self
+            .stdb
+            .iter_by_col_eq(self.tx, self.table_id, BLOB_ID_COL, &blob_id)?;
+        Ok(iter.count() as u64)
+    }
+
+    fn chunk(&self, blob_id: u64, chunk: u32) -> Result<Option<Box<[u8]>>, DBError> {
+        let key = chunk_key(blob_id, chunk);
+        let mut iter = self
+            .stdb
+            .iter_by_col_eq(self.tx, self.table_id, col_list![BLOB_ID_COL, CHUNK_COL], &key)?;
+        Ok(iter.next().map(read_chunk_data))
+    }
+}
+
+fn len(src: &impl ChunkSource, blob_id: u64) -> Result<u64, DBError> {
+    let count = src.chunk_count(blob_id)?;
+    let Some(last) = count.checked_sub(1) else {
+        return Ok(0);
+    };
+    let last_len = src.chunk(blob_id, last as u32)?.map_or(0, |data| data.len());
+    Ok(last * BLOB_CHUNK_SIZE as u64 + last_len as u64)
+}
+
+/// Passes up to `max_len` bytes of the blob `blob_id`, starting at `offset`, to `sink`,
+/// as slices of one chunk at most.
+fn read_with<E: From<DBError>>(
+    src: &impl ChunkSource,
+    blob_id: u64,
+    offset: u64,
+    max_len: u64,
+    mut sink: impl FnMut(&[u8]) -> Result<(), E>,
+) -> Result<(), E> {
+    let mut remaining = max_len;
+    let mut chunk = (offset / BLOB_CHUNK_SIZE as u64) as u32;
+    let mut start = (offset % BLOB_CHUNK_SIZE as u64) as usize;
+    while remaining > 0 {
+        let Some(data) = src.chunk(blob_id, chunk)? else { break };
+        let Some(rest) = data.get(start..) else { break };
+        let n = rest.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        sink(&rest[..n])?;
+        remaining -= n as u64;
+        if data.len() < BLOB_CHUNK_SIZE {
+            // Only the last chunk is short.
+            break;
+        }
+        chunk += 1;
+        start = 0;
+    }
+    Ok(())
+}
+
+fn read(src: &impl ChunkSource, blob_id: u64, offset: u64, max_len: usize) -> Result<Vec<u8>, DBError> {
+    let mut buf = Vec::new();
+    read_with(src, blob_id, offset, max_len as u64, |data| {
+        buf.extend_from_slice(data);
+        Ok::<_, DBError>(())
+    })?;
+    Ok(buf)
+}
+
+fn blob_table<'a, T>(
+    stdb: &'a RelationalDB,
+    tx: &'a T,
+    table_id: TableId,
+    schema: &TableSchema,
+) -> Result<Chunks<'a, T>, BlobError> {
+    check_blob_table(schema)?;
+    Ok(Chunks { stdb, tx, table_id })
+}
+
+/// Returns the length in bytes of the blob `blob_id` in the blob table `table_id`.
+///
+/// A blob which was never written has length `0`.
+pub fn blob_len_mut(stdb: &RelationalDB, tx: &MutTx, table_id: TableId, blob_id: u64) -> Result<u64, BlobError> {
+    let schema = stdb.schema_for_table_mut(tx, table_id)?;
+    Ok(len(&blob_table(stdb, tx, table_id, &schema)?, blob_id)?)
+}
+
+/// Returns the length in bytes of the blob `blob_id` in the blob table `table_id`.
+///
+/// A blob which was never written has length `0`.
+pub fn blob_len(stdb: &RelationalDB, tx: &Tx, table_id: TableId, blob_id: u64) -> Result<u64, BlobError> {
+    let schema = stdb.schema_for_table(tx, table_id)?;
+    Ok(len(&blob_table(stdb, tx, table_id, &schema)?, blob_id)?)
+}
+
+/// Reads up to `max_len` bytes of the blob `blob_id` in the blob table `table_id`, starting at `offset`.
+///
+/// Returns fewer than `max_len` bytes only if the end of the blob was reached.
+pub fn read_blob_mut(
+    stdb: &RelationalDB,
+    tx: &MutTx,
+    table_id: TableId,
+    blob_id: u64,
+    offset: u64,
+    max_len: usize,
+) -> Result<Vec<u8>, BlobError> {
+    let schema = stdb.schema_for_table_mut(tx, table_id)?;
+    Ok(read(
+        &blob_table(stdb, tx, table_id, &schema)?,
+        blob_id,
+        offset,
+        max_len,
+    )?)
+}
+
+/// Reads up to `max_len` bytes of the blob `blob_id` in the blob table `table_id`, starting at `offset`.
+///
+/// Returns fewer than `max_len` bytes only if the end of the blob was reached.
+pub fn read_blob(
+    stdb: &RelationalDB,
+    tx: &Tx,
+    table_id: TableId,
+    blob_id: u64,
+    offset: u64,
+    max_len: usize,
+) -> Result<Vec<u8>, BlobError> {
+    let schema = stdb.schema_for_table(tx, table_id)?;
+    Ok(read(
+        &blob_table(stdb, tx, table_id, &schema)?,
+        blob_id,
+        offset,
+        max_len,
+    )?)
+}
+
+/// Writes up to `max_len` bytes of the blob `blob_id` in the blob table `table_id`, starting at `offset`, to `out`,
+/// one chunk at a time, so that the bytes are never all held in memory at once.
+///
+/// Writes fewer than `max_len` bytes only if the end of the blob was reached.
+pub fn copy_blob(
+    stdb: &RelationalDB,
+    tx: &Tx,
+    table_id: TableId,
+    blob_id: u64,
+    offset: u64,
+    max_len: u64,
+    out: &mut impl io::Write,
+) -> Result<(), BlobError> {
+    let schema = stdb.schema_for_table(tx, table_id)?;
+    read_with(
+        &blob_table(stdb, tx, table_id, &schema)?,
+        blob_id,
+        offset,
+        max_len,
+        |data| Ok(out.write_all(data)?),
+    )
+}
+
+/// Returns whether `auth.caller` may read the blob `blob_id` in the blob table `schema`,
+/// which is the case if the caller owns the database,
+/// or sees every chunk of the blob through the row level security filters of the table, if any.
+///
+/// Whether the caller may read the table at all, i.e. whether it is public, is not checked.
+pub fn is_blob_visible(
+    stdb: &RelationalDB,
+    tx: &Tx,
+    auth: AuthCtx,
+    schema: &TableSchema,
+    blob_id: u64,
+) -> Result<bool, BlobError> {
+    let chunks = blob_table(stdb, tx, schema.table_id, schema)?;
+    if auth.caller == auth.owner || tx.row_level_security_for_table_id(schema.table_id)?.is_empty() {
+        return Ok(true);
+    }
+    let columns = schema.columns();
+    let (blob_id_col, chunk_col) = (&columns[BLOB_ID_COL.idx()].col_name, &columns[CHUNK_COL.idx()].col_name);
+    let sql = format!(
+        "SELECT {chunk_col} FROM {} WHERE {blob_id_col} = {blob_id}",
+        schema.table_name
+    );
+    let ast = compile_sql_for_caller(stdb, &auth, tx, &sql)?;
+    let visible = execute_sql_tx(stdb, tx, &sql, ast, auth)?
+        .unwrap_or_default()
+        .iter()
+        .map(|result| result.data.len() as u64)
+        .sum::<u64>();
+    Ok(visible == chunks.chunk_count(blob_id)?)
+}
+
+/// Writes `data` into the blob `blob_id` in the blob table `table_id`, starting at `offset`,
+/// overwriting existing bytes and growing the blob as needed.
+///
+/// Errors with [`BlobError::OutOfBounds`] if `offset` is past the end of the blob,
+/// as blobs cannot have holes.
+pub fn write_blob(
+    stdb: &RelationalDB,
+    tx: &mut MutTx,
+    table_id: TableId,
+    blob_id: u64,
+    offset: u64,
+    data: &[u8],
+) -> Result<(), BlobError> {
+    let schema = stdb.schema_for_table_mut(tx, table_id)?;
+    let blob_len = len(&blob_table(stdb, &*tx, table_id, &schema)?, blob_id)?;
+    if offset > blob_len {
+        return Err(BlobError::OutOfBounds {
+            blob_id,
+            offset,
+            len: blob_len,
+        });
+    }
+
+    let mut written = 0;
+    while written < data.len() {
+        let pos = offset + written as u64;
+        let chunk = (pos / BLOB_CHUNK_SIZE as u64) as u32;
+        let start = (pos % BLOB_CHUNK_SIZE as u64) as usize;
+        let n = (data.len() - written).min(BLOB_CHUNK_SIZE - start);
+
+        // Replace the existing chunk, if any, with one which has `data` spliced in.
+        let key = chunk_key(blob_id, chunk);
+        let existing = stdb
+            .iter_by_col_eq_mut(tx, table_id, col_list![BLOB_ID_COL, CHUNK_COL], &key)?
+            .next()
+            .map(|row| (row.pointer(), read_chunk_data(row)));
+        let mut contents = match existing {
+            Some((ptr, contents)) => {
+                stdb.delete(tx, table_id, [ptr]);
+                contents.into_vec()
+            }
+            None => Vec::new(),
+        };
+        if contents.len() < start + n {
+            contents.resize(start + n, 0);
+        }
+        contents[start..start + n].copy_from_slice(&data[written..written + n]);
+
+        let row = product![blob_id, chunk, AlgebraicValue::Bytes(contents.into())];
+        stdb.insert(tx, table_id, &bsatn::to_vec(&row).unwrap())?;
+
+        written += n;
+    }
+    Ok(())
+}
+
+/// Deletes all chunks of the blob `blob_id` in the blob table `table_id`,
+/// returning the number of chunks deleted.
+pub fn delete_blob(stdb: &RelationalDB, tx: &mut MutTx, table_id: TableId, blob_id: u64) -> Result<u32, BlobError> {
+    let schema = stdb.schema_for_table_mut(tx, table_id)?;
+    check_blob_table(&schema)?;
+    let blob_id_val = AlgebraicValue::U64(blob_id);
+    let chunks = stdb
+        .iter_by_col_eq_mut(tx, table_id, BLOB_ID_COL, &blob_id_val)?
+        .map(|row| row.pointer())
+        .collect::<Vec<_>>();
+    Ok(stdb.delete(tx, table_id, chunks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::relational_db::tests_utils::TestDB;
+    use crate::execution_context::Workload;
+    use spacetimedb_lib::error::ResultTest;
+    use spacetimedb_lib::Identity;
+    use spacetimedb_schema::schema::RowLevelSecuritySchema;
+
+    fn blob_table_for_test(db: &RelationalDB) -> ResultTest<TableId> {
+        let schema = [
+            ("blob_id", AlgebraicType::U64),
+            ("chunk", AlgebraicType::U32),
+            ("data", AlgebraicType::bytes()),
+        ];
+        Ok(db.create_table_for_test_multi_column("blobs", &schema, col_list![0, 1])?)
+    }
+
+    fn write(db: &RelationalDB, table_id: TableId, blob_id: u64, offset: u64, data: &[u8]) -> Result<(), BlobError> {
+        db.with_auto_commit(Workload::ForTests, |tx| {
+            write_blob(db, tx, table_id, blob_id, offset, data)
+        })
+    }
+
+    /// Returns the contents of the blob, and the lengths of its chunks.
+    fn contents(db: &RelationalDB, table_id: TableId, blob_id: u64) -> ResultTest<(Vec<u8>, Vec<usize>)> {
+        db.with_read_only(Workload::ForTests, |tx| {
+            let data = read_blob(db, tx, table_id, blob_id, 0, usize::MAX)?;
+            let chunks = Chunks { stdb: db, tx, table_id };
+            let chunk_lens = (0..chunks.chunk_count(blob_id)? as u32)
+                .map(|chunk| Ok(chunks.chunk(blob_id, chunk)?.unwrap().len()))
+                .collect::<Result<_, DBError>>()?;
+            Ok((data, chunk_lens))
+        })
+    }
+
+    /// Returns `len` bytes which differ from chunk to chunk.
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn splits_blobs_into_chunks() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let table_id = blob_table_for_test(&db)?;
+        let data = pattern(2 * BLOB_CHUNK_SIZE + 10);
+        write(&db, table_id, 1, 0, &data)?;
+
+        let (contents, chunk_lens) = contents(&db, table_id, 1)?;
+        assert_eq!(contents, data);
+        assert_eq!(chunk_lens, [BLOB_CHUNK_SIZE, BLOB_CHUNK_SIZE, 10]);
+        db.with_read_only(Workload::ForTests, |tx| -> ResultTest<()> {
+            assert_eq!(blob_len(&db, tx, table_id, 1)?, data.len() as u64);
+            // Reads across chunk boundaries, and past the end of the blob.
+            let offset = BLOB_CHUNK_SIZE as u64 - 5;
+            assert_eq!(
+                read_blob(&db, tx, table_id, 1, offset, 10)?,
+                data[offset as usize..][..10]
+            );
+            let offset = 2 * BLOB_CHUNK_SIZE as u64 + 5;
+            assert_eq!(read_blob(&db, tx, table_id, 1, offset, 100)?, data[offset as usize..]);
+            assert_eq!(read_blob(&db, tx, table_id, 2, 0, 100)?, Vec::<u8>::new());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn splices_writes_into_existing_chunks() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let table_id = blob_table_for_test(&db)?;
+        let mut data = pattern(2 * BLOB_CHUNK_SIZE + 10);
+        write(&db, table_id, 1, 0, &data)?;
+
+        // Overwrite the end of the first chunk and the start of the second.
+        let offset = BLOB_CHUNK_SIZE - 3;
+        write(&db, table_id, 1, offset as u64, &[0xff; 6])?;
+        data[offset..offset + 6].fill(0xff);
+        assert_eq!(
+            contents(&db, table_id, 1)?,
+            (data.clone(), vec![BLOB_CHUNK_SIZE, BLOB_CHUNK_SIZE, 10])
+        );
+
+        // Overwrite the end of the last chunk, growing it.
+        let offset = data.len() - 2;
+        write(&db, table_id, 1, offset as u64, &[0xee; 4])?;
+        data.truncate(offset);
+        data.extend([0xee; 4]);
+        assert_eq!(
+            contents(&db, table_id, 1)?,
+            (data.clone(), vec![BLOB_CHUNK_SIZE, BLOB_CHUNK_SIZE, 12])
+        );
+
+        // Append, filling up the last chunk and starting a new one.
+        let appended = pattern(BLOB_CHUNK_SIZE);
+        write(&db, table_id, 1, data.len() as u64, &appended)?;
+        data.extend(appended);
+        assert_eq!(
+            contents(&db, table_id, 1)?,
+            (data, vec![BLOB_CHUNK_SIZE, BLOB_CHUNK_SIZE, BLOB_CHUNK_SIZE, 12])
+        );
+
+        // Other blobs are untouched.
+        write(&db, table_id, 2, 0, b"other")?;
+        assert_eq!(contents(&db, table_id, 2)?, (b"other".to_vec(), vec![5]));
+        Ok(())
+    }
+
+    #[test]
+    fn rejects_writes_leaving_holes() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let table_id = blob_table_for_test(&db)?;
+        write(&db, table_id, 1, 0, b"abc")?;
+
+        let err = write(&db, table_id, 1, 4, b"d").unwrap_err();
+        assert!(matches!(
+            err,
+            BlobError::OutOfBounds {
+                blob_id: 1,
+                offset: 4,
+                len: 3
+            }
+        ));
+        // Writing right at the end appends.
+        write(&db, table_id, 1, 3, b"d")?;
+        assert_eq!(contents(&db, table_id, 1)?.0, b"abcd");
+        Ok(())
+    }
+
+    #[test]
+    fn deletes_every_chunk() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let table_id = blob_table_for_test(&db)?;
+        write(&db, table_id, 1, 0, &pattern(BLOB_CHUNK_SIZE + 1))?;
+        write(&db, table_id, 2, 0, b"kept")?;
+
+        let deleted = db.with_auto_commit(Workload::ForTests, |tx| delete_blob(&db, tx, table_id, 1))?;
+        assert_eq!(deleted, 2);
+        assert_eq!(contents(&db, table_id, 1)?, (vec![], vec![]));
+        assert_eq!(contents(&db, table_id, 2)?.0, b"kept");
+        Ok(())
+    }
+
+    #[test]
+    fn copies_blobs_to_writers() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let table_id = blob_table_for_test(&db)?;
+        let data = pattern(3 * BLOB_CHUNK_SIZE);
+        write(&db, table_id, 1, 0, &data)?;
+
+        db.with_read_only(Workload::ForTests, |tx| -> ResultTest<()> {
+            let mut out = Vec::new();
+            copy_blob(&db, tx, table_id, 1, 10, u64::MAX, &mut out)?;
+            assert_eq!(out, data[10..]);
+            out.clear();
+            copy_blob(&db, tx, table_id, 1, 10, 2 * BLOB_CHUNK_SIZE as u64, &mut out)?;
+            assert_eq!(out, data[10..][..2 * BLOB_CHUNK_SIZE]);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn hides_blobs_filtered_by_row_level_security() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let table_id = blob_table_for_test(&db)?;
+        write(&db, table_id, 1, 0, &pattern(BLOB_CHUNK_SIZE + 1))?;
+        write(&db, table_id, 2, 0, &pattern(BLOB_CHUNK_SIZE + 1))?;
+
+        let owner = Identity::from_hashing_bytes("owner");
+        let client = Identity::from_hashing_bytes("client");
+        let is_visible = |auth, blob_id| {
+            db.with_read_only(Workload::ForTests, |tx| {
+                let schema = db.schema_for_table(tx, table_id)?;
+                is_blob_visible(&db, tx, auth, &schema, blob_id)
+            })
+        };
+
+        // Without row level security, every blob of a table is visible.
+        assert!(is_visible(AuthCtx::new(owner, client), 2)?);
+
+        db.with_auto_commit(Workload::ForTests, |tx| {
+            // Shows all of blob 1, but only the first chunk of blob 2.
+            let sql = "SELECT * FROM blobs WHERE blob_id = 1 OR chunk = 0".into();
+            db.create_row_level_security(tx, RowLevelSecuritySchema { table_id, sql })
+        })?;
+        assert!(is_visible(AuthCtx::new(owner, client), 1)?);
+        assert!(!is_visible(AuthCtx::new(owner, client), 2)?);
+        assert!(is_visible(AuthCtx::new(owner, owner), 2)?);
+        Ok(())
+    }
+}
@@ -13,12 +13,13 @@ use crate::{
     db::{
         datastore::{
             system_tables::{
-                system_tables, StColumnRow, StConstraintData, StConstraintRow, StIndexAlgorithm, StIndexRow,
-                StSequenceRow, StTableFields, StTableRow, SystemTable, ST_CLIENT_ID, ST_CLIENT_IDX, ST_COLUMN_ID,
-                ST_COLUMN_IDX, ST_COLUMN_NAME, ST_CONSTRAINT_ID, ST_CONSTRAINT_IDX, ST_CONSTRAINT_NAME, ST_INDEX_ID,
-                ST_INDEX_IDX, ST_INDEX_NAME, ST_MODULE_ID, ST_MODULE_IDX, ST_RESERVED_SEQUENCE_RANGE,
-                ST_ROW_LEVEL_SECURITY_ID, ST_ROW_LEVEL_SECURITY_IDX, ST_SCHEDULED_ID, ST_SCHEDULED_IDX, ST_SEQUENCE_ID,
-                ST_SEQUENCE_IDX, ST_SEQUENCE_NAME, ST_TABLE_ID, ST_TABLE_IDX, ST_VAR_ID, ST_VAR_IDX,
+                system_table_schema, system_tables, StColumnRow, StConstraintData, StConstraintRow, StIndexAlgorithm,
+                StIndexRow, StSequenceRow, StTableDurabilityRow, StTableFields, StTableRow, SystemTable, ST_CLIENT_IDX,
+                ST_COLUMN_ID, ST_COLUMN_IDX, ST_CONNECTION_IDX, ST_CONSTRAINT_ID, ST_CONSTRAINT_IDX, ST_COUNTER_IDX,
+                ST_GENERATED_COLUMN_IDX, ST_INDEX_ID, ST_INDEX_IDX, ST_MODULE_IDX, ST_RESERVED_SEQUENCE_RANGE,
+                ST_ROW_HISTORY_IDX, ST_ROW_LEVEL_SECURITY_IDX, ST_SCHEDULED_IDX, ST_SEQUENCE_ID, ST_SEQUENCE_IDX,
+                ST_SOFT_DELETE_IDX, ST_SUBSCRIPTION_IDX, ST_TABLE_DURABILITY_ID, ST_TABLE_DURABILITY_IDX, ST_TABLE_ID,
+                ST_TABLE_IDX, ST_VAR_IDX,
             },
             traits::TxData,
         },
@@ -32,19 +33,21 @@ use core::ops::RangeBounds;
 use itertools::Itertools;
 use spacetimedb_data_structures::map::{HashSet, IntMap};
 use spacetimedb_lib::{
-    db::auth::{StAccess, StTableType},
+    db::auth::{StDurability, StTableType},
     Identity,
 };
 use spacetimedb_primitives::{ColList, ColSet, IndexId, TableId};
 use spacetimedb_sats::{AlgebraicValue, ProductValue};
 use spacetimedb_schema::schema::TableSchema;
+use spacetimedb_snapshot::ReconstructedSnapshot;
 use spacetimedb_table::{
     blob_store::{BlobStore, HashMapBlobStore},
     indexes::{RowPointer, SquashedOffset},
     table::{IndexScanIter, InsertError, RowRef, Table, TableAndIndex},
     MemoryUsage,
 };
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::BTreeMap;
+use std::num::NonZeroU64;
 use std::sync::Arc;
 
 /// Contains the live, in-memory snapshot of a database. This structure
@@ -59,6 +62,13 @@ pub struct CommittedState {
     pub(crate) blob_store: HashMapBlobStore,
     /// Provides fast lookup for index id -> an index.
     pub(super) index_id_map: IndexIdMap,
+    /// If set, the pages of tables which have not been modified in this many transactions are compressed.
+    pub(super) compress_idle_tables_after: Option<NonZeroU64>,
+    /// The number of transactions merged into the committed state.
+    num_merged_txs: u64,
+    /// The value of `num_merged_txs` when each table was last modified or compressed.
+    /// Tables which are absent have not been either since the committed state was created.
+    table_idle_since: IntMap<TableId, u64>,
 }
 
 impl MemoryUsage for CommittedState {
@@ -68,8 +78,16 @@ impl MemoryUsage for CommittedState {
             tables,
             blob_store,
             index_id_map,
+            compress_idle_tables_after: _,
+            num_merged_txs,
+            table_idle_since,
         } = self;
-        next_tx_offset.heap_usage() + tables.heap_usage() + blob_store.heap_usage() + index_id_map.heap_usage()
+        next_tx_offset.heap_usage()
+            + tables.heap_usage()
+            + blob_store.heap_usage()
+            + index_id_map.heap_usage()
+            + num_merged_txs.heap_usage()
+            + table_idle_since.heap_usage()
     }
 }
 
@@ -144,30 +162,50 @@ impl CommittedState {
     /// Extremely delicate function to bootstrap the system tables.
     /// Don't update this unless you know what you're doing.
     pub(super) fn bootstrap_system_tables(&mut self, database_identity: Identity) -> Result<()> {
-        // NOTE: the `rdb_num_table_rows` metric is used by the query optimizer,
-        // and therefore has performance implications and must not be disabled.
-        let with_label_values = |table_id: TableId, table_name: &str| {
-            DB_METRICS
-                .rdb_num_table_rows
-                .with_label_values(&database_identity, &table_id.0, table_name)
-        };
+        self.bootstrap_system_tables_where(|_| true)?;
+        self.set_system_table_metrics(database_identity);
+        Ok(())
+    }
 
+    /// Creates the system tables missing from a committed state restored from a snapshot,
+    /// which happens when the snapshot was taken before those system tables were introduced,
+    /// along with their rows in `st_table`, `st_column` and friends.
+    ///
+    /// Does not record metrics, see [`Self::set_system_table_metrics`].
+    pub(super) fn bootstrap_missing_system_tables(&mut self) -> Result<()> {
+        let missing = system_tables()
+            .iter()
+            .map(|schema| schema.table_id)
+            .filter(|table_id| !self.tables.contains_key(table_id))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        log::info!("creating system tables missing from snapshot: {missing:?}");
+        self.bootstrap_system_tables_where(|table_id| missing.contains(&table_id))
+    }
+
+    /// Creates the system tables for which `include` returns true,
+    /// and inserts the rows describing them into `st_table`, `st_column` and friends.
+    ///
+    /// The IDs of the indexes, constraints and sequences of a system table
+    /// don't depend on which tables are included,
+    /// so that system tables bootstrapped into an existing database get the same IDs as in a new database.
+    /// This holds as long as system tables introduced later have greater table IDs than the existing ones,
+    /// and no sequences.
+    fn bootstrap_system_tables_where(&mut self, include: impl Fn(TableId) -> bool) -> Result<()> {
         let schemas = system_tables().map(Arc::new);
         let ref_schemas = schemas.each_ref().map(|s| &**s);
 
         // Insert the table row into st_tables, creating st_tables if it's missing.
         let (st_tables, blob_store) = self.get_table_and_blob_store_or_create(ST_TABLE_ID, &schemas[ST_TABLE_IDX]);
         // Insert the table row into `st_tables` for all system tables
-        for schema in ref_schemas {
-            let table_id = schema.table_id;
-            // Metric for this system table.
-            with_label_values(table_id, &schema.table_name).set(0);
-
+        for schema in ref_schemas.iter().filter(|x| include(x.table_id)) {
             let row = StTableRow {
-                table_id,
+                table_id: schema.table_id,
                 table_name: schema.table_name.clone(),
                 table_type: StTableType::System,
-                table_access: StAccess::Public,
+                table_access: schema.table_access,
                 table_primary_key: schema.primary_key.map(Into::into),
             };
             let row = ProductValue::from(row);
@@ -176,9 +214,32 @@ impl CommittedState {
             ignore_duplicate_insert_error(st_tables.insert(blob_store, &row))?;
         }
 
+        // Insert the durability of the system tables which aren't durable into `st_table_durability`,
+        // including the tables which existed before `st_table_durability` did.
+        let (st_table_durability, blob_store) =
+            self.get_table_and_blob_store_or_create(ST_TABLE_DURABILITY_ID, &schemas[ST_TABLE_DURABILITY_IDX]);
+        for schema in ref_schemas
+            .iter()
+            .filter(|x| include(x.table_id) || include(ST_TABLE_DURABILITY_ID))
+            .filter(|x| x.durability != StDurability::Durable)
+        {
+            let row = StTableDurabilityRow {
+                table_id: schema.table_id,
+                durability: schema.durability,
+            };
+            let row = ProductValue::from(row);
+            // If the row is already there, no-op.
+            ignore_duplicate_insert_error(st_table_durability.insert(blob_store, &row))?;
+        }
+
         // Insert the columns into `st_columns`
         let (st_columns, blob_store) = self.get_table_and_blob_store_or_create(ST_COLUMN_ID, &schemas[ST_COLUMN_IDX]);
-        for col in ref_schemas.iter().flat_map(|x| x.columns()).cloned() {
+        for col in ref_schemas
+            .iter()
+            .filter(|x| include(x.table_id))
+            .flat_map(|x| x.columns())
+            .cloned()
+        {
             let row = StColumnRow {
                 table_id: col.table_id,
                 col_pos: col.col_pos,
@@ -189,8 +250,6 @@ impl CommittedState {
             // Insert the meta-row into the in-memory ST_COLUMNS.
             // If the row is already there, no-op.
             ignore_duplicate_insert_error(st_columns.insert(blob_store, &row))?;
-            // Increment row count for st_columns.
-            with_label_values(ST_COLUMN_ID, ST_COLUMN_NAME).inc();
         }
 
         // Insert the FK sorted by table/column so it show together when queried.
@@ -204,6 +263,7 @@ impl CommittedState {
             .sorted_by_key(|x| (x.table_id, x.data.unique_columns()))
             .cloned()
             .enumerate()
+            .filter(|(_, x)| include(x.table_id))
         {
             // Start sequence from 1,
             // to avoid any confusion with 0 as the autoinc sentinel value.
@@ -218,8 +278,6 @@ impl CommittedState {
             // Insert the meta-row into the in-memory ST_CONSTRAINTS.
             // If the row is already there, no-op.
             ignore_duplicate_insert_error(st_constraints.insert(blob_store, &row))?;
-            // Increment row count for st_constraints.
-            with_label_values(ST_CONSTRAINT_ID, ST_CONSTRAINT_NAME).inc();
         }
 
         // Insert the indexes into `st_indexes`
@@ -230,6 +288,7 @@ impl CommittedState {
             .sorted_by_key(|x| (x.table_id, x.index_algorithm.columns()))
             .cloned()
             .enumerate()
+            .filter(|(_, x)| include(x.table_id))
         {
             // Start sequence from 1,
             // to avoid any confusion with 0 as the autoinc sentinel value.
@@ -244,21 +303,29 @@ impl CommittedState {
             // Insert the meta-row into the in-memory ST_INDEXES.
             // If the row is already there, no-op.
             ignore_duplicate_insert_error(st_indexes.insert(blob_store, &row))?;
-            // Increment row count for st_indexes.
-            with_label_values(ST_INDEX_ID, ST_INDEX_NAME).inc();
         }
 
-        // We don't add the row here but with `MutProgrammable::set_program_hash`, but we need to register the table
-        // in the internal state.
-        self.create_table(ST_MODULE_ID, schemas[ST_MODULE_IDX].clone());
-
-        self.create_table(ST_CLIENT_ID, schemas[ST_CLIENT_IDX].clone());
-
-        self.create_table(ST_VAR_ID, schemas[ST_VAR_IDX].clone());
-
-        self.create_table(ST_SCHEDULED_ID, schemas[ST_SCHEDULED_IDX].clone());
-
-        self.create_table(ST_ROW_LEVEL_SECURITY_ID, schemas[ST_ROW_LEVEL_SECURITY_IDX].clone());
+        // We don't add the row to `st_module` here but with `MutProgrammable::set_program_hash`,
+        // but we need to register the table in the internal state.
+        // The same goes for the other system tables which start out empty.
+        for idx in [
+            ST_MODULE_IDX,
+            ST_CLIENT_IDX,
+            ST_VAR_IDX,
+            ST_SCHEDULED_IDX,
+            ST_ROW_LEVEL_SECURITY_IDX,
+            ST_GENERATED_COLUMN_IDX,
+            ST_SUBSCRIPTION_IDX,
+            ST_COUNTER_IDX,
+            ST_SOFT_DELETE_IDX,
+            ST_ROW_HISTORY_IDX,
+            ST_CONNECTION_IDX,
+        ] {
+            let schema = &schemas[idx];
+            if include(schema.table_id) {
+                self.create_table(schema.table_id, schema.clone());
+            }
+        }
 
         // IMPORTANT: It is crucial that the `st_sequences` table is created last
 
@@ -267,7 +334,12 @@ impl CommittedState {
             self.get_table_and_blob_store_or_create(ST_SEQUENCE_ID, &schemas[ST_SEQUENCE_IDX]);
         // We create sequences last to get right the starting number
         // so, we don't sort here
-        for (i, col) in ref_schemas.iter().flat_map(|x| &x.sequences).enumerate() {
+        for (i, col) in ref_schemas
+            .iter()
+            .flat_map(|x| &x.sequences)
+            .enumerate()
+            .filter(|(_, x)| include(x.table_id))
+        {
             // Start sequence from 1,
             // to avoid any confusion with 0 as the autoinc sentinel value.
             let sequence_id = (i + 1).into();
@@ -290,8 +362,6 @@ impl CommittedState {
             // Insert the meta-row into the in-memory ST_SEQUENCES.
             // If the row is already there, no-op.
             ignore_duplicate_insert_error(st_sequences.insert(blob_store, &row))?;
-            // Increment row count for st_sequences
-            with_label_values(ST_SEQUENCE_ID, ST_SEQUENCE_NAME).inc();
         }
 
         self.reset_system_table_schemas()?;
@@ -299,6 +369,46 @@ impl CommittedState {
         Ok(())
     }
 
+    /// Sets the `rdb_num_table_rows` metric of every system table to its number of rows.
+    pub(super) fn set_system_table_metrics(&self, database_identity: Identity) {
+        // NOTE: the `rdb_num_table_rows` metric is used by the query optimizer,
+        // and therefore has performance implications and must not be disabled.
+        for schema in system_tables() {
+            let row_count = self.table_row_count(schema.table_id).unwrap_or(0);
+            DB_METRICS
+                .rdb_num_table_rows
+                .with_label_values(&database_identity, &schema.table_id.0, &schema.table_name)
+                .set(row_count as i64);
+        }
+    }
+
+    /// Construct a [`CommittedState`] holding only the system tables of `snapshot`,
+    /// and its blob store.
+    ///
+    /// This suffices to compute the schemas of the user tables in `snapshot`,
+    /// e.g. to read their pages directly from the snapshot.
+    /// The state is not meant to serve transactions,
+    /// so no indexes or sequences are built, and no metrics are recorded.
+    pub(crate) fn from_snapshot_system_tables(snapshot: ReconstructedSnapshot) -> Result<Self> {
+        let mut state = Self {
+            blob_store: snapshot.blob_store,
+            ..Self::default()
+        };
+        for (table_id, pages) in snapshot.tables {
+            let Some(schema) = system_table_schema(table_id) else {
+                continue;
+            };
+            let (table, blob_store) = state.get_table_and_blob_store_or_create(table_id, &Arc::new(schema));
+            // SAFETY: `schema` is the known schema of the system table `table_id`,
+            // and we trust that the snapshot was consistent when created,
+            // so the layout used in the `pages` is consistent with it.
+            unsafe { table.set_pages(pages, blob_store) };
+        }
+        state.bootstrap_missing_system_tables()?;
+        state.reset_system_table_schemas()?;
+        Ok(state)
+    }
+
     /// Compute the system table schemas from the system tables,
     /// and store those schemas in the in-memory [`Table`] structures.
     ///
@@ -329,6 +439,17 @@ impl CommittedState {
         Ok(())
     }
 
+    /// Deletes every row of the table `table_id`, returning how many there were.
+    pub(super) fn replay_truncate(&mut self, table_id: TableId) -> Result<u64> {
+        let table = self
+            .tables
+            .get_mut(&table_id)
+            .ok_or_else(|| TableError::IdNotFoundState(table_id))?;
+        let row_count = table.row_count;
+        table.clear(&mut self.blob_store);
+        Ok(row_count)
+    }
+
     pub(super) fn replay_insert(
         &mut self,
         table_id: TableId,
@@ -496,8 +617,8 @@ impl CommittedState {
     /// and therefore consumes a value from `self.next_tx_offset`.
     ///
     /// A TX is written to the logs if any of the following holds:
-    /// - The TX inserted at least one row.
-    /// - The TX deleted at least one row.
+    /// - The TX inserted at least one row into a durable table.
+    /// - The TX deleted at least one row from a durable table.
     /// - The TX was the result of the reducers `__identity_connected__` or `__identity_disconnected__`.
     fn tx_consumes_offset(&self, tx_data: &TxData, ctx: &ExecutionContext) -> bool {
         // Avoid appending transactions to the commitlog which don't modify
@@ -527,6 +648,18 @@ impl CommittedState {
         // before allocating new pages.
         self.merge_apply_inserts(&mut tx_data, tx_state.insert_tables, tx_state.blob_store);
 
+        // Record which of the modified tables have relaxed or ephemeral durability,
+        // so that their rows are left out of the commitlog.
+        let relaxed = tx_data
+            .table_ids()
+            .filter(|table_id| {
+                self.tables
+                    .get(table_id)
+                    .is_some_and(|table| table.get_schema().durability != StDurability::Durable)
+            })
+            .collect::<Vec<_>>();
+        relaxed.into_iter().for_each(|table_id| tx_data.set_relaxed(table_id));
+
         // If the TX will be logged, record its projected tx offset,
         // then increment the counter.
         if self.tx_consumes_offset(&tx_data, ctx) {
@@ -534,19 +667,78 @@ impl CommittedState {
             self.next_tx_offset += 1;
         }
 
+        self.num_merged_txs += 1;
+        for table_id in tx_data.table_ids() {
+            self.table_idle_since.insert(table_id, self.num_merged_txs);
+        }
+
         tx_data
     }
 
+    /// Returns the tables with pages which aren't compressed
+    /// that have been idle for at least `self.compress_idle_tables_after` transactions.
+    ///
+    /// Tables count as idle since they were last modified or compressed,
+    /// so that tables which are only read, which decompresses their pages,
+    /// are compressed again only periodically.
+    pub(super) fn idle_tables(&self) -> Vec<TableId> {
+        self.tables
+            .iter()
+            .filter(|(table_id, table)| self.is_idle(**table_id) && !table.is_compressed())
+            .map(|(table_id, _)| *table_id)
+            .collect()
+    }
+
+    fn is_idle(&self, table_id: TableId) -> bool {
+        let Some(after) = self.compress_idle_tables_after else {
+            return false;
+        };
+        let idle_since = self.table_idle_since.get(&table_id).copied().unwrap_or_default();
+        self.num_merged_txs - idle_since >= after.get()
+    }
+
+    /// Compresses the pages of the table `table_id`, if it is still idle.
+    ///
+    /// Returns whether the table was compressed.
+    pub(super) fn compress_table_if_idle(&mut self, table_id: TableId) -> bool {
+        if !self.is_idle(table_id) {
+            return false;
+        }
+        let Some(table) = self.tables.get_mut(&table_id) else {
+            return false;
+        };
+        table.compress_pages();
+        self.table_idle_since.insert(table_id, self.num_merged_txs);
+        true
+    }
+
     fn merge_apply_deletes(&mut self, tx_data: &mut TxData, delete_tables: BTreeMap<TableId, DeleteTable>) {
         for (table_id, row_ptrs) in delete_tables {
             if let Some((table, blob_store)) = self.get_table_and_blob_store(table_id) {
-                let mut deletes = Vec::with_capacity(row_ptrs.len());
+                // When the table was truncated and nothing re-inserted since,
+                // clear the table wholesale rather than row by row.
+                if row_ptrs.is_cleared() {
+                    // TODO: re-write `TxData` to remove `ProductValue`s
+                    let deletes = table
+                        .scan_rows(blob_store)
+                        .map(|row| row.to_product_value())
+                        .collect::<Arc<[_]>>();
+                    tx_data.set_deletes_for_table(table_id, &table.get_schema().table_name, deletes);
+                    tx_data.set_truncated(table_id);
+                    table.clear(blob_store);
+                    continue;
+                }
+
+                let deleted = row_ptrs
+                    .deleted_rows(table.scan_rows(blob_store).map(|row| row.pointer()))
+                    .collect::<Vec<_>>();
+                let mut deletes = Vec::with_capacity(deleted.len());
 
                 // Note: we maintain the invariant that the delete_tables
                 // holds only committed rows which should be deleted,
                 // i.e. `RowPointer`s with `SquashedOffset::COMMITTED_STATE`,
                 // so no need to check before applying the deletes.
-                for row_ptr in row_ptrs.iter().copied() {
+                for row_ptr in deleted {
                     debug_assert!(row_ptr.squashed_offset().is_committed_state());
 
                     // TODO: re-write `TxData` to remove `ProductValue`s
@@ -685,7 +877,7 @@ pub struct CommittedIndexIterWithDeletedMutTx<'a> {
 }
 
 impl<'a> CommittedIndexIterWithDeletedMutTx<'a> {
-    pub(super) fn new(committed_rows: IndexScanIter<'a>, del_table: &'a BTreeSet<RowPointer>) -> Self {
+    pub(super) fn new(committed_rows: IndexScanIter<'a>, del_table: &'a DeleteTable) -> Self {
         Self {
             committed_rows,
             del_table,
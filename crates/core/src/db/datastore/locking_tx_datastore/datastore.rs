@@ -1,6 +1,6 @@
 use super::{
     committed_state::CommittedState,
-    mut_tx::MutTxId,
+    mut_tx::{MutTxId, RowRefInsertion, UndoLog},
     sequence::SequencesState,
     state_view::{IterByColRangeTx, StateView},
     tx::TxId,
@@ -31,7 +31,7 @@ use core::{cell::RefCell, ops::RangeBounds};
 use parking_lot::{Mutex, RwLock};
 use spacetimedb_commitlog::payload::{txdata, Txdata};
 use spacetimedb_durability::TxOffset;
-use spacetimedb_lib::db::auth::StAccess;
+use spacetimedb_lib::db::auth::{StAccess, StDurability};
 use spacetimedb_lib::{Address, Identity};
 use spacetimedb_paths::server::SnapshotDirPath;
 use spacetimedb_primitives::{ColList, ConstraintId, IndexId, SequenceId, TableId};
@@ -40,10 +40,12 @@ use spacetimedb_schema::schema::{IndexSchema, SequenceSchema, TableSchema};
 use spacetimedb_snapshot::{ReconstructedSnapshot, SnapshotRepository};
 use spacetimedb_table::{
     indexes::RowPointer,
+    page::Page,
     table::{RowRef, Table},
     MemoryUsage,
 };
 use std::borrow::Cow;
+use std::num::NonZeroU64;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
@@ -142,6 +144,30 @@ impl Locking {
         Ok(())
     }
 
+    /// Compress the pages of tables which have not been modified in `after` transactions
+    /// when [`Locking::compress_idle_tables`] is next called,
+    /// or never compress pages if `after` is `None`.
+    ///
+    /// Compressed pages are transparently decompressed when they are next accessed.
+    pub fn set_compress_idle_tables_after(&self, after: Option<NonZeroU64>) {
+        self.committed_state.write().compress_idle_tables_after = after;
+    }
+
+    /// Compresses the pages of the tables which have been idle
+    /// for the number of transactions set by [`Locking::set_compress_idle_tables_after`].
+    ///
+    /// The committed state is locked for writing once per table,
+    /// so that transactions may commit in between compressing tables.
+    ///
+    /// Returns the number of tables compressed.
+    pub fn compress_idle_tables(&self) -> usize {
+        let idle_tables = self.committed_state.read().idle_tables();
+        idle_tables
+            .into_iter()
+            .filter(|&table_id| self.committed_state.write().compress_table_if_idle(table_id))
+            .count()
+    }
+
     /// Obtain a [`spacetimedb_commitlog::Decoder`] suitable for replaying a
     /// [`spacetimedb_durability::History`] onto the currently committed state.
     ///
@@ -163,6 +189,8 @@ impl Locking {
     /// - Populate those tables with all rows in `snapshot`.
     /// - Construct a [`HashMapBlobStore`] containing all the large blobs referenced by `snapshot`,
     ///   with reference counts specified in `snapshot`.
+    /// - Create the system tables which `snapshot` predates,
+    ///   see [`CommittedState::bootstrap_missing_system_tables`].
     /// - Do [`CommittedState::reset_system_table_schemas`] to fix-up auto_inc IDs in the system tables,
     ///   to ensure those schemas match what [`Self::bootstrap`] would install.
     /// - Notably, **do not** construct indexes or sequences.
@@ -183,40 +211,25 @@ impl Locking {
 
         // Note that `tables` is a `BTreeMap`, and so iterates in increasing order.
         // This means that we will instantiate and populate the system tables before any user tables.
-        for (table_id, pages) in tables {
-            let schema = match system_table_schema(table_id) {
-                Some(schema) => Arc::new(schema),
-                // In this case, `schema_for_table` will never see a cached schema,
-                // as the committed state is newly constructed and we have not accessed this schema yet.
-                // As such, this call will compute and save the schema from `st_table` and friends.
-                None => committed_state.schema_for_table(table_id)?,
-            };
-            let (table, blob_store) = committed_state.get_table_and_blob_store_or_create(table_id, &schema);
-            unsafe {
-                // Safety:
-                // - The snapshot is uncorrupted because reconstructing it verified its hashes.
-                // - The schema in `table` is either derived from the st_table and st_column,
-                //   which were restored from the snapshot,
-                //   or it is a known schema for a system table.
-                // - We trust that the snapshot was consistent when created,
-                //   so the layout used in the `pages` must be consistent with the schema.
-                table.set_pages(pages, blob_store);
-            }
+        let mut tables = tables.into_iter().peekable();
+        while let Some((table_id, pages)) = tables.next_if(|(table_id, _)| system_table_schema(*table_id).is_some()) {
+            let schema = Arc::new(system_table_schema(table_id).unwrap());
+            // SAFETY: `schema` is the known schema for the system table `table_id`.
+            unsafe { Self::restore_table(&mut committed_state, database_identity, table_id, &schema, pages) };
+        }
 
-            // Set the `rdb_num_table_rows` metric for the table.
-            // NOTE: the `rdb_num_table_rows` metric is used by the query optimizer,
-            // and therefore has performance implications and must not be disabled.
-            DB_METRICS
-                .rdb_num_table_rows
-                .with_label_values(&database_identity, &table_id.0, &schema.table_name)
-                .set(table.row_count as i64);
+        // Computing the schemas of user tables reads every system table describing them,
+        // so those missing from the snapshot must exist by now.
+        committed_state.bootstrap_missing_system_tables()?;
+        committed_state.set_system_table_metrics(database_identity);
 
-            // Also set the `rdb_table_size` metric for the table.
-            let table_size = table.bytes_occupied_overestimate();
-            DB_METRICS
-                .rdb_table_size
-                .with_label_values(&database_identity, &table_id.into(), &schema.table_name)
-                .set(table_size as i64);
+        for (table_id, pages) in tables {
+            // In this case, `schema_for_table` will never see a cached schema,
+            // as the committed state is newly constructed and we have not accessed this schema yet.
+            // As such, this call will compute and save the schema from `st_table` and friends.
+            let schema = committed_state.schema_for_table(table_id)?;
+            // SAFETY: `schema` is derived from `st_table` and `st_column`, which were restored from the snapshot.
+            unsafe { Self::restore_table(&mut committed_state, database_identity, table_id, &schema, pages) };
         }
 
         // Fix up auto_inc IDs in the cached system table schemas.
@@ -228,6 +241,45 @@ impl Locking {
         Ok(datastore)
     }
 
+    /// Creates the table `table_id` in `committed_state`, holding the `pages` restored from a snapshot.
+    ///
+    /// # Safety
+    ///
+    /// `schema` must be the schema of `table_id` as of the snapshot,
+    /// either the known schema of a system table or one derived from the system tables in the snapshot.
+    unsafe fn restore_table(
+        committed_state: &mut CommittedState,
+        database_identity: Identity,
+        table_id: TableId,
+        schema: &Arc<TableSchema>,
+        pages: Vec<Box<Page>>,
+    ) {
+        let (table, blob_store) = committed_state.get_table_and_blob_store_or_create(table_id, schema);
+        unsafe {
+            // Safety:
+            // - The snapshot is uncorrupted because reconstructing it verified its hashes.
+            // - The schema in `table` is the schema of the table as of the snapshot, per our caller.
+            // - We trust that the snapshot was consistent when created,
+            //   so the layout used in the `pages` must be consistent with the schema.
+            table.set_pages(pages, blob_store);
+        }
+
+        // Set the `rdb_num_table_rows` metric for the table.
+        // NOTE: the `rdb_num_table_rows` metric is used by the query optimizer,
+        // and therefore has performance implications and must not be disabled.
+        DB_METRICS
+            .rdb_num_table_rows
+            .with_label_values(&database_identity, &table_id.0, &schema.table_name)
+            .set(table.row_count as i64);
+
+        // Also set the `rdb_table_size` metric for the table.
+        let table_size = table.bytes_occupied_overestimate();
+        DB_METRICS
+            .rdb_table_size
+            .with_label_values(&database_identity, &table_id.into(), &schema.table_name)
+            .set(table_size as i64);
+    }
+
     /// Take a snapshot of this [`Locking`] datastore's [`CommittedState`]
     /// and store it in `repo`.
     ///
@@ -266,7 +318,12 @@ impl Locking {
             ref blob_store,
             ..
         } = *committed_state;
-        let snapshot_dir = repo.create_snapshot(tables.values_mut(), blob_store, tx_offset)?;
+        // Ephemeral tables live only in memory, so they're left out,
+        // and are created empty when restoring the snapshot.
+        let tables = tables
+            .values_mut()
+            .filter(|table| table.get_schema().durability != StDurability::Ephemeral);
+        let snapshot_dir = repo.create_snapshot(tables, blob_store, tx_offset)?;
 
         Ok(Some((tx_offset, snapshot_dir)))
     }
@@ -292,6 +349,19 @@ impl Locking {
 
         tx.alter_table_access(table_id, access)
     }
+
+    pub(crate) fn alter_table_durability_mut_tx(
+        &self,
+        tx: &mut MutTxId,
+        name: Box<str>,
+        durability: StDurability,
+    ) -> Result<()> {
+        let table_id = self
+            .table_id_from_name_mut_tx(tx, &name)?
+            .ok_or_else(|| TableError::NotFound(name.into()))?;
+
+        tx.alter_table_durability(table_id, durability)
+    }
 }
 
 impl DataRow for Locking {
@@ -485,6 +555,14 @@ impl MutTxDatastore for Locking {
         tx.get_next_sequence_value(seq_id)
     }
 
+    fn peek_sequence_value_mut_tx(&self, tx: &mut Self::MutTx, seq_id: SequenceId) -> Result<i128> {
+        tx.peek_sequence_value(seq_id)
+    }
+
+    fn advance_sequence_past_mut_tx(&self, tx: &mut Self::MutTx, seq_id: SequenceId, value: i128) -> Result<bool> {
+        tx.advance_sequence_past(seq_id, value)
+    }
+
     fn create_sequence_mut_tx(&self, tx: &mut Self::MutTx, sequence_schema: SequenceSchema) -> Result<SequenceId> {
         tx.create_sequence(sequence_schema)
     }
@@ -545,11 +623,28 @@ impl MutTxDatastore for Locking {
         table_id: TableId,
         row_ptrs: impl IntoIterator<Item = Self::RowId>,
     ) -> u32 {
+        let observed = tx.has_row_observers(table_id);
         let mut num_deleted = 0;
         for row_ptr in row_ptrs {
+            // Read the row before deleting it, so that its deletion can be observed.
+            let row = if observed {
+                tx.get(table_id, row_ptr)
+                    .ok()
+                    .flatten()
+                    .map(|row| row.to_product_value())
+            } else {
+                None
+            };
             match tx.delete(table_id, row_ptr) {
                 Err(e) => log::error!("delete_mut_tx: {:?}", e),
-                Ok(b) => num_deleted += b as u32,
+                Ok(b) => {
+                    num_deleted += b as u32;
+                    if let Some(row) = row.filter(|_| b) {
+                        if let Err(e) = tx.observe_delete(table_id, &row) {
+                            log::error!("delete_mut_tx: {:?}", e);
+                        }
+                    }
+                }
             }
         }
         num_deleted
@@ -561,24 +656,63 @@ impl MutTxDatastore for Locking {
         table_id: TableId,
         relation: impl IntoIterator<Item = ProductValue>,
     ) -> u32 {
+        let observed = tx.has_row_observers(table_id);
         let mut num_deleted = 0;
         for row in relation {
             match tx.delete_by_row_value(table_id, &row) {
                 Err(e) => log::error!("delete_by_rel_mut_tx: {:?}", e),
-                Ok(b) => num_deleted += b as u32,
+                Ok(b) => {
+                    num_deleted += b as u32;
+                    if b && observed {
+                        if let Err(e) = tx.observe_delete(table_id, &row) {
+                            log::error!("delete_by_rel_mut_tx: {:?}", e);
+                        }
+                    }
+                }
             }
         }
         num_deleted
     }
 
+    fn truncate_mut_tx(&self, tx: &mut Self::MutTx, table_id: TableId) -> Result<u64> {
+        if !tx.has_row_observers(table_id) {
+            return tx.truncate(table_id);
+        }
+        let rows = tx.iter(table_id)?.map(|row| row.to_product_value()).collect::<Vec<_>>();
+        let num_deleted = tx.truncate(table_id)?;
+        for row in &rows {
+            tx.observe_delete(table_id, row)?;
+        }
+        Ok(num_deleted)
+    }
+
     fn insert_mut_tx<'a>(
         &'a self,
         tx: &'a mut Self::MutTx,
         table_id: TableId,
         row: &[u8],
     ) -> Result<(ColList, RowRef<'a>)> {
-        let (gens, row_ref) = tx.insert::<true>(table_id, row)?;
-        Ok((gens, row_ref.collapse()))
+        if !tx.has_row_observers(table_id) {
+            let (gens, row_ref) = tx.insert::<true>(table_id, row)?;
+            return Ok((gens, row_ref.collapse()));
+        }
+
+        // Only observe the insertion if the row wasn't already present.
+        let (gens, row_ptr, inserted) = {
+            let (gens, row_ref) = tx.insert::<true>(table_id, row)?;
+            let inserted = match row_ref {
+                RowRefInsertion::Inserted(row_ref) => Some(row_ref.to_product_value()),
+                RowRefInsertion::Existed(_) => None,
+            };
+            (gens, row_ref.collapse().pointer(), inserted)
+        };
+        if let Some(inserted) = inserted {
+            tx.observe_insert(table_id, &inserted)?;
+        }
+        let row_ref = tx
+            .get(table_id, row_ptr)?
+            .ok_or(TableError::IdNotFoundState(table_id))?;
+        Ok((gens, row_ref))
     }
 
     fn update_mut_tx<'a>(
@@ -588,7 +722,25 @@ impl MutTxDatastore for Locking {
         index_id: IndexId,
         row: &[u8],
     ) -> Result<(ColList, RowRef<'a>)> {
-        tx.update(table_id, index_id, row)
+        if !tx.has_row_observers(table_id) {
+            return tx.update(table_id, index_id, row);
+        }
+
+        // Observe the update as the deletion of the old row and the insertion of the new one,
+        // e.g., moving the row from the count of the row it referred to, to that of the row it refers to now.
+        let old_row = tx.find_row_to_update(table_id, index_id, row)?;
+        let (gens, row_ptr, new_row) = {
+            let (gens, row_ref) = tx.update(table_id, index_id, row)?;
+            (gens, row_ref.pointer(), row_ref.to_product_value())
+        };
+        if let Some(old_row) = old_row {
+            tx.observe_delete(table_id, &old_row)?;
+        }
+        tx.observe_insert(table_id, &new_row)?;
+        let row_ref = tx
+            .get(table_id, row_ptr)?
+            .ok_or(TableError::IdNotFoundState(table_id))?;
+        Ok((gens, row_ref))
     }
 
     fn metadata_mut_tx(&self, tx: &Self::MutTx) -> Result<Option<Metadata>> {
@@ -715,6 +867,9 @@ impl MutTx for Locking {
             lock_wait_time,
             timer,
             ctx,
+            undo_log: UndoLog::default(),
+            read_tables: None,
+            table_reads: <_>::default(),
         }
     }
 
@@ -943,8 +1098,23 @@ impl<F: FnMut(u64)> spacetimedb_commitlog::payload::txdata::Visitor for ReplayVi
         Ok(row)
     }
 
-    fn visit_truncate(&mut self, _table_id: TableId) -> std::result::Result<(), Self::Error> {
-        Err(anyhow!("visit: truncate not yet supported").into())
+    fn visit_truncate(&mut self, table_id: TableId) -> std::result::Result<(), Self::Error> {
+        let schema = self.committed_state.schema_for_table(table_id)?;
+
+        let row_count = self.committed_state.replay_truncate(table_id).with_context(|| {
+            format!(
+                "Error truncating table {:?} during transaction {:?} playback",
+                table_id, self.committed_state.next_tx_offset
+            )
+        })?;
+        // NOTE: the `rdb_num_table_rows` metric is used by the query optimizer,
+        // and therefore has performance implications and must not be disabled.
+        DB_METRICS
+            .rdb_num_table_rows
+            .with_label_values(self.database_identity, &table_id.into(), &schema.table_name)
+            .sub(row_count as i64);
+
+        Ok(())
     }
 
     fn visit_tx_start(&mut self, offset: u64) -> std::result::Result<(), Self::Error> {
@@ -989,28 +1159,33 @@ fn metadata_from_row(row: RowRef<'_>) -> Result<Metadata> {
 mod tests {
     use super::*;
     use crate::db::datastore::system_tables::{
-        system_tables, StColumnRow, StConstraintData, StConstraintFields, StConstraintRow, StIndexAlgorithm,
-        StIndexFields, StIndexRow, StRowLevelSecurityFields, StScheduledFields, StSequenceFields, StSequenceRow,
-        StTableRow, StVarFields, StVarValue, ST_CLIENT_NAME, ST_COLUMN_ID, ST_COLUMN_NAME, ST_CONSTRAINT_ID,
-        ST_CONSTRAINT_NAME, ST_INDEX_ID, ST_INDEX_NAME, ST_MODULE_NAME, ST_RESERVED_SEQUENCE_RANGE,
-        ST_ROW_LEVEL_SECURITY_ID, ST_ROW_LEVEL_SECURITY_NAME, ST_SCHEDULED_ID, ST_SCHEDULED_NAME, ST_SEQUENCE_ID,
-        ST_SEQUENCE_NAME, ST_TABLE_NAME, ST_VAR_ID, ST_VAR_NAME,
+        system_tables, StColumnFields, StColumnRow, StConstraintData, StConstraintFields, StConstraintRow,
+        StIndexAlgorithm, StIndexFields, StIndexRow, StRowLevelSecurityFields, StScheduledFields, StSequenceFields,
+        StSequenceRow, StTableDurabilityRow, StTableRow, StVarFields, StVarValue, ST_CLIENT_NAME, ST_COLUMN_ID,
+        ST_COLUMN_NAME, ST_CONNECTION_ID, ST_CONNECTION_NAME, ST_CONSTRAINT_ID, ST_CONSTRAINT_NAME, ST_COUNTER_ID,
+        ST_COUNTER_NAME, ST_GENERATED_COLUMN_ID, ST_GENERATED_COLUMN_NAME, ST_INDEX_ID, ST_INDEX_NAME, ST_MODULE_NAME,
+        ST_RESERVED_SEQUENCE_RANGE, ST_ROW_HISTORY_ID, ST_ROW_HISTORY_NAME, ST_ROW_LEVEL_SECURITY_ID,
+        ST_ROW_LEVEL_SECURITY_NAME, ST_SCHEDULED_ID, ST_SCHEDULED_NAME, ST_SEQUENCE_ID, ST_SEQUENCE_NAME,
+        ST_SOFT_DELETE_ID, ST_SOFT_DELETE_NAME, ST_SUBSCRIPTION_ID, ST_SUBSCRIPTION_NAME, ST_TABLE_DURABILITY_ID,
+        ST_TABLE_DURABILITY_NAME, ST_TABLE_NAME, ST_VAR_ID, ST_VAR_NAME,
     };
     use crate::db::datastore::traits::{IsolationLevel, MutTx};
     use crate::db::datastore::Result;
+    use crate::db::relational_db::{open_snapshot_repo, tests_utils::TempReplicaDir};
     use crate::error::{DBError, IndexError};
     use bsatn::to_vec;
     use core::{fmt, mem};
     use itertools::Itertools;
     use pretty_assertions::{assert_eq, assert_matches};
-    use spacetimedb_lib::db::auth::{StAccess, StTableType};
+    use spacetimedb_lib::db::auth::{StAccess, StDurability, StTableType};
     use spacetimedb_lib::error::ResultTest;
     use spacetimedb_lib::resolved_type_via_v9;
     use spacetimedb_primitives::{col_list, ColId, ScheduleId};
     use spacetimedb_sats::{product, AlgebraicType, GroundSpacetimeType};
     use spacetimedb_schema::def::{BTreeAlgorithm, ConstraintData, IndexAlgorithm, UniqueConstraintData};
     use spacetimedb_schema::schema::{
-        ColumnSchema, ConstraintSchema, IndexSchema, RowLevelSecuritySchema, SequenceSchema,
+        ColumnSchema, ConstraintSchema, CounterSchema, GeneratedColumnSchema, IndexSchema, RowLevelSecuritySchema,
+        SequenceSchema,
     };
     use spacetimedb_table::table::UniqueConstraintViolation;
 
@@ -1433,7 +1608,7 @@ mod tests {
         let tx = datastore.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
         let query = query_st_tables(&tx);
         #[rustfmt::skip]
-        assert_eq!(query.scan_st_tables()?, map_array([
+        let st_tables: Vec<StTableRow> = map_array([
             TableRow { id: ST_TABLE_ID.into(), name: ST_TABLE_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: Some(StTableFields::TableId.into()) },
             TableRow { id: ST_COLUMN_ID.into(), name: ST_COLUMN_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: None },
             TableRow { id: ST_SEQUENCE_ID.into(), name: ST_SEQUENCE_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: Some(StSequenceFields::SequenceId.into()) },
@@ -1444,7 +1619,27 @@ mod tests {
             TableRow { id: ST_VAR_ID.into(), name: ST_VAR_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: Some(StVarFields::Name.into()) },
             TableRow { id: ST_SCHEDULED_ID.into(), name: ST_SCHEDULED_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: Some(StScheduledFields::ScheduleId.into()) },
             TableRow { id: ST_ROW_LEVEL_SECURITY_ID.into(), name: ST_ROW_LEVEL_SECURITY_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: Some(StRowLevelSecurityFields::Sql.into()) },
-        ]));
+            TableRow { id: ST_GENERATED_COLUMN_ID.into(), name: ST_GENERATED_COLUMN_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: None },
+            TableRow { id: ST_SUBSCRIPTION_ID.into(), name: ST_SUBSCRIPTION_NAME, ty: StTableType::System, access: StAccess::Private, primary_key: None },
+            TableRow { id: ST_COUNTER_ID.into(), name: ST_COUNTER_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: None },
+            TableRow { id: ST_SOFT_DELETE_ID.into(), name: ST_SOFT_DELETE_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: None },
+            TableRow { id: ST_ROW_HISTORY_ID.into(), name: ST_ROW_HISTORY_NAME, ty: StTableType::System, access: StAccess::Private, primary_key: None },
+            TableRow { id: ST_CONNECTION_ID.into(), name: ST_CONNECTION_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: None },
+            TableRow { id: ST_TABLE_DURABILITY_ID.into(), name: ST_TABLE_DURABILITY_NAME, ty: StTableType::System, access: StAccess::Public, primary_key: None },
+        ]);
+        assert_eq!(query.scan_st_tables()?, st_tables);
+        // `st_subscription` only reflects the subscriptions of the running host.
+        let st_table_durability = datastore
+            .iter_mut_tx(&tx, ST_TABLE_DURABILITY_ID)?
+            .map(StTableDurabilityRow::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            st_table_durability,
+            [StTableDurabilityRow {
+                table_id: ST_SUBSCRIPTION_ID,
+                durability: StDurability::Relaxed,
+            }]
+        );
         #[rustfmt::skip]
         assert_eq!(query.scan_st_columns()?, map_array([
             ColRow { table: ST_TABLE_ID.into(), pos: 0, name: "table_id", ty: TableId::get_type() },
@@ -1499,6 +1694,36 @@ mod tests {
 
             ColRow { table: ST_ROW_LEVEL_SECURITY_ID.into(), pos: 0, name: "table_id", ty: TableId::get_type() },
             ColRow { table: ST_ROW_LEVEL_SECURITY_ID.into(), pos: 1, name: "sql", ty: AlgebraicType::String },
+
+            ColRow { table: ST_GENERATED_COLUMN_ID.into(), pos: 0, name: "table_id", ty: TableId::get_type() },
+            ColRow { table: ST_GENERATED_COLUMN_ID.into(), pos: 1, name: "col_pos", ty: ColId::get_type() },
+            ColRow { table: ST_GENERATED_COLUMN_ID.into(), pos: 2, name: "expr", ty: AlgebraicType::String },
+
+            ColRow { table: ST_SUBSCRIPTION_ID.into(), pos: 0, name: "identity", ty: AlgebraicType::U256 },
+            ColRow { table: ST_SUBSCRIPTION_ID.into(), pos: 1, name: "address", ty: AlgebraicType::U128 },
+            ColRow { table: ST_SUBSCRIPTION_ID.into(), pos: 2, name: "query", ty: AlgebraicType::String },
+            ColRow { table: ST_SUBSCRIPTION_ID.into(), pos: 3, name: "created_at", ty: AlgebraicType::U64 },
+            ColRow { table: ST_SUBSCRIPTION_ID.into(), pos: 4, name: "eval_cost", ty: AlgebraicType::U64 },
+
+            ColRow { table: ST_COUNTER_ID.into(), pos: 0, name: "table_id", ty: TableId::get_type() },
+            ColRow { table: ST_COUNTER_ID.into(), pos: 1, name: "group_by", ty: ColId::get_type() },
+            ColRow { table: ST_COUNTER_ID.into(), pos: 2, name: "into_table", ty: AlgebraicType::String },
+            ColRow { table: ST_COUNTER_ID.into(), pos: 3, name: "into_column", ty: ColId::get_type() },
+
+            ColRow { table: ST_SOFT_DELETE_ID.into(), pos: 0, name: "table_id", ty: TableId::get_type() },
+            ColRow { table: ST_SOFT_DELETE_ID.into(), pos: 1, name: "retention", ty: AlgebraicType::U64 },
+
+            ColRow { table: ST_ROW_HISTORY_ID.into(), pos: 0, name: "table_id", ty: TableId::get_type() },
+            ColRow { table: ST_ROW_HISTORY_ID.into(), pos: 1, name: "tx_offset", ty: AlgebraicType::U64 },
+            ColRow { table: ST_ROW_HISTORY_ID.into(), pos: 2, name: "deleted", ty: AlgebraicType::Bool },
+            ColRow { table: ST_ROW_HISTORY_ID.into(), pos: 3, name: "row", ty: AlgebraicType::bytes() },
+
+            ColRow { table: ST_CONNECTION_ID.into(), pos: 0, name: "identity", ty: AlgebraicType::U256 },
+            ColRow { table: ST_CONNECTION_ID.into(), pos: 1, name: "address", ty: AlgebraicType::U128 },
+            ColRow { table: ST_CONNECTION_ID.into(), pos: 2, name: "connected_at", ty: AlgebraicType::U64 },
+
+            ColRow { table: ST_TABLE_DURABILITY_ID.into(), pos: 0, name: "table_id", ty: TableId::get_type() },
+            ColRow { table: ST_TABLE_DURABILITY_ID.into(), pos: 1, name: "durability", ty: AlgebraicType::String },
         ]));
         #[rustfmt::skip]
         assert_eq!(query.scan_st_indexes()?, map_array([
@@ -1514,6 +1739,10 @@ mod tests {
             IndexRow { id: 10, table: ST_SCHEDULED_ID.into(), col: col(1), name: "st_scheduled_table_id_idx_btree", },
             IndexRow { id: 11, table: ST_ROW_LEVEL_SECURITY_ID.into(), col: col(0), name: "st_row_level_security_table_id_idx_btree", },
             IndexRow { id: 12, table: ST_ROW_LEVEL_SECURITY_ID.into(), col: col(1), name: "st_row_level_security_sql_idx_btree", },
+            IndexRow { id: 13, table: ST_GENERATED_COLUMN_ID.into(), col: col_list![0, 1], name: "st_generated_column_table_id_col_pos_idx_btree", },
+            IndexRow { id: 14, table: ST_ROW_HISTORY_ID.into(), col: col_list![0, 1], name: "st_row_history_table_id_tx_offset_idx_btree", },
+            IndexRow { id: 15, table: ST_CONNECTION_ID.into(), col: col_list![0, 1], name: "st_connection_identity_address_idx_btree", },
+            IndexRow { id: 16, table: ST_TABLE_DURABILITY_ID.into(), col: col(0), name: "st_table_durability_table_id_idx_btree", },
         ]));
         let start = FIRST_NON_SYSTEM_ID as i128;
         #[rustfmt::skip]
@@ -1543,6 +1772,9 @@ mod tests {
             ConstraintRow { constraint_id: 9, table_id: ST_SCHEDULED_ID.into(), unique_columns: col(0), constraint_name: "st_scheduled_schedule_id_key", },
             ConstraintRow { constraint_id: 10, table_id: ST_SCHEDULED_ID.into(), unique_columns: col(1), constraint_name: "st_scheduled_table_id_key", },
             ConstraintRow { constraint_id: 11, table_id: ST_ROW_LEVEL_SECURITY_ID.into(), unique_columns: col(1), constraint_name: "st_row_level_security_sql_key", },
+            ConstraintRow { constraint_id: 12, table_id: ST_GENERATED_COLUMN_ID.into(), unique_columns: col_list![0, 1], constraint_name: "st_generated_column_table_id_col_pos_key", },
+            ConstraintRow { constraint_id: 13, table_id: ST_CONNECTION_ID.into(), unique_columns: col_list![0, 1], constraint_name: "st_connection_identity_address_key", },
+            ConstraintRow { constraint_id: 14, table_id: ST_TABLE_DURABILITY_ID.into(), unique_columns: col(0), constraint_name: "st_table_durability_table_id_key", },
         ]));
 
         // Verify we get back the tables correctly with the proper ids...
@@ -1602,6 +1834,107 @@ mod tests {
         Ok(())
     }
 
+    /// Restores a snapshot of a database which predates the system tables `missing`,
+    /// and checks that they are created as they would be in a new database.
+    fn test_restore_snapshot_missing_system_tables(missing: &[TableId]) -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        insert(&datastore, &mut tx, table_id, &random_row())?;
+        commit(&datastore, tx)?;
+
+        // Make the database look like one which predates the system tables `missing`,
+        // by removing those tables along with the rows describing them.
+        {
+            let mut committed_state = datastore.committed_state.write();
+            let CommittedState { tables, blob_store, .. } = &mut *committed_state;
+            for table_id in missing {
+                tables.remove(table_id);
+            }
+            for (st_table_id, col) in [
+                (ST_TABLE_ID, ColId::from(StTableFields::TableId)),
+                (ST_COLUMN_ID, StColumnFields::TableId.into()),
+                (ST_INDEX_ID, StIndexFields::TableId.into()),
+                (ST_CONSTRAINT_ID, StConstraintFields::TableId.into()),
+                (ST_SEQUENCE_ID, StSequenceFields::TableId.into()),
+            ] {
+                let table = tables.get_mut(&st_table_id).unwrap();
+                let ptrs = table
+                    .scan_rows(blob_store)
+                    .filter(|row| missing.contains(&row.read_col(col).unwrap()))
+                    .map(|row| row.pointer())
+                    .collect::<Vec<_>>();
+                for ptr in ptrs {
+                    table.delete(blob_store, ptr, |_| ());
+                }
+            }
+        }
+
+        let dir = TempReplicaDir::new()?;
+        let repo = open_snapshot_repo(dir.snapshots(), Identity::ZERO, 0)?;
+        let (tx_offset, _) = Locking::take_snapshot_internal(&datastore.committed_state, &repo)?.unwrap();
+        let restored = Locking::restore_from_snapshot(repo.read_snapshot(tx_offset)?)?;
+        restored.rebuild_state_after_replay()?;
+
+        // The missing system tables are created as they would be in a new database.
+        let bootstrapped = get_datastore()?;
+        let bootstrapped_tx = begin_mut_tx(&bootstrapped);
+        let tx = begin_mut_tx(&restored);
+        let (query, expected) = (query_st_tables(&tx), query_st_tables(&bootstrapped_tx));
+        let is_system = |table_id: TableId| table_id.0 <= ST_RESERVED_SEQUENCE_RANGE;
+        let st_tables = query.scan_st_tables()?;
+        let st_tables = st_tables.into_iter().filter(|x| is_system(x.table_id));
+        assert_eq!(st_tables.collect::<Vec<_>>(), expected.scan_st_tables()?);
+        let st_columns = query.scan_st_columns()?;
+        let st_columns = st_columns.into_iter().filter(|x| is_system(x.table_id));
+        assert_eq!(st_columns.collect::<Vec<_>>(), expected.scan_st_columns()?);
+        let st_indexes = query.scan_st_indexes()?;
+        let st_indexes = st_indexes.into_iter().filter(|x| is_system(x.table_id));
+        assert_eq!(st_indexes.collect::<Vec<_>>(), expected.scan_st_indexes()?);
+        let st_constraints = query.scan_st_constraints()?;
+        let st_constraints = st_constraints.into_iter().filter(|x| is_system(x.table_id));
+        assert_eq!(st_constraints.collect::<Vec<_>>(), expected.scan_st_constraints()?);
+        for st in system_tables() {
+            assert_eq!(
+                restored.schema_for_table_mut_tx(&tx, st.table_id)?,
+                bootstrapped.schema_for_table_mut_tx(&bootstrapped_tx, st.table_id)?,
+            );
+        }
+
+        // And the user table, whose schema is read from them, is restored.
+        assert_eq!(&*restored.schema_for_table_mut_tx(&tx, table_id)?.table_name, "Foo");
+        assert_eq!(all_rows(&restored, &tx, table_id), vec![random_row()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_snapshot_missing_st_generated_column() -> ResultTest<()> {
+        test_restore_snapshot_missing_system_tables(&[ST_GENERATED_COLUMN_ID])
+    }
+
+    #[test]
+    fn test_restore_snapshot_missing_st_subscription() -> ResultTest<()> {
+        test_restore_snapshot_missing_system_tables(&[ST_SUBSCRIPTION_ID])
+    }
+
+    #[test]
+    fn test_restore_snapshot_missing_st_counter() -> ResultTest<()> {
+        test_restore_snapshot_missing_system_tables(&[ST_COUNTER_ID])
+    }
+
+    #[test]
+    fn test_restore_snapshot_missing_st_soft_delete_and_st_row_history() -> ResultTest<()> {
+        test_restore_snapshot_missing_system_tables(&[ST_SOFT_DELETE_ID, ST_ROW_HISTORY_ID])
+    }
+
+    #[test]
+    fn test_restore_snapshot_missing_st_connection() -> ResultTest<()> {
+        test_restore_snapshot_missing_system_tables(&[ST_CONNECTION_ID])
+    }
+
+    #[test]
+    fn test_restore_snapshot_missing_st_table_durability() -> ResultTest<()> {
+        test_restore_snapshot_missing_system_tables(&[ST_TABLE_DURABILITY_ID])
+    }
+
     #[test]
     fn test_create_table_pre_commit() -> ResultTest<()> {
         let (_, tx, table_id) = setup_table()?;
@@ -1951,6 +2284,10 @@ mod tests {
             IndexRow { id: 10, table: ST_SCHEDULED_ID.into(), col: col(1), name: "st_scheduled_table_id_idx_btree", },
             IndexRow { id: 11, table: ST_ROW_LEVEL_SECURITY_ID.into(), col: col(0), name: "st_row_level_security_table_id_idx_btree", },
             IndexRow { id: 12, table: ST_ROW_LEVEL_SECURITY_ID.into(), col: col(1), name: "st_row_level_security_sql_idx_btree", },
+            IndexRow { id: 13, table: ST_GENERATED_COLUMN_ID.into(), col: col_list![0, 1], name: "st_generated_column_table_id_col_pos_idx_btree", },
+            IndexRow { id: 14, table: ST_ROW_HISTORY_ID.into(), col: col_list![0, 1], name: "st_row_history_table_id_tx_offset_idx_btree", },
+            IndexRow { id: 15, table: ST_CONNECTION_ID.into(), col: col_list![0, 1], name: "st_connection_identity_address_idx_btree", },
+            IndexRow { id: 16, table: ST_TABLE_DURABILITY_ID.into(), col: col(0), name: "st_table_durability_table_id_idx_btree", },
             IndexRow { id: seq_start,     table: FIRST_NON_SYSTEM_ID, col: col(0), name: "Foo_id_idx_btree",  },
             IndexRow { id: seq_start + 1, table: FIRST_NON_SYSTEM_ID, col: col(1), name: "Foo_name_idx_btree",  },
             IndexRow { id: seq_start + 2, table: FIRST_NON_SYSTEM_ID, col: col(2), name: "Foo_age_idx_btree",  },
@@ -2005,6 +2342,10 @@ mod tests {
             IndexRow { id: 10, table: ST_SCHEDULED_ID.into(), col: col(1), name: "st_scheduled_table_id_idx_btree", },
             IndexRow { id: 11, table: ST_ROW_LEVEL_SECURITY_ID.into(), col: col(0), name: "st_row_level_security_table_id_idx_btree", },
             IndexRow { id: 12, table: ST_ROW_LEVEL_SECURITY_ID.into(), col: col(1), name: "st_row_level_security_sql_idx_btree", },
+            IndexRow { id: 13, table: ST_GENERATED_COLUMN_ID.into(), col: col_list![0, 1], name: "st_generated_column_table_id_col_pos_idx_btree", },
+            IndexRow { id: 14, table: ST_ROW_HISTORY_ID.into(), col: col_list![0, 1], name: "st_row_history_table_id_tx_offset_idx_btree", },
+            IndexRow { id: 15, table: ST_CONNECTION_ID.into(), col: col_list![0, 1], name: "st_connection_identity_address_idx_btree", },
+            IndexRow { id: 16, table: ST_TABLE_DURABILITY_ID.into(), col: col(0), name: "st_table_durability_table_id_idx_btree", },
             IndexRow { id: seq_start    , table: FIRST_NON_SYSTEM_ID, col: col(0), name: "Foo_id_idx_btree",  },
             IndexRow { id: seq_start + 1, table: FIRST_NON_SYSTEM_ID, col: col(1), name: "Foo_name_idx_btree", },
             IndexRow { id: seq_start + 2, table: FIRST_NON_SYSTEM_ID, col: col(2), name: "Foo_age_idx_btree", },
@@ -2060,6 +2401,10 @@ mod tests {
             IndexRow { id: 10, table: ST_SCHEDULED_ID.into(), col: col(1), name: "st_scheduled_table_id_idx_btree", },
             IndexRow { id: 11, table: ST_ROW_LEVEL_SECURITY_ID.into(), col: col(0), name: "st_row_level_security_table_id_idx_btree", },
             IndexRow { id: 12, table: ST_ROW_LEVEL_SECURITY_ID.into(), col: col(1), name: "st_row_level_security_sql_idx_btree", },
+            IndexRow { id: 13, table: ST_GENERATED_COLUMN_ID.into(), col: col_list![0, 1], name: "st_generated_column_table_id_col_pos_idx_btree", },
+            IndexRow { id: 14, table: ST_ROW_HISTORY_ID.into(), col: col_list![0, 1], name: "st_row_history_table_id_tx_offset_idx_btree", },
+            IndexRow { id: 15, table: ST_CONNECTION_ID.into(), col: col_list![0, 1], name: "st_connection_identity_address_idx_btree", },
+            IndexRow { id: 16, table: ST_TABLE_DURABILITY_ID.into(), col: col(0), name: "st_table_durability_table_id_idx_btree", },
             IndexRow { id: seq_start,     table: FIRST_NON_SYSTEM_ID, col: col(0), name: "Foo_id_idx_btree", },
             IndexRow { id: seq_start + 1, table: FIRST_NON_SYSTEM_ID, col: col(1), name: "Foo_name_idx_btree", },
         ].map(Into::into));
@@ -2118,6 +2463,189 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_generated_columns() -> ResultTest<()> {
+        let datastore = get_datastore()?;
+        let mut tx = begin_mut_tx(&datastore);
+        #[rustfmt::skip]
+        let columns: Vec<ColumnSchema> = map_array([
+            ColRow { table: 0, pos: 0, name: "id", ty: AlgebraicType::U32 },
+            ColRow { table: 0, pos: 1, name: "name", ty: AlgebraicType::String },
+            ColRow { table: 0, pos: 2, name: "name_key", ty: AlgebraicType::String },
+        ]);
+        let generated = GeneratedColumnSchema::parse(TableId::SENTINEL, 2.into(), "lower(name)".into(), &columns)?;
+        let mut schema = TableSchema::new(
+            TableId::SENTINEL,
+            "Foo".into(),
+            columns,
+            vec![IndexSchema {
+                index_id: IndexId::SENTINEL,
+                table_id: TableId::SENTINEL,
+                index_name: "Foo_name_key_idx_btree".into(),
+                index_algorithm: IndexAlgorithm::BTree(BTreeAlgorithm { columns: col_list![2] }),
+            }],
+            vec![ConstraintSchema {
+                table_id: TableId::SENTINEL,
+                constraint_id: ConstraintId::SENTINEL,
+                constraint_name: "Foo_name_key_key".into(),
+                data: ConstraintData::Unique(UniqueConstraintData {
+                    columns: col_list![2].into(),
+                }),
+            }],
+            vec![],
+            StTableType::User,
+            StAccess::Public,
+            None,
+            None,
+        );
+        schema.generated_columns = vec![generated];
+        let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
+
+        // The generated column is computed on insert, whatever the module wrote into it.
+        insert(&datastore, &mut tx, table_id, &product![1u32, "Ada", "placeholder"])?;
+        assert_eq!(all_rows(&datastore, &tx, table_id), vec![product![1u32, "Ada", "ada"]]);
+
+        // And it is indexed, so uniqueness is case-insensitive.
+        let res = insert(&datastore, &mut tx, table_id, &product![2u32, "ADA", ""]);
+        assert_matches!(expect_index_err(res), IndexError::UniqueConstraintViolation(_));
+        datastore.commit_mut_tx(tx)?;
+
+        // The generated columns are read back from `st_generated_column`.
+        let tx = begin_mut_tx(&datastore);
+        let schema = tx.schema_for_table_raw(table_id)?;
+        assert_eq!(schema.generated_columns.len(), 1);
+        assert_eq!(schema.generated_columns[0].col_pos, ColId(2));
+        assert_eq!(&*schema.generated_columns[0].expr_source, "lower(name)");
+        Ok(())
+    }
+
+    #[test]
+    fn test_counters() -> ResultTest<()> {
+        let datastore = get_datastore()?;
+        let mut tx = begin_mut_tx(&datastore);
+        // A table with a unique primary key `id` in column 0.
+        let table_with_pk = |name: &str, columns: Vec<ColumnSchema>| {
+            TableSchema::new(
+                TableId::SENTINEL,
+                name.into(),
+                columns,
+                vec![IndexSchema {
+                    index_id: IndexId::SENTINEL,
+                    table_id: TableId::SENTINEL,
+                    index_name: format!("{name}_id_idx_btree").into(),
+                    index_algorithm: IndexAlgorithm::BTree(BTreeAlgorithm { columns: col_list![0] }),
+                }],
+                vec![ConstraintSchema {
+                    table_id: TableId::SENTINEL,
+                    constraint_id: ConstraintId::SENTINEL,
+                    constraint_name: format!("{name}_id_key").into(),
+                    data: ConstraintData::Unique(UniqueConstraintData {
+                        columns: col_list![0].into(),
+                    }),
+                }],
+                vec![],
+                StTableType::User,
+                StAccess::Public,
+                None,
+                Some(ColId(0)),
+            )
+        };
+        #[rustfmt::skip]
+        let canvas = table_with_pk("canvas", map_array([
+            ColRow { table: 0, pos: 0, name: "id", ty: AlgebraicType::U32 },
+            ColRow { table: 0, pos: 1, name: "element_count", ty: AlgebraicType::U32 },
+        ]));
+        #[rustfmt::skip]
+        let mut element = table_with_pk("element", map_array([
+            ColRow { table: 0, pos: 0, name: "id", ty: AlgebraicType::U32 },
+            ColRow { table: 0, pos: 1, name: "canvas_id", ty: AlgebraicType::U32 },
+        ]));
+        element.counters = vec![CounterSchema {
+            table_id: TableId::SENTINEL,
+            group_by: ColId(1),
+            into_table: "canvas".into(),
+            into_column: ColId(1),
+        }];
+        let canvas_id = datastore.create_table_mut_tx(&mut tx, canvas)?;
+        let element_id = datastore.create_table_mut_tx(&mut tx, element)?;
+        let element_index = tx.index_id_from_name("element_id_idx_btree")?.unwrap();
+        insert(&datastore, &mut tx, canvas_id, &product![1u32, 0u32])?;
+        insert(&datastore, &mut tx, canvas_id, &product![2u32, 0u32])?;
+
+        // Inserting elements counts them under their canvas.
+        insert(&datastore, &mut tx, element_id, &product![1u32, 1u32])?;
+        insert(&datastore, &mut tx, element_id, &product![2u32, 1u32])?;
+        insert(&datastore, &mut tx, element_id, &product![3u32, 2u32])?;
+        // Elements of canvases which don't exist aren't counted.
+        insert(&datastore, &mut tx, element_id, &product![4u32, 3u32])?;
+        let counts = |tx: &MutTxId| {
+            all_rows(&datastore, tx, canvas_id)
+                .into_iter()
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(counts(&tx), [product![1u32, 2u32], product![2u32, 1u32]]);
+        datastore.commit_mut_tx(tx)?;
+
+        // Moving an element moves it from one count to the other.
+        let mut tx = begin_mut_tx(&datastore);
+        update(&datastore, &mut tx, element_id, element_index, &product![1u32, 2u32])?;
+        assert_eq!(counts(&tx), [product![1u32, 1u32], product![2u32, 2u32]]);
+
+        // Deleting elements uncounts them.
+        let deleted = datastore.delete_by_rel_mut_tx(&mut tx, element_id, [product![2u32, 1u32]]);
+        assert_eq!(deleted, 1);
+        assert_eq!(counts(&tx), [product![1u32, 0u32], product![2u32, 2u32]]);
+        datastore.truncate_mut_tx(&mut tx, element_id)?;
+        assert_eq!(counts(&tx), [product![1u32, 0u32], product![2u32, 0u32]]);
+        datastore.commit_mut_tx(tx)?;
+
+        // The counters are read back from `st_counter`.
+        let tx = begin_mut_tx(&datastore);
+        let schema = tx.schema_for_table_raw(element_id)?;
+        assert_eq!(schema.counters.len(), 1);
+        assert_eq!(&*schema.counters[0].into_table, "canvas");
+        Ok(())
+    }
+
+    #[test]
+    fn test_relaxed_durability() -> ResultTest<()> {
+        let datastore = get_datastore()?;
+        let mut tx = begin_mut_tx(&datastore);
+        #[rustfmt::skip]
+        let columns: Vec<ColumnSchema> = map_array([
+            ColRow { table: 0, pos: 0, name: "x", ty: AlgebraicType::U32 },
+        ]);
+        let mut schema = TableSchema::new(
+            TableId::SENTINEL,
+            "Cursor".into(),
+            columns,
+            vec![],
+            vec![],
+            vec![],
+            StTableType::User,
+            StAccess::Public,
+            None,
+            None,
+        );
+        schema.durability = StDurability::Relaxed;
+        let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
+        // Creating the table writes to the system tables, which are durable.
+        let tx_data = datastore.commit_mut_tx(tx)?.expect("commit should produce `TxData`");
+        assert!(tx_data.tx_offset().is_some());
+
+        // Updates to the table are reported, but don't consume a tx offset,
+        // so they aren't written to the commitlog.
+        let mut tx = begin_mut_tx(&datastore);
+        assert_eq!(tx.schema_for_table_raw(table_id)?.durability, StDurability::Relaxed);
+        insert(&datastore, &mut tx, table_id, &product![1u32])?;
+        let tx_data = datastore.commit_mut_tx(tx)?.expect("commit should produce `TxData`");
+        assert!(tx_data.is_relaxed(table_id));
+        assert_eq!(tx_data.inserts().count(), 1);
+        assert_eq!(tx_data.tx_offset(), None);
+        Ok(())
+    }
+
     fn expect_index_err(res: Result<impl fmt::Debug>) -> IndexError {
         res.expect_err("`res` should be an error")
             .into_index()
@@ -2526,6 +3054,301 @@ mod tests {
         Ok(())
     }
 
+    fn sorted_rows(datastore: &Locking, tx: &MutTxId, table_id: TableId) -> Vec<ProductValue> {
+        all_rows(datastore, tx, table_id).into_iter().sorted().collect()
+    }
+
+    #[test]
+    fn test_savepoint_rollback_discards_writes_since() -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        let committed = [u32_str_u32(1, "Foo", 18), u32_str_u32(2, "Bar", 19)];
+        for row in &committed {
+            insert(&datastore, &mut tx, table_id, row)?;
+        }
+        datastore.commit_mut_tx(tx)?;
+        let mut tx = begin_mut_tx(&datastore);
+        let index_id = extract_index_id(&datastore, &tx, &basic_indices()[0])?;
+
+        // Write before the savepoint; these writes must survive.
+        let before = u32_str_u32(3, "Baz", 20);
+        insert(&datastore, &mut tx, table_id, &before)?;
+        let expected = sorted_rows(&datastore, &tx, table_id);
+
+        let savepoint = tx.savepoint();
+        // Insert a row, delete a committed row and a row inserted before the savepoint,
+        // update a committed row and delete and reinsert a committed row.
+        insert(&datastore, &mut tx, table_id, &u32_str_u32(4, "Qux", 21))?;
+        assert_eq!(
+            datastore.delete_by_rel_mut_tx(&mut tx, table_id, [committed[0].clone()]),
+            1
+        );
+        assert_eq!(datastore.delete_by_rel_mut_tx(&mut tx, table_id, [before.clone()]), 1);
+        update(&datastore, &mut tx, table_id, index_id, &u32_str_u32(2, "Bar", 42))?;
+        update(&datastore, &mut tx, table_id, index_id, &u32_str_u32(2, "Bar", 43))?;
+        assert_eq!(
+            datastore.delete_by_rel_mut_tx(&mut tx, table_id, [committed[0].clone()]),
+            0
+        );
+        insert(&datastore, &mut tx, table_id, &committed[0])?;
+        tx.rollback_to_savepoint(savepoint)?;
+        assert_eq!(sorted_rows(&datastore, &tx, table_id), expected);
+
+        // The transaction is still usable, and commits the writes made before the savepoint.
+        let after = u32_str_u32(5, "Quux", 22);
+        insert(&datastore, &mut tx, table_id, &after)?;
+        datastore.commit_mut_tx(tx)?;
+        let tx = begin_mut_tx(&datastore);
+        let expected = [committed[0].clone(), committed[1].clone(), before, after];
+        assert_eq!(
+            sorted_rows(&datastore, &tx, table_id),
+            expected.into_iter().sorted().collect_vec()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_savepoint_rollback_truncate() -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        insert(&datastore, &mut tx, table_id, &u32_str_u32(1, "Foo", 18))?;
+        datastore.commit_mut_tx(tx)?;
+        let mut tx = begin_mut_tx(&datastore);
+        insert(&datastore, &mut tx, table_id, &u32_str_u32(2, "Bar", 19))?;
+        let expected = sorted_rows(&datastore, &tx, table_id);
+
+        let savepoint = tx.savepoint();
+        assert_eq!(datastore.truncate_mut_tx(&mut tx, table_id)?, 2);
+        assert_eq!(all_rows(&datastore, &tx, table_id), vec![]);
+        tx.rollback_to_savepoint(savepoint)?;
+        assert_eq!(sorted_rows(&datastore, &tx, table_id), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_savepoints() -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        let (row1, row2, row3) = (
+            u32_str_u32(1, "Foo", 18),
+            u32_str_u32(2, "Bar", 19),
+            u32_str_u32(3, "Baz", 20),
+        );
+
+        // Rolling back an inner savepoint keeps the writes made within the outer one.
+        let outer = tx.savepoint();
+        insert(&datastore, &mut tx, table_id, &row1)?;
+        let inner = tx.savepoint();
+        insert(&datastore, &mut tx, table_id, &row2)?;
+        tx.rollback_to_savepoint(inner)?;
+        assert_eq!(all_rows(&datastore, &tx, table_id), [row1.clone()]);
+
+        // Rolling back the outer savepoint discards the writes of a released inner one.
+        let inner = tx.savepoint();
+        insert(&datastore, &mut tx, table_id, &row3)?;
+        tx.release_savepoint(inner);
+        assert_eq!(sorted_rows(&datastore, &tx, table_id), [row1.clone(), row3.clone()]);
+        tx.rollback_to_savepoint(outer)?;
+        assert_eq!(all_rows(&datastore, &tx, table_id), vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_savepoint_rollback_keeps_sequence_values() -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        let savepoint = tx.savepoint();
+        let (id, _) = insert(&datastore, &mut tx, table_id, &u32_str_u32(0, "Foo", 18))?;
+        assert_eq!(id, AlgebraicValue::U32(1));
+        tx.rollback_to_savepoint(savepoint)?;
+
+        // The value generated after the savepoint must not be generated again.
+        let (id, _) = insert(&datastore, &mut tx, table_id, &u32_str_u32(0, "Foo", 18))?;
+        assert_eq!(id, AlgebraicValue::U32(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_savepoint_rollback_across_schema_change_fails() -> ResultTest<()> {
+        // Creating a table after the savepoint.
+        let datastore = get_datastore()?;
+        let mut tx = begin_mut_tx(&datastore);
+        let savepoint = tx.savepoint();
+        let schema = basic_table_schema_with_indices(basic_indices(), basic_constraints());
+        let table_id = datastore.create_table_mut_tx(&mut tx, schema)?;
+        let row = u32_str_u32(1, "Foo", 18);
+        insert(&datastore, &mut tx, table_id, &row)?;
+        assert_matches!(
+            tx.rollback_to_savepoint(savepoint),
+            Err(DBError::Table(TableError::SchemaChangedSinceSavepoint))
+        );
+        // Nothing was rolled back.
+        assert_eq!(all_rows(&datastore, &tx, table_id), [row.clone()]);
+        datastore.commit_mut_tx(tx)?;
+
+        // Dropping a table after the savepoint.
+        let mut tx = begin_mut_tx(&datastore);
+        let savepoint = tx.savepoint();
+        datastore.drop_table_mut_tx(&mut tx, table_id)?;
+        assert_matches!(
+            tx.rollback_to_savepoint(savepoint),
+            Err(DBError::Table(TableError::SchemaChangedSinceSavepoint))
+        );
+        datastore.rollback_mut_tx(tx);
+
+        // Changing the schema before the savepoint doesn't prevent rolling back to it.
+        let mut tx = begin_mut_tx(&datastore);
+        let index_id = extract_index_id(&datastore, &tx, &basic_indices()[1])?;
+        datastore.drop_index_mut_tx(&mut tx, index_id)?;
+        let savepoint = tx.savepoint();
+        insert(&datastore, &mut tx, table_id, &u32_str_u32(2, "Bar", 19))?;
+        tx.rollback_to_savepoint(savepoint)?;
+        assert_eq!(all_rows(&datastore, &tx, table_id), [row]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate_clears_committed_table() -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        let rows = [u32_str_u32(1, "Foo", 18), u32_str_u32(2, "Bar", 19)];
+        for row in &rows {
+            insert(&datastore, &mut tx, table_id, row)?;
+        }
+        commit(&datastore, tx)?;
+
+        let mut tx = begin_mut_tx(&datastore);
+        insert(&datastore, &mut tx, table_id, &u32_str_u32(3, "Baz", 20))?;
+        assert_eq!(datastore.truncate_mut_tx(&mut tx, table_id)?, 3);
+        assert_eq!(tx.table_row_count(table_id), Some(0));
+        assert_eq!(all_rows(&datastore, &tx, table_id), vec![]);
+        let tx_data = commit(&datastore, tx)?;
+        assert!(tx_data.is_truncated(table_id));
+
+        let tx = begin_mut_tx(&datastore);
+        assert_eq!(all_rows(&datastore, &tx, table_id), vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate_then_reinsert_deletes_row_by_row() -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        let rows = [u32_str_u32(1, "Foo", 18), u32_str_u32(2, "Bar", 19)];
+        for row in &rows {
+            insert(&datastore, &mut tx, table_id, row)?;
+        }
+        commit(&datastore, tx)?;
+
+        // Re-inserting a committed row after the truncate undeletes it,
+        // so the table is no longer cleared wholesale.
+        let mut tx = begin_mut_tx(&datastore);
+        assert_eq!(datastore.truncate_mut_tx(&mut tx, table_id)?, 2);
+        insert(&datastore, &mut tx, table_id, &rows[0])?;
+        assert_eq!(tx.table_row_count(table_id), Some(1));
+        assert_eq!(all_rows(&datastore, &tx, table_id), [rows[0].clone()]);
+        // Truncating again deletes just the undeleted row.
+        assert_eq!(datastore.truncate_mut_tx(&mut tx, table_id)?, 1);
+        insert(&datastore, &mut tx, table_id, &rows[1])?;
+        let tx_data = commit(&datastore, tx)?;
+        assert!(!tx_data.is_truncated(table_id));
+        let deletes = tx_data
+            .deletes()
+            .filter(|(id, _)| **id == table_id)
+            .map(|(_, rows)| rows.to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(deletes, [vec![rows[0].clone()]]);
+
+        let tx = begin_mut_tx(&datastore);
+        assert_eq!(all_rows(&datastore, &tx, table_id), [rows[1].clone()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deleting_every_row_does_not_truncate() -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        let rows = [u32_str_u32(1, "Foo", 18), u32_str_u32(2, "Bar", 19)];
+        for row in &rows {
+            insert(&datastore, &mut tx, table_id, row)?;
+        }
+        commit(&datastore, tx)?;
+
+        let mut tx = begin_mut_tx(&datastore);
+        assert_eq!(datastore.delete_by_rel_mut_tx(&mut tx, table_id, rows.clone()), 2);
+        let tx_data = commit(&datastore, tx)?;
+        assert!(!tx_data.is_truncated(table_id));
+        let deletes = tx_data
+            .deletes()
+            .filter(|(id, _)| **id == table_id)
+            .flat_map(|(_, rows)| rows.iter().cloned())
+            .sorted()
+            .collect::<Vec<_>>();
+        assert_eq!(deletes, rows.into_iter().sorted().collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_idle_tables() -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        let row = u32_str_u32(1, "Foo", 18);
+        insert(&datastore, &mut tx, table_id, &row)?;
+        commit(&datastore, tx)?;
+        datastore.set_compress_idle_tables_after(NonZeroU64::new(2));
+        let num_compressed = || datastore.committed_state.read().tables[&table_id].num_compressed_pages();
+        let commit_empty = || commit(&datastore, begin_mut_tx(&datastore));
+
+        // The table was modified by the last transaction, so it isn't idle yet.
+        datastore.compress_idle_tables();
+        assert_eq!(num_compressed(), 0);
+
+        // Committing transactions never compresses pages itself.
+        commit_empty()?;
+        commit_empty()?;
+        assert_eq!(num_compressed(), 0);
+        assert!(datastore.compress_idle_tables() >= 1);
+        assert_eq!(num_compressed(), 1);
+        // Compressing again does nothing.
+        datastore.compress_idle_tables();
+        assert_eq!(num_compressed(), 1);
+
+        // Reading the table decompresses its pages and discards the compressed bytes.
+        let tx = datastore.begin_tx(Workload::ForTests);
+        assert_eq!(all_rows_tx(&tx, table_id), [row.clone()]);
+        tx.release();
+        assert_eq!(num_compressed(), 0);
+
+        // A table which was compressed is idle again only after as many transactions.
+        commit_empty()?;
+        datastore.compress_idle_tables();
+        assert_eq!(num_compressed(), 0);
+        commit_empty()?;
+        datastore.compress_idle_tables();
+        assert_eq!(num_compressed(), 1);
+
+        // Modifying the table decompresses its pages and resets its idleness.
+        let mut tx = begin_mut_tx(&datastore);
+        insert(&datastore, &mut tx, table_id, &u32_str_u32(2, "Bar", 19))?;
+        commit(&datastore, tx)?;
+        assert_eq!(num_compressed(), 0);
+        commit_empty()?;
+        datastore.compress_idle_tables();
+        assert_eq!(num_compressed(), 0);
+
+        let tx = begin_mut_tx(&datastore);
+        assert_eq!(all_rows(&datastore, &tx, table_id).len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_idle_tables_when_disabled() -> ResultTest<()> {
+        let (datastore, mut tx, table_id) = setup_table()?;
+        insert(&datastore, &mut tx, table_id, &u32_str_u32(1, "Foo", 18))?;
+        commit(&datastore, tx)?;
+        for _ in 0..3 {
+            commit(&datastore, begin_mut_tx(&datastore))?;
+        }
+        assert_eq!(datastore.compress_idle_tables(), 0);
+        assert_eq!(
+            datastore.committed_state.read().tables[&table_id].num_compressed_pages(),
+            0
+        );
+        Ok(())
+    }
+
     // TODO: Add the following tests
     // - Create index with unique constraint and immediately insert a row that violates the constraint before committing.
     // - Create a tx that inserts 2000 rows with an auto_inc column
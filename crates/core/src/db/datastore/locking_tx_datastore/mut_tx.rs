@@ -12,12 +12,16 @@ use crate::db::datastore::locking_tx_datastore::state_view::{
     IndexSeekIterIdWithDeletedMutTx, IterByColEqMutTx, IterByColRangeMutTx, IterMutTx,
 };
 use crate::db::datastore::system_tables::{
-    with_sys_table_buf, StColumnFields, StColumnRow, StConstraintFields, StConstraintRow, StFields as _, StIndexFields,
-    StIndexRow, StRowLevelSecurityFields, StRowLevelSecurityRow, StScheduledFields, StScheduledRow, StSequenceFields,
-    StSequenceRow, StTableFields, StTableRow, SystemTable, ST_COLUMN_ID, ST_CONSTRAINT_ID, ST_INDEX_ID,
-    ST_ROW_LEVEL_SECURITY_ID, ST_SCHEDULED_ID, ST_SEQUENCE_ID, ST_TABLE_ID,
+    with_sys_table_buf, StColumnFields, StColumnRow, StConstraintFields, StConstraintRow, StCounterFields,
+    StCounterRow, StFields as _, StGeneratedColumnFields, StGeneratedColumnRow, StIndexFields, StIndexRow,
+    StRowHistoryFields, StRowHistoryRow, StRowLevelSecurityFields, StRowLevelSecurityRow, StScheduledFields,
+    StScheduledRow, StSequenceFields, StSequenceRow, StSoftDeleteFields, StSoftDeleteRow, StTableDurabilityFields,
+    StTableDurabilityRow, StTableFields, StTableRow, SystemTable, ST_COLUMN_ID, ST_CONSTRAINT_ID, ST_COUNTER_ID,
+    ST_GENERATED_COLUMN_ID, ST_INDEX_ID, ST_RESERVED_SEQUENCE_RANGE, ST_ROW_HISTORY_ID, ST_ROW_LEVEL_SECURITY_ID,
+    ST_SCHEDULED_ID, ST_SEQUENCE_ID, ST_SOFT_DELETE_ID, ST_TABLE_DURABILITY_ID, ST_TABLE_ID,
 };
 use crate::db::datastore::traits::{RowTypeForTable, TxData};
+use crate::db::db_metrics::DB_METRICS;
 use crate::execution_context::Workload;
 use crate::{
     error::{IndexError, SequenceError, TableError},
@@ -25,11 +29,21 @@ use crate::{
 };
 use core::cell::RefCell;
 use core::ops::RangeBounds;
-use core::{iter, ops::Bound};
+use core::{
+    iter,
+    ops::{Bound, RangeInclusive},
+};
+use parking_lot::Mutex;
 use smallvec::SmallVec;
+use spacetimedb_data_structures::map::{IntMap, IntSet};
 use spacetimedb_lib::db::raw_def::v9::RawSql;
-use spacetimedb_lib::db::{auth::StAccess, raw_def::SEQUENCE_ALLOCATION_STEP};
-use spacetimedb_primitives::{ColId, ColList, ColSet, ConstraintId, IndexId, ScheduleId, SequenceId, TableId};
+use spacetimedb_lib::db::{
+    auth::{StAccess, StDurability},
+    raw_def::SEQUENCE_ALLOCATION_STEP,
+};
+use spacetimedb_primitives::{
+    col_list, ColId, ColList, ColSet, ConstraintId, IndexId, ScheduleId, SequenceId, TableId,
+};
 use spacetimedb_sats::{
     bsatn::{self, to_writer, DecodeError, Deserializer},
     de::{DeserializeSeed, WithBound},
@@ -46,12 +60,47 @@ use spacetimedb_table::{
     table::{DuplicateError, IndexScanIter, InsertError, RowRef, Table, TableAndIndex},
 };
 use std::{
+    borrow::Cow,
+    mem,
     sync::Arc,
     time::{Duration, Instant},
 };
 
 type DecodeResult<T> = core::result::Result<T, DecodeError>;
 
+/// Returns `row`, a BSATN-encoded row of `table`,
+/// with the values of the table's generated columns computed from the row's other columns.
+///
+/// Borrows `row` unchanged when the table has no generated columns.
+fn compute_generated_columns<'r>(table: &Table, row: &'r [u8]) -> Result<Cow<'r, [u8]>> {
+    let generated_columns = &table.get_schema().generated_columns;
+    if generated_columns.is_empty() {
+        return Ok(Cow::Borrowed(row));
+    }
+
+    let mut pv: ProductValue = bsatn::decode(table.get_row_type(), &mut &*row)?;
+    for generated in generated_columns {
+        let value = generated.expr.eval(&pv);
+        pv.elements[generated.col_pos.idx()] = value;
+    }
+    Ok(Cow::Owned(
+        bsatn::to_vec(&pv).expect("encoding a `ProductValue` as BSATN should never fail"),
+    ))
+}
+
+/// Returns `count`, the value of a counter column, moved by `delta`,
+/// saturating at the bounds of the column's type.
+fn offset_count(count: &AlgebraicValue, delta: i32) -> AlgebraicValue {
+    match *count {
+        AlgebraicValue::U32(n) => n.saturating_add_signed(delta).into(),
+        AlgebraicValue::U64(n) => n.saturating_add_signed(delta.into()).into(),
+        AlgebraicValue::I32(n) => n.saturating_add(delta).into(),
+        AlgebraicValue::I64(n) => n.saturating_add(delta.into()).into(),
+        // Validation only allows the above types for counter columns.
+        ref other => other.clone(),
+    }
+}
+
 /// Represents a Mutable transaction. Holds locks for its duration
 ///
 /// The initialization of this struct is sensitive because improper
@@ -64,6 +113,24 @@ pub struct MutTxId {
     pub(super) lock_wait_time: Duration,
     pub(crate) timer: Instant,
     pub(crate) ctx: ExecutionContext,
+    pub(super) undo_log: UndoLog,
+    /// The tables read by the module during this transaction,
+    /// if recording them was requested with [`MutTxId::record_reads`].
+    pub(super) read_tables: Option<IntSet<TableId>>,
+    /// The number of times each table was read during this transaction, by how.
+    ///
+    /// These are counted here, as that is cheap,
+    /// and added to [`DB_METRICS`] once the transaction ends.
+    pub(super) table_reads: Mutex<IntMap<TableId, TableReads>>,
+}
+
+/// The number of times a transaction read a table, by how.
+#[derive(Default, Clone, Copy)]
+pub(super) struct TableReads {
+    /// The number of seeks into the table's indices.
+    index_seeks: u64,
+    /// The number of scans of all of the table's rows.
+    table_scans: u64,
 }
 
 impl MutTxId {
@@ -95,6 +162,7 @@ impl MutTxId {
     /// - The table metadata is inserted into the system tables.
     /// - The returned ID is unique and not `TableId::SENTINEL`.
     pub fn create_table(&mut self, mut table_schema: TableSchema) -> Result<TableId> {
+        self.undo_log.record_schema_change();
         if table_schema.table_id != TableId::SENTINEL {
             return Err(anyhow::anyhow!("`table_id` must be `TableId::SENTINEL` in `{:#?}`", table_schema).into());
             // checks for children are performed in the relevant `create_...` functions.
@@ -161,6 +229,45 @@ impl MutTxId {
             table.with_mut_schema(|s| s.schedule.as_mut().unwrap().schedule_id = id);
         }
 
+        // Insert the generated columns into `st_generated_column`
+        for generated in &table_schema.generated_columns {
+            let row = StGeneratedColumnRow {
+                table_id,
+                col_pos: generated.col_pos,
+                expr: generated.expr_source.clone(),
+            };
+            self.insert_via_serialize_bsatn(ST_GENERATED_COLUMN_ID, &row)?;
+        }
+
+        // Insert the counters into `st_counter`
+        for counter in &table_schema.counters {
+            let row = StCounterRow {
+                table_id,
+                group_by: counter.group_by,
+                into_table: counter.into_table.clone(),
+                into_column: counter.into_column,
+            };
+            self.insert_via_serialize_bsatn(ST_COUNTER_ID, &row)?;
+        }
+
+        // Insert the history kept of the table into `st_soft_delete`
+        if let Some(soft_delete) = &table_schema.soft_delete {
+            let row = StSoftDeleteRow {
+                table_id,
+                retention: soft_delete.retention,
+            };
+            self.insert_via_serialize_bsatn(ST_SOFT_DELETE_ID, &row)?;
+        }
+
+        // Insert the durability of the table into `st_table_durability`, unless it's durable.
+        if table_schema.durability != StDurability::Durable {
+            let row = StTableDurabilityRow {
+                table_id,
+                durability: table_schema.durability,
+            };
+            self.insert_via_serialize_bsatn(ST_TABLE_DURABILITY_ID, &row)?;
+        }
+
         // Insert constraints into `st_constraints`
         for constraint in table_schema.constraints.iter().cloned() {
             self.create_constraint(constraint)?;
@@ -224,6 +331,7 @@ impl MutTxId {
     }
 
     pub fn drop_table(&mut self, table_id: TableId) -> Result<()> {
+        self.undo_log.record_schema_change();
         let schema = &*self.schema_for_table(table_id)?;
 
         for row in &schema.indexes {
@@ -250,6 +358,35 @@ impl MutTxId {
             )?;
         }
 
+        if !schema.generated_columns.is_empty() {
+            self.drop_col_eq(
+                ST_GENERATED_COLUMN_ID,
+                StGeneratedColumnFields::TableId.col_id(),
+                &table_id.into(),
+            )?;
+        }
+
+        if !schema.counters.is_empty() {
+            self.drop_col_eq(ST_COUNTER_ID, StCounterFields::TableId.col_id(), &table_id.into())?;
+        }
+
+        if schema.soft_delete.is_some() {
+            self.drop_col_eq(
+                ST_SOFT_DELETE_ID,
+                StSoftDeleteFields::TableId.col_id(),
+                &table_id.into(),
+            )?;
+            self.drop_history(table_id, 0..=u64::MAX)?;
+        }
+
+        if schema.durability != StDurability::Durable {
+            self.drop_col_eq(
+                ST_TABLE_DURABILITY_ID,
+                StTableDurabilityFields::TableId.col_id(),
+                &table_id.into(),
+            )?;
+        }
+
         // Delete the table and its rows and indexes from memory.
         // TODO: This needs to not remove it from the committed state, because it can still be rolled back.
         // We will have to store the deletion in the TxState and then apply it to the CommittedState in commit.
@@ -325,6 +462,7 @@ impl MutTxId {
 
     /// Set the table access of `table_id` to `access`.
     pub(crate) fn alter_table_access(&mut self, table_id: TableId, access: StAccess) -> Result<()> {
+        self.undo_log.record_schema_change();
         // Write to the table in the tx state.
         let (table, ..) = self.get_or_create_insert_table_mut(table_id)?;
         table.with_mut_schema(|s| s.table_access = access);
@@ -334,6 +472,30 @@ impl MutTxId {
         Ok(())
     }
 
+    /// Set the durability of `table_id` to `durability`.
+    pub(crate) fn alter_table_durability(&mut self, table_id: TableId, durability: StDurability) -> Result<()> {
+        self.undo_log.record_schema_change();
+        // Write to the table in the tx state.
+        let (table, ..) = self.get_or_create_insert_table_mut(table_id)?;
+        let old_durability = table.get_schema().durability;
+        table.with_mut_schema(|s| s.durability = durability);
+
+        // Update system tables.
+        // Only the tables which aren't durable have a row in `st_table_durability`.
+        if old_durability != StDurability::Durable {
+            self.drop_col_eq(
+                ST_TABLE_DURABILITY_ID,
+                StTableDurabilityFields::TableId.col_id(),
+                &table_id.into(),
+            )?;
+        }
+        if durability != StDurability::Durable {
+            let row = StTableDurabilityRow { table_id, durability };
+            self.insert_via_serialize_bsatn(ST_TABLE_DURABILITY_ID, &row)?;
+        }
+        Ok(())
+    }
+
     /// Create an index.
     ///
     /// Requires:
@@ -347,6 +509,7 @@ impl MutTxId {
     /// - The index metadata is inserted into the system tables (and other data structures reflecting them).
     /// - The returned ID is unique and is not `IndexId::SENTINEL`.
     pub fn create_index(&mut self, mut index: IndexSchema, is_unique: bool) -> Result<IndexId> {
+        self.undo_log.record_schema_change();
         if index.index_id != IndexId::SENTINEL {
             return Err(anyhow::anyhow!("`index_id` must be `IndexId::SENTINEL` in `{:#?}`", index).into());
         }
@@ -440,6 +603,7 @@ impl MutTxId {
     }
 
     pub fn drop_index(&mut self, index_id: IndexId) -> Result<()> {
+        self.undo_log.record_schema_change();
         log::trace!("INDEX DROPPING: {}", index_id);
         // Find the index in `st_indexes`.
         let st_index_ref = self
@@ -506,6 +670,9 @@ impl MutTxId {
         let bounds =
             Self::btree_decode_bounds(index_ty, prefix, prefix_elems, rstart, rend).map_err(IndexError::Decode)?;
 
+        // Count the seek towards the table's index usage, as reported by `spacetime describe`.
+        self.count_read(table_id, |reads| reads.index_seeks += 1);
+
         // Get an index seek iterator for the tx and committed state.
         let tx_iter = tx_index.map(|i| i.seek(&bounds));
         let commit_iter = commit_index.map(|i| i.seek(&bounds));
@@ -697,6 +864,40 @@ impl MutTxId {
             }
         }
         // Allocate new sequence values
+        self.allocate_sequence_values(seq_id)?;
+
+        self.get_sequence_mut(seq_id)?
+            .gen_next_value()
+            .ok_or_else(|| SequenceError::UnableToAllocate(seq_id).into())
+    }
+
+    /// Returns the value which the sequence `seq_id` will generate next,
+    /// without consuming it.
+    pub fn peek_sequence_value(&mut self, seq_id: SequenceId) -> Result<i128> {
+        Ok(self.get_sequence_mut(seq_id)?.peek_value())
+    }
+
+    /// Moves the sequence `seq_id` past `value`,
+    /// e.g., after `value` was inserted explicitly into the sequenced column,
+    /// so that the sequence will not later generate `value` or any value before it.
+    ///
+    /// Returns whether the sequence moved.
+    /// It does not if it has already passed `value` or if `value` is out of its range.
+    pub fn advance_sequence_past(&mut self, seq_id: SequenceId, value: i128) -> Result<bool> {
+        let sequence = self.get_sequence_mut(seq_id)?;
+        if !sequence.advance_past(value) {
+            return Ok(false);
+        }
+        if sequence.needs_allocation() {
+            self.allocate_sequence_values(seq_id)?;
+        }
+        Ok(true)
+    }
+
+    /// Allocates a fresh batch of values for the sequence `seq_id`,
+    /// starting from its current value,
+    /// by updating its row in `st_sequences`.
+    fn allocate_sequence_values(&mut self, seq_id: SequenceId) -> Result<()> {
         // If we're out of allocations, then update the sequence row in st_sequences to allocate a fresh batch of sequences.
         let old_seq_row_ref = self
             .iter_by_col_eq(ST_SEQUENCE_ID, StSequenceFields::SequenceId, &seq_id.into())?
@@ -723,10 +924,7 @@ impl MutTxId {
             to_writer(buf, &seq_row).unwrap();
             self.insert::<false>(ST_SEQUENCE_ID, buf)
         })?;
-
-        self.get_sequence_mut(seq_id)?
-            .gen_next_value()
-            .ok_or_else(|| SequenceError::UnableToAllocate(seq_id).into())
+        Ok(())
     }
 
     /// Create a sequence.
@@ -739,6 +937,7 @@ impl MutTxId {
     /// - The sequence metadata is inserted into the system tables (and other data structures reflecting them).
     /// - The returned ID is unique and not `SequenceId::SENTINEL`.
     pub fn create_sequence(&mut self, seq: SequenceSchema) -> Result<SequenceId> {
+        self.undo_log.record_schema_change();
         if seq.sequence_id != SequenceId::SENTINEL {
             return Err(anyhow::anyhow!("`sequence_id` must be `SequenceId::SENTINEL` in `{:#?}`", seq).into());
         }
@@ -784,6 +983,7 @@ impl MutTxId {
     }
 
     pub fn drop_sequence(&mut self, sequence_id: SequenceId) -> Result<()> {
+        self.undo_log.record_schema_change();
         let st_sequence_ref = self
             .iter_by_col_eq(ST_SEQUENCE_ID, StSequenceFields::SequenceId, &sequence_id.into())?
             .next()
@@ -827,6 +1027,7 @@ impl MutTxId {
     /// - The constraint metadata is inserted into the system tables (and other data structures reflecting them).
     /// - The returned ID is unique and is not `constraintId::SENTINEL`.
     fn create_constraint(&mut self, mut constraint: ConstraintSchema) -> Result<ConstraintId> {
+        self.undo_log.record_schema_change();
         if constraint.constraint_id != ConstraintId::SENTINEL {
             return Err(anyhow::anyhow!(
                 "`constraint_id` must be `ConstraintId::SENTINEL` in `{:#?}`",
@@ -884,6 +1085,7 @@ impl MutTxId {
     }
 
     pub fn drop_constraint(&mut self, constraint_id: ConstraintId) -> Result<()> {
+        self.undo_log.record_schema_change();
         // Delete row in `st_constraint`.
         let st_constraint_ref = self
             .iter_by_col_eq(
@@ -967,20 +1169,6 @@ impl MutTxId {
         Ok(row_level_security_sql)
     }
 
-    pub fn row_level_security_for_table_id(&self, table_id: TableId) -> Result<Vec<RowLevelSecuritySchema>> {
-        Ok(self
-            .iter_by_col_eq(
-                ST_ROW_LEVEL_SECURITY_ID,
-                StRowLevelSecurityFields::TableId,
-                &table_id.into(),
-            )?
-            .map(|row| {
-                let row = StRowLevelSecurityRow::try_from(row).unwrap();
-                row.into()
-            })
-            .collect())
-    }
-
     pub fn drop_row_level_security(&mut self, sql: RawSql) -> Result<()> {
         let st_rls_ref = self
             .iter_by_col_eq(
@@ -1041,7 +1229,16 @@ impl MutTxId {
         })
     }
 
+    /// Returns whether this transaction inserts any rows into user tables.
+    pub(crate) fn inserts_into_user_tables(&self) -> bool {
+        self.tx_state
+            .insert_tables
+            .iter()
+            .any(|(table_id, table)| table_id.0 > ST_RESERVED_SEQUENCE_RANGE && table.row_count > 0)
+    }
+
     pub fn commit(self) -> TxData {
+        self.record_read_metrics();
         let Self {
             mut committed_state_write_lock,
             tx_state,
@@ -1062,6 +1259,7 @@ impl MutTxId {
     }
 
     pub fn commit_downgrade(mut self, workload: Workload) -> (TxData, TxId) {
+        self.record_read_metrics();
         let Self {
             mut committed_state_write_lock,
             tx_state,
@@ -1090,12 +1288,14 @@ impl MutTxId {
     }
 
     pub fn rollback(self) {
+        self.record_read_metrics();
         // Record metrics for the transaction at the very end,
         // right before we drop and release the lock.
         record_metrics(&self.ctx, self.timer, self.lock_wait_time, false, None, None);
     }
 
     pub fn rollback_downgrade(mut self, workload: Workload) -> TxId {
+        self.record_read_metrics();
         // Record metrics for the transaction at the very end,
         // right before we drop and release the lock.
         record_metrics(&self.ctx, self.timer, self.lock_wait_time, false, None, None);
@@ -1169,7 +1369,208 @@ impl<'a> Iterator for IndexScanFilterDeleted<'a> {
     }
 }
 
+/// A point within a [`MutTxId`] which the transaction can be rolled back to
+/// via [`MutTxId::rollback_to_savepoint`],
+/// discarding the writes it has made since, but not those made before.
+///
+/// A savepoint must be either rolled back to or released via [`MutTxId::release_savepoint`],
+/// as the transaction records its writes for as long as it has savepoints.
+pub struct Savepoint {
+    /// The length of the undo log when the savepoint was taken.
+    undo_len: usize,
+}
+
+/// The writes a [`MutTxId`] has made since its oldest savepoint was taken,
+/// oldest first, each recorded as what it takes to undo it.
+///
+/// Nothing is recorded while the transaction has no savepoints,
+/// so a transaction not using savepoints pays only for a branch per write.
+#[derive(Default)]
+pub(super) struct UndoLog {
+    /// The number of savepoints neither rolled back to nor released.
+    savepoints: usize,
+    entries: Vec<Undo>,
+}
+
+/// A write recorded in an [`UndoLog`].
+enum Undo {
+    /// The row was inserted into the insert table of the table.
+    Insert(TableId, ProductValue),
+    /// The row was deleted from the insert table of the table.
+    Delete(TableId, ProductValue),
+    /// The committed row was marked as deleted.
+    MarkDeleted(TableId, RowPointer),
+    /// The committed row was no longer marked as deleted.
+    Undelete(TableId, RowPointer),
+    /// Every committed row was marked as deleted,
+    /// replacing the delete table held before.
+    Truncate(TableId, DeleteTable),
+    /// A table, index, sequence or constraint was created, dropped or altered,
+    /// which can't be undone.
+    SchemaChange,
+}
+
+impl UndoLog {
+    /// Returns whether writes to the table `table_id` are recorded.
+    ///
+    /// Writes to `st_sequence` never are, as sequence allocations must never be rolled back,
+    /// lest values generated after a savepoint be generated again.
+    fn is_recording(&self, table_id: TableId) -> bool {
+        self.savepoints != 0 && table_id != ST_SEQUENCE_ID
+    }
+
+    /// Records the write `undo` to the table `table_id`, if writes to it are recorded.
+    fn record(&mut self, table_id: TableId, undo: impl FnOnce() -> Undo) {
+        if self.is_recording(table_id) {
+            self.entries.push(undo());
+        }
+    }
+
+    /// Records that the schema changed, if there are savepoints.
+    fn record_schema_change(&mut self) {
+        if self.savepoints != 0 && !matches!(self.entries.last(), Some(Undo::SchemaChange)) {
+            self.entries.push(Undo::SchemaChange);
+        }
+    }
+
+    /// Forgets `savepoint`, and with it, the recorded writes if it was the last savepoint.
+    fn release(&mut self, savepoint: Savepoint) {
+        debug_assert!(savepoint.undo_len <= self.entries.len());
+        self.savepoints -= 1;
+        if self.savepoints == 0 {
+            self.entries.clear();
+        }
+    }
+}
+
 impl MutTxId {
+    /// Start recording the tables read by the module during this transaction.
+    ///
+    /// Reads are reported by the host with [`MutTxId::record_read`],
+    /// as they are made through the module ABI rather than by the datastore.
+    pub fn record_reads(&mut self) {
+        self.read_tables.get_or_insert_with(IntSet::default);
+    }
+
+    /// Record that the module read the table `table_id`, if reads are being recorded.
+    pub fn record_read(&mut self, table_id: TableId) {
+        if let Some(read_tables) = &mut self.read_tables {
+            read_tables.insert(table_id);
+        }
+    }
+
+    /// Returns the tables read by the module during this transaction,
+    /// or `None` if reads weren't recorded.
+    pub fn take_read_tables(&mut self) -> Option<IntSet<TableId>> {
+        self.read_tables.take()
+    }
+
+    /// Count a read of the table `table_id` towards its usage, as reported by `spacetime describe`.
+    fn count_read(&self, table_id: TableId, count: impl FnOnce(&mut TableReads)) {
+        count(self.table_reads.lock().entry(table_id).or_default());
+    }
+
+    /// Add the reads of tables counted during this transaction to [`DB_METRICS`].
+    fn record_read_metrics(&self) {
+        let workload = &self.ctx.workload();
+        let db = &self.ctx.database_identity();
+        let reducer = self.ctx.reducer_name();
+        for (table_id, reads) in self.table_reads.lock().drain() {
+            // The table may have been dropped by this transaction.
+            let Some(schema) = self.get_schema(table_id) else {
+                continue;
+            };
+            let table_name = &schema.table_name;
+            if reads.index_seeks > 0 {
+                DB_METRICS
+                    .rdb_num_index_seeks
+                    .with_label_values(workload, db, reducer, &table_id.0, table_name)
+                    .inc_by(reads.index_seeks);
+            }
+            if reads.table_scans > 0 {
+                DB_METRICS
+                    .rdb_num_table_scans
+                    .with_label_values(workload, db, reducer, &table_id.0, table_name)
+                    .inc_by(reads.table_scans);
+            }
+        }
+    }
+
+    /// Returns a [`Savepoint`] at the writes this transaction has made so far.
+    ///
+    /// Taking a savepoint is constant-time,
+    /// but until it is rolled back to or released,
+    /// every write the transaction makes is recorded so that it can be undone.
+    pub fn savepoint(&mut self) -> Savepoint {
+        self.undo_log.savepoints += 1;
+        Savepoint {
+            undo_len: self.undo_log.entries.len(),
+        }
+    }
+
+    /// Keep the writes this transaction has made since `savepoint` was taken,
+    /// releasing `savepoint`.
+    ///
+    /// `savepoint` must have been taken by this transaction.
+    pub fn release_savepoint(&mut self, savepoint: Savepoint) {
+        self.undo_log.release(savepoint);
+    }
+
+    /// Discard the writes this transaction has made since `savepoint` was taken,
+    /// releasing `savepoint`.
+    ///
+    /// `savepoint` must have been taken by this transaction,
+    /// and any savepoints taken since must have been released.
+    ///
+    /// Fails with [`TableError::SchemaChangedSinceSavepoint`], discarding nothing,
+    /// if a table, index, sequence or constraint was created, dropped or altered since.
+    /// `savepoint` is released regardless.
+    pub fn rollback_to_savepoint(&mut self, savepoint: Savepoint) -> Result<()> {
+        let undo_len = savepoint.undo_len;
+        let since = &self.undo_log.entries[undo_len..];
+        if since.iter().any(|undo| matches!(undo, Undo::SchemaChange)) {
+            self.undo_log.release(savepoint);
+            return Err(TableError::SchemaChangedSinceSavepoint.into());
+        }
+
+        // Undo the writes newest first, so that each is undone in the state it left behind.
+        // Undoing writes them again, so don't record them while doing so.
+        let mut undo_log = mem::take(&mut self.undo_log);
+        let res = undo_log
+            .entries
+            .drain(undo_len..)
+            .rev()
+            .try_for_each(|undo| self.undo(undo));
+        self.undo_log = undo_log;
+        self.undo_log.release(savepoint);
+        res
+    }
+
+    /// Undoes the write `undo`, which must be the last write made that hasn't been undone.
+    fn undo(&mut self, undo: Undo) -> Result<()> {
+        match undo {
+            Undo::Insert(table_id, row) => {
+                self.delete_by_row_value(table_id, &row)?;
+            }
+            Undo::Delete(table_id, row) => {
+                // The row already holds any values generated for it, so don't generate new ones.
+                let row = bsatn::to_vec(&row).expect("encoding a `ProductValue` as BSATN should never fail");
+                self.insert::<false>(table_id, &row)?;
+            }
+            Undo::MarkDeleted(table_id, ptr) => {
+                self.tx_state.get_delete_table_mut(table_id).remove(&ptr);
+            }
+            Undo::Undelete(table_id, ptr) => {
+                self.tx_state.get_delete_table_mut(table_id).insert(ptr);
+            }
+            Undo::Truncate(table_id, before) => {
+                *self.tx_state.get_delete_table_mut(table_id) = before;
+            }
+            Undo::SchemaChange => unreachable!("schema changes are never undone"),
+        }
+        Ok(())
+    }
+
     pub(crate) fn insert_via_serialize_bsatn<'a, T: Serialize>(
         &'a mut self,
         table_id: TableId,
@@ -1190,6 +1591,8 @@ impl MutTxId {
     /// Zero placeholders, i.e., sequence triggers,
     /// in auto-inc columns in the new row will be replaced with generated values
     /// if and only if `GENERATE` is true.
+    /// Likewise, the table's generated columns are computed from the row's other columns
+    /// if and only if `GENERATE` is true.
     /// This method is called with `GENERATE` false when updating the `st_sequence` system table.
     ///
     /// Requires:
@@ -1213,8 +1616,13 @@ impl MutTxId {
             )
             .ok_or(TableError::IdNotFoundState(table_id))?;
 
-        // 1. Insert the physical row.
-        let (tx_row_ref, blob_bytes) = tx_table.insert_physically_bsatn(tx_blob_store, row)?;
+        // 1. Compute the generated columns, if any, and insert the physical row.
+        let row = if GENERATE {
+            compute_generated_columns(tx_table, row)?
+        } else {
+            Cow::Borrowed(row)
+        };
+        let (tx_row_ref, blob_bytes) = tx_table.insert_physically_bsatn(tx_blob_store, &row)?;
         // 2. Optionally: Detect, generate, write sequence values.
         // 3. Confirm that the insertion respects constraints and update statistics.
         // 4. Post condition (PC.INS.1):
@@ -1314,7 +1722,9 @@ impl MutTxId {
                         // It's possible that `row` appears in the committed state,
                         // but is marked as deleted.
                         // In this case, undelete it, so it remains in the committed state.
-                        delete_table.remove(&commit_ptr);
+                        if delete_table.remove(&commit_ptr) {
+                            self.undo_log.record(table_id, || Undo::Undelete(table_id, commit_ptr));
+                        }
 
                         // No new row was inserted, but return `committed_ptr`.
                         let blob_store = &self.committed_state_write_lock.blob_store;
@@ -1345,12 +1755,12 @@ impl MutTxId {
                     }
                 }
 
-                let rri = RowRefInsertion::Inserted(unsafe {
-                    // SAFETY: `tx_row_ptr` is still correct for `tx_table` per (PC.INS.1).
-                    // as there haven't been any interleaving `&mut` calls that could invalidate the pointer.
-                    tx_table.get_row_ref_unchecked(tx_blob_store, tx_row_ptr)
-                });
-                Ok((gen_cols, rri))
+                // SAFETY: `tx_row_ptr` is still correct for `tx_table` per (PC.INS.1).
+                // as there haven't been any interleaving `&mut` calls that could invalidate the pointer.
+                let tx_row_ref = unsafe { tx_table.get_row_ref_unchecked(tx_blob_store, tx_row_ptr) };
+                self.undo_log
+                    .record(table_id, || Undo::Insert(table_id, tx_row_ref.to_product_value()));
+                Ok((gen_cols, RowRefInsertion::Inserted(tx_row_ref)))
             }
             // `row` previously present in insert tables; do nothing but return `ptr`.
             Err(InsertError::Duplicate(DuplicateError(ptr))) => {
@@ -1398,7 +1808,8 @@ impl MutTxId {
                 self.committed_state_write_lock.get_table(table_id),
             )
             .ok_or(TableError::IdNotFoundState(table_id))?;
-        let (tx_row_ref, blob_bytes) = tx_table.insert_physically_bsatn(tx_blob_store, row)?;
+        let row = compute_generated_columns(tx_table, row)?;
+        let (tx_row_ref, blob_bytes) = tx_table.insert_physically_bsatn(tx_blob_store, &row)?;
 
         // 2. Detect, generate, write sequence values in the new row.
         //----------------------------------------------------------------------
@@ -1502,6 +1913,7 @@ impl MutTxId {
                 let (_, tx_row_ptr) = unsafe { tx_table.confirm_insertion(tx_blob_store, tx_row_ptr, blob_bytes) }?;
                 // Delete the old row.
                 del_table.insert(old_ptr);
+                self.undo_log.record(table_id, || Undo::MarkDeleted(table_id, old_ptr));
                 tx_row_ptr
             } else if let Some(tx_index) =
                 // Either the row was not found in the committed state index,
@@ -1541,9 +1953,17 @@ impl MutTxId {
                             unsafe { tx_table.confirm_insertion(tx_blob_store, tx_row_ptr, blob_bytes) }?;
                         // Delete the old row.
                         del_table.insert(old_ptr);
+                        self.undo_log.record(table_id, || Undo::MarkDeleted(table_id, old_ptr));
                         tx_row_ptr
                     }
                     SquashedOffset::TX_STATE => {
+                        let old_row = if self.undo_log.is_recording(table_id) {
+                            // SAFETY: `self.is_row_present(old_ptr)` holds as we haven't deleted it.
+                            let old_row = unsafe { tx_table.get_row_ref_unchecked(tx_blob_store, old_ptr) };
+                            Some(old_row.to_product_value())
+                        } else {
+                            None
+                        };
                         // Check constraints and confirm the update of the new row.
                         // This ensures that the old row is removed from the indices
                         // before attempting to insert the new row into the indices.
@@ -1551,8 +1971,13 @@ impl MutTxId {
                         // SAFETY: `self.is_row_present(tx_row_ptr)` and `self.is_row_present(old_ptr)` both hold
                         // as we've deleted neither.
                         // In particular, the `write_gen_val_to_col` call does not remove the row.
-                        unsafe { tx_table.confirm_update(tx_blob_store, tx_row_ptr, old_ptr, blob_bytes) }
-                            .map_err(IndexError::UniqueConstraintViolation)?
+                        let tx_row_ptr =
+                            unsafe { tx_table.confirm_update(tx_blob_store, tx_row_ptr, old_ptr, blob_bytes) }
+                                .map_err(IndexError::UniqueConstraintViolation)?;
+                        if let Some(old_row) = old_row {
+                            self.undo_log.record(table_id, || Undo::Delete(table_id, old_row));
+                        }
+                        tx_row_ptr
                     }
                     _ => unreachable!("Invalid SquashedOffset for RowPointer: {:?}", old_ptr),
                 }
@@ -1564,6 +1989,10 @@ impl MutTxId {
             // per post-condition of `confirm_insertion` and `confirm_update`
             // in the if/else branches respectively.
             let tx_row_ref = unsafe { tx_table.get_row_ref_unchecked(tx_blob_store, tx_row_ptr) };
+            // Record the insertion after the deletion of the old row,
+            // so that undoing them removes the new row before restoring the old one.
+            self.undo_log
+                .record(table_id, || Undo::Insert(table_id, tx_row_ref.to_product_value()));
             return Ok((cols_to_gen, tx_row_ref));
         };
 
@@ -1583,19 +2012,189 @@ impl MutTxId {
                     .tx_state
                     .get_table_and_blob_store(table_id)
                     .ok_or_else(|| TableError::IdNotFoundState(table_id))?;
-                Ok(table.delete(blob_store, row_pointer, |_| ()).is_some())
+                let recording = self.undo_log.is_recording(table_id);
+                let Some(row) = table.delete(blob_store, row_pointer, |row| recording.then(|| row.to_product_value()))
+                else {
+                    return Ok(false);
+                };
+                if let Some(row) = row {
+                    self.undo_log.record(table_id, || Undo::Delete(table_id, row));
+                }
+                Ok(true)
             }
             SquashedOffset::COMMITTED_STATE => {
                 // NOTE: We trust the `row_pointer` refers to an extant row,
                 // and check only that it hasn't yet been deleted.
                 let delete_table = self.tx_state.get_delete_table_mut(table_id);
 
-                Ok(delete_table.insert(row_pointer))
+                let deleted = delete_table.insert(row_pointer);
+                if deleted {
+                    self.undo_log
+                        .record(table_id, || Undo::MarkDeleted(table_id, row_pointer));
+                }
+                Ok(deleted)
             }
             _ => unreachable!("Invalid SquashedOffset for RowPointer: {:?}", row_pointer),
         }
     }
 
+    /// Deletes every row of the table `table_id` visible to this transaction,
+    /// returning how many rows were deleted.
+    ///
+    /// Rows inserted by this transaction are dropped immediately.
+    /// Committed rows are marked deleted without being read,
+    /// so that merging the transaction clears the committed table wholesale.
+    pub(super) fn truncate(&mut self, table_id: TableId) -> Result<u64> {
+        let commit_table = self.committed_state_write_lock.get_table(table_id);
+        let tx_table = self.tx_state.get_table_and_blob_store(table_id);
+        if commit_table.is_none() && tx_table.is_none() {
+            return Err(TableError::IdNotFoundState(table_id).into());
+        }
+
+        let mut num_deleted = 0;
+        if let Some((tx_table, tx_blob_store)) = tx_table {
+            num_deleted += tx_table.row_count;
+            if self.undo_log.is_recording(table_id) {
+                for row_ref in tx_table.scan_rows(tx_blob_store) {
+                    self.undo_log
+                        .record(table_id, || Undo::Delete(table_id, row_ref.to_product_value()));
+                }
+            }
+            tx_table.clear(tx_blob_store);
+        }
+        if let Some(commit_table) = commit_table {
+            let commit_count = commit_table.row_count;
+            let delete_table = self.tx_state.get_delete_table_mut(table_id);
+            num_deleted += commit_count - delete_table.len(commit_count);
+            let before = delete_table.clear_all();
+            self.undo_log.record(table_id, || Undo::Truncate(table_id, before));
+        }
+        Ok(num_deleted)
+    }
+
+    /// Returns whether the datastore keeps anything derived from the rows of the table `table_id`,
+    /// i.e., counts or history, which must be told of the rows inserted into and deleted from the table.
+    pub(super) fn has_row_observers(&self, table_id: TableId) -> bool {
+        self.get_schema(table_id)
+            .is_some_and(|schema| !schema.counters.is_empty() || schema.soft_delete.is_some())
+    }
+
+    /// Updates the counts and history kept of the table `table_id` for the insertion of `row`.
+    pub(super) fn observe_insert(&mut self, table_id: TableId, row: &ProductValue) -> Result<()> {
+        self.offset_counters(table_id, row, 1)?;
+        self.record_history(table_id, row, false)
+    }
+
+    /// Updates the counts and history kept of the table `table_id` for the deletion of `row`.
+    pub(super) fn observe_delete(&mut self, table_id: TableId, row: &ProductValue) -> Result<()> {
+        self.offset_counters(table_id, row, -1)?;
+        self.record_history(table_id, row, true)
+    }
+
+    /// Adds `delta` to the counts kept for `row`, a row of the table `table_id`,
+    /// in the rows whose primary key `row` refers to.
+    ///
+    /// A row referring to no row of the table holding the counts isn't counted.
+    fn offset_counters(&mut self, table_id: TableId, row: &ProductValue, delta: i32) -> Result<()> {
+        let Some(schema) = self.get_schema(table_id).cloned() else {
+            return Ok(());
+        };
+        for counter in &schema.counters {
+            let Some(into_table_id) = self.table_id_from_name(&counter.into_table)? else {
+                continue;
+            };
+            let into_schema = self.schema_for_table(into_table_id)?;
+            let Some(primary_key) = into_schema.primary_key else {
+                continue;
+            };
+            let Some(index_id) = into_schema
+                .indexes
+                .iter()
+                .find(|index| index.index_algorithm.columns().as_singleton() == Some(primary_key))
+                .map(|index| index.index_id)
+            else {
+                continue;
+            };
+
+            let key = row.get_field(counter.group_by.idx(), None)?;
+            let Some(into_row) = self.iter_by_col_eq(into_table_id, primary_key, key)?.next() else {
+                continue;
+            };
+            let mut into_row = into_row.to_product_value();
+            let count = &mut into_row.elements[counter.into_column.idx()];
+            *count = offset_count(count, delta);
+            let into_row = bsatn::to_vec(&into_row).expect("encoding a `ProductValue` as BSATN should never fail");
+            self.update(into_table_id, index_id, &into_row)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the row of the table `table_id` which `update` would replace with `row`,
+    /// looking it up in the unique index `index_id`.
+    pub(super) fn find_row_to_update(
+        &self,
+        table_id: TableId,
+        index_id: IndexId,
+        row: &[u8],
+    ) -> Result<Option<ProductValue>> {
+        let schema = self.schema_for_table(table_id)?;
+        let Some(index) = schema.indexes.iter().find(|index| index.index_id == index_id) else {
+            return Ok(None);
+        };
+        let cols = index.index_algorithm.columns().clone();
+        let row: ProductValue = bsatn::decode(schema.get_row_type(), &mut &*row)?;
+        let key = row.project(&cols)?;
+        Ok(self
+            .iter_by_col_eq(table_id, cols, &key)?
+            .next()
+            .map(|row_ref| row_ref.to_product_value()))
+    }
+
+    /// Records in the history of the table `table_id` that this transaction inserted or, if `deleted`, deleted `row`,
+    /// and discards the history which the table no longer retains.
+    ///
+    /// Recording the opposite of an event of this same transaction cancels the event out instead,
+    /// so that only the net effect of the transaction is recorded.
+    fn record_history(&mut self, table_id: TableId, row: &ProductValue, deleted: bool) -> Result<()> {
+        let Some(soft_delete) = self.get_schema(table_id).and_then(|schema| schema.soft_delete) else {
+            return Ok(());
+        };
+        // Recording history writes to `st_row_history`, which is durable,
+        // so this transaction will be committed with the next offset.
+        let tx_offset = self.committed_state_write_lock.next_tx_offset;
+        let row = bsatn::to_vec(row).expect("encoding a `ProductValue` as BSATN should never fail");
+        let opposite = StRowHistoryRow {
+            table_id,
+            tx_offset,
+            deleted: !deleted,
+            row: row.into(),
+        };
+        if !self.delete_by_row_value(ST_ROW_HISTORY_ID, &opposite.clone().into())? {
+            let event = StRowHistoryRow { deleted, ..opposite };
+            self.insert_via_serialize_bsatn(ST_ROW_HISTORY_ID, &event)?;
+        }
+
+        if let Some(expired) = tx_offset.checked_sub(soft_delete.retention) {
+            self.drop_history(table_id, 0..=expired)?;
+        }
+        Ok(())
+    }
+
+    /// Deletes the history of the table `table_id` recorded by the transactions `tx_offsets`.
+    fn drop_history(&mut self, table_id: TableId, tx_offsets: RangeInclusive<u64>) -> Result<()> {
+        let cols = col_list![StRowHistoryFields::TableId, StRowHistoryFields::TxOffset];
+        let key = |tx_offset: u64| AlgebraicValue::product([AlgebraicValue::from(table_id), tx_offset.into()]);
+        let range = key(*tx_offsets.start())..=key(*tx_offsets.end());
+        let ptrs = self
+            .iter_by_col_range(ST_ROW_HISTORY_ID, cols, range)?
+            .map(|row_ref| row_ref.pointer())
+            .collect::<Vec<_>>();
+        for ptr in ptrs {
+            self.delete(ST_ROW_HISTORY_ID, ptr)?;
+        }
+        Ok(())
+    }
+
     pub(super) fn delete_by_row_value(&mut self, table_id: TableId, rel: &ProductValue) -> Result<bool> {
         // Four cases here:
         // - Table exists in both tx_state and committed_state.
@@ -1684,7 +2283,7 @@ impl StateView for MutTxId {
 
     fn table_row_count(&self, table_id: TableId) -> Option<u64> {
         let commit_count = self.committed_state_write_lock.table_row_count(table_id);
-        let (tx_ins_count, tx_del_count) = self.tx_state.table_row_count(table_id);
+        let (tx_ins_count, tx_del_count) = self.tx_state.table_row_count(table_id, commit_count.unwrap_or(0));
         let commit_count = commit_count.map(|cc| cc - tx_del_count);
         // Keep track of whether `table_id` exists.
         match (commit_count, tx_ins_count) {
@@ -1696,6 +2295,7 @@ impl StateView for MutTxId {
 
     fn iter(&self, table_id: TableId) -> Result<Self::Iter<'_>> {
         if self.table_name(table_id).is_some() {
+            self.count_read(table_id, |reads| reads.table_scans += 1);
             return Ok(IterMutTx::new(
                 table_id,
                 &self.tx_state,
@@ -1723,6 +2323,7 @@ impl StateView for MutTxId {
         // yet. In particular, I don't know if creating an index in a transaction and
         // rolling it back will leave the index in place.
         if let Some(inserted_rows) = self.tx_state.index_seek_by_cols(table_id, &cols, &range) {
+            self.count_read(table_id, |reads| reads.index_seeks += 1);
             let committed_rows = self.committed_state_write_lock.index_seek(table_id, &cols, &range);
             // The current transaction has modified this table, and the table is indexed.
             Ok(if let Some(del_table) = self.tx_state.get_delete_table(table_id) {
@@ -1741,14 +2342,17 @@ impl StateView for MutTxId {
             // Either the current transaction has not modified this table, or the table is not
             // indexed.
             match self.committed_state_write_lock.index_seek(table_id, &cols, &range) {
-                Some(committed_rows) => Ok(if let Some(del_table) = self.tx_state.get_delete_table(table_id) {
-                    IterByColRangeMutTx::CommittedIndexWithDeletes(CommittedIndexIterWithDeletedMutTx::new(
-                        committed_rows,
-                        del_table,
-                    ))
-                } else {
-                    IterByColRangeMutTx::CommittedIndex(committed_rows)
-                }),
+                Some(committed_rows) => {
+                    self.count_read(table_id, |reads| reads.index_seeks += 1);
+                    Ok(if let Some(del_table) = self.tx_state.get_delete_table(table_id) {
+                        IterByColRangeMutTx::CommittedIndexWithDeletes(CommittedIndexIterWithDeletedMutTx::new(
+                            committed_rows,
+                            del_table,
+                        ))
+                    } else {
+                        IterByColRangeMutTx::CommittedIndex(committed_rows)
+                    })
+                }
                 None => {
                     #[cfg(feature = "unindexed_iter_by_col_range_warn")]
                     match self.table_row_count(table_id) {
@@ -61,6 +61,36 @@ impl Sequence {
         Some(value)
     }
 
+    /// Returns the value which the sequence will generate next.
+    pub(super) fn peek_value(&self) -> i128 {
+        self.value
+    }
+
+    /// Moves the sequence past `value`,
+    /// so that it generates the value one increment after `value` next,
+    /// returning whether the sequence moved.
+    ///
+    /// Does nothing if `value` is outside the range of the sequence,
+    /// or if the sequence has not yet reached `value`.
+    pub(super) fn advance_past(&mut self, value: i128) -> bool {
+        let SequenceSchema {
+            min_value,
+            max_value,
+            increment,
+            ..
+        } = self.schema;
+        let reached = if increment > 0 {
+            value >= self.value
+        } else {
+            value <= self.value
+        };
+        if !reached || value < min_value || value > max_value {
+            return false;
+        }
+        self.value = Self::next_in_sequence(min_value, max_value, increment, value);
+        true
+    }
+
     pub(super) fn allocated(&self) -> i128 {
         self.schema.allocated
     }
@@ -94,7 +124,7 @@ impl Sequence {
     /// 5. restart
     /// 6. incr = 1 allocated = 10, value = 10
     /// 7. next_value() -> 11
-    fn needs_allocation(&self) -> bool {
+    pub(super) fn needs_allocation(&self) -> bool {
         // In order to yield a value, it must be strictly less than the allocation amount,
         // because on restart we will begin at the allocation amount.
         self.value >= self.schema.allocated
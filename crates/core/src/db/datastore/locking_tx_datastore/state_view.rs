@@ -6,16 +6,20 @@ use super::{
 use crate::db::datastore::locking_tx_datastore::committed_state::CommittedIndexIterWithDeletedMutTx;
 use crate::{
     db::datastore::system_tables::{
-        StColumnFields, StColumnRow, StConstraintFields, StConstraintRow, StIndexFields, StIndexRow, StScheduledFields,
-        StScheduledRow, StSequenceFields, StSequenceRow, StTableFields, StTableRow, SystemTable, ST_COLUMN_ID,
-        ST_CONSTRAINT_ID, ST_INDEX_ID, ST_SCHEDULED_ID, ST_SEQUENCE_ID, ST_TABLE_ID,
+        StColumnFields, StColumnRow, StConstraintFields, StConstraintRow, StCounterFields, StCounterRow,
+        StGeneratedColumnFields, StGeneratedColumnRow, StIndexFields, StIndexRow, StRowLevelSecurityFields,
+        StRowLevelSecurityRow, StScheduledFields, StScheduledRow, StSequenceFields, StSequenceRow, StSoftDeleteFields,
+        StSoftDeleteRow, StTableDurabilityFields, StTableDurabilityRow, StTableFields, StTableRow, SystemTable,
+        ST_COLUMN_ID, ST_CONSTRAINT_ID, ST_COUNTER_ID, ST_GENERATED_COLUMN_ID, ST_INDEX_ID, ST_ROW_LEVEL_SECURITY_ID,
+        ST_SCHEDULED_ID, ST_SEQUENCE_ID, ST_SOFT_DELETE_ID, ST_TABLE_DURABILITY_ID, ST_TABLE_ID,
     },
     error::TableError,
 };
 use core::ops::RangeBounds;
+use spacetimedb_lib::db::auth::StDurability;
 use spacetimedb_primitives::{ColList, TableId};
 use spacetimedb_sats::AlgebraicValue;
-use spacetimedb_schema::schema::{ColumnSchema, TableSchema};
+use spacetimedb_schema::schema::{ColumnSchema, GeneratedColumnSchema, RowLevelSecuritySchema, TableSchema};
 use spacetimedb_table::{
     blob_store::HashMapBlobStore,
     table::{IndexScanIter, RowRef, Table, TableScanIter},
@@ -130,7 +134,50 @@ pub trait StateView {
             })
             .transpose()?;
 
-        Ok(TableSchema::new(
+        // Look up the generated columns for the table in question.
+        let mut generated_columns = self
+            .iter_by_col_eq(ST_GENERATED_COLUMN_ID, StGeneratedColumnFields::TableId, value_eq)?
+            .map(|row| {
+                let row = StGeneratedColumnRow::try_from(row)?;
+                GeneratedColumnSchema::parse(table_id, row.col_pos, row.expr, &columns).map_err(|error| {
+                    TableError::InvalidGeneratedColumn {
+                        table_id,
+                        col_pos: row.col_pos,
+                        error,
+                    }
+                    .into()
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        generated_columns.sort_by_key(|col| col.col_pos);
+
+        // Look up the counters of the rows of the table in question.
+        let counters = self
+            .iter_by_col_eq(ST_COUNTER_ID, StCounterFields::TableId, value_eq)?
+            .map(|row| {
+                let row = StCounterRow::try_from(row)?;
+                Ok(row.into())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let soft_delete = self
+            .iter_by_col_eq(ST_SOFT_DELETE_ID, StSoftDeleteFields::TableId, value_eq)?
+            .next()
+            .map(|row| -> Result<_> {
+                let row = StSoftDeleteRow::try_from(row)?;
+                Ok(row.into())
+            })
+            .transpose()?;
+
+        // Tables without a row in `st_table_durability` are durable.
+        let durability = self
+            .iter_by_col_eq(ST_TABLE_DURABILITY_ID, StTableDurabilityFields::TableId, value_eq)?
+            .next()
+            .map(|row| -> Result<_> { Ok(StTableDurabilityRow::try_from(row)?.durability) })
+            .transpose()?
+            .unwrap_or(StDurability::Durable);
+
+        let mut schema = TableSchema::new(
             table_id,
             table_name,
             columns,
@@ -141,7 +188,12 @@ pub trait StateView {
             table_access,
             schedule,
             table_primary_key,
-        ))
+        );
+        schema.generated_columns = generated_columns;
+        schema.counters = counters;
+        schema.soft_delete = soft_delete;
+        schema.durability = durability;
+        Ok(schema)
     }
 
     /// Reads the schema information for the specified `table_id`, consulting the `cache` first.
@@ -156,6 +208,20 @@ pub trait StateView {
 
         self.schema_for_table_raw(table_id).map(Arc::new)
     }
+
+    /// Reads the row level security filters of the table `table_id`.
+    fn row_level_security_for_table_id(&self, table_id: TableId) -> Result<Vec<RowLevelSecuritySchema>> {
+        self.iter_by_col_eq(
+            ST_ROW_LEVEL_SECURITY_ID,
+            StRowLevelSecurityFields::TableId,
+            &table_id.into(),
+        )?
+        .map(|row| {
+            let row = StRowLevelSecurityRow::try_from(row)?;
+            Ok(row.into())
+        })
+        .collect()
+    }
 }
 
 pub struct IterMutTx<'a> {
@@ -6,13 +6,16 @@ use super::{
     IterByColEqTx, SharedReadGuard,
 };
 use crate::db::datastore::locking_tx_datastore::state_view::IterTx;
+use crate::db::datastore::system_tables::{StRowHistoryFields, StRowHistoryRow, ST_ROW_HISTORY_ID};
+use crate::error::TableError;
 use crate::execution_context::ExecutionContext;
 use spacetimedb_execution::Datastore;
-use spacetimedb_primitives::{ColList, TableId};
-use spacetimedb_sats::AlgebraicValue;
+use spacetimedb_primitives::{col_list, ColList, TableId};
+use spacetimedb_sats::{bsatn, AlgebraicValue, ProductValue};
 use spacetimedb_schema::schema::TableSchema;
 use spacetimedb_table::blob_store::BlobStore;
 use spacetimedb_table::table::Table;
+use std::collections::BTreeSet;
 use std::num::NonZeroU64;
 use std::sync::Arc;
 use std::{
@@ -103,4 +106,48 @@ impl TxId {
         let (_, index) = table.get_index_by_cols(cols)?;
         NonZeroU64::new(index.num_keys() as u64)
     }
+
+    /// Returns the rows of the table `table_id` as they were after the transaction `tx_offset`,
+    /// by undoing, from the latest to the earliest, the transactions recorded in the table's history since.
+    ///
+    /// Fails if the table doesn't keep its history, or no longer retains the history since `tx_offset`.
+    pub(crate) fn rows_as_of(&self, table_id: TableId, tx_offset: u64) -> Result<Vec<ProductValue>> {
+        let schema = self.schema_for_table(table_id)?;
+        let Some(soft_delete) = schema.soft_delete else {
+            return Err(TableError::NoHistory(schema.table_name.clone()).into());
+        };
+        let latest = self.committed_state_shared_lock.next_tx_offset.saturating_sub(1);
+        let oldest = latest.saturating_sub(soft_delete.retention);
+        if !(oldest..=latest).contains(&tx_offset) {
+            return Err(TableError::HistoryNotRetained {
+                table: schema.table_name.clone(),
+                tx_offset,
+                oldest,
+                latest,
+            }
+            .into());
+        }
+
+        let cols = col_list![StRowHistoryFields::TableId, StRowHistoryFields::TxOffset];
+        let key = |tx_offset: u64| AlgebraicValue::product([AlgebraicValue::from(table_id), tx_offset.into()]);
+        let mut events = self
+            .iter_by_col_range(ST_ROW_HISTORY_ID, cols, key(tx_offset + 1)..=key(u64::MAX))?
+            .map(StRowHistoryRow::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        events.sort_by_key(|event| event.tx_offset);
+
+        let mut rows = self
+            .iter(table_id)?
+            .map(|row_ref| row_ref.to_product_value())
+            .collect::<BTreeSet<_>>();
+        for event in events.into_iter().rev() {
+            let row = bsatn::decode(schema.get_row_type(), &mut &*event.row)?;
+            if event.deleted {
+                rows.insert(row);
+            } else {
+                rows.remove(&row);
+            }
+        }
+        Ok(rows.into_iter().collect())
+    }
 }
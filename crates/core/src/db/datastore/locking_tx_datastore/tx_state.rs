@@ -9,8 +9,93 @@ use spacetimedb_table::{
     table::{IndexScanIter, RowRef, Table, TableAndIndex},
 };
 use std::collections::{btree_map, BTreeMap, BTreeSet};
+use std::mem;
 
-pub(super) type DeleteTable = BTreeSet<RowPointer>;
+/// The previously-committed rows of a table deleted in a transaction.
+///
+/// Truncating a table marks every committed row as deleted at once,
+/// without reading any of them, by setting `cleared`.
+/// From then on, `rows` holds the committed rows which are *not* deleted,
+/// i.e., those re-inserted since the truncate.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub(super) struct DeleteTable {
+    cleared: bool,
+    rows: BTreeSet<RowPointer>,
+}
+
+impl DeleteTable {
+    /// Returns whether the committed row `ptr` is deleted.
+    pub(super) fn contains(&self, ptr: &RowPointer) -> bool {
+        self.cleared != self.rows.contains(ptr)
+    }
+
+    /// Marks the committed row `ptr` as deleted,
+    /// returning whether it wasn't already.
+    pub(super) fn insert(&mut self, ptr: RowPointer) -> bool {
+        if self.cleared {
+            self.rows.remove(&ptr)
+        } else {
+            self.rows.insert(ptr)
+        }
+    }
+
+    /// Marks the committed row `ptr` as no longer deleted,
+    /// returning whether it was.
+    pub(super) fn remove(&mut self, ptr: &RowPointer) -> bool {
+        if self.cleared {
+            self.rows.insert(*ptr)
+        } else {
+            self.rows.remove(ptr)
+        }
+    }
+
+    /// Returns whether no committed row is deleted.
+    pub(super) fn is_empty(&self) -> bool {
+        !self.cleared && self.rows.is_empty()
+    }
+
+    /// Returns the number of deleted rows of a committed table holding `committed_rows` rows.
+    pub(super) fn len(&self, committed_rows: u64) -> u64 {
+        let rows = self.rows.len() as u64;
+        if self.cleared {
+            committed_rows - rows
+        } else {
+            rows
+        }
+    }
+
+    /// Marks every committed row as deleted,
+    /// returning the delete table as it was before.
+    pub(super) fn clear_all(&mut self) -> Self {
+        mem::replace(
+            self,
+            Self {
+                cleared: true,
+                rows: BTreeSet::new(),
+            },
+        )
+    }
+
+    /// Returns whether every committed row is deleted.
+    pub(super) fn is_cleared(&self) -> bool {
+        self.cleared && self.rows.is_empty()
+    }
+
+    /// Returns the deleted rows of `committed_rows`, the rows of the committed table.
+    ///
+    /// Only iterates through `committed_rows` when the table was truncated.
+    pub(super) fn deleted_rows<'a>(
+        &'a self,
+        committed_rows: impl Iterator<Item = RowPointer> + 'a,
+    ) -> impl Iterator<Item = RowPointer> + 'a {
+        use itertools::Either::*;
+        if self.cleared {
+            Left(committed_rows.filter(|ptr| !self.rows.contains(ptr)))
+        } else {
+            Right(self.rows.iter().copied())
+        }
+    }
+}
 
 /// A mapping to find the actual index given an `IndexId`.
 pub(super) type IndexIdMap = IntMap<IndexId, TableId>;
@@ -81,9 +166,14 @@ static_assert_size!(TxState, 120);
 
 impl TxState {
     /// Returns the row count in insert tables
-    /// and the number of rows deleted from committed state.
-    pub(super) fn table_row_count(&self, table_id: TableId) -> (Option<u64>, u64) {
-        let del_count = self.delete_tables.get(&table_id).map(|dt| dt.len() as u64).unwrap_or(0);
+    /// and the number of rows deleted from committed state,
+    /// where the committed table holds `committed_rows` rows.
+    pub(super) fn table_row_count(&self, table_id: TableId, committed_rows: u64) -> (Option<u64>, u64) {
+        let del_count = self
+            .delete_tables
+            .get(&table_id)
+            .map(|dt| dt.len(committed_rows))
+            .unwrap_or(0);
         let ins_count = self.insert_tables.get(&table_id).map(|it| it.row_count);
         (ins_count, del_count)
     }
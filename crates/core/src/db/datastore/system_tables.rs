@@ -14,7 +14,7 @@
 use crate::db::relational_db::RelationalDB;
 use crate::error::DBError;
 use derive_more::From;
-use spacetimedb_lib::db::auth::{StAccess, StTableType};
+use spacetimedb_lib::db::auth::{StAccess, StDurability, StTableType};
 use spacetimedb_lib::db::raw_def::v9::{RawIndexAlgorithm, RawSql};
 use spacetimedb_lib::db::raw_def::*;
 use spacetimedb_lib::de::{Deserialize, DeserializeOwned, Error};
@@ -30,8 +30,8 @@ use spacetimedb_sats::{
 };
 use spacetimedb_schema::def::{BTreeAlgorithm, ConstraintData, IndexAlgorithm, ModuleDef, UniqueConstraintData};
 use spacetimedb_schema::schema::{
-    ColumnSchema, ConstraintSchema, IndexSchema, RowLevelSecuritySchema, ScheduleSchema, Schema, SequenceSchema,
-    TableSchema,
+    ColumnSchema, ConstraintSchema, CounterSchema, IndexSchema, RowLevelSecuritySchema, ScheduleSchema, Schema,
+    SequenceSchema, SoftDeleteSchema, TableSchema,
 };
 use spacetimedb_table::table::RowRef;
 use spacetimedb_vm::errors::{ErrorType, ErrorVm};
@@ -39,7 +39,7 @@ use spacetimedb_vm::ops::parse;
 use std::cell::RefCell;
 use std::str::FromStr;
 use strum::Display;
-use v9::{RawModuleDefV9Builder, TableType};
+use v9::{RawModuleDefV9Builder, TableAccess, TableDurability, TableType};
 
 use super::locking_tx_datastore::tx::TxId;
 use super::locking_tx_datastore::MutTxId;
@@ -66,6 +66,20 @@ pub(crate) const ST_SCHEDULED_ID: TableId = TableId(9);
 
 /// The static ID of the table that defines the row level security (RLS) policies
 pub(crate) const ST_ROW_LEVEL_SECURITY_ID: TableId = TableId(10);
+/// The static ID of the table that defines generated columns
+pub(crate) const ST_GENERATED_COLUMN_ID: TableId = TableId(11);
+/// The static ID of the table that defines the active subscriptions of clients
+pub(crate) const ST_SUBSCRIPTION_ID: TableId = TableId(12);
+/// The static ID of the table that defines the counters of grouped rows
+pub(crate) const ST_COUNTER_ID: TableId = TableId(13);
+/// The static ID of the table that defines which tables keep their history
+pub(crate) const ST_SOFT_DELETE_ID: TableId = TableId(14);
+/// The static ID of the table that holds the history of the tables which keep it
+pub(crate) const ST_ROW_HISTORY_ID: TableId = TableId(15);
+/// The static ID of the table that defines when connected clients connected
+pub(crate) const ST_CONNECTION_ID: TableId = TableId(16);
+/// The static ID of the table that defines which tables aren't durable
+pub(crate) const ST_TABLE_DURABILITY_ID: TableId = TableId(17);
 pub(crate) const ST_TABLE_NAME: &str = "st_table";
 pub(crate) const ST_COLUMN_NAME: &str = "st_column";
 pub(crate) const ST_SEQUENCE_NAME: &str = "st_sequence";
@@ -76,6 +90,13 @@ pub(crate) const ST_CLIENT_NAME: &str = "st_client";
 pub(crate) const ST_SCHEDULED_NAME: &str = "st_scheduled";
 pub(crate) const ST_VAR_NAME: &str = "st_var";
 pub(crate) const ST_ROW_LEVEL_SECURITY_NAME: &str = "st_row_level_security";
+pub(crate) const ST_GENERATED_COLUMN_NAME: &str = "st_generated_column";
+pub(crate) const ST_SUBSCRIPTION_NAME: &str = "st_subscription";
+pub(crate) const ST_COUNTER_NAME: &str = "st_counter";
+pub(crate) const ST_SOFT_DELETE_NAME: &str = "st_soft_delete";
+pub(crate) const ST_ROW_HISTORY_NAME: &str = "st_row_history";
+pub(crate) const ST_CONNECTION_NAME: &str = "st_connection";
+pub(crate) const ST_TABLE_DURABILITY_NAME: &str = "st_table_durability";
 /// Reserved range of sequence values used for system tables.
 ///
 /// Ids for user-created tables will start at `ST_RESERVED_SEQUENCE_RANGE + 1`.
@@ -103,7 +124,7 @@ pub enum SystemTable {
     st_row_level_security,
 }
 
-pub(crate) fn system_tables() -> [TableSchema; 10] {
+pub(crate) fn system_tables() -> [TableSchema; 17] {
     [
         // The order should match the `id` of the system table, that start with [ST_TABLE_IDX].
         st_table_schema(),
@@ -115,6 +136,13 @@ pub(crate) fn system_tables() -> [TableSchema; 10] {
         st_var_schema(),
         st_scheduled_schema(),
         st_row_level_security_schema(),
+        st_generated_column_schema(),
+        st_subscription_schema(),
+        st_counter_schema(),
+        st_soft_delete_schema(),
+        st_row_history_schema(),
+        st_connection_schema(),
+        st_table_durability_schema(),
         // Is important this is always last, so the starting sequence for each
         // system table is correct.
         st_sequence_schema(),
@@ -155,8 +183,15 @@ pub(crate) const ST_CLIENT_IDX: usize = 5;
 pub(crate) const ST_VAR_IDX: usize = 6;
 pub(crate) const ST_SCHEDULED_IDX: usize = 7;
 pub(crate) const ST_ROW_LEVEL_SECURITY_IDX: usize = 8;
+pub(crate) const ST_GENERATED_COLUMN_IDX: usize = 9;
+pub(crate) const ST_SUBSCRIPTION_IDX: usize = 10;
+pub(crate) const ST_COUNTER_IDX: usize = 11;
+pub(crate) const ST_SOFT_DELETE_IDX: usize = 12;
+pub(crate) const ST_ROW_HISTORY_IDX: usize = 13;
+pub(crate) const ST_CONNECTION_IDX: usize = 14;
+pub(crate) const ST_TABLE_DURABILITY_IDX: usize = 15;
 // Must be the last index in the array.
-pub(crate) const ST_SEQUENCE_IDX: usize = 9;
+pub(crate) const ST_SEQUENCE_IDX: usize = 16;
 
 macro_rules! st_fields_enum {
     ($(#[$attr:meta])* enum $ty_name:ident { $($name:expr, $var:ident = $discr:expr,)* }) => {
@@ -255,6 +290,12 @@ st_fields_enum!(enum StClientFields {
     "address", Address = 1,
 });
 // WARNING: For a stable schema, don't change the field names and discriminants.
+st_fields_enum!(enum StConnectionFields {
+    "identity", Identity = 0,
+    "address", Address = 1,
+    "connected_at", ConnectedAt = 2,
+});
+// WARNING: For a stable schema, don't change the field names and discriminants.
 st_fields_enum!(enum StVarFields {
     "name", Name = 0,
     "value", Value = 1,
@@ -268,6 +309,45 @@ st_fields_enum!(enum StScheduledFields {
     "at_column", AtColumn = 4,
 });
 
+// WARNING: For a stable schema, don't change the field names and discriminants.
+st_fields_enum!(enum StGeneratedColumnFields {
+    "table_id", TableId = 0,
+    "col_pos", ColPos = 1,
+    "expr", Expr = 2,
+});
+// WARNING: For a stable schema, don't change the field names and discriminants.
+st_fields_enum!(enum StSubscriptionFields {
+    "identity", Identity = 0,
+    "address", Address = 1,
+    "query", Query = 2,
+    "created_at", CreatedAt = 3,
+    "eval_cost", EvalCost = 4,
+});
+// WARNING: For a stable schema, don't change the field names and discriminants.
+st_fields_enum!(enum StCounterFields {
+    "table_id", TableId = 0,
+    "group_by", GroupBy = 1,
+    "into_table", IntoTable = 2,
+    "into_column", IntoColumn = 3,
+});
+// WARNING: For a stable schema, don't change the field names and discriminants.
+st_fields_enum!(enum StSoftDeleteFields {
+    "table_id", TableId = 0,
+    "retention", Retention = 1,
+});
+// WARNING: For a stable schema, don't change the field names and discriminants.
+st_fields_enum!(enum StTableDurabilityFields {
+    "table_id", TableId = 0,
+    "durability", Durability = 1,
+});
+// WARNING: For a stable schema, don't change the field names and discriminants.
+st_fields_enum!(enum StRowHistoryFields {
+    "table_id", TableId = 0,
+    "tx_offset", TxOffset = 1,
+    "deleted", Deleted = 2,
+    "row", Row = 3,
+});
+
 /// Helper method to check that a system table has the correct fields.
 /// Does not check field types since those aren't included in `StFields` types.
 /// If anything in here is not true, the system is completely broken, so it's fine to assert.
@@ -353,6 +433,14 @@ fn system_module_def() -> ModuleDef {
         .with_type(TableType::System)
         .with_unique_constraint(col_list![StClientFields::Identity, StClientFields::Address]); // FIXME: this is a noop?
 
+    // Kept apart from `st_client`, so that the layout of `st_client` stays the same
+    // for databases created before connection times were recorded.
+    let st_connection_type = builder.add_type::<StConnectionRow>();
+    builder
+        .build_table(ST_CONNECTION_NAME, *st_connection_type.as_ref().expect("should be ref"))
+        .with_type(TableType::System)
+        .with_unique_constraint(col_list![StConnectionFields::Identity, StConnectionFields::Address]);
+
     let st_schedule_type = builder.add_type::<StScheduledRow>();
     builder
         .build_table(ST_SCHEDULED_NAME, *st_schedule_type.as_ref().expect("should be ref"))
@@ -361,6 +449,72 @@ fn system_module_def() -> ModuleDef {
         .with_auto_inc_primary_key(StScheduledFields::ScheduleId);
     // TODO(1.0): unique constraint on name?
 
+    let st_generated_column_type = builder.add_type::<StGeneratedColumnRow>();
+    builder
+        .build_table(
+            ST_GENERATED_COLUMN_NAME,
+            *st_generated_column_type.as_ref().expect("should be ref"),
+        )
+        .with_type(TableType::System)
+        .with_unique_constraint(col_list![
+            StGeneratedColumnFields::TableId.col_id(),
+            StGeneratedColumnFields::ColPos.col_id()
+        ]);
+
+    // Subscriptions don't survive a restart, and their costs are updated frequently,
+    // so the table is kept out of the commitlog.
+    // The queries of clients are only for the eyes of the database owner.
+    let st_subscription_type = builder.add_type::<StSubscriptionRow>();
+    builder
+        .build_table(
+            ST_SUBSCRIPTION_NAME,
+            *st_subscription_type.as_ref().expect("should be ref"),
+        )
+        .with_type(TableType::System)
+        .with_access(TableAccess::Private)
+        .with_durability(TableDurability::Relaxed);
+
+    let st_counter_type = builder.add_type::<StCounterRow>();
+    builder
+        .build_table(ST_COUNTER_NAME, *st_counter_type.as_ref().expect("should be ref"))
+        .with_type(TableType::System);
+
+    // Kept apart from `st_table`, so that the layout of `st_table` stays the same
+    // for databases created before tables could be other than durable.
+    let st_table_durability_type = builder.add_type::<StTableDurabilityRow>();
+    builder
+        .build_table(
+            ST_TABLE_DURABILITY_NAME,
+            *st_table_durability_type.as_ref().expect("should be ref"),
+        )
+        .with_type(TableType::System)
+        .with_unique_constraint(StTableDurabilityFields::TableId);
+
+    let st_soft_delete_type = builder.add_type::<StSoftDeleteRow>();
+    builder
+        .build_table(
+            ST_SOFT_DELETE_NAME,
+            *st_soft_delete_type.as_ref().expect("should be ref"),
+        )
+        .with_type(TableType::System);
+
+    // The history of a table is read back from a transaction onwards, hence the index.
+    // It holds the rows of private tables too, so it's only for the eyes of the database owner.
+    let st_row_history_type = builder.add_type::<StRowHistoryRow>();
+    builder
+        .build_table(
+            ST_ROW_HISTORY_NAME,
+            *st_row_history_type.as_ref().expect("should be ref"),
+        )
+        .with_type(TableType::System)
+        .with_access(TableAccess::Private)
+        .with_index(
+            RawIndexAlgorithm::BTree {
+                columns: col_list![StRowHistoryFields::TableId, StRowHistoryFields::TxOffset],
+            },
+            "accessor_name_doesnt_matter",
+        );
+
     let st_var_type = builder.add_type::<StVarRow>();
     builder
         .build_table(ST_VAR_NAME, *st_var_type.as_ref().expect("should be ref"))
@@ -383,6 +537,13 @@ fn system_module_def() -> ModuleDef {
     validate_system_table::<StClientFields>(&result, ST_CLIENT_NAME);
     validate_system_table::<StVarFields>(&result, ST_VAR_NAME);
     validate_system_table::<StScheduledFields>(&result, ST_SCHEDULED_NAME);
+    validate_system_table::<StGeneratedColumnFields>(&result, ST_GENERATED_COLUMN_NAME);
+    validate_system_table::<StSubscriptionFields>(&result, ST_SUBSCRIPTION_NAME);
+    validate_system_table::<StCounterFields>(&result, ST_COUNTER_NAME);
+    validate_system_table::<StSoftDeleteFields>(&result, ST_SOFT_DELETE_NAME);
+    validate_system_table::<StRowHistoryFields>(&result, ST_ROW_HISTORY_NAME);
+    validate_system_table::<StConnectionFields>(&result, ST_CONNECTION_NAME);
+    validate_system_table::<StTableDurabilityFields>(&result, ST_TABLE_DURABILITY_NAME);
 
     result
 }
@@ -435,6 +596,30 @@ fn st_row_level_security_schema() -> TableSchema {
     st_schema(ST_ROW_LEVEL_SECURITY_NAME, ST_ROW_LEVEL_SECURITY_ID)
 }
 
+fn st_generated_column_schema() -> TableSchema {
+    st_schema(ST_GENERATED_COLUMN_NAME, ST_GENERATED_COLUMN_ID)
+}
+
+fn st_subscription_schema() -> TableSchema {
+    st_schema(ST_SUBSCRIPTION_NAME, ST_SUBSCRIPTION_ID)
+}
+
+fn st_counter_schema() -> TableSchema {
+    st_schema(ST_COUNTER_NAME, ST_COUNTER_ID)
+}
+
+fn st_table_durability_schema() -> TableSchema {
+    st_schema(ST_TABLE_DURABILITY_NAME, ST_TABLE_DURABILITY_ID)
+}
+
+fn st_soft_delete_schema() -> TableSchema {
+    st_schema(ST_SOFT_DELETE_NAME, ST_SOFT_DELETE_ID)
+}
+
+fn st_row_history_schema() -> TableSchema {
+    st_schema(ST_ROW_HISTORY_NAME, ST_ROW_HISTORY_ID)
+}
+
 pub(crate) fn st_module_schema() -> TableSchema {
     st_schema(ST_MODULE_NAME, ST_MODULE_ID)
 }
@@ -443,6 +628,10 @@ fn st_client_schema() -> TableSchema {
     st_schema(ST_CLIENT_NAME, ST_CLIENT_ID)
 }
 
+fn st_connection_schema() -> TableSchema {
+    st_schema(ST_CONNECTION_NAME, ST_CONNECTION_ID)
+}
+
 fn st_scheduled_schema() -> TableSchema {
     st_schema(ST_SCHEDULED_NAME, ST_SCHEDULED_ID)
 }
@@ -465,8 +654,15 @@ pub(crate) fn system_table_schema(table_id: TableId) -> Option<TableSchema> {
         ST_INDEX_ID => Some(st_index_schema()),
         ST_CONSTRAINT_ID => Some(st_constraint_schema()),
         ST_ROW_LEVEL_SECURITY_ID => Some(st_row_level_security_schema()),
+        ST_GENERATED_COLUMN_ID => Some(st_generated_column_schema()),
+        ST_SUBSCRIPTION_ID => Some(st_subscription_schema()),
+        ST_COUNTER_ID => Some(st_counter_schema()),
+        ST_SOFT_DELETE_ID => Some(st_soft_delete_schema()),
+        ST_TABLE_DURABILITY_ID => Some(st_table_durability_schema()),
+        ST_ROW_HISTORY_ID => Some(st_row_history_schema()),
         ST_MODULE_ID => Some(st_module_schema()),
         ST_CLIENT_ID => Some(st_client_schema()),
+        ST_CONNECTION_ID => Some(st_connection_schema()),
         ST_VAR_ID => Some(st_var_schema()),
         ST_SCHEDULED_ID => Some(st_scheduled_schema()),
         _ => None,
@@ -784,6 +980,168 @@ impl From<StRowLevelSecurityRow> for RowLevelSecuritySchema {
         }
     }
 }
+/// System Table [ST_GENERATED_COLUMN_NAME]
+///
+/// | table_id | col_pos | expr          |
+/// |----------|---------|---------------|
+/// | 4097     | 2       | "lower(name)" |
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct StGeneratedColumnRow {
+    pub(crate) table_id: TableId,
+    pub(crate) col_pos: ColId,
+    pub(crate) expr: Box<str>,
+}
+
+impl TryFrom<RowRef<'_>> for StGeneratedColumnRow {
+    type Error = DBError;
+    fn try_from(row: RowRef<'_>) -> Result<Self, DBError> {
+        read_via_bsatn(row)
+    }
+}
+
+impl From<StGeneratedColumnRow> for ProductValue {
+    fn from(x: StGeneratedColumnRow) -> Self {
+        to_product_value(&x)
+    }
+}
+
+/// System Table [ST_COUNTER_NAME]
+///
+/// | table_id | group_by | into_table     | into_column |
+/// |----------|----------|----------------|-------------|
+/// | 4098     | 1        | "canvas_stats" | 1           |
+///
+/// There is one row for each counter of the rows of `table_id`.
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct StCounterRow {
+    pub(crate) table_id: TableId,
+    pub(crate) group_by: ColId,
+    pub(crate) into_table: Box<str>,
+    pub(crate) into_column: ColId,
+}
+
+impl TryFrom<RowRef<'_>> for StCounterRow {
+    type Error = DBError;
+    fn try_from(row: RowRef<'_>) -> Result<Self, DBError> {
+        read_via_bsatn(row)
+    }
+}
+
+impl From<StCounterRow> for ProductValue {
+    fn from(x: StCounterRow) -> Self {
+        to_product_value(&x)
+    }
+}
+
+impl From<StCounterRow> for CounterSchema {
+    fn from(x: StCounterRow) -> Self {
+        Self {
+            table_id: x.table_id,
+            group_by: x.group_by,
+            into_table: x.into_table,
+            into_column: x.into_column,
+        }
+    }
+}
+
+/// System Table [ST_SOFT_DELETE_NAME]
+///
+/// | table_id | retention |
+/// |----------|-----------|
+/// | 4097     | 1000      |
+///
+/// There is one row for each table which keeps the history of its last `retention` transactions.
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct StSoftDeleteRow {
+    pub(crate) table_id: TableId,
+    pub(crate) retention: u64,
+}
+
+impl TryFrom<RowRef<'_>> for StSoftDeleteRow {
+    type Error = DBError;
+    fn try_from(row: RowRef<'_>) -> Result<Self, DBError> {
+        read_via_bsatn(row)
+    }
+}
+
+impl From<StSoftDeleteRow> for ProductValue {
+    fn from(x: StSoftDeleteRow) -> Self {
+        to_product_value(&x)
+    }
+}
+
+impl From<StSoftDeleteRow> for SoftDeleteSchema {
+    fn from(x: StSoftDeleteRow) -> Self {
+        Self {
+            table_id: x.table_id,
+            retention: x.retention,
+        }
+    }
+}
+
+/// System Table [ST_TABLE_DURABILITY_NAME]
+///
+/// | table_id | durability  |
+/// |----------|-------------|
+/// | 4097     | "relaxed"   |
+///
+/// There is one row for each table whose updates aren't written to the commitlog.
+/// Tables without a row are durable.
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct StTableDurabilityRow {
+    pub(crate) table_id: TableId,
+    pub(crate) durability: StDurability,
+}
+
+impl TryFrom<RowRef<'_>> for StTableDurabilityRow {
+    type Error = DBError;
+    fn try_from(row: RowRef<'_>) -> Result<Self, DBError> {
+        read_via_bsatn(row)
+    }
+}
+
+impl From<StTableDurabilityRow> for ProductValue {
+    fn from(x: StTableDurabilityRow) -> Self {
+        to_product_value(&x)
+    }
+}
+
+/// System Table [ST_ROW_HISTORY_NAME]
+///
+/// | table_id | tx_offset | deleted | row     |
+/// |----------|-----------|---------|---------|
+/// | 4097     | 1234      | true    | <bytes> |
+///
+/// There is one row for each row, encoded in BSATN, which the transaction `tx_offset`
+/// inserted into or, if `deleted`, deleted from the table `table_id`.
+/// Only the net effect of a transaction is recorded,
+/// so a row deleted and then inserted again by the same transaction has no history for that transaction.
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct StRowHistoryRow {
+    pub(crate) table_id: TableId,
+    pub(crate) tx_offset: u64,
+    pub(crate) deleted: bool,
+    pub(crate) row: Box<[u8]>,
+}
+
+impl TryFrom<RowRef<'_>> for StRowHistoryRow {
+    type Error = DBError;
+    fn try_from(row: RowRef<'_>) -> Result<Self, DBError> {
+        read_via_bsatn(row)
+    }
+}
+
+impl From<StRowHistoryRow> for ProductValue {
+    fn from(x: StRowHistoryRow) -> Self {
+        to_product_value(&x)
+    }
+}
+
 /// Indicates the kind of module the `program_bytes` of a [`StModuleRow`]
 /// describes.
 ///
@@ -802,7 +1160,7 @@ impl_deserialize!([] ModuleKind, de => u8::deserialize(de).map(Self));
 impl_st!([] ModuleKind, AlgebraicType::U8);
 
 /// A wrapper for `Address` that acts like `AlgebraicType::bytes()` for serialization purposes.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct AddressViaU128(pub Address);
 impl_serialize!([] AddressViaU128, (self, ser) => self.0.to_u128().serialize(ser));
 impl_deserialize!([] AddressViaU128, de => <u128>::deserialize(de).map(Address::from_u128).map(AddressViaU128));
@@ -814,7 +1172,7 @@ impl From<Address> for AddressViaU128 {
 }
 
 /// A wrapper for `Identity` that acts like `AlgebraicType::bytes()` for serialization purposes.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub struct IdentityViaU256(pub Identity);
 impl_serialize!([] IdentityViaU256, (self, ser) => self.0.to_u256().serialize(ser));
 impl_deserialize!([] IdentityViaU256, de => <u256>::deserialize(de).map(Identity::from_u256).map(IdentityViaU256));
@@ -931,6 +1289,73 @@ impl TryFrom<RowRef<'_>> for StClientRow {
     }
 }
 
+/// System table [ST_CONNECTION_NAME]
+///
+/// | identity        | address        | connected_at     |
+/// |-----------------|----------------|------------------|
+/// | 0x7452047061... | 0x6bdea3ab5... | 1729000000000000 |
+///
+/// There is one row for each row of [ST_CLIENT_NAME],
+/// inserted and deleted in the same transaction.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct StConnectionRow {
+    pub(crate) identity: IdentityViaU256,
+    pub(crate) address: AddressViaU128,
+    /// When the client connected, in microseconds since the Unix epoch.
+    pub(crate) connected_at: u64,
+}
+
+impl From<StConnectionRow> for ProductValue {
+    fn from(var: StConnectionRow) -> Self {
+        to_product_value(&var)
+    }
+}
+
+impl TryFrom<RowRef<'_>> for StConnectionRow {
+    type Error = DBError;
+
+    fn try_from(row: RowRef<'_>) -> Result<Self, Self::Error> {
+        read_via_bsatn(row)
+    }
+}
+
+/// System table [ST_SUBSCRIPTION_NAME]
+///
+/// | identity        | address        | query                  | created_at       | eval_cost |
+/// |-----------------|----------------|------------------------|------------------|-----------|
+/// | 0x7452047061... | 0x6bdea3ab5... | "SELECT * FROM player" | 1729000000000000 | 1234      |
+///
+/// There is one row for each query a connected client is subscribed to.
+#[derive(Clone, Debug, Eq, PartialEq, Hash, SpacetimeType)]
+#[sats(crate = spacetimedb_lib)]
+pub struct StSubscriptionRow {
+    pub(crate) identity: IdentityViaU256,
+    pub(crate) address: AddressViaU128,
+    pub(crate) query: Box<str>,
+    /// When the client subscribed to the query, in microseconds since the Unix epoch.
+    pub(crate) created_at: u64,
+    /// The time spent evaluating the query against transactions, in microseconds.
+    ///
+    /// The query is evaluated once for all of its subscribers,
+    /// so they all report the same cost.
+    pub(crate) eval_cost: u64,
+}
+
+impl From<StSubscriptionRow> for ProductValue {
+    fn from(row: StSubscriptionRow) -> Self {
+        to_product_value(&row)
+    }
+}
+
+impl TryFrom<RowRef<'_>> for StSubscriptionRow {
+    type Error = DBError;
+
+    fn try_from(row: RowRef<'_>) -> Result<Self, Self::Error> {
+        read_via_bsatn(row)
+    }
+}
+
 /// A handle for reading system variables from `st_var`
 pub struct StVarTable;
 
@@ -945,6 +1370,22 @@ impl StVarTable {
         Ok(None)
     }
 
+    /// Read the value of [ST_VARNAME_SUB_COST_LIMIT] from `st_var`
+    pub fn subscription_cost_limit(db: &RelationalDB, tx: &TxId) -> Result<Option<u64>, DBError> {
+        if let Some(StVarValue::U64(cost)) = Self::read_var(db, tx, StVarName::SubscriptionCostLimit)? {
+            return Ok(Some(cost));
+        }
+        Ok(None)
+    }
+
+    /// Read the value of [ST_VARNAME_SUB_COST_WARN] from `st_var`
+    pub fn subscription_cost_warn(db: &RelationalDB, tx: &TxId) -> Result<Option<u64>, DBError> {
+        if let Some(StVarValue::U64(cost)) = Self::read_var(db, tx, StVarName::SubscriptionCostWarn)? {
+            return Ok(Some(cost));
+        }
+        Ok(None)
+    }
+
     /// Read the value of [ST_VARNAME_SLOW_QRY] from `st_var`
     pub fn query_limit(db: &RelationalDB, tx: &TxId) -> Result<Option<u64>, DBError> {
         if let Some(StVarValue::U64(ms)) = Self::read_var(db, tx, StVarName::SlowQryThreshold)? {
@@ -969,6 +1410,14 @@ impl StVarTable {
         Ok(None)
     }
 
+    /// Read the value of [ST_VARNAME_HIDE_CALLERS] from `st_var`
+    pub fn hide_callers(db: &RelationalDB, tx: &TxId) -> Result<bool, DBError> {
+        if let Some(StVarValue::Bool(hide)) = Self::read_var(db, tx, StVarName::HideCallers)? {
+            return Ok(hide);
+        }
+        Ok(false)
+    }
+
     /// Read the value of a system variable from `st_var`
     pub fn read_var(db: &RelationalDB, tx: &TxId, name: StVarName) -> Result<Option<StVarValue>, DBError> {
         if let Some(row_ref) = db
@@ -1034,28 +1483,44 @@ impl From<StVarRow> for AlgebraicValue {
 /// If the cardinality of a query is estimated to exceed this limit,
 /// it will be rejected before being executed.
 pub const ST_VARNAME_ROW_LIMIT: &str = "row_limit";
+/// A system variable that defines a cost limit for subscription queries.
+/// If the cost of a query, as in rows scanned without an index plus rows produced by joins,
+/// is estimated to exceed this limit, subscribing to it will be rejected.
+pub const ST_VARNAME_SUB_COST_LIMIT: &str = "subscription_cost_limit";
+/// A system variable that defines a threshold for flagging costly subscription queries.
+/// Subscribing to such a query succeeds, but logs a warning.
+pub const ST_VARNAME_SUB_COST_WARN: &str = "subscription_cost_warn";
 /// A system variable that defines a threshold for logging slow queries.
 pub const ST_VARNAME_SLOW_QRY: &str = "slow_ad_hoc_query_ms";
 /// A system variable that defines a threshold for logging slow subscriptions.
 pub const ST_VARNAME_SLOW_SUB: &str = "slow_subscription_query_ms";
 /// A system variable that defines a threshold for logging slow tx updates.
 pub const ST_VARNAME_SLOW_INC: &str = "slow_tx_update_ms";
+/// A system variable that, when `true`, hides who called a reducer from the clients other than the caller.
+/// They are sent the reducer's transaction update without its caller identity, caller address and request id.
+pub const ST_VARNAME_HIDE_CALLERS: &str = "hide_callers";
 
 /// The name of a system variable in `st_var`
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StVarName {
     RowLimit,
+    SubscriptionCostLimit,
+    SubscriptionCostWarn,
     SlowQryThreshold,
     SlowSubThreshold,
     SlowIncThreshold,
+    HideCallers,
 }
 impl From<StVarName> for &'static str {
     fn from(value: StVarName) -> Self {
         match value {
             StVarName::RowLimit => ST_VARNAME_ROW_LIMIT,
+            StVarName::SubscriptionCostLimit => ST_VARNAME_SUB_COST_LIMIT,
+            StVarName::SubscriptionCostWarn => ST_VARNAME_SUB_COST_WARN,
             StVarName::SlowQryThreshold => ST_VARNAME_SLOW_QRY,
             StVarName::SlowSubThreshold => ST_VARNAME_SLOW_SUB,
             StVarName::SlowIncThreshold => ST_VARNAME_SLOW_INC,
+            StVarName::HideCallers => ST_VARNAME_HIDE_CALLERS,
         }
     }
 }
@@ -1071,9 +1536,12 @@ impl FromStr for StVarName {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             ST_VARNAME_ROW_LIMIT => Ok(StVarName::RowLimit),
+            ST_VARNAME_SUB_COST_LIMIT => Ok(StVarName::SubscriptionCostLimit),
+            ST_VARNAME_SUB_COST_WARN => Ok(StVarName::SubscriptionCostWarn),
             ST_VARNAME_SLOW_QRY => Ok(StVarName::SlowQryThreshold),
             ST_VARNAME_SLOW_SUB => Ok(StVarName::SlowSubThreshold),
             ST_VARNAME_SLOW_INC => Ok(StVarName::SlowIncThreshold),
+            ST_VARNAME_HIDE_CALLERS => Ok(StVarName::HideCallers),
             _ => Err(anyhow::anyhow!("Invalid system variable {}", s)),
         }
     }
@@ -1091,9 +1559,12 @@ impl StVarName {
     pub fn type_of(&self) -> AlgebraicType {
         match self {
             StVarName::RowLimit
+            | StVarName::SubscriptionCostLimit
+            | StVarName::SubscriptionCostWarn
             | StVarName::SlowQryThreshold
             | StVarName::SlowSubThreshold
             | StVarName::SlowIncThreshold => AlgebraicType::U64,
+            StVarName::HideCallers => AlgebraicType::Bool,
         }
     }
 }
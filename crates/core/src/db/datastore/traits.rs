@@ -1,6 +1,6 @@
 use core::ops::Deref;
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::{ops::RangeBounds, sync::Arc};
 
 use super::system_tables::ModuleKind;
@@ -171,6 +171,14 @@ pub struct TxData {
     inserts: BTreeMap<TableId, Arc<[ProductValue]>>,
     /// The deleted rows per table.
     deletes: BTreeMap<TableId, Arc<[ProductValue]>>,
+    /// The tables which were cleared wholesale.
+    ///
+    /// For each of these, `deletes` contains every row the table held,
+    /// but the durability layer records only that the table was truncated.
+    truncates: BTreeSet<TableId>,
+    /// The tables with relaxed or ephemeral durability,
+    /// whose rows are broadcast to subscribers but not written to the commitlog.
+    relaxed: BTreeSet<TableId>,
     /// Map of all `TableId`s in both `inserts` and `deletes` to their
     /// corresponding table name.
     tables: IntMap<TableId, String>,
@@ -209,6 +217,39 @@ impl TxData {
         self.tables.entry(table_id).or_insert_with(|| table_name.to_owned());
     }
 
+    /// Record that `table_id` was cleared wholesale.
+    ///
+    /// The deleted rows of `table_id` must also be set via [`Self::set_deletes_for_table`].
+    pub fn set_truncated(&mut self, table_id: TableId) {
+        self.truncates.insert(table_id);
+    }
+
+    /// Returns whether `table_id` was cleared wholesale.
+    pub fn is_truncated(&self, table_id: TableId) -> bool {
+        self.truncates.contains(&table_id)
+    }
+
+    /// Record that `table_id` has relaxed or ephemeral durability,
+    /// so its rows are not to be written to the commitlog.
+    pub fn set_relaxed(&mut self, table_id: TableId) {
+        self.relaxed.insert(table_id);
+    }
+
+    /// Returns whether `table_id` has relaxed or ephemeral durability.
+    pub fn is_relaxed(&self, table_id: TableId) -> bool {
+        self.relaxed.contains(&table_id)
+    }
+
+    /// Obtain an iterator over the tables with inserted or deleted rows.
+    pub fn table_ids(&self) -> impl Iterator<Item = TableId> + '_ {
+        self.tables.keys().copied()
+    }
+
+    /// Obtain an iterator over the tables which were cleared wholesale.
+    pub fn truncates(&self) -> impl Iterator<Item = TableId> + '_ {
+        self.truncates.iter().copied()
+    }
+
     /// Obtain an iterator over the inserted rows per table.
     pub fn inserts(&self) -> impl Iterator<Item = (&TableId, &Arc<[ProductValue]>)> + '_ {
         self.inserts.iter()
@@ -247,12 +288,17 @@ impl TxData {
         })
     }
 
-    /// Check if this [`TxData`] contains any `inserted | deleted` rows or `connect/disconnect` operations.
+    /// Check if this [`TxData`] contains any `inserted | deleted` rows of durable tables
+    /// or `connect/disconnect` operations.
     ///
     /// This is used to determine if a transaction should be written to disk.
     pub fn has_rows_or_connect_disconnect(&self, reducer_context: Option<&ReducerContext>) -> bool {
-        self.inserts().any(|(_, inserted_rows)| !inserted_rows.is_empty())
-            || self.deletes().any(|(_, deleted_rows)| !deleted_rows.is_empty())
+        let durable = |table_id: &TableId| !self.is_relaxed(*table_id);
+        self.inserts()
+            .any(|(table_id, inserted_rows)| durable(table_id) && !inserted_rows.is_empty())
+            || self
+                .deletes()
+                .any(|(table_id, deleted_rows)| durable(table_id) && !deleted_rows.is_empty())
             || matches!(
                 reducer_context.map(|rcx| rcx.name.strip_prefix("__identity_")),
                 Some(Some("connected__" | "disconnected__"))
@@ -440,6 +486,8 @@ pub trait MutTxDatastore: TxDatastore + MutTx {
 
     // Sequences
     fn get_next_sequence_value_mut_tx(&self, tx: &mut Self::MutTx, seq_id: SequenceId) -> Result<i128>;
+    fn peek_sequence_value_mut_tx(&self, tx: &mut Self::MutTx, seq_id: SequenceId) -> Result<i128>;
+    fn advance_sequence_past_mut_tx(&self, tx: &mut Self::MutTx, seq_id: SequenceId, value: i128) -> Result<bool>;
     fn create_sequence_mut_tx(&self, tx: &mut Self::MutTx, sequence_schema: SequenceSchema) -> Result<SequenceId>;
     fn drop_sequence_mut_tx(&self, tx: &mut Self::MutTx, seq_id: SequenceId) -> Result<()>;
     fn sequence_id_from_name_mut_tx(&self, tx: &Self::MutTx, sequence_name: &str) -> super::Result<Option<SequenceId>>;
@@ -482,6 +530,11 @@ pub trait MutTxDatastore: TxDatastore + MutTx {
         table_id: TableId,
         relation: impl IntoIterator<Item = ProductValue>,
     ) -> u32;
+    /// Deletes every row of the table identified by `table_id`,
+    /// returning the number of rows deleted.
+    ///
+    /// When committed, this clears the table wholesale rather than row by row.
+    fn truncate_mut_tx(&self, tx: &mut Self::MutTx, table_id: TableId) -> Result<u64>;
     /// Inserts `row`, encoded in BSATN, into the table identified by `table_id`.
     ///
     /// Returns the list of columns with sequence-trigger values that were replaced with generated ones
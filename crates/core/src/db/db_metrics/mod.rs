@@ -1,9 +1,13 @@
 use crate::execution_context::WorkloadType;
 use once_cell::sync::Lazy;
+use prometheus::core::Collector;
+use prometheus::proto::Metric;
 use prometheus::{HistogramVec, IntCounterVec, IntGaugeVec};
+use serde::Serialize;
 use spacetimedb_lib::Identity;
 use spacetimedb_metrics::metrics_group;
 use spacetimedb_primitives::TableId;
+use std::collections::HashMap;
 
 metrics_group!(
     #[non_exhaustive]
@@ -38,6 +42,11 @@ metrics_group!(
         #[labels(txn_type: WorkloadType, db: Identity, reducer_or_query: str, table_id: u32, table_name: str)]
         pub rdb_num_index_seeks: IntCounterVec,
 
+        #[name = spacetime_num_table_scans_total]
+        #[help = "The cumulative number of scans of all of the rows of a table"]
+        #[labels(txn_type: WorkloadType, db: Identity, reducer_or_query: str, table_id: u32, table_name: str)]
+        pub rdb_num_table_scans: IntCounterVec,
+
         #[name = spacetime_num_txns_total]
         #[help = "The cumulative number of transactions, including both commits and rollbacks"]
         #[labels(txn_type: WorkloadType, db: Identity, reducer: str, committed: bool)]
@@ -79,10 +88,30 @@ metrics_group!(
         #[labels(db: Identity)]
         pub module_log_file_size: IntGaugeVec,
 
+        #[name = spacetime_database_size_bytes]
+        #[help = "For a given database, the total size of its tables, commitlog, and snapshots (in bytes)"]
+        #[labels(db: Identity)]
+        pub database_size: IntGaugeVec,
+
+        #[name = spacetime_size_quota_exceeded_total]
+        #[help = "The cumulative number of transactions refused because their database was over its size, memory or disk quota"]
+        #[labels(db: Identity)]
+        pub size_quota_exceeded: IntCounterVec,
+
         #[name = spacetime_table_size_bytes]
         #[help = "The number of bytes in a table with the precision of a page size"]
         #[labels(db: Identity, table_id: u32, table_name: str)]
         pub rdb_table_size: IntGaugeVec,
+
+        #[name = spacetime_replay_tx_offset]
+        #[help = "For a database being opened, the offset of the last transaction replayed from its commitlog"]
+        #[labels(db: Identity)]
+        pub replay_tx_offset: IntGaugeVec,
+
+        #[name = spacetime_replay_max_tx_offset]
+        #[help = "For a database being opened, the offset of the last transaction in its commitlog, or -1 if not known"]
+        #[labels(db: Identity)]
+        pub replay_max_tx_offset: IntGaugeVec,
     }
 );
 
@@ -95,3 +124,138 @@ pub fn table_num_rows(db_identity: Identity, table_id: TableId, table_name: &str
         .with_label_values(&db_identity, &table_id.0, table_name)
         .get() as _
 }
+
+/// Live usage statistics of a database, accumulated by the metrics above since the host started.
+#[derive(Debug, Default)]
+pub struct DatabaseStats {
+    /// Statistics for each reducer that has been called, by name.
+    pub reducers: HashMap<String, ReducerStats>,
+    /// Statistics for each table that has been used, by name.
+    pub tables: HashMap<String, TableStats>,
+}
+
+/// Live usage statistics of a reducer.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct ReducerStats {
+    /// The number of calls, including those which failed.
+    pub calls: u64,
+    /// The number of calls which failed and were rolled back.
+    pub failed_calls: u64,
+    /// The number of rows inserted by committed calls.
+    pub rows_inserted: u64,
+    /// The number of rows deleted by committed calls.
+    pub rows_deleted: u64,
+}
+
+impl ReducerStats {
+    /// Returns the average number of rows inserted or deleted per call.
+    pub fn avg_rows_touched(&self) -> f64 {
+        match self.calls {
+            0 => 0.0,
+            calls => (self.rows_inserted + self.rows_deleted) as f64 / calls as f64,
+        }
+    }
+}
+
+/// Live usage statistics of a table.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct TableStats {
+    /// The number of committed rows in the table.
+    pub rows: u64,
+    /// The number of rows inserted into the table.
+    pub rows_inserted: u64,
+    /// The number of rows deleted from the table.
+    pub rows_deleted: u64,
+    /// The number of seeks into the table's indices.
+    pub index_seeks: u64,
+    /// The number of scans of all of the table's rows.
+    pub table_scans: u64,
+}
+
+impl DatabaseStats {
+    /// Collects the statistics of the database `db` from [`DB_METRICS`].
+    pub fn collect(db: Identity) -> Self {
+        let db = db.to_hex();
+        let mut stats = Self::default();
+
+        for_each_sample(&DB_METRICS.rdb_num_txns, &db, |sample| {
+            if sample.label("txn_type") != WorkloadType::Reducer.as_ref() {
+                return;
+            }
+            let reducer = stats.reducers.entry(sample.label("reducer").to_owned()).or_default();
+            reducer.calls += sample.counter();
+            if sample.label("committed") == "false" {
+                reducer.failed_calls += sample.counter();
+            }
+        });
+        for_each_sample(&DB_METRICS.rdb_num_table_rows, &db, |sample| {
+            stats.table(&sample).rows = sample.gauge();
+        });
+        for_each_sample(&DB_METRICS.rdb_num_rows_inserted, &db, |sample| {
+            stats.table(&sample).rows_inserted += sample.counter();
+            if let Some(reducer) = stats.reducer(&sample) {
+                reducer.rows_inserted += sample.counter();
+            }
+        });
+        for_each_sample(&DB_METRICS.rdb_num_rows_deleted, &db, |sample| {
+            stats.table(&sample).rows_deleted += sample.counter();
+            if let Some(reducer) = stats.reducer(&sample) {
+                reducer.rows_deleted += sample.counter();
+            }
+        });
+        for_each_sample(&DB_METRICS.rdb_num_index_seeks, &db, |sample| {
+            stats.table(&sample).index_seeks += sample.counter();
+        });
+        for_each_sample(&DB_METRICS.rdb_num_table_scans, &db, |sample| {
+            stats.table(&sample).table_scans += sample.counter();
+        });
+
+        stats
+    }
+
+    fn table(&mut self, sample: &Sample<'_>) -> &mut TableStats {
+        self.tables.entry(sample.label("table_name").to_owned()).or_default()
+    }
+
+    /// Returns the statistics of the reducer `sample` was recorded for, if any.
+    fn reducer(&mut self, sample: &Sample<'_>) -> Option<&mut ReducerStats> {
+        if sample.label("txn_type") != WorkloadType::Reducer.as_ref() {
+            return None;
+        }
+        self.reducers.get_mut(sample.label("reducer_or_query"))
+    }
+}
+
+/// A single labeled value of a metric.
+struct Sample<'a>(&'a Metric);
+
+impl Sample<'_> {
+    /// Returns the value of the label `name`, or `""` if there is no such label.
+    fn label(&self, name: &str) -> &str {
+        self.0
+            .get_label()
+            .iter()
+            .find(|pair| pair.get_name() == name)
+            .map_or("", |pair| pair.get_value())
+    }
+
+    fn counter(&self) -> u64 {
+        self.0.get_counter().get_value() as u64
+    }
+
+    fn gauge(&self) -> u64 {
+        self.0.get_gauge().get_value() as u64
+    }
+}
+
+/// Calls `f` with each sample of `collector` recorded for the database `db`, given in hex.
+fn for_each_sample(collector: &impl Collector, db: &str, mut f: impl FnMut(Sample<'_>)) {
+    for family in collector.collect() {
+        family
+            .get_metric()
+            .iter()
+            .map(Sample)
+            .filter(|sample| sample.label("db") == db)
+            .for_each(&mut f);
+    }
+}
@@ -0,0 +1,366 @@
+//! Export of the rows of a table from a snapshot to Parquet,
+//! and of the whole state of a database, as of some transaction, to SQLite.
+//!
+//! Exports read from a snapshot rather than from the live database,
+//! so that they don't hold a read transaction for their duration.
+//! Rows are converted to Arrow record batches via [`spacetimedb_sats::arrow`].
+//!
+//! SQLite exports additionally replay the commitlog on top of the snapshot,
+//! and run offline against a replica directory, without a server.
+
+use super::datastore::locking_tx_datastore::committed_state::CommittedState;
+use super::datastore::locking_tx_datastore::datastore::{Locking, Replay};
+use super::datastore::locking_tx_datastore::state_view::StateView;
+use super::datastore::system_tables::{StTableFields, ST_RESERVED_SEQUENCE_RANGE, ST_TABLE_ID};
+use super::relational_db::{open_snapshot_repo, Txdata};
+use crate::error::DBError;
+use arrow::error::ArrowError;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use rusqlite::types::Value;
+use spacetimedb_commitlog::{self as commitlog, Decoder};
+use spacetimedb_durability::TxOffset;
+use spacetimedb_lib::db::auth::StTableType;
+use spacetimedb_lib::{Address, Identity};
+use spacetimedb_paths::server::ReplicaDir;
+use spacetimedb_primitives::TableId;
+use spacetimedb_sats::algebraic_value::ser::ValueSerializer;
+use spacetimedb_sats::arrow::RecordBatchBuilder;
+use spacetimedb_sats::buffer::BufReader;
+use spacetimedb_sats::satn::Satn;
+use spacetimedb_sats::{AlgebraicType, AlgebraicValue, ArrayValue};
+use spacetimedb_schema::schema::TableSchema;
+use spacetimedb_snapshot::{SnapshotError, SnapshotRepository};
+use spacetimedb_table::bflatn_from::serialize_row_from_page;
+use spacetimedb_table::layout::RowTypeLayout;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The number of rows in each record batch passed to the Parquet writer.
+const BATCH_ROWS: usize = 8192;
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("database has no snapshot to export from")]
+    NoSnapshot,
+    #[error("no such table: `{0}`")]
+    NoSuchTable(Box<str>),
+    #[error("{} already contains tables", .0.display())]
+    NotEmpty(PathBuf),
+    #[error(transparent)]
+    DB(#[from] DBError),
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+    #[error(transparent)]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+impl From<SnapshotError> for ExportError {
+    fn from(e: SnapshotError) -> Self {
+        DBError::from(e).into()
+    }
+}
+
+/// Write the rows of the table `table_name`, as of the snapshot at `tx_offset` in `repo`,
+/// to `out` in Parquet format.
+///
+/// The table's schema is computed from the system tables in the snapshot,
+/// after which its pages are read from the snapshot one at a time,
+/// so the table is never held in memory as a whole.
+///
+/// Returns the number of rows written.
+pub fn export_table_parquet(
+    repo: &SnapshotRepository,
+    tx_offset: TxOffset,
+    table_name: &str,
+    out: impl Write + Send,
+) -> Result<u64, ExportError> {
+    let snapshot = repo.open_snapshot(tx_offset)?;
+    let system_tables = snapshot.reconstruct(|table_id| table_id.0 <= ST_RESERVED_SEQUENCE_RANGE)?;
+    let state = CommittedState::from_snapshot_system_tables(system_tables)?;
+
+    let table_id = state
+        .table_id_from_name(table_name)?
+        .ok_or_else(|| ExportError::NoSuchTable(table_name.into()))?;
+    let schema = state.schema_for_table_raw(table_id)?;
+    let row_type = schema.get_row_type();
+    let layout = RowTypeLayout::from(row_type.clone());
+
+    let mut batch = RecordBatchBuilder::new(row_type);
+    let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(out, batch.schema(), Some(props))?;
+    let mut rows = 0;
+    for page in snapshot.table_pages(table_id) {
+        let page = page?;
+        for offset in page.iter_fixed_len(layout.size()) {
+            // SAFETY:
+            // - The page is uncorrupted, as reading it from the snapshot verified its hash.
+            // - `offset` is that of a present row in `page`, as yielded by `iter_fixed_len`.
+            // - `layout` is derived from the schema in `st_table` and `st_column`,
+            //   which were read from the same snapshot,
+            //   and we trust that the snapshot was consistent when created,
+            //   so the rows in `page` are valid for `layout`.
+            let row = unsafe { serialize_row_from_page(ValueSerializer, &page, &state.blob_store, offset, &layout) }
+                .unwrap()
+                .into_product()
+                .unwrap();
+            batch.append(&row)?;
+            rows += 1;
+
+            if batch.len() >= BATCH_ROWS {
+                writer.write(&batch.finish()?)?;
+            }
+        }
+    }
+    if !batch.is_empty() {
+        writer.write(&batch.finish()?)?;
+    }
+    writer.close()?;
+
+    Ok(rows)
+}
+
+/// The outcome of an [`export_sqlite`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqliteExport {
+    /// The offset of the snapshot the export started from,
+    /// or `None` if the whole commitlog was replayed.
+    pub snapshot_tx_offset: Option<TxOffset>,
+    /// The offset of the last transaction reflected in the export,
+    /// or `None` if the database has no transactions yet.
+    pub tx_offset: Option<TxOffset>,
+    /// The number of tables written.
+    pub tables: usize,
+    /// The number of rows written, across all tables.
+    pub rows: u64,
+}
+
+/// Write the state of the database `database_identity`, stored in `replica_dir`,
+/// as of the transaction `up_to`, to a new SQLite database at `out`.
+///
+/// The latest snapshot at or before `up_to` is restored,
+/// after which the commitlog is replayed up to and including `up_to`.
+/// If `up_to` is `None`, the latest snapshot is restored and the whole commitlog is replayed.
+///
+/// Each user table becomes an SQLite table of the same name and columns:
+/// - Booleans and integers of up to 64 bits are stored as `INTEGER`s,
+///   except for `u64`s too large for an `INTEGER`, which are stored as text.
+/// - Floats are stored as `REAL`s, strings as `TEXT` and byte arrays as `BLOB`s.
+/// - Options are stored as their inner value, with `none` stored as `NULL`.
+/// - Identities and addresses are stored as hex strings,
+///   and 128- and 256-bit integers as decimal strings.
+/// - Any other value is stored as its SATN text representation.
+///
+/// `replica_dir` may belong to a running database,
+/// in which case transactions it commits during the export may be left out of it.
+/// Fails if `out` is an existing SQLite database which already contains tables.
+pub fn export_sqlite(
+    replica_dir: &ReplicaDir,
+    database_identity: Identity,
+    replica_id: u64,
+    up_to: Option<TxOffset>,
+    out: &Path,
+) -> Result<SqliteExport, ExportError> {
+    let repo = open_snapshot_repo(replica_dir.snapshots(), database_identity, replica_id).map_err(DBError::from)?;
+    let snapshot_tx_offset = match up_to {
+        Some(up_to) => repo.latest_snapshot_older_than(up_to)?,
+        None => repo.latest_snapshot()?,
+    };
+    let datastore = match snapshot_tx_offset {
+        Some(tx_offset) => Locking::restore_from_snapshot(repo.read_snapshot(tx_offset)?)?,
+        None => Locking::bootstrap(database_identity)?,
+    };
+
+    let replay = ReplayUpTo {
+        replay: datastore.replay(|_| {}),
+        up_to,
+    };
+    let start = replay.replay.next_tx_offset();
+    commitlog::fold_transactions_from(replica_dir.commit_log(), start, replay).map_err(DBError::from)?;
+    datastore.rebuild_state_after_replay()?;
+
+    let state = datastore.committed_state.read();
+    let mut export = SqliteExport {
+        snapshot_tx_offset,
+        tx_offset: state.next_tx_offset.checked_sub(1),
+        tables: 0,
+        rows: 0,
+    };
+
+    let mut conn = rusqlite::Connection::open_with_flags(
+        out,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE | rusqlite::OpenFlags::SQLITE_OPEN_CREATE,
+    )?;
+    if conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))? != 0 {
+        return Err(ExportError::NotEmpty(out.to_owned()));
+    }
+    let tx = conn.transaction()?;
+    for row in state.iter(ST_TABLE_ID)? {
+        let table_id: TableId = row.read_col(StTableFields::TableId).map_err(DBError::from)?;
+        let schema = state.schema_for_table_raw(table_id)?;
+        if schema.table_type != StTableType::User {
+            continue;
+        }
+
+        tx.execute(&create_table_sql(&schema), [])?;
+        let mut insert = tx.prepare(&insert_sql(&schema))?;
+        for row in state.iter(table_id)? {
+            let row = row.to_product_value();
+            let values = row
+                .elements
+                .iter()
+                .zip(schema.columns())
+                .map(|(value, column)| sqlite_value(value, &column.col_type));
+            insert.execute(rusqlite::params_from_iter(values))?;
+            export.rows += 1;
+        }
+        export.tables += 1;
+    }
+    tx.commit()?;
+
+    Ok(export)
+}
+
+/// A [`commitlog::Decoder`] which replays the transactions up to and including `up_to`,
+/// and skips those after it.
+struct ReplayUpTo<F> {
+    replay: Replay<F>,
+    up_to: Option<TxOffset>,
+}
+
+impl<F: FnMut(u64)> ReplayUpTo<F> {
+    fn includes(&self, tx_offset: TxOffset) -> bool {
+        self.up_to.map_or(true, |up_to| tx_offset <= up_to)
+    }
+}
+
+impl<F: FnMut(u64)> Decoder for ReplayUpTo<F> {
+    type Record = Txdata;
+    type Error = anyhow::Error;
+
+    fn decode_record<'a, R: BufReader<'a>>(
+        &self,
+        version: u8,
+        tx_offset: u64,
+        reader: &mut R,
+    ) -> Result<Self::Record, Self::Error> {
+        if !self.includes(tx_offset) {
+            anyhow::bail!("transaction {tx_offset} is past the end of the replayed range");
+        }
+        Ok(self.replay.decode_record(version, tx_offset, reader)?)
+    }
+
+    fn consume_record<'a, R: BufReader<'a>>(
+        &self,
+        version: u8,
+        tx_offset: u64,
+        reader: &mut R,
+    ) -> Result<(), Self::Error> {
+        if self.includes(tx_offset) {
+            Ok(self.replay.consume_record(version, tx_offset, reader)?)
+        } else {
+            self.skip_record(version, tx_offset, reader)
+        }
+    }
+
+    fn skip_record<'a, R: BufReader<'a>>(
+        &self,
+        version: u8,
+        tx_offset: u64,
+        reader: &mut R,
+    ) -> Result<(), Self::Error> {
+        Ok(self.replay.skip_record(version, tx_offset, reader)?)
+    }
+}
+
+/// Quote `ident` for use as an identifier in SQLite.
+fn quote_ident(ident: &str) -> String {
+    format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+fn create_table_sql(schema: &TableSchema) -> String {
+    let columns = schema
+        .columns()
+        .iter()
+        .map(|column| {
+            let name = quote_ident(&column.col_name);
+            match sqlite_type(&column.col_type) {
+                Some(ty) => format!("{name} {ty}"),
+                None => name,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("CREATE TABLE {} ({columns})", quote_ident(&schema.table_name))
+}
+
+fn insert_sql(schema: &TableSchema) -> String {
+    let params = vec!["?"; schema.columns().len()].join(", ");
+    format!("INSERT INTO {} VALUES ({params})", quote_ident(&schema.table_name))
+}
+
+/// Returns the declared SQLite type of a column of `ty`.
+///
+/// `u64` columns have no declared type, so that SQLite stores their large values
+/// as text as given, rather than converting them to lossy `REAL`s.
+fn sqlite_type(ty: &AlgebraicType) -> Option<&'static str> {
+    let ty = ty.as_option().unwrap_or(ty);
+    match ty {
+        AlgebraicType::U64 => None,
+        AlgebraicType::Bool
+        | AlgebraicType::I8
+        | AlgebraicType::U8
+        | AlgebraicType::I16
+        | AlgebraicType::U16
+        | AlgebraicType::I32
+        | AlgebraicType::U32
+        | AlgebraicType::I64 => Some("INTEGER"),
+        AlgebraicType::F32 | AlgebraicType::F64 => Some("REAL"),
+        _ if ty.is_bytes() => Some("BLOB"),
+        _ => Some("TEXT"),
+    }
+}
+
+/// Converts `value`, of the column type `ty`, to the SQLite value it is stored as.
+/// See [`export_sqlite`] for the representation of each type.
+fn sqlite_value(value: &AlgebraicValue, ty: &AlgebraicType) -> Value {
+    if let (Some(inner_ty), AlgebraicValue::Sum(sum)) = (ty.as_option(), value) {
+        return match sum.tag {
+            0 => sqlite_value(&sum.value, inner_ty),
+            _ => Value::Null,
+        };
+    }
+    match value {
+        AlgebraicValue::Bool(v) => Value::Integer(*v as i64),
+        AlgebraicValue::I8(v) => Value::Integer(*v as i64),
+        AlgebraicValue::U8(v) => Value::Integer(*v as i64),
+        AlgebraicValue::I16(v) => Value::Integer(*v as i64),
+        AlgebraicValue::U16(v) => Value::Integer(*v as i64),
+        AlgebraicValue::I32(v) => Value::Integer(*v as i64),
+        AlgebraicValue::U32(v) => Value::Integer(*v as i64),
+        AlgebraicValue::I64(v) => Value::Integer(*v),
+        AlgebraicValue::U64(v) => i64::try_from(*v).map_or_else(|_| Value::Text(v.to_string()), Value::Integer),
+        AlgebraicValue::I128(v) => Value::Text({ v.0 }.to_string()),
+        AlgebraicValue::U128(v) => Value::Text({ v.0 }.to_string()),
+        AlgebraicValue::I256(v) => Value::Text(v.to_string()),
+        AlgebraicValue::U256(v) => Value::Text(v.to_string()),
+        AlgebraicValue::F32(v) => Value::Real(v.into_inner() as f64),
+        AlgebraicValue::F64(v) => Value::Real(v.into_inner()),
+        AlgebraicValue::String(v) => Value::Text(v.to_string()),
+        AlgebraicValue::Array(ArrayValue::U8(v)) => Value::Blob(v.to_vec()),
+        AlgebraicValue::Product(v) if ty.is_identity() => match &*v.elements {
+            [AlgebraicValue::U256(v)] => Value::Text(Identity::from_u256(**v).to_hex().to_string()),
+            _ => Value::Text(value.to_satn()),
+        },
+        AlgebraicValue::Product(v) if ty.is_address() => match &*v.elements {
+            [AlgebraicValue::U128(v)] => Value::Text(Address::from_u128(v.0).to_hex().to_string()),
+            _ => Value::Text(value.to_satn()),
+        },
+        value => Value::Text(value.to_satn()),
+    }
+}
@@ -0,0 +1,400 @@
+//! Import of rows into a table from Parquet or CSV files.
+//!
+//! The columns of a file are mapped onto the columns of the table by name,
+//! optionally renamed by the caller,
+//! and their values are coerced into the column types
+//! following the rules of [`spacetimedb_sats::arrow`].
+//! CSV fields are read as strings, with empty fields being null.
+//!
+//! This module only decodes files into rows;
+//! inserting them is done by [`ModuleHost::import_table`](crate::host::ModuleHost::import_table).
+
+use arrow::array::{ArrayRef, StringBuilder};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchReader};
+use bytes::{Buf, Bytes};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::errors::ParquetError;
+use spacetimedb_sats::arrow::column_values;
+use spacetimedb_sats::{AlgebraicType, AlgebraicValue, ProductValue};
+use spacetimedb_schema::schema::TableSchema;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::error::DBError;
+
+/// The number of rows in each record batch read from a file.
+const BATCH_ROWS: usize = 8192;
+
+/// The number of rows after which an import commits a transaction.
+///
+/// As whole record batches are inserted, transactions hold up to [`BATCH_ROWS`] rows more.
+pub const IMPORT_TX_ROWS: usize = 8 * BATCH_ROWS;
+
+/// The formats of files that can be imported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    Parquet,
+    /// Comma-separated values, with a header row naming the columns.
+    Csv,
+}
+
+/// How to import a file into a table.
+#[derive(Clone, Debug)]
+pub struct ImportOptions {
+    pub format: ImportFormat,
+    /// Pairs of the name of a column in the file,
+    /// and the name of the table column to import it into.
+    ///
+    /// Columns of the file not listed here are imported into the table column of the same name, if any.
+    pub renames: Vec<(String, String)>,
+    /// Only decode and validate the rows of the file, without inserting them.
+    pub dry_run: bool,
+}
+
+/// The outcome of a successful import.
+#[derive(Clone, Debug, Default)]
+pub struct ImportSummary {
+    /// The number of rows imported, or that would be imported in a dry run.
+    pub rows: u64,
+    /// The number of transactions the rows were committed in.
+    pub transactions: u64,
+    /// For each column of the table, the column of the file it was imported from,
+    /// or `None` if it was filled in with a default value.
+    pub columns: Vec<(String, Option<String>)>,
+    /// The columns of the file which were not imported.
+    pub ignored: Vec<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("no such table: `{0}`")]
+    NoSuchTable(Box<str>),
+    #[error("cannot import into system table `{0}`")]
+    SystemTable(Box<str>),
+    #[error("the file has no column `{0}`")]
+    NoSuchSourceColumn(String),
+    #[error("the table has no column `{0}`")]
+    NoSuchColumn(String),
+    #[error("more than one column of the file maps to column `{0}`")]
+    DuplicateColumn(String),
+    #[error("the file has no column for `{0}`, which is not optional and has no default")]
+    MissingColumn(String),
+    #[error("column `{column}`, rows {first_row}..: {source}")]
+    Coerce {
+        column: String,
+        first_row: u64,
+        #[source]
+        source: ArrowError,
+    },
+    #[error("import failed after committing {committed_rows} rows: {reason}")]
+    Rejected { committed_rows: u64, reason: String },
+    #[error("import failed after committing {committed_rows} rows: {source}")]
+    Insert {
+        committed_rows: u64,
+        #[source]
+        source: DBError,
+    },
+    #[error(transparent)]
+    Csv(#[from] csv::Error),
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    #[error(transparent)]
+    Parquet(#[from] ParquetError),
+    #[error(transparent)]
+    DB(#[from] DBError),
+}
+
+/// A stream of record batches read from a file.
+pub type Batches = Box<dyn Iterator<Item = Result<RecordBatch, ImportError>> + Send>;
+
+/// Open `data` as a file of `format`,
+/// returning the schema of its record batches, and the batches themselves.
+pub fn read_batches(format: ImportFormat, data: Bytes) -> Result<(SchemaRef, Batches), ImportError> {
+    match format {
+        ImportFormat::Parquet => {
+            let reader = ParquetRecordBatchReaderBuilder::try_new(data)?
+                .with_batch_size(BATCH_ROWS)
+                .build()?;
+            let schema = reader.schema();
+            Ok((schema, Box::new(reader.map(|batch| Ok(batch?)))))
+        }
+        ImportFormat::Csv => {
+            let reader = CsvBatches::new(data)?;
+            Ok((reader.schema.clone(), Box::new(reader)))
+        }
+    }
+}
+
+/// Reads record batches from a CSV file, with every column being a nullable `Utf8`.
+struct CsvBatches {
+    reader: csv::Reader<bytes::buf::Reader<Bytes>>,
+    schema: SchemaRef,
+    record: csv::StringRecord,
+}
+
+impl CsvBatches {
+    fn new(data: Bytes) -> Result<Self, ImportError> {
+        let mut reader = csv::Reader::from_reader(data.reader());
+        let fields = reader
+            .headers()?
+            .iter()
+            .map(|name| Field::new(name, DataType::Utf8, true))
+            .collect::<Vec<_>>();
+        Ok(Self {
+            reader,
+            schema: Arc::new(Schema::new(fields)),
+            record: csv::StringRecord::new(),
+        })
+    }
+
+    fn next_batch(&mut self) -> Result<Option<RecordBatch>, ImportError> {
+        let mut columns = (0..self.schema.fields().len())
+            .map(|_| StringBuilder::new())
+            .collect::<Vec<_>>();
+        let mut rows = 0;
+        while rows < BATCH_ROWS && self.reader.read_record(&mut self.record)? {
+            // The `csv` reader checks that all records have as many fields as the header.
+            for (column, field) in columns.iter_mut().zip(&self.record) {
+                match field {
+                    "" => column.append_null(),
+                    field => column.append_value(field),
+                }
+            }
+            rows += 1;
+        }
+        if rows == 0 {
+            return Ok(None);
+        }
+        let columns = columns
+            .iter_mut()
+            .map(|column| Arc::new(column.finish()) as ArrayRef)
+            .collect();
+        Ok(Some(RecordBatch::try_new(self.schema.clone(), columns)?))
+    }
+}
+
+impl Iterator for CsvBatches {
+    type Item = Result<RecordBatch, ImportError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_batch().transpose()
+    }
+}
+
+/// Where the values of a table column come from.
+#[derive(Clone, Debug)]
+enum ColumnSource {
+    /// The column of the file at this index.
+    File(usize),
+    /// This value, for every row.
+    Default(AlgebraicValue),
+}
+
+/// Maps the columns of a file onto the columns of a table, and converts record batches into rows.
+pub struct ColumnMapping {
+    row_type: Vec<(String, AlgebraicType)>,
+    sources: Vec<ColumnSource>,
+    summary: ImportSummary,
+}
+
+impl ColumnMapping {
+    /// Map the columns of a file with `source` schema onto those of `table`,
+    /// renaming them according to `renames`.
+    ///
+    /// Table columns without a column in the file are filled in with `none` if they are optional,
+    /// or with zero if their values are generated by a sequence or a generated column expression.
+    pub fn new(source: &Schema, table: &TableSchema, renames: &[(String, String)]) -> Result<Self, ImportError> {
+        let mut targets = source
+            .fields()
+            .iter()
+            .map(|field| field.name().as_str())
+            .collect::<Vec<_>>();
+        for (from, to) in renames {
+            let index = source
+                .index_of(from)
+                .map_err(|_| ImportError::NoSuchSourceColumn(from.clone()))?;
+            if table.get_column_by_name(to).is_none() {
+                return Err(ImportError::NoSuchColumn(to.clone()));
+            }
+            targets[index] = to.as_str();
+        }
+
+        let mut summary = ImportSummary::default();
+        let mut sources = Vec::with_capacity(table.columns().len());
+        for column in table.columns() {
+            let name = &*column.col_name;
+            let mut mapped = targets.iter().enumerate().filter(|(_, target)| **target == name);
+            let source = match (mapped.next(), mapped.next()) {
+                (Some(_), Some(_)) => return Err(ImportError::DuplicateColumn(name.into())),
+                (Some((index, _)), None) => {
+                    summary
+                        .columns
+                        .push((name.into(), Some(source.field(index).name().clone())));
+                    ColumnSource::File(index)
+                }
+                (None, _) => {
+                    let generated = table.sequences.iter().any(|seq| seq.col_pos == column.col_pos)
+                        || table.generated_columns.iter().any(|col| col.col_pos == column.col_pos);
+                    let default = match &column.col_type {
+                        ty if ty.as_option().is_some() => Some(AlgebraicValue::OptionNone()),
+                        ty if generated => zero_value(ty),
+                        _ => None,
+                    };
+                    summary.columns.push((name.into(), None));
+                    ColumnSource::Default(default.ok_or_else(|| ImportError::MissingColumn(name.into()))?)
+                }
+            };
+            sources.push(source);
+        }
+        summary.ignored = targets
+            .iter()
+            .zip(source.fields())
+            .filter(|(target, _)| table.get_column_by_name(target).is_none())
+            .map(|(_, field)| field.name().clone())
+            .collect();
+
+        let row_type = table
+            .columns()
+            .iter()
+            .map(|column| (column.col_name.to_string(), column.col_type.clone()))
+            .collect();
+        Ok(Self {
+            row_type,
+            sources,
+            summary,
+        })
+    }
+
+    /// Convert the record batch `batch`, starting at row `first_row` of the file, into rows of the table.
+    pub fn rows(&self, batch: &RecordBatch, first_row: u64) -> Result<Vec<ProductValue>, ImportError> {
+        let num_rows = batch.num_rows();
+        let mut columns = self
+            .sources
+            .iter()
+            .zip(&self.row_type)
+            .map(|(source, (name, ty))| match source {
+                ColumnSource::File(index) => {
+                    column_values(batch.column(*index), ty).map_err(|source| ImportError::Coerce {
+                        column: name.clone(),
+                        first_row,
+                        source,
+                    })
+                }
+                ColumnSource::Default(value) => Ok(vec![value.clone(); num_rows]),
+            })
+            .map(|column| column.map(Vec::into_iter))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((0..num_rows)
+            .map(|_| columns.iter_mut().map(|column| column.next().unwrap()).collect())
+            .collect())
+    }
+
+    /// A summary of the import with no rows imported yet.
+    pub fn summary(&self) -> ImportSummary {
+        self.summary.clone()
+    }
+}
+
+/// Returns the zero value of `ty`, if it is a numeric type.
+fn zero_value(ty: &AlgebraicType) -> Option<AlgebraicValue> {
+    Some(match ty {
+        AlgebraicType::I8 => AlgebraicValue::I8(0),
+        AlgebraicType::U8 => AlgebraicValue::U8(0),
+        AlgebraicType::I16 => AlgebraicValue::I16(0),
+        AlgebraicType::U16 => AlgebraicValue::U16(0),
+        AlgebraicType::I32 => AlgebraicValue::I32(0),
+        AlgebraicType::U32 => AlgebraicValue::U32(0),
+        AlgebraicType::I64 => AlgebraicValue::I64(0),
+        AlgebraicType::U64 => AlgebraicValue::U64(0),
+        AlgebraicType::I128 => 0i128.into(),
+        AlgebraicType::U128 => 0u128.into(),
+        AlgebraicType::I256 => spacetimedb_sats::i256::ZERO.into(),
+        AlgebraicType::U256 => spacetimedb_sats::u256::ZERO.into(),
+        AlgebraicType::F32 => 0f32.into(),
+        AlgebraicType::F64 => 0f64.into(),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spacetimedb_sats::{product, ProductType};
+
+    fn table() -> TableSchema {
+        TableSchema::from_product_type(ProductType::from([
+            ("name", AlgebraicType::String),
+            ("points", AlgebraicType::I32),
+            ("note", AlgebraicType::option(AlgebraicType::String)),
+        ]))
+    }
+
+    fn read_csv(csv: &'static str) -> (SchemaRef, Vec<RecordBatch>) {
+        let (schema, batches) = read_batches(ImportFormat::Csv, Bytes::from_static(csv.as_bytes())).unwrap();
+        (schema, batches.collect::<Result<_, _>>().unwrap())
+    }
+
+    fn renames(renames: &[(&str, &str)]) -> Vec<(String, String)> {
+        renames
+            .iter()
+            .map(|(from, to)| (from.to_string(), to.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn import_csv() {
+        let (schema, batches) = read_csv("name,score,extra\na,1,x\nb,-2,\n");
+        let mapping = ColumnMapping::new(&schema, &table(), &renames(&[("score", "points")])).unwrap();
+
+        let summary = mapping.summary();
+        assert_eq!(
+            summary.columns,
+            [
+                ("name".into(), Some("name".into())),
+                ("points".into(), Some("score".into())),
+                ("note".into(), None)
+            ]
+        );
+        assert_eq!(summary.ignored, ["extra"]);
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            mapping.rows(&batches[0], 0).unwrap(),
+            [product!["a", 1i32, None::<&str>], product!["b", -2i32, None::<&str>]]
+        );
+    }
+
+    #[test]
+    fn import_errors() {
+        let table = table();
+
+        let (schema, _) = read_csv("name\na\n");
+        assert!(matches!(
+            ColumnMapping::new(&schema, &table, &[]),
+            Err(ImportError::MissingColumn(col)) if col == "points"
+        ));
+
+        let (schema, batches) = read_csv("name,points,score\na,x,1\n");
+        assert!(matches!(
+            ColumnMapping::new(&schema, &table, &renames(&[("nope", "points")])),
+            Err(ImportError::NoSuchSourceColumn(_))
+        ));
+        assert!(matches!(
+            ColumnMapping::new(&schema, &table, &renames(&[("score", "nope")])),
+            Err(ImportError::NoSuchColumn(_))
+        ));
+        assert!(matches!(
+            ColumnMapping::new(&schema, &table, &renames(&[("score", "points")])),
+            Err(ImportError::DuplicateColumn(col)) if col == "points"
+        ));
+
+        let mapping = ColumnMapping::new(&schema, &table, &[]).unwrap();
+        assert!(matches!(
+            mapping.rows(&batches[0], 0),
+            Err(ImportError::Coerce { column, .. }) if column == "points"
+        ));
+    }
+}
@@ -0,0 +1,219 @@
+use crate::db::datastore::locking_tx_datastore::state_view::StateView as _;
+use crate::db::datastore::system_tables::{StRowLevelSecurityRow, ST_ROW_LEVEL_SECURITY_ID};
+use crate::db::relational_db::{RelationalDB, Tx};
+use crate::error::DBError;
+use crate::sql::ast::SchemaViewer;
+use parking_lot::Mutex;
+use spacetimedb_data_structures::map::{HashMap, HashSet};
+use spacetimedb_expr::check::parse_and_type_sub;
+use spacetimedb_lib::identity::AuthCtx;
+use spacetimedb_lib::operator::{OpCmp, OpLogic};
+use spacetimedb_physical_plan::compile::compile_project_plan;
+use spacetimedb_physical_plan::plan::{HashJoin, Label, PhysicalExpr, PhysicalPlan};
+use spacetimedb_primitives::{ColId, TableId};
+use spacetimedb_sats::AlgebraicValue;
+use spacetimedb_sql_parser::ast::{BinOp, LogOp};
+use spacetimedb_vm::expr::{ColumnOp, Query, QueryExpr};
+
+/// How often queries scanned a table, filtering on a column without the help of an index.
+#[derive(Debug, Default, Clone, Copy)]
+struct ScanStats {
+    scans: u64,
+    rows_scanned: u64,
+}
+
+/// Records the columns which queries filter on while scanning entire tables,
+/// to suggest indexes which would have let them look up the matching rows instead.
+///
+/// Subscriptions and SQL queries are recorded as they are evaluated.
+/// The filters of row level security policies are taken into account
+/// whenever advice is requested, as they apply to every query of their table.
+#[derive(Debug, Default)]
+pub struct IndexAdvisor {
+    scans: Mutex<HashMap<(TableId, ColId), ScanStats>>,
+}
+
+/// A suggestion to add a btree index on a column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexAdvice {
+    pub table_name: Box<str>,
+    pub column_name: Box<str>,
+    /// The number of times queries scanned the table, filtering on the column.
+    pub scans: u64,
+    /// The number of rows read by those scans.
+    pub rows_scanned: u64,
+    /// The estimated number of rows an index would have saved those scans from reading,
+    /// assuming the values of the column are distributed uniformly.
+    pub rows_saved: u64,
+}
+
+impl IndexAdvisor {
+    /// Record that a query read all `rows` rows of `table_id`, filtering on `cols`.
+    fn record_scan(&self, table_id: TableId, cols: Vec<ColId>, rows: u64) {
+        let cols = cols.into_iter().collect::<HashSet<_>>();
+        let mut scans = self.scans.lock();
+        for col in cols {
+            let stats = scans.entry((table_id, col)).or_default();
+            stats.scans += 1;
+            stats.rows_scanned = stats.rows_scanned.saturating_add(rows);
+        }
+    }
+
+    /// Record the table scans of a physical plan, as evaluated by subscriptions.
+    pub fn record_plan(&self, tx: &Tx, plan: &PhysicalPlan) {
+        let row_count = |table_id| tx.table_row_count(table_id).unwrap_or_default();
+        plan.visit(&mut |plan| match plan {
+            PhysicalPlan::Filter(input, expr) => {
+                if let PhysicalPlan::TableScan(schema, label, None) = &**input {
+                    let mut cols = vec![];
+                    filtered_fields(expr, *label, &mut cols);
+                    self.record_scan(schema.table_id, cols, row_count(schema.table_id));
+                }
+            }
+            // An index on the join column of the rhs would permit an index join.
+            PhysicalPlan::HashJoin(HashJoin { rhs, rhs_field, .. }, _) => {
+                if let PhysicalPlan::TableScan(schema, label, None) = &**rhs {
+                    if rhs_field.label == *label {
+                        let cols = vec![rhs_field.field_pos.into()];
+                        self.record_scan(schema.table_id, cols, row_count(schema.table_id));
+                    }
+                }
+            }
+            _ => {}
+        });
+    }
+
+    /// Record the table scans of a query compiled for the vm, as evaluated by SQL.
+    ///
+    /// `row_count` returns the number of rows in a table.
+    pub fn record_query(&self, query: &QueryExpr, row_count: &impl Fn(TableId) -> u64) {
+        // Filters read the entire table, unless they follow an index scan.
+        if let Some(table) = query.source.get_db_table() {
+            let mut cols = vec![];
+            for op in query.query.iter().map_while(|op| match op {
+                Query::Select(op) => Some(op),
+                _ => None,
+            }) {
+                filtered_cols(op, &mut cols);
+            }
+            if !cols.is_empty() {
+                self.record_scan(table.table_id, cols, row_count(table.table_id));
+            }
+        }
+
+        for op in &query.query {
+            match op {
+                Query::IndexJoin(join) => self.record_query(&join.probe_side, row_count),
+                Query::JoinInner(join) => {
+                    self.record_query(&join.rhs, row_count);
+                    // An index on the join column of the rhs would permit an index join.
+                    if let Some(table) = join.rhs.source.get_db_table() {
+                        self.record_scan(table.table_id, vec![join.col_rhs], row_count(table.table_id));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Suggest indexes for the recorded scans and for the filters of row level security policies,
+    /// ordered by the estimated number of rows they would save.
+    ///
+    /// Columns which are the first column of an existing index are never suggested.
+    pub fn advise(&self, db: &RelationalDB, tx: &Tx, auth: &AuthCtx) -> Result<Vec<IndexAdvice>, DBError> {
+        let rls = IndexAdvisor::default();
+        for row in db.iter(tx, ST_ROW_LEVEL_SECURITY_ID)? {
+            let row = StRowLevelSecurityRow::try_from(row)?;
+            // Filters which no longer type check, e.g. after a table was dropped, can be ignored.
+            if let Ok(sub) = parse_and_type_sub(&row.sql, &SchemaViewer::new(tx, auth)) {
+                rls.record_plan(tx, &compile_project_plan(sub).optimize());
+            }
+        }
+
+        let mut scans = self.scans.lock().clone();
+        for (key, rls_stats) in rls.scans.into_inner() {
+            let stats = scans.entry(key).or_default();
+            stats.scans += rls_stats.scans;
+            stats.rows_scanned = stats.rows_scanned.saturating_add(rls_stats.rows_scanned);
+        }
+
+        let mut advice = vec![];
+        for ((table_id, col), stats) in scans {
+            // The table or column may have been dropped since.
+            let Some(schema) = tx.get_schema(table_id) else {
+                continue;
+            };
+            let Some(column) = schema.get_column(col.idx()) else {
+                continue;
+            };
+            let indexed = schema
+                .indexes
+                .iter()
+                .any(|index| index.index_algorithm.columns().head() == Some(col));
+            if indexed {
+                continue;
+            }
+
+            // Each lookup of an index returns one in `distinct` rows.
+            let distinct = db
+                .iter(tx, table_id)?
+                .map(|row| row.read_col::<AlgebraicValue>(col))
+                .collect::<Result<HashSet<_>, _>>()?
+                .len() as u64;
+            let rows_saved = stats.rows_scanned - stats.rows_scanned / distinct.max(1);
+            if rows_saved == 0 {
+                continue;
+            }
+
+            advice.push(IndexAdvice {
+                table_name: schema.table_name.clone(),
+                column_name: column.col_name.clone(),
+                scans: stats.scans,
+                rows_scanned: stats.rows_scanned,
+                rows_saved,
+            });
+        }
+        advice.sort_by(|a, b| {
+            b.rows_saved
+                .cmp(&a.rows_saved)
+                .then_with(|| (&a.table_name, &a.column_name).cmp(&(&b.table_name, &b.column_name)))
+        });
+
+        Ok(advice)
+    }
+}
+
+/// Collect the columns of `op` which an index could look up,
+/// i.e. those compared with a constant in a conjunction.
+fn filtered_cols(op: &ColumnOp, cols: &mut Vec<ColId>) {
+    match op {
+        ColumnOp::ColCmpVal { lhs, cmp, .. } if *cmp != OpCmp::NotEq => cols.push(*lhs),
+        ColumnOp::Cmp { lhs, cmp, rhs } if *cmp != OpCmp::NotEq => match (&**lhs, &**rhs) {
+            (ColumnOp::Col(col), ColumnOp::Val(_)) | (ColumnOp::Val(_), ColumnOp::Col(col)) => cols.push(*col),
+            _ => {}
+        },
+        ColumnOp::Log {
+            op: OpLogic::And,
+            operands,
+        } => operands.iter().for_each(|op| filtered_cols(op, cols)),
+        _ => {}
+    }
+}
+
+/// Collect the fields of the relvar `label` which an index could look up,
+/// i.e. those compared with a constant in a conjunction.
+fn filtered_fields(expr: &PhysicalExpr, label: Label, cols: &mut Vec<ColId>) {
+    match expr {
+        PhysicalExpr::BinOp(op, lhs, rhs) if *op != BinOp::Ne => match (&**lhs, &**rhs) {
+            (PhysicalExpr::Field(field), PhysicalExpr::Value(_))
+            | (PhysicalExpr::Value(_), PhysicalExpr::Field(field))
+                if field.label == label =>
+            {
+                cols.push(field.field_pos.into())
+            }
+            _ => {}
+        },
+        PhysicalExpr::LogOp(LogOp::And, exprs) => exprs.iter().for_each(|expr| filtered_fields(expr, label, cols)),
+        _ => {}
+    }
+}
@@ -1,8 +1,14 @@
+pub mod blob;
 pub mod datastore;
 pub mod db_metrics;
+pub mod export;
+pub mod import;
+pub mod index_advisor;
 pub mod relational_db;
 pub mod update;
 
+use crate::config::{DurabilityConfig, MemoryConfig, QuotaConfig};
+
 /// Whether SpacetimeDB is run in memory, or persists objects and
 /// a message log to disk.
 #[derive(Clone, Copy)]
@@ -15,8 +21,14 @@ pub enum Storage {
 }
 
 /// Internal database config parameters
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Config {
     /// Specifies the object storage model.
     pub storage: Storage,
+    /// The resource limits applied to each database.
+    pub quotas: QuotaConfig,
+    /// How the commitlogs of databases are synced to disk.
+    pub durability: DurabilityConfig,
+    /// How databases hold their data in memory.
+    pub memory: MemoryConfig,
 }
@@ -2,7 +2,9 @@ use super::datastore::locking_tx_datastore::committed_state::CommittedState;
 use super::datastore::locking_tx_datastore::state_view::{
     IterByColEqMutTx, IterByColRangeMutTx, IterMutTx, IterTx, StateView,
 };
-use super::datastore::system_tables::ST_MODULE_ID;
+use super::datastore::system_tables::{
+    StTableDurabilityRow, ST_CLIENT_ID, ST_CONNECTION_ID, ST_MODULE_ID, ST_SUBSCRIPTION_ID, ST_TABLE_DURABILITY_ID,
+};
 use super::datastore::traits::{
     IsolationLevel, Metadata, MutTx as _, MutTxDatastore, Program, RowTypeForTable, Tx as _, TxDatastore,
 };
@@ -14,8 +16,10 @@ use super::datastore::{
     traits::TxData,
 };
 use super::db_metrics::DB_METRICS;
+use super::export::{self, ExportError};
+use super::index_advisor::{IndexAdvice, IndexAdvisor};
 use crate::db::datastore::system_tables::{StModuleRow, WASM_MODULE};
-use crate::error::{DBError, DatabaseError, TableError};
+use crate::error::{DBError, DatabaseError, SizeQuotaExceeded, SizeQuotaKind, TableError};
 use crate::execution_context::{ReducerContext, Workload};
 use crate::messages::control_db::HostType;
 use crate::util::spawn_rayon;
@@ -23,19 +27,24 @@ use anyhow::{anyhow, Context};
 use fs2::FileExt;
 use futures::channel::mpsc;
 use futures::StreamExt;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use spacetimedb_commitlog as commitlog;
-pub use spacetimedb_durability::Durability;
 use spacetimedb_durability::{self as durability, TxOffset};
+pub use spacetimedb_durability::{
+    local::{ArchiveOptions, Options as LocalDurabilityOptions},
+    Durability,
+};
 use spacetimedb_lib::address::Address;
-use spacetimedb_lib::db::auth::StAccess;
+use spacetimedb_lib::db::auth::{StAccess, StDurability};
 use spacetimedb_lib::db::raw_def::v9::{RawIndexAlgorithm, RawModuleDefV9Builder, RawSql};
+use spacetimedb_lib::identity::AuthCtx;
 use spacetimedb_lib::Identity;
 use spacetimedb_paths::server::{CommitLogDir, ReplicaDir, SnapshotsPath};
 use spacetimedb_primitives::*;
-use spacetimedb_sats::{AlgebraicType, AlgebraicValue, ProductType, ProductValue};
+use spacetimedb_sats::{bsatn, AlgebraicType, AlgebraicValue, ProductType, ProductValue};
 use spacetimedb_schema::def::{ModuleDef, TableDef};
 use spacetimedb_schema::schema::{IndexSchema, RowLevelSecuritySchema, Schema, SequenceSchema, TableSchema};
+pub use spacetimedb_snapshot::CompressionStats;
 use spacetimedb_snapshot::{SnapshotError, SnapshotRepository};
 use spacetimedb_table::indexes::RowPointer;
 use spacetimedb_table::table::RowRef;
@@ -45,9 +54,13 @@ use std::collections::HashSet;
 use std::fmt;
 use std::fs::File;
 use std::io;
+use std::num::NonZeroU64;
 use std::ops::RangeBounds;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::watch;
 
 pub type MutTx = <Locking as super::datastore::traits::MutTx>::MutTx;
 pub type Tx = <Locking as super::datastore::traits::Tx>::Tx;
@@ -96,6 +109,16 @@ pub struct RelationalDB {
     /// `Some` if `durability` is `Some`, `None` otherwise.
     disk_size_fn: Option<DiskSizeFn>,
 
+    size_quota: Arc<SizeQuota>,
+
+    index_advisor: Arc<IndexAdvisor>,
+
+    /// The offset of the most recently committed transaction which consumed an offset,
+    /// `None` if no such transaction has been committed yet.
+    ///
+    /// Readers which must observe a certain transaction wait on this via [`Self::wait_for_tx_offset`].
+    committed_tx_offset: watch::Sender<Option<TxOffset>>,
+
     // DO NOT ADD FIELDS AFTER THIS.
     // By default, fields are dropped in declaration order.
     // We want to release the file lock last.
@@ -103,21 +126,90 @@ pub struct RelationalDB {
     _lock: LockFile,
 }
 
+/// How long [`RelationalDB::wait_for_tx_offset`] waits for a transaction to be committed.
+pub const TX_OFFSET_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returned by [`RelationalDB::wait_for_tx_offset`]
+/// if the transaction was not committed within [`TX_OFFSET_WAIT_TIMEOUT`].
+#[derive(thiserror::Error, Debug)]
+#[error("transaction {tx_offset} has not been committed yet, the latest committed transaction is {committed:?}")]
+pub struct TxOffsetTimeout {
+    /// The offset of the transaction waited for.
+    pub tx_offset: TxOffset,
+    /// The offset of the latest committed transaction.
+    pub committed: Option<TxOffset>,
+}
+
+/// Tracks the sizes of a database against their maximums, if any.
+///
+/// Measuring the size is expensive, as it walks the files on disk,
+/// so it is done periodically by [`RelationalDB::measure_size`].
+/// In between measurements, the size of the rows inserted by each transaction
+/// is added to the estimate, so that a burst of writes is noticed before the next measurement.
+///
+/// The memory and disk usage are measured by the host,
+/// and recorded by [`RelationalDB::record_storage_usage`].
+struct SizeQuota {
+    /// The maximum size in bytes, or [`u64::MAX`] if unlimited.
+    max: AtomicU64,
+    /// The size in bytes as last measured, plus the size of the rows inserted since.
+    size: AtomicU64,
+    /// The maximum size in bytes of the in-memory data, or [`u64::MAX`] if unlimited.
+    max_memory: AtomicU64,
+    /// The size in bytes of the in-memory data, as last measured.
+    memory: AtomicU64,
+    /// The maximum size in bytes of the commitlog and module logs, or [`u64::MAX`] if unlimited.
+    max_disk: AtomicU64,
+    /// The size in bytes of the commitlog and module logs, as last measured.
+    disk: AtomicU64,
+}
+
+impl Default for SizeQuota {
+    fn default() -> Self {
+        Self {
+            max: AtomicU64::new(u64::MAX),
+            size: AtomicU64::new(0),
+            max_memory: AtomicU64::new(u64::MAX),
+            memory: AtomicU64::new(0),
+            max_disk: AtomicU64::new(u64::MAX),
+            disk: AtomicU64::new(0),
+        }
+    }
+}
+
 struct SnapshotWorker {
     _handle: tokio::task::JoinHandle<()>,
+    /// The repository the `snapshot_loop` writes snapshots into.
+    repo: Arc<SnapshotRepository>,
     /// Send end of the [`Self::snapshot_loop`]'s `trigger` receiver.
     ///
     /// Send a message along this queue to request that the `snapshot_loop` asynchronously capture a snapshot.
     request_snapshot: mpsc::UnboundedSender<()>,
+    /// When to request snapshots, see [`RelationalDB::maybe_do_snapshot`].
+    policy: RwLock<SnapshotPolicy>,
+    /// When a snapshot was last requested, for [`SnapshotPolicy::EveryMinutes`].
+    last_requested: Mutex<Instant>,
+    /// The snapshot captured most recently since the database was opened, if any.
+    last_snapshot: Arc<Mutex<Option<SnapshotInfo>>>,
 }
 
 impl SnapshotWorker {
     fn new(committed_state: Arc<RwLock<CommittedState>>, repo: Arc<SnapshotRepository>) -> Self {
         let (request_snapshot, trigger) = mpsc::unbounded();
-        let handle = tokio::spawn(Self::snapshot_loop(trigger, committed_state, repo));
+        let last_snapshot = Arc::new(Mutex::new(None));
+        let handle = tokio::spawn(Self::snapshot_loop(
+            trigger,
+            committed_state,
+            repo.clone(),
+            last_snapshot.clone(),
+        ));
         SnapshotWorker {
             _handle: handle,
+            repo,
             request_snapshot,
+            policy: RwLock::new(SnapshotPolicy::default()),
+            last_requested: Mutex::new(Instant::now()),
+            last_snapshot,
         }
     }
 
@@ -126,18 +218,25 @@ impl SnapshotWorker {
         mut trigger: mpsc::UnboundedReceiver<()>,
         committed_state: Arc<RwLock<CommittedState>>,
         repo: Arc<SnapshotRepository>,
+        last_snapshot: Arc<Mutex<Option<SnapshotInfo>>>,
     ) {
         while let Some(()) = trigger.next().await {
             let committed_state = committed_state.clone();
             let repo = repo.clone();
-            tokio::task::spawn_blocking(move || Self::take_snapshot(&committed_state, &repo))
+            let last_snapshot = last_snapshot.clone();
+            tokio::task::spawn_blocking(move || Self::take_snapshot(&committed_state, &repo, &last_snapshot))
                 .await
                 .unwrap();
         }
     }
 
-    fn take_snapshot(committed_state: &RwLock<CommittedState>, snapshot_repo: &SnapshotRepository) {
-        let start_time = std::time::Instant::now();
+    fn take_snapshot(
+        committed_state: &RwLock<CommittedState>,
+        snapshot_repo: &SnapshotRepository,
+        last_snapshot: &Mutex<Option<SnapshotInfo>>,
+    ) {
+        let captured_at = SystemTime::now();
+        let start_time = Instant::now();
         match Locking::take_snapshot_internal(committed_state, snapshot_repo) {
             Err(e) => {
                 log::error!(
@@ -154,19 +253,24 @@ impl SnapshotWorker {
             }
 
             Ok(Some((tx_offset, _path))) => {
+                let duration = start_time.elapsed();
                 log::info!(
                     "Captured snapshot of database {:?} at TX offset {} in {:?}",
                     snapshot_repo.database_identity(),
                     tx_offset,
-                    start_time.elapsed()
+                    duration
                 );
+                *last_snapshot.lock() = Some(SnapshotInfo {
+                    tx_offset,
+                    captured_at,
+                    duration,
+                });
             }
         }
     }
 }
 
-/// Perform a snapshot every `SNAPSHOT_FREQUENCY` transactions.
-// TODO(config): Allow DBs to specify how frequently to snapshot.
+/// By default, perform a snapshot every `SNAPSHOT_FREQUENCY` transactions.
 // TODO(bikeshedding): Snapshot based on number of bytes written to commitlog, not tx offsets.
 //
 // NOTE: Replicas must agree on the snapshot frequency. By making them consult
@@ -174,6 +278,50 @@ impl SnapshotWorker {
 // compiler to find external dependencies.
 pub const SNAPSHOT_FREQUENCY: u64 = 1_000_000;
 
+/// When a database captures snapshots of its committed state by itself,
+/// in addition to those requested via [`RelationalDB::take_snapshot`].
+///
+/// In TOML, written e.g. `{ every-txs = 1000000 }`, `{ every-minutes = 60 }` or `"on-demand"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SnapshotPolicy {
+    /// Capture a snapshot whenever the TX offset is a multiple of this many transactions.
+    EveryTxs(NonZeroU64),
+    /// Capture a snapshot with the first transaction committed
+    /// at least this many minutes after the previous snapshot was requested.
+    EveryMinutes(NonZeroU64),
+    /// Only capture snapshots when requested via [`RelationalDB::take_snapshot`].
+    OnDemand,
+}
+
+impl Default for SnapshotPolicy {
+    fn default() -> Self {
+        Self::EveryTxs(NonZeroU64::new(SNAPSHOT_FREQUENCY).unwrap())
+    }
+}
+
+/// A snapshot captured by a [`RelationalDB`].
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotInfo {
+    /// The TX offset of the snapshot.
+    pub tx_offset: TxOffset,
+    /// When capturing the snapshot started.
+    pub captured_at: SystemTime,
+    /// How long capturing the snapshot took.
+    pub duration: Duration,
+}
+
+/// The snapshot settings and history of a [`RelationalDB`], see [`RelationalDB::snapshot_status`].
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotStatus {
+    /// The policy currently in effect.
+    pub policy: SnapshotPolicy,
+    /// The TX offset of the latest snapshot on disk, if any.
+    pub latest_tx_offset: Option<TxOffset>,
+    /// The snapshot captured most recently since the database was opened, if any.
+    pub last_captured: Option<SnapshotInfo>,
+}
+
 impl std::fmt::Debug for RelationalDB {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("RelationalDB")
@@ -194,6 +342,7 @@ impl RelationalDB {
         let (durability, disk_size_fn) = durability.unzip();
         let snapshot_worker =
             snapshot_repo.map(|repo| Arc::new(SnapshotWorker::new(inner.committed_state.clone(), repo.clone())));
+        let (committed_tx_offset, _) = watch::channel(inner.committed_state.read().next_tx_offset.checked_sub(1));
         Self {
             inner,
             durability,
@@ -204,6 +353,9 @@ impl RelationalDB {
 
             row_count_fn: default_row_count_fn(database_identity),
             disk_size_fn,
+            size_quota: <_>::default(),
+            index_advisor: <_>::default(),
+            committed_tx_offset,
             _lock: lock,
         }
     }
@@ -295,6 +447,73 @@ impl RelationalDB {
         durability: Option<(Arc<dyn Durability<TxData = Txdata>>, DiskSizeFn)>,
         snapshot_repo: Option<Arc<SnapshotRepository>>,
     ) -> Result<(Self, ConnectedClients), DBError> {
+        let db = Self::open_unchecked(
+            root,
+            database_identity,
+            owner_identity,
+            history,
+            durability,
+            snapshot_repo,
+        )?;
+
+        if let Some(meta) = db.metadata()? {
+            if meta.database_identity != database_identity {
+                return Err(anyhow!(
+                    "mismatched database identity: {} != {}",
+                    meta.database_identity,
+                    database_identity
+                )
+                .into());
+            }
+            if meta.owner_identity != owner_identity {
+                return Err(anyhow!(
+                    "mismatched owner identity: {} != {}",
+                    meta.owner_identity,
+                    owner_identity
+                )
+                .into());
+            }
+        };
+        let connected_clients = db.connected_clients()?;
+
+        // Subscriptions don't outlive the host, but `st_subscription` may have been restored from a snapshot.
+        db.with_auto_commit(Workload::Internal, |tx| {
+            let subscriptions = db
+                .iter_mut(tx, ST_SUBSCRIPTION_ID)?
+                .map(|row| row.pointer())
+                .collect::<Vec<_>>();
+            db.delete(tx, ST_SUBSCRIPTION_ID, subscriptions);
+            Ok::<_, DBError>(())
+        })?;
+
+        // Nor do the rows of ephemeral tables,
+        // though those inserted while the table was still durable may have been replayed from the commitlog.
+        db.with_auto_commit(Workload::Internal, |tx| {
+            let tables = db
+                .iter_mut(tx, ST_TABLE_DURABILITY_ID)?
+                .map(StTableDurabilityRow::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            for table in tables {
+                if table.durability == StDurability::Ephemeral {
+                    db.clear_table(tx, table.table_id)?;
+                }
+            }
+            Ok::<_, DBError>(())
+        })?;
+
+        Ok((db, connected_clients))
+    }
+
+    /// Like [`Self::open`], but without checking that the metadata of the database
+    /// matches `database_identity` and `owner_identity`.
+    fn open_unchecked(
+        root: &ReplicaDir,
+        database_identity: Identity,
+        owner_identity: Identity,
+        history: impl durability::History<TxData = Txdata>,
+        durability: Option<(Arc<dyn Durability<TxData = Txdata>>, DiskSizeFn)>,
+        snapshot_repo: Option<Arc<SnapshotRepository>>,
+    ) -> Result<Self, DBError> {
         log::trace!("[{}] DATABASE: OPEN", database_identity);
 
         let lock = LockFile::lock(root)?;
@@ -313,36 +532,95 @@ impl RelationalDB {
             Self::restore_from_snapshot_or_bootstrap(database_identity, snapshot_repo.as_deref(), durable_tx_offset)?;
 
         apply_history(&inner, database_identity, history)?;
-        let db = Self::new(
+        Ok(Self::new(
             lock,
             database_identity,
             owner_identity,
             inner,
             durability,
             snapshot_repo,
-        );
+        ))
+    }
 
-        if let Some(meta) = db.metadata()? {
-            if meta.database_identity != database_identity {
-                return Err(anyhow!(
-                    "mismatched database identity: {} != {}",
-                    meta.database_identity,
-                    database_identity
-                )
-                .into());
+    /// Fork the database `source_identity`, stored in the replica directory `source`,
+    /// into the new database `target_identity`, stored in the empty replica directory `target`.
+    ///
+    /// The commitlog of `source` is copied and the objects of its latest snapshot are hardlinked,
+    /// so that opening the fork restores that snapshot
+    /// and replays only the transactions committed after it.
+    /// The fork then records `target_identity` and `owner_identity` in its metadata,
+    /// and forgets the clients which were connected to `source`.
+    ///
+    /// `source` may belong to a running database,
+    /// in which case the transactions it commits while being forked may be left out of the fork.
+    ///
+    /// If the commitlog of `source` is archived to `source_archive`,
+    /// archived segments which are no longer on local disk are downloaded into the fork.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn fork(
+        source: &ReplicaDir,
+        source_identity: Identity,
+        source_replica_id: u64,
+        source_archive: Option<Arc<dyn commitlog::archive::Archive>>,
+        target: &ReplicaDir,
+        target_identity: Identity,
+        target_replica_id: u64,
+        owner_identity: Identity,
+    ) -> Result<(), DBError> {
+        log::info!("[{target_identity}] DATABASE: forking from {source_identity}");
+
+        let snapshot_repo = spawn_rayon({
+            let (source, target) = (source.clone(), target.clone());
+            move || {
+                match source_archive {
+                    Some(archive) => commitlog::repo::Fs::with_archive(source.commit_log(), archive)?,
+                    None => commitlog::repo::Fs::new(source.commit_log())?,
+                }
+                .fork(target.commit_log())?;
+                let source_repo = open_snapshot_repo(source.snapshots(), source_identity, source_replica_id)?;
+                let target_repo = open_snapshot_repo(target.snapshots(), target_identity, target_replica_id)?;
+                if let Some(tx_offset) = source_repo.latest_snapshot()? {
+                    source_repo.fork_snapshot(tx_offset, &target_repo)?;
+                }
+                Ok::<_, DBError>(target_repo)
             }
-            if meta.owner_identity != owner_identity {
-                return Err(anyhow!(
-                    "mismatched owner identity: {} != {}",
-                    meta.owner_identity,
-                    owner_identity
-                )
-                .into());
+        })
+        .await?;
+
+        let (durability, disk_size_fn) = local_durability(target.commit_log(), None, None).await?;
+        let db = Self::open_unchecked(
+            target,
+            target_identity,
+            owner_identity,
+            durability.clone(),
+            Some((durability.clone() as Arc<dyn Durability<TxData = Txdata>>, disk_size_fn)),
+            Some(snapshot_repo),
+        )?;
+        db.with_auto_commit(Workload::Internal, |tx| {
+            let Some(module) = db.iter_mut(tx, ST_MODULE_ID)?.next() else {
+                return Err(anyhow!("database {source_identity} improperly initialized: no metadata").into());
+            };
+            let ptr = module.pointer();
+            let mut row = StModuleRow::try_from(module)?;
+            row.database_identity = target_identity.into();
+            row.owner_identity = owner_identity.into();
+            db.delete(tx, ST_MODULE_ID, [ptr]);
+            tx.insert_via_serialize_bsatn(ST_MODULE_ID, &row)?;
+
+            for table_id in [ST_CLIENT_ID, ST_CONNECTION_ID] {
+                let clients = db.iter_mut(tx, table_id)?.map(|row| row.pointer()).collect::<Vec<_>>();
+                db.delete(tx, table_id, clients);
             }
-        };
-        let connected_clients = db.connected_clients()?;
+            Ok::<_, DBError>(())
+        })?;
 
-        Ok((db, connected_clients))
+        // Flush the fork's commitlog, so that it can be reopened.
+        drop(db);
+        let durability =
+            Arc::into_inner(durability).expect("`drop(db)` should have dropped all references to durability");
+        durability.close().await?;
+
+        Ok(())
     }
 
     /// Mark the database as initialized with the given module parameters.
@@ -476,6 +754,8 @@ impl RelationalDB {
         T: durability::History<TxData = Txdata>,
     {
         apply_history(&self.inner, self.database_identity, history)?;
+        self.committed_tx_offset
+            .send_replace(self.inner.committed_state.read().next_tx_offset.checked_sub(1));
         Ok(self)
     }
 
@@ -496,6 +776,116 @@ impl RelationalDB {
         self.database_identity
     }
 
+    /// Capture a snapshot of the committed state right away,
+    /// regardless of the [`SnapshotPolicy`], and wait for it to be written.
+    ///
+    /// Returns the TX offset of the new snapshot,
+    /// or `None` if the database doesn't keep snapshots or has no transactions yet.
+    ///
+    /// Note that, like the periodic snapshots, the snapshot may include transactions
+    /// which are not yet durable.
+    pub fn take_snapshot(&self) -> Result<Option<TxOffset>, DBError> {
+        let Some(snapshot_worker) = &self.snapshot_worker else {
+            return Ok(None);
+        };
+        let captured_at = SystemTime::now();
+        let start_time = Instant::now();
+        let snapshot = Locking::take_snapshot_internal(&self.inner.committed_state, &snapshot_worker.repo)?;
+        let tx_offset = snapshot.map(|(tx_offset, _)| tx_offset);
+        if let Some(tx_offset) = tx_offset {
+            *snapshot_worker.last_snapshot.lock() = Some(SnapshotInfo {
+                tx_offset,
+                captured_at,
+                duration: start_time.elapsed(),
+            });
+        }
+        Ok(tx_offset)
+    }
+
+    /// Change when this database captures snapshots by itself.
+    ///
+    /// Takes effect with the next committed transaction.
+    /// Has no effect if the database doesn't keep snapshots.
+    pub fn set_snapshot_policy(&self, policy: SnapshotPolicy) {
+        if let Some(snapshot_worker) = &self.snapshot_worker {
+            *snapshot_worker.policy.write() = policy;
+        }
+    }
+
+    /// The snapshot policy of this database and information about its latest snapshots,
+    /// or `None` if the database doesn't keep snapshots.
+    pub fn snapshot_status(&self) -> Result<Option<SnapshotStatus>, DBError> {
+        let Some(snapshot_worker) = &self.snapshot_worker else {
+            return Ok(None);
+        };
+        Ok(Some(SnapshotStatus {
+            policy: *snapshot_worker.policy.read(),
+            latest_tx_offset: snapshot_worker.repo.latest_snapshot()?,
+            last_captured: *snapshot_worker.last_snapshot.lock(),
+        }))
+    }
+
+    /// Compress the objects of the snapshots of this database captured more than `older_than` ago,
+    /// calling `progress` with the totals so far after each snapshot.
+    ///
+    /// Returns `None` if the database doesn't keep snapshots.
+    /// See [`SnapshotRepository::compress_snapshots`].
+    pub fn compress_snapshots(
+        &self,
+        older_than: Duration,
+        progress: impl FnMut(TxOffset, &CompressionStats),
+    ) -> Result<Option<CompressionStats>, DBError> {
+        let Some(snapshot_worker) = &self.snapshot_worker else {
+            return Ok(None);
+        };
+        let cutoff = SystemTime::now()
+            .checked_sub(older_than)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        Ok(Some(snapshot_worker.repo.compress_snapshots(cutoff, progress)?))
+    }
+
+    /// The TX offset of the most recent snapshot of this database, if any.
+    pub fn latest_snapshot(&self) -> Result<Option<TxOffset>, DBError> {
+        let Some(snapshot_worker) = &self.snapshot_worker else {
+            return Ok(None);
+        };
+        Ok(snapshot_worker.repo.latest_snapshot()?)
+    }
+
+    /// Write the rows of the table `table_name` to `out` in Parquet format,
+    /// reading them from the latest snapshot of this database,
+    /// which is taken first if there is none yet.
+    ///
+    /// Transactions committed after that snapshot are not included.
+    /// See [`export::export_table_parquet`].
+    ///
+    /// Returns the TX offset of the snapshot and the number of rows written.
+    pub fn export_table_parquet(
+        &self,
+        table_name: &str,
+        out: impl io::Write + Send,
+    ) -> Result<(TxOffset, u64), ExportError> {
+        let Some(snapshot_worker) = &self.snapshot_worker else {
+            return Err(ExportError::NoSnapshot);
+        };
+        let tx_offset = match snapshot_worker.repo.latest_snapshot()? {
+            Some(tx_offset) => tx_offset,
+            None => self.take_snapshot()?.ok_or(ExportError::NoSnapshot)?,
+        };
+        let rows = export::export_table_parquet(&snapshot_worker.repo, tx_offset, table_name, out)?;
+        Ok((tx_offset, rows))
+    }
+
+    /// Invalidate all snapshots newer than `upper_bound`,
+    /// so that the next time the database is opened, it is restored from
+    /// the latest snapshot at or before `upper_bound` and the commitlog suffix.
+    pub fn invalidate_newer_snapshots(&self, upper_bound: TxOffset) -> Result<(), DBError> {
+        if let Some(snapshot_worker) = &self.snapshot_worker {
+            snapshot_worker.repo.invalidate_newer_snapshots(upper_bound)?;
+        }
+        Ok(())
+    }
+
     /// The number of bytes on disk occupied by the durability layer.
     ///
     /// If this is an in-memory instance, `Ok(0)` is returned.
@@ -503,11 +893,138 @@ impl RelationalDB {
         self.disk_size_fn.as_ref().map_or(Ok(0), |f| f())
     }
 
+    /// Compress the pages of tables which have not been modified in `after` transactions,
+    /// trading the CPU time to decompress them on access for the memory saved.
+    ///
+    /// Pages are never compressed if `after` is `None`, which is the default.
+    pub fn set_compress_idle_tables_after(&self, after: Option<NonZeroU64>) {
+        self.inner.set_compress_idle_tables_after(after);
+    }
+
+    /// Compresses the pages of the tables which have been idle
+    /// for the number of transactions set by [`Self::set_compress_idle_tables_after`].
+    ///
+    /// Returns the number of tables compressed.
+    pub fn compress_idle_tables(&self) -> usize {
+        self.inner.compress_idle_tables()
+    }
+
     /// The size in bytes of all of the in-memory data in this database.
     pub fn size_in_memory(&self) -> usize {
         self.inner.heap_usage()
     }
 
+    /// Limit the total size of this database's tables, commitlog, and snapshots to `max` bytes.
+    ///
+    /// While the database is over this size,
+    /// transactions which insert rows into user tables fail to commit
+    /// with [`SizeQuotaExceeded`].
+    /// The size is unlimited if `max` is `None`, which is the default.
+    pub fn set_max_size(&self, max: Option<u64>) {
+        self.size_quota.max.store(max.unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    /// Limit the size of this database's in-memory data to `max_memory` bytes,
+    /// and that of its commitlog and module logs to `max_disk` bytes.
+    ///
+    /// While the database is over either limit, as last recorded by [`Self::record_storage_usage`],
+    /// transactions which insert rows into user tables fail to commit
+    /// with [`SizeQuotaExceeded`].
+    /// Either is unlimited if `None`, which is the default.
+    pub fn set_max_memory_and_disk(&self, max_memory: Option<u64>, max_disk: Option<u64>) {
+        let quota = &self.size_quota;
+        quota
+            .max_memory
+            .store(max_memory.unwrap_or(u64::MAX), Ordering::Relaxed);
+        quota.max_disk.store(max_disk.unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+
+    /// Record the latest measurement of the size of this database's in-memory data,
+    /// and of its commitlog and module logs,
+    /// as the sizes checked against the limits set by [`Self::set_max_memory_and_disk`].
+    pub fn record_storage_usage(&self, memory: u64, disk: u64) {
+        self.size_quota.memory.store(memory, Ordering::Relaxed);
+        self.size_quota.disk.store(disk, Ordering::Relaxed);
+    }
+
+    /// Returns the limit set by [`Self::set_max_memory_and_disk`] which this database is over, if any.
+    pub fn storage_quota_exceeded(&self) -> Option<SizeQuotaExceeded> {
+        let quota = &self.size_quota;
+        let limits = [
+            (SizeQuotaKind::Memory, &quota.max_memory, &quota.memory),
+            (SizeQuotaKind::Disk, &quota.max_disk, &quota.disk),
+        ];
+        limits.into_iter().find_map(|(kind, max, size)| {
+            let max = max.load(Ordering::Relaxed);
+            let size = size.load(Ordering::Relaxed);
+            (size > max).then_some(SizeQuotaExceeded { kind, max, size })
+        })
+    }
+
+    /// Records the columns which queries filter on without the help of an index.
+    pub fn index_advisor(&self) -> &IndexAdvisor {
+        &self.index_advisor
+    }
+
+    /// Suggest btree indexes for the columns which queries have filtered on
+    /// while scanning entire tables, see [`IndexAdvisor::advise`].
+    pub fn advise_indexes(&self, tx: &Tx) -> Result<Vec<IndexAdvice>, DBError> {
+        self.index_advisor
+            .advise(self, tx, &AuthCtx::for_current(self.owner_identity))
+    }
+
+    /// Measure the total size in bytes of this database's tables, commitlog, and snapshots,
+    /// and record it as the size checked against the limit set by [`Self::set_max_size`].
+    pub fn measure_size(&self) -> io::Result<u64> {
+        let snapshots = match &self.snapshot_worker {
+            Some(snapshot_worker) => snapshot_worker.repo.size_on_disk()?,
+            None => 0,
+        };
+        let size = self.size_in_memory() as u64 + self.size_on_disk()? + snapshots;
+        self.size_quota.size.store(size, Ordering::Relaxed);
+        DB_METRICS
+            .database_size
+            .with_label_values(&self.database_identity)
+            .set(size as i64);
+        Ok(size)
+    }
+
+    /// Returns an error if `tx` inserts rows into user tables
+    /// while this database is over the size set by [`Self::set_max_size`],
+    /// or over one of the limits set by [`Self::set_max_memory_and_disk`].
+    ///
+    /// Transactions which only delete rows are always allowed,
+    /// so that a database over its limit can shrink.
+    pub fn check_size_quota(&self, tx: &MutTx) -> Result<(), SizeQuotaExceeded> {
+        let exceeded = self.storage_quota_exceeded().or_else(|| {
+            let max = self.size_quota.max.load(Ordering::Relaxed);
+            let size = self.size_quota.size.load(Ordering::Relaxed);
+            let kind = SizeQuotaKind::Size;
+            (size > max).then_some(SizeQuotaExceeded { kind, max, size })
+        });
+        let Some(exceeded) = exceeded.filter(|_| tx.inserts_into_user_tables()) else {
+            return Ok(());
+        };
+        DB_METRICS
+            .size_quota_exceeded
+            .with_label_values(&self.database_identity)
+            .inc();
+        Err(exceeded)
+    }
+
+    /// Add the size of the rows inserted by `tx_data` to the estimated size of this database.
+    fn record_size_of_inserts(&self, tx_data: &TxData) {
+        if self.size_quota.max.load(Ordering::Relaxed) == u64::MAX {
+            return;
+        }
+        let inserted: u64 = tx_data
+            .inserts()
+            .flat_map(|(_, rows)| rows.iter())
+            .map(|row| bsatn::to_len(row).map_or(0, |len| len as u64))
+            .sum();
+        self.size_quota.size.fetch_add(inserted, Ordering::Relaxed);
+    }
+
     pub fn encode_row(row: &ProductValue, bytes: &mut Vec<u8>) {
         // TODO: large file storage of the row elements
         row.encode(bytes);
@@ -616,21 +1133,33 @@ impl RelationalDB {
     pub fn commit_tx(&self, tx: MutTx) -> Result<Option<TxData>, DBError> {
         log::trace!("COMMIT MUT TX");
 
+        if let Err(e) = self.check_size_quota(&tx) {
+            self.rollback_mut_tx(tx);
+            return Err(e.into());
+        }
+
         // TODO: Never returns `None` -- should it?
         let reducer_context = tx.ctx.reducer_context().cloned();
         let Some(tx_data) = self.inner.commit_mut_tx(tx)? else {
             return Ok(None);
         };
 
+        self.record_size_of_inserts(&tx_data);
         self.maybe_do_snapshot(&tx_data);
 
         if let Some(durability) = &self.durability {
             Self::do_durability(&**durability, reducer_context.as_ref(), &tx_data)
         }
+        self.record_committed_tx_offset(&tx_data);
 
         Ok(Some(tx_data))
     }
 
+    /// Commit `tx`, downgrading it to a read-only transaction.
+    ///
+    /// Unlike [`Self::commit_tx`], this does not refuse transactions
+    /// which would grow a database over its maximum size,
+    /// so callers should first call [`Self::check_size_quota`].
     #[tracing::instrument(level = "trace", skip_all)]
     pub fn commit_tx_downgrade(&self, tx: MutTx, workload: Workload) -> Result<Option<(TxData, Tx)>, DBError> {
         log::trace!("COMMIT MUT TX");
@@ -639,15 +1168,79 @@ impl RelationalDB {
             return Ok(None);
         };
 
+        self.record_size_of_inserts(&tx_data);
         self.maybe_do_snapshot(&tx_data);
 
         if let Some(durability) = &self.durability {
             Self::do_durability(&**durability, tx.ctx.reducer_context(), &tx_data)
         }
+        self.record_committed_tx_offset(&tx_data);
 
         Ok(Some((tx_data, tx)))
     }
 
+    /// Wake up the readers waiting for the transaction `tx_data` to be committed, if it consumed an offset.
+    fn record_committed_tx_offset(&self, tx_data: &TxData) {
+        if let Some(tx_offset) = tx_data.tx_offset() {
+            self.committed_tx_offset.send_replace(Some(tx_offset));
+        }
+    }
+
+    /// The offset of the most recently committed transaction,
+    /// or `None` if no transaction has been committed yet.
+    ///
+    /// Only transactions which are written to the commitlog consume an offset.
+    pub fn committed_tx_offset(&self) -> Option<TxOffset> {
+        *self.committed_tx_offset.borrow()
+    }
+
+    /// The offset of the most recent transaction known to be persisted by the durability layer,
+    /// or `None` if it isn't known or the database isn't persistent.
+    ///
+    /// The difference to [`Self::committed_tx_offset`] is the commitlog lag.
+    pub fn durable_tx_offset(&self) -> Option<TxOffset> {
+        self.durability
+            .as_ref()
+            .and_then(|durability| durability.durable_tx_offset())
+    }
+
+    /// Wait until all committed transactions have been persisted by the durability layer,
+    /// returning `false` if that takes longer than `timeout`.
+    ///
+    /// Returns `true` right away if the database isn't persistent.
+    pub async fn wait_durable(&self, timeout: Duration) -> bool {
+        let Some(durability) = &self.durability else {
+            return true;
+        };
+        let caught_up = async {
+            let mut interval = tokio::time::interval(Duration::from_millis(10));
+            loop {
+                interval.tick().await;
+                let committed = self.committed_tx_offset();
+                if committed.is_none() || durability.durable_tx_offset() >= committed {
+                    break;
+                }
+            }
+        };
+        tokio::time::timeout(timeout, caught_up).await.is_ok()
+    }
+
+    /// Wait until the transaction at `tx_offset` has been committed to this database,
+    /// so that reads made afterwards observe its effects.
+    ///
+    /// Gives up after [`TX_OFFSET_WAIT_TIMEOUT`].
+    pub async fn wait_for_tx_offset(&self, tx_offset: TxOffset) -> Result<(), TxOffsetTimeout> {
+        let mut committed = self.committed_tx_offset.subscribe();
+        let caught_up = committed.wait_for(|committed| committed.is_some_and(|committed| committed >= tx_offset));
+        if tokio::time::timeout(TX_OFFSET_WAIT_TIMEOUT, caught_up).await.is_ok() {
+            return Ok(());
+        }
+        Err(TxOffsetTimeout {
+            tx_offset,
+            committed: self.committed_tx_offset(),
+        })
+    }
+
     /// If `(tx_data, ctx)` should be appended to the commitlog, do so.
     ///
     /// Note that by this stage,
@@ -666,15 +1259,25 @@ impl RelationalDB {
         };
 
         if tx_data.tx_offset().is_some() {
+            // Tables with relaxed durability are left out of the commitlog.
             let inserts: Box<_> = tx_data
                 .inserts()
+                .filter(|(table_id, _)| !tx_data.is_relaxed(**table_id))
                 .map(|(table_id, rowdata)| Ops {
                     table_id: *table_id,
                     rowdata: rowdata.clone(),
                 })
                 .collect();
+            // Truncates are replayed after inserts,
+            // so tables which were also inserted into must have their deletes logged row by row.
+            let truncates: Box<_> = tx_data
+                .truncates()
+                .filter(|table_id| !tx_data.is_relaxed(*table_id))
+                .filter(|table_id| tx_data.inserts().all(|(id, _)| id != table_id))
+                .collect();
             let deletes: Box<_> = tx_data
                 .deletes()
+                .filter(|(table_id, _)| !tx_data.is_relaxed(**table_id) && !truncates.contains(table_id))
                 .map(|(table_id, rowdata)| Ops {
                     table_id: *table_id,
                     rowdata: rowdata.clone(),
@@ -689,7 +1292,7 @@ impl RelationalDB {
                 mutations: Some(Mutations {
                     inserts,
                     deletes,
-                    truncates: [].into(),
+                    truncates,
                 }),
             };
 
@@ -704,7 +1307,7 @@ impl RelationalDB {
         }
     }
 
-    /// Decide based on the `committed_state.next_tx_offset`
+    /// Decide based on the `committed_state.next_tx_offset` and the [`SnapshotPolicy`]
     /// whether to request that the [`SnapshotWorker`] in `self` capture a snapshot of the database.
     ///
     /// Actual snapshotting happens asynchronously in a Tokio worker.
@@ -723,7 +1326,19 @@ impl RelationalDB {
     fn maybe_do_snapshot(&self, tx_data: &TxData) {
         if let Some(snapshot_worker) = &self.snapshot_worker {
             if let Some(tx_offset) = tx_data.tx_offset() {
-                if tx_offset % SNAPSHOT_FREQUENCY == 0 {
+                let request = match *snapshot_worker.policy.read() {
+                    SnapshotPolicy::EveryTxs(n) => tx_offset % n.get() == 0,
+                    SnapshotPolicy::EveryMinutes(minutes) => {
+                        let mut last_requested = snapshot_worker.last_requested.lock();
+                        let due = last_requested.elapsed() >= Duration::from_secs(minutes.get().saturating_mul(60));
+                        if due {
+                            *last_requested = Instant::now();
+                        }
+                        due
+                    }
+                    SnapshotPolicy::OnDemand => false,
+                };
+                if request {
                     snapshot_worker.request_snapshot.unbounded_send(()).unwrap();
                 }
             }
@@ -832,6 +1447,15 @@ impl RelationalDB {
     pub(crate) fn alter_table_access(&self, tx: &mut MutTx, name: Box<str>, access: StAccess) -> Result<(), DBError> {
         self.inner.alter_table_access_mut_tx(tx, name, access)
     }
+
+    pub(crate) fn alter_table_durability(
+        &self,
+        tx: &mut MutTx,
+        name: Box<str>,
+        durability: StDurability,
+    ) -> Result<(), DBError> {
+        self.inner.alter_table_durability_mut_tx(tx, name, durability)
+    }
 }
 
 impl RelationalDB {
@@ -1148,6 +1772,14 @@ impl RelationalDB {
         self.inner.delete_mut_tx(tx, table_id, row_ids)
     }
 
+    /// Deletes every row of the table `table_id`, returning the number of rows deleted.
+    ///
+    /// Unlike [`Self::delete`], this does not read the rows individually,
+    /// and commits as a single truncation rather than as one delete per row.
+    pub fn truncate(&self, tx: &mut MutTx, table_id: TableId) -> Result<u64, DBError> {
+        self.inner.truncate_mut_tx(tx, table_id)
+    }
+
     pub fn delete_by_rel<R: IntoIterator<Item = ProductValue>>(
         &self,
         tx: &mut MutTx,
@@ -1159,18 +1791,24 @@ impl RelationalDB {
 
     /// Clear all rows from a table without dropping it.
     pub fn clear_table(&self, tx: &mut MutTx, table_id: TableId) -> Result<(), DBError> {
-        let relation = self
-            .iter_mut(tx, table_id)?
-            .map(|row_ref| row_ref.pointer())
-            .collect::<Vec<_>>();
-        self.delete(tx, table_id, relation);
-        Ok(())
+        self.truncate(tx, table_id).map(drop)
     }
 
     pub fn create_sequence(&self, tx: &mut MutTx, sequence_schema: SequenceSchema) -> Result<SequenceId, DBError> {
         self.inner.create_sequence_mut_tx(tx, sequence_schema)
     }
 
+    /// Returns the value which the sequence `seq_id` will generate next, without consuming it.
+    pub fn peek_sequence_value(&self, tx: &mut MutTx, seq_id: SequenceId) -> Result<i128, DBError> {
+        self.inner.peek_sequence_value_mut_tx(tx, seq_id)
+    }
+
+    /// Moves the sequence `seq_id` past `value`, so that it won't later generate `value`,
+    /// returning whether the sequence moved.
+    pub fn advance_sequence_past(&self, tx: &mut MutTx, seq_id: SequenceId, value: i128) -> Result<bool, DBError> {
+        self.inner.advance_sequence_past_mut_tx(tx, seq_id, value)
+    }
+
     ///Removes the [Sequence] from database instance
     pub fn drop_sequence(&self, tx: &mut MutTx, seq_id: SequenceId) -> Result<(), DBError> {
         self.inner.drop_sequence_mut_tx(tx, seq_id)
@@ -1223,8 +1861,14 @@ where
     // to spawn a timer task here which just prints the progress periodically
     // in case the history is finite but very long.
     let max_tx_offset = history.max_tx_offset();
+    let replayed_gauge = DB_METRICS.replay_tx_offset.with_label_values(&database_identity);
+    DB_METRICS
+        .replay_max_tx_offset
+        .with_label_values(&database_identity)
+        .set(max_tx_offset.map_or(-1, |offset| offset as i64));
     let mut last_logged_percentage = 0;
     let progress = |tx_offset: u64| {
+        replayed_gauge.set(tx_offset as i64);
         if let Some(max_tx_offset) = max_tx_offset {
             let percentage = f64::floor((tx_offset as f64 / max_tx_offset as f64) * 100.0) as i32;
             if percentage > last_logged_percentage && percentage % 10 == 0 {
@@ -1258,11 +1902,38 @@ where
 pub type LocalDurability = Arc<durability::Local<ProductValue>>;
 /// Initialize local durability with the default parameters.
 ///
+/// If `group_commit_window` is given, it overrides the default
+/// [`durability::local::Options::group_commit_window`].
+///
+/// If `archive` is given, sealed segments of the commitlog are moved to the archive
+/// in the background, and downloaded again when replaying the commitlog.
+///
 /// Also returned is a [`DiskSizeFn`] as required by [`RelationalDB::open`].
 ///
 /// Note that this operation can be expensive, as it needs to traverse a suffix
 /// of the commitlog.
-pub async fn local_durability(commitlog_dir: CommitLogDir) -> io::Result<(LocalDurability, DiskSizeFn)> {
+pub async fn local_durability(
+    commitlog_dir: CommitLogDir,
+    group_commit_window: Option<Duration>,
+    archive: Option<ArchiveOptions>,
+) -> io::Result<(LocalDurability, DiskSizeFn)> {
+    local_durability_with(
+        commitlog_dir,
+        LocalDurabilityOptions::default(),
+        group_commit_window,
+        archive,
+    )
+    .await
+}
+
+/// Like [`local_durability`], but starting from the parameters `defaults`,
+/// e.g. [`LocalDurabilityOptions::network_fs`], rather than the default ones.
+pub async fn local_durability_with(
+    commitlog_dir: CommitLogDir,
+    defaults: LocalDurabilityOptions,
+    group_commit_window: Option<Duration>,
+    archive: Option<ArchiveOptions>,
+) -> io::Result<(LocalDurability, DiskSizeFn)> {
     let rt = tokio::runtime::Handle::current();
     // TODO: Should this better be spawn_blocking?
     let local = spawn_rayon(move || {
@@ -1270,11 +1941,12 @@ pub async fn local_durability(commitlog_dir: CommitLogDir) -> io::Result<(LocalD
             commitlog_dir,
             rt,
             durability::local::Options {
+                group_commit_window: group_commit_window.unwrap_or(defaults.group_commit_window),
                 commitlog: commitlog::Options {
                     max_records_in_commit: 1.try_into().unwrap(),
-                    ..Default::default()
+                    ..defaults.commitlog
                 },
-                ..Default::default()
+                archive,
             },
         )
     })
@@ -1511,7 +2183,7 @@ pub mod tests_utils {
             root: &ReplicaDir,
             rt: tokio::runtime::Handle,
         ) -> Result<(RelationalDB, Arc<durability::Local<ProductValue>>), DBError> {
-            let (local, disk_size_fn) = rt.block_on(local_durability(root.commit_log()))?;
+            let (local, disk_size_fn) = rt.block_on(local_durability(root.commit_log(), None, None))?;
             let history = local.clone();
             let durability = local.clone() as Arc<dyn Durability<TxData = Txdata>>;
             let snapshot_repo = open_snapshot_repo(root.snapshots(), Identity::ZERO, 0)?;
@@ -1618,14 +2290,17 @@ mod tests {
         system_tables, StConstraintRow, StIndexRow, StSequenceRow, StTableRow, ST_CONSTRAINT_ID, ST_INDEX_ID,
         ST_SEQUENCE_ID, ST_TABLE_ID,
     };
-    use crate::db::relational_db::tests_utils::{insert, TestDB};
+    use crate::db::relational_db::tests_utils::{insert, TempReplicaDir, TestDB};
     use crate::error::IndexError;
     use crate::execution_context::ReducerContext;
     use anyhow::bail;
+    use arrow::array::AsArray;
+    use arrow::datatypes::Int32Type;
     use bytes::Bytes;
     use commitlog::payload::txdata;
     use commitlog::Commitlog;
     use durability::EmptyHistory;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
     use pretty_assertions::assert_eq;
     use spacetimedb_client_api_messages::timestamp::Timestamp;
     use spacetimedb_data_structures::map::IntMap;
@@ -1825,6 +2500,245 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_size_quota() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+
+        stdb.set_max_size(Some(1));
+        assert!(stdb.measure_size()? > 1);
+
+        // Inserting into a database over its quota fails, and is rolled back.
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&stdb, &mut tx, table_id, &product![2])?;
+        let err = stdb.commit_tx(tx).unwrap_err();
+        assert!(matches!(err, DBError::SizeQuota(SizeQuotaExceeded { max: 1, .. })));
+
+        // Deleting is still allowed.
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let ptrs = stdb
+            .iter_mut(&tx, table_id)?
+            .map(|row| row.pointer())
+            .collect::<Vec<_>>();
+        assert_eq!(stdb.delete(&mut tx, table_id, ptrs), 3);
+        stdb.commit_tx(tx)?;
+
+        stdb.set_max_size(None);
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&stdb, &mut tx, table_id, &product![2])?;
+        stdb.commit_tx(tx)?;
+
+        let tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(collect_sorted::<i32>(&stdb, &tx, table_id)?, vec![2]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_and_disk_quotas() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+
+        stdb.set_max_memory_and_disk(Some(100), Some(200));
+        assert_eq!(stdb.storage_quota_exceeded(), None);
+
+        for (memory, disk, kind, max) in [(101, 0, SizeQuotaKind::Memory, 100), (0, 201, SizeQuotaKind::Disk, 200)] {
+            stdb.record_storage_usage(memory, disk);
+            let exceeded = stdb.storage_quota_exceeded();
+            assert!(matches!(exceeded, Some(SizeQuotaExceeded { kind: k, max: m, .. }) if k == kind && m == max));
+
+            // Inserting into a database over its quota fails, and is rolled back.
+            let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+            insert(&stdb, &mut tx, table_id, &product![2])?;
+            let err = stdb.commit_tx(tx).unwrap_err();
+            assert!(matches!(err, DBError::SizeQuota(e) if Some(e) == exceeded));
+
+            // Deleting is still allowed.
+            let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+            let ptrs = stdb
+                .iter_mut(&tx, table_id)?
+                .filter(|row| row.read_col::<i32>(0).unwrap() == -1)
+                .map(|row| row.pointer())
+                .collect::<Vec<_>>();
+            assert_eq!(stdb.delete(&mut tx, table_id, ptrs), 1);
+            stdb.commit_tx(tx)?;
+
+            // Once back under the quota, inserting is allowed again.
+            stdb.record_storage_usage(0, 0);
+            let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+            insert(&stdb, &mut tx, table_id, &product![-1])?;
+            stdb.commit_tx(tx)?;
+        }
+
+        let tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(collect_sorted::<i32>(&stdb, &tx, table_id)?, vec![-1, 0, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_for_tx_offset() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+        let rt = stdb.runtime().expect("durable db has a runtime").clone();
+        assert_eq!(stdb.committed_tx_offset(), None);
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        let tx_offset = stdb
+            .commit_tx(tx)?
+            .and_then(|tx_data| tx_data.tx_offset())
+            .expect("tx should consume an offset");
+        assert_eq!(stdb.committed_tx_offset(), Some(tx_offset));
+        rt.block_on(stdb.wait_for_tx_offset(tx_offset))?;
+
+        // Waiting for a later transaction completes once it is committed.
+        let (waited, committed) = rt.block_on(async {
+            tokio::join!(stdb.wait_for_tx_offset(tx_offset + 1), async {
+                tokio::task::yield_now().await;
+                let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+                insert(&stdb, &mut tx, table_id, &product![2])?;
+                stdb.commit_tx(tx)
+            })
+        });
+        committed?;
+        waited?;
+        assert_eq!(stdb.committed_tx_offset(), Some(tx_offset + 1));
+
+        // The committed offset is restored when the commitlog is replayed.
+        let stdb = stdb.reopen()?;
+        assert_eq!(stdb.committed_tx_offset(), Some(tx_offset + 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fork() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+
+        let (db, durability, rt, source) = stdb.into_parts();
+        let (durability, rt) = (durability.unwrap(), rt.unwrap());
+        drop(db);
+        rt.block_on(Arc::into_inner(durability).unwrap().close())?;
+
+        let target = TempReplicaDir::new()?;
+        let target_identity = Identity::from_byte_array([1; 32]);
+        rt.block_on(RelationalDB::fork(
+            &source,
+            TestDB::DATABASE_IDENTITY,
+            0,
+            None,
+            &target,
+            target_identity,
+            1,
+            TestDB::OWNER,
+        ))?;
+
+        // The fork opens under its own identity, with the source's data.
+        let _rt = rt.enter();
+        let (local, disk_size_fn) = rt.block_on(local_durability(target.commit_log(), None, None))?;
+        let snapshot_repo = open_snapshot_repo(target.snapshots(), target_identity, 1)?;
+        let (db, connected_clients) = RelationalDB::open(
+            &target,
+            target_identity,
+            TestDB::OWNER,
+            local.clone(),
+            Some((local as Arc<dyn Durability<TxData = Txdata>>, disk_size_fn)),
+            Some(snapshot_repo),
+        )?;
+        assert!(connected_clients.is_empty());
+
+        let tx = db.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(collect_sorted::<i32>(&db, &tx, table_id)?, vec![-1, 0, 1]);
+        db.rollback_mut_tx(tx);
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_table_parquet() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+
+        let mut out = Vec::new();
+        let (_, rows) = stdb.export_table_parquet("MyTable", &mut out)?;
+        assert_eq!(rows, 3);
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(out))?.build()?;
+        let mut col = Vec::new();
+        for batch in reader {
+            let batch = batch?;
+            let array = batch.column(0).as_primitive::<Int32Type>();
+            col.extend(array.values().iter().copied());
+        }
+        col.sort();
+        assert_eq!(col, vec![-1, 0, 1]);
+
+        assert!(matches!(
+            stdb.export_table_parquet("NoSuchTable", io::sink()),
+            Err(ExportError::NoSuchTable(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_sqlite() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+        let snapshot = stdb.take_snapshot()?.expect("snapshot should be taken");
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&stdb, &mut tx, table_id, &product![5])?;
+        stdb.commit_tx(tx)?;
+
+        let (db, durability, rt, replica_dir) = stdb.into_parts();
+        let (durability, rt) = (durability.unwrap(), rt.unwrap());
+        drop(db);
+        rt.block_on(Arc::into_inner(durability).unwrap().close())?;
+
+        let out_dir = tempfile::tempdir()?;
+        let export_to = |up_to, name: &str| -> ResultTest<(export::SqliteExport, Vec<i32>)> {
+            let path = out_dir.path().join(name);
+            let export = export::export_sqlite(&replica_dir, TestDB::DATABASE_IDENTITY, 0, up_to, &path)?;
+            let conn = rusqlite::Connection::open(&path)?;
+            let mut stmt = conn.prepare("SELECT my_col FROM MyTable ORDER BY my_col")?;
+            let col = stmt.query_map([], |row| row.get(0))?.collect::<Result<_, _>>()?;
+            Ok((export, col))
+        };
+
+        // The commitlog is replayed on top of the latest snapshot.
+        let (export, col) = export_to(None, "latest.sqlite")?;
+        assert_eq!(export.snapshot_tx_offset, Some(snapshot));
+        assert_eq!(export.tx_offset, Some(snapshot + 1));
+        assert_eq!((export.tables, export.rows), (1, 4));
+        assert_eq!(col, vec![-1, 0, 1, 5]);
+
+        // Transactions after `up_to` are left out.
+        let (export, col) = export_to(Some(snapshot), "at_snapshot.sqlite")?;
+        assert_eq!(export.tx_offset, Some(snapshot));
+        assert_eq!(col, vec![-1, 0, 1]);
+
+        // An existing export is not overwritten.
+        assert!(export_to(None, "latest.sqlite").is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_filter_range_pre_commit() -> ResultTest<()> {
         let stdb = TestDB::durable()?;
@@ -1931,6 +2845,37 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_auto_inc_advance_past() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, table_auto_inc())?;
+        let seq_id = stdb.sequence_id_from_name(&tx, "MyTable_my_col_seq")?.unwrap();
+        assert_eq!(stdb.peek_sequence_value(&mut tx, seq_id)?, 1);
+
+        // Advancing past a value the sequence has already passed does nothing.
+        insert(&stdb, &mut tx, table_id, &product![0i64])?;
+        assert!(!stdb.advance_sequence_past(&mut tx, seq_id, 1)?);
+        assert_eq!(stdb.peek_sequence_value(&mut tx, seq_id)?, 2);
+
+        // Advancing past an explicitly inserted value, even beyond the allocation, avoids reusing it.
+        insert(&stdb, &mut tx, table_id, &product![10_000i64])?;
+        assert!(stdb.advance_sequence_past(&mut tx, seq_id, 10_000)?);
+        assert_eq!(stdb.peek_sequence_value(&mut tx, seq_id)?, 10_001);
+        insert(&stdb, &mut tx, table_id, &product![0i64])?;
+        stdb.commit_tx(tx)?;
+
+        // The new allocation survives a restart.
+        let stdb = stdb.reopen()?;
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&stdb, &mut tx, table_id, &product![0i64])?;
+        let rows = collect_from_sorted(&stdb, &tx, table_id, 0i64)?;
+        assert_eq!(rows[..3], [1, 10_000, 10_001]);
+        assert!(rows[3] > 10_001);
+        Ok(())
+    }
+
     #[test]
     fn test_auto_inc_reload() -> ResultTest<()> {
         let _ = env_logger::builder()
@@ -2002,6 +2947,252 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_truncate() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+
+        // Truncating deletes both committed rows and rows inserted by the same transaction.
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&stdb, &mut tx, table_id, &product![5])?;
+        assert_eq!(stdb.truncate(&mut tx, table_id)?, 4);
+        assert_eq!(collect_sorted::<i32>(&stdb, &tx, table_id)?, Vec::<i32>::new());
+        insert(&stdb, &mut tx, table_id, &product![7])?;
+        let tx_data = stdb.commit_tx(tx)?.expect("tx should commit");
+        assert!(tx_data.is_truncated(table_id));
+        let (_, deletes) = tx_data.deletes().find(|(id, _)| **id == table_id).unwrap();
+        assert_eq!(deletes.len(), 3);
+
+        let stdb = stdb.reopen()?;
+        let tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(collect_sorted::<i32>(&stdb, &tx, table_id)?, vec![7]);
+        stdb.rollback_mut_tx(tx);
+
+        // A truncation without inserts is logged as such and replayed.
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(stdb.truncate(&mut tx, table_id)?, 1);
+        stdb.commit_tx(tx)?;
+
+        let stdb = stdb.reopen()?;
+        let tx = stdb.begin_tx(Workload::ForTests);
+        assert_eq!(tx.table_row_count(table_id).unwrap(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ephemeral_table_is_empty_after_restart() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+
+        // Rows inserted while the table was durable are replayed, but then cleared.
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        stdb.alter_table_durability(&mut tx, "MyTable".into(), StDurability::Ephemeral)?;
+        stdb.commit_tx(tx)?;
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&stdb, &mut tx, table_id, &product![5])?;
+        let tx_data = stdb.commit_tx(tx)?.expect("tx should commit");
+        assert!(tx_data.is_relaxed(table_id));
+        assert_eq!(tx_data.tx_offset(), None);
+
+        let stdb = stdb.reopen()?;
+        let tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(collect_sorted::<i32>(&stdb, &tx, table_id)?, Vec::<i32>::new());
+        stdb.rollback_mut_tx(tx);
+
+        // Nor are the rows of the table captured by snapshots.
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&stdb, &mut tx, table_id, &product![7])?;
+        stdb.commit_tx(tx)?;
+        assert!(stdb.take_snapshot()?.is_some());
+
+        let stdb = stdb.reopen()?;
+        let tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(
+            stdb.schema_for_table_mut(&tx, table_id)?.durability,
+            StDurability::Ephemeral
+        );
+        assert_eq!(collect_sorted::<i32>(&stdb, &tx, table_id)?, Vec::<i32>::new());
+        stdb.rollback_mut_tx(tx);
+        Ok(())
+    }
+
+    #[test]
+    fn test_relaxed_table_is_restored_from_snapshot() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        stdb.alter_table_durability(&mut tx, "MyTable".into(), StDurability::Relaxed)?;
+        stdb.commit_tx(tx)?;
+
+        // Without a snapshot, the rows of the table are lost on restart.
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        let tx_data = stdb.commit_tx(tx)?.expect("tx should commit");
+        assert_eq!(tx_data.tx_offset(), None);
+
+        let stdb = stdb.reopen()?;
+        let tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(
+            stdb.schema_for_table_mut(&tx, table_id)?.durability,
+            StDurability::Relaxed
+        );
+        assert_eq!(collect_sorted::<i32>(&stdb, &tx, table_id)?, Vec::<i32>::new());
+        stdb.rollback_mut_tx(tx);
+
+        // The rows captured by the latest snapshot are restored,
+        // but the updates committed after it are lost.
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+        // The snapshot is taken at the offset of the latest durable transaction,
+        // but captures the rows of the relaxed table as they are now.
+        assert!(stdb.take_snapshot()?.is_some());
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&stdb, &mut tx, table_id, &product![7])?;
+        stdb.commit_tx(tx)?;
+
+        let stdb = stdb.reopen()?;
+        let tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(collect_sorted::<i32>(&stdb, &tx, table_id)?, vec![-1, 0, 1]);
+        stdb.rollback_mut_tx(tx);
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_policy() -> ResultTest<()> {
+        assert!(TestDB::in_memory()?.snapshot_status()?.is_none());
+
+        let stdb = TestDB::durable()?;
+        let status = stdb.snapshot_status()?.expect("durable database should keep snapshots");
+        assert_eq!(status.policy, SnapshotPolicy::default());
+        assert_eq!(status.latest_tx_offset, None);
+        assert!(status.last_captured.is_none());
+
+        stdb.set_snapshot_policy(SnapshotPolicy::OnDemand);
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+
+        let tx_offset = stdb.take_snapshot()?.expect("snapshot should be taken");
+        let status = stdb.snapshot_status()?.unwrap();
+        assert_eq!(status.policy, SnapshotPolicy::OnDemand);
+        assert_eq!(status.latest_tx_offset, Some(tx_offset));
+        assert_eq!(status.last_captured.map(|info| info.tx_offset), Some(tx_offset));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compress_snapshots() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+        let tx_offset = stdb.take_snapshot()?.expect("snapshot should be taken");
+
+        let mut snapshots = Vec::new();
+        let stats = stdb
+            .compress_snapshots(Duration::ZERO, |tx_offset, _| snapshots.push(tx_offset))?
+            .expect("durable database should keep snapshots");
+        assert_eq!(snapshots, [tx_offset]);
+        assert_eq!(stats.snapshots_compressed, 1);
+        assert!(stats.objects_compressed > 0);
+        assert!(stats.bytes_after < stats.bytes_before);
+
+        // The compressed snapshot is restored as before.
+        let stdb = stdb.reopen()?;
+        let tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        assert_eq!(collect_sorted::<i32>(&stdb, &tx, table_id)?, vec![-1, 0, 1]);
+        stdb.rollback_mut_tx(tx);
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_diff_archive() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, my_table(AlgebraicType::I32))?;
+        insert_three_i32s(&stdb, &mut tx, table_id)?;
+        stdb.commit_tx(tx)?;
+        let base = stdb.take_snapshot()?.expect("snapshot should be taken");
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&stdb, &mut tx, table_id, &product![5])?;
+        stdb.commit_tx(tx)?;
+        let target = stdb.take_snapshot()?.expect("snapshot should be taken");
+
+        let repo = &stdb.snapshot_worker.as_ref().unwrap().repo;
+        let diff = repo.diff(base, target)?;
+        assert!(diff.changed_tables.contains(&table_id));
+        assert!(!diff.added.is_empty());
+        let mut archive = Vec::new();
+        repo.write_diff_archive(&diff, &mut archive)?;
+
+        // A backup holding only the base snapshot can restore the target from the archive.
+        let backup_dir = TempReplicaDir::new()?;
+        let backup = open_snapshot_repo(backup_dir.snapshots(), TestDB::DATABASE_IDENTITY, 0)?;
+        repo.fork_snapshot(base, &backup)?;
+        backup.apply_diff_archive(&archive[..])?;
+        let restored = backup.read_snapshot(target)?;
+        let original = repo.read_snapshot(target)?;
+        assert_eq!(restored.tx_offset, target);
+        assert!(restored.tables.keys().eq(original.tables.keys()));
+        assert!(restored
+            .tables
+            .values()
+            .map(Vec::len)
+            .eq(original.tables.values().map(Vec::len)));
+
+        // Applying it again is refused.
+        assert!(backup.apply_diff_archive(&archive[..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_advise_indexes() -> ResultTest<()> {
+        let stdb = TestDB::durable()?;
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        let table_id = stdb.create_table(&mut tx, table_indexed(false))?;
+        for i in 0..4i64 {
+            insert(&stdb, &mut tx, table_id, &product![i, i])?;
+        }
+        for sql in [
+            "SELECT * FROM MyTable WHERE my_col = 1",
+            "SELECT * FROM MyTable WHERE other_col = 1",
+        ] {
+            tx.create_row_level_security(RowLevelSecuritySchema {
+                sql: sql.into(),
+                table_id,
+            })?;
+        }
+        stdb.commit_tx(tx)?;
+
+        // Only the filter on the unindexed column warrants an index.
+        let tx = stdb.begin_tx(Workload::ForTests);
+        assert_eq!(
+            stdb.advise_indexes(&tx)?,
+            vec![IndexAdvice {
+                table_name: "MyTable".into(),
+                column_name: "other_col".into(),
+                scans: 1,
+                rows_scanned: 4,
+                rows_saved: 3,
+            }]
+        );
+        Ok(())
+    }
+
     // Because we don't create `rls` when first creating the database, check we pass the bootstrap
     #[test]
     fn test_row_level_reopen() -> ResultTest<()> {
@@ -2581,4 +3772,77 @@ mod tests {
 
         stdb.release_tx(read_tx);
     }
+
+    /// Test that `st_connection` is replayed alongside `st_client`,
+    /// keeping the connection time of the clients which are still connected.
+    #[test]
+    fn replay_st_connection() {
+        use crate::db::datastore::system_tables::{StClientRow, StConnectionRow, ST_CLIENT_ID, ST_CONNECTION_ID};
+
+        let client = |n| StClientRow {
+            identity: Identity::ZERO.into(),
+            address: Address::from_u128(n).into(),
+        };
+        let connection = |n| StConnectionRow {
+            identity: Identity::ZERO.into(),
+            address: Address::from_u128(n).into(),
+            connected_at: 1_000 + n as u64,
+        };
+        let ops = |n| {
+            Box::new([
+                txdata::Ops {
+                    table_id: ST_CLIENT_ID,
+                    rowdata: Arc::new([client(n).into()]),
+                },
+                txdata::Ops {
+                    table_id: ST_CONNECTION_ID,
+                    rowdata: Arc::new([connection(n).into()]),
+                },
+            ])
+        };
+
+        let history = TestHistory::from_txes([
+            // TX 0: connect client 0
+            Txdata {
+                inputs: None,
+                outputs: None,
+                mutations: Some(txdata::Mutations {
+                    inserts: ops(0),
+                    deletes: Box::new([]),
+                    truncates: Box::new([]),
+                }),
+            },
+            // TX 1: connect client 1
+            Txdata {
+                inputs: None,
+                outputs: None,
+                mutations: Some(txdata::Mutations {
+                    inserts: ops(1),
+                    deletes: Box::new([]),
+                    truncates: Box::new([]),
+                }),
+            },
+            // TX 2: disconnect client 0
+            Txdata {
+                inputs: None,
+                outputs: None,
+                mutations: Some(txdata::Mutations {
+                    inserts: Box::new([]),
+                    deletes: ops(0),
+                    truncates: Box::new([]),
+                }),
+            },
+        ]);
+
+        let stdb = TestDB::in_memory_with_history(history, /* expected_num_clients: */ 1).unwrap();
+
+        let read_tx = stdb.begin_tx(Workload::ForTests);
+        let present_rows: Vec<StConnectionRow> = stdb
+            .iter(&read_tx, ST_CONNECTION_ID)
+            .unwrap()
+            .map(|row_ref| row_ref.try_into().unwrap())
+            .collect();
+        assert_eq!(present_rows, [connection(1)]);
+        stdb.release_tx(read_tx);
+    }
 }
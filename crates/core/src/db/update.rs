@@ -217,6 +217,10 @@ fn auto_migrate_database(
                 let table_def = plan.new.stored_in_table_def(table_name).unwrap();
                 stdb.alter_table_access(tx, table_name[..].into(), table_def.table_access.into())?;
             }
+            spacetimedb_schema::auto_migrate::AutoMigrateStep::ChangeDurability(table_name) => {
+                let table_def = plan.new.stored_in_table_def(table_name).unwrap();
+                stdb.alter_table_durability(tx, table_name[..].into(), table_def.durability.into())?;
+            }
             spacetimedb_schema::auto_migrate::AutoMigrateStep::AddSchedule(_) => {
                 anyhow::bail!("Adding schedules is not yet implemented");
             }
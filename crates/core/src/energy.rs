@@ -8,6 +8,7 @@ pub use spacetimedb_client_api_messages::energy::*;
 pub struct ReducerFingerprint<'a> {
     pub module_hash: Hash,
     pub module_identity: Identity,
+    pub database_identity: Identity,
     pub caller_identity: Identity,
     pub reducer_name: &'a str,
 }
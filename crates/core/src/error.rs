@@ -8,12 +8,14 @@ use hex::FromHexError;
 use spacetimedb_expr::errors::TypingError;
 use spacetimedb_sats::AlgebraicType;
 use spacetimedb_schema::error::ValidationErrors;
+use spacetimedb_schema::generated::GeneratedExprError;
 use spacetimedb_snapshot::SnapshotError;
 use spacetimedb_table::table::{self, ReadViaBsatnError, UniqueConstraintViolation};
 use spacetimedb_table::{bflatn_to, read_column};
 use thiserror::Error;
 
 use crate::client::ClientActorId;
+use crate::db::blob::BlobError;
 use crate::db::datastore::system_tables::SystemTable;
 use crate::host::scheduler::ScheduleError;
 use spacetimedb_lib::buffer::DecodeError;
@@ -74,6 +76,23 @@ pub enum TableError {
     Duplicate(#[from] table::DuplicateError),
     #[error(transparent)]
     ReadColTypeError(#[from] read_column::TypeError),
+    #[error("Generated column `{table_id}.{col_pos}` has an invalid expression: {error}")]
+    InvalidGeneratedColumn {
+        table_id: TableId,
+        col_pos: ColId,
+        error: GeneratedExprError,
+    },
+    #[error("Table `{0}` does not keep its history, so it can't be read as of a transaction")]
+    NoHistory(Box<str>),
+    #[error("Table `{table}` can only be read as of transactions {oldest} to {latest}, not {tx_offset}")]
+    HistoryNotRetained {
+        table: Box<str>,
+        tx_offset: u64,
+        oldest: u64,
+        latest: u64,
+    },
+    #[error("The database schema changed since the savepoint, so the transaction can't be rolled back to it")]
+    SchemaChangedSinceSavepoint,
 }
 
 #[derive(Error, Debug, PartialEq, Eq)]
@@ -174,6 +193,30 @@ pub enum SequenceError {
     MultiColumnAutoInc(TableId, ColList),
 }
 
+/// A transaction refused because it would grow a database which is over one of its size quotas.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("database is over its {kind} limit of {max} bytes ({size} bytes used)")]
+pub struct SizeQuotaExceeded {
+    /// Which of the database's sizes is over its limit.
+    pub kind: SizeQuotaKind,
+    /// The maximum size of the database, in bytes.
+    pub max: u64,
+    /// The estimated size of the database, in bytes.
+    pub size: u64,
+}
+
+/// The sizes of a database which may be limited, see [`SizeQuotaExceeded`].
+#[derive(strum::Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum SizeQuotaKind {
+    /// The size of its in-memory data.
+    Memory,
+    /// The size of its commitlog and module logs.
+    Disk,
+    /// The total size of its tables, commitlog, and snapshots.
+    Size,
+}
+
 #[derive(Error, Debug, EnumAsInner)]
 pub enum DBError {
     #[error("LibError: {0}")]
@@ -230,6 +273,8 @@ pub enum DBError {
     Other(#[from] anyhow::Error),
     #[error(transparent)]
     TypeError(#[from] TypingError),
+    #[error(transparent)]
+    SizeQuota(#[from] SizeQuotaExceeded),
 }
 
 impl From<bflatn_to::Error> for DBError {
@@ -360,6 +405,10 @@ pub enum NodesError {
     TableNotFound,
     #[error("index with provided name or id doesn't exist")]
     IndexNotFound,
+    #[error("column has no sequence")]
+    SequenceNotFound,
+    #[error("no asset at path `{0}`")]
+    AssetNotFound(Box<str>),
     #[error("index was not unique")]
     IndexNotUnique,
     #[error("row was not found in index")]
@@ -380,6 +429,17 @@ pub enum NodesError {
     BadIndexType(u8),
     #[error("Failed to scheduled timer: {0}")]
     ScheduleError(#[source] ScheduleError),
+    #[error(transparent)]
+    Blob(BlobError),
+}
+
+impl From<BlobError> for NodesError {
+    fn from(e: BlobError) -> Self {
+        match e {
+            BlobError::Db(e) => e.into(),
+            e => Self::Blob(e),
+        }
+    }
 }
 
 impl From<DBError> for NodesError {
@@ -387,7 +447,9 @@ impl From<DBError> for NodesError {
         match e {
             DBError::Table(TableError::Exist(name)) => Self::AlreadyExists(name),
             DBError::Table(TableError::System(name)) => Self::SystemName(name),
-            DBError::Table(TableError::IdNotFound(_, _) | TableError::NotFound(_)) => Self::TableNotFound,
+            DBError::Table(TableError::IdNotFound(_, _) | TableError::IdNotFoundState(_) | TableError::NotFound(_)) => {
+                Self::TableNotFound
+            }
             DBError::Table(TableError::ColumnNotFound(_)) => Self::BadColumn,
             DBError::Index(IndexError::NotFound(_)) => Self::IndexNotFound,
             DBError::Index(IndexError::Decode(e)) => Self::DecodeRow(e),
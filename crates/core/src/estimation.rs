@@ -3,6 +3,7 @@ use spacetimedb_lib::query::Delta;
 use spacetimedb_physical_plan::plan::{HashJoin, IxJoin, IxScan, PhysicalPlan, Sarg};
 use spacetimedb_primitives::{ColList, TableId};
 use spacetimedb_vm::expr::{Query, QueryExpr, SourceExpr};
+use std::fmt;
 
 /// The estimated number of rows that a query plan will return.
 pub fn num_rows(tx: &Tx, expr: &QueryExpr) -> u64 {
@@ -44,6 +45,68 @@ pub fn estimate_rows_scanned(tx: &Tx, plan: &PhysicalPlan) -> u64 {
     }
 }
 
+/// The estimated cost of evaluating a subscription query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubscriptionCost {
+    /// The number of rows read by table scans, i.e. without the help of an index.
+    pub unindexed_rows: u64,
+    /// The number of rows produced by joins,
+    /// which grows with the number of rows each row on one side matches on the other.
+    pub join_fan_out: u64,
+}
+
+impl SubscriptionCost {
+    /// The combined cost, as compared against the `subscription_cost_*` system variables.
+    pub fn total(&self) -> u64 {
+        self.unindexed_rows.saturating_add(self.join_fan_out)
+    }
+
+    fn saturating_add(self, other: Self) -> Self {
+        Self {
+            unindexed_rows: self.unindexed_rows.saturating_add(other.unindexed_rows),
+            join_fan_out: self.join_fan_out.saturating_add(other.join_fan_out),
+        }
+    }
+}
+
+impl fmt::Display for SubscriptionCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} rows scanned without an index, {} rows produced by joins",
+            self.unindexed_rows, self.join_fan_out
+        )
+    }
+}
+
+/// Use cardinality estimates to predict the cost of a subscription query,
+/// counting the rows it scans without an index, and the rows its joins produce.
+pub fn estimate_subscription_cost(tx: &Tx, plan: &PhysicalPlan) -> SubscriptionCost {
+    match plan {
+        PhysicalPlan::TableScan(..) => SubscriptionCost {
+            unindexed_rows: row_estimate(tx, plan),
+            join_fan_out: 0,
+        },
+        PhysicalPlan::IxScan(..) => SubscriptionCost::default(),
+        PhysicalPlan::Filter(input, _) => estimate_subscription_cost(tx, input),
+        PhysicalPlan::NLJoin(lhs, rhs) | PhysicalPlan::HashJoin(HashJoin { lhs, rhs, .. }, _) => {
+            estimate_subscription_cost(tx, lhs)
+                .saturating_add(estimate_subscription_cost(tx, rhs))
+                .saturating_add(SubscriptionCost {
+                    unindexed_rows: 0,
+                    join_fan_out: row_estimate(tx, plan),
+                })
+        }
+        // The rhs of an index join is only probed via its index.
+        PhysicalPlan::IxJoin(IxJoin { lhs, .. }, _) => {
+            estimate_subscription_cost(tx, lhs).saturating_add(SubscriptionCost {
+                unindexed_rows: 0,
+                join_fan_out: row_estimate(tx, plan),
+            })
+        }
+    }
+}
+
 /// Estimate the cardinality of a physical plan
 pub fn row_estimate(tx: &Tx, plan: &PhysicalPlan) -> u64 {
     match plan {
@@ -156,7 +219,7 @@ mod tests {
     use spacetimedb_sats::product;
     use spacetimedb_vm::expr::CrudExpr;
 
-    use super::row_estimate;
+    use super::{estimate_subscription_cost, row_estimate, SubscriptionCost};
 
     fn in_mem_db() -> TestDB {
         TestDB::in_memory().expect("failed to make test db")
@@ -179,6 +242,14 @@ mod tests {
         row_estimate(&tx, &plan)
     }
 
+    fn subscription_cost_for(db: &RelationalDB, sql: &str) -> SubscriptionCost {
+        let auth = AuthCtx::for_testing();
+        let tx = db.begin_tx(Workload::ForTests);
+        let tx = SchemaViewer::new(&tx, &auth);
+        let plan = SubscribePlan::compile(sql, &tx).expect("failed to compile sql");
+        estimate_subscription_cost(&tx, &plan)
+    }
+
     const NUM_T_ROWS: u64 = 10;
     const NDV_T: u64 = 5;
     const NUM_S_ROWS: u64 = 2;
@@ -298,4 +369,63 @@ mod tests {
         assert_eq!(est, num_rows_for(&db, sql));
         assert_eq!(est, new_row_estimate(&db, sql));
     }
+
+    /// Scanning a table without an index costs one for each of its rows.
+    #[test]
+    fn subscription_cost_table_scan() {
+        let db = in_mem_db();
+        create_table_t(&db, false);
+        let cost = subscription_cost_for(&db, "select * from T where a = 0");
+        assert_eq!(
+            cost,
+            SubscriptionCost {
+                unindexed_rows: NUM_T_ROWS,
+                join_fan_out: 0,
+            }
+        );
+    }
+
+    /// Index lookups don't count towards the cost of a subscription.
+    #[test]
+    fn subscription_cost_index_lookup() {
+        let db = in_mem_db();
+        create_table_t(&db, true);
+        let cost = subscription_cost_for(&db, "select * from T where a = 0");
+        assert_eq!(cost, SubscriptionCost::default());
+    }
+
+    /// Joins cost the rows they produce, in addition to the cost of their inputs.
+    #[test]
+    fn subscription_cost_join() {
+        let db = in_mem_db();
+        create_table_t(&db, false);
+        create_table_s(&db, false);
+        let sql = "select T.* from T join S on T.a = S.a where S.c = 0";
+        let cost = subscription_cost_for(&db, sql);
+        assert_eq!(
+            cost,
+            SubscriptionCost {
+                unindexed_rows: NUM_T_ROWS + NUM_S_ROWS,
+                join_fan_out: NUM_T_ROWS * NUM_S_ROWS,
+            }
+        );
+        assert_eq!(cost.total(), NUM_T_ROWS + NUM_S_ROWS + NUM_T_ROWS * NUM_S_ROWS);
+    }
+
+    /// An index join only produces the rows matching each probe.
+    #[test]
+    fn subscription_cost_index_join() {
+        let db = in_mem_db();
+        create_table_t(&db, true);
+        create_table_s(&db, true);
+        let sql = "select T.* from T join S on T.a = S.a where S.c = 0";
+        let cost = subscription_cost_for(&db, sql);
+        assert_eq!(
+            cost,
+            SubscriptionCost {
+                unindexed_rows: 0,
+                join_fan_out: NUM_T_ROWS / NDV_T * NUM_S_ROWS / NDV_S,
+            }
+        );
+    }
 }
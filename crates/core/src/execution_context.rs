@@ -102,6 +102,7 @@ pub enum Workload {
     Subscribe,
     Unsubscribe,
     Update,
+    Import,
     Internal,
 }
 
@@ -116,6 +117,7 @@ pub enum WorkloadType {
     Subscribe,
     Unsubscribe,
     Update,
+    Import,
     Internal,
 }
 
@@ -129,6 +131,7 @@ impl From<Workload> for WorkloadType {
             Workload::Subscribe => Self::Subscribe,
             Workload::Unsubscribe => Self::Unsubscribe,
             Workload::Update => Self::Update,
+            Workload::Import => Self::Import,
             Workload::Internal => Self::Internal,
         }
     }
@@ -161,6 +164,7 @@ impl ExecutionContext {
             Workload::Subscribe => Self::subscribe(database_identity),
             Workload::Unsubscribe => Self::unsubscribe(database_identity),
             Workload::Update => Self::incremental_update(database_identity),
+            Workload::Import => Self::import(database_identity),
         }
     }
 
@@ -195,6 +199,11 @@ impl ExecutionContext {
         Self::new(database, Some(ctx), WorkloadType::Update)
     }
 
+    /// Returns an [ExecutionContext] for a bulk import of rows into a table.
+    pub fn import(database_identity: Identity) -> Self {
+        Self::new(database_identity, None, WorkloadType::Import)
+    }
+
     /// Returns an [ExecutionContext] for an internal database operation.
     pub fn internal(database_identity: Identity) -> Self {
         Self::new(database_identity, None, WorkloadType::Internal)
@@ -0,0 +1,50 @@
+//! The asset bundle of a module, which is published inside its program.
+
+use bytes::Bytes;
+use spacetimedb_data_structures::map::HashMap;
+use spacetimedb_lib::assets::AssetBundle;
+use spacetimedb_lib::{hash_bytes, Hash};
+use spacetimedb_sats::bsatn::DecodeError;
+
+/// The read-only files bundled with a module, by path.
+///
+/// See [`spacetimedb_lib::assets`] for how they are published.
+#[derive(Default)]
+pub struct Assets {
+    files: HashMap<Box<str>, Asset>,
+}
+
+/// A file in a module's asset bundle.
+pub struct Asset {
+    /// The contents of the file.
+    pub data: Bytes,
+    /// The hash of `data`, which changes whenever the file does.
+    pub hash: Hash,
+}
+
+impl Assets {
+    /// Loads the asset bundle from the `program` of a module, if it has one.
+    pub fn from_program(program: &[u8]) -> Result<Self, DecodeError> {
+        let bundle = AssetBundle::from_program(program)?.unwrap_or_default();
+        let files = bundle
+            .files
+            .into_iter()
+            .map(|file| {
+                let hash = hash_bytes(&file.data);
+                (
+                    file.path.into(),
+                    Asset {
+                        data: file.data.into(),
+                        hash,
+                    },
+                )
+            })
+            .collect();
+        Ok(Self { files })
+    }
+
+    /// Returns the file at `path`, if there is one.
+    pub fn get(&self, path: &str) -> Option<&Asset> {
+        self.files.get(path)
+    }
+}
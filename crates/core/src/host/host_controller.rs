@@ -2,30 +2,37 @@ use super::module_host::{EventStatus, ModuleHost, ModuleInfo, NoSuchModule};
 use super::scheduler::SchedulerStarter;
 use super::wasmtime::WasmtimeRuntime;
 use super::{Scheduler, UpdateDatabaseResult};
+use crate::config::QuotaConfig;
 use crate::database_logger::DatabaseLogger;
 use crate::db;
 use crate::db::datastore::traits::Program;
 use crate::db::db_metrics::DB_METRICS;
-use crate::db::relational_db::{self, DiskSizeFn, RelationalDB, Txdata};
+use crate::db::relational_db::{
+    self, CompressionStats, DiskSizeFn, RelationalDB, SnapshotPolicy, SnapshotStatus, Txdata,
+};
 use crate::energy::{EnergyMonitor, EnergyQuanta};
+use crate::execution_context::Workload;
 use crate::messages::control_db::{Database, HostType};
 use crate::module_host_context::ModuleCreationContext;
 use crate::replica_context::ReplicaContext;
+use crate::subscription::coalesce::CoalesceWindow;
 use crate::subscription::module_subscription_actor::ModuleSubscriptions;
 use crate::util::spawn_rayon;
 use anyhow::{anyhow, ensure, Context};
 use async_trait::async_trait;
-use durability::{Durability, EmptyHistory};
+use durability::{local::ArchiveOptions, Durability, EmptyHistory};
 use log::{info, trace, warn};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use serde::Serialize;
 use spacetimedb_data_structures::map::IntMap;
 use spacetimedb_durability as durability;
-use spacetimedb_lib::hash_bytes;
+use spacetimedb_lib::{hash_bytes, Identity};
 use spacetimedb_paths::server::{ReplicaDir, ServerDataDir};
 use spacetimedb_sats::hash::Hash;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::future::Future;
+use std::io;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{watch, OwnedRwLockReadGuard, OwnedRwLockWriteGuard, RwLock as AsyncRwLock};
@@ -41,11 +48,59 @@ type HostCell = Arc<AsyncRwLock<Option<Host>>>;
 /// The registry of all running hosts.
 type Hosts = Arc<Mutex<IntMap<u64, HostCell>>>;
 
+/// The hosts which are being launched, and those whose last launch failed.
+type Launches = Arc<Mutex<IntMap<u64, LaunchState>>>;
+
+#[derive(Clone, Debug)]
+enum LaunchState {
+    Launching,
+    Failed(String),
+}
+
 pub type ExternalDurability = (Arc<dyn Durability<TxData = Txdata>>, DiskSizeFn);
 
 #[async_trait]
 pub trait DurabilityProvider: Send + Sync + 'static {
-    async fn durability(&self, replica_id: u64) -> anyhow::Result<ExternalDurability>;
+    /// Provide the durability for the replica `replica_id`,
+    /// syncing it to disk at most `group_commit_window` after each commit, if given,
+    /// and moving sealed commitlog segments to `archive`, if given.
+    async fn durability(
+        &self,
+        replica_id: u64,
+        group_commit_window: Option<Duration>,
+        archive: Option<ArchiveOptions>,
+    ) -> anyhow::Result<ExternalDurability>;
+}
+
+/// The [`DurabilityProvider`]s available to the databases of a [`HostController`],
+/// keyed by the name of the backend each implements.
+///
+/// Each database persists its transactions with the backend selected by
+/// [`DurabilityConfig::backend`], and fails to launch if no such backend is registered.
+/// Note that databases are always replayed from the commitlog in their replica directory,
+/// so backends which persist transactions elsewhere must also keep it up to date.
+///
+/// [`DurabilityConfig::backend`]: crate::config::DurabilityConfig::backend
+#[derive(Clone, Default)]
+pub struct DurabilityBackends {
+    providers: BTreeMap<Box<str>, Arc<dyn DurabilityProvider>>,
+}
+
+impl DurabilityBackends {
+    /// Register `provider` as the backend `name`,
+    /// replacing any provider previously registered under that name.
+    pub fn with(mut self, name: impl Into<Box<str>>, provider: Arc<dyn DurabilityProvider>) -> Self {
+        self.providers.insert(name.into(), provider);
+        self
+    }
+
+    /// The provider registered as the backend `name`.
+    fn get(&self, name: &str) -> anyhow::Result<&dyn DurabilityProvider> {
+        self.providers.get(name).map(|provider| &**provider).ok_or_else(|| {
+            let known = self.providers.keys().map(|name| &**name).collect::<Vec<_>>();
+            anyhow!("unknown durability backend `{name}`, expected one of {known:?}")
+        })
+    }
 }
 
 #[async_trait]
@@ -72,17 +127,22 @@ pub struct HostController {
     /// Map of all hosts managed by this controller,
     /// keyed by database instance id.
     hosts: Hosts,
+    /// The hosts which are being launched or failed to launch,
+    /// keyed by database instance id.
+    launches: Launches,
     /// The root directory for database data.
     pub data_dir: Arc<ServerDataDir>,
     /// The default configuration to use for databases created by this
     /// controller.
-    default_config: db::Config,
+    ///
+    /// May be replaced while the server runs, see [`Self::reload_config`].
+    default_config: Arc<RwLock<db::Config>>,
     /// The [`ProgramStorage`] to query when instantiating a module.
     program_storage: ProgramStorage,
     /// The [`EnergyMonitor`] used by this controller.
     energy_monitor: Arc<dyn EnergyMonitor>,
     /// Provides implementations of [`Durability`] for each replica.
-    durability: Arc<dyn DurabilityProvider>,
+    durability: DurabilityBackends,
     /// The runtimes for running our modules.
     runtimes: Arc<HostRuntimes>,
 }
@@ -129,11 +189,79 @@ impl fmt::Display for DescribedEntityType {
     }
 }
 
+/// The status of a replica managed by a [`HostController`], see [`HostController::replica_status`].
+#[derive(Clone, Debug)]
+pub struct ReplicaStatus {
+    pub module: ModuleStatus,
+    /// The state of the replica's database, if its module host is running.
+    pub database: Option<DatabaseStatus>,
+}
+
+#[derive(Clone, Debug)]
+pub enum ModuleStatus {
+    /// The module host has not been launched since the server started, or was shut down.
+    NotLaunched,
+    /// The module host is being launched, and its database restored.
+    Launching { replay: ReplayProgress },
+    /// The module host is running.
+    Running,
+    /// The module host is running, and is being updated or shut down.
+    Updating,
+    /// The last attempt to launch the module host failed with `error`.
+    Failed { error: String },
+}
+
+/// How far the commitlog of a database has been replayed while its module host is being launched.
+#[derive(Clone, Copy, Debug)]
+pub struct ReplayProgress {
+    /// The offset of the transaction replayed last.
+    pub tx_offset: u64,
+    /// The offset of the last transaction to replay, if known.
+    pub max_tx_offset: Option<u64>,
+}
+
+impl ReplayProgress {
+    fn of(database_identity: &Identity) -> Self {
+        let tx_offset = DB_METRICS.replay_tx_offset.with_label_values(database_identity).get();
+        let max_tx_offset = DB_METRICS
+            .replay_max_tx_offset
+            .with_label_values(database_identity)
+            .get();
+        Self {
+            tx_offset: tx_offset as u64,
+            max_tx_offset: u64::try_from(max_tx_offset).ok(),
+        }
+    }
+
+    fn reset(database_identity: &Identity) {
+        DB_METRICS.replay_tx_offset.with_label_values(database_identity).set(0);
+        DB_METRICS
+            .replay_max_tx_offset
+            .with_label_values(database_identity)
+            .set(-1);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DatabaseStatus {
+    /// The offset of the most recently committed transaction.
+    pub committed_tx_offset: Option<durability::TxOffset>,
+    /// The offset of the most recent transaction persisted to the commitlog.
+    pub durable_tx_offset: Option<durability::TxOffset>,
+    /// The snapshot policy and latest snapshots, or `None` if the database doesn't keep snapshots.
+    pub snapshots: Option<SnapshotStatus>,
+}
+
 #[derive(Clone, Debug)]
 pub struct ReducerCallResult {
     pub outcome: ReducerOutcome,
     pub energy_used: EnergyQuanta,
     pub execution_duration: Duration,
+    /// The offset of the transaction committed by the reducer, if it committed any changes.
+    ///
+    /// Reads which must observe the reducer's changes can wait for this offset
+    /// via [`RelationalDB::wait_for_tx_offset`].
+    pub tx_offset: Option<u64>,
 }
 
 impl ReducerCallResult {
@@ -157,6 +285,7 @@ pub enum ReducerOutcome {
     Committed,
     Failed(String),
     BudgetExceeded,
+    TimedOut,
 }
 
 impl ReducerOutcome {
@@ -165,6 +294,7 @@ impl ReducerOutcome {
             Self::Committed => Ok(()),
             Self::Failed(e) => Err(anyhow::anyhow!(e)),
             Self::BudgetExceeded => Err(anyhow::anyhow!("reducer ran out of energy")),
+            Self::TimedOut => Err(anyhow::anyhow!("reducer exceeded its maximum duration")),
         }
     }
 
@@ -178,7 +308,9 @@ impl From<&EventStatus> for ReducerOutcome {
         match &status {
             EventStatus::Committed(_) => ReducerOutcome::Committed,
             EventStatus::Failed(e) => ReducerOutcome::Failed(e.clone()),
+            EventStatus::FailedWithValue(e) => ReducerOutcome::Failed(e.message.clone()),
             EventStatus::OutOfEnergy => ReducerOutcome::BudgetExceeded,
+            EventStatus::TimedOut => ReducerOutcome::TimedOut,
         }
     }
 }
@@ -189,11 +321,12 @@ impl HostController {
         default_config: db::Config,
         program_storage: ProgramStorage,
         energy_monitor: Arc<impl EnergyMonitor>,
-        durability: Arc<dyn DurabilityProvider>,
+        durability: DurabilityBackends,
     ) -> Self {
         Self {
             hosts: <_>::default(),
-            default_config,
+            launches: <_>::default(),
+            default_config: Arc::new(RwLock::new(default_config)),
             program_storage,
             energy_monitor,
             durability,
@@ -202,6 +335,29 @@ impl HostController {
         }
     }
 
+    /// Update the default configuration of this controller with `update`.
+    ///
+    /// The settings which can change while a database runs,
+    /// i.e. its quotas, snapshot policy and page compression,
+    /// are applied to the running databases right away,
+    /// while the others take effect for databases launched afterwards.
+    pub async fn reload_config(&self, update: impl FnOnce(&mut db::Config)) {
+        let config = {
+            let mut default_config = self.default_config.write();
+            update(&mut default_config);
+            default_config.clone()
+        };
+        let hosts = self.hosts.lock().values().cloned().collect::<Vec<_>>();
+        for host in hosts {
+            if let Some(host) = &*host.read().await {
+                let replica_ctx = &host.replica_ctx;
+                let database_identity = &replica_ctx.database.database_identity;
+                apply_runtime_config(&replica_ctx.relational_db, database_identity, &config);
+                *replica_ctx.quotas.write() = config.quotas.for_database(database_identity);
+            }
+        }
+    }
+
     /// Replace the [`ProgramStorage`] used by this controller.
     pub fn set_program_storage(&mut self, ps: ProgramStorage) {
         self.program_storage = ps;
@@ -457,6 +613,36 @@ impl HostController {
         Ok(())
     }
 
+    /// Shut down all module hosts, for the server to exit.
+    ///
+    /// Each module finishes the reducer calls in progress, refusing any still waiting to run.
+    /// Then the transactions committed to its database are given up to `flush_timeout`
+    /// to be persisted to the commitlog, after which, if `snapshot` is set,
+    /// a snapshot of the database is captured so that it launches faster next time.
+    pub async fn shutdown(&self, flush_timeout: Duration, snapshot: bool) {
+        let hosts = std::mem::take(&mut *self.hosts.lock());
+        let shutdowns = hosts.into_iter().map(|(replica_id, lock)| async move {
+            let Some(host) = lock.write_owned().await.take() else {
+                return;
+            };
+            let module = host.module.borrow().clone();
+            module.exit().await;
+            let db = host.replica_ctx.relational_db.clone();
+            let database_identity = host.replica_ctx.database.database_identity;
+            if !db.wait_durable(flush_timeout).await {
+                warn!("[{database_identity}] commitlog of replica {replica_id} not flushed after {flush_timeout:?}");
+            }
+            if snapshot {
+                match spawn_rayon(move || db.take_snapshot()).await {
+                    Ok(Some(tx_offset)) => info!("[{database_identity}] captured snapshot at {tx_offset}"),
+                    Ok(None) => {}
+                    Err(e) => warn!("[{database_identity}] failed to capture snapshot: {e}"),
+                }
+            }
+        });
+        futures::future::join_all(shutdowns).await;
+    }
+
     /// Get the [`ModuleHost`] identified by `replica_id` or return an error
     /// if it is not registered with the controller.
     ///
@@ -487,6 +673,178 @@ impl HostController {
             .ok_or(NoSuchModule)
     }
 
+    /// Move the clock of the module host `replica_id` forward by `by`,
+    /// returning once the scheduled reducers which come due in the meantime have run.
+    ///
+    /// See [`Scheduler::advance_time`].
+    pub async fn advance_time(&self, replica_id: u64, by: Duration) -> Result<(), NoSuchModule> {
+        trace!("advance time of module host {} by {:?}", replica_id, by);
+        let scheduler = {
+            let guard = self.acquire_read_lock(replica_id).await;
+            let Host { scheduler, .. } = guard.as_ref().ok_or(NoSuchModule)?;
+            scheduler.clone()
+        };
+        scheduler.advance_time(by).await;
+        Ok(())
+    }
+
+    /// Capture a snapshot of the database of the module host `replica_id` right away,
+    /// returning its TX offset.
+    ///
+    /// See [`RelationalDB::take_snapshot`].
+    pub async fn take_snapshot(&self, replica_id: u64) -> anyhow::Result<Option<durability::TxOffset>> {
+        let db = self.relational_db(replica_id).await?;
+        Ok(spawn_rayon(move || db.take_snapshot()).await?)
+    }
+
+    /// The TX offset of the latest snapshot of the database of the module host `replica_id`.
+    pub async fn latest_snapshot(&self, replica_id: u64) -> anyhow::Result<Option<durability::TxOffset>> {
+        let db = self.relational_db(replica_id).await?;
+        Ok(db.latest_snapshot()?)
+    }
+
+    /// The snapshot policy and latest snapshots of the database of the module host `replica_id`,
+    /// or `None` if it doesn't keep snapshots.
+    ///
+    /// See [`RelationalDB::snapshot_status`].
+    pub async fn snapshot_status(&self, replica_id: u64) -> anyhow::Result<Option<SnapshotStatus>> {
+        let db = self.relational_db(replica_id).await?;
+        Ok(db.snapshot_status()?)
+    }
+
+    /// Change when the database of the module host `replica_id` captures snapshots by itself.
+    ///
+    /// The change lasts until the module host is restarted,
+    /// after which the policy from the server config applies again.
+    pub async fn set_snapshot_policy(&self, replica_id: u64, policy: SnapshotPolicy) -> anyhow::Result<()> {
+        let db = self.relational_db(replica_id).await?;
+        db.set_snapshot_policy(policy);
+        Ok(())
+    }
+
+    /// Compress the snapshots of the database of the module host `replica_id`
+    /// captured more than `older_than` ago, calling `progress` after each snapshot.
+    ///
+    /// See [`RelationalDB::compress_snapshots`].
+    pub async fn compress_snapshots(
+        &self,
+        replica_id: u64,
+        older_than: Duration,
+        progress: impl FnMut(durability::TxOffset, &CompressionStats) + Send + 'static,
+    ) -> anyhow::Result<Option<CompressionStats>> {
+        let db = self.relational_db(replica_id).await?;
+        Ok(tokio::task::spawn_blocking(move || db.compress_snapshots(older_than, progress)).await??)
+    }
+
+    /// Write the rows of the table `table_name` of the database of the module host `replica_id`
+    /// to `out` in Parquet format, returning the number of rows written.
+    ///
+    /// See [`RelationalDB::export_table_parquet`].
+    pub async fn export_table_parquet(
+        &self,
+        replica_id: u64,
+        table_name: String,
+        out: impl io::Write + Send + 'static,
+    ) -> anyhow::Result<u64> {
+        let db = self.relational_db(replica_id).await?;
+        let (_, rows) = tokio::task::spawn_blocking(move || db.export_table_parquet(&table_name, out)).await??;
+        Ok(rows)
+    }
+
+    /// Shut down the module host `replica_id`, arranging for its database to be
+    /// restored from the latest snapshot at or before `tx_offset` the next time it is launched.
+    ///
+    /// Snapshots newer than `tx_offset` are invalidated,
+    /// and the transactions after the snapshot are replayed from the commitlog,
+    /// so no committed data is lost.
+    pub async fn restore_snapshot(&self, replica_id: u64, tx_offset: durability::TxOffset) -> anyhow::Result<()> {
+        let db = self.relational_db(replica_id).await?;
+        db.invalidate_newer_snapshots(tx_offset)?;
+        // Release our handle before exiting, so the database can be reopened.
+        drop(db);
+        self.exit_module_host(replica_id).await
+    }
+
+    /// Prepare the replica `target_replica_id` of the new database `target`
+    /// as a fork of the replica `source_replica_id` of the database `source`,
+    /// so that launching it restores the latest state of `source`.
+    ///
+    /// See [`RelationalDB::fork`].
+    pub async fn fork_database(
+        &self,
+        source: &Database,
+        source_replica_id: u64,
+        target: &Database,
+        target_replica_id: u64,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            matches!(self.default_config.read().storage, db::Storage::Disk),
+            "cannot fork a database which is only stored in memory"
+        );
+        let source_archive = self
+            .default_config
+            .read()
+            .durability
+            .archive(&source.database_identity, source_replica_id)?
+            .map(|archive| archive.archive);
+        RelationalDB::fork(
+            &self.data_dir.replica(source_replica_id),
+            source.database_identity,
+            source_replica_id,
+            source_archive,
+            &self.data_dir.replica(target_replica_id),
+            target.database_identity,
+            target_replica_id,
+            target.owner_identity,
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// The status of the replica `replica_id` of the database `database_identity`.
+    ///
+    /// Unlike most other methods, this neither launches the module host nor waits for it to be launched,
+    /// so it can report how far a long recovery has progressed.
+    pub async fn replica_status(&self, database_identity: &Identity, replica_id: u64) -> anyhow::Result<ReplicaStatus> {
+        let not_running = |module| ReplicaStatus { module, database: None };
+        match self.launches.lock().get(&replica_id).cloned() {
+            Some(LaunchState::Launching) => {
+                let replay = ReplayProgress::of(database_identity);
+                return Ok(not_running(ModuleStatus::Launching { replay }));
+            }
+            Some(LaunchState::Failed(error)) => return Ok(not_running(ModuleStatus::Failed { error })),
+            None => {}
+        }
+
+        let Some(lock) = self.hosts.lock().get(&replica_id).cloned() else {
+            return Ok(not_running(ModuleStatus::NotLaunched));
+        };
+        let db = match lock.try_read() {
+            Ok(guard) => match &*guard {
+                Some(host) => host.replica_ctx.relational_db.clone(),
+                None => return Ok(not_running(ModuleStatus::NotLaunched)),
+            },
+            Err(_) => return Ok(not_running(ModuleStatus::Updating)),
+        };
+        let database = DatabaseStatus {
+            committed_tx_offset: db.committed_tx_offset(),
+            durable_tx_offset: db.durable_tx_offset(),
+            snapshots: db.snapshot_status()?,
+        };
+        Ok(ReplicaStatus {
+            module: ModuleStatus::Running,
+            database: Some(database),
+        })
+    }
+
+    async fn relational_db(&self, replica_id: u64) -> Result<Arc<RelationalDB>, NoSuchModule> {
+        let guard = self.acquire_read_lock(replica_id).await;
+        guard
+            .as_ref()
+            .map(|Host { replica_ctx, .. }| replica_ctx.relational_db.clone())
+            .ok_or(NoSuchModule)
+    }
+
     /// `true` if the module host `replica_id` is currently registered with
     /// the controller.
     pub async fn has_module_host(&self, replica_id: u64) -> bool {
@@ -515,8 +873,23 @@ impl HostController {
         lock.read_owned().await
     }
 
+    /// Launch the host `replica_id`, recording its progress for [`Self::replica_status`].
     async fn try_init_host(&self, database: Database, replica_id: u64) -> anyhow::Result<Host> {
-        Host::try_init(self, database, replica_id).await
+        ReplayProgress::reset(&database.database_identity);
+        self.launches.lock().insert(replica_id, LaunchState::Launching);
+        // Forget about the launch if it is cancelled, e.g. because the client gave up waiting.
+        let launches = scopeguard::guard(self.launches.clone(), |launches| {
+            launches.lock().remove(&replica_id);
+        });
+
+        let result = Host::try_init(self, database, replica_id).await;
+        if let Err(e) = &result {
+            let launches = scopeguard::ScopeGuard::into_inner(launches);
+            launches
+                .lock()
+                .insert(replica_id, LaunchState::Failed(format!("{e:#}")));
+        }
+        result
     }
 }
 
@@ -530,6 +903,7 @@ async fn make_replica_ctx(
     database: Database,
     replica_id: u64,
     relational_db: Arc<RelationalDB>,
+    quotas: QuotaConfig,
 ) -> anyhow::Result<ReplicaContext> {
     let logger = tokio::task::block_in_place(move || Arc::new(DatabaseLogger::open_today(path.module_logs())));
     let subscriptions = ModuleSubscriptions::new(relational_db.clone(), database.owner_identity);
@@ -540,9 +914,45 @@ async fn make_replica_ctx(
         logger,
         relational_db,
         subscriptions,
+        quotas: Arc::new(RwLock::new(quotas)),
+        quota_usage: <_>::default(),
+        reducer_replays: <_>::default(),
+        clock: <_>::default(),
     })
 }
 
+/// Apply the settings of `config` which can change while the database `database_identity` runs
+/// to its `relational_db`, other than its [`ReplicaContext::quotas`].
+fn apply_runtime_config(relational_db: &RelationalDB, database_identity: &Identity, config: &db::Config) {
+    let quotas = config.quotas.for_database(database_identity);
+    relational_db.set_compress_idle_tables_after(config.memory.compress_idle_tables_after_txs);
+    relational_db.set_max_size(quotas.max_size_bytes);
+    relational_db.set_max_memory_and_disk(quotas.max_memory_bytes, quotas.max_disk_bytes);
+    relational_db.set_snapshot_policy(config.durability.snapshot_policy(database_identity));
+}
+
+/// Coalesce the updates sent to subscribers of the tables of `module_host` which declared it.
+fn coalesce_subscriptions(replica_ctx: &ReplicaContext, module_host: &ModuleHost) -> anyhow::Result<()> {
+    let db = &replica_ctx.relational_db;
+    let info = module_host.info();
+    let windows = db.with_read_only(Workload::Internal, |tx| -> anyhow::Result<_> {
+        let mut windows = IntMap::default();
+        for table in info.module_def.tables() {
+            let (Some(coalesce), Some(primary_key)) = (table.coalesce, table.primary_key) else {
+                continue;
+            };
+            let Some(table_id) = db.table_id_from_name(tx, &table.name)? else {
+                continue;
+            };
+            let window = Duration::from_millis(coalesce.window_ms);
+            windows.insert(table_id, CoalesceWindow { window, primary_key });
+        }
+        Ok(windows)
+    })?;
+    replica_ctx.subscriptions.set_coalesce_windows(windows);
+    Ok(())
+}
+
 /// Initialize a module host for the given program.
 /// The passed replica_ctx may not be configured for this version of the program's database schema yet.
 async fn make_module_host(
@@ -564,9 +974,15 @@ async fn make_module_host(
                     energy_monitor,
                 };
                 let start = Instant::now();
-                let actor = runtimes.wasmtime.make_actor(mcc)?;
-                trace!("wasmtime::make_actor blocked for {:?}", start.elapsed());
-                ModuleHost::new(actor, unregister)
+                if WasmtimeRuntime::is_component(&program.bytes) {
+                    let actor = runtimes.wasmtime.make_component_actor(mcc)?;
+                    trace!("wasmtime::make_component_actor blocked for {:?}", start.elapsed());
+                    ModuleHost::new(actor, unregister)
+                } else {
+                    let actor = runtimes.wasmtime.make_actor(mcc)?;
+                    trace!("wasmtime::make_actor blocked for {:?}", start.elapsed());
+                    ModuleHost::new(actor, unregister)
+                }
             }
         };
         Ok((program, module_host))
@@ -599,14 +1015,15 @@ async fn launch_module(
     energy_monitor: Arc<dyn EnergyMonitor>,
     replica_dir: ReplicaDir,
     runtimes: Arc<HostRuntimes>,
+    quotas: QuotaConfig,
 ) -> anyhow::Result<(Program, LaunchedModule)> {
     let address = database.database_identity;
     let host_type = database.host_type;
 
-    let replica_ctx = make_replica_ctx(replica_dir, database, replica_id, relational_db)
+    let replica_ctx = make_replica_ctx(replica_dir, database, replica_id, relational_db, quotas)
         .await
         .map(Arc::new)?;
-    let (scheduler, scheduler_starter) = Scheduler::open(replica_ctx.relational_db.clone());
+    let (scheduler, scheduler_starter) = Scheduler::open(replica_ctx.relational_db.clone(), replica_ctx.clock.clone());
     let (program, module_host) = make_module_host(
         runtimes.clone(),
         host_type,
@@ -684,6 +1101,10 @@ struct Host {
     /// The task collects metrics from the `replica_ctx`, and so stays alive as long
     /// as the `replica_ctx` is live. The task is aborted when [`Host`] is dropped.
     metrics_task: AbortHandle,
+    /// Handle to the task refreshing the views clients are subscribed to, started via [`view_refresher`].
+    ///
+    /// The task is aborted when [`Host`] is dropped.
+    view_refresh_task: AbortHandle,
 }
 
 impl Host {
@@ -696,13 +1117,14 @@ impl Host {
     async fn try_init(host_controller: &HostController, database: Database, replica_id: u64) -> anyhow::Result<Self> {
         let HostController {
             data_dir,
-            default_config: config,
+            default_config,
             program_storage,
             energy_monitor,
             runtimes,
             durability,
             ..
         } = host_controller;
+        let config = default_config.read().clone();
         let on_panic = host_controller.unregister_fn(replica_id);
         let replica_dir = data_dir.replica(replica_id);
 
@@ -718,8 +1140,14 @@ impl Host {
             db::Storage::Disk => {
                 let snapshot_repo =
                     relational_db::open_snapshot_repo(replica_dir.snapshots(), database.database_identity, replica_id)?;
-                let (history, _) = relational_db::local_durability(replica_dir.commit_log()).await?;
-                let durability = durability.durability(replica_id).await?;
+                let archive = config.durability.archive(&database.database_identity, replica_id)?;
+                let (history, _) =
+                    relational_db::local_durability(replica_dir.commit_log(), None, archive.clone()).await?;
+                let group_commit_window = config.durability.group_commit_window(&database.database_identity);
+                let durability = durability
+                    .get(config.durability.backend(&database.database_identity))?
+                    .durability(replica_id, group_commit_window, archive)
+                    .await?;
 
                 RelationalDB::open(
                     &replica_dir,
@@ -731,6 +1159,8 @@ impl Host {
                 )?
             }
         };
+        apply_runtime_config(&db, &database.database_identity, &config);
+        let quotas = config.quotas.for_database(&database.database_identity);
         let (program, program_needs_init) = match db.program()? {
             // Launch module with program from existing database.
             Some(program) => (program, false),
@@ -748,6 +1178,7 @@ impl Host {
             energy_monitor.clone(),
             replica_dir,
             runtimes.clone(),
+            quotas,
         )
         .await?;
 
@@ -770,7 +1201,7 @@ impl Host {
         // Disconnect dangling clients.
         for (identity, address) in connected_clients {
             module_host
-                .call_identity_connected_disconnected(identity, address, false)
+                .call_identity_connected_disconnected(identity, address, false, None, None)
                 .await
                 .with_context(|| {
                     format!(
@@ -781,13 +1212,17 @@ impl Host {
         }
 
         scheduler_starter.start(&module_host)?;
+        coalesce_subscriptions(&replica_ctx, &module_host)?;
         let metrics_task = tokio::spawn(storage_monitor(replica_ctx.clone(), energy_monitor.clone())).abort_handle();
+        let module = watch::Sender::new(module_host);
+        let view_refresh_task = tokio::spawn(view_refresher(module.subscribe())).abort_handle();
 
         Ok(Host {
-            module: watch::Sender::new(module_host),
+            module,
             replica_ctx,
             scheduler,
             metrics_task,
+            view_refresh_task,
         })
     }
 
@@ -812,7 +1247,8 @@ impl Host {
         on_panic: impl Fn() + Send + Sync + 'static,
     ) -> anyhow::Result<UpdateDatabaseResult> {
         let replica_ctx = &self.replica_ctx;
-        let (scheduler, scheduler_starter) = Scheduler::open(self.replica_ctx.relational_db.clone());
+        let (scheduler, scheduler_starter) =
+            Scheduler::open(self.replica_ctx.relational_db.clone(), self.replica_ctx.clock.clone());
 
         let (program, module) = make_module_host(
             runtimes,
@@ -835,6 +1271,7 @@ impl Host {
         if update_result.was_successful() {
             self.scheduler = scheduler;
             scheduler_starter.start(&module)?;
+            coalesce_subscriptions(replica_ctx, &module)?;
             let old_module = self.module.send_replace(module);
             old_module.exit().await;
         }
@@ -850,26 +1287,59 @@ impl Host {
 impl Drop for Host {
     fn drop(&mut self) {
         self.metrics_task.abort();
+        self.view_refresh_task.abort();
+    }
+}
+
+/// Refresh the views clients are subscribed to as transactions modify the tables they read,
+/// using the current module of the host.
+async fn view_refresher(mut module: watch::Receiver<ModuleHost>) {
+    loop {
+        let views = module.borrow().subscriptions().views().clone();
+        let modified = views.modified_tables().await;
+        loop {
+            let module_host = module.borrow_and_update().clone();
+            match module_host.refresh_views(modified.clone()).await {
+                Ok(()) => break,
+                // The module exited because it is being replaced,
+                // so refresh the views once the new module is in place.
+                Err(NoSuchModule) => {
+                    if module.changed().await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
     }
 }
 
 const STORAGE_METERING_INTERVAL: Duration = Duration::from_secs(5);
 
-/// Periodically collect the disk usage of `replica_ctx` and update metrics as well as
-/// the `energy_monitor` accordingly.
+/// Periodically collect the disk usage of `replica_ctx` and update metrics,
+/// quota usage, and the `energy_monitor` accordingly.
+///
+/// Also measures the total size of the database,
+/// which is checked against its size quota as it commits transactions,
+/// and compresses the pages of its idle tables, if configured to.
 async fn storage_monitor(replica_ctx: Arc<ReplicaContext>, energy_monitor: Arc<dyn EnergyMonitor>) {
     let mut interval = tokio::time::interval(STORAGE_METERING_INTERVAL);
     // We don't care about happening precisely every 5 seconds - it just matters
     // that the time between ticks is accurate.
     interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
 
-    let mut prev_disk_usage = tokio::task::block_in_place(|| replica_ctx.total_disk_usage());
+    let mut prev_disk_usage = tokio::task::block_in_place(|| {
+        measure_db_size(&replica_ctx);
+        replica_ctx.total_disk_usage()
+    });
     let mut prev_tick = interval.tick().await;
     loop {
         let tick = interval.tick().await;
         let dt = tick - prev_tick;
-        let (disk_usage, mem_usage) =
-            tokio::task::block_in_place(|| (replica_ctx.total_disk_usage(), replica_ctx.mem_usage()));
+        let (disk_usage, mem_usage) = tokio::task::block_in_place(|| {
+            replica_ctx.relational_db.compress_idle_tables();
+            measure_db_size(&replica_ctx);
+            (replica_ctx.total_disk_usage(), replica_ctx.mem_usage())
+        });
         if let Some(num_bytes) = disk_usage.durability {
             DB_METRICS
                 .message_log_size
@@ -883,9 +1353,152 @@ async fn storage_monitor(replica_ctx: Arc<ReplicaContext>, energy_monitor: Arc<d
                 .set(num_bytes as i64);
         }
         let disk_usage = disk_usage.or(prev_disk_usage);
+        replica_ctx.update_quota_usage(mem_usage as u64, disk_usage.sum());
         energy_monitor.record_disk_usage(&replica_ctx.database, replica_ctx.replica_id, disk_usage.sum(), dt);
         energy_monitor.record_memory_usage(&replica_ctx.database, replica_ctx.replica_id, mem_usage as u64, dt);
         prev_disk_usage = disk_usage;
         prev_tick = tick;
     }
 }
+
+/// Measure the total size of the database of `replica_ctx` for its size quota.
+///
+/// If the measurement fails, the previous estimate is kept.
+fn measure_db_size(replica_ctx: &ReplicaContext) {
+    if let Err(e) = replica_ctx.relational_db.measure_size() {
+        log::warn!(
+            "failed to measure the size of database {}: {e}",
+            replica_ctx.database_identity
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A backend which fails to provide any durability, naming itself in the error.
+    struct Unavailable(&'static str);
+
+    #[async_trait]
+    impl DurabilityProvider for Unavailable {
+        async fn durability(
+            &self,
+            _replica_id: u64,
+            _group_commit_window: Option<Duration>,
+            _archive: Option<ArchiveOptions>,
+        ) -> anyhow::Result<ExternalDurability> {
+            anyhow::bail!("{} is unavailable", self.0)
+        }
+    }
+
+    #[tokio::test]
+    async fn durability_backends_are_selected_by_name() {
+        let backends = DurabilityBackends::default()
+            .with("local", Arc::new(Unavailable("replaced")))
+            .with("remote", Arc::new(Unavailable("remote")))
+            .with("local", Arc::new(Unavailable("local")));
+        for name in ["local", "remote"] {
+            let provider = backends.get(name).unwrap();
+            let Err(e) = provider.durability(0, None, None).await else {
+                panic!("backend `{name}` provided durability");
+            };
+            assert_eq!(e.to_string(), format!("{name} is unavailable"));
+        }
+
+        let Err(e) = backends.get("nfs") else {
+            panic!("found the unregistered backend `nfs`");
+        };
+        assert_eq!(
+            e.to_string(),
+            r#"unknown durability backend `nfs`, expected one of ["local", "remote"]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn replica_status_follows_launches() -> anyhow::Result<()> {
+        use crate::config::{DurabilityConfig, MemoryConfig};
+        use crate::energy::NullEnergyMonitor;
+        use futures::FutureExt;
+        use spacetimedb_paths::FromPathUnchecked;
+        use tokio::sync::Notify;
+
+        /// A program storage which finds no programs, but only once notified.
+        struct NotFoundWhenNotified(Arc<Notify>);
+
+        #[async_trait]
+        impl ExternalStorage for NotFoundWhenNotified {
+            async fn lookup(&self, _program_hash: Hash) -> anyhow::Result<Option<Box<[u8]>>> {
+                self.0.notified().await;
+                Ok(None)
+            }
+        }
+
+        async fn module_status(
+            controller: &HostController,
+            database_identity: &Identity,
+        ) -> anyhow::Result<ModuleStatus> {
+            Ok(controller.replica_status(database_identity, 1).await?.module)
+        }
+
+        let tmp = tempfile::tempdir()?;
+        let config = db::Config {
+            storage: db::Storage::Memory,
+            quotas: QuotaConfig::UNLIMITED,
+            durability: DurabilityConfig::DEFAULT,
+            memory: MemoryConfig::DEFAULT,
+        };
+        let program_lookup = Arc::new(Notify::new());
+        let controller = HostController::new(
+            Arc::new(ServerDataDir::from_path_unchecked(tmp.path())),
+            config,
+            Arc::new(NotFoundWhenNotified(program_lookup.clone())),
+            Arc::new(NullEnergyMonitor),
+            DurabilityBackends::default(),
+        );
+        let database_identity = Identity::from_byte_array([0x31; 32]);
+        let database = Database {
+            id: 1,
+            database_identity,
+            owner_identity: Identity::ZERO,
+            host_type: HostType::Wasm,
+            initial_program: Hash::ZERO,
+        };
+        let status = || module_status(&controller, &database_identity);
+
+        assert!(matches!(status().await?, ModuleStatus::NotLaunched));
+
+        // A launch which is cancelled before it completes is forgotten.
+        let mut launch = Box::pin(controller.try_init_host(database.clone(), 1));
+        assert!(launch.as_mut().now_or_never().is_none());
+        let ModuleStatus::Launching { replay } = status().await? else {
+            panic!("replica is not reported as launching");
+        };
+        assert_eq!((replay.tx_offset, replay.max_tx_offset), (0, None));
+        drop(launch);
+        assert!(matches!(status().await?, ModuleStatus::NotLaunched));
+
+        // While launching, report how far the commitlog has been replayed.
+        let mut launch = Box::pin(controller.try_init_host(database, 1));
+        assert!(launch.as_mut().now_or_never().is_none());
+        DB_METRICS.replay_tx_offset.with_label_values(&database_identity).set(5);
+        DB_METRICS
+            .replay_max_tx_offset
+            .with_label_values(&database_identity)
+            .set(10);
+        let ModuleStatus::Launching { replay } = status().await? else {
+            panic!("replica is not reported as launching");
+        };
+        assert_eq!((replay.tx_offset, replay.max_tx_offset), (5, Some(10)));
+
+        // A launch which fails reports its error until the next launch.
+        program_lookup.notify_one();
+        assert!(launch.await.is_err());
+        let ModuleStatus::Failed { error } = status().await? else {
+            panic!("replica is not reported as failed");
+        };
+        assert_eq!(error, format!("program {} not found", Hash::ZERO));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use super::{ReducerArgs, ReducerCallError, ReducerCallResult};
+use parking_lot::Mutex;
+use spacetimedb_data_structures::map::HashMap;
+use spacetimedb_lib::identity::RequestId;
+use spacetimedb_lib::{Address, Identity};
+use spacetimedb_sats::hash::{hash_bytes, Hash};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// What identifies retries of the same reducer call by a caller.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IdempotencyKey {
+    /// The `Idempotency-Key` header of a call over HTTP.
+    Header(Box<str>),
+    /// The request id of a call over a WebSocket, which is only unique to the client address.
+    Request(Address, RequestId),
+}
+
+/// What identifies a reducer call, so that a retry can be told apart from another call reusing the same key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallFingerprint(Hash);
+
+impl CallFingerprint {
+    /// The fingerprint of a call to `reducer` with `args`.
+    pub fn new(reducer: &str, args: &ReducerArgs) -> Self {
+        let (tag, args): (u8, &[u8]) = match args {
+            ReducerArgs::Json(json) => (0, json.as_bytes()),
+            ReducerArgs::Bsatn(bsatn) => (1, &bsatn[..]),
+            ReducerArgs::Nullary => (2, &[]),
+        };
+        // Reducer names never contain a NUL byte, so this separates the name from the arguments.
+        Self(hash_bytes([reducer.as_bytes(), &[0, tag], args].concat()))
+    }
+}
+
+/// A call kept in a [`ReplayWindow`].
+struct Call {
+    fingerprint: CallFingerprint,
+    result: Arc<OnceCell<ReducerCallResult>>,
+}
+
+/// The calls kept in a [`ReplayWindow`], along with the order in which they were made.
+#[derive(Default)]
+struct Calls {
+    by_key: HashMap<(Identity, IdempotencyKey), Call>,
+    /// The keys of `by_key`, from the oldest call to the newest.
+    made_at: VecDeque<(Instant, (Identity, IdempotencyKey))>,
+}
+
+impl Calls {
+    /// Forgets the calls made more than [`ReplayWindow::DURATION`] before `now`,
+    /// and the oldest calls beyond [`ReplayWindow::MAX_CALLS`],
+    /// looking at no more calls than it forgets.
+    fn expire(&mut self, now: Instant) {
+        while let Some((at, _)) = self.made_at.front() {
+            if now.duration_since(*at) < ReplayWindow::DURATION && self.made_at.len() < ReplayWindow::MAX_CALLS {
+                break;
+            }
+            let (_, key) = self.made_at.pop_front().unwrap();
+            self.by_key.remove(&key);
+        }
+    }
+}
+
+/// The results of the reducer calls a database received with an [`IdempotencyKey`] recently,
+/// so that retrying a call, e.g. after a timeout, doesn't run it twice.
+#[derive(Default)]
+pub struct ReplayWindow {
+    calls: Mutex<Calls>,
+}
+
+impl ReplayWindow {
+    /// How long the result of a call is kept for its retries.
+    pub const DURATION: Duration = Duration::from_secs(60 * 60);
+
+    /// How many calls are kept at most, the oldest being forgotten first.
+    pub const MAX_CALLS: usize = 100_000;
+
+    /// Run the reducer `call` made by `caller` with `key`,
+    /// unless such a call was made within [`Self::DURATION`],
+    /// in which case its result is returned instead, after waiting for it if it is still running.
+    ///
+    /// Returns whether the result was replayed along with it.
+    /// A call which failed before running the reducer, e.g. for being over quota, isn't kept,
+    /// so that retrying it runs the reducer.
+    ///
+    /// `call` is spawned onto its own task,
+    /// so that its result is kept for retries even if the caller stops waiting for it.
+    ///
+    /// Fails with [`ReducerCallError::IdempotencyKeyReused`]
+    /// if the call made with `key` had a different `fingerprint`.
+    pub async fn call_once(
+        &self,
+        caller: Identity,
+        key: IdempotencyKey,
+        fingerprint: CallFingerprint,
+        call: impl Future<Output = Result<ReducerCallResult, ReducerCallError>> + Send + 'static,
+    ) -> Result<(ReducerCallResult, bool), ReducerCallError> {
+        let result = {
+            let now = Instant::now();
+            let mut calls = self.calls.lock();
+            calls.expire(now);
+            let key = (caller, key);
+            match calls.by_key.get(&key) {
+                Some(call) if call.fingerprint != fingerprint => return Err(ReducerCallError::IdempotencyKeyReused),
+                Some(call) => call.result.clone(),
+                None => {
+                    let result = Arc::<OnceCell<_>>::default();
+                    let call = Call {
+                        fingerprint,
+                        result: result.clone(),
+                    };
+                    calls.by_key.insert(key.clone(), call);
+                    calls.made_at.push_back((now, key));
+                    result
+                }
+            }
+        };
+
+        tokio::spawn(async move {
+            let mut replayed = true;
+            let res = result
+                .get_or_try_init(|| {
+                    replayed = false;
+                    call
+                })
+                .await?;
+            Ok((res.clone(), replayed))
+        })
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::energy::EnergyQuanta;
+    use crate::host::ReducerOutcome;
+
+    fn committed(tx_offset: u64) -> ReducerCallResult {
+        ReducerCallResult {
+            outcome: ReducerOutcome::Committed,
+            energy_used: EnergyQuanta::ZERO,
+            execution_duration: Duration::ZERO,
+            tx_offset: Some(tx_offset),
+        }
+    }
+
+    fn purchase(item: &str) -> CallFingerprint {
+        CallFingerprint::new("purchase", &ReducerArgs::Json(format!("[{item:?}]").into()))
+    }
+
+    #[tokio::test]
+    async fn test_retries_are_replayed() -> Result<(), ReducerCallError> {
+        let window = ReplayWindow::default();
+        let caller = Identity::from_hashing_bytes("caller");
+        let key = || IdempotencyKey::Header("purchase-1".into());
+        let call = purchase("sword");
+
+        let (result, replayed) = window
+            .call_once(caller, key(), call, async { Ok(committed(1)) })
+            .await?;
+        assert_eq!((result.tx_offset, replayed), (Some(1), false));
+
+        // The retry gets the result of the first call.
+        let (result, replayed) = window
+            .call_once(caller, key(), call, async { Ok(committed(2)) })
+            .await?;
+        assert_eq!((result.tx_offset, replayed), (Some(1), true));
+
+        // Another caller, or another key, runs the reducer.
+        let other = Identity::from_hashing_bytes("other");
+        let (result, replayed) = window.call_once(other, key(), call, async { Ok(committed(3)) }).await?;
+        assert_eq!((result.tx_offset, replayed), (Some(3), false));
+        let key = IdempotencyKey::Request(Address::ZERO, 0);
+        let (result, replayed) = window.call_once(caller, key, call, async { Ok(committed(4)) }).await?;
+        assert_eq!((result.tx_offset, replayed), (Some(4), false));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_calls_are_not_kept() -> Result<(), ReducerCallError> {
+        let window = ReplayWindow::default();
+        let caller = Identity::from_hashing_bytes("caller");
+        let key = || IdempotencyKey::Header("purchase-1".into());
+        let call = purchase("sword");
+
+        let res = window
+            .call_once(caller, key(), call, async { Err(ReducerCallError::NoSuchReducer) })
+            .await;
+        assert!(matches!(res, Err(ReducerCallError::NoSuchReducer)));
+
+        let (result, replayed) = window
+            .call_once(caller, key(), call, async { Ok(committed(1)) })
+            .await?;
+        assert_eq!((result.tx_offset, replayed), (Some(1), false));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reused_keys_are_rejected() -> Result<(), ReducerCallError> {
+        let window = ReplayWindow::default();
+        let caller = Identity::from_hashing_bytes("caller");
+        let key = || IdempotencyKey::Header("purchase-1".into());
+
+        window
+            .call_once(caller, key(), purchase("sword"), async { Ok(committed(1)) })
+            .await?;
+
+        // The same key with other arguments, or for another reducer, is a mistake of the caller.
+        let res = window
+            .call_once(caller, key(), purchase("shield"), async { Ok(committed(2)) })
+            .await;
+        assert!(matches!(res, Err(ReducerCallError::IdempotencyKeyReused)));
+        let sell = CallFingerprint::new("sell", &ReducerArgs::Json(r#"["sword"]"#.into()));
+        let res = window.call_once(caller, key(), sell, async { Ok(committed(3)) }).await;
+        assert!(matches!(res, Err(ReducerCallError::IdempotencyKeyReused)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_result_is_kept_when_caller_stops_waiting() -> Result<(), ReducerCallError> {
+        let window = ReplayWindow::default();
+        let caller = Identity::from_hashing_bytes("caller");
+        let key = || IdempotencyKey::Header("purchase-1".into());
+        let call = purchase("sword");
+
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let (finish_tx, finish_rx) = tokio::sync::oneshot::channel::<()>();
+        let first = window.call_once(caller, key(), call, async move {
+            started_tx.send(()).unwrap();
+            finish_rx.await.unwrap();
+            Ok(committed(1))
+        });
+
+        // The caller gives up once the reducer has started, e.g. because its connection dropped.
+        tokio::select! {
+            _ = first => unreachable!("the reducer hasn't finished"),
+            started = started_rx => started.unwrap(),
+        }
+        finish_tx.send(()).unwrap();
+
+        // The retry gets the result of the reducer started by the first call.
+        let (result, replayed) = window
+            .call_once(caller, key(), call, async { Ok(committed(2)) })
+            .await?;
+        assert_eq!((result.tx_offset, replayed), (Some(1), true));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_calls_expire() {
+        let now = Instant::now();
+        let mut calls = Calls::default();
+        let make_call = |calls: &mut Calls, i: u32| {
+            let key = (Identity::ZERO, IdempotencyKey::Request(Address::ZERO, i));
+            let call = Call {
+                fingerprint: purchase("sword"),
+                result: Arc::default(),
+            };
+            calls.by_key.insert(key.clone(), call);
+            calls.made_at.push_back((now, key));
+        };
+
+        // Making room for another call forgets the oldest one.
+        for i in 0..ReplayWindow::MAX_CALLS as u32 {
+            make_call(&mut calls, i);
+        }
+        calls.expire(now);
+        assert_eq!(calls.by_key.len(), ReplayWindow::MAX_CALLS - 1);
+        assert!(!calls
+            .by_key
+            .contains_key(&(Identity::ZERO, IdempotencyKey::Request(Address::ZERO, 0))));
+
+        // Calls are forgotten once they are older than the window.
+        calls.expire(now + ReplayWindow::DURATION);
+        assert!(calls.by_key.is_empty());
+        assert!(calls.made_at.is_empty());
+    }
+}
@@ -1,13 +1,15 @@
+use super::assets::{Asset, Assets};
 use super::scheduler::{get_schedule_from_row, ScheduleError, Scheduler};
 use crate::database_logger::{BacktraceProvider, LogLevel, Record};
-use crate::db::datastore::locking_tx_datastore::MutTxId;
+use crate::db::blob;
+use crate::db::datastore::locking_tx_datastore::{MutTxId, Savepoint};
 use crate::db::relational_db::{MutTx, RelationalDB};
 use crate::error::{DBError, IndexError, NodesError};
 use crate::replica_context::ReplicaContext;
 use core::mem;
 use parking_lot::{Mutex, MutexGuard};
 use smallvec::SmallVec;
-use spacetimedb_primitives::{ColId, ColList, IndexId, TableId};
+use spacetimedb_primitives::{ColId, ColList, IndexId, SequenceId, TableId};
 use spacetimedb_sats::{
     bsatn::{self, ToBsatn},
     buffer::{CountWriter, TeeWriter},
@@ -23,6 +25,7 @@ pub struct InstanceEnv {
     pub replica_ctx: Arc<ReplicaContext>,
     pub scheduler: Scheduler,
     pub tx: TxSlot,
+    pub assets: Arc<Assets>,
 }
 
 #[derive(Clone, Default)]
@@ -97,11 +100,12 @@ impl ChunkedWriter {
 
 // Generic 'instance environment' delegated to from various host types.
 impl InstanceEnv {
-    pub fn new(replica_ctx: Arc<ReplicaContext>, scheduler: Scheduler) -> Self {
+    pub fn new(replica_ctx: Arc<ReplicaContext>, scheduler: Scheduler, assets: Arc<Assets>) -> Self {
         Self {
             replica_ctx,
             scheduler,
             tx: TxSlot::default(),
+            assets,
         }
     }
 
@@ -256,6 +260,20 @@ impl InstanceEnv {
         Ok(stdb.delete_by_rel(tx, table_id, relation))
     }
 
+    /// Deletes all rows in the table identified by `table_id`,
+    /// returning how many rows were deleted.
+    ///
+    /// Returns an error if
+    /// - not in a transaction.
+    /// - the table didn't exist.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn datastore_table_truncate(&self, table_id: TableId) -> Result<u64, NodesError> {
+        let stdb = &*self.replica_ctx.relational_db;
+        let tx = &mut *self.get_tx()?;
+
+        Ok(stdb.truncate(tx, table_id)?)
+    }
+
     /// Returns the `table_id` associated with the given `table_name`.
     ///
     /// Errors with `GetTxError` if not in a transaction
@@ -292,11 +310,166 @@ impl InstanceEnv {
     pub fn datastore_table_row_count(&self, table_id: TableId) -> Result<u64, NodesError> {
         let stdb = &*self.replica_ctx.relational_db;
         let tx = &mut *self.get_tx()?;
+        tx.record_read(table_id);
 
         // Query the row count for id.
         stdb.table_row_count_mut(tx, table_id).ok_or(NodesError::TableNotFound)
     }
 
+    /// Returns the length in bytes of the blob `blob_id` in the blob table `table_id`.
+    ///
+    /// Errors with `GetTxError` if not in a transaction,
+    /// `TableNotFound` if the table does not exist,
+    /// and `Blob` if it is not a blob table.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn blob_len(&self, table_id: TableId, blob_id: u64) -> Result<u64, NodesError> {
+        let stdb = &*self.replica_ctx.relational_db;
+        let tx = &mut *self.get_tx()?;
+        tx.record_read(table_id);
+
+        Ok(blob::blob_len_mut(stdb, tx, table_id, blob_id)?)
+    }
+
+    /// Reads up to `max_len` bytes of the blob `blob_id` in the blob table `table_id`,
+    /// starting at `offset`.
+    ///
+    /// Errors as [`Self::blob_len`].
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn blob_read(
+        &self,
+        table_id: TableId,
+        blob_id: u64,
+        offset: u64,
+        max_len: usize,
+    ) -> Result<Vec<u8>, NodesError> {
+        let stdb = &*self.replica_ctx.relational_db;
+        let tx = &mut *self.get_tx()?;
+        tx.record_read(table_id);
+
+        Ok(blob::read_blob_mut(stdb, tx, table_id, blob_id, offset, max_len)?)
+    }
+
+    /// Writes `data` into the blob `blob_id` in the blob table `table_id`, starting at `offset`.
+    ///
+    /// Errors as [`Self::blob_len`], and also with `Blob` if `offset` is past the end of the blob.
+    #[tracing::instrument(level = "trace", skip(self, data))]
+    pub fn blob_write(&self, table_id: TableId, blob_id: u64, offset: u64, data: &[u8]) -> Result<(), NodesError> {
+        let stdb = &*self.replica_ctx.relational_db;
+        let tx = &mut *self.get_tx()?;
+
+        Ok(blob::write_blob(stdb, tx, table_id, blob_id, offset, data)?)
+    }
+
+    /// Deletes the blob `blob_id` in the blob table `table_id`,
+    /// returning the number of chunks deleted.
+    ///
+    /// Errors as [`Self::blob_len`].
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn blob_delete(&self, table_id: TableId, blob_id: u64) -> Result<u32, NodesError> {
+        let stdb = &*self.replica_ctx.relational_db;
+        let tx = &mut *self.get_tx()?;
+
+        Ok(blob::delete_blob(stdb, tx, table_id, blob_id)?)
+    }
+
+    /// Returns the value which the sequence on the column `col_id` of the table `table_id`
+    /// will generate next, without consuming it.
+    ///
+    /// Errors with `GetTxError` if not in a transaction,
+    /// `TableNotFound` if the table does not exist,
+    /// and `SequenceNotFound` if the column has no sequence.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn sequence_peek(&self, table_id: TableId, col_id: ColId) -> Result<i128, NodesError> {
+        let stdb = &*self.replica_ctx.relational_db;
+        let tx = &mut *self.get_tx()?;
+        tx.record_read(table_id);
+
+        let seq_id = Self::sequence_for_column(stdb, tx, table_id, col_id)?;
+        Ok(stdb.peek_sequence_value(tx, seq_id)?)
+    }
+
+    /// Moves the sequence on the column `col_id` of the table `table_id` past `value`,
+    /// so that it won't later generate `value`,
+    /// returning whether the sequence moved.
+    ///
+    /// Errors as [`Self::sequence_peek`].
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn sequence_advance_past(&self, table_id: TableId, col_id: ColId, value: i128) -> Result<bool, NodesError> {
+        let stdb = &*self.replica_ctx.relational_db;
+        let tx = &mut *self.get_tx()?;
+
+        let seq_id = Self::sequence_for_column(stdb, tx, table_id, col_id)?;
+        Ok(stdb.advance_sequence_past(tx, seq_id, value)?)
+    }
+
+    /// Returns the length of the asset at `path` in the module's asset bundle.
+    ///
+    /// Errors with `AssetNotFound` if there is no such asset.
+    /// Assets are read-only, so unlike most calls, this may be made outside of a transaction.
+    pub fn asset_len(&self, path: &str) -> Result<u64, NodesError> {
+        Ok(self.asset(path)?.data.len() as u64)
+    }
+
+    /// Reads up to `max_len` bytes of the asset at `path`, starting at `offset`.
+    ///
+    /// Fewer bytes are returned only if the end of the asset was reached.
+    /// Errors as [`Self::asset_len`].
+    pub fn asset_read(&self, path: &str, offset: u64, max_len: usize) -> Result<&[u8], NodesError> {
+        let data = &self.asset(path)?.data[..];
+        let start = usize::try_from(offset).unwrap_or(usize::MAX).min(data.len());
+        let end = start.saturating_add(max_len).min(data.len());
+        Ok(&data[start..end])
+    }
+
+    fn asset(&self, path: &str) -> Result<&Asset, NodesError> {
+        self.assets
+            .get(path)
+            .ok_or_else(|| NodesError::AssetNotFound(path.into()))
+    }
+
+    fn sequence_for_column(
+        stdb: &RelationalDB,
+        tx: &MutTx,
+        table_id: TableId,
+        col_id: ColId,
+    ) -> Result<SequenceId, NodesError> {
+        let schema = stdb.schema_for_table_mut(tx, table_id)?;
+        schema
+            .sequences
+            .iter()
+            .find(|seq| seq.col_pos == col_id)
+            .map(|seq| seq.sequence_id)
+            .ok_or(NodesError::SequenceNotFound)
+    }
+
+    /// Returns a [`Savepoint`] at the writes made by the current transaction so far.
+    ///
+    /// Errors with `GetTxError` if not in a transaction.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn savepoint(&self) -> Result<Savepoint, NodesError> {
+        Ok(self.get_tx()?.savepoint())
+    }
+
+    /// Keeps the writes made by the current transaction since `savepoint` was taken,
+    /// releasing `savepoint`.
+    ///
+    /// Errors with `GetTxError` if not in a transaction.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn release_savepoint(&self, savepoint: Savepoint) -> Result<(), NodesError> {
+        self.get_tx()?.release_savepoint(savepoint);
+        Ok(())
+    }
+
+    /// Discards the writes made by the current transaction since `savepoint` was taken,
+    /// releasing `savepoint`.
+    ///
+    /// Errors with `GetTxError` if not in a transaction.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn rollback_to_savepoint(&self, savepoint: Savepoint) -> Result<(), NodesError> {
+        self.get_tx()?.rollback_to_savepoint(savepoint)?;
+        Ok(())
+    }
+
     #[tracing::instrument(level = "trace", skip_all)]
     pub fn datastore_table_scan_bsatn_chunks(
         &self,
@@ -305,6 +478,7 @@ impl InstanceEnv {
     ) -> Result<Vec<Vec<u8>>, NodesError> {
         let stdb = &*self.replica_ctx.relational_db;
         let tx = &mut *self.tx.get()?;
+        tx.record_read(table_id);
 
         let chunks = ChunkedWriter::collect_iter(pool, stdb.iter_mut(tx, table_id)?);
         Ok(chunks)
@@ -323,8 +497,9 @@ impl InstanceEnv {
         let stdb = &*self.replica_ctx.relational_db;
         let tx = &mut *self.tx.get()?;
 
-        let (_, iter) = stdb.btree_scan(tx, index_id, prefix, prefix_elems, rstart, rend)?;
+        let (table_id, iter) = stdb.btree_scan(tx, index_id, prefix, prefix_elems, rstart, rend)?;
         let chunks = ChunkedWriter::collect_iter(pool, iter);
+        tx.record_read(table_id);
         Ok(chunks)
     }
 }
@@ -8,10 +8,14 @@ use spacetimedb_lib::bsatn;
 use spacetimedb_lib::de::serde::SeedWrapper;
 use spacetimedb_lib::de::DeserializeSeed;
 use spacetimedb_lib::ProductValue;
+use spacetimedb_sats::WithTypespace;
 use spacetimedb_schema::def::deserialize::ReducerArgsDeserializeSeed;
+use spacetimedb_schema::def::ViewDef;
 
+pub mod assets;
 mod disk_storage;
 mod host_controller;
+mod idempotency;
 #[allow(clippy::too_many_arguments)]
 pub mod module_host;
 pub mod scheduler;
@@ -22,11 +26,14 @@ mod wasm_common;
 
 pub use disk_storage::DiskStorage;
 pub use host_controller::{
-    DescribedEntityType, DurabilityProvider, ExternalDurability, ExternalStorage, HostController, ProgramStorage,
-    ReducerCallResult, ReducerOutcome,
+    DatabaseStatus, DescribedEntityType, DurabilityBackends, DurabilityProvider, ExternalDurability, ExternalStorage,
+    HostController, ModuleStatus, ProgramStorage, ReducerCallResult, ReducerOutcome, ReplayProgress, ReplicaStatus,
 };
-pub use module_host::{ModuleHost, NoSuchModule, ReducerCallError, UpdateDatabaseResult};
-pub use scheduler::Scheduler;
+pub use idempotency::{CallFingerprint, IdempotencyKey, ReplayWindow};
+pub use module_host::{
+    HttpRouteCallError, ModuleHost, NoSuchModule, ReducerCallError, UpdateDatabaseResult, ViewCallError,
+};
+pub use scheduler::{Clock, Scheduler};
 pub use spacetimedb_client_api_messages::timestamp::Timestamp;
 
 #[derive(Debug)]
@@ -38,12 +45,27 @@ pub enum ReducerArgs {
 
 impl ReducerArgs {
     fn into_tuple(self, seed: ReducerArgsDeserializeSeed) -> Result<ArgsTuple, InvalidReducerArguments> {
-        self._into_tuple(seed).map_err(|err| InvalidReducerArguments {
-            err,
-            reducer: (*seed.reducer_def().name).into(),
-        })
+        let reducer_def = seed.reducer_def();
+        let nullary = reducer_def.params.elements.is_empty();
+        self._into_tuple(seed, nullary)
+            .and_then(|args| {
+                reducer_def.check_arg_constraints(&args.tuple)?;
+                Ok(args)
+            })
+            .map_err(|err| InvalidReducerArguments {
+                err,
+                reducer: (*reducer_def.name).into(),
+            })
+    }
+    fn into_view_tuple(self, view: WithTypespace<'_, ViewDef>) -> anyhow::Result<ArgsTuple> {
+        let params = view.map(|view| &view.params);
+        self._into_tuple(params, params.ty().elements.is_empty())
+            .with_context(|| format!("invalid arguments for view {}", view.ty().name))
     }
-    fn _into_tuple(self, seed: ReducerArgsDeserializeSeed) -> anyhow::Result<ArgsTuple> {
+    fn _into_tuple<S>(self, seed: S, nullary: bool) -> anyhow::Result<ArgsTuple>
+    where
+        S: for<'de> DeserializeSeed<'de, Output = ProductValue> + Copy,
+    {
         Ok(match self {
             ReducerArgs::Json(json) => ArgsTuple {
                 tuple: from_json_seed(&json, SeedWrapper(seed))?,
@@ -56,10 +78,7 @@ impl ReducerArgs {
                 json: OnceCell::new(),
             },
             ReducerArgs::Nullary => {
-                anyhow::ensure!(
-                    seed.reducer_def().params.elements.is_empty(),
-                    "failed to typecheck args"
-                );
+                anyhow::ensure!(nullary, "failed to typecheck args");
                 ArgsTuple::nullary()
             }
         })
@@ -136,12 +155,28 @@ pub enum AbiCall {
     DatastoreUpdateBsatn,
     DatastoreDeleteByBtreeScanBsatn,
     DatastoreDeleteAllByEqBsatn,
+    DatastoreTableTruncate,
+    DatastoreBatchBsatn,
     BytesSourceRead,
     BytesSinkWrite,
     ConsoleLog,
     ConsoleTimerStart,
     ConsoleTimerEnd,
     Identity,
+    SavepointBegin,
+    SavepointRollback,
+    SavepointRelease,
+    BlobLen,
+    BlobRead,
+    BlobWrite,
+    BlobDelete,
+    SequencePeek,
+    SequenceAdvancePast,
+    AssetLen,
+    AssetRead,
+    CallerMetadata,
+    SessionGet,
+    Broadcast,
 
     VolatileNonatomicScheduleImmediate,
 }
@@ -1,9 +1,18 @@
-use super::{ArgsTuple, InvalidReducerArguments, ReducerArgs, ReducerCallResult, ReducerId};
-use crate::client::{ClientActorId, ClientConnectionSender};
+use super::assets::Assets;
+use super::{
+    ArgsTuple, CallFingerprint, IdempotencyKey, InvalidReducerArguments, ReducerArgs, ReducerCallResult, ReducerId,
+    ReducerOutcome,
+};
+use crate::client::{ClientActorId, ClientConnectionSender, SessionVariables};
 use crate::database_logger::{LogLevel, Record};
 use crate::db::datastore::locking_tx_datastore::MutTxId;
-use crate::db::datastore::system_tables::{StClientFields, StClientRow, ST_CLIENT_ID};
+use crate::db::datastore::system_tables::{
+    StClientFields, StClientRow, StConnectionFields, StConnectionRow, ST_CLIENT_ID, ST_CONNECTION_ID,
+};
 use crate::db::datastore::traits::{IsolationLevel, Program, TxData};
+use crate::db::import::{self, ColumnMapping, ImportError, ImportOptions, ImportSummary, IMPORT_TX_ROWS};
+use crate::db::index_advisor::IndexAdvice;
+use crate::db::relational_db::TxOffsetTimeout;
 use crate::energy::EnergyQuanta;
 use crate::error::DBError;
 use crate::estimation::estimate_rows_scanned;
@@ -11,35 +20,46 @@ use crate::execution_context::{ExecutionContext, ReducerContext, Workload};
 use crate::hash::Hash;
 use crate::identity::Identity;
 use crate::messages::control_db::Database;
-use crate::replica_context::ReplicaContext;
+use crate::replica_context::{QuotaExceeded, ReplicaContext};
 use crate::sql::ast::SchemaViewer;
-use crate::subscription::module_subscription_actor::ModuleSubscriptions;
+use crate::subscription::module_subscription_actor::{ModuleSubscriptions, WriteConflict};
 use crate::subscription::tx::DeltaTx;
+use crate::subscription::view_subscriptions::{self, ViewSubscription};
 use crate::util::lending_pool::{Closed, LendingPool, LentResource, PoolClosed};
 use crate::vm::check_row_limit;
 use crate::worker_metrics::WORKER_METRICS;
 use anyhow::Context;
 use bytes::Bytes;
+use bytestring::ByteString;
 use derive_more::From;
 use futures::{Future, FutureExt};
 use indexmap::IndexSet;
 use itertools::Itertools;
 use smallvec::SmallVec;
 use spacetimedb_client_api_messages::timestamp::Timestamp;
-use spacetimedb_client_api_messages::websocket::{Compression, OneOffTable, QueryUpdate, WebsocketFormat};
+use spacetimedb_client_api_messages::websocket::{
+    Compression, OneOffTable, QueryUpdate, SubscribeView, ViewPage, WebsocketFormat,
+};
 use spacetimedb_data_structures::error_stream::ErrorStream;
-use spacetimedb_data_structures::map::{HashCollectionExt as _, IntMap};
-use spacetimedb_lib::db::raw_def::v9::Lifecycle;
+use spacetimedb_data_structures::map::{HashCollectionExt as _, IntMap, IntSet};
+use spacetimedb_lib::client_metadata::ClientMetadata;
+use spacetimedb_lib::db::auth::StTableType;
+use spacetimedb_lib::db::raw_def::v9::{HttpMethod, Lifecycle, ReducerPriority};
 use spacetimedb_lib::identity::{AuthCtx, RequestId};
 use spacetimedb_lib::Address;
-use spacetimedb_primitives::{col_list, TableId};
+use spacetimedb_primitives::{col_list, TableId, ViewId};
 use spacetimedb_query::SubscribePlan;
-use spacetimedb_sats::{algebraic_value, ProductValue};
+use spacetimedb_sats::bsatn::{self, DecodeError, ToBsatn};
+use spacetimedb_sats::de::DeserializeSeed as _;
+use spacetimedb_sats::ser::Serialize;
+use spacetimedb_sats::{algebraic_value, AlgebraicType, ProductValue, WithTypespace};
 use spacetimedb_schema::auto_migrate::AutoMigrateError;
 use spacetimedb_schema::def::deserialize::ReducerArgsDeserializeSeed;
 use spacetimedb_schema::def::{ModuleDef, ReducerDef};
 use spacetimedb_vm::relation::RelValue;
 use std::fmt;
+use std::iter;
+use std::mem;
 use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
@@ -71,6 +91,7 @@ impl DatabaseUpdate {
             table_name: table_name.into(),
             inserts: [].into(),
             deletes: [].into(),
+            truncated: false,
         };
         for (table_id, table_name, rows) in tx_data.inserts_with_table_name() {
             map.entry(*table_id)
@@ -82,6 +103,11 @@ impl DatabaseUpdate {
                 .or_insert_with(|| new_update(*table_id, table_name))
                 .deletes = rows.clone();
         }
+        for table_id in tx_data.truncates() {
+            if let Some(update) = map.get_mut(&table_id) {
+                update.truncated = true;
+            }
+        }
         DatabaseUpdate {
             tables: map.into_values().collect(),
         }
@@ -102,6 +128,8 @@ pub struct DatabaseTableUpdate {
     // contained `ProductValue`s.
     pub inserts: Arc<[ProductValue]>,
     pub deletes: Arc<[ProductValue]>,
+    /// Whether the table was truncated, in which case `deletes` holds every row it had.
+    pub truncated: bool,
 }
 
 #[derive(Debug)]
@@ -129,8 +157,21 @@ impl UpdatesRelValue<'_> {
     }
 
     pub fn encode<F: WebsocketFormat>(&self, compression: Compression) -> (F::QueryUpdate, u64) {
-        let (deletes, nr_del) = F::encode_list(self.deletes.iter());
-        let (inserts, nr_ins) = F::encode_list(self.inserts.iter());
+        Self::encode_lists::<F, _>(self.deletes.iter(), self.inserts.iter(), compression)
+    }
+
+    /// Encodes only the inserts, for clients told that the table was cleared.
+    pub fn encode_inserts<F: WebsocketFormat>(&self, compression: Compression) -> (F::QueryUpdate, u64) {
+        Self::encode_lists::<F, _>(iter::empty(), self.inserts.iter(), compression)
+    }
+
+    fn encode_lists<F: WebsocketFormat, R: ToBsatn + Serialize>(
+        deletes: impl Iterator<Item = R>,
+        inserts: impl Iterator<Item = R>,
+        compression: Compression,
+    ) -> (F::QueryUpdate, u64) {
+        let (deletes, nr_del) = F::encode_list(deletes);
+        let (inserts, nr_ins) = F::encode_list(inserts);
         let num_rows = nr_del + nr_ins;
         let qu = QueryUpdate { deletes, inserts };
         let cqu = F::into_query_update(qu, compression);
@@ -142,7 +183,44 @@ impl UpdatesRelValue<'_> {
 pub enum EventStatus {
     Committed(DatabaseUpdate),
     Failed(String),
+    /// The reducer failed with a value of its error type.
+    FailedWithValue(ReducerErrorValue),
     OutOfEnergy,
+    /// The reducer ran past the maximum reducer duration of the database.
+    TimedOut,
+}
+
+/// A value of a reducer's error type, which the reducer failed with.
+#[derive(Debug, Clone)]
+pub struct ReducerErrorValue {
+    /// The value, formatted as SATN, for logs and for callers who don't understand typed errors.
+    pub message: String,
+    bsatn: Bytes,
+    json: ByteString,
+}
+
+impl ReducerErrorValue {
+    /// Decode the BSATN-encoded `bytes` as a value of the reducer's error type `ty`.
+    pub fn decode(ty: WithTypespace<'_, AlgebraicType>, bytes: Bytes) -> Result<Self, DecodeError> {
+        use spacetimedb_sats::{satn::Satn, ser::serde::SerializeWrapper};
+        let value = ty.deserialize(bsatn::Deserializer::new(&mut &bytes[..]))?;
+        let value = ty.with_value(&value);
+        Ok(Self {
+            message: value.to_satn(),
+            json: serde_json::to_string(SerializeWrapper::from_ref(&value))
+                .unwrap()
+                .into(),
+            bsatn: bytes,
+        })
+    }
+
+    pub fn get_bsatn(&self) -> &Bytes {
+        &self.bsatn
+    }
+
+    pub fn get_json(&self) -> &ByteString {
+        &self.json
+    }
 }
 
 impl EventStatus {
@@ -170,6 +248,9 @@ pub struct ModuleEvent {
     pub status: EventStatus,
     pub energy_quanta_used: EnergyQuanta,
     pub host_execution_duration: Duration,
+    /// The offset of the transaction committed by this event,
+    /// if it committed one which consumed an offset.
+    pub tx_offset: Option<u64>,
     pub request_id: Option<RequestId>,
     pub timer: Option<Instant>,
 }
@@ -191,6 +272,8 @@ pub struct ModuleInfo {
     pub log_tx: tokio::sync::broadcast::Sender<bytes::Bytes>,
     /// Subscriptions to this module.
     pub subscriptions: ModuleSubscriptions,
+    /// The asset bundle published with the module's program.
+    pub assets: Arc<Assets>,
 }
 
 impl ModuleInfo {
@@ -203,6 +286,7 @@ impl ModuleInfo {
         module_hash: Hash,
         log_tx: tokio::sync::broadcast::Sender<bytes::Bytes>,
         subscriptions: ModuleSubscriptions,
+        assets: Arc<Assets>,
     ) -> Arc<Self> {
         Arc::new(ModuleInfo {
             module_def,
@@ -211,6 +295,7 @@ impl ModuleInfo {
             module_hash,
             log_tx,
             subscriptions,
+            assets,
         })
     }
 }
@@ -272,6 +357,9 @@ pub trait ModuleInstance: Send + 'static {
     ) -> anyhow::Result<UpdateDatabaseResult>;
 
     fn call_reducer(&mut self, tx: Option<MutTxId>, params: CallReducerParams) -> ReducerCallResult;
+
+    /// Call a view, returning the rows of its result.
+    fn call_view(&mut self, params: CallViewParams) -> anyhow::Result<ViewCallResult>;
 }
 
 pub struct CallReducerParams {
@@ -279,12 +367,37 @@ pub struct CallReducerParams {
     pub caller_identity: Identity,
     pub caller_address: Address,
     pub client: Option<Arc<ClientConnectionSender>>,
+    /// What the host learned about the caller when it connected,
+    /// if the reducer was called by a connected client.
+    pub caller_metadata: Option<Arc<ClientMetadata>>,
+    /// A snapshot of the variables the caller has set in its session,
+    /// if the reducer was called by a connected client.
+    pub caller_session: Option<Arc<SessionVariables>>,
     pub request_id: Option<RequestId>,
     pub timer: Option<Instant>,
     pub reducer_id: ReducerId,
     pub args: ArgsTuple,
 }
 
+pub struct CallViewParams {
+    pub timestamp: Timestamp,
+    pub caller_identity: Identity,
+    pub caller_address: Address,
+    pub view_id: ViewId,
+    pub args: ArgsTuple,
+    /// The window of the view's result to return, or `None` to return all of it.
+    pub page: Option<ViewPage>,
+}
+
+/// The result of calling a view.
+pub struct ViewCallResult {
+    /// The rows of the view's result.
+    pub rows: Vec<ProductValue>,
+    /// The tables the view read,
+    /// so its result can only change once a transaction modifies one of them.
+    pub read_tables: IntSet<TableId>,
+}
+
 // TODO: figure out how we want to handle traps. maybe it should just not return to the LendingPool and
 //       let the get_instance logic handle it?
 struct AutoReplacingModuleInstance<T: Module> {
@@ -323,6 +436,11 @@ impl<T: Module> ModuleInstance for AutoReplacingModuleInstance<T> {
         self.check_trap();
         ret
     }
+    fn call_view(&mut self, params: CallViewParams) -> anyhow::Result<ViewCallResult> {
+        let ret = self.inst.call_view(params);
+        self.check_trap();
+        ret
+    }
 }
 
 #[derive(Clone)]
@@ -344,7 +462,11 @@ impl fmt::Debug for ModuleHost {
 
 #[async_trait::async_trait]
 trait DynModuleHost: Send + Sync + 'static {
-    async fn get_instance(&self, db: Identity) -> Result<Box<dyn ModuleInstance>, NoSuchModule>;
+    async fn get_instance(
+        &self,
+        db: Identity,
+        priority: ReducerPriority,
+    ) -> Result<Box<dyn ModuleInstance>, NoSuchModule>;
     fn replica_ctx(&self) -> &ReplicaContext;
     fn exit(&self) -> Closed<'_>;
     fn exited(&self) -> Closed<'_>;
@@ -381,18 +503,22 @@ async fn select_first<A: Future, B: Future<Output = ()>>(fut_a: A, fut_b: B) ->
 
 #[async_trait::async_trait]
 impl<T: Module> DynModuleHost for HostControllerActor<T> {
-    async fn get_instance(&self, db: Identity) -> Result<Box<dyn ModuleInstance>, NoSuchModule> {
+    async fn get_instance(
+        &self,
+        db: Identity,
+        priority: ReducerPriority,
+    ) -> Result<Box<dyn ModuleInstance>, NoSuchModule> {
         // in the future we should do something like in the else branch here -- add more instances based on load.
         // we need to do write-skew retries first - right now there's only ever once instance per module.
         let inst = if true {
             self.instance_pool
-                .request_with_context(db)
+                .request_with_context(db, priority)
                 .await
                 .map_err(|_| NoSuchModule)?
         } else {
             const GET_INSTANCE_TIMEOUT: Duration = Duration::from_millis(500);
             select_first(
-                self.instance_pool.request_with_context(db),
+                self.instance_pool.request_with_context(db, priority),
                 tokio::time::sleep(GET_INSTANCE_TIMEOUT).map(|()| self.spinup_new_instance()),
             )
             .await
@@ -456,6 +582,36 @@ pub enum ReducerCallError {
     ScheduleReducerNotFound,
     #[error("can't directly call special {0:?} lifecycle reducer")]
     LifecycleReducer(Lifecycle),
+    #[error("can't directly call the topic authorizer")]
+    TopicAuthorizer,
+    #[error(transparent)]
+    QuotaExceeded(#[from] QuotaExceeded),
+    #[error("the idempotency key was already used for a different call")]
+    IdempotencyKeyReused,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ViewCallError {
+    #[error("no such view")]
+    NoSuchView,
+    #[error("invalid arguments: {0:#}")]
+    Args(anyhow::Error),
+    #[error(transparent)]
+    NoSuchModule(#[from] NoSuchModule),
+    #[error("view failed: {0:#}")]
+    View(anyhow::Error),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum HttpRouteCallError {
+    #[error("no such route")]
+    NoSuchRoute,
+    #[error("invalid query parameters: {0:#}")]
+    Args(anyhow::Error),
+    #[error(transparent)]
+    NoSuchModule(#[from] NoSuchModule),
+    #[error("view failed: {0:#}")]
+    View(anyhow::Error),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -491,7 +647,9 @@ impl ModuleHost {
         &self.info.subscriptions
     }
 
-    async fn call<F, R>(&self, reducer: &str, f: F) -> Result<R, NoSuchModule>
+    /// Run `f` on an instance of the module,
+    /// waiting in the instance queue behind calls of the same or a higher `priority`.
+    async fn call<F, R>(&self, reducer: &str, priority: ReducerPriority, f: F) -> Result<R, NoSuchModule>
     where
         F: FnOnce(&mut dyn ModuleInstance) -> R + Send + 'static,
         R: Send + 'static,
@@ -502,7 +660,7 @@ impl ModuleHost {
                 .reducer_wait_time
                 .with_label_values(&self.info.database_identity, reducer)
                 .start_timer();
-            self.inner.get_instance(self.info.database_identity).await?
+            self.inner.get_instance(self.info.database_identity, priority).await?
         };
 
         let result = tokio::task::spawn_blocking(move || f(&mut *inst))
@@ -515,7 +673,12 @@ impl ModuleHost {
         Ok(result)
     }
 
-    pub async fn disconnect_client(&self, client_id: ClientActorId) {
+    pub async fn disconnect_client(
+        &self,
+        client_id: ClientActorId,
+        caller_metadata: Option<Arc<ClientMetadata>>,
+        caller_session: Option<Arc<SessionVariables>>,
+    ) {
         log::trace!("disconnecting client {}", client_id);
         let this = self.clone();
         let _ = tokio::task::spawn_blocking(move || {
@@ -524,7 +687,13 @@ impl ModuleHost {
         .await;
         // ignore NoSuchModule; if the module's already closed, that's fine
         let _ = self
-            .call_identity_connected_disconnected(client_id.identity, client_id.address, false)
+            .call_identity_connected_disconnected(
+                client_id.identity,
+                client_id.address,
+                false,
+                caller_metadata,
+                caller_session,
+            )
             .await;
     }
 
@@ -538,6 +707,8 @@ impl ModuleHost {
         caller_identity: Identity,
         caller_address: Address,
         connected: bool,
+        caller_metadata: Option<Arc<ClientMetadata>>,
+        caller_session: Option<Arc<SessionVariables>>,
     ) -> Result<(), ReducerCallError> {
         let (lifecycle, fake_name) = if connected {
             (Lifecycle::OnConnect, "__identity_connected__")
@@ -564,6 +735,8 @@ impl ModuleHost {
                 caller_identity,
                 Some(caller_address),
                 None,
+                caller_metadata,
+                caller_session,
                 None,
                 None,
                 reducer_id,
@@ -597,17 +770,81 @@ impl ModuleHost {
 
         // Deleting client from `st_clients`does not depend upon result of disconnect reducer hence done in a separate tx.
         if !connected {
-            let _ = db
-                .with_auto_commit(workload(), |mut_tx| {
-                    self.update_st_clients(mut_tx, caller_identity, caller_address, connected)
-                })
-                .map_err(|e| {
-                    log::error!("st_clients table update failed with params with error: {:?}", e);
-                });
+            if self
+                .info
+                .module_def
+                .tables()
+                .any(|table| table.bound_to_connection.is_some())
+            {
+                // The rows bound to the connection are deleted in the same tx,
+                // which is broadcast, so that subscribers see them go.
+                self.disconnect_bound_rows(workload(), caller_identity, caller_address);
+            } else {
+                let _ = db
+                    .with_auto_commit(workload(), |mut_tx| {
+                        self.update_st_clients(mut_tx, caller_identity, caller_address, connected)
+                    })
+                    .map_err(|e| {
+                        log::error!("st_clients table update failed with params with error: {:?}", e);
+                    });
+            }
         }
         result
     }
 
+    /// Deletes the client from `st_clients` along with the rows of the tables bound to connections
+    /// which are bound to its connection, and broadcasts the deletions to subscribers.
+    fn disconnect_bound_rows(&self, workload: Workload, caller_identity: Identity, caller_address: Address) {
+        let db = &*self.inner.replica_ctx().relational_db;
+        let mut tx = db.begin_mut_tx(IsolationLevel::Serializable, workload);
+        let deleted = self
+            .update_st_clients(&mut tx, caller_identity, caller_address, false)
+            .and_then(|()| self.delete_connection_bound_rows(&mut tx, caller_address));
+        if let Err(e) = deleted {
+            db.rollback_mut_tx(tx);
+            log::error!("failed to delete the rows bound to connection {caller_address}: {e:?}");
+            return;
+        }
+
+        let event = ModuleEvent {
+            timestamp: Timestamp::now(),
+            caller_identity,
+            caller_address: Some(caller_address),
+            function_call: ModuleFunctionCall::default(),
+            status: EventStatus::Committed(DatabaseUpdate::default()),
+            energy_quanta_used: EnergyQuanta::ZERO,
+            host_execution_duration: Duration::ZERO,
+            tx_offset: None,
+            request_id: None,
+            timer: None,
+        };
+        match self.info.subscriptions.commit_and_broadcast_event(None, event, tx) {
+            Ok(Ok(_)) => {}
+            Ok(Err(WriteConflict)) => todo!("See module_host_actor::call_reducer_with_tx"),
+            Err(e) => log::error!("failed to delete the rows bound to connection {caller_address}: {e:?}"),
+        }
+    }
+
+    /// Deletes the rows of each table bound to connections whose connection column holds `caller_address`.
+    fn delete_connection_bound_rows(&self, tx: &mut MutTxId, caller_address: Address) -> Result<(), DBError> {
+        let db = &*self.inner.replica_ctx().relational_db;
+        let address = algebraic_value::AlgebraicValue::from(caller_address);
+        for table in self.info.module_def.tables() {
+            let Some(bound) = table.bound_to_connection else {
+                continue;
+            };
+            let Some(table_id) = db.table_id_from_name_mut(tx, &table.name)? else {
+                continue;
+            };
+            let rows = db
+                .iter_by_col_eq_mut(tx, table_id, bound.column, &address)?
+                .map(|row_ref| row_ref.pointer())
+                .collect::<Vec<_>>();
+            db.delete(tx, table_id, rows);
+        }
+        Ok(())
+    }
+
     fn update_st_clients(
         &self,
         mut_tx: &mut MutTxId,
@@ -617,24 +854,40 @@ impl ModuleHost {
     ) -> Result<(), DBError> {
         let db = &*self.inner.replica_ctx().relational_db;
 
-        let row = &StClientRow {
-            identity: caller_identity.into(),
-            address: caller_address.into(),
-        };
-
         if connected {
-            mut_tx.insert_via_serialize_bsatn(ST_CLIENT_ID, &row).map(|_| ())
+            let row = &StClientRow {
+                identity: caller_identity.into(),
+                address: caller_address.into(),
+            };
+            mut_tx.insert_via_serialize_bsatn(ST_CLIENT_ID, row)?;
+            let row = &StConnectionRow {
+                identity: caller_identity.into(),
+                address: caller_address.into(),
+                connected_at: Timestamp::now().microseconds,
+            };
+            mut_tx.insert_via_serialize_bsatn(ST_CONNECTION_ID, row).map(|_| ())
         } else {
-            let row = db
-                .iter_by_col_eq_mut(
-                    mut_tx,
+            // The client is identified by `(identity, address)`, regardless of when it connected.
+            let key = algebraic_value::AlgebraicValue::product([
+                caller_identity.to_u256().into(),
+                caller_address.to_u128().into(),
+            ]);
+            for (table_id, cols) in [
+                (
                     ST_CLIENT_ID,
                     col_list![StClientFields::Identity, StClientFields::Address],
-                    &algebraic_value::AlgebraicValue::product(row),
-                )?
-                .map(|row_ref| row_ref.pointer())
-                .collect::<SmallVec<[_; 1]>>();
-            db.delete(mut_tx, ST_CLIENT_ID, row);
+                ),
+                (
+                    ST_CONNECTION_ID,
+                    col_list![StConnectionFields::Identity, StConnectionFields::Address],
+                ),
+            ] {
+                let row = db
+                    .iter_by_col_eq_mut(mut_tx, table_id, cols, &key)?
+                    .map(|row_ref| row_ref.pointer())
+                    .collect::<SmallVec<[_; 1]>>();
+                db.delete(mut_tx, table_id, row);
+            }
             Ok::<(), DBError>(())
         }
     }
@@ -644,6 +897,8 @@ impl ModuleHost {
         caller_identity: Identity,
         caller_address: Option<Address>,
         client: Option<Arc<ClientConnectionSender>>,
+        caller_metadata: Option<Arc<ClientMetadata>>,
+        caller_session: Option<Arc<SessionVariables>>,
         request_id: Option<RequestId>,
         timer: Option<Instant>,
         reducer_id: ReducerId,
@@ -654,7 +909,7 @@ impl ModuleHost {
         let args = args.into_tuple(reducer_seed)?;
         let caller_address = caller_address.unwrap_or(Address::__DUMMY);
 
-        self.call(&reducer_def.name, move |inst| {
+        self.call(&reducer_def.name, reducer_def.priority, move |inst| {
             inst.call_reducer(
                 None,
                 CallReducerParams {
@@ -662,6 +917,8 @@ impl ModuleHost {
                     caller_identity,
                     caller_address,
                     client,
+                    caller_metadata,
+                    caller_session,
                     request_id,
                     timer,
                     reducer_id,
@@ -678,24 +935,47 @@ impl ModuleHost {
         caller_identity: Identity,
         caller_address: Option<Address>,
         client: Option<Arc<ClientConnectionSender>>,
+        caller_metadata: Option<Arc<ClientMetadata>>,
+        caller_session: Option<Arc<SessionVariables>>,
         request_id: Option<RequestId>,
         timer: Option<Instant>,
         reducer_name: &str,
         args: ReducerArgs,
     ) -> Result<ReducerCallResult, ReducerCallError> {
         let res = async {
-            let (reducer_id, reducer_def) = self
+            let (reducer_id, reducer_def, alias) = self
                 .info
                 .module_def
-                .reducer_full(reducer_name)
+                .reducer_or_alias_full(reducer_name)
                 .ok_or(ReducerCallError::NoSuchReducer)?;
             if let Some(lifecycle) = reducer_def.lifecycle {
                 return Err(ReducerCallError::LifecycleReducer(lifecycle));
             }
+            if self
+                .info
+                .module_def
+                .topic_authorizer()
+                .is_some_and(|(id, _)| id == reducer_id)
+            {
+                return Err(ReducerCallError::TopicAuthorizer);
+            }
+            if let Some(deprecated) = alias.and_then(|alias| alias.deprecated.as_deref()) {
+                self.inject_logs(
+                    LogLevel::Warn,
+                    &format!(
+                        "Reducer \"{}\" was called under its deprecated alias \"{reducer_name}\": {deprecated}",
+                        reducer_def.name
+                    ),
+                );
+            }
+            self.replica_ctx().check_quotas()?;
+            let _permit = self.replica_ctx().try_enqueue_reducer()?;
             self.call_reducer_inner(
                 caller_identity,
                 caller_address,
                 client,
+                caller_metadata,
+                caller_session,
                 request_id,
                 timer,
                 reducer_id,
@@ -725,18 +1005,228 @@ impl ModuleHost {
         res
     }
 
+    /// Checks with the module's topic authorizer, if it has one, that `client` may subscribe to `topic`.
+    ///
+    /// The authorizer is called like any other reducer called by the client,
+    /// so the client is sent its outcome, including the error with which it refused the subscription.
+    /// Returns whether the subscription is allowed.
+    pub async fn authorize_topic(
+        &self,
+        client: Arc<ClientConnectionSender>,
+        topic: &str,
+    ) -> Result<bool, ReducerCallError> {
+        let Some((reducer_id, reducer_def)) = self.info.module_def.topic_authorizer() else {
+            return Ok(true);
+        };
+        // The arguments are a tuple of the topic alone, which is encoded like the topic.
+        let args = ReducerArgs::Bsatn(bsatn::to_vec(topic).unwrap().into());
+        let result = self
+            .call_reducer_inner(
+                client.id.identity,
+                Some(client.id.address),
+                Some(client),
+                None,
+                None,
+                None,
+                None,
+                reducer_id,
+                reducer_def,
+                args,
+            )
+            .await?;
+        Ok(matches!(result.outcome, ReducerOutcome::Committed))
+    }
+
+    /// Subscribe `client` to the result of a view.
+    ///
+    /// The client is sent the view's current result,
+    /// or an error if the view doesn't exist, the arguments are invalid, or the view fails.
+    pub async fn subscribe_view(
+        &self,
+        client: Arc<ClientConnectionSender>,
+        request: SubscribeView<ReducerArgs>,
+        timer: Instant,
+    ) -> Result<(), NoSuchModule> {
+        let SubscribeView {
+            view,
+            args,
+            request_id,
+            query_id,
+            page,
+            min_tx_offset: _,
+        } = request;
+        let module_def = &self.info.module_def;
+        let view_and_args = module_def
+            .view_full(&*view)
+            .with_context(|| format!("no such view `{view}`"))
+            .and_then(|(view_id, view_def)| {
+                let args = args.into_view_tuple(module_def.typespace().with_type(view_def))?;
+                Ok((view_id, args))
+            });
+        let (view_id, args) = match view_and_args {
+            Ok(view_and_args) => view_and_args,
+            Err(e) => {
+                view_subscriptions::send_error(&client, Some(timer), request_id, query_id, e);
+                return Ok(());
+            }
+        };
+
+        let views = self.subscriptions().views().clone();
+        let sub = ViewSubscription::new(client, request_id, query_id, view, args, page);
+        let name = sub.view.clone();
+        self.call(&name, ReducerPriority::Normal, move |inst| {
+            views.add(sub, timer, |sub| {
+                inst.call_view(CallViewParams {
+                    timestamp: Timestamp::now(),
+                    caller_identity: sub.client.id.identity,
+                    caller_address: sub.client.id.address,
+                    view_id,
+                    args: sub.args.clone(),
+                    page: Some(sub.page.clone()),
+                })
+            })
+        })
+        .await
+    }
+
+    /// Run the reducer `call` made by `caller_identity` with the idempotency `key`,
+    /// unless such a call was made recently, in which case its result is returned instead.
+    ///
+    /// Returns whether the result was replayed from the earlier call along with it.
+    /// See [`ReplayWindow::call_once`](super::ReplayWindow::call_once).
+    pub async fn call_reducer_once(
+        &self,
+        caller_identity: Identity,
+        key: IdempotencyKey,
+        fingerprint: CallFingerprint,
+        call: impl Future<Output = Result<ReducerCallResult, ReducerCallError>> + Send + 'static,
+    ) -> Result<(ReducerCallResult, bool), ReducerCallError> {
+        self.replica_ctx()
+            .reducer_replays
+            .call_once(caller_identity, key, fingerprint, call)
+            .await
+    }
+
+    /// Serve a `method` request to the module's HTTP endpoint at `path`,
+    /// by calling the view serving it with the arguments in `query`.
+    ///
+    /// Query parameters are matched to the view's parameters by name.
+    /// Values of `String` parameters are taken verbatim, and all other values are parsed as JSON.
+    pub async fn call_http_route(
+        &self,
+        caller_identity: Identity,
+        caller_address: Address,
+        method: HttpMethod,
+        path: &str,
+        query: Vec<(String, String)>,
+    ) -> Result<Vec<ProductValue>, HttpRouteCallError> {
+        let module_def = &self.info.module_def;
+        let route = module_def
+            .http_route(method, path)
+            .ok_or(HttpRouteCallError::NoSuchRoute)?;
+        let (_, view_def) = module_def
+            .view_full(&route.view)
+            .ok_or(HttpRouteCallError::NoSuchRoute)?;
+
+        let args = query
+            .into_iter()
+            .map(|(name, value)| {
+                let is_string = view_def
+                    .params
+                    .elements
+                    .iter()
+                    .any(|param| param.has_name(&name) && param.algebraic_type == AlgebraicType::String);
+                let value = match serde_json::from_str(&value) {
+                    Ok(json) if !is_string => json,
+                    _ => serde_json::Value::String(value),
+                };
+                (name, value)
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let args = ReducerArgs::Json(serde_json::Value::Object(args).to_string().into());
+
+        self.call_view(caller_identity, caller_address, &view_def.name, args)
+            .await
+            .map_err(|e| match e {
+                ViewCallError::NoSuchView => HttpRouteCallError::NoSuchRoute,
+                ViewCallError::Args(e) => HttpRouteCallError::Args(e),
+                ViewCallError::NoSuchModule(e) => HttpRouteCallError::NoSuchModule(e),
+                ViewCallError::View(e) => HttpRouteCallError::View(e),
+            })
+    }
+
+    /// Call the view named `view_name` with `args`, returning the rows it produced.
+    ///
+    /// The arguments are checked against the parameters of the view before it is called.
+    pub async fn call_view(
+        &self,
+        caller_identity: Identity,
+        caller_address: Address,
+        view_name: &str,
+        args: ReducerArgs,
+    ) -> Result<Vec<ProductValue>, ViewCallError> {
+        let module_def = &self.info.module_def;
+        let (view_id, view_def) = module_def.view_full(view_name).ok_or(ViewCallError::NoSuchView)?;
+        let args = args
+            .into_view_tuple(module_def.typespace().with_type(view_def))
+            .map_err(ViewCallError::Args)?;
+
+        self.call(&view_def.name, ReducerPriority::Normal, move |inst| {
+            inst.call_view(CallViewParams {
+                timestamp: Timestamp::now(),
+                caller_identity,
+                caller_address,
+                view_id,
+                args,
+                page: None,
+            })
+        })
+        .await?
+        .map(|result| result.rows)
+        .map_err(ViewCallError::View)
+    }
+
+    /// Re-evaluate the views which clients are subscribed to which read any of the tables `modified`,
+    /// sending the clients any changes to their results.
+    pub async fn refresh_views(&self, modified: IntSet<TableId>) -> Result<(), NoSuchModule> {
+        let views = self.subscriptions().views().clone();
+        if views.is_empty() {
+            return Ok(());
+        }
+        let info = self.info.clone();
+        self.call("refresh_views", ReducerPriority::Normal, move |inst| {
+            views.refresh(&modified, |sub| {
+                // Look the view up by name, as the module may have been updated since the client subscribed.
+                let (view_id, _) = info
+                    .module_def
+                    .view_full(&*sub.view)
+                    .with_context(|| format!("no such view `{}`", sub.view))?;
+                inst.call_view(CallViewParams {
+                    timestamp: Timestamp::now(),
+                    caller_identity: sub.client.id.identity,
+                    caller_address: sub.client.id.address,
+                    view_id,
+                    args: sub.args.clone(),
+                    page: Some(sub.page.clone()),
+                })
+            })
+        })
+        .await
+    }
+
     // Scheduled reducers require a different function here to call their reducer
     // because their reducer arguments are stored in the database and need to be fetched
     // within the same transaction as the reducer call.
     pub async fn call_scheduled_reducer(
         &self,
+        priority: ReducerPriority,
         call_reducer_params: impl FnOnce(&MutTxId) -> anyhow::Result<Option<CallReducerParams>> + Send + 'static,
     ) -> Result<ReducerCallResult, ReducerCallError> {
         let db = self.inner.replica_ctx().relational_db.clone();
         // scheduled reducer name not fetched yet, anyway this is only for logging purpose
         const REDUCER: &str = "scheduled_reducer";
         let module = self.info.clone();
-        self.call(REDUCER, move |inst: &mut dyn ModuleInstance| {
+        self.call(REDUCER, priority, move |inst: &mut dyn ModuleInstance| {
             let mut tx = db.begin_mut_tx(IsolationLevel::Serializable, Workload::Internal);
 
             match call_reducer_params(&mut tx) {
@@ -778,9 +1268,11 @@ impl ModuleHost {
     }
 
     pub async fn init_database(&self, program: Program) -> Result<Option<ReducerCallResult>, InitDatabaseError> {
-        self.call("<init_database>", move |inst| inst.init_database(program))
-            .await?
-            .map_err(InitDatabaseError::Other)
+        self.call("<init_database>", ReducerPriority::Normal, move |inst| {
+            inst.init_database(program)
+        })
+        .await?
+        .map_err(InitDatabaseError::Other)
     }
 
     pub async fn update_database(
@@ -788,7 +1280,7 @@ impl ModuleHost {
         program: Program,
         old_module_info: Arc<ModuleInfo>,
     ) -> Result<UpdateDatabaseResult, anyhow::Error> {
-        self.call("<update_database>", move |inst| {
+        self.call("<update_database>", ReducerPriority::Normal, move |inst| {
             inst.update_database(program, old_module_info)
         })
         .await?
@@ -832,6 +1324,7 @@ impl ModuleHost {
             let tx = SchemaViewer::new(tx, &auth);
             let plan = SubscribePlan::compile(&query, &tx)?;
             check_row_limit(&plan, db, &tx, |plan, tx| estimate_rows_scanned(tx, plan), &auth)?;
+            db.index_advisor().record_plan(&tx, &plan);
             plan.execute::<_, F>(&DeltaTx::from(&*tx))
                 .map(|(rows, _)| OneOffTable {
                     table_name: plan.table_name().to_owned().into_boxed_str(),
@@ -861,6 +1354,101 @@ impl ModuleHost {
         })
     }
 
+    /// Insert the rows of the file `data` into the table `table_name`, bypassing reducers.
+    ///
+    /// The file is decoded, and its columns mapped onto those of the table, as described in [`import`].
+    /// Its rows are inserted in transactions of about [`IMPORT_TX_ROWS`] rows each,
+    /// each of which is broadcast to subscribers.
+    /// If inserting fails, the transactions committed so far are kept.
+    ///
+    /// With `options.dry_run`, every row of the file is decoded, but none are inserted.
+    pub fn import_table(
+        &self,
+        table_name: &str,
+        data: Bytes,
+        options: &ImportOptions,
+    ) -> Result<ImportSummary, ImportError> {
+        let db = &*self.replica_ctx().relational_db;
+
+        let schema = db.with_read_only(Workload::Import, |tx| -> Result<_, ImportError> {
+            let table_id = db
+                .table_id_from_name(tx, table_name)?
+                .ok_or_else(|| ImportError::NoSuchTable(table_name.into()))?;
+            Ok(db.schema_for_table(tx, table_id)?)
+        })?;
+        if schema.table_type == StTableType::System {
+            return Err(ImportError::SystemTable(table_name.into()));
+        }
+
+        let (source, batches) = import::read_batches(options.format, data)?;
+        let mapping = ColumnMapping::new(&source, &schema, &options.renames)?;
+        let mut summary = mapping.summary();
+        let mut rows = Vec::new();
+        for batch in batches {
+            rows.extend(mapping.rows(&batch?, summary.rows + rows.len() as u64)?);
+            if rows.len() >= IMPORT_TX_ROWS {
+                self.commit_imported_rows(schema.table_id, mem::take(&mut rows), options.dry_run, &mut summary)?;
+            }
+        }
+        if !rows.is_empty() {
+            self.commit_imported_rows(schema.table_id, rows, options.dry_run, &mut summary)?;
+        }
+        Ok(summary)
+    }
+
+    /// Insert `rows` into the table `table_id` in a single transaction,
+    /// unless `dry_run`, and count them in `summary`.
+    fn commit_imported_rows(
+        &self,
+        table_id: TableId,
+        rows: Vec<ProductValue>,
+        dry_run: bool,
+        summary: &mut ImportSummary,
+    ) -> Result<(), ImportError> {
+        if !dry_run {
+            let db = &*self.replica_ctx().relational_db;
+            let committed_rows = summary.rows;
+
+            let mut tx = db.begin_mut_tx(IsolationLevel::Serializable, Workload::Import);
+            let mut buf = Vec::new();
+            let inserted = rows.iter().try_for_each(|row| {
+                buf.clear();
+                row.encode(&mut buf);
+                db.insert(&mut tx, table_id, &buf).map(drop)
+            });
+            if let Err(source) = inserted {
+                db.rollback_mut_tx(tx);
+                return Err(ImportError::Insert { committed_rows, source });
+            }
+
+            let event = ModuleEvent {
+                timestamp: Timestamp::now(),
+                caller_identity: self.info.owner_identity,
+                caller_address: None,
+                function_call: ModuleFunctionCall::default(),
+                status: EventStatus::Committed(DatabaseUpdate::default()),
+                energy_quanta_used: EnergyQuanta::ZERO,
+                host_execution_duration: Duration::ZERO,
+                tx_offset: None,
+                request_id: None,
+                timer: None,
+            };
+            let event = match self.info.subscriptions.commit_and_broadcast_event(None, event, tx)? {
+                Ok(event) => event,
+                Err(WriteConflict) => todo!("See module_host_actor::call_reducer_with_tx"),
+            };
+            if let EventStatus::Failed(reason) = &event.status {
+                return Err(ImportError::Rejected {
+                    committed_rows,
+                    reason: reason.clone(),
+                });
+            }
+            summary.transactions += 1;
+        }
+        summary.rows += rows.len() as u64;
+        Ok(())
+    }
+
     pub fn downgrade(&self) -> WeakModuleHost {
         WeakModuleHost {
             info: self.info.clone(),
@@ -873,6 +1461,27 @@ impl ModuleHost {
         &self.replica_ctx().database
     }
 
+    /// Returns an error if the database has reached its limit of connected clients.
+    ///
+    /// The limit is enforced when the client connects;
+    /// this lets callers refuse a connection before doing any work for it.
+    pub fn check_connection_quota(&self) -> Result<(), QuotaExceeded> {
+        self.replica_ctx().try_connect().map(drop)
+    }
+
+    /// Suggest btree indexes for the columns which queries have filtered on
+    /// while scanning entire tables, ordered by their estimated benefit.
+    pub fn advise_indexes(&self) -> Result<Vec<IndexAdvice>, DBError> {
+        let db = &*self.replica_ctx().relational_db;
+        db.with_read_only(Workload::Internal, |tx| db.advise_indexes(tx))
+    }
+
+    /// Wait until the transaction at `tx_offset` has been committed by this replica,
+    /// so that reads issued afterwards observe its effects.
+    pub async fn wait_for_tx_offset(&self, tx_offset: u64) -> Result<(), TxOffsetTimeout> {
+        self.replica_ctx().relational_db.wait_for_tx_offset(tx_offset).await
+    }
+
     pub(crate) fn replica_ctx(&self) -> &ReplicaContext {
         self.inner.replica_ctx()
     }
@@ -1,17 +1,22 @@
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::anyhow;
 use futures::StreamExt;
-use rustc_hash::FxHashMap;
+use rand::Rng;
+use rustc_hash::{FxHashMap, FxHashSet};
 use spacetimedb_client_api_messages::energy::EnergyQuanta;
 use spacetimedb_client_api_messages::timestamp::Timestamp;
+use spacetimedb_lib::db::raw_def::v9::{CatchUpPolicy, ReducerPriority};
 use spacetimedb_lib::scheduler::ScheduleAt;
 use spacetimedb_lib::Address;
 use spacetimedb_primitives::{ColId, TableId};
 use spacetimedb_sats::{bsatn::ToBsatn as _, AlgebraicValue};
 use spacetimedb_table::table::RowRef;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
 use tokio_util::time::delay_queue::Expired;
 use tokio_util::time::{delay_queue, DelayQueue};
 
@@ -53,6 +58,7 @@ enum MsgOrExit<T> {
 enum SchedulerMessage {
     Schedule { id: ScheduledReducerId, at: ScheduleAt },
     ScheduleImmediate { reducer_name: String, args: ReducerArgs },
+    AdvanceTime { by: Duration, done: oneshot::Sender<()> },
 }
 
 pub struct ScheduledReducer {
@@ -60,28 +66,78 @@ pub struct ScheduledReducer {
     bsatn_args: Vec<u8>,
 }
 
+/// The clock of a database, which reads the system clock moved forward by an offset.
+///
+/// The offset is zero unless a test moves the clock forward with [`Scheduler::advance_time`].
+/// Both the scheduler and the timestamps of reducer calls read this clock,
+/// so that a module sees time pass as its scheduled reducers do.
+#[derive(Default)]
+pub struct Clock {
+    /// The offset, in microseconds.
+    offset: AtomicU64,
+}
+
+impl Clock {
+    /// Returns the current time on this clock.
+    pub fn now(&self) -> Timestamp {
+        self.shift(Timestamp::now())
+    }
+
+    /// Moves `timestamp`, read from the system clock, forward by the offset of this clock.
+    pub fn shift(&self, timestamp: Timestamp) -> Timestamp {
+        let offset = self.offset.load(Ordering::Relaxed);
+        Timestamp::from_microseconds(timestamp.microseconds.saturating_add(offset))
+    }
+
+    /// Returns how long from now on this clock `at` is.
+    fn delay_until(&self, at: &ScheduleAt) -> Duration {
+        at.to_duration_from(self.now().to_systemtime())
+    }
+
+    fn advance(&self, by: Duration) {
+        let by = by.as_micros().try_into().unwrap_or(u64::MAX);
+        self.offset.fetch_add(by, Ordering::Relaxed);
+    }
+}
+
 #[derive(Clone)]
 pub struct Scheduler {
     tx: mpsc::UnboundedSender<MsgOrExit<SchedulerMessage>>,
+    clock: Arc<Clock>,
 }
 
 pub struct SchedulerStarter {
     rx: mpsc::UnboundedReceiver<MsgOrExit<SchedulerMessage>>,
     db: Arc<RelationalDB>,
+    clock: Arc<Clock>,
 }
 
 impl Scheduler {
-    pub fn open(db: Arc<RelationalDB>) -> (Self, SchedulerStarter) {
+    pub fn open(db: Arc<RelationalDB>, clock: Arc<Clock>) -> (Self, SchedulerStarter) {
         let (tx, rx) = mpsc::unbounded_channel();
-        (Scheduler { tx }, SchedulerStarter { rx, db })
+        (
+            Scheduler {
+                tx,
+                clock: clock.clone(),
+            },
+            SchedulerStarter { rx, db, clock },
+        )
     }
 }
 
 impl SchedulerStarter {
     // TODO(cloutiertyler): This whole start dance is scuffed, but I don't have
     // time to make it better right now.
-    pub fn start(mut self, module_host: &ModuleHost) -> anyhow::Result<()> {
-        let mut queue: DelayQueue<QueueItem> = DelayQueue::new();
+    pub fn start(self, module_host: &ModuleHost) -> anyhow::Result<()> {
+        let mut actor = SchedulerActor {
+            rx: self.rx,
+            queue: DelayQueue::new(),
+            key_map: FxHashMap::default(),
+            priorities: FxHashMap::default(),
+            module_host: module_host.downgrade(),
+            clock: self.clock,
+            advances: Vec::new(),
+        };
 
         let tx = self.db.begin_tx(Workload::Internal);
 
@@ -92,42 +148,74 @@ impl SchedulerStarter {
         // there will be an in-flight message in tx that has already been inserted into the DB.
         // We are building the `queue` below with the DB and then spawning `SchedulerActor`, which will processes
         // the in-flight message, resulting in a duplicate entry in the queue.
-        while self.rx.try_recv().is_ok() {}
+        while actor.rx.try_recv().is_ok() {}
+
+        // Rows whose time passed while the database was offline, and which the table's policy says not to call.
+        let mut skipped = Vec::new();
 
         // Find all Scheduled tables
         for st_scheduled_row in self.db.iter(&tx, ST_SCHEDULED_ID)? {
             let table_id = st_scheduled_row.read_col(StScheduledFields::TableId)?;
+            let reducer = st_scheduled_row.read_col::<Box<str>>(StScheduledFields::ReducerName)?;
+            actor
+                .priorities
+                .insert(table_id, reducer_priority(module_host, &reducer));
             let (id_column, at_column) = self
                 .db
                 .table_scheduled_id_and_at(&tx, table_id)?
                 .ok_or_else(|| anyhow!("scheduled table {table_id} doesn't have valid columns"))?;
 
+            let catch_up = self
+                .db
+                .table_name_from_id(&tx, table_id)?
+                .and_then(|name| Some(module_host.info().module_def.table(&*name)?.schedule.as_ref()?.catch_up))
+                .unwrap_or(CatchUpPolicy::FireAll);
+
             // Insert each entry (row) in the scheduled table into `queue`.
+            let mut missed = Vec::new();
             for scheduled_row in self.db.iter(&tx, table_id)? {
                 let (schedule_id, schedule_at) = get_schedule_from_row(&scheduled_row, id_column, at_column)?;
-                // calculate duration left to call the scheduled reducer
-                let duration = schedule_at.to_duration_from_now();
-                queue.insert(
-                    QueueItem::Id(ScheduledReducerId {
-                        table_id,
-                        schedule_id,
-                        id_column,
-                        at_column,
-                    }),
-                    duration,
-                );
+                let id = ScheduledReducerId {
+                    table_id,
+                    schedule_id,
+                    id_column,
+                    at_column,
+                };
+                match schedule_at {
+                    ScheduleAt::Time(time)
+                        if catch_up != CatchUpPolicy::FireAll && actor.clock.delay_until(&schedule_at).is_zero() =>
+                    {
+                        missed.push((time, id));
+                    }
+                    _ => actor.schedule(id, schedule_at),
+                }
             }
-        }
 
-        tokio::spawn(
-            SchedulerActor {
-                rx: self.rx,
-                queue,
-                key_map: FxHashMap::default(),
-                module_host: module_host.downgrade(),
+            if catch_up == CatchUpPolicy::FireOnce {
+                // Call the most recently missed row only.
+                missed.sort_by_key(|(time, _)| *time);
+                if let Some((time, id)) = missed.pop() {
+                    actor.schedule(id, ScheduleAt::Time(time));
+                }
             }
-            .run(),
-        );
+            skipped.extend(missed.into_iter().map(|(_, id)| id));
+        }
+        self.db.release_tx(tx);
+
+        if !skipped.is_empty() {
+            log::info!(
+                "skipping {} scheduled calls missed while the database was offline",
+                skipped.len()
+            );
+            let mut tx = self.db.begin_mut_tx(IsolationLevel::Serializable, Workload::Internal);
+            for id in skipped {
+                let row_ptr = get_schedule_row_mut(&tx, &self.db, id)?.pointer();
+                self.db.delete(&mut tx, id.table_id, [row_ptr]);
+            }
+            commit_and_broadcast_deletion_event(tx, module_host.clone());
+        }
+
+        tokio::spawn(actor.run());
 
         Ok(())
     }
@@ -203,7 +291,10 @@ impl Scheduler {
         // rather than `SystemTime`,
         // but we don't currently have a meaningful way
         // to convert a `Timestamp` into an `Instant`.
-        let delay = schedule_at.to_duration_from_now();
+        let delay = self
+            .clock
+            .delay_until(&schedule_at)
+            .saturating_add(schedule_at.jitter());
         if delay >= MAX_SCHEDULE_DELAY {
             return Err(ScheduleError::DelayTooLong(delay));
         }
@@ -230,6 +321,24 @@ impl Scheduler {
         }));
     }
 
+    /// Move the [`Clock`] of the database forward by `by`,
+    /// and run every scheduled reducer which comes due within that window right away,
+    /// returning once they have all run.
+    ///
+    /// This is meant for tests of scheduled reducers with long delays.
+    /// A repeating reducer runs at most once per call, no matter how many intervals `by` spans.
+    pub async fn advance_time(&self, by: Duration) {
+        let (done, ran) = oneshot::channel();
+        if self
+            .tx
+            .send(MsgOrExit::Msg(SchedulerMessage::AdvanceTime { by, done }))
+            .is_ok()
+        {
+            // The scheduler exited if `done` was dropped, in which case there's nothing left to wait for.
+            let _ = ran.await;
+        }
+    }
+
     pub fn close(&self) {
         let _ = self.tx.send(MsgOrExit::Exit);
     }
@@ -238,8 +347,29 @@ impl Scheduler {
 struct SchedulerActor {
     rx: mpsc::UnboundedReceiver<MsgOrExit<SchedulerMessage>>,
     queue: DelayQueue<QueueItem>,
-    key_map: FxHashMap<ScheduledReducerId, delay_queue::Key>,
+    key_map: FxHashMap<ScheduledReducerId, QueuedSchedule>,
+    /// The priority of the reducer called by each scheduled table.
+    priorities: FxHashMap<TableId, ReducerPriority>,
     module_host: WeakModuleHost,
+    clock: Arc<Clock>,
+    /// The calls of [`Scheduler::advance_time`] which still wait for calls to come due.
+    advances: Vec<PendingAdvance>,
+}
+
+/// A call of [`Scheduler::advance_time`] waiting for the calls it made due to run.
+struct PendingAdvance {
+    due: FxHashSet<ScheduledReducerId>,
+    done: oneshot::Sender<()>,
+}
+
+/// Returns the priority of the reducer named `reducer`,
+/// or the default priority if the module has no such reducer.
+fn reducer_priority(module_host: &ModuleHost, reducer: &str) -> ReducerPriority {
+    module_host
+        .info()
+        .module_def
+        .reducer(reducer)
+        .map_or_else(Default::default, |reducer| reducer.priority)
 }
 
 enum QueueItem {
@@ -247,7 +377,47 @@ enum QueueItem {
     VolatileNonatomicImmediate { reducer_name: String, args: ReducerArgs },
 }
 
+struct QueuedSchedule {
+    key: delay_queue::Key,
+    /// When the call is due, before adding jitter.
+    /// Fixed-rate schedules are due again a whole number of periods after this.
+    due: Instant,
+}
+
+/// Returns a random delay of at most `max`.
+fn random_jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    rand::thread_rng().gen_range(Duration::ZERO..=max)
+}
+
+/// Returns the first instant after `now` which is a whole number of `period`s after `prev`,
+/// skipping the calls which fell due while the previous one was running.
+fn next_fixed_rate_due(prev: Instant, period: Duration, now: Instant) -> Instant {
+    if period.is_zero() {
+        return now;
+    }
+    let periods = now.saturating_duration_since(prev).as_nanos() / period.as_nanos() + 1;
+    prev + Duration::from_nanos((period.as_nanos() * periods) as u64)
+}
+
 impl SchedulerActor {
+    /// Queue the call `id`, due at `schedule_at`.
+    fn schedule(&mut self, id: ScheduledReducerId, schedule_at: ScheduleAt) {
+        self.schedule_at(
+            id,
+            Instant::now() + self.clock.delay_until(&schedule_at),
+            schedule_at.jitter(),
+        );
+    }
+
+    /// Queue the call `id`, due at `due` plus a random delay of at most `jitter`.
+    fn schedule_at(&mut self, id: ScheduledReducerId, due: Instant, jitter: Duration) {
+        let key = self.queue.insert_at(QueueItem::Id(id), due + random_jitter(jitter));
+        self.key_map.insert(id, QueuedSchedule { key, due });
+    }
+
     async fn run(mut self) {
         loop {
             tokio::select! {
@@ -268,11 +438,12 @@ impl SchedulerActor {
         match msg {
             SchedulerMessage::Schedule { id, at } => {
                 // Incase of row update, remove the existing entry from queue first
-                if let Some(key) = self.key_map.get(&id) {
-                    self.queue.remove(key);
+                if let Some(queued) = self.key_map.get(&id) {
+                    self.queue.remove(&queued.key);
                 }
-                let key = self.queue.insert(QueueItem::Id(id), at.to_duration_from_now());
-                self.key_map.insert(id, key);
+                // The call is no longer due when an earlier advance of the clock says it is.
+                self.settle(id);
+                self.schedule(id, at);
             }
             SchedulerMessage::ScheduleImmediate { reducer_name, args } => {
                 self.queue.insert(
@@ -280,25 +451,75 @@ impl SchedulerActor {
                     Duration::ZERO,
                 );
             }
+            SchedulerMessage::AdvanceTime { by, done } => {
+                self.clock.advance(by);
+                let now = Instant::now();
+                let mut due = FxHashSet::default();
+                for (id, queued) in &mut self.key_map {
+                    let remaining = self
+                        .queue
+                        .deadline(&queued.key)
+                        .saturating_duration_since(now)
+                        .saturating_sub(by);
+                    self.queue.reset(&queued.key, remaining);
+                    queued.due = now + queued.due.saturating_duration_since(now).saturating_sub(by);
+                    if remaining.is_zero() {
+                        due.insert(*id);
+                    }
+                }
+                if due.is_empty() {
+                    let _ = done.send(());
+                } else {
+                    self.advances.push(PendingAdvance { due, done });
+                }
+            }
+        }
+    }
+
+    /// Stop waiting for the call `id` in the pending advances of the clock,
+    /// completing those which waited for it last.
+    fn settle(&mut self, id: ScheduledReducerId) {
+        for advance in &mut self.advances {
+            advance.due.remove(&id);
+        }
+        let (done, pending): (Vec<_>, Vec<_>) = mem::take(&mut self.advances)
+            .into_iter()
+            .partition(|advance| advance.due.is_empty());
+        self.advances = pending;
+        for advance in done {
+            let _ = advance.done.send(());
         }
     }
 
     async fn handle_queued(&mut self, id: Expired<QueueItem>) {
+        let deadline = id.deadline();
         let item = id.into_inner();
         let id = match item {
             QueueItem::Id(id) => Some(id),
             QueueItem::VolatileNonatomicImmediate { .. } => None,
         };
-        if let Some(id) = id {
-            self.key_map.remove(&id);
-        }
+        let due = id
+            .and_then(|id| self.key_map.remove(&id))
+            .map_or(deadline, |queued| queued.due);
 
         let Some(module_host) = self.module_host.upgrade() else {
             return;
         };
+        if let Some(wait) = module_host.replica_ctx().cpu_quota_wait() {
+            // Put the call off until the database is back under its CPU quota.
+            let key = self.queue.insert(item, wait);
+            if let Some(id) = id {
+                self.key_map.insert(id, QueuedSchedule { key, due });
+            }
+            return;
+        }
         let db = module_host.replica_ctx().relational_db.clone();
         let caller_identity = module_host.info().database_identity;
         let module_info = module_host.info.clone();
+        let priority = match &item {
+            QueueItem::Id(id) => self.priorities.get(&id.table_id).copied().unwrap_or_default(),
+            QueueItem::VolatileNonatomicImmediate { reducer_name, .. } => reducer_priority(&module_host, reducer_name),
+        };
 
         let call_reducer_params = move |tx: &MutTxId| -> Result<Option<CallReducerParams>, anyhow::Error> {
             let id = match item {
@@ -315,6 +536,8 @@ impl SchedulerActor {
                         caller_identity,
                         caller_address: Address::default(),
                         client: None,
+                        caller_metadata: None,
+                        caller_session: None,
                         request_id: None,
                         timer: None,
                         reducer_id,
@@ -347,6 +570,8 @@ impl SchedulerActor {
                 caller_identity,
                 caller_address: Address::default(),
                 client: None,
+                caller_metadata: None,
+                caller_session: None,
                 request_id: None,
                 timer: None,
                 reducer_id,
@@ -357,7 +582,8 @@ impl SchedulerActor {
         let db = module_host.replica_ctx().relational_db.clone();
         let module_host_clone = module_host.clone();
 
-        let res = tokio::spawn(async move { module_host.call_scheduled_reducer(call_reducer_params).await }).await;
+        let res =
+            tokio::spawn(async move { module_host.call_scheduled_reducer(priority, call_reducer_params).await }).await;
 
         match res {
             // if we didn't actually call the reducer because the module exited or it was already deleted, leave
@@ -367,10 +593,13 @@ impl SchedulerActor {
             // delete the scheduled reducer row if its not repeated reducer
             Ok(_) | Err(_) => {
                 if let Some(id) = id {
-                    self.delete_scheduled_reducer_row(&db, id, module_host_clone).await;
+                    self.delete_scheduled_reducer_row(&db, id, due, module_host_clone).await;
                 }
             }
         }
+        if let Some(id) = id {
+            self.settle(id);
+        }
 
         if let Err(e) = res {
             log::error!("invoking scheduled reducer failed: {e:#}");
@@ -382,13 +611,20 @@ impl SchedulerActor {
     fn handle_repeated_schedule(
         &mut self,
         id: ScheduledReducerId,
+        due: Instant,
         schedule_row: &RowRef<'_>,
     ) -> Result<bool, anyhow::Error> {
         let schedule_at = read_schedule_at(schedule_row, id.at_column)?;
 
-        if let ScheduleAt::Interval(dur) = schedule_at {
-            let key = self.queue.insert(QueueItem::Id(id), Duration::from_micros(dur));
-            self.key_map.insert(id, key);
+        if let ScheduleAt::Interval { period, fixed_rate, .. } = schedule_at {
+            let period = Duration::from_micros(period);
+            let now = Instant::now();
+            let due = if fixed_rate {
+                next_fixed_rate_due(due, period, now)
+            } else {
+                now + period
+            };
+            self.schedule_at(id, due, schedule_at.jitter());
             Ok(true)
         } else {
             Ok(false)
@@ -399,13 +635,14 @@ impl SchedulerActor {
         &mut self,
         db: &RelationalDB,
         id: ScheduledReducerId,
+        due: Instant,
         module_host: ModuleHost,
     ) {
         let mut tx = db.begin_mut_tx(IsolationLevel::Serializable, Workload::Internal);
 
         match get_schedule_row_mut(&tx, db, id) {
             Ok(schedule_row) => {
-                if let Ok(is_repeated) = self.handle_repeated_schedule(id, &schedule_row) {
+                if let Ok(is_repeated) = self.handle_repeated_schedule(id, due, &schedule_row) {
                     if is_repeated {
                         return; // Do not delete entry for repeated reducer
                     }
@@ -439,6 +676,7 @@ fn commit_and_broadcast_deletion_event(tx: MutTxId, module_host: ModuleHost) {
         //Keeping them 0 as it is internal transaction, not by reducer
         energy_quanta_used: EnergyQuanta { quanta: 0 },
         host_execution_duration: Duration::from_millis(0),
+        tx_offset: None,
         request_id: None,
         timer: None,
     };
@@ -7,6 +7,7 @@ use std::num::NonZeroU16;
 use std::time::Instant;
 
 use super::{scheduler::ScheduleError, AbiCall};
+use crate::db::blob::BlobError;
 use crate::error::{DBError, IndexError, NodesError};
 use spacetimedb_primitives::errno;
 use spacetimedb_sats::typespace::TypeRefError;
@@ -14,6 +15,9 @@ use spacetimedb_table::table::UniqueConstraintViolation;
 
 pub const CALL_REDUCER_DUNDER: &str = "__call_reducer__";
 
+/// Exported only by modules which define views.
+pub const CALL_VIEW_DUNDER: &str = "__call_view__";
+
 pub const DESCRIBE_MODULE_DUNDER: &str = "__describe_module__";
 
 /// functions with this prefix run prior to __setup__, initializing global variables and the like
@@ -344,10 +348,14 @@ pub fn err_to_errno(err: &NodesError) -> Option<NonZeroU16> {
         NodesError::DecodeRow(_) => Some(errno::BSATN_DECODE_ERROR),
         NodesError::TableNotFound => Some(errno::NO_SUCH_TABLE),
         NodesError::IndexNotFound => Some(errno::NO_SUCH_INDEX),
+        NodesError::SequenceNotFound => Some(errno::NO_SUCH_SEQUENCE),
+        NodesError::AssetNotFound(_) => Some(errno::NO_SUCH_ASSET),
         NodesError::IndexNotUnique => Some(errno::INDEX_NOT_UNIQUE),
         NodesError::IndexRowNotFound => Some(errno::NO_SUCH_ROW),
         NodesError::ScheduleError(ScheduleError::DelayTooLong(_)) => Some(errno::SCHEDULE_AT_DELAY_TOO_LONG),
         NodesError::AlreadyExists(_) => Some(errno::UNIQUE_ALREADY_EXISTS),
+        NodesError::Blob(BlobError::NotABlobTable(_)) => Some(errno::NOT_A_BLOB_TABLE),
+        NodesError::Blob(BlobError::OutOfBounds { .. }) => Some(errno::BLOB_OUT_OF_BOUNDS),
         NodesError::Internal(internal) => match **internal {
             DBError::Index(IndexError::UniqueConstraintViolation(UniqueConstraintViolation {
                 constraint_name: _,
@@ -389,6 +397,22 @@ macro_rules! abi_funcs {
             "spacetime_10.0"::datastore_btree_scan_bsatn,
             "spacetime_10.0"::datastore_delete_by_btree_scan_bsatn,
             "spacetime_10.0"::identity,
+            "spacetime_10.1"::savepoint_begin,
+            "spacetime_10.1"::savepoint_rollback,
+            "spacetime_10.1"::savepoint_release,
+            "spacetime_10.2"::blob_len,
+            "spacetime_10.2"::blob_read,
+            "spacetime_10.2"::blob_write,
+            "spacetime_10.2"::blob_delete,
+            "spacetime_10.3"::datastore_table_truncate,
+            "spacetime_10.4"::sequence_peek,
+            "spacetime_10.4"::sequence_advance_past,
+            "spacetime_10.5"::broadcast,
+            "spacetime_10.6"::asset_len,
+            "spacetime_10.6"::asset_read,
+            "spacetime_10.7"::caller_metadata,
+            "spacetime_10.8"::session_get,
+            "spacetime_10.9"::datastore_batch_bsatn,
 
             // unstable:
             "spacetime_10.0"::volatile_nonatomic_schedule_immediate,
@@ -2,7 +2,7 @@ use anyhow::Context;
 use bytes::Bytes;
 use spacetimedb_client_api_messages::timestamp::Timestamp;
 use spacetimedb_lib::db::raw_def::v9::Lifecycle;
-use spacetimedb_primitives::TableId;
+use spacetimedb_primitives::{TableId, ViewId};
 use spacetimedb_schema::auto_migrate::ponder_migrate;
 use spacetimedb_schema::def::ModuleDef;
 use spacetimedb_schema::schema::{Schema, TableSchema};
@@ -10,15 +10,19 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use super::instrumentation::CallTimes;
+use crate::client::messages::TopicMessage;
+use crate::client::SessionVariables;
 use crate::database_logger::SystemLogger;
 use crate::db::datastore::locking_tx_datastore::MutTxId;
-use crate::db::datastore::system_tables::{StClientRow, ST_CLIENT_ID};
+use crate::db::datastore::system_tables::{StClientRow, StConnectionRow, ST_CLIENT_ID, ST_CONNECTION_ID};
 use crate::db::datastore::traits::{IsolationLevel, Program};
 use crate::energy::{EnergyMonitor, EnergyQuanta, ReducerBudget, ReducerFingerprint};
 use crate::execution_context::{self, ReducerContext, Workload};
+use crate::host::assets::Assets;
 use crate::host::instance_env::InstanceEnv;
 use crate::host::module_host::{
-    CallReducerParams, DatabaseUpdate, EventStatus, Module, ModuleEvent, ModuleFunctionCall, ModuleInfo, ModuleInstance,
+    CallReducerParams, CallViewParams, DatabaseUpdate, EventStatus, Module, ModuleEvent, ModuleFunctionCall,
+    ModuleInfo, ModuleInstance, ReducerErrorValue, ViewCallResult,
 };
 use crate::host::{ArgsTuple, ReducerCallResult, ReducerId, ReducerOutcome, Scheduler, UpdateDatabaseResult};
 use crate::identity::Identity;
@@ -27,12 +31,16 @@ use crate::module_host_context::ModuleCreationContext;
 use crate::replica_context::ReplicaContext;
 use crate::sql::parser::RowLevelExpr;
 use crate::subscription::module_subscription_actor::WriteConflict;
+use crate::subscription::view_subscriptions;
 use crate::util::const_unwrap;
 use crate::util::prometheus_handle::HistogramExt;
 use crate::worker_metrics::WORKER_METRICS;
 use spacetimedb_lib::buffer::DecodeError;
+use spacetimedb_lib::client_metadata::ClientMetadata;
+use spacetimedb_lib::de::DeserializeSeed;
 use spacetimedb_lib::identity::AuthCtx;
-use spacetimedb_lib::{bsatn, Address, RawModuleDef};
+use spacetimedb_lib::{bsatn, Address, AlgebraicValue, ProductValue, RawModuleDef};
+use spacetimedb_sats::product;
 
 use super::*;
 
@@ -40,9 +48,9 @@ pub trait WasmModule: Send + 'static {
     type Instance: WasmInstance;
     type InstancePre: WasmInstancePre<Instance = Self::Instance>;
 
-    type ExternType: FuncSigLike;
-    fn get_export(&self, s: &str) -> Option<Self::ExternType>;
-    fn for_each_export<E>(&self, f: impl FnMut(&str, &Self::ExternType) -> Result<(), E>) -> Result<(), E>;
+    /// Check that the module exports the functions the host requires,
+    /// and collect the names of the optional ones it exports.
+    fn func_names(&self) -> Result<FuncNames, ValidationError>;
 
     fn instantiate_pre(&self) -> Result<Self::InstancePre, InitializationError>;
 }
@@ -61,7 +69,12 @@ pub trait WasmInstance: Send + Sync + 'static {
 
     fn call_reducer(&mut self, op: ReducerOp<'_>, budget: ReducerBudget) -> ExecuteResult<Self::Trap>;
 
+    fn call_view(&mut self, op: ViewOp<'_>, budget: ReducerBudget) -> ViewExecuteResult<Self::Trap>;
+
     fn log_traceback(func_type: &str, func: &str, trap: &Self::Trap);
+
+    /// Returns whether `trap` was caused by the call running past its deadline.
+    fn timed_out(trap: &Self::Trap) -> bool;
 }
 
 pub struct EnergyStats {
@@ -77,7 +90,25 @@ pub struct ExecutionTimings {
 pub struct ExecuteResult<E> {
     pub energy: EnergyStats,
     pub timings: ExecutionTimings,
-    pub call_result: Result<Result<(), Box<str>>, E>,
+    pub call_result: Result<Result<(), ReducerFailure>, E>,
+    /// The ephemeral messages the reducer broadcast,
+    /// to be sent to subscribed clients if its transaction commits.
+    pub broadcasts: Vec<TopicMessage>,
+}
+
+/// The error a reducer returned.
+pub enum ReducerFailure {
+    /// An error message.
+    Message(Box<str>),
+    /// A BSATN-encoded value of the reducer's error type.
+    Value(Bytes),
+}
+
+pub struct ViewExecuteResult<E> {
+    pub energy: EnergyStats,
+    pub timings: ExecutionTimings,
+    /// The BSATN-encoded result of the view, or the error it returned.
+    pub call_result: Result<Result<Vec<u8>, Box<str>>, E>,
 }
 
 pub(crate) struct WasmModuleHostActor<T: WasmModule> {
@@ -108,6 +139,8 @@ pub enum InitializationError {
     Instantiation(anyhow::Error),
     #[error("error getting module description: {0}")]
     Describe(#[from] DescribeError),
+    #[error("error decoding asset bundle: {0}")]
+    Assets(DecodeError),
 }
 
 impl From<TypeRefError> for InitializationError {
@@ -144,14 +177,13 @@ impl<T: WasmModule> WasmModuleHostActor<T> {
         );
         let log_tx = replica_context.logger.tx.clone();
 
-        FuncNames::check_required(|name| module.get_export(name))?;
-        let mut func_names = FuncNames::default();
-        module.for_each_export(|sym, ty| func_names.update_from_general(sym, ty))?;
-        func_names.preinits.sort_unstable();
+        let assets = Arc::new(Assets::from_program(&program.bytes).map_err(InitializationError::Assets)?);
+
+        let func_names = module.func_names()?;
 
         let uninit_instance = module.instantiate_pre()?;
         let mut instance = uninit_instance.instantiate(
-            InstanceEnv::new(replica_context.clone(), scheduler.clone()),
+            InstanceEnv::new(replica_context.clone(), scheduler.clone(), assets.clone()),
             &func_names,
         )?;
 
@@ -169,6 +201,7 @@ impl<T: WasmModule> WasmModuleHostActor<T> {
             module_hash,
             log_tx,
             replica_context.subscriptions.clone(),
+            assets,
         );
 
         let func_names = Arc::new(func_names);
@@ -212,7 +245,11 @@ impl<T: WasmModule> Module for WasmModuleHostActor<T> {
     }
 
     fn create_instance(&self) -> Self::Instance {
-        let env = InstanceEnv::new(self.replica_context.clone(), self.scheduler.clone());
+        let env = InstanceEnv::new(
+            self.replica_context.clone(),
+            self.scheduler.clone(),
+            self.info.assets.clone(),
+        );
         // this shouldn't fail, since we already called module.create_instance()
         // before and it didn't error, and ideally they should be deterministic
         let mut instance = self
@@ -266,7 +303,7 @@ impl<T: WasmInstance> ModuleInstance for WasmModuleInstance<T> {
     )]
     fn init_database(&mut self, program: Program) -> anyhow::Result<Option<ReducerCallResult>> {
         log::debug!("init database");
-        let timestamp = Timestamp::now();
+        let timestamp = self.replica_context().clock.now();
         let stdb = &*self.replica_context().relational_db;
 
         let tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::Internal);
@@ -319,6 +356,8 @@ impl<T: WasmInstance> ModuleInstance for WasmModuleInstance<T> {
                         caller_identity,
                         caller_address: Address::__DUMMY,
                         client: None,
+                        caller_metadata: None,
+                        caller_session: None,
                         request_id: None,
                         timer: None,
                         reducer_id,
@@ -375,6 +414,10 @@ impl<T: WasmInstance> ModuleInstance for WasmModuleInstance<T> {
     fn call_reducer(&mut self, tx: Option<MutTxId>, params: CallReducerParams) -> ReducerCallResult {
         crate::callgrind_flag::invoke_allowing_callgrind(|| self.call_reducer_with_tx(tx, params))
     }
+
+    fn call_view(&mut self, params: CallViewParams) -> anyhow::Result<ViewCallResult> {
+        self.call_view_with_tx(params)
+    }
 }
 
 impl<T: WasmInstance> WasmModuleInstance<T> {
@@ -401,6 +444,8 @@ impl<T: WasmInstance> WasmModuleInstance<T> {
             caller_identity,
             caller_address,
             client,
+            caller_metadata,
+            caller_session,
             request_id,
             reducer_id,
             args,
@@ -409,6 +454,8 @@ impl<T: WasmInstance> WasmModuleInstance<T> {
         let caller_address_opt = (caller_address != Address::__DUMMY).then_some(caller_address);
 
         let replica_ctx = self.replica_context();
+        // The clock may have been moved forward by a test, see `Scheduler::advance_time`.
+        let timestamp = replica_ctx.clock.shift(timestamp);
         let stdb = &*replica_ctx.relational_db.clone();
         let address = replica_ctx.database_identity;
         let reducer_def = self.info.module_def.reducer_by_id(reducer_id);
@@ -424,16 +471,22 @@ impl<T: WasmInstance> WasmModuleInstance<T> {
         let energy_fingerprint = ReducerFingerprint {
             module_hash: self.info.module_hash,
             module_identity: self.info.owner_identity,
+            database_identity: self.info.database_identity,
             caller_identity,
             reducer_name,
         };
-        let budget = self.energy_monitor.reducer_budget(&energy_fingerprint);
+        let mut budget = self.energy_monitor.reducer_budget(&energy_fingerprint);
+        if let Some(max) = replica_ctx.quotas.read().max_reducer_energy {
+            budget = ReducerBudget::new(budget.get().min(max));
+        }
 
         let op = ReducerOp {
             id: reducer_id,
             name: reducer_name,
             caller_identity: &caller_identity,
             caller_address: &caller_address,
+            caller_metadata: caller_metadata.as_deref(),
+            caller_session,
             timestamp,
             arg_bytes: args.get_bsatn().clone(),
         };
@@ -467,10 +520,12 @@ impl<T: WasmInstance> WasmModuleInstance<T> {
             energy,
             timings,
             call_result,
+            broadcasts,
         } = result;
 
         self.energy_monitor
             .record_reducer(&energy_fingerprint, energy.used, timings.total_duration);
+        self.replica_context().record_cpu_time(timings.total_duration);
 
         reducer_span
             .record("timings.total_duration", tracing::field::debug(timings.total_duration))
@@ -501,22 +556,41 @@ impl<T: WasmInstance> WasmModuleInstance<T> {
 
                 if energy.remaining.get() == 0 {
                     EventStatus::OutOfEnergy
+                } else if T::timed_out(&err) {
+                    log::info!("reducer `{reducer_name}` exceeded its maximum duration");
+                    EventStatus::TimedOut
                 } else {
                     EventStatus::Failed("The Wasm instance encountered a fatal error.".into())
                 }
             }
-            Ok(Err(errmsg)) => {
+            Ok(Err(ReducerFailure::Message(errmsg))) => {
                 log::info!("reducer returned error: {errmsg}");
 
                 EventStatus::Failed(errmsg.into())
             }
+            Ok(Err(ReducerFailure::Value(bytes))) => {
+                let typespace = self.info.module_def.typespace();
+                let value = reducer_def
+                    .error_type
+                    .as_ref()
+                    .context("reducer has no error type")
+                    .and_then(|ty| Ok(ReducerErrorValue::decode(typespace.with_type(ty), bytes)?));
+                match value {
+                    Ok(value) => {
+                        log::info!("reducer returned error: {}", value.message);
+
+                        EventStatus::FailedWithValue(value)
+                    }
+                    Err(err) => EventStatus::Failed(format!("reducer returned an invalid error value: {err:#}")),
+                }
+            }
             // we haven't actually comitted yet - `commit_and_broadcast_event` will commit
             // for us and replace this with the actual database update.
             Ok(Ok(())) => {
                 // Detecing a new client, and inserting it in `st_clients`
                 // Disconnect logic is written in module_host.rs, due to different transacationality requirements.
                 if reducer_def.lifecycle == Some(Lifecycle::OnConnect) {
-                    match self.insert_st_client(&mut tx, caller_identity, caller_address) {
+                    match self.insert_st_client(&mut tx, caller_identity, caller_address, timestamp) {
                         Ok(_) => EventStatus::Committed(DatabaseUpdate::default()),
                         Err(err) => EventStatus::Failed(err.to_string()),
                     }
@@ -538,6 +612,7 @@ impl<T: WasmInstance> WasmModuleInstance<T> {
             status,
             energy_quanta_used: energy.used,
             host_execution_duration: timings.total_duration,
+            tx_offset: None,
             request_id,
             timer,
         };
@@ -551,24 +626,135 @@ impl<T: WasmInstance> WasmModuleInstance<T> {
             Err(WriteConflict) => todo!("Write skew, you need to implement retries my man, T-dawg."),
         };
 
+        if let EventStatus::Committed(_) = event.status {
+            self.info.subscriptions.topics().broadcast(broadcasts);
+        }
+
         ReducerCallResult {
             outcome: ReducerOutcome::from(&event.status),
             energy_used: energy.used,
             execution_duration: timings.total_duration,
+            tx_offset: event.tx_offset,
         }
     }
 
+    /// Execute a view, returning the rows of its result,
+    /// or the window of them described by `params.page`.
+    ///
+    /// The view runs in a fresh transaction which is always rolled back,
+    /// so it observes the committed state of the database and can't modify it.
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn call_view_with_tx(&mut self, params: CallViewParams) -> anyhow::Result<ViewCallResult> {
+        let CallViewParams {
+            timestamp,
+            caller_identity,
+            caller_address,
+            view_id,
+            args,
+            page,
+        } = params;
+        anyhow::ensure!(!self.trapped, "the module instance encountered a fatal error");
+
+        let info = self.info.clone();
+        let view_def = info.module_def.get_view_by_id(view_id).context("no such view")?;
+        let view_name = &*view_def.name;
+
+        let replica_ctx = self.replica_context();
+        let stdb = replica_ctx.relational_db.clone();
+        let energy_fingerprint = ReducerFingerprint {
+            module_hash: info.module_hash,
+            module_identity: info.owner_identity,
+            database_identity: info.database_identity,
+            caller_identity,
+            reducer_name: view_name,
+        };
+        let mut budget = self.energy_monitor.reducer_budget(&energy_fingerprint);
+        if let Some(max) = replica_ctx.quotas.read().max_reducer_energy {
+            budget = ReducerBudget::new(budget.get().min(max));
+        }
+
+        let op = ViewOp {
+            id: view_id,
+            name: view_name,
+            caller_identity: &caller_identity,
+            caller_address: &caller_address,
+            timestamp,
+            arg_bytes: args.get_bsatn().clone(),
+        };
+
+        let mut tx = stdb.begin_mut_tx(IsolationLevel::Serializable, Workload::Internal);
+        tx.record_reads();
+        let mut tx_slot = self.instance.instance_env().tx.clone();
+        // As with reducers, run the call in rayon, without acquiring any locks inside the rayon task.
+        let (mut tx, result) = rayon::scope(|_| tx_slot.set(tx, || self.instance.call_view(op, budget)));
+        let read_tables = tx.take_read_tables().unwrap_or_default();
+        stdb.rollback_mut_tx(tx);
+
+        let ViewExecuteResult {
+            energy,
+            timings,
+            call_result,
+        } = result;
+
+        self.energy_monitor
+            .record_reducer(&energy_fingerprint, energy.used, timings.total_duration);
+        self.replica_context().record_cpu_time(timings.total_duration);
+
+        let bytes = match call_result {
+            Err(err) => {
+                T::log_traceback("view", view_name, &err);
+                // discard this instance
+                self.trapped = true;
+                if energy.remaining.get() == 0 {
+                    anyhow::bail!("view `{view_name}` ran out of energy");
+                }
+                if T::timed_out(&err) {
+                    anyhow::bail!("view `{view_name}` exceeded its maximum duration");
+                }
+                anyhow::bail!("The Wasm instance encountered a fatal error.");
+            }
+            Ok(Err(errmsg)) => anyhow::bail!("view `{view_name}` returned an error: {errmsg}"),
+            Ok(Ok(bytes)) => bytes,
+        };
+
+        let value = info
+            .module_def
+            .typespace()
+            .with_type(&view_def.return_type)
+            .deserialize(bsatn::Deserializer::new(&mut &bytes[..]))
+            .with_context(|| format!("view `{view_name}` returned an invalid value"))?;
+        let mut rows = view_rows(value);
+        if let Some(page) = page {
+            rows = stdb.with_read_only(Workload::Internal, |tx| {
+                view_subscriptions::paginate_in_tx(&stdb, tx, rows, &read_tables, &page)
+            })?;
+        }
+        Ok(ViewCallResult { rows, read_tables })
+    }
+
     // Helpers - NOT API
     fn system_logger(&self) -> &SystemLogger {
         self.replica_context().logger.system_logger()
     }
 
-    fn insert_st_client(&self, tx: &mut MutTxId, identity: Identity, address: Address) -> Result<(), DBError> {
+    fn insert_st_client(
+        &self,
+        tx: &mut MutTxId,
+        identity: Identity,
+        address: Address,
+        connected_at: Timestamp,
+    ) -> Result<(), DBError> {
         let row = &StClientRow {
             identity: identity.into(),
             address: address.into(),
         };
-        tx.insert_via_serialize_bsatn(ST_CLIENT_ID, row).map(|_| ())
+        tx.insert_via_serialize_bsatn(ST_CLIENT_ID, row)?;
+        let row = &StConnectionRow {
+            identity: identity.into(),
+            address: address.into(),
+            connected_at: connected_at.microseconds,
+        };
+        tx.insert_via_serialize_bsatn(ST_CONNECTION_ID, row).map(|_| ())
     }
 }
 
@@ -579,11 +765,39 @@ pub struct ReducerOp<'a> {
     pub name: &'a str,
     pub caller_identity: &'a Identity,
     pub caller_address: &'a Address,
+    /// What the host learned about the caller when it connected, if the caller is a connected client.
+    pub caller_metadata: Option<&'a ClientMetadata>,
+    /// A snapshot of the variables the caller has set in its session, if the caller is a connected client.
+    pub caller_session: Option<Arc<SessionVariables>>,
     pub timestamp: Timestamp,
     /// The BSATN-serialized arguments passed to the reducer.
     pub arg_bytes: Bytes,
 }
 
+/// Describes a view call.
+#[derive(Clone, Debug)]
+pub struct ViewOp<'a> {
+    pub id: ViewId,
+    pub name: &'a str,
+    pub caller_identity: &'a Identity,
+    pub caller_address: &'a Address,
+    pub timestamp: Timestamp,
+    /// The BSATN-serialized arguments passed to the view.
+    pub arg_bytes: Bytes,
+}
+
+/// Converts the result of a view into rows.
+///
+/// An array is a row per element, and any other value is a single row.
+/// Values which aren't products are wrapped in a product of one element.
+fn view_rows(value: AlgebraicValue) -> Vec<ProductValue> {
+    let into_row = |value: AlgebraicValue| value.into_product().unwrap_or_else(|value| product![value]);
+    match value {
+        AlgebraicValue::Array(array) => array.into_iter().map(into_row).collect(),
+        value => vec![into_row(value)],
+    }
+}
+
 impl From<ReducerOp<'_>> for execution_context::ReducerContext {
     fn from(
         ReducerOp {
@@ -591,6 +805,8 @@ impl From<ReducerOp<'_>> for execution_context::ReducerContext {
             name,
             caller_identity,
             caller_address,
+            caller_metadata: _,
+            caller_session: _,
             timestamp,
             arg_bytes,
         }: ReducerOp<'_>,
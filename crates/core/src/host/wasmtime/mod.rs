@@ -1,16 +1,22 @@
 use std::borrow::Cow;
+use std::time::Duration;
 
 use anyhow::Context;
+use parking_lot::Mutex;
 use spacetimedb_paths::server::{ServerDataDir, WasmtimeCacheDir};
-use wasmtime::{Engine, Linker, Module, StoreContext, StoreContextMut};
+use wasmtime::{Engine, EngineWeak, Linker, Module, StoreContext, StoreContextMut};
 
 use crate::energy::{EnergyQuanta, ReducerBudget};
 use crate::error::NodesError;
 use crate::module_host_context::ModuleCreationContext;
 
+mod program_upload;
 mod wasm_instance_env;
+mod wasmtime_component;
 mod wasmtime_module;
 
+pub use program_upload::{ProgramUpload, ProgramUploadError};
+use wasmtime_component::WasmtimeComponent;
 use wasmtime_module::WasmtimeModule;
 
 use self::wasm_instance_env::WasmInstanceEnv;
@@ -21,6 +27,9 @@ use super::wasm_common::{abi, module_host_actor::WasmModuleHostActor, ModuleCrea
 pub struct WasmtimeRuntime {
     engine: Engine,
     linker: Box<Linker<WasmInstanceEnv>>,
+    /// Like `linker`, but for `wasm64` modules, whose pointers and lengths are 64-bit.
+    linker64: Box<Linker<WasmInstanceEnv>>,
+    component_linker: Box<wasmtime::component::Linker<WasmInstanceEnv>>,
 }
 
 impl WasmtimeRuntime {
@@ -29,17 +38,34 @@ impl WasmtimeRuntime {
         config
             .cranelift_opt_level(wasmtime::OptLevel::Speed)
             .consume_fuel(true)
-            .wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
+            .epoch_interruption(true)
+            .wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable)
+            .wasm_component_model(true)
+            .wasm_memory64(true);
 
         // ignore errors for this - if we're not able to set up caching, that's fine, it's just an optimization
         let _ = Self::set_cache_config(&mut config, data_dir.wasmtime_cache());
 
         let engine = Engine::new(&config).unwrap();
 
+        // Advance the engine's epoch periodically, interrupting calls which are past their deadline.
+        advance_epoch_periodically(&engine);
+
         let mut linker = Box::new(Linker::new(&engine));
-        WasmtimeModule::link_imports(&mut linker).unwrap();
+        WasmtimeModule::link_imports::<u32>(&mut linker).unwrap();
+
+        let mut linker64 = Box::new(Linker::new(&engine));
+        WasmtimeModule::link_imports::<u64>(&mut linker64).unwrap();
+
+        let mut component_linker = Box::new(wasmtime::component::Linker::new(&engine));
+        WasmtimeComponent::link_imports(&mut component_linker).unwrap();
 
-        WasmtimeRuntime { engine, linker }
+        WasmtimeRuntime {
+            engine,
+            linker,
+            linker64,
+            component_linker,
+        }
     }
 
     fn set_cache_config(config: &mut wasmtime::Config, cache_dir: WasmtimeCacheDir) -> anyhow::Result<()> {
@@ -70,7 +96,7 @@ impl WasmtimeRuntime {
         abi::verify_supported(WasmtimeModule::IMPLEMENTED_ABI, abi)?;
 
         let module = self
-            .linker
+            .linker_for(&module)
             .instantiate_pre(&module)
             .map_err(InitializationError::Instantiation)?;
 
@@ -78,6 +104,88 @@ impl WasmtimeRuntime {
 
         WasmModuleHostActor::new(mcc, module).map_err(Into::into)
     }
+
+    /// Returns the linker providing the host functions to `module`.
+    ///
+    /// A `wasm64` module passes 64-bit pointers and lengths to the host,
+    /// so it is linked against 64-bit versions of the host functions.
+    fn linker_for(&self, module: &Module) -> &Linker<WasmInstanceEnv> {
+        match module.get_export("memory") {
+            Some(wasmtime::ExternType::Memory(memory)) if memory.is_64() => &self.linker64,
+            _ => &self.linker,
+        }
+    }
+
+    /// Returns whether `program` is a WASM component, rather than a core WASM module.
+    ///
+    /// Both start with the `\0asm` magic number,
+    /// but components have a different version and layer following it.
+    pub fn is_component(program: &[u8]) -> bool {
+        const COMPONENT_HEADER: [u8; 8] = *b"\0asm\x0d\0\x01\0";
+        program.starts_with(&COMPONENT_HEADER)
+    }
+
+    /// Like [`Self::make_actor`], but for programs compiled as WASM components
+    /// targeting the `module` world of `wit/spacetimedb.wit`.
+    pub fn make_component_actor(
+        &self,
+        mcc: ModuleCreationContext,
+    ) -> Result<impl super::module_host::Module, ModuleCreationError> {
+        let component = wasmtime::component::Component::new(&self.engine, &mcc.program.bytes)
+            .map_err(ModuleCreationError::WasmCompileError)?;
+
+        // Linking also checks that the component exports the functions of the world.
+        let component = self
+            .component_linker
+            .instantiate_pre(&component)
+            .and_then(wasmtime_component::bindings::ModulePre::new)
+            .map_err(InitializationError::Instantiation)?;
+
+        let component = WasmtimeComponent::new(component);
+
+        WasmModuleHostActor::new(mcc, component).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests_utils {
+    use crate::database_logger::DatabaseLogger;
+    use crate::db::relational_db::tests_utils::{TempReplicaDir, TestDB};
+    use crate::host::assets::Assets;
+    use crate::host::instance_env::InstanceEnv;
+    use crate::host::Scheduler;
+    use crate::messages::control_db::{Database, HostType};
+    use crate::replica_context::ReplicaContext;
+    use crate::subscription::module_subscription_actor::ModuleSubscriptions;
+    use spacetimedb_sats::hash::Hash;
+    use std::sync::Arc;
+
+    /// Returns an environment for instantiating a module with `db` as its database.
+    pub(super) fn instance_env(db: TestDB) -> (InstanceEnv, TempReplicaDir) {
+        let (db, _, _, dir) = db.into_parts();
+        let db = Arc::new(db);
+        let database = Database {
+            id: 0,
+            database_identity: TestDB::DATABASE_IDENTITY,
+            owner_identity: TestDB::OWNER,
+            host_type: HostType::Wasm,
+            initial_program: Hash::ZERO,
+        };
+        let replica_ctx = ReplicaContext {
+            database,
+            replica_id: 0,
+            logger: Arc::new(DatabaseLogger::open_today(dir.clone().module_logs())),
+            subscriptions: ModuleSubscriptions::new(db.clone(), TestDB::OWNER),
+            relational_db: db.clone(),
+            quotas: <_>::default(),
+            quota_usage: <_>::default(),
+            reducer_replays: <_>::default(),
+            clock: <_>::default(),
+        };
+        let (scheduler, _) = Scheduler::open(db, replica_ctx.clock.clone());
+        let env = InstanceEnv::new(Arc::new(replica_ctx), scheduler, Arc::new(Assets::default()));
+        (env, dir)
+    }
 }
 
 #[derive(Debug, derive_more::From)]
@@ -87,6 +195,52 @@ pub enum WasmError {
     Wasm(anyhow::Error),
 }
 
+/// How often the epoch of the engine is advanced,
+/// which is the granularity at which call deadlines are enforced.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The deadline, in epoch ticks from now, of calls without a maximum duration.
+///
+/// Wasmtime adds the deadline to the current epoch, so it must leave room for the epoch to grow,
+/// which, advancing every [`EPOCH_TICK`], will never come close to this.
+const NO_DEADLINE: u64 = u64::MAX / 2;
+
+/// Converts `duration` into a number of epoch ticks, rounding up.
+fn epoch_ticks(duration: Duration) -> u64 {
+    duration
+        .as_nanos()
+        .div_ceil(EPOCH_TICK.as_nanos())
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+/// Returns the deadline, in epoch ticks from now, of a call which may run for at most `max_duration`.
+fn deadline_ticks(max_duration: Option<Duration>) -> u64 {
+    max_duration.map_or(NO_DEADLINE, |duration| epoch_ticks(duration).min(NO_DEADLINE))
+}
+
+/// The engines whose epoch is advanced by the `wasmtime-epoch` thread.
+static EPOCH_ENGINES: Mutex<Vec<EngineWeak>> = Mutex::new(Vec::new());
+
+/// Advances the epoch of `engine` every [`EPOCH_TICK`], for as long as `engine` is alive.
+///
+/// A single thread, started by the first call, advances the epochs of all engines.
+fn advance_epoch_periodically(engine: &Engine) {
+    static TICKER: std::sync::Once = std::sync::Once::new();
+    EPOCH_ENGINES.lock().push(engine.weak());
+    TICKER.call_once(|| {
+        std::thread::Builder::new()
+            .name("wasmtime-epoch".into())
+            .spawn(|| loop {
+                std::thread::sleep(EPOCH_TICK);
+                EPOCH_ENGINES
+                    .lock()
+                    .retain(|engine| engine.upgrade().map(|engine| engine.increment_epoch()).is_some());
+            })
+            .unwrap();
+    });
+}
+
 #[derive(Copy, Clone)]
 struct WasmtimeFuel(u64);
 
@@ -118,43 +272,75 @@ impl From<WasmtimeFuel> for EnergyQuanta {
 }
 
 pub trait WasmPointee {
-    type Pointer;
-    fn write_to(self, mem: &mut MemView, ptr: Self::Pointer) -> Result<(), MemError>;
-    fn read_from(mem: &mut MemView, ptr: Self::Pointer) -> Result<Self, MemError>
+    fn write_to(self, mem: &mut MemView, ptr: impl Into<u64>) -> Result<(), MemError>;
+    fn read_from(mem: &mut MemView, ptr: impl Into<u64>) -> Result<Self, MemError>
     where
         Self: Sized;
 }
 macro_rules! impl_pointee {
     ($($t:ty),*) => {
         $(impl WasmPointee for $t {
-            type Pointer = u32;
-            fn write_to(self, mem: &mut MemView, ptr: Self::Pointer) -> Result<(), MemError> {
+            fn write_to(self, mem: &mut MemView, ptr: impl Into<u64>) -> Result<(), MemError> {
                 let bytes = self.to_le_bytes();
                 mem.deref_slice_mut(ptr, bytes.len() as u32)?.copy_from_slice(&bytes);
                 Ok(())
             }
-            fn read_from(mem: &mut MemView, ptr: Self::Pointer) -> Result<Self, MemError> {
+            fn read_from(mem: &mut MemView, ptr: impl Into<u64>) -> Result<Self, MemError> {
                 Ok(Self::from_le_bytes(*mem.deref_array(ptr)?))
             }
         })*
     };
 }
-impl_pointee!(u8, u16, u32, u64);
+impl_pointee!(u8, u16, u32, u64, i128);
 impl_pointee!(super::wasm_common::RowIterIdx);
 
 impl WasmPointee for spacetimedb_lib::Identity {
-    type Pointer = u32;
-    fn write_to(self, mem: &mut MemView, ptr: Self::Pointer) -> Result<(), MemError> {
+    fn write_to(self, mem: &mut MemView, ptr: impl Into<u64>) -> Result<(), MemError> {
         let bytes = self.to_byte_array();
         mem.deref_slice_mut(ptr, bytes.len() as u32)?.copy_from_slice(&bytes);
         Ok(())
     }
-    fn read_from(mem: &mut MemView, ptr: Self::Pointer) -> Result<Self, MemError> {
+    fn read_from(mem: &mut MemView, ptr: impl Into<u64>) -> Result<Self, MemError> {
         Ok(Self::from_byte_array(*mem.deref_array(ptr)?))
     }
 }
 
-type WasmPtr<T> = <T as WasmPointee>::Pointer;
+/// The type of the pointers and lengths a module passes to host functions:
+/// `u32` for modules with a 32-bit linear memory, and `u64` for `wasm64` modules.
+///
+/// The host functions are generic over it, and linked once for each.
+pub trait WasmAddr: wasmtime::WasmTy + WasmPointee + Copy + Into<u64> {
+    /// Converts a length on the host into a length in the module's memory.
+    ///
+    /// Panics if `len` does not fit, which can only happen for lengths of buffers in the module's memory.
+    fn from_len(len: usize) -> Self;
+}
+
+impl WasmAddr for u32 {
+    fn from_len(len: usize) -> Self {
+        len.try_into().unwrap()
+    }
+}
+
+impl WasmAddr for u64 {
+    fn from_len(len: usize) -> Self {
+        len as u64
+    }
+}
+
+/// Relates a pointer of type `A` to the type `T` it points to, for [`WasmPtr`].
+pub trait PointerTo<T> {
+    type Addr;
+}
+
+impl<A, T> PointerTo<T> for A {
+    type Addr = A;
+}
+
+/// A pointer of type `A` to a `T` in WASM memory.
+///
+/// This is just `A`, but `T` documents what the pointer points to.
+type WasmPtr<A, T> = <A as PointerTo<T>>::Addr;
 
 /// Wraps access to WASM linear memory with some additional functionality.
 #[derive(Clone, Copy)]
@@ -196,42 +382,43 @@ impl MemView {
         unsafe { &*(v as *const [u8] as *const MemView) }
     }
 
-    /// Get a byte slice of wasm memory given a pointer and a length.
-    pub fn deref_slice(&self, offset: WasmPtr<u8>, len: u32) -> Result<&[u8], MemError> {
+    /// Returns the range of wasm memory given a pointer and a length,
+    /// which is in bounds if the pointer and length fit in a `usize`.
+    fn range(offset: impl Into<u64>, len: impl Into<u64>) -> Result<std::ops::Range<usize>, MemError> {
+        let offset = offset.into();
         if offset == 0 {
             return Err(MemError::Null);
         }
-        self.0
-            .get(offset as usize..)
-            .and_then(|s| s.get(..len as usize))
-            .ok_or(MemError::OutOfBounds)
+        let start = usize::try_from(offset).map_err(|_| MemError::OutOfBounds)?;
+        let len = usize::try_from(len.into()).map_err(|_| MemError::OutOfBounds)?;
+        let end = start.checked_add(len).ok_or(MemError::OutOfBounds)?;
+        Ok(start..end)
+    }
+
+    /// Get a byte slice of wasm memory given a pointer and a length.
+    pub fn deref_slice(&self, offset: impl Into<u64>, len: impl Into<u64>) -> Result<&[u8], MemError> {
+        self.0.get(Self::range(offset, len)?).ok_or(MemError::OutOfBounds)
     }
 
     /// Get a utf8 slice of wasm memory given a pointer and a length.
-    fn deref_str(&self, offset: WasmPtr<u8>, len: u32) -> Result<&str, MemError> {
+    fn deref_str(&self, offset: impl Into<u64>, len: impl Into<u64>) -> Result<&str, MemError> {
         let b = self.deref_slice(offset, len)?;
         std::str::from_utf8(b).map_err(MemError::Utf8)
     }
 
     /// Lossily get a utf8 slice of wasm memory given a pointer and a length, converting any
     /// non-utf8 bytes to `U+FFFD REPLACEMENT CHARACTER`.
-    fn deref_str_lossy(&self, offset: WasmPtr<u8>, len: u32) -> Result<Cow<str>, MemError> {
+    fn deref_str_lossy(&self, offset: impl Into<u64>, len: impl Into<u64>) -> Result<Cow<str>, MemError> {
         self.deref_slice(offset, len).map(String::from_utf8_lossy)
     }
 
     /// Get a mutable byte slice of wasm memory given a pointer and a length;
-    fn deref_slice_mut(&mut self, offset: WasmPtr<u8>, len: u32) -> Result<&mut [u8], MemError> {
-        if offset == 0 {
-            return Err(MemError::Null);
-        }
-        self.0
-            .get_mut(offset as usize..)
-            .and_then(|s| s.get_mut(..len as usize))
-            .ok_or(MemError::OutOfBounds)
+    fn deref_slice_mut(&mut self, offset: impl Into<u64>, len: impl Into<u64>) -> Result<&mut [u8], MemError> {
+        self.0.get_mut(Self::range(offset, len)?).ok_or(MemError::OutOfBounds)
     }
 
     /// Get a byte array of wasm memory the size of `N`.
-    fn deref_array<const N: usize>(&self, offset: WasmPtr<u8>) -> Result<&[u8; N], MemError> {
+    fn deref_array<const N: usize>(&self, offset: impl Into<u64>) -> Result<&[u8; N], MemError> {
         Ok(self.deref_slice(offset, N as u32)?.try_into().unwrap())
     }
 }
@@ -0,0 +1,254 @@
+//! Checks on a program made while it is being uploaded,
+//! so that a program which could never be published is rejected
+//! without waiting for the rest of it to arrive.
+
+use super::wasmtime_module::WasmtimeModule;
+use super::WasmtimeRuntime;
+use crate::host::wasm_common::abi::{self, AbiVersionError};
+
+/// The header of a core WASM module: the `\0asm` magic number and version 1.
+const MODULE_HEADER: [u8; 8] = *b"\0asm\x01\0\0\0";
+
+/// The id of the import section of a WASM module.
+const IMPORT_SECTION: u8 = 2;
+
+/// A program being uploaded for publishing, checked incrementally as its bytes arrive.
+///
+/// Compiling the program and validating its `ModuleDef` still require the whole program,
+/// and happen as before once the upload has finished.
+/// What this checks early is that the program is WASM at all,
+/// and, for core modules, that it targets an ABI version the host implements,
+/// which is known as soon as the module's import section has arrived.
+#[derive(Default)]
+pub struct ProgramUpload {
+    bytes: Vec<u8>,
+    check: Check,
+}
+
+#[derive(Default)]
+enum Check {
+    /// Waiting for the header of the program.
+    #[default]
+    Header,
+    /// Waiting for the section of the module starting at this offset.
+    Section(usize),
+    /// Nothing more can be checked before the upload finishes.
+    Done,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ProgramUploadError {
+    #[error("program is not a WASM module or component")]
+    NotWasm,
+    #[error(transparent)]
+    Abi(#[from] AbiVersionError),
+}
+
+impl ProgramUpload {
+    /// Appends the next `chunk` of the program, checking whatever it makes checkable.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), ProgramUploadError> {
+        self.bytes.extend_from_slice(chunk);
+        loop {
+            self.check = match self.check {
+                Check::Header if self.bytes.len() < MODULE_HEADER.len() => return Ok(()),
+                Check::Header if self.bytes.starts_with(&MODULE_HEADER) => Check::Section(MODULE_HEADER.len()),
+                // Components declare their imports in nested modules and types,
+                // so they are left to be checked in full.
+                Check::Header if WasmtimeRuntime::is_component(&self.bytes) => Check::Done,
+                Check::Header => return Err(ProgramUploadError::NotWasm),
+                Check::Section(offset) => {
+                    let mut reader = Reader(&self.bytes[offset..]);
+                    let Some((id, payload)) = reader.section() else {
+                        // The section hasn't fully arrived yet.
+                        return Ok(());
+                    };
+                    match id {
+                        // Custom sections may appear anywhere,
+                        // and the type section precedes the import section.
+                        0 | 1 => Check::Section(self.bytes.len() - reader.0.len()),
+                        IMPORT_SECTION => {
+                            // A malformed section is left for compilation to report.
+                            if let Some(modules) = func_import_modules(payload) {
+                                let abi = abi::determine_spacetime_abi(modules, |module| *module)?;
+                                abi::verify_supported(WasmtimeModule::IMPLEMENTED_ABI, abi)?;
+                            }
+                            Check::Done
+                        }
+                        // The module has no import section, so it imports nothing from the host.
+                        _ => return Err(AbiVersionError::NotDetected.into()),
+                    }
+                }
+                Check::Done => return Ok(()),
+            };
+        }
+    }
+
+    /// Finishes the upload, returning the whole program.
+    pub fn finish(self) -> Result<Vec<u8>, ProgramUploadError> {
+        match self.check {
+            Check::Header => Err(ProgramUploadError::NotWasm),
+            Check::Section(_) | Check::Done => Ok(self.bytes),
+        }
+    }
+}
+
+/// Returns the module names of the function imports in the import section `payload`,
+/// or `None` if the section is malformed.
+fn func_import_modules(payload: &[u8]) -> Option<Vec<&str>> {
+    let mut reader = Reader(payload);
+    let count = reader.leb()?;
+    let mut modules = Vec::new();
+    for _ in 0..count {
+        let module = reader.name()?;
+        let _name = reader.name()?;
+        match reader.byte()? {
+            // A function, with its type index.
+            0x00 => {
+                reader.leb()?;
+                modules.push(module);
+            }
+            // A table, with its reference type and limits.
+            0x01 => {
+                reader.val_type()?;
+                reader.limits()?;
+            }
+            // A memory, with its limits.
+            0x02 => reader.limits()?,
+            // A global, with its type and mutability.
+            0x03 => {
+                reader.val_type()?;
+                reader.byte()?;
+            }
+            // A tag, with its attribute and type index.
+            0x04 => {
+                reader.byte()?;
+                reader.leb()?;
+            }
+            _ => return None,
+        }
+    }
+    Some(modules)
+}
+
+/// Reads the binary format of WASM modules, returning `None` when out of bytes.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Option<u8> {
+        let (&byte, rest) = self.0.split_first()?;
+        self.0 = rest;
+        Some(byte)
+    }
+
+    /// Reads an unsigned LEB128 integer.
+    /// Signed integers are read the same, as only their size matters here.
+    fn leb(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn bytes(&mut self, len: u64) -> Option<&'a [u8]> {
+        let len = usize::try_from(len).ok().filter(|&len| len <= self.0.len())?;
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn name(&mut self) -> Option<&'a str> {
+        let len = self.leb()?;
+        std::str::from_utf8(self.bytes(len)?).ok()
+    }
+
+    /// Reads a section, returning its id and payload.
+    fn section(&mut self) -> Option<(u8, &'a [u8])> {
+        let id = self.byte()?;
+        let len = self.leb()?;
+        Some((id, self.bytes(len)?))
+    }
+
+    fn val_type(&mut self) -> Option<()> {
+        // The reference types `(ref null? <heaptype>)` are followed by their heap type.
+        if let 0x63 | 0x64 = self.byte()? {
+            self.leb()?;
+        }
+        Some(())
+    }
+
+    fn limits(&mut self) -> Option<()> {
+        let flags = self.byte()?;
+        self.leb()?;
+        // A maximum.
+        if flags & 0x01 != 0 {
+            self.leb()?;
+        }
+        // A custom page size.
+        if flags & 0x08 != 0 {
+            self.leb()?;
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a module with the function imports `modules` and no other sections.
+    fn module(modules: &[&str]) -> Vec<u8> {
+        let mut section = vec![modules.len() as u8];
+        for module in modules {
+            section.push(module.len() as u8);
+            section.extend_from_slice(module.as_bytes());
+            section.extend_from_slice(&[1, b'f', 0x00, 0]);
+        }
+        let mut bytes = MODULE_HEADER.to_vec();
+        // A custom section, which is skipped.
+        bytes.extend_from_slice(&[0, 3, 1, b'x', 0]);
+        bytes.extend_from_slice(&[IMPORT_SECTION, section.len() as u8]);
+        bytes.extend_from_slice(&section);
+        bytes
+    }
+
+    fn upload_bytewise(program: &[u8]) -> Result<Vec<u8>, ProgramUploadError> {
+        let mut upload = ProgramUpload::default();
+        for byte in program {
+            upload.push(&[*byte])?;
+        }
+        upload.finish()
+    }
+
+    #[test]
+    fn accepts_supported_abi() {
+        let program = module(&["spacetime_10.0", "env"]);
+        assert_eq!(upload_bytewise(&program).unwrap(), program);
+    }
+
+    #[test]
+    fn rejects_early() {
+        let program = module(&["spacetime_11.0"]);
+        let mut upload = ProgramUpload::default();
+        assert!(matches!(
+            upload.push(&program),
+            Err(ProgramUploadError::Abi(AbiVersionError::UnsupportedVersion { .. }))
+        ));
+
+        let program = [&MODULE_HEADER[..], &[3, 1, 0]].concat();
+        assert!(matches!(
+            upload_bytewise(&program),
+            Err(ProgramUploadError::Abi(AbiVersionError::NotDetected))
+        ));
+
+        assert!(matches!(
+            ProgramUpload::default().push(b"not wasm"),
+            Err(ProgramUploadError::NotWasm)
+        ));
+        assert!(matches!(upload_bytewise(b"\0as"), Err(ProgramUploadError::NotWasm)));
+    }
+}
@@ -1,8 +1,13 @@
 #![allow(clippy::too_many_arguments)]
 
+use std::sync::Arc;
 use std::time::Instant;
 
-use crate::database_logger::{BacktraceFrame, BacktraceProvider, ModuleBacktrace, Record};
+use crate::client::messages::TopicMessage;
+use crate::client::SessionVariables;
+use crate::database_logger::{BacktraceFrame, BacktraceProvider, LogLevel, ModuleBacktrace, Record};
+use crate::db::datastore::locking_tx_datastore::Savepoint;
+use crate::error::NodesError;
 use crate::host::instance_env::{ChunkPool, InstanceEnv};
 use crate::host::wasm_common::instrumentation;
 use crate::host::wasm_common::module_host_actor::ExecutionTimings;
@@ -12,10 +17,14 @@ use crate::host::wasm_common::{
 };
 use crate::host::AbiCall;
 use anyhow::Context as _;
-use spacetimedb_primitives::{errno, ColId};
+use spacetimedb_lib::batch::{BatchInsert, BatchOp, BatchResult, BatchUpdate};
+use spacetimedb_lib::bsatn;
+use spacetimedb_lib::client_metadata::ClientMetadata;
+use spacetimedb_primitives::{errno, ColId, IndexId, TableId};
 use wasmtime::{AsContext, Caller, StoreContextMut};
 
-use super::{Mem, MemView, NullableMemOp, WasmError, WasmPointee, WasmPtr};
+use super::wasmtime_component::bindings::host;
+use super::{Mem, MemView, NullableMemOp, WasmAddr, WasmError, WasmPointee, WasmPtr};
 
 #[cfg(not(feature = "spacetimedb-wasm-instance-env-times"))]
 use instrumentation::noop as span;
@@ -50,15 +59,34 @@ pub(super) struct WasmInstanceEnv {
     /// that it can read via [`Self::bytes_source_read`].
     call_reducer_args: Option<(bytes::Bytes, usize)>,
 
+    /// The BSATN-encoded `Option<ClientMetadata>` of the caller of the current reducer,
+    /// which it can read via [`Self::caller_metadata`].
+    caller_metadata: Vec<u8>,
+
+    /// The session of the caller of the current reducer,
+    /// which it can read via [`Self::session_get`].
+    caller_session: Option<Arc<SessionVariables>>,
+
     /// The standard sink used for [`Self::bytes_sink_write`].
     standard_bytes_sink: Option<Vec<u8>>,
 
+    /// The sink a view writes its result to via [`Self::bytes_sink_write`].
+    view_result_sink: Option<Vec<u8>>,
+
     /// The slab of `BufferIters` created for this instance.
     iters: RowIters,
 
     /// Track time spent in module-defined spans.
     timing_spans: TimingSpanSet,
 
+    /// The savepoints taken by the current reducer, outermost first,
+    /// each with the number of messages broadcast before it was taken.
+    savepoints: Vec<(Savepoint, usize)>,
+
+    /// The ephemeral messages broadcast by the current reducer,
+    /// which are sent to subscribed clients if its transaction commits.
+    broadcasts: Vec<TopicMessage>,
+
     /// The point in time the last reducer call started at.
     reducer_start: Instant,
 
@@ -77,12 +105,15 @@ pub(super) struct WasmInstanceEnv {
 
 const CALL_REDUCER_ARGS_SOURCE: u32 = 1;
 const STANDARD_BYTES_SINK: u32 = 1;
+const VIEW_RESULT_SINK: u32 = 2;
 
 type WasmResult<T> = Result<T, WasmError>;
 type RtResult<T> = anyhow::Result<T>;
 
 /// Wraps an `InstanceEnv` with the magic necessary to push
 /// and pull bytes from webassembly memory.
+// The host functions are generic over the address width `A` of the module's memory,
+// including those which take no pointers, so that `link_imports` can link them all alike.
 impl WasmInstanceEnv {
     /// Create a new `WasmEnstanceEnv` from the given `InstanceEnv`.
     pub fn new(instance_env: InstanceEnv) -> Self {
@@ -91,9 +122,14 @@ impl WasmInstanceEnv {
             instance_env,
             mem: None,
             call_reducer_args: None,
+            caller_metadata: Vec::new(),
+            caller_session: None,
             standard_bytes_sink: None,
+            view_result_sink: None,
             iters: Default::default(),
             timing_spans: Default::default(),
+            savepoints: Vec::new(),
+            broadcasts: Vec::new(),
             reducer_start,
             call_times: CallTimes::new(),
             reducer_name: String::from(""),
@@ -139,7 +175,13 @@ impl WasmInstanceEnv {
     ///
     /// Returns the handle used by reducers to read from `args`
     /// as well as the handle used to write the error message, if any.
-    pub fn start_reducer(&mut self, name: &str, args: bytes::Bytes) -> (u32, u32) {
+    pub fn start_reducer(
+        &mut self,
+        name: &str,
+        args: bytes::Bytes,
+        caller_metadata: Option<&ClientMetadata>,
+        caller_session: Option<Arc<SessionVariables>>,
+    ) -> (u32, u32) {
         let errors = self.setup_standard_bytes_sink();
 
         // Pass an invalid source when the reducer args were empty.
@@ -151,8 +193,13 @@ impl WasmInstanceEnv {
             0
         };
 
+        self.caller_metadata.clear();
+        bsatn::to_writer(&mut self.caller_metadata, &caller_metadata).expect("encoding to a `Vec` can't fail");
+        self.caller_session = caller_session;
+
         self.reducer_start = Instant::now();
         name.clone_into(&mut self.reducer_name);
+        self.broadcasts.clear();
 
         (args, errors)
     }
@@ -176,9 +223,195 @@ impl WasmInstanceEnv {
         };
 
         self.call_reducer_args = None;
+        self.caller_session = None;
+        // The savepoints belong to the reducer's transaction, which is now over.
+        self.savepoints.clear();
         (timings, self.take_standard_bytes_sink())
     }
 
+    /// Extract the messages broadcast by the last reducer call.
+    pub fn take_broadcasts(&mut self) -> Vec<TopicMessage> {
+        std::mem::take(&mut self.broadcasts)
+    }
+
+    /// Starts a scan of the table `table_id`,
+    /// returning the handle of an iterator over its rows.
+    fn scan_table(&mut self, table_id: TableId) -> Result<RowIterIdx, NodesError> {
+        // Collect the iterator chunks.
+        let chunks = self
+            .instance_env
+            .datastore_table_scan_bsatn_chunks(&mut self.chunk_pool, table_id)?;
+        // Register the iterator and get back its handle.
+        // Calls to the iterator are done through dynamic dispatch.
+        Ok(self.iters.insert(chunks.into_iter()))
+    }
+
+    /// Starts a scan of the index `index_id`, as described by [`Self::datastore_btree_scan_bsatn`],
+    /// returning the handle of an iterator over the rows found.
+    fn scan_btree(
+        &mut self,
+        index_id: IndexId,
+        prefix: &[u8],
+        prefix_elems: ColId,
+        rstart: &[u8],
+        rend: &[u8],
+    ) -> Result<RowIterIdx, NodesError> {
+        // Find the relevant rows.
+        let chunks = self.instance_env.datastore_btree_scan_bsatn_chunks(
+            &mut self.chunk_pool,
+            index_id,
+            prefix,
+            prefix_elems,
+            rstart,
+            rend,
+        )?;
+        // Register the iterator over the encoded + concatenated rows and get back its handle.
+        Ok(self.iters.insert(chunks.into_iter()))
+    }
+
+    /// Hands the chunks of rows of the iterator `iter` to `take`, in order,
+    /// until `take` refuses one by returning `false`,
+    /// or until the iterator is exhausted, in which case it is destroyed.
+    ///
+    /// Returns `None` if `iter` is not a valid iterator,
+    /// and otherwise the length of the chunk refused by `take`, if any.
+    fn advance_row_iter(&mut self, iter: RowIterIdx, mut take: impl FnMut(&[u8]) -> bool) -> Option<Option<usize>> {
+        let rows = self.iters.get_mut(iter)?;
+        while let Some(chunk) = rows.as_slice().first() {
+            if !take(chunk) {
+                return Some(Some(chunk.len()));
+            }
+            // Advance the iterator, as we used a chunk.
+            // SAFETY: We peeked one `chunk`, so there must be one at least.
+            let chunk = unsafe { rows.next().unwrap_unchecked() };
+            self.chunk_pool.put(chunk);
+        }
+        // The iterator is exhausted, destroy it.
+        self.iters.take(iter);
+        Some(None)
+    }
+
+    /// Destroys the iterator `iter`, returning whether it was valid.
+    fn close_row_iter(&mut self, iter: RowIterIdx) -> bool {
+        // TODO(Centril): consider putting these into a pool for reuse.
+        self.iters.take(iter).is_some()
+    }
+
+    /// Takes a savepoint in the current transaction, returning its handle.
+    fn begin_savepoint(&mut self) -> Result<u32, NodesError> {
+        let savepoint = self.instance_env.savepoint()?;
+        self.savepoints.push((savepoint, self.broadcasts.len()));
+        Ok(self.savepoints.len() as u32 - 1)
+    }
+
+    /// Rolls the current transaction back to the savepoint `savepoint`,
+    /// releasing it along with the savepoints nested within it.
+    ///
+    /// Returns `false` if `savepoint` is not a valid savepoint.
+    fn rollback_to_savepoint(&mut self, savepoint: u32) -> Result<bool, NodesError> {
+        if savepoint as usize >= self.savepoints.len() {
+            return Ok(false);
+        }
+        self.release_savepoints(savepoint + 1)?;
+        let (savepoint, broadcasts) = self.savepoints.pop().unwrap();
+        self.instance_env.rollback_to_savepoint(savepoint)?;
+        self.broadcasts.truncate(broadcasts);
+        Ok(true)
+    }
+
+    /// Releases the savepoint `savepoint` and those nested within it, innermost first.
+    ///
+    /// Returns `false` if `savepoint` is not a valid savepoint.
+    fn release_savepoints(&mut self, savepoint: u32) -> Result<bool, NodesError> {
+        let idx = savepoint as usize;
+        if idx >= self.savepoints.len() {
+            return Ok(false);
+        }
+        for (savepoint, _) in self.savepoints.drain(idx..).rev() {
+            self.instance_env.release_savepoint(savepoint)?;
+        }
+        Ok(true)
+    }
+
+    /// Returns the value of the variable `key` in the session of the caller of the current reducer.
+    fn session_var(&self, key: &str) -> Option<&str> {
+        self.caller_session.as_deref().and_then(|session| session.get(key))
+    }
+
+    /// Queues a call to the reducer `name` with the BSATN-encoded `args`, to run after the current one.
+    fn schedule_immediate(&self, name: String, args: Vec<u8>) {
+        let args = crate::host::ReducerArgs::Bsatn(args.into());
+        self.instance_env
+            .scheduler
+            .volatile_nonatomic_schedule_immediate(name, args);
+    }
+
+    /// Broadcasts `payload` on `topic` if the current reducer's transaction commits.
+    fn push_broadcast(&mut self, topic: &str, payload: Vec<u8>) {
+        self.broadcasts.push(TopicMessage {
+            topic: topic.into(),
+            payload: payload.into(),
+        });
+    }
+
+    /// Writes `message` to the module's logs at `level`, with the backtrace `bt`.
+    fn log_message(
+        &self,
+        level: LogLevel,
+        target: Option<&str>,
+        filename: Option<&str>,
+        line_number: Option<u32>,
+        message: &str,
+        bt: &dyn BacktraceProvider,
+    ) {
+        let record = Record {
+            // TODO: figure out whether to use walltime now or logical reducer now (env.reducer_start)
+            ts: chrono::Utc::now(),
+            target,
+            filename,
+            line_number,
+            message,
+        };
+        // Write the log record to the `DatabaseLogger` in the database instance context (replica_ctx).
+        self.instance_env.console_log(level, &record, bt);
+    }
+
+    /// Begins a timing span with `name`, returning its handle.
+    fn start_timing_span(&mut self, name: String) -> u32 {
+        self.timing_spans.insert(TimingSpan::new(name)).0
+    }
+
+    /// Ends the timing span `span`, returning the message to log about its duration,
+    /// or `None` if `span` is not a valid timing span.
+    fn end_timing_span(&mut self, span: u32) -> Option<String> {
+        let span = self.timing_spans.take(TimingSpanIdx(span))?;
+        let elapsed = span.start.elapsed();
+        Some(format!("Timing span {:?}: {:?}", &span.name, elapsed))
+    }
+
+    /// Signal to this `WasmInstanceEnv` that a view call is beginning.
+    ///
+    /// Returns the handle used by the view to read from `args`,
+    /// the handle used to write its result,
+    /// and the handle used to write the error message, if any.
+    pub fn start_view(&mut self, name: &str, args: bytes::Bytes) -> (u32, u32, u32) {
+        let (args, errors) = self.start_reducer(name, args, None, None);
+        self.view_result_sink = Some(Vec::new());
+        (args, VIEW_RESULT_SINK, errors)
+    }
+
+    /// Signal to this `WasmInstanceEnv` that a view call is over.
+    ///
+    /// Returns instrumentation records, the bytes the view wrote as its result,
+    /// and the error message, if any.
+    pub fn finish_view(&mut self) -> (ExecutionTimings, Vec<u8>, Vec<u8>) {
+        let (timings, error) = self.finish_reducer();
+        // Views are read-only, so anything they broadcast is dropped.
+        self.broadcasts.clear();
+        let result = self.view_result_sink.take().unwrap_or_default();
+        (timings, result, error)
+    }
+
     fn with_span<R>(mut caller: Caller<'_, Self>, func: AbiCall, run: impl FnOnce(&mut Caller<'_, Self>) -> R) -> R {
         let span_start = span::CallSpanStart::new(func);
 
@@ -254,10 +487,10 @@ impl WasmInstanceEnv {
     /// as it helps with upholding the safety invariants of [`bindings_sys::call`].
     ///
     /// Returns an error if writing `T` to `out` errors.
-    fn cvt_ret<O: WasmPointee>(
+    fn cvt_ret<O: WasmPointee, A: WasmAddr>(
         caller: Caller<'_, Self>,
         call: AbiCall,
-        out: WasmPtr<O>,
+        out: WasmPtr<A, O>,
         f: impl FnOnce(&mut Caller<'_, Self>) -> WasmResult<O>,
     ) -> RtResult<u32> {
         Self::cvt(caller, call, |caller| {
@@ -304,13 +537,13 @@ impl WasmInstanceEnv {
     /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
     /// - `NO_SUCH_TABLE`, when `name` is not the name of a table.
     #[tracing::instrument(level = "trace", skip_all)]
-    pub fn table_id_from_name(
+    pub fn table_id_from_name<A: WasmAddr>(
         caller: Caller<'_, Self>,
-        name: WasmPtr<u8>,
-        name_len: u32,
-        out: WasmPtr<u32>,
+        name: WasmPtr<A, u8>,
+        name_len: A,
+        out: WasmPtr<A, u32>,
     ) -> RtResult<u32> {
-        Self::cvt_ret::<u32>(caller, AbiCall::TableIdFromName, out, |caller| {
+        Self::cvt_ret::<u32, A>(caller, AbiCall::TableIdFromName, out, |caller| {
             let (mem, env) = Self::mem_env(caller);
             // Read the table name from WASM memory.
             let name = mem.deref_str(name, name_len)?;
@@ -339,13 +572,13 @@ impl WasmInstanceEnv {
     /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
     /// - `NO_SUCH_INDEX`, when `name` is not the name of an index.
     #[tracing::instrument(level = "trace", skip_all)]
-    pub fn index_id_from_name(
+    pub fn index_id_from_name<A: WasmAddr>(
         caller: Caller<'_, Self>,
-        name: WasmPtr<u8>,
-        name_len: u32,
-        out: WasmPtr<u32>,
+        name: WasmPtr<A, u8>,
+        name_len: A,
+        out: WasmPtr<A, u32>,
     ) -> RtResult<u32> {
-        Self::cvt_ret::<u32>(caller, AbiCall::IndexIdFromName, out, |caller| {
+        Self::cvt_ret::<u32, A>(caller, AbiCall::IndexIdFromName, out, |caller| {
             let (mem, env) = Self::mem_env(caller);
             // Read the index name from WASM memory.
             let name = mem.deref_str(name, name_len)?;
@@ -369,8 +602,12 @@ impl WasmInstanceEnv {
     /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
     /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
     #[tracing::instrument(level = "trace", skip_all)]
-    pub fn datastore_table_row_count(caller: Caller<'_, Self>, table_id: u32, out: WasmPtr<u64>) -> RtResult<u32> {
-        Self::cvt_ret::<u64>(caller, AbiCall::DatastoreTableRowCount, out, |caller| {
+    pub fn datastore_table_row_count<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        table_id: u32,
+        out: WasmPtr<A, u64>,
+    ) -> RtResult<u32> {
+        Self::cvt_ret::<u64, A>(caller, AbiCall::DatastoreTableRowCount, out, |caller| {
             let (_, env) = Self::mem_env(caller);
             Ok(env.instance_env.datastore_table_row_count(table_id.into())?)
         })
@@ -392,20 +629,13 @@ impl WasmInstanceEnv {
     /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
     /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
     // #[tracing::instrument(level = "trace", skip_all)]
-    pub fn datastore_table_scan_bsatn(
+    pub fn datastore_table_scan_bsatn<A: WasmAddr>(
         caller: Caller<'_, Self>,
         table_id: u32,
-        out: WasmPtr<RowIterIdx>,
+        out: WasmPtr<A, RowIterIdx>,
     ) -> RtResult<u32> {
         Self::cvt_ret(caller, AbiCall::DatastoreTableScanBsatn, out, |caller| {
-            let env = caller.data_mut();
-            // Collect the iterator chunks.
-            let chunks = env
-                .instance_env
-                .datastore_table_scan_bsatn_chunks(&mut env.chunk_pool, table_id.into())?;
-            // Register the iterator and get back the index to write to `out`.
-            // Calls to the iterator are done through dynamic dispatch.
-            Ok(env.iters.insert(chunks.into_iter()))
+            Ok(caller.data_mut().scan_table(table_id.into())?)
         })
     }
 
@@ -471,17 +701,17 @@ impl WasmInstanceEnv {
     ///    Or when `rstart` or `rend` cannot be decoded to an `Bound<AlgebraicValue>`
     ///    where the inner `AlgebraicValue`s are
     ///    typed at the `prefix_elems + 1` `AlgebraicType` of the index's key type.
-    pub fn datastore_btree_scan_bsatn(
+    pub fn datastore_btree_scan_bsatn<A: WasmAddr>(
         caller: Caller<'_, Self>,
         index_id: u32,
-        prefix_ptr: WasmPtr<u8>,
-        prefix_len: u32,
+        prefix_ptr: WasmPtr<A, u8>,
+        prefix_len: A,
         prefix_elems: u32,
-        rstart_ptr: WasmPtr<u8>, // Bound<AlgebraicValue>
-        rstart_len: u32,
-        rend_ptr: WasmPtr<u8>, // Bound<AlgebraicValue>
-        rend_len: u32,
-        out: WasmPtr<RowIterIdx>,
+        rstart_ptr: WasmPtr<A, u8>, // Bound<AlgebraicValue>
+        rstart_len: A,
+        rend_ptr: WasmPtr<A, u8>, // Bound<AlgebraicValue>
+        rend_len: A,
+        out: WasmPtr<A, RowIterIdx>,
     ) -> RtResult<u32> {
         Self::cvt_ret(caller, AbiCall::DatastoreBtreeScanBsatn, out, |caller| {
             let prefix_elems = Self::convert_u32_to_col_id(prefix_elems)?;
@@ -496,18 +726,7 @@ impl WasmInstanceEnv {
             let rstart = mem.deref_slice(rstart_ptr, rstart_len)?;
             let rend = mem.deref_slice(rend_ptr, rend_len)?;
 
-            // Find the relevant rows.
-            let chunks = env.instance_env.datastore_btree_scan_bsatn_chunks(
-                &mut env.chunk_pool,
-                index_id.into(),
-                prefix,
-                prefix_elems,
-                rstart,
-                rend,
-            )?;
-
-            // Insert the encoded + concatenated rows into a new buffer and return its id.
-            Ok(env.iters.insert(chunks.into_iter()))
+            Ok(env.scan_btree(index_id.into(), prefix, prefix_elems, rstart, rend)?)
         })
     }
 
@@ -542,60 +761,50 @@ impl WasmInstanceEnv {
     ///   When this occurs, `buffer_len` is set to the size of the next item in the iterator.
     ///   To make progress, the caller should reallocate the buffer to at least that size and try again.
     // #[tracing::instrument(level = "trace", skip_all)]
-    pub fn row_iter_bsatn_advance(
+    pub fn row_iter_bsatn_advance<A: WasmAddr>(
         caller: Caller<'_, Self>,
         iter: u32,
-        buffer_ptr: WasmPtr<u8>,
-        buffer_len_ptr: WasmPtr<u32>,
+        buffer_ptr: WasmPtr<A, u8>,
+        buffer_len_ptr: WasmPtr<A, A>,
     ) -> RtResult<i32> {
         let row_iter_idx = RowIterIdx(iter);
         Self::cvt_custom(caller, AbiCall::RowIterBsatnAdvance, |caller| {
             let (mem, env) = Self::mem_env(caller);
 
-            // Retrieve the iterator by `row_iter_idx`, or error.
-            let Some(iter) = env.iters.get_mut(row_iter_idx) else {
-                return Ok(errno::NO_SUCH_ITER.get().into());
-            };
-
             // Read `buffer_len`, i.e., the capacity of `buffer` pointed to by `buffer_ptr`.
-            let buffer_len = u32::read_from(mem, buffer_len_ptr)?;
-            let write_buffer_len = |mem, len| u32::try_from(len).unwrap().write_to(mem, buffer_len_ptr);
+            let buffer_len = A::read_from(mem, buffer_len_ptr)?;
+            let write_buffer_len = |mem, len| A::from_len(len).write_to(mem, buffer_len_ptr);
             // Get a mutable view to the `buffer`.
             let mut buffer = mem.deref_slice_mut(buffer_ptr, buffer_len)?;
 
             let mut written = 0;
             // Fill the buffer as much as possible.
-            while let Some(chunk) = iter.as_slice().first() {
-                // TODO(Centril): refactor using `split_at_mut_checked`.
-                let Some(buf_chunk) = buffer.get_mut(..chunk.len()) else {
+            let advanced = env.advance_row_iter(row_iter_idx, |chunk| {
+                if chunk.len() > buffer.len() {
                     // Cannot fit chunk into the buffer,
                     // either because we already filled it too much,
                     // or because it is too small.
-                    break;
-                };
+                    return false;
+                }
+                let (buf_chunk, rest) = std::mem::take(&mut buffer).split_at_mut(chunk.len());
                 buf_chunk.copy_from_slice(chunk);
                 written += chunk.len();
-                buffer = &mut buffer[chunk.len()..];
-
-                // Advance the iterator, as we used a chunk.
-                // SAFETY: We peeked one `chunk`, so there must be one at least.
-                let chunk = unsafe { iter.next().unwrap_unchecked() };
-                env.chunk_pool.put(chunk);
-            }
+                buffer = rest;
+                true
+            });
 
-            let ret = match (written, iter.as_slice().first()) {
+            let ret = match (written, advanced) {
+                // There is no such iterator.
+                (_, None) => return Ok(errno::NO_SUCH_ITER.get().into()),
                 // Nothing was written and the iterator is not exhausted.
-                (0, Some(chunk)) => {
-                    write_buffer_len(mem, chunk.len())?;
+                (0, Some(Some(chunk_len))) => {
+                    write_buffer_len(mem, chunk_len)?;
                     return Ok(errno::BUFFER_TOO_SMALL.get().into());
                 }
-                // The iterator is exhausted, destroy it, and tell the caller.
-                (_, None) => {
-                    env.iters.take(row_iter_idx);
-                    -1
-                }
+                // The iterator is exhausted and was destroyed, tell the caller.
+                (_, Some(None)) => -1,
                 // Something was written, but the iterator is not exhausted.
-                (_, Some(_)) => 0,
+                (_, Some(Some(_))) => 0,
             };
             write_buffer_len(mem, written)?;
             Ok(ret)
@@ -613,16 +822,16 @@ impl WasmInstanceEnv {
     ///
     /// - `NO_SUCH_ITER`, when `iter` is not a valid iterator.
     // #[tracing::instrument(level = "trace", skip_all)]
-    pub fn row_iter_bsatn_close(caller: Caller<'_, Self>, iter: u32) -> RtResult<u32> {
+    #[allow(clippy::extra_unused_type_parameters)]
+    pub fn row_iter_bsatn_close<A: WasmAddr>(caller: Caller<'_, Self>, iter: u32) -> RtResult<u32> {
         let row_iter_idx = RowIterIdx(iter);
         Self::cvt_custom(caller, AbiCall::RowIterBsatnClose, |caller| {
             let (_, env) = Self::mem_env(caller);
 
-            // Retrieve the iterator by `row_iter_idx`, or error.
-            Ok(match env.iters.take(row_iter_idx) {
-                None => errno::NO_SUCH_ITER.get().into(),
-                // TODO(Centril): consider putting these into a pool for reuse.
-                Some(_) => 0,
+            // Destroy the iterator by `row_iter_idx`, or error.
+            Ok(match env.close_row_iter(row_iter_idx) {
+                false => errno::NO_SUCH_ITER.get().into(),
+                true => 0,
             })
         })
     }
@@ -661,23 +870,23 @@ impl WasmInstanceEnv {
     /// - `UNIQUE_ALREADY_EXISTS`, when inserting `row` would violate a unique constraint.
     /// - `SCHEDULE_AT_DELAY_TOO_LONG`, when the delay specified in the row was too long.
     #[tracing::instrument(level = "trace", skip_all)]
-    pub fn datastore_insert_bsatn(
+    pub fn datastore_insert_bsatn<A: WasmAddr>(
         caller: Caller<'_, Self>,
         table_id: u32,
-        row_ptr: WasmPtr<u8>,
-        row_len_ptr: WasmPtr<u32>,
+        row_ptr: WasmPtr<A, u8>,
+        row_len_ptr: WasmPtr<A, A>,
     ) -> RtResult<u32> {
         Self::cvt(caller, AbiCall::DatastoreInsertBsatn, |caller| {
             let (mem, env) = Self::mem_env(caller);
 
             // Read `row-len`, i.e., the capacity of `row` pointed to by `row_ptr`.
-            let row_len = u32::read_from(mem, row_len_ptr)?;
+            let row_len = A::read_from(mem, row_len_ptr)?;
             // Get a mutable view to the `row`.
             let row = mem.deref_slice_mut(row_ptr, row_len)?;
 
             // Insert the row into the DB and write back the generated column values.
             let row_len = env.instance_env.insert(table_id.into(), row)?;
-            u32::try_from(row_len).unwrap().write_to(mem, row_len_ptr)?;
+            A::from_len(row_len).write_to(mem, row_len_ptr)?;
             Ok(())
         })
     }
@@ -724,24 +933,24 @@ impl WasmInstanceEnv {
     /// - `UNIQUE_ALREADY_EXISTS`, when inserting `row` would violate a unique constraint.
     /// - `SCHEDULE_AT_DELAY_TOO_LONG`, when the delay specified in the row was too long.
     #[tracing::instrument(level = "trace", skip_all)]
-    pub fn datastore_update_bsatn(
+    pub fn datastore_update_bsatn<A: WasmAddr>(
         caller: Caller<'_, Self>,
         table_id: u32,
         index_id: u32,
-        row_ptr: WasmPtr<u8>,
-        row_len_ptr: WasmPtr<u32>,
+        row_ptr: WasmPtr<A, u8>,
+        row_len_ptr: WasmPtr<A, A>,
     ) -> RtResult<u32> {
         Self::cvt(caller, AbiCall::DatastoreUpdateBsatn, |caller| {
             let (mem, env) = Self::mem_env(caller);
 
             // Read `row-len`, i.e., the capacity of `row` pointed to by `row_ptr`.
-            let row_len = u32::read_from(mem, row_len_ptr)?;
+            let row_len = A::read_from(mem, row_len_ptr)?;
             // Get a mutable view to the `row`.
             let row = mem.deref_slice_mut(row_ptr, row_len)?;
 
             // Update the row in the DB and write back the generated column values.
             let row_len = env.instance_env.update(table_id.into(), index_id.into(), row)?;
-            u32::try_from(row_len).unwrap().write_to(mem, row_len_ptr)?;
+            A::from_len(row_len).write_to(mem, row_len_ptr)?;
             Ok(())
         })
     }
@@ -782,17 +991,17 @@ impl WasmInstanceEnv {
     ///    Or when `rstart` or `rend` cannot be decoded to an `Bound<AlgebraicValue>`
     ///    where the inner `AlgebraicValue`s are
     ///    typed at the `prefix_elems + 1` `AlgebraicType` of the index's key type.
-    pub fn datastore_delete_by_btree_scan_bsatn(
+    pub fn datastore_delete_by_btree_scan_bsatn<A: WasmAddr>(
         caller: Caller<'_, Self>,
         index_id: u32,
-        prefix_ptr: WasmPtr<u8>,
-        prefix_len: u32,
+        prefix_ptr: WasmPtr<A, u8>,
+        prefix_len: A,
         prefix_elems: u32,
-        rstart_ptr: WasmPtr<u8>, // Bound<AlgebraicValue>
-        rstart_len: u32,
-        rend_ptr: WasmPtr<u8>, // Bound<AlgebraicValue>
-        rend_len: u32,
-        out: WasmPtr<u32>,
+        rstart_ptr: WasmPtr<A, u8>, // Bound<AlgebraicValue>
+        rstart_len: A,
+        rend_ptr: WasmPtr<A, u8>, // Bound<AlgebraicValue>
+        rend_len: A,
+        out: WasmPtr<A, u32>,
     ) -> RtResult<u32> {
         Self::cvt_ret(caller, AbiCall::DatastoreDeleteByBtreeScanBsatn, out, |caller| {
             let prefix_elems = Self::convert_u32_to_col_id(prefix_elems)?;
@@ -845,12 +1054,12 @@ impl WasmInstanceEnv {
     /// - `BSATN_DECODE_ERROR`, when `rel` cannot be decoded to `Vec<ProductValue>`
     ///   where each `ProductValue` is typed at the `ProductType` the table's schema specifies.
     #[tracing::instrument(level = "trace", skip_all)]
-    pub fn datastore_delete_all_by_eq_bsatn(
+    pub fn datastore_delete_all_by_eq_bsatn<A: WasmAddr>(
         caller: Caller<'_, Self>,
         table_id: u32,
-        rel_ptr: WasmPtr<u8>,
-        rel_len: u32,
-        out: WasmPtr<u32>,
+        rel_ptr: WasmPtr<A, u8>,
+        rel_len: A,
+        out: WasmPtr<A, u32>,
     ) -> RtResult<u32> {
         Self::cvt_ret(caller, AbiCall::DatastoreDeleteAllByEqBsatn, out, |caller| {
             let (mem, env) = Self::mem_env(caller);
@@ -861,280 +1070,879 @@ impl WasmInstanceEnv {
         })
     }
 
-    pub fn volatile_nonatomic_schedule_immediate(
-        caller: Caller<'_, Self>,
-        name: WasmPtr<u8>,
-        name_len: u32,
-        args: WasmPtr<u8>,
-        args_len: u32,
-    ) -> RtResult<()> {
-        Self::with_span(caller, AbiCall::VolatileNonatomicScheduleImmediate, |caller| {
-            let (mem, env) = Self::mem_env(caller);
-            let name = mem.deref_str(name, name_len)?;
-            let args = mem.deref_slice(args, args_len)?;
-            env.instance_env.scheduler.volatile_nonatomic_schedule_immediate(
-                name.to_owned(),
-                crate::host::ReducerArgs::Bsatn(args.to_vec().into()),
-            );
-
-            Ok(())
-        })
-    }
-
-    /// Reads bytes from `source`, registered in the host environment,
-    /// and stores them in the memory pointed to by `buffer = buffer_ptr[..buffer_len]`.
+    /// Deletes all rows in the table identified by `table_id`.
     ///
-    /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
-    /// On success (`0` or `-1` is returned),
-    /// `buffer_len` is set to the number of bytes written to `buffer`.
-    /// When `-1` is returned, the resource has been exhausted
-    /// and there are no more bytes to read,
-    /// leading to the resource being immediately destroyed.
-    /// Note that the host is free to reuse allocations in a pool,
-    /// destroying the handle logically does not entail that memory is necessarily reclaimed.
+    /// Unlike deleting each row individually,
+    /// this clears the table's storage and indices wholesale when the transaction commits,
+    /// is logged as a single truncation,
+    /// and is reported to subscribers as the table having been cleared.
+    ///
+    /// The number of rows deleted is written to the WASM pointer `out`.
     ///
     /// # Traps
     ///
     /// Traps if:
-    ///
-    /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
-    /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+    /// - `out` is NULL or `out[..size_of::<u64>()]` is not in bounds of WASM memory.
     ///
     /// # Errors
     ///
     /// Returns an error:
     ///
-    /// - `NO_SUCH_BYTES`, when `source` is not a valid bytes source.
-    ///
-    /// # Example
-    ///
-    /// The typical use case for this ABI is in `__call_reducer__`,
-    /// to read and deserialize the `args`.
-    /// An example definition, dealing with `args` might be:
-    /// ```rust,ignore
-    /// /// #[no_mangle]
-    /// extern "C" fn __call_reducer__(..., args: BytesSource, ...) -> i16 {
-    ///     // ...
-    ///
-    ///     let mut buf = Vec::<u8>::with_capacity(1024);
-    ///     loop {
-    ///         // Write into the spare capacity of the buffer.
-    ///         let buf_ptr = buf.spare_capacity_mut();
-    ///         let spare_len = buf_ptr.len();
-    ///         let mut buf_len = buf_ptr.len();
-    ///         let buf_ptr = buf_ptr.as_mut_ptr().cast();
-    ///         let ret = unsafe { bytes_source_read(args, buf_ptr, &mut buf_len) };
-    ///         // SAFETY: `bytes_source_read` just appended `spare_len` bytes to `buf`.
-    ///         unsafe { buf.set_len(buf.len() + spare_len) };
-    ///         match ret {
-    ///             // Host side source exhausted, we're done.
-    ///             -1 => break,
-    ///             // Wrote the entire spare capacity.
-    ///             // Need to reserve more space in the buffer.
-    ///             0 if spare_len == buf_len => buf.reserve(1024),
-    ///             // Host didn't write as much as possible.
-    ///             // Try to read some more.
-    ///             // The host will likely not trigger this branch,
-    ///             // but a module should be prepared for it.
-    ///             0 => {}
-    ///             _ => unreachable!(),
-    ///         }
-    ///     }
-    ///
-    ///     // ...
-    /// }
-    /// ```
-    pub fn bytes_source_read(
+    /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+    /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn datastore_table_truncate<A: WasmAddr>(
         caller: Caller<'_, Self>,
-        source: u32,
-        buffer_ptr: WasmPtr<u8>,
-        buffer_len_ptr: WasmPtr<u32>,
-    ) -> RtResult<i32> {
-        Self::cvt_custom(caller, AbiCall::BytesSourceRead, |caller| {
-            let (mem, env) = Self::mem_env(caller);
-
-            // Retrieve the reducer args if available and requested, or error.
-            let Some((reducer_args, cursor)) = env
-                .call_reducer_args
-                .as_mut()
-                .filter(|_| source == CALL_REDUCER_ARGS_SOURCE)
-            else {
-                return Ok(errno::NO_SUCH_BYTES.get().into());
-            };
-
-            // Read `buffer_len`, i.e., the capacity of `buffer` pointed to by `buffer_ptr`.
-            let buffer_len = u32::read_from(mem, buffer_len_ptr)?;
-            // Get a mutable view to the `buffer`.
-            let buffer = mem.deref_slice_mut(buffer_ptr, buffer_len)?;
-            let buffer_len = buffer_len as usize;
-
-            // Derive the portion that we can read and what remains,
-            // based on what is left to read and the capacity.
-            let left_to_read = &reducer_args[*cursor..];
-            let can_read_len = buffer_len.min(left_to_read.len());
-            let (can_read, remainder) = left_to_read.split_at(can_read_len);
-            // Copy to the `buffer` and write written bytes count to `buffer_len`.
-            buffer[..can_read_len].copy_from_slice(can_read);
-            (can_read_len as u32).write_to(mem, buffer_len_ptr)?;
-
-            // Destroy the source if exhausted, or advance `cursor`.
-            if remainder.is_empty() {
-                env.call_reducer_args = None;
-                Ok(-1i32)
-            } else {
-                *cursor += can_read_len;
-                Ok(0)
-            }
+        table_id: u32,
+        out: WasmPtr<A, u64>,
+    ) -> RtResult<u32> {
+        Self::cvt_ret::<u64, A>(caller, AbiCall::DatastoreTableTruncate, out, |caller| {
+            let (_, env) = Self::mem_env(caller);
+            Ok(env.instance_env.datastore_table_truncate(table_id.into())?)
         })
     }
 
-    /// Writes up to `buffer_len` bytes from `buffer = buffer_ptr[..buffer_len]`,
-    /// to the `sink`, registered in the host environment.
+    /// Runs the batch of operations `ops = ops_ptr[..ops_len]` in WASM memory in one call,
+    /// where `ops` is a BSATN-encoded `Vec<BatchOp>`.
     ///
-    /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
-    /// On success (`0` is returned),
-    /// `buffer_len` is set to the number of bytes written to `sink`.
+    /// The operations run in order, each behaving as the host call of the same name would.
+    /// An operation failing with an error that its host call would return
+    /// doesn't stop the batch, but is reported in its result instead.
+    ///
+    /// On success, the handle of an iterator over the BSATN-encoded `Vec<BatchResult>`,
+    /// holding one result per operation, is written to `out`.
+    /// The iterator can be advanced by [`row_iter_bsatn_advance`].
     ///
     /// # Traps
     ///
-    /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
-    /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+    /// Traps if:
+    /// - `ops_ptr` is NULL or `ops` is not in bounds of WASM memory.
+    /// - `out` is NULL or `out[..size_of::<RowIter>()]` is not in bounds of WASM memory.
+    /// - an operation fails with an error that would trap its host call.
     ///
     /// # Errors
     ///
     /// Returns an error:
     ///
-    /// - `NO_SUCH_BYTES`, when `sink` is not a valid bytes sink.
-    /// - `NO_SPACE`, when there is no room for more bytes in `sink`.
-    ///    (Doesn't currently happen.)
-    pub fn bytes_sink_write(
+    /// - `BSATN_DECODE_ERROR`, when `ops` cannot be decoded to a `Vec<BatchOp>`.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn datastore_batch_bsatn<A: WasmAddr>(
         caller: Caller<'_, Self>,
-        sink: u32,
-        buffer_ptr: WasmPtr<u8>,
-        buffer_len_ptr: WasmPtr<u32>,
+        ops_ptr: WasmPtr<A, u8>,
+        ops_len: A,
+        out: WasmPtr<A, RowIterIdx>,
     ) -> RtResult<u32> {
-        Self::cvt_custom(caller, AbiCall::BytesSinkWrite, |caller| {
+        Self::cvt_ret(caller, AbiCall::DatastoreBatchBsatn, out, |caller| {
             let (mem, env) = Self::mem_env(caller);
+            let ops = mem.deref_slice(ops_ptr, ops_len)?;
+            let results = env.run_batch(ops)?;
+            Ok(env.iters.insert(vec![results].into_iter()))
+        })
+    }
 
-            // Retrieve the reducer args if available and requested, or error.
-            let Some(sink) = env.standard_bytes_sink.as_mut().filter(|_| sink == STANDARD_BYTES_SINK) else {
-                return Ok(errno::NO_SUCH_BYTES.get().into());
+    /// Runs the BSATN-encoded `Vec<BatchOp>` in `ops`,
+    /// returning the BSATN-encoded `Vec<BatchResult>`.
+    ///
+    /// Errors that map to an `errno` are reported per operation,
+    /// while any other error aborts the batch.
+    fn run_batch(&mut self, ops: &[u8]) -> Result<Vec<u8>, NodesError> {
+        let ops: Vec<BatchOp> = bsatn::from_slice(ops).map_err(NodesError::DecodeRow)?;
+        let instance_env = &self.instance_env;
+        let mut results = Vec::with_capacity(ops.len());
+        for op in ops {
+            let result = match op {
+                BatchOp::Find(find) => instance_env
+                    .datastore_btree_scan_bsatn_chunks(
+                        &mut self.chunk_pool,
+                        find.index_id,
+                        &find.prefix,
+                        find.prefix_elems,
+                        &find.rstart,
+                        &find.rend,
+                    )
+                    .map(|chunks| {
+                        let rows = chunks.concat();
+                        chunks.into_iter().for_each(|chunk| self.chunk_pool.put(chunk));
+                        BatchResult::Rows(rows)
+                    }),
+                BatchOp::Insert(BatchInsert { table_id, mut row }) => {
+                    instance_env.insert(table_id, &mut row).map(|row_len| {
+                        row.truncate(row_len);
+                        BatchResult::GeneratedColumns(row)
+                    })
+                }
+                BatchOp::Update(BatchUpdate {
+                    table_id,
+                    index_id,
+                    mut row,
+                }) => instance_env.update(table_id, index_id, &mut row).map(|row_len| {
+                    row.truncate(row_len);
+                    BatchResult::GeneratedColumns(row)
+                }),
+                BatchOp::DeleteByBtreeScan(find) => instance_env
+                    .datastore_delete_by_btree_scan_bsatn(
+                        find.index_id,
+                        &find.prefix,
+                        find.prefix_elems,
+                        &find.rstart,
+                        &find.rend,
+                    )
+                    .map(BatchResult::Deleted),
+                BatchOp::DeleteAllByEq(delete) => instance_env
+                    .datastore_delete_all_by_eq_bsatn(delete.table_id, &delete.relation)
+                    .map(BatchResult::Deleted),
             };
-
-            // Read `buffer_len`, i.e., the capacity of `buffer` pointed to by `buffer_ptr`.
-            let buffer_len = u32::read_from(mem, buffer_len_ptr)?;
-            // Write `buffer` to `sink`.
-            let buffer = mem.deref_slice(buffer_ptr, buffer_len)?;
-            sink.extend(buffer);
-
-            Ok(0)
-        })
+            results.push(match result {
+                Ok(result) => result,
+                Err(err) => BatchResult::Error(err_to_errno(&err).ok_or(err)?.get()),
+            });
+        }
+        Ok(bsatn::to_vec(&results).expect("encoding to a `Vec` can't fail"))
     }
 
-    /// Logs at `level` a `message` message occuring in `filename:line_number`
-    /// with [`target`](target) being the module path at the `log!` invocation site.
-    ///
-    /// These various pointers are interpreted lossily as UTF-8 strings with a corresponding `_len`.
-    ///
-    /// The `target` and `filename` pointers are ignored by passing `NULL`.
-    /// The line number is ignored if `line_number == u32::MAX`.
+    /// Takes a savepoint in the current transaction,
+    /// writing a handle to it to `out`.
     ///
-    /// No message is logged if
-    /// - `target != NULL && target + target_len > u64::MAX`
-    /// - `filename != NULL && filename + filename_len > u64::MAX`
-    /// - `message + message_len > u64::MAX`
+    /// The transaction can later be rolled back to the savepoint with [`savepoint_rollback`],
+    /// discarding only the writes made after it was taken.
+    /// Savepoints nest, and their handles are only valid until the end of the current reducer.
     ///
     /// # Traps
     ///
     /// Traps if:
-    /// - `target` is not NULL and `target_ptr[..target_len]` is not in bounds of WASM memory.
-    /// - `filename` is not NULL and `filename_ptr[..filename_len]` is not in bounds of WASM memory.
-    /// - `message` is not NULL and `message_ptr[..message_len]` is not in bounds of WASM memory.
+    /// - `out` is NULL or `out[..size_of::<u32>()]` is not in bounds of WASM memory.
     ///
-    /// [target]: https://docs.rs/log/latest/log/struct.Record.html#method.target
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
     #[tracing::instrument(level = "trace", skip_all)]
-    pub fn console_log(
-        caller: Caller<'_, Self>,
-        level: u32,
-        target_ptr: WasmPtr<u8>,
-        target_len: u32,
-        filename_ptr: WasmPtr<u8>,
-        filename_len: u32,
-        line_number: u32,
-        message_ptr: WasmPtr<u8>,
-        message_len: u32,
-    ) {
-        let do_console_log = |caller: &mut Caller<'_, Self>| -> WasmResult<()> {
-            let env = caller.data();
-            let mem = env.get_mem().view(&caller);
-
-            // Read the `target`, `filename`, and `message` strings from WASM memory.
-            let target = mem.deref_str_lossy(target_ptr, target_len).check_nullptr()?;
-            let filename = mem.deref_str_lossy(filename_ptr, filename_len).check_nullptr()?;
-            let message = mem.deref_str_lossy(message_ptr, message_len)?;
-
-            // The line number cannot be `u32::MAX` as this represents `Option::None`.
-            let line_number = (line_number != u32::MAX).then_some(line_number);
-
-            let record = Record {
-                // TODO: figure out whether to use walltime now or logical reducer now (env.reducer_start)
-                ts: chrono::Utc::now(),
-                target: target.as_deref(),
-                filename: filename.as_deref(),
-                line_number,
-                message: &message,
-            };
-
-            // Write the log record to the `DatabaseLogger` in the database instance context (replica_ctx).
-            env.instance_env
-                .console_log((level as u8).into(), &record, &caller.as_context());
-            Ok(())
-        };
-        Self::cvt_noret(caller, AbiCall::ConsoleLog, |caller| {
-            let _ = do_console_log(caller);
+    pub fn savepoint_begin<A: WasmAddr>(caller: Caller<'_, Self>, out: WasmPtr<A, u32>) -> RtResult<u32> {
+        Self::cvt_ret(caller, AbiCall::SavepointBegin, out, |caller| {
+            Ok(caller.data_mut().begin_savepoint()?)
         })
     }
 
-    /// Begins a timing span with `name = name_ptr[..name_len]`.
+    /// Rolls the current transaction back to the savepoint `savepoint`,
+    /// discarding the writes made since it was taken.
     ///
-    /// When the returned `ConsoleTimerId` is passed to [`console_timer_end`],
-    /// the duration between the calls will be printed to the module's logs.
+    /// The savepoint is released, along with any savepoints nested within it.
     ///
-    /// The `name` is interpreted lossily as a UTF-8 string.
+    /// # Errors
     ///
-    /// # Traps
+    /// Returns an error:
     ///
-    /// Traps if:
+    /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+    /// - `NO_SUCH_SAVEPOINT`, when `savepoint` is not a valid savepoint.
+    #[tracing::instrument(level = "trace", skip_all)]
+    #[allow(clippy::extra_unused_type_parameters)]
+    pub fn savepoint_rollback<A: WasmAddr>(caller: Caller<'_, Self>, savepoint: u32) -> RtResult<u32> {
+        Self::cvt_custom(caller, AbiCall::SavepointRollback, |caller| {
+            Ok(match caller.data_mut().rollback_to_savepoint(savepoint)? {
+                false => errno::NO_SUCH_SAVEPOINT.get().into(),
+                true => 0,
+            })
+        })
+    }
+
+    /// Releases the savepoint `savepoint`, along with any savepoints nested within it,
+    /// keeping the writes made since it was taken.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NO_SUCH_SAVEPOINT`, when `savepoint` is not a valid savepoint.
+    #[tracing::instrument(level = "trace", skip_all)]
+    #[allow(clippy::extra_unused_type_parameters)]
+    pub fn savepoint_release<A: WasmAddr>(caller: Caller<'_, Self>, savepoint: u32) -> RtResult<u32> {
+        Self::cvt_custom(caller, AbiCall::SavepointRelease, |caller| {
+            Ok(match caller.data_mut().release_savepoints(savepoint)? {
+                false => errno::NO_SUCH_SAVEPOINT.get().into(),
+                true => 0,
+            })
+        })
+    }
+
+    /// Writes the length in bytes of the blob `blob_id`
+    /// in the blob table identified by `table_id` to `out`.
+    ///
+    /// A blob table has exactly the columns `(blob_id: u64, chunk: u32, data: Vec<u8>)`.
+    /// A blob which was never written has length `0`.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `out` is NULL or `out[..size_of::<u64>()]` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+    /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+    /// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn blob_len<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        table_id: u32,
+        blob_id: u64,
+        out: WasmPtr<A, u64>,
+    ) -> RtResult<u32> {
+        Self::cvt_ret::<u64, A>(caller, AbiCall::BlobLen, out, |caller| {
+            let (_, env) = Self::mem_env(caller);
+            Ok(env.instance_env.blob_len(table_id.into(), blob_id)?)
+        })
+    }
+
+    /// Reads up to `buffer_len` bytes of the blob `blob_id`
+    /// in the blob table identified by `table_id`, starting at `offset`,
+    /// into `buffer = buffer_ptr[..buffer_len]`.
+    ///
+    /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+    /// On success (`0` is returned),
+    /// `buffer_len` is set to the number of bytes read,
+    /// which is less than the capacity only if the end of the blob was reached.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+    /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+    /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+    /// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn blob_read<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        table_id: u32,
+        blob_id: u64,
+        offset: u64,
+        buffer_ptr: WasmPtr<A, u8>,
+        buffer_len_ptr: WasmPtr<A, A>,
+    ) -> RtResult<u32> {
+        Self::cvt(caller, AbiCall::BlobRead, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+            let buffer_len = A::read_from(mem, buffer_len_ptr)?;
+            let buffer = mem.deref_slice_mut(buffer_ptr, buffer_len)?;
+            let data = env
+                .instance_env
+                .blob_read(table_id.into(), blob_id, offset, buffer.len())?;
+            buffer[..data.len()].copy_from_slice(&data);
+            A::from_len(data.len()).write_to(mem, buffer_len_ptr)?;
+            Ok(())
+        })
+    }
+
+    /// Writes `data = data_ptr[..data_len]` into the blob `blob_id`
+    /// in the blob table identified by `table_id`, starting at `offset`.
+    ///
+    /// Existing bytes are overwritten, and the blob grows as needed.
+    /// Writing to a blob which was never written creates it.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `data_ptr` is NULL or `data` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+    /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+    /// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+    /// - `BLOB_OUT_OF_BOUNDS`, when `offset` is past the end of the blob.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn blob_write<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        table_id: u32,
+        blob_id: u64,
+        offset: u64,
+        data_ptr: WasmPtr<A, u8>,
+        data_len: A,
+    ) -> RtResult<u32> {
+        Self::cvt(caller, AbiCall::BlobWrite, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+            let data = mem.deref_slice(data_ptr, data_len)?;
+            env.instance_env.blob_write(table_id.into(), blob_id, offset, data)?;
+            Ok(())
+        })
+    }
+
+    /// Deletes the blob `blob_id` in the blob table identified by `table_id`,
+    /// writing the number of chunks deleted to `out`.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `out` is NULL or `out[..size_of::<u32>()]` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+    /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+    /// - `NOT_A_BLOB_TABLE`, when the table does not have the column layout of a blob table.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn blob_delete<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        table_id: u32,
+        blob_id: u64,
+        out: WasmPtr<A, u32>,
+    ) -> RtResult<u32> {
+        Self::cvt_ret(caller, AbiCall::BlobDelete, out, |caller| {
+            let (_, env) = Self::mem_env(caller);
+            Ok(env.instance_env.blob_delete(table_id.into(), blob_id)?)
+        })
+    }
+
+    /// Writes the value which the sequence on the column `col_id` of the table `table_id`
+    /// will generate next to `out`, without consuming it.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `out` is NULL or `out[..size_of::<i128>()]` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+    /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+    /// - `NO_SUCH_SEQUENCE`, when `col_id` is not a column of the table with a sequence.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn sequence_peek<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        table_id: u32,
+        col_id: u32,
+        out: WasmPtr<A, i128>,
+    ) -> RtResult<u32> {
+        Self::cvt_ret::<i128, A>(caller, AbiCall::SequencePeek, out, |caller| {
+            let (_, env) = Self::mem_env(caller);
+            let col_id = Self::sequence_col_id(col_id)?;
+            Ok(env.instance_env.sequence_peek(table_id.into(), col_id)?)
+        })
+    }
+
+    /// Moves the sequence on the column `col_id` of the table `table_id`
+    /// past the value read from `value_ptr`,
+    /// so that the sequence won't later generate that value,
+    /// e.g., because it was inserted explicitly.
+    ///
+    /// Writes `1` to `out` if the sequence moved,
+    /// or `0` if it had already passed the value or the value is out of the sequence's range.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `value_ptr` is NULL or `value_ptr[..size_of::<i128>()]` is not in bounds of WASM memory.
+    /// - `out` is NULL or `out[..size_of::<u32>()]` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NOT_IN_TRANSACTION`, when called outside of a transaction.
+    /// - `NO_SUCH_TABLE`, when `table_id` is not a known ID of a table.
+    /// - `NO_SUCH_SEQUENCE`, when `col_id` is not a column of the table with a sequence.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn sequence_advance_past<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        table_id: u32,
+        col_id: u32,
+        value_ptr: WasmPtr<A, i128>,
+        out: WasmPtr<A, u32>,
+    ) -> RtResult<u32> {
+        Self::cvt_ret::<u32, A>(caller, AbiCall::SequenceAdvancePast, out, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+            let col_id = Self::sequence_col_id(col_id)?;
+            let value = i128::read_from(mem, value_ptr)?;
+            let moved = env.instance_env.sequence_advance_past(table_id.into(), col_id, value)?;
+            Ok(moved as u32)
+        })
+    }
+
+    /// Converts `col_id` from the ABI to a [`ColId`],
+    /// treating columns which can't exist as having no sequence.
+    fn sequence_col_id(col_id: u32) -> Result<ColId, NodesError> {
+        u16::try_from(col_id)
+            .map(ColId)
+            .map_err(|_| NodesError::SequenceNotFound)
+    }
+
+    /// Queries the length of the asset at the UTF-8 `path = path_ptr[..path_len]`
+    /// in the module's asset bundle, writing it to `out`.
+    ///
+    /// Assets are read-only, so this may be called outside of a transaction.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `path_ptr` is NULL or `path` is not in bounds of WASM memory.
+    /// - `path` is not valid UTF-8.
+    /// - `out` is NULL or `out[..size_of::<u64>()]` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NO_SUCH_ASSET`, when there is no asset at `path`.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn asset_len<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        path_ptr: WasmPtr<A, u8>,
+        path_len: A,
+        out: WasmPtr<A, u64>,
+    ) -> RtResult<u32> {
+        Self::cvt_ret::<u64, A>(caller, AbiCall::AssetLen, out, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+            let path = mem.deref_str(path_ptr, path_len)?;
+            Ok(env.instance_env.asset_len(path)?)
+        })
+    }
+
+    /// Reads up to `buffer_len` bytes of the asset at the UTF-8 `path = path_ptr[..path_len]`,
+    /// starting at `offset`, into `buffer = buffer_ptr[..buffer_len]`.
+    ///
+    /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+    /// On success (`0` is returned),
+    /// `buffer_len` is set to the number of bytes read,
+    /// which is less than the capacity only if the end of the asset was reached.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `path_ptr` is NULL or `path` is not in bounds of WASM memory.
+    /// - `path` is not valid UTF-8.
+    /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+    /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NO_SUCH_ASSET`, when there is no asset at `path`.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn asset_read<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        path_ptr: WasmPtr<A, u8>,
+        path_len: A,
+        offset: u64,
+        buffer_ptr: WasmPtr<A, u8>,
+        buffer_len_ptr: WasmPtr<A, A>,
+    ) -> RtResult<u32> {
+        Self::cvt(caller, AbiCall::AssetRead, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+            let path = mem.deref_str(path_ptr, path_len)?.to_owned();
+            let buffer_len = A::read_from(mem, buffer_len_ptr)?;
+            let buffer = mem.deref_slice_mut(buffer_ptr, buffer_len)?;
+            let data = env.instance_env.asset_read(&path, offset, buffer.len())?;
+            buffer[..data.len()].copy_from_slice(data);
+            A::from_len(data.len()).write_to(mem, buffer_len_ptr)?;
+            Ok(())
+        })
+    }
+
+    /// Writes what the host learned about the caller of the current reducer when it connected,
+    /// as a BSATN-encoded `Option<ClientMetadata>`, to `buffer = buffer_ptr[..buffer_len]`.
+    ///
+    /// The metadata is `None` unless the reducer was called by a connected client,
+    /// including when it is the `client_connected` or `client_disconnected` reducer.
+    ///
+    /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+    /// On success (`0` is returned), `buffer_len` is set to the length of the encoded metadata.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+    /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `BUFFER_TOO_SMALL`, when the encoded metadata cannot fit in `buffer`.
+    ///   When this occurs, `buffer_len` is set to the length of the encoded metadata.
+    ///   To make progress, the caller should reallocate the buffer to at least that size and try again.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn caller_metadata<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        buffer_ptr: WasmPtr<A, u8>,
+        buffer_len_ptr: WasmPtr<A, A>,
+    ) -> RtResult<u32> {
+        Self::cvt_custom(caller, AbiCall::CallerMetadata, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+            let metadata = &env.caller_metadata;
+            let buffer_len = A::read_from(mem, buffer_len_ptr)?;
+            let buffer = mem.deref_slice_mut(buffer_ptr, buffer_len)?;
+            let ret = match buffer.get_mut(..metadata.len()) {
+                Some(buffer) => {
+                    buffer.copy_from_slice(metadata);
+                    0
+                }
+                None => errno::BUFFER_TOO_SMALL.get().into(),
+            };
+            A::from_len(metadata.len()).write_to(mem, buffer_len_ptr)?;
+            Ok(ret)
+        })
+    }
+
+    /// Writes the value of the variable `key = key_ptr[..key_len]`
+    /// in the session of the caller of the current reducer, as UTF-8, to `buffer = buffer_ptr[..buffer_len]`.
+    ///
+    /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+    /// On success (`0` is returned), `buffer_len` is set to the length of the value.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `key_ptr` is NULL or `key` is not in bounds of WASM memory.
+    /// - `key` is not valid UTF-8.
+    /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+    /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NO_SUCH_SESSION_VARIABLE`, when the caller has no session or has not set `key` in it.
+    /// - `BUFFER_TOO_SMALL`, when the value cannot fit in `buffer`.
+    ///   When this occurs, `buffer_len` is set to the length of the value.
+    ///   To make progress, the caller should reallocate the buffer to at least that size and try again.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn session_get<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        key_ptr: WasmPtr<A, u8>,
+        key_len: A,
+        buffer_ptr: WasmPtr<A, u8>,
+        buffer_len_ptr: WasmPtr<A, A>,
+    ) -> RtResult<u32> {
+        Self::cvt_custom(caller, AbiCall::SessionGet, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+            let key = mem.deref_str(key_ptr, key_len)?;
+            let Some(value) = env.session_var(key) else {
+                return Ok(errno::NO_SUCH_SESSION_VARIABLE.get().into());
+            };
+            let buffer_len = A::read_from(mem, buffer_len_ptr)?;
+            let buffer = mem.deref_slice_mut(buffer_ptr, buffer_len)?;
+            let ret = match buffer.get_mut(..value.len()) {
+                Some(buffer) => {
+                    buffer.copy_from_slice(value.as_bytes());
+                    0
+                }
+                None => errno::BUFFER_TOO_SMALL.get().into(),
+            };
+            A::from_len(value.len()).write_to(mem, buffer_len_ptr)?;
+            Ok(ret)
+        })
+    }
+
+    pub fn volatile_nonatomic_schedule_immediate<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        name: WasmPtr<A, u8>,
+        name_len: A,
+        args: WasmPtr<A, u8>,
+        args_len: A,
+    ) -> RtResult<()> {
+        Self::with_span(caller, AbiCall::VolatileNonatomicScheduleImmediate, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+            let name = mem.deref_str(name, name_len)?;
+            let args = mem.deref_slice(args, args_len)?;
+            env.schedule_immediate(name.to_owned(), args.to_vec());
+            Ok(())
+        })
+    }
+
+    /// Broadcasts the message `payload = payload_ptr[..payload_len]`
+    /// on the topic `topic = topic_ptr[..topic_len]`.
+    ///
+    /// The message is sent to the clients subscribed to `topic`
+    /// once the current reducer's transaction commits,
+    /// and is dropped if it doesn't, or if the message was broadcast after a savepoint
+    /// which is then rolled back to.
+    /// Broadcast messages are never written to tables or to the commitlog.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `topic_ptr` is NULL or `topic` is not in bounds of WASM memory.
+    /// - `topic` is not valid UTF-8.
+    /// - `payload_ptr` is NULL or `payload` is not in bounds of WASM memory.
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn broadcast<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        topic_ptr: WasmPtr<A, u8>,
+        topic_len: A,
+        payload_ptr: WasmPtr<A, u8>,
+        payload_len: A,
+    ) -> RtResult<()> {
+        Self::with_span(caller, AbiCall::Broadcast, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+            let topic = mem.deref_str(topic_ptr, topic_len)?;
+            let payload = mem.deref_slice(payload_ptr, payload_len)?;
+            env.push_broadcast(topic, payload.to_vec());
+            Ok(())
+        })
+    }
+
+    /// Reads bytes from `source`, registered in the host environment,
+    /// and stores them in the memory pointed to by `buffer = buffer_ptr[..buffer_len]`.
+    ///
+    /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+    /// On success (`0` or `-1` is returned),
+    /// `buffer_len` is set to the number of bytes written to `buffer`.
+    /// When `-1` is returned, the resource has been exhausted
+    /// and there are no more bytes to read,
+    /// leading to the resource being immediately destroyed.
+    /// Note that the host is free to reuse allocations in a pool,
+    /// destroying the handle logically does not entail that memory is necessarily reclaimed.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    ///
+    /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+    /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NO_SUCH_BYTES`, when `source` is not a valid bytes source.
+    ///
+    /// # Example
+    ///
+    /// The typical use case for this ABI is in `__call_reducer__`,
+    /// to read and deserialize the `args`.
+    /// An example definition, dealing with `args` might be:
+    /// ```rust,ignore
+    /// /// #[no_mangle]
+    /// extern "C" fn __call_reducer__(..., args: BytesSource, ...) -> i16 {
+    ///     // ...
+    ///
+    ///     let mut buf = Vec::<u8>::with_capacity(1024);
+    ///     loop {
+    ///         // Write into the spare capacity of the buffer.
+    ///         let buf_ptr = buf.spare_capacity_mut();
+    ///         let spare_len = buf_ptr.len();
+    ///         let mut buf_len = buf_ptr.len();
+    ///         let buf_ptr = buf_ptr.as_mut_ptr().cast();
+    ///         let ret = unsafe { bytes_source_read(args, buf_ptr, &mut buf_len) };
+    ///         // SAFETY: `bytes_source_read` just appended `spare_len` bytes to `buf`.
+    ///         unsafe { buf.set_len(buf.len() + spare_len) };
+    ///         match ret {
+    ///             // Host side source exhausted, we're done.
+    ///             -1 => break,
+    ///             // Wrote the entire spare capacity.
+    ///             // Need to reserve more space in the buffer.
+    ///             0 if spare_len == buf_len => buf.reserve(1024),
+    ///             // Host didn't write as much as possible.
+    ///             // Try to read some more.
+    ///             // The host will likely not trigger this branch,
+    ///             // but a module should be prepared for it.
+    ///             0 => {}
+    ///             _ => unreachable!(),
+    ///         }
+    ///     }
+    ///
+    ///     // ...
+    /// }
+    /// ```
+    pub fn bytes_source_read<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        source: u32,
+        buffer_ptr: WasmPtr<A, u8>,
+        buffer_len_ptr: WasmPtr<A, A>,
+    ) -> RtResult<i32> {
+        Self::cvt_custom(caller, AbiCall::BytesSourceRead, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+
+            // Retrieve the reducer args if available and requested, or error.
+            let Some((reducer_args, cursor)) = env
+                .call_reducer_args
+                .as_mut()
+                .filter(|_| source == CALL_REDUCER_ARGS_SOURCE)
+            else {
+                return Ok(errno::NO_SUCH_BYTES.get().into());
+            };
+
+            // Read `buffer_len`, i.e., the capacity of `buffer` pointed to by `buffer_ptr`.
+            let buffer_len = A::read_from(mem, buffer_len_ptr)?;
+            // Get a mutable view to the `buffer`.
+            let buffer = mem.deref_slice_mut(buffer_ptr, buffer_len)?;
+            let buffer_len = buffer.len();
+
+            // Derive the portion that we can read and what remains,
+            // based on what is left to read and the capacity.
+            let left_to_read = &reducer_args[*cursor..];
+            let can_read_len = buffer_len.min(left_to_read.len());
+            let (can_read, remainder) = left_to_read.split_at(can_read_len);
+            // Copy to the `buffer` and write written bytes count to `buffer_len`.
+            buffer[..can_read_len].copy_from_slice(can_read);
+            A::from_len(can_read_len).write_to(mem, buffer_len_ptr)?;
+
+            // Destroy the source if exhausted, or advance `cursor`.
+            if remainder.is_empty() {
+                env.call_reducer_args = None;
+                Ok(-1i32)
+            } else {
+                *cursor += can_read_len;
+                Ok(0)
+            }
+        })
+    }
+
+    /// Writes up to `buffer_len` bytes from `buffer = buffer_ptr[..buffer_len]`,
+    /// to the `sink`, registered in the host environment.
+    ///
+    /// The `buffer_len = buffer_len_ptr[..size_of::<usize>()]` stores the capacity of `buffer`.
+    /// On success (`0` is returned),
+    /// `buffer_len` is set to the number of bytes written to `sink`.
+    ///
+    /// # Traps
+    ///
+    /// - `buffer_len_ptr` is NULL or `buffer_len` is not in bounds of WASM memory.
+    /// - `buffer_ptr` is NULL or `buffer` is not in bounds of WASM memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error:
+    ///
+    /// - `NO_SUCH_BYTES`, when `sink` is not a valid bytes sink.
+    /// - `NO_SPACE`, when there is no room for more bytes in `sink`.
+    ///    (Doesn't currently happen.)
+    pub fn bytes_sink_write<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        sink: u32,
+        buffer_ptr: WasmPtr<A, u8>,
+        buffer_len_ptr: WasmPtr<A, A>,
+    ) -> RtResult<u32> {
+        Self::cvt_custom(caller, AbiCall::BytesSinkWrite, |caller| {
+            let (mem, env) = Self::mem_env(caller);
+
+            // Retrieve the requested sink if available, or error.
+            let sink = match sink {
+                STANDARD_BYTES_SINK => env.standard_bytes_sink.as_mut(),
+                VIEW_RESULT_SINK => env.view_result_sink.as_mut(),
+                _ => None,
+            };
+            let Some(sink) = sink else {
+                return Ok(errno::NO_SUCH_BYTES.get().into());
+            };
+
+            // Read `buffer_len`, i.e., the capacity of `buffer` pointed to by `buffer_ptr`.
+            let buffer_len = A::read_from(mem, buffer_len_ptr)?;
+            // Write `buffer` to `sink`.
+            let buffer = mem.deref_slice(buffer_ptr, buffer_len)?;
+            sink.extend(buffer);
+
+            Ok(0)
+        })
+    }
+
+    /// Logs at `level` a `message` message occuring in `filename:line_number`
+    /// with [`target`](target) being the module path at the `log!` invocation site.
+    ///
+    /// These various pointers are interpreted lossily as UTF-8 strings with a corresponding `_len`.
+    ///
+    /// The `target` and `filename` pointers are ignored by passing `NULL`.
+    /// The line number is ignored if `line_number == u32::MAX`.
+    ///
+    /// No message is logged if
+    /// - `target != NULL && target + target_len > u64::MAX`
+    /// - `filename != NULL && filename + filename_len > u64::MAX`
+    /// - `message + message_len > u64::MAX`
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
+    /// - `target` is not NULL and `target_ptr[..target_len]` is not in bounds of WASM memory.
+    /// - `filename` is not NULL and `filename_ptr[..filename_len]` is not in bounds of WASM memory.
+    /// - `message` is not NULL and `message_ptr[..message_len]` is not in bounds of WASM memory.
+    ///
+    /// [target]: https://docs.rs/log/latest/log/struct.Record.html#method.target
+    #[tracing::instrument(level = "trace", skip_all)]
+    pub fn console_log<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        level: u32,
+        target_ptr: WasmPtr<A, u8>,
+        target_len: A,
+        filename_ptr: WasmPtr<A, u8>,
+        filename_len: A,
+        line_number: u32,
+        message_ptr: WasmPtr<A, u8>,
+        message_len: A,
+    ) {
+        let do_console_log = |caller: &mut Caller<'_, Self>| -> WasmResult<()> {
+            let env = caller.data();
+            let mem = env.get_mem().view(&caller);
+
+            // Read the `target`, `filename`, and `message` strings from WASM memory.
+            let target = mem.deref_str_lossy(target_ptr, target_len).check_nullptr()?;
+            let filename = mem.deref_str_lossy(filename_ptr, filename_len).check_nullptr()?;
+            let message = mem.deref_str_lossy(message_ptr, message_len)?;
+
+            // The line number cannot be `u32::MAX` as this represents `Option::None`.
+            let line_number = (line_number != u32::MAX).then_some(line_number);
+
+            env.log_message(
+                (level as u8).into(),
+                target.as_deref(),
+                filename.as_deref(),
+                line_number,
+                &message,
+                &caller.as_context(),
+            );
+            Ok(())
+        };
+        Self::cvt_noret(caller, AbiCall::ConsoleLog, |caller| {
+            let _ = do_console_log(caller);
+        })
+    }
+
+    /// Begins a timing span with `name = name_ptr[..name_len]`.
+    ///
+    /// When the returned `ConsoleTimerId` is passed to [`console_timer_end`],
+    /// the duration between the calls will be printed to the module's logs.
+    ///
+    /// The `name` is interpreted lossily as a UTF-8 string.
+    ///
+    /// # Traps
+    ///
+    /// Traps if:
     /// - `name_ptr` is NULL or `name` is not in bounds of WASM memory.
-    pub fn console_timer_start(caller: Caller<'_, Self>, name_ptr: WasmPtr<u8>, name_len: u32) -> RtResult<u32> {
+    pub fn console_timer_start<A: WasmAddr>(
+        caller: Caller<'_, Self>,
+        name_ptr: WasmPtr<A, u8>,
+        name_len: A,
+    ) -> RtResult<u32> {
         Self::with_span(caller, AbiCall::ConsoleTimerStart, |caller| {
             let (mem, env) = Self::mem_env(caller);
             let name = mem.deref_str_lossy(name_ptr, name_len)?.into_owned();
-            Ok(env.timing_spans.insert(TimingSpan::new(name)).0)
+            Ok(env.start_timing_span(name))
         })
     }
 
-    pub fn console_timer_end(caller: Caller<'_, Self>, span_id: u32) -> RtResult<u32> {
+    #[allow(clippy::extra_unused_type_parameters)]
+    pub fn console_timer_end<A: WasmAddr>(caller: Caller<'_, Self>, span_id: u32) -> RtResult<u32> {
         Self::cvt_custom(caller, AbiCall::ConsoleTimerEnd, |caller| {
-            let Some(span) = caller.data_mut().timing_spans.take(TimingSpanIdx(span_id)) else {
+            let Some(message) = caller.data_mut().end_timing_span(span_id) else {
                 return Ok(errno::NO_SUCH_CONSOLE_TIMER.get().into());
             };
-
-            let elapsed = span.start.elapsed();
-            let message = format!("Timing span {:?}: {:?}", &span.name, elapsed);
-
-            let record = Record {
-                ts: chrono::Utc::now(),
-                target: None,
-                filename: None,
-                line_number: None,
-                message: &message,
-            };
-            caller.data().instance_env.console_log(
-                crate::database_logger::LogLevel::Info,
-                &record,
-                &caller.as_context(),
-            );
+            let bt = &caller.as_context();
+            caller
+                .data()
+                .log_message(LogLevel::Info, None, None, None, &message, bt);
             Ok(0)
         })
     }
@@ -1146,14 +1954,14 @@ impl WasmInstanceEnv {
     /// Traps if:
     ///
     /// - `out_ptr` is NULL or `out` is not in bounds of WASM memory.
-    pub fn identity(caller: Caller<'_, Self>, out_ptr: WasmPtr<u8>) -> RtResult<()> {
+    pub fn identity<A: WasmAddr>(caller: Caller<'_, Self>, out_ptr: WasmPtr<A, u8>) -> RtResult<()> {
         // Use `with_span` rather than one of the `cvt_*` functions,
         // as we want to possibly trap, but not to return an error code.
         Self::with_span(caller, AbiCall::Identity, |caller| {
             let (mem, env) = Self::mem_env(caller);
             let identity = env.instance_env.replica_ctx.database.database_identity;
-            // We're implicitly casting `out_ptr` to `WasmPtr<Identity>` here.
-            // (Both types are actually `u32`.)
+            // We're implicitly casting `out_ptr` to `WasmPtr<A, Identity>` here.
+            // (Both types are actually `A`.)
             // This works because `Identity::write_to` does not require an aligned pointer,
             // as it gets a `&mut [u8]` from WASM memory and does `copy_from_slice` with it.
             identity.write_to(mem, out_ptr)?;
@@ -1162,6 +1970,335 @@ impl WasmInstanceEnv {
     }
 }
 
+/// The host functions of modules compiled as WASM components.
+///
+/// These share their semantics with the core module ABI above,
+/// but receive and return buffers directly, rather than through linear memory.
+impl WasmInstanceEnv {
+    /// Call the function `run` with the name `func`, tracking the time spent in it.
+    fn component_span<R>(&mut self, func: AbiCall, run: impl FnOnce(&mut Self) -> R) -> R {
+        let span_start = span::CallSpanStart::new(func);
+        let result = run(self);
+        let span = span_start.end();
+        span::record_span(&mut self.call_times, span);
+        result
+    }
+
+    /// Call the function `run` with the name `func`,
+    /// converting database errors into an `errno`, as for [`Self::cvt`].
+    fn cvt_component<T>(
+        &mut self,
+        func: AbiCall,
+        run: impl FnOnce(&mut Self) -> Result<T, NodesError>,
+    ) -> RtResult<Result<T, host::Errno>> {
+        match self.component_span(func, run) {
+            Ok(ret) => Ok(Ok(ret)),
+            Err(err) => Self::convert_wasm_result(func, err.into()).map(Err),
+        }
+    }
+
+    /// Splits `value` into the words of a [`host::SequenceValue`].
+    fn to_sequence_value(value: i128) -> host::SequenceValue {
+        host::SequenceValue {
+            low: value as u64,
+            high: (value >> 64) as i64,
+        }
+    }
+
+    /// Joins the words of `value` back into an `i128`.
+    fn from_sequence_value(value: host::SequenceValue) -> i128 {
+        (i128::from(value.high) << 64) | i128::from(value.low)
+    }
+}
+
+impl host::Host for WasmInstanceEnv {
+    fn table_id_from_name(&mut self, name: String) -> RtResult<Result<host::TableId, host::Errno>> {
+        self.cvt_component(AbiCall::TableIdFromName, |env| {
+            Ok(env.instance_env.table_id_from_name(&name)?.into())
+        })
+    }
+
+    fn index_id_from_name(&mut self, name: String) -> RtResult<Result<host::IndexId, host::Errno>> {
+        self.cvt_component(AbiCall::IndexIdFromName, |env| {
+            Ok(env.instance_env.index_id_from_name(&name)?.into())
+        })
+    }
+
+    fn datastore_table_row_count(&mut self, table_id: host::TableId) -> RtResult<Result<u64, host::Errno>> {
+        self.cvt_component(AbiCall::DatastoreTableRowCount, |env| {
+            env.instance_env.datastore_table_row_count(table_id.into())
+        })
+    }
+
+    fn datastore_table_scan_bsatn(&mut self, table_id: host::TableId) -> RtResult<Result<host::RowIter, host::Errno>> {
+        self.cvt_component(AbiCall::DatastoreTableScanBsatn, |env| {
+            Ok(env.scan_table(table_id.into())?.0)
+        })
+    }
+
+    fn datastore_btree_scan_bsatn(
+        &mut self,
+        index_id: host::IndexId,
+        prefix: Vec<u8>,
+        prefix_elems: host::ColId,
+        rstart: Vec<u8>,
+        rend: Vec<u8>,
+    ) -> RtResult<Result<host::RowIter, host::Errno>> {
+        self.cvt_component(AbiCall::DatastoreBtreeScanBsatn, |env| {
+            let iter = env.scan_btree(index_id.into(), &prefix, prefix_elems.into(), &rstart, &rend)?;
+            Ok(iter.0)
+        })
+    }
+
+    fn row_iter_bsatn_advance(&mut self, iter: host::RowIter) -> RtResult<Result<(Vec<u8>, bool), host::Errno>> {
+        self.component_span(AbiCall::RowIterBsatnAdvance, |env| {
+            // There's no buffer to fill, so hand over a single chunk at a time.
+            let mut rows = None;
+            let advanced = env.advance_row_iter(RowIterIdx(iter), |chunk| {
+                let first = rows.is_none();
+                if first {
+                    rows = Some(chunk.to_vec());
+                }
+                first
+            });
+            Ok(match advanced {
+                None => Err(errno::NO_SUCH_ITER.get()),
+                Some(next) => Ok((rows.unwrap_or_default(), next.is_none())),
+            })
+        })
+    }
+
+    fn row_iter_bsatn_close(&mut self, iter: host::RowIter) -> RtResult<Result<(), host::Errno>> {
+        self.component_span(AbiCall::RowIterBsatnClose, |env| {
+            Ok(match env.close_row_iter(RowIterIdx(iter)) {
+                false => Err(errno::NO_SUCH_ITER.get()),
+                true => Ok(()),
+            })
+        })
+    }
+
+    fn datastore_insert_bsatn(
+        &mut self,
+        table_id: host::TableId,
+        mut row: Vec<u8>,
+    ) -> RtResult<Result<Vec<u8>, host::Errno>> {
+        self.cvt_component(AbiCall::DatastoreInsertBsatn, |env| {
+            let row_len = env.instance_env.insert(table_id.into(), &mut row)?;
+            row.truncate(row_len);
+            Ok(row)
+        })
+    }
+
+    fn datastore_update_bsatn(
+        &mut self,
+        table_id: host::TableId,
+        index_id: host::IndexId,
+        mut row: Vec<u8>,
+    ) -> RtResult<Result<Vec<u8>, host::Errno>> {
+        self.cvt_component(AbiCall::DatastoreUpdateBsatn, |env| {
+            let row_len = env.instance_env.update(table_id.into(), index_id.into(), &mut row)?;
+            row.truncate(row_len);
+            Ok(row)
+        })
+    }
+
+    fn datastore_delete_by_btree_scan_bsatn(
+        &mut self,
+        index_id: host::IndexId,
+        prefix: Vec<u8>,
+        prefix_elems: host::ColId,
+        rstart: Vec<u8>,
+        rend: Vec<u8>,
+    ) -> RtResult<Result<u32, host::Errno>> {
+        self.cvt_component(AbiCall::DatastoreDeleteByBtreeScanBsatn, |env| {
+            env.instance_env.datastore_delete_by_btree_scan_bsatn(
+                index_id.into(),
+                &prefix,
+                prefix_elems.into(),
+                &rstart,
+                &rend,
+            )
+        })
+    }
+
+    fn datastore_delete_all_by_eq_bsatn(
+        &mut self,
+        table_id: host::TableId,
+        relation: Vec<u8>,
+    ) -> RtResult<Result<u32, host::Errno>> {
+        self.cvt_component(AbiCall::DatastoreDeleteAllByEqBsatn, |env| {
+            env.instance_env
+                .datastore_delete_all_by_eq_bsatn(table_id.into(), &relation)
+        })
+    }
+
+    fn datastore_table_truncate(&mut self, table_id: host::TableId) -> RtResult<Result<u64, host::Errno>> {
+        self.cvt_component(AbiCall::DatastoreTableTruncate, |env| {
+            env.instance_env.datastore_table_truncate(table_id.into())
+        })
+    }
+
+    fn datastore_batch_bsatn(&mut self, ops: Vec<u8>) -> RtResult<Result<Vec<u8>, host::Errno>> {
+        self.cvt_component(AbiCall::DatastoreBatchBsatn, |env| env.run_batch(&ops))
+    }
+
+    fn savepoint_begin(&mut self) -> RtResult<Result<host::Savepoint, host::Errno>> {
+        self.cvt_component(AbiCall::SavepointBegin, |env| env.begin_savepoint())
+    }
+
+    fn savepoint_rollback(&mut self, savepoint: host::Savepoint) -> RtResult<Result<(), host::Errno>> {
+        let rolled_back = self.cvt_component(AbiCall::SavepointRollback, |env| env.rollback_to_savepoint(savepoint))?;
+        Ok(rolled_back.and_then(|valid| valid.then_some(()).ok_or(errno::NO_SUCH_SAVEPOINT.get())))
+    }
+
+    fn savepoint_release(&mut self, savepoint: host::Savepoint) -> RtResult<Result<(), host::Errno>> {
+        let released = self.cvt_component(AbiCall::SavepointRelease, |env| env.release_savepoints(savepoint))?;
+        Ok(released.and_then(|valid| valid.then_some(()).ok_or(errno::NO_SUCH_SAVEPOINT.get())))
+    }
+
+    fn blob_len(&mut self, table_id: host::TableId, blob_id: u64) -> RtResult<Result<u64, host::Errno>> {
+        self.cvt_component(AbiCall::BlobLen, |env| {
+            env.instance_env.blob_len(table_id.into(), blob_id)
+        })
+    }
+
+    fn blob_read(
+        &mut self,
+        table_id: host::TableId,
+        blob_id: u64,
+        offset: u64,
+        len: u32,
+    ) -> RtResult<Result<Vec<u8>, host::Errno>> {
+        self.cvt_component(AbiCall::BlobRead, |env| {
+            env.instance_env
+                .blob_read(table_id.into(), blob_id, offset, len as usize)
+        })
+    }
+
+    fn blob_write(
+        &mut self,
+        table_id: host::TableId,
+        blob_id: u64,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> RtResult<Result<(), host::Errno>> {
+        self.cvt_component(AbiCall::BlobWrite, |env| {
+            env.instance_env.blob_write(table_id.into(), blob_id, offset, &data)
+        })
+    }
+
+    fn blob_delete(&mut self, table_id: host::TableId, blob_id: u64) -> RtResult<Result<u32, host::Errno>> {
+        self.cvt_component(AbiCall::BlobDelete, |env| {
+            env.instance_env.blob_delete(table_id.into(), blob_id)
+        })
+    }
+
+    fn sequence_peek(
+        &mut self,
+        table_id: host::TableId,
+        col_id: host::ColId,
+    ) -> RtResult<Result<host::SequenceValue, host::Errno>> {
+        self.cvt_component(AbiCall::SequencePeek, |env| {
+            let value = env.instance_env.sequence_peek(table_id.into(), col_id.into())?;
+            Ok(Self::to_sequence_value(value))
+        })
+    }
+
+    fn sequence_advance_past(
+        &mut self,
+        table_id: host::TableId,
+        col_id: host::ColId,
+        value: host::SequenceValue,
+    ) -> RtResult<Result<bool, host::Errno>> {
+        self.cvt_component(AbiCall::SequenceAdvancePast, |env| {
+            let value = Self::from_sequence_value(value);
+            env.instance_env
+                .sequence_advance_past(table_id.into(), col_id.into(), value)
+        })
+    }
+
+    fn asset_len(&mut self, path: String) -> RtResult<Result<u64, host::Errno>> {
+        self.cvt_component(AbiCall::AssetLen, |env| env.instance_env.asset_len(&path))
+    }
+
+    fn asset_read(&mut self, path: String, offset: u64, len: u32) -> RtResult<Result<Vec<u8>, host::Errno>> {
+        self.cvt_component(AbiCall::AssetRead, |env| {
+            let data = env.instance_env.asset_read(&path, offset, len as usize)?;
+            Ok(data.to_vec())
+        })
+    }
+
+    fn caller_metadata(&mut self) -> RtResult<Vec<u8>> {
+        self.component_span(AbiCall::CallerMetadata, |env| Ok(env.caller_metadata.clone()))
+    }
+
+    fn session_get(&mut self, key: String) -> RtResult<Option<String>> {
+        self.component_span(AbiCall::SessionGet, |env| Ok(env.session_var(&key).map(Into::into)))
+    }
+
+    fn volatile_nonatomic_schedule_immediate(&mut self, name: String, args: Vec<u8>) -> RtResult<()> {
+        self.component_span(AbiCall::VolatileNonatomicScheduleImmediate, |env| {
+            env.schedule_immediate(name, args);
+            Ok(())
+        })
+    }
+
+    fn broadcast(&mut self, topic: String, payload: Vec<u8>) -> RtResult<()> {
+        self.component_span(AbiCall::Broadcast, |env| {
+            env.push_broadcast(&topic, payload);
+            Ok(())
+        })
+    }
+
+    fn console_log(
+        &mut self,
+        level: host::LogLevel,
+        target: Option<String>,
+        filename: Option<String>,
+        line_number: Option<u32>,
+        message: String,
+    ) -> RtResult<()> {
+        let level = match level {
+            host::LogLevel::Error => LogLevel::Error,
+            host::LogLevel::Warn => LogLevel::Warn,
+            host::LogLevel::Info => LogLevel::Info,
+            host::LogLevel::Debug => LogLevel::Debug,
+            host::LogLevel::Trace => LogLevel::Trace,
+            host::LogLevel::Panic => LogLevel::Panic,
+        };
+        self.component_span(AbiCall::ConsoleLog, |env| {
+            let (target, filename) = (target.as_deref(), filename.as_deref());
+            // Host functions of components don't have access to the caller's backtrace.
+            env.log_message(level, target, filename, line_number, &message, &());
+        });
+        Ok(())
+    }
+
+    fn console_timer_start(&mut self, name: String) -> RtResult<host::ConsoleTimer> {
+        self.component_span(AbiCall::ConsoleTimerStart, |env| Ok(env.start_timing_span(name)))
+    }
+
+    fn console_timer_end(&mut self, timer: host::ConsoleTimer) -> RtResult<Result<(), host::Errno>> {
+        self.component_span(AbiCall::ConsoleTimerEnd, |env| {
+            let Some(message) = env.end_timing_span(timer) else {
+                return Ok(Err(errno::NO_SUCH_CONSOLE_TIMER.get()));
+            };
+            env.log_message(LogLevel::Info, None, None, None, &message, &());
+            Ok(Ok(()))
+        })
+    }
+
+    fn identity(&mut self) -> RtResult<host::Identity> {
+        self.component_span(AbiCall::Identity, |env| {
+            let identity = env.instance_env.replica_ctx.database.database_identity;
+            let [word_0, word_1, word_2, word_3] = bytemuck::must_cast(identity.to_byte_array());
+            Ok(host::Identity {
+                words: (word_0, word_1, word_2, word_3),
+            })
+        })
+    }
+}
+
 impl<T> BacktraceProvider for wasmtime::StoreContext<'_, T> {
     fn capture(&self) -> Box<dyn ModuleBacktrace> {
         Box::new(wasmtime::WasmBacktrace::capture(self))
@@ -1172,9 +2309,24 @@ impl ModuleBacktrace for wasmtime::WasmBacktrace {
     fn frames(&self) -> Vec<BacktraceFrame<'_>> {
         self.frames()
             .iter()
-            .map(|f| BacktraceFrame {
-                module_name: None,
-                func_name: f.func_name(),
+            .flat_map(|f| {
+                let unsymbolicated = f.symbols().is_empty().then(|| BacktraceFrame {
+                    module_name: None,
+                    func_name: f.func_name(),
+                    filename: None,
+                    line_number: None,
+                    column: None,
+                });
+                // When the module has DWARF debug info, a frame has a symbol for each function inlined into it,
+                // innermost first.
+                let symbols = f.symbols().iter().map(|sym| BacktraceFrame {
+                    module_name: None,
+                    func_name: sym.name().or(f.func_name()),
+                    filename: sym.file(),
+                    line_number: sym.line(),
+                    column: sym.column(),
+                });
+                unsymbolicated.into_iter().chain(symbols)
             })
             .collect()
     }
@@ -0,0 +1,339 @@
+use self::bindings::host::{Address, Identity};
+use self::bindings::ReducerError;
+use self::module_host_actor::{ReducerOp, ViewOp};
+
+use super::wasm_instance_env::WasmInstanceEnv;
+use super::wasmtime_module::{get_store_fuel, log_traceback, set_store_deadline, set_store_fuel, trap_timed_out};
+use crate::energy::ReducerBudget;
+use crate::host::instance_env::InstanceEnv;
+use crate::host::wasm_common::module_host_actor::{DescribeError, InitializationError, ReducerFailure};
+use crate::host::wasm_common::*;
+use wasmtime::component::Linker;
+use wasmtime::Store;
+
+pub(super) mod bindings {
+    wasmtime::component::bindgen!({
+        path: "wit/spacetimedb.wit",
+        world: "module",
+        trappable_imports: true,
+    });
+
+    pub use self::spacetimedb::module::host;
+}
+
+/// A module compiled as a WASM component targeting the `module` world of `wit/spacetimedb.wit`.
+#[derive(Clone)]
+pub struct WasmtimeComponent {
+    component: bindings::ModulePre<WasmInstanceEnv>,
+}
+
+impl WasmtimeComponent {
+    pub(super) fn new(component: bindings::ModulePre<WasmInstanceEnv>) -> Self {
+        WasmtimeComponent { component }
+    }
+
+    pub(super) fn link_imports(linker: &mut Linker<WasmInstanceEnv>) -> anyhow::Result<()> {
+        bindings::Module::add_to_linker(linker, |env| env)
+    }
+}
+
+impl module_host_actor::WasmModule for WasmtimeComponent {
+    type Instance = WasmtimeComponentInstance;
+    type InstancePre = Self;
+
+    fn func_names(&self) -> Result<FuncNames, ValidationError> {
+        // The exports of a component are checked against the world when it is linked,
+        // and components have no preinit functions.
+        Ok(FuncNames::default())
+    }
+
+    fn instantiate_pre(&self) -> Result<Self::InstancePre, InitializationError> {
+        Ok(self.clone())
+    }
+}
+
+impl module_host_actor::WasmInstancePre for WasmtimeComponent {
+    type Instance = WasmtimeComponentInstance;
+
+    fn instantiate(&self, env: InstanceEnv, _func_names: &FuncNames) -> Result<Self::Instance, InitializationError> {
+        let env = WasmInstanceEnv::new(env);
+        let mut store = Store::new(self.component.engine(), env);
+        set_store_deadline(&mut store);
+
+        // Note: this budget is just for initializers
+        set_store_fuel(&mut store, ReducerBudget::DEFAULT_BUDGET.into());
+
+        let bindings = self
+            .component
+            .instantiate(&mut store)
+            .map_err(InitializationError::Instantiation)?;
+
+        Ok(WasmtimeComponentInstance { store, bindings })
+    }
+}
+
+pub struct WasmtimeComponentInstance {
+    store: Store<WasmInstanceEnv>,
+    bindings: bindings::Module,
+}
+
+/// Split a caller's identity and address into LITTLE-ENDIAN words.
+fn caller_words(identity: &crate::identity::Identity, address: &spacetimedb_lib::Address) -> (Identity, Address) {
+    let [sender_0, sender_1, sender_2, sender_3] = bytemuck::must_cast(identity.to_byte_array());
+    let [address_0, address_1] = bytemuck::must_cast(address.as_byte_array());
+    (
+        Identity {
+            words: (sender_0, sender_1, sender_2, sender_3),
+        },
+        Address {
+            words: (address_0, address_1),
+        },
+    )
+}
+
+impl module_host_actor::WasmInstance for WasmtimeComponentInstance {
+    fn extract_descriptions(&mut self) -> Result<Vec<u8>, DescribeError> {
+        let describer_func_name = "describe-module";
+
+        let start = std::time::Instant::now();
+        log::trace!("Start describer \"{}\"...", describer_func_name);
+
+        let result = self.bindings.call_describe_module(&mut self.store);
+
+        let duration = start.elapsed();
+        log::trace!("Describer \"{}\" ran: {} us", describer_func_name, duration.as_micros());
+
+        result
+            .inspect_err(|err| log_traceback("describer", describer_func_name, err))
+            .map_err(DescribeError::RuntimeError)
+    }
+
+    fn instance_env(&self) -> &InstanceEnv {
+        self.store.data().instance_env()
+    }
+
+    type Trap = anyhow::Error;
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn call_reducer(
+        &mut self,
+        op: ReducerOp<'_>,
+        budget: ReducerBudget,
+    ) -> module_host_actor::ExecuteResult<Self::Trap> {
+        let store = &mut self.store;
+        set_store_fuel(store, budget.into());
+        set_store_deadline(store);
+
+        let (sender, address) = caller_words(op.caller_identity, op.caller_address);
+
+        // Components receive their arguments directly, rather than through a bytes source.
+        store
+            .data_mut()
+            .start_reducer(op.name, bytes::Bytes::new(), op.caller_metadata, op.caller_session);
+
+        let call_result = self.bindings.call_call_reducer(
+            &mut *store,
+            op.id.0,
+            sender,
+            address,
+            op.timestamp.microseconds,
+            &op.arg_bytes,
+        );
+
+        let (timings, _) = store.data_mut().finish_reducer();
+        let broadcasts = store.data_mut().take_broadcasts();
+
+        let call_result = call_result.map(|res| {
+            res.map_err(|err| match err {
+                ReducerError::Message(errmsg) => ReducerFailure::Message(errmsg.into()),
+                ReducerError::Value(value) => ReducerFailure::Value(value.into()),
+            })
+        });
+
+        let remaining: ReducerBudget = get_store_fuel(store).into();
+        let energy = module_host_actor::EnergyStats {
+            used: (budget - remaining).into(),
+            remaining,
+        };
+
+        module_host_actor::ExecuteResult {
+            energy,
+            timings,
+            call_result,
+            broadcasts,
+        }
+    }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn call_view(&mut self, op: ViewOp<'_>, budget: ReducerBudget) -> module_host_actor::ViewExecuteResult<Self::Trap> {
+        let store = &mut self.store;
+        set_store_fuel(store, budget.into());
+        set_store_deadline(store);
+
+        let (sender, address) = caller_words(op.caller_identity, op.caller_address);
+
+        store.data_mut().start_view(op.name, bytes::Bytes::new());
+
+        let call_result = self.bindings.call_call_view(
+            &mut *store,
+            op.id.0,
+            sender,
+            address,
+            op.timestamp.microseconds,
+            &op.arg_bytes,
+        );
+
+        let (timings, _, _) = store.data_mut().finish_view();
+
+        let call_result = call_result.map(|res| res.map_err(Into::into));
+
+        let remaining: ReducerBudget = get_store_fuel(store).into();
+        let energy = module_host_actor::EnergyStats {
+            used: (budget - remaining).into(),
+            remaining,
+        };
+
+        module_host_actor::ViewExecuteResult {
+            energy,
+            timings,
+            call_result,
+        }
+    }
+
+    fn log_traceback(func_type: &str, func: &str, trap: &Self::Trap) {
+        log_traceback(func_type, func, trap)
+    }
+
+    fn timed_out(trap: &Self::Trap) -> bool {
+        trap_timed_out(trap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::relational_db::tests_utils::TestDB;
+    use crate::host::wasm_common::module_host_actor::{WasmInstance, WasmInstancePre};
+    use crate::host::wasmtime::tests_utils::instance_env;
+    use crate::host::wasmtime::WasmtimeRuntime;
+    use crate::host::ReducerId;
+    use spacetimedb_client_api_messages::timestamp::Timestamp;
+    use spacetimedb_paths::server::ServerDataDir;
+
+    /// A component which describes itself as the bytes `[1, 2, 3]`,
+    /// and whose reducers broadcast their arguments on the topic `fixture`.
+    const FIXTURE: &str = r#"
+        (component
+            (import "spacetimedb:module/host@10.0.0" (instance $host
+                (type $identity (record (field "words" (tuple u64 u64 u64 u64))))
+                (export "identity" (type (eq $identity)))
+                (type $address (record (field "words" (tuple u64 u64))))
+                (export "address" (type (eq $address)))
+                (export "broadcast" (func (param "topic" string) (param "payload" (list u8))))
+            ))
+            (alias export $host "identity" (type $identity))
+            (alias export $host "address" (type $address))
+
+            (core module $memory
+                (memory (export "memory") 1)
+            )
+            (core instance $memory (instantiate $memory))
+            (alias core export $memory "memory" (core memory $mem))
+            (core func $broadcast (canon lower (func $host "broadcast") (memory $mem)))
+            (core instance $host-lowered (export "broadcast" (func $broadcast)))
+
+            (core module $module
+                (import "env" "memory" (memory 1))
+                (import "host" "broadcast" (func $broadcast (param i32 i32 i32 i32)))
+                (global $heap (mut i32) (i32.const 1024))
+                (data (i32.const 0) "fixture")
+                ;; The list `[1, 2, 3]` at 16, and a pointer to it and its length at 32.
+                (data (i32.const 16) "\01\02\03")
+                (data (i32.const 32) "\10\00\00\00\03\00\00\00")
+                ;; The results are written to 48, which is zeroed, so they are all `ok` and empty.
+                (func (export "cabi_realloc") (param i32 i32 i32 i32) (result i32)
+                    (local $ptr i32)
+                    (local.set $ptr (i32.and (i32.add (global.get $heap) (i32.const 7)) (i32.const -8)))
+                    (global.set $heap (i32.add (local.get $ptr) (local.get 3)))
+                    (local.get $ptr)
+                )
+                (func (export "describe-module") (result i32)
+                    (i32.const 32)
+                )
+                (func (export "call-reducer")
+                    (param i32 i64 i64 i64 i64 i64 i64 i64 i32 i32) (result i32)
+                    (call $broadcast (i32.const 0) (i32.const 7) (local.get 8) (local.get 9))
+                    (i32.const 48)
+                )
+                (func (export "call-view")
+                    (param i32 i64 i64 i64 i64 i64 i64 i64 i32 i32) (result i32)
+                    (i32.const 48)
+                )
+            )
+            (core instance $module (instantiate $module
+                (with "env" (instance $memory))
+                (with "host" (instance $host-lowered))
+            ))
+            (alias core export $module "cabi_realloc" (core func $realloc))
+
+            (type $reducer-error (variant (case "message" string) (case "value" (list u8))))
+            (export $reducer-error-export "reducer-error" (type $reducer-error))
+
+            (func (export "describe-module") (result (list u8))
+                (canon lift (core func $module "describe-module") (memory $mem))
+            )
+            (func (export "call-reducer")
+                (param "id" u32) (param "sender" $identity) (param "address" $address)
+                (param "timestamp" u64) (param "args" (list u8))
+                (result (result (error $reducer-error-export)))
+                (canon lift (core func $module "call-reducer") (memory $mem) (realloc $realloc))
+            )
+            (func (export "call-view")
+                (param "id" u32) (param "sender" $identity) (param "address" $address)
+                (param "timestamp" u64) (param "args" (list u8))
+                (result (result (list u8) (error string)))
+                (canon lift (core func $module "call-view") (memory $mem) (realloc $realloc))
+            )
+        )
+    "#;
+
+    #[test]
+    fn call_component_fixture() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let runtime = WasmtimeRuntime::new(&ServerDataDir(data_dir.path().to_owned()));
+
+        // Linking checks that the fixture exports the functions of the world.
+        let component = wasmtime::component::Component::new(&runtime.engine, FIXTURE).unwrap();
+        let component = runtime
+            .component_linker
+            .instantiate_pre(&component)
+            .and_then(bindings::ModulePre::new)
+            .unwrap();
+
+        let (env, _dir) = instance_env(TestDB::in_memory().unwrap());
+        let mut instance = WasmtimeComponent::new(component)
+            .instantiate(env, &FuncNames::default())
+            .unwrap();
+
+        assert_eq!(instance.extract_descriptions().unwrap(), [1, 2, 3]);
+
+        let (identity, address) = (crate::identity::Identity::ZERO, spacetimedb_lib::Address::ZERO);
+        let op = ReducerOp {
+            id: ReducerId(0),
+            name: "say",
+            caller_identity: &identity,
+            caller_address: &address,
+            caller_metadata: None,
+            caller_session: None,
+            timestamp: Timestamp::now(),
+            arg_bytes: bytes::Bytes::from_static(b"hello"),
+        };
+        let result = instance.call_reducer(op, ReducerBudget::DEFAULT_BUDGET);
+        assert!(matches!(result.call_result, Ok(Ok(()))));
+        let [message] = &result.broadcasts[..] else {
+            panic!("expected one broadcast, got {:?}", result.broadcasts);
+        };
+        assert_eq!(&*message.topic, "fixture");
+        assert_eq!(&message.payload[..], b"hello");
+    }
+}
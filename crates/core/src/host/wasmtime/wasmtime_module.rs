@@ -1,16 +1,16 @@
-use self::module_host_actor::ReducerOp;
+use self::module_host_actor::{ReducerOp, ViewOp};
 
 use super::wasm_instance_env::WasmInstanceEnv;
-use super::{Mem, WasmtimeFuel};
+use super::{deadline_ticks, Mem, WasmAddr, WasmtimeFuel};
 use crate::energy::ReducerBudget;
 use crate::host::instance_env::InstanceEnv;
-use crate::host::wasm_common::module_host_actor::{DescribeError, InitializationError};
+use crate::host::wasm_common::module_host_actor::{DescribeError, InitializationError, ReducerFailure};
 use crate::host::wasm_common::*;
 use crate::util::string_from_utf8_lossy_owned;
-use spacetimedb_primitives::errno::HOST_CALL_FAILURE;
-use wasmtime::{AsContext, AsContextMut, ExternType, Instance, InstancePre, Linker, Store, TypedFunc, WasmBacktrace};
+use spacetimedb_primitives::errno::{HOST_CALL_FAILURE, HOST_CALL_FAILURE_VALUE};
+use wasmtime::{AsContext, AsContextMut, Instance, InstancePre, Linker, Store, TypedFunc, WasmBacktrace};
 
-fn log_traceback(func_type: &str, func: &str, e: &wasmtime::Error) {
+pub(super) fn log_traceback(func_type: &str, func: &str, e: &wasmtime::Error) {
     log::info!("{} \"{}\" runtime error: {}", func_type, func, e);
     if let Some(bt) = e.downcast_ref::<WasmBacktrace>() {
         let frames_len = bt.frames().len();
@@ -20,6 +20,11 @@ fn log_traceback(func_type: &str, func: &str, e: &wasmtime::Error) {
                 frames_len - i,
                 rustc_demangle::demangle(frame.func_name().unwrap_or("<unknown>"))
             );
+            for sym in frame.symbols() {
+                if let (Some(file), Some(line)) = (sym.file(), sym.line()) {
+                    log::info!("    at {}:{}:{}", file, line, sym.column().unwrap_or(0));
+                }
+            }
         }
     }
 }
@@ -34,14 +39,15 @@ impl WasmtimeModule {
         WasmtimeModule { module }
     }
 
-    pub const IMPLEMENTED_ABI: abi::VersionTuple = abi::VersionTuple::new(10, 0);
+    pub const IMPLEMENTED_ABI: abi::VersionTuple = abi::VersionTuple::new(10, 9);
 
-    pub(super) fn link_imports(linker: &mut Linker<WasmInstanceEnv>) -> anyhow::Result<()> {
+    /// Links the host functions, taking pointers and lengths of type `A`, into `linker`.
+    pub(super) fn link_imports<A: WasmAddr>(linker: &mut Linker<WasmInstanceEnv>) -> anyhow::Result<()> {
         #[allow(clippy::assertions_on_constants)]
         const _: () = assert!(WasmtimeModule::IMPLEMENTED_ABI.major == spacetimedb_lib::MODULE_ABI_MAJOR_VERSION);
         macro_rules! link_functions {
             ($($module:literal :: $func:ident,)*) => {
-                linker$(.func_wrap($module, stringify!($func), WasmInstanceEnv::$func)?)*;
+                linker$(.func_wrap($module, stringify!($func), WasmInstanceEnv::$func::<A>)?)*;
             }
         }
         abi_funcs!(link_functions);
@@ -53,21 +59,15 @@ impl module_host_actor::WasmModule for WasmtimeModule {
     type Instance = WasmtimeInstance;
     type InstancePre = Self;
 
-    type ExternType = ExternType;
-
-    fn get_export(&self, s: &str) -> Option<Self::ExternType> {
-        self.module
-            .module()
-            .exports()
-            .find(|exp| exp.name() == s)
-            .map(|exp| exp.ty())
-    }
-
-    fn for_each_export<E>(&self, mut f: impl FnMut(&str, &Self::ExternType) -> Result<(), E>) -> Result<(), E> {
-        self.module
-            .module()
-            .exports()
-            .try_for_each(|exp| f(exp.name(), &exp.ty()))
+    fn func_names(&self) -> Result<FuncNames, ValidationError> {
+        let module = self.module.module();
+        FuncNames::check_required(|name| module.get_export(name))?;
+        let mut func_names = FuncNames::default();
+        for exp in module.exports() {
+            func_names.update_from_general(exp.name(), &exp.ty())?;
+        }
+        func_names.preinits.sort_unstable();
+        Ok(func_names)
     }
 
     fn instantiate_pre(&self) -> Result<Self::InstancePre, InitializationError> {
@@ -75,15 +75,17 @@ impl module_host_actor::WasmModule for WasmtimeModule {
     }
 }
 
-fn handle_error_sink_code(code: i32, error: Vec<u8>) -> Result<(), Box<str>> {
+fn handle_error_sink_code(code: i32, error: Vec<u8>) -> Result<(), ReducerFailure> {
     match code {
         0 => Ok(()),
-        CALL_FAILURE => Err(string_from_utf8_lossy_owned(error).into()),
-        _ => Err("unknown return code".into()),
+        CALL_FAILURE => Err(ReducerFailure::Message(string_from_utf8_lossy_owned(error).into())),
+        CALL_FAILURE_VALUE => Err(ReducerFailure::Value(error.into())),
+        _ => Err(ReducerFailure::Message("unknown return code".into())),
     }
 }
 
 const CALL_FAILURE: i32 = HOST_CALL_FAILURE.get() as i32;
+const CALL_FAILURE_VALUE: i32 = HOST_CALL_FAILURE_VALUE.get() as i32;
 
 impl module_host_actor::WasmInstancePre for WasmtimeModule {
     type Instance = WasmtimeInstance;
@@ -91,6 +93,7 @@ impl module_host_actor::WasmInstancePre for WasmtimeModule {
     fn instantiate(&self, env: InstanceEnv, func_names: &FuncNames) -> Result<Self::Instance, InitializationError> {
         let env = WasmInstanceEnv::new(env);
         let mut store = Store::new(self.module.module().engine(), env);
+        set_store_deadline(&mut store);
         let instance = self
             .module
             .instantiate(&mut store)
@@ -128,21 +131,26 @@ impl module_host_actor::WasmInstancePre for WasmtimeModule {
         let call_reducer = instance
             .get_typed_func(&mut store, CALL_REDUCER_DUNDER)
             .expect("no call_reducer");
+        let call_view = instance.get_typed_func(&mut store, CALL_VIEW_DUNDER).ok();
 
         Ok(WasmtimeInstance {
             store,
             instance,
             call_reducer,
+            call_view,
         })
     }
 }
 
 type CallReducerType = TypedFunc<(u32, u64, u64, u64, u64, u64, u64, u64, u32, u32), i32>;
+type CallViewType = TypedFunc<(u32, u64, u64, u64, u64, u64, u64, u64, u32, u32, u32), i32>;
 
 pub struct WasmtimeInstance {
     store: Store<WasmInstanceEnv>,
     instance: Instance,
     call_reducer: CallReducerType,
+    /// `None` if the module doesn't define any views.
+    call_view: Option<CallViewType>,
 }
 
 impl module_host_actor::WasmInstance for WasmtimeInstance {
@@ -192,13 +200,16 @@ impl module_host_actor::WasmInstance for WasmtimeInstance {
         // EnergyQuanta at the end of this function, from_energy_quanta clamps it to a u64 range.
         // otherwise, we'd return something like `used: i128::MAX - u64::MAX`, which is inaccurate.
         set_store_fuel(store, budget.into());
+        set_store_deadline(store);
 
         // Prepare sender identity and address, as LITTLE-ENDIAN byte arrays.
         let [sender_0, sender_1, sender_2, sender_3] = bytemuck::must_cast(op.caller_identity.to_byte_array());
         let [address_0, address_1] = bytemuck::must_cast(op.caller_address.as_byte_array());
 
         // Prepare arguments to the reducer + the error sink & start timings.
-        let (args_source, errors_sink) = store.data_mut().start_reducer(op.name, op.arg_bytes);
+        let env = store.data_mut();
+        let (args_source, errors_sink) =
+            env.start_reducer(op.name, op.arg_bytes, op.caller_metadata, op.caller_session);
 
         let call_result = self.call_reducer.call(
             &mut *store,
@@ -220,6 +231,7 @@ impl module_host_actor::WasmInstance for WasmtimeInstance {
         // associated to our reducer call, and clears all of the instance state
         // associated to the call.
         let (timings, error) = store.data_mut().finish_reducer();
+        let broadcasts = store.data_mut().take_broadcasts();
 
         let call_result = call_result.map(|code| handle_error_sink_code(code, error));
 
@@ -233,26 +245,169 @@ impl module_host_actor::WasmInstance for WasmtimeInstance {
             energy,
             timings,
             call_result,
+            broadcasts,
+        }
+    }
+
+    #[tracing::instrument(level = "trace", skip_all)]
+    fn call_view(&mut self, op: ViewOp<'_>, budget: ReducerBudget) -> module_host_actor::ViewExecuteResult<Self::Trap> {
+        let store = &mut self.store;
+        set_store_fuel(store, budget.into());
+        set_store_deadline(store);
+
+        // Prepare sender identity and address, as LITTLE-ENDIAN byte arrays.
+        let [sender_0, sender_1, sender_2, sender_3] = bytemuck::must_cast(op.caller_identity.to_byte_array());
+        let [address_0, address_1] = bytemuck::must_cast(op.caller_address.as_byte_array());
+
+        let (args_source, result_sink, errors_sink) = store.data_mut().start_view(op.name, op.arg_bytes);
+
+        let call_result = match &self.call_view {
+            Some(call_view) => call_view.call(
+                &mut *store,
+                (
+                    op.id.0,
+                    sender_0,
+                    sender_1,
+                    sender_2,
+                    sender_3,
+                    address_0,
+                    address_1,
+                    op.timestamp.microseconds,
+                    args_source,
+                    result_sink,
+                    errors_sink,
+                ),
+            ),
+            None => Err(anyhow::anyhow!("module does not export `{CALL_VIEW_DUNDER}`")),
+        };
+
+        let (timings, result, error) = store.data_mut().finish_view();
+
+        // Only reducers can fail with values of an error type.
+        let call_result = call_result.map(|code| match handle_error_sink_code(code, error) {
+            Ok(()) => Ok(result),
+            Err(ReducerFailure::Message(errmsg)) => Err(errmsg),
+            Err(ReducerFailure::Value(_)) => Err("unknown return code".into()),
+        });
+
+        let remaining: ReducerBudget = get_store_fuel(store).into();
+        let energy = module_host_actor::EnergyStats {
+            used: (budget - remaining).into(),
+            remaining,
+        };
+
+        module_host_actor::ViewExecuteResult {
+            energy,
+            timings,
+            call_result,
         }
     }
 
     fn log_traceback(func_type: &str, func: &str, trap: &Self::Trap) {
         log_traceback(func_type, func, trap)
     }
+
+    fn timed_out(trap: &Self::Trap) -> bool {
+        trap_timed_out(trap)
+    }
 }
 
-fn set_store_fuel(store: &mut impl AsContextMut, fuel: WasmtimeFuel) {
+pub(super) fn set_store_fuel(store: &mut impl AsContextMut, fuel: WasmtimeFuel) {
     store.as_context_mut().set_fuel(fuel.0).unwrap();
 }
 
-fn get_store_fuel(store: &impl AsContext) -> WasmtimeFuel {
+pub(super) fn get_store_fuel(store: &impl AsContext) -> WasmtimeFuel {
     WasmtimeFuel(store.as_context().get_fuel().unwrap())
 }
 
+/// Sets the deadline for the next call into `store`
+/// from the database's maximum reducer duration, if any.
+pub(super) fn set_store_deadline(store: &mut Store<WasmInstanceEnv>) {
+    let max_duration = store
+        .data()
+        .instance_env()
+        .replica_ctx
+        .quotas
+        .read()
+        .max_reducer_duration();
+    store.set_epoch_deadline(deadline_ticks(max_duration));
+}
+
+/// Returns whether `err` is the trap of a call interrupted for running past its deadline.
+pub(super) fn trap_timed_out(err: &wasmtime::Error) -> bool {
+    matches!(err.downcast_ref::<wasmtime::Trap>(), Some(wasmtime::Trap::Interrupt))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::db::relational_db::tests_utils::TestDB;
     use crate::energy::EnergyQuanta;
+    use crate::host::wasm_common::module_host_actor::{WasmInstance, WasmInstancePre, WasmModule};
+    use crate::host::wasmtime::tests_utils::instance_env;
+    use crate::host::wasmtime::WasmtimeRuntime;
+    use crate::host::ReducerId;
+    use spacetimedb_client_api_messages::timestamp::Timestamp;
+    use spacetimedb_paths::server::ServerDataDir;
+    use std::time::Duration;
+
+    /// A `wasm64` module which describes itself as the bytes `[1, 2, 3]`,
+    /// and whose reducers broadcast them on the topic `fixture`.
+    const WASM64_FIXTURE: &str = r#"
+        (module
+            (import "spacetime_10.0" "bytes_sink_write" (func $bytes_sink_write (param i32 i64 i64) (result i32)))
+            (import "spacetime_10.5" "broadcast" (func $broadcast (param i64 i64 i64 i64)))
+            (memory (export "memory") i64 1)
+            (data (i64.const 0) "fixture")
+            ;; The bytes `[1, 2, 3]` at 16, and their length at 32.
+            (data (i64.const 16) "\01\02\03")
+            (data (i64.const 32) "\03\00\00\00\00\00\00\00")
+            (func (export "__describe_module__") (param i32)
+                (drop (call $bytes_sink_write (local.get 0) (i64.const 16) (i64.const 32)))
+            )
+            (func (export "__call_reducer__")
+                (param i32 i64 i64 i64 i64 i64 i64 i64 i32 i32) (result i32)
+                (call $broadcast (i64.const 0) (i64.const 7) (i64.const 16) (i64.const 3))
+                (i32.const 0)
+            )
+        )
+    "#;
+
+    #[test]
+    fn call_wasm64_fixture() {
+        let data_dir = tempfile::tempdir().unwrap();
+        let runtime = WasmtimeRuntime::new(&ServerDataDir(data_dir.path().to_owned()));
+
+        // The fixture only links against the 64-bit host functions.
+        let module = wasmtime::Module::new(&runtime.engine, WASM64_FIXTURE).unwrap();
+        assert!(runtime.linker.instantiate_pre(&module).is_err());
+        let module = WasmtimeModule::new(runtime.linker_for(&module).instantiate_pre(&module).unwrap());
+
+        let (env, _dir) = instance_env(TestDB::in_memory().unwrap());
+        let func_names = module.func_names().unwrap();
+        let mut instance = module.instantiate(env, &func_names).unwrap();
+
+        assert_eq!(instance.extract_descriptions().unwrap(), [1, 2, 3]);
+
+        let (identity, address) = (crate::identity::Identity::ZERO, spacetimedb_lib::Address::ZERO);
+        let op = ReducerOp {
+            id: ReducerId(0),
+            name: "say",
+            caller_identity: &identity,
+            caller_address: &address,
+            caller_metadata: None,
+            caller_session: None,
+            timestamp: Timestamp::now(),
+            arg_bytes: bytes::Bytes::new(),
+        };
+        let result = instance.call_reducer(op, ReducerBudget::DEFAULT_BUDGET);
+        assert!(matches!(result.call_result, Ok(Ok(()))));
+        let [message] = &result.broadcasts[..] else {
+            panic!("expected one broadcast, got {:?}", result.broadcasts);
+        };
+        assert_eq!(&*message.topic, "fixture");
+        assert_eq!(&message.payload[..], [1, 2, 3]);
+    }
 
     #[test]
     fn test_fuel() {
@@ -267,4 +422,50 @@ mod tests {
         let used = EnergyQuanta::from(budget) - remaining;
         assert_eq!(used, EnergyQuanta::new(10_000));
     }
+
+    #[test]
+    fn test_epoch_ticks() {
+        use super::super::{epoch_ticks, EPOCH_TICK, NO_DEADLINE};
+        assert_eq!(epoch_ticks(Duration::ZERO), 0);
+        assert_eq!(epoch_ticks(EPOCH_TICK), 1);
+        assert_eq!(epoch_ticks(Duration::from_millis(15)), 2);
+        assert_eq!(epoch_ticks(Duration::MAX), u64::MAX);
+        assert_eq!(deadline_ticks(Some(Duration::from_millis(15))), 2);
+        assert_eq!(deadline_ticks(Some(Duration::MAX)), NO_DEADLINE);
+        assert_eq!(deadline_ticks(None), NO_DEADLINE);
+    }
+
+    /// A module exporting `f`, which returns 1.
+    const RETURN_ONE: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // magic and version
+        0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f, // type section: `() -> i32`
+        0x03, 0x02, 0x01, 0x00, // function section
+        0x07, 0x05, 0x01, 0x01, b'f', 0x00, 0x00, // export section
+        0x0a, 0x06, 0x01, 0x04, 0x00, 0x41, 0x01, 0x0b, // code section: `i32.const 1`
+    ];
+
+    fn call_with_deadline(epochs_elapsed: u64, deadline: u64) -> anyhow::Result<i32> {
+        let engine = wasmtime::Engine::new(wasmtime::Config::new().epoch_interruption(true)).unwrap();
+        let module = wasmtime::Module::new(&engine, RETURN_ONE).unwrap();
+        for _ in 0..epochs_elapsed {
+            engine.increment_epoch();
+        }
+        let mut store = wasmtime::Store::new(&engine, ());
+        store.set_epoch_deadline(deadline);
+        let instance = wasmtime::Instance::new(&mut store, &module, &[]).unwrap();
+        let f = instance.get_typed_func::<(), i32>(&mut store, "f").unwrap();
+        f.call(&mut store, ())
+    }
+
+    #[test]
+    fn test_call_without_deadline_after_epoch_advanced() {
+        assert_eq!(call_with_deadline(3, deadline_ticks(None)).unwrap(), 1);
+        assert_eq!(call_with_deadline(3, deadline_ticks(Some(Duration::MAX))).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_call_past_deadline_times_out() {
+        let err = call_with_deadline(0, 0).unwrap_err();
+        assert!(trap_timed_out(&err));
+    }
 }
@@ -6,3 +6,16 @@ pub struct StmtResultJson {
     pub schema: ProductType,
     pub rows: Vec<ProductValue>,
 }
+
+/// A line of the newline-delimited JSON response to a streamed SQL query.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum StmtResultLine {
+    /// The schema of the rows of the next statement's result.
+    Schema(ProductType),
+    /// A row of the current statement's result.
+    Row(ProductValue),
+    /// All the results have been sent. Always the last line if no error occurred.
+    ///
+    /// `truncated` is set if rows were left out to stay within the row cap.
+    Done { rows: u64, truncated: bool },
+}
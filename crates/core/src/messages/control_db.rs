@@ -1,3 +1,5 @@
+use spacetimedb_client_api_messages::timestamp::Timestamp;
+use spacetimedb_lib::db::raw_def::v9::RawModuleDefV9;
 use spacetimedb_lib::Identity;
 use spacetimedb_sats::de::Deserialize;
 use spacetimedb_sats::hash::Hash;
@@ -38,6 +40,28 @@ pub struct Database {
     pub initial_program: Hash,
 }
 
+/// A module published to a database, as recorded in the database's version history.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ModuleVersion {
+    /// Internal id of the database the module was published to.
+    pub database_id: u64,
+    /// The position of the module in the database's history,
+    /// starting at 1 for the module the database was created with.
+    ///
+    /// Assigned by the control database.
+    pub version: u64,
+    /// [`Hash`] of the compiled module, under which it is kept in program storage.
+    pub program_hash: Hash,
+    /// [`HostType`] of the compiled module.
+    pub host_type: HostType,
+    /// The definition of the module's schema and reducers.
+    pub module_def: RawModuleDefV9,
+    /// When the module was published.
+    pub published_at: Timestamp,
+    /// [`Identity`] of the publisher.
+    pub publisher: Identity,
+}
+
 #[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct DatabaseStatus {
     pub state: String,
@@ -1,11 +1,16 @@
 use super::database_logger::DatabaseLogger;
+use crate::config::QuotaConfig;
 use crate::db::relational_db::RelationalDB;
-use crate::error::DBError;
+use crate::error::{DBError, SizeQuotaExceeded, SizeQuotaKind};
+use crate::host::{Clock, ReplayWindow};
 use crate::messages::control_db::Database;
 use crate::subscription::module_subscription_actor::ModuleSubscriptions;
+use parking_lot::{Mutex, RwLock};
 use std::io;
 use std::ops::Deref;
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub type Result<T> = anyhow::Result<T>;
 
@@ -17,6 +22,13 @@ pub struct ReplicaContext {
     pub logger: Arc<DatabaseLogger>,
     pub subscriptions: ModuleSubscriptions,
     pub relational_db: Arc<RelationalDB>,
+    /// The resource limits of the database, which may be changed while it runs.
+    pub quotas: Arc<RwLock<QuotaConfig>>,
+    pub quota_usage: Arc<QuotaUsage>,
+    /// The results of recent reducer calls made with an idempotency key.
+    pub reducer_replays: Arc<ReplayWindow>,
+    /// The clock which timestamps reducer calls, and by which scheduled reducers come due.
+    pub clock: Arc<Clock>,
 }
 
 impl ReplicaContext {
@@ -46,6 +58,163 @@ impl ReplicaContext {
     pub fn mem_usage(&self) -> usize {
         self.relational_db.size_in_memory()
     }
+
+    /// Returns an error if the database is over its CPU quota,
+    /// or over its memory or disk quota, as last measured by [`Self::update_quota_usage`].
+    ///
+    /// The memory and disk quotas are also enforced when transactions commit,
+    /// see [`RelationalDB::check_size_quota`].
+    pub fn check_quotas(&self) -> std::result::Result<(), QuotaExceeded> {
+        if self.cpu_quota_wait().is_some() {
+            let max = self.quotas.read().max_cpu_ms_per_minute.unwrap_or_default();
+            return Err(QuotaExceeded::Cpu(max));
+        }
+        let Some(exceeded) = self.relational_db.storage_quota_exceeded() else {
+            return Ok(());
+        };
+        match exceeded.kind {
+            SizeQuotaKind::Memory => Err(QuotaExceeded::Memory(exceeded.max)),
+            SizeQuotaKind::Disk => Err(QuotaExceeded::Disk(exceeded.max)),
+            // Only enforced for transactions which insert rows.
+            SizeQuotaKind::Size => Ok(()),
+        }
+    }
+
+    /// Record the latest measurement of the database's memory and disk usage,
+    /// so that [`Self::check_quotas`] refuses work while it is over quota.
+    pub fn update_quota_usage(&self, mem_usage: u64, disk_usage: u64) {
+        let kind = |exceeded: Option<SizeQuotaExceeded>| exceeded.map(|e| e.kind);
+        let was_over = kind(self.relational_db.storage_quota_exceeded());
+        self.relational_db.record_storage_usage(mem_usage, disk_usage);
+        let exceeded = self.relational_db.storage_quota_exceeded();
+        if kind(exceeded) == was_over {
+            return;
+        }
+        match exceeded {
+            Some(e) => log::warn!("{e}: database {}", self.database_identity),
+            None => log::warn!(
+                "database {} is back under its memory and disk quotas ({mem_usage} bytes of memory, {disk_usage} bytes on disk used)",
+                self.database_identity,
+            ),
+        }
+    }
+
+    /// Count `duration` spent running a reducer or view against the database's CPU quota.
+    pub fn record_cpu_time(&self, duration: Duration) {
+        let Some(max) = self.quotas.read().max_cpu_per_minute() else {
+            return;
+        };
+        let mut cpu = self.quota_usage.cpu.lock();
+        cpu.pay_back(max);
+        cpu.owed += duration;
+    }
+
+    /// Returns how long the database has to wait to be back under its CPU quota,
+    /// or `None` if it is not over it.
+    pub fn cpu_quota_wait(&self) -> Option<Duration> {
+        let max = self.quotas.read().max_cpu_per_minute()?;
+        let mut cpu = self.quota_usage.cpu.lock();
+        cpu.pay_back(max);
+        let over = cpu.owed.checked_sub(max).filter(|over| !over.is_zero())?;
+        // Time is paid back at the rate of `max` per minute.
+        // Check again in a minute at the latest, in case the quota is raised meanwhile.
+        let wait = (over.as_secs_f64() * 60.0 / max.as_secs_f64()).min(60.0);
+        Some(Duration::from_secs_f64(wait))
+    }
+
+    /// Reserve one of the database's client connections,
+    /// returning an error if it already has its maximum number of connected clients.
+    ///
+    /// The connection is released when the returned [`ConnectionPermit`] is dropped.
+    pub fn try_connect(&self) -> std::result::Result<ConnectionPermit, QuotaExceeded> {
+        let usage = &self.quota_usage;
+        match self.quotas.read().max_connections {
+            Some(max) => usage
+                .connections
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| (n < max).then_some(n + 1))
+                .map_err(|_| QuotaExceeded::Connections(max))?,
+            None => usage.connections.fetch_add(1, Ordering::Relaxed),
+        };
+        Ok(ConnectionPermit(usage.clone()))
+    }
+
+    /// Reserve a place in the database's queue of reducer calls,
+    /// returning an error if it already has its maximum number of pending calls.
+    ///
+    /// The place is released when the returned [`ReducerCallPermit`] is dropped.
+    pub fn try_enqueue_reducer(&self) -> std::result::Result<ReducerCallPermit, QuotaExceeded> {
+        let usage = &self.quota_usage;
+        match self.quotas.read().max_reducer_queue_depth {
+            Some(max) => usage
+                .reducer_calls
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| (n < max).then_some(n + 1))
+                .map_err(|_| QuotaExceeded::ReducerQueue(max))?,
+            None => usage.reducer_calls.fetch_add(1, Ordering::Relaxed),
+        };
+        Ok(ReducerCallPermit(usage.clone()))
+    }
+}
+
+/// A database's usage of the resources limited by its [`QuotaConfig`].
+#[derive(Default)]
+pub struct QuotaUsage {
+    connections: AtomicU32,
+    reducer_calls: AtomicU32,
+    cpu: Mutex<CpuUsage>,
+}
+
+/// The time a database has spent running reducers and views,
+/// which is paid back over time at the rate of its CPU quota.
+#[derive(Default)]
+struct CpuUsage {
+    /// The time spent which has not been paid back yet.
+    owed: Duration,
+    /// When [`Self::owed`] was last paid back.
+    paid_at: Option<Instant>,
+}
+
+impl CpuUsage {
+    /// Pay back the time owed at the rate of `max` per minute since this was last paid back.
+    fn pay_back(&mut self, max: Duration) {
+        let now = Instant::now();
+        if let Some(paid_at) = self.paid_at {
+            let elapsed = now.saturating_duration_since(paid_at);
+            self.owed = self.owed.saturating_sub(max.mul_f64(elapsed.as_secs_f64() / 60.0));
+        }
+        self.paid_at = Some(now);
+    }
+}
+
+/// A client connection counted against a database's connection quota.
+pub struct ConnectionPermit(Arc<QuotaUsage>);
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A pending reducer call counted against a database's reducer queue quota.
+pub struct ReducerCallPermit(Arc<QuotaUsage>);
+
+impl Drop for ReducerCallPermit {
+    fn drop(&mut self) {
+        self.0.reducer_calls.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum QuotaExceeded {
+    #[error("database has reached its limit of {0} connected clients")]
+    Connections(u32),
+    #[error("database has reached its limit of {0} pending reducer calls")]
+    ReducerQueue(u32),
+    #[error("database is over its limit of {0} milliseconds of CPU time per minute")]
+    Cpu(u64),
+    #[error("database is over its memory limit of {0} bytes")]
+    Memory(u64),
+    #[error("database is over its disk limit of {0} bytes")]
+    Disk(u64),
 }
 
 impl Deref for ReplicaContext {
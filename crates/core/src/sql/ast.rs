@@ -280,6 +280,7 @@ pub enum SqlAst {
         table: Arc<TableSchema>,
         columns: Box<[ColId]>,
         values: Box<[Box<[ColExpr]>]>,
+        returning: Option<Box<[ColId]>>,
     },
     Update {
         table: Arc<TableSchema>,
@@ -755,6 +756,7 @@ fn compile_insert<T: TableSchemaView + StateView>(
     table_name: ObjectName,
     columns: Vec<Ident>,
     data: &Values,
+    returning: Option<Vec<SelectItem>>,
 ) -> Result<SqlAst, PlanError> {
     let table = tx.find_table(db, Table::new(table_name))?;
 
@@ -780,13 +782,39 @@ fn compile_insert<T: TableSchemaView + StateView>(
     }
     let values = values.into();
 
+    let returning = returning.map(|items| compile_returning(&table, items)).transpose()?;
+
     Ok(SqlAst::Insert {
         table: table.root,
         columns,
         values,
+        returning,
     })
 }
 
+/// Compiles the `RETURNING ...` clause of an `INSERT` into the columns to report for each inserted row
+fn compile_returning(table: &From, items: Vec<SelectItem>) -> Result<Box<[ColId]>, PlanError> {
+    let mut cols = Vec::new();
+    for item in items {
+        match compile_select_item(table, item)? {
+            Column::Wildcard => cols.extend(table.root.columns().iter().map(|col| col.col_pos)),
+            Column::QualifiedWildcard { table: name } => {
+                if *table.root.table_name != *name {
+                    return Err(PlanError::TableNotFoundQualified { expect: name });
+                }
+                cols.extend(table.root.columns().iter().map(|col| col.col_pos));
+            }
+            Column::UnnamedExpr(Expr::Ident(name)) => cols.push(table.find_field(&name)?.0.col),
+            Column::UnnamedExpr(_) => {
+                return Err(PlanError::Unsupported {
+                    feature: "Only columns names are supported in RETURNING.".into(),
+                });
+            }
+        }
+    }
+    Ok(cols.into())
+}
+
 /// Compiles the `UPDATE ...` clause
 fn compile_update<T: TableSchemaView + StateView>(
     db: &RelationalDB,
@@ -847,6 +875,7 @@ fn compile_set_config(name: ObjectName, value: Vec<SqlExpr>) -> Result<SqlAst, P
     let literal = match value {
         SqlExpr::Value(x) => match x {
             Value::Number(value, _) => value,
+            Value::Boolean(value) => value.to_string(),
             x => {
                 return Err(PlanError::Unsupported {
                     feature: format!("Unsupported value for config: {x}."),
@@ -897,16 +926,7 @@ fn compile_statement<T: TableSchemaView + StateView>(
             on,
             returning,
         } => {
-            unsupported!(
-                "INSERT",
-                or,
-                overwrite,
-                partitioned,
-                after_columns,
-                table,
-                on,
-                returning
-            );
+            unsupported!("INSERT", or, overwrite, partitioned, after_columns, table, on);
             if into {
                 let values = match &*source.body {
                     SetExpr::Values(values) => values,
@@ -917,7 +937,7 @@ fn compile_statement<T: TableSchemaView + StateView>(
                     }
                 };
 
-                return compile_insert(db, tx, table_name, columns, values);
+                return compile_insert(db, tx, table_name, columns, values, returning);
             };
 
             Err(PlanError::Unsupported {
@@ -969,24 +989,53 @@ fn compile_statement<T: TableSchemaView + StateView>(
 }
 
 /// Compiles a `sql` string into a `Vec<SqlAst>` using a SQL parser with [PostgreSqlDialect]
+/// Unwraps the statements of a batch from an explicit `BEGIN; ...; COMMIT` block.
+///
+/// All the statements of a batch run in a single transaction either way,
+/// so the block is accepted for familiarity, but only if it is complete.
+fn unwrap_transaction_block(mut ast: Vec<Statement>) -> Result<Vec<Statement>, PlanError> {
+    if !matches!(ast.first(), Some(Statement::StartTransaction { .. })) {
+        return Ok(ast);
+    }
+    if ast.len() < 2 || !matches!(ast.last(), Some(Statement::Commit { .. })) {
+        return Err(PlanError::Unsupported {
+            feature: "`BEGIN` without a matching `COMMIT`".into(),
+        });
+    }
+    ast.pop();
+    ast.remove(0);
+    Ok(ast)
+}
+
 pub(crate) fn compile_to_ast<T: TableSchemaView + StateView>(
     db: &RelationalDB,
     auth: &AuthCtx,
     tx: &T,
     sql_text: &str,
 ) -> Result<Vec<SqlAst>, DBError> {
-    // NOTE: The following ensures compliance with the 1.0 sql api.
-    // Come 1.0, it will have replaced the current compilation stack.
-    compile_sql_stmt(sql_text, &SchemaViewer::new(tx, auth))?;
-
     let dialect = PostgreSqlDialect {};
     let ast = Parser::parse_sql(&dialect, sql_text).map_err(|error| DBError::SqlParser {
         sql: sql_text.to_string(),
         error,
     })?;
 
+    // NOTE: The following ensures compliance with the 1.0 sql api.
+    // Come 1.0, it will have replaced the current compilation stack.
+    // It only accepts a single statement, so the statements of a batch are checked one by one below.
+    let is_batch = ast.len() > 1;
+    if !is_batch {
+        compile_sql_stmt(sql_text, &SchemaViewer::new(tx, auth))?;
+    }
+    let ast = unwrap_transaction_block(ast).map_err(|error| DBError::Plan {
+        sql: sql_text.to_string(),
+        error,
+    })?;
+
     let mut results = Vec::new();
     for statement in ast {
+        if is_batch {
+            compile_sql_stmt(&statement.to_string(), &SchemaViewer::new(tx, auth))?;
+        }
         let plan_result = compile_statement(db, tx, statement);
         let query = match plan_result {
             Ok(plan) => plan,
@@ -1001,3 +1050,74 @@ pub(crate) fn compile_to_ast<T: TableSchemaView + StateView>(
     }
     Ok(results)
 }
+
+/// Restricts each `SELECT` in `ast` to the rows `auth.caller` may see,
+/// by adding the row level security filters of the tables it reads to its `WHERE` clause.
+///
+/// The owner of the database sees every row,
+/// and a table with several filters shows the rows passing any one of them.
+/// Filters with a `JOIN` can't be expressed as a `WHERE` clause,
+/// so reading their tables is rejected rather than showing too much.
+pub(crate) fn restrict_to_visible_rows<T: TableSchemaView + StateView>(
+    db: &RelationalDB,
+    tx: &T,
+    auth: &AuthCtx,
+    ast: &mut [SqlAst],
+) -> Result<(), DBError> {
+    if auth.caller == auth.owner {
+        return Ok(());
+    }
+    for statement in ast {
+        let SqlAst::Select { from, selection, .. } = statement else {
+            continue;
+        };
+        let joined = from.joins.iter().map(|Join::Inner { rhs, .. }| rhs);
+        for table in std::iter::once(&from.root).chain(joined) {
+            if let Some(filter) = visible_rows(db, tx, table)? {
+                *selection = Some(match selection.take() {
+                    Some(lhs) => Selection::with_cmp(OpLogic::And.into(), lhs.clause, filter),
+                    None => Selection { clause: filter },
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the condition on the rows of `table` passing any of its row level security filters,
+/// or `None` if every row is visible.
+fn visible_rows<T: TableSchemaView + StateView>(
+    db: &RelationalDB,
+    tx: &T,
+    table: &TableSchema,
+) -> Result<Option<FieldOp>, DBError> {
+    let filters = tx.row_level_security_for_table_id(table.table_id)?;
+    let mut visible: Option<FieldOp> = None;
+    for rls in filters {
+        let unsupported = || DBError::Plan {
+            sql: rls.sql.to_string(),
+            error: PlanError::Unsupported {
+                feature: format!(
+                    "SQL queries on the table `{}`, whose row level security filter has a `JOIN`",
+                    table.table_name
+                ),
+            },
+        };
+        let mut filter = compile_to_ast(db, &AuthCtx::for_testing(), tx, &rls.sql)?;
+        let Some(SqlAst::Select { from, selection, .. }) = filter.pop() else {
+            return Err(unsupported());
+        };
+        if !from.joins.is_empty() {
+            return Err(unsupported());
+        }
+        // A filter without a `WHERE` clause shows the whole table.
+        let Some(Selection { clause }) = selection else {
+            return Ok(None);
+        };
+        visible = Some(match visible {
+            Some(lhs) => FieldOp::new(OpLogic::Or.into(), lhs, clause),
+            None => clause,
+        });
+    }
+    Ok(visible)
+}
@@ -1,16 +1,18 @@
-use super::ast::{compile_to_ast, Column, From, Join, Selection, SqlAst};
+use super::ast::{compile_to_ast, restrict_to_visible_rows, Column, From, Join, Selection, SqlAst};
 use super::type_check::TypeCheck;
 use crate::db::datastore::locking_tx_datastore::state_view::StateView;
-use crate::db::relational_db::RelationalDB;
+use crate::db::relational_db::{RelationalDB, Tx};
 use crate::error::{DBError, PlanError};
 use core::ops::Deref;
 use spacetimedb_data_structures::map::IntMap;
 use spacetimedb_lib::identity::AuthCtx;
 use spacetimedb_lib::relation::{self, ColExpr, DbTable, FieldName, Header};
+use spacetimedb_lib::ProductValue;
 use spacetimedb_primitives::ColId;
 use spacetimedb_schema::schema::TableSchema;
-use spacetimedb_vm::expr::{CrudExpr, Expr, FieldExpr, QueryExpr, SourceExpr};
+use spacetimedb_vm::expr::{CrudExpr, Expr, FieldExpr, QueryExpr, SourceExpr, SourceSet};
 use spacetimedb_vm::operator::OpCmp;
+use spacetimedb_vm::relation::MemTable;
 use std::sync::Arc;
 
 use super::ast::TableSchemaView;
@@ -27,12 +29,30 @@ pub fn compile_sql<T: TableSchemaView + StateView>(
     tx: &T,
     sql_text: &str,
 ) -> Result<Vec<CrudExpr>, DBError> {
-    if sql_text.len() > MAX_SQL_LENGTH {
-        return Err(anyhow::anyhow!("SQL query exceeds maximum allowed length: \"{sql_text:.120}...\"").into());
-    }
+    check_sql_length(sql_text)?;
     tracing::trace!(sql = sql_text);
     let ast = compile_to_ast(db, auth, tx, sql_text)?;
+    compile_statements(db, ast, sql_text)
+}
+
+/// Compile the `SQL` expression into an `ast`, to be run on behalf of `auth.caller`.
+///
+/// Access to the tables it reads is checked when it is run,
+/// but the rows of tables with row level security are restricted here to those the caller may see.
+pub fn compile_sql_for_caller<T: TableSchemaView + StateView>(
+    db: &RelationalDB,
+    auth: &AuthCtx,
+    tx: &T,
+    sql_text: &str,
+) -> Result<Vec<CrudExpr>, DBError> {
+    check_sql_length(sql_text)?;
+    tracing::trace!(sql = sql_text);
+    let mut ast = compile_to_ast(db, &AuthCtx::for_testing(), tx, sql_text)?;
+    restrict_to_visible_rows(db, tx, auth, &mut ast)?;
+    compile_statements(db, ast, sql_text)
+}
 
+fn compile_statements(db: &RelationalDB, ast: Vec<SqlAst>, sql_text: &str) -> Result<Vec<CrudExpr>, DBError> {
     // TODO(perf, bikeshedding): SmallVec?
     let mut results = Vec::with_capacity(ast.len());
 
@@ -46,6 +66,64 @@ pub fn compile_sql<T: TableSchemaView + StateView>(
     Ok(results)
 }
 
+/// Compile the `SQL` query `sql_text`, which must be a `SELECT` from a single table,
+/// reading the table as it was after the transaction `tx_offset`, rather than as it is now.
+///
+/// As with [`compile_sql_for_caller`], the rows are restricted to those visible to `auth.caller`.
+///
+/// Returns the query along with the rows of the table it reads, to be supplied when running it.
+pub fn compile_sql_as_of(
+    db: &RelationalDB,
+    auth: &AuthCtx,
+    tx: &Tx,
+    sql_text: &str,
+    tx_offset: u64,
+) -> Result<(CrudExpr, SourceSet<Vec<ProductValue>, 1>), DBError> {
+    check_sql_length(sql_text)?;
+    tracing::trace!(sql = sql_text, tx_offset);
+    let plan_error = |error| DBError::Plan {
+        sql: sql_text.to_string(),
+        error,
+    };
+    let unsupported = || {
+        plan_error(PlanError::Unsupported {
+            feature: "`AS OF` on anything but a `SELECT` from a single table".into(),
+        })
+    };
+
+    let mut ast = compile_to_ast(db, &AuthCtx::for_testing(), tx, sql_text)?;
+    restrict_to_visible_rows(db, tx, auth, &mut ast)?;
+    let statement = ast.pop().filter(|_| ast.is_empty()).ok_or_else(unsupported)?;
+    statement.type_check().map_err(plan_error)?;
+    let SqlAst::Select {
+        from,
+        project,
+        selection,
+    } = statement
+    else {
+        return Err(unsupported());
+    };
+    if !from.joins.is_empty() {
+        return Err(unsupported());
+    }
+
+    let rows = tx.rows_as_of(from.root.table_id, tx_offset)?;
+    let header = Arc::new(Header::from(&*from.root));
+    let mut sources = SourceSet::empty();
+    let root = sources.add_mem_table(MemTable::new(header, from.root.table_access, rows));
+    let query = compile_select(root, from, project, selection).map_err(plan_error)?;
+    let query = CrudExpr::Query(query).optimize(&|table_id, table_name| db.row_count(table_id, table_name));
+    Ok((query, sources))
+}
+
+/// Rejects queries too long to compile.
+fn check_sql_length(sql_text: &str) -> Result<(), DBError> {
+    if sql_text.len() > MAX_SQL_LENGTH {
+        return Err(anyhow::anyhow!("SQL query exceeds maximum allowed length: \"{sql_text:.120}...\"").into());
+    }
+    Ok(())
+}
+
 fn expr_for_projection(table: &From, of: Expr) -> Result<FieldExpr, PlanError> {
     match of {
         Expr::Ident(x) => table.find_field(&x).map(|(f, _)| FieldExpr::Name(f)),
@@ -62,8 +140,13 @@ fn compile_where(mut q: QueryExpr, filter: Selection) -> Result<QueryExpr, PlanE
     Ok(q)
 }
 
-/// Compiles a `SELECT ...` clause
-fn compile_select(table: From, project: Box<[Column]>, selection: Option<Selection>) -> Result<QueryExpr, PlanError> {
+/// Compiles a `SELECT ...` clause, reading the root table of `table` from `root`
+fn compile_select(
+    root: SourceExpr,
+    table: From,
+    project: Box<[Column]>,
+    selection: Option<Selection>,
+) -> Result<QueryExpr, PlanError> {
     let mut not_found = Vec::with_capacity(project.len());
     let mut col_ids = Vec::new();
     let mut qualified_wildcards = Vec::new();
@@ -96,8 +179,7 @@ fn compile_select(table: From, project: Box<[Column]>, selection: Option<Selecti
         });
     }
 
-    let source_expr: SourceExpr = table.root.deref().into();
-    let mut q = QueryExpr::new(source_expr);
+    let mut q = QueryExpr::new(root);
 
     for join in table.joins {
         match join {
@@ -154,7 +236,17 @@ fn compile_columns(table: &TableSchema, cols: &[ColId]) -> DbTable {
 }
 
 /// Compiles a `INSERT ...` clause
-fn compile_insert(table: &TableSchema, cols: &[ColId], values: Box<[Box<[ColExpr]>]>) -> CrudExpr {
+fn compile_insert(
+    table: &TableSchema,
+    cols: &[ColId],
+    values: Box<[Box<[ColExpr]>]>,
+    returning: Option<Box<[ColId]>>,
+) -> CrudExpr {
+    let returning = returning.map(|returning| {
+        let head = Header::from(table);
+        let fields = returning.iter().map(|col| head.fields[col.idx()].clone()).collect();
+        Arc::new(Header::new(head.table_id, head.table_name, fields, []))
+    });
     let table = compile_columns(table, cols);
 
     let mut rows = Vec::with_capacity(values.len());
@@ -173,7 +265,7 @@ fn compile_insert(table: &TableSchema, cols: &[ColId], values: Box<[Box<[ColExpr
         rows.push(row.into())
     }
 
-    CrudExpr::Insert { table, rows }
+    CrudExpr::Insert { table, rows, returning }
 }
 
 /// Compiles a `DELETE ...` clause
@@ -212,8 +304,16 @@ fn compile_statement(db: &RelationalDB, statement: SqlAst) -> Result<CrudExpr, P
             from,
             project,
             selection,
-        } => CrudExpr::Query(compile_select(from, project, selection)?),
-        SqlAst::Insert { table, columns, values } => compile_insert(&table, &columns, values),
+        } => {
+            let root = from.root.deref().into();
+            CrudExpr::Query(compile_select(root, from, project, selection)?)
+        }
+        SqlAst::Insert {
+            table,
+            columns,
+            values,
+            returning,
+        } => compile_insert(&table, &columns, values, returning),
         SqlAst::Update {
             table,
             assignments,
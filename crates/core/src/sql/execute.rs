@@ -1,6 +1,7 @@
+use std::iter;
 use std::time::Duration;
 
-use super::compiler::compile_sql;
+use super::compiler::{compile_sql_as_of, compile_sql_for_caller};
 use crate::db::datastore::locking_tx_datastore::state_view::StateView;
 use crate::db::datastore::system_tables::StVarTable;
 use crate::db::datastore::traits::IsolationLevel;
@@ -10,16 +11,17 @@ use crate::error::DBError;
 use crate::execution_context::Workload;
 use crate::host::module_host::{DatabaseTableUpdate, DatabaseUpdate, EventStatus, ModuleEvent, ModuleFunctionCall};
 use crate::host::ArgsTuple;
+use crate::json::client_api::StmtResultLine;
 use crate::subscription::module_subscription_actor::{ModuleSubscriptions, WriteConflict};
 use crate::util::slow::SlowQueryLogger;
 use crate::vm::{DbProgram, TxMode};
 use itertools::Either;
 use spacetimedb_client_api_messages::timestamp::Timestamp;
 use spacetimedb_lib::identity::AuthCtx;
-use spacetimedb_lib::relation::FieldName;
-use spacetimedb_lib::{ProductType, ProductValue};
+use spacetimedb_lib::relation::{FieldName, Header};
+use spacetimedb_lib::{ProductType, ProductTypeElement, ProductValue};
 use spacetimedb_vm::eval::run_ast;
-use spacetimedb_vm::expr::{CodeResult, CrudExpr, Expr};
+use spacetimedb_vm::expr::{CodeResult, CrudExpr, Expr, SourceSet};
 use spacetimedb_vm::relation::MemTable;
 
 pub struct StmtResult {
@@ -52,6 +54,7 @@ pub(crate) fn collect_result(
                     table_id: update.table_id,
                     inserts: update.inserts.into(),
                     deletes: update.deletes.into(),
+                    truncated: false,
                 });
             }
         },
@@ -65,6 +68,18 @@ fn execute(
     ast: Vec<CrudExpr>,
     sql: &str,
     updates: &mut Vec<DatabaseTableUpdate>,
+) -> Result<Vec<MemTable>, DBError> {
+    // SQL queries can never reference `MemTable`s, so pass an empty `SourceSet`.
+    execute_with_sources(p, ast, sql, updates, [].into())
+}
+
+/// Like [`execute`], but for queries reading the `MemTable`s in `sources`.
+fn execute_with_sources<const N: usize>(
+    p: &mut DbProgram<'_, '_>,
+    ast: Vec<CrudExpr>,
+    sql: &str,
+    updates: &mut Vec<DatabaseTableUpdate>,
+    sources: SourceSet<Vec<ProductValue>, N>,
 ) -> Result<Vec<MemTable>, DBError> {
     let slow_query_threshold = if let TxMode::Tx(tx) = p.tx {
         StVarTable::query_limit(p.db, tx)?.map(Duration::from_millis)
@@ -74,8 +89,7 @@ fn execute(
     let _slow_query_logger = SlowQueryLogger::new(sql, slow_query_threshold, p.tx.ctx().workload()).log_guard();
     let mut result = Vec::with_capacity(ast.len());
     let query = Expr::Block(ast.into_iter().map(|x| Expr::Crud(Box::new(x))).collect());
-    // SQL queries can never reference `MemTable`s, so pass an empty `SourceSet`.
-    collect_result(&mut result, updates, run_ast(p, query, [].into()).into())?;
+    collect_result(&mut result, updates, run_ast(p, query, sources).into())?;
     Ok(result)
 }
 
@@ -133,6 +147,7 @@ pub fn execute_sql(
                 status: EventStatus::Committed(DatabaseUpdate { tables: updates }),
                 energy_quanta_used: EnergyQuanta::ZERO,
                 host_execution_duration: Duration::ZERO,
+                tx_offset: None,
                 request_id: None,
                 timer: None,
             };
@@ -173,8 +188,11 @@ pub fn run(
     auth: AuthCtx,
     subs: Option<&ModuleSubscriptions>,
 ) -> Result<Vec<MemTable>, DBError> {
+    if let (sql_text, Some(tx_offset)) = split_as_of(sql_text)? {
+        return run_as_of(db, sql_text, tx_offset, auth);
+    }
     let result = db.with_read_only(Workload::Sql, |tx| {
-        let ast = compile_sql(db, &AuthCtx::for_testing(), tx, sql_text)?;
+        let ast = compile_sql_for_caller(db, &auth, tx, sql_text)?;
         if CrudExpr::is_reads(&ast) {
             let mut updates = Vec::new();
             let result = execute(
@@ -199,6 +217,182 @@ pub fn run(
     }
 }
 
+/// How many lines [`run_streaming`] reads in each of its read transactions.
+const STREAM_PAGE_LINES: usize = 4096;
+
+/// Run the read-only `SQL` in `sql_text` using the `auth` credentials,
+/// passing the results to `on_line` a row at a time instead of collecting them,
+/// so that results too large to buffer can be streamed to the client.
+///
+/// The rows of each statement are preceded by a [`StmtResultLine::Schema`],
+/// and the last line is a [`StmtResultLine::Done`].
+/// If `max_rows` is given, the results are truncated after that many rows.
+///
+/// The results are read a page of lines at a time, each in a read transaction of its own,
+/// which is released before the page is passed to `on_line`,
+/// so that a slow `on_line` never holds up writers.
+/// A write committed between two pages may therefore be partially visible in the results.
+pub fn run_streaming(
+    db: &RelationalDB,
+    sql_text: &str,
+    auth: AuthCtx,
+    max_rows: Option<u64>,
+    on_line: impl FnMut(StmtResultLine) -> anyhow::Result<()>,
+) -> Result<(), DBError> {
+    run_streaming_paged(db, sql_text, auth, max_rows, STREAM_PAGE_LINES, on_line)
+}
+
+/// [`run_streaming`], with pages of `page_lines` lines.
+fn run_streaming_paged(
+    db: &RelationalDB,
+    sql_text: &str,
+    auth: AuthCtx,
+    max_rows: Option<u64>,
+    page_lines: usize,
+    mut on_line: impl FnMut(StmtResultLine) -> anyhow::Result<()>,
+) -> Result<(), DBError> {
+    let mut page = Vec::with_capacity(page_lines);
+    let mut skip = 0;
+    loop {
+        let done = read_stream_page(db, sql_text, auth, max_rows, skip, page_lines, &mut page)?;
+        skip += page.len();
+        for line in page.drain(..) {
+            on_line(line)?;
+        }
+        if let Some(done) = done {
+            on_line(done)?;
+            return Ok(());
+        }
+    }
+}
+
+/// Reads the lines of the results of [`run_streaming`] after the first `skip` into `page`,
+/// until it holds `page_lines` of them.
+///
+/// Returns the final [`StmtResultLine::Done`] if no lines are left after those in `page`.
+fn read_stream_page(
+    db: &RelationalDB,
+    sql_text: &str,
+    auth: AuthCtx,
+    max_rows: Option<u64>,
+    skip: usize,
+    page_lines: usize,
+    page: &mut Vec<StmtResultLine>,
+) -> Result<Option<StmtResultLine>, DBError> {
+    db.with_read_only(Workload::Sql, |tx| {
+        let ast = compile_sql_for_caller(db, &auth, tx, sql_text)?;
+        if !CrudExpr::is_reads(&ast) {
+            return Err(anyhow::anyhow!("Only queries can be streamed, `{sql_text}` has writes").into());
+        }
+
+        let slow_query_threshold = StVarTable::query_limit(db, tx)?.map(Duration::from_millis);
+        let _slow_query_logger = SlowQueryLogger::new(sql_text, slow_query_threshold, tx.ctx.workload()).log_guard();
+
+        let schema = |head: &Header| -> ProductType {
+            head.fields
+                .iter()
+                .map(|x| ProductTypeElement::new(x.algebraic_type.clone(), translate_col(tx, x.field)))
+                .collect()
+        };
+        let mut rows = 0;
+        let mut truncated = false;
+        // The number of lines seen so far, including those skipped.
+        let mut seen = 0;
+        let mut full = false;
+        let mut emit = |line: StmtResultLine| -> Result<bool, DBError> {
+            let is_row = matches!(line, StmtResultLine::Row(_));
+            if is_row && max_rows.is_some_and(|max_rows| rows >= max_rows) {
+                truncated = true;
+                return Ok(false);
+            }
+            if seen >= skip && page.len() >= page_lines {
+                full = true;
+                return Ok(false);
+            }
+            if is_row {
+                rows += 1;
+            }
+            if seen >= skip {
+                page.push(line);
+            }
+            seen += 1;
+            Ok(true)
+        };
+
+        let mut tx_mode = TxMode::Tx(tx);
+        let mut program = DbProgram::new(db, &mut tx_mode, auth);
+        for expr in ast {
+            let complete = match expr {
+                CrudExpr::Query(query) => {
+                    emit(StmtResultLine::Schema(schema(query.head())))?
+                        && program.stream_query(&query, |row| emit(StmtResultLine::Row(row)))?
+                }
+                // Other reads, i.e. `SHOW`, have tiny results.
+                expr => {
+                    let mut complete = true;
+                    'tables: for table in execute(&mut program, vec![expr], sql_text, &mut Vec::new())? {
+                        for line in iter::once(StmtResultLine::Schema(schema(&table.head)))
+                            .chain(table.data.into_iter().map(StmtResultLine::Row))
+                        {
+                            if !emit(line)? {
+                                complete = false;
+                                break 'tables;
+                            }
+                        }
+                    }
+                    complete
+                }
+            };
+            if !complete {
+                break;
+            }
+        }
+
+        Ok((!full).then_some(StmtResultLine::Done { rows, truncated }))
+    })
+}
+
+/// Run the `SELECT` in `sql_text` against its table as it was after the transaction `tx_offset`
+fn run_as_of(db: &RelationalDB, sql_text: &str, tx_offset: u64, auth: AuthCtx) -> Result<Vec<MemTable>, DBError> {
+    db.with_read_only(Workload::Sql, |tx| {
+        let (query, sources) = compile_sql_as_of(db, &auth, tx, sql_text, tx_offset)?;
+        execute_with_sources(
+            &mut DbProgram::new(db, &mut TxMode::Tx(tx), auth),
+            vec![query],
+            sql_text,
+            &mut Vec::new(),
+            sources,
+        )
+    })
+}
+
+/// Splits a trailing `AS OF <tx_offset>` off of `sql_text`.
+///
+/// This is recognized here rather than by the SQL parser, which has no syntax for it.
+fn split_as_of(sql_text: &str) -> Result<(&str, Option<u64>), DBError> {
+    fn split_last_word(s: &str) -> Option<(&str, &str)> {
+        s.trim_end().rsplit_once(char::is_whitespace)
+    }
+
+    let stmt = sql_text.trim_end().trim_end_matches(';');
+    let Some((rest, tx_offset)) = split_last_word(stmt) else {
+        return Ok((sql_text, None));
+    };
+    let Some((rest, of)) = split_last_word(rest) else {
+        return Ok((sql_text, None));
+    };
+    let Some((rest, as_)) = split_last_word(rest) else {
+        return Ok((sql_text, None));
+    };
+    if !as_.eq_ignore_ascii_case("AS") || !of.eq_ignore_ascii_case("OF") {
+        return Ok((sql_text, None));
+    }
+    let tx_offset = tx_offset
+        .parse()
+        .map_err(|_| anyhow::anyhow!("`AS OF` expects a transaction offset, got `{tx_offset}`"))?;
+    Ok((rest, Some(tx_offset)))
+}
+
 /// Translates a `FieldName` to the field's name.
 pub fn translate_col(tx: &Tx, field: FieldName) -> Option<Box<str>> {
     Some(
@@ -217,12 +411,15 @@ pub(crate) mod tests {
     use crate::vm::tests::create_table_with_rows;
     use pretty_assertions::assert_eq;
     use spacetimedb_lib::db::auth::{StAccess, StTableType};
+    use spacetimedb_lib::db::raw_def::v9::RawModuleDefV9Builder;
     use spacetimedb_lib::error::{ResultTest, TestError};
     use spacetimedb_lib::relation::ColExpr;
     use spacetimedb_lib::relation::Header;
     use spacetimedb_lib::{AlgebraicValue, Identity};
-    use spacetimedb_primitives::{col_list, ColId};
+    use spacetimedb_primitives::{col_list, ColId, TableId};
     use spacetimedb_sats::{product, AlgebraicType, ArrayValue, ProductType};
+    use spacetimedb_schema::def::ModuleDef;
+    use spacetimedb_schema::schema::{RowLevelSecuritySchema, TableSchema};
     use spacetimedb_vm::eval::test_helpers::{create_game_data, mem_table, mem_table_without_table_name};
     use std::sync::Arc;
 
@@ -530,6 +727,138 @@ pub(crate) mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_insert_multi_row_returning() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type_for_tests(
+                "T",
+                ProductType::from([("id", AlgebraicType::U64), ("name", AlgebraicType::String)]),
+                true,
+            )
+            .with_column_sequence(0)
+            .with_unique_constraint(0)
+            .finish();
+        let module_def: ModuleDef = builder.finish().try_into()?;
+        let schema = TableSchema::from_module_def(&module_def, module_def.table("T").unwrap(), (), TableId::SENTINEL);
+        db.with_auto_commit(Workload::ForTests, |tx| db.create_table(tx, schema))?;
+
+        // The generated `auto_inc` values are reported, not the `0` placeholders.
+        let result = run_for_testing(&db, "INSERT INTO T (id, name) VALUES (0, 'a'), (0, 'b') RETURNING *")?;
+        assert_eq!(result.len(), 1, "Return results");
+        assert_eq!(result[0].data, vec![product![1u64, "a"], product![2u64, "b"]]);
+
+        let result = run_for_testing(&db, "INSERT INTO T (id, name) VALUES (0, 'c') RETURNING name, T.id")?;
+        assert_eq!(result[0].data, vec![product!["c", 3u64]]);
+
+        let result = run_for_testing(&db, "SELECT * FROM T")?;
+        assert_eq!(result[0].data.len(), 3);
+
+        assert!(run_for_testing(&db, "INSERT INTO T (id, name) VALUES (0, 'd') RETURNING 1").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_is_atomic() -> ResultTest<()> {
+        let (db, _) = create_data(1)?;
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type_for_tests(
+                "T",
+                ProductType::from([("id", AlgebraicType::U64), ("name", AlgebraicType::String)]),
+                true,
+            )
+            .with_unique_constraint(0)
+            .finish();
+        let module_def: ModuleDef = builder.finish().try_into()?;
+        let schema = TableSchema::from_module_def(&module_def, module_def.table("T").unwrap(), (), TableId::SENTINEL);
+        db.with_auto_commit(Workload::ForTests, |tx| db.create_table(tx, schema))?;
+
+        let count = |table: &str| -> ResultTest<usize> {
+            Ok(run_for_testing(&db, &format!("SELECT * FROM {table}"))?[0].data.len())
+        };
+
+        // The last statement violates the unique constraint, so nothing is applied.
+        let sql = "BEGIN;
+            INSERT INTO inventory (inventory_id, name) VALUES (2, 'a');
+            INSERT INTO T (id, name) VALUES (1, 'a');
+            INSERT INTO T (id, name) VALUES (1, 'b');
+            COMMIT;";
+        assert!(run_for_testing(&db, sql).is_err());
+        assert_eq!(count("inventory")?, 1);
+        assert_eq!(count("T")?, 0);
+
+        // The `BEGIN; ...; COMMIT` block is optional.
+        let sql =
+            "INSERT INTO inventory (inventory_id, name) VALUES (2, 'a'); INSERT INTO T (id, name) VALUES (1, 'a')";
+        run_for_testing(&db, sql)?;
+        assert_eq!(count("inventory")?, 2);
+        assert_eq!(count("T")?, 1);
+
+        // An unterminated block is rejected.
+        assert!(run_for_testing(&db, "BEGIN; DELETE FROM T").is_err());
+        assert_eq!(count("T")?, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_streaming() -> ResultTest<()> {
+        let (db, input) = create_data(3)?;
+
+        let stream_paged = |sql: &str, max_rows, page_lines| -> Result<Vec<StmtResultLine>, DBError> {
+            let mut lines = Vec::new();
+            run_streaming_paged(&db, sql, AuthCtx::for_testing(), max_rows, page_lines, |line| {
+                lines.push(line);
+                Ok(())
+            })?;
+            Ok(lines)
+        };
+        let stream = |sql: &str, max_rows| stream_paged(sql, max_rows, STREAM_PAGE_LINES);
+
+        let is_done = |line: Option<&StmtResultLine>, n: u64, cut: bool| match line {
+            Some(StmtResultLine::Done { rows, truncated }) => (*rows, *truncated) == (n, cut),
+            _ => false,
+        };
+        let rows = |lines: &[StmtResultLine]| {
+            lines
+                .iter()
+                .filter_map(|line| match line {
+                    StmtResultLine::Row(row) => Some(row.clone()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let lines = stream("SELECT * FROM inventory", None)?;
+        assert!(matches!(&lines[0], StmtResultLine::Schema(schema) if schema.elements.len() == 2));
+        assert!(is_done(lines.last(), 3, false));
+        let mut result = rows(&lines);
+        result.sort();
+        assert_eq!(result, input.data);
+
+        // The row cap applies across all the statements.
+        let lines = stream("SELECT * FROM inventory; SELECT * FROM inventory", Some(4))?;
+        assert_eq!(rows(&lines).len(), 4);
+        assert!(is_done(lines.last(), 4, true));
+
+        // Reading a page at a time yields the same lines, whatever the size of the pages.
+        let sql = "SELECT * FROM inventory; SELECT * FROM inventory";
+        for page_lines in 1..=4 {
+            assert_eq!(stream_paged(sql, None, page_lines)?, stream(sql, None)?);
+            assert_eq!(stream_paged(sql, Some(4), page_lines)?, stream(sql, Some(4))?);
+        }
+
+        assert!(stream("DELETE FROM inventory", None).is_err());
+        assert_eq!(run_for_testing(&db, "SELECT * FROM inventory")?[0].data.len(), 3);
+
+        Ok(())
+    }
+
     #[test]
     fn test_delete() -> ResultTest<()> {
         let (db, _input) = create_data(1)?;
@@ -748,4 +1077,108 @@ pub(crate) mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_row_level_security() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+
+        let table_id = db.create_table_for_test("T", &[("a", AlgebraicType::U8)], &[])?;
+        db.with_auto_commit(Workload::ForTests, |tx| -> Result<_, DBError> {
+            for i in 0..5u8 {
+                insert(&db, tx, table_id, &product!(i))?;
+            }
+            for sql in ["SELECT * FROM T WHERE a = 0", "SELECT * FROM T WHERE a > 3"] {
+                let sql = sql.into();
+                db.create_row_level_security(tx, RowLevelSecuritySchema { table_id, sql })?;
+            }
+            Ok(())
+        })?;
+
+        let server = Identity::from_hashing_bytes("server");
+        let client = Identity::from_hashing_bytes("client");
+
+        let internal_auth = AuthCtx::new(server, server);
+        let external_auth = AuthCtx::new(server, client);
+
+        let select = |sql, auth| -> ResultTest<Vec<ProductValue>> {
+            let mut result = run(&db, sql, auth, None)?.remove(0).data;
+            result.sort();
+            Ok(result)
+        };
+
+        // The owner sees every row.
+        assert_eq!(select("SELECT * FROM T", internal_auth)?.len(), 5);
+
+        // Clients only see the rows passing any of the filters.
+        assert_eq!(
+            select("SELECT * FROM T", external_auth)?,
+            [product![0u8], product![4u8]]
+        );
+        assert_eq!(select("SELECT * FROM T WHERE a < 4", external_auth)?, [product![0u8]]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hide_callers() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let hide_callers = || db.with_read_only(Workload::ForTests, |tx| StVarTable::hide_callers(&db, tx));
+
+        assert!(!hide_callers()?);
+        run_for_testing(&db, "SET hide_callers = true")?;
+        assert!(hide_callers()?);
+        run_for_testing(&db, "SET hide_callers = false")?;
+        assert!(!hide_callers()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_as_of() -> ResultTest<()> {
+        assert_eq!(split_as_of("SELECT * FROM T")?, ("SELECT * FROM T", None));
+        assert_eq!(split_as_of("SELECT * FROM T AS OF 12")?, ("SELECT * FROM T", Some(12)));
+        assert_eq!(
+            split_as_of("SELECT * FROM T\nas  of 12 ;")?,
+            ("SELECT * FROM T", Some(12))
+        );
+        assert!(split_as_of("SELECT * FROM T AS OF yesterday").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_select_as_of() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type_for_tests("T", ProductType::from([("a", AlgebraicType::U8)]), true)
+            .with_soft_delete(2)
+            .finish();
+        let module_def: ModuleDef = builder.finish().try_into()?;
+        let schema = TableSchema::from_module_def(&module_def, module_def.table("T").unwrap(), (), TableId::SENTINEL);
+        let table_id = db.with_auto_commit(Workload::ForTests, |tx| db.create_table(tx, schema))?;
+
+        let mut offsets = Vec::new();
+        for i in 0..4u8 {
+            db.with_auto_commit(Workload::ForTests, |tx| -> Result<_, DBError> {
+                let rows = db.iter_mut(tx, table_id)?.map(|row| row.pointer()).collect::<Vec<_>>();
+                db.delete(tx, table_id, rows);
+                insert(&db, tx, table_id, &product!(i))?;
+                Ok(())
+            })?;
+            offsets.push(db.committed_tx_offset().unwrap());
+        }
+
+        let select_as_of = |tx_offset: u64| -> Result<Vec<ProductValue>, DBError> {
+            let sql = format!("SELECT * FROM T AS OF {tx_offset}");
+            Ok(run_for_testing(&db, &sql)?.swap_remove(0).data)
+        };
+        assert_eq!(select_as_of(offsets[3])?, vec![product!(3u8)]);
+        assert_eq!(select_as_of(offsets[2])?, vec![product!(2u8)]);
+        assert_eq!(select_as_of(offsets[1])?, vec![product!(1u8)]);
+        // Only the last two transactions are retained.
+        assert!(select_as_of(offsets[0]).is_err());
+
+        Ok(())
+    }
 }
@@ -1,5 +1,6 @@
 use spacetimedb_paths::server::{ConfigToml, LogsDir};
 use std::path::PathBuf;
+use std::sync::OnceLock;
 use std::time::Duration;
 use tracing_appender::rolling;
 use tracing_core::LevelFilter;
@@ -118,13 +119,18 @@ fn configure_tracing(opts: TracingOptions) {
         .with(fmt_layer)
         .with(flame_layer);
 
+    let (reload_layer, reload_handle) = tracing_subscriber::reload::Layer::new(env_filter_layer);
+    let _ = LOG_FILTER_RELOAD.set(Box::new({
+        let reload_handle = reload_handle.clone();
+        move |filter| {
+            // Fails only once the subscriber is gone, when there is nothing left to reload.
+            let _ = reload_handle.reload(filter);
+        }
+    }));
     if let Some(conf_file) = opts.reload_config {
-        let (reload_layer, reload_handle) = tracing_subscriber::reload::Layer::new(env_filter_layer);
         std::thread::spawn(move || reload_config(&conf_file, &reload_handle));
-        subscriber.with(reload_layer).init();
-    } else {
-        subscriber.with(env_filter_layer).init();
-    };
+    }
+    subscriber.with(reload_layer).init();
 
     if let Some(guard) = flame_guard {
         tokio::spawn(async move {
@@ -136,6 +142,19 @@ fn configure_tracing(opts: TracingOptions) {
     }
 }
 
+/// Replaces the log filter of the global tracing subscriber, once [`StartupOptions::configure`] has set it up.
+static LOG_FILTER_RELOAD: OnceLock<Box<dyn Fn(EnvFilter) + Send + Sync>> = OnceLock::new();
+
+/// Applies the log level and directives of `conf` to the global tracing subscriber,
+/// e.g. after the server's `config.toml` was changed.
+///
+/// Does nothing if tracing wasn't configured by [`StartupOptions::configure`].
+pub fn reload_log_config(conf: LogConfig) {
+    if let Some(reload) = LOG_FILTER_RELOAD.get() {
+        reload(conf_to_filter(conf));
+    }
+}
+
 fn conf_to_filter(conf: LogConfig) -> EnvFilter {
     EnvFilter::builder()
         .with_default_directive(conf.level.unwrap_or(LevelFilter::ERROR).into())
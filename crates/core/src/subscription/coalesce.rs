@@ -0,0 +1,145 @@
+use super::execution_unit::QueryHash;
+use super::module_subscription_manager::Plan;
+use crate::host::module_host::UpdatesRelValue;
+use crate::messages::websocket::{self as ws, TableUpdate};
+use spacetimedb_client_api_messages::websocket::{Compression, WebsocketFormat};
+use spacetimedb_data_structures::map::{Entry, HashMap};
+use spacetimedb_lib::{AlgebraicValue, ProductValue};
+use spacetimedb_primitives::{ColId, TableId};
+use spacetimedb_vm::relation::RelValue;
+use std::time::{Duration, Instant};
+
+/// How the updates to a table sent to subscribers are coalesced, as declared by the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CoalesceWindow {
+    /// How long updates are held back before they are sent.
+    pub window: Duration,
+    /// The primary key of the table, by which the updates to a row are merged.
+    pub primary_key: ColId,
+}
+
+/// The updates to a row with a particular primary key, held back from a subscriber.
+#[derive(Debug, Default)]
+struct PendingRow {
+    /// The row as the subscriber last saw it, if it was deleted since.
+    deleted: Option<ProductValue>,
+    /// The row as it is now, if it was inserted since.
+    inserted: Option<ProductValue>,
+}
+
+impl PendingRow {
+    fn delete(&mut self, row: ProductValue) {
+        // A row inserted during the window was never seen by the subscriber,
+        // so its deletion cancels its insertion.
+        if self.inserted.take().is_none() {
+            self.deleted = Some(row);
+        }
+    }
+
+    fn insert(&mut self, row: ProductValue) {
+        self.inserted = Some(row);
+    }
+}
+
+/// The updates to the rows of a query, held back from a subscriber.
+#[derive(Debug)]
+struct PendingQuery {
+    table_id: TableId,
+    table_name: Box<str>,
+    /// How many times the subscriber is subscribed to the query,
+    /// and so how many times it is sent the query's updates.
+    copies: usize,
+    rows: HashMap<AlgebraicValue, PendingRow>,
+}
+
+impl PendingQuery {
+    /// Returns the deletes and inserts which bring the subscriber up to date.
+    /// A row updated back to how the subscriber last saw it is left out.
+    fn into_updates(self) -> UpdatesRelValue<'static> {
+        let mut updates = UpdatesRelValue::default();
+        for (_, row) in self.rows {
+            if row.deleted == row.inserted {
+                continue;
+            }
+            updates.deletes.extend(row.deleted.map(RelValue::Projection));
+            updates.inserts.extend(row.inserted.map(RelValue::Projection));
+        }
+        updates
+    }
+}
+
+/// The coalesced updates held back from a subscriber, until they are due.
+#[derive(Debug)]
+pub(super) struct PendingUpdates {
+    due: Instant,
+    queries: HashMap<QueryHash, PendingQuery>,
+}
+
+impl PendingUpdates {
+    pub(super) fn new(due: Instant) -> Self {
+        Self {
+            due,
+            queries: HashMap::default(),
+        }
+    }
+
+    /// When the updates are to be sent.
+    pub(super) fn due(&self) -> Instant {
+        self.due
+    }
+
+    /// Merges the `deletes` and `inserts` a transaction made to the rows of `plan`
+    /// into the updates held back, which are then due within `window.window` at the latest.
+    pub(super) fn push(
+        &mut self,
+        plan: &Plan,
+        window: &CoalesceWindow,
+        copies: usize,
+        deletes: &[ProductValue],
+        inserts: &[ProductValue],
+    ) {
+        self.due = self.due.min(Instant::now() + window.window);
+        let query = match self.queries.entry(plan.hash()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(PendingQuery {
+                table_id: plan.table_id(),
+                table_name: plan.table_name(),
+                copies,
+                rows: HashMap::default(),
+            }),
+        };
+        query.copies = copies;
+        // An update within a transaction deletes the old row before inserting the new one.
+        for row in deletes {
+            let key = row.elements[window.primary_key.idx()].clone();
+            query.rows.entry(key).or_default().delete(row.clone());
+        }
+        for row in inserts {
+            let key = row.elements[window.primary_key.idx()].clone();
+            query.rows.entry(key).or_default().insert(row.clone());
+        }
+    }
+
+    /// Encodes the updates held back into one update of the database,
+    /// with the updates of each query repeated as many times as the subscriber is subscribed to it.
+    pub(super) fn into_database_update<F: WebsocketFormat>(self, compression: Compression) -> ws::DatabaseUpdate<F> {
+        let mut tables = HashMap::<TableId, TableUpdate<F>>::default();
+        for query in self.queries.into_values() {
+            let (table_id, table_name, copies) = (query.table_id, query.table_name.clone(), query.copies);
+            let updates = query.into_updates();
+            if !updates.has_updates() {
+                continue;
+            }
+            let update = updates.encode::<F>(compression);
+            let table = tables
+                .entry(table_id)
+                .or_insert_with(|| TableUpdate::empty(table_id, table_name));
+            for _ in 0..copies {
+                table.push(update.clone());
+            }
+        }
+        ws::DatabaseUpdate {
+            tables: tables.into_values().collect(),
+        }
+    }
+}
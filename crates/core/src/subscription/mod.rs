@@ -1,3 +1,4 @@
+pub mod coalesce;
 pub mod delta;
 pub mod execution_unit;
 pub mod module_subscription_actor;
@@ -5,4 +6,6 @@ pub mod module_subscription_manager;
 pub mod query;
 #[allow(clippy::module_inception)] // it's right this isn't ideal :/
 pub mod subscription;
+pub mod topic_subscriptions;
 pub mod tx;
+pub mod view_subscriptions;
@@ -1,31 +1,44 @@
+use super::coalesce::CoalesceWindow;
 use super::execution_unit::QueryHash;
 use super::module_subscription_manager::{Plan, SubscriptionManager};
 use super::query::compile_read_only_query;
+use super::topic_subscriptions::TopicSubscriptions;
 use super::tx::DeltaTx;
+use super::view_subscriptions::ViewSubscriptions;
 use crate::client::messages::{
-    SubscriptionError, SubscriptionMessage, SubscriptionResult, SubscriptionRows, SubscriptionUpdateMessage,
-    TransactionUpdateMessage,
+    SubscriptionChunkMessage, SubscriptionError, SubscriptionMessage, SubscriptionResult, SubscriptionRows,
+    SubscriptionUpdateMessage, TransactionUpdateMessage,
 };
 use crate::client::{ClientActorId, ClientConnectionSender, Protocol};
 use crate::db::datastore::locking_tx_datastore::tx::TxId;
+use crate::db::datastore::system_tables::{StSubscriptionRow, StVarTable, ST_SUBSCRIPTION_ID};
 use crate::db::relational_db::{MutTx, RelationalDB, Tx};
 use crate::error::DBError;
-use crate::estimation::estimate_rows_scanned;
+use crate::estimation::{estimate_rows_scanned, estimate_subscription_cost};
 use crate::execution_context::Workload;
 use crate::host::module_host::{DatabaseUpdate, EventStatus, ModuleEvent};
-use crate::messages::websocket::Subscribe;
+use crate::messages::websocket::{self as ws, Subscribe};
 use crate::vm::check_row_limit;
 use crate::worker_metrics::WORKER_METRICS;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use spacetimedb_client_api_messages::websocket::{
-    BsatnFormat, FormatSwitch, JsonFormat, SubscribeSingle, TableUpdate, Unsubscribe,
+    BsatnFormat, Compression, FormatSwitch, JsonFormat, SubscribeSingle, TableUpdate, Unsubscribe,
 };
-use spacetimedb_lib::identity::AuthCtx;
+use spacetimedb_data_structures::map::{HashSet, IntMap};
+use spacetimedb_lib::identity::{AuthCtx, RequestId};
 use spacetimedb_lib::Identity;
+use spacetimedb_primitives::TableId;
 use spacetimedb_query::{execute_plans, SubscribePlan};
-use std::{sync::Arc, time::Instant};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 
 type Subscriptions = Arc<RwLock<SubscriptionManager>>;
+type SwitchedDbUpdate = FormatSwitch<ws::DatabaseUpdate<BsatnFormat>, ws::DatabaseUpdate<JsonFormat>>;
+
+/// How often the evaluation costs in `st_subscription` are brought up to date,
+/// as queries are evaluated against transactions.
+const ST_SUBSCRIPTION_SYNC_INTERVAL: Duration = Duration::from_secs(1);
 
 #[derive(Debug, Clone)]
 pub struct ModuleSubscriptions {
@@ -33,7 +46,15 @@ pub struct ModuleSubscriptions {
     /// If taking a lock (tx) on the db at the same time, ALWAYS lock the db first.
     /// You will deadlock otherwise.
     subscriptions: Subscriptions,
+    /// Subscriptions to the results of views, which are evaluated by the module.
+    views: ViewSubscriptions,
+    /// Subscriptions to topics on which reducers broadcast ephemeral messages.
+    topics: TopicSubscriptions,
     owner_identity: Identity,
+    /// When `st_subscription` was last brought up to date.
+    st_subscription_synced: Arc<Mutex<Instant>>,
+    /// Whether a task is sending the coalesced updates held back from clients.
+    coalescing: Arc<AtomicBool>,
 }
 
 type AssertTxFn = Arc<dyn Fn(&Tx)>;
@@ -43,7 +64,60 @@ impl ModuleSubscriptions {
         Self {
             relational_db,
             subscriptions: Arc::new(RwLock::new(SubscriptionManager::default())),
+            views: ViewSubscriptions::default(),
+            topics: TopicSubscriptions::default(),
             owner_identity,
+            st_subscription_synced: Arc::new(Mutex::new(Instant::now())),
+            coalescing: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// The subscriptions of clients to the results of views.
+    pub fn views(&self) -> &ViewSubscriptions {
+        &self.views
+    }
+
+    /// The subscriptions of clients to topics.
+    pub fn topics(&self) -> &TopicSubscriptions {
+        &self.topics
+    }
+
+    /// Coalesce the updates sent to clients of the tables in `windows`, as declared by the module.
+    ///
+    /// The first time any table is coalesced, a task is spawned to send the updates held back as they come due,
+    /// so this must be called within a tokio runtime.
+    pub fn set_coalesce_windows(&self, windows: IntMap<TableId, CoalesceWindow>) {
+        let spawn = !windows.is_empty() && !self.coalescing.swap(true, Ordering::Relaxed);
+        self.subscriptions.write().set_coalesce_windows(windows);
+        if spawn {
+            tokio::spawn(flush_coalesced(Arc::downgrade(&self.subscriptions)));
+        }
+    }
+
+    /// Bring `st_subscription` in line with the subscriptions of connected clients,
+    /// and the time spent evaluating their queries.
+    ///
+    /// Must not be called while holding a lock on `subscriptions` or a tx on the db.
+    fn sync_st_subscription(&self) {
+        *self.st_subscription_synced.lock() = Instant::now();
+        let db = &self.relational_db;
+        let res = db.with_auto_commit(Workload::Internal, |tx| {
+            let mut rows = self.subscriptions.read().st_subscription_rows().collect::<HashSet<_>>();
+            let mut stale = vec![];
+            for row_ref in db.iter_mut(tx, ST_SUBSCRIPTION_ID)? {
+                // Rows which are up to date are left as they are.
+                if !rows.remove(&StSubscriptionRow::try_from(row_ref)?) {
+                    stale.push(row_ref.pointer());
+                }
+            }
+            db.delete(tx, ST_SUBSCRIPTION_ID, stale);
+            for row in rows {
+                tx.insert_via_serialize_bsatn(ST_SUBSCRIPTION_ID, &row)?;
+            }
+            Ok::<_, DBError>(())
+        });
+        if let Err(e) = res {
+            log::warn!("Failed to update `st_subscription`: {e}");
         }
     }
 
@@ -73,6 +147,46 @@ impl ModuleSubscriptions {
         })
     }
 
+    /// Reject a new subscription if its estimated cost exceeds `subscription_cost_limit`,
+    /// unless the subscriber is the database owner,
+    /// and flag it if its estimated cost exceeds `subscription_cost_warn`.
+    ///
+    /// Every subscription is evaluated against every subsequent transaction,
+    /// so one costly query can hold up updates for all clients of the database.
+    fn check_subscription_cost(&self, plan: &SubscribePlan, tx: &TxId, auth: &AuthCtx) -> Result<(), DBError> {
+        let db = &self.relational_db;
+        let limit = StVarTable::subscription_cost_limit(db, tx)?.filter(|_| auth.caller != auth.owner);
+        let warn = StVarTable::subscription_cost_warn(db, tx)?;
+        if limit.is_none() && warn.is_none() {
+            return Ok(());
+        }
+
+        let cost = estimate_subscription_cost(tx, plan);
+        let total = cost.total();
+        if let Some(limit) = limit.filter(|&limit| total > limit) {
+            WORKER_METRICS
+                .costly_subscriptions
+                .with_label_values(&db.database_identity(), "rejected")
+                .inc();
+            return Err(DBError::Other(anyhow::anyhow!(
+                "Estimated cost of subscribing to `{}` ({total}: {cost}) exceeds limit ({limit})",
+                plan.table_name()
+            )));
+        }
+        if let Some(warn) = warn.filter(|&warn| total > warn) {
+            WORKER_METRICS
+                .costly_subscriptions
+                .with_label_values(&db.database_identity(), "flagged")
+                .inc();
+            log::warn!(
+                "Subscription of {} to `{}` has an estimated cost of {total} ({cost}), exceeding {warn}",
+                auth.caller,
+                plan.table_name()
+            );
+        }
+        Ok(())
+    }
+
     #[tracing::instrument(level = "trace", skip_all)]
     pub fn add_subscription(
         &self,
@@ -114,6 +228,9 @@ impl ModuleSubscriptions {
             }
         };
 
+        let plan = SubscribePlan::from_delta_plan(&query);
+        self.check_subscription_cost(&plan, &tx, &auth)?;
+        self.relational_db.index_advisor().record_plan(&tx, &plan);
         let table_rows = self.evaluate_initial_subscription(sender.clone(), query.clone(), &tx, &auth)?;
 
         // It acquires the subscription lock after `eval`, allowing `add_subscription` to run concurrently.
@@ -146,6 +263,10 @@ impl ModuleSubscriptions {
                 table_rows,
             }),
         });
+
+        drop(subscriptions);
+        drop(tx);
+        self.sync_st_subscription();
         Ok(())
     }
 
@@ -193,6 +314,10 @@ impl ModuleSubscriptions {
                 table_rows,
             }),
         });
+
+        drop(tx);
+        drop(subscriptions);
+        self.sync_st_subscription();
         Ok(())
     }
 
@@ -261,11 +386,19 @@ impl ModuleSubscriptions {
             |plan, tx| rows_scanned(tx, plan),
             &auth,
         )?;
+        for plan in &plans {
+            self.check_subscription_cost(plan, &tx, &auth)?;
+            self.relational_db.index_advisor().record_plan(&tx, plan);
+        }
 
-        let tx = DeltaTx::from(&*tx);
+        // When the client asked for the rows in chunks,
+        // compress each chunk rather than the whole update, which we need to split.
+        let chunk_rows = subscription.chunk_rows.map(|n| n as usize);
+        let eval_comp = if chunk_rows.is_some() { Compression::None } else { comp };
+        let delta_tx = DeltaTx::from(&*tx);
         let database_update = match sender.config.protocol {
-            Protocol::Text => FormatSwitch::Json(execute_plans(plans, comp, &tx)?),
-            Protocol::Binary => FormatSwitch::Bsatn(execute_plans(plans, comp, &tx)?),
+            Protocol::Text => FormatSwitch::Json(execute_plans(plans, eval_comp, &delta_tx)?),
+            Protocol::Binary => FormatSwitch::Bsatn(execute_plans(plans, eval_comp, &delta_tx)?),
         };
 
         // It acquires the subscription lock after `eval`, allowing `add_subscription` to run concurrently.
@@ -282,28 +415,40 @@ impl ModuleSubscriptions {
 
         #[cfg(test)]
         if let Some(assert) = _assert {
-            assert(&tx);
+            assert(&delta_tx);
         }
 
         // NOTE: It is important to send the state in this thread because if you spawn a new
         // thread it's possible for messages to get sent to the client out of order. If you do
         // spawn in another thread messages will need to be buffered until the state is sent out
         // on the wire
+        let database_update = match chunk_rows {
+            Some(max_rows) => send_initial_chunks(&sender, database_update, max_rows, comp, request_id),
+            None => database_update,
+        };
         let _ = sender.send_message(SubscriptionUpdateMessage {
             database_update,
             request_id: Some(request_id),
             timer: Some(timer),
         });
+
+        drop(subscriptions);
+        drop(tx);
+        self.sync_st_subscription();
         Ok(())
     }
 
     pub fn remove_subscriber(&self, client_id: ClientActorId) {
+        self.views.remove_client(&client_id);
+        self.topics.remove_client(&client_id);
         let mut subscriptions = self.subscriptions.write();
         subscriptions.remove_all_subscriptions(&(client_id.identity, client_id.address));
         WORKER_METRICS
             .subscription_queries
             .with_label_values(&self.relational_db.database_identity())
             .set(subscriptions.num_unique_queries() as i64);
+        drop(subscriptions);
+        self.sync_st_subscription();
     }
 
     /// Commit a transaction and broadcast its ModuleEvent to all interested subscribers.
@@ -317,6 +462,12 @@ impl ModuleSubscriptions {
         // else it can result in subscriber receiving duplicate updates.
         let subscriptions = self.subscriptions.read();
         let stdb = &self.relational_db;
+        // Refuse writes which would grow a database that is over its size quota.
+        if let EventStatus::Committed(_) = event.status {
+            if let Err(e) = stdb.check_size_quota(&tx) {
+                event.status = EventStatus::Failed(e.to_string());
+            }
+        }
         // Downgrade mutable tx.
         // Ensure tx is released/cleaned up once out of scope.
         let (read_tx, tx_data) = match &mut event.status {
@@ -325,18 +476,20 @@ impl ModuleSubscriptions {
                     return Ok(Err(WriteConflict));
                 };
                 *db_update = DatabaseUpdate::from_writes(&tx_data);
+                event.tx_offset = tx_data.tx_offset();
                 (read_tx, Some(tx_data))
             }
-            EventStatus::Failed(_) | EventStatus::OutOfEnergy => {
-                (stdb.rollback_mut_tx_downgrade(tx, Workload::Update), None)
-            }
+            EventStatus::Failed(_)
+            | EventStatus::FailedWithValue(_)
+            | EventStatus::OutOfEnergy
+            | EventStatus::TimedOut => (stdb.rollback_mut_tx_downgrade(tx, Workload::Update), None),
         };
 
         let read_tx = scopeguard::guard(read_tx, |tx| {
             self.relational_db.release_tx(tx);
         });
 
-        let read_tx = tx_data
+        let delta_tx = tx_data
             .as_ref()
             .map(|tx_data| DeltaTx::new(&read_tx, tx_data))
             .unwrap_or_else(|| DeltaTx::from(&*read_tx));
@@ -344,12 +497,16 @@ impl ModuleSubscriptions {
         let event = Arc::new(event);
 
         match &event.status {
-            EventStatus::Committed(_) => subscriptions.eval_updates(&read_tx, event.clone(), caller),
-            EventStatus::Failed(_) => {
+            EventStatus::Committed(_) => {
+                let hide_caller = StVarTable::hide_callers(stdb, &read_tx)?;
+                subscriptions.eval_updates(&delta_tx, event.clone(), caller, hide_caller)
+            }
+            EventStatus::Failed(_) | EventStatus::FailedWithValue(_) | EventStatus::TimedOut => {
                 if let Some(client) = caller {
                     let message = TransactionUpdateMessage {
                         event: Some(event.clone()),
                         database_update: SubscriptionUpdateMessage::default_for_protocol(client.config.protocol, None),
+                        hide_caller: false,
                     };
                     let _ = client.send_message(message);
                 } else {
@@ -359,16 +516,88 @@ impl ModuleSubscriptions {
             EventStatus::OutOfEnergy => {} // ?
         }
 
+        drop(read_tx);
+        drop(subscriptions);
+        // Views are refreshed by a separate task, so as not to delay the response to the transaction.
+        if let Some(tx_data) = &tx_data {
+            self.views.tables_modified(tx_data.table_ids());
+        }
+        if self.st_subscription_synced.lock().elapsed() >= ST_SUBSCRIPTION_SYNC_INTERVAL {
+            self.sync_st_subscription();
+        }
+
         Ok(Ok(event))
     }
 }
 
+/// Sends the coalesced updates held back in `subscriptions` as they come due,
+/// for as long as the subscriptions are around.
+async fn flush_coalesced(subscriptions: Weak<RwLock<SubscriptionManager>>) {
+    loop {
+        let Some(manager) = subscriptions.upgrade() else {
+            return;
+        };
+        let (next_due, notify) = {
+            let manager = manager.read();
+            (manager.flush_due(Instant::now()), manager.pending_notify())
+        };
+        drop(manager);
+        match next_due {
+            Some(due) => tokio::select! {
+                _ = tokio::time::sleep_until(due.into()) => {}
+                _ = notify.notified() => {}
+            },
+            None => notify.notified().await,
+        }
+    }
+}
+
+/// Splits the initial rows of a legacy subscription into chunks of at most `max_rows` rows,
+/// sends all but the last chunk to `sender`, and returns the last chunk.
+fn send_initial_chunks(
+    sender: &ClientConnectionSender,
+    database_update: SwitchedDbUpdate,
+    max_rows: usize,
+    comp: Compression,
+    request_id: RequestId,
+) -> SwitchedDbUpdate {
+    let (mut chunks, total_rows): (Vec<SwitchedDbUpdate>, _) = match database_update {
+        FormatSwitch::Bsatn(update) => {
+            let total_rows = update.num_rows();
+            let chunks = update.into_chunks(max_rows, comp).into_iter().map(FormatSwitch::Bsatn);
+            (chunks.collect(), total_rows)
+        }
+        FormatSwitch::Json(update) => {
+            let total_rows = update.num_rows();
+            let chunks = update.into_chunks(max_rows, comp).into_iter().map(FormatSwitch::Json);
+            (chunks.collect(), total_rows)
+        }
+    };
+    // `into_chunks` always returns at least one chunk.
+    let last = chunks.pop().unwrap();
+    let mut rows_sent = 0;
+    for database_update in chunks {
+        rows_sent += match &database_update {
+            FormatSwitch::Bsatn(update) => update.num_rows(),
+            FormatSwitch::Json(update) => update.num_rows(),
+        } as u64;
+        let _ = sender.send_message(SubscriptionChunkMessage {
+            database_update,
+            request_id,
+            rows_sent,
+            total_rows: total_rows as u64,
+        });
+    }
+    last
+}
+
 pub struct WriteConflict;
 
 #[cfg(test)]
 mod tests {
     use super::{AssertTxFn, ModuleSubscriptions};
     use crate::client::{ClientActorId, ClientConfig, ClientConnectionSender};
+    use crate::db::datastore::system_tables::{StSubscriptionRow, StVarName, StVarTable, ST_SUBSCRIPTION_ID};
     use crate::db::relational_db::tests_utils::{insert, TestDB};
     use crate::db::relational_db::RelationalDB;
     use crate::error::DBError;
@@ -381,16 +610,28 @@ mod tests {
     use std::{sync::Arc, time::Duration};
     use tokio::sync::mpsc;
 
+    const OWNER: Identity = Identity::from_byte_array([1; 32]);
+
     fn add_subscriber(db: Arc<RelationalDB>, sql: &str, assert: Option<AssertTxFn>) -> Result<(), DBError> {
-        let owner = Identity::from_byte_array([1; 32]);
-        let client = ClientActorId::for_test(Identity::ZERO);
+        add_subscriber_as(db, Identity::ZERO, sql, assert)
+    }
+
+    fn add_subscriber_as(
+        db: Arc<RelationalDB>,
+        identity: Identity,
+        sql: &str,
+        assert: Option<AssertTxFn>,
+    ) -> Result<(), DBError> {
+        let client = ClientActorId::for_test(identity);
         let config = ClientConfig::for_test();
         let sender = Arc::new(ClientConnectionSender::dummy(client, config));
-        let module_subscriptions = ModuleSubscriptions::new(db.clone(), owner);
+        let module_subscriptions = ModuleSubscriptions::new(db.clone(), OWNER);
 
         let subscribe = Subscribe {
             query_strings: [sql.into()].into(),
             request_id: 0,
+            min_tx_offset: None,
+            chunk_rows: None,
         };
         module_subscriptions.add_legacy_subscriber(sender, subscribe, Instant::now(), assert)
     }
@@ -479,4 +720,74 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn costly_subs_are_rejected() -> ResultTest<()> {
+        let test_db = TestDB::durable()?;
+        let db = Arc::new(test_db.db.clone());
+
+        let table_id =
+            db.create_table_for_test("T", &[("a", AlgebraicType::U8), ("b", AlgebraicType::U8)], &[0.into()])?;
+        db.with_auto_commit(Workload::ForTests, |tx| -> Result<_, DBError> {
+            for i in 0..10u8 {
+                insert(&db, tx, table_id, &product!(i, i))?;
+            }
+            Ok(())
+        })?;
+
+        // Without a limit, any query is accepted.
+        assert!(add_subscriber(db.clone(), "SELECT * FROM T WHERE b = 1", None).is_ok());
+
+        db.with_auto_commit(Workload::ForTests, |tx| {
+            StVarTable::write_var(&db, tx, StVarName::SubscriptionCostLimit, "5")
+        })?;
+
+        // Index lookups are cheap.
+        assert!(add_subscriber(db.clone(), "SELECT * FROM T WHERE a = 1", None).is_ok());
+        // Scanning all 10 rows of `T` is not.
+        assert!(add_subscriber(db.clone(), "SELECT * FROM T WHERE b = 1", None).is_err());
+        // Unless the subscriber is the database owner.
+        assert!(add_subscriber_as(db.clone(), OWNER, "SELECT * FROM T WHERE b = 1", None).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn st_subscription_tracks_subscriptions() -> ResultTest<()> {
+        let test_db = TestDB::durable()?;
+        let db = Arc::new(test_db.db.clone());
+        db.create_table_for_test("T", &[("a", AlgebraicType::U8)], &[])?;
+
+        let client = ClientActorId::for_test(Identity::ZERO);
+        let sender = Arc::new(ClientConnectionSender::dummy(client, ClientConfig::for_test()));
+        let module_subscriptions = ModuleSubscriptions::new(db.clone(), OWNER);
+        let subscribe = Subscribe {
+            query_strings: ["SELECT * FROM T".into()].into(),
+            request_id: 0,
+            min_tx_offset: None,
+            chunk_rows: None,
+        };
+        module_subscriptions.add_legacy_subscriber(sender, subscribe, Instant::now(), None)?;
+
+        let st_subscription = || {
+            db.with_read_only(Workload::ForTests, |tx| {
+                db.iter(tx, ST_SUBSCRIPTION_ID)?
+                    .map(StSubscriptionRow::try_from)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+        };
+
+        // The subscription is listed as soon as it is made.
+        let rows = st_subscription()?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].identity, Identity::ZERO.into());
+        assert_eq!(rows[0].address, client.address.into());
+        assert_eq!(&*rows[0].query, "SELECT * FROM T");
+
+        // And removed once the client disconnects.
+        module_subscriptions.remove_subscriber(client);
+        assert!(st_subscription()?.is_empty());
+
+        Ok(())
+    }
 }
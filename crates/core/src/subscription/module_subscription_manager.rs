@@ -1,13 +1,17 @@
+use super::coalesce::{CoalesceWindow, PendingUpdates};
 use super::execution_unit::QueryHash;
 use super::tx::DeltaTx;
 use crate::client::messages::{SubscriptionUpdateMessage, TransactionUpdateMessage};
-use crate::client::{ClientConnectionSender, Protocol};
+use crate::client::{ClientConnectionSender, Protocol, ProtocolVersion};
+use crate::db::datastore::system_tables::StSubscriptionRow;
 use crate::error::DBError;
 use crate::host::module_host::{DatabaseTableUpdate, ModuleEvent, UpdatesRelValue};
 use crate::messages::websocket::{self as ws, TableUpdate};
 use crate::subscription::delta::eval_delta;
 use hashbrown::hash_map::OccupiedError;
+use parking_lot::Mutex;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use spacetimedb_client_api_messages::timestamp::Timestamp;
 use spacetimedb_client_api_messages::websocket::{
     BsatnFormat, CompressableQueryUpdate, FormatSwitch, JsonFormat, QueryId, QueryUpdate, WebsocketFormat,
 };
@@ -15,8 +19,13 @@ use spacetimedb_data_structures::map::{Entry, HashCollectionExt, HashMap, HashSe
 use spacetimedb_lib::{Address, Identity};
 use spacetimedb_primitives::TableId;
 use spacetimedb_query::delta::DeltaPlan;
+use spacetimedb_query::top_n::TopNWindow;
+use spacetimedb_vm::relation::RelValue;
 use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Notify;
 
 /// Clients are uniquely identified by their Identity and Address.
 /// Identity is insufficient because different Addresses can use the same Identity.
@@ -34,6 +43,7 @@ type SubscriptionId = (ClientId, ClientQueryId);
 #[derive(Debug)]
 pub struct Plan {
     hash: QueryHash,
+    sql: Box<str>,
     plan: DeltaPlan,
 }
 
@@ -46,13 +56,19 @@ impl Deref for Plan {
 }
 
 impl Plan {
-    pub fn new(plan: DeltaPlan, hash: QueryHash) -> Self {
-        Self { plan, hash }
+    pub fn new(plan: DeltaPlan, hash: QueryHash, sql: impl Into<Box<str>>) -> Self {
+        let sql = sql.into();
+        Self { plan, hash, sql }
     }
 
     pub fn hash(&self) -> QueryHash {
         self.hash
     }
+
+    /// The text of the query, as subscribed to by clients.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
 }
 
 /// For each client, we hold a handle for sending messages, and we track the queries they are subscribed to.
@@ -62,6 +78,8 @@ struct ClientInfo {
     subscriptions: HashMap<SubscriptionId, QueryHash>,
     // This should be removed when we migrate to SubscribeSingle.
     legacy_subscriptions: HashSet<QueryHash>,
+    // When the client subscribed to each of its queries, in microseconds since the Unix epoch.
+    subscribed_at: HashMap<QueryHash, u64>,
 }
 
 impl ClientInfo {
@@ -70,8 +88,29 @@ impl ClientInfo {
             outbound_ref,
             subscriptions: HashMap::default(),
             legacy_subscriptions: HashSet::default(),
+            subscribed_at: HashMap::default(),
         }
     }
+
+    /// Record that the client is subscribed to `hash` from now on,
+    /// unless it already was.
+    fn subscribed(&mut self, hash: QueryHash) {
+        self.subscribed_at
+            .entry(hash)
+            .or_insert_with(|| Timestamp::now().microseconds);
+    }
+
+    /// Forget when the client subscribed to the queries it is no longer subscribed to.
+    fn retain_subscribed(&mut self) {
+        let Self {
+            subscriptions,
+            legacy_subscriptions,
+            subscribed_at,
+            ..
+        } = self;
+        subscribed_at
+            .retain(|hash, _| legacy_subscriptions.contains(hash) || subscriptions.values().any(|h| h == hash));
+    }
 }
 
 /// For each query that has subscribers, we track a set of legacy subscribers and individual subscriptions.
@@ -82,14 +121,21 @@ struct QueryState {
     legacy_subscribers: HashSet<ClientId>,
     // For clients that subscribe to a single query, we track them here.
     subscriptions: HashSet<SubscriptionId>,
+    // The time spent evaluating the query against transactions, in microseconds.
+    eval_micros: AtomicU64,
+    // For a top-N query, the rows its subscribers currently see.
+    top_n: Option<Mutex<TopNWindow>>,
 }
 
 impl QueryState {
     fn new(query: Query) -> Self {
+        let top_n = query.top_n_window().map(Mutex::new);
         Self {
             query,
             legacy_subscribers: HashSet::default(),
             subscriptions: HashSet::default(),
+            eval_micros: AtomicU64::new(0),
+            top_n,
         }
     }
     fn has_subscribers(&self) -> bool {
@@ -119,6 +165,15 @@ pub struct SubscriptionManager {
 
     // Inverted index from tables to queries that read from them.
     tables: IntMap<TableId, HashSet<QueryHash>>,
+
+    // The tables whose updates are held back and merged before they are sent to subscribers.
+    coalesce: IntMap<TableId, CoalesceWindow>,
+
+    // The coalesced updates held back from each client.
+    pending: Mutex<HashMap<ClientId, PendingUpdates>>,
+
+    // Notified when updates are first held back from a client.
+    pending_notify: Arc<Notify>,
 }
 
 impl SubscriptionManager {
@@ -134,6 +189,86 @@ impl SubscriptionManager {
         self.queries.len()
     }
 
+    /// Coalesce the updates to the tables in `windows`, instead of those given before.
+    pub fn set_coalesce_windows(&mut self, windows: IntMap<TableId, CoalesceWindow>) {
+        self.coalesce = windows;
+    }
+
+    /// Notified when updates are first held back from a client,
+    /// so that whoever flushes them knows when to next call [`Self::flush_due`].
+    pub fn pending_notify(&self) -> Arc<Notify> {
+        self.pending_notify.clone()
+    }
+
+    /// Sends the coalesced updates which are due by `now`,
+    /// returning when the next of those held back are due, if any are.
+    pub fn flush_due(&self, now: Instant) -> Option<Instant> {
+        let mut pending = self.pending.lock();
+        let due = pending
+            .iter()
+            .filter(|(_, updates)| updates.due() <= now)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in due {
+            if let Some(updates) = pending.remove(&id) {
+                self.send_pending(&id, updates);
+            }
+        }
+        pending.values().map(PendingUpdates::due).min()
+    }
+
+    /// Sends the coalesced updates held back from `client`, if any,
+    /// so that they reach it before a change to its subscriptions.
+    fn flush_client(&self, client: &ClientId) {
+        if let Some(updates) = self.pending.lock().remove(client) {
+            self.send_pending(client, updates);
+        }
+    }
+
+    /// Merges the `updates` to the rows of a coalesced query into those held back from its subscribers.
+    fn hold_back(&self, state: &QueryState, window: &CoalesceWindow, updates: UpdatesRelValue<'_>) {
+        let deletes = updates
+            .deletes
+            .into_iter()
+            .map(RelValue::into_product_value)
+            .collect::<Vec<_>>();
+        let inserts = updates
+            .inserts
+            .into_iter()
+            .map(RelValue::into_product_value)
+            .collect::<Vec<_>>();
+        let mut copies = HashMap::<&ClientId, usize>::new();
+        for id in state.all_clients() {
+            *copies.entry(id).or_default() += 1;
+        }
+        let mut pending = self.pending.lock();
+        for (id, copies) in copies {
+            let updates = pending.entry(*id).or_insert_with(|| {
+                self.pending_notify.notify_one();
+                PendingUpdates::new(Instant::now() + window.window)
+            });
+            updates.push(&state.query, window, copies, &deletes, &inserts);
+        }
+    }
+
+    /// Sends `updates` to `client` as a light transaction update,
+    /// as they don't belong to any one transaction.
+    fn send_pending(&self, client: &ClientId, updates: PendingUpdates) {
+        let Some(ci) = self.clients.get(client) else {
+            return;
+        };
+        let client = &ci.outbound_ref;
+        let database_update = match client.config.protocol {
+            Protocol::Binary => FormatSwitch::Bsatn(updates.into_database_update(client.config.compression)),
+            Protocol::Text => FormatSwitch::Json(updates.into_database_update(client.config.compression)),
+        };
+        let update = SubscriptionUpdateMessage {
+            database_update,
+            ..SubscriptionUpdateMessage::default_for_protocol(client.config.protocol, None)
+        };
+        send_to_client(client, None, update, false);
+    }
+
     #[cfg(test)]
     fn contains_query(&self, hash: &QueryHash) -> bool {
         self.queries.contains_key(hash)
@@ -172,6 +307,7 @@ impl SubscriptionManager {
                 }
             }
             ci.legacy_subscriptions.clear();
+            ci.retain_subscribed();
             for query_hash in queries_to_remove {
                 self.queries.remove(&query_hash);
             }
@@ -181,6 +317,7 @@ impl SubscriptionManager {
     /// Remove a single subscription for a client.
     /// This will return an error if the client does not have a subscription with the given query id.
     pub fn remove_subscription(&mut self, client_id: ClientId, query_id: ClientQueryId) -> Result<Query, DBError> {
+        self.flush_client(&client_id);
         let subscription_id = (client_id, query_id);
         let Some(ci) = self.clients.get_mut(&client_id) else {
             return Err(anyhow::anyhow!("Client not found: {:?}", client_id).into());
@@ -189,6 +326,7 @@ impl SubscriptionManager {
         let Some(query_hash) = ci.subscriptions.remove(&subscription_id) else {
             return Err(anyhow::anyhow!("Subscription not found: {:?}", subscription_id).into());
         };
+        ci.retain_subscribed();
         let Some(query_state) = self.queries.get_mut(&query_hash) else {
             return Err(anyhow::anyhow!("Query state not found for query hash: {:?}", query_hash).into());
         };
@@ -205,6 +343,7 @@ impl SubscriptionManager {
     /// Adds a single subscription for a client.
     pub fn add_subscription(&mut self, client: Client, query: Query, query_id: ClientQueryId) -> Result<(), DBError> {
         let client_id = (client.id.identity, client.id.address);
+        self.flush_client(&client_id);
         let ci = self
             .clients
             .entry(client_id)
@@ -220,6 +359,7 @@ impl SubscriptionManager {
             )
             .into());
         }
+        ci.subscribed(hash);
 
         let query_state = self
             .queries
@@ -246,6 +386,7 @@ impl SubscriptionManager {
     // #[tracing::instrument(level = "trace", skip_all)]
     pub fn set_legacy_subscription(&mut self, client: Client, queries: impl IntoIterator<Item = Query>) {
         let client_id = (client.id.identity, client.id.address);
+        self.flush_client(&client_id);
         // First, remove any existing legacy subscriptions.
         self.remove_legacy_subscriptions(&client_id);
 
@@ -257,6 +398,7 @@ impl SubscriptionManager {
         for unit in queries {
             let hash = unit.hash();
             ci.legacy_subscriptions.insert(hash);
+            ci.subscribed(hash);
             let query_state = self
                 .queries
                 .entry(hash)
@@ -288,6 +430,7 @@ impl SubscriptionManager {
     /// it is removed from the index along with its table ids.
     #[tracing::instrument(level = "trace", skip_all)]
     pub fn remove_all_subscriptions(&mut self, client: &ClientId) {
+        self.pending.get_mut().remove(client);
         self.remove_legacy_subscriptions(client);
         let Some(client_info) = self.clients.remove(client) else {
             return;
@@ -311,14 +454,47 @@ impl SubscriptionManager {
         }
     }
 
+    /// The rows of `st_subscription`, one for each query each client is subscribed to.
+    pub fn st_subscription_rows(&self) -> impl Iterator<Item = StSubscriptionRow> + '_ {
+        self.clients.iter().flat_map(move |(&(identity, address), ci)| {
+            ci.subscribed_at.iter().filter_map(move |(hash, &created_at)| {
+                let state = self.queries.get(hash)?;
+                Some(StSubscriptionRow {
+                    identity: identity.into(),
+                    address: address.into(),
+                    query: state.query.sql().into(),
+                    created_at,
+                    eval_cost: state.eval_micros.load(Ordering::Relaxed),
+                })
+            })
+        })
+    }
+
     /// This method takes a set of delta tables,
     /// evaluates only the necessary queries for those delta tables,
     /// and then sends the results to each client.
     #[tracing::instrument(level = "trace", skip_all)]
-    pub fn eval_updates(&self, tx: &DeltaTx, event: Arc<ModuleEvent>, caller: Option<&ClientConnectionSender>) {
+    /// Sends the updates of the transaction committed by `event` to its subscribers.
+    ///
+    /// If `hide_caller`, the clients other than `caller` are not told who called the reducer.
+    pub fn eval_updates(
+        &self,
+        tx: &DeltaTx,
+        event: Arc<ModuleEvent>,
+        caller: Option<&ClientConnectionSender>,
+        hide_caller: bool,
+    ) {
         use FormatSwitch::{Bsatn, Json};
 
         let tables = &event.status.database_update().unwrap().tables;
+        // Subscribers are told that truncated tables were cleared,
+        // rather than being sent each of their deleted rows,
+        // unless they speak a version of the protocol which can't say so.
+        let truncated = tables
+            .iter()
+            .filter(|table| table.truncated)
+            .map(|table| table.table_id)
+            .collect::<HashSet<_>>();
 
         // Put the main work on a rayon compute thread.
         rayon::scope(|_| {
@@ -331,11 +507,12 @@ impl SubscriptionManager {
                 .flatten()
                 .collect::<HashSet<_>>()
                 .par_iter()
-                .filter_map(|&hash| self.queries.get(hash).map(|state| (hash, &state.query)))
+                .filter_map(|&hash| self.queries.get(hash).map(|state| (hash, state)))
                 // If N clients are subscribed to a query,
                 // we copy the DatabaseTableUpdate N times,
                 // which involves cloning BSATN (binary) or product values (json).
-                .flat_map_iter(|(hash, plan)| {
+                .flat_map_iter(|(hash, state)| {
+                    let plan = &state.query;
                     let table_id = plan.table_id();
                     let table_name = plan.table_name();
                     // Store at most one copy of the serialization to BSATN
@@ -344,41 +521,67 @@ impl SubscriptionManager {
                     // but we only fill `ops_bin` and `ops_json` at most once.
                     // The former will be `Some(_)` if some subscriber uses `Protocol::Binary`
                     // and the latter `Some(_)` if some subscriber uses `Protocol::Text`.
+                    // When the table was cleared, ditto for the serializations without the deletes.
                     let mut ops_bin: Option<(CompressableQueryUpdate<BsatnFormat>, _)> = None;
                     let mut ops_json: Option<(QueryUpdate<JsonFormat>, _)> = None;
+                    let mut ops_bin_cleared: Option<(CompressableQueryUpdate<BsatnFormat>, _)> = None;
+                    let mut ops_json_cleared: Option<(QueryUpdate<JsonFormat>, _)> = None;
 
                     fn memo_encode<F: WebsocketFormat>(
                         updates: &UpdatesRelValue<'_>,
+                        cleared: bool,
                         client: &ClientConnectionSender,
                         memory: &mut Option<(F::QueryUpdate, u64)>,
                     ) -> (F::QueryUpdate, u64) {
                         memory
-                            .get_or_insert_with(|| updates.encode::<F>(client.config.compression))
+                            .get_or_insert_with(|| match cleared {
+                                true => updates.encode_inserts::<F>(client.config.compression),
+                                false => updates.encode::<F>(client.config.compression),
+                            })
                             .clone()
                     }
 
                     let evaluator = plan.evaluator(tx);
+                    let cleared = truncated.contains(&table_id);
+
+                    let start = Instant::now();
+                    let delta_updates = eval_delta(tx, &evaluator).and_then(|updates| match &state.top_n {
+                        Some(window) => eval_top_n(tx, window, updates),
+                        None => Ok(updates),
+                    });
+                    let elapsed = start.elapsed().as_micros() as u64;
+                    state.eval_micros.fetch_add(elapsed, Ordering::Relaxed);
 
                     // TODO: Handle errors instead of skipping them
-                    eval_delta(tx, &evaluator)
+                    delta_updates
                         .ok()
                         .filter(|delta_updates| delta_updates.has_updates())
                         .map(|delta_updates| {
+                            // The updates to a coalesced table are sent once its window has passed.
+                            if let Some(window) = self.coalesce.get(&table_id) {
+                                self.hold_back(state, window, delta_updates);
+                                return vec![];
+                            }
                             self.queries
                                 .get(hash)
                                 .into_iter()
                                 .flat_map(|query| query.all_clients())
                                 .map(move |id| {
                                     let client = &self.clients[id].outbound_ref;
+                                    let cleared = cleared && client.config.version >= ProtocolVersion::V2;
+                                    let (ops_bin, ops_json) = match cleared {
+                                        true => (&mut ops_bin_cleared, &mut ops_json_cleared),
+                                        false => (&mut ops_bin, &mut ops_json),
+                                    };
                                     let update = match client.config.protocol {
                                         Protocol::Binary => {
-                                            Bsatn(memo_encode::<BsatnFormat>(&delta_updates, client, &mut ops_bin))
+                                            Bsatn(memo_encode::<BsatnFormat>(&delta_updates, cleared, client, ops_bin))
                                         }
                                         Protocol::Text => {
-                                            Json(memo_encode::<JsonFormat>(&delta_updates, client, &mut ops_json))
+                                            Json(memo_encode::<JsonFormat>(&delta_updates, cleared, client, ops_json))
                                         }
                                     };
-                                    (id, table_id, table_name.clone(), update)
+                                    (id, table_id, table_name.clone(), cleared, update)
                                 })
                                 .collect::<Vec<_>>()
                         })
@@ -393,15 +596,21 @@ impl SubscriptionManager {
                 // or BSATN (`Protocol::Binary`).
                 .fold(
                     HashMap::<(&ClientId, TableId), FormatSwitch<TableUpdate<_>, TableUpdate<_>>>::new(),
-                    |mut tables, (id, table_id, table_name, update)| {
+                    |mut tables, (id, table_id, table_name, cleared, update)| {
                         match tables.entry((id, table_id)) {
                             Entry::Occupied(mut entry) => match entry.get_mut().zip_mut(update) {
                                 Bsatn((tbl_upd, update)) => tbl_upd.push(update),
                                 Json((tbl_upd, update)) => tbl_upd.push(update),
                             },
                             Entry::Vacant(entry) => drop(entry.insert(match update {
-                                Bsatn(update) => Bsatn(TableUpdate::new(table_id, table_name, update)),
-                                Json(update) => Json(TableUpdate::new(table_id, table_name, update)),
+                                Bsatn(update) => Bsatn(TableUpdate {
+                                    cleared,
+                                    ..TableUpdate::new(table_id, table_name, update)
+                                }),
+                                Json(update) => Json(TableUpdate {
+                                    cleared,
+                                    ..TableUpdate::new(table_id, table_name, update)
+                                }),
                             })),
                         }
                         tables
@@ -443,7 +652,7 @@ impl SubscriptionManager {
                     .unwrap_or_else(|| {
                         SubscriptionUpdateMessage::default_for_protocol(caller.config.protocol, event.request_id)
                     });
-                send_to_client(caller, Some(event.clone()), update);
+                send_to_client(caller, Some(event.clone()), update, false);
             }
 
             // Send all the other updates.
@@ -452,39 +661,77 @@ impl SubscriptionManager {
                 let client = self.client(id);
                 // Conditionally send out a full update or a light one otherwise.
                 let event = client.config.tx_update_full.then(|| event.clone());
-                send_to_client(&client, event, message);
+                send_to_client(&client, event, message, hide_caller);
             }
         })
     }
 }
 
+/// Turns the changes a transaction made to the un-truncated rows of a top-N query
+/// into the changes to the rows its subscribers see.
+fn eval_top_n<'a>(
+    tx: &DeltaTx,
+    window: &Mutex<TopNWindow>,
+    updates: UpdatesRelValue<'a>,
+) -> anyhow::Result<UpdatesRelValue<'a>> {
+    if !updates.has_updates() {
+        return Ok(updates);
+    }
+    let UpdatesRelValue { deletes, inserts } = updates;
+    let inserts = inserts.into_iter().map(RelValue::into_product_value).collect();
+    let deletes = deletes.into_iter().map(RelValue::into_product_value).collect();
+    let (deletes, inserts) = window.lock().update(tx, inserts, deletes)?;
+    Ok(UpdatesRelValue {
+        deletes: deletes.into_iter().map(RelValue::Projection).collect(),
+        inserts: inserts.into_iter().map(RelValue::Projection).collect(),
+    })
+}
+
 fn send_to_client(
     client: &ClientConnectionSender,
     event: Option<Arc<ModuleEvent>>,
     database_update: SubscriptionUpdateMessage,
+    hide_caller: bool,
 ) {
-    if let Err(e) = client.send_message(TransactionUpdateMessage { event, database_update }) {
+    let message = TransactionUpdateMessage {
+        event,
+        database_update,
+        hide_caller,
+    };
+    if let Err(e) = client.send_message(message) {
         tracing::warn!(%client.id, "failed to send update message to client: {e}")
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{sync::Arc, time::Duration};
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
 
     use spacetimedb_client_api_messages::timestamp::Timestamp;
-    use spacetimedb_client_api_messages::websocket::QueryId;
+    use spacetimedb_client_api_messages::websocket::{FormatSwitch, QueryId, RowListLen};
+    use spacetimedb_data_structures::map::IntMap;
     use spacetimedb_lib::{error::ResultTest, identity::AuthCtx, Address, AlgebraicType, Identity};
-    use spacetimedb_primitives::TableId;
+    use spacetimedb_primitives::{ColId, TableId};
     use spacetimedb_query::delta::DeltaPlan;
+    use spacetimedb_sats::product;
 
     use super::{Plan, SubscriptionManager};
+    use crate::client::messages::SerializableMessage;
+    use crate::db::datastore::traits::IsolationLevel;
     use crate::execution_context::Workload;
     use crate::sql::ast::SchemaViewer;
+    use crate::subscription::coalesce::CoalesceWindow;
     use crate::subscription::module_subscription_manager::ClientQueryId;
+    use crate::subscription::tx::DeltaTx;
     use crate::{
-        client::{ClientActorId, ClientConfig, ClientConnectionSender, ClientName},
-        db::relational_db::{tests_utils::TestDB, RelationalDB},
+        client::{ClientActorId, ClientConfig, ClientConnectionSender, ClientName, ProtocolVersion},
+        db::relational_db::{
+            tests_utils::{insert, TestDB},
+            MutTx, RelationalDB,
+        },
         energy::EnergyQuanta,
         host::{
             module_host::{DatabaseUpdate, EventStatus, ModuleEvent, ModuleFunctionCall},
@@ -503,7 +750,7 @@ mod tests {
             let tx = SchemaViewer::new(&*tx, &auth);
             let hash = QueryHash::from_string(sql);
             let plan = DeltaPlan::compile(sql, &tx).unwrap();
-            Ok(Arc::new(Plan::new(plan, hash)))
+            Ok(Arc::new(Plan::new(plan, hash, sql)))
         })
     }
 
@@ -951,12 +1198,13 @@ mod tests {
             status: EventStatus::Committed(DatabaseUpdate::default()),
             energy_quanta_used: EnergyQuanta::ZERO,
             host_execution_duration: Duration::default(),
+            tx_offset: None,
             request_id: None,
             timer: None,
         });
 
         db.with_read_only(Workload::Update, |tx| {
-            subscriptions.eval_updates(&(&*tx).into(), event, Some(&client0))
+            subscriptions.eval_updates(&(&*tx).into(), event, Some(&client0), false)
         });
 
         tokio::runtime::Builder::new_current_thread()
@@ -973,4 +1221,136 @@ mod tests {
 
         Ok(())
     }
+
+    /// Commits `tx` and sends its updates to the subscribers in `subscriptions`.
+    fn commit_and_eval(db: &RelationalDB, subscriptions: &SubscriptionManager, tx: MutTx) -> ResultTest<()> {
+        let (data, tx) = db
+            .commit_tx_downgrade(tx, Workload::ForTests)?
+            .expect("tx should commit");
+        let event = Arc::new(ModuleEvent {
+            timestamp: Timestamp::now(),
+            caller_identity: Identity::ZERO,
+            caller_address: None,
+            function_call: ModuleFunctionCall {
+                reducer: "DummyReducer".into(),
+                reducer_id: u32::MAX.into(),
+                args: ArgsTuple::nullary(),
+            },
+            status: EventStatus::Committed(DatabaseUpdate::from_writes(&data)),
+            energy_quanta_used: EnergyQuanta::ZERO,
+            host_execution_duration: Duration::default(),
+            tx_offset: None,
+            request_id: None,
+            timer: None,
+        });
+        subscriptions.eval_updates(&DeltaTx::new(&tx, &data), event, None, false);
+        db.release_tx(tx);
+        Ok(())
+    }
+
+    #[test]
+    fn test_coalesced_updates_are_merged_by_primary_key() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let table_id =
+            db.create_table_for_test("cursor", &[("id", AlgebraicType::U64), ("x", AlgebraicType::U64)], &[])?;
+        let plan = compile_plan(&db, "select * from cursor")?;
+
+        let (client, mut rx) = ClientConnectionSender::dummy_with_channel(
+            ClientActorId::for_test(Identity::ZERO),
+            ClientConfig::for_test(),
+        );
+        let mut subscriptions = SubscriptionManager::default();
+        subscriptions.add_subscription(Arc::new(client), plan, QueryId::new(1))?;
+        let window = CoalesceWindow {
+            window: Duration::from_secs(60),
+            primary_key: ColId(0),
+        };
+        subscriptions.set_coalesce_windows(IntMap::from_iter([(table_id, window)]));
+
+        // Insert a cursor, then move it and another one.
+        let mut tx = db.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&db, &mut tx, table_id, &product![1u64, 10u64])?;
+        commit_and_eval(&db, &subscriptions, tx)?;
+        let mut tx = db.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        db.delete_by_rel(&mut tx, table_id, [product![1u64, 10u64]]);
+        insert(&db, &mut tx, table_id, &product![1u64, 20u64])?;
+        insert(&db, &mut tx, table_id, &product![2u64, 30u64])?;
+        commit_and_eval(&db, &subscriptions, tx)?;
+
+        // Nothing is sent before the window has passed.
+        assert!(rx.try_recv().is_err());
+        assert!(subscriptions.flush_due(Instant::now()).is_some());
+        assert!(rx.try_recv().is_err());
+
+        // Then the client is sent just the latest version of each cursor.
+        assert_eq!(subscriptions.flush_due(Instant::now() + window.window), None);
+        let Ok(SerializableMessage::TxUpdate(message)) = rx.try_recv() else {
+            panic!("expected a transaction update");
+        };
+        assert!(message.event.is_none());
+        let FormatSwitch::Bsatn(update) = message.database_update.database_update else {
+            panic!("expected a binary update");
+        };
+        assert_eq!(update.tables.len(), 1);
+        let query_update = update.tables[0].updates[0].clone().maybe_decompress();
+        assert_eq!(query_update.deletes.len(), 0);
+        assert_eq!(query_update.inserts.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncate_clears_table_for_v2_clients_only() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let table_id = create_table(&db, "T")?;
+        let plan = compile_plan(&db, "select * from T")?;
+
+        let mut subscriptions = SubscriptionManager::default();
+        let mut receivers = vec![];
+        for (address, version) in [(1, ProtocolVersion::V2), (2, ProtocolVersion::V1)] {
+            let (identity, address) = id(address);
+            let id = ClientActorId {
+                identity,
+                address,
+                name: ClientName(0),
+            };
+            let config = ClientConfig {
+                version,
+                ..ClientConfig::for_test()
+            };
+            let (client, rx) = ClientConnectionSender::dummy_with_channel(id, config);
+            subscriptions.add_subscription(Arc::new(client), plan.clone(), QueryId::new(1))?;
+            receivers.push(rx);
+        }
+
+        let mut tx = db.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        insert(&db, &mut tx, table_id, &product![1u8])?;
+        insert(&db, &mut tx, table_id, &product![2u8])?;
+        commit_and_eval(&db, &subscriptions, tx)?;
+        for rx in &mut receivers {
+            assert!(rx.try_recv().is_ok());
+        }
+
+        let mut tx = db.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+        db.truncate(&mut tx, table_id)?;
+        commit_and_eval(&db, &subscriptions, tx)?;
+
+        // A version 2 client is told the table was cleared, without the deleted rows,
+        // while a version 1 client, which can't be told so, is sent every deleted row.
+        for (rx, (cleared, num_deletes)) in receivers.iter_mut().zip([(true, 0), (false, 2)]) {
+            let Ok(SerializableMessage::TxUpdate(message)) = rx.try_recv() else {
+                panic!("expected a transaction update");
+            };
+            let FormatSwitch::Bsatn(update) = message.database_update.database_update else {
+                panic!("expected a binary update");
+            };
+            assert_eq!(update.tables.len(), 1);
+            assert_eq!(update.tables[0].cleared, cleared);
+            let query_update = update.tables[0].updates[0].clone().maybe_decompress();
+            assert_eq!(query_update.deletes.len(), num_deletes);
+            assert_eq!(query_update.inserts.len(), 0);
+        }
+
+        Ok(())
+    }
 }
@@ -82,7 +82,7 @@ pub fn compile_read_only_query(auth: &AuthCtx, tx: &Tx, input: &str) -> Result<P
     let plan = DeltaPlan::compile(&input, &tx)?;
     let hash = QueryHash::from_string(&input);
 
-    Ok(Plan::new(plan, hash))
+    Ok(Plan::new(plan, hash, input))
 }
 
 /// The kind of [`QueryExpr`] currently supported for incremental evaluation.
@@ -167,6 +167,7 @@ mod tests {
             table_id,
             table_name: table_name.into(),
             deletes: [].into(),
+            truncated: false,
             inserts: [row].into(),
         }
     }
@@ -176,6 +177,7 @@ mod tests {
             table_id,
             table_name: table_name.into(),
             deletes: [row].into(),
+            truncated: false,
             inserts: [].into(),
         }
     }
@@ -204,6 +206,7 @@ mod tests {
             table_id: schema.table_id,
             table_name: table_name.into(),
             deletes: [].into(),
+            truncated: false,
             inserts: [row.clone()].into(),
         };
 
@@ -371,6 +374,7 @@ mod tests {
                 table_id,
                 table_name: "test".into(),
                 deletes: deletes.into(),
+                truncated: false,
                 inserts: [].into(),
             }],
         };
@@ -453,6 +457,7 @@ mod tests {
             table_id: schema.table_id,
             table_name: "inventory".into(),
             deletes: [].into(),
+            truncated: false,
             inserts: [row.clone()].into(),
         };
 
@@ -567,6 +572,7 @@ mod tests {
             table_id: schema_1.table_id,
             table_name: "inventory".into(),
             deletes: [row_1].into(),
+            truncated: false,
             inserts: [].into(),
         };
 
@@ -574,6 +580,7 @@ mod tests {
             table_id: schema_2.table_id,
             table_name: "player".into(),
             deletes: [].into(),
+            truncated: false,
             inserts: [row_2].into(),
         };
 
@@ -731,6 +738,101 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_eval_incr_for_delta_join() -> ResultTest<()> {
+        run_eval_incr_test(delta_join_case)
+    }
+
+    // Insert and delete rows of rhs,
+    // which is joined with a filtered lhs,
+    // on columns at different positions in each table.
+    fn delta_join_case(db: &RelationalDB) -> ResultTest<()> {
+        let _ = create_lhs_table_for_eval_incr(db)?;
+        let rhs_id = create_rhs_table_for_eval_incr(db)?;
+        let query = db.with_read_only(Workload::ForTests, |tx| {
+            let auth = AuthCtx::for_testing();
+            let tx = SchemaViewer::new(tx, &auth);
+            let sql = "select rhs.* from lhs join rhs on lhs.id = rhs.id where lhs.x = 7";
+            DeltaPlan::compile(sql, &tx).unwrap()
+        });
+
+        // A single insert into rhs
+        let result = eval_incr(db, &query, vec![(rhs_id, product!(20, 2, 0), true)])?;
+        assert_eq!(result.tables.len(), 1);
+        assert_eq!(result.tables[0], insert_op(rhs_id, "rhs", product!(20, 2, 0)));
+
+        // A single delete from rhs
+        let result = eval_incr(db, &query, vec![(rhs_id, product!(12, 2, 4), false)])?;
+        assert_eq!(result.tables.len(), 1);
+        assert_eq!(result.tables[0], delete_op(rhs_id, "rhs", product!(12, 2, 4)));
+
+        // No updates to report for rows which don't match the filter
+        let result = eval_incr(db, &query, vec![(rhs_id, product!(21, 3, 0), true)])?;
+        assert!(result.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_eval_incr_for_top_n() -> ResultTest<()> {
+        run_eval_incr_test(top_n_case)
+    }
+
+    // Insert and delete rows in and out of the window of a top-N subscription.
+    fn top_n_case(db: &RelationalDB) -> ResultTest<()> {
+        const U64: AlgebraicType = AlgebraicType::U64;
+        let table_id = db.create_table_for_test("scores", &[("id", U64), ("score", U64)], &[])?;
+        db.with_auto_commit(Workload::ForTests, |tx| -> ResultTest<()> {
+            for (id, score) in [(1u64, 10u64), (2, 20), (3, 30)] {
+                insert(db, tx, table_id, &product!(id, score))?;
+            }
+            Ok(())
+        })?;
+        let query = db.with_read_only(Workload::ForTests, |tx| {
+            let auth = AuthCtx::for_testing();
+            let tx = SchemaViewer::new(tx, &auth);
+            let sql = "select * from scores order by score desc limit 2";
+            DeltaPlan::compile(sql, &tx).unwrap()
+        });
+        let mut window = query.top_n_window().unwrap();
+
+        let mut eval_top_n = |ops: Vec<(ProductValue, bool)>| -> ResultTest<_> {
+            let mut tx = db.begin_mut_tx(IsolationLevel::Serializable, Workload::ForTests);
+            for (row, insert) in ops {
+                if insert {
+                    insert_row(db, &mut tx, table_id, row)?;
+                } else {
+                    delete_row(db, &mut tx, table_id, row);
+                }
+            }
+            let (data, tx) = tx.commit_downgrade(Workload::ForTests);
+            let tx = DeltaTx::new(&tx, &data);
+            let evaluator = query.evaluator(&tx);
+            let updates = eval_delta(&tx, &evaluator).unwrap();
+            let inserts = updates.inserts.into_iter().map(RelValue::into_product_value).collect();
+            let deletes = updates.deletes.into_iter().map(RelValue::into_product_value).collect();
+            Ok(window.update(&tx, inserts, deletes).unwrap())
+        };
+
+        // A row below the window
+        let (deletes, inserts) = eval_top_n(vec![(product!(4u64, 5u64), true)])?;
+        assert!(deletes.is_empty() && inserts.is_empty());
+
+        // A row which pushes the last row out of the window
+        let (deletes, inserts) = eval_top_n(vec![(product!(5u64, 25u64), true)])?;
+        assert_eq!(deletes, vec![product!(2u64, 20u64)]);
+        assert_eq!(inserts, vec![product!(5u64, 25u64)]);
+
+        // Deleting a row from the full window brings in the next one
+        let (deletes, inserts) = eval_top_n(vec![(product!(3u64, 30u64), false)])?;
+        assert_eq!(deletes, vec![product!(3u64, 30u64)]);
+        assert_eq!(inserts, vec![product!(2u64, 20u64)]);
+
+        // Deleting a row outside the window changes nothing
+        let (deletes, inserts) = eval_top_n(vec![(product!(1u64, 10u64), false)])?;
+        assert!(deletes.is_empty() && inserts.is_empty());
+        Ok(())
+    }
+
     fn eval_incr(
         db: &RelationalDB,
         plan: &DeltaPlan,
@@ -772,6 +874,7 @@ mod tests {
                 table_name,
                 inserts,
                 deletes,
+                truncated: false,
             }]
         };
         Ok(DatabaseUpdate { tables })
@@ -960,6 +1063,7 @@ mod tests {
                 table_id: lhs_id,
                 table_name: "lhs".into(),
                 deletes: [lhs_old].into(),
+                truncated: false,
                 inserts: [lhs_new].into(),
             },
         );
@@ -619,9 +619,10 @@ pub(crate) fn get_all(relational_db: &RelationalDB, tx: &Tx, auth: &AuthCtx) ->
             t.table_type == StTableType::User && (auth.owner == auth.caller || t.table_access == StAccess::Public)
         })
         .map(|schema| {
-            let sql = format!("SELECT * FROM {}", schema.table_name);
+            // Quote the table name, as it may be a SQL keyword, e.g. `order`.
+            let sql = format!("SELECT * FROM \"{}\"", schema.table_name);
             let hash = QueryHash::from_string(&sql);
-            DeltaPlan::compile(&sql, &SchemaViewer::new(tx, auth)).map(|plan| Plan::new(plan, hash))
+            DeltaPlan::compile(&sql, &SchemaViewer::new(tx, auth)).map(|plan| Plan::new(plan, hash, sql))
         })
         .collect::<Result<_, _>>()?)
 }
@@ -0,0 +1,70 @@
+use crate::client::messages::TopicMessage;
+use crate::client::{ClientActorId, ClientConnectionSender};
+use parking_lot::Mutex;
+use spacetimedb_data_structures::map::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// The subscriptions of clients to topics,
+/// on which reducers broadcast messages that are never persisted.
+#[derive(Clone, Default)]
+pub struct TopicSubscriptions {
+    subscribers: Arc<Mutex<HashMap<Box<str>, Vec<Arc<ClientConnectionSender>>>>>,
+}
+
+impl fmt::Debug for TopicSubscriptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TopicSubscriptions")
+            .field("topics", &self.subscribers.lock().len())
+            .finish()
+    }
+}
+
+impl TopicSubscriptions {
+    /// Subscribe `client` to `topic`, if it isn't already.
+    pub fn subscribe(&self, client: Arc<ClientConnectionSender>, topic: Box<str>) {
+        let mut subscribers = self.subscribers.lock();
+        let clients = subscribers.entry(topic).or_default();
+        if !clients.iter().any(|c| c.id == client.id) {
+            clients.push(client);
+        }
+    }
+
+    /// Unsubscribe `client` from `topic`, if it is subscribed.
+    pub fn unsubscribe(&self, client: &ClientActorId, topic: &str) {
+        let mut subscribers = self.subscribers.lock();
+        if let Some(clients) = subscribers.get_mut(topic) {
+            clients.retain(|c| c.id != *client);
+            if clients.is_empty() {
+                subscribers.remove(topic);
+            }
+        }
+    }
+
+    /// Unsubscribe `client` from every topic.
+    pub fn remove_client(&self, client: &ClientActorId) {
+        self.subscribers.lock().retain(|_, clients| {
+            clients.retain(|c| c.id != *client);
+            !clients.is_empty()
+        });
+    }
+
+    /// Send each of `messages` to the clients subscribed to its topic.
+    ///
+    /// Messages on topics without subscribers are dropped.
+    pub fn broadcast(&self, messages: Vec<TopicMessage>) {
+        if messages.is_empty() {
+            return;
+        }
+        let subscribers = self.subscribers.lock();
+        for msg in messages {
+            let Some(clients) = subscribers.get(&*msg.topic) else {
+                continue;
+            };
+            for client in clients {
+                // A client which has disconnected is removed by `remove_client`.
+                let _ = client.send_message(msg.clone());
+            }
+        }
+    }
+}
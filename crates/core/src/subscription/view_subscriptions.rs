@@ -0,0 +1,597 @@
+use crate::client::messages::{SubscriptionError, SubscriptionMessage, SubscriptionResult, ViewUpdateMessage};
+use crate::client::{ClientActorId, ClientConnectionSender};
+use crate::db::relational_db::{RelationalDB, Tx};
+use crate::error::DBError;
+use crate::host::module_host::ViewCallResult;
+use crate::host::ArgsTuple;
+use parking_lot::Mutex;
+use spacetimedb_client_api_messages::websocket::{QueryId, Unsubscribe, ViewPage};
+use spacetimedb_data_structures::map::{HashMap, IntSet};
+use spacetimedb_lib::identity::RequestId;
+use spacetimedb_lib::ProductValue;
+use spacetimedb_primitives::{ColId, TableId};
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter;
+use std::mem;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Notify;
+
+/// A client's subscription to the result of a view.
+pub struct ViewSubscription {
+    pub client: Arc<ClientConnectionSender>,
+    pub request_id: RequestId,
+    pub query_id: QueryId,
+    /// The name of the view.
+    pub view: Box<str>,
+    /// The arguments the view is called with.
+    pub args: ArgsTuple,
+    /// Which rows of the view's result the client is sent.
+    pub page: ViewPage,
+    /// The rows of the view's result which the client was last sent.
+    rows: Vec<ProductValue>,
+    /// The tables the view read when it was last evaluated.
+    read_tables: IntSet<TableId>,
+}
+
+impl ViewSubscription {
+    pub fn new(
+        client: Arc<ClientConnectionSender>,
+        request_id: RequestId,
+        query_id: QueryId,
+        view: Box<str>,
+        args: ArgsTuple,
+        page: ViewPage,
+    ) -> Self {
+        Self {
+            client,
+            request_id,
+            query_id,
+            view,
+            args,
+            page,
+            rows: Vec::new(),
+            read_tables: IntSet::default(),
+        }
+    }
+
+    /// Evaluate the view with `eval`, returning the rows of its result which the client should be sent
+    /// and the tables the view read.
+    ///
+    /// `eval` is expected to select the window of the result described by [`Self::page`],
+    /// see [`paginate_in_tx`].
+    fn eval(
+        &self,
+        eval: impl FnOnce(&Self) -> anyhow::Result<ViewCallResult>,
+    ) -> anyhow::Result<(Vec<ProductValue>, IntSet<TableId>)> {
+        let ViewCallResult { rows, read_tables } = eval(self)?;
+        Ok((rows, read_tables))
+    }
+
+    /// Returns whether the view read any of the tables `modified` when it was last evaluated.
+    fn reads_any(&self, modified: &IntSet<TableId>) -> bool {
+        self.read_tables.iter().any(|table_id| modified.contains(table_id))
+    }
+
+    fn is_for(&self, client: &ClientActorId) -> bool {
+        self.client.id.identity == client.identity && self.client.id.address == client.address
+    }
+
+    fn send_update(&self, timer: Option<Instant>, inserts: Vec<ProductValue>, deletes: Vec<ProductValue>) -> bool {
+        let message = ViewUpdateMessage {
+            timer,
+            request_id: self.request_id,
+            query_id: self.query_id,
+            view: self.view.clone(),
+            inserts,
+            deletes,
+        };
+        self.client.send_message(message).is_ok()
+    }
+
+    fn send_error(&self, timer: Option<Instant>, error: anyhow::Error) {
+        send_error(&self.client, timer, self.request_id, self.query_id, error)
+    }
+}
+
+/// Send `client` an error for its view subscription with `query_id`.
+pub fn send_error(
+    client: &ClientConnectionSender,
+    timer: Option<Instant>,
+    request_id: RequestId,
+    query_id: QueryId,
+    error: anyhow::Error,
+) {
+    let message = SubscriptionMessage {
+        timer,
+        request_id: Some(request_id),
+        query_id: Some(query_id),
+        result: SubscriptionResult::Error(SubscriptionError {
+            table_id: None,
+            message: format!("{error:#}").into(),
+        }),
+    };
+    // Apparently we ignore errors sending messages.
+    let _ = client.send_message(message);
+}
+
+/// The view subscriptions of a database.
+///
+/// Views are evaluated by calling into the module, so rather than being incrementally maintained
+/// like SQL subscriptions, they are re-evaluated in full
+/// once a committed transaction modifies a table they read,
+/// and the difference from their previous result is sent to the client.
+///
+/// Committing a transaction only records the tables it modified, see [`Self::tables_modified`].
+/// The views are re-evaluated afterwards by a task of the host, see [`Self::modified_tables`],
+/// so that they don't delay the response to the transaction.
+///
+/// The lock is held while views are evaluated,
+/// so that concurrent refreshes can't send a client updates out of order.
+#[derive(Clone, Default)]
+pub struct ViewSubscriptions {
+    subscriptions: Arc<Mutex<Vec<ViewSubscription>>>,
+    /// The tables modified by the transactions committed since the views were last refreshed.
+    modified: Arc<Mutex<IntSet<TableId>>>,
+    /// Notified when `modified` becomes non-empty.
+    refresh_needed: Arc<Notify>,
+}
+
+impl fmt::Debug for ViewSubscriptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViewSubscriptions")
+            .field("len", &self.subscriptions.lock().len())
+            .finish()
+    }
+}
+
+impl ViewSubscriptions {
+    /// Add `sub`, replacing any subscription of the same client with the same query id,
+    /// and send the client the view's current result, as computed by `eval`.
+    ///
+    /// If `eval` fails, the client is sent an error and the subscription is not added.
+    pub fn add(
+        &self,
+        mut sub: ViewSubscription,
+        timer: Instant,
+        eval: impl FnOnce(&ViewSubscription) -> anyhow::Result<ViewCallResult>,
+    ) {
+        let mut subscriptions = self.subscriptions.lock();
+        subscriptions.retain(|s| !(s.is_for(&sub.client.id) && s.query_id == sub.query_id));
+        match sub.eval(eval) {
+            Ok((rows, read_tables)) => {
+                if sub.send_update(Some(timer), rows.clone(), Vec::new()) {
+                    sub.rows = rows;
+                    sub.read_tables = read_tables;
+                    subscriptions.push(sub);
+                }
+            }
+            Err(e) => sub.send_error(Some(timer), e),
+        }
+    }
+
+    /// Remove the subscription of `sender` with the query id of `request`,
+    /// sending it an update which deletes every row of the view's result.
+    ///
+    /// If there is no such subscription, the client is sent an error.
+    pub fn remove(&self, sender: &ClientConnectionSender, request: Unsubscribe, timer: Instant) {
+        let mut subscriptions = self.subscriptions.lock();
+        let Some(idx) = subscriptions
+            .iter()
+            .position(|s| s.is_for(&sender.id) && s.query_id == request.query_id)
+        else {
+            send_error(
+                sender,
+                Some(timer),
+                request.request_id,
+                request.query_id,
+                anyhow::anyhow!("no view subscription with query id {}", request.query_id.id),
+            );
+            return;
+        };
+        let sub = subscriptions.swap_remove(idx);
+        let _ = sender.send_message(ViewUpdateMessage {
+            timer: Some(timer),
+            request_id: request.request_id,
+            query_id: sub.query_id,
+            view: sub.view,
+            inserts: Vec::new(),
+            deletes: sub.rows,
+        });
+    }
+
+    /// Remove all subscriptions of `client`.
+    pub fn remove_client(&self, client: &ClientActorId) {
+        self.subscriptions.lock().retain(|s| !s.is_for(client));
+    }
+
+    /// Re-evaluate with `eval` every subscribed view which read any of the tables `modified`,
+    /// sending each client the changes to the results of its views.
+    ///
+    /// Subscriptions whose view fails to evaluate are removed, after sending the client an error.
+    /// Subscriptions of clients which have disconnected are removed.
+    pub fn refresh(
+        &self,
+        modified: &IntSet<TableId>,
+        mut eval: impl FnMut(&ViewSubscription) -> anyhow::Result<ViewCallResult>,
+    ) {
+        self.subscriptions.lock().retain_mut(|sub| {
+            if !sub.reads_any(modified) {
+                return true;
+            }
+            match sub.eval(&mut eval) {
+                Ok((rows, read_tables)) => {
+                    let (deletes, inserts) = diff_rows(&sub.rows, &rows);
+                    if !(deletes.is_empty() && inserts.is_empty()) && !sub.send_update(None, inserts, deletes) {
+                        return false;
+                    }
+                    sub.rows = rows;
+                    sub.read_tables = read_tables;
+                    true
+                }
+                Err(e) => {
+                    sub.send_error(None, e);
+                    false
+                }
+            }
+        })
+    }
+
+    /// Record that a committed transaction modified the tables `table_ids`,
+    /// so that the views which read them are refreshed.
+    pub fn tables_modified(&self, table_ids: impl IntoIterator<Item = TableId>) {
+        if self.is_empty() {
+            return;
+        }
+        let mut modified = self.modified.lock();
+        let was_empty = modified.is_empty();
+        modified.extend(table_ids);
+        if was_empty && !modified.is_empty() {
+            self.refresh_needed.notify_one();
+        }
+    }
+
+    /// Wait until a committed transaction modifies a table,
+    /// returning the tables modified since this was last called.
+    pub async fn modified_tables(&self) -> IntSet<TableId> {
+        loop {
+            self.refresh_needed.notified().await;
+            let modified = mem::take(&mut *self.modified.lock());
+            if !modified.is_empty() {
+                return modified;
+            }
+        }
+    }
+
+    /// Returns whether there are no view subscriptions.
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.lock().is_empty()
+    }
+}
+
+/// Select the window described by `page` of `rows`, the result of a view which read the tables `read_tables`.
+///
+/// When the view read a single table with a btree index whose columns start with those `page` is ordered by,
+/// all ascending, and every row of the result is a row of that table,
+/// the rows are ordered by scanning that index rather than by sorting them.
+/// Rows which compare equal are then kept in the order of the index.
+pub fn paginate_in_tx(
+    stdb: &RelationalDB,
+    tx: &Tx,
+    rows: Vec<ProductValue>,
+    read_tables: &IntSet<TableId>,
+    page: &ViewPage,
+) -> anyhow::Result<Vec<ProductValue>> {
+    match order_by_index(stdb, tx, &rows, read_tables, page)? {
+        Some(rows) => Ok(window(rows, page)),
+        None => paginate(rows, page),
+    }
+}
+
+/// Returns `rows` in the order of an index of the single table in `read_tables` matching `page`,
+/// or `None` if there is no such index or `rows` aren't all rows of the table.
+fn order_by_index(
+    stdb: &RelationalDB,
+    tx: &Tx,
+    rows: &[ProductValue],
+    read_tables: &IntSet<TableId>,
+    page: &ViewPage,
+) -> Result<Option<Vec<ProductValue>>, DBError> {
+    if page.order_by.is_empty() || page.order_by.iter().any(|col| col.descending) {
+        return Ok(None);
+    }
+    let mut tables = read_tables.iter();
+    let (Some(&table_id), None) = (tables.next(), tables.next()) else {
+        return Ok(None);
+    };
+    let schema = stdb.schema_for_table(tx, table_id)?;
+    let Some(index) = schema.indexes.iter().find(|index| {
+        let mut cols = index.index_algorithm.columns().iter();
+        page.order_by.iter().all(|col| cols.next() == Some(ColId(col.column)))
+    }) else {
+        return Ok(None);
+    };
+
+    let mut unmatched = HashMap::<&ProductValue, usize>::default();
+    for row in rows {
+        *unmatched.entry(row).or_default() += 1;
+    }
+    let mut ordered = Vec::with_capacity(rows.len());
+    for row in stdb.iter_by_col_range(tx, table_id, index.index_algorithm.columns().clone(), ..)? {
+        if ordered.len() == rows.len() {
+            break;
+        }
+        let row = row.to_product_value();
+        // A view may return the same row of the table more than once.
+        if let Some(n) = unmatched.remove(&row) {
+            ordered.extend(iter::repeat(row).take(n));
+        }
+    }
+    Ok((ordered.len() == rows.len()).then_some(ordered))
+}
+
+/// Sort `rows` and select the window of them described by `page`.
+fn paginate(mut rows: Vec<ProductValue>, page: &ViewPage) -> anyhow::Result<Vec<ProductValue>> {
+    if !page.order_by.is_empty() {
+        let width = rows.first().map_or(0, |row| row.elements.len());
+        if let Some(col) = page.order_by.iter().find(|col| usize::from(col.column) >= width) {
+            // An empty result can't be sorted by a missing column, so there's nothing to report.
+            anyhow::ensure!(
+                rows.is_empty(),
+                "cannot order by column {} of a row with {width} columns",
+                col.column
+            );
+        }
+        // A stable sort, so that rows which compare equal keep the order the view returned them in.
+        rows.sort_by(|a, b| {
+            page.order_by
+                .iter()
+                .map(|col| {
+                    let col_idx = usize::from(col.column);
+                    let ord = a.elements[col_idx].cmp(&b.elements[col_idx]);
+                    if col.descending {
+                        ord.reverse()
+                    } else {
+                        ord
+                    }
+                })
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+    Ok(window(rows, page))
+}
+
+/// Select the window of the sorted `rows` described by `page`.
+fn window(mut rows: Vec<ProductValue>, page: &ViewPage) -> Vec<ProductValue> {
+    let offset = (page.offset as usize).min(rows.len());
+    rows.drain(..offset);
+    if let Some(limit) = page.limit {
+        rows.truncate(limit as usize);
+    }
+    rows
+}
+
+/// Returns the rows to delete from `old` and the rows to insert into it to obtain `new`.
+///
+/// Rows are compared as a multiset, so duplicate rows are inserted and deleted individually.
+fn diff_rows(old: &[ProductValue], new: &[ProductValue]) -> (Vec<ProductValue>, Vec<ProductValue>) {
+    let mut unmatched = HashMap::<&ProductValue, usize>::default();
+    for row in new {
+        *unmatched.entry(row).or_default() += 1;
+    }
+    let mut take = |row: &ProductValue| match unmatched.get_mut(row) {
+        Some(n) if *n > 0 => {
+            *n -= 1;
+            true
+        }
+        _ => false,
+    };
+    let deletes = old.iter().filter(|row| !take(row)).cloned().collect();
+    // What's left in `unmatched` are the rows of `new` which aren't in `old`.
+    let inserts = new.iter().filter(|row| take(row)).cloned().collect();
+    (deletes, inserts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        diff_rows, order_by_index, paginate, paginate_in_tx, ViewCallResult, ViewSubscription, ViewSubscriptions,
+    };
+    use crate::client::messages::SerializableMessage;
+    use crate::client::{ClientActorId, ClientConfig, ClientConnectionSender, ClientName};
+    use crate::db::relational_db::tests_utils::{insert, TestDB};
+    use crate::execution_context::Workload;
+    use crate::host::ArgsTuple;
+    use spacetimedb_client_api_messages::websocket::{QueryId, ViewOrderBy, ViewPage};
+    use spacetimedb_lib::error::ResultTest;
+    use spacetimedb_lib::{Address, AlgebraicType, Identity};
+    use spacetimedb_primitives::TableId;
+    use spacetimedb_sats::product;
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tokio::sync::mpsc;
+
+    /// Returns a subscription of a client of its own, and the receiving end of the client's messages.
+    fn subscription(n: u32) -> (ViewSubscription, mpsc::Receiver<SerializableMessage>) {
+        let id = ClientActorId {
+            identity: Identity::ZERO,
+            address: Address::from_u128(n.into()),
+            name: ClientName(0),
+        };
+        let (client, rx) = ClientConnectionSender::dummy_with_channel(id, ClientConfig::for_test());
+        let sub = ViewSubscription::new(
+            Arc::new(client),
+            0,
+            QueryId::new(n),
+            "view".into(),
+            ArgsTuple::default(),
+            ViewPage::default(),
+        );
+        (sub, rx)
+    }
+
+    /// Returns the result of a view which read only the table `table_id`.
+    fn result(table_id: u32, value: u32) -> anyhow::Result<ViewCallResult> {
+        Ok(ViewCallResult {
+            rows: vec![product![value]],
+            read_tables: [TableId(table_id)].into_iter().collect(),
+        })
+    }
+
+    #[test]
+    fn refresh_only_views_which_read_modified_tables() {
+        let views = ViewSubscriptions::default();
+        let (sub_1, mut rx_1) = subscription(1);
+        let (sub_2, mut rx_2) = subscription(2);
+        views.add(sub_1, Instant::now(), |_| result(1, 0));
+        views.add(sub_2, Instant::now(), |_| result(2, 0));
+        assert!(rx_1.try_recv().is_ok());
+        assert!(rx_2.try_recv().is_ok());
+
+        let mut evaluated = vec![];
+        let modified = [TableId(1)].into_iter().collect();
+        views.refresh(&modified, |sub| {
+            evaluated.push(sub.query_id.id);
+            result(1, 1)
+        });
+        assert_eq!(evaluated, [1]);
+        assert!(rx_1.try_recv().is_ok());
+        assert!(rx_2.try_recv().is_err());
+
+        // The view now reads another table, so it is refreshed when that table is modified instead.
+        views.refresh(&modified, |_| result(3, 1));
+        let mut evaluated = vec![];
+        views.refresh(&modified, |sub| {
+            evaluated.push(sub.query_id.id);
+            result(3, 1)
+        });
+        assert!(evaluated.is_empty());
+    }
+
+    #[tokio::test]
+    async fn modified_tables_accumulate_until_taken() {
+        let views = ViewSubscriptions::default();
+        // Nothing is recorded while no one is subscribed.
+        views.tables_modified([TableId(1)]);
+        assert!(views.modified.lock().is_empty());
+
+        let (sub, _rx) = subscription(1);
+        views.add(sub, Instant::now(), |_| result(1, 0));
+        views.tables_modified([TableId(2)]);
+        views.tables_modified([TableId(2), TableId(3)]);
+        let modified = views.modified_tables().await;
+        assert_eq!(modified, [TableId(2), TableId(3)].into_iter().collect());
+        assert!(views.modified.lock().is_empty());
+    }
+
+    #[test]
+    fn paginate_sorts_then_windows() {
+        let rows = vec![
+            product![1u32, "c"],
+            product![2u32, "a"],
+            product![2u32, "b"],
+            product![3u32, "d"],
+        ];
+        let page = ViewPage {
+            order_by: [
+                ViewOrderBy {
+                    column: 0,
+                    descending: true,
+                },
+                ViewOrderBy {
+                    column: 1,
+                    descending: false,
+                },
+            ]
+            .into(),
+            offset: 1,
+            limit: Some(2),
+        };
+        let rows = paginate(rows, &page).unwrap();
+        assert_eq!(rows, [product![2u32, "a"], product![2u32, "b"]]);
+    }
+
+    #[test]
+    fn paginate_rejects_missing_column() {
+        let page = ViewPage {
+            order_by: [ViewOrderBy {
+                column: 1,
+                descending: false,
+            }]
+            .into(),
+            ..ViewPage::default()
+        };
+        assert!(paginate(vec![product![1u32]], &page).is_err());
+        assert!(paginate(vec![], &page).unwrap().is_empty());
+    }
+
+    /// Returns a page of at most `limit` rows after `offset`, ordered by the column `column`.
+    fn page_by(column: u16, descending: bool, offset: u32, limit: Option<u32>) -> ViewPage {
+        ViewPage {
+            order_by: [ViewOrderBy { column, descending }].into(),
+            offset,
+            limit,
+        }
+    }
+
+    #[test]
+    fn paginate_in_tx_orders_by_index() -> ResultTest<()> {
+        let db = TestDB::durable()?;
+        let schema = &[("id", AlgebraicType::U32), ("name", AlgebraicType::String)];
+        let table_id = db.create_table_for_test("T", schema, &[0.into()])?;
+        db.with_auto_commit(Workload::ForTests, |tx| -> ResultTest<()> {
+            for row in [product![3u32, "c"], product![1u32, "a"], product![2u32, "b"]] {
+                insert(&db, tx, table_id, &row)?;
+            }
+            Ok(())
+        })?;
+        let read_tables = [table_id].into_iter().collect();
+
+        db.with_read_only(Workload::ForTests, |tx| -> ResultTest<()> {
+            let page = page_by(0, false, 1, Some(2));
+            // Every row is a row of the table, so the rows are ordered by its index on `id`,
+            // keeping the duplicates the view returned.
+            let rows = vec![product![3u32, "c"], product![1u32, "a"], product![3u32, "c"]];
+            let ordered = order_by_index(&db, tx, &rows, &read_tables, &page)?;
+            assert_eq!(
+                ordered.as_deref(),
+                Some(&[product![1u32, "a"], product![3u32, "c"], product![3u32, "c"]][..])
+            );
+            let window = paginate_in_tx(&db, tx, rows, &read_tables, &page)?;
+            assert_eq!(window, [product![3u32, "c"], product![3u32, "c"]]);
+
+            // A row which isn't in the table can't be ordered by the index, so the rows are sorted.
+            let rows = vec![product![4u32, "d"], product![2u32, "b"], product![1u32, "a"]];
+            assert_eq!(order_by_index(&db, tx, &rows, &read_tables, &page)?, None);
+            let window = paginate_in_tx(&db, tx, rows, &read_tables, &page)?;
+            assert_eq!(window, [product![2u32, "b"], product![4u32, "d"]]);
+
+            // There's no index on `name`, nor a descending scan of the index on `id`.
+            let rows = vec![product![1u32, "a"]];
+            for page in [page_by(1, false, 0, None), page_by(0, true, 0, None)] {
+                assert_eq!(order_by_index(&db, tx, &rows, &read_tables, &page)?, None);
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn diff_rows_counts_duplicates() {
+        let old = [product![1u32], product![2u32], product![2u32], product![3u32]];
+        let new = [product![2u32], product![3u32], product![4u32], product![4u32]];
+        let (deletes, inserts) = diff_rows(&old, &new);
+        assert_eq!(deletes, [product![1u32], product![2u32]]);
+        assert_eq!(inserts, [product![4u32], product![4u32]]);
+    }
+
+    #[test]
+    fn diff_rows_unchanged() {
+        let rows = [product![1u32, "a"], product![2u32, "b"]];
+        let (deletes, inserts) = diff_rows(&rows, &rows);
+        assert!(deletes.is_empty());
+        assert!(inserts.is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 //! like, a semaphore but with values. or something
 
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::future::Future;
 use std::mem::ManuallyDrop;
 use std::ops::{Deref, DerefMut};
@@ -9,15 +9,15 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use parking_lot::Mutex;
+use spacetimedb_lib::db::raw_def::v9::ReducerPriority;
 use spacetimedb_lib::Identity;
-use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::sync::oneshot;
 
 use crate::worker_metrics::WORKER_METRICS;
 
 use super::notify_once::{NotifiedOnce, NotifyOnce};
 
 pub struct LendingPool<T> {
-    sem: Arc<Semaphore>,
     inner: Arc<LendingPoolInner<T>>,
 }
 
@@ -30,7 +30,6 @@ impl<T> Default for LendingPool<T> {
 impl<T> Clone for LendingPool<T> {
     fn clone(&self) -> Self {
         Self {
-            sem: self.sem.clone(),
             inner: self.inner.clone(),
         }
     }
@@ -44,6 +43,42 @@ struct LendingPoolInner<T> {
 struct PoolVec<T> {
     total_count: usize,
     deque: Option<VecDeque<T>>,
+    /// Requests waiting for a resource, served in order of priority and then arrival.
+    waiters: BTreeMap<(ReducerPriority, u64), oneshot::Sender<LentResource<T>>>,
+    next_waiter: u64,
+}
+
+impl<T> LendingPoolInner<T> {
+    /// Hand `resource` to the most urgent waiter,
+    /// or put it back in the pool if no one is waiting.
+    fn lend_or_return(self: &Arc<Self>, vec: &mut PoolVec<T>, mut resource: T) {
+        let PoolVec {
+            total_count,
+            deque,
+            waiters,
+            ..
+        } = vec;
+        let Some(deque) = deque else {
+            drop(resource);
+            *total_count -= 1;
+            if *total_count == 0 {
+                self.closed_notify.notify();
+            }
+            return;
+        };
+        // Waiters whose request was cancelled are skipped.
+        while let Some((_, waiter)) = waiters.pop_first() {
+            let lent = LentResource {
+                resource: ManuallyDrop::new(resource),
+                pool_inner: self.clone(),
+            };
+            match waiter.send(lent) {
+                Ok(()) => return,
+                Err(lent) => resource = lent.into_resource(),
+            }
+        }
+        deque.push_back(resource);
+    }
 }
 
 #[derive(Debug)]
@@ -83,26 +118,39 @@ impl<T> LendingPool<T> {
         Self::from_iter(std::iter::empty())
     }
 
-    pub fn request_with_context(&self, db: Identity) -> impl Future<Output = Result<LentResource<T>, PoolClosed>> {
-        let acq = self.sem.clone().acquire_owned();
+    /// Request a resource from the pool.
+    ///
+    /// If none is available, the request waits behind all requests of the same or a higher `priority`.
+    pub fn request_with_context(
+        &self,
+        db: Identity,
+        priority: ReducerPriority,
+    ) -> impl Future<Output = Result<LentResource<T>, PoolClosed>> {
         let pool_inner = self.inner.clone();
 
         async move {
             let _guard = QueueMetric::inc(db);
-            let permit = acq.await.map_err(|_| PoolClosed)?;
-            let resource = pool_inner
-                .vec
-                .lock()
-                .deque
-                .as_mut()
-                .ok_or(PoolClosed)?
-                .pop_front()
-                .ok_or(PoolClosed)?;
-            Ok(LentResource {
-                resource: ManuallyDrop::new(resource),
-                permit: ManuallyDrop::new(permit),
-                pool_inner,
-            })
+            let rx = {
+                let mut vec = pool_inner.vec.lock();
+                let PoolVec {
+                    deque,
+                    waiters,
+                    next_waiter,
+                    ..
+                } = &mut *vec;
+                // Resources are only ever in the deque when there are no waiters.
+                if let Some(resource) = deque.as_mut().ok_or(PoolClosed)?.pop_front() {
+                    return Ok(LentResource {
+                        resource: ManuallyDrop::new(resource),
+                        pool_inner: pool_inner.clone(),
+                    });
+                }
+                let (tx, rx) = oneshot::channel();
+                waiters.insert((priority, *next_waiter), tx);
+                *next_waiter += 1;
+                rx
+            };
+            rx.await.map_err(|_| PoolClosed)
         }
     }
 
@@ -111,13 +159,14 @@ impl<T> LendingPool<T> {
     }
 
     pub fn add_multiple<I: IntoIterator<Item = T>>(&self, resources: I) -> Result<(), PoolClosed> {
-        let resources = resources.into_iter();
-        let mut inner = self.inner.vec.lock();
-        let deque = inner.deque.as_mut().ok_or(PoolClosed)?;
-        let mut num_new = 0;
-        deque.extend(resources.inspect(|_| num_new += 1));
-        inner.total_count += num_new;
-        self.sem.add_permits(num_new);
+        let mut vec = self.inner.vec.lock();
+        if vec.deque.is_none() {
+            return Err(PoolClosed);
+        }
+        for resource in resources {
+            vec.total_count += 1;
+            self.inner.lend_or_return(&mut vec, resource);
+        }
         Ok(())
     }
 
@@ -126,12 +175,13 @@ impl<T> LendingPool<T> {
     }
 
     pub fn num_available(&self) -> usize {
-        self.sem.available_permits()
+        self.inner.vec.lock().deque.as_ref().map_or(0, |deque| deque.len())
     }
 
     pub fn close(&self) -> Closed<'_> {
         let mut vec = self.inner.vec.lock();
-        self.sem.close();
+        // Dropping the waiters' senders fails their requests with `PoolClosed`.
+        vec.waiters.clear();
         if let Some(deque) = vec.deque.take() {
             vec.total_count -= deque.len();
         }
@@ -152,12 +202,13 @@ impl<T> FromIterator<T> for LendingPool<T> {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
         let deque = VecDeque::from_iter(iter);
         Self {
-            sem: Arc::new(Semaphore::new(deque.len())),
             inner: Arc::new(LendingPoolInner {
                 closed_notify: NotifyOnce::new(),
                 vec: Mutex::new(PoolVec {
                     total_count: deque.len(),
                     deque: Some(deque),
+                    waiters: BTreeMap::new(),
+                    next_waiter: 0,
                 }),
             }),
         }
@@ -181,10 +232,21 @@ impl Future for Closed<'_> {
 
 pub struct LentResource<T> {
     resource: ManuallyDrop<T>,
-    permit: ManuallyDrop<OwnedSemaphorePermit>,
     pool_inner: Arc<LendingPoolInner<T>>,
 }
 
+impl<T> LentResource<T> {
+    /// Take the resource out without returning it to the pool.
+    fn into_resource(self) -> T {
+        let mut this = ManuallyDrop::new(self);
+        // SAFETY: `this` is never dropped, so neither field is touched again.
+        unsafe {
+            std::ptr::drop_in_place(&mut this.pool_inner);
+            ManuallyDrop::take(&mut this.resource)
+        }
+    }
+}
+
 impl<T> Deref for LentResource<T> {
     type Target = T;
     fn deref(&self) -> &T {
@@ -201,20 +263,31 @@ impl<T> DerefMut for LentResource<T> {
 impl<T> Drop for LentResource<T> {
     fn drop(&mut self) {
         let resource = unsafe { ManuallyDrop::take(&mut self.resource) };
-        let permit = unsafe { ManuallyDrop::take(&mut self.permit) };
-        {
-            let mut vec = self.pool_inner.vec.lock();
-            if let Some(deque) = &mut vec.deque {
-                deque.push_back(resource);
-                drop(permit);
-            } else {
-                drop(resource);
-                permit.forget();
-                vec.total_count -= 1;
-                if vec.total_count == 0 {
-                    self.pool_inner.closed_notify.notify();
-                }
-            }
-        }
+        let mut vec = self.pool_inner.vec.lock();
+        self.pool_inner.lend_or_return(&mut vec, resource);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn waiters_are_served_by_priority() {
+        let pool = LendingPool::from_iter([()]);
+        let db = Identity::ZERO;
+        let held = pool.request_with_context(db, ReducerPriority::Normal).await.unwrap();
+
+        let low = tokio::spawn(pool.request_with_context(db, ReducerPriority::Low));
+        tokio::task::yield_now().await;
+        let high = tokio::spawn(pool.request_with_context(db, ReducerPriority::High));
+        tokio::task::yield_now().await;
+
+        drop(held);
+        let high = high.await.unwrap().unwrap();
+        assert!(!low.is_finished());
+        drop(high);
+        low.await.unwrap().unwrap();
+        assert_eq!(pool.num_available(), 1);
     }
 }
@@ -1,6 +1,6 @@
 //! The [DbProgram] that execute arbitrary queries & code against the database.
 
-use crate::db::datastore::locking_tx_datastore::state_view::IterByColRangeMutTx;
+use crate::db::datastore::locking_tx_datastore::state_view::{IterByColRangeMutTx, StateView as _};
 use crate::db::datastore::locking_tx_datastore::tx::TxId;
 use crate::db::datastore::locking_tx_datastore::IterByColRangeTx;
 use crate::db::datastore::system_tables::{st_var_schema, StVarName, StVarRow, StVarTable};
@@ -12,7 +12,7 @@ use core::ops::{Bound, RangeBounds};
 use itertools::Itertools;
 use spacetimedb_data_structures::map::IntMap;
 use spacetimedb_lib::identity::AuthCtx;
-use spacetimedb_lib::relation::{ColExpr, DbTable};
+use spacetimedb_lib::relation::{ColExpr, DbTable, Header};
 use spacetimedb_primitives::*;
 use spacetimedb_sats::{AlgebraicValue, ProductValue};
 use spacetimedb_table::static_assert_size;
@@ -47,6 +47,13 @@ impl<'a> TxMode<'a> {
             Self::Tx(tx) => &tx.ctx,
         }
     }
+
+    fn table_row_count(&self, table_id: TableId) -> Option<u64> {
+        match self {
+            Self::MutTx(tx) => tx.table_row_count(table_id),
+            Self::Tx(tx) => tx.table_row_count(table_id),
+        }
+    }
 }
 
 impl<'a> From<&'a mut MutTx> for TxMode<'a> {
@@ -494,6 +501,9 @@ impl<'db, 'tx> DbProgram<'db, 'tx> {
                 &self.auth,
             )?;
         }
+        self.db
+            .index_advisor()
+            .record_query(query, &|table_id| self.tx.table_row_count(table_id).unwrap_or_default());
 
         let table_access = query.source.table_access();
         tracing::trace!(table = query.source.table_name());
@@ -507,21 +517,81 @@ impl<'db, 'tx> DbProgram<'db, 'tx> {
         Ok(Code::Table(MemTable::new(head, table_access, rows)))
     }
 
+    /// Like evaluating `query`, which must not read any in-memory tables,
+    /// but passes each row to `on_row` as it is read instead of collecting them into a [`MemTable`].
+    ///
+    /// Stops early once `on_row` returns `Ok(false)`, and returns whether all rows were passed.
+    pub fn stream_query(
+        &mut self,
+        query: &QueryExpr,
+        mut on_row: impl FnMut(ProductValue) -> Result<bool, DBError>,
+    ) -> Result<bool, DBError> {
+        query
+            .check_auth(self.auth.owner, self.auth.caller)
+            .map_err(|err| DBError::VmUser(ErrorVm::from(err).into()))?;
+        if let TxMode::Tx(tx) = self.tx {
+            check_row_limit(
+                query,
+                self.db,
+                tx,
+                |expr, tx| estimation::num_rows(tx, expr),
+                &self.auth,
+            )?;
+        }
+        self.db
+            .index_advisor()
+            .record_query(query, &|table_id| self.tx.table_row_count(table_id).unwrap_or_default());
+
+        let mut rows = build_query(self.db, self.tx, query, &mut NoInMemUsed);
+        while let Some(row) = rows.next() {
+            if !on_row(row.into_product_value())? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
     // TODO(centril): investigate taking bsatn as input instead.
-    fn _execute_insert(&mut self, table: &DbTable, inserts: Vec<ProductValue>) -> Result<Code, ErrorVm> {
+    fn _execute_insert(
+        &mut self,
+        table: &DbTable,
+        rows: Vec<ProductValue>,
+        returning: Option<Arc<Header>>,
+    ) -> Result<Code, ErrorVm> {
         let tx = self.tx.unwrap_mut();
         let mut scratch = Vec::new();
-        for row in &inserts {
+        let mut inserts = Vec::with_capacity(rows.len());
+        for row in &rows {
             row.encode(&mut scratch);
-            self.db.insert(tx, table.table_id, &scratch)?;
+            // Report the row as stored, i.e., with any `auto_inc` values generated.
+            let (_, row_ref) = self.db.insert(tx, table.table_id, &scratch)?;
+            inserts.push(row_ref.to_product_value());
             scratch.clear();
         }
-        Ok(Code::Pass(Some(Update {
+
+        let returned = returning.map(|head| {
+            let rows = inserts
+                .iter()
+                .map(|row| {
+                    head.fields
+                        .iter()
+                        .map(|f| row.elements[f.field.col.idx()].clone())
+                        .collect()
+                })
+                .collect::<Vec<_>>();
+            MemTable::new(head, table.table_access, rows)
+        });
+        let update = Code::Pass(Some(Update {
             table_id: table.table_id,
             table_name: table.head.table_name.clone(),
             inserts,
             deletes: Vec::default(),
-        })))
+        }));
+
+        Ok(match returned {
+            Some(returned) => Code::Block(vec![update, Code::Table(returned)]),
+            None => update,
+        })
     }
 
     fn _execute_update<const N: usize>(
@@ -571,7 +641,7 @@ impl<'db, 'tx> DbProgram<'db, 'tx> {
             })
             .collect_vec();
 
-        let result = self._execute_insert(table, insert_rows);
+        let result = self._execute_insert(table, insert_rows, None);
         let Ok(Code::Pass(Some(insert))) = result else {
             return result;
         };
@@ -628,7 +698,7 @@ impl ProgramVm for DbProgram<'_, '_> {
 
         match query {
             CrudExpr::Query(query) => self._eval_query(&query, sources),
-            CrudExpr::Insert { table, rows } => self._execute_insert(&table, rows),
+            CrudExpr::Insert { table, rows, returning } => self._execute_insert(&table, rows, returning),
             CrudExpr::Update { delete, assignments } => self._execute_update(&delete, assignments, sources),
             CrudExpr::Delete { query } => self._delete_query(&query, sources),
             CrudExpr::SetVar { name, literal } => self._set_var(name, literal),
@@ -90,6 +90,11 @@ metrics_group!(
         #[labels(database_identity: Identity)]
         pub subscription_queries: IntGaugeVec,
 
+        #[name = spacetime_costly_subscriptions_total]
+        #[help = "The number of subscriptions rejected or flagged for exceeding a cost threshold"]
+        #[labels(database_identity: Identity, action: str)]
+        pub costly_subscriptions: IntCounterVec,
+
         #[name = spacetime_request_round_trip_time]
         #[help = "The total time it takes for request to complete"]
         #[labels(txn_type: WorkloadType, database_identity: Identity, reducer_symbol: str)]
@@ -321,6 +321,8 @@ tuple_combine_errors!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
 tuple_combine_errors!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
 tuple_combine_errors!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
 tuple_combine_errors!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+tuple_combine_errors!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+tuple_combine_errors!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
 
 /// A trait for collecting errors from an iterator of results,
 /// returning all errors if anything failed.
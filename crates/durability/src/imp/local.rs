@@ -15,37 +15,86 @@ use std::{
 use anyhow::Context as _;
 use itertools::Itertools as _;
 use log::{info, trace, warn};
-use spacetimedb_commitlog::{error, payload::Txdata, Commit, Commitlog, Decoder, Encode, Transaction};
+use spacetimedb_commitlog::{
+    archive::Archive, error, payload::Txdata, Commit, Commitlog, Decoder, Encode, Transaction,
+};
 use spacetimedb_paths::server::CommitLogDir;
 use tokio::{
-    sync::mpsc,
+    sync::{mpsc, Notify},
     task::{spawn_blocking, AbortHandle, JoinHandle},
-    time::{interval, MissedTickBehavior},
+    time::{interval_at, sleep, Instant, MissedTickBehavior},
 };
 use tracing::instrument;
 
 use crate::{Durability, History, TxOffset};
 
 /// [`Local`] configuration.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct Options {
-    /// Periodically flush and sync the log this often.
+    /// The longest a transaction waits after being appended before the log
+    /// is flushed and synced.
+    ///
+    /// All transactions appended within this window are made durable by a
+    /// single fsync ("group commit"), so a longer window trades latency for
+    /// fewer fsyncs under high transaction rates.
     ///
     /// Default: 500ms
-    pub sync_interval: Duration,
+    pub group_commit_window: Duration,
     /// [`Commitlog`] configuration.
     pub commitlog: spacetimedb_commitlog::Options,
+    /// Where to move sealed segments of the [`Commitlog`], if anywhere.
+    ///
+    /// Default: `None`, all segments are kept on local storage
+    pub archive: Option<ArchiveOptions>,
 }
 
 impl Default for Options {
     fn default() -> Self {
         Self {
-            sync_interval: Duration::from_millis(500),
+            group_commit_window: Duration::from_millis(500),
             commitlog: Default::default(),
+            archive: None,
+        }
+    }
+}
+
+impl Options {
+    /// Options suitable for a commitlog stored on a network file system, such as NFS.
+    ///
+    /// Network file systems only guarantee that writes are persisted,
+    /// and visible to other hosts, once the file has been synced.
+    /// The offset index of each segment is thus only updated for synced data,
+    /// so that it never points past the end of the segment after a crash,
+    /// or after the database fails over to another host.
+    pub fn network_fs() -> Self {
+        Self {
+            commitlog: spacetimedb_commitlog::Options {
+                offset_index_require_segment_fsync: true,
+                ..Default::default()
+            },
+            ..Default::default()
         }
     }
 }
 
+/// Configuration for moving sealed segments of the [`Commitlog`] to an [`Archive`].
+///
+/// Segments are uploaded by a background task, after which all but the newest
+/// [`Self::keep_local_segments`] are removed from local storage. Archived
+/// segments are downloaded again when they are needed to replay the history.
+#[derive(Clone, Debug)]
+pub struct ArchiveOptions {
+    /// The archive to upload sealed segments to.
+    pub archive: Arc<dyn Archive>,
+    /// How often to check for sealed segments which are not archived yet.
+    pub interval: Duration,
+    /// How many of the newest sealed segments to keep on local storage after
+    /// they have been archived.
+    ///
+    /// The segment currently being written to is always kept.
+    pub keep_local_segments: usize,
+}
+
 /// [`Durability`] implementation backed by a [`Commitlog`] on local storage.
 ///
 /// The commitlog is constrained to store the canonical [`Txdata`] payload,
@@ -86,6 +135,10 @@ pub struct Local<T> {
     /// Handle to the [`PersisterTask`], allowing to drain the `queue` when
     /// explicitly dropped via [`Self::close`].
     persister_task: JoinHandle<()>,
+    /// Handle to the [`ArchiverTask`], if archival is configured.
+    ///
+    /// The task is aborted when this value is dropped.
+    _archiver_task: Option<AbortOnDrop>,
 }
 
 impl<T: Encode + Send + Sync + 'static> Local<T> {
@@ -97,9 +150,13 @@ impl<T: Encode + Send + Sync + 'static> Local<T> {
     pub fn open(root: CommitLogDir, rt: tokio::runtime::Handle, opts: Options) -> io::Result<Self> {
         info!("open local durability");
 
-        let clog = Arc::new(Commitlog::open(root, opts.commitlog)?);
+        let clog = Arc::new(match &opts.archive {
+            Some(archive) => Commitlog::open_archived(root, opts.commitlog, archive.archive.clone())?,
+            None => Commitlog::open(root, opts.commitlog)?,
+        });
         let (queue, rx) = mpsc::unbounded_channel();
         let queue_depth = Arc::new(AtomicU64::new(0));
+        let appended = Arc::new(Notify::new());
         let offset = {
             let offset = clog.max_committed_offset().map(|x| x as i64).unwrap_or(-1);
             Arc::new(AtomicI64::new(offset))
@@ -111,25 +168,40 @@ impl<T: Encode + Send + Sync + 'static> Local<T> {
                 rx,
                 queue_depth: queue_depth.clone(),
                 max_records_in_commit: opts.commitlog.max_records_in_commit,
+                appended: appended.clone(),
             }
             .run(),
         );
         rt.spawn(
             FlushAndSyncTask {
                 clog: clog.clone(),
-                period: opts.sync_interval,
+                window: opts.group_commit_window,
+                appended,
                 offset: offset.clone(),
                 abort: persister_task.abort_handle(),
             }
             .run(),
         );
 
+        let archiver_task = opts.archive.map(|archive| {
+            let task = rt.spawn(
+                ArchiverTask {
+                    clog: clog.clone(),
+                    interval: archive.interval,
+                    keep_local_segments: archive.keep_local_segments,
+                }
+                .run(),
+            );
+            AbortOnDrop(task.abort_handle())
+        });
+
         Ok(Self {
             clog,
             durable_offset: offset,
             queue,
             queue_depth,
             persister_task,
+            _archiver_task: archiver_task,
         })
     }
 
@@ -174,6 +246,8 @@ struct PersisterTask<T> {
     rx: mpsc::UnboundedReceiver<Txdata<T>>,
     queue_depth: Arc<AtomicU64>,
     max_records_in_commit: NonZeroU16,
+    /// Signals the [`FlushAndSyncTask`] that there is new data to sync.
+    appended: Arc<Notify>,
 }
 
 impl<T: Encode + Send + Sync + 'static> PersisterTask<T> {
@@ -195,6 +269,7 @@ impl<T: Encode + Send + Sync + 'static> PersisterTask<T> {
             } else if let Err(retry) = self.clog.append(txdata) {
                 self.flush_append(retry, false).await
             }
+            self.appended.notify_one();
 
             trace!("appended txdata");
         }
@@ -245,7 +320,10 @@ fn flush_error(e: io::Error) {
 
 struct FlushAndSyncTask<T> {
     clog: Arc<Commitlog<Txdata<T>>>,
-    period: Duration,
+    /// How long to wait after the first unsynced append before syncing.
+    window: Duration,
+    /// Notified by the [`PersisterTask`] whenever it appends to the log.
+    appended: Arc<Notify>,
     offset: Arc<AtomicI64>,
     /// Handle to abort the [`PersisterTask`] if fsync panics.
     abort: AbortHandle,
@@ -256,16 +334,20 @@ impl<T: Send + Sync + 'static> FlushAndSyncTask<T> {
     async fn run(self) {
         info!("starting syncer task");
 
-        let mut interval = interval(self.period);
-        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
-
         loop {
-            interval.tick().await;
+            // Wait for a transaction to be appended, then for the rest of
+            // the window, so that every transaction appended in the meantime
+            // is synced by the same fsync.
+            //
+            // Appends which happen while we're syncing leave a permit in
+            // `appended`, so they are picked up by the next iteration.
+            self.appended.notified().await;
+            sleep(self.window).await;
 
             // Skip if nothing changed.
             if let Some(committed) = self.clog.max_committed_offset() {
                 let durable = self.offset.load(Acquire);
-                if durable.is_positive() && committed == durable as _ {
+                if durable.is_positive() && committed == durable as u64 {
                     continue;
                 }
             }
@@ -297,6 +379,60 @@ impl<T: Send + Sync + 'static> FlushAndSyncTask<T> {
     }
 }
 
+struct ArchiverTask<T> {
+    clog: Arc<Commitlog<Txdata<T>>>,
+    /// How often to check for sealed segments.
+    interval: Duration,
+    keep_local_segments: usize,
+}
+
+impl<T: Send + Sync + 'static> ArchiverTask<T> {
+    #[instrument(name = "durability::local::archiver_task", skip_all)]
+    async fn run(self) {
+        info!("starting archiver task");
+
+        // Wait for a full interval before archiving for the first time,
+        // so as to not remove segments from local storage while they are
+        // being replayed after a restart.
+        let mut interval = interval_at(Instant::now() + self.interval, self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+
+            let clog = self.clog.clone();
+            let keep_local_segments = self.keep_local_segments;
+            let task = spawn_blocking(move || clog.archive_sealed_segments(keep_local_segments)).await;
+            match task {
+                Err(e) => {
+                    if e.is_panic() {
+                        panic::resume_unwind(e.into_panic())
+                    }
+                    break;
+                }
+                // The archive may be unreachable temporarily,
+                // so just try again later.
+                Ok(Err(e)) => {
+                    warn!("failed to archive segments: {e}");
+                }
+                Ok(Ok(uploaded)) => {
+                    trace!("archived {uploaded} segments");
+                }
+            }
+        }
+
+        info!("exiting archiver task");
+    }
+}
+
+/// Aborts a background task when dropped.
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
 impl<T: Send + Sync + 'static> Durability for Local<T> {
     type TxData = Txdata<T>;
 
@@ -339,3 +475,56 @@ impl<T: Encode + 'static> History for Local<T> {
         self.clog.max_committed_offset()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spacetimedb_paths::FromPathUnchecked;
+    use tokio::time::timeout;
+
+    fn empty_tx() -> Txdata<()> {
+        Txdata {
+            inputs: None,
+            outputs: None,
+            mutations: None,
+        }
+    }
+
+    /// Wait until the transaction at `offset` is durable.
+    async fn synced(local: &Local<()>, offset: TxOffset) {
+        while local.durable_tx_offset() < Some(offset) {
+            sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn syncs_transactions_appended_within_the_window_together() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let window = Duration::from_millis(300);
+        let opts = Options {
+            group_commit_window: window,
+            ..Options::default()
+        };
+        let local = Local::open(
+            CommitLogDir::from_path_unchecked(tmp.path()),
+            tokio::runtime::Handle::current(),
+            opts,
+        )?;
+        assert_eq!(local.durable_tx_offset(), None);
+
+        let start = Instant::now();
+        for _ in 0..3 {
+            local.append_tx(empty_tx());
+        }
+        timeout(window * 10, synced(&local, 2)).await?;
+        // Nothing is synced before the window of the first transaction is over.
+        assert!(start.elapsed() >= window);
+
+        // An append to an idle log is synced by the next window.
+        local.append_tx(empty_tx());
+        timeout(window * 10, synced(&local, 3)).await?;
+
+        assert_eq!(local.close().await?, Some(3));
+        Ok(())
+    }
+}
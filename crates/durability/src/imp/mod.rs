@@ -1,2 +1,4 @@
 pub mod local;
+pub mod s3;
 pub use local::Local;
+pub use s3::S3Archive;
@@ -0,0 +1,289 @@
+use std::{
+    fmt,
+    fs::File,
+    future::Future,
+    io::{self, Write as _},
+    path::Path,
+};
+
+use hmac::{Hmac, Mac as _};
+use itertools::Itertools as _;
+use reqwest::{header, Method, RequestBuilder, Response, StatusCode, Url};
+use sha2::{Digest as _, Sha256};
+use spacetimedb_commitlog::archive::Archive;
+
+/// Suffix of the object keys of archived segments.
+const SEGMENT_KEY_EXT: &str = ".stdb.log";
+
+/// Segments are streamed from disk, so their hash is not part of the signature.
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// [`S3Archive`] configuration.
+#[derive(Clone)]
+pub struct S3Config {
+    /// The base URL of the object store, e.g. `https://s3.us-east-1.amazonaws.com`.
+    ///
+    /// Buckets are addressed path-style, i.e. as `{endpoint}/{bucket}`,
+    /// which is supported by most S3-compatible stores.
+    pub endpoint: String,
+    /// The region the bucket is located in, e.g. `us-east-1`.
+    pub region: String,
+    /// The bucket to store segments in.
+    pub bucket: String,
+    /// The prefix of the object keys of segments, e.g. `clog/`.
+    ///
+    /// Archives sharing a bucket must use distinct prefixes.
+    pub prefix: String,
+    /// The access key used to sign requests.
+    pub access_key_id: String,
+    /// The secret key used to sign requests.
+    pub secret_access_key: String,
+}
+
+impl fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("region", &self.region)
+            .field("bucket", &self.bucket)
+            .field("prefix", &self.prefix)
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An [`Archive`] which stores segments as objects in an S3-compatible object store.
+///
+/// Segment `offset` is stored under the key `{prefix}{offset:0>20}.stdb.log`,
+/// mirroring the file names of segments on local storage.
+///
+/// Requests are driven by a tokio runtime, which the blocking [`Archive`]
+/// methods enter via [`tokio::task::block_in_place`]. They must thus not be
+/// called from within a current-thread runtime.
+#[derive(Debug)]
+pub struct S3Archive {
+    config: S3Config,
+    client: reqwest::Client,
+    rt: tokio::runtime::Handle,
+}
+
+impl S3Archive {
+    /// Create an [`S3Archive`] which performs requests on the runtime `rt`.
+    pub fn new(config: S3Config, rt: tokio::runtime::Handle) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+            rt,
+        }
+    }
+
+    fn key(&self, offset: u64) -> String {
+        format!("{}{offset:0>20}{SEGMENT_KEY_EXT}", self.config.prefix)
+    }
+
+    fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        tokio::task::block_in_place(|| self.rt.block_on(fut))
+    }
+
+    /// Prepare a request for the object `key`, or for the bucket if `key` is empty,
+    /// signed using AWS Signature Version 4.
+    fn request(&self, method: Method, key: &str, query: &[(&str, &str)]) -> io::Result<RequestBuilder> {
+        let S3Config {
+            endpoint,
+            region,
+            bucket,
+            access_key_id,
+            secret_access_key,
+            ..
+        } = &self.config;
+
+        let mut path = format!("/{}", urlencoding::encode(bucket));
+        if !key.is_empty() {
+            path.push('/');
+            path.push_str(&key.split('/').map(urlencoding::encode).join("/"));
+        }
+        let query = query
+            .iter()
+            .map(|(k, v)| (urlencoding::encode(k), urlencoding::encode(v)))
+            .sorted()
+            .map(|(k, v)| format!("{k}={v}"))
+            .join("&");
+        let mut url = Url::parse(endpoint).map_err(io::Error::other)?;
+        url.set_path(&path);
+        url.set_query((!query.is_empty()).then_some(&query));
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_owned(),
+            (None, _) => return Err(io::Error::other(format!("invalid S3 endpoint: {endpoint}"))),
+        };
+
+        let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let date = &amz_date[..8];
+        let canonical_headers =
+            format!("host:{host}\nx-amz-content-sha256:{UNSIGNED_PAYLOAD}\nx-amz-date:{amz_date}\n");
+        let canonical_request =
+            format!("{method}\n{path}\n{query}\n{canonical_headers}\n{SIGNED_HEADERS}\n{UNSIGNED_PAYLOAD}");
+        let scope = format!("{date}/{region}/s3/aws4_request");
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request))
+        );
+        let signing_key = [date, region, "s3", "aws4_request"]
+            .into_iter()
+            .fold(format!("AWS4{secret_access_key}").into_bytes(), |key, msg| {
+                hmac_sha256(&key, msg)
+            });
+        let signature = hex::encode(hmac_sha256(&signing_key, &string_to_sign));
+        let credential = format!("{access_key_id}/{scope}");
+        let authorization =
+            format!("AWS4-HMAC-SHA256 Credential={credential}, SignedHeaders={SIGNED_HEADERS}, Signature={signature}");
+
+        Ok(self
+            .client
+            .request(method, url)
+            .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+            .header("x-amz-date", amz_date)
+            .header(header::AUTHORIZATION, authorization))
+    }
+}
+
+impl Archive for S3Archive {
+    fn upload(&self, offset: u64, path: &Path) -> io::Result<()> {
+        let request = self.request(Method::PUT, &self.key(offset), &[])?;
+        self.block_on(async {
+            let file = tokio::fs::File::open(path).await?;
+            let len = file.metadata().await?.len();
+            let res = request
+                .header(header::CONTENT_LENGTH, len)
+                .body(file)
+                .send()
+                .await
+                .map_err(io::Error::other)?;
+            check_status(res).await.map(drop)
+        })
+    }
+
+    fn download(&self, offset: u64, path: &Path) -> io::Result<()> {
+        let request = self.request(Method::GET, &self.key(offset), &[])?;
+        self.block_on(async {
+            let mut res = check_status(request.send().await.map_err(io::Error::other)?).await?;
+            let mut file = File::create_new(path)?;
+            while let Some(chunk) = res.chunk().await.map_err(io::Error::other)? {
+                file.write_all(&chunk)?;
+            }
+            file.sync_all()
+        })
+    }
+
+    fn remove(&self, offset: u64) -> io::Result<()> {
+        let key = self.key(offset);
+        // Deleting a missing object succeeds, so check that it exists first.
+        let head = self.request(Method::HEAD, &key, &[])?;
+        let delete = self.request(Method::DELETE, &key, &[])?;
+        self.block_on(async {
+            check_status(head.send().await.map_err(io::Error::other)?).await?;
+            check_status(delete.send().await.map_err(io::Error::other)?)
+                .await
+                .map(drop)
+        })
+    }
+
+    fn archived_offsets(&self) -> io::Result<Vec<u64>> {
+        let prefix = &self.config.prefix;
+        let mut offsets = Vec::new();
+        let mut continuation_token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2"), ("prefix", prefix.as_str())];
+            if let Some(token) = &continuation_token {
+                query.push(("continuation-token", token.as_str()));
+            }
+            let request = self.request(Method::GET, "", &query)?;
+            let body = self.block_on(async {
+                let res = check_status(request.send().await.map_err(io::Error::other)?).await?;
+                res.text().await.map_err(io::Error::other)
+            })?;
+
+            offsets.extend(xml_elements(&body, "Key").into_iter().filter_map(|key| {
+                key.strip_prefix(prefix.as_str())?
+                    .strip_suffix(SEGMENT_KEY_EXT)?
+                    .parse::<u64>()
+                    .ok()
+            }));
+            continuation_token = match xml_elements(&body, "IsTruncated").first() {
+                Some(&"true") => xml_elements(&body, "NextContinuationToken")
+                    .first()
+                    .map(|t| t.to_string()),
+                _ => None,
+            };
+            if continuation_token.is_none() {
+                break;
+            }
+        }
+        offsets.sort_unstable();
+
+        Ok(offsets)
+    }
+}
+
+/// Turn an unsuccessful response into an error,
+/// of kind [`io::ErrorKind::NotFound`] if the object doesn't exist.
+async fn check_status(res: Response) -> io::Result<Response> {
+    let status = res.status();
+    if status.is_success() {
+        return Ok(res);
+    }
+    let body = res.text().await.unwrap_or_default();
+    let msg = format!("S3 request failed with status {status}: {body}");
+    Err(if status == StatusCode::NOT_FOUND {
+        io::Error::new(io::ErrorKind::NotFound, msg)
+    } else {
+        io::Error::other(msg)
+    })
+}
+
+fn hmac_sha256(key: &[u8], msg: &str) -> Vec<u8> {
+    Hmac::<Sha256>::new_from_slice(key)
+        .expect("HMAC accepts keys of any length")
+        .chain_update(msg)
+        .finalize()
+        .into_bytes()
+        .to_vec()
+}
+
+/// The text contents of all elements named `tag` in `xml`.
+///
+/// This is just enough XML to read the keys and continuation tokens of an
+/// S3 object listing, which contain no nested elements or entities.
+fn xml_elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{tag}>");
+    let close = format!("</{tag}>");
+    xml.split(open.as_str())
+        .skip(1)
+        .filter_map(|s| s.split_once(close.as_str()).map(|(contents, _)| contents))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_listing() {
+        let body = "<ListBucketResult><IsTruncated>true</IsTruncated>\
+            <Contents><Key>clog/00000000000000000000.stdb.log</Key></Contents>\
+            <Contents><Key>clog/00000000000000000042.stdb.log</Key></Contents>\
+            <NextContinuationToken>abc=</NextContinuationToken></ListBucketResult>";
+        assert_eq!(
+            xml_elements(body, "Key"),
+            [
+                "clog/00000000000000000000.stdb.log",
+                "clog/00000000000000000042.stdb.log"
+            ]
+        );
+        assert_eq!(xml_elements(body, "IsTruncated"), ["true"]);
+        assert_eq!(xml_elements(body, "NextContinuationToken"), ["abc="]);
+        assert!(xml_elements(body, "Prefix").is_empty());
+    }
+}
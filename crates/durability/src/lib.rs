@@ -3,7 +3,7 @@ use std::{iter, marker::PhantomData, sync::Arc};
 pub use spacetimedb_commitlog::{error, payload::Txdata, Decoder, Transaction};
 
 mod imp;
-pub use imp::{local, Local};
+pub use imp::{local, s3, Local, S3Archive};
 
 /// Transaction offset.
 ///
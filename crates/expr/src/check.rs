@@ -2,21 +2,24 @@ use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
-use crate::expr::{Expr, ProjectList, ProjectName, Relvar};
+use crate::expr::{Expr, FieldProject, ProjectList, ProjectName, Relvar, TopN};
 use crate::{expr::LeftDeepJoin, statement::Statement};
 use spacetimedb_lib::AlgebraicType;
 use spacetimedb_primitives::TableId;
 use spacetimedb_schema::schema::TableSchema;
 use spacetimedb_sql_parser::ast::BinOp;
 use spacetimedb_sql_parser::{
-    ast::{sub::SqlSelect, SqlFrom, SqlIdent, SqlJoin},
+    ast::{
+        sub::{SqlSelect, SqlTopN},
+        SqlFrom, SqlIdent, SqlJoin,
+    },
     parser::sub::parse_subscription,
 };
 
 use super::{
     errors::{DuplicateName, TypingError, Unresolved, Unsupported},
     expr::RelExpr,
-    type_expr, type_proj, type_select, StatementCtx, StatementSource,
+    op_supports_type, type_expr, type_proj, type_select, StatementCtx, StatementSource,
 };
 
 /// The result of type checking and name resolution
@@ -134,10 +137,12 @@ impl TypeChecker for SubChecker {
 
     fn type_set(ast: Self::Set, vars: &mut Relvars, tx: &impl SchemaView) -> TypingResult<ProjectList> {
         match ast {
+            SqlSelect { top_n: Some(_), .. } => Err(Unsupported::TopN.into()),
             SqlSelect {
                 project,
                 from,
                 filter: None,
+                top_n: None,
             } => {
                 let input = Self::type_from(from, vars, tx)?;
                 type_proj(input, project, vars)
@@ -146,6 +151,7 @@ impl TypeChecker for SubChecker {
                 project,
                 from,
                 filter: Some(expr),
+                top_n: None,
             } => {
                 let input = Self::type_from(from, vars, tx)?;
                 type_proj(type_select(input, expr, vars)?, project, vars)
@@ -164,6 +170,38 @@ pub fn type_subscription(ast: SqlSelect, tx: &impl SchemaView) -> TypingResult<P
     expect_table_type(SubChecker::type_ast(ast, tx)?)
 }
 
+/// Type check a subscription query which may be a top-N subscription,
+/// returning its ORDER BY and LIMIT separately.
+pub fn type_top_n_subscription(ast: SqlSelect, tx: &impl SchemaView) -> TypingResult<(ProjectName, Option<TopN>)> {
+    let SqlSelect {
+        project,
+        from,
+        filter,
+        top_n,
+    } = ast;
+    let mut vars = Relvars::default();
+    let ast = SqlSelect {
+        project,
+        from,
+        filter,
+        top_n: None,
+    };
+    let proj = expect_table_type(SubChecker::type_set(ast, &mut vars, tx)?)?;
+    let top_n = top_n.map(|top_n| type_top_n(top_n, &vars)).transpose()?;
+    Ok((proj, top_n))
+}
+
+/// Type check the ORDER BY and LIMIT of a top-N subscription
+fn type_top_n(SqlTopN { field, desc, limit }: SqlTopN, vars: &Relvars) -> TypingResult<TopN> {
+    match type_expr(vars, field.into(), None)? {
+        Expr::Field(FieldProject { field, ty, .. }) if op_supports_type(BinOp::Lt, &ty) => {
+            Ok(TopN { field, desc, limit })
+        }
+        Expr::Field(_) => Err(Unsupported::OrderByType.into()),
+        _ => unreachable!("Projection expressions are always typed as fields"),
+    }
+}
+
 /// Parse and type check a *subscription* query into a `StatementCtx`
 pub fn compile_sql_sub<'a>(sql: &'a str, tx: &impl SchemaView) -> TypingResult<StatementCtx<'a>> {
     Ok(StatementCtx {
@@ -232,7 +270,9 @@ mod tests {
     use spacetimedb_lib::{AlgebraicType, ProductType};
     use spacetimedb_schema::def::ModuleDef;
 
-    use super::parse_and_type_sub;
+    use super::{parse_and_type_sub, type_top_n_subscription};
+    use crate::expr::TopN;
+    use spacetimedb_sql_parser::parser::sub::parse_subscription;
 
     fn module_def() -> ModuleDef {
         build_module_def(vec![
@@ -311,4 +351,26 @@ mod tests {
             assert!(result.is_err());
         }
     }
+
+    #[test]
+    fn top_n() {
+        let tx = SchemaViewer(module_def());
+        let type_top_n = |sql| type_top_n_subscription(parse_subscription(sql).unwrap(), &tx).map(|(_, top_n)| top_n);
+
+        assert_eq!(type_top_n("select * from t").unwrap(), None);
+        assert_eq!(
+            type_top_n("select * from t where t.str = '' order by u32 desc limit 10").unwrap(),
+            Some(TopN {
+                field: 1,
+                desc: true,
+                limit: 10
+            })
+        );
+        // Field a does not exist on table t
+        assert!(type_top_n("select * from t order by a limit 10").is_err());
+        // Arrays are not ordered
+        assert!(type_top_n("select * from t order by arr limit 10").is_err());
+        // Only top-N subscriptions may have ORDER BY and LIMIT
+        assert!(parse_and_type_sub("select * from t order by u32 limit 10", &tx).is_err());
+    }
 }
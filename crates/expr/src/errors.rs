@@ -46,6 +46,10 @@ pub enum Unsupported {
     ReturnType,
     #[error("Unsupported expression in projection")]
     ProjectExpr,
+    #[error("ORDER BY and LIMIT are only supported in top-N subscriptions")]
+    TopN,
+    #[error("ORDER BY is only supported on fields of primitive types")]
+    OrderByType,
 }
 
 // TODO: It might be better to return the missing/extra fields
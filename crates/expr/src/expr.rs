@@ -176,3 +176,14 @@ pub struct FieldProject {
     pub field: usize,
     pub ty: AlgebraicType,
 }
+
+/// The ORDER BY and LIMIT of a top-N subscription,
+/// which returns the first `limit` rows when ordered by `field`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TopN {
+    /// The position of the field within the rows
+    pub field: usize,
+    /// Are the rows ordered from largest to smallest?
+    pub desc: bool,
+    pub limit: u64,
+}
@@ -6,7 +6,7 @@ use spacetimedb_schema::schema::{ColumnSchema, TableSchema};
 use spacetimedb_sql_parser::{
     ast::{
         sql::{SqlAst, SqlDelete, SqlInsert, SqlSelect, SqlSet, SqlShow, SqlUpdate},
-        SqlIdent, SqlLiteral,
+        Project, ProjectElem, ProjectExpr, SqlIdent, SqlLiteral,
     },
     parser::sql::parse_sql,
 };
@@ -36,6 +36,8 @@ pub type Row = Box<[AlgebraicValue]>;
 pub struct TableInsert {
     pub into: Arc<TableSchema>,
     pub rows: Box<[Row]>,
+    /// The columns to report for each inserted row, if any
+    pub returning: Option<Box<[ColId]>>,
 }
 
 pub struct TableDelete {
@@ -64,6 +66,7 @@ pub fn type_insert(insert: SqlInsert, tx: &impl SchemaView) -> TypingResult<Tabl
         table: SqlIdent(table_name),
         fields,
         values,
+        returning,
     } = insert;
 
     let schema = tx
@@ -116,9 +119,37 @@ pub fn type_insert(insert: SqlInsert, tx: &impl SchemaView) -> TypingResult<Tabl
         }
         rows.push(values.into_boxed_slice());
     }
+    let returning = returning
+        .map(|project| type_returning(project, &table_name, &schema))
+        .transpose()?;
     let into = schema;
     let rows = rows.into_boxed_slice();
-    Ok(TableInsert { into, rows })
+    Ok(TableInsert { into, rows, returning })
+}
+
+/// Type check the RETURNING clause of an INSERT statement
+fn type_returning(project: Project, table_name: &str, schema: &TableSchema) -> TypingResult<Box<[ColId]>> {
+    let all_cols = || schema.columns().iter().map(|col| col.col_pos).collect::<Box<[_]>>();
+    match project {
+        Project::Star(None) => Ok(all_cols()),
+        Project::Star(Some(SqlIdent(name))) if *name == *table_name => Ok(all_cols()),
+        Project::Star(Some(SqlIdent(name))) => Err(Unresolved::table(&name).into()),
+        Project::Exprs(elems) => elems
+            .into_iter()
+            .map(|ProjectElem(expr, _)| -> TypingResult<ColId> {
+                let (table, field): (Box<str>, _) = match expr {
+                    ProjectExpr::Var(SqlIdent(field)) => (table_name.into(), field),
+                    ProjectExpr::Field(SqlIdent(table), SqlIdent(field)) => (table, field),
+                };
+                if *table != *table_name {
+                    return Err(Unresolved::table(&table).into());
+                }
+                schema
+                    .get_column_id_by_name(&field)
+                    .ok_or_else(|| Unresolved::field(&table, &field).into())
+            })
+            .collect(),
+    }
 }
 
 /// Type check a DELETE statement
@@ -14,7 +14,7 @@
 //! which we expect to shrink the linear lookups to an acceptable size.
 
 use std::{
-    fs::{create_dir_all, File, OpenOptions},
+    fs::{create_dir_all, File, Metadata, OpenOptions},
     io::{self, Read, Write},
     path::{Path, PathBuf},
 };
@@ -190,6 +190,93 @@ impl DirTrie {
         file.read_to_end(&mut buf)?;
         Ok(buf)
     }
+
+    /// Returns the keys of all the entries in this trie, in no particular order.
+    ///
+    /// Files and directories whose names are not those of entries are skipped.
+    pub fn entries(&self) -> Result<Vec<FileId>, io::Error> {
+        let mut entries = Vec::new();
+        for dir in self.root.read_dir()? {
+            let dir = dir?;
+            let dir_name = dir.file_name();
+            let Some(prefix) = dir_name.to_str().filter(|name| name.len() == DIR_HEX_CHARS) else {
+                continue;
+            };
+            if !dir.file_type()?.is_dir() {
+                continue;
+            }
+            for file in dir.path().read_dir()? {
+                let file_name = file?.file_name();
+                let Some(rest) = file_name
+                    .to_str()
+                    .filter(|name| name.len() == FILE_ID_HEX_CHARS - DIR_HEX_CHARS)
+                else {
+                    continue;
+                };
+                let mut file_id = [0; FILE_ID_BYTES];
+                if hex::decode_to_slice(format!("{prefix}{rest}"), &mut file_id).is_ok() {
+                    entries.push(file_id);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Returns the metadata of the entry keyed with `file_id`.
+    pub fn entry_metadata(&self, file_id: &FileId) -> Result<Metadata, io::Error> {
+        std::fs::metadata(self.file_path(file_id))
+    }
+
+    /// Remove the entry keyed with `file_id`,
+    /// along with its directory if no other entry is left in it.
+    ///
+    /// If the entry is hardlinked into other tries, it remains in them.
+    pub fn remove_entry(&self, file_id: &FileId) -> Result<(), io::Error> {
+        let path = self.file_path(file_id);
+        std::fs::remove_file(&path)?;
+        // Known to have a parent because `self.file_path` creates a path with a parent.
+        // Fails, harmlessly, if the directory is not empty.
+        let _ = std::fs::remove_dir(path.parent().unwrap());
+        Ok(())
+    }
+
+    /// Atomically replace the contents of the existing entry keyed with `file_id` with `contents`.
+    ///
+    /// The entry is replaced by a new file, so if it is hardlinked into other tries,
+    /// they keep the old contents.
+    pub fn replace_entry(&self, file_id: &FileId, contents: &[u8]) -> Result<(), io::Error> {
+        let path = self.file_path(file_id);
+        let tmp = Self::tmp_path(&path);
+        let res = o_excl().open(&tmp).and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        });
+        res.and_then(|()| std::fs::rename(&tmp, &path)).inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp);
+        })
+    }
+
+    /// Atomically replace the existing entry keyed with `file_id`
+    /// with a hardlink to the entry for `file_id` in `src_repo`.
+    ///
+    /// See [`Self::try_hardlink_from`].
+    pub fn replace_with_hardlink_from(&self, src_repo: &DirTrie, file_id: &FileId) -> Result<(), io::Error> {
+        let path = self.file_path(file_id);
+        let tmp = Self::tmp_path(&path);
+        std::fs::hard_link(src_repo.file_path(file_id), &tmp)?;
+        std::fs::rename(&tmp, &path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp);
+        })
+    }
+
+    /// The path next to `file` at which to prepare its replacement.
+    ///
+    /// Not the name of an entry, so it is skipped by [`Self::entries`].
+    fn tmp_path(file: &Path) -> PathBuf {
+        let mut tmp = file.as_os_str().to_owned();
+        tmp.push(".tmp");
+        PathBuf::from(tmp)
+    }
 }
 
 #[cfg(test)]
@@ -267,6 +354,43 @@ mod test {
         })
     }
 
+    #[test]
+    fn entries_remove() {
+        with_test_dir_trie(|trie| {
+            assert_eq!(trie.entries().unwrap(), Vec::<FileId>::new());
+
+            write_test_string(&trie);
+            // Files which aren't entries are skipped.
+            File::create(trie.root().join("not_an_entry")).unwrap();
+            assert_eq!(trie.entries().unwrap(), vec![TEST_ID]);
+            assert_eq!(trie.entry_metadata(&TEST_ID).unwrap().len(), TEST_STRING.len() as u64);
+
+            trie.remove_entry(&TEST_ID).unwrap();
+            assert!(!trie.contains_entry(&TEST_ID));
+            assert_eq!(trie.entries().unwrap(), Vec::<FileId>::new());
+        })
+    }
+
+    #[test]
+    fn replace() {
+        with_test_dir_trie(|src| {
+            with_test_dir_trie(|dst| {
+                write_test_string(&src);
+                assert!(dst.try_hardlink_from(&src, &TEST_ID).unwrap());
+
+                // Replacing the entry in `src` leaves the hardlinked entry in `dst` alone.
+                src.replace_entry(&TEST_ID, b"replaced").unwrap();
+                assert_eq!(src.read_entry(&TEST_ID).unwrap(), b"replaced");
+                read_test_string(&dst);
+                assert_eq!(src.entries().unwrap(), vec![TEST_ID]);
+
+                dst.replace_with_hardlink_from(&src, &TEST_ID).unwrap();
+                assert_eq!(dst.read_entry(&TEST_ID).unwrap(), b"replaced");
+                assert_eq!(dst.entries().unwrap(), vec![TEST_ID]);
+            })
+        })
+    }
+
     #[test]
     fn open_options() {
         with_test_dir_trie(|trie| {
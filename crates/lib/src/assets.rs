@@ -0,0 +1,135 @@
+//! Asset bundles: read-only static files published alongside a module's program.
+//!
+//! A bundle travels inside the program, as a WASM custom section named [`ASSETS_SECTION`].
+//! Custom sections are ignored when the program is compiled,
+//! so a bundle is stored, hashed and updated together with the program it belongs to.
+
+use crate::bsatn::{self, DecodeError};
+use crate::SpacetimeType;
+
+/// The name of the custom section holding a program's asset bundle.
+pub const ASSETS_SECTION: &str = "spacetime_assets";
+
+/// The files in an asset bundle.
+#[derive(Debug, Clone, Default, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub struct AssetBundle {
+    pub files: Vec<AssetFile>,
+}
+
+/// A file in an asset bundle.
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub struct AssetFile {
+    /// The path of the file, relative to the root of the bundle, with `/` as the separator.
+    pub path: String,
+    /// The contents of the file.
+    pub data: Vec<u8>,
+}
+
+impl AssetBundle {
+    /// Appends this bundle to the WASM `program` as a custom section named [`ASSETS_SECTION`].
+    pub fn append_to_program(&self, program: &mut Vec<u8>) {
+        let mut payload = Vec::new();
+        write_leb(&mut payload, ASSETS_SECTION.len() as u64);
+        payload.extend_from_slice(ASSETS_SECTION.as_bytes());
+        bsatn::to_writer(&mut payload, self).unwrap();
+
+        program.push(0);
+        write_leb(program, payload.len() as u64);
+        program.extend_from_slice(&payload);
+    }
+
+    /// Returns the bundle appended to the WASM `program` by [`Self::append_to_program`],
+    /// or `None` if it has none.
+    ///
+    /// Both core modules and components are supported,
+    /// as both frame their sections the same way.
+    pub fn from_program(program: &[u8]) -> Result<Option<Self>, DecodeError> {
+        // Skip the magic number and version.
+        let mut rest = program.get(8..).unwrap_or_default();
+        while let Some((&id, tail)) = rest.split_first() {
+            let (len, tail) = read_leb(tail)?;
+            let (section, tail) = split_at_checked(tail, len)?;
+            rest = tail;
+            if id != 0 {
+                continue;
+            }
+            let (name_len, section) = read_leb(section)?;
+            let (name, data) = split_at_checked(section, name_len)?;
+            if name == ASSETS_SECTION.as_bytes() {
+                return bsatn::from_slice(data).map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+fn write_leb(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+fn read_leb(buf: &[u8]) -> Result<(u64, &[u8]), DecodeError> {
+    let mut value = 0;
+    for (i, &byte) in buf.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            return Ok((value, &buf[i + 1..]));
+        }
+    }
+    Err(DecodeError::BufferLength {
+        for_type: "LEB128",
+        expected: buf.len() + 1,
+        given: buf.len(),
+    })
+}
+
+fn split_at_checked(buf: &[u8], len: u64) -> Result<(&[u8], &[u8]), DecodeError> {
+    match usize::try_from(len) {
+        Ok(len) if len <= buf.len() => Ok(buf.split_at(len)),
+        _ => Err(DecodeError::BufferLength {
+            for_type: "WASM section",
+            expected: len as usize,
+            given: buf.len(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip_through_program() {
+        let mut program = b"\0asm\x01\0\0\0".to_vec();
+        // An unrelated custom section, which is skipped.
+        program.extend_from_slice(&[0, 3, 1, b'x', 0]);
+        assert_eq!(AssetBundle::from_program(&program).unwrap(), None);
+
+        let bundle = AssetBundle {
+            files: vec![
+                AssetFile {
+                    path: "config.json".into(),
+                    data: b"{}".to_vec(),
+                },
+                AssetFile {
+                    path: "maps/start.bin".into(),
+                    data: vec![0xff; 300],
+                },
+            ],
+        };
+        bundle.append_to_program(&mut program);
+        assert_eq!(AssetBundle::from_program(&program).unwrap(), Some(bundle));
+
+        program.pop();
+        assert!(AssetBundle::from_program(&program).is_err());
+    }
+}
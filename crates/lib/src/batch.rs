@@ -0,0 +1,114 @@
+//! The operations a module may submit to the host in one `datastore_batch_bsatn` call,
+//! and the results the host hands back for them.
+//!
+//! Each operation does exactly what the host call of the same name does,
+//! and they run in order, so later operations see the effects of earlier ones.
+//! An operation that fails doesn't stop the rest of the batch;
+//! its error is reported in its result instead.
+
+use crate::SpacetimeType;
+use spacetimedb_primitives::{ColId, IndexId, TableId};
+
+/// One operation in a batch.
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub enum BatchOp {
+    /// Finds rows in a btree index, as `datastore_btree_scan_bsatn` does.
+    Find(BatchFind),
+    /// Inserts a row, as `datastore_insert_bsatn` does.
+    Insert(BatchInsert),
+    /// Updates a row, as `datastore_update_bsatn` does.
+    Update(BatchUpdate),
+    /// Deletes rows found in a btree index, as `datastore_delete_by_btree_scan_bsatn` does.
+    DeleteByBtreeScan(BatchFind),
+    /// Deletes rows equal to given ones, as `datastore_delete_all_by_eq_bsatn` does.
+    DeleteAllByEq(BatchDeleteAllByEq),
+}
+
+/// The arguments to [`BatchOp::Find`] and [`BatchOp::DeleteByBtreeScan`].
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub struct BatchFind {
+    pub index_id: IndexId,
+    /// The BSATN-encoded values of the first `prefix_elems` indexed columns.
+    pub prefix: Vec<u8>,
+    pub prefix_elems: ColId,
+    /// The BSATN-encoded `Bound<AlgebraicValue>` starting the range of the next indexed column.
+    pub rstart: Vec<u8>,
+    /// The BSATN-encoded `Bound<AlgebraicValue>` ending the range of the next indexed column.
+    pub rend: Vec<u8>,
+}
+
+/// The arguments to [`BatchOp::Insert`].
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub struct BatchInsert {
+    pub table_id: TableId,
+    /// The BSATN-encoded row.
+    pub row: Vec<u8>,
+}
+
+/// The arguments to [`BatchOp::Update`].
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub struct BatchUpdate {
+    pub table_id: TableId,
+    /// The unique index by which to find the row to update.
+    pub index_id: IndexId,
+    /// The BSATN-encoded new row.
+    pub row: Vec<u8>,
+}
+
+/// The arguments to [`BatchOp::DeleteAllByEq`].
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub struct BatchDeleteAllByEq {
+    pub table_id: TableId,
+    /// The BSATN-encoded `Vec` of rows to delete.
+    pub relation: Vec<u8>,
+}
+
+/// The result of one [`BatchOp`], at the same position in the batch.
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub enum BatchResult {
+    /// The BSATN-encoded rows found by a [`BatchOp::Find`], concatenated.
+    Rows(Vec<u8>),
+    /// The BSATN-encoded values of the columns generated
+    /// by a [`BatchOp::Insert`] or [`BatchOp::Update`].
+    GeneratedColumns(Vec<u8>),
+    /// The number of rows deleted by a [`BatchOp::DeleteByBtreeScan`] or [`BatchOp::DeleteAllByEq`].
+    Deleted(u32),
+    /// The operation failed with this error code,
+    /// which is what the equivalent host call would have returned.
+    Error(u16),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bsatn;
+
+    #[test]
+    fn batch_roundtrips() {
+        let ops = vec![
+            BatchOp::Insert(BatchInsert {
+                table_id: TableId(4096),
+                row: vec![1, 2, 3],
+            }),
+            BatchOp::Find(BatchFind {
+                index_id: IndexId(7),
+                prefix: vec![],
+                prefix_elems: ColId(0),
+                rstart: vec![0, 42],
+                rend: vec![0, 42],
+            }),
+        ];
+        let bytes = bsatn::to_vec(&ops).unwrap();
+        assert_eq!(bsatn::from_slice::<Vec<BatchOp>>(&bytes).unwrap(), ops);
+
+        let results = vec![BatchResult::GeneratedColumns(vec![]), BatchResult::Error(3)];
+        let bytes = bsatn::to_vec(&results).unwrap();
+        assert_eq!(bsatn::from_slice::<Vec<BatchResult>>(&bytes).unwrap(), results);
+    }
+}
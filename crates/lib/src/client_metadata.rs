@@ -0,0 +1,137 @@
+//! Metadata about the connection of a client, as learned by the host when the client connected,
+//! which reducers may read to gate old clients or to spot abuse.
+
+use crate::{Identity, SpacetimeType};
+use std::net::IpAddr;
+
+/// The metadata of the connection of a client.
+#[derive(Debug, Clone, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub struct ClientMetadata {
+    /// The websocket subprotocol negotiated when the client connected,
+    /// e.g., `v2.bsatn.spacetimedb`, which names the version of the protocol.
+    pub protocol: String,
+    /// The language of the client's SDK, e.g., `rust`, if the client said so when connecting.
+    pub sdk_language: Option<String>,
+    /// The version of the client's SDK, if the client said so when connecting.
+    pub sdk_version: Option<String>,
+    /// Where the client connected from.
+    pub remote_address: RemoteAddress,
+}
+
+/// Where a client connected from, without the address itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub struct RemoteAddress {
+    /// What kind of network the client connected from.
+    pub category: RemoteAddressCategory,
+    /// A hash of the network the client connected from,
+    /// i.e., of the `/24` prefix of an IPv4 address or the `/48` prefix of an IPv6 address.
+    ///
+    /// The hash is keyed by the database,
+    /// so it can't be traced back to the address, nor matched across databases.
+    /// Clients sharing a hash are likely on the same network.
+    ///
+    /// `None` if the address is unknown.
+    pub network_hash: Option<u64>,
+}
+
+/// What kind of network a client connected from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SpacetimeType)]
+#[sats(crate = crate)]
+pub enum RemoteAddressCategory {
+    /// The address of the client is unknown,
+    /// e.g., because no proxy in front of the host reported it.
+    Unknown,
+    /// The client is on the same machine as the host.
+    Loopback,
+    /// The client is on a private or link-local network.
+    Private,
+    /// The client is on the public internet.
+    Public,
+}
+
+impl RemoteAddress {
+    /// The remote address of a client whose address is unknown.
+    pub const UNKNOWN: Self = Self {
+        category: RemoteAddressCategory::Unknown,
+        network_hash: None,
+    };
+
+    /// Returns the anonymized address `ip` of a client connecting to the database `database`.
+    pub fn anonymize(ip: IpAddr, database: Identity) -> Self {
+        // An IPv4 client may reach an IPv6 socket through an IPv4-mapped address.
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            IpAddr::V4(_) => ip,
+        };
+        let (category, network) = match ip {
+            IpAddr::V4(v4) => {
+                let category = if v4.is_loopback() {
+                    RemoteAddressCategory::Loopback
+                } else if v4.is_private() || v4.is_link_local() {
+                    RemoteAddressCategory::Private
+                } else {
+                    RemoteAddressCategory::Public
+                };
+                let [a, b, c, _] = v4.octets();
+                (category, vec![4, a, b, c])
+            }
+            IpAddr::V6(v6) => {
+                let first = v6.segments()[0];
+                // Unique local addresses are in `fc00::/7`, and link-local ones in `fe80::/10`.
+                let category = if v6.is_loopback() {
+                    RemoteAddressCategory::Loopback
+                } else if first & 0xfe00 == 0xfc00 || first & 0xffc0 == 0xfe80 {
+                    RemoteAddressCategory::Private
+                } else {
+                    RemoteAddressCategory::Public
+                };
+                let mut network = vec![6];
+                network.extend_from_slice(&v6.octets()[..6]);
+                (category, network)
+            }
+        };
+        let hash = blake3::keyed_hash(&database.to_byte_array(), &network);
+        let network_hash = u64::from_le_bytes(hash.as_bytes()[..8].try_into().unwrap());
+        Self {
+            category,
+            network_hash: Some(network_hash),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anonymize(ip: &str, database: u8) -> RemoteAddress {
+        RemoteAddress::anonymize(ip.parse().unwrap(), Identity::from_byte_array([database; 32]))
+    }
+
+    #[test]
+    fn categories() {
+        let category = |ip| anonymize(ip, 0).category;
+        assert_eq!(category("127.0.0.1"), RemoteAddressCategory::Loopback);
+        assert_eq!(category("::1"), RemoteAddressCategory::Loopback);
+        assert_eq!(category("10.1.2.3"), RemoteAddressCategory::Private);
+        assert_eq!(category("192.168.0.7"), RemoteAddressCategory::Private);
+        assert_eq!(category("fd12:3456::1"), RemoteAddressCategory::Private);
+        assert_eq!(category("fe80::1"), RemoteAddressCategory::Private);
+        assert_eq!(category("::ffff:10.0.0.1"), RemoteAddressCategory::Private);
+        assert_eq!(category("8.8.8.8"), RemoteAddressCategory::Public);
+        assert_eq!(category("2001:db8::1"), RemoteAddressCategory::Public);
+    }
+
+    #[test]
+    fn network_hash() {
+        let hash = |ip, database| anonymize(ip, database).network_hash.unwrap();
+        // Addresses on the same network hash alike, but only within a database.
+        assert_eq!(hash("203.0.113.7", 0), hash("203.0.113.200", 0));
+        assert_ne!(hash("203.0.113.7", 0), hash("203.0.114.7", 0));
+        assert_ne!(hash("203.0.113.7", 0), hash("203.0.113.7", 1));
+        assert_eq!(hash("2001:db8:1::1", 0), hash("2001:db8:1:ffff::2", 0));
+        assert_ne!(hash("2001:db8:1::1", 0), hash("2001:db8:2::1", 0));
+        assert_eq!(hash("::ffff:203.0.113.7", 0), hash("203.0.113.7", 0));
+    }
+}
@@ -85,3 +85,50 @@ impl_deserialize!([] StTableType, de => {
     })
 });
 impl_st!([] StTableType, AlgebraicType::String);
+
+/// Describe whether the table's updates are written to the commitlog.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StDurability {
+    /// Updates are written to the commitlog.
+    Durable,
+    /// Updates are not written to the commitlog,
+    /// so on restart the table holds its rows as of the latest snapshot.
+    Relaxed,
+    /// Updates are not written to the commitlog, nor are the rows to snapshots,
+    /// so the table is empty on restart.
+    Ephemeral,
+}
+
+impl StDurability {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Durable => "durable",
+            Self::Relaxed => "relaxed",
+            Self::Ephemeral => "ephemeral",
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for StDurability {
+    type Error = &'a str;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        Ok(match value {
+            "durable" => Self::Durable,
+            "relaxed" => Self::Relaxed,
+            "ephemeral" => Self::Ephemeral,
+            x => return Err(x),
+        })
+    }
+}
+
+impl_serialize!([] StDurability, (self, ser) => ser.serialize_str(self.as_str()));
+impl_deserialize!([] StDurability, de => {
+    let value = de.deserialize_str_slice()?;
+    StDurability::try_from(value).map_err(|x| {
+        Error::custom(format!(
+            "DecodeError for StDurability: `{x}`. Expected 'durable' | 'relaxed' | 'ephemeral'"
+        ))
+    })
+});
+impl_st!([] StDurability, AlgebraicType::String);
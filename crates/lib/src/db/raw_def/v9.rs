@@ -20,6 +20,7 @@ use spacetimedb_sats::SpacetimeType;
 use spacetimedb_sats::Typespace;
 
 use crate::db::auth::StAccess;
+use crate::db::auth::StDurability;
 use crate::db::auth::StTableType;
 
 /// A not-yet-validated identifier.
@@ -45,6 +46,7 @@ pub type RawSql = Box<str>;
 ///         - indexes
 /// - module-level objects:
 ///     - reducers
+///     - views
 ///     - schedule definitions
 /// - binding-level objects:
 ///     - type aliases
@@ -197,6 +199,42 @@ impl From<TableAccess> for StAccess {
     }
 }
 
+/// Whether the updates to a table are written to the commitlog.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, SpacetimeType)]
+#[sats(crate = crate)]
+pub enum TableDurability {
+    /// Updates are written to the commitlog, and survive a restart.
+    #[default]
+    Durable,
+    /// Updates are broadcast to subscribers, but are not written to the commitlog,
+    /// so they stay off the critical path of committing to disk.
+    /// On restart, the table holds its rows as of the latest snapshot, if any,
+    /// and the updates committed since are lost.
+    /// A transaction which only updates relaxed tables takes no offset in the commitlog.
+    Relaxed,
+    /// Like [`TableDurability::Relaxed`], but the rows of the table are also left out of snapshots,
+    /// so the table lives only in memory and is empty after a restart.
+    Ephemeral,
+}
+impl From<StDurability> for TableDurability {
+    fn from(t: StDurability) -> Self {
+        match t {
+            StDurability::Durable => TableDurability::Durable,
+            StDurability::Relaxed => TableDurability::Relaxed,
+            StDurability::Ephemeral => TableDurability::Ephemeral,
+        }
+    }
+}
+impl From<TableDurability> for StDurability {
+    fn from(t: TableDurability) -> Self {
+        match t {
+            TableDurability::Durable => StDurability::Durable,
+            TableDurability::Relaxed => StDurability::Relaxed,
+            TableDurability::Ephemeral => StDurability::Ephemeral,
+        }
+    }
+}
+
 /// A sequence definition for a database table column.
 #[derive(Debug, Clone, SpacetimeType)]
 #[sats(crate = crate)]
@@ -344,7 +382,36 @@ pub struct RawRowLevelSecurityDefV9 {
 #[sats(crate = crate)]
 #[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
 #[non_exhaustive]
-pub enum RawMiscModuleExportV9 {}
+pub enum RawMiscModuleExportV9 {
+    /// A read-only view exported by the module.
+    View(RawViewDefV9),
+    /// The type of the values a reducer can fail with.
+    ReducerErrorType(RawReducerErrorTypeV9),
+    /// A column whose values are computed from the other columns of its table.
+    GeneratedColumn(RawGeneratedColumnDefV9),
+    /// The durability of a table, if not [`TableDurability::Durable`].
+    TableDurability(RawTableDurabilityDefV9),
+    /// An HTTP endpoint served by a view.
+    HttpRoute(RawHttpRouteDefV9),
+    /// A constraint on the values of a reducer argument.
+    ReducerArgConstraint(RawReducerArgConstraintDefV9),
+    /// The priority of a reducer, if not [`ReducerPriority::Normal`].
+    ReducerPriority(RawReducerPriorityDefV9),
+    /// How a scheduled table catches up on missed calls, if not [`CatchUpPolicy::FireAll`].
+    ScheduleCatchUp(RawScheduleCatchUpDefV9),
+    /// An additional name under which a reducer may be called.
+    ReducerAlias(RawReducerAliasDefV9),
+    /// A column counting the rows of another table which refer to its row.
+    Counter(RawCounterDefV9),
+    /// The history of a table, kept to read the table as of a recent transaction.
+    SoftDelete(RawSoftDeleteDefV9),
+    /// How long the updates to a table are held back and merged before they are sent to subscribers.
+    Coalesce(RawCoalesceDefV9),
+    /// The column of a table tying each row to the connection which must be open for it to exist.
+    BoundToConnection(RawBoundToConnectionDefV9),
+    /// The reducer deciding which clients may subscribe to which topics.
+    TopicAuthorizer(RawTopicAuthorizerDefV9),
+}
 
 /// A type declaration.
 ///
@@ -410,6 +477,320 @@ pub struct RawReducerDefV9 {
     pub lifecycle: Option<Lifecycle>,
 }
 
+/// A view definition.
+///
+/// A view is a read-only function of the database state and its arguments.
+/// Views cannot modify the database; the host invokes them with a read-only transaction.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawViewDefV9 {
+    /// The name of the view. Must be unique among the reducers and views of the module.
+    pub name: RawIdentifier,
+
+    /// The types and optional names of the parameters, in order.
+    /// This `ProductType` need not be registered in the typespace.
+    pub params: ProductType,
+
+    /// The type of the value returned by the view.
+    /// Must satisfy `AlgebraicType::is_valid_for_client_type_use`.
+    pub return_type: AlgebraicType,
+}
+
+/// The type of the values a reducer can fail with.
+///
+/// Reducers without one of these fail only with error messages.
+/// This is a misc export, rather than a field of [`RawReducerDefV9`],
+/// so that modules which don't use typed errors are described as before.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawReducerErrorTypeV9 {
+    /// The name of the reducer.
+    pub reducer: RawIdentifier,
+
+    /// The type of the reducer's error values.
+    /// Must satisfy `AlgebraicType::is_valid_for_client_type_use`.
+    pub error_type: AlgebraicType,
+}
+
+/// A column whose values are computed by the database from the other columns of its table,
+/// whenever a row is inserted or updated.
+///
+/// This is a misc export, rather than a field of [`RawTableDefV9`],
+/// so that modules which don't use generated columns are described as before.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawGeneratedColumnDefV9 {
+    /// The name of the table.
+    pub table: RawIdentifier,
+
+    /// The generated column.
+    pub column: ColId,
+
+    /// The expression computing the column's value,
+    /// e.g., `lower(name)`.
+    pub expr: Box<str>,
+}
+
+/// The durability of a table.
+///
+/// This is a misc export, rather than a field of [`RawTableDefV9`],
+/// so that modules whose tables are all durable are described as before.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawTableDurabilityDefV9 {
+    /// The name of the table.
+    pub table: RawIdentifier,
+
+    /// The durability of the table.
+    pub durability: TableDurability,
+}
+
+/// An HTTP method a module can serve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, SpacetimeType)]
+#[sats(crate = crate)]
+#[non_exhaustive]
+pub enum HttpMethod {
+    /// `GET`.
+    Get,
+}
+
+/// An HTTP endpoint of the module,
+/// served by the host under `/v1/database/:name_or_identity/http`.
+///
+/// The endpoint is served by calling a view, whose arguments are read from the query string
+/// and whose result is returned as JSON.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawHttpRouteDefV9 {
+    /// The method of the endpoint.
+    pub method: HttpMethod,
+
+    /// The path of the endpoint, relative to the module's HTTP root, e.g., `/leaderboard`.
+    /// Must start with `/`.
+    pub path: Box<str>,
+
+    /// The name of the view serving the endpoint.
+    pub view: RawIdentifier,
+}
+
+/// A constraint on the values of a reducer argument.
+///
+/// The host rejects calls whose arguments violate a constraint before running the reducer,
+/// and client code generation emits matching validators, so clients can check input without a round trip.
+/// This is a misc export, rather than a field of [`RawReducerDefV9`],
+/// so that modules which don't declare constraints are described as before.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawReducerArgConstraintDefV9 {
+    /// The name of the reducer.
+    pub reducer: RawIdentifier,
+
+    /// The position of the constrained argument among the reducer's parameters.
+    pub arg: u16,
+
+    /// The constraint.
+    pub constraint: ArgConstraint,
+}
+
+/// A constraint on the values of a reducer argument.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, SpacetimeType)]
+#[sats(crate = crate)]
+pub enum ArgConstraint {
+    /// The minimum length of a string or an array, inclusive.
+    ///
+    /// The length of a string is counted in Unicode scalar values, not bytes.
+    MinLength(u32),
+    /// The maximum length of a string or an array, inclusive.
+    ///
+    /// The length of a string is counted in Unicode scalar values, not bytes.
+    MaxLength(u32),
+    /// The minimum value of an integer, inclusive.
+    Min(i128),
+    /// The maximum value of an integer, inclusive.
+    Max(i128),
+    /// The permitted values of a string.
+    OneOf(Vec<Box<str>>),
+}
+
+/// The priority of a reducer.
+///
+/// This is a misc export, rather than a field of [`RawReducerDefV9`],
+/// so that modules whose reducers all have normal priority are described as before.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawReducerPriorityDefV9 {
+    /// The name of the reducer.
+    pub reducer: RawIdentifier,
+
+    /// The priority of the reducer.
+    pub priority: ReducerPriority,
+}
+
+/// An additional name under which a reducer may be called, e.g. its name before it was renamed.
+///
+/// This is a misc export, rather than a field of [`RawReducerDefV9`],
+/// so that modules without aliases are described as before.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawReducerAliasDefV9 {
+    /// The name of the reducer.
+    pub reducer: RawIdentifier,
+
+    /// The alias. Must not be the name of any reducer, or an alias of another.
+    pub alias: RawIdentifier,
+
+    /// If set, calls made under the alias are deprecated, and the host logs this note when one is made.
+    pub deprecated: Option<Box<str>>,
+}
+
+/// How a scheduled table catches up on the calls which fell due while the database was offline.
+///
+/// This is a misc export, rather than a field of [`RawScheduleDefV9`],
+/// so that modules whose scheduled tables all fire every missed call are described as before.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawScheduleCatchUpDefV9 {
+    /// The name of the scheduled table.
+    pub table: RawIdentifier,
+
+    /// The catch-up policy of the table.
+    pub policy: CatchUpPolicy,
+}
+
+/// A column which the database keeps equal to the number of rows of another table grouped under its row,
+/// e.g., the number of elements on each canvas.
+///
+/// The rows of `table` are grouped by the value of their `group_by` column,
+/// which must be the primary key of a row of `into_table` for the row to be counted.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawCounterDefV9 {
+    /// The name of the table whose rows are counted.
+    pub table: RawIdentifier,
+
+    /// The column of `table` holding the primary key of the row to count each row under.
+    pub group_by: ColId,
+
+    /// The name of the table holding the counts.
+    pub into_table: RawIdentifier,
+
+    /// The name of the column of `into_table` holding the counts.
+    pub into_column: RawIdentifier,
+}
+
+/// Keeps the recent history of a table, so that it can be read as it was after a recent transaction,
+/// e.g., with `SELECT * FROM canvas AS OF 1234` in SQL.
+///
+/// Rather than being forgotten, deleted rows are kept as tombstones recording the transaction which deleted them,
+/// and inserted rows record the transaction which inserted them.
+/// History older than `retention` transactions is discarded.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawSoftDeleteDefV9 {
+    /// The name of the table.
+    pub table: RawIdentifier,
+
+    /// How many of the most recent transactions the table can be read as of.
+    /// Must be positive.
+    pub retention: u64,
+}
+
+/// Coalesces the updates of a rapidly-updated table, e.g., one holding cursor positions.
+///
+/// Rather than being sent each transaction's updates to the table right away,
+/// each subscriber is sent them at most once every `window_ms` milliseconds,
+/// with the updates to rows with the same primary key merged into one.
+/// The table must have a primary key.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawCoalesceDefV9 {
+    /// The name of the table.
+    pub table: RawIdentifier,
+
+    /// How long, in milliseconds, updates are held back.
+    /// Must be positive.
+    pub window_ms: u64,
+}
+
+/// Binds the rows of a table to client connections, e.g., the rows of a presence or typing-indicator table.
+///
+/// When a client disconnects, the rows whose `column` holds the `Address` of its connection
+/// are deleted in the same transaction which records the disconnection.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawBoundToConnectionDefV9 {
+    /// The name of the table.
+    pub table: RawIdentifier,
+
+    /// The column holding the address of the connection each row is bound to.
+    /// Must be of type `Address`.
+    ///
+    /// If `None`, the table must have exactly one column of type `Address`, which is used.
+    pub column: Option<ColId>,
+}
+
+/// The reducer called whenever a client subscribes to a topic, to decide whether it may.
+///
+/// The reducer takes the topic, a `String`, as its only argument,
+/// and is called with the identity and address of the subscribing client.
+/// The subscription is refused if it returns an error, and its changes to the database are rolled back.
+/// Modules without a topic authorizer let every client subscribe to every topic.
+#[derive(Debug, Clone, SpacetimeType)]
+#[sats(crate = crate)]
+#[cfg_attr(feature = "test", derive(PartialEq, Eq, PartialOrd, Ord))]
+pub struct RawTopicAuthorizerDefV9 {
+    /// The name of the reducer.
+    pub reducer: RawIdentifier,
+}
+
+/// What happens to the rows of a scheduled table whose `ScheduleAt::Time` passed while the database was offline.
+///
+/// Rows with a `ScheduleAt::Interval` don't record when they last fired,
+/// so they always resume one period after the database starts, regardless of the policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, SpacetimeType)]
+#[sats(crate = crate)]
+pub enum CatchUpPolicy {
+    /// Delete the missed rows without calling the reducer.
+    SkipMissed,
+    /// Call the reducer for the most recently missed row only, and delete the others.
+    FireOnce,
+    /// Call the reducer for every missed row, as soon as the database starts.
+    #[default]
+    FireAll,
+}
+
+/// How urgently the calls of a reducer are run when the database is busy.
+///
+/// Calls waiting for the database are run in order of priority, and in the order they arrived within a priority,
+/// so latency-sensitive reducers can jump ahead of batch jobs.
+/// A call which is already running is never interrupted.
+///
+/// Priorities are ordered from the most to the least urgent.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, SpacetimeType)]
+#[sats(crate = crate)]
+pub enum ReducerPriority {
+    /// Run before calls of any other priority.
+    High,
+    /// The priority of reducers which don't declare one.
+    #[default]
+    Normal,
+    /// Run only when no calls of a higher priority are waiting, e.g. for cleanup or autosave jobs.
+    Low,
+}
+
 /// Special roles a reducer can play in the module lifecycle.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, SpacetimeType)]
 #[cfg_attr(feature = "enum-map", derive(enum_map::Enum))]
@@ -591,6 +972,159 @@ impl RawModuleDefV9Builder {
         });
     }
 
+    /// Declare that the reducer `reducer` fails with values of type `error_type`,
+    /// rather than with error messages.
+    pub fn add_reducer_error_type(&mut self, reducer: impl Into<RawIdentifier>, error_type: AlgebraicType) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::ReducerErrorType(RawReducerErrorTypeV9 {
+                reducer: reducer.into(),
+                error_type,
+            }));
+    }
+
+    /// Constrain the values of the argument at position `arg` of the reducer `reducer`.
+    pub fn add_reducer_arg_constraint(
+        &mut self,
+        reducer: impl Into<RawIdentifier>,
+        arg: u16,
+        constraint: ArgConstraint,
+    ) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::ReducerArgConstraint(
+                RawReducerArgConstraintDefV9 {
+                    reducer: reducer.into(),
+                    arg,
+                    constraint,
+                },
+            ));
+    }
+
+    /// Set the priority of the reducer `reducer`.
+    pub fn add_reducer_priority(&mut self, reducer: impl Into<RawIdentifier>, priority: ReducerPriority) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::ReducerPriority(RawReducerPriorityDefV9 {
+                reducer: reducer.into(),
+                priority,
+            }));
+    }
+
+    /// Allow the reducer `reducer` to also be called as `alias`.
+    ///
+    /// If `deprecated` is set, the host logs it as a warning whenever a call is made under `alias`.
+    pub fn add_reducer_alias(
+        &mut self,
+        reducer: impl Into<RawIdentifier>,
+        alias: impl Into<RawIdentifier>,
+        deprecated: Option<Box<str>>,
+    ) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::ReducerAlias(RawReducerAliasDefV9 {
+                reducer: reducer.into(),
+                alias: alias.into(),
+                deprecated,
+            }));
+    }
+
+    /// Count the rows of the table `table` in the column `into_column` of `into_table`,
+    /// grouping them by their `group_by` column, which holds the primary key of the row to count them under.
+    ///
+    /// Equivalent to [`RawTableDefBuilder::with_counter`], for tables which have already been built.
+    pub fn add_counter(
+        &mut self,
+        table: impl Into<RawIdentifier>,
+        group_by: impl Into<ColId>,
+        into_table: impl Into<RawIdentifier>,
+        into_column: impl Into<RawIdentifier>,
+    ) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::Counter(RawCounterDefV9 {
+                table: table.into(),
+                group_by: group_by.into(),
+                into_table: into_table.into(),
+                into_column: into_column.into(),
+            }));
+    }
+
+    /// Keep the history of the last `retention` transactions of the table `table`.
+    ///
+    /// Equivalent to [`RawTableDefBuilder::with_soft_delete`], for tables which have already been built.
+    pub fn add_soft_delete(&mut self, table: impl Into<RawIdentifier>, retention: u64) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::SoftDelete(RawSoftDeleteDefV9 {
+                table: table.into(),
+                retention,
+            }));
+    }
+
+    /// Coalesce the updates to the table `table` sent to subscribers over windows of `window_ms` milliseconds.
+    ///
+    /// Equivalent to [`RawTableDefBuilder::with_coalesce`], for tables which have already been built.
+    pub fn add_coalesce(&mut self, table: impl Into<RawIdentifier>, window_ms: u64) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::Coalesce(RawCoalesceDefV9 {
+                table: table.into(),
+                window_ms,
+            }));
+    }
+
+    /// Delete the rows of the table `table` whose `column` holds the address of a connection when it disconnects.
+    ///
+    /// Equivalent to [`RawTableDefBuilder::with_bound_to_connection`], for tables which have already been built.
+    pub fn add_bound_to_connection(&mut self, table: impl Into<RawIdentifier>, column: Option<ColId>) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::BoundToConnection(RawBoundToConnectionDefV9 {
+                table: table.into(),
+                column,
+            }));
+    }
+
+    /// Make the reducer `reducer` decide which clients may subscribe to which topics.
+    ///
+    /// See [`RawTopicAuthorizerDefV9`].
+    pub fn set_topic_authorizer(&mut self, reducer: impl Into<RawIdentifier>) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::TopicAuthorizer(RawTopicAuthorizerDefV9 {
+                reducer: reducer.into(),
+            }));
+    }
+
+    /// Add a view to the in-progress module.
+    ///
+    /// As with [`Self::add_reducer`], the view's context argument is an implementation detail of the
+    /// module bindings and must not be included in `params`.
+    pub fn add_view(
+        &mut self,
+        name: impl Into<RawIdentifier>,
+        params: spacetimedb_sats::ProductType,
+        return_type: AlgebraicType,
+    ) {
+        self.module.misc_exports.push(RawMiscModuleExportV9::View(RawViewDefV9 {
+            name: name.into(),
+            params,
+            return_type,
+        }));
+    }
+
+    /// Serve `method` requests to `path` by calling the view `view`.
+    pub fn add_http_route(&mut self, method: HttpMethod, path: impl Into<Box<str>>, view: impl Into<RawIdentifier>) {
+        self.module
+            .misc_exports
+            .push(RawMiscModuleExportV9::HttpRoute(RawHttpRouteDefV9 {
+                method,
+                path: path.into(),
+                view: view.into(),
+            }));
+    }
+
     /// Add a row-level security policy to the module.
     ///
     /// The `sql` expression should be a valid SQL expression that will be used to filter rows.
@@ -730,20 +1264,54 @@ impl<'a> RawTableDefBuilder<'a> {
     }
 
     /// Adds a [RawSequenceDef] on the supplied `column`.
-    pub fn with_column_sequence(mut self, column: impl Into<ColId>) -> Self {
+    pub fn with_column_sequence(self, column: impl Into<ColId>) -> Self {
+        self.with_column_sequence_config(column, None, 1)
+    }
+
+    /// Adds a [RawSequenceDef] on the supplied `column`,
+    /// starting at `start`, if provided, and moving by `increment` for each new value.
+    pub fn with_column_sequence_config(
+        mut self,
+        column: impl Into<ColId>,
+        start: Option<i128>,
+        increment: i128,
+    ) -> Self {
         let column = column.into();
         self.table.sequences.push(RawSequenceDefV9 {
             name: None,
             column,
-            start: None,
+            start,
             min_value: None,
             max_value: None,
-            increment: 1,
+            increment,
         });
 
         self
     }
 
+    /// Makes `column` a generated column, whose value is computed from `expr`.
+    pub fn with_generated_column(self, column: impl Into<ColId>, expr: impl Into<Box<str>>) -> Self {
+        self.module_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::GeneratedColumn(RawGeneratedColumnDefV9 {
+                table: self.table.name.clone(),
+                column: column.into(),
+                expr: expr.into(),
+            }));
+        self
+    }
+
+    /// Sets the durability of the table.
+    pub fn with_durability(self, durability: TableDurability) -> Self {
+        self.module_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::TableDurability(RawTableDurabilityDefV9 {
+                table: self.table.name.clone(),
+                durability,
+            }));
+        self
+    }
+
     /// Adds a schedule definition to the table.
     ///
     /// The table must have the appropriate columns for a scheduled table.
@@ -762,6 +1330,75 @@ impl<'a> RawTableDefBuilder<'a> {
         self
     }
 
+    /// Sets how the table catches up on calls missed while the database was offline.
+    ///
+    /// The table must also have a schedule.
+    pub fn with_catch_up(self, policy: CatchUpPolicy) -> Self {
+        self.module_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::ScheduleCatchUp(RawScheduleCatchUpDefV9 {
+                table: self.table.name.clone(),
+                policy,
+            }));
+        self
+    }
+
+    /// Counts the rows of this table in the column `into_column` of `into_table`,
+    /// grouping them by their `group_by` column, which holds the primary key of the row to count them under.
+    pub fn with_counter(
+        self,
+        group_by: impl Into<ColId>,
+        into_table: impl Into<RawIdentifier>,
+        into_column: impl Into<RawIdentifier>,
+    ) -> Self {
+        self.module_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::Counter(RawCounterDefV9 {
+                table: self.table.name.clone(),
+                group_by: group_by.into(),
+                into_table: into_table.into(),
+                into_column: into_column.into(),
+            }));
+        self
+    }
+
+    /// Keeps the history of the last `retention` transactions of the table,
+    /// so that it can be read as of any of them.
+    pub fn with_soft_delete(self, retention: u64) -> Self {
+        self.module_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::SoftDelete(RawSoftDeleteDefV9 {
+                table: self.table.name.clone(),
+                retention,
+            }));
+        self
+    }
+
+    /// Coalesces the updates to the table sent to subscribers over windows of `window_ms` milliseconds,
+    /// merging the updates to rows with the same primary key.
+    pub fn with_coalesce(self, window_ms: u64) -> Self {
+        self.module_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::Coalesce(RawCoalesceDefV9 {
+                table: self.table.name.clone(),
+                window_ms,
+            }));
+        self
+    }
+
+    /// Deletes the rows of the table whose `column` holds the address of a connection when it disconnects.
+    ///
+    /// If `column` is `None`, the table's only column of type `Address` is used.
+    pub fn with_bound_to_connection(self, column: Option<ColId>) -> Self {
+        self.module_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::BoundToConnection(RawBoundToConnectionDefV9 {
+                table: self.table.name.clone(),
+                column,
+            }));
+        self
+    }
+
     /// Build the table and add it to the module, returning the `product_type_ref` of the table.
     pub fn finish(self) -> AlgebraicTypeRef {
         self.table.product_type_ref
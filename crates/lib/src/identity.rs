@@ -173,8 +173,20 @@ impl Identity {
         spacetimedb_sats::hex::encode(&self.abbreviate())
     }
 
-    pub fn from_hex(hex: impl AsRef<[u8]>) -> Result<Self, hex::FromHexError> {
-        hex::FromHex::from_hex(hex)
+    /// Returns the short form of this `Identity` used in logs and user-facing messages,
+    /// i.e. the first 16 characters of its hexadecimal representation.
+    ///
+    /// This is also what `format!("{identity:#}")` prints.
+    pub fn to_abbreviated(&self) -> HexString<8> {
+        self.to_abbreviated_hex()
+    }
+
+    /// Parses an `Identity` from its hexadecimal representation,
+    /// as printed by its `Display` impl or [`Self::to_hex`].
+    ///
+    /// The string must be 64 hex characters long, and may be prefixed with `0x`.
+    pub fn from_hex(hex: impl AsRef<[u8]>) -> Result<Self, ParseIdentityError> {
+        hex::FromHex::from_hex(hex).map_err(ParseIdentityError)
     }
 
     pub fn from_hashing_bytes(bytes: impl AsRef<[u8]>) -> Self {
@@ -182,9 +194,15 @@ impl Identity {
     }
 }
 
+/// Prints the full hexadecimal representation of the `Identity`,
+/// or, with the alternate flag (`{:#}`), its [abbreviated](Identity::to_abbreviated) form.
 impl fmt::Display for Identity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.pad(&self.to_hex())
+        if f.alternate() {
+            f.pad(&self.to_abbreviated())
+        } else {
+            f.pad(&self.to_hex())
+        }
     }
 }
 
@@ -202,8 +220,13 @@ impl hex::FromHex for Identity {
     }
 }
 
+/// An error from parsing an [`Identity`] out of a string.
+#[derive(thiserror::Error, Debug, Clone, PartialEq)]
+#[error("an identity must be 64 hex characters (32 bytes), optionally prefixed with `0x`: {0}")]
+pub struct ParseIdentityError(hex::FromHexError);
+
 impl FromStr for Identity {
-    type Err = <Self as hex::FromHex>::Error;
+    type Err = ParseIdentityError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         Self::from_hex(s)
@@ -269,6 +292,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn identity_parse_errors() {
+        assert!("".parse::<Identity>().is_err());
+        assert!("c200".parse::<Identity>().is_err());
+        assert!(Identity::from_hex("zz".repeat(32)).is_err());
+        assert_eq!(format!("{:#}", Identity::ZERO), "0".repeat(16));
+    }
+
     proptest! {
         #[test]
         fn identity_conversions(w0: u128, w1: u128) {
@@ -277,6 +308,10 @@ mod tests {
             prop_assert_eq!(Identity::from_byte_array(v.to_byte_array()), v);
             prop_assert_eq!(Identity::from_be_byte_array(v.to_be_byte_array()), v);
             prop_assert_eq!(Identity::from_hex(v.to_hex()).unwrap(), v);
+            prop_assert_eq!(v.to_string().parse::<Identity>().unwrap(), v);
+            prop_assert_eq!(format!("0x{}", v).parse::<Identity>().unwrap(), v);
+            let abbreviated = format!("{:#}", v);
+            prop_assert!(v.to_string().starts_with(&abbreviated));
 
             let de1: Identity = serde_json::from_str(&serde_json::to_string(&v).unwrap()).unwrap();
             prop_assert_eq!(de1, v);
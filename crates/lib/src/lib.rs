@@ -7,6 +7,9 @@ use std::any::TypeId;
 use std::collections::{btree_map, BTreeMap};
 
 pub mod address;
+pub mod assets;
+pub mod batch;
+pub mod client_metadata;
 pub mod db;
 pub mod error;
 pub mod identity;
@@ -24,7 +27,7 @@ pub mod type_value {
 }
 
 pub use address::Address;
-pub use identity::Identity;
+pub use identity::{Identity, ParseIdentityError};
 pub use scheduler::ScheduleAt;
 pub use spacetimedb_sats::hash::{self, hash_bytes, Hash};
 pub use spacetimedb_sats::SpacetimeType;
@@ -3,9 +3,9 @@ use std::{fmt::Debug, time::Duration};
 use spacetimedb_sats::{
     algebraic_value::de::{ValueDeserializeError, ValueDeserializer},
     de::Deserialize,
-    impl_st,
+    impl_deserialize, impl_serialize, impl_st,
     ser::Serialize,
-    AlgebraicType, AlgebraicValue,
+    AlgebraicType, AlgebraicValue, SumValue,
 };
 
 /// When a scheduled reducer should execute,
@@ -15,47 +15,161 @@ use spacetimedb_sats::{
 /// Stored in reducer-scheduling tables as a column.
 ///
 /// This is a special type.
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub enum ScheduleAt {
     /// A regular interval at which the repeated reducer is scheduled.
-    /// Value is a duration in microseconds.
-    Interval(u64),
+    Interval {
+        /// The time between calls, in microseconds.
+        period: u64,
+        /// The upper bound, in microseconds, of a random delay added to each call,
+        /// so that many timers with the same period don't all fire at once.
+        jitter: u64,
+        /// Whether calls are due every `period` since the first one (fixed rate),
+        /// rather than `period` after the previous call has finished (fixed delay).
+        ///
+        /// With a fixed rate, calls that would be due while a previous call is still running
+        /// are skipped rather than run back-to-back.
+        fixed_rate: bool,
+    },
     /// A specific time to which the reducer is scheduled.
     /// Value is a UNIX timestamp in microseconds.
     Time(u64),
 }
 impl_st!([] ScheduleAt, ScheduleAt::get_type());
+impl_serialize!([] ScheduleAt, (self, ser) => ScheduleAtRepr::from(*self).serialize(ser));
+impl_deserialize!([] ScheduleAt, de => ScheduleAtRepr::deserialize(de).map(Into::into));
+
+/// The SATS representation of [`ScheduleAt`],
+/// as the derives don't support variants with several fields.
+#[derive(Serialize, Deserialize)]
+enum ScheduleAtRepr {
+    Interval(IntervalRepr),
+    Time(u64),
+}
+
+#[derive(Serialize, Deserialize)]
+struct IntervalRepr {
+    period: u64,
+    jitter: u64,
+    fixed_rate: bool,
+}
+
+impl From<ScheduleAt> for ScheduleAtRepr {
+    fn from(value: ScheduleAt) -> Self {
+        match value {
+            ScheduleAt::Interval {
+                period,
+                jitter,
+                fixed_rate,
+            } => Self::Interval(IntervalRepr {
+                period,
+                jitter,
+                fixed_rate,
+            }),
+            ScheduleAt::Time(time) => Self::Time(time),
+        }
+    }
+}
+
+impl From<ScheduleAtRepr> for ScheduleAt {
+    fn from(value: ScheduleAtRepr) -> Self {
+        match value {
+            ScheduleAtRepr::Interval(IntervalRepr {
+                period,
+                jitter,
+                fixed_rate,
+            }) => Self::Interval {
+                period,
+                jitter,
+                fixed_rate,
+            },
+            ScheduleAtRepr::Time(time) => Self::Time(time),
+        }
+    }
+}
 
 impl ScheduleAt {
+    /// Returns a fixed-delay, unjittered schedule repeating every `period`.
+    pub fn interval(period: Duration) -> Self {
+        ScheduleAt::Interval {
+            period: period.as_micros() as u64,
+            jitter: 0,
+            fixed_rate: false,
+        }
+    }
+
+    /// Adds a random delay of at most `jitter` to each call of an interval schedule.
+    ///
+    /// Has no effect on a `Time` schedule.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        if let ScheduleAt::Interval { jitter: j, .. } = &mut self {
+            *j = jitter.as_micros() as u64;
+        }
+        self
+    }
+
+    /// Makes an interval schedule fixed-rate rather than fixed-delay.
+    ///
+    /// Has no effect on a `Time` schedule.
+    pub fn fixed_rate(mut self) -> Self {
+        if let ScheduleAt::Interval { fixed_rate, .. } = &mut self {
+            *fixed_rate = true;
+        }
+        self
+    }
+
+    /// Returns the maximum jitter of this schedule.
+    pub fn jitter(&self) -> Duration {
+        match self {
+            ScheduleAt::Interval { jitter, .. } => Duration::from_micros(*jitter),
+            ScheduleAt::Time(_) => Duration::ZERO,
+        }
+    }
+
     /// Converts the `ScheduleAt` to a `std::time::Duration` from now.
     pub fn to_duration_from_now(&self) -> std::time::Duration {
+        self.to_duration_from(std::time::SystemTime::now())
+    }
+
+    /// Converts the `ScheduleAt` to a `std::time::Duration` from `now`.
+    pub fn to_duration_from(&self, now: std::time::SystemTime) -> std::time::Duration {
         match self {
             ScheduleAt::Time(time) => {
-                let now = std::time::SystemTime::now();
                 // Safety: Now is always after UNIX_EPOCH.
                 let now = now.duration_since(std::time::UNIX_EPOCH).unwrap();
                 let time = std::time::Duration::from_micros(*time);
                 time.checked_sub(now).unwrap_or(Duration::from_micros(0))
             }
-            ScheduleAt::Interval(dur) => Duration::from_micros(*dur),
+            ScheduleAt::Interval { period, .. } => Duration::from_micros(*period),
         }
     }
 
     /// Get the special `AlgebraicType` for `ScheduleAt`.
     pub fn get_type() -> AlgebraicType {
-        AlgebraicType::sum([("Interval", AlgebraicType::U64), ("Time", AlgebraicType::U64)])
+        let interval = AlgebraicType::product([
+            ("period", AlgebraicType::U64),
+            ("jitter", AlgebraicType::U64),
+            ("fixed_rate", AlgebraicType::Bool),
+        ]);
+        AlgebraicType::sum([("Interval", interval), ("Time", AlgebraicType::U64)])
     }
 }
 
 impl From<std::time::Duration> for ScheduleAt {
     fn from(value: std::time::Duration) -> Self {
-        ScheduleAt::Interval(value.as_micros() as u64)
+        ScheduleAt::interval(value)
     }
 }
 
 impl TryFrom<AlgebraicValue> for ScheduleAt {
     type Error = ValueDeserializeError;
     fn try_from(value: AlgebraicValue) -> Result<Self, Self::Error> {
+        // Modules built against older bindings store intervals as a bare `u64` of microseconds.
+        if let AlgebraicValue::Sum(SumValue { tag: 0, value }) = &value {
+            if let AlgebraicValue::U64(period) = **value {
+                return Ok(ScheduleAt::interval(Duration::from_micros(period)));
+            }
+        }
         ScheduleAt::deserialize(ValueDeserializer::new(value))
     }
 }
@@ -67,12 +181,25 @@ mod tests {
 
     #[test]
     fn test_bsatn_roundtrip() {
-        let schedule_at = ScheduleAt::Interval(10000);
+        let schedule_at = ScheduleAt::Interval {
+            period: 10000,
+            jitter: 500,
+            fixed_rate: true,
+        };
         let ser = bsatn::to_vec(&schedule_at).unwrap();
         let de = bsatn::from_slice(&ser).unwrap();
         assert_eq!(schedule_at, de);
     }
 
+    #[test]
+    fn legacy_interval_from_value() {
+        let value = AlgebraicValue::sum(0, AlgebraicValue::U64(10000));
+        assert_eq!(
+            ScheduleAt::try_from(value).unwrap(),
+            ScheduleAt::interval(Duration::from_micros(10000))
+        );
+    }
+
     #[test]
     fn schedule_at_is_special() {
         assert!(ScheduleAt::get_type().is_special());
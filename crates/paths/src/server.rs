@@ -25,6 +25,10 @@ impl ServerDataDir {
         MetadataTomlPath(self.0.join("metadata.toml"))
     }
 
+    pub fn acme(&self) -> AcmeDir {
+        AcmeDir(self.0.join("acme"))
+    }
+
     pub fn pid_file(&self) -> Result<PidFile, PidFileError> {
         use fs2::FileExt;
         use io::{Read, Write};
@@ -97,6 +101,34 @@ path_type! {
     MetadataTomlPath: file
 }
 
+path_type! {
+    /// The directory where the server keeps the certificate it obtained from an ACME
+    /// certificate authority, and the key of its account there. `{data-dir}/acme`
+    AcmeDir: dir
+}
+
+impl AcmeDir {
+    /// The private key of the server's account with the certificate authority.
+    pub fn account_key(&self) -> PemFile {
+        PemFile(self.0.join("account.key.pem"))
+    }
+
+    /// The certificate chain the server serves, leaf certificate first.
+    pub fn cert_chain(&self) -> PemFile {
+        PemFile(self.0.join("cert.pem"))
+    }
+
+    /// The private key of the certificate the server serves.
+    pub fn cert_key(&self) -> PemFile {
+        PemFile(self.0.join("cert.key.pem"))
+    }
+}
+
+path_type! {
+    /// A PEM-encoded certificate or key.
+    PemFile: file
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum PidFileError {
     #[error("error while taking database lock on spacetime.pid")]
@@ -971,6 +971,7 @@ mod tests {
     use spacetimedb_expr::check::{parse_and_type_sub, SchemaView};
     use spacetimedb_lib::{
         db::auth::{StAccess, StTableType},
+        query::Delta,
         AlgebraicType, AlgebraicValue,
     };
     use spacetimedb_primitives::{ColId, ColList, ColSet, TableId};
@@ -1663,4 +1664,79 @@ mod tests {
             proj => panic!("unexpected plan: {:#?}", proj),
         };
     }
+
+    /// Joining a delta table with a static table probes the index of the static table,
+    /// regardless of which side of the join the delta table is on.
+    ///
+    /// ```text
+    ///   rx
+    ///  /  \
+    /// dp   u
+    /// ```
+    #[test]
+    fn delta_index_joins() {
+        let u_id = TableId(1);
+        let p_id = TableId(2);
+
+        let u = Arc::new(schema(
+            u_id,
+            "u",
+            &[("id", AlgebraicType::U64), ("name", AlgebraicType::String)],
+            &[&[0]],
+            &[&[0]],
+            Some(0),
+        ));
+
+        let p = Arc::new(schema(
+            p_id,
+            "p",
+            &[
+                ("x", AlgebraicType::U64),
+                ("y", AlgebraicType::U64),
+                ("u_id", AlgebraicType::U64),
+            ],
+            &[&[2]],
+            &[],
+            None,
+        ));
+
+        let db = SchemaViewer {
+            schemas: vec![u.clone(), p.clone()],
+        };
+
+        for sql in [
+            "select u.* from u join p on u.id = p.u_id",
+            "select u.* from p join u on u.id = p.u_id",
+        ] {
+            let lp = parse_and_type_sub(sql, &db).unwrap();
+            let mut pp = compile_project_plan(lp);
+            pp.visit_mut(&mut |plan| match plan {
+                PhysicalPlan::TableScan(schema, _, delta) if schema.table_id == p_id => {
+                    *delta = Some(Delta::Inserts(1));
+                }
+                _ => {}
+            });
+
+            match pp.optimize() {
+                ProjectPlan::None(PhysicalPlan::IxJoin(
+                    IxJoin {
+                        lhs,
+                        rhs,
+                        rhs_field: ColId(0),
+                        unique: true,
+                        lhs_field: TupleField { field_pos: 2, .. },
+                        ..
+                    },
+                    Semi::Rhs,
+                )) => {
+                    assert_eq!(rhs.table_id, u_id);
+                    assert!(matches!(
+                        &*lhs,
+                        PhysicalPlan::TableScan(schema, _, Some(Delta::Inserts(1))) if schema.table_id == p_id
+                    ));
+                }
+                proj => panic!("unexpected project: {:#?}", proj),
+            };
+        }
+    }
 }
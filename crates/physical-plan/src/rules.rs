@@ -851,6 +851,11 @@ impl RewriteRule for ReorderHashJoin {
 ///     |
 ///     b
 /// ```
+///
+/// Either side may or may not be filtered.
+/// The delta table then drives the join,
+/// probing the index on the join column of the other table,
+/// rather than the other table being scanned in its entirety.
 pub(crate) struct ReorderDeltaJoinRhs;
 
 impl RewriteRule for ReorderDeltaJoinRhs {
@@ -859,11 +864,7 @@ impl RewriteRule for ReorderDeltaJoinRhs {
 
     fn matches(plan: &Self::Plan) -> Option<Self::Info> {
         if let PhysicalPlan::HashJoin(HashJoin { lhs, rhs, .. }, Semi::All) = plan {
-            if let PhysicalPlan::Filter(input, _) = &**lhs {
-                return (matches!(&**input, PhysicalPlan::TableScan(_, _, None))
-                    && matches!(&**rhs, PhysicalPlan::TableScan(_, _, Some(_))))
-                .then_some(());
-            }
+            return (is_scan(lhs, false) && is_scan(rhs, true)).then_some(());
         }
         None
     }
@@ -874,7 +875,9 @@ impl RewriteRule for ReorderDeltaJoinRhs {
                 HashJoin {
                     lhs: join.rhs,
                     rhs: join.lhs,
-                    ..join
+                    lhs_field: join.rhs_field,
+                    rhs_field: join.lhs_field,
+                    unique: false,
                 },
                 Semi::All,
             ),
@@ -885,7 +888,7 @@ impl RewriteRule for ReorderDeltaJoinRhs {
 
 /// Pull a filter above a hash join if:
 ///
-/// 1. The lhs is a delta table
+/// 1. The lhs is a delta table, possibly filtered
 /// 2. The rhs has an index for the join
 ///
 /// ```text
@@ -919,7 +922,7 @@ impl RewriteRule for PullFilterAboveHashJoin {
         {
             if let PhysicalPlan::Filter(input, _) = &**rhs {
                 if let PhysicalPlan::TableScan(schema, _, None) = &**input {
-                    return (matches!(&**lhs, PhysicalPlan::TableScan(_, _, Some(_)))
+                    return (is_scan(lhs, true)
                         && schema.indexes.iter().any(|schema| {
                             schema
                                 .index_algorithm
@@ -954,6 +957,15 @@ impl RewriteRule for PullFilterAboveHashJoin {
     }
 }
 
+/// Is this a scan of a table, or of a delta table if `delta`, possibly filtered?
+fn is_scan(plan: &PhysicalPlan, delta: bool) -> bool {
+    match plan {
+        PhysicalPlan::Filter(input, _) => is_scan(input, delta),
+        PhysicalPlan::TableScan(_, _, is_delta) => is_delta.is_some() == delta,
+        _ => false,
+    }
+}
+
 /// Always prefer an index join to a hash join
 pub(crate) struct HashToIxJoin;
 
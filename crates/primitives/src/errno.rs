@@ -22,6 +22,13 @@ macro_rules! errnos {
             SCHEDULE_AT_DELAY_TOO_LONG(13, "Specified delay in scheduling row was too long"),
             INDEX_NOT_UNIQUE(14, "The index was not unique"),
             NO_SUCH_ROW(15, "The row was not found, e.g., in an update call"),
+            NO_SUCH_SAVEPOINT(16, "The provided savepoint is not valid"),
+            HOST_CALL_FAILURE_VALUE(17, "ABI called by host returned a BSATN-encoded error value"),
+            NOT_A_BLOB_TABLE(18, "The table does not have the column layout of a blob table"),
+            BLOB_OUT_OF_BOUNDS(19, "The offset is past the end of the blob"),
+            NO_SUCH_SEQUENCE(20, "The column has no sequence"),
+            NO_SUCH_ASSET(21, "No asset exists at the given path"),
+            NO_SUCH_SESSION_VARIABLE(22, "No session variable is set under the given key"),
         );
     };
 }
@@ -113,3 +113,9 @@ system_id! {
     // This is never stored in a system table, but is useful to have defined here.
     pub struct ReducerId(pub u32);
 }
+
+system_id! {
+    /// The index of a view as defined in a module's views list.
+    // This is never stored in a system table, but is useful to have defined here.
+    pub struct ViewId(pub u32);
+}
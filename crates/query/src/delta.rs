@@ -1,9 +1,12 @@
-use std::ops::Deref;
+use std::{borrow::Cow, ops::Deref};
 
 use anyhow::{bail, Result};
 use itertools::Either;
 use spacetimedb_execution::{pipelined::PipelinedProject, Datastore, DeltaStore, Row};
-use spacetimedb_expr::check::{type_subscription, SchemaView};
+use spacetimedb_expr::{
+    check::{type_top_n_subscription, SchemaView},
+    expr::TopN,
+};
 use spacetimedb_lib::query::Delta;
 use spacetimedb_physical_plan::{
     compile::compile_project_plan,
@@ -12,7 +15,7 @@ use spacetimedb_physical_plan::{
 use spacetimedb_primitives::TableId;
 use spacetimedb_sql_parser::parser::sub::parse_subscription;
 
-use crate::MAX_SQL_LENGTH;
+use crate::{top_n::TopNWindow, MAX_SQL_LENGTH};
 
 /// A delta plan performs incremental view maintenance
 #[derive(Debug)]
@@ -38,7 +41,7 @@ impl DeltaPlan {
             bail!("SQL query exceeds maximum allowed length: \"{sql:.120}...\"")
         }
         let ast = parse_subscription(sql)?;
-        let sub = type_subscription(ast, tx)?;
+        let (sub, top_n) = type_top_n_subscription(ast, tx)?;
 
         let Some(table_id) = sub.table_id() else {
             bail!("Failed to determine TableId for query")
@@ -83,6 +86,7 @@ impl DeltaPlan {
                 table_id,
                 table_name,
                 plan,
+                top_n,
             })),
             [(lhs_table, lhs_label), (rhs_table, rhs_label)] => Ok(Self::Join(JoinPlan {
                 table_id,
@@ -91,6 +95,7 @@ impl DeltaPlan {
                 rhs_label: *rhs_label,
                 lhs_table: *lhs_table,
                 rhs_table: *rhs_table,
+                deltas: JoinDeltaPlans::new(&plan, *lhs_label, *rhs_label),
                 plan,
             })),
             _ => bail!("Subscriptions cannot join more than 2 tables"),
@@ -121,8 +126,23 @@ impl DeltaPlan {
         }
     }
 
+    /// The ORDER BY and LIMIT of a top-N subscription.
+    /// The other methods of a delta plan ignore them.
+    pub fn top_n(&self) -> Option<TopN> {
+        match self {
+            Self::Select(plan) => plan.top_n,
+            Self::Join(_) => None,
+        }
+    }
+
+    /// Returns a window for incrementally maintaining the rows of a top-N subscription
+    pub fn top_n_window(&self) -> Option<TopNWindow> {
+        self.top_n()
+            .map(|top_n| TopNWindow::new(top_n, ProjectPlan::clone(self).optimize()))
+    }
+
     /// Return an evaluator for this delta plan
-    pub fn evaluator<Tx: Datastore + DeltaStore>(&self, tx: &Tx) -> DeltaPlanEvaluator {
+    pub fn evaluator<Tx: Datastore + DeltaStore>(&self, tx: &Tx) -> DeltaPlanEvaluator<'_> {
         match self {
             Self::Select(plan) => plan.evaluator(tx),
             Self::Join(plan) => plan.evaluator(tx),
@@ -133,17 +153,17 @@ impl DeltaPlan {
 /// An evaluator for a delta plan.
 /// It returns the rows that were added to the view,
 /// as well as the rows that were removed from it.
-pub struct DeltaPlanEvaluator {
+pub struct DeltaPlanEvaluator<'p> {
     is_join: bool,
-    insert_plans: Vec<ProjectPlan>,
-    delete_plans: Vec<ProjectPlan>,
+    insert_plans: Vec<Cow<'p, ProjectPlan>>,
+    delete_plans: Vec<Cow<'p, ProjectPlan>>,
 }
 
-impl DeltaPlanEvaluator {
+impl DeltaPlanEvaluator<'_> {
     pub fn eval_inserts<'a, Tx: Datastore + DeltaStore>(&'a self, tx: &'a Tx) -> Result<impl Iterator<Item = Row<'a>>> {
         let mut rows = vec![];
         for plan in &self.insert_plans {
-            let plan = PipelinedProject::from(plan.clone());
+            let plan = PipelinedProject::from(ProjectPlan::clone(plan));
             plan.execute(tx, &mut |row| {
                 rows.push(row);
                 Ok(())
@@ -155,7 +175,7 @@ impl DeltaPlanEvaluator {
     pub fn eval_deletes<'a, Tx: Datastore + DeltaStore>(&'a self, tx: &'a Tx) -> Result<impl Iterator<Item = Row<'a>>> {
         let mut rows = vec![];
         for plan in &self.delete_plans {
-            let plan = PipelinedProject::from(plan.clone());
+            let plan = PipelinedProject::from(ProjectPlan::clone(plan));
             plan.execute(tx, &mut |row| {
                 rows.push(row);
                 Ok(())
@@ -186,6 +206,8 @@ pub struct SelectPlan {
     table_name: Box<str>,
     /// The query plan for the original view
     plan: ProjectPlan,
+    /// The ORDER BY and LIMIT of a top-N subscription
+    top_n: Option<TopN>,
 }
 
 impl SelectPlan {
@@ -205,9 +227,9 @@ impl SelectPlan {
     }
 
     /// Returns an evaluator for computing the view delta
-    pub fn evaluator<Tx: Datastore + DeltaStore>(&self, tx: &Tx) -> DeltaPlanEvaluator {
+    pub fn evaluator<Tx: Datastore + DeltaStore>(&self, tx: &Tx) -> DeltaPlanEvaluator<'_> {
         /// Mutate a query plan by adding delta scans
-        fn delta_plan(plan: &ProjectPlan, table_id: TableId, delta: Delta) -> Vec<ProjectPlan> {
+        fn delta_plan(plan: &ProjectPlan, table_id: TableId, delta: Delta) -> Vec<Cow<'_, ProjectPlan>> {
             let mut plan = plan.clone();
             plan.visit_mut(&mut |plan| match plan {
                 PhysicalPlan::TableScan(schema, _, is_delta @ None) if schema.table_id == table_id => {
@@ -215,7 +237,7 @@ impl SelectPlan {
                 }
                 _ => {}
             });
-            vec![plan]
+            vec![Cow::Owned(plan)]
         }
         DeltaPlanEvaluator {
             is_join: false,
@@ -315,6 +337,8 @@ pub struct JoinPlan {
     rhs_table: TableId,
     /// An unoptimized query plan for the original view
     plan: ProjectPlan,
+    /// The optimized plans for the terms of `dv`
+    deltas: JoinDeltaPlans,
 }
 
 impl JoinPlan {
@@ -334,114 +358,106 @@ impl JoinPlan {
     }
 
     /// Returns an evaluator for computing the view delta
-    pub fn evaluator<Tx: Datastore + DeltaStore>(&self, tx: &Tx) -> DeltaPlanEvaluator {
-        /// Mutate a query plan by adding delta scans
-        fn delta_plan(plan: &mut ProjectPlan, label: Label, delta: Delta) {
-            plan.visit_mut(&mut |plan| match plan {
-                PhysicalPlan::TableScan(_, var, is_delta @ None) if var == &label => {
-                    *is_delta = Some(delta);
-                }
-                _ => {}
-            });
+    pub fn evaluator<Tx: Datastore + DeltaStore>(&self, tx: &Tx) -> DeltaPlanEvaluator<'_> {
+        let dr_ins = tx.has_inserts(self.lhs_table).is_some();
+        let dr_del = tx.has_deletes(self.lhs_table).is_some();
+        let ds_ins = tx.has_inserts(self.rhs_table).is_some();
+        let ds_del = tx.has_deletes(self.rhs_table).is_some();
+
+        /// Select the terms of `dv` which may be non-empty
+        fn terms<const N: usize>(terms: [(bool, &ProjectPlan); N]) -> Vec<Cow<'_, ProjectPlan>> {
+            terms
+                .into_iter()
+                .filter(|(nonempty, _)| *nonempty)
+                .map(|(_, plan)| Cow::Borrowed(plan))
+                .collect()
         }
 
-        /// Instantiate and optimize a delta plan
-        fn delta_plan_opt1(plan: &ProjectPlan, label: Label, delta: Delta) -> ProjectPlan {
-            let mut plan = plan.clone();
-            delta_plan(&mut plan, label, delta);
-            plan.optimize()
+        let plans = &self.deltas;
+        DeltaPlanEvaluator {
+            is_join: true,
+            // R'ds(+) U dr(+)S' U dr(+)ds(-) U dr(-)ds(+)
+            insert_plans: terms([
+                (ds_ins, &plans.ds_ins),
+                (dr_ins, &plans.dr_ins),
+                (dr_ins && ds_del, &plans.dr_ins_ds_del),
+                (dr_del && ds_ins, &plans.dr_del_ds_ins),
+            ]),
+            // R'ds(-) U dr(-)S' U dr(+)ds(+) U dr(-)ds(-)
+            delete_plans: terms([
+                (ds_del, &plans.ds_del),
+                (dr_del, &plans.dr_del),
+                (dr_ins && ds_ins, &plans.dr_ins_ds_ins),
+                (dr_del && ds_del, &plans.dr_del_ds_del),
+            ]),
         }
+    }
+}
+
+/// The optimized plans for the terms of the delta of a 2-way join.
+///
+/// A plan only depends on which of the two tables have changed,
+/// and in what way, not on the contents of the change.
+/// So rather than instantiating and optimizing them for each transaction,
+/// they are compiled once, along with the subscription.
+///
+/// Each plan reads its delta tables first,
+/// and then probes the index on the join column of the other table.
+/// Subscriptions require indexes on their join columns,
+/// so these indexes are the state, keyed by join column,
+/// which makes the cost of an update proportional to the size of the delta,
+/// and not to the size of the tables being joined.
+/// E.g. updating a subscription to a high churn table joined with a static one
+/// never scans the static table.
+#[derive(Debug)]
+struct JoinDeltaPlans {
+    /// dr(+)S'
+    dr_ins: ProjectPlan,
+    /// dr(-)S'
+    dr_del: ProjectPlan,
+    /// R'ds(+)
+    ds_ins: ProjectPlan,
+    /// R'ds(-)
+    ds_del: ProjectPlan,
+    /// dr(+)ds(+)
+    dr_ins_ds_ins: ProjectPlan,
+    /// dr(+)ds(-)
+    dr_ins_ds_del: ProjectPlan,
+    /// dr(-)ds(+)
+    dr_del_ds_ins: ProjectPlan,
+    /// dr(-)ds(-)
+    dr_del_ds_del: ProjectPlan,
+}
+
+impl JoinDeltaPlans {
+    /// Compile the delta plans for the join `plan` of `lhs` and `rhs`
+    fn new(plan: &ProjectPlan, lhs: Label, rhs: Label) -> Self {
+        // Neither the optimizer nor the executor depend on the size of a delta,
+        // only on whether it holds inserts or deletes.
+        let ins = Delta::Inserts(0);
+        let del = Delta::Deletes(0);
 
         /// Instantiate and optimize a delta plan
-        fn delta_plan_opt2(plan: &ProjectPlan, lhs: Label, n: Delta, rhs: Label, m: Delta) -> ProjectPlan {
+        fn delta_plan(plan: &ProjectPlan, deltas: &[(Label, Delta)]) -> ProjectPlan {
             let mut plan = plan.clone();
-            delta_plan(&mut plan, lhs, n);
-            delta_plan(&mut plan, rhs, m);
+            plan.visit_mut(&mut |plan| match plan {
+                PhysicalPlan::TableScan(_, var, is_delta @ None) => {
+                    *is_delta = deltas.iter().find(|(label, _)| label == var).map(|(_, delta)| *delta);
+                }
+                _ => {}
+            });
             plan.optimize()
         }
 
-        // dr(+)S'
-        let dr_ins = tx
-            .has_inserts(self.lhs_table)
-            .map(|delta| delta_plan_opt1(&self.plan, self.lhs_label, delta))
-            .map(std::iter::once)
-            .map(Either::Left)
-            .unwrap_or_else(|| Either::Right(std::iter::empty()));
-
-        // dr(-)S'
-        let dr_del = tx
-            .has_deletes(self.lhs_table)
-            .map(|delta| delta_plan_opt1(&self.plan, self.lhs_label, delta))
-            .map(std::iter::once)
-            .map(Either::Left)
-            .unwrap_or_else(|| Either::Right(std::iter::empty()));
-
-        // R'ds(+)
-        let ds_ins = tx
-            .has_inserts(self.rhs_table)
-            .map(|delta| delta_plan_opt1(&self.plan, self.rhs_label, delta))
-            .map(std::iter::once)
-            .map(Either::Left)
-            .unwrap_or_else(|| Either::Right(std::iter::empty()));
-
-        // R'ds(-)
-        let ds_del = tx
-            .has_deletes(self.rhs_table)
-            .map(|delta| delta_plan_opt1(&self.plan, self.rhs_label, delta))
-            .map(std::iter::once)
-            .map(Either::Left)
-            .unwrap_or_else(|| Either::Right(std::iter::empty()));
-
-        // dr(+)ds(+)
-        let dr_ins_ds_ins = tx
-            .has_inserts(self.lhs_table)
-            .zip(tx.has_inserts(self.rhs_table))
-            .map(|(n, m)| delta_plan_opt2(&self.plan, self.lhs_label, n, self.rhs_label, m))
-            .map(std::iter::once)
-            .map(Either::Left)
-            .unwrap_or_else(|| Either::Right(std::iter::empty()));
-
-        // dr(+)ds(-)
-        let dr_ins_ds_del = tx
-            .has_inserts(self.lhs_table)
-            .zip(tx.has_deletes(self.rhs_table))
-            .map(|(n, m)| delta_plan_opt2(&self.plan, self.lhs_label, n, self.rhs_label, m))
-            .map(std::iter::once)
-            .map(Either::Left)
-            .unwrap_or_else(|| Either::Right(std::iter::empty()));
-
-        // dr(-)ds(+)
-        let dr_del_ds_ins = tx
-            .has_deletes(self.lhs_table)
-            .zip(tx.has_inserts(self.rhs_table))
-            .map(|(n, m)| delta_plan_opt2(&self.plan, self.lhs_label, n, self.rhs_label, m))
-            .map(std::iter::once)
-            .map(Either::Left)
-            .unwrap_or_else(|| Either::Right(std::iter::empty()));
-
-        // dr(-)ds(-)
-        let dr_del_ds_del = tx
-            .has_deletes(self.lhs_table)
-            .zip(tx.has_deletes(self.rhs_table))
-            .map(|(n, m)| delta_plan_opt2(&self.plan, self.lhs_label, n, self.rhs_label, m))
-            .map(std::iter::once)
-            .map(Either::Left)
-            .unwrap_or_else(|| Either::Right(std::iter::empty()));
-
-        DeltaPlanEvaluator {
-            is_join: true,
-            insert_plans: ds_ins
-                // R'ds(+) U dr(+)S' U dr(+)dr(-) U dr(-)ds(+)
-                .chain(dr_ins)
-                .chain(dr_ins_ds_del)
-                .chain(dr_del_ds_ins)
-                .collect(),
-            delete_plans: ds_del
-                // R'ds(-) U dr(-)S' U dr(+)ds(+) U dr(-)ds(-)
-                .chain(dr_del)
-                .chain(dr_ins_ds_ins)
-                .chain(dr_del_ds_del)
-                .collect(),
+        Self {
+            dr_ins: delta_plan(plan, &[(lhs, ins)]),
+            dr_del: delta_plan(plan, &[(lhs, del)]),
+            ds_ins: delta_plan(plan, &[(rhs, ins)]),
+            ds_del: delta_plan(plan, &[(rhs, del)]),
+            dr_ins_ds_ins: delta_plan(plan, &[(lhs, ins), (rhs, ins)]),
+            dr_ins_ds_del: delta_plan(plan, &[(lhs, ins), (rhs, del)]),
+            dr_del_ds_ins: delta_plan(plan, &[(lhs, del), (rhs, ins)]),
+            dr_del_ds_del: delta_plan(plan, &[(lhs, del), (rhs, del)]),
         }
     }
 }
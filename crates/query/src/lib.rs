@@ -7,12 +7,16 @@ use spacetimedb_client_api_messages::websocket::{
     Compression, DatabaseUpdate, QueryUpdate, TableUpdate, WebsocketFormat,
 };
 use spacetimedb_execution::{pipelined::PipelinedProject, Datastore, DeltaStore};
-use spacetimedb_expr::check::{type_subscription, SchemaView};
+use spacetimedb_expr::{
+    check::{type_top_n_subscription, SchemaView},
+    expr::TopN,
+};
 use spacetimedb_physical_plan::{compile::compile_project_plan, plan::ProjectPlan};
 use spacetimedb_primitives::TableId;
 use spacetimedb_sql_parser::parser::sub::parse_subscription;
 
 pub mod delta;
+pub mod top_n;
 
 /// DIRTY HACK ALERT: Maximum allowed length, in UTF-8 bytes, of SQL queries.
 /// Any query longer than this will be rejected.
@@ -28,6 +32,8 @@ pub struct SubscribePlan {
     table_id: TableId,
     /// Table name of the returned rows
     table_name: Box<str>,
+    /// The ORDER BY and LIMIT of a top-N subscription
+    top_n: Option<TopN>,
 }
 
 impl Deref for SubscribePlan {
@@ -54,12 +60,14 @@ impl SubscribePlan {
     pub fn from_delta_plan(plan: &DeltaPlan) -> Self {
         let table_id = plan.table_id();
         let table_name = plan.table_name();
+        let top_n = plan.top_n();
         let plan = &**plan;
         let plan = plan.clone().optimize();
         Self {
             plan,
             table_id,
             table_name,
+            top_n,
         }
     }
 
@@ -69,7 +77,7 @@ impl SubscribePlan {
             bail!("SQL query exceeds maximum allowed length: \"{sql:.120}...\"")
         }
         let ast = parse_subscription(sql)?;
-        let sub = type_subscription(ast, tx)?;
+        let (sub, top_n) = type_top_n_subscription(ast, tx)?;
 
         let Some(table_id) = sub.table_id() else {
             bail!("Failed to determine TableId for query")
@@ -86,6 +94,7 @@ impl SubscribePlan {
             plan,
             table_id,
             table_name,
+            top_n,
         })
     }
 
@@ -101,7 +110,13 @@ impl SubscribePlan {
             rows.push(row);
             Ok(())
         })?;
-        Ok(F::encode_list(rows.into_iter()))
+        match &self.top_n {
+            None => Ok(F::encode_list(rows.into_iter())),
+            Some(top_n) => {
+                let rows = top_n::first_n(top_n, rows.into_iter().map(top_n::into_product_value));
+                Ok(F::encode_list(rows.into_iter()))
+            }
+        }
     }
 
     /// Execute a subscription query and collect the results in a [TableUpdate]
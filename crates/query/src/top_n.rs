@@ -0,0 +1,171 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use anyhow::Result;
+use spacetimedb_execution::{pipelined::PipelinedProject, Datastore, DeltaStore, Row};
+use spacetimedb_expr::expr::TopN;
+use spacetimedb_lib::{bsatn::ToBsatn, AlgebraicValue, ProductValue};
+use spacetimedb_physical_plan::plan::ProjectPlan;
+
+/// The position of a row in the order of a top-N subscription.
+/// Rows with equal ORDER BY fields are ordered by their BSATN encoding,
+/// so that which rows make the cut is deterministic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopNKey {
+    desc: bool,
+    value: AlgebraicValue,
+    bsatn: Vec<u8>,
+}
+
+impl TopNKey {
+    pub fn new(top_n: &TopN, row: &ProductValue) -> Self {
+        Self {
+            desc: top_n.desc,
+            value: row.elements[top_n.field].clone(),
+            bsatn: row.to_bsatn_vec().unwrap(),
+        }
+    }
+}
+
+impl Ord for TopNKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let ord = self.value.cmp(&other.value).then_with(|| self.bsatn.cmp(&other.bsatn));
+        if self.desc {
+            ord.reverse()
+        } else {
+            ord
+        }
+    }
+}
+
+impl PartialOrd for TopNKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Returns the first `top_n.limit` of `rows` in the order of `top_n`
+pub fn first_n(top_n: &TopN, rows: impl IntoIterator<Item = ProductValue>) -> Vec<ProductValue> {
+    let mut window = BTreeMap::new();
+    insert_all(top_n, &mut window, rows);
+    window.into_values().collect()
+}
+
+/// Adds `rows` to `window`, keeping only its first `top_n.limit` rows
+fn insert_all(
+    top_n: &TopN,
+    window: &mut BTreeMap<TopNKey, ProductValue>,
+    rows: impl IntoIterator<Item = ProductValue>,
+) {
+    for row in rows {
+        window.insert(TopNKey::new(top_n, &row), row);
+        if window.len() as u64 > top_n.limit {
+            window.pop_last();
+        }
+    }
+}
+
+/// Converts a row returned by a query plan into a [ProductValue]
+pub fn into_product_value(row: Row<'_>) -> ProductValue {
+    match row {
+        Row::Ptr(ptr) => ptr.to_product_value(),
+        Row::Ref(row) => row.clone(),
+    }
+}
+
+/// The rows of a top-N subscription, maintained incrementally as transactions commit.
+///
+/// Given the rows a transaction added to and removed from the un-truncated query,
+/// the window is updated without evaluating the query,
+/// unless the transaction removed a row from a full window.
+/// Then the next row is unknown, and the query is evaluated anew.
+#[derive(Debug)]
+pub struct TopNWindow {
+    top_n: TopN,
+    /// The optimized plan of the un-truncated query
+    plan: ProjectPlan,
+    /// The current rows, or `None` before the first transaction is seen
+    rows: Option<BTreeMap<TopNKey, ProductValue>>,
+}
+
+impl TopNWindow {
+    pub fn new(top_n: TopN, plan: ProjectPlan) -> Self {
+        Self {
+            top_n,
+            plan,
+            rows: None,
+        }
+    }
+
+    /// Updates the window for a transaction whose committed state is `tx`,
+    /// and which added `inserts` to and removed `deletes` from the un-truncated query.
+    /// Returns the rows which left the window and those which entered it, in that order.
+    pub fn update<Tx: Datastore + DeltaStore>(
+        &mut self,
+        tx: &Tx,
+        inserts: Vec<ProductValue>,
+        deletes: Vec<ProductValue>,
+    ) -> Result<(Vec<ProductValue>, Vec<ProductValue>)> {
+        let top_n = &self.top_n;
+        let old = match self.rows.take() {
+            Some(rows) => rows,
+            // The first transaction since the query was subscribed to.
+            // Reconstruct the window as of before the transaction.
+            None => {
+                let inserted = inserts.iter().map(|row| TopNKey::new(top_n, row)).collect();
+                let mut window = self.eval(tx, &inserted)?;
+                insert_all(top_n, &mut window, deletes.iter().cloned());
+                window
+            }
+        };
+
+        let was_full = old.len() as u64 == top_n.limit;
+        let mut new = old.clone();
+        let mut removed = false;
+        for row in &deletes {
+            removed |= new.remove(&TopNKey::new(top_n, row)).is_some();
+        }
+        let new = if was_full && removed {
+            self.eval(tx, &BTreeSet::new())?
+        } else {
+            insert_all(top_n, &mut new, inserts);
+            new
+        };
+
+        let left = old
+            .iter()
+            .filter(|(key, _)| !new.contains_key(key))
+            .map(|(_, row)| row.clone())
+            .collect();
+        let entered = new
+            .iter()
+            .filter(|(key, _)| !old.contains_key(key))
+            .map(|(_, row)| row.clone())
+            .collect();
+        self.rows = Some(new);
+        Ok((left, entered))
+    }
+
+    /// Evaluates the query against `tx`, ignoring the rows in `skip`
+    fn eval<Tx: Datastore + DeltaStore>(
+        &self,
+        tx: &Tx,
+        skip: &BTreeSet<TopNKey>,
+    ) -> Result<BTreeMap<TopNKey, ProductValue>> {
+        let mut window = BTreeMap::new();
+        PipelinedProject::from(self.plan.clone()).execute(tx, &mut |row| {
+            let row = into_product_value(row);
+            let key = TopNKey::new(&self.top_n, &row);
+            if !skip.contains(&key) {
+                window.insert(key, row);
+                if window.len() as u64 > self.top_n.limit {
+                    window.pop_last();
+                }
+            }
+            Ok(())
+        })?;
+        Ok(window)
+    }
+}
@@ -0,0 +1,506 @@
+//! Conversion between rows of a [`ProductType`] and [Apache Arrow](https://arrow.apache.org) record batches.
+//!
+//! When writing, each element of the row type becomes a column:
+//! - Booleans, integers of up to 64 bits, floats and strings
+//!   map to the corresponding Arrow type.
+//! - `Vec<u8>` maps to `Binary`.
+//! - 128- and 256-bit integers map to `FixedSizeBinary`,
+//!   holding the little-endian bytes of the integer.
+//! - `Option<T>`, for any `T` above, maps to a nullable column of `T`'s Arrow type.
+//! - Any other type maps to `Binary`, holding the BSATN encoding of each value.
+//!
+//! When reading, with [`column_values`], the Arrow type of a column need not match exactly.
+//! Values are coerced into the column type `T` as follows:
+//! - Integers of any width can be read into any integer type, if the value fits.
+//! - Integers and floats can be read into float types.
+//! - Strings are parsed into booleans, integers and floats.
+//! - Strings can be read from any Arrow string type,
+//!   and `Vec<u8>` from any Arrow binary type.
+//! - 128- and 256-bit integers are also read from `FixedSizeBinary` of their width.
+//! - Nulls are read as `none` into `Option<T>` and rejected for any other type.
+//! - Any other type is read from `Binary` holding its BSATN encoding.
+
+use crate::algebraic_value::{i256, u256};
+use crate::{bsatn, AlgebraicType, AlgebraicValue, ArrayType, ArrayValue, ProductType, ProductValue};
+use arrow::array::{
+    Array, ArrayBuilder, ArrayRef, AsArray, BinaryBuilder, BooleanBuilder, FixedSizeBinaryBuilder, Float32Builder,
+    Float64Builder, Int16Builder, Int32Builder, Int64Builder, Int8Builder, StringBuilder, UInt16Builder, UInt32Builder,
+    UInt64Builder, UInt8Builder,
+};
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::datatypes::{
+    DataType, Field, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, Schema, SchemaRef,
+    UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+/// Returns the Arrow [`Schema`] of record batches holding rows of `ty`.
+pub fn arrow_schema(ty: &ProductType) -> Schema {
+    let fields = ty.elements.iter().enumerate().map(|(i, elem)| {
+        let name = elem.name().map_or_else(|| i.to_string(), Into::into);
+        let (kind, nullable) = ColumnKind::of_column(&elem.algebraic_type);
+        Field::new(name, kind.data_type(), nullable)
+    });
+    Schema::new(fields.collect::<Vec<_>>())
+}
+
+/// The kinds of columns, by how their values are stored in Arrow.
+#[derive(Clone, Copy)]
+enum ColumnKind {
+    Bool,
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+    String,
+    Bytes,
+    /// A 128- or 256-bit integer of the given width in bytes.
+    Wide(i32),
+    Bsatn,
+}
+
+impl ColumnKind {
+    /// Returns the kind of a column of `ty`, and whether it is nullable.
+    fn of_column(ty: &AlgebraicType) -> (Self, bool) {
+        match ty.as_option().map(Self::of) {
+            Some(kind) if !matches!(kind, Self::Bsatn) => (kind, true),
+            _ => (Self::of(ty), false),
+        }
+    }
+
+    fn of(ty: &AlgebraicType) -> Self {
+        match ty {
+            AlgebraicType::Bool => Self::Bool,
+            AlgebraicType::I8 => Self::I8,
+            AlgebraicType::U8 => Self::U8,
+            AlgebraicType::I16 => Self::I16,
+            AlgebraicType::U16 => Self::U16,
+            AlgebraicType::I32 => Self::I32,
+            AlgebraicType::U32 => Self::U32,
+            AlgebraicType::I64 => Self::I64,
+            AlgebraicType::U64 => Self::U64,
+            AlgebraicType::I128 | AlgebraicType::U128 => Self::Wide(16),
+            AlgebraicType::I256 | AlgebraicType::U256 => Self::Wide(32),
+            AlgebraicType::F32 => Self::F32,
+            AlgebraicType::F64 => Self::F64,
+            AlgebraicType::String => Self::String,
+            AlgebraicType::Array(ArrayType { elem_ty }) if **elem_ty == AlgebraicType::U8 => Self::Bytes,
+            _ => Self::Bsatn,
+        }
+    }
+
+    fn data_type(self) -> DataType {
+        match self {
+            Self::Bool => DataType::Boolean,
+            Self::I8 => DataType::Int8,
+            Self::U8 => DataType::UInt8,
+            Self::I16 => DataType::Int16,
+            Self::U16 => DataType::UInt16,
+            Self::I32 => DataType::Int32,
+            Self::U32 => DataType::UInt32,
+            Self::I64 => DataType::Int64,
+            Self::U64 => DataType::UInt64,
+            Self::F32 => DataType::Float32,
+            Self::F64 => DataType::Float64,
+            Self::String => DataType::Utf8,
+            Self::Bytes | Self::Bsatn => DataType::Binary,
+            Self::Wide(width) => DataType::FixedSizeBinary(width),
+        }
+    }
+
+    fn builder(self) -> Column {
+        match self {
+            Self::Bool => Column::Bool(BooleanBuilder::new()),
+            Self::I8 => Column::I8(Int8Builder::new()),
+            Self::U8 => Column::U8(UInt8Builder::new()),
+            Self::I16 => Column::I16(Int16Builder::new()),
+            Self::U16 => Column::U16(UInt16Builder::new()),
+            Self::I32 => Column::I32(Int32Builder::new()),
+            Self::U32 => Column::U32(UInt32Builder::new()),
+            Self::I64 => Column::I64(Int64Builder::new()),
+            Self::U64 => Column::U64(UInt64Builder::new()),
+            Self::F32 => Column::F32(Float32Builder::new()),
+            Self::F64 => Column::F64(Float64Builder::new()),
+            Self::String => Column::String(StringBuilder::new()),
+            Self::Bytes => Column::Bytes(BinaryBuilder::new()),
+            Self::Wide(width) => Column::Wide(FixedSizeBinaryBuilder::new(width)),
+            Self::Bsatn => Column::Bsatn(BinaryBuilder::new()),
+        }
+    }
+
+    /// Returns whether a column of Arrow type `from` can be coerced into a column of this kind.
+    fn can_read_from(self, from: &DataType) -> bool {
+        let is_string = matches!(from, DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View);
+        let is_binary = matches!(
+            from,
+            DataType::Binary | DataType::LargeBinary | DataType::BinaryView | DataType::FixedSizeBinary(_)
+        );
+        match self {
+            Self::Bool => matches!(from, DataType::Boolean) || is_string,
+            Self::I8 | Self::U8 | Self::I16 | Self::U16 | Self::I32 | Self::U32 | Self::I64 | Self::U64 => {
+                from.is_integer() || is_string
+            }
+            Self::F32 | Self::F64 => from.is_integer() || from.is_floating() || is_string,
+            Self::String => is_string,
+            Self::Bytes => is_binary,
+            Self::Wide(width) => *from == DataType::FixedSizeBinary(width) || from.is_integer() || is_string,
+            Self::Bsatn => matches!(from, DataType::Binary | DataType::LargeBinary | DataType::BinaryView),
+        }
+    }
+
+    /// The Arrow type that a column of Arrow type `from` is cast to before reading it as this kind.
+    ///
+    /// 128- and 256-bit integers other than `FixedSizeBinary` are read via their decimal strings.
+    fn read_type(self, from: &DataType) -> DataType {
+        match self {
+            Self::Wide(_) if !matches!(from, DataType::FixedSizeBinary(_)) => DataType::Utf8,
+            _ => self.data_type(),
+        }
+    }
+
+    /// Reads the non-null value at `index` of `array`, which is of [`Self::read_type`],
+    /// as a value of `ty`.
+    fn read(self, array: &dyn Array, index: usize, ty: &AlgebraicType) -> Result<AlgebraicValue, ArrowError> {
+        Ok(match self {
+            Self::Bool => AlgebraicValue::Bool(array.as_boolean().value(index)),
+            Self::I8 => AlgebraicValue::I8(array.as_primitive::<Int8Type>().value(index)),
+            Self::U8 => AlgebraicValue::U8(array.as_primitive::<UInt8Type>().value(index)),
+            Self::I16 => AlgebraicValue::I16(array.as_primitive::<Int16Type>().value(index)),
+            Self::U16 => AlgebraicValue::U16(array.as_primitive::<UInt16Type>().value(index)),
+            Self::I32 => AlgebraicValue::I32(array.as_primitive::<Int32Type>().value(index)),
+            Self::U32 => AlgebraicValue::U32(array.as_primitive::<UInt32Type>().value(index)),
+            Self::I64 => AlgebraicValue::I64(array.as_primitive::<Int64Type>().value(index)),
+            Self::U64 => AlgebraicValue::U64(array.as_primitive::<UInt64Type>().value(index)),
+            Self::F32 => array.as_primitive::<Float32Type>().value(index).into(),
+            Self::F64 => array.as_primitive::<Float64Type>().value(index).into(),
+            Self::String => array.as_string::<i32>().value(index).into(),
+            Self::Bytes => array.as_binary::<i32>().value(index).into(),
+            Self::Wide(_) if matches!(array.data_type(), DataType::FixedSizeBinary(_)) => {
+                let bytes = array.as_fixed_size_binary().value(index);
+                match ty {
+                    AlgebraicType::I128 => i128::from_le_bytes(bytes.try_into().unwrap()).into(),
+                    AlgebraicType::U128 => u128::from_le_bytes(bytes.try_into().unwrap()).into(),
+                    AlgebraicType::I256 => i256::from_le_bytes(bytes.try_into().unwrap()).into(),
+                    _ => u256::from_le_bytes(bytes.try_into().unwrap()).into(),
+                }
+            }
+            Self::Wide(_) => {
+                let s = array.as_string::<i32>().value(index);
+                let err = || ArrowError::ParseError(format!("cannot parse {s:?} as an integer of type {ty:?}"));
+                match ty {
+                    AlgebraicType::I128 => s.parse::<i128>().map_err(|_| err())?.into(),
+                    AlgebraicType::U128 => s.parse::<u128>().map_err(|_| err())?.into(),
+                    AlgebraicType::I256 => i256::from_str_radix(s, 10).map_err(|_| err())?.into(),
+                    _ => u256::from_str_radix(s, 10).map_err(|_| err())?.into(),
+                }
+            }
+            Self::Bsatn => {
+                let bytes = &mut array.as_binary::<i32>().value(index);
+                let value = AlgebraicValue::decode(ty, bytes).map_err(|e| ArrowError::ExternalError(e.into()))?;
+                if !bytes.is_empty() {
+                    return Err(ArrowError::ParseError(format!(
+                        "trailing bytes after BSATN-encoded value of type {ty:?}"
+                    )));
+                }
+                value
+            }
+        })
+    }
+}
+
+/// Reads the values of `array` as values of the column type `ty`,
+/// coercing them as described in the [module documentation](self).
+///
+/// Fails if the Arrow type of `array` cannot be coerced into `ty`,
+/// or if any of its values cannot.
+pub fn column_values(array: &dyn Array, ty: &AlgebraicType) -> Result<Vec<AlgebraicValue>, ArrowError> {
+    let (kind, nullable) = ColumnKind::of_column(ty);
+    let elem_ty = if nullable { ty.as_option().unwrap() } else { ty };
+
+    let from = array.data_type();
+    if !kind.can_read_from(from) {
+        return Err(ArrowError::CastError(format!(
+            "cannot read a column of type {from} as values of type {ty:?}"
+        )));
+    }
+    // With `safe: false`, values which don't fit or don't parse are errors,
+    // rather than being replaced by nulls.
+    let options = CastOptions {
+        safe: false,
+        ..CastOptions::default()
+    };
+    let array = cast_with_options(array, &kind.read_type(from), &options)?;
+
+    (0..array.len())
+        .map(|index| match array.is_null(index) {
+            true if nullable => Ok(AlgebraicValue::OptionNone()),
+            true => Err(ArrowError::InvalidArgumentError(format!(
+                "null at row {index} in a column of non-optional type {ty:?}"
+            ))),
+            false if nullable => kind.read(&array, index, elem_ty).map(AlgebraicValue::OptionSome),
+            false => kind.read(&array, index, elem_ty),
+        })
+        .collect()
+}
+
+/// The builder of a single column.
+enum Column {
+    Bool(BooleanBuilder),
+    I8(Int8Builder),
+    U8(UInt8Builder),
+    I16(Int16Builder),
+    U16(UInt16Builder),
+    I32(Int32Builder),
+    U32(UInt32Builder),
+    I64(Int64Builder),
+    U64(UInt64Builder),
+    F32(Float32Builder),
+    F64(Float64Builder),
+    String(StringBuilder),
+    Bytes(BinaryBuilder),
+    Wide(FixedSizeBinaryBuilder),
+    Bsatn(BinaryBuilder),
+}
+
+/// Applies `$f` to the builder of `$column`, whatever its type.
+macro_rules! with_builder {
+    ($column:expr, $b:ident => $f:expr) => {
+        match $column {
+            Column::Bool($b) => $f,
+            Column::I8($b) => $f,
+            Column::U8($b) => $f,
+            Column::I16($b) => $f,
+            Column::U16($b) => $f,
+            Column::I32($b) => $f,
+            Column::U32($b) => $f,
+            Column::I64($b) => $f,
+            Column::U64($b) => $f,
+            Column::F32($b) => $f,
+            Column::F64($b) => $f,
+            Column::String($b) => $f,
+            Column::Bytes($b) => $f,
+            Column::Wide($b) => $f,
+            Column::Bsatn($b) => $f,
+        }
+    };
+}
+
+impl Column {
+    fn append(&mut self, value: &AlgebraicValue) -> Result<(), ArrowError> {
+        match (self, value) {
+            (Self::Bool(b), AlgebraicValue::Bool(v)) => b.append_value(*v),
+            (Self::I8(b), AlgebraicValue::I8(v)) => b.append_value(*v),
+            (Self::U8(b), AlgebraicValue::U8(v)) => b.append_value(*v),
+            (Self::I16(b), AlgebraicValue::I16(v)) => b.append_value(*v),
+            (Self::U16(b), AlgebraicValue::U16(v)) => b.append_value(*v),
+            (Self::I32(b), AlgebraicValue::I32(v)) => b.append_value(*v),
+            (Self::U32(b), AlgebraicValue::U32(v)) => b.append_value(*v),
+            (Self::I64(b), AlgebraicValue::I64(v)) => b.append_value(*v),
+            (Self::U64(b), AlgebraicValue::U64(v)) => b.append_value(*v),
+            (Self::F32(b), AlgebraicValue::F32(v)) => b.append_value(v.into_inner()),
+            (Self::F64(b), AlgebraicValue::F64(v)) => b.append_value(v.into_inner()),
+            (Self::String(b), AlgebraicValue::String(v)) => b.append_value(v),
+            (Self::Bytes(b), AlgebraicValue::Array(ArrayValue::U8(v))) => b.append_value(v),
+            (Self::Wide(b), AlgebraicValue::I128(v)) => b.append_value({ v.0 }.to_le_bytes())?,
+            (Self::Wide(b), AlgebraicValue::U128(v)) => b.append_value({ v.0 }.to_le_bytes())?,
+            (Self::Wide(b), AlgebraicValue::I256(v)) => b.append_value(v.to_le_bytes())?,
+            (Self::Wide(b), AlgebraicValue::U256(v)) => b.append_value(v.to_le_bytes())?,
+            (Self::Bsatn(b), v) => b.append_value(bsatn::to_vec(v).map_err(|e| ArrowError::ExternalError(e.into()))?),
+            (_, v) => {
+                return Err(ArrowError::InvalidArgumentError(format!(
+                    "value {v:?} does not match the type of its column"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    fn append_null(&mut self) {
+        with_builder!(self, b => b.append_null())
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        with_builder!(self, b => Arc::new(b.finish()))
+    }
+
+    fn len(&self) -> usize {
+        with_builder!(self, b => b.len())
+    }
+}
+
+/// Builds Arrow [`RecordBatch`]es from rows of a [`ProductType`].
+///
+/// See the [module documentation](self) for how each column is represented.
+pub struct RecordBatchBuilder {
+    schema: SchemaRef,
+    columns: Vec<(Column, bool)>,
+}
+
+impl RecordBatchBuilder {
+    /// Returns a builder of record batches holding rows of `ty`.
+    pub fn new(ty: &ProductType) -> Self {
+        let schema = Arc::new(arrow_schema(ty));
+        let columns = ty
+            .elements
+            .iter()
+            .map(|elem| {
+                let (kind, nullable) = ColumnKind::of_column(&elem.algebraic_type);
+                (kind.builder(), nullable)
+            })
+            .collect();
+        Self { schema, columns }
+    }
+
+    /// The schema of the record batches built by `self`.
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    /// Append `row` to the current record batch.
+    ///
+    /// Fails if `row` is not a value of the row type of `self`,
+    /// in which case the current record batch is left in an unspecified state.
+    pub fn append(&mut self, row: &ProductValue) -> Result<(), ArrowError> {
+        if row.elements.len() != self.columns.len() {
+            return Err(ArrowError::InvalidArgumentError(format!(
+                "expected a row of {} columns, got {}",
+                self.columns.len(),
+                row.elements.len()
+            )));
+        }
+        for ((column, nullable), value) in self.columns.iter_mut().zip(&*row.elements) {
+            match value {
+                // For nullable columns, `some(x)` is stored as `x` and `none` as null.
+                AlgebraicValue::Sum(sum) if *nullable && sum.tag == 0 => column.append(&sum.value)?,
+                AlgebraicValue::Sum(_) if *nullable => column.append_null(),
+                value => column.append(value)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of rows appended to the current record batch.
+    pub fn len(&self) -> usize {
+        self.columns.first().map_or(0, |(column, _)| column.len())
+    }
+
+    /// Returns whether no rows have been appended to the current record batch.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Finish the current record batch, and start a new, empty one.
+    pub fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        let columns = self.columns.iter_mut().map(|(column, _)| column.finish()).collect();
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product;
+    use arrow::array::{Int64Array, StringArray};
+
+    #[test]
+    fn build_record_batch() {
+        let ty = ProductType::from([
+            ("id", AlgebraicType::U64),
+            ("name", AlgebraicType::String),
+            ("score", AlgebraicType::option(AlgebraicType::I32)),
+            ("pos", AlgebraicType::product([AlgebraicType::I32, AlgebraicType::I32])),
+        ]);
+        let mut builder = RecordBatchBuilder::new(&ty);
+
+        let schema = builder.schema();
+        assert_eq!(schema.field(0).data_type(), &DataType::UInt64);
+        assert_eq!(schema.field(1).data_type(), &DataType::Utf8);
+        assert_eq!(schema.field(2).data_type(), &DataType::Int32);
+        assert!(schema.field(2).is_nullable());
+        assert_eq!(schema.field(3).data_type(), &DataType::Binary);
+
+        let pos = AlgebraicValue::product([AlgebraicValue::I32(1), AlgebraicValue::I32(2)]);
+        builder.append(&product![1u64, "a", Some(7i32), pos.clone()]).unwrap();
+        builder.append(&product![2u64, "b", None::<i32>, pos.clone()]).unwrap();
+        assert_eq!(builder.len(), 2);
+
+        let batch = builder.finish().unwrap();
+        assert!(builder.is_empty());
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.column(0).as_primitive::<UInt64Type>().values(), &[1, 2]);
+        assert_eq!(batch.column(1).as_string::<i32>().value(1), "b");
+        let score = batch.column(2).as_primitive::<Int32Type>();
+        assert_eq!(score.value(0), 7);
+        assert!(score.is_null(1));
+        assert_eq!(
+            batch.column(3).as_binary::<i32>().value(0),
+            &bsatn::to_vec(&pos).unwrap()[..]
+        );
+
+        // A row of the wrong type is rejected.
+        assert!(builder.append(&product![1u64, "a"]).is_err());
+        assert!(builder.append(&product![1u64, 2u64, None::<i32>, pos]).is_err());
+    }
+
+    #[test]
+    fn read_column_values() {
+        // Values round-trip through record batches.
+        let ty = ProductType::from([
+            ("id", AlgebraicType::U128),
+            ("score", AlgebraicType::option(AlgebraicType::I32)),
+            ("pos", AlgebraicType::product([AlgebraicType::I32, AlgebraicType::I32])),
+        ]);
+        let pos = AlgebraicValue::product([AlgebraicValue::I32(1), AlgebraicValue::I32(2)]);
+        let rows = [
+            product![1u128, Some(7i32), pos.clone()],
+            product![2u128, None::<i32>, pos],
+        ];
+        let mut builder = RecordBatchBuilder::new(&ty);
+        for row in &rows {
+            builder.append(row).unwrap();
+        }
+        let batch = builder.finish().unwrap();
+        for (i, elem) in ty.elements.iter().enumerate() {
+            let values = column_values(batch.column(i), &elem.algebraic_type).unwrap();
+            assert_eq!(
+                values,
+                rows.iter().map(|row| row.elements[i].clone()).collect::<Vec<_>>()
+            );
+        }
+
+        // Integers are coerced if they fit.
+        let ints = Int64Array::from(vec![1, 255]);
+        assert_eq!(
+            column_values(&ints, &AlgebraicType::U8).unwrap(),
+            [AlgebraicValue::U8(1), AlgebraicValue::U8(255)]
+        );
+        assert_eq!(
+            column_values(&ints, &AlgebraicType::F64).unwrap(),
+            [AlgebraicValue::from(1.0f64), AlgebraicValue::from(255.0f64)]
+        );
+        assert!(column_values(&ints, &AlgebraicType::I8).is_err());
+
+        // Strings are parsed, and nulls are only accepted for options.
+        let strings = StringArray::from(vec![Some("-3"), None]);
+        assert_eq!(
+            column_values(&strings, &AlgebraicType::option(AlgebraicType::I128)).unwrap(),
+            [
+                AlgebraicValue::OptionSome((-3i128).into()),
+                AlgebraicValue::OptionNone()
+            ]
+        );
+        assert!(column_values(&strings, &AlgebraicType::I32).is_err());
+        assert!(column_values(&StringArray::from(vec!["x"]), &AlgebraicType::I32).is_err());
+
+        // Strings aren't read as BSATN.
+        assert!(column_values(&strings, &AlgebraicType::product([AlgebraicType::I32])).is_err());
+    }
+}
@@ -39,6 +39,15 @@ pub fn from_reader<'de, T: Deserialize<'de>>(reader: &mut impl BufReader<'de>) -
     T::deserialize(Deserializer::new(reader))
 }
 
+/// Deserialize a `T` from the BSATN format in the buffered `reader` into `place`,
+/// reusing the allocations of `place` where possible.
+pub fn from_reader_in_place<'de, T: Deserialize<'de>>(
+    reader: &mut impl BufReader<'de>,
+    place: &mut T,
+) -> Result<(), DecodeError> {
+    T::deserialize_in_place(Deserializer::new(reader), place)
+}
+
 /// Deserialize a `T` from the BSATN format in `bytes`.
 pub fn from_slice<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, DecodeError> {
     from_reader(&mut &*bytes)
@@ -152,12 +161,20 @@ impl ToBsatn for ProductValue {
 
 #[cfg(test)]
 mod tests {
-    use super::{to_vec, DecodeError};
+    use super::{from_reader_in_place, from_slice, to_vec, DecodeError};
     use crate::proptest::generate_typed_value;
-    use crate::{meta_type::MetaType, AlgebraicType, AlgebraicValue};
+    use crate::{meta_type::MetaType, AlgebraicType, AlgebraicValue, SpacetimeType};
     use proptest::prelude::*;
     use proptest::proptest;
 
+    #[derive(Debug, PartialEq, SpacetimeType)]
+    #[sats(crate = crate)]
+    struct Row {
+        name: String,
+        bytes: Vec<u8>,
+        nums: Vec<u32>,
+    }
+
     #[test]
     fn type_to_binary_equivalent() {
         check_type(&AlgebraicType::meta_type());
@@ -180,6 +197,19 @@ mod tests {
             prop_assert_eq!(val, val_decoded);
         }
 
+        #[test]
+        fn bsatn_in_place_matches_fresh(
+            old in any::<(String, Vec<u8>, Vec<u32>)>(),
+            new in any::<(String, Vec<u8>, Vec<u32>)>(),
+        ) {
+            let new = Row { name: new.0, bytes: new.1, nums: new.2 };
+            let bytes = to_vec(&new).unwrap();
+            let mut place = Row { name: old.0, bytes: old.1, nums: old.2 };
+            from_reader_in_place(&mut &bytes[..], &mut place).unwrap();
+            prop_assert_eq!(&place, &new);
+            prop_assert_eq!(place, from_slice::<Row>(&bytes).unwrap());
+        }
+
         #[test]
         fn bsatn_non_zero_one_u8_aint_bool(val in 2u8..) {
             let bytes = [val];
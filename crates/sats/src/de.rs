@@ -557,6 +557,17 @@ pub trait Deserialize<'de>: Sized {
     /// Deserialize this value from the given `deserializer`.
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
 
+    /// Deserialize this value from the given `deserializer` into `place`,
+    /// reusing any allocations already owned by `place` where possible.
+    ///
+    /// The default implementation just overwrites `place` with a freshly deserialized value.
+    /// If an error is returned, `place` is left in an unspecified but valid state.
+    #[inline]
+    fn deserialize_in_place<D: Deserializer<'de>>(deserializer: D, place: &mut Self) -> Result<(), D::Error> {
+        *place = Self::deserialize(deserializer)?;
+        Ok(())
+    }
+
     /// used in the Deserialize for Vec<T> impl to allow specializing deserializing Vec<T> as bytes
     #[doc(hidden)]
     #[inline(always)]
@@ -564,6 +575,16 @@ pub trait Deserialize<'de>: Sized {
         deserializer.deserialize_array(BasicVecVisitor)
     }
 
+    /// used in the Deserialize for Vec<T> impl to allow specializing deserializing Vec<T> in place as bytes
+    #[doc(hidden)]
+    #[inline(always)]
+    fn __deserialize_vec_in_place<D: Deserializer<'de>>(
+        deserializer: D,
+        place: &mut Vec<Self>,
+    ) -> Result<(), D::Error> {
+        deserializer.deserialize_array(InPlaceVecVisitor(place))
+    }
+
     #[doc(hidden)]
     #[inline(always)]
     fn __deserialize_array<D: Deserializer<'de>, const N: usize>(deserializer: D) -> Result<[Self; N], D::Error> {
@@ -584,6 +605,18 @@ impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for PhantomData<T> {
     }
 }
 
+/// A [`DeserializeSeed`] which deserializes a `T` into an existing value,
+/// via [`Deserialize::deserialize_in_place`].
+pub struct InPlace<'a, T>(pub &'a mut T);
+
+impl<'de, T: Deserialize<'de>> DeserializeSeed<'de> for InPlace<'_, T> {
+    type Output = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Output, D::Error> {
+        T::deserialize_in_place(deserializer, self.0)
+    }
+}
+
 /// A vector with two operations: `with_capacity` and `push`.
 pub trait GrowingVec<T> {
     /// Create the collection with the given capacity.
@@ -631,6 +664,23 @@ impl<'de, T> ArrayVisitor<'de, T> for BasicVecVisitor {
     }
 }
 
+/// An implementation of [`ArrayVisitor<'de, T>`] which clears and refills an existing `Vec<T>`,
+/// keeping its allocation.
+struct InPlaceVecVisitor<'a, T>(&'a mut Vec<T>);
+
+impl<'de, T> ArrayVisitor<'de, T> for InPlaceVecVisitor<'_, T> {
+    type Output = ();
+
+    fn visit<A: ArrayAccess<'de, Element = T>>(self, mut vec: A) -> Result<Self::Output, A::Error> {
+        self.0.clear();
+        self.0.reserve(vec.size_hint().unwrap_or(0));
+        while let Some(x) = vec.next_element()? {
+            self.0.push(x)
+        }
+        Ok(())
+    }
+}
+
 /// An implementation of [`ArrayVisitor<'de, T>`] where the output is a `SmallVec<[T; N]>`.
 pub struct BasicSmallVecVisitor<const N: usize>;
 
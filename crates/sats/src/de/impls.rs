@@ -88,6 +88,13 @@ impl<'de> Deserialize<'de> for u8 {
         deserializer.deserialize_bytes(OwnedSliceVisitor)
     }
 
+    fn __deserialize_vec_in_place<D: Deserializer<'de>>(
+        deserializer: D,
+        place: &mut Vec<Self>,
+    ) -> Result<(), D::Error> {
+        deserializer.deserialize_bytes(InPlaceSliceVisitor(place))
+    }
+
     fn __deserialize_array<D: Deserializer<'de>, const N: usize>(deserializer: D) -> Result<[Self; N], D::Error> {
         deserializer.deserialize_bytes(ByteArrayVisitor)
     }
@@ -95,8 +102,27 @@ impl<'de> Deserialize<'de> for u8 {
 
 impl_deserialize!([] F32, de => f32::deserialize(de).map(Into::into));
 impl_deserialize!([] F64, de => f64::deserialize(de).map(Into::into));
-impl_deserialize!([] String, de => de.deserialize_str(OwnedSliceVisitor));
-impl_deserialize!([T: Deserialize<'de>] Vec<T>, de => T::__deserialize_vec(de));
+
+impl<'de> Deserialize<'de> for String {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(OwnedSliceVisitor)
+    }
+
+    fn deserialize_in_place<D: Deserializer<'de>>(deserializer: D, place: &mut Self) -> Result<(), D::Error> {
+        deserializer.deserialize_str(InPlaceSliceVisitor(place))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Vec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::__deserialize_vec(deserializer)
+    }
+
+    fn deserialize_in_place<D: Deserializer<'de>>(deserializer: D, place: &mut Self) -> Result<(), D::Error> {
+        T::__deserialize_vec_in_place(deserializer, place)
+    }
+}
+
 impl_deserialize!([T: Deserialize<'de>, const N: usize] SmallVec<[T; N]>, de => {
     de.deserialize_array(BasicSmallVecVisitor)
 });
@@ -121,6 +147,29 @@ impl<T: ToOwned + ?Sized> SliceVisitor<'_, T> for OwnedSliceVisitor {
     }
 }
 
+/// The visitor copies the slice into an existing owned buffer, keeping its allocation.
+struct InPlaceSliceVisitor<'a, O>(&'a mut O);
+
+impl SliceVisitor<'_, str> for InPlaceSliceVisitor<'_, String> {
+    type Output = ();
+
+    fn visit<E: Error>(self, slice: &str) -> Result<Self::Output, E> {
+        self.0.clear();
+        self.0.push_str(slice);
+        Ok(())
+    }
+}
+
+impl SliceVisitor<'_, [u8]> for InPlaceSliceVisitor<'_, Vec<u8>> {
+    type Output = ();
+
+    fn visit<E: Error>(self, slice: &[u8]) -> Result<Self::Output, E> {
+        self.0.clear();
+        self.0.extend_from_slice(slice);
+        Ok(())
+    }
+}
+
 /// The visitor will convert the byte slice to `[u8; N]`.
 ///
 /// When `slice.len() != N` an error will be raised.
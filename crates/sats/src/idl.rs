@@ -0,0 +1,690 @@
+//! A human-readable textual format for [`AlgebraicType`]s.
+//!
+//! The format is the base of the `.sats` schema language,
+//! which the `spacetimedb_schema` crate extends with declarations of tables, reducers, etc.
+//! Schemas written in it can be reviewed and diffed like any other source file,
+//! and authored independently of any module language.
+//!
+//! ```text
+//! ty := bool | i8 | u8 | i16 | u16 | i32 | u32 | i64 | u64 | i128 | u128 | i256 | u256
+//!     | f32 | f64 | string
+//!     | [ty]                              -- an array
+//!     | ty?                               -- an option, i.e., (some: ty | none)
+//!     | ()                                -- the unit product
+//!     | (elem, elem, ...)                 -- a product
+//!     | (|)                               -- the never sum
+//!     | (elem | elem | ...)               -- a sum
+//!     | &42                               -- a reference into a typespace
+//!     | name::name                        -- a named type, if the context supports them
+//! elem := name: ty | ty
+//! ```
+//!
+//! A sum with a single variant is written with a trailing `|`, e.g., `(a: u32 |)`.
+//! Within a sum, a bare name is a variant of the unit type, e.g., `(circle: f32 | empty)`.
+//! Names which aren't identifiers are written as string literals.
+//! Line comments start with `//`.
+
+use crate::de::fmt_fn;
+use crate::{AlgebraicType, AlgebraicTypeRef, ProductTypeElement, SumTypeVariant};
+use std::fmt::{self, Display};
+
+/// An error parsing a `.sats` source.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("{line}:{column}: {message}")]
+pub struct ParseError {
+    /// The line, counted from 1, at which the error occurred.
+    pub line: usize,
+    /// The column, counted in characters from 1, at which the error occurred.
+    pub column: usize,
+    pub message: String,
+}
+
+/// Parse the type `source`, in which named types are unknown.
+pub fn parse_type(source: &str) -> Result<AlgebraicType, ParseError> {
+    let mut parser = Parser::new(source)?;
+    let ty = parser.parse_type(&mut |_| None)?;
+    parser.expect_eof()?;
+    Ok(ty)
+}
+
+/// Wraps `ty` into a `Display`able in the `.sats` format, in which references are written as `&42`.
+pub fn fmt_type(ty: &AlgebraicType) -> impl '_ + Display {
+    fmt_type_with(ty, fmt_ref_default)
+}
+
+fn fmt_ref_default(r: AlgebraicTypeRef, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{r}")
+}
+
+/// Wraps `ty` into a `Display`able in the `.sats` format,
+/// in which references are written by `fmt_ref`, e.g., as the names of the types they refer to.
+pub fn fmt_type_with<'a>(
+    ty: &'a AlgebraicType,
+    fmt_ref: impl 'a + Fn(AlgebraicTypeRef, &mut fmt::Formatter) -> fmt::Result,
+) -> impl 'a + Display {
+    fmt_fn(move |f| write_type(f, ty, &fmt_ref))
+}
+
+fn write_type(
+    f: &mut fmt::Formatter,
+    ty: &AlgebraicType,
+    fmt_ref: &dyn Fn(AlgebraicTypeRef, &mut fmt::Formatter) -> fmt::Result,
+) -> fmt::Result {
+    let name = match ty {
+        AlgebraicType::Ref(r) => return fmt_ref(*r, f),
+        AlgebraicType::Array(array) => {
+            f.write_str("[")?;
+            write_type(f, &array.elem_ty, fmt_ref)?;
+            return f.write_str("]");
+        }
+        AlgebraicType::Sum(sum) => {
+            if let Some(some) = sum.as_option() {
+                write_type(f, some, fmt_ref)?;
+                return f.write_str("?");
+            }
+            if sum.variants.is_empty() {
+                return f.write_str("(|)");
+            }
+            f.write_str("(")?;
+            for (i, variant) in sum.variants.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(" | ")?;
+                }
+                write_sum_variant(f, variant, fmt_ref)?;
+            }
+            return f.write_str(if sum.variants.len() == 1 { " |)" } else { ")" });
+        }
+        AlgebraicType::Product(product) => {
+            f.write_str("(")?;
+            for (i, elem) in product.elements.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_product_element(f, elem, fmt_ref)?;
+            }
+            return f.write_str(")");
+        }
+        AlgebraicType::Bool => "bool",
+        AlgebraicType::I8 => "i8",
+        AlgebraicType::U8 => "u8",
+        AlgebraicType::I16 => "i16",
+        AlgebraicType::U16 => "u16",
+        AlgebraicType::I32 => "i32",
+        AlgebraicType::U32 => "u32",
+        AlgebraicType::I64 => "i64",
+        AlgebraicType::U64 => "u64",
+        AlgebraicType::I128 => "i128",
+        AlgebraicType::U128 => "u128",
+        AlgebraicType::I256 => "i256",
+        AlgebraicType::U256 => "u256",
+        AlgebraicType::F32 => "f32",
+        AlgebraicType::F64 => "f64",
+        AlgebraicType::String => "string",
+    };
+    f.write_str(name)
+}
+
+/// Writes the element of a product, e.g., `name: string`.
+pub fn write_product_element(
+    f: &mut fmt::Formatter,
+    elem: &ProductTypeElement,
+    fmt_ref: &dyn Fn(AlgebraicTypeRef, &mut fmt::Formatter) -> fmt::Result,
+) -> fmt::Result {
+    if let Some(name) = &elem.name {
+        write!(f, "{}: ", fmt_name(name))?;
+    }
+    write_type(f, &elem.algebraic_type, fmt_ref)
+}
+
+/// Writes the variant of a sum, e.g., `circle: f32` or `empty`.
+pub fn write_sum_variant(
+    f: &mut fmt::Formatter,
+    variant: &SumTypeVariant,
+    fmt_ref: &dyn Fn(AlgebraicTypeRef, &mut fmt::Formatter) -> fmt::Result,
+) -> fmt::Result {
+    match &variant.name {
+        Some(name) if variant.algebraic_type.is_unit() && is_bare_name(name) => f.write_str(name),
+        Some(name) => {
+            write!(f, "{}: ", fmt_name(name))?;
+            write_type(f, &variant.algebraic_type, fmt_ref)
+        }
+        None => write_type(f, &variant.algebraic_type, fmt_ref),
+    }
+}
+
+/// Wraps `name` into a `Display`able, which is a string literal if `name` is not an identifier.
+pub fn fmt_name(name: &str) -> impl '_ + Display {
+    fmt_fn(move |f| {
+        if is_identifier(name) {
+            f.write_str(name)
+        } else {
+            write!(f, "{name:?}")
+        }
+    })
+}
+
+/// The names of the primitive types, which are reserved in type position.
+const PRIMITIVES: &[(&str, AlgebraicType)] = &[
+    ("bool", AlgebraicType::Bool),
+    ("i8", AlgebraicType::I8),
+    ("u8", AlgebraicType::U8),
+    ("i16", AlgebraicType::I16),
+    ("u16", AlgebraicType::U16),
+    ("i32", AlgebraicType::I32),
+    ("u32", AlgebraicType::U32),
+    ("i64", AlgebraicType::I64),
+    ("u64", AlgebraicType::U64),
+    ("i128", AlgebraicType::I128),
+    ("u128", AlgebraicType::U128),
+    ("i256", AlgebraicType::I256),
+    ("u256", AlgebraicType::U256),
+    ("f32", AlgebraicType::F32),
+    ("f64", AlgebraicType::F64),
+    ("string", AlgebraicType::String),
+];
+
+fn primitive(name: &str) -> Option<AlgebraicType> {
+    PRIMITIVES.iter().find(|(n, _)| *n == name).map(|(_, ty)| ty.clone())
+}
+
+/// Returns whether `s` is an identifier, i.e., `[A-Za-z_][A-Za-z0-9_]*`.
+pub fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Returns whether a unit variant named `name` may be written as just its name.
+fn is_bare_name(name: &str) -> bool {
+    is_identifier(name) && primitive(name).is_none()
+}
+
+/// A token of a `.sats` source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token<'a> {
+    /// An identifier or keyword.
+    Ident(&'a str),
+    /// An unsigned integer literal.
+    Int(u128),
+    /// A string literal, with its escapes processed.
+    Str(String),
+    /// A punctuation mark, e.g., `::`.
+    Punct(&'static str),
+    /// The end of the source.
+    Eof,
+}
+
+impl Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "`{ident}`"),
+            Token::Int(n) => write!(f, "`{n}`"),
+            Token::Str(s) => write!(f, "`{s:?}`"),
+            Token::Punct(p) => write!(f, "`{p}`"),
+            Token::Eof => f.write_str("end of input"),
+        }
+    }
+}
+
+/// The punctuation marks of the format, longest first.
+const PUNCTUATION: &[&str] = &[
+    "::", "->", "=>", "(", ")", "[", "]", "{", "}", ",", ":", ";", "|", "?", "&", "=", "@", "-",
+];
+
+/// A parser of `.sats` sources.
+///
+/// Besides parsing types, the parser exposes its tokens,
+/// so that other crates can parse declarations containing types.
+pub struct Parser<'a> {
+    tokens: Vec<(Token<'a>, usize, usize)>,
+    pos: usize,
+}
+
+/// An element of a product or sum, before it's known which.
+enum Elem<'a> {
+    Named(Box<str>, AlgebraicType),
+    /// A bare identifier, which is a type in a product but a unit variant in a sum.
+    Bare(&'a str),
+    Unnamed(AlgebraicType),
+}
+
+impl<'a> Parser<'a> {
+    /// Tokenizes `source`.
+    pub fn new(source: &'a str) -> Result<Self, ParseError> {
+        let mut tokens = Vec::new();
+        let (mut line, mut line_start) = (1, 0);
+        let mut rest = source;
+        loop {
+            let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace());
+            for (i, c) in rest[..rest.len() - trimmed.len()].char_indices() {
+                if c == '\n' {
+                    line += 1;
+                    line_start = source.len() - rest.len() + i + 1;
+                }
+            }
+            rest = trimmed;
+            if let Some(comment) = rest.strip_prefix("//") {
+                rest = comment.trim_start_matches(|c: char| c != '\n');
+                continue;
+            }
+            let offset = source.len() - rest.len();
+            let column = source[line_start..offset].chars().count() + 1;
+            let error = |message: String| ParseError { line, column, message };
+            let Some(c) = rest.chars().next() else {
+                tokens.push((Token::Eof, line, column));
+                break;
+            };
+            let (token, len) = if c.is_ascii_alphabetic() || c == '_' {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                (Token::Ident(&rest[..len]), len)
+            } else if c.is_ascii_digit() {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_digit() || c == '_'))
+                    .unwrap_or(rest.len());
+                let n = rest[..len]
+                    .replace('_', "")
+                    .parse()
+                    .map_err(|_| error("integer literal out of range".into()))?;
+                (Token::Int(n), len)
+            } else if c == '"' {
+                let (s, len) = lex_string(rest).map_err(error)?;
+                (Token::Str(s), len)
+            } else if let Some(p) = PUNCTUATION.iter().find(|p| rest.starts_with(**p)) {
+                (Token::Punct(*p), p.len())
+            } else {
+                return Err(error(format!("unexpected character `{c}`")));
+            };
+            tokens.push((token, line, column));
+            rest = &rest[len..];
+        }
+        Ok(Self { tokens, pos: 0 })
+    }
+
+    /// Returns the next token, without consuming it.
+    pub fn peek(&self) -> &Token<'a> {
+        &self.tokens[self.pos].0
+    }
+
+    /// Returns the token after the next one, without consuming anything.
+    pub fn peek2(&self) -> &Token<'a> {
+        &self.tokens[(self.pos + 1).min(self.tokens.len() - 1)].0
+    }
+
+    /// Consumes and returns the next token.
+    pub fn next_token(&mut self) -> Token<'a> {
+        let token = self.tokens[self.pos].0.clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    /// Returns an error at the next token.
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        self.error_at(self.position(), message)
+    }
+
+    /// Returns the position of the next token, for use with [`Parser::error_at`].
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Returns an error at the token at `position`.
+    pub fn error_at(&self, position: usize, message: impl Into<String>) -> ParseError {
+        let (_, line, column) = self.tokens[position];
+        ParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Returns an error at the next token, saying that `expected` was expected instead.
+    pub fn unexpected(&self, expected: &str) -> ParseError {
+        self.error(format!("expected {expected}, found {}", self.peek()))
+    }
+
+    /// Returns whether the whole source has been consumed.
+    pub fn is_eof(&self) -> bool {
+        *self.peek() == Token::Eof
+    }
+
+    /// Fails unless the whole source has been consumed.
+    pub fn expect_eof(&self) -> Result<(), ParseError> {
+        if self.is_eof() {
+            Ok(())
+        } else {
+            Err(self.unexpected("end of input"))
+        }
+    }
+
+    /// Consumes the punctuation mark `punct`, if it is next.
+    pub fn eat(&mut self, punct: &str) -> bool {
+        let found = matches!(self.peek(), Token::Punct(p) if *p == punct);
+        if found {
+            self.next_token();
+        }
+        found
+    }
+
+    /// Consumes the punctuation mark `punct`, which must be next.
+    pub fn expect(&mut self, punct: &str) -> Result<(), ParseError> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{punct}`")))
+        }
+    }
+
+    /// Consumes the keyword `keyword`, if it is next.
+    pub fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = *self.peek() == Token::Ident(keyword);
+        if found {
+            self.next_token();
+        }
+        found
+    }
+
+    /// Consumes the keyword `keyword`, which must be next.
+    pub fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{keyword}`")))
+        }
+    }
+
+    /// Consumes an identifier.
+    pub fn ident(&mut self) -> Result<&'a str, ParseError> {
+        match *self.peek() {
+            Token::Ident(ident) => {
+                self.next_token();
+                Ok(ident)
+            }
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    /// Consumes a name, which is an identifier or a string literal.
+    pub fn name(&mut self) -> Result<Box<str>, ParseError> {
+        match self.peek() {
+            Token::Str(_) => self.string().map(Into::into),
+            _ => self.ident().map(Into::into),
+        }
+    }
+
+    /// Consumes a string literal.
+    pub fn string(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Token::Str(_) => match self.next_token() {
+                Token::Str(s) => Ok(s),
+                _ => unreachable!(),
+            },
+            _ => Err(self.unexpected("a string literal")),
+        }
+    }
+
+    /// Consumes an integer literal, which may be negated.
+    pub fn int(&mut self) -> Result<i128, ParseError> {
+        let negative = self.eat("-");
+        match *self.peek() {
+            Token::Int(n) => {
+                let n = if negative {
+                    0i128.checked_sub_unsigned(n)
+                } else {
+                    i128::try_from(n).ok()
+                };
+                let n = n.ok_or_else(|| self.error("integer literal out of range"))?;
+                self.next_token();
+                Ok(n)
+            }
+            _ => Err(self.unexpected("an integer literal")),
+        }
+    }
+
+    /// Consumes a path, e.g., `a::b::c`.
+    pub fn path(&mut self) -> Result<Vec<&'a str>, ParseError> {
+        let mut path = vec![self.ident()?];
+        while self.eat("::") {
+            path.push(self.ident()?);
+        }
+        Ok(path)
+    }
+
+    /// Consumes a type.
+    ///
+    /// Named types are resolved by `resolve`, which is passed the name, e.g., `a::b::c`,
+    /// and returns `None` if there is no such type.
+    pub fn parse_type(
+        &mut self,
+        resolve: &mut dyn FnMut(&str) -> Option<AlgebraicType>,
+    ) -> Result<AlgebraicType, ParseError> {
+        let mut ty = self.parse_type_atom(resolve)?;
+        while self.eat("?") {
+            ty = AlgebraicType::option(ty);
+        }
+        Ok(ty)
+    }
+
+    fn parse_type_atom(
+        &mut self,
+        resolve: &mut dyn FnMut(&str) -> Option<AlgebraicType>,
+    ) -> Result<AlgebraicType, ParseError> {
+        if self.eat("[") {
+            let elem_ty = self.parse_type(resolve)?;
+            self.expect("]")?;
+            return Ok(AlgebraicType::array(elem_ty));
+        }
+        if self.eat("&") {
+            let Token::Int(n) = *self.peek() else {
+                return Err(self.unexpected("a type reference"));
+            };
+            let r = u32::try_from(n).map_err(|_| self.error("type reference out of range"))?;
+            self.next_token();
+            return Ok(AlgebraicType::Ref(AlgebraicTypeRef(r)));
+        }
+        if self.eat("(") {
+            return self.parse_product_or_sum(resolve);
+        }
+        if let Token::Ident(ident) = *self.peek() {
+            if let Some(ty) = primitive(ident) {
+                self.next_token();
+                return Ok(ty);
+            }
+            let start = self.position();
+            let path = self.path()?.join("::");
+            return resolve(&path).ok_or_else(|| self.error_at(start, format!("unknown type `{path}`")));
+        }
+        Err(self.unexpected("a type"))
+    }
+
+    /// Parses the rest of a product or sum, after the opening `(`.
+    fn parse_product_or_sum(
+        &mut self,
+        resolve: &mut dyn FnMut(&str) -> Option<AlgebraicType>,
+    ) -> Result<AlgebraicType, ParseError> {
+        if self.eat("|") {
+            self.expect(")")?;
+            return Ok(AlgebraicType::never());
+        }
+        let mut elems = Vec::new();
+        let mut separator = None;
+        while !self.eat(")") {
+            elems.push(self.parse_elem(resolve)?);
+            if self.eat(")") {
+                break;
+            }
+            let sep = match self.peek() {
+                Token::Punct(p @ ("," | "|")) => *p,
+                _ => return Err(self.unexpected("`,`, `|` or `)`")),
+            };
+            if separator.is_some_and(|s| s != sep) {
+                return Err(self.error("products are separated by `,` and sums by `|`, but not both"));
+            }
+            separator = Some(sep);
+            self.next_token();
+        }
+
+        if separator == Some("|") {
+            let variants = elems.into_iter().map(|elem| match elem {
+                Elem::Named(name, ty) => SumTypeVariant::new_named(ty, name),
+                Elem::Bare(name) => SumTypeVariant::unit(name),
+                Elem::Unnamed(ty) => SumTypeVariant::from(ty),
+            });
+            Ok(AlgebraicType::sum(variants.collect::<Box<[_]>>()))
+        } else {
+            let elements = elems
+                .into_iter()
+                .map(|elem| {
+                    Ok(match elem {
+                        Elem::Named(name, ty) => ProductTypeElement::new_named(ty, name),
+                        Elem::Bare(name) => ProductTypeElement::from(
+                            resolve(name).ok_or_else(|| self.error(format!("unknown type `{name}`")))?,
+                        ),
+                        Elem::Unnamed(ty) => ProductTypeElement::from(ty),
+                    })
+                })
+                .collect::<Result<Box<[_]>, ParseError>>()?;
+            Ok(AlgebraicType::product(elements))
+        }
+    }
+
+    fn parse_elem(&mut self, resolve: &mut dyn FnMut(&str) -> Option<AlgebraicType>) -> Result<Elem<'a>, ParseError> {
+        match (self.peek(), self.peek2()) {
+            (Token::Ident(_) | Token::Str(_), Token::Punct(":")) => {
+                let name = self.name()?;
+                self.expect(":")?;
+                Ok(Elem::Named(name, self.parse_type(resolve)?))
+            }
+            (&Token::Ident(ident), Token::Punct("," | "|" | ")")) if primitive(ident).is_none() => {
+                self.next_token();
+                Ok(Elem::Bare(ident))
+            }
+            _ => Ok(Elem::Unnamed(self.parse_type(resolve)?)),
+        }
+    }
+}
+
+/// Lexes the string literal at the start of `s`, returning its value and length.
+fn lex_string(s: &str) -> Result<(String, usize), String> {
+    let mut value = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((value, i + 1)),
+            '\\' => {
+                let escaped = match chars.next().map(|(_, c)| c) {
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('0') => '\0',
+                    Some('\\') => '\\',
+                    Some('"') => '"',
+                    Some('\'') => '\'',
+                    Some('u') => {
+                        let rest = &s[i + 2..];
+                        let hex = rest
+                            .strip_prefix('{')
+                            .and_then(|rest| rest.split_once('}'))
+                            .map(|(hex, _)| hex)
+                            .ok_or("invalid unicode escape")?;
+                        let c = u32::from_str_radix(hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or("invalid unicode escape")?;
+                        // Skip the `{hex}`.
+                        for _ in 0..hex.len() + 2 {
+                            chars.next();
+                        }
+                        c
+                    }
+                    _ => return Err("invalid escape in string literal".into()),
+                };
+                value.push(escaped);
+            }
+            c => value.push(c),
+        }
+    }
+    Err("unterminated string literal".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proptest::generate_algebraic_type;
+    use proptest::prelude::*;
+
+    #[test]
+    fn parse_types() {
+        assert_eq!(parse_type("u32").unwrap(), AlgebraicType::U32);
+        assert_eq!(
+            parse_type("[string]").unwrap(),
+            AlgebraicType::array(AlgebraicType::String)
+        );
+        assert_eq!(parse_type("()").unwrap(), AlgebraicType::unit());
+        assert_eq!(parse_type("(|)").unwrap(), AlgebraicType::never());
+        assert_eq!(parse_type("u8?").unwrap(), AlgebraicType::option(AlgebraicType::U8));
+        assert_eq!(
+            parse_type("(x: f32, y: f32)").unwrap(),
+            AlgebraicType::product([("x", AlgebraicType::F32), ("y", AlgebraicType::F32)])
+        );
+        assert_eq!(
+            parse_type("(circle: f32 | empty)").unwrap(),
+            AlgebraicType::sum([("circle", AlgebraicType::F32), ("empty", AlgebraicType::unit())])
+        );
+        assert_eq!(
+            parse_type("(a: u32 |)").unwrap(),
+            AlgebraicType::sum([("a", AlgebraicType::U32)])
+        );
+        assert_eq!(
+            parse_type("(u32, &3) // a comment").unwrap(),
+            AlgebraicType::product([AlgebraicType::U32, AlgebraicType::Ref(AlgebraicTypeRef(3))])
+        );
+        assert_eq!(
+            parse_type(r#"("not an ident": bool, "\u{e9}\t\"": bool)"#).unwrap(),
+            AlgebraicType::product([("not an ident", AlgebraicType::Bool), ("é\t\"", AlgebraicType::Bool)])
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = parse_type("(a: u32,\n  b: Point)").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 6));
+        assert_eq!(err.message, "unknown type `Point`");
+
+        let err = parse_type("(a: u32, b | c)").unwrap_err();
+        assert_eq!(
+            err.message,
+            "products are separated by `,` and sums by `|`, but not both"
+        );
+
+        let err = parse_type("[u32").unwrap_err();
+        assert_eq!(err.message, "expected `]`, found end of input");
+    }
+
+    #[test]
+    fn print_types() {
+        let ty = AlgebraicType::product([
+            ("id", AlgebraicType::U64),
+            ("tags", AlgebraicType::array(AlgebraicType::String)),
+            ("nick", AlgebraicType::option(AlgebraicType::String)),
+            (
+                "shape",
+                AlgebraicType::sum([("circle", AlgebraicType::F32), ("u8", AlgebraicType::unit())]),
+            ),
+        ]);
+        assert_eq!(
+            fmt_type(&ty).to_string(),
+            "(id: u64, tags: [string], nick: string?, shape: (circle: f32 | u8: ()))"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn print_parse_roundtrip(ty in generate_algebraic_type()) {
+            prop_assert_eq!(parse_type(&fmt_type(&ty).to_string()).unwrap(), ty);
+        }
+    }
+}
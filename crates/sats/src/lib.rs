@@ -4,12 +4,15 @@ pub mod algebraic_value;
 mod algebraic_value_hash;
 pub mod array_type;
 pub mod array_value;
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod bsatn;
 pub mod buffer;
 pub mod convert;
 pub mod de;
 pub mod hash;
 pub mod hex;
+pub mod idl;
 pub mod meta_type;
 pub mod primitives;
 pub mod product_type;
@@ -13,6 +13,26 @@ pub const OPTION_SOME_TAG: &str = "some";
 /// The tag used for the `none` variant of the special `option` sum type.
 pub const OPTION_NONE_TAG: &str = "none";
 
+/// Returns whether `ty` is the payload of the `Interval` variant of `ScheduleAt`,
+/// either `{ period: u64, jitter: u64, fixed_rate: bool }` or, in older modules, `u64`.
+fn is_schedule_interval(ty: &AlgebraicType) -> bool {
+    match ty {
+        AlgebraicType::U64 => true,
+        AlgebraicType::Product(prod) => match &*prod.elements {
+            [period, jitter, fixed_rate] => {
+                period.has_name("period")
+                    && period.algebraic_type.is_u64()
+                    && jitter.has_name("jitter")
+                    && jitter.algebraic_type.is_u64()
+                    && fixed_rate.has_name("fixed_rate")
+                    && fixed_rate.algebraic_type.is_bool()
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 /// A structural sum type.
 ///
 /// Unlike most languages, sums in SATS are *[structural]* and not nominal.
@@ -123,13 +143,14 @@ impl SumType {
     }
 
     /// Return whether this sum type is the special `ScheduleAt` type,
-    /// `Interval(u64) | Time(u64)`.
+    /// `Interval({ period: u64, jitter: u64, fixed_rate: bool }) | Time(u64)`.
+    /// The older `Interval(u64) | Time(u64)` is also recognized.
     /// Does not follow `Ref`s.
     pub fn is_schedule_at(&self) -> bool {
         match &*self.variants {
             [first, second] => {
                 first.has_name(SCHEDULE_AT_INTERVAL_TAG)
-                    && first.algebraic_type.is_u64()
+                    && is_schedule_interval(&first.algebraic_type)
                     && second.has_name(SCHEDULE_AT_TIME_TAG)
                     && second.algebraic_type.is_u64()
             }
@@ -0,0 +1,175 @@
+//! Checking the arguments of reducer calls against the constraints declared for them.
+//!
+//! The host checks every call before running the reducer,
+//! and generated client code performs the same checks before sending a call,
+//! so the rules here must stay in sync with the validators emitted by client codegen.
+
+use spacetimedb_lib::db::raw_def::v9::ArgConstraint;
+use spacetimedb_sats::{i256, u256, AlgebraicType, AlgebraicValue, ArrayValue};
+use std::cmp::Ordering;
+
+/// An argument which violates a constraint declared for it.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ArgConstraintViolation {
+    #[error("`{arg}` must be at least {min} long")]
+    TooShort { arg: Box<str>, min: u32 },
+    #[error("`{arg}` must be at most {max} long")]
+    TooLong { arg: Box<str>, max: u32 },
+    #[error("`{arg}` must be at least {min}")]
+    TooSmall { arg: Box<str>, min: i128 },
+    #[error("`{arg}` must be at most {max}")]
+    TooLarge { arg: Box<str>, max: i128 },
+    #[error("`{arg}` must be one of {}", .allowed.iter().map(|v| format!("{v:?}")).collect::<Vec<_>>().join(", "))]
+    NotOneOf { arg: Box<str>, allowed: Vec<Box<str>> },
+}
+
+/// Returns whether `constraint` can be declared for arguments of type `ty`.
+pub fn applies_to(constraint: &ArgConstraint, ty: &AlgebraicType) -> bool {
+    match constraint {
+        ArgConstraint::MinLength(_) | ArgConstraint::MaxLength(_) => ty.is_string() || ty.is_array(),
+        ArgConstraint::Min(_) | ArgConstraint::Max(_) => ty.is_integer(),
+        ArgConstraint::OneOf(_) => ty.is_string(),
+    }
+}
+
+/// Check the argument `value`, named `arg` in error messages, against `constraint`.
+///
+/// `value` must be of a type to which the constraint [applies](applies_to).
+/// Values of other types pass every check.
+pub fn check(constraint: &ArgConstraint, arg: &str, value: &AlgebraicValue) -> Result<(), ArgConstraintViolation> {
+    let violated = match constraint {
+        &ArgConstraint::MinLength(min) => length(value).is_some_and(|len| len < min as usize),
+        &ArgConstraint::MaxLength(max) => length(value).is_some_and(|len| len > max as usize),
+        &ArgConstraint::Min(min) => cmp_integer(value, min) == Some(Ordering::Less),
+        &ArgConstraint::Max(max) => cmp_integer(value, max) == Some(Ordering::Greater),
+        ArgConstraint::OneOf(allowed) => matches!(value, AlgebraicValue::String(s) if !allowed.contains(s)),
+    };
+    match violated {
+        true => Err(violation(constraint, arg)),
+        false => Ok(()),
+    }
+}
+
+/// Returns the violation reported for an argument, named `arg`, which violates `constraint`.
+///
+/// Client codegen uses this to report the same messages as the host.
+pub fn violation(constraint: &ArgConstraint, arg: &str) -> ArgConstraintViolation {
+    let arg = arg.into();
+    match *constraint {
+        ArgConstraint::MinLength(min) => ArgConstraintViolation::TooShort { arg, min },
+        ArgConstraint::MaxLength(max) => ArgConstraintViolation::TooLong { arg, max },
+        ArgConstraint::Min(min) => ArgConstraintViolation::TooSmall { arg, min },
+        ArgConstraint::Max(max) => ArgConstraintViolation::TooLarge { arg, max },
+        ArgConstraint::OneOf(ref allowed) => ArgConstraintViolation::NotOneOf {
+            arg,
+            allowed: allowed.clone(),
+        },
+    }
+}
+
+/// Returns the length of a string, in Unicode scalar values, or of an array.
+fn length(value: &AlgebraicValue) -> Option<usize> {
+    match value {
+        AlgebraicValue::String(s) => Some(s.chars().count()),
+        AlgebraicValue::Array(array) => Some(ArrayValue::len(array)),
+        _ => None,
+    }
+}
+
+/// Compares the integer `value` to `bound`.
+fn cmp_integer(value: &AlgebraicValue, bound: i128) -> Option<Ordering> {
+    Some(match *value {
+        AlgebraicValue::I8(v) => i128::from(v).cmp(&bound),
+        AlgebraicValue::U8(v) => i128::from(v).cmp(&bound),
+        AlgebraicValue::I16(v) => i128::from(v).cmp(&bound),
+        AlgebraicValue::U16(v) => i128::from(v).cmp(&bound),
+        AlgebraicValue::I32(v) => i128::from(v).cmp(&bound),
+        AlgebraicValue::U32(v) => i128::from(v).cmp(&bound),
+        AlgebraicValue::I64(v) => i128::from(v).cmp(&bound),
+        AlgebraicValue::U64(v) => i128::from(v).cmp(&bound),
+        AlgebraicValue::I128(v) => { v.0 }.cmp(&bound),
+        AlgebraicValue::U128(v) => match u128::try_from(bound) {
+            Ok(bound) => { v.0 }.cmp(&bound),
+            Err(_) => Ordering::Greater,
+        },
+        AlgebraicValue::I256(ref v) => (**v).cmp(&i256::from(bound)),
+        AlgebraicValue::U256(ref v) => match u128::try_from(bound) {
+            Ok(bound) => (**v).cmp(&u256::from(bound)),
+            Err(_) => Ordering::Greater,
+        },
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_counts_chars() {
+        let value = AlgebraicValue::String("héllo".into());
+        assert!(check(&ArgConstraint::MaxLength(5), "name", &value).is_ok());
+        assert_eq!(
+            check(&ArgConstraint::MaxLength(4), "name", &value),
+            Err(ArgConstraintViolation::TooLong {
+                arg: "name".into(),
+                max: 4
+            })
+        );
+        assert!(check(&ArgConstraint::MinLength(5), "name", &value).is_ok());
+        assert!(check(&ArgConstraint::MinLength(6), "name", &value).is_err());
+    }
+
+    #[test]
+    fn length_of_arrays() {
+        let value = AlgebraicValue::Array(ArrayValue::U32([1, 2, 3].into()));
+        assert!(check(&ArgConstraint::MaxLength(3), "xs", &value).is_ok());
+        assert!(check(&ArgConstraint::MaxLength(2), "xs", &value).is_err());
+    }
+
+    #[test]
+    fn integer_bounds() {
+        assert!(check(&ArgConstraint::Min(0), "n", &AlgebraicValue::I32(0)).is_ok());
+        assert!(check(&ArgConstraint::Min(0), "n", &AlgebraicValue::I32(-1)).is_err());
+        assert!(check(&ArgConstraint::Max(100), "n", &AlgebraicValue::U8(100)).is_ok());
+        assert!(check(&ArgConstraint::Max(100), "n", &AlgebraicValue::U8(101)).is_err());
+        assert!(check(&ArgConstraint::Max(-1), "n", &AlgebraicValue::U128(0.into())).is_err());
+        assert!(check(
+            &ArgConstraint::Max(i128::MAX),
+            "n",
+            &AlgebraicValue::U128(u128::MAX.into())
+        )
+        .is_err());
+        assert!(check(
+            &ArgConstraint::Min(-5),
+            "n",
+            &AlgebraicValue::I256(Box::new(i256::new(-5)))
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn one_of() {
+        let constraint = ArgConstraint::OneOf(vec!["red".into(), "green".into()]);
+        assert!(check(&constraint, "color", &AlgebraicValue::String("red".into())).is_ok());
+        assert_eq!(
+            check(&constraint, "color", &AlgebraicValue::String("blue".into()))
+                .unwrap_err()
+                .to_string(),
+            r#"`color` must be one of "red", "green""#
+        );
+    }
+
+    #[test]
+    fn applicability() {
+        assert!(applies_to(&ArgConstraint::MaxLength(1), &AlgebraicType::String));
+        assert!(applies_to(
+            &ArgConstraint::MaxLength(1),
+            &AlgebraicType::array(AlgebraicType::U8)
+        ));
+        assert!(!applies_to(&ArgConstraint::MaxLength(1), &AlgebraicType::U32));
+        assert!(applies_to(&ArgConstraint::Min(1), &AlgebraicType::I64));
+        assert!(!applies_to(&ArgConstraint::Min(1), &AlgebraicType::F64));
+        assert!(!applies_to(&ArgConstraint::OneOf(vec![]), &AlgebraicType::U8));
+    }
+}
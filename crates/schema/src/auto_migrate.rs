@@ -111,6 +111,8 @@ pub enum AutoMigrateStep<'def> {
 
     /// Change the access of a table.
     ChangeAccess(<TableDef as ModuleDefLookup>::Key<'def>),
+    /// Change the durability of a table.
+    ChangeDurability(<TableDef as ModuleDefLookup>::Key<'def>),
 }
 
 /// Something that might prevent an automatic migration.
@@ -135,6 +137,15 @@ pub enum AutoMigrateError {
         type2: PrettyAlgebraicType,
     },
 
+    #[error("Changing the generated columns of table {table} requires a manual migration")]
+    ChangeGeneratedColumns { table: Identifier },
+
+    #[error("Changing the counters of table {table} requires a manual migration")]
+    ChangeCounters { table: Identifier },
+
+    #[error("Changing the soft deletes of table {table} requires a manual migration")]
+    ChangeSoftDelete { table: Identifier },
+
     #[error("Adding a unique constraint {constraint} requires a manual migration")]
     AddUniqueConstraint { constraint: Box<str> },
 
@@ -172,6 +183,25 @@ pub fn ponder_migrate<'def>(old: &'def ModuleDef, new: &'def ModuleDef) -> Resul
 
 /// Construct an automatic migration plan, or reject with reasons why automatic migration can't be performed.
 pub fn ponder_auto_migrate<'def>(old: &'def ModuleDef, new: &'def ModuleDef) -> Result<AutoMigratePlan<'def>> {
+    let (plan, errors) = ponder_auto_migrate_partial(old, new);
+    if errors.is_empty() {
+        Ok(plan)
+    } else {
+        Err(ErrorStream::expect_nonempty(errors))
+    }
+}
+
+/// Compare `old` and `new` as [`ponder_auto_migrate`] does,
+/// but rather than rejecting the migration outright when some changes can't be performed automatically,
+/// return the steps for the changes that can be alongside the reasons why the rest can't.
+///
+/// Unless there are no such reasons, the returned plan must not be executed,
+/// as it is missing the changes which require a manual migration.
+/// It is only suitable for describing the differences between `old` and `new`.
+pub fn ponder_auto_migrate_partial<'def>(
+    old: &'def ModuleDef,
+    new: &'def ModuleDef,
+) -> (AutoMigratePlan<'def>, Vec<AutoMigrateError>) {
     // Both the old and new database definitions have already been validated (this is enforced by the types).
     // All we have to do is walk through and compare them.
     let mut plan = AutoMigratePlan {
@@ -199,12 +229,16 @@ pub fn ponder_auto_migrate<'def>(old: &'def ModuleDef, new: &'def ModuleDef) ->
     // have already been reflected in the database state.
     let rls_ok = auto_migrate_row_level_security(&mut plan);
 
-    let ((), (), (), (), ()) = (tables_ok, indexes_ok, sequences_ok, constraints_ok, rls_ok).combine_errors()?;
+    let errors = [tables_ok, indexes_ok, sequences_ok, constraints_ok, rls_ok]
+        .into_iter()
+        .filter_map(|result| result.err())
+        .flatten()
+        .collect();
 
     plan.steps.sort();
     plan.prechecks.sort();
 
-    Ok(plan)
+    (plan, errors)
 }
 
 /// A diff between two items.
@@ -276,6 +310,36 @@ fn auto_migrate_table<'def>(plan: &mut AutoMigratePlan<'def>, old: &'def TableDe
     if old.table_access != new.table_access {
         plan.steps.push(AutoMigrateStep::ChangeAccess(key));
     }
+    if old.durability != new.durability {
+        plan.steps.push(AutoMigrateStep::ChangeDurability(key));
+    }
+    // Existing rows would need their generated columns recomputed.
+    let generated_ok: Result<()> = if old.generated_columns == new.generated_columns {
+        Ok(())
+    } else {
+        Err(AutoMigrateError::ChangeGeneratedColumns {
+            table: old.name.clone(),
+        }
+        .into())
+    };
+    // Existing counts would need to be recomputed.
+    let counters_ok: Result<()> = if old.counters == new.counters {
+        Ok(())
+    } else {
+        Err(AutoMigrateError::ChangeCounters {
+            table: old.name.clone(),
+        }
+        .into())
+    };
+    // The history kept so far would need to be discarded or backfilled.
+    let soft_delete_ok: Result<()> = if old.soft_delete == new.soft_delete {
+        Ok(())
+    } else {
+        Err(AutoMigrateError::ChangeSoftDelete {
+            table: old.name.clone(),
+        }
+        .into())
+    };
     if old.schedule != new.schedule {
         // Note: this handles the case where there's an altered ScheduleDef for some reason.
         if let Some(old_schedule) = old.schedule.as_ref() {
@@ -336,7 +400,7 @@ fn auto_migrate_table<'def>(plan: &mut AutoMigratePlan<'def>, old: &'def TableDe
     })
     .collect_all_errors();
 
-    let ((), ()) = (type_ok, columns_ok).combine_errors()?;
+    let ((), (), (), (), ()) = (type_ok, generated_ok, counters_ok, soft_delete_ok, columns_ok).combine_errors()?;
     Ok(())
 }
 
@@ -876,4 +940,49 @@ mod tests {
         // but different columns from an old one.
         // We've left the check in, just in case this changes in the future.
     }
+
+    #[test]
+    fn partial_auto_migration() {
+        let mut old_builder = RawModuleDefV9Builder::new();
+        old_builder
+            .build_table_with_new_type("Apples", ProductType::from([("id", AlgebraicType::U64)]), true)
+            .finish();
+        let old_def: ModuleDef = old_builder
+            .finish()
+            .try_into()
+            .expect("old_def should be a valid database definition");
+
+        let mut new_builder = RawModuleDefV9Builder::new();
+        new_builder
+            .build_table_with_new_type(
+                "Apples",
+                ProductType::from([("id", AlgebraicType::U64), ("weight", AlgebraicType::U16)]),
+                true,
+            )
+            .finish();
+        new_builder
+            .build_table_with_new_type("Oranges", ProductType::from([("id", AlgebraicType::U64)]), true)
+            .finish();
+        let new_def: ModuleDef = new_builder
+            .finish()
+            .try_into()
+            .expect("new_def should be a valid database definition");
+
+        let apples = expect_identifier("Apples");
+        let oranges = expect_identifier("Oranges");
+        let weight = expect_identifier("weight");
+
+        // The added table is planned, even though the added column can't be.
+        let (plan, errors) = ponder_auto_migrate_partial(&old_def, &new_def);
+        assert_eq!(plan.steps, [AutoMigrateStep::AddTable(&oranges)]);
+        assert_eq!(
+            errors,
+            [AutoMigrateError::AddColumn {
+                table: apples,
+                column: weight
+            }]
+        );
+
+        assert!(ponder_auto_migrate(&old_def, &new_def).is_err());
+    }
 }
@@ -19,7 +19,9 @@ use std::collections::BTreeMap;
 use std::fmt::{self, Debug, Write};
 use std::hash::Hash;
 
+use crate::arg_constraint::{self, ArgConstraintViolation};
 use crate::error::{IdentifierError, ValidationErrors};
+use crate::generated::GeneratedExpr;
 use crate::identifier::Identifier;
 use crate::schema::{Schema, TableSchema};
 use crate::type_for_generate::{AlgebraicTypeUse, ProductTypeDef, TypespaceForGenerate};
@@ -32,16 +34,21 @@ use spacetimedb_data_structures::error_stream::{CollectAllErrors, CombineErrors,
 use spacetimedb_data_structures::map::HashMap;
 use spacetimedb_lib::db::raw_def;
 use spacetimedb_lib::db::raw_def::v9::{
-    Lifecycle, RawConstraintDataV9, RawConstraintDefV9, RawIdentifier, RawIndexAlgorithm, RawIndexDefV9,
-    RawModuleDefV9, RawReducerDefV9, RawRowLevelSecurityDefV9, RawScheduleDefV9, RawScopedTypeNameV9, RawSequenceDefV9,
-    RawSql, RawTableDefV9, RawTypeDefV9, RawUniqueConstraintDataV9, TableAccess, TableType,
+    ArgConstraint, CatchUpPolicy, HttpMethod, Lifecycle, RawBoundToConnectionDefV9, RawCoalesceDefV9,
+    RawConstraintDataV9, RawConstraintDefV9, RawCounterDefV9, RawGeneratedColumnDefV9, RawHttpRouteDefV9,
+    RawIdentifier, RawIndexAlgorithm, RawIndexDefV9, RawMiscModuleExportV9, RawModuleDefV9, RawReducerAliasDefV9,
+    RawReducerArgConstraintDefV9, RawReducerDefV9, RawReducerErrorTypeV9, RawReducerPriorityDefV9,
+    RawRowLevelSecurityDefV9, RawScheduleCatchUpDefV9, RawScheduleDefV9, RawScopedTypeNameV9, RawSequenceDefV9,
+    RawSoftDeleteDefV9, RawSql, RawTableDefV9, RawTableDurabilityDefV9, RawTopicAuthorizerDefV9, RawTypeDefV9,
+    RawUniqueConstraintDataV9, RawViewDefV9, ReducerPriority, TableAccess, TableDurability, TableType,
 };
-use spacetimedb_lib::{ProductType, RawModuleDef};
-use spacetimedb_primitives::{ColId, ColList, ColSet, ReducerId, TableId};
+use spacetimedb_lib::{ProductType, ProductValue, RawModuleDef};
+use spacetimedb_primitives::{ColId, ColList, ColSet, ReducerId, TableId, ViewId};
 use spacetimedb_sats::AlgebraicType;
 use spacetimedb_sats::{AlgebraicTypeRef, Typespace};
 use validate::v9::generate_index_name;
 
+pub mod builder;
 pub mod deserialize;
 pub mod validate;
 
@@ -60,7 +67,8 @@ pub type StrMap<T> = HashMap<Box<str>, T>;
 
 /// A validated, canonicalized, immutable module definition.
 ///
-/// Cannot be created directly. Instead, create/deserialize a [spacetimedb_lib::RawModuleDef] and call [ModuleDef::try_from].
+/// Cannot be created directly. Instead, create/deserialize a [spacetimedb_lib::RawModuleDef] and call [ModuleDef::try_from],
+/// or construct one in Rust code using a [builder::ModuleDefBuilder].
 ///
 /// ```rust
 /// use spacetimedb_lib::RawModuleDef;
@@ -102,9 +110,23 @@ pub struct ModuleDef {
     /// and must be preserved for future calls to `__call_reducer__`.
     reducers: IndexMap<Identifier, ReducerDef>,
 
+    /// A map from the aliases of reducers to the names of the reducers they alias.
+    reducer_aliases: IdentifierMap<Identifier>,
+
     /// A map from lifecycle reducer kind to reducer id.
     lifecycle_reducers: EnumMap<Lifecycle, Option<ReducerId>>,
 
+    /// The views of the module definition.
+    /// Note: like `reducers`, this is using IndexMap because view order is important
+    /// and must be preserved for future calls to `__call_view__`.
+    views: IndexMap<Identifier, ViewDef>,
+
+    /// The HTTP endpoints of the module definition, each served by one of `views`.
+    http_routes: Vec<HttpRouteDef>,
+
+    /// The reducer deciding which clients may subscribe to which topics, if any.
+    topic_authorizer: Option<ReducerId>,
+
     /// The type definitions of the module definition.
     types: HashMap<ScopedTypeName, TypeDef>,
 
@@ -160,6 +182,16 @@ impl ModuleDef {
         self.reducers.values()
     }
 
+    /// The views of the module definition.
+    pub fn views(&self) -> impl Iterator<Item = &ViewDef> {
+        self.views.values()
+    }
+
+    /// The HTTP endpoints of the module definition.
+    pub fn http_routes(&self) -> impl Iterator<Item = &HttpRouteDef> {
+        self.http_routes.iter()
+    }
+
     /// The type definitions of the module definition.
     pub fn types(&self) -> impl Iterator<Item = &TypeDef> {
         self.types.values()
@@ -232,6 +264,22 @@ impl ModuleDef {
         self.reducers.get_full(name).map(|(idx, _, def)| (idx.into(), def))
     }
 
+    /// Look up a reducer by its name or by one of its aliases, returning its id as well.
+    ///
+    /// If `name` is an alias, the alias's definition is returned too,
+    /// so that callers can warn about calls made under a deprecated name.
+    pub fn reducer_or_alias_full<K: ?Sized + Hash + Equivalent<Identifier>>(
+        &self,
+        name: &K,
+    ) -> Option<(ReducerId, &ReducerDef, Option<&ReducerAliasDef>)> {
+        if let Some((id, def)) = self.reducer_full(name) {
+            return Some((id, def, None));
+        }
+        let (id, def) = self.reducer_full(self.reducer_aliases.get(name)?)?;
+        let alias = def.aliases.iter().find(|alias| name.equivalent(&alias.name))?;
+        Some((id, def, Some(alias)))
+    }
+
     /// Look up a reducer by its id.
     pub fn reducer_by_id(&self, id: ReducerId) -> &ReducerDef {
         &self.reducers[id.idx()]
@@ -242,6 +290,28 @@ impl ModuleDef {
         self.reducers.get_index(id.idx()).map(|(_, def)| def)
     }
 
+    /// Convenience method to look up a view, possibly by a string, returning its id as well.
+    pub fn view_full<K: ?Sized + Hash + Equivalent<Identifier>>(&self, name: &K) -> Option<(ViewId, &ViewDef)> {
+        self.views.get_full(name).map(|(idx, _, def)| (idx.into(), def))
+    }
+
+    /// Look up a view by its id.
+    pub fn get_view_by_id(&self, id: ViewId) -> Option<&ViewDef> {
+        self.views.get_index(id.idx()).map(|(_, def)| def)
+    }
+
+    /// Look up the HTTP endpoint serving `method` requests to `path`.
+    pub fn http_route(&self, method: HttpMethod, path: &str) -> Option<&HttpRouteDef> {
+        self.http_routes
+            .iter()
+            .find(|route| route.method == method && &*route.path == path)
+    }
+
+    /// Looks up the reducer deciding which clients may subscribe to which topics, if the module defines one.
+    pub fn topic_authorizer(&self) -> Option<(ReducerId, &ReducerDef)> {
+        self.topic_authorizer.map(|i| (i, &self.reducers[i.idx()]))
+    }
+
     /// Looks up a lifecycle reducer defined in the module.
     pub fn lifecycle_reducer(&self, lifecycle: Lifecycle) -> Option<(ReducerId, &ReducerDef)> {
         self.lifecycle_reducers[lifecycle].map(|i| (i, &self.reducers[i.idx()]))
@@ -389,7 +459,11 @@ impl From<ModuleDef> for RawModuleDefV9 {
         let ModuleDef {
             tables,
             reducers,
+            reducer_aliases: _,
             lifecycle_reducers: _,
+            views,
+            http_routes,
+            topic_authorizer,
             types,
             typespace,
             stored_in_table_def: _,
@@ -398,11 +472,170 @@ impl From<ModuleDef> for RawModuleDefV9 {
             row_level_security_raw,
         } = val;
 
+        let reducer_error_types = reducers
+            .values()
+            .filter_map(|def| {
+                Some(RawReducerErrorTypeV9 {
+                    reducer: def.name.clone().into(),
+                    error_type: def.error_type.clone()?,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let arg_constraints = reducers
+            .values()
+            .flat_map(|def| {
+                def.arg_constraints
+                    .iter()
+                    .map(|constraint| RawReducerArgConstraintDefV9 {
+                        reducer: def.name.clone().into(),
+                        arg: constraint.arg,
+                        constraint: constraint.constraint.clone(),
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        let priorities = reducers
+            .values()
+            .filter(|def| def.priority != ReducerPriority::Normal)
+            .map(|def| RawReducerPriorityDefV9 {
+                reducer: def.name.clone().into(),
+                priority: def.priority,
+            })
+            .collect::<Vec<_>>();
+
+        let topic_authorizer = topic_authorizer.map(|id| RawTopicAuthorizerDefV9 {
+            reducer: reducers[id.idx()].name.clone().into(),
+        });
+
+        let aliases = reducers
+            .values()
+            .flat_map(|def| {
+                def.aliases.iter().map(|alias| RawReducerAliasDefV9 {
+                    reducer: def.name.clone().into(),
+                    alias: alias.name.clone().into(),
+                    deprecated: alias.deprecated.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let generated_columns = tables
+            .values()
+            .flat_map(|table| {
+                table.generated_columns.iter().map(|generated| RawGeneratedColumnDefV9 {
+                    table: table.name.clone().into(),
+                    column: generated.column,
+                    expr: generated.expr_source.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let counters = tables
+            .values()
+            .flat_map(|table| {
+                table.counters.iter().map(|counter| RawCounterDefV9 {
+                    table: table.name.clone().into(),
+                    group_by: counter.group_by,
+                    into_table: counter.into_table.clone().into(),
+                    into_column: tables[&counter.into_table].columns[counter.into_column.idx()]
+                        .name
+                        .clone()
+                        .into(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let soft_deletes = tables
+            .values()
+            .filter_map(|table| {
+                table.soft_delete.map(|soft_delete| RawSoftDeleteDefV9 {
+                    table: table.name.clone().into(),
+                    retention: soft_delete.retention,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let coalesces = tables
+            .values()
+            .filter_map(|table| {
+                table.coalesce.map(|coalesce| RawCoalesceDefV9 {
+                    table: table.name.clone().into(),
+                    window_ms: coalesce.window_ms,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let bound_to_connections = tables
+            .values()
+            .filter_map(|table| {
+                table.bound_to_connection.map(|bound| RawBoundToConnectionDefV9 {
+                    table: table.name.clone().into(),
+                    column: Some(bound.column),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let durabilities = tables
+            .values()
+            .filter(|table| table.durability != TableDurability::Durable)
+            .map(|table| RawTableDurabilityDefV9 {
+                table: table.name.clone().into(),
+                durability: table.durability,
+            })
+            .collect::<Vec<_>>();
+
+        let catch_ups = tables
+            .values()
+            .filter_map(|table| {
+                let schedule = table.schedule.as_ref()?;
+                (schedule.catch_up != CatchUpPolicy::FireAll).then(|| RawScheduleCatchUpDefV9 {
+                    table: table.name.clone().into(),
+                    policy: schedule.catch_up,
+                })
+            })
+            .collect::<Vec<_>>();
+
         RawModuleDefV9 {
             tables: to_raw(tables),
             reducers: reducers.into_iter().map(|(_, def)| def.into()).collect(),
             types: to_raw(types),
-            misc_exports: vec![],
+            misc_exports: reducer_error_types
+                .into_iter()
+                .map(RawMiscModuleExportV9::ReducerErrorType)
+                .chain(
+                    views
+                        .into_iter()
+                        .map(|(_, def)| RawMiscModuleExportV9::View(def.into())),
+                )
+                .chain(
+                    generated_columns
+                        .into_iter()
+                        .map(RawMiscModuleExportV9::GeneratedColumn),
+                )
+                .chain(counters.into_iter().map(RawMiscModuleExportV9::Counter))
+                .chain(soft_deletes.into_iter().map(RawMiscModuleExportV9::SoftDelete))
+                .chain(coalesces.into_iter().map(RawMiscModuleExportV9::Coalesce))
+                .chain(
+                    bound_to_connections
+                        .into_iter()
+                        .map(RawMiscModuleExportV9::BoundToConnection),
+                )
+                .chain(durabilities.into_iter().map(RawMiscModuleExportV9::TableDurability))
+                .chain(
+                    http_routes
+                        .into_iter()
+                        .map(|def| RawMiscModuleExportV9::HttpRoute(def.into())),
+                )
+                .chain(
+                    arg_constraints
+                        .into_iter()
+                        .map(RawMiscModuleExportV9::ReducerArgConstraint),
+                )
+                .chain(priorities.into_iter().map(RawMiscModuleExportV9::ReducerPriority))
+                .chain(catch_ups.into_iter().map(RawMiscModuleExportV9::ScheduleCatchUp))
+                .chain(aliases.into_iter().map(RawMiscModuleExportV9::ReducerAlias))
+                .chain(topic_authorizer.map(RawMiscModuleExportV9::TopicAuthorizer))
+                .collect(),
             typespace,
             row_level_security: row_level_security_raw.into_iter().map(|(_, def)| def).collect(),
         }
@@ -476,6 +709,25 @@ pub struct TableDef {
     /// The schedule for the table, if present.
     pub schedule: Option<ScheduleDef>,
 
+    /// The generated columns of the table, sorted by column.
+    pub generated_columns: Vec<GeneratedColumnDef>,
+
+    /// The counters of the rows of other tables kept in the columns of this table.
+    /// Note that a counter belongs to the table whose rows it counts, not to the table holding the counts.
+    pub counters: Vec<CounterDef>,
+
+    /// The history kept of the table, if it can be read as of recent transactions.
+    pub soft_delete: Option<SoftDeleteDef>,
+
+    /// How the updates to the table are coalesced before they are sent to subscribers, if they are.
+    pub coalesce: Option<CoalesceDef>,
+
+    /// The column tying each row of the table to the connection whose disconnection deletes it, if there is one.
+    pub bound_to_connection: Option<BoundToConnectionDef>,
+
+    /// Whether updates to the table are written to the commitlog.
+    pub durability: TableDurability,
+
     /// Whether this is a system- or user-created table.
     pub table_type: TableType,
 
@@ -505,6 +757,12 @@ impl From<TableDef> for RawTableDefV9 {
             constraints,
             sequences,
             schedule,
+            generated_columns: _,   // exported separately, as misc exports.
+            counters: _,            // exported separately, as misc exports.
+            soft_delete: _,         // exported separately, as a misc export.
+            coalesce: _,            // exported separately, as a misc export.
+            bound_to_connection: _, // exported separately, as a misc export.
+            durability: _,          // exported separately, as a misc export.
             table_type,
             table_access,
         } = val;
@@ -523,6 +781,61 @@ impl From<TableDef> for RawTableDefV9 {
     }
 }
 
+/// A column whose values are computed by the database from the other columns of its table.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct GeneratedColumnDef {
+    /// The generated column.
+    pub column: ColId,
+
+    /// The expression computing the column's value, as written in the module.
+    pub expr_source: Box<str>,
+
+    /// The parsed and type checked expression.
+    pub expr: GeneratedExpr,
+}
+
+/// A count of the rows of a table, grouped by one of its columns,
+/// which the database keeps in a column of the table whose primary key is referred to by that column.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct CounterDef {
+    /// The column of the counted table holding the primary key of the row to count each row under.
+    pub group_by: ColId,
+
+    /// The table holding the counts.
+    pub into_table: Identifier,
+
+    /// The column of `into_table` holding the counts.
+    pub into_column: ColId,
+}
+
+/// The history kept of a table, so that it can be read as of a recent transaction.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct SoftDeleteDef {
+    /// How many of the most recent transactions the table can be read as of.
+    pub retention: u64,
+}
+
+/// The coalescing of the updates to a table sent to subscribers,
+/// which merges the updates to rows with the same primary key over a window of time.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct CoalesceDef {
+    /// How long, in milliseconds, updates are held back.
+    pub window_ms: u64,
+}
+
+/// The binding of the rows of a table to client connections,
+/// which deletes the rows bound to a connection when it disconnects.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct BoundToConnectionDef {
+    /// The column, of type `Address`, holding the address of the connection each row is bound to.
+    pub column: ColId,
+}
+
 /// A sequence definition for a database table column.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SequenceDef {
@@ -778,6 +1091,9 @@ pub struct ScheduleDef {
     /// The name of the reducer to call. Not yet an `Identifier` because
     /// reducer names are not currently validated.
     pub reducer_name: Identifier,
+
+    /// What happens to the calls which fell due while the database was offline.
+    pub catch_up: CatchUpPolicy,
 }
 
 impl From<ScheduleDef> for RawScheduleDefV9 {
@@ -786,6 +1102,7 @@ impl From<ScheduleDef> for RawScheduleDefV9 {
             name: Some(val.name),
             reducer_name: val.reducer_name.into(),
             scheduled_at_column: val.at_column,
+            // `catch_up` is exported separately, as a misc export.
         }
     }
 }
@@ -930,6 +1247,57 @@ pub struct ReducerDef {
 
     /// The special role of this reducer in the module lifecycle, if any.
     pub lifecycle: Option<Lifecycle>,
+
+    /// The type of the values this reducer fails with, if it has one.
+    /// Otherwise, the reducer fails with error messages.
+    pub error_type: Option<AlgebraicType>,
+
+    /// The error type of the reducer, formatted for client codegen.
+    pub error_type_for_generate: Option<AlgebraicTypeUse>,
+
+    /// The constraints on the reducer's arguments, sorted by argument.
+    pub arg_constraints: Vec<ReducerArgConstraintDef>,
+
+    /// How urgently calls of this reducer are run when the database is busy.
+    pub priority: ReducerPriority,
+
+    /// The additional names under which this reducer may be called.
+    pub aliases: Vec<ReducerAliasDef>,
+}
+
+impl ReducerDef {
+    /// Check the arguments `args` of a call to this reducer against its [`Self::arg_constraints`].
+    pub fn check_arg_constraints(&self, args: &ProductValue) -> Result<(), ArgConstraintViolation> {
+        self.arg_constraints.iter().try_for_each(|def| {
+            let Some(value) = args.elements.get(def.arg as usize) else {
+                return Ok(());
+            };
+            let name = self.params.elements[def.arg as usize].name().unwrap_or_default();
+            arg_constraint::check(&def.constraint, name, value)
+        })
+    }
+}
+
+/// A constraint on the values of a reducer argument.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ReducerArgConstraintDef {
+    /// The position of the constrained argument among the reducer's parameters.
+    pub arg: u16,
+
+    /// The constraint.
+    pub constraint: ArgConstraint,
+}
+
+/// An additional name under which a reducer may be called.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ReducerAliasDef {
+    /// The alias.
+    pub name: Identifier,
+
+    /// If set, calls made under the alias are deprecated, and this note is logged when one is made.
+    pub deprecated: Option<Box<str>>,
 }
 
 impl From<ReducerDef> for RawReducerDefV9 {
@@ -942,6 +1310,65 @@ impl From<ReducerDef> for RawReducerDefV9 {
     }
 }
 
+/// A read-only view exported by the module.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ViewDef {
+    /// The name of the view. This must be unique among the reducers and views of the module.
+    pub name: Identifier,
+
+    /// The parameters of the view.
+    ///
+    /// This `ProductType` need not be registered in the module's `Typespace`.
+    pub params: ProductType,
+
+    /// The parameters of the view, formatted for client codegen.
+    ///
+    /// This `ProductType` need not be registered in the module's `TypespaceForGenerate`.
+    pub params_for_generate: ProductTypeDef,
+
+    /// The type of the value returned by the view.
+    pub return_type: AlgebraicType,
+
+    /// The return type of the view, formatted for client codegen.
+    pub return_type_for_generate: AlgebraicTypeUse,
+}
+
+impl From<ViewDef> for RawViewDefV9 {
+    fn from(val: ViewDef) -> Self {
+        RawViewDefV9 {
+            name: val.name.into(),
+            params: val.params,
+            return_type: val.return_type,
+        }
+    }
+}
+
+/// An HTTP endpoint of the module, served by calling a view.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct HttpRouteDef {
+    /// The method of the endpoint.
+    pub method: HttpMethod,
+
+    /// The path of the endpoint, relative to the module's HTTP root.
+    /// Starts with `/`.
+    pub path: Box<str>,
+
+    /// The name of the view serving the endpoint.
+    pub view: Identifier,
+}
+
+impl From<HttpRouteDef> for RawHttpRouteDefV9 {
+    fn from(val: HttpRouteDef) -> Self {
+        RawHttpRouteDefV9 {
+            method: val.method,
+            path: val.path,
+            view: val.view.into(),
+        }
+    }
+}
+
 impl ModuleDefLookup for TableDef {
     type Key<'a> = &'a Identifier;
 
@@ -1061,6 +1488,18 @@ impl ModuleDefLookup for ReducerDef {
     }
 }
 
+impl ModuleDefLookup for ViewDef {
+    type Key<'a> = &'a Identifier;
+
+    fn key(&self) -> Self::Key<'_> {
+        &self.name
+    }
+
+    fn lookup<'a>(module_def: &'a ModuleDef, key: Self::Key<'_>) -> Option<&'a Self> {
+        module_def.views.get(key)
+    }
+}
+
 fn to_raw<Def, RawDef, Name, A>(data: HashMap<Name, Def, A>) -> Vec<RawDef>
 where
     Def: ModuleDefLookup + Into<RawDef>,
@@ -0,0 +1,437 @@
+//! A builder for validated [`ModuleDef`]s.
+//!
+//! Tooling which constructs modules in Rust code, e.g., schema generators, test fixtures and migration linters,
+//! can use a [`ModuleDefBuilder`] rather than assembling a [`RawModuleDefV9`] by hand.
+//! Columns and parameters are referred to by name,
+//! and the result is validated just like a module uploaded by a user.
+//!
+//! ```rust
+//! use spacetimedb_lib::db::raw_def::v9::{ArgConstraint, Lifecycle};
+//! use spacetimedb_sats::{AlgebraicType, ProductType};
+//! use spacetimedb_schema::def::builder::ModuleDefBuilder;
+//!
+//! let mut builder = ModuleDefBuilder::new();
+//! builder
+//!     .table("player", [("id", AlgebraicType::U64), ("name", AlgebraicType::String)])
+//!     .with_auto_inc_primary_key("id")
+//!     .with_unique_constraint(["name"])
+//!     .with_btree_index("by_name", ["name"]);
+//! builder.reducer("init", ProductType::unit()).with_lifecycle(Lifecycle::Init);
+//! builder
+//!     .reducer("add_player", [("name", AlgebraicType::String)])
+//!     .with_arg_constraint("name", ArgConstraint::MaxLength(32));
+//!
+//! let def = builder.build().expect("valid module");
+//! assert_eq!(def.table("player").unwrap().constraints.len(), 2);
+//! ```
+
+use super::ModuleDef;
+use crate::error::{RawColumnName, ValidationError, ValidationErrors};
+use spacetimedb_data_structures::error_stream::ErrorStream;
+use spacetimedb_lib::db::raw_def::v9::{
+    ArgConstraint, HttpMethod, Lifecycle, RawIdentifier, RawIndexAlgorithm, RawModuleDefV9, RawModuleDefV9Builder,
+    RawTableDefBuilder, TableAccess, TableDurability, TableType,
+};
+use spacetimedb_primitives::{ColId, ColList};
+use spacetimedb_sats::{AlgebraicType, ProductType, SpacetimeType};
+
+/// A builder for a validated [`ModuleDef`].
+///
+/// Mistakes, like referring to a column that doesn't exist,
+/// are collected and reported by [`ModuleDefBuilder::build`] together with the validation errors of the module.
+#[derive(Default)]
+pub struct ModuleDefBuilder {
+    raw: RawModuleDefV9Builder,
+    errors: Vec<ValidationError>,
+}
+
+impl ModuleDefBuilder {
+    /// Returns a builder for an empty module.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the named type `scope::name`, whose elements are ordered as given, to the module.
+    ///
+    /// Returns a reference to the type, for use in columns, parameters, and other types.
+    pub fn add_type(
+        &mut self,
+        scope: impl IntoIterator<Item = RawIdentifier>,
+        name: impl Into<RawIdentifier>,
+        ty: AlgebraicType,
+    ) -> AlgebraicType {
+        AlgebraicType::Ref(self.raw.add_algebraic_type(scope, name, ty, true))
+    }
+
+    /// Adds the Rust type `T`, and the types it uses, to the module.
+    ///
+    /// Returns the type, for use in columns, parameters, and other types.
+    pub fn add_spacetime_type<T: SpacetimeType>(&mut self) -> AlgebraicType {
+        self.raw.add_type::<T>()
+    }
+
+    /// Starts building the table `name`, with the columns `columns`.
+    ///
+    /// The row type is added to the module as a named type, with the same name as the table.
+    /// The table is public, and has no constraints, indexes or sequences, unless they are added using the returned builder.
+    /// The table is added to the module when the builder is dropped.
+    pub fn table(&mut self, name: impl Into<RawIdentifier>, columns: impl Into<ProductType>) -> TableBuilder<'_> {
+        let name = name.into();
+        TableBuilder {
+            table: self.raw.build_table_with_new_type(name.clone(), columns.into(), true),
+            name,
+            errors: &mut self.errors,
+        }
+    }
+
+    /// Starts building the reducer `name`, with the parameters `params`.
+    ///
+    /// The parameters do not include the reducer context.
+    /// The reducer is added to the module when the builder is dropped.
+    pub fn reducer(&mut self, name: impl Into<RawIdentifier>, params: impl Into<ProductType>) -> ReducerBuilder<'_> {
+        ReducerBuilder {
+            module: self,
+            name: name.into(),
+            params: params.into(),
+            lifecycle: None,
+        }
+    }
+
+    /// Adds the view `name`, with the parameters `params`, which returns values of type `return_type`.
+    ///
+    /// The parameters do not include the view context.
+    pub fn add_view(
+        &mut self,
+        name: impl Into<RawIdentifier>,
+        params: impl Into<ProductType>,
+        return_type: AlgebraicType,
+    ) -> &mut Self {
+        self.raw.add_view(name, params.into(), return_type);
+        self
+    }
+
+    /// Serves `method` requests to `path` by calling the view `view`.
+    pub fn add_http_route(
+        &mut self,
+        method: HttpMethod,
+        path: impl Into<Box<str>>,
+        view: impl Into<RawIdentifier>,
+    ) -> &mut Self {
+        self.raw.add_http_route(method, path, view);
+        self
+    }
+
+    /// Adds the row-level security filter `sql`.
+    pub fn add_row_level_security(&mut self, sql: &str) -> &mut Self {
+        self.raw.add_row_level_security(sql);
+        self
+    }
+
+    /// Returns the module built so far, without validating it.
+    pub fn finish_raw(self) -> RawModuleDefV9 {
+        self.raw.finish()
+    }
+
+    /// Validates the module built so far.
+    pub fn build(self) -> Result<ModuleDef, ValidationErrors> {
+        ErrorStream::add_extra_errors(ModuleDef::try_from(self.raw.finish()), self.errors)
+    }
+}
+
+/// A builder for a table of a [`ModuleDefBuilder`], which refers to columns by name.
+pub struct TableBuilder<'a> {
+    table: RawTableDefBuilder<'a>,
+    name: RawIdentifier,
+    errors: &'a mut Vec<ValidationError>,
+}
+
+impl TableBuilder<'_> {
+    /// Sets whether the table is visible to clients.
+    pub fn with_access(mut self, access: TableAccess) -> Self {
+        self.table = self.table.with_access(access);
+        self
+    }
+
+    /// Sets whether the table was created by the system or the user.
+    pub fn with_type(mut self, table_type: TableType) -> Self {
+        self.table = self.table.with_type(table_type);
+        self
+    }
+
+    /// Makes `column` the primary key of the table, with the unique constraint this requires.
+    pub fn with_primary_key(mut self, column: &str) -> Self {
+        if let Some(column) = self.column(column) {
+            self.table = self.table.with_primary_key(column).with_unique_constraint(column);
+        }
+        self
+    }
+
+    /// Makes `column` the primary key of the table, with a unique constraint,
+    /// and with a sequence to assign its values.
+    pub fn with_auto_inc_primary_key(mut self, column: &str) -> Self {
+        if let Some(column) = self.column(column) {
+            self.table = self.table.with_auto_inc_primary_key(column);
+        }
+        self
+    }
+
+    /// Adds a unique constraint on `columns`.
+    pub fn with_unique_constraint<'c>(mut self, columns: impl IntoIterator<Item = &'c str>) -> Self {
+        if let Some(columns) = self.columns(columns) {
+            self.table = self.table.with_unique_constraint(columns);
+        }
+        self
+    }
+
+    /// Adds a B-tree index on `columns`, accessed in client code by `accessor_name`.
+    pub fn with_btree_index<'c>(
+        mut self,
+        accessor_name: impl Into<RawIdentifier>,
+        columns: impl IntoIterator<Item = &'c str>,
+    ) -> Self {
+        if let Some(columns) = self.columns(columns) {
+            self.table = self
+                .table
+                .with_index(RawIndexAlgorithm::BTree { columns }, accessor_name);
+        }
+        self
+    }
+
+    /// Adds a sequence on `column`, starting at `start`, if provided, and moving by `increment` for each new value.
+    pub fn with_sequence(mut self, column: &str, start: Option<i128>, increment: i128) -> Self {
+        if let Some(column) = self.column(column) {
+            self.table = self.table.with_column_sequence_config(column, start, increment);
+        }
+        self
+    }
+
+    /// Makes this table the schedule of the reducer `reducer_name`,
+    /// with the times at which to call the reducer in `scheduled_at_column`.
+    pub fn with_schedule(mut self, reducer_name: impl Into<RawIdentifier>, scheduled_at_column: &str) -> Self {
+        if let Some(column) = self.column(scheduled_at_column) {
+            self.table = self.table.with_schedule(reducer_name, column);
+        }
+        self
+    }
+
+    /// Sets whether updates to the table are written to the commitlog.
+    pub fn with_durability(mut self, durability: TableDurability) -> Self {
+        self.table = self.table.with_durability(durability);
+        self
+    }
+
+    /// Keeps the history of the last `retention` transactions of the table,
+    /// so that it can be read as of any of them.
+    pub fn with_soft_delete(mut self, retention: u64) -> Self {
+        self.table = self.table.with_soft_delete(retention);
+        self
+    }
+
+    /// Coalesces the updates to the table sent to subscribers over windows of `window_ms` milliseconds.
+    pub fn with_coalesce(mut self, window_ms: u64) -> Self {
+        self.table = self.table.with_coalesce(window_ms);
+        self
+    }
+
+    /// Deletes the rows of the table whose `column` holds the address of a connection when it disconnects.
+    /// If `column` is `None`, the table's only column of type `Address` is used.
+    pub fn with_bound_to_connection(mut self, column: Option<&str>) -> Self {
+        match column {
+            Some(column) => {
+                if let Some(column) = self.column(column) {
+                    self.table = self.table.with_bound_to_connection(Some(column));
+                }
+            }
+            None => self.table = self.table.with_bound_to_connection(None),
+        }
+        self
+    }
+
+    /// Makes `column` a generated column, whose value is computed from `expr`.
+    pub fn with_generated_column(mut self, column: &str, expr: impl Into<Box<str>>) -> Self {
+        if let Some(column) = self.column(column) {
+            self.table = self.table.with_generated_column(column, expr);
+        }
+        self
+    }
+
+    /// Counts the rows of this table in the column `into_column` of `into_table`,
+    /// grouping them by `group_by`, which holds the primary key of the row to count them under.
+    pub fn with_counter(
+        mut self,
+        group_by: &str,
+        into_table: impl Into<RawIdentifier>,
+        into_column: impl Into<RawIdentifier>,
+    ) -> Self {
+        if let Some(group_by) = self.column(group_by) {
+            self.table = self.table.with_counter(group_by, into_table, into_column);
+        }
+        self
+    }
+
+    /// Adds the table to the module, returning its row type.
+    pub fn finish(self) -> AlgebraicType {
+        AlgebraicType::Ref(self.table.finish())
+    }
+
+    /// Finds the column named `name`, recording an error if there is none.
+    fn column(&mut self, name: &str) -> Option<ColId> {
+        let column = self.table.find_col_pos_by_name(name);
+        if column.is_none() {
+            self.errors.push(ValidationError::UnknownColumn {
+                column: RawColumnName::new(self.name.clone(), name),
+            });
+        }
+        column
+    }
+
+    /// Finds the columns named `names`, recording an error for each one there is none of.
+    fn columns<'c>(&mut self, names: impl IntoIterator<Item = &'c str>) -> Option<ColList> {
+        let columns = names.into_iter().map(|name| self.column(name)).collect::<Vec<_>>();
+        columns.into_iter().collect()
+    }
+}
+
+/// A builder for a reducer of a [`ModuleDefBuilder`], which refers to parameters by name.
+pub struct ReducerBuilder<'a> {
+    module: &'a mut ModuleDefBuilder,
+    name: RawIdentifier,
+    params: ProductType,
+    lifecycle: Option<Lifecycle>,
+}
+
+impl ReducerBuilder<'_> {
+    /// Makes the reducer run on the lifecycle event `lifecycle`.
+    pub fn with_lifecycle(mut self, lifecycle: Lifecycle) -> Self {
+        self.lifecycle = Some(lifecycle);
+        self
+    }
+
+    /// Declares that the reducer fails with values of type `error_type`, rather than with error messages.
+    pub fn with_error_type(self, error_type: AlgebraicType) -> Self {
+        self.module.raw.add_reducer_error_type(self.name.clone(), error_type);
+        self
+    }
+
+    /// Constrains the values of the parameter `param`.
+    pub fn with_arg_constraint(self, param: &str, constraint: ArgConstraint) -> Self {
+        let arg = self
+            .params
+            .elements
+            .iter()
+            .position(|elem| elem.has_name(param))
+            .and_then(|arg| u16::try_from(arg).ok());
+        match arg {
+            Some(arg) => self
+                .module
+                .raw
+                .add_reducer_arg_constraint(self.name.clone(), arg, constraint),
+            None => self.module.errors.push(ValidationError::UnknownReducerParam {
+                reducer: self.name.clone(),
+                param: param.into(),
+            }),
+        }
+        self
+    }
+}
+
+impl Drop for ReducerBuilder<'_> {
+    fn drop(&mut self) {
+        let params = std::mem::replace(&mut self.params, ProductType::unit());
+        self.module.raw.add_reducer(self.name.clone(), params, self.lifecycle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spacetimedb_lib::ScheduleAt;
+
+    #[test]
+    fn build_module() {
+        let mut builder = ModuleDefBuilder::new();
+        let schedule_at = builder.add_spacetime_type::<ScheduleAt>();
+        builder
+            .table(
+                "player",
+                [
+                    ("id", AlgebraicType::U64),
+                    ("name", AlgebraicType::String),
+                    ("name_key", AlgebraicType::String),
+                ],
+            )
+            .with_access(TableAccess::Private)
+            .with_primary_key("id")
+            .with_sequence("id", Some(10), 2)
+            .with_btree_index("by_name", ["name"])
+            .with_durability(TableDurability::Relaxed)
+            .with_generated_column("name_key", "lower(name)");
+        let tick = builder
+            .table(
+                "tick",
+                [("scheduled_id", AlgebraicType::U64), ("scheduled_at", schedule_at)],
+            )
+            .with_auto_inc_primary_key("scheduled_id")
+            .with_schedule("run_tick", "scheduled_at")
+            .finish();
+        builder.reducer("run_tick", [("tick", tick)]);
+        builder
+            .reducer("rename", [("name", AlgebraicType::String)])
+            .with_arg_constraint("name", ArgConstraint::MinLength(1))
+            .with_error_type(AlgebraicType::String);
+        builder
+            .reducer("init", ProductType::unit())
+            .with_lifecycle(Lifecycle::Init);
+        builder
+            .add_view("top", [("limit", AlgebraicType::U32)], AlgebraicType::U64)
+            .add_http_route(HttpMethod::Get, "/top", "top");
+
+        let def = builder.build().unwrap();
+
+        let player = def.table("player").unwrap();
+        assert_eq!(player.table_access, TableAccess::Private);
+        assert_eq!(player.primary_key, Some(ColId(0)));
+        assert_eq!(player.constraints.len(), 1);
+        assert!(player
+            .indexes
+            .values()
+            .any(|index| index.accessor_name.as_deref() == Some("by_name")));
+        let sequence = player.sequences.values().next().unwrap();
+        assert_eq!(
+            (sequence.column, sequence.start, sequence.increment),
+            (ColId(0), Some(10), 2)
+        );
+        assert_eq!(player.generated_columns[0].column, ColId(2));
+        assert!(def.table("tick").unwrap().schedule.is_some());
+
+        let rename = def.reducer("rename").unwrap();
+        assert_eq!(rename.arg_constraints[0].constraint, ArgConstraint::MinLength(1));
+        assert_eq!(rename.error_type, Some(AlgebraicType::String));
+        assert_eq!(def.reducer("init").unwrap().lifecycle, Some(Lifecycle::Init));
+    }
+
+    #[test]
+    fn unknown_names() {
+        let mut builder = ModuleDefBuilder::new();
+        builder
+            .table("player", [("id", AlgebraicType::U64)])
+            .with_unique_constraint(["id", "nmae"]);
+        builder
+            .reducer("rename", [("name", AlgebraicType::String)])
+            .with_arg_constraint("nmae", ArgConstraint::MinLength(1));
+
+        let errors = builder.build().unwrap_err().into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                ValidationError::UnknownColumn {
+                    column: RawColumnName::new("player", "nmae"),
+                },
+                ValidationError::UnknownReducerParam {
+                    reducer: "rename".into(),
+                    param: "nmae".into(),
+                },
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use crate::arg_constraint;
 use crate::def::*;
 use crate::error::{RawColumnName, ValidationError};
+use crate::generated::GeneratedExpr;
 use crate::type_for_generate::{ClientCodegenError, ProductTypeDef, TypespaceForGenerateBuilder};
 use crate::{def::validate::Result, error::TypeLocation};
 use spacetimedb_data_structures::error_stream::{CollectAllErrors, CombineErrors};
@@ -74,18 +76,115 @@ pub fn validate(def: RawModuleDefV9) -> Result<ModuleDef> {
         })
         .collect_all_errors::<HashMap<_, _>>();
 
-    // It's statically impossible for this assert to fire until `RawMiscModuleExportV9` grows some variants.
-    assert_eq!(
-        misc_exports.len(),
-        0,
-        "Misc module exports are not yet supported in ABI v9."
-    );
+    let mut reducer_error_types = Vec::new();
+    let mut generated_columns = Vec::new();
+    let mut counters = Vec::new();
+    let mut soft_deletes = Vec::new();
+    let mut coalesces = Vec::new();
+    let mut bound_to_connections = Vec::new();
+    let mut durabilities = Vec::new();
+    let mut http_routes = Vec::new();
+    let mut arg_constraints = Vec::new();
+    let mut priorities = Vec::new();
+    let mut catch_ups = Vec::new();
+    let mut aliases = Vec::new();
+    let mut topic_authorizers = Vec::new();
+    let views = misc_exports
+        .into_iter()
+        .filter_map(|export| match export {
+            RawMiscModuleExportV9::View(view) => Some(validator.validate_view_def(view)),
+            RawMiscModuleExportV9::ReducerErrorType(error_type) => {
+                reducer_error_types.push(validator.validate_reducer_error_type(error_type));
+                None
+            }
+            RawMiscModuleExportV9::GeneratedColumn(generated) => {
+                generated_columns.push(generated);
+                None
+            }
+            RawMiscModuleExportV9::Counter(counter) => {
+                counters.push(counter);
+                None
+            }
+            RawMiscModuleExportV9::SoftDelete(soft_delete) => {
+                soft_deletes.push(soft_delete);
+                None
+            }
+            RawMiscModuleExportV9::Coalesce(coalesce) => {
+                coalesces.push(coalesce);
+                None
+            }
+            RawMiscModuleExportV9::BoundToConnection(bound) => {
+                bound_to_connections.push(bound);
+                None
+            }
+            RawMiscModuleExportV9::TableDurability(durability) => {
+                durabilities.push(durability);
+                None
+            }
+            RawMiscModuleExportV9::HttpRoute(route) => {
+                http_routes.push(route);
+                None
+            }
+            RawMiscModuleExportV9::ReducerArgConstraint(constraint) => {
+                arg_constraints.push(constraint);
+                None
+            }
+            RawMiscModuleExportV9::ReducerPriority(priority) => {
+                priorities.push(priority);
+                None
+            }
+            RawMiscModuleExportV9::ScheduleCatchUp(catch_up) => {
+                catch_ups.push(catch_up);
+                None
+            }
+            RawMiscModuleExportV9::ReducerAlias(alias) => {
+                aliases.push(alias);
+                None
+            }
+            RawMiscModuleExportV9::TopicAuthorizer(authorizer) => {
+                topic_authorizers.push(authorizer);
+                None
+            }
+            export => Some(Err(ValidationError::UnsupportedMiscExport {
+                export: format!("{export:?}").into(),
+            }
+            .into())),
+        })
+        .collect_all_errors::<Vec<_>>();
+    let reducer_error_types = reducer_error_types.into_iter().collect_all_errors::<Vec<_>>();
 
-    let tables_types_reducers = (tables, types, reducers)
+    let tables_types_reducers = (tables, types, reducers, views, reducer_error_types)
         .combine_errors()
-        .and_then(|(tables, types, reducers)| {
-            check_scheduled_reducers_exist(&tables, &reducers)?;
-            Ok((tables, types, reducers))
+        .and_then(|(mut tables, types, mut reducers, views, reducer_error_types)| {
+            let ((), views, (), (), (), reducer_aliases, (), (), (), (), (), (), ()) = (
+                check_scheduled_reducers_exist(&tables, &reducers),
+                check_view_names_unique(&reducers, views),
+                attach_reducer_error_types(&mut reducers, reducer_error_types),
+                attach_reducer_arg_constraints(&mut reducers, arg_constraints),
+                attach_reducer_priorities(&mut reducers, priorities),
+                attach_reducer_aliases(&mut reducers, aliases),
+                attach_generated_columns(&mut tables, generated_columns),
+                // After the generated columns, which can't hold counts.
+                attach_counters(&mut tables, counters),
+                attach_soft_deletes(&mut tables, soft_deletes),
+                attach_coalesces(&mut tables, coalesces),
+                attach_bound_to_connections(&mut tables, bound_to_connections),
+                attach_table_durabilities(&mut tables, durabilities),
+                attach_schedule_catch_ups(&mut tables, catch_ups),
+            )
+                .combine_errors()?;
+            // Routes are checked against the views, so this can't be combined with the above.
+            let http_routes = check_http_routes(&views, http_routes)?;
+            let topic_authorizer = check_topic_authorizer(&reducers, topic_authorizers)?;
+            Ok((
+                tables,
+                types,
+                reducers,
+                reducer_aliases,
+                views,
+                http_routes,
+                topic_authorizer,
+            ))
         });
 
     let ModuleValidator {
@@ -95,13 +194,15 @@ pub fn validate(def: RawModuleDefV9) -> Result<ModuleDef> {
         ..
     } = validator;
 
-    let (tables, types, reducers) = (tables_types_reducers).map_err(|errors| errors.sort_deduplicate())?;
+    let (tables, types, reducers, reducer_aliases, views, http_routes, topic_authorizer) =
+        (tables_types_reducers).map_err(|errors| errors.sort_deduplicate())?;
 
     let typespace_for_generate = typespace_for_generate.finish();
 
     let mut result = ModuleDef {
         tables,
         reducers,
+        reducer_aliases,
         types,
         typespace,
         typespace_for_generate,
@@ -109,6 +210,9 @@ pub fn validate(def: RawModuleDefV9) -> Result<ModuleDef> {
         refmap,
         row_level_security_raw,
         lifecycle_reducers,
+        views,
+        http_routes,
+        topic_authorizer,
     };
 
     result.generate_indexes();
@@ -238,6 +342,12 @@ impl ModuleValidator<'_> {
             constraints,
             sequences,
             schedule,
+            generated_columns: Vec::new(),
+            counters: Vec::new(),
+            soft_delete: None,
+            coalesce: None,
+            bound_to_connection: None,
+            durability: TableDurability::Durable,
             table_type,
             table_access,
         })
@@ -301,6 +411,87 @@ impl ModuleValidator<'_> {
                 recursive: false, // A ProductTypeDef not stored in a Typespace cannot be recursive.
             },
             lifecycle,
+            error_type: None,
+            error_type_for_generate: None,
+            arg_constraints: Vec::new(),
+            priority: ReducerPriority::Normal,
+            aliases: Vec::new(),
+        })
+    }
+
+    /// Validate the error type of a reducer.
+    ///
+    /// The error type is attached to its reducer by [`attach_reducer_error_types`],
+    /// once all the reducers have been validated.
+    fn validate_reducer_error_type(
+        &mut self,
+        error_type: RawReducerErrorTypeV9,
+    ) -> Result<(RawIdentifier, AlgebraicType, AlgebraicTypeUse)> {
+        let RawReducerErrorTypeV9 { reducer, error_type } = error_type;
+        let error_type_for_generate = self.validate_for_type_use(
+            &TypeLocation::ReducerError {
+                reducer_name: (&*reducer).into(),
+            },
+            &error_type,
+        )?;
+        Ok((reducer, error_type, error_type_for_generate))
+    }
+
+    /// Validate a view definition.
+    fn validate_view_def(&mut self, view_def: RawViewDefV9) -> Result<ViewDef> {
+        let RawViewDefV9 {
+            name,
+            params,
+            return_type,
+        } = view_def;
+
+        let params_for_generate: Result<_> = params
+            .elements
+            .iter()
+            .enumerate()
+            .map(|(position, param)| {
+                let location = TypeLocation::ViewArg {
+                    view_name: (&*name).into(),
+                    position,
+                    arg_name: param.name().map(Into::into),
+                };
+                let param_name = param
+                    .name()
+                    .ok_or_else(|| {
+                        ValidationError::ClientCodegenError {
+                            location: location.clone().make_static(),
+                            error: ClientCodegenError::NamelessViewParam,
+                        }
+                        .into()
+                    })
+                    .and_then(|s| identifier(s.into()));
+                let ty_use = self.validate_for_type_use(&location, &param.algebraic_type);
+                (param_name, ty_use).combine_errors()
+            })
+            .collect_all_errors();
+
+        let return_type_for_generate = self.validate_for_type_use(
+            &TypeLocation::ViewReturn {
+                view_name: (&*name).into(),
+            },
+            &return_type,
+        );
+
+        // Like reducers, views don't live in the global namespace.
+        let name = identifier(name);
+
+        let (name, params_for_generate, return_type_for_generate) =
+            (name, params_for_generate, return_type_for_generate).combine_errors()?;
+
+        Ok(ViewDef {
+            name,
+            params,
+            params_for_generate: ProductTypeDef {
+                elements: params_for_generate,
+                recursive: false, // A ProductTypeDef not stored in a Typespace cannot be recursive.
+            },
+            return_type,
+            return_type_for_generate,
         })
     }
 
@@ -655,6 +846,7 @@ impl TableValidator<'_, '_> {
             at_column,
             id_column,
             reducer_name,
+            catch_up: CatchUpPolicy::FireAll,
         })
     }
 
@@ -829,6 +1021,470 @@ fn check_scheduled_reducers_exist(
         .collect_all_errors()
 }
 
+/// Attach each reducer error type to the reducer it was declared for.
+fn attach_reducer_error_types(
+    reducers: &mut IndexMap<Identifier, ReducerDef>,
+    error_types: Vec<(RawIdentifier, AlgebraicType, AlgebraicTypeUse)>,
+) -> Result<()> {
+    error_types
+        .into_iter()
+        .map(|(reducer, error_type, error_type_for_generate)| -> Result<()> {
+            let Some(reducer_def) = reducers.get_mut(&*reducer) else {
+                return Err(ValidationError::MissingReducerForErrorType { reducer }.into());
+            };
+            if reducer_def.error_type.is_some() {
+                return Err(ValidationError::DuplicateReducerErrorType { reducer }.into());
+            }
+            reducer_def.error_type = Some(error_type);
+            reducer_def.error_type_for_generate = Some(error_type_for_generate);
+            Ok(())
+        })
+        .collect_all_errors()
+}
+
+/// Attach each reducer argument constraint to the reducer it was declared for,
+/// checking that the argument exists and that the constraint applies to its type.
+fn attach_reducer_arg_constraints(
+    reducers: &mut IndexMap<Identifier, ReducerDef>,
+    constraints: Vec<RawReducerArgConstraintDefV9>,
+) -> Result<()> {
+    let result = constraints
+        .into_iter()
+        .map(|constraint| -> Result<()> {
+            let RawReducerArgConstraintDefV9 {
+                reducer,
+                arg,
+                constraint,
+            } = constraint;
+            let Some(reducer_def) = reducers.get_mut(&*reducer) else {
+                return Err(ValidationError::MissingReducerForArgConstraint { reducer }.into());
+            };
+            let Some(param) = reducer_def.params.elements.get(arg as usize) else {
+                return Err(ValidationError::ArgConstraintArgOutOfRange { reducer, arg }.into());
+            };
+            if !arg_constraint::applies_to(&constraint, &param.algebraic_type) {
+                return Err(ValidationError::ArgConstraintTypeMismatch {
+                    reducer,
+                    arg,
+                    constraint,
+                    ty: param.algebraic_type.clone().into(),
+                }
+                .into());
+            }
+            reducer_def
+                .arg_constraints
+                .push(ReducerArgConstraintDef { arg, constraint });
+            Ok(())
+        })
+        .collect_all_errors();
+    for reducer_def in reducers.values_mut() {
+        reducer_def.arg_constraints.sort_by_key(|def| def.arg);
+    }
+    result
+}
+
+/// Set the priority of each reducer which declared one.
+fn attach_reducer_priorities(
+    reducers: &mut IndexMap<Identifier, ReducerDef>,
+    priorities: Vec<RawReducerPriorityDefV9>,
+) -> Result<()> {
+    let mut declared = HashSet::default();
+    priorities
+        .into_iter()
+        .map(|RawReducerPriorityDefV9 { reducer, priority }| -> Result<()> {
+            let Some(reducer_def) = reducers.get_mut(&*reducer) else {
+                return Err(ValidationError::MissingReducerForPriority { reducer }.into());
+            };
+            if !declared.insert(reducer_def.name.clone()) {
+                return Err(ValidationError::DuplicateReducerPriority { reducer }.into());
+            }
+            reducer_def.priority = priority;
+            Ok(())
+        })
+        .collect_all_errors()
+}
+
+/// Attach each alias to the reducer it names,
+/// returning a map from the aliases to the names of their reducers.
+///
+/// An alias may not be the name of any reducer, nor an alias of another.
+fn attach_reducer_aliases(
+    reducers: &mut IndexMap<Identifier, ReducerDef>,
+    aliases: Vec<RawReducerAliasDefV9>,
+) -> Result<IdentifierMap<Identifier>> {
+    let mut reducer_aliases = IdentifierMap::default();
+    aliases
+        .into_iter()
+        .map(
+            |RawReducerAliasDefV9 {
+                 reducer,
+                 alias,
+                 deprecated,
+             }|
+             -> Result<()> {
+                if !reducers.contains_key(&*reducer) {
+                    return Err(ValidationError::MissingReducerForAlias { reducer, alias }.into());
+                }
+                let alias = identifier(alias)?;
+                if reducers.contains_key(&alias) || reducer_aliases.contains_key(&alias) {
+                    return Err(ValidationError::ReducerAliasCollides { alias }.into());
+                }
+                let reducer_def = &mut reducers[&*reducer];
+                reducer_aliases.insert(alias.clone(), reducer_def.name.clone());
+                reducer_def.aliases.push(ReducerAliasDef {
+                    name: alias,
+                    deprecated,
+                });
+                Ok(())
+            },
+        )
+        .collect_all_errors::<()>()?;
+    Ok(reducer_aliases)
+}
+
+/// Set the durability of each table which declared one.
+fn attach_table_durabilities(
+    tables: &mut IdentifierMap<TableDef>,
+    durabilities: Vec<RawTableDurabilityDefV9>,
+) -> Result<()> {
+    let mut declared = HashSet::default();
+    durabilities
+        .into_iter()
+        .map(|RawTableDurabilityDefV9 { table, durability }| -> Result<()> {
+            let Some(table_def) = tables.get_mut(&*table) else {
+                return Err(ValidationError::MissingTableForDurability { table }.into());
+            };
+            if !declared.insert(table_def.name.clone()) {
+                return Err(ValidationError::DuplicateTableDurability { table }.into());
+            }
+            table_def.durability = durability;
+            Ok(())
+        })
+        .collect_all_errors()
+}
+
+/// Set the history kept of each table which declared soft deletes.
+fn attach_soft_deletes(tables: &mut IdentifierMap<TableDef>, soft_deletes: Vec<RawSoftDeleteDefV9>) -> Result<()> {
+    soft_deletes
+        .into_iter()
+        .map(|RawSoftDeleteDefV9 { table, retention }| -> Result<()> {
+            let Some(table_def) = tables.get_mut(&*table) else {
+                return Err(ValidationError::MissingTableForSoftDelete { table }.into());
+            };
+            if table_def.soft_delete.is_some() {
+                return Err(ValidationError::DuplicateSoftDelete { table }.into());
+            }
+            if retention == 0 {
+                return Err(ValidationError::ZeroSoftDeleteRetention { table }.into());
+            }
+            table_def.soft_delete = Some(SoftDeleteDef { retention });
+            Ok(())
+        })
+        .collect_all_errors()
+}
+
+/// Set how the updates to each table which declared coalescing are sent to subscribers.
+fn attach_coalesces(tables: &mut IdentifierMap<TableDef>, coalesces: Vec<RawCoalesceDefV9>) -> Result<()> {
+    coalesces
+        .into_iter()
+        .map(|RawCoalesceDefV9 { table, window_ms }| -> Result<()> {
+            let Some(table_def) = tables.get_mut(&*table) else {
+                return Err(ValidationError::MissingTableForCoalesce { table }.into());
+            };
+            if table_def.coalesce.is_some() {
+                return Err(ValidationError::DuplicateCoalesce { table }.into());
+            }
+            if window_ms == 0 {
+                return Err(ValidationError::ZeroCoalesceWindow { table }.into());
+            }
+            // Updates are merged by primary key.
+            if table_def.primary_key.is_none() {
+                return Err(ValidationError::CoalesceWithoutPrimaryKey { table }.into());
+            }
+            table_def.coalesce = Some(CoalesceDef { window_ms });
+            Ok(())
+        })
+        .collect_all_errors()
+}
+
+/// Set the column binding the rows of each table which declared one to connections.
+fn attach_bound_to_connections(
+    tables: &mut IdentifierMap<TableDef>,
+    bound_to_connections: Vec<RawBoundToConnectionDefV9>,
+) -> Result<()> {
+    bound_to_connections
+        .into_iter()
+        .map(|RawBoundToConnectionDefV9 { table, column }| -> Result<()> {
+            let Some(table_def) = tables.get_mut(&*table) else {
+                return Err(ValidationError::MissingTableForBoundToConnection { table }.into());
+            };
+            if table_def.bound_to_connection.is_some() {
+                return Err(ValidationError::DuplicateBoundToConnection { table }.into());
+            }
+            let invalid = |error: &str| ValidationError::InvalidBoundToConnection {
+                table: table.clone(),
+                error: error.into(),
+            };
+            let column = match column {
+                Some(column) => {
+                    let column = table_def
+                        .get_column(column)
+                        .ok_or_else(|| invalid("the column is not a column of the table"))?;
+                    if !column.ty.is_address() {
+                        return Err(invalid("the column must be of type `Address`").into());
+                    }
+                    column.col_id
+                }
+                None => {
+                    let mut addresses = table_def.columns.iter().filter(|col| col.ty.is_address());
+                    match (addresses.next(), addresses.next()) {
+                        (Some(column), None) => column.col_id,
+                        (None, _) => return Err(invalid("the table has no column of type `Address`").into()),
+                        (Some(_), Some(_)) => {
+                            return Err(
+                                invalid("the table has more than one column of type `Address`, name one").into(),
+                            )
+                        }
+                    }
+                }
+            };
+            table_def.bound_to_connection = Some(BoundToConnectionDef { column });
+            Ok(())
+        })
+        .collect_all_errors()
+}
+
+/// Set the catch-up policy of each scheduled table which declared one.
+fn attach_schedule_catch_ups(
+    tables: &mut IdentifierMap<TableDef>,
+    catch_ups: Vec<RawScheduleCatchUpDefV9>,
+) -> Result<()> {
+    let mut declared = HashSet::default();
+    catch_ups
+        .into_iter()
+        .map(|RawScheduleCatchUpDefV9 { table, policy }| -> Result<()> {
+            let Some((name, schedule)) = tables
+                .get_mut(&*table)
+                .and_then(|table_def| Some((table_def.name.clone(), table_def.schedule.as_mut()?)))
+            else {
+                return Err(ValidationError::MissingScheduleForCatchUp { table }.into());
+            };
+            if !declared.insert(name) {
+                return Err(ValidationError::DuplicateScheduleCatchUp { table }.into());
+            }
+            schedule.catch_up = policy;
+            Ok(())
+        })
+        .collect_all_errors()
+}
+
+/// Attach each generated column to the table it was declared for,
+/// parsing and type checking its expression against the table's columns.
+fn attach_generated_columns(
+    tables: &mut IdentifierMap<TableDef>,
+    generated_columns: Vec<RawGeneratedColumnDefV9>,
+) -> Result<()> {
+    // Find the tables first, so that expressions can be checked
+    // against all of the generated columns of their table.
+    let generated_columns = generated_columns
+        .into_iter()
+        .map(|generated| -> Result<_> {
+            let table =
+                tables
+                    .get(&*generated.table)
+                    .ok_or_else(|| ValidationError::MissingTableForGeneratedColumn {
+                        table: generated.table.clone(),
+                    })?;
+            let column = table
+                .get_column(generated.column)
+                .ok_or_else(|| ValidationError::InvalidGeneratedColumn {
+                    column: RawColumnName::new(generated.table.clone(), generated.column.to_string()),
+                    expr: generated.expr.clone(),
+                    error: "not a column of the table".into(),
+                })?;
+            Ok((table.name.clone(), column.name.clone(), generated))
+        })
+        .collect_all_errors::<Vec<_>>()?;
+
+    generated_columns
+        .iter()
+        .map(|(table_name, column_name, generated)| -> Result<()> {
+            let table = &tables[table_name];
+            let invalid = |error: String| ValidationError::InvalidGeneratedColumn {
+                column: RawColumnName::new(generated.table.clone(), &**column_name),
+                expr: generated.expr.clone(),
+                error,
+            };
+            let is_generated = |col: ColId| {
+                generated_columns
+                    .iter()
+                    .any(|(t, _, other)| t == table_name && other.column == col)
+            };
+            if table.sequences.values().any(|seq| seq.column == generated.column) {
+                return Err(invalid("generated columns cannot be auto-incremented".into()).into());
+            }
+            if table.generated_columns.iter().any(|g| g.column == generated.column) {
+                return Err(invalid("column is generated more than once".into()).into());
+            }
+            let ty = &table.columns[generated.column.idx()].ty;
+            let expr = GeneratedExpr::parse(&generated.expr, ty, |name| {
+                let col = table.columns.iter().find(|col| &*col.name == name)?;
+                Some((col.col_id, &col.ty, is_generated(col.col_id)))
+            })
+            .map_err(|e| invalid(e.to_string()))?;
+            let table = tables.get_mut(table_name).unwrap();
+            table.generated_columns.push(GeneratedColumnDef {
+                column: generated.column,
+                expr_source: generated.expr.clone(),
+                expr,
+            });
+            table.generated_columns.sort_by_key(|g| g.column);
+            Ok(())
+        })
+        .collect_all_errors()
+}
+
+/// Attach each counter to the table whose rows it counts,
+/// checking that the rows are grouped by the primary key of the table holding the counts.
+fn attach_counters(tables: &mut IdentifierMap<TableDef>, counters: Vec<RawCounterDefV9>) -> Result<()> {
+    counters
+        .into_iter()
+        .map(|counter| -> Result<()> {
+            let table = tables
+                .get(&*counter.table)
+                .ok_or_else(|| ValidationError::MissingTableForCounter {
+                    table: counter.table.clone(),
+                })?;
+            let invalid = |error: &str| ValidationError::InvalidCounter {
+                table: counter.table.clone(),
+                into: RawColumnName::new(counter.into_table.clone(), counter.into_column.clone()),
+                error: error.into(),
+            };
+            let group_by = table
+                .get_column(counter.group_by)
+                .ok_or_else(|| invalid("the grouping column is not a column of the counted table"))?;
+            let into_table = tables
+                .get(&*counter.into_table)
+                .ok_or_else(|| invalid("the table holding the counts does not exist"))?;
+            let into_column = into_table
+                .columns
+                .iter()
+                .find(|col| *col.name == *counter.into_column)
+                .ok_or_else(|| invalid("not a column of the table holding the counts"))?;
+            let primary_key = into_table
+                .primary_key
+                .and_then(|pk| into_table.get_column(pk))
+                .ok_or_else(|| invalid("the table holding the counts has no primary key"))?;
+            if primary_key.ty != group_by.ty {
+                return Err(invalid("the grouping column does not have the type of the primary key").into());
+            }
+            if into_column.col_id == primary_key.col_id {
+                return Err(invalid("counts cannot be kept in the primary key").into());
+            }
+            if !matches!(
+                into_column.ty,
+                AlgebraicType::U32 | AlgebraicType::U64 | AlgebraicType::I32 | AlgebraicType::I64
+            ) {
+                return Err(invalid("counts must be kept in a column of type u32, u64, i32 or i64").into());
+            }
+            if into_table
+                .sequences
+                .values()
+                .any(|seq| seq.column == into_column.col_id)
+                || into_table
+                    .generated_columns
+                    .iter()
+                    .any(|g| g.column == into_column.col_id)
+            {
+                return Err(invalid("counts cannot be kept in an auto-incremented or generated column").into());
+            }
+            let into_column = into_column.col_id;
+            let into_table = into_table.name.clone();
+            let table = tables.get_mut(&*counter.table).unwrap();
+            table.counters.push(CounterDef {
+                group_by: counter.group_by,
+                into_table,
+                into_column,
+            });
+            Ok(())
+        })
+        .collect_all_errors()
+}
+
+/// Check that view names are unique, and that no view shares a name with a reducer,
+/// since clients address both by name.
+fn check_view_names_unique(
+    reducers: &IndexMap<Identifier, ReducerDef>,
+    views: Vec<ViewDef>,
+) -> Result<IndexMap<Identifier, ViewDef>> {
+    let mut result = IndexMap::with_capacity(views.len());
+    views
+        .into_iter()
+        .map(|view| -> Result<()> {
+            if reducers.contains_key(&view.name) {
+                return Err(ValidationError::ViewNameCollidesWithReducer { view: view.name }.into());
+            }
+            let name = view.name.clone();
+            match result.insert(name.clone(), view) {
+                Some(_) => Err(ValidationError::DuplicateName { name: name.into() }.into()),
+                None => Ok(()),
+            }
+        })
+        .collect_all_errors::<()>()?;
+    Ok(result)
+}
+
+/// Check that each HTTP route has a valid path, is served by a view that exists,
+/// and is declared only once.
+fn check_http_routes(
+    views: &IndexMap<Identifier, ViewDef>,
+    http_routes: Vec<RawHttpRouteDefV9>,
+) -> Result<Vec<HttpRouteDef>> {
+    let mut declared = HashSet::default();
+    http_routes
+        .into_iter()
+        .map(|RawHttpRouteDefV9 { method, path, view }| -> Result<HttpRouteDef> {
+            if !path.starts_with('/') || path.contains(['?', '#']) {
+                return Err(ValidationError::InvalidHttpRoutePath { path }.into());
+            }
+            let Some(view_def) = views.get(&*view) else {
+                return Err(ValidationError::MissingViewForHttpRoute { method, path, view }.into());
+            };
+            if !declared.insert((method, path.clone())) {
+                return Err(ValidationError::DuplicateHttpRoute { method, path }.into());
+            }
+            Ok(HttpRouteDef {
+                method,
+                path,
+                view: view_def.name.clone(),
+            })
+        })
+        .collect_all_errors()
+}
+
+/// Check that at most one topic authorizer is declared,
+/// and that it is a reducer taking the topic as its only argument.
+fn check_topic_authorizer(
+    reducers: &IndexMap<Identifier, ReducerDef>,
+    topic_authorizers: Vec<RawTopicAuthorizerDefV9>,
+) -> Result<Option<ReducerId>> {
+    let mut topic_authorizers = topic_authorizers.into_iter();
+    let Some(RawTopicAuthorizerDefV9 { reducer }) = topic_authorizers.next() else {
+        return Ok(None);
+    };
+    if let Some(RawTopicAuthorizerDefV9 { reducer }) = topic_authorizers.next() {
+        return Err(ValidationError::DuplicateTopicAuthorizer { reducer }.into());
+    }
+    let Some((idx, _, reducer_def)) = reducers.get_full(&*reducer) else {
+        return Err(ValidationError::MissingReducerForTopicAuthorizer { reducer }.into());
+    };
+    let takes_topic = matches!(&*reducer_def.params.elements, [param] if param.algebraic_type.is_string());
+    if !takes_topic || reducer_def.lifecycle.is_some() {
+        return Err(ValidationError::InvalidTopicAuthorizer { reducer }.into());
+    }
+    Ok(Some(ReducerId(idx as u32)))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::def::validate::tests::{
@@ -843,8 +1499,13 @@ mod tests {
     use spacetimedb_lib::db::raw_def::*;
     use spacetimedb_lib::ScheduleAt;
     use spacetimedb_primitives::{col_list, ColId, ColList};
-    use spacetimedb_sats::{AlgebraicType, AlgebraicTypeRef, ProductType};
-    use v9::{Lifecycle, RawIndexAlgorithm, RawModuleDefV9Builder, TableAccess, TableType};
+    use spacetimedb_sats::{AlgebraicType, AlgebraicTypeRef, AlgebraicValue, ProductType, ProductValue};
+    use v9::{
+        ArgConstraint, CatchUpPolicy, HttpMethod, Lifecycle, RawBoundToConnectionDefV9, RawCoalesceDefV9,
+        RawCounterDefV9, RawGeneratedColumnDefV9, RawHttpRouteDefV9, RawIndexAlgorithm, RawMiscModuleExportV9,
+        RawModuleDefV9, RawModuleDefV9Builder, RawReducerAliasDefV9, RawReducerPriorityDefV9, RawSoftDeleteDefV9,
+        RawTableDurabilityDefV9, RawTopicAuthorizerDefV9, ReducerPriority, TableAccess, TableDurability, TableType,
+    };
 
     /// This test attempts to exercise every successful path in the validation code.
     #[test]
@@ -1543,4 +2204,704 @@ mod tests {
         assert!(def.lookup::<IndexDef>("wacky.index()").is_some());
         assert!(def.lookup::<SequenceDef>("wacky.sequence()").is_some());
     }
+
+    #[test]
+    fn valid_views() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("add_apple", ProductType::from([("count", AlgebraicType::U32)]), None);
+        builder.add_view(
+            "apples_by_count",
+            ProductType::from([("count", AlgebraicType::U32)]),
+            AlgebraicType::array(AlgebraicType::U64),
+        );
+        builder.add_view("apple_total", ProductType::unit(), AlgebraicType::U64);
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        let views = def.views().map(|view| &view.name[..]).collect::<Vec<_>>();
+        assert_eq!(views, ["apples_by_count", "apple_total"]);
+
+        let (id, view) = def.view_full("apple_total").unwrap();
+        assert_eq!(id, 1.into());
+        assert_eq!(view.return_type, AlgebraicType::U64);
+        assert!(view.params.elements.is_empty());
+    }
+
+    #[test]
+    fn view_name_collides_with_reducer() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("apples", ProductType::unit(), None);
+        builder.add_view("apples", ProductType::unit(), AlgebraicType::U64);
+        let result: Result<ModuleDef> = builder.finish().try_into();
+
+        expect_error_matching!(result, ValidationError::ViewNameCollidesWithReducer { view } => {
+            view == &expect_identifier("apples")
+        });
+    }
+
+    #[test]
+    fn duplicate_view_name() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_view("apples", ProductType::unit(), AlgebraicType::U64);
+        builder.add_view("apples", ProductType::unit(), AlgebraicType::U32);
+        let result: Result<ModuleDef> = builder.finish().try_into();
+
+        expect_error_matching!(result, ValidationError::DuplicateName { name } => {
+            &name[..] == "apples"
+        });
+    }
+
+    #[test]
+    fn reducer_error_type() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("apples", ProductType::unit(), None);
+        builder.add_reducer_error_type("apples", AlgebraicType::String);
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        let reducer = def.reducer("apples").unwrap();
+        assert_eq!(reducer.error_type, Some(AlgebraicType::String));
+    }
+
+    #[test]
+    fn reducer_error_type_without_reducer() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer_error_type("apples", AlgebraicType::String);
+        let result: Result<ModuleDef> = builder.finish().try_into();
+
+        expect_error_matching!(result, ValidationError::MissingReducerForErrorType { reducer } => {
+            &reducer[..] == "apples"
+        });
+    }
+
+    #[test]
+    fn duplicate_reducer_error_type() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("apples", ProductType::unit(), None);
+        builder.add_reducer_error_type("apples", AlgebraicType::String);
+        builder.add_reducer_error_type("apples", AlgebraicType::U32);
+        let result: Result<ModuleDef> = builder.finish().try_into();
+
+        expect_error_matching!(result, ValidationError::DuplicateReducerErrorType { reducer } => {
+            &reducer[..] == "apples"
+        });
+    }
+
+    #[test]
+    fn reducer_arg_constraints() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer(
+            "rename",
+            ProductType::from([("name", AlgebraicType::String), ("age", AlgebraicType::U8)]),
+            None,
+        );
+        builder.add_reducer_arg_constraint("rename", 1, ArgConstraint::Max(150));
+        builder.add_reducer_arg_constraint("rename", 0, ArgConstraint::MaxLength(3));
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        let reducer = def.reducer("rename").unwrap();
+        assert_eq!(
+            reducer
+                .arg_constraints
+                .iter()
+                .map(|def| (def.arg, def.constraint.clone()))
+                .collect::<Vec<_>>(),
+            [(0, ArgConstraint::MaxLength(3)), (1, ArgConstraint::Max(150))]
+        );
+
+        let args = ProductValue::from_iter([AlgebraicValue::String("Ada".into()), AlgebraicValue::U8(36)]);
+        assert!(reducer.check_arg_constraints(&args).is_ok());
+        let args = ProductValue::from_iter([AlgebraicValue::String("Ada".into()), AlgebraicValue::U8(151)]);
+        assert!(reducer.check_arg_constraints(&args).is_err());
+    }
+
+    #[test]
+    fn invalid_reducer_arg_constraints() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("rename", ProductType::from([("name", AlgebraicType::String)]), None);
+        builder.add_reducer_arg_constraint("rename", 0, ArgConstraint::Min(0));
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::ArgConstraintTypeMismatch { reducer, arg, .. } => {
+            &reducer[..] == "rename" && *arg == 0
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("rename", ProductType::from([("name", AlgebraicType::String)]), None);
+        builder.add_reducer_arg_constraint("rename", 1, ArgConstraint::MaxLength(3));
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::ArgConstraintArgOutOfRange { reducer, arg } => {
+            &reducer[..] == "rename" && *arg == 1
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer_arg_constraint("rename", 0, ArgConstraint::MaxLength(3));
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::MissingReducerForArgConstraint { reducer } => {
+            &reducer[..] == "rename"
+        });
+    }
+
+    #[test]
+    fn reducer_priorities() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("move_player", ProductType::unit(), None);
+        builder.add_reducer("autosave", ProductType::unit(), None);
+        builder.add_reducer("chat", ProductType::unit(), None);
+        builder.add_reducer_priority("move_player", ReducerPriority::High);
+        builder.add_reducer_priority("autosave", ReducerPriority::Low);
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        assert_eq!(def.reducer("move_player").unwrap().priority, ReducerPriority::High);
+        assert_eq!(def.reducer("autosave").unwrap().priority, ReducerPriority::Low);
+        assert_eq!(def.reducer("chat").unwrap().priority, ReducerPriority::Normal);
+
+        let mut raw_def = RawModuleDefV9::from(def);
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::ReducerPriority(RawReducerPriorityDefV9 {
+                reducer: "autosave".into(),
+                priority: ReducerPriority::Normal,
+            }));
+        let result: Result<ModuleDef> = raw_def.try_into();
+        expect_error_matching!(result, ValidationError::DuplicateReducerPriority { reducer } => {
+            &reducer[..] == "autosave"
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer_priority("autosave", ReducerPriority::Low);
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::MissingReducerForPriority { reducer } => {
+            &reducer[..] == "autosave"
+        });
+    }
+
+    #[test]
+    fn topic_authorizer() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("chat", ProductType::unit(), None);
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+        assert!(def.topic_authorizer().is_none());
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("chat", ProductType::unit(), None);
+        builder.add_reducer(
+            "authorize_topic",
+            ProductType::from([("topic", AlgebraicType::String)]),
+            None,
+        );
+        builder.set_topic_authorizer("authorize_topic");
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+        let (id, reducer) = def.topic_authorizer().unwrap();
+        assert_eq!(id, def.reducer_full("authorize_topic").unwrap().0);
+        assert_eq!(&reducer.name[..], "authorize_topic");
+
+        let mut raw_def = RawModuleDefV9::from(def);
+        assert_eq!(
+            raw_def
+                .misc_exports
+                .iter()
+                .filter(|export| matches!(export, RawMiscModuleExportV9::TopicAuthorizer(_)))
+                .count(),
+            1
+        );
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::TopicAuthorizer(RawTopicAuthorizerDefV9 {
+                reducer: "chat".into(),
+            }));
+        let result: Result<ModuleDef> = raw_def.try_into();
+        expect_error_matching!(result, ValidationError::DuplicateTopicAuthorizer { reducer } => {
+            &reducer[..] == "chat"
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.set_topic_authorizer("authorize_topic");
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::MissingReducerForTopicAuthorizer { reducer } => {
+            &reducer[..] == "authorize_topic"
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer(
+            "authorize_topic",
+            ProductType::from([("topic", AlgebraicType::U32)]),
+            None,
+        );
+        builder.set_topic_authorizer("authorize_topic");
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::InvalidTopicAuthorizer { reducer } => {
+            &reducer[..] == "authorize_topic"
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("on_connect", ProductType::unit(), Some(Lifecycle::OnConnect));
+        builder.set_topic_authorizer("on_connect");
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::InvalidTopicAuthorizer { reducer } => {
+            &reducer[..] == "on_connect"
+        });
+    }
+
+    #[test]
+    fn schedule_catch_up() {
+        let scheduled_module = |policy| {
+            let mut builder = RawModuleDefV9Builder::new();
+            let schedule_at_type = builder.add_type::<ScheduleAt>();
+            let reports = builder
+                .build_table_with_new_type(
+                    "reports",
+                    ProductType::from([("scheduled_id", AlgebraicType::U64), ("scheduled_at", schedule_at_type)]),
+                    true,
+                )
+                .with_auto_inc_primary_key(0)
+                .with_schedule("send_report", 1)
+                .with_catch_up(policy)
+                .finish();
+            builder.add_reducer("send_report", ProductType::from([("a", reports.into())]), None);
+            builder
+        };
+
+        let def: ModuleDef = scheduled_module(CatchUpPolicy::FireOnce).finish().try_into().unwrap();
+        let schedule = def.table("reports").unwrap().schedule.as_ref().unwrap();
+        assert_eq!(schedule.catch_up, CatchUpPolicy::FireOnce);
+
+        let mut builder = scheduled_module(CatchUpPolicy::SkipMissed);
+        builder
+            .build_table_with_new_type("players", ProductType::from([("name", AlgebraicType::String)]), true)
+            .with_catch_up(CatchUpPolicy::SkipMissed)
+            .finish();
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::MissingScheduleForCatchUp { table } => {
+            &table[..] == "players"
+        });
+    }
+
+    #[test]
+    fn reducer_aliases() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("send_message", ProductType::unit(), None);
+        builder.add_reducer("set_name", ProductType::unit(), None);
+        builder.add_reducer_alias("send_message", "say", Some("use send_message".into()));
+        builder.add_reducer_alias("send_message", "post", None);
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        let (id, reducer, alias) = def.reducer_or_alias_full("say").unwrap();
+        assert_eq!(def.reducer_full("send_message").unwrap().0, id);
+        assert_eq!(&reducer.name[..], "send_message");
+        assert_eq!(alias.unwrap().deprecated.as_deref(), Some("use send_message"));
+        let (_, _, alias) = def.reducer_or_alias_full("post").unwrap();
+        assert_eq!(alias.unwrap().deprecated, None);
+        let (_, _, alias) = def.reducer_or_alias_full("send_message").unwrap();
+        assert!(alias.is_none());
+        assert!(def.reducer_or_alias_full("shout").is_none());
+
+        let mut raw_def = RawModuleDefV9::from(def);
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::ReducerAlias(RawReducerAliasDefV9 {
+                reducer: "set_name".into(),
+                alias: "say".into(),
+                deprecated: None,
+            }));
+        let result: Result<ModuleDef> = raw_def.try_into();
+        expect_error_matching!(result, ValidationError::ReducerAliasCollides { alias } => {
+            &alias[..] == "say"
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer("send_message", ProductType::unit(), None);
+        builder.add_reducer("set_name", ProductType::unit(), None);
+        builder.add_reducer_alias("send_message", "set_name", None);
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::ReducerAliasCollides { alias } => {
+            &alias[..] == "set_name"
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_reducer_alias("send_message", "say", None);
+        let result: Result<ModuleDef> = builder.finish().try_into();
+        expect_error_matching!(result, ValidationError::MissingReducerForAlias { reducer, alias } => {
+            &reducer[..] == "send_message" && &alias[..] == "say"
+        });
+    }
+
+    #[test]
+    fn generated_columns() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type(
+                "users",
+                ProductType::from([("name", AlgebraicType::String), ("name_key", AlgebraicType::String)]),
+                true,
+            )
+            .with_unique_constraint(1)
+            .with_generated_column(1, "lower(name)");
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        let users = def.table("users").unwrap();
+        assert_eq!(users.generated_columns.len(), 1);
+        assert_eq!(users.generated_columns[0].column, ColId(1));
+        assert_eq!(&*users.generated_columns[0].expr_source, "lower(name)");
+    }
+
+    #[test]
+    fn invalid_generated_columns() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type(
+                "users",
+                ProductType::from([("name", AlgebraicType::String), ("age", AlgebraicType::U32)]),
+                true,
+            )
+            .with_generated_column(1, "lower(name)");
+        let result: Result<ModuleDef> = builder.finish().try_into();
+
+        expect_error_matching!(result, ValidationError::InvalidGeneratedColumn { column, .. } => {
+            column == &RawColumnName::new("users", "age")
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type("users", ProductType::from([("name", AlgebraicType::String)]), true)
+            .finish();
+        builder
+            .build_table_with_new_type("other", ProductType::from([("name", AlgebraicType::String)]), true)
+            .finish();
+        let mut raw_def = builder.finish();
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::GeneratedColumn(RawGeneratedColumnDefV9 {
+                table: "nope".into(),
+                column: ColId(0),
+                expr: "'x'".into(),
+            }));
+        let result: Result<ModuleDef> = raw_def.try_into();
+
+        expect_error_matching!(result, ValidationError::MissingTableForGeneratedColumn { table } => {
+            &table[..] == "nope"
+        });
+    }
+
+    #[test]
+    fn counters() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type(
+                "canvas_stats",
+                ProductType::from([("canvas_id", AlgebraicType::U64), ("element_count", AlgebraicType::U32)]),
+                true,
+            )
+            .with_unique_constraint(0)
+            .with_primary_key(0)
+            .finish();
+        builder
+            .build_table_with_new_type(
+                "element",
+                ProductType::from([("id", AlgebraicType::U64), ("canvas_id", AlgebraicType::U64)]),
+                true,
+            )
+            .with_counter(1, "canvas_stats", "element_count")
+            .finish();
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        let element = def.table("element").unwrap();
+        assert_eq!(element.counters.len(), 1);
+        assert_eq!(element.counters[0].group_by, ColId(1));
+        assert_eq!(&element.counters[0].into_table[..], "canvas_stats");
+        assert_eq!(element.counters[0].into_column, ColId(1));
+    }
+
+    #[test]
+    fn invalid_counters() {
+        let build = |into_column: &str, group_by_type: AlgebraicType| {
+            let mut builder = RawModuleDefV9Builder::new();
+            builder
+                .build_table_with_new_type(
+                    "canvas_stats",
+                    ProductType::from([
+                        ("canvas_id", AlgebraicType::U64),
+                        ("element_count", AlgebraicType::U32),
+                        ("name", AlgebraicType::String),
+                    ]),
+                    true,
+                )
+                .with_unique_constraint(0)
+                .with_primary_key(0)
+                .finish();
+            builder
+                .build_table_with_new_type(
+                    "element",
+                    ProductType::from([("id", AlgebraicType::U64), ("canvas_id", group_by_type)]),
+                    true,
+                )
+                .with_counter(1, "canvas_stats", into_column)
+                .finish();
+            builder.finish()
+        };
+
+        for (into_column, group_by_type) in [
+            ("name", AlgebraicType::U64),
+            ("canvas_id", AlgebraicType::U64),
+            ("nope", AlgebraicType::U64),
+            ("element_count", AlgebraicType::String),
+        ] {
+            let result: Result<ModuleDef> = build(into_column, group_by_type).try_into();
+            expect_error_matching!(result, ValidationError::InvalidCounter { table, into, .. } => {
+                &table[..] == "element" && into == &RawColumnName::new("canvas_stats", into_column)
+            });
+        }
+
+        let mut raw_def = build("element_count", AlgebraicType::U64);
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::Counter(RawCounterDefV9 {
+                table: "nope".into(),
+                group_by: ColId(0),
+                into_table: "canvas_stats".into(),
+                into_column: "element_count".into(),
+            }));
+        let result: Result<ModuleDef> = raw_def.try_into();
+
+        expect_error_matching!(result, ValidationError::MissingTableForCounter { table } => {
+            &table[..] == "nope"
+        });
+    }
+
+    #[test]
+    fn soft_delete() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type("canvas", ProductType::from([("name", AlgebraicType::String)]), true)
+            .with_soft_delete(100)
+            .finish();
+        builder
+            .build_table_with_new_type("cursors", ProductType::from([("x", AlgebraicType::F32)]), true)
+            .finish();
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        assert_eq!(
+            def.table("canvas").unwrap().soft_delete,
+            Some(SoftDeleteDef { retention: 100 })
+        );
+        assert_eq!(def.table("cursors").unwrap().soft_delete, None);
+
+        let mut raw_def = RawModuleDefV9::from(def);
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::SoftDelete(RawSoftDeleteDefV9 {
+                table: "canvas".into(),
+                retention: 10,
+            }));
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::SoftDelete(RawSoftDeleteDefV9 {
+                table: "cursors".into(),
+                retention: 0,
+            }));
+        let result: Result<ModuleDef> = raw_def.try_into();
+
+        expect_error_matching!(result, ValidationError::DuplicateSoftDelete { table } => {
+            &table[..] == "canvas"
+        });
+        expect_error_matching!(result, ValidationError::ZeroSoftDeleteRetention { table } => {
+            &table[..] == "cursors"
+        });
+    }
+
+    #[test]
+    fn coalesce() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type(
+                "cursors",
+                ProductType::from([("id", AlgebraicType::U64), ("x", AlgebraicType::F32)]),
+                true,
+            )
+            .with_primary_key(0)
+            .with_coalesce(50)
+            .finish();
+        builder
+            .build_table_with_new_type("clicks", ProductType::from([("x", AlgebraicType::F32)]), true)
+            .finish();
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        assert_eq!(
+            def.table("cursors").unwrap().coalesce,
+            Some(CoalesceDef { window_ms: 50 })
+        );
+        assert_eq!(def.table("clicks").unwrap().coalesce, None);
+
+        let mut raw_def = RawModuleDefV9::from(def);
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::Coalesce(RawCoalesceDefV9 {
+                table: "cursors".into(),
+                window_ms: 0,
+            }));
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::Coalesce(RawCoalesceDefV9 {
+                table: "clicks".into(),
+                window_ms: 50,
+            }));
+        let result: Result<ModuleDef> = raw_def.try_into();
+
+        expect_error_matching!(result, ValidationError::DuplicateCoalesce { table } => {
+            &table[..] == "cursors"
+        });
+        expect_error_matching!(result, ValidationError::CoalesceWithoutPrimaryKey { table } => {
+            &table[..] == "clicks"
+        });
+    }
+
+    #[test]
+    fn bound_to_connection() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type(
+                "presence",
+                ProductType::from([("id", AlgebraicType::U64), ("connection", AlgebraicType::address())]),
+                true,
+            )
+            .with_bound_to_connection(None)
+            .finish();
+        builder
+            .build_table_with_new_type(
+                "invites",
+                ProductType::from([("from", AlgebraicType::address()), ("to", AlgebraicType::address())]),
+                true,
+            )
+            .with_bound_to_connection(Some(1.into()))
+            .finish();
+        builder
+            .build_table_with_new_type("scores", ProductType::from([("score", AlgebraicType::U64)]), true)
+            .finish();
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        assert_eq!(
+            def.table("presence").unwrap().bound_to_connection,
+            Some(BoundToConnectionDef { column: 1.into() })
+        );
+        assert_eq!(
+            def.table("invites").unwrap().bound_to_connection,
+            Some(BoundToConnectionDef { column: 1.into() })
+        );
+        assert_eq!(def.table("scores").unwrap().bound_to_connection, None);
+
+        let mut raw_def = RawModuleDefV9::from(def);
+        for (table, column) in [("presence", None), ("scores", None), ("nope", None)] {
+            raw_def
+                .misc_exports
+                .push(RawMiscModuleExportV9::BoundToConnection(RawBoundToConnectionDefV9 {
+                    table: table.into(),
+                    column,
+                }));
+        }
+        let result: Result<ModuleDef> = raw_def.try_into();
+
+        expect_error_matching!(result, ValidationError::DuplicateBoundToConnection { table } => {
+            &table[..] == "presence"
+        });
+        expect_error_matching!(result, ValidationError::InvalidBoundToConnection { table, .. } => {
+            &table[..] == "scores"
+        });
+        expect_error_matching!(result, ValidationError::MissingTableForBoundToConnection { table } => {
+            &table[..] == "nope"
+        });
+
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type(
+                "invites",
+                ProductType::from([("from", AlgebraicType::address()), ("to", AlgebraicType::address())]),
+                true,
+            )
+            .with_bound_to_connection(None)
+            .finish();
+        builder
+            .build_table_with_new_type("scores", ProductType::from([("score", AlgebraicType::U64)]), true)
+            .with_bound_to_connection(Some(0.into()))
+            .finish();
+        let result: Result<ModuleDef> = builder.finish().try_into();
+
+        expect_error_matching!(result, ValidationError::InvalidBoundToConnection { table, error } => {
+            &table[..] == "invites" && error.contains("more than one")
+        });
+        expect_error_matching!(result, ValidationError::InvalidBoundToConnection { table, error } => {
+            &table[..] == "scores" && error.contains("type `Address`")
+        });
+    }
+
+    #[test]
+    fn table_durability() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder
+            .build_table_with_new_type("cursors", ProductType::from([("x", AlgebraicType::F32)]), true)
+            .with_durability(TableDurability::Relaxed)
+            .finish();
+        builder
+            .build_table_with_new_type("messages", ProductType::from([("text", AlgebraicType::String)]), true)
+            .finish();
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        assert_eq!(def.table("cursors").unwrap().durability, TableDurability::Relaxed);
+        assert_eq!(def.table("messages").unwrap().durability, TableDurability::Durable);
+
+        let mut raw_def = RawModuleDefV9::from(def);
+        raw_def
+            .misc_exports
+            .push(RawMiscModuleExportV9::TableDurability(RawTableDurabilityDefV9 {
+                table: "cursors".into(),
+                durability: TableDurability::Durable,
+            }));
+        let result: Result<ModuleDef> = raw_def.try_into();
+
+        expect_error_matching!(result, ValidationError::DuplicateTableDurability { table } => {
+            &table[..] == "cursors"
+        });
+    }
+
+    #[test]
+    fn http_routes() {
+        let mut builder = RawModuleDefV9Builder::new();
+        builder.add_view(
+            "top_players",
+            ProductType::from([("limit", AlgebraicType::U32)]),
+            AlgebraicType::U64,
+        );
+        builder.add_http_route(HttpMethod::Get, "/leaderboard", "top_players");
+        let def: ModuleDef = builder.finish().try_into().unwrap();
+
+        let route = def.http_route(HttpMethod::Get, "/leaderboard").unwrap();
+        assert_eq!(&route.view[..], "top_players");
+        assert!(def.http_route(HttpMethod::Get, "/nope").is_none());
+
+        let mut raw_def = RawModuleDefV9::from(def);
+        raw_def.misc_exports.extend([
+            RawMiscModuleExportV9::HttpRoute(RawHttpRouteDefV9 {
+                method: HttpMethod::Get,
+                path: "/leaderboard".into(),
+                view: "top_players".into(),
+            }),
+            RawMiscModuleExportV9::HttpRoute(RawHttpRouteDefV9 {
+                method: HttpMethod::Get,
+                path: "/players".into(),
+                view: "players".into(),
+            }),
+            RawMiscModuleExportV9::HttpRoute(RawHttpRouteDefV9 {
+                method: HttpMethod::Get,
+                path: "scores".into(),
+                view: "top_players".into(),
+            }),
+        ]);
+        let result: Result<ModuleDef> = raw_def.try_into();
+
+        expect_error_matching!(result, ValidationError::DuplicateHttpRoute { path, .. } => {
+            &path[..] == "/leaderboard"
+        });
+        expect_error_matching!(result, ValidationError::MissingViewForHttpRoute { view, .. } => {
+            &view[..] == "players"
+        });
+        expect_error_matching!(result, ValidationError::InvalidHttpRoutePath { path } => {
+            &path[..] == "scores"
+        });
+    }
 }
@@ -1,5 +1,5 @@
 use spacetimedb_data_structures::error_stream::ErrorStream;
-use spacetimedb_lib::db::raw_def::v9::{Lifecycle, RawIdentifier, RawScopedTypeNameV9};
+use spacetimedb_lib::db::raw_def::v9::{ArgConstraint, HttpMethod, Lifecycle, RawIdentifier, RawScopedTypeNameV9};
 use spacetimedb_lib::{ProductType, SumType};
 use spacetimedb_primitives::{ColId, ColList};
 use spacetimedb_sats::algebraic_type::fmt::fmt_algebraic_type;
@@ -108,10 +108,107 @@ pub enum ValidationError {
         expected: PrettyAlgebraicType,
         actual: PrettyAlgebraicType,
     },
+    #[error("View {view} has the same name as a reducer")]
+    ViewNameCollidesWithReducer { view: Identifier },
+    #[error("Error type declared for reducer {reducer} that does not exist")]
+    MissingReducerForErrorType { reducer: RawIdentifier },
+    #[error("Reducer {reducer} has more than one error type")]
+    DuplicateReducerErrorType { reducer: RawIdentifier },
     #[error("Table name is reserved for system use: {table}")]
     TableNameReserved { table: Identifier },
     #[error("Row-level security invalid: `{error}`, query: `{sql}")]
     InvalidRowLevelQuery { sql: String, error: String },
+    #[error("Durability declared for table {table} that does not exist")]
+    MissingTableForDurability { table: RawIdentifier },
+    #[error("Durability declared more than once for table {table}")]
+    DuplicateTableDurability { table: RawIdentifier },
+    #[error("Catch-up policy declared for table {table}, which does not exist or is not scheduled")]
+    MissingScheduleForCatchUp { table: RawIdentifier },
+    #[error("Catch-up policy declared more than once for table {table}")]
+    DuplicateScheduleCatchUp { table: RawIdentifier },
+    #[error("HTTP route {method:?} {path} is served by view {view}, which does not exist")]
+    MissingViewForHttpRoute {
+        method: HttpMethod,
+        path: Box<str>,
+        view: RawIdentifier,
+    },
+    #[error("HTTP route path `{path}` is invalid: it must start with `/` and contain no query or fragment")]
+    InvalidHttpRoutePath { path: Box<str> },
+    #[error("HTTP route {method:?} {path} is declared more than once")]
+    DuplicateHttpRoute { method: HttpMethod, path: Box<str> },
+    #[error("Generated column declared for table {table} that does not exist")]
+    MissingTableForGeneratedColumn { table: RawIdentifier },
+    #[error("Generated column {column} is invalid: {error}, expression: `{expr}`")]
+    InvalidGeneratedColumn {
+        column: RawColumnName,
+        expr: Box<str>,
+        error: String,
+    },
+    #[error("Counter declared for table {table} that does not exist")]
+    MissingTableForCounter { table: RawIdentifier },
+    #[error("Counter of table {table} into {into} is invalid: {error}")]
+    InvalidCounter {
+        table: RawIdentifier,
+        into: RawColumnName,
+        error: String,
+    },
+    #[error("Soft delete declared for table {table} that does not exist")]
+    MissingTableForSoftDelete { table: RawIdentifier },
+    #[error("Soft delete declared more than once for table {table}")]
+    DuplicateSoftDelete { table: RawIdentifier },
+    #[error("Soft delete of table {table} must retain at least one transaction")]
+    ZeroSoftDeleteRetention { table: RawIdentifier },
+    #[error("Coalescing declared for table {table} that does not exist")]
+    MissingTableForCoalesce { table: RawIdentifier },
+    #[error("Coalescing declared more than once for table {table}")]
+    DuplicateCoalesce { table: RawIdentifier },
+    #[error("Coalescing window of table {table} must be at least one millisecond")]
+    ZeroCoalesceWindow { table: RawIdentifier },
+    #[error("Table {table} must have a primary key to coalesce its updates")]
+    CoalesceWithoutPrimaryKey { table: RawIdentifier },
+    #[error("Binding to connections declared for table {table} that does not exist")]
+    MissingTableForBoundToConnection { table: RawIdentifier },
+    #[error("Binding to connections declared more than once for table {table}")]
+    DuplicateBoundToConnection { table: RawIdentifier },
+    #[error("Binding of table {table} to connections is invalid: {error}")]
+    InvalidBoundToConnection { table: RawIdentifier, error: String },
+    #[error("Alias {alias} declared for reducer {reducer} that does not exist")]
+    MissingReducerForAlias {
+        reducer: RawIdentifier,
+        alias: RawIdentifier,
+    },
+    #[error("Alias {alias} is already the name or an alias of a reducer")]
+    ReducerAliasCollides { alias: Identifier },
+    #[error("Priority declared for reducer {reducer} that does not exist")]
+    MissingReducerForPriority { reducer: RawIdentifier },
+    #[error("Priority declared more than once for reducer {reducer}")]
+    DuplicateReducerPriority { reducer: RawIdentifier },
+    #[error("Topic authorizer {reducer} is not a reducer")]
+    MissingReducerForTopicAuthorizer { reducer: RawIdentifier },
+    #[error("Topic authorizer declared more than once, as {reducer}")]
+    DuplicateTopicAuthorizer { reducer: RawIdentifier },
+    #[error("Topic authorizer {reducer} must take a single `String` argument and not be a lifecycle reducer")]
+    InvalidTopicAuthorizer { reducer: RawIdentifier },
+    #[error("Argument constraint declared for reducer {reducer} that does not exist")]
+    MissingReducerForArgConstraint { reducer: RawIdentifier },
+    #[error("Argument constraint declared for argument {arg} of reducer {reducer}, which has fewer arguments")]
+    ArgConstraintArgOutOfRange { reducer: RawIdentifier, arg: u16 },
+    #[error("Argument constraint {constraint:?} cannot apply to argument {arg} of reducer {reducer}, of type {ty}")]
+    ArgConstraintTypeMismatch {
+        reducer: RawIdentifier,
+        arg: u16,
+        constraint: ArgConstraint,
+        ty: PrettyAlgebraicType,
+    },
+    #[error("{column} does not exist")]
+    UnknownColumn { column: RawColumnName },
+    #[error("Reducer {reducer} has no parameter named `{param}`")]
+    UnknownReducerParam {
+        reducer: RawIdentifier,
+        param: RawIdentifier,
+    },
+    #[error("Module exports {export}, which this host does not support")]
+    UnsupportedMiscExport { export: Box<str> },
 }
 
 /// A wrapper around an `AlgebraicType` that implements `fmt::Display`.
@@ -155,6 +252,16 @@ pub enum TypeLocation<'a> {
         position: usize,
         arg_name: Option<Cow<'a, str>>,
     },
+    /// A view argument.
+    ViewArg {
+        view_name: Cow<'a, str>,
+        position: usize,
+        arg_name: Option<Cow<'a, str>>,
+    },
+    /// The return type of a view.
+    ViewReturn { view_name: Cow<'a, str> },
+    /// The error type of a reducer.
+    ReducerError { reducer_name: Cow<'a, str> },
     /// A type in the typespace.
     InTypespace {
         /// The reference to the type within the typespace.
@@ -175,6 +282,21 @@ impl TypeLocation<'_> {
                 position,
                 arg_name: arg_name.map(|s| s.to_string().into()),
             },
+            TypeLocation::ViewArg {
+                view_name,
+                position,
+                arg_name,
+            } => TypeLocation::ViewArg {
+                view_name: view_name.to_string().into(),
+                position,
+                arg_name: arg_name.map(|s| s.to_string().into()),
+            },
+            TypeLocation::ViewReturn { view_name } => TypeLocation::ViewReturn {
+                view_name: view_name.to_string().into(),
+            },
+            TypeLocation::ReducerError { reducer_name } => TypeLocation::ReducerError {
+                reducer_name: reducer_name.to_string().into(),
+            },
             // needed to convince rustc this is allowed.
             TypeLocation::InTypespace { ref_ } => TypeLocation::InTypespace { ref_ },
         }
@@ -195,6 +317,23 @@ impl fmt::Display for TypeLocation<'_> {
                 }
                 Ok(())
             }
+            TypeLocation::ViewArg {
+                view_name,
+                position,
+                arg_name,
+            } => {
+                write!(f, "view `{}` argument {}", view_name, position)?;
+                if let Some(arg_name) = arg_name {
+                    write!(f, " (`{}`)", arg_name)?;
+                }
+                Ok(())
+            }
+            TypeLocation::ViewReturn { view_name } => {
+                write!(f, "view `{}` return type", view_name)
+            }
+            TypeLocation::ReducerError { reducer_name } => {
+                write!(f, "reducer `{}` error type", reducer_name)
+            }
             TypeLocation::InTypespace { ref_ } => {
                 write!(f, "typespace ref `{}`", ref_)
             }
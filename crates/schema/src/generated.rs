@@ -0,0 +1,334 @@
+//! Expressions computing the values of generated columns.
+//!
+//! A generated column is maintained by the datastore,
+//! which recomputes it from the other columns of a row whenever the row is inserted or updated.
+//! The expression language is deliberately tiny:
+//!
+//! ```text
+//! expr := column_name
+//!       | 'string literal'
+//!       | lower(expr) | upper(expr) | trim(expr)
+//!       | concat(expr, expr, ...)
+//! ```
+//!
+//! Within string literals, a quote is escaped by doubling it, as in SQL.
+
+use spacetimedb_primitives::ColId;
+use spacetimedb_sats::{AlgebraicType, AlgebraicValue, ProductValue};
+
+/// An error parsing or type checking a generated column expression.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum GeneratedExprError {
+    #[error("unexpected end of expression")]
+    UnexpectedEnd,
+    #[error("unexpected `{0}`")]
+    Unexpected(char),
+    #[error("unterminated string literal")]
+    UnterminatedString,
+    #[error("unknown function `{0}`")]
+    UnknownFunction(Box<str>),
+    #[error("`{function}` expects {expected} argument(s), but got {actual}")]
+    Arity {
+        function: Box<str>,
+        expected: &'static str,
+        actual: usize,
+    },
+    #[error("`{0}` is not a column of the table")]
+    UnknownColumn(Box<str>),
+    #[error("`{0}` is itself a generated column")]
+    GeneratedColumnReference(Box<str>),
+    #[error("`{function}` expects a string argument")]
+    NotAString { function: Box<str> },
+    #[error("the expression does not have the type of the column")]
+    TypeMismatch,
+}
+
+/// A function which may be applied in a generated column expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GeneratedFn {
+    Lower,
+    Upper,
+    Trim,
+    Concat,
+}
+
+impl GeneratedFn {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match &*name.to_ascii_lowercase() {
+            "lower" => Self::Lower,
+            "upper" => Self::Upper,
+            "trim" => Self::Trim,
+            "concat" => Self::Concat,
+            _ => return None,
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Lower => "lower",
+            Self::Upper => "upper",
+            Self::Trim => "trim",
+            Self::Concat => "concat",
+        }
+    }
+}
+
+/// A parsed and type checked generated column expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeneratedExpr {
+    /// The value of another column of the row.
+    Column(ColId),
+    /// A string literal.
+    Literal(Box<str>),
+    /// A function applied to the values of some expressions.
+    Call(GeneratedFn, Vec<GeneratedExpr>),
+}
+
+impl GeneratedExpr {
+    /// Parses `source` as the expression of a generated column with type `ty`.
+    ///
+    /// The column names in `source` are resolved with `resolve_column`,
+    /// which returns the column's ID and type, and whether it is itself generated.
+    pub fn parse<'a>(
+        source: &str,
+        ty: &AlgebraicType,
+        resolve_column: impl Fn(&str) -> Option<(ColId, &'a AlgebraicType, bool)>,
+    ) -> Result<Self, GeneratedExprError> {
+        let mut parser = Parser {
+            rest: source,
+            resolve_column: &resolve_column,
+        };
+        let (expr, expr_ty) = parser.parse_expr()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.rest.chars().next() {
+            return Err(GeneratedExprError::Unexpected(c));
+        }
+        if expr_ty != *ty {
+            return Err(GeneratedExprError::TypeMismatch);
+        }
+        Ok(expr)
+    }
+
+    /// Computes the value of this expression for `row`.
+    ///
+    /// The expression must have been parsed against the type of `row`.
+    pub fn eval(&self, row: &ProductValue) -> AlgebraicValue {
+        match self {
+            Self::Column(col) => row.elements[col.idx()].clone(),
+            Self::Literal(s) => AlgebraicValue::String(s.clone()),
+            Self::Call(func, args) => {
+                let mut args = args.iter().map(|arg| match arg.eval(row) {
+                    AlgebraicValue::String(s) => s,
+                    _ => unreachable!("arguments of `{}` are type checked to be strings", func.name()),
+                });
+                let s: Box<str> = match func {
+                    GeneratedFn::Lower => args.next().unwrap().to_lowercase().into(),
+                    GeneratedFn::Upper => args.next().unwrap().to_uppercase().into(),
+                    GeneratedFn::Trim => args.next().unwrap().trim().into(),
+                    GeneratedFn::Concat => args.collect::<String>().into(),
+                };
+                AlgebraicValue::String(s)
+            }
+        }
+    }
+
+    /// Calls `f` on each column that this expression reads.
+    pub fn visit_columns(&self, f: &mut impl FnMut(ColId)) {
+        match self {
+            Self::Column(col) => f(*col),
+            Self::Literal(_) => {}
+            Self::Call(_, args) => args.iter().for_each(|arg| arg.visit_columns(f)),
+        }
+    }
+}
+
+struct Parser<'s, 'r, F> {
+    rest: &'s str,
+    resolve_column: &'r F,
+}
+
+impl<'s, 'a, F: Fn(&str) -> Option<(ColId, &'a AlgebraicType, bool)>> Parser<'s, '_, F> {
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.rest.chars().next()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), GeneratedExprError> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.rest = &self.rest[c.len_utf8()..];
+                Ok(())
+            }
+            Some(c) => Err(GeneratedExprError::Unexpected(c)),
+            None => Err(GeneratedExprError::UnexpectedEnd),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<(GeneratedExpr, AlgebraicType), GeneratedExprError> {
+        match self.peek() {
+            None => Err(GeneratedExprError::UnexpectedEnd),
+            Some('\'') => self
+                .parse_string()
+                .map(|s| (GeneratedExpr::Literal(s), AlgebraicType::String)),
+            Some(c) if c == '_' || c.is_alphabetic() => {
+                let ident = self.parse_ident();
+                if self.peek() == Some('(') {
+                    self.parse_call(ident)
+                } else {
+                    let (col, ty, generated) =
+                        (self.resolve_column)(ident).ok_or_else(|| GeneratedExprError::UnknownColumn(ident.into()))?;
+                    if generated {
+                        return Err(GeneratedExprError::GeneratedColumnReference(ident.into()));
+                    }
+                    Ok((GeneratedExpr::Column(col), ty.clone()))
+                }
+            }
+            Some(c) => Err(GeneratedExprError::Unexpected(c)),
+        }
+    }
+
+    fn parse_ident(&mut self) -> &'s str {
+        let end = self
+            .rest
+            .find(|c: char| c != '_' && !c.is_alphanumeric())
+            .unwrap_or(self.rest.len());
+        let (ident, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        ident
+    }
+
+    fn parse_string(&mut self) -> Result<Box<str>, GeneratedExprError> {
+        self.expect('\'')?;
+        let mut s = String::new();
+        loop {
+            let end = self.rest.find('\'').ok_or(GeneratedExprError::UnterminatedString)?;
+            s.push_str(&self.rest[..end]);
+            self.rest = &self.rest[end + 1..];
+            // A doubled quote is an escaped quote.
+            match self.rest.strip_prefix('\'') {
+                Some(rest) => {
+                    s.push('\'');
+                    self.rest = rest;
+                }
+                None => return Ok(s.into()),
+            }
+        }
+    }
+
+    fn parse_call(&mut self, name: &str) -> Result<(GeneratedExpr, AlgebraicType), GeneratedExprError> {
+        let func = GeneratedFn::from_name(name).ok_or_else(|| GeneratedExprError::UnknownFunction(name.into()))?;
+        self.expect('(')?;
+        let mut args = Vec::new();
+        if self.peek() != Some(')') {
+            loop {
+                let (arg, ty) = self.parse_expr()?;
+                if ty != AlgebraicType::String {
+                    return Err(GeneratedExprError::NotAString {
+                        function: func.name().into(),
+                    });
+                }
+                args.push(arg);
+                if self.peek() == Some(',') {
+                    self.expect(',')?;
+                } else {
+                    break;
+                }
+            }
+        }
+        self.expect(')')?;
+
+        let (arity_ok, expected) = match func {
+            GeneratedFn::Lower | GeneratedFn::Upper | GeneratedFn::Trim => (args.len() == 1, "1"),
+            GeneratedFn::Concat => (!args.is_empty(), "at least 1"),
+        };
+        if !arity_ok {
+            return Err(GeneratedExprError::Arity {
+                function: func.name().into(),
+                expected,
+                actual: args.len(),
+            });
+        }
+        Ok((GeneratedExpr::Call(func, args), AlgebraicType::String))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(name: &str) -> Option<(ColId, &'static AlgebraicType, bool)> {
+        static STRING: AlgebraicType = AlgebraicType::String;
+        static U32: AlgebraicType = AlgebraicType::U32;
+        match name {
+            "first" => Some((ColId(0), &STRING, false)),
+            "last" => Some((ColId(1), &STRING, false)),
+            "age" => Some((ColId(2), &U32, false)),
+            "key" => Some((ColId(3), &STRING, true)),
+            _ => None,
+        }
+    }
+
+    fn eval(source: &str) -> Result<AlgebraicValue, GeneratedExprError> {
+        let row = ProductValue::from_iter([
+            AlgebraicValue::String("  Ada ".into()),
+            AlgebraicValue::String("Lovelace".into()),
+            AlgebraicValue::U32(36),
+            AlgebraicValue::String("".into()),
+        ]);
+        let ty = if source == "age" {
+            AlgebraicType::U32
+        } else {
+            AlgebraicType::String
+        };
+        GeneratedExpr::parse(source, &ty, columns).map(|expr| expr.eval(&row))
+    }
+
+    #[test]
+    fn evaluates_functions() {
+        assert_eq!(eval("lower(last)"), Ok(AlgebraicValue::String("lovelace".into())));
+        assert_eq!(eval("UPPER(last)"), Ok(AlgebraicValue::String("LOVELACE".into())));
+        assert_eq!(
+            eval("concat(lower(last), ', ', trim(first))"),
+            Ok(AlgebraicValue::String("lovelace, Ada".into()))
+        );
+        assert_eq!(eval("'it''s'"), Ok(AlgebraicValue::String("it's".into())));
+        assert_eq!(eval("age"), Ok(AlgebraicValue::U32(36)));
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        assert_eq!(
+            eval("lower(age)"),
+            Err(GeneratedExprError::NotAString {
+                function: "lower".into()
+            })
+        );
+        assert_eq!(
+            eval("lower(nope)"),
+            Err(GeneratedExprError::UnknownColumn("nope".into()))
+        );
+        assert_eq!(
+            eval("lower(key)"),
+            Err(GeneratedExprError::GeneratedColumnReference("key".into()))
+        );
+        assert_eq!(
+            eval("reverse(last)"),
+            Err(GeneratedExprError::UnknownFunction("reverse".into()))
+        );
+        assert_eq!(eval("lower(last"), Err(GeneratedExprError::UnexpectedEnd));
+        assert_eq!(eval("lower(last))"), Err(GeneratedExprError::Unexpected(')')));
+        assert_eq!(eval("'oops"), Err(GeneratedExprError::UnterminatedString));
+        assert!(matches!(
+            eval("lower(first, last)"),
+            Err(GeneratedExprError::Arity { .. })
+        ));
+        assert_eq!(
+            GeneratedExpr::parse("last", &AlgebraicType::U32, columns),
+            Err(GeneratedExprError::TypeMismatch)
+        );
+    }
+}
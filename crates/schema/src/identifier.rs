@@ -9,7 +9,51 @@ use unicode_normalization::UnicodeNormalization;
 
 lazy_static::lazy_static! {
     /// TODO(1.0): Pull in the rest of the reserved identifiers from the Identifier Proposal once that's merged.
-    static ref RESERVED_IDENTIFIERS: HashSet<&'static str> = include_str!("reserved_identifiers.txt").lines().collect();
+    static ref RESERVED_IDENTIFIERS: ReservedWords = include_str!("reserved_identifiers.txt").lines().collect();
+}
+
+/// A set of words which cannot be used as unquoted identifiers.
+///
+/// Words are compared case-insensitively,
+/// by converting candidate identifiers with [`str::to_uppercase`].
+///
+/// The set is not configurable.
+/// A module's schema is validated the same way by every host and by codegen,
+/// so a per-host list could accept a module on one host and reject it on another.
+/// Quoting a name, e.g. `"order"`, is how to use a reserved word as an identifier.
+#[derive(Debug, Clone, Default)]
+struct ReservedWords {
+    words: HashSet<Box<str>>,
+}
+
+impl ReservedWords {
+    /// Returns the words reserved by SpacetimeDB,
+    /// which are used by [`Identifier::new`].
+    fn builtin() -> &'static Self {
+        &RESERVED_IDENTIFIERS
+    }
+
+    /// Returns an empty set, reserving no words.
+    fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Returns this set with `word` reserved as well.
+    fn with(mut self, word: &str) -> Self {
+        self.words.insert(word.to_uppercase().into());
+        self
+    }
+
+    /// Returns whether `name` is reserved.
+    fn contains(&self, name: &str) -> bool {
+        self.words.contains(&*name.to_uppercase())
+    }
+}
+
+impl<'a> FromIterator<&'a str> for ReservedWords {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        iter.into_iter().fold(Self::empty(), Self::with)
+    }
 }
 
 /// A valid SpacetimeDB Identifier.
@@ -24,6 +68,11 @@ lazy_static::lazy_static! {
 /// [`String::to_uppercase`](https://doc.rust-lang.org/std/string/struct.String.html#method.to_uppercase) will be rejected.
 ///
 /// The list of reserved words can be found in the file `SpacetimeDB/crates/sats/db/reserved_identifiers.txt`.
+///
+/// A reserved word may still be used if it is quoted, as in SQL:
+/// the raw name `"order"` is accepted as the identifier `order`.
+/// Only the reserved word check is lifted for quoted identifiers;
+/// they must otherwise follow the same rules as unquoted ones.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, de::Deserialize, ser::Serialize)]
 #[sats(crate = spacetimedb_sats)]
 pub struct Identifier {
@@ -31,11 +80,26 @@ pub struct Identifier {
 }
 
 impl Identifier {
-    /// Validates that the input string is a valid identifier.
+    /// Validates that the input string is a valid identifier,
+    /// rejecting unquoted identifiers which are reserved by SpacetimeDB.
     ///
     /// Currently, this rejects non-canonicalized identifiers.
     /// Eventually, it will be changed to canonicalize the input string.
     pub fn new(name: Box<str>) -> Result<Self, IdentifierError> {
+        Self::new_with_reserved(name, ReservedWords::builtin())
+    }
+
+    /// Validates that the input string is a valid identifier,
+    /// rejecting unquoted identifiers which are in `reserved`.
+    ///
+    /// A quoted identifier, e.g. `"order"`, has its quotes removed
+    /// and is never rejected for being reserved.
+    fn new_with_reserved(name: Box<str>, reserved: &ReservedWords) -> Result<Self, IdentifierError> {
+        let (name, quoted) = match unquote(&name) {
+            Some(unquoted) => (unquoted.into(), true),
+            None => (name, false),
+        };
+
         if name.is_empty() {
             return Err(IdentifierError::Empty {});
         }
@@ -64,7 +128,7 @@ impl Identifier {
             }
         }
 
-        if Identifier::is_reserved(&name) {
+        if !quoted && reserved.contains(&name) {
             return Err(IdentifierError::Reserved { name });
         }
 
@@ -73,8 +137,20 @@ impl Identifier {
 
     /// Check if a string is a reserved identifier.
     pub fn is_reserved(name: &str) -> bool {
-        RESERVED_IDENTIFIERS.contains(&*name.to_uppercase())
+        ReservedWords::builtin().contains(name)
     }
+
+    /// Returns this identifier quoted for use in SQL, e.g. `"order"`.
+    ///
+    /// Quoting is always valid, so this does not check whether the identifier is reserved.
+    pub fn to_sql_quoted(&self) -> String {
+        format!("\"{}\"", self.id)
+    }
+}
+
+/// Returns the contents of `name` if it is surrounded by double quotes.
+fn unquote(name: &str) -> Option<&str> {
+    name.strip_prefix('"')?.strip_suffix('"')
 }
 
 impl Debug for Identifier {
@@ -140,6 +216,35 @@ mod tests {
         assert!(Identifier::new("_\u{00C5}".into()).is_ok());
     }
 
+    #[test]
+    fn test_reserved_words() {
+        let reserved = ReservedWords::empty().with("order").with("USER");
+        assert!(reserved.contains("ORDER"));
+        assert!(reserved.contains("user"));
+        assert!(!reserved.contains("orders"));
+
+        assert!(matches!(
+            Identifier::new_with_reserved("Order".into(), &reserved),
+            Err(IdentifierError::Reserved { .. })
+        ));
+        assert!(Identifier::new_with_reserved("orders".into(), &reserved).is_ok());
+        assert!(Identifier::new_with_reserved("order".into(), &ReservedWords::empty()).is_ok());
+    }
+
+    #[test]
+    fn test_quoted_identifiers() {
+        let reserved = ReservedWords::empty().with("order");
+        let order = Identifier::new_with_reserved("\"order\"".into(), &reserved).unwrap();
+        assert_eq!(&*order, "order");
+        assert_eq!(order.to_sql_quoted(), "\"order\"");
+
+        // Quoting only lifts the reserved word check.
+        assert!(Identifier::new_with_reserved("\"\"".into(), &reserved).is_err());
+        assert!(Identifier::new_with_reserved("\"my order\"".into(), &reserved).is_err());
+        assert!(Identifier::new_with_reserved("\"123\"".into(), &reserved).is_err());
+        assert!(Identifier::new_with_reserved("\"order".into(), &reserved).is_err());
+    }
+
     proptest! {
         #[test]
         fn test_standard_ascii_identifiers(s in "[a-zA-Z_][a-zA-Z0-9_]*") {
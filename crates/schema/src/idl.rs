@@ -0,0 +1,1214 @@
+//! The `.sats` schema language, describing whole modules.
+//!
+//! The language extends the type syntax of [`spacetimedb_sats::idl`]
+//! with declarations of the types, tables, reducers, views, HTTP routes and row-level security filters of a module,
+//! so that schemas can be reviewed and diffed in PRs, and authored independently of any module language:
+//!
+//! ```text
+//! // `@custom_ordering` marks types whose elements are not sorted by name.
+//! @custom_ordering type game::Player = (
+//!     id: u64,
+//!     name: string,
+//!     shape: game::Shape,
+//! );
+//! type game::Shape = (
+//!     circle: f32 |
+//!     empty |
+//! );
+//!
+//! table player: game::Player public {
+//!     primary_key(id);
+//!     unique(name);
+//!     index by_name btree(name);
+//!     sequence(id) start 1 increment 1;
+//!     durability relaxed;
+//!     soft_delete retention 1000;
+//!     coalesce window_ms 50;
+//! }
+//!
+//! @init reducer init();
+//! reducer add_player(@max_len(32) name: string) fails string;
+//! reducer autosave() priority low;
+//! reducer add_user(name: string) alias new_user deprecated "use add_user";
+//! @authorize_topic reducer can_subscribe(topic: string);
+//! view top_players(limit: u32) -> [game::Player];
+//! route get "/top" => top_players;
+//! rls "SELECT * FROM player";
+//! ```
+//!
+//! Named types may be used before they are declared,
+//! but the row type of a table must be declared before the table,
+//! as the table refers to the columns of the row type by name, or by position for unnamed columns.
+//! Constraints, indexes, sequences and schedules may be given explicit names with `as "name"`.
+
+use crate::def::ModuleDef;
+use spacetimedb_data_structures::map::HashMap;
+use spacetimedb_lib::db::raw_def::v9::*;
+use spacetimedb_primitives::{ColId, ColList};
+use spacetimedb_sats::de::fmt_fn;
+use spacetimedb_sats::idl::{
+    fmt_name, fmt_type_with, write_product_element, write_sum_variant, ParseError, Parser, Token,
+};
+use spacetimedb_sats::{AlgebraicType, AlgebraicTypeRef, ProductType, ProductTypeElement, Typespace};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Write};
+
+/// Prints `def` in the `.sats` format.
+pub fn print_module(def: &ModuleDef) -> String {
+    let mut def = RawModuleDefV9::from(def.clone());
+    // A `ModuleDef` stores its definitions in hash maps,
+    // so sort them, so that the same module is always printed the same way.
+    def.types.sort_by_key(|ty| scoped_name(&ty.name));
+    def.tables.sort_by(|a, b| a.name.cmp(&b.name));
+    for table in &mut def.tables {
+        table.constraints.sort_by(|a, b| a.name.cmp(&b.name));
+        table.indexes.sort_by(|a, b| a.name.cmp(&b.name));
+        table.sequences.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+    def.reducers.sort_by(|a, b| a.name.cmp(&b.name));
+    def.misc_exports.sort_by_key(|export| match export {
+        RawMiscModuleExportV9::View(view) => Some(view.name.clone()),
+        RawMiscModuleExportV9::HttpRoute(route) => Some(route.path.clone()),
+        _ => None,
+    });
+    def.row_level_security.sort_by(|a, b| a.sql.cmp(&b.sql));
+    print_raw_module(&def)
+}
+
+/// Prints the not-yet-validated `def` in the `.sats` format.
+pub fn print_raw_module(def: &RawModuleDefV9) -> String {
+    let mut out = String::new();
+    Printer::new(def)
+        .write_module(&mut out)
+        .expect("writing to a `String` can't fail");
+    out
+}
+
+/// Parses a module in the `.sats` format.
+///
+/// The result is not validated; convert it into a [`ModuleDef`] for that.
+pub fn parse_module(source: &str) -> Result<RawModuleDefV9, ParseError> {
+    ModuleParser {
+        p: Parser::new(source)?,
+        def: RawModuleDefV9::default(),
+        types: TypeNames::default(),
+    }
+    .parse()
+}
+
+struct Printer<'a> {
+    def: &'a RawModuleDefV9,
+    /// The names of the named types.
+    names: HashMap<AlgebraicTypeRef, String>,
+    /// The unnamed types currently being inlined, used to break cycles.
+    inlining: RefCell<Vec<AlgebraicTypeRef>>,
+}
+
+impl<'a> Printer<'a> {
+    fn new(def: &'a RawModuleDefV9) -> Self {
+        let mut names = HashMap::default();
+        for ty in &def.types {
+            names.entry(ty.ty).or_insert_with(|| scoped_name(&ty.name));
+        }
+        Self {
+            def,
+            names,
+            inlining: RefCell::default(),
+        }
+    }
+
+    /// Writes the named type `r` by its name, and inlines unnamed types.
+    fn write_ref(&self, r: AlgebraicTypeRef, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(name) = self.names.get(&r) {
+            return f.write_str(name);
+        }
+        match self.def.typespace.get(r) {
+            Some(ty) if !self.inlining.borrow().contains(&r) => {
+                self.inlining.borrow_mut().push(r);
+                let res = write!(f, "{}", self.ty(ty));
+                self.inlining.borrow_mut().pop();
+                res
+            }
+            _ => write!(f, "{r}"),
+        }
+    }
+
+    fn ty<'b>(&'b self, ty: &'b AlgebraicType) -> impl 'b + Display {
+        fmt_type_with(ty, |r, f| self.write_ref(r, f))
+    }
+
+    fn ty_ref(&self, r: AlgebraicTypeRef) -> impl '_ + Display {
+        fmt_fn(move |f| self.write_ref(r, f))
+    }
+
+    fn write_module(&self, out: &mut String) -> fmt::Result {
+        let def = self.def;
+        let mut sections = Vec::new();
+
+        let mut types = String::new();
+        for ty in &def.types {
+            self.write_type_decl(&mut types, ty)?;
+        }
+        sections.push(types);
+
+        for table in &def.tables {
+            let mut section = String::new();
+            self.write_table(&mut section, table)?;
+            sections.push(section);
+        }
+
+        let mut reducers = String::new();
+        for reducer in &def.reducers {
+            self.write_reducer(&mut reducers, reducer)?;
+        }
+        sections.push(reducers);
+
+        let (mut views, mut routes) = (String::new(), String::new());
+        for export in &def.misc_exports {
+            match export {
+                RawMiscModuleExportV9::View(view) => writeln!(
+                    views,
+                    "view {}{} -> {};",
+                    fmt_name(&view.name),
+                    self.params(&view.params, &[]),
+                    self.ty(&view.return_type)
+                )?,
+                RawMiscModuleExportV9::HttpRoute(route) => {
+                    let method = match route.method {
+                        HttpMethod::Get => "get",
+                        // Routes unknown to this version of the format are omitted.
+                        _ => continue,
+                    };
+                    writeln!(routes, "route {method} {:?} => {};", route.path, fmt_name(&route.view))?
+                }
+                _ => {}
+            }
+        }
+        sections.push(views);
+        sections.push(routes);
+
+        let mut rls = String::new();
+        for filter in &def.row_level_security {
+            writeln!(rls, "rls {:?};", filter.sql)?;
+        }
+        sections.push(rls);
+
+        sections.retain(|section| !section.is_empty());
+        out.push_str(&sections.join("\n"));
+        Ok(())
+    }
+
+    fn write_type_decl(&self, out: &mut String, decl: &RawTypeDefV9) -> fmt::Result {
+        if decl.custom_ordering {
+            out.push_str("@custom_ordering ");
+        }
+        write!(out, "type {} = ", scoped_name(&decl.name))?;
+        match self.def.typespace.get(decl.ty) {
+            // Write the elements of products and sums on lines of their own.
+            Some(AlgebraicType::Product(product)) if !product.elements.is_empty() => {
+                out.push_str("(\n");
+                for elem in &product.elements[..] {
+                    let elem = fmt_fn(|f| write_product_element(f, elem, &|r, f| self.write_ref(r, f)));
+                    writeln!(out, "    {elem},")?;
+                }
+                out.push_str(");\n");
+            }
+            Some(AlgebraicType::Sum(sum)) if !sum.variants.is_empty() && sum.as_option().is_none() => {
+                out.push_str("(\n");
+                for variant in &sum.variants[..] {
+                    let variant = fmt_fn(|f| write_sum_variant(f, variant, &|r, f| self.write_ref(r, f)));
+                    writeln!(out, "    {variant} |")?;
+                }
+                out.push_str(");\n");
+            }
+            Some(ty) => writeln!(out, "{};", self.ty(ty))?,
+            None => writeln!(out, "{};", decl.ty)?,
+        }
+        Ok(())
+    }
+
+    fn write_table(&self, out: &mut String, table: &RawTableDefV9) -> fmt::Result {
+        let access = match table.table_access {
+            TableAccess::Public => "public",
+            TableAccess::Private => "private",
+        };
+        write!(
+            out,
+            "table {}: {} {access}",
+            fmt_name(&table.name),
+            self.ty_ref(table.product_type_ref)
+        )?;
+        if table.table_type == TableType::System {
+            out.push_str(" system");
+        }
+        out.push_str(" {\n");
+
+        let columns = &row_columns(&self.def.typespace, table.product_type_ref).unwrap_or_default();
+        let col = |col: ColId| {
+            fmt_fn(move |f| match columns.get(col.idx()) {
+                Some(Some(name)) => write!(f, "{}", fmt_name(name)),
+                _ => write!(f, "{col}"),
+            })
+        };
+        let cols = |cols: &ColList| {
+            let cols = cols.iter().map(|c| col(c).to_string()).collect::<Vec<_>>();
+            cols.join(", ")
+        };
+        let explicit_name = |name: &Option<Box<str>>| match name {
+            Some(name) => format!(" as {name:?}"),
+            None => String::new(),
+        };
+
+        if !table.primary_key.is_empty() {
+            writeln!(out, "    primary_key({});", cols(&table.primary_key))?;
+        }
+        for constraint in &table.constraints {
+            let columns = match &constraint.data {
+                RawConstraintDataV9::Unique(unique) => &unique.columns,
+                // Constraints unknown to this version of the format are omitted.
+                _ => continue,
+            };
+            writeln!(out, "    unique({}){};", cols(columns), explicit_name(&constraint.name))?;
+        }
+        for index in &table.indexes {
+            let (algorithm, columns) = match &index.algorithm {
+                RawIndexAlgorithm::BTree { columns } => ("btree", columns),
+                RawIndexAlgorithm::Hash { columns } => ("hash", columns),
+                // Indexes unknown to this version of the format are omitted.
+                _ => continue,
+            };
+            out.push_str("    index ");
+            if let Some(accessor) = &index.accessor_name {
+                write!(out, "{} ", fmt_name(accessor))?;
+            }
+            writeln!(out, "{algorithm}({}){};", cols(columns), explicit_name(&index.name))?;
+        }
+        for sequence in &table.sequences {
+            write!(out, "    sequence({})", col(sequence.column))?;
+            if let Some(start) = sequence.start {
+                write!(out, " start {start}")?;
+            }
+            if let Some(min) = sequence.min_value {
+                write!(out, " min {min}")?;
+            }
+            if let Some(max) = sequence.max_value {
+                write!(out, " max {max}")?;
+            }
+            if sequence.increment != 1 {
+                write!(out, " increment {}", sequence.increment)?;
+            }
+            writeln!(out, "{};", explicit_name(&sequence.name))?;
+        }
+        if let Some(schedule) = &table.schedule {
+            writeln!(
+                out,
+                "    schedule {}({}){};",
+                fmt_name(&schedule.reducer_name),
+                col(schedule.scheduled_at_column),
+                explicit_name(&schedule.name)
+            )?;
+        }
+        for export in &self.def.misc_exports {
+            match export {
+                RawMiscModuleExportV9::TableDurability(durability) if durability.table == table.name => {
+                    let durability = match durability.durability {
+                        TableDurability::Durable => "durable",
+                        TableDurability::Relaxed => "relaxed",
+                        TableDurability::Ephemeral => "ephemeral",
+                    };
+                    writeln!(out, "    durability {durability};")?;
+                }
+                RawMiscModuleExportV9::SoftDelete(soft_delete) if soft_delete.table == table.name => {
+                    writeln!(out, "    soft_delete retention {};", soft_delete.retention)?;
+                }
+                RawMiscModuleExportV9::Coalesce(coalesce) if coalesce.table == table.name => {
+                    writeln!(out, "    coalesce window_ms {};", coalesce.window_ms)?;
+                }
+                RawMiscModuleExportV9::BoundToConnection(bound) if bound.table == table.name => match bound.column {
+                    Some(column) => writeln!(out, "    bound_to_connection({});", col(column))?,
+                    None => writeln!(out, "    bound_to_connection;")?,
+                },
+                RawMiscModuleExportV9::ScheduleCatchUp(catch_up) if catch_up.table == table.name => {
+                    let policy = match catch_up.policy {
+                        CatchUpPolicy::SkipMissed => "skip_missed",
+                        CatchUpPolicy::FireOnce => "fire_once",
+                        CatchUpPolicy::FireAll => "fire_all",
+                    };
+                    writeln!(out, "    catch_up {policy};")?;
+                }
+                RawMiscModuleExportV9::GeneratedColumn(generated) if generated.table == table.name => {
+                    writeln!(out, "    generated({}) = {:?};", col(generated.column), generated.expr)?;
+                }
+                RawMiscModuleExportV9::Counter(counter) if counter.table == table.name => {
+                    writeln!(
+                        out,
+                        "    counter({}) into {}({});",
+                        col(counter.group_by),
+                        fmt_name(&counter.into_table),
+                        fmt_name(&counter.into_column)
+                    )?;
+                }
+                _ => {}
+            }
+        }
+        out.push_str("}\n");
+        Ok(())
+    }
+
+    fn write_reducer(&self, out: &mut String, reducer: &RawReducerDefV9) -> fmt::Result {
+        match reducer.lifecycle {
+            Some(Lifecycle::Init) => out.push_str("@init "),
+            Some(Lifecycle::OnConnect) => out.push_str("@client_connected "),
+            Some(Lifecycle::OnDisconnect) => out.push_str("@client_disconnected "),
+            _ => {}
+        }
+        let is_topic_authorizer = self
+            .def
+            .misc_exports
+            .iter()
+            .any(|export| matches!(export, RawMiscModuleExportV9::TopicAuthorizer(a) if a.reducer == reducer.name));
+        if is_topic_authorizer {
+            out.push_str("@authorize_topic ");
+        }
+        let mut arg_constraints = Vec::new();
+        let mut error_type = None;
+        let mut priority = None;
+        let mut aliases = Vec::new();
+        for export in &self.def.misc_exports {
+            match export {
+                RawMiscModuleExportV9::ReducerArgConstraint(c) if c.reducer == reducer.name => {
+                    arg_constraints.push((c.arg, &c.constraint));
+                }
+                RawMiscModuleExportV9::ReducerErrorType(e) if e.reducer == reducer.name => {
+                    error_type = Some(&e.error_type);
+                }
+                RawMiscModuleExportV9::ReducerPriority(p) if p.reducer == reducer.name => {
+                    priority = Some(p.priority);
+                }
+                RawMiscModuleExportV9::ReducerAlias(a) if a.reducer == reducer.name => {
+                    aliases.push(a);
+                }
+                _ => {}
+            }
+        }
+        write!(
+            out,
+            "reducer {}{}",
+            fmt_name(&reducer.name),
+            self.params(&reducer.params, &arg_constraints)
+        )?;
+        if let Some(error_type) = error_type {
+            write!(out, " fails {}", self.ty(error_type))?;
+        }
+        if let Some(priority) = priority {
+            let priority = match priority {
+                ReducerPriority::High => "high",
+                ReducerPriority::Normal => "normal",
+                ReducerPriority::Low => "low",
+            };
+            write!(out, " priority {priority}")?;
+        }
+        for alias in aliases {
+            write!(out, " alias {}", fmt_name(&alias.alias))?;
+            if let Some(deprecated) = &alias.deprecated {
+                write!(out, " deprecated {deprecated:?}")?;
+            }
+        }
+        out.push_str(";\n");
+        Ok(())
+    }
+
+    /// Returns the parameter list `params`, with `arg_constraints` on their parameters.
+    fn params<'b>(
+        &'b self,
+        params: &'b ProductType,
+        arg_constraints: &'b [(u16, &'b ArgConstraint)],
+    ) -> impl 'b + Display {
+        fmt_fn(move |f| {
+            f.write_str("(")?;
+            for (i, elem) in params.elements.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                for (_, constraint) in arg_constraints.iter().filter(|(arg, _)| usize::from(*arg) == i) {
+                    match constraint {
+                        ArgConstraint::MinLength(min) => write!(f, "@min_len({min}) ")?,
+                        ArgConstraint::MaxLength(max) => write!(f, "@max_len({max}) ")?,
+                        ArgConstraint::Min(min) => write!(f, "@min({min}) ")?,
+                        ArgConstraint::Max(max) => write!(f, "@max({max}) ")?,
+                        ArgConstraint::OneOf(allowed) => {
+                            let allowed = allowed.iter().map(|v| format!("{v:?}")).collect::<Vec<_>>();
+                            write!(f, "@one_of({}) ", allowed.join(", "))?
+                        }
+                    }
+                }
+                write_product_element(f, elem, &|r, f| self.write_ref(r, f))?;
+            }
+            f.write_str(")")
+        })
+    }
+}
+
+/// Returns the name `a::b::C` of a scoped type.
+fn scoped_name(name: &RawScopedTypeNameV9) -> String {
+    let mut path = name.scope.iter().map(|s| &**s).collect::<Vec<_>>();
+    path.push(&*name.name);
+    path.join("::")
+}
+
+/// Returns the names of the columns of the row type `r`, or `None` if it isn't a product.
+fn row_columns(typespace: &Typespace, r: AlgebraicTypeRef) -> Option<Vec<Option<Box<str>>>> {
+    let mut ty = typespace.get(r)?;
+    // Follow aliases, giving up on cycles.
+    for _ in 0..typespace.types.len() {
+        match ty {
+            AlgebraicType::Ref(r) => ty = typespace.get(*r)?,
+            _ => break,
+        }
+    }
+    let product = ty.as_product()?;
+    Some(product.elements.iter().map(|elem| elem.name.clone()).collect())
+}
+
+/// The named types of a module being parsed.
+#[derive(Default)]
+struct TypeNames {
+    refs: HashMap<String, AlgebraicTypeRef>,
+    /// The named types which have been used but not declared yet,
+    /// with the positions of the items which first used them.
+    undeclared: BTreeMap<AlgebraicTypeRef, (String, usize)>,
+}
+
+struct ModuleParser<'a> {
+    p: Parser<'a>,
+    def: RawModuleDefV9,
+    types: TypeNames,
+}
+
+impl ModuleParser<'_> {
+    fn parse(mut self) -> Result<RawModuleDefV9, ParseError> {
+        while !self.p.is_eof() {
+            self.parse_item()?;
+        }
+        if let Some((name, position)) = self.types.undeclared.values().min_by_key(|(_, position)| *position) {
+            return Err(self.p.error_at(*position, format!("type `{name}` is never declared")));
+        }
+        Ok(self.def)
+    }
+
+    /// Parses a type, allocating the named types used before being declared.
+    fn parse_type(&mut self) -> Result<AlgebraicType, ParseError> {
+        let position = self.p.position();
+        let Self { p, def, types } = self;
+        p.parse_type(&mut |name| {
+            let r = *types.refs.entry(name.to_owned()).or_insert_with(|| {
+                let r = def.typespace.add(AlgebraicType::never());
+                types.undeclared.insert(r, (name.to_owned(), position));
+                r
+            });
+            Some(AlgebraicType::Ref(r))
+        })
+    }
+
+    fn parse_item(&mut self) -> Result<(), ParseError> {
+        let attribute = match self.p.eat("@") {
+            true => Some((self.p.position(), self.p.ident()?)),
+            false => None,
+        };
+        let position = self.p.position();
+        let Token::Ident(item) = *self.p.peek() else {
+            return Err(self.p.unexpected("an item"));
+        };
+        self.p.next_token();
+        match (item, attribute.map(|(_, attr)| attr)) {
+            ("type", None) => self.parse_type_decl(false),
+            ("type", Some("custom_ordering")) => self.parse_type_decl(true),
+            ("table", None) => self.parse_table(),
+            ("reducer", None) => self.parse_reducer(None),
+            ("reducer", Some("init")) => self.parse_reducer(Some(Lifecycle::Init)),
+            ("reducer", Some("client_connected")) => self.parse_reducer(Some(Lifecycle::OnConnect)),
+            ("reducer", Some("client_disconnected")) => self.parse_reducer(Some(Lifecycle::OnDisconnect)),
+            ("reducer", Some("authorize_topic")) => {
+                self.parse_reducer(None)?;
+                let reducer = self.def.reducers.last().unwrap().name.clone();
+                self.def
+                    .misc_exports
+                    .push(RawMiscModuleExportV9::TopicAuthorizer(RawTopicAuthorizerDefV9 {
+                        reducer,
+                    }));
+                Ok(())
+            }
+            ("view", None) => self.parse_view(),
+            ("route", None) => self.parse_route(),
+            ("rls", None) => self.parse_rls(),
+            ("type" | "table" | "reducer" | "view" | "route" | "rls", Some(attr)) => {
+                let (position, _) = attribute.unwrap();
+                Err(self
+                    .p
+                    .error_at(position, format!("unknown attribute `@{attr}` on {item}")))
+            }
+            _ => Err(self.p.error_at(position, format!("expected an item, found `{item}`"))),
+        }
+    }
+
+    fn parse_type_decl(&mut self, custom_ordering: bool) -> Result<(), ParseError> {
+        let position = self.p.position();
+        let path = self.p.path()?;
+        self.p.expect("=")?;
+        let ty = self.parse_type()?;
+        self.p.expect(";")?;
+
+        let full_name = path.join("::");
+        let r = match self.types.refs.get(&full_name).copied() {
+            Some(r) => {
+                if self.types.undeclared.remove(&r).is_none() {
+                    return Err(self
+                        .p
+                        .error_at(position, format!("type `{full_name}` is declared more than once")));
+                }
+                self.def.typespace.types[r.idx()] = ty;
+                r
+            }
+            None => {
+                let r = self.def.typespace.add(ty);
+                self.types.refs.insert(full_name, r);
+                r
+            }
+        };
+        let (name, scope) = path.split_last().expect("paths are not empty");
+        self.def.types.push(RawTypeDefV9 {
+            name: RawScopedTypeNameV9 {
+                scope: scope.iter().map(|&s| s.into()).collect(),
+                name: (*name).into(),
+            },
+            ty: r,
+            custom_ordering,
+        });
+        Ok(())
+    }
+
+    fn parse_table(&mut self) -> Result<(), ParseError> {
+        let name = self.p.name()?;
+        self.p.expect(":")?;
+        let row_position = self.p.position();
+        let product_type_ref = match self.parse_type()? {
+            AlgebraicType::Ref(r) => r,
+            ty => self.def.typespace.add(ty),
+        };
+        let position = self.p.position();
+        let table_access = match self.p.ident()? {
+            "public" => TableAccess::Public,
+            "private" => TableAccess::Private,
+            other => {
+                let message = format!("expected `public` or `private`, found `{other}`");
+                return Err(self.p.error_at(position, message));
+            }
+        };
+        let table_type = match self.p.eat_keyword("system") {
+            true => TableType::System,
+            false => TableType::User,
+        };
+        let columns = row_columns(&self.def.typespace, product_type_ref).ok_or_else(|| {
+            self.p.error_at(
+                row_position,
+                "the row type of a table must be a product type declared before the table",
+            )
+        })?;
+
+        let mut table = RawTableDefV9 {
+            name,
+            product_type_ref,
+            primary_key: ColList::empty(),
+            indexes: Vec::new(),
+            constraints: Vec::new(),
+            sequences: Vec::new(),
+            schedule: None,
+            table_type,
+            table_access,
+        };
+        self.p.expect("{")?;
+        while !self.p.eat("}") {
+            let position = self.p.position();
+            match self.p.ident()? {
+                "primary_key" => table.primary_key = self.parse_columns(&columns)?,
+                "unique" => {
+                    let columns = self.parse_columns(&columns)?;
+                    table.constraints.push(RawConstraintDefV9 {
+                        name: self.parse_explicit_name()?,
+                        data: RawConstraintDataV9::Unique(RawUniqueConstraintDataV9 { columns }),
+                    });
+                }
+                "index" => {
+                    let accessor_name = match self.p.peek2() {
+                        Token::Punct("(") => None,
+                        _ => Some(self.p.name()?),
+                    };
+                    let position = self.p.position();
+                    let algorithm = self.p.ident()?;
+                    let columns = self.parse_columns(&columns)?;
+                    let algorithm = match algorithm {
+                        "btree" => RawIndexAlgorithm::BTree { columns },
+                        "hash" => RawIndexAlgorithm::Hash { columns },
+                        other => return Err(self.p.error_at(position, format!("unknown index algorithm `{other}`"))),
+                    };
+                    table.indexes.push(RawIndexDefV9 {
+                        name: self.parse_explicit_name()?,
+                        accessor_name,
+                        algorithm,
+                    });
+                }
+                "sequence" => {
+                    self.p.expect("(")?;
+                    let column = self.parse_column(&columns)?;
+                    self.p.expect(")")?;
+                    let mut sequence = RawSequenceDefV9 {
+                        name: None,
+                        column,
+                        start: None,
+                        min_value: None,
+                        max_value: None,
+                        increment: 1,
+                    };
+                    loop {
+                        if self.p.eat_keyword("start") {
+                            sequence.start = Some(self.p.int()?);
+                        } else if self.p.eat_keyword("min") {
+                            sequence.min_value = Some(self.p.int()?);
+                        } else if self.p.eat_keyword("max") {
+                            sequence.max_value = Some(self.p.int()?);
+                        } else if self.p.eat_keyword("increment") {
+                            sequence.increment = self.p.int()?;
+                        } else {
+                            break;
+                        }
+                    }
+                    sequence.name = self.parse_explicit_name()?;
+                    table.sequences.push(sequence);
+                }
+                "schedule" => {
+                    if table.schedule.is_some() {
+                        return Err(self.p.error_at(position, "a table can only have one schedule"));
+                    }
+                    let reducer_name = self.p.name()?;
+                    self.p.expect("(")?;
+                    let scheduled_at_column = self.parse_column(&columns)?;
+                    self.p.expect(")")?;
+                    table.schedule = Some(RawScheduleDefV9 {
+                        name: self.parse_explicit_name()?,
+                        reducer_name,
+                        scheduled_at_column,
+                    });
+                }
+                "durability" => {
+                    let position = self.p.position();
+                    let durability = match self.p.ident()? {
+                        "durable" => TableDurability::Durable,
+                        "relaxed" => TableDurability::Relaxed,
+                        "ephemeral" => TableDurability::Ephemeral,
+                        other => {
+                            let message = format!("expected `durable`, `relaxed`, or `ephemeral`, found `{other}`");
+                            return Err(self.p.error_at(position, message));
+                        }
+                    };
+                    let durability = RawTableDurabilityDefV9 {
+                        table: table.name.clone(),
+                        durability,
+                    };
+                    self.def
+                        .misc_exports
+                        .push(RawMiscModuleExportV9::TableDurability(durability));
+                }
+                "soft_delete" => {
+                    self.p.expect_keyword("retention")?;
+                    let position = self.p.position();
+                    let retention = self.p.int()?;
+                    let retention =
+                        u64::try_from(retention).map_err(|_| self.p.error_at(position, "retention out of range"))?;
+                    let soft_delete = RawSoftDeleteDefV9 {
+                        table: table.name.clone(),
+                        retention,
+                    };
+                    self.def
+                        .misc_exports
+                        .push(RawMiscModuleExportV9::SoftDelete(soft_delete));
+                }
+                "coalesce" => {
+                    self.p.expect_keyword("window_ms")?;
+                    let position = self.p.position();
+                    let window_ms = self.p.int()?;
+                    let window_ms =
+                        u64::try_from(window_ms).map_err(|_| self.p.error_at(position, "window out of range"))?;
+                    let coalesce = RawCoalesceDefV9 {
+                        table: table.name.clone(),
+                        window_ms,
+                    };
+                    self.def.misc_exports.push(RawMiscModuleExportV9::Coalesce(coalesce));
+                }
+                "bound_to_connection" => {
+                    let column = if self.p.eat("(") {
+                        let column = self.parse_column(&columns)?;
+                        self.p.expect(")")?;
+                        Some(column)
+                    } else {
+                        None
+                    };
+                    let bound = RawBoundToConnectionDefV9 {
+                        table: table.name.clone(),
+                        column,
+                    };
+                    self.def
+                        .misc_exports
+                        .push(RawMiscModuleExportV9::BoundToConnection(bound));
+                }
+                "catch_up" => {
+                    let position = self.p.position();
+                    let policy = match self.p.ident()? {
+                        "skip_missed" => CatchUpPolicy::SkipMissed,
+                        "fire_once" => CatchUpPolicy::FireOnce,
+                        "fire_all" => CatchUpPolicy::FireAll,
+                        other => {
+                            let message = format!("expected `skip_missed`, `fire_once` or `fire_all`, found `{other}`");
+                            return Err(self.p.error_at(position, message));
+                        }
+                    };
+                    let catch_up = RawScheduleCatchUpDefV9 {
+                        table: table.name.clone(),
+                        policy,
+                    };
+                    self.def
+                        .misc_exports
+                        .push(RawMiscModuleExportV9::ScheduleCatchUp(catch_up));
+                }
+                "generated" => {
+                    self.p.expect("(")?;
+                    let column = self.parse_column(&columns)?;
+                    self.p.expect(")")?;
+                    self.p.expect("=")?;
+                    let generated = RawGeneratedColumnDefV9 {
+                        table: table.name.clone(),
+                        column,
+                        expr: self.p.string()?.into(),
+                    };
+                    self.def
+                        .misc_exports
+                        .push(RawMiscModuleExportV9::GeneratedColumn(generated));
+                }
+                "counter" => {
+                    self.p.expect("(")?;
+                    let group_by = self.parse_column(&columns)?;
+                    self.p.expect(")")?;
+                    self.p.expect_keyword("into")?;
+                    let into_table = self.p.name()?;
+                    self.p.expect("(")?;
+                    let into_column = self.p.name()?;
+                    self.p.expect(")")?;
+                    let counter = RawCounterDefV9 {
+                        table: table.name.clone(),
+                        group_by,
+                        into_table,
+                        into_column,
+                    };
+                    self.def.misc_exports.push(RawMiscModuleExportV9::Counter(counter));
+                }
+                other => return Err(self.p.error_at(position, format!("unknown table property `{other}`"))),
+            }
+            self.p.expect(";")?;
+        }
+        self.def.tables.push(table);
+        Ok(())
+    }
+
+    /// Parses a parenthesized, non-empty list of columns.
+    fn parse_columns(&mut self, columns: &[Option<Box<str>>]) -> Result<ColList, ParseError> {
+        self.p.expect("(")?;
+        let mut list = ColList::new(self.parse_column(columns)?);
+        while self.p.eat(",") {
+            list.push(self.parse_column(columns)?);
+        }
+        self.p.expect(")")?;
+        Ok(list)
+    }
+
+    /// Parses a column, by its name or position.
+    fn parse_column(&mut self, columns: &[Option<Box<str>>]) -> Result<ColId, ParseError> {
+        let position = self.p.position();
+        let col = if let Token::Int(_) = self.p.peek() {
+            let col = self.p.int()?;
+            u16::try_from(col).ok().filter(|&col| usize::from(col) < columns.len())
+        } else {
+            let name = self.p.name()?;
+            columns
+                .iter()
+                .position(|col| col.as_deref() == Some(&*name))
+                .map(|col| col as u16)
+        };
+        col.map(ColId)
+            .ok_or_else(|| self.p.error_at(position, "no such column in the row type of the table"))
+    }
+
+    /// Parses an optional `as "name"`.
+    fn parse_explicit_name(&mut self) -> Result<Option<Box<str>>, ParseError> {
+        match self.p.eat_keyword("as") {
+            true => Ok(Some(self.p.string()?.into())),
+            false => Ok(None),
+        }
+    }
+
+    fn parse_reducer(&mut self, lifecycle: Option<Lifecycle>) -> Result<(), ParseError> {
+        let name = self.p.name()?;
+        let mut arg_constraints = Vec::new();
+        let params = self.parse_params(Some(&mut arg_constraints))?;
+        if self.p.eat_keyword("fails") {
+            let error_type = RawReducerErrorTypeV9 {
+                reducer: name.clone(),
+                error_type: self.parse_type()?,
+            };
+            self.def
+                .misc_exports
+                .push(RawMiscModuleExportV9::ReducerErrorType(error_type));
+        }
+        if self.p.eat_keyword("priority") {
+            let position = self.p.position();
+            let priority = match self.p.ident()? {
+                "high" => ReducerPriority::High,
+                "normal" => ReducerPriority::Normal,
+                "low" => ReducerPriority::Low,
+                other => {
+                    let message = format!("expected `high`, `normal` or `low`, found `{other}`");
+                    return Err(self.p.error_at(position, message));
+                }
+            };
+            let priority = RawReducerPriorityDefV9 {
+                reducer: name.clone(),
+                priority,
+            };
+            self.def
+                .misc_exports
+                .push(RawMiscModuleExportV9::ReducerPriority(priority));
+        }
+        while self.p.eat_keyword("alias") {
+            let alias = self.p.name()?;
+            let deprecated = match self.p.eat_keyword("deprecated") {
+                true => Some(self.p.string()?.into()),
+                false => None,
+            };
+            let alias = RawReducerAliasDefV9 {
+                reducer: name.clone(),
+                alias,
+                deprecated,
+            };
+            self.def.misc_exports.push(RawMiscModuleExportV9::ReducerAlias(alias));
+        }
+        self.p.expect(";")?;
+        self.def
+            .misc_exports
+            .extend(arg_constraints.into_iter().map(|(arg, constraint)| {
+                RawMiscModuleExportV9::ReducerArgConstraint(RawReducerArgConstraintDefV9 {
+                    reducer: name.clone(),
+                    arg,
+                    constraint,
+                })
+            }));
+        self.def.reducers.push(RawReducerDefV9 {
+            name,
+            params,
+            lifecycle,
+        });
+        Ok(())
+    }
+
+    /// Parses a parameter list, collecting the constraints on the parameters into `arg_constraints`.
+    ///
+    /// Constraints are an error when `arg_constraints` is `None`.
+    fn parse_params(
+        &mut self,
+        mut arg_constraints: Option<&mut Vec<(u16, ArgConstraint)>>,
+    ) -> Result<ProductType, ParseError> {
+        self.p.expect("(")?;
+        let mut params = Vec::new();
+        while !self.p.eat(")") {
+            let arg = u16::try_from(params.len()).map_err(|_| self.p.error("too many parameters"))?;
+            while *self.p.peek() == Token::Punct("@") {
+                let position = self.p.position();
+                self.p.next_token();
+                let Some(arg_constraints) = arg_constraints.as_deref_mut() else {
+                    return Err(self
+                        .p
+                        .error_at(position, "only reducer parameters can have constraints"));
+                };
+                arg_constraints.push((arg, self.parse_arg_constraint()?));
+            }
+            let name = match (self.p.peek(), self.p.peek2()) {
+                (Token::Ident(_) | Token::Str(_), Token::Punct(":")) => {
+                    let name = self.p.name()?;
+                    self.p.expect(":")?;
+                    Some(name)
+                }
+                _ => None,
+            };
+            params.push(ProductTypeElement {
+                name,
+                algebraic_type: self.parse_type()?,
+            });
+            if !self.p.eat(",") {
+                self.p.expect(")")?;
+                break;
+            }
+        }
+        Ok(params.into_boxed_slice().into())
+    }
+
+    /// Parses an argument constraint, after its `@`.
+    fn parse_arg_constraint(&mut self) -> Result<ArgConstraint, ParseError> {
+        let position = self.p.position();
+        let kind = self.p.ident()?;
+        self.p.expect("(")?;
+        let constraint = match kind {
+            "min_len" => ArgConstraint::MinLength(self.parse_length()?),
+            "max_len" => ArgConstraint::MaxLength(self.parse_length()?),
+            "min" => ArgConstraint::Min(self.p.int()?),
+            "max" => ArgConstraint::Max(self.p.int()?),
+            "one_of" => {
+                let mut allowed = Vec::new();
+                while *self.p.peek() != Token::Punct(")") {
+                    allowed.push(self.p.string()?.into());
+                    if !self.p.eat(",") {
+                        break;
+                    }
+                }
+                ArgConstraint::OneOf(allowed)
+            }
+            other => return Err(self.p.error_at(position, format!("unknown constraint `@{other}`"))),
+        };
+        self.p.expect(")")?;
+        Ok(constraint)
+    }
+
+    fn parse_length(&mut self) -> Result<u32, ParseError> {
+        let position = self.p.position();
+        let len = self.p.int()?;
+        u32::try_from(len).map_err(|_| self.p.error_at(position, "length out of range"))
+    }
+
+    fn parse_view(&mut self) -> Result<(), ParseError> {
+        let name = self.p.name()?;
+        let params = self.parse_params(None)?;
+        self.p.expect("->")?;
+        let return_type = self.parse_type()?;
+        self.p.expect(";")?;
+        self.def.misc_exports.push(RawMiscModuleExportV9::View(RawViewDefV9 {
+            name,
+            params,
+            return_type,
+        }));
+        Ok(())
+    }
+
+    fn parse_route(&mut self) -> Result<(), ParseError> {
+        let position = self.p.position();
+        let method = match self.p.ident()? {
+            "get" => HttpMethod::Get,
+            other => return Err(self.p.error_at(position, format!("unknown HTTP method `{other}`"))),
+        };
+        let path = self.p.string()?.into();
+        self.p.expect("=>")?;
+        let view = self.p.name()?;
+        self.p.expect(";")?;
+        self.def
+            .misc_exports
+            .push(RawMiscModuleExportV9::HttpRoute(RawHttpRouteDefV9 {
+                method,
+                path,
+                view,
+            }));
+        Ok(())
+    }
+
+    fn parse_rls(&mut self) -> Result<(), ParseError> {
+        let sql = self.p.string()?.into();
+        self.p.expect(";")?;
+        self.def.row_level_security.push(RawRowLevelSecurityDefV9 { sql });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spacetimedb_lib::ScheduleAt;
+
+    fn module() -> ModuleDef {
+        let mut builder = RawModuleDefV9Builder::new();
+        let schedule_at_type = builder.add_type::<ScheduleAt>();
+        let shape = builder.add_algebraic_type(
+            ["game".into()],
+            "Shape",
+            AlgebraicType::sum([("circle", AlgebraicType::F32), ("empty", AlgebraicType::unit())]),
+            true,
+        );
+        let player = builder
+            .build_table_with_new_type(
+                "player",
+                ProductType::from([
+                    ("id", AlgebraicType::U64),
+                    ("name", AlgebraicType::String),
+                    ("shape", AlgebraicType::Ref(shape)),
+                    ("nick", AlgebraicType::option(AlgebraicType::String)),
+                ]),
+                true,
+            )
+            .with_access(TableAccess::Public)
+            .with_auto_inc_primary_key(0)
+            .with_unique_constraint(1)
+            .with_index(RawIndexAlgorithm::BTree { columns: 1.into() }, "by_name")
+            .with_durability(TableDurability::Relaxed)
+            .with_soft_delete(1000)
+            .with_coalesce(50)
+            .finish();
+        let tick = builder
+            .build_table_with_new_type(
+                "tick",
+                ProductType::from([("scheduled_id", AlgebraicType::U64), ("scheduled_at", schedule_at_type)]),
+                true,
+            )
+            .with_auto_inc_primary_key(0)
+            .with_schedule("run_tick", 1)
+            .with_catch_up(CatchUpPolicy::SkipMissed)
+            .finish();
+        builder
+            .build_table_with_new_type(
+                "presence",
+                ProductType::from([
+                    ("player_id", AlgebraicType::U64),
+                    ("connection", AlgebraicType::address()),
+                ]),
+                true,
+            )
+            .with_bound_to_connection(None)
+            .finish();
+
+        builder.add_reducer("init", ProductType::unit(), Some(Lifecycle::Init));
+        builder.add_reducer(
+            "run_tick",
+            ProductType::from([("tick", AlgebraicType::Ref(tick))]),
+            None,
+        );
+        builder.add_reducer(
+            "add_player",
+            ProductType::from([("name", AlgebraicType::String), ("level", AlgebraicType::U8)]),
+            None,
+        );
+        builder.add_reducer_arg_constraint("add_player", 0, ArgConstraint::MaxLength(32));
+        builder.add_reducer_arg_constraint("add_player", 1, ArgConstraint::Min(1));
+        builder.add_reducer_error_type("add_player", AlgebraicType::String);
+        builder.add_reducer_priority("run_tick", ReducerPriority::Low);
+        builder.add_reducer_alias("add_player", "new_player", Some("use add_player".into()));
+        builder.add_reducer_alias("add_player", "join", None);
+        builder.add_reducer(
+            "can_subscribe",
+            ProductType::from([("topic", AlgebraicType::String)]),
+            None,
+        );
+        builder.set_topic_authorizer("can_subscribe");
+        builder.add_view(
+            "top_players",
+            ProductType::from([("limit", AlgebraicType::U32)]),
+            AlgebraicType::array(AlgebraicType::Ref(player)),
+        );
+        builder.add_http_route(HttpMethod::Get, "/top", "top_players");
+        builder.add_row_level_security("SELECT * FROM player WHERE name = 'me'");
+        builder.finish().try_into().unwrap()
+    }
+
+    #[test]
+    fn print_parse_roundtrip() {
+        let printed = print_module(&module());
+        let parsed = parse_module(&printed).unwrap();
+        assert_eq!(print_raw_module(&parsed), printed);
+
+        let def = ModuleDef::try_from(parsed).unwrap();
+        assert_eq!(print_module(&def), printed);
+    }
+
+    #[test]
+    fn parse() {
+        let source = r#"
+            // A player of the game.
+            type Player = (id: u64, name: string, name_key: string, shape: Shape);
+            type Shape = (circle: f32 | empty);
+
+            table player: Player public {
+                primary_key(id);
+                unique(0);
+                index by_name btree(name);
+                sequence(id) start 1;
+                generated(name_key) = "lower(name)";
+            }
+
+            reducer add_player(@max_len(32) @one_of("ada", "bob") name: string) fails string;
+        "#;
+        let def = parse_module(source).unwrap();
+
+        assert_eq!(def.typespace.types.len(), 2);
+        let player = def.tables[0].product_type_ref;
+        let shape = def.types[1].ty;
+        assert_eq!(
+            def.typespace[player],
+            AlgebraicType::product([
+                ("id", AlgebraicType::U64),
+                ("name", AlgebraicType::String),
+                ("name_key", AlgebraicType::String),
+                ("shape", AlgebraicType::Ref(shape)),
+            ])
+        );
+        assert_eq!(
+            def.typespace[shape],
+            AlgebraicType::sum([("circle", AlgebraicType::F32), ("empty", AlgebraicType::unit())])
+        );
+
+        let table = &def.tables[0];
+        assert_eq!(table.primary_key, ColList::new(ColId(0)));
+        assert_eq!(table.sequences[0].start, Some(1));
+        assert!(matches!(
+            &def.misc_exports[0],
+            RawMiscModuleExportV9::GeneratedColumn(generated) if generated.column == ColId(2) && &*generated.expr == "lower(name)"
+        ));
+        let constraints = def
+            .misc_exports
+            .iter()
+            .filter_map(|export| match export {
+                RawMiscModuleExportV9::ReducerArgConstraint(c) => Some((c.arg, c.constraint.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            constraints,
+            [
+                (0, ArgConstraint::MaxLength(32)),
+                (0, ArgConstraint::OneOf(vec!["ada".into(), "bob".into()])),
+            ]
+        );
+        ModuleDef::try_from(def).unwrap();
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = parse_module("type A = (b: B);\ntype C = u32;").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 10));
+        assert_eq!(err.message, "type `B` is never declared");
+
+        let err = parse_module("table t: T public {}\ntype T = (a: u32);").unwrap_err();
+        assert_eq!(
+            err.message,
+            "the row type of a table must be a product type declared before the table"
+        );
+
+        let err = parse_module("type T = (a: u32);\ntable t: T public { unique(b); }").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 28));
+        assert_eq!(err.message, "no such column in the row type of the table");
+
+        let err = parse_module("type T = u32;\ntype T = u8;").unwrap_err();
+        assert_eq!(err.message, "type `T` is declared more than once");
+    }
+}
@@ -2,9 +2,12 @@
 //!
 //! Handles validation and normalization of raw schema definitions from the `spacetimedb_lib` crate.
 
+pub mod arg_constraint;
 pub mod auto_migrate;
 pub mod def;
 pub mod error;
+pub mod generated;
 pub mod identifier;
+pub mod idl;
 pub mod schema;
 pub mod type_for_generate;
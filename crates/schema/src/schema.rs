@@ -7,7 +7,7 @@
 // This doesn't affect the ABI so can wait until 1.0.
 
 use itertools::Itertools;
-use spacetimedb_lib::db::auth::{StAccess, StTableType};
+use spacetimedb_lib::db::auth::{StAccess, StDurability, StTableType};
 use spacetimedb_lib::db::error::{DefType, SchemaError};
 use spacetimedb_lib::db::raw_def::v9::RawSql;
 use spacetimedb_lib::db::raw_def::{generate_cols_name, RawConstraintDefV8};
@@ -20,9 +20,10 @@ use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use crate::def::{
-    ColumnDef, ConstraintData, ConstraintDef, IndexAlgorithm, IndexDef, ModuleDef, ModuleDefLookup, ScheduleDef,
-    SequenceDef, TableDef, UniqueConstraintData,
+    ColumnDef, ConstraintData, ConstraintDef, CounterDef, GeneratedColumnDef, IndexAlgorithm, IndexDef, ModuleDef,
+    ModuleDefLookup, ScheduleDef, SequenceDef, SoftDeleteDef, TableDef, UniqueConstraintData,
 };
+use crate::generated::{GeneratedExpr, GeneratedExprError};
 use crate::identifier::Identifier;
 
 /// Helper trait documenting allowing schema entities to be built from a validated `ModuleDef`.
@@ -93,6 +94,18 @@ pub struct TableSchema {
     /// The schedule for the table, if present.
     pub schedule: Option<ScheduleSchema>,
 
+    /// The generated columns of the table, sorted by column.
+    pub generated_columns: Vec<GeneratedColumnSchema>,
+
+    /// The counters of the rows of this table kept in the columns of other tables.
+    pub counters: Vec<CounterSchema>,
+
+    /// The history kept of the table, if it can be read as of recent transactions.
+    pub soft_delete: Option<SoftDeleteSchema>,
+
+    /// Whether updates to the table are written to the commitlog.
+    pub durability: StDurability,
+
     /// Cache for `row_type_for_table` in the data store.
     row_type: ProductType,
 }
@@ -133,6 +146,10 @@ impl TableSchema {
             table_access,
             row_type,
             schedule,
+            generated_columns: Vec::new(),
+            counters: Vec::new(),
+            soft_delete: None,
+            durability: StDurability::Durable,
             primary_key,
         }
     }
@@ -178,6 +195,11 @@ impl TableSchema {
         if let Some(s) = self.schedule.as_mut() {
             s.table_id = id;
         }
+        self.generated_columns.iter_mut().for_each(|g| g.table_id = id);
+        self.counters.iter_mut().for_each(|c| c.table_id = id);
+        if let Some(s) = self.soft_delete.as_mut() {
+            s.table_id = id;
+        }
     }
 
     /// Convert a table schema into a list of columns.
@@ -566,6 +588,12 @@ impl Schema for TableSchema {
             constraints,
             sequences,
             schedule,
+            generated_columns,
+            counters,
+            soft_delete,
+            coalesce: _,
+            bound_to_connection: _,
+            durability,
             table_type,
             table_access,
         } = def;
@@ -597,7 +625,7 @@ impl Schema for TableSchema {
             .as_ref()
             .map(|schedule| ScheduleSchema::from_module_def(module_def, schedule, table_id, ScheduleId::SENTINEL));
 
-        TableSchema::new(
+        let mut schema = TableSchema::new(
             table_id,
             (*name).clone().into(),
             columns,
@@ -608,7 +636,18 @@ impl Schema for TableSchema {
             (*table_access).into(),
             schedule,
             *primary_key,
-        )
+        );
+        schema.generated_columns = generated_columns
+            .iter()
+            .map(|def| GeneratedColumnSchema::from_def(table_id, def))
+            .collect();
+        schema.counters = counters
+            .iter()
+            .map(|def| CounterSchema::from_def(table_id, def))
+            .collect();
+        schema.soft_delete = soft_delete.map(|def| SoftDeleteSchema::from_def(table_id, &def));
+        schema.durability = (*durability).into();
+        schema
     }
 
     fn check_compatible(&self, module_def: &ModuleDef, def: &Self::Def) -> Result<(), anyhow::Error> {
@@ -618,6 +657,8 @@ impl Schema for TableSchema {
         ensure_eq!(self.table_access, def_table_access, "Table access mismatch");
         let def_table_type: StTableType = (def.table_type).into();
         ensure_eq!(self.table_type, def_table_type, "Table type mismatch");
+        let def_durability: StDurability = (def.durability).into();
+        ensure_eq!(self.durability, def_durability, "Table durability mismatch");
 
         for col in &self.columns {
             let col_def = def
@@ -671,6 +712,36 @@ impl Schema for TableSchema {
             def.schedule.is_some(),
             "Schedule presence mismatch"
         );
+
+        let generated_cols = self
+            .generated_columns
+            .iter()
+            .map(|g| (g.col_pos, &*g.expr_source))
+            .collect::<Vec<_>>();
+        let def_generated_cols = def
+            .generated_columns
+            .iter()
+            .map(|g| (g.column, &*g.expr_source))
+            .collect::<Vec<_>>();
+        ensure_eq!(generated_cols, def_generated_cols, "Generated columns mismatch");
+
+        let counters = self
+            .counters
+            .iter()
+            .map(|c| (c.group_by, &*c.into_table, c.into_column))
+            .collect::<Vec<_>>();
+        let def_counters = def
+            .counters
+            .iter()
+            .map(|c| (c.group_by, &c.into_table[..], c.into_column))
+            .collect::<Vec<_>>();
+        ensure_eq!(counters, def_counters, "Counters mismatch");
+
+        ensure_eq!(
+            self.soft_delete.map(|s| s.retention),
+            def.soft_delete.map(|s| s.retention),
+            "Soft delete mismatch"
+        );
         Ok(())
     }
 }
@@ -920,6 +991,111 @@ impl Schema for ScheduleSchema {
     }
 }
 
+/// A column whose values are computed by the datastore from the other columns of its table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedColumnSchema {
+    /// The identifier of the table.
+    pub table_id: TableId,
+
+    /// The position of the generated column.
+    pub col_pos: ColId,
+
+    /// The expression computing the column's value, as written in the module.
+    pub expr_source: Box<str>,
+
+    /// The parsed expression.
+    pub expr: GeneratedExpr,
+}
+
+impl GeneratedColumnSchema {
+    /// Returns the schema of the generated column `def` in the table `table_id`.
+    pub fn from_def(table_id: TableId, def: &GeneratedColumnDef) -> Self {
+        GeneratedColumnSchema {
+            table_id,
+            col_pos: def.column,
+            expr_source: def.expr_source.clone(),
+            expr: def.expr.clone(),
+        }
+    }
+
+    /// Parses `expr_source` as the expression of the column `col_pos` among `columns`.
+    ///
+    /// For reading generated columns back from the system tables,
+    /// so the expression is assumed to have been validated
+    /// against the other generated columns of the table already.
+    pub fn parse(
+        table_id: TableId,
+        col_pos: ColId,
+        expr_source: Box<str>,
+        columns: &[ColumnSchema],
+    ) -> Result<Self, GeneratedExprError> {
+        let ty = &columns
+            .get(col_pos.idx())
+            .ok_or_else(|| GeneratedExprError::UnknownColumn(col_pos.to_string().into()))?
+            .col_type;
+        let expr = GeneratedExpr::parse(&expr_source, ty, |name| {
+            let col = columns.iter().find(|col| &*col.col_name == name)?;
+            Some((col.col_pos, &col.col_type, false))
+        })?;
+        Ok(GeneratedColumnSchema {
+            table_id,
+            col_pos,
+            expr_source,
+            expr,
+        })
+    }
+}
+
+/// A count of the rows of a table, grouped by one of its columns,
+/// which the datastore keeps in a column of the table whose primary key is referred to by that column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterSchema {
+    /// The identifier of the counted table.
+    pub table_id: TableId,
+
+    /// The column of the counted table holding the primary key of the row to count each row under.
+    pub group_by: ColId,
+
+    /// The name of the table holding the counts.
+    /// Refers to the table by name, as it may be created after the counted table.
+    pub into_table: Box<str>,
+
+    /// The column of `into_table` holding the counts.
+    pub into_column: ColId,
+}
+
+impl CounterSchema {
+    /// Returns the schema of the counter `def` of the table `table_id`.
+    pub fn from_def(table_id: TableId, def: &CounterDef) -> Self {
+        CounterSchema {
+            table_id,
+            group_by: def.group_by,
+            into_table: def.into_table.clone().into(),
+            into_column: def.into_column,
+        }
+    }
+}
+
+/// The history which the datastore keeps of a table, so that it can be read as of a recent transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoftDeleteSchema {
+    /// The identifier of the table.
+    pub table_id: TableId,
+
+    /// How many of the most recent transactions the table can be read as of.
+    pub retention: u64,
+}
+
+impl SoftDeleteSchema {
+    /// Returns the schema of the soft deletes `def` of the table `table_id`.
+    pub fn from_def(table_id: TableId, def: &SoftDeleteDef) -> Self {
+        SoftDeleteSchema {
+            table_id,
+            retention: def.retention,
+        }
+    }
+}
+
 /// A struct representing the schema of a database index.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct IndexSchema {
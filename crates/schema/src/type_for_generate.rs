@@ -40,6 +40,9 @@ pub enum ClientCodegenError {
     #[error("internal codegen error: all reducer parameters require names")]
     NamelessReducerParam,
 
+    #[error("internal codegen error: all view parameters require names")]
+    NamelessViewParam,
+
     #[error("internal codegen error: type {ty} is not valid for generating a definition")]
     NotValidForDefinition { ty: PrettyAlgebraicType },
 
@@ -92,9 +95,20 @@ type Result<T> = std::result::Result<T, ErrorStream<ClientCodegenError>>;
 /// ]
 /// ```
 /// are forbidden. (Because most languages do not support anonymous recursive types.)
+///
+/// The permitted cycles are recorded, see [`TypespaceForGenerate::cycles`].
+/// Languages which store values inline must add indirection to break them,
+/// see [`TypespaceForGenerate::needs_indirection`].
 #[derive(Debug, Clone)]
 pub struct TypespaceForGenerate {
     defs: HashMap<AlgebraicTypeRef, AlgebraicTypeDef>,
+
+    /// The cycles in the typespace, that is, the sets of mutually recursive definitions.
+    /// Each cycle is sorted, and the cycles are sorted by their first definition.
+    cycles: Vec<Box<[AlgebraicTypeRef]>>,
+
+    /// Maps each recursive definition to the index of its cycle in `cycles`.
+    cycle_of: HashMap<AlgebraicTypeRef, usize>,
 }
 
 impl TypespaceForGenerate {
@@ -110,6 +124,8 @@ impl TypespaceForGenerate {
             typespace,
             result: TypespaceForGenerate {
                 defs: HashMap::default(),
+                cycles: Vec::new(),
+                cycle_of: HashMap::default(),
             },
             is_def: is_def.into_iter().collect(),
             uses: HashSet::default(),
@@ -123,6 +139,37 @@ impl TypespaceForGenerate {
         &self.defs
     }
 
+    /// Get the cycles in the typespace, that is, the sets of mutually recursive definitions.
+    ///
+    /// A definition which refers only to itself forms a cycle on its own.
+    /// Each cycle is sorted, and the cycles are sorted by their first definition.
+    pub fn cycles(&self) -> &[Box<[AlgebraicTypeRef]>] {
+        &self.cycles
+    }
+
+    /// Check if the definitions `a` and `b` are part of the same cycle.
+    /// A definition is only part of the same cycle as itself if it is recursive.
+    pub fn in_same_cycle(&self, a: AlgebraicTypeRef, b: AlgebraicTypeRef) -> bool {
+        match (self.cycle_of.get(&a), self.cycle_of.get(&b)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+
+    /// Check if `use_`, the type of an element or variant of the definition `def`,
+    /// must be stored behind an indirection, such as a `Box` in Rust,
+    /// in languages which store values inline.
+    ///
+    /// This is the case if `use_` refers back to `def` through the cycle containing it,
+    /// other than through an array, which is already stored out of line.
+    pub fn needs_indirection(&self, def: AlgebraicTypeRef, use_: &AlgebraicTypeUse) -> bool {
+        match use_ {
+            AlgebraicTypeUse::Ref(ref_) => self.in_same_cycle(def, *ref_),
+            AlgebraicTypeUse::Option(elem_ty) => self.needs_indirection(def, elem_ty),
+            _ => false,
+        }
+    }
+
     /// Get a definition in the typespace.
     pub fn get(&self, ref_: AlgebraicTypeRef) -> Option<&AlgebraicTypeDef> {
         self.defs.get(&ref_)
@@ -608,32 +655,32 @@ impl TypespaceForGenerateBuilder<'_> {
     /// This function is called after all definitions have been processed.
     fn mark_allowed_cycles(&mut self) {
         let strongly_connected_components: Vec<Vec<AlgebraicTypeRef>> = tarjan_scc(&*self);
-        for component in strongly_connected_components {
-            if component.len() == 1 {
-                // petgraph's implementation returns a vector for all nodes, not distinguishing between
-                // self referential and non-self-referential nodes. ignore this for now.
-                continue;
+        let mut cycles = Vec::new();
+        for mut component in strongly_connected_components {
+            // petgraph's implementation returns a vector for all nodes, not distinguishing between
+            // self referential and non-self-referential nodes, so check for those separately.
+            if let [ref_] = component[..] {
+                let def = &self.result.defs[&ref_];
+                if !def.extract_refs().contains(&ref_) {
+                    continue;
+                }
             }
-            for ref_ in component {
+            for ref_ in &component {
                 self.result
                     .defs
-                    .get_mut(&ref_)
+                    .get_mut(ref_)
                     .expect("all defs should be processed by now")
                     .mark_recursive();
             }
+            component.sort();
+            cycles.push(component.into_boxed_slice());
         }
 
-        // Now, fix up directly self-referential nodes.
-        for (ref_, def_) in &mut self.result.defs {
-            let ref_ = *ref_;
-            if def_.is_recursive() {
-                continue;
-            }
-            let refs = def_.extract_refs();
-            if refs.contains(&ref_) {
-                def_.mark_recursive();
-            }
+        cycles.sort();
+        for (i, cycle) in cycles.iter().enumerate() {
+            self.result.cycle_of.extend(cycle.iter().map(|ref_| (*ref_, i)));
         }
+        self.result.cycles = cycles;
     }
 }
 
@@ -893,5 +940,51 @@ mod tests {
                 "recursion detected incorrectly"
             );
         }
+
+        assert_eq!(result.cycles().len(), 1);
+        assert_eq!(&*result.cycles()[0], [0, 1, 2, 3].map(AlgebraicTypeRef));
+        assert!(result.in_same_cycle(AlgebraicTypeRef(0), AlgebraicTypeRef(3)));
+        assert!(!result.in_same_cycle(AlgebraicTypeRef(4), AlgebraicTypeRef(2)));
+        assert!(!result.in_same_cycle(AlgebraicTypeRef(5), AlgebraicTypeRef(5)));
+    }
+
+    #[test]
+    fn test_needs_indirection() {
+        // A linked list, and a tree whose children are stored in an array.
+        let typespace = Typespace::new(vec![
+            AlgebraicType::product([
+                ("head", AlgebraicType::U32),
+                ("tail", AlgebraicType::option(AlgebraicTypeRef(0).into())),
+            ]),
+            AlgebraicType::product([
+                ("value", AlgebraicType::U32),
+                ("children", AlgebraicType::array(AlgebraicTypeRef(1).into())),
+                ("list", AlgebraicTypeRef(0).into()),
+            ]),
+        ]);
+        let mut for_generate = TypespaceForGenerate::builder(&typespace, [AlgebraicTypeRef(0), AlgebraicTypeRef(1)]);
+        for i in 0..2 {
+            for_generate
+                .add_definition(AlgebraicTypeRef(i))
+                .expect("should be allowed");
+        }
+        let result = for_generate.finish();
+
+        let cycles: &[&[_]] = &[&[AlgebraicTypeRef(0)], &[AlgebraicTypeRef(1)]];
+        assert_eq!(result.cycles().iter().map(|c| &**c).collect::<Vec<_>>(), cycles);
+
+        let list = result[AlgebraicTypeRef(0)].as_product().unwrap();
+        let needs_indirection = |def, elements: &[(Identifier, AlgebraicTypeUse)]| {
+            elements
+                .iter()
+                .map(|(_, use_)| result.needs_indirection(def, use_))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(needs_indirection(AlgebraicTypeRef(0), &list.elements), [false, true]);
+        let tree = result[AlgebraicTypeRef(1)].as_product().unwrap();
+        assert_eq!(
+            needs_indirection(AlgebraicTypeRef(1), &tree.elements),
+            [false, false, false]
+        );
     }
 }
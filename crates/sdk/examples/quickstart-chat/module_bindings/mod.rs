@@ -116,9 +116,9 @@ impl __sdk::InModule for DbUpdate {
 }
 
 impl __sdk::DbUpdate for DbUpdate {
-    fn apply_to_client_cache(&self, cache: &mut __sdk::ClientCache<RemoteModule>) {
-        cache.apply_diff_to_table::<Message>("message", &self.message);
-        cache.apply_diff_to_table::<User>("user", &self.user);
+    fn apply_to_client_cache(&mut self, cache: &mut __sdk::ClientCache<RemoteModule>) {
+        cache.apply_diff_to_table::<Message>("message", &mut self.message);
+        cache.apply_diff_to_table::<User>("user", &mut self.user);
     }
     fn invoke_row_callbacks(&self, event: &EventContext, callbacks: &mut __sdk::DbCallbacks<RemoteModule>) {
         callbacks.invoke_table_row_callbacks::<Message>("message", &self.message, event);
@@ -319,6 +319,10 @@ impl __sdk::DbConnection for DbConnection {
             imp,
         }
     }
+
+    fn imp(&self) -> &__sdk::DbContextImpl<RemoteModule> {
+        &self.imp
+    }
 }
 
 /// A [`DbConnection`] augmented with an [`__sdk::Event`],
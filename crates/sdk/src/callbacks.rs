@@ -1,4 +1,4 @@
-//! Internal structures for managing row and reducer callbacks.
+//! Internal structures for managing row, reducer and topic callbacks.
 //!
 //! The SpacetimeDB Rust Client SDK embraces a callback-driven API,
 //! where client authors register callbacks to later run in response to some event.
@@ -258,3 +258,47 @@ impl<M: SpacetimeModule> ReducerCallbacks<M> {
             .expect("Attempt to remove non-existent reducer callback");
     }
 }
+
+/// A callback for the messages broadcast on a topic, which receives their payload.
+pub(crate) type TopicCallback<M> = Box<dyn FnMut(&<M as SpacetimeModule>::DbConnection, &[u8]) + Send + 'static>;
+
+type TopicCallbackMap<M> = HashMap<CallbackId, TopicCallback<M>>;
+
+/// A collection of topic callbacks.
+pub(crate) struct TopicCallbacks<M: SpacetimeModule> {
+    /// Maps topic to a set of callbacks.
+    callbacks: HashMap<Box<str>, TopicCallbackMap<M>>,
+}
+
+impl<M: SpacetimeModule> Default for TopicCallbacks<M> {
+    fn default() -> Self {
+        Self {
+            callbacks: Default::default(),
+        }
+    }
+}
+
+impl<M: SpacetimeModule> TopicCallbacks<M> {
+    pub(crate) fn invoke_on_message(&mut self, ctx: &M::DbConnection, topic: &str, payload: &[u8]) {
+        if let Some(callbacks) = self.callbacks.get_mut(topic) {
+            for callback in callbacks.values_mut() {
+                callback(ctx, payload);
+            }
+        }
+    }
+
+    pub(crate) fn register_on_message(&mut self, topic: Box<str>, callback_id: CallbackId, callback: TopicCallback<M>) {
+        self.callbacks.entry(topic).or_default().insert(callback_id, callback);
+    }
+
+    pub(crate) fn remove_on_message(&mut self, topic: &str, callback_id: CallbackId) {
+        // Ugly: `impl FnMut` is `must_use`.
+        // See `ReducerCallbacks::remove_on_reducer`.
+        let _ = self
+            .callbacks
+            .get_mut(topic)
+            .expect("Attempt to remove a callback from a topic which doesn't have any")
+            .remove(&callback_id)
+            .expect("Attempt to remove non-existent topic callback");
+    }
+}
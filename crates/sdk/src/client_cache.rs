@@ -9,7 +9,7 @@
 
 use crate::callbacks::CallbackId;
 use crate::db_connection::{PendingMutation, SharedCell};
-use crate::spacetime_module::{InModule, SpacetimeModule, TableUpdate};
+use crate::spacetime_module::{InModule, SpacetimeModule, TableUpdate, WithBsatn};
 use anymap::{any::Any, Map};
 use bytes::Bytes;
 use futures_channel::mpsc;
@@ -52,7 +52,15 @@ impl<Row> Default for TableCache<Row> {
 
 impl<Row: Clone + Send + Sync + 'static> TableCache<Row> {
     /// Apply all the deletes, inserts and updates recorded in `diff`.
-    fn apply_diff(&mut self, diff: &TableUpdate<Row>) {
+    ///
+    /// If `diff` clears the table, every cached row is added to its `deletes`,
+    /// so that row callbacks see them.
+    fn apply_diff(&mut self, diff: &mut TableUpdate<Row>) {
+        if diff.cleared {
+            diff.deletes
+                .extend(self.entries.drain().map(|(bsatn, row)| WithBsatn { bsatn, row }));
+        }
+
         // Apply deletes strictly before inserts,
         // to avoid duplicates in any unique index.
 
@@ -124,6 +132,10 @@ pub struct ClientCache<M: SpacetimeModule + ?Sized> {
     /// The strings are table names, since we may have multiple tables with the same row type.
     tables: Map<dyn Any + Send + Sync>,
 
+    /// The number of rows in each table which has held rows,
+    /// maintained separately since `tables` erases the row types.
+    row_counts: HashMap<&'static str, u64>,
+
     _module: PhantomData<M>,
 }
 
@@ -131,6 +143,7 @@ impl<M: SpacetimeModule> Default for ClientCache<M> {
     fn default() -> Self {
         Self {
             tables: Map::new(),
+            row_counts: HashMap::new(),
             _module: PhantomData,
         }
     }
@@ -165,7 +178,7 @@ impl<M: SpacetimeModule> ClientCache<M> {
     pub fn apply_diff_to_table<Row: InModule<Module = M> + Clone + Send + Sync + 'static>(
         &mut self,
         table_name: &'static str,
-        diff: &TableUpdate<Row>,
+        diff: &mut TableUpdate<Row>,
     ) {
         if diff.is_empty() {
             return;
@@ -174,6 +187,13 @@ impl<M: SpacetimeModule> ClientCache<M> {
         let table = self.get_or_make_table::<Row>(table_name);
 
         table.apply_diff(diff);
+        let rows = table.entries.len() as u64;
+        self.row_counts.insert(table_name, rows);
+    }
+
+    /// The number of rows in each table which has held rows.
+    pub(crate) fn row_counts(&self) -> HashMap<&'static str, u64> {
+        self.row_counts.clone()
     }
 }
 
@@ -19,10 +19,15 @@
 //! This module is internal, and may incompatibly change without warning.
 
 use crate::{
-    callbacks::{CallbackId, DbCallbacks, ReducerCallback, ReducerCallbacks, RowCallback, UpdateCallback},
+    callbacks::{
+        CallbackId, DbCallbacks, ReducerCallback, ReducerCallbacks, RowCallback, TopicCallback, TopicCallbacks,
+        UpdateCallback,
+    },
     client_cache::{ClientCache, TableHandle},
+    metrics::{ConnectionMetrics, MetricsCounters},
+    reducer_call::{ReducerCall, ReducerCallCallback, ReducerCallPolicy, ReducerCalls},
     spacetime_module::{DbConnection, DbUpdate, EventContext, InModule, SpacetimeModule},
-    subscription::{OnAppliedCallback, OnErrorCallback, SubscriptionManager},
+    subscription::{OnAppliedCallback, OnErrorCallback, OnProgressCallback, SubscriptionManager},
     websocket::{WsConnection, WsParams},
     Event, ReducerEvent, Status,
 };
@@ -37,7 +42,7 @@ use spacetimedb_lib::{bsatn, ser::Serialize, Address, Identity};
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex as StdMutex, OnceLock},
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 use tokio::{
     runtime::{self, Runtime},
@@ -79,6 +84,10 @@ pub struct DbContextImpl<M: SpacetimeModule> {
     /// May be `None` if we connected anonymously
     /// and have not yet received the [`ws::IdentityToken`] message.
     identity: SharedCell<Option<Identity>>,
+
+    /// Counters for the [`ConnectionMetrics`] of this connection,
+    /// shared with the background WebSocket loop.
+    metrics: Arc<MetricsCounters>,
 }
 
 impl<M: SpacetimeModule> Clone for DbContextImpl<M> {
@@ -94,6 +103,7 @@ impl<M: SpacetimeModule> Clone for DbContextImpl<M> {
             pending_mutations_send: self.pending_mutations_send.clone(),
             pending_mutations_recv: Arc::clone(&self.pending_mutations_recv),
             identity: Arc::clone(&self.identity),
+            metrics: Arc::clone(&self.metrics),
         }
     }
 }
@@ -128,7 +138,7 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
                 let mut inner = self.inner.lock().unwrap();
                 if let Some(on_connect) = inner.on_connect.take() {
                     let ctx = <M::DbConnection as DbConnection>::new(self.clone());
-                    on_connect(&ctx, identity, &token);
+                    self.time_callbacks(|| on_connect(&ctx, identity, &token));
                 }
                 Ok(())
             }
@@ -136,7 +146,7 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
             // Subscription applied:
             // set the received state to store all the rows,
             // then invoke the on-applied and row callbacks.
-            ParsedMessage::InitialSubscription { db_update, sub_id } => {
+            ParsedMessage::InitialSubscription { mut db_update, sub_id } => {
                 // Lock the client cache in a restricted scope,
                 // so that it will be unlocked when callbacks run.
                 {
@@ -146,16 +156,43 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
                 }
                 let event_ctx = self.make_event_ctx(Event::SubscribeApplied);
                 let mut inner = self.inner.lock().unwrap();
-                inner.subscriptions.subscription_applied(&event_ctx, sub_id);
-                // FIXME: invoke delete callbacks for no-longer-subscribed rows.
-                db_update.invoke_row_callbacks(&event_ctx, &mut inner.db_callbacks);
+                self.time_callbacks(|| {
+                    inner.subscriptions.subscription_applied(&event_ctx, sub_id);
+                    // FIXME: invoke delete callbacks for no-longer-subscribed rows.
+                    db_update.invoke_row_callbacks(&event_ctx, &mut inner.db_callbacks);
+                });
+                Ok(())
+            }
+
+            // Part of the initial rows of a subscription:
+            // add the rows to the client cache,
+            // then invoke the row and on-progress callbacks.
+            // The subscription is applied by the `InitialSubscription` holding the last rows.
+            ParsedMessage::InitialSubscriptionChunk {
+                mut db_update,
+                sub_id,
+                rows_sent,
+                total_rows,
+            } => {
+                {
+                    let mut cache = self.cache.lock().unwrap();
+                    db_update.apply_to_client_cache(&mut *cache);
+                }
+                let event_ctx = self.make_event_ctx(Event::SubscribeApplied);
+                let mut inner = self.inner.lock().unwrap();
+                self.time_callbacks(|| {
+                    db_update.invoke_row_callbacks(&event_ctx, &mut inner.db_callbacks);
+                    inner
+                        .subscriptions
+                        .subscription_progress(&event_ctx, sub_id, rows_sent, total_rows);
+                });
                 Ok(())
             }
 
             // Successful transaction update:
             // apply the received diff to the client cache,
             // then invoke on-reducer and row callbacks.
-            ParsedMessage::TransactionUpdate(event, Some(update)) => {
+            ParsedMessage::TransactionUpdate(event, Some(mut update), own_call) => {
                 // Lock the client cache in a restricted scope,
                 // so that it will be unlocked when callbacks run.
                 {
@@ -164,25 +201,40 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
                 }
                 let event_ctx = self.make_event_ctx(event);
                 let mut inner = self.inner.lock().unwrap();
-                if let Event::Reducer(reducer_event) = event_ctx.event() {
-                    inner
-                        .reducer_callbacks
-                        .invoke_on_reducer(&event_ctx, &reducer_event.reducer);
-                }
-                update.invoke_row_callbacks(&event_ctx, &mut inner.db_callbacks);
+                self.time_callbacks(|| {
+                    if let Event::Reducer(reducer_event) = event_ctx.event() {
+                        inner
+                            .reducer_callbacks
+                            .invoke_on_reducer(&event_ctx, &reducer_event.reducer);
+                    }
+                    update.invoke_row_callbacks(&event_ctx, &mut inner.db_callbacks);
+                });
+                self.finish_reducer_call(&mut inner, own_call);
                 Ok(())
             }
 
             // Failed transaction update:
             // invoke on-reducer callbacks.
-            ParsedMessage::TransactionUpdate(event, None) => {
+            ParsedMessage::TransactionUpdate(event, None, own_call) => {
                 let event_ctx = self.make_event_ctx(event);
+                let mut inner = self.inner.lock().unwrap();
                 if let Event::Reducer(reducer_event) = event_ctx.event() {
-                    let mut inner = self.inner.lock().unwrap();
-                    inner
-                        .reducer_callbacks
-                        .invoke_on_reducer(&event_ctx, &reducer_event.reducer);
+                    self.time_callbacks(|| {
+                        inner
+                            .reducer_callbacks
+                            .invoke_on_reducer(&event_ctx, &reducer_event.reducer)
+                    });
                 }
+                self.finish_reducer_call(&mut inner, own_call);
+                Ok(())
+            }
+
+            // Message broadcast on a topic we subscribed to:
+            // invoke the topic's callbacks.
+            ParsedMessage::TopicMessage { topic, payload } => {
+                let ctx = <M::DbConnection as DbConnection>::new(self.clone());
+                let mut inner = self.inner.lock().unwrap();
+                self.time_callbacks(|| inner.topic_callbacks.invoke_on_message(&ctx, &topic, &payload));
                 Ok(())
             }
         };
@@ -207,8 +259,51 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
         };
         if let Some(disconnect_callback) = disconnected_callback {
             let ctx = <M::DbConnection as DbConnection>::new(self.clone());
-            disconnect_callback(&ctx, err);
+            self.time_callbacks(|| disconnect_callback(&ctx, err));
+        }
+    }
+
+    /// Run `callbacks`, adding the time they take to the connection's metrics.
+    fn time_callbacks<T>(&self, callbacks: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let res = callbacks();
+        self.metrics.callbacks_ran(start.elapsed());
+        res
+    }
+
+    /// Invoke the on-reducer-call callback, if any, with a state transition of a reducer call.
+    fn invoke_on_reducer_call(&self, inner: &mut DbContextImplInner<M>, call: &ReducerCall) {
+        if let Some(callback) = inner.on_reducer_call.as_mut() {
+            let ctx = <M::DbConnection as DbConnection>::new(self.clone());
+            self.time_callbacks(|| callback(&ctx, call));
+        }
+    }
+
+    /// If `own_call` is the outcome of a reducer call made by this connection, record it.
+    fn finish_reducer_call(&self, inner: &mut DbContextImplInner<M>, own_call: Option<(u32, Status)>) {
+        let Some((request_id, status)) = own_call else {
+            return;
+        };
+        if let Some(call) = inner.reducer_calls.finish(request_id, status) {
+            self.invoke_on_reducer_call(inner, &call);
+        }
+    }
+
+    /// Retry or give up on the reducer calls whose outcome the host didn't report in time.
+    fn expire_reducer_calls(&self) -> Result<()> {
+        let inner = &mut *self.inner.lock().unwrap();
+        for (call, retry) in inner.reducer_calls.expire(Instant::now()) {
+            if let Some(msg) = retry {
+                inner
+                    .send_chan
+                    .as_mut()
+                    .ok_or(DisconnectedError {})?
+                    .unbounded_send(msg)
+                    .expect("Unable to send reducer call message: WS sender loop has dropped its recv channel");
+            }
+            self.invoke_on_reducer_call(inner, &call);
         }
+        Ok(())
     }
 
     fn make_event_ctx(&self, event: Event<M::Reducer>) -> M::EventContext {
@@ -232,11 +327,15 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
             PendingMutation::Subscribe {
                 on_applied,
                 queries,
+                chunk_rows,
                 sub_id,
                 on_error,
+                on_progress,
             } => {
                 let mut inner = self.inner.lock().unwrap();
-                inner.subscriptions.register_subscription(sub_id, on_applied, on_error);
+                inner
+                    .subscriptions
+                    .register_subscription(sub_id, on_applied, on_error, on_progress);
                 inner
                     .send_chan
                     .as_mut()
@@ -244,27 +343,48 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
                     .unbounded_send(ws::ClientMessage::Subscribe(ws::Subscribe {
                         query_strings: queries,
                         request_id: sub_id,
+                        min_tx_offset: None,
+                        chunk_rows,
                     }))
                     .expect("Unable to send subscribe message: WS sender loop has dropped its recv channel");
             }
 
-            // CallReducer: send the `CallReducer` WS message.
+            // CallReducer: send the `CallReducer` WS message,
+            // and track the call until the host reports its outcome.
             PendingMutation::CallReducer { reducer, args_bsatn } => {
                 let inner = &mut *self.inner.lock().unwrap();
 
                 let flags = inner.call_reducer_flags.get_flags(reducer);
-                let msg = ws::ClientMessage::CallReducer(ws::CallReducer {
-                    reducer: reducer.into(),
-                    args: args_bsatn.into(),
-                    request_id: 0,
-                    flags,
-                });
+                let (msg, call) = inner.reducer_calls.start(reducer, args_bsatn.into(), flags);
                 inner
                     .send_chan
                     .as_mut()
                     .ok_or(DisconnectedError {})?
                     .unbounded_send(msg)
                     .expect("Unable to send reducer call message: WS sender loop has dropped its recv channel");
+                self.invoke_on_reducer_call(inner, &call);
+            }
+
+            // SubscribeTopic and UnsubscribeTopic: send the WS message of the same name.
+            PendingMutation::SubscribeTopic { topic } => {
+                self.inner
+                    .lock()
+                    .unwrap()
+                    .send_chan
+                    .as_mut()
+                    .ok_or(DisconnectedError {})?
+                    .unbounded_send(ws::ClientMessage::SubscribeTopic(ws::SubscribeTopic { topic }))
+                    .expect("Unable to send subscribe message: WS sender loop has dropped its recv channel");
+            }
+            PendingMutation::UnsubscribeTopic { topic } => {
+                self.inner
+                    .lock()
+                    .unwrap()
+                    .send_chan
+                    .as_mut()
+                    .ok_or(DisconnectedError {})?
+                    .unbounded_send(ws::ClientMessage::UnsubscribeTopic(ws::SubscribeTopic { topic }))
+                    .expect("Unable to send unsubscribe message: WS sender loop has dropped its recv channel");
             }
 
             // Disconnect: close the connection.
@@ -355,6 +475,24 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
                     .reducer_callbacks
                     .remove_on_reducer(reducer, callback_id);
             }
+            PendingMutation::AddTopicCallback {
+                topic,
+                callback_id,
+                callback,
+            } => {
+                self.inner
+                    .lock()
+                    .unwrap()
+                    .topic_callbacks
+                    .register_on_message(topic, callback_id, callback);
+            }
+            PendingMutation::RemoveTopicCallback { topic, callback_id } => {
+                self.inner
+                    .lock()
+                    .unwrap()
+                    .topic_callbacks
+                    .remove_on_message(&topic, callback_id);
+            }
             PendingMutation::SetCallReducerFlags {
                 reducer: reducer_name,
                 flags,
@@ -377,6 +515,7 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
         // Apply any pending mutations before processing a WS message,
         // so that pending callbacks don't get skipped.
         self.apply_pending_mutations()?;
+        self.expire_reducer_calls()?;
 
         // Deranged behavior: mpsc's `try_next` returns `Ok(None)` when the channel is closed,
         // and `Err(_)` when the channel is open and waiting. This seems exactly backwards.
@@ -402,6 +541,7 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
         // We call this out as an incorrect and unsupported thing to do.
         #![allow(clippy::await_holding_lock)]
 
+        let deadline = self.inner.lock().unwrap().reducer_calls.next_deadline();
         let mut pending_mutations = self.pending_mutations_recv.lock().await;
         let mut recv = self.recv.lock().await;
 
@@ -417,6 +557,7 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
         tokio::select! {
             pending_mutation = pending_mutations.next() => Message::Local(pending_mutation.unwrap()),
             incoming_message = recv.next() => Message::Ws(incoming_message),
+            _ = sleep_until(deadline) => Message::ReducerCallDeadline,
         }
     }
 
@@ -431,6 +572,7 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
                 Err(anyhow::Error::new(DisconnectedError {}))
             }
             Message::Ws(Some(msg)) => self.process_message(msg),
+            Message::ReducerCallDeadline => self.expire_reducer_calls(),
         }
     }
 
@@ -445,6 +587,7 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
                 Err(anyhow::Error::new(DisconnectedError {}))
             }
             Message::Ws(Some(msg)) => self.process_message(msg),
+            Message::ReducerCallDeadline => self.expire_reducer_calls(),
         }
     }
 
@@ -564,6 +707,35 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
         });
     }
 
+    /// Called by [`crate::topic::DbConnectionTopicsExt::subscribe_topic`].
+    pub(crate) fn subscribe_topic(&self, topic: &str) {
+        self.queue_mutation(PendingMutation::SubscribeTopic { topic: topic.into() });
+    }
+
+    /// Called by [`crate::topic::DbConnectionTopicsExt::unsubscribe_topic`].
+    pub(crate) fn unsubscribe_topic(&self, topic: &str) {
+        self.queue_mutation(PendingMutation::UnsubscribeTopic { topic: topic.into() });
+    }
+
+    /// Called by [`crate::topic::DbConnectionTopicsExt::on_topic_message`].
+    pub(crate) fn on_topic_message(&self, topic: &str, callback: TopicCallback<M>) -> CallbackId {
+        let callback_id = CallbackId::get_next();
+        self.queue_mutation(PendingMutation::AddTopicCallback {
+            topic: topic.into(),
+            callback_id,
+            callback,
+        });
+        callback_id
+    }
+
+    /// Called by [`crate::topic::DbConnectionTopicsExt::remove_on_topic_message`].
+    pub(crate) fn remove_on_topic_message(&self, topic: &str, callback: CallbackId) {
+        self.queue_mutation(PendingMutation::RemoveTopicCallback {
+            topic: topic.into(),
+            callback_id: callback,
+        });
+    }
+
     /// Called by the autogenerated `DbConnection` method of the same name.
     pub fn try_identity(&self) -> Option<Identity> {
         *self.identity.lock().unwrap()
@@ -573,6 +745,12 @@ impl<M: SpacetimeModule> DbContextImpl<M> {
     pub fn address(&self) -> Address {
         get_client_address()
     }
+
+    /// Called by [`crate::metrics::DbConnectionMetricsExt::metrics`].
+    pub(crate) fn metrics(&self) -> ConnectionMetrics {
+        let cache_rows = self.cache.lock().unwrap().row_counts();
+        self.metrics.snapshot(cache_rows)
+    }
 }
 
 type OnConnectCallback<M> = Box<dyn FnOnce(&<M as SpacetimeModule>::DbConnection, Identity, &str) + Send + 'static>;
@@ -594,6 +772,7 @@ pub(crate) struct DbContextImplInner<M: SpacetimeModule> {
 
     db_callbacks: DbCallbacks<M>,
     reducer_callbacks: ReducerCallbacks<M>,
+    topic_callbacks: TopicCallbacks<M>,
     pub(crate) subscriptions: SubscriptionManager<M>,
 
     on_connect: Option<OnConnectCallback<M>>,
@@ -601,8 +780,10 @@ pub(crate) struct DbContextImplInner<M: SpacetimeModule> {
     // TODO: Make use of this to handle `ParsedMessage::Error` before receiving `IdentityToken`.
     on_connect_error: Option<OnConnectErrorCallback>,
     on_disconnect: Option<OnDisconnectCallback<M>>,
+    on_reducer_call: Option<ReducerCallCallback<M>>,
 
     call_reducer_flags: CallReducerFlagsMap,
+    reducer_calls: ReducerCalls,
 }
 
 /// Maps reducer names to the flags to use for `.call_reducer(..)`.
@@ -645,7 +826,9 @@ pub struct DbConnectionBuilder<M: SpacetimeModule> {
     on_connect: Option<OnConnectCallback<M>>,
     on_connect_error: Option<OnConnectErrorCallback>,
     on_disconnect: Option<OnDisconnectCallback<M>>,
+    on_reducer_call: Option<ReducerCallCallback<M>>,
 
+    reducer_call_policy: ReducerCallPolicy,
     params: WsParams,
 }
 
@@ -689,6 +872,8 @@ impl<M: SpacetimeModule> DbConnectionBuilder<M> {
             on_connect: None,
             on_connect_error: None,
             on_disconnect: None,
+            on_reducer_call: None,
+            reducer_call_policy: <_>::default(),
             params: <_>::default(),
         }
     }
@@ -740,7 +925,9 @@ but you must call one of them, or else the connection will never progress.
             ))
         })?;
 
-        let (_websocket_loop_handle, raw_msg_recv, raw_msg_send) = ws_connection.spawn_message_loop(&handle);
+        let metrics = Arc::new(MetricsCounters::default());
+        let (_websocket_loop_handle, raw_msg_recv, raw_msg_send) =
+            ws_connection.spawn_message_loop(Arc::clone(&metrics), &handle);
         let (_parse_loop_handle, parsed_recv_chan) = spawn_parse_loop::<M>(raw_msg_recv, &handle);
 
         let inner = Arc::new(StdMutex::new(DbContextImplInner {
@@ -749,12 +936,15 @@ but you must call one of them, or else the connection will never progress.
             send_chan: Some(raw_msg_send),
             db_callbacks,
             reducer_callbacks,
+            topic_callbacks: TopicCallbacks::default(),
             subscriptions: SubscriptionManager::default(),
 
             on_connect: self.on_connect,
             on_connect_error: self.on_connect_error,
             on_disconnect: self.on_disconnect,
+            on_reducer_call: self.on_reducer_call,
             call_reducer_flags: <_>::default(),
+            reducer_calls: ReducerCalls::new(self.reducer_call_policy),
         }));
 
         let mut cache = ClientCache::default();
@@ -770,6 +960,7 @@ but you must call one of them, or else the connection will never progress.
             pending_mutations_send,
             pending_mutations_recv: Arc::new(TokioMutex::new(pending_mutations_recv)),
             identity: Arc::new(StdMutex::new(None)),
+            metrics,
         };
 
         Ok(ctx_imp)
@@ -827,6 +1018,19 @@ but you must call one of them, or else the connection will never progress.
         self
     }
 
+    /// Sets how long to wait for the outcome of each reducer call,
+    /// and how many times to retry calls which time out.
+    ///
+    /// If `policy` retries calls, the host is asked to deduplicate them,
+    /// so that a retried call which already ran replays its outcome rather than running again.
+    ///
+    /// Defaults to waiting 30 seconds, without retrying.
+    pub fn with_reducer_call_policy(mut self, policy: ReducerCallPolicy) -> Self {
+        self.params.replay_request_ids = policy.max_retries > 0;
+        self.reducer_call_policy = policy;
+        self
+    }
+
     /// Register a callback to run when the connection is successfully initiated.
     ///
     /// The callback will receive three arguments:
@@ -880,6 +1084,31 @@ Instead of registering multiple `on_disconnect` callbacks, register a single cal
         self.on_disconnect = Some(Box::new(callback));
         self
     }
+
+    /// Register a callback to run whenever a reducer call made by the connection changes state:
+    /// when it is sent or retried, when the host reports its outcome, and when it times out.
+    ///
+    /// Unlike on-reducer callbacks, this callback runs only for calls made by the connection,
+    /// and may be used to follow a particular call by its [`ReducerCall::id`](crate::reducer_call::ReducerCall::id).
+    pub fn on_reducer_call(mut self, callback: impl FnMut(&M::DbConnection, &ReducerCall) + Send + 'static) -> Self {
+        if self.on_reducer_call.is_some() {
+            panic!(
+                "DbConnectionBuilder can only register a single `on_reducer_call` callback.
+
+Instead of registering multiple `on_reducer_call` callbacks, register a single callback which does multiple operations."
+            );
+        }
+        self.on_reducer_call = Some(Box::new(callback));
+        self
+    }
+}
+
+/// Sleep until `deadline`, or forever if there is none.
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
 }
 
 // When called from within an async context, return a handle to it (and no
@@ -903,9 +1132,24 @@ fn enter_or_create_runtime() -> Result<(Option<Runtime>, runtime::Handle)> {
 }
 
 enum ParsedMessage<M: SpacetimeModule> {
-    InitialSubscription { db_update: M::DbUpdate, sub_id: u32 },
-    TransactionUpdate(Event<M::Reducer>, Option<M::DbUpdate>),
+    InitialSubscription {
+        db_update: M::DbUpdate,
+        sub_id: u32,
+    },
+    InitialSubscriptionChunk {
+        db_update: M::DbUpdate,
+        sub_id: u32,
+        rows_sent: u64,
+        total_rows: u64,
+    },
+    /// A transaction, its update if it committed,
+    /// and, if it ran a reducer called by this connection, that call's request id and status.
+    TransactionUpdate(Event<M::Reducer>, Option<M::DbUpdate>, Option<(u32, Status)>),
     IdentityToken(Identity, Box<str>, Address),
+    TopicMessage {
+        topic: Box<str>,
+        payload: Bytes,
+    },
     Error(anyhow::Error),
 }
 
@@ -934,6 +1178,16 @@ async fn parse_loop<M: SpacetimeModule>(
                 .unwrap_or_else(|e| {
                     ParsedMessage::Error(e.context("Failed to parse DbUpdate from InitialSubscription"))
                 }),
+            ws::ServerMessage::InitialSubscriptionChunk(chunk) => M::DbUpdate::try_from(chunk.database_update)
+                .map(|update| ParsedMessage::InitialSubscriptionChunk {
+                    db_update: update,
+                    sub_id: chunk.request_id,
+                    rows_sent: chunk.rows_sent,
+                    total_rows: chunk.total_rows,
+                })
+                .unwrap_or_else(|e| {
+                    ParsedMessage::Error(e.context("Failed to parse DbUpdate from InitialSubscriptionChunk"))
+                }),
             ws::ServerMessage::TransactionUpdate(ws::TransactionUpdate {
                 status,
                 timestamp,
@@ -945,6 +1199,8 @@ async fn parse_loop<M: SpacetimeModule>(
             }) => match Status::parse_status_and_update::<M>(status) {
                 Err(e) => ParsedMessage::Error(e.context("Failed to parse Status from TransactionUpdate")),
                 Ok((status, db_update)) => {
+                    let own_call = (caller_address == get_client_address() && reducer_call.request_id != 0)
+                        .then(|| (reducer_call.request_id, status.clone()));
                     let event = M::Reducer::try_from(reducer_call)
                         .map(|reducer| {
                             Event::Reducer(ReducerEvent {
@@ -959,13 +1215,18 @@ async fn parse_loop<M: SpacetimeModule>(
                             })
                         })
                         .unwrap_or(Event::UnknownTransaction);
-                    ParsedMessage::TransactionUpdate(event, db_update)
+                    ParsedMessage::TransactionUpdate(event, db_update, own_call)
                 }
             },
-            ws::ServerMessage::TransactionUpdateLight(ws::TransactionUpdateLight { update, request_id: _ }) => {
+            ws::ServerMessage::TransactionUpdateLight(ws::TransactionUpdateLight { update, request_id }) => {
+                // Light updates are only sent for committed transactions,
+                // and only carry a request id for our own calls.
+                let own_call = (request_id != 0).then_some((request_id, Status::Committed));
                 match M::DbUpdate::parse_update(update) {
                     Err(e) => ParsedMessage::Error(e.context("Failed to parse update from TransactionUpdateLight")),
-                    Ok(db_update) => ParsedMessage::TransactionUpdate(Event::UnknownTransaction, Some(db_update)),
+                    Ok(db_update) => {
+                        ParsedMessage::TransactionUpdate(Event::UnknownTransaction, Some(db_update), own_call)
+                    }
                 }
             }
             ws::ServerMessage::IdentityToken(ws::IdentityToken {
@@ -979,6 +1240,10 @@ async fn parse_loop<M: SpacetimeModule>(
             ws::ServerMessage::SubscribeApplied(_) => todo!(),
             ws::ServerMessage::UnsubscribeApplied(_) => todo!(),
             ws::ServerMessage::SubscriptionError(_) => todo!(),
+            ws::ServerMessage::ViewUpdate(_) => unreachable!("The Rust SDK does not subscribe to views"),
+            ws::ServerMessage::TopicMessage(ws::TopicMessage { topic, payload }) => {
+                ParsedMessage::TopicMessage { topic, payload }
+            }
         })
         .expect("Failed to send ParsedMessage to main thread");
     }
@@ -989,8 +1254,10 @@ pub(crate) enum PendingMutation<M: SpacetimeModule> {
     Subscribe {
         on_applied: Option<OnAppliedCallback<M>>,
         on_error: Option<OnErrorCallback<M>>,
+        on_progress: Option<OnProgressCallback<M>>,
         queries: Box<[Box<str>]>,
         // TODO: replace `queries` with query_sql: String,
+        chunk_rows: Option<u32>,
         sub_id: u32,
     },
     // TODO: Unsubscribe { ??? },
@@ -1034,6 +1301,21 @@ pub(crate) enum PendingMutation<M: SpacetimeModule> {
         reducer: &'static str,
         callback_id: CallbackId,
     },
+    SubscribeTopic {
+        topic: Box<str>,
+    },
+    UnsubscribeTopic {
+        topic: Box<str>,
+    },
+    AddTopicCallback {
+        topic: Box<str>,
+        callback_id: CallbackId,
+        callback: TopicCallback<M>,
+    },
+    RemoveTopicCallback {
+        topic: Box<str>,
+        callback_id: CallbackId,
+    },
     Disconnect,
     SetCallReducerFlags {
         reducer: &'static str,
@@ -1044,6 +1326,8 @@ pub(crate) enum PendingMutation<M: SpacetimeModule> {
 enum Message<M: SpacetimeModule> {
     Ws(Option<ParsedMessage<M>>),
     Local(PendingMutation<M>),
+    /// The deadline of a reducer call in flight has passed.
+    ReducerCallDeadline,
 }
 
 #[derive(Debug, Clone)]
@@ -11,8 +11,9 @@
 //! to determine what change in your connection's state caused the callback to run.
 
 use crate::spacetime_module::{DbUpdate as _, SpacetimeModule};
+use anyhow::Context as _;
 use spacetimedb_client_api_messages::websocket as ws;
-use spacetimedb_lib::{Address, Identity};
+use spacetimedb_lib::{bsatn, de::Deserialize, Address, Identity};
 use std::time::SystemTime;
 
 #[non_exhaustive]
@@ -95,6 +96,12 @@ pub enum Status {
 
     /// The reducer was aborted due to insufficient energy, and its mutations were discarded or rolled back.
     OutOfEnergy,
+
+    /// The reducer returned a value of its declared error type, and its mutations were discarded or rolled back.
+    ///
+    /// `message` is the value formatted for display, and `value` is its BSATN encoding.
+    /// Use [`Status::error_value`], or the typed helper generated for the reducer, to decode it.
+    FailedWithValue { message: Box<str>, value: Box<[u8]> },
 }
 
 impl Status {
@@ -105,6 +112,26 @@ impl Status {
             ws::UpdateStatus::Committed(update) => (Self::Committed, Some(M::DbUpdate::parse_update(update)?)),
             ws::UpdateStatus::Failed(errmsg) => (Self::Failed(errmsg), None),
             ws::UpdateStatus::OutOfEnergy => (Self::OutOfEnergy, None),
+            ws::UpdateStatus::FailedWithValue(err) => (
+                Self::FailedWithValue {
+                    message: err.message,
+                    value: err.value,
+                },
+                None,
+            ),
         })
     }
+
+    /// Decode the error value the reducer failed with as an `E`.
+    ///
+    /// Returns `None` if this status is not [`Status::FailedWithValue`],
+    /// or `Some(Err(_))` if the value is not a valid BSATN-encoded `E`.
+    pub fn error_value<E: for<'de> Deserialize<'de>>(&self) -> Option<anyhow::Result<E>> {
+        match self {
+            Self::FailedWithValue { value, .. } => {
+                Some(bsatn::from_slice(value).context("Failed to decode reducer error value"))
+            }
+            _ => None,
+        }
+    }
 }
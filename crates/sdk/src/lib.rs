@@ -20,12 +20,18 @@ mod websocket;
 pub mod credentials;
 pub mod db_context;
 pub mod event;
+pub mod metrics;
+pub mod reducer_call;
 pub mod table;
+pub mod topic;
 
 pub use db_connection::{DbConnectionBuilder, DisconnectedError};
 pub use db_context::DbContext;
 pub use event::{Event, ReducerEvent, Status};
+pub use metrics::{ConnectionMetrics, DbConnectionMetricsExt};
+pub use reducer_call::{ReducerCall, ReducerCallId, ReducerCallPolicy, ReducerCallState};
 pub use table::{Table, TableWithPrimaryKey};
+pub use topic::{DbConnectionTopicsExt, TopicCallbackId};
 
 pub use spacetimedb_lib::{Address, Identity, ScheduleAt};
 pub use spacetimedb_sats::{i256, u256};
@@ -52,8 +58,8 @@ pub mod __codegen {
     };
     pub use crate::subscription::{SubscriptionBuilder, SubscriptionHandleImpl};
     pub use crate::{
-        Address, DbConnectionBuilder, DbContext, DisconnectedError, Event, Identity, ReducerEvent, ScheduleAt, Table,
-        TableWithPrimaryKey,
+        Address, DbConnectionBuilder, DbContext, DisconnectedError, Event, Identity, ReducerEvent, ScheduleAt, Status,
+        Table, TableWithPrimaryKey,
     };
 }
 
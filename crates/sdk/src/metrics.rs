@@ -0,0 +1,114 @@
+//! Hooks for observing the health of a `DbConnection`,
+//! e.g. to feed it into an application's own telemetry.
+//!
+//! Import [`DbConnectionMetricsExt`] to call [`DbConnectionMetricsExt::metrics`] on a `DbConnection`,
+//! which returns a [`ConnectionMetrics`] snapshot.
+
+use crate::spacetime_module::{DbConnection, SpacetimeModule};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// A snapshot of the metrics of a `DbConnection`, accumulated since it was built.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionMetrics {
+    /// The number of bytes sent to the host, in WebSocket message payloads.
+    pub bytes_sent: u64,
+
+    /// The number of bytes received from the host, in WebSocket message payloads,
+    /// before decompression.
+    pub bytes_received: u64,
+
+    /// The number of WebSocket messages sent to the host.
+    pub messages_sent: u64,
+
+    /// The number of WebSocket messages received from the host.
+    pub messages_received: u64,
+
+    /// The number of rows in the client cache, for each table which has held rows.
+    pub cache_rows: HashMap<&'static str, u64>,
+
+    /// The total time spent running callbacks registered with the connection,
+    /// including row, reducer, subscription and connection callbacks.
+    pub callback_time: Duration,
+}
+
+/// Counters updated by a connection and its background tasks.
+#[derive(Default)]
+pub(crate) struct MetricsCounters {
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    messages_sent: AtomicU64,
+    messages_received: AtomicU64,
+    callback_nanos: AtomicU64,
+}
+
+impl MetricsCounters {
+    pub(crate) fn message_sent(&self, len: usize) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn message_received(&self, len: usize) {
+        self.messages_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received.fetch_add(len as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn callbacks_ran(&self, elapsed: Duration) {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.callback_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    /// Take a snapshot of the counters, along with the given sizes of the client cache.
+    pub(crate) fn snapshot(&self, cache_rows: HashMap<&'static str, u64>) -> ConnectionMetrics {
+        ConnectionMetrics {
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            messages_received: self.messages_received.load(Ordering::Relaxed),
+            cache_rows,
+            callback_time: Duration::from_nanos(self.callback_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Extension trait for observing the metrics of a `DbConnection`.
+pub trait DbConnectionMetricsExt {
+    /// Get a snapshot of the metrics of this connection.
+    fn metrics(&self) -> ConnectionMetrics;
+}
+
+impl<C: DbConnection> DbConnectionMetricsExt for C
+where
+    C::Module: SpacetimeModule<DbConnection = C>,
+{
+    fn metrics(&self) -> ConnectionMetrics {
+        self.imp().metrics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_accumulate_into_snapshots() {
+        let counters = MetricsCounters::default();
+        counters.message_sent(10);
+        counters.message_sent(5);
+        counters.message_received(100);
+        counters.callbacks_ran(Duration::from_millis(2));
+        counters.callbacks_ran(Duration::from_micros(500));
+
+        let cache_rows = HashMap::from([("one_u8", 3)]);
+        let metrics = counters.snapshot(cache_rows.clone());
+        assert_eq!(metrics.messages_sent, 2);
+        assert_eq!(metrics.bytes_sent, 15);
+        assert_eq!(metrics.messages_received, 1);
+        assert_eq!(metrics.bytes_received, 100);
+        assert_eq!(metrics.cache_rows, cache_rows);
+        assert_eq!(metrics.callback_time, Duration::from_micros(2500));
+    }
+}
@@ -0,0 +1,279 @@
+//! Types for following the reducer calls made by a `DbConnection`,
+//! from being sent to the host until the host reports their outcome.
+//!
+//! Register a callback with [`crate::DbConnectionBuilder::on_reducer_call`]
+//! to be notified of each transition, e.g. to display a "saving…" indicator,
+//! and configure how long to wait for the host and how often to retry with
+//! [`crate::DbConnectionBuilder::with_reducer_call_policy`].
+
+use crate::spacetime_module::SpacetimeModule;
+use crate::Status;
+use bytes::Bytes;
+use spacetimedb_client_api_messages::websocket as ws;
+use spacetimedb_client_api_messages::websocket::CallReducerFlags;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
+
+/// How long a `DbConnection` waits for the outcome of each reducer call,
+/// and how many times it retries calls which time out.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ReducerCallPolicy {
+    /// How long to wait for the host to report the outcome of a call,
+    /// before retrying it or giving up on it.
+    pub timeout: Duration,
+
+    /// How many times to resend a call which timed out.
+    ///
+    /// Retries reuse the request id of the first attempt,
+    /// and the host is asked to replay the outcome of calls it has already run,
+    /// so a call whose outcome was lost doesn't run twice.
+    pub max_retries: u32,
+}
+
+impl Default for ReducerCallPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+            max_retries: 0,
+        }
+    }
+}
+
+/// Identifies a reducer call made by a `DbConnection`,
+/// across all of its state transitions.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ReducerCallId(u32);
+
+impl ReducerCallId {
+    /// We maintain a global counter of request ids,
+    /// because the host identifies retries by the client address,
+    /// which is shared by all connections of this process.
+    fn get_next() -> Self {
+        static NEXT: AtomicU32 = AtomicU32::new(1);
+        loop {
+            // The host treats the request id 0 as absent, so skip it on wraparound.
+            let id = NEXT.fetch_add(1, Ordering::Relaxed);
+            if id != 0 {
+                return Self(id);
+            }
+        }
+    }
+}
+
+/// The state of a reducer call made by a `DbConnection`.
+#[derive(Debug, Clone)]
+pub enum ReducerCallState {
+    /// The call was sent to the host for the `attempt`th time, starting from 1.
+    ///
+    /// The host doesn't acknowledge calls before running them,
+    /// so a call remains in this state until the host reports its outcome.
+    Sent { attempt: u32 },
+
+    /// The host ran the reducer, which terminated with the given status.
+    Finished(Status),
+
+    /// The host didn't report the outcome of the call in time, even after retrying it.
+    ///
+    /// The reducer may still have run.
+    /// In particular, in light mode the host doesn't report a successful call
+    /// which didn't touch any subscribed rows.
+    TimedOut,
+}
+
+/// A state transition of a reducer call,
+/// passed to the callback registered with [`crate::DbConnectionBuilder::on_reducer_call`].
+#[derive(Debug, Clone)]
+pub struct ReducerCall {
+    /// Identifies the call across its transitions.
+    pub id: ReducerCallId,
+
+    /// The name of the called reducer.
+    pub reducer: &'static str,
+
+    /// The new state of the call.
+    pub state: ReducerCallState,
+}
+
+pub(crate) type ReducerCallCallback<M> =
+    Box<dyn FnMut(&<M as SpacetimeModule>::DbConnection, &ReducerCall) + Send + 'static>;
+
+/// A call sent to the host whose outcome has yet to be reported.
+struct InFlight {
+    reducer: &'static str,
+    args: Bytes,
+    flags: CallReducerFlags,
+    attempt: u32,
+    deadline: Instant,
+}
+
+impl InFlight {
+    fn message(&self, id: ReducerCallId) -> ws::ClientMessage<Bytes> {
+        ws::ClientMessage::CallReducer(ws::CallReducer {
+            reducer: self.reducer.into(),
+            args: self.args.clone(),
+            request_id: id.0,
+            flags: self.flags,
+        })
+    }
+}
+
+/// Tracks the reducer calls made by a `DbConnection` until their outcome is known.
+pub(crate) struct ReducerCalls {
+    policy: ReducerCallPolicy,
+    in_flight: HashMap<ReducerCallId, InFlight>,
+}
+
+impl ReducerCalls {
+    pub(crate) fn new(policy: ReducerCallPolicy) -> Self {
+        Self {
+            policy,
+            in_flight: HashMap::new(),
+        }
+    }
+
+    /// Start a call to `reducer`, returning the message to send to the host
+    /// and the resulting state transition.
+    ///
+    /// Calls with [`CallReducerFlags::NoSuccessNotify`] can't be followed past being sent,
+    /// as the host doesn't report their success.
+    pub(crate) fn start(
+        &mut self,
+        reducer: &'static str,
+        args: Bytes,
+        flags: CallReducerFlags,
+    ) -> (ws::ClientMessage<Bytes>, ReducerCall) {
+        let id = ReducerCallId::get_next();
+        let call = InFlight {
+            reducer,
+            args,
+            flags,
+            attempt: 1,
+            deadline: Instant::now() + self.policy.timeout,
+        };
+        let msg = call.message(id);
+        if flags != CallReducerFlags::NoSuccessNotify {
+            self.in_flight.insert(id, call);
+        }
+        let state = ReducerCallState::Sent { attempt: 1 };
+        (msg, ReducerCall { id, reducer, state })
+    }
+
+    /// Record the outcome of the call with `request_id`, as reported by the host,
+    /// returning the resulting state transition if the call was in flight.
+    pub(crate) fn finish(&mut self, request_id: u32, status: Status) -> Option<ReducerCall> {
+        let id = ReducerCallId(request_id);
+        let call = self.in_flight.remove(&id)?;
+        Some(ReducerCall {
+            id,
+            reducer: call.reducer,
+            state: ReducerCallState::Finished(status),
+        })
+    }
+
+    /// Retry or give up on the calls whose deadline is past `now`,
+    /// returning the resulting state transitions,
+    /// along with the message to resend for the retried calls.
+    pub(crate) fn expire(&mut self, now: Instant) -> Vec<(ReducerCall, Option<ws::ClientMessage<Bytes>>)> {
+        let policy = self.policy;
+        let mut transitions = Vec::new();
+        self.in_flight.retain(|&id, call| {
+            if call.deadline > now {
+                return true;
+            }
+            let retry = call.attempt <= policy.max_retries;
+            let (state, msg) = if retry {
+                call.attempt += 1;
+                call.deadline = now + policy.timeout;
+                let state = ReducerCallState::Sent { attempt: call.attempt };
+                (state, Some(call.message(id)))
+            } else {
+                (ReducerCallState::TimedOut, None)
+            };
+            let reducer = call.reducer;
+            transitions.push((ReducerCall { id, reducer, state }, msg));
+            retry
+        });
+        transitions
+    }
+
+    /// Returns the earliest deadline of the calls in flight, if any.
+    pub(crate) fn next_deadline(&self) -> Option<Instant> {
+        self.in_flight.values().map(|call| call.deadline).min()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the request id of `msg`, which must be a reducer call.
+    fn request_id(msg: &ws::ClientMessage<Bytes>) -> u32 {
+        match msg {
+            ws::ClientMessage::CallReducer(call) => call.request_id,
+            _ => panic!("not a reducer call"),
+        }
+    }
+
+    fn calls(max_retries: u32) -> ReducerCalls {
+        ReducerCalls::new(ReducerCallPolicy {
+            timeout: Duration::from_secs(10),
+            max_retries,
+        })
+    }
+
+    #[test]
+    fn calls_are_followed_until_finished() {
+        let mut calls = calls(0);
+        let (msg, sent) = calls.start("add", Bytes::new(), CallReducerFlags::FullUpdate);
+        assert_eq!(request_id(&msg), sent.id.0);
+        assert!(matches!(sent.state, ReducerCallState::Sent { attempt: 1 }));
+        assert!(calls.next_deadline().is_some());
+
+        let finished = calls.finish(sent.id.0, Status::Committed).unwrap();
+        assert_eq!(finished.id, sent.id);
+        assert_eq!(finished.reducer, "add");
+        assert!(matches!(finished.state, ReducerCallState::Finished(Status::Committed)));
+        assert_eq!(calls.next_deadline(), None);
+
+        // The outcome of a call is only reported once.
+        assert!(calls.finish(sent.id.0, Status::Committed).is_none());
+    }
+
+    #[test]
+    fn calls_without_success_notifications_are_not_followed() {
+        let mut calls = calls(0);
+        let (_, sent) = calls.start("add", Bytes::new(), CallReducerFlags::NoSuccessNotify);
+        assert!(matches!(sent.state, ReducerCallState::Sent { attempt: 1 }));
+        assert_eq!(calls.next_deadline(), None);
+        assert!(calls.finish(sent.id.0, Status::Committed).is_none());
+    }
+
+    #[test]
+    fn expired_calls_are_retried_then_time_out() {
+        let mut calls = calls(1);
+        let (_, sent) = calls.start("add", Bytes::new(), CallReducerFlags::FullUpdate);
+        let deadline = calls.next_deadline().unwrap();
+        assert!(calls.expire(deadline - Duration::from_millis(1)).is_empty());
+
+        // The retry reuses the request id of the first attempt.
+        let transitions = calls.expire(deadline);
+        let [(retried, Some(msg))] = &transitions[..] else {
+            panic!("the call wasn't retried");
+        };
+        assert_eq!(retried.id, sent.id);
+        assert!(matches!(retried.state, ReducerCallState::Sent { attempt: 2 }));
+        assert_eq!(request_id(msg), sent.id.0);
+        let deadline = calls.next_deadline().unwrap();
+
+        let transitions = calls.expire(deadline);
+        let [(timed_out, None)] = &transitions[..] else {
+            panic!("the call didn't time out");
+        };
+        assert_eq!(timed_out.id, sent.id);
+        assert!(matches!(timed_out.state, ReducerCallState::TimedOut));
+        assert_eq!(calls.next_deadline(), None);
+    }
+}
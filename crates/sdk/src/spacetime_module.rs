@@ -59,7 +59,7 @@ pub trait DbUpdate:
 where
     Self::Module: SpacetimeModule<DbUpdate = Self>,
 {
-    fn apply_to_client_cache(&self, cache: &mut ClientCache<Self::Module>);
+    fn apply_to_client_cache(&mut self, cache: &mut ClientCache<Self::Module>);
     fn invoke_row_callbacks(
         &self,
         event: &<<Self as InModule>::Module as SpacetimeModule>::EventContext,
@@ -80,6 +80,10 @@ where
     /// Called by [`crate::db_connection::DbConnectionBuilder::build`]
     /// to wrap a `DbConnectionImpl` into the codegen-defined type.
     fn new(imp: DbContextImpl<Self::Module>) -> Self;
+
+    /// Get the `DbContextImpl` wrapped by this connection,
+    /// e.g. for the extension traits defined by the SDK.
+    fn imp(&self) -> &DbContextImpl<Self::Module>;
 }
 
 /// Implemented by the autogenerated `EventContext` type,
@@ -130,6 +134,11 @@ pub struct TableUpdate<Row> {
     pub inserts: Vec<WithBsatn<Row>>,
     pub deletes: Vec<WithBsatn<Row>>,
     pub updates: Vec<RowUpdate<Row>>,
+    /// Whether the table was truncated, deleting all of its rows.
+    ///
+    /// The deleted rows are not sent by the server;
+    /// they're filled into `deletes` from the client cache when this update is applied.
+    pub cleared: bool,
 }
 
 impl<Row> Default for TableUpdate<Row> {
@@ -138,13 +147,14 @@ impl<Row> Default for TableUpdate<Row> {
             inserts: Default::default(),
             deletes: Default::default(),
             updates: Default::default(),
+            cleared: false,
         }
     }
 }
 
 impl<Row> TableUpdate<Row> {
     pub(crate) fn is_empty(&self) -> bool {
-        self.inserts.is_empty() && self.deletes.is_empty() && self.updates.is_empty()
+        self.inserts.is_empty() && self.deletes.is_empty() && self.updates.is_empty() && !self.cleared
     }
 }
 
@@ -200,6 +210,8 @@ impl<Row: DeserializeOwned + Debug> TableUpdate<Row> {
             .clone()
         };
 
+        let cleared = raw_updates.cleared;
+
         // Pre-allocate plenty of space to minimize hash collisions.
         let mut diff: HashMap<Pk, DiffEntry<Row>> =
             HashMap::<_, _, DefaultHashBuilder>::with_capacity(raw_updates.num_rows() * 2);
@@ -246,6 +258,7 @@ impl<Row: DeserializeOwned + Debug> TableUpdate<Row> {
             inserts,
             deletes,
             updates,
+            cleared,
         })
     }
 
@@ -255,6 +268,7 @@ impl<Row: DeserializeOwned + Debug> TableUpdate<Row> {
     ) -> anyhow::Result<TableUpdate<Row>> {
         let mut inserts = Vec::new();
         let mut deletes = Vec::new();
+        let cleared = raw_updates.cleared;
         for update in raw_updates.updates {
             let update = update.maybe_decompress();
             Self::parse_from_row_list(&mut deletes, &update.deletes)?;
@@ -264,6 +278,7 @@ impl<Row: DeserializeOwned + Debug> TableUpdate<Row> {
             inserts,
             deletes,
             updates: Vec::new(),
+            cleared,
         })
     }
 
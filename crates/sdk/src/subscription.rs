@@ -27,6 +27,8 @@ impl<M: SpacetimeModule> Default for SubscriptionManager<M> {
 
 pub(crate) type OnAppliedCallback<M> = Box<dyn FnOnce(&<M as SpacetimeModule>::EventContext) + Send + 'static>;
 pub(crate) type OnErrorCallback<M> = Box<dyn FnOnce(&<M as SpacetimeModule>::EventContext) + Send + 'static>;
+pub(crate) type OnProgressCallback<M> =
+    Box<dyn FnMut(&<M as SpacetimeModule>::EventContext, u64, u64) + Send + 'static>;
 
 impl<M: SpacetimeModule> SubscriptionManager<M> {
     pub(crate) fn register_subscription(
@@ -34,6 +36,7 @@ impl<M: SpacetimeModule> SubscriptionManager<M> {
         sub_id: u32,
         on_applied: Option<OnAppliedCallback<M>>,
         on_error: Option<OnErrorCallback<M>>,
+        on_progress: Option<OnProgressCallback<M>>,
     ) {
         self.subscriptions
             .try_insert(
@@ -41,11 +44,24 @@ impl<M: SpacetimeModule> SubscriptionManager<M> {
                 SubscribedQuery {
                     on_applied,
                     on_error,
+                    on_progress,
                     is_applied: false,
                 },
             )
             .unwrap_or_else(|_| unreachable!("Duplicate subscription id {sub_id}"));
     }
+    pub(crate) fn subscription_progress(
+        &mut self,
+        ctx: &M::EventContext,
+        sub_id: u32,
+        rows_sent: u64,
+        total_rows: u64,
+    ) {
+        let sub = self.subscriptions.get_mut(&sub_id).unwrap();
+        if let Some(callback) = &mut sub.on_progress {
+            callback(ctx, rows_sent, total_rows);
+        }
+    }
     pub(crate) fn subscription_applied(&mut self, ctx: &M::EventContext, sub_id: u32) {
         let sub = self.subscriptions.get_mut(&sub_id).unwrap();
         sub.is_applied = true;
@@ -59,6 +75,7 @@ struct SubscribedQuery<M: SpacetimeModule> {
     on_applied: Option<OnAppliedCallback<M>>,
     #[allow(unused)]
     on_error: Option<OnErrorCallback<M>>,
+    on_progress: Option<OnProgressCallback<M>>,
     is_applied: bool,
 }
 
@@ -70,6 +87,8 @@ struct SubscribedQuery<M: SpacetimeModule> {
 pub struct SubscriptionBuilder<M: SpacetimeModule> {
     on_applied: Option<OnAppliedCallback<M>>,
     on_error: Option<OnErrorCallback<M>>,
+    on_progress: Option<OnProgressCallback<M>>,
+    chunk_rows: Option<u32>,
     conn: DbContextImpl<M>,
 }
 
@@ -80,6 +99,8 @@ impl<M: SpacetimeModule> SubscriptionBuilder<M> {
         Self {
             on_applied: None,
             on_error: None,
+            on_progress: None,
+            chunk_rows: None,
             conn: imp.clone(),
         }
     }
@@ -102,6 +123,26 @@ impl<M: SpacetimeModule> SubscriptionBuilder<M> {
         self
     }
 
+    /// Receive the initial rows of the subscription in messages of at most `max_rows` rows,
+    /// rather than in one message.
+    ///
+    /// Each message is applied to the client cache as it arrives,
+    /// invoking the row callbacks for its rows and then [`Self::on_progress`] callback.
+    /// The [`Self::on_applied`] callback runs once the last message has been applied.
+    pub fn chunk_rows(mut self, max_rows: u32) -> Self {
+        self.chunk_rows = Some(max_rows);
+        self
+    }
+
+    /// Register a callback to run after each part of the initial rows requested with [`Self::chunk_rows`],
+    /// other than the last, has been applied.
+    ///
+    /// The callback is passed the number of rows received so far and the number of initial rows in total.
+    pub fn on_progress(mut self, callback: impl FnMut(&M::EventContext, u64, u64) + Send + 'static) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
     /// Subscribe to `queries`, which should be a collection of SQL queries,
     /// each of which is a single-table non-projected `SELECT` statement
     /// with an optional `WHERE` clause,
@@ -114,13 +155,17 @@ impl<M: SpacetimeModule> SubscriptionBuilder<M> {
         let Self {
             on_applied,
             on_error,
+            on_progress,
+            chunk_rows,
             conn,
         } = self;
         conn.pending_mutations_send
             .unbounded_send(PendingMutation::Subscribe {
                 on_applied,
                 on_error,
+                on_progress,
                 queries: queries.into_queries(),
+                chunk_rows,
                 sub_id,
             })
             .unwrap();
@@ -0,0 +1,67 @@
+//! Ephemeral messages which reducers broadcast on topics with `ReducerContext::broadcast`.
+//!
+//! Import [`DbConnectionTopicsExt`] to subscribe a `DbConnection` to a topic,
+//! and to register callbacks which run whenever a message is broadcast on it.
+//!
+//! Messages are never stored, so a connection only receives those broadcast while it is subscribed.
+//! If the module declares a topic authorizer, the host calls it whenever the connection subscribes to a topic,
+//! and the connection is notified of its outcome like that of any reducer it calls.
+//! A subscription which the authorizer refuses receives no messages.
+
+use crate::callbacks::CallbackId;
+use crate::db_connection::DisconnectedError;
+use crate::spacetime_module::{DbConnection, SpacetimeModule};
+use anyhow::Result;
+
+/// Identifies a callback registered with [`DbConnectionTopicsExt::on_topic_message`],
+/// to remove it with [`DbConnectionTopicsExt::remove_on_topic_message`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct TopicCallbackId(CallbackId);
+
+/// Extension trait for subscribing a `DbConnection` to topics.
+pub trait DbConnectionTopicsExt: Sized {
+    /// Start receiving the messages broadcast on `topic`.
+    ///
+    /// Subscribing to a topic the connection is already subscribed to does nothing.
+    fn subscribe_topic(&self, topic: &str) -> Result<()>;
+
+    /// Stop receiving the messages broadcast on `topic`.
+    fn unsubscribe_topic(&self, topic: &str) -> Result<()>;
+
+    /// Register a callback to run with the payload of each message broadcast on `topic`.
+    ///
+    /// The callback only runs while the connection is subscribed to `topic`.
+    fn on_topic_message(&self, topic: &str, callback: impl FnMut(&Self, &[u8]) + Send + 'static) -> TopicCallbackId;
+
+    /// Remove a callback registered with [`Self::on_topic_message`] for `topic`.
+    fn remove_on_topic_message(&self, topic: &str, callback: TopicCallbackId);
+}
+
+impl<C: DbConnection> DbConnectionTopicsExt for C
+where
+    C::Module: SpacetimeModule<DbConnection = C>,
+{
+    fn subscribe_topic(&self, topic: &str) -> Result<()> {
+        if !self.imp().is_active() {
+            return Err(DisconnectedError {}.into());
+        }
+        self.imp().subscribe_topic(topic);
+        Ok(())
+    }
+
+    fn unsubscribe_topic(&self, topic: &str) -> Result<()> {
+        if !self.imp().is_active() {
+            return Err(DisconnectedError {}.into());
+        }
+        self.imp().unsubscribe_topic(topic);
+        Ok(())
+    }
+
+    fn on_topic_message(&self, topic: &str, callback: impl FnMut(&Self, &[u8]) + Send + 'static) -> TopicCallbackId {
+        TopicCallbackId(self.imp().on_topic_message(topic, Box::new(callback)))
+    }
+
+    fn remove_on_topic_message(&self, topic: &str, callback: TopicCallbackId) {
+        self.imp().remove_on_topic_message(topic, callback.0)
+    }
+}
@@ -2,17 +2,19 @@
 //!
 //! This module is internal, and may incompatibly change without warning.
 
+use crate::metrics::MetricsCounters;
 use anyhow::{anyhow, bail, Context, Result};
 use bytes::Bytes;
 use futures::{SinkExt, StreamExt as _, TryStreamExt};
 use futures_channel::mpsc;
 use http::uri::{Scheme, Uri};
 use spacetimedb_client_api_messages::websocket::{
-    brotli_decompress, gzip_decompress, BsatnFormat, Compression, SERVER_MSG_COMPRESSION_TAG_BROTLI,
+    brotli_decompress, gzip_decompress, BsatnFormat, Compression, BIN_PROTOCOL, SERVER_MSG_COMPRESSION_TAG_BROTLI,
     SERVER_MSG_COMPRESSION_TAG_GZIP, SERVER_MSG_COMPRESSION_TAG_NONE,
 };
 use spacetimedb_client_api_messages::websocket::{ClientMessage, ServerMessage};
 use spacetimedb_lib::{bsatn, Address};
+use std::sync::Arc;
 use tokio::task::JoinHandle;
 use tokio::{net::TcpStream, runtime};
 use tokio_tungstenite::{
@@ -42,6 +44,7 @@ fn parse_scheme(scheme: Option<Scheme>) -> Result<Scheme> {
 pub(crate) struct WsParams {
     pub compression: Compression,
     pub light: bool,
+    pub replay_request_ids: bool,
 }
 
 fn make_uri<Host>(host: Host, db_name: &str, client_address: Address, params: WsParams) -> Result<Uri>
@@ -88,6 +91,14 @@ where
         path.push_str("&light=true");
     }
 
+    // Ask the host to replay the outcome of reducer calls retried with the same request id.
+    if params.replay_request_ids {
+        path.push_str("&replay_request_ids=true");
+    }
+
+    // Tell the host which SDK we are, so that reducers may read it.
+    path.push_str(concat!("&sdk_language=rust&sdk_version=", env!("CARGO_PKG_VERSION")));
+
     parts.path_and_query = Some(path.parse()?);
     Ok(Uri::from_parts(parts)?)
 }
@@ -125,7 +136,7 @@ fn request_add_header(req: &mut http::Request<()>, key: &'static str, val: http:
 }
 
 const PROTOCOL_HEADER_KEY: &str = "Sec-WebSocket-Protocol";
-const PROTOCOL_HEADER_VALUE: &str = "v1.bsatn.spacetimedb";
+const PROTOCOL_HEADER_VALUE: &str = BIN_PROTOCOL;
 
 fn request_insert_protocol_header(req: &mut http::Request<()>) {
     request_add_header(
@@ -213,6 +224,7 @@ impl WsConnection {
 
     async fn message_loop(
         mut self,
+        metrics: Arc<MetricsCounters>,
         incoming_messages: mpsc::UnboundedSender<ServerMessage<BsatnFormat>>,
         outgoing_messages: mpsc::UnboundedReceiver<ClientMessage<Bytes>>,
     ) {
@@ -228,6 +240,7 @@ impl WsConnection {
                     ),
 
                     Ok(Some(WebSocketMessage::Binary(bytes))) => {
+                        metrics.message_received(bytes.len());
                         match Self::parse_response(&bytes) {
                             Err(e) => Self::maybe_log_error::<(), _>(
                                 "Error decoding WebSocketMessage::Binary payload",
@@ -249,6 +262,7 @@ impl WsConnection {
                 Some(outgoing) = async { Some(outgoing_messages.as_mut()?.next().await) } => match outgoing {
                     Some(outgoing) => {
                         let msg = Self::encode_message(outgoing);
+                        metrics.message_sent(msg.len());
                         Self::maybe_log_error(
                             "Error sending outgoing message",
                                 self.sock.send(msg).await,
@@ -265,6 +279,7 @@ impl WsConnection {
 
     pub(crate) fn spawn_message_loop(
         self,
+        metrics: Arc<MetricsCounters>,
         runtime: &runtime::Handle,
     ) -> (
         JoinHandle<()>,
@@ -273,7 +288,7 @@ impl WsConnection {
     ) {
         let (outgoing_send, outgoing_recv) = mpsc::unbounded();
         let (incoming_send, incoming_recv) = mpsc::unbounded();
-        let handle = runtime.spawn(self.message_loop(incoming_send, outgoing_recv));
+        let handle = runtime.spawn(self.message_loop(metrics, incoming_send, outgoing_recv));
         (handle, incoming_recv, outgoing_send)
     }
 }
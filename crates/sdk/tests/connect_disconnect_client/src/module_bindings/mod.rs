@@ -95,9 +95,9 @@ impl __sdk::InModule for DbUpdate {
 }
 
 impl __sdk::DbUpdate for DbUpdate {
-    fn apply_to_client_cache(&self, cache: &mut __sdk::ClientCache<RemoteModule>) {
-        cache.apply_diff_to_table::<Connected>("connected", &self.connected);
-        cache.apply_diff_to_table::<Disconnected>("disconnected", &self.disconnected);
+    fn apply_to_client_cache(&mut self, cache: &mut __sdk::ClientCache<RemoteModule>) {
+        cache.apply_diff_to_table::<Connected>("connected", &mut self.connected);
+        cache.apply_diff_to_table::<Disconnected>("disconnected", &mut self.disconnected);
     }
     fn invoke_row_callbacks(&self, event: &EventContext, callbacks: &mut __sdk::DbCallbacks<RemoteModule>) {
         callbacks.invoke_table_row_callbacks::<Connected>("connected", &self.connected, event);
@@ -298,6 +298,10 @@ impl __sdk::DbConnection for DbConnection {
             imp,
         }
     }
+
+    fn imp(&self) -> &__sdk::DbContextImpl<RemoteModule> {
+        &self.imp
+    }
 }
 
 /// A [`DbConnection`] augmented with an [`__sdk::Event`],
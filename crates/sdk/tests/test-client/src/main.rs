@@ -7,8 +7,8 @@ use core::fmt::Display;
 use module_bindings::*;
 
 use spacetimedb_sdk::{
-    credentials, i256, u256, unstable::CallReducerFlags, Address, DbConnectionBuilder, DbContext, Event, Identity,
-    ReducerEvent, Status, Table,
+    credentials, i256, u256, unstable::CallReducerFlags, Address, DbConnectionBuilder, DbConnectionMetricsExt,
+    DbContext, Event, Identity, ReducerEvent, Status, Table,
 };
 use test_counter::TestCounter;
 
@@ -94,6 +94,8 @@ fn main() {
         "caller_alice_receives_reducer_callback_but_not_bob" => {
             exec_caller_alice_receives_reducer_callback_but_not_bob()
         }
+
+        "connection_metrics" => exec_connection_metrics(),
         _ => panic!("Unknown test: {}", test),
     }
 }
@@ -1732,3 +1734,34 @@ fn exec_caller_alice_receives_reducer_callback_but_not_bob() {
     // We do this after `run_threaded` so that the ids have been filled.
     assert_ne!(conns[0].identity(), conns[1].identity());
 }
+
+/// This tests that the metrics of a connection account for
+/// the messages it exchanged with the host, the rows in its client cache,
+/// and the time spent running its callbacks.
+fn exec_connection_metrics() {
+    let test_counter = TestCounter::new();
+
+    let sub_applied_nothing_result = test_counter.add_test("on_subscription_applied_nothing");
+
+    let connection = connect(&test_counter);
+
+    subscribe_all_then(&connection, {
+        let test_counter = test_counter.clone();
+        move |ctx| {
+            insert_one::<OneU8>(ctx, &test_counter, 0);
+
+            sub_applied_nothing_result(assert_all_tables_empty(ctx));
+        }
+    });
+
+    test_counter.wait_for_all();
+
+    let metrics = connection.metrics();
+    // We sent the subscription and the reducer call,
+    // and received our identity, the subscribed rows and the reducer's transaction.
+    assert!(metrics.messages_sent >= 2, "{metrics:?}");
+    assert!(metrics.messages_received >= 3, "{metrics:?}");
+    assert!(metrics.bytes_sent > 0 && metrics.bytes_received > 0, "{metrics:?}");
+    assert_eq!(metrics.cache_rows.get("one_u8"), Some(&1), "{metrics:?}");
+    assert!(!metrics.callback_time.is_zero(), "{metrics:?}");
+}
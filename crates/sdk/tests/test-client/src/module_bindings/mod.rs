@@ -2785,107 +2785,107 @@ impl __sdk::InModule for DbUpdate {
 }
 
 impl __sdk::DbUpdate for DbUpdate {
-    fn apply_to_client_cache(&self, cache: &mut __sdk::ClientCache<RemoteModule>) {
-        cache.apply_diff_to_table::<IndexedTable>("indexed_table", &self.indexed_table);
-        cache.apply_diff_to_table::<IndexedTable2>("indexed_table_2", &self.indexed_table_2);
-        cache.apply_diff_to_table::<LargeTable>("large_table", &self.large_table);
-        cache.apply_diff_to_table::<OneAddress>("one_address", &self.one_address);
-        cache.apply_diff_to_table::<OneBool>("one_bool", &self.one_bool);
-        cache.apply_diff_to_table::<OneByteStruct>("one_byte_struct", &self.one_byte_struct);
-        cache.apply_diff_to_table::<OneEnumWithPayload>("one_enum_with_payload", &self.one_enum_with_payload);
+    fn apply_to_client_cache(&mut self, cache: &mut __sdk::ClientCache<RemoteModule>) {
+        cache.apply_diff_to_table::<IndexedTable>("indexed_table", &mut self.indexed_table);
+        cache.apply_diff_to_table::<IndexedTable2>("indexed_table_2", &mut self.indexed_table_2);
+        cache.apply_diff_to_table::<LargeTable>("large_table", &mut self.large_table);
+        cache.apply_diff_to_table::<OneAddress>("one_address", &mut self.one_address);
+        cache.apply_diff_to_table::<OneBool>("one_bool", &mut self.one_bool);
+        cache.apply_diff_to_table::<OneByteStruct>("one_byte_struct", &mut self.one_byte_struct);
+        cache.apply_diff_to_table::<OneEnumWithPayload>("one_enum_with_payload", &mut self.one_enum_with_payload);
         cache.apply_diff_to_table::<OneEveryPrimitiveStruct>(
             "one_every_primitive_struct",
             &self.one_every_primitive_struct,
         );
-        cache.apply_diff_to_table::<OneEveryVecStruct>("one_every_vec_struct", &self.one_every_vec_struct);
-        cache.apply_diff_to_table::<OneF32>("one_f32", &self.one_f_32);
-        cache.apply_diff_to_table::<OneF64>("one_f64", &self.one_f_64);
-        cache.apply_diff_to_table::<OneI128>("one_i128", &self.one_i_128);
-        cache.apply_diff_to_table::<OneI16>("one_i16", &self.one_i_16);
-        cache.apply_diff_to_table::<OneI256>("one_i256", &self.one_i_256);
-        cache.apply_diff_to_table::<OneI32>("one_i32", &self.one_i_32);
-        cache.apply_diff_to_table::<OneI64>("one_i64", &self.one_i_64);
-        cache.apply_diff_to_table::<OneI8>("one_i8", &self.one_i_8);
-        cache.apply_diff_to_table::<OneIdentity>("one_identity", &self.one_identity);
-        cache.apply_diff_to_table::<OneSimpleEnum>("one_simple_enum", &self.one_simple_enum);
-        cache.apply_diff_to_table::<OneString>("one_string", &self.one_string);
-        cache.apply_diff_to_table::<OneU128>("one_u128", &self.one_u_128);
-        cache.apply_diff_to_table::<OneU16>("one_u16", &self.one_u_16);
-        cache.apply_diff_to_table::<OneU256>("one_u256", &self.one_u_256);
-        cache.apply_diff_to_table::<OneU32>("one_u32", &self.one_u_32);
-        cache.apply_diff_to_table::<OneU64>("one_u64", &self.one_u_64);
-        cache.apply_diff_to_table::<OneU8>("one_u8", &self.one_u_8);
-        cache.apply_diff_to_table::<OneUnitStruct>("one_unit_struct", &self.one_unit_struct);
+        cache.apply_diff_to_table::<OneEveryVecStruct>("one_every_vec_struct", &mut self.one_every_vec_struct);
+        cache.apply_diff_to_table::<OneF32>("one_f32", &mut self.one_f_32);
+        cache.apply_diff_to_table::<OneF64>("one_f64", &mut self.one_f_64);
+        cache.apply_diff_to_table::<OneI128>("one_i128", &mut self.one_i_128);
+        cache.apply_diff_to_table::<OneI16>("one_i16", &mut self.one_i_16);
+        cache.apply_diff_to_table::<OneI256>("one_i256", &mut self.one_i_256);
+        cache.apply_diff_to_table::<OneI32>("one_i32", &mut self.one_i_32);
+        cache.apply_diff_to_table::<OneI64>("one_i64", &mut self.one_i_64);
+        cache.apply_diff_to_table::<OneI8>("one_i8", &mut self.one_i_8);
+        cache.apply_diff_to_table::<OneIdentity>("one_identity", &mut self.one_identity);
+        cache.apply_diff_to_table::<OneSimpleEnum>("one_simple_enum", &mut self.one_simple_enum);
+        cache.apply_diff_to_table::<OneString>("one_string", &mut self.one_string);
+        cache.apply_diff_to_table::<OneU128>("one_u128", &mut self.one_u_128);
+        cache.apply_diff_to_table::<OneU16>("one_u16", &mut self.one_u_16);
+        cache.apply_diff_to_table::<OneU256>("one_u256", &mut self.one_u_256);
+        cache.apply_diff_to_table::<OneU32>("one_u32", &mut self.one_u_32);
+        cache.apply_diff_to_table::<OneU64>("one_u64", &mut self.one_u_64);
+        cache.apply_diff_to_table::<OneU8>("one_u8", &mut self.one_u_8);
+        cache.apply_diff_to_table::<OneUnitStruct>("one_unit_struct", &mut self.one_unit_struct);
         cache.apply_diff_to_table::<OptionEveryPrimitiveStruct>(
             "option_every_primitive_struct",
             &self.option_every_primitive_struct,
         );
-        cache.apply_diff_to_table::<OptionI32>("option_i32", &self.option_i_32);
-        cache.apply_diff_to_table::<OptionIdentity>("option_identity", &self.option_identity);
-        cache.apply_diff_to_table::<OptionSimpleEnum>("option_simple_enum", &self.option_simple_enum);
-        cache.apply_diff_to_table::<OptionString>("option_string", &self.option_string);
-        cache.apply_diff_to_table::<OptionVecOptionI32>("option_vec_option_i32", &self.option_vec_option_i_32);
-        cache.apply_diff_to_table::<PkAddress>("pk_address", &self.pk_address);
-        cache.apply_diff_to_table::<PkBool>("pk_bool", &self.pk_bool);
-        cache.apply_diff_to_table::<PkI128>("pk_i128", &self.pk_i_128);
-        cache.apply_diff_to_table::<PkI16>("pk_i16", &self.pk_i_16);
-        cache.apply_diff_to_table::<PkI256>("pk_i256", &self.pk_i_256);
-        cache.apply_diff_to_table::<PkI32>("pk_i32", &self.pk_i_32);
-        cache.apply_diff_to_table::<PkI64>("pk_i64", &self.pk_i_64);
-        cache.apply_diff_to_table::<PkI8>("pk_i8", &self.pk_i_8);
-        cache.apply_diff_to_table::<PkIdentity>("pk_identity", &self.pk_identity);
-        cache.apply_diff_to_table::<PkString>("pk_string", &self.pk_string);
-        cache.apply_diff_to_table::<PkU128>("pk_u128", &self.pk_u_128);
-        cache.apply_diff_to_table::<PkU16>("pk_u16", &self.pk_u_16);
-        cache.apply_diff_to_table::<PkU256>("pk_u256", &self.pk_u_256);
-        cache.apply_diff_to_table::<PkU32>("pk_u32", &self.pk_u_32);
-        cache.apply_diff_to_table::<PkU64>("pk_u64", &self.pk_u_64);
-        cache.apply_diff_to_table::<PkU8>("pk_u8", &self.pk_u_8);
-        cache.apply_diff_to_table::<ScheduledTable>("scheduled_table", &self.scheduled_table);
-        cache.apply_diff_to_table::<TableHoldsTable>("table_holds_table", &self.table_holds_table);
-        cache.apply_diff_to_table::<UniqueAddress>("unique_address", &self.unique_address);
-        cache.apply_diff_to_table::<UniqueBool>("unique_bool", &self.unique_bool);
-        cache.apply_diff_to_table::<UniqueI128>("unique_i128", &self.unique_i_128);
-        cache.apply_diff_to_table::<UniqueI16>("unique_i16", &self.unique_i_16);
-        cache.apply_diff_to_table::<UniqueI256>("unique_i256", &self.unique_i_256);
-        cache.apply_diff_to_table::<UniqueI32>("unique_i32", &self.unique_i_32);
-        cache.apply_diff_to_table::<UniqueI64>("unique_i64", &self.unique_i_64);
-        cache.apply_diff_to_table::<UniqueI8>("unique_i8", &self.unique_i_8);
-        cache.apply_diff_to_table::<UniqueIdentity>("unique_identity", &self.unique_identity);
-        cache.apply_diff_to_table::<UniqueString>("unique_string", &self.unique_string);
-        cache.apply_diff_to_table::<UniqueU128>("unique_u128", &self.unique_u_128);
-        cache.apply_diff_to_table::<UniqueU16>("unique_u16", &self.unique_u_16);
-        cache.apply_diff_to_table::<UniqueU256>("unique_u256", &self.unique_u_256);
-        cache.apply_diff_to_table::<UniqueU32>("unique_u32", &self.unique_u_32);
-        cache.apply_diff_to_table::<UniqueU64>("unique_u64", &self.unique_u_64);
-        cache.apply_diff_to_table::<UniqueU8>("unique_u8", &self.unique_u_8);
-        cache.apply_diff_to_table::<VecAddress>("vec_address", &self.vec_address);
-        cache.apply_diff_to_table::<VecBool>("vec_bool", &self.vec_bool);
-        cache.apply_diff_to_table::<VecByteStruct>("vec_byte_struct", &self.vec_byte_struct);
-        cache.apply_diff_to_table::<VecEnumWithPayload>("vec_enum_with_payload", &self.vec_enum_with_payload);
+        cache.apply_diff_to_table::<OptionI32>("option_i32", &mut self.option_i_32);
+        cache.apply_diff_to_table::<OptionIdentity>("option_identity", &mut self.option_identity);
+        cache.apply_diff_to_table::<OptionSimpleEnum>("option_simple_enum", &mut self.option_simple_enum);
+        cache.apply_diff_to_table::<OptionString>("option_string", &mut self.option_string);
+        cache.apply_diff_to_table::<OptionVecOptionI32>("option_vec_option_i32", &mut self.option_vec_option_i_32);
+        cache.apply_diff_to_table::<PkAddress>("pk_address", &mut self.pk_address);
+        cache.apply_diff_to_table::<PkBool>("pk_bool", &mut self.pk_bool);
+        cache.apply_diff_to_table::<PkI128>("pk_i128", &mut self.pk_i_128);
+        cache.apply_diff_to_table::<PkI16>("pk_i16", &mut self.pk_i_16);
+        cache.apply_diff_to_table::<PkI256>("pk_i256", &mut self.pk_i_256);
+        cache.apply_diff_to_table::<PkI32>("pk_i32", &mut self.pk_i_32);
+        cache.apply_diff_to_table::<PkI64>("pk_i64", &mut self.pk_i_64);
+        cache.apply_diff_to_table::<PkI8>("pk_i8", &mut self.pk_i_8);
+        cache.apply_diff_to_table::<PkIdentity>("pk_identity", &mut self.pk_identity);
+        cache.apply_diff_to_table::<PkString>("pk_string", &mut self.pk_string);
+        cache.apply_diff_to_table::<PkU128>("pk_u128", &mut self.pk_u_128);
+        cache.apply_diff_to_table::<PkU16>("pk_u16", &mut self.pk_u_16);
+        cache.apply_diff_to_table::<PkU256>("pk_u256", &mut self.pk_u_256);
+        cache.apply_diff_to_table::<PkU32>("pk_u32", &mut self.pk_u_32);
+        cache.apply_diff_to_table::<PkU64>("pk_u64", &mut self.pk_u_64);
+        cache.apply_diff_to_table::<PkU8>("pk_u8", &mut self.pk_u_8);
+        cache.apply_diff_to_table::<ScheduledTable>("scheduled_table", &mut self.scheduled_table);
+        cache.apply_diff_to_table::<TableHoldsTable>("table_holds_table", &mut self.table_holds_table);
+        cache.apply_diff_to_table::<UniqueAddress>("unique_address", &mut self.unique_address);
+        cache.apply_diff_to_table::<UniqueBool>("unique_bool", &mut self.unique_bool);
+        cache.apply_diff_to_table::<UniqueI128>("unique_i128", &mut self.unique_i_128);
+        cache.apply_diff_to_table::<UniqueI16>("unique_i16", &mut self.unique_i_16);
+        cache.apply_diff_to_table::<UniqueI256>("unique_i256", &mut self.unique_i_256);
+        cache.apply_diff_to_table::<UniqueI32>("unique_i32", &mut self.unique_i_32);
+        cache.apply_diff_to_table::<UniqueI64>("unique_i64", &mut self.unique_i_64);
+        cache.apply_diff_to_table::<UniqueI8>("unique_i8", &mut self.unique_i_8);
+        cache.apply_diff_to_table::<UniqueIdentity>("unique_identity", &mut self.unique_identity);
+        cache.apply_diff_to_table::<UniqueString>("unique_string", &mut self.unique_string);
+        cache.apply_diff_to_table::<UniqueU128>("unique_u128", &mut self.unique_u_128);
+        cache.apply_diff_to_table::<UniqueU16>("unique_u16", &mut self.unique_u_16);
+        cache.apply_diff_to_table::<UniqueU256>("unique_u256", &mut self.unique_u_256);
+        cache.apply_diff_to_table::<UniqueU32>("unique_u32", &mut self.unique_u_32);
+        cache.apply_diff_to_table::<UniqueU64>("unique_u64", &mut self.unique_u_64);
+        cache.apply_diff_to_table::<UniqueU8>("unique_u8", &mut self.unique_u_8);
+        cache.apply_diff_to_table::<VecAddress>("vec_address", &mut self.vec_address);
+        cache.apply_diff_to_table::<VecBool>("vec_bool", &mut self.vec_bool);
+        cache.apply_diff_to_table::<VecByteStruct>("vec_byte_struct", &mut self.vec_byte_struct);
+        cache.apply_diff_to_table::<VecEnumWithPayload>("vec_enum_with_payload", &mut self.vec_enum_with_payload);
         cache.apply_diff_to_table::<VecEveryPrimitiveStruct>(
             "vec_every_primitive_struct",
             &self.vec_every_primitive_struct,
         );
-        cache.apply_diff_to_table::<VecEveryVecStruct>("vec_every_vec_struct", &self.vec_every_vec_struct);
-        cache.apply_diff_to_table::<VecF32>("vec_f32", &self.vec_f_32);
-        cache.apply_diff_to_table::<VecF64>("vec_f64", &self.vec_f_64);
-        cache.apply_diff_to_table::<VecI128>("vec_i128", &self.vec_i_128);
-        cache.apply_diff_to_table::<VecI16>("vec_i16", &self.vec_i_16);
-        cache.apply_diff_to_table::<VecI256>("vec_i256", &self.vec_i_256);
-        cache.apply_diff_to_table::<VecI32>("vec_i32", &self.vec_i_32);
-        cache.apply_diff_to_table::<VecI64>("vec_i64", &self.vec_i_64);
-        cache.apply_diff_to_table::<VecI8>("vec_i8", &self.vec_i_8);
-        cache.apply_diff_to_table::<VecIdentity>("vec_identity", &self.vec_identity);
-        cache.apply_diff_to_table::<VecSimpleEnum>("vec_simple_enum", &self.vec_simple_enum);
-        cache.apply_diff_to_table::<VecString>("vec_string", &self.vec_string);
-        cache.apply_diff_to_table::<VecU128>("vec_u128", &self.vec_u_128);
-        cache.apply_diff_to_table::<VecU16>("vec_u16", &self.vec_u_16);
-        cache.apply_diff_to_table::<VecU256>("vec_u256", &self.vec_u_256);
-        cache.apply_diff_to_table::<VecU32>("vec_u32", &self.vec_u_32);
-        cache.apply_diff_to_table::<VecU64>("vec_u64", &self.vec_u_64);
-        cache.apply_diff_to_table::<VecU8>("vec_u8", &self.vec_u_8);
-        cache.apply_diff_to_table::<VecUnitStruct>("vec_unit_struct", &self.vec_unit_struct);
+        cache.apply_diff_to_table::<VecEveryVecStruct>("vec_every_vec_struct", &mut self.vec_every_vec_struct);
+        cache.apply_diff_to_table::<VecF32>("vec_f32", &mut self.vec_f_32);
+        cache.apply_diff_to_table::<VecF64>("vec_f64", &mut self.vec_f_64);
+        cache.apply_diff_to_table::<VecI128>("vec_i128", &mut self.vec_i_128);
+        cache.apply_diff_to_table::<VecI16>("vec_i16", &mut self.vec_i_16);
+        cache.apply_diff_to_table::<VecI256>("vec_i256", &mut self.vec_i_256);
+        cache.apply_diff_to_table::<VecI32>("vec_i32", &mut self.vec_i_32);
+        cache.apply_diff_to_table::<VecI64>("vec_i64", &mut self.vec_i_64);
+        cache.apply_diff_to_table::<VecI8>("vec_i8", &mut self.vec_i_8);
+        cache.apply_diff_to_table::<VecIdentity>("vec_identity", &mut self.vec_identity);
+        cache.apply_diff_to_table::<VecSimpleEnum>("vec_simple_enum", &mut self.vec_simple_enum);
+        cache.apply_diff_to_table::<VecString>("vec_string", &mut self.vec_string);
+        cache.apply_diff_to_table::<VecU128>("vec_u128", &mut self.vec_u_128);
+        cache.apply_diff_to_table::<VecU16>("vec_u16", &mut self.vec_u_16);
+        cache.apply_diff_to_table::<VecU256>("vec_u256", &mut self.vec_u_256);
+        cache.apply_diff_to_table::<VecU32>("vec_u32", &mut self.vec_u_32);
+        cache.apply_diff_to_table::<VecU64>("vec_u64", &mut self.vec_u_64);
+        cache.apply_diff_to_table::<VecU8>("vec_u8", &mut self.vec_u_8);
+        cache.apply_diff_to_table::<VecUnitStruct>("vec_unit_struct", &mut self.vec_unit_struct);
     }
     fn invoke_row_callbacks(&self, event: &EventContext, callbacks: &mut __sdk::DbCallbacks<RemoteModule>) {
         callbacks.invoke_table_row_callbacks::<IndexedTable>("indexed_table", &self.indexed_table, event);
@@ -3207,6 +3207,10 @@ impl __sdk::DbConnection for DbConnection {
             imp,
         }
     }
+
+    fn imp(&self) -> &__sdk::DbContextImpl<RemoteModule> {
+        &self.imp
+    }
 }
 
 /// A [`DbConnection`] augmented with an [`__sdk::Event`],
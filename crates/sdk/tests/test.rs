@@ -176,6 +176,11 @@ macro_rules! declare_tests_with_suffix {
             fn caller_alice_receives_reducer_callback_but_not_bob() {
                 make_test("caller_alice_receives_reducer_callback_but_not_bob").run();
             }
+
+            #[test]
+            fn connection_metrics() {
+                make_test("connection_metrics").run();
+            }
         }
     };
 }
@@ -183,3 +188,18 @@ macro_rules! declare_tests_with_suffix {
 declare_tests_with_suffix!(rust, "");
 // TODO: migrate csharp to snake_case table names
 declare_tests_with_suffix!(csharp, "-cs");
+
+// TODO: add a C# module with a topic authorizer, and run this test against it too.
+#[test]
+fn topic_subscriptions() {
+    spacetimedb_testing::sdk::Test::builder()
+        .with_name("topic_subscriptions")
+        .with_module("topics-test")
+        .with_client(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/topics_client"))
+        .with_language("rust")
+        .with_bindings_dir("src/module_bindings")
+        .with_compile_command("cargo build")
+        .with_run_command("cargo run")
+        .build()
+        .run();
+}
@@ -0,0 +1,90 @@
+mod module_bindings;
+
+use module_bindings::*;
+
+use spacetimedb_sdk::{DbConnectionTopicsExt, DbContext, Event, ReducerEvent, Status};
+
+use test_counter::TestCounter;
+
+const LOCALHOST: &str = "http://localhost:3000";
+
+const PUBLIC_TOPIC: &str = "public/chat";
+const PRIVATE_TOPIC: &str = "private/secrets";
+
+fn db_name_or_panic() -> String {
+    std::env::var("SPACETIME_SDK_TEST_DB_NAME").expect("Failed to read db name from env")
+}
+
+fn main() {
+    let authorize_test_counter = TestCounter::new();
+    let connected_result = authorize_test_counter.add_test("on_connect");
+    let mut public_authorized_result = Some(authorize_test_counter.add_test("public_authorized"));
+    let mut private_refused_result = Some(authorize_test_counter.add_test("private_refused"));
+
+    let connection = DbConnection::builder()
+        .with_module_name(db_name_or_panic())
+        .with_uri(LOCALHOST)
+        .on_connect_error(|e| panic!("on_connect_error: {e:?}"))
+        .on_connect(move |ctx, _, _| {
+            ctx.subscribe_topic(PRIVATE_TOPIC).unwrap();
+            ctx.subscribe_topic(PUBLIC_TOPIC).unwrap();
+            connected_result(Ok(()));
+        })
+        .build()
+        .unwrap();
+
+    connection.reducers.on_authorize_topic(move |ctx, topic| {
+        let Event::Reducer(ReducerEvent { status, .. }) = &ctx.event else {
+            unreachable!()
+        };
+        let result = match (&topic[..], status) {
+            (PUBLIC_TOPIC, Status::Committed) | (PRIVATE_TOPIC, Status::Failed(_)) => Ok(()),
+            _ => Err(anyhow::anyhow!("Unexpected status {status:?} authorizing {topic}")),
+        };
+        let put_result = if topic == PUBLIC_TOPIC {
+            &mut public_authorized_result
+        } else {
+            &mut private_refused_result
+        };
+        if let Some(put_result) = put_result.take() {
+            put_result(result);
+        }
+    });
+
+    connection.run_threaded();
+
+    authorize_test_counter.wait_for_all();
+
+    let message_test_counter = TestCounter::new();
+    let mut public_message_result = Some(message_test_counter.add_test("public_message"));
+
+    connection.on_topic_message(PRIVATE_TOPIC, |_, payload| {
+        panic!("Received {payload:?} on {PRIVATE_TOPIC}, though the module refused the subscription")
+    });
+    connection.on_topic_message(PUBLIC_TOPIC, move |_, payload| {
+        let result = if payload == b"hello" {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "Expected b\"hello\" on {PUBLIC_TOPIC}, but got {payload:?}"
+            ))
+        };
+        if let Some(put_result) = public_message_result.take() {
+            put_result(result);
+        }
+    });
+
+    // Broadcast on the private topic first, so that its message would arrive before the public one.
+    connection
+        .reducers
+        .say(PRIVATE_TOPIC.to_string(), "psst".to_string())
+        .unwrap();
+    connection
+        .reducers
+        .say(PUBLIC_TOPIC.to_string(), "hello".to_string())
+        .unwrap();
+
+    message_test_counter.wait_for_all();
+
+    connection.disconnect().unwrap();
+}
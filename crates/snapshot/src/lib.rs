@@ -11,8 +11,16 @@
 //! - Creating a snapshot given a view of a DB's committed state in [`SnapshotRepository::create_snapshot`].
 //! - Reading an on-disk snapshot into memory as a [`ReconstructedSnapshot`] in [`SnapshotRepository::read_snapshot`].
 //!   The [`ReconstructedSnapshot`] can then be installed into a datastore.
+//! - Reading the pages of individual tables from an on-disk snapshot in [`SnapshotRepository::open_snapshot`].
 //! - Locating the most-recent snapshot of a DB, or the most recent snapshot not newer than a given tx offset,
 //!   in [`SnapshotRepository::latest_snapshot`] and [`SnapshotRepository::latest_snapshot_older_than`].
+//! - Removing the objects which no valid snapshot refers to in [`SnapshotRepository::gc`].
+//! - Verifying the objects of a snapshot in [`SnapshotRepository::verify_snapshot`],
+//!   and re-fetching the corrupted ones from another source in [`SnapshotRepository::repair_from`].
+//! - Compressing the objects of old snapshots in [`SnapshotRepository::compress_snapshots`].
+//! - Comparing two snapshots in [`SnapshotRepository::diff`], and transferring the difference
+//!   as an archive written by [`SnapshotRepository::write_diff_archive`]
+//!   and applied by [`SnapshotRepository::apply_diff_archive`], for incremental backups.
 //!
 //! This crate *is not* responsible for:
 //! - Determining when to capture snapshots.
@@ -23,6 +31,7 @@
 
 #![allow(clippy::result_large_err)]
 
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
 use spacetimedb_durability::TxOffset;
 use spacetimedb_fs_utils::{
     dir_trie::{o_excl, o_rdonly, CountCreated, DirTrie},
@@ -42,10 +51,12 @@ use spacetimedb_table::{
     table::Table,
 };
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap, HashSet},
     ffi::OsStr,
+    fs::Metadata,
     io::{Read, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    time::SystemTime,
 };
 
 #[derive(Debug, Copy, Clone)]
@@ -112,6 +123,8 @@ pub enum SnapshotError {
     BadVersion { tx_offset: TxOffset, version: u8 },
     #[error("Cannot open snapshot repository in non-directory {root:?}")]
     NotDirectory { root: SnapshotsPath },
+    #[error("Refusing to apply snapshot diff archive: {0}")]
+    BadDiffArchive(String),
     #[error(transparent)]
     Lockfile(#[from] LockfileError),
     #[error(transparent)]
@@ -139,6 +152,20 @@ pub const SNAPSHOT_FILE_EXT: &str = "snapshot_bsatn";
 /// File extension of snapshots which have been marked invalid by [`SnapshotRepository::invalidate_newer_snapshots`].
 pub const INVALID_SNAPSHOT_DIR_EXT: &str = "invalid_snapshot";
 
+/// Magic number preceding the deflated contents of object files
+/// compressed by [`SnapshotRepository::compress_snapshots`].
+///
+/// Objects are hashed and verified by their uncompressed contents,
+/// so an uncompressed object which happens to start with this number is still read correctly
+/// unless the rest of it is also a valid deflate stream.
+pub const COMPRESSED_OBJECT_MAGIC: [u8; 8] = *b"txyz\0dfl";
+
+/// Magic number for the archives written by [`SnapshotRepository::write_diff_archive`].
+pub const DIFF_ARCHIVE_MAGIC: [u8; 4] = *b"txyd";
+
+/// Diff archive format version number.
+pub const CURRENT_DIFF_ARCHIVE_VERSION: u8 = 0;
+
 #[derive(Serialize, Deserialize)]
 /// The hash and refcount of a single blob in the blob store.
 struct BlobEntry {
@@ -344,6 +371,24 @@ impl Snapshot {
         Ok(snapshot)
     }
 
+    /// Write `self` to a new file at `path`, preceded by its hash.
+    ///
+    /// Fails if a file already exists at `path`.
+    fn write_to_file(&self, path: &SnapshotFilePath) -> Result<(), SnapshotError> {
+        // Serialize and hash the in-memory `Snapshot` object.
+        let snapshot_bsatn = bsatn::to_vec(self).map_err(|cause| SnapshotError::Serialize {
+            ty: ObjectType::Snapshot,
+            cause,
+        })?;
+        let hash = blake3::hash(&snapshot_bsatn);
+
+        // Create the snapshot file, containing first the hash, then the `Snapshot`.
+        let mut snapshot_file = path.open_file(&o_excl())?;
+        snapshot_file.write_all(hash.as_bytes())?;
+        snapshot_file.write_all(&snapshot_bsatn)?;
+        Ok(())
+    }
+
     /// Construct a [`HashMapBlobStore`] containing all the blobs referenced in `self`,
     /// reading their data from files in the `object_repo`.
     ///
@@ -354,13 +399,7 @@ impl Snapshot {
 
         for BlobEntry { hash, uses } in &self.blobs {
             // Read the bytes of the blob object.
-            let buf = object_repo
-                .read_entry(&hash.data)
-                .map_err(|cause| SnapshotError::ReadObject {
-                    ty: ObjectType::Blob(*hash),
-                    source_repo: object_repo.root().to_path_buf(),
-                    cause,
-                })?;
+            let buf = read_object(object_repo, ObjectType::Blob(*hash))?;
 
             // Compute the blob's hash.
             let computed_hash = BlobHash::hash_from_bytes(&buf);
@@ -382,6 +421,38 @@ impl Snapshot {
         Ok(blob_store)
     }
 
+    /// Read the page with hash `hash` from the `object_repo`.
+    ///
+    /// Fails if the page file is missing or corrupted,
+    /// as detected by comparing the hash of its bytes to `hash`.
+    fn read_page(object_repo: &DirTrie, hash: &blake3::Hash) -> Result<Box<Page>, SnapshotError> {
+        // Read the BSATN bytes of the on-disk page object.
+        let buf = read_object(object_repo, ObjectType::Page(*hash))?;
+
+        // Deserialize the bytes into a `Page`.
+        let page = bsatn::from_slice::<Box<Page>>(&buf).map_err(|cause| SnapshotError::Deserialize {
+            ty: ObjectType::Page(*hash),
+            source_repo: object_repo.root().to_path_buf(),
+            cause,
+        })?;
+
+        // Compute the hash of the page.
+        let computed_hash = page.content_hash();
+
+        // Compare the computed hash to the one recorded in the `Snapshot`,
+        // and fail if they do not match.
+        if *hash != computed_hash {
+            return Err(SnapshotError::HashMismatch {
+                ty: ObjectType::Page(*hash),
+                expected: *hash.as_bytes(),
+                computed: *computed_hash.as_bytes(),
+                source_repo: object_repo.root().to_path_buf(),
+            });
+        }
+
+        Ok(page)
+    }
+
     /// Read all the pages referenced by `pages` from the `object_repo`.
     ///
     /// Fails if any of the pages files is missing or corrupted,
@@ -390,42 +461,7 @@ impl Snapshot {
         object_repo: &DirTrie,
         pages: &[blake3::Hash],
     ) -> Result<Vec<Box<Page>>, SnapshotError> {
-        pages
-            .iter()
-            .map(|hash| {
-                // Read the BSATN bytes of the on-disk page object.
-                let buf = object_repo
-                    .read_entry(hash.as_bytes())
-                    .map_err(|cause| SnapshotError::ReadObject {
-                        ty: ObjectType::Page(*hash),
-                        source_repo: object_repo.root().to_path_buf(),
-                        cause,
-                    })?;
-
-                // Deserialize the bytes into a `Page`.
-                let page = bsatn::from_slice::<Box<Page>>(&buf).map_err(|cause| SnapshotError::Deserialize {
-                    ty: ObjectType::Page(*hash),
-                    source_repo: object_repo.root().to_path_buf(),
-                    cause,
-                })?;
-
-                // Compute the hash of the page.
-                let computed_hash = page.content_hash();
-
-                // Compare the computed hash to the one recorded in the `Snapshot`,
-                // and fail if they do not match.
-                if *hash != computed_hash {
-                    return Err(SnapshotError::HashMismatch {
-                        ty: ObjectType::Page(*hash),
-                        expected: *hash.as_bytes(),
-                        computed: *computed_hash.as_bytes(),
-                        source_repo: object_repo.root().to_path_buf(),
-                    });
-                }
-
-                Ok::<Box<Page>, SnapshotError>(page)
-            })
-            .collect()
+        pages.iter().map(|hash| Self::read_page(object_repo, hash)).collect()
     }
 
     fn reconstruct_one_table(
@@ -435,7 +471,7 @@ impl Snapshot {
         Ok((*table_id, Self::reconstruct_one_table_pages(object_repo, pages)?))
     }
 
-    /// Reconstruct all the table data from `self`,
+    /// Reconstruct the table data from `self` of all tables for which `filter` returns `true`,
     /// reading pages from files in the `object_repo`.
     ///
     /// This method cannot construct [`Table`] objects
@@ -445,13 +481,55 @@ impl Snapshot {
     /// Fails if any object file referenced in `self` (as a page or large blob)
     /// is missing or corrupted,
     /// as detected by comparing the hash of its bytes to the hash recorded in `self`.
-    fn reconstruct_tables(&self, object_repo: &DirTrie) -> Result<BTreeMap<TableId, Vec<Box<Page>>>, SnapshotError> {
+    fn reconstruct_tables(
+        &self,
+        object_repo: &DirTrie,
+        filter: impl Fn(TableId) -> bool,
+    ) -> Result<BTreeMap<TableId, Vec<Box<Page>>>, SnapshotError> {
         self.tables
             .iter()
+            .filter(|tbl| filter(tbl.table_id))
             .map(|tbl| Self::reconstruct_one_table(object_repo, tbl))
             .collect()
     }
 
+    /// Obtain an iterator over all objects this snapshot is referring to, as [`ObjectType`]s.
+    fn object_types(&self) -> impl Iterator<Item = ObjectType> + '_ {
+        self.blobs.iter().map(|b| ObjectType::Blob(b.hash)).chain(
+            self.tables
+                .iter()
+                .flat_map(|t| t.pages.iter().copied().map(ObjectType::Page)),
+        )
+    }
+
+    /// Check that `buf` holds the object `ty`,
+    /// as detected by comparing the hash of its contents to the hash recorded for it.
+    ///
+    /// `ty` must be a blob or a page.
+    fn check_object(ty: ObjectType, buf: &[u8], source_repo: &Path) -> Result<(), SnapshotError> {
+        let (expected, computed) = match ty {
+            ObjectType::Blob(hash) => (hash.data, BlobHash::hash_from_bytes(buf).data),
+            ObjectType::Page(hash) => {
+                let page = bsatn::from_slice::<Box<Page>>(buf).map_err(|cause| SnapshotError::Deserialize {
+                    ty,
+                    source_repo: source_repo.to_path_buf(),
+                    cause,
+                })?;
+                (*hash.as_bytes(), *page.content_hash().as_bytes())
+            }
+            ObjectType::Snapshot => unreachable!("the snapshot file is not an object"),
+        };
+        if expected != computed {
+            return Err(SnapshotError::HashMismatch {
+                ty,
+                expected,
+                computed,
+                source_repo: source_repo.to_path_buf(),
+            });
+        }
+        Ok(())
+    }
+
     /// Obtain an iterator over the [`blake3::Hash`]es of all objects
     /// this snapshot is referring to.
     pub fn objects(&self) -> impl Iterator<Item = blake3::Hash> + '_ {
@@ -462,6 +540,134 @@ impl Snapshot {
     }
 }
 
+/// How thoroughly [`SnapshotRepository::verify_snapshot`] checks the objects of a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyDepth {
+    /// Check that every object exists,
+    /// but read and hash only a sample of at most [`FAST_VERIFY_SAMPLE`] of them, spread evenly.
+    Fast,
+    /// Read and hash every object.
+    Full,
+}
+
+/// The number of objects whose contents are checked by [`VerifyDepth::Fast`].
+pub const FAST_VERIFY_SAMPLE: usize = 64;
+
+/// An object of a snapshot found to be missing or corrupted.
+#[derive(Debug)]
+pub struct BadObject {
+    pub ty: ObjectType,
+    pub error: SnapshotError,
+}
+
+/// The result of [`SnapshotRepository::verify_snapshot`].
+#[derive(Debug)]
+pub struct VerifyReport {
+    /// The transaction offset of the verified snapshot.
+    pub tx_offset: TxOffset,
+    /// The number of objects whose contents were read and hashed.
+    pub objects_checked: usize,
+    /// The objects found to be missing or corrupted.
+    pub bad_objects: Vec<BadObject>,
+}
+
+impl VerifyReport {
+    /// Whether no missing or corrupted object was found.
+    pub fn is_ok(&self) -> bool {
+        self.bad_objects.is_empty()
+    }
+}
+
+/// A source of objects from which [`SnapshotRepository::repair_from`] re-fetches
+/// the missing or corrupted objects of a snapshot.
+///
+/// Implemented by [`SnapshotReader`], for other snapshots of the same database,
+/// and may be implemented by callers, e.g., to fetch objects from a remote replica.
+pub trait ObjectSource {
+    /// Fetch the contents of the object `ty`, which is a blob or a page,
+    /// or return `None` if this source doesn't have it.
+    ///
+    /// The contents needn't be verified; [`SnapshotRepository::repair_from`] checks their hash.
+    fn fetch_object(&self, ty: ObjectType) -> Result<Option<Vec<u8>>, SnapshotError>;
+}
+
+/// Where [`SnapshotRepository::repair_from`] re-fetches objects from.
+pub enum RepairSource<'a> {
+    /// Another snapshot in the same repository, e.g., the one a snapshot was hardlinked from.
+    Parent(TxOffset),
+    /// Any other source, e.g., a remote replica of the database.
+    Remote(&'a dyn ObjectSource),
+}
+
+/// The result of [`SnapshotRepository::repair_from`].
+#[derive(Debug)]
+pub struct RepairReport {
+    /// The transaction offset of the repaired snapshot.
+    pub tx_offset: TxOffset,
+    /// The objects which were missing or corrupted and have been re-fetched.
+    pub repaired: Vec<ObjectType>,
+    /// The objects which are still missing or corrupted,
+    /// because the source didn't have them or had corrupted them too.
+    pub unrepaired: Vec<BadObject>,
+}
+
+impl RepairReport {
+    /// Whether the snapshot is now free of missing or corrupted objects.
+    pub fn is_ok(&self) -> bool {
+        self.unrepaired.is_empty()
+    }
+}
+
+/// The objects removed by [`SnapshotRepository::gc`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GcSummary {
+    /// The number of object files removed.
+    pub objects_removed: u64,
+    /// The number of bytes freed on disk.
+    ///
+    /// On Unix, an object hardlinked into several snapshots is only counted
+    /// if all of its links were removed.
+    pub bytes_reclaimed: u64,
+}
+
+/// The progress of [`SnapshotRepository::compress_snapshots`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionStats {
+    /// The number of snapshots whose objects have been compressed.
+    pub snapshots_compressed: u64,
+    /// The number of object files compressed, or replaced by a hardlink to a compressed object.
+    pub objects_compressed: u64,
+    /// The number of object files left as they were,
+    /// because they were already compressed, are shared with a snapshot too recent to compress,
+    /// or would not get any smaller.
+    pub objects_skipped: u64,
+    /// The size in bytes of the compressed objects before compression.
+    ///
+    /// On Unix, an object hardlinked into several snapshots is counted once.
+    pub bytes_before: u64,
+    /// The size in bytes of the compressed objects after compression.
+    pub bytes_after: u64,
+}
+
+/// The difference between two snapshots of the same database, computed by [`SnapshotRepository::diff`].
+///
+/// Objects are content-addressed, so an object which changed between the snapshots
+/// appears as the removal of its old version and the addition of its new one.
+#[derive(Debug, Clone)]
+pub struct SnapshotDiff {
+    /// The transaction offset of the snapshot compared against.
+    pub base: TxOffset,
+    /// The transaction offset of the snapshot compared.
+    pub target: TxOffset,
+    /// The objects `target` refers to but `base` doesn't.
+    pub added: Vec<ObjectType>,
+    /// The objects `base` refers to but `target` doesn't.
+    pub removed: Vec<ObjectType>,
+    /// The tables whose pages differ between the snapshots,
+    /// including those only present in one of them.
+    pub changed_tables: Vec<TableId>,
+}
+
 /// A repository of snapshots of a particular database instance.
 pub struct SnapshotRepository {
     /// The directory which contains all the snapshots.
@@ -544,19 +750,7 @@ impl SnapshotRepository {
         snapshot.write_all_blobs(&object_repo, blobs, prev_snapshot.as_ref(), &mut counter)?;
         snapshot.write_all_tables(&object_repo, tables, prev_snapshot.as_ref(), &mut counter)?;
 
-        // Serialize and hash the in-memory `Snapshot` object.
-        let snapshot_bsatn = bsatn::to_vec(&snapshot).map_err(|cause| SnapshotError::Serialize {
-            ty: ObjectType::Snapshot,
-            cause,
-        })?;
-        let hash = blake3::hash(&snapshot_bsatn);
-
-        // Create the snapshot file, containing first the hash, then the `Snapshot`.
-        {
-            let mut snapshot_file = snapshot_dir.snapshot_file(tx_offset).open_file(&o_excl())?;
-            snapshot_file.write_all(hash.as_bytes())?;
-            snapshot_file.write_all(&snapshot_bsatn)?;
-        }
+        snapshot.write_to_file(&snapshot_dir.snapshot_file(tx_offset))?;
 
         log::info!(
             "[{}] SNAPSHOT {:0>20}: Hardlinked {} objects and wrote {} objects",
@@ -571,6 +765,82 @@ impl SnapshotRepository {
         Ok(snapshot_dir)
     }
 
+    /// Copy the snapshot of `tx_offset` in `self` into the repository `target`,
+    /// which snapshots a different database, making it a snapshot of that database.
+    ///
+    /// The objects of the snapshot are hardlinked rather than copied,
+    /// so this takes time proportional to the number of objects, not to their size.
+    ///
+    /// Returns the path of the newly-created snapshot directory in `target`.
+    ///
+    /// Fails if:
+    /// - No snapshot exists in `self` for `tx_offset`, or it is incomplete.
+    /// - The snapshot file is corrupted, or any object it references is missing.
+    ///   The objects themselves are not verified.
+    /// - `target` already contains a snapshot of `tx_offset`.
+    pub fn fork_snapshot(
+        &self,
+        tx_offset: TxOffset,
+        target: &SnapshotRepository,
+    ) -> Result<SnapshotDirPath, SnapshotError> {
+        let source_dir = self.snapshot_dir_path(tx_offset);
+        let lockfile = Lockfile::lock_path(&source_dir);
+        if lockfile.try_exists()? {
+            return Err(SnapshotError::Incomplete { tx_offset, lockfile });
+        }
+        let mut snapshot = Snapshot::read_from_file(&source_dir.snapshot_file(tx_offset))?;
+        let source_repo = Self::object_repo(&source_dir)?;
+
+        let snapshot_dir = target.snapshot_dir_path(tx_offset);
+        let _lock = Lockfile::for_file(&snapshot_dir)?;
+        snapshot_dir.create()?;
+        let object_repo = Self::object_repo(&snapshot_dir)?;
+
+        let blobs = snapshot
+            .blobs
+            .iter()
+            .map(|blob| (ObjectType::Blob(blob.hash), blob.hash.data));
+        let pages = snapshot
+            .tables
+            .iter()
+            .flat_map(|table| &table.pages)
+            .map(|hash| (ObjectType::Page(*hash), *hash.as_bytes()));
+        let mut num_objects = 0;
+        for (ty, file_id) in blobs.chain(pages) {
+            let linked =
+                object_repo
+                    .try_hardlink_from(&source_repo, &file_id)
+                    .map_err(|cause| SnapshotError::WriteObject {
+                        ty,
+                        dest_repo: object_repo.root().to_path_buf(),
+                        source_repo: Some(source_repo.root().to_path_buf()),
+                        cause,
+                    })?;
+            if !linked {
+                return Err(SnapshotError::ReadObject {
+                    ty,
+                    source_repo: source_repo.root().to_path_buf(),
+                    cause: std::io::ErrorKind::NotFound.into(),
+                });
+            }
+            num_objects += 1;
+        }
+
+        snapshot.database_identity = target.database_identity;
+        snapshot.replica_id = target.replica_id;
+        snapshot.write_to_file(&snapshot_dir.snapshot_file(tx_offset))?;
+
+        log::info!(
+            "[{}] SNAPSHOT {:0>20}: Forked from {} by hardlinking {} objects",
+            target.database_identity,
+            tx_offset,
+            self.database_identity,
+            num_objects,
+        );
+
+        Ok(snapshot_dir)
+    }
+
     fn empty_snapshot(&self, tx_offset: TxOffset) -> Snapshot {
         Snapshot {
             magic: MAGIC,
@@ -644,6 +914,19 @@ impl SnapshotRepository {
     /// This means that callers must inspect the returned [`ReconstructedSnapshot`]
     /// and verify that they can handle its contained database address, instance ID, module ABI version and transaction offset.
     pub fn read_snapshot(&self, tx_offset: TxOffset) -> Result<ReconstructedSnapshot, SnapshotError> {
+        self.open_snapshot(tx_offset)?.reconstruct(|_| true)
+    }
+
+    /// Open the snapshot contained in self referring to `tx_offset` for reading,
+    /// without reading any of the objects it refers to yet.
+    ///
+    /// Unlike [`Self::read_snapshot`], the returned [`SnapshotReader`]
+    /// can read the pages of a single table one at a time,
+    /// so that they need not all be held in memory at once.
+    ///
+    /// Fails under the same conditions as [`Self::read_snapshot`],
+    /// except that missing or corrupted object files are only detected when they are read.
+    pub fn open_snapshot(&self, tx_offset: TxOffset) -> Result<SnapshotReader, SnapshotError> {
         let snapshot_dir = self.snapshot_dir_path(tx_offset);
         let lockfile = Lockfile::lock_path(&snapshot_dir);
         if lockfile.try_exists()? {
@@ -669,18 +952,123 @@ impl SnapshotRepository {
 
         let object_repo = Self::object_repo(&snapshot_dir)?;
 
-        let blob_store = snapshot.reconstruct_blob_store(&object_repo)?;
+        Ok(SnapshotReader { snapshot, object_repo })
+    }
+
+    /// Check the objects of the snapshot contained in self referring to `tx_offset`
+    /// as thoroughly as `depth` asks,
+    /// reporting the missing and corrupted ones rather than failing at the first.
+    ///
+    /// Fails if the snapshot itself can't be opened, under the same conditions as [`Self::open_snapshot`].
+    pub fn verify_snapshot(&self, tx_offset: TxOffset, depth: VerifyDepth) -> Result<VerifyReport, SnapshotError> {
+        let reader = self.open_snapshot(tx_offset)?;
+        let object_repo = &reader.object_repo;
+        let objects = reader.snapshot.object_types().collect::<Vec<_>>();
+
+        // Check evenly spaced objects, so that all tables are likely to be sampled.
+        let stride = match depth {
+            VerifyDepth::Fast => objects.len().div_ceil(FAST_VERIFY_SAMPLE).max(1),
+            VerifyDepth::Full => 1,
+        };
 
-        let tables = snapshot.reconstruct_tables(&object_repo)?;
+        let mut report = VerifyReport {
+            tx_offset,
+            objects_checked: 0,
+            bad_objects: Vec::new(),
+        };
+        for (i, ty) in objects.into_iter().enumerate() {
+            let file_id = object_file_id(ty);
+            let checked = if i % stride == 0 {
+                report.objects_checked += 1;
+                read_object(object_repo, ty).and_then(|buf| Snapshot::check_object(ty, &buf, object_repo.root()))
+            } else if object_repo.contains_entry(&file_id) {
+                Ok(())
+            } else {
+                Err(SnapshotError::ReadObject {
+                    ty,
+                    source_repo: object_repo.root().to_path_buf(),
+                    cause: std::io::ErrorKind::NotFound.into(),
+                })
+            };
+            if let Err(error) = checked {
+                report.bad_objects.push(BadObject { ty, error });
+            }
+        }
+        Ok(report)
+    }
 
-        Ok(ReconstructedSnapshot {
-            database_identity: snapshot.database_identity,
-            replica_id: snapshot.replica_id,
-            tx_offset: snapshot.tx_offset,
-            module_abi_version: snapshot.module_abi_version,
-            blob_store,
-            tables,
-        })
+    /// Fully verify the snapshot contained in self referring to `tx_offset`,
+    /// then replace each of its missing or corrupted objects with the object fetched from `source`,
+    /// rather than declaring the whole snapshot unusable.
+    ///
+    /// Fetched objects are checked against the hash recorded in the snapshot before being written.
+    /// A corrupted object hardlinked into other snapshots is unlinked before it is replaced,
+    /// so the other snapshots keep the corrupted copy and must be repaired separately.
+    ///
+    /// Fails if the snapshot itself, or the `Parent` snapshot, can't be opened,
+    /// or if writing a fetched object fails.
+    pub fn repair_from(&self, tx_offset: TxOffset, source: RepairSource<'_>) -> Result<RepairReport, SnapshotError> {
+        let parent;
+        let source = match source {
+            RepairSource::Parent(parent_offset) => {
+                parent = self.open_snapshot(parent_offset)?;
+                &parent as &dyn ObjectSource
+            }
+            RepairSource::Remote(source) => source,
+        };
+
+        let verified = self.verify_snapshot(tx_offset, VerifyDepth::Full)?;
+        let object_repo = Self::object_repo(&self.snapshot_dir_path(tx_offset))?;
+
+        let mut report = RepairReport {
+            tx_offset,
+            repaired: Vec::new(),
+            unrepaired: Vec::new(),
+        };
+        for bad in verified.bad_objects {
+            let ty = bad.ty;
+            let fetched = match source.fetch_object(ty) {
+                Ok(Some(buf)) => buf,
+                Ok(None) => {
+                    report.unrepaired.push(bad);
+                    continue;
+                }
+                Err(error) => {
+                    report.unrepaired.push(BadObject { ty, error });
+                    continue;
+                }
+            };
+            if let Err(error) = Snapshot::check_object(ty, &fetched, object_repo.root()) {
+                report.unrepaired.push(BadObject { ty, error });
+                continue;
+            }
+
+            let file_id = object_file_id(ty);
+            let err_write_object = |cause| SnapshotError::WriteObject {
+                ty,
+                dest_repo: object_repo.root().to_path_buf(),
+                source_repo: None,
+                cause,
+            };
+            if object_repo.contains_entry(&file_id) {
+                object_repo.remove_entry(&file_id).map_err(err_write_object)?;
+            }
+            object_repo
+                .open_entry(&file_id, &o_excl())
+                .and_then(|mut file| file.write_all(&fetched))
+                .map_err(err_write_object)?;
+            report.repaired.push(ty);
+        }
+
+        log::info!(
+            "[{}] SNAPSHOT {:0>20}: Repaired {} objects, {} remain missing or corrupted",
+            self.database_identity,
+            tx_offset,
+            report.repaired.len(),
+            report.unrepaired.len(),
+        );
+
+        Ok(report)
     }
 
     /// Open a repository at `root`, failing if the `root` doesn't exist or isn't a directory.
@@ -771,6 +1159,637 @@ impl SnapshotRepository {
         }
         Ok(())
     }
+
+    /// Determine the size on disk of all the snapshots in the repository,
+    /// including locked and invalidated ones.
+    ///
+    /// Snapshots share unchanged objects with their parent through hardlinks.
+    /// On Unix, each such object is counted once.
+    pub fn size_on_disk(&self) -> std::io::Result<u64> {
+        fn dir_size(dir: &Path, seen: &mut HashSet<(u64, u64)>) -> std::io::Result<u64> {
+            let mut size = 0;
+            for entry in dir.read_dir()? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    size += dir_size(&entry.path(), seen)?;
+                    continue;
+                }
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::MetadataExt;
+                    if metadata.nlink() > 1 && !seen.insert((metadata.dev(), metadata.ino())) {
+                        continue;
+                    }
+                }
+                size += metadata.len();
+            }
+            Ok(size)
+        }
+
+        dir_size(&self.root, &mut HashSet::new())
+    }
+
+    /// Remove the objects which no valid snapshot in the repository refers to,
+    /// returning how many were removed and how many bytes that freed.
+    ///
+    /// These are all the objects of snapshots invalidated by [`Self::invalidate_newer_snapshots`],
+    /// and any object in the object repository of a valid snapshot which that snapshot doesn't refer to.
+    /// The snapshot files themselves are kept.
+    ///
+    /// Locked snapshots, which are still being created, are left alone,
+    /// as are the objects of any valid snapshot whose snapshot file can't be read.
+    ///
+    /// This must not be called while a snapshot is being created in the repository,
+    /// as it may remove the objects of the previous snapshot which are being hardlinked into the new one.
+    pub fn gc(&self) -> Result<GcSummary, SnapshotError> {
+        // The object repository of each snapshot, with the objects it refers to.
+        let mut repos = Vec::new();
+        for tx_offset in self.all_snapshots()? {
+            let snapshot_dir = self.snapshot_dir_path(tx_offset);
+            match Snapshot::read_from_file(&snapshot_dir.snapshot_file(tx_offset)) {
+                Ok(snapshot) => {
+                    let reachable = snapshot.objects().map(|hash| *hash.as_bytes()).collect::<HashSet<_>>();
+                    repos.push((Self::object_repo(&snapshot_dir)?, reachable));
+                }
+                Err(e) => log::warn!(
+                    "[{}] SNAPSHOT {:0>20}: Not collecting objects of unreadable snapshot: {e}",
+                    self.database_identity,
+                    tx_offset,
+                ),
+            }
+        }
+        for path in self.invalid_snapshot_dirs()? {
+            let objects = SnapshotDirPath(path).objects();
+            if objects.0.is_dir() {
+                repos.push((DirTrie::open(objects.0)?, HashSet::new()));
+            }
+        }
+
+        let mut garbage = Vec::new();
+        for (repo, reachable) in &repos {
+            for file_id in repo.entries()? {
+                if !reachable.contains(&file_id) {
+                    garbage.push((repo, file_id, repo.entry_metadata(&file_id)?));
+                }
+            }
+        }
+
+        let summary = GcSummary {
+            objects_removed: garbage.len() as u64,
+            bytes_reclaimed: reclaimed_bytes(garbage.iter().map(|(_, _, metadata)| metadata)),
+        };
+        for (repo, file_id, _) in garbage {
+            repo.remove_entry(&file_id)?;
+        }
+
+        log::info!(
+            "[{}] SNAPSHOT GC: Removed {} objects, reclaiming {} bytes",
+            self.database_identity,
+            summary.objects_removed,
+            summary.bytes_reclaimed,
+        );
+
+        Ok(summary)
+    }
+
+    /// Compress the objects of the valid snapshots in the repository captured before `cutoff`,
+    /// so that snapshots kept for a long time take up less disk space.
+    ///
+    /// Compressed objects are decompressed transparently when they are read.
+    /// An object hardlinked into several of these snapshots is compressed once
+    /// and hardlinked again, while one shared with a snapshot captured after `cutoff`
+    /// is left alone, so as not to store it both compressed and uncompressed.
+    ///
+    /// `progress` is called with the totals so far after each snapshot is compressed,
+    /// oldest first, and the final totals are returned.
+    ///
+    /// Snapshots whose snapshot file can't be read are skipped, as are missing objects.
+    /// Objects are not verified, so a corrupted object stays corrupted when compressed.
+    pub fn compress_snapshots(
+        &self,
+        cutoff: SystemTime,
+        mut progress: impl FnMut(TxOffset, &CompressionStats),
+    ) -> Result<CompressionStats, SnapshotError> {
+        let mut old = Vec::new();
+        let mut recent_files = HashSet::new();
+        for tx_offset in self.all_snapshots()? {
+            let snapshot_dir = self.snapshot_dir_path(tx_offset);
+            let captured_at = snapshot_dir.snapshot_file(tx_offset).metadata()?.modified()?;
+            if captured_at < cutoff {
+                old.push(tx_offset);
+                continue;
+            }
+            let object_repo = Self::object_repo(&snapshot_dir)?;
+            for file_id in object_repo.entries()? {
+                recent_files.extend(file_key(&object_repo.entry_metadata(&file_id)?));
+            }
+        }
+        old.sort_unstable();
+
+        // For each file compressed so far, keyed by the file it replaced,
+        // the snapshot containing the compressed file, or `None` if it was left alone.
+        let mut done = HashMap::new();
+        let mut stats = CompressionStats::default();
+        for tx_offset in old {
+            let snapshot_dir = self.snapshot_dir_path(tx_offset);
+            let snapshot = match Snapshot::read_from_file(&snapshot_dir.snapshot_file(tx_offset)) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    log::warn!(
+                        "[{}] SNAPSHOT {:0>20}: Not compressing objects of unreadable snapshot: {e}",
+                        self.database_identity,
+                        tx_offset,
+                    );
+                    continue;
+                }
+            };
+            let object_repo = Self::object_repo(&snapshot_dir)?;
+            // Objects referred to several times by the same snapshot are only compressed once.
+            let objects = snapshot.object_types().map(|ty| (object_file_id(ty), ty));
+            for (file_id, ty) in objects.collect::<BTreeMap<_, _>>() {
+                let err_write_object = |cause| SnapshotError::WriteObject {
+                    ty,
+                    dest_repo: object_repo.root().to_path_buf(),
+                    source_repo: None,
+                    cause,
+                };
+                let Ok(metadata) = object_repo.entry_metadata(&file_id) else {
+                    continue;
+                };
+                let key = file_key(&metadata);
+                if key.is_some_and(|key| recent_files.contains(&key)) {
+                    stats.objects_skipped += 1;
+                    continue;
+                }
+                match key.and_then(|key| done.get(&key)) {
+                    Some(Some(compressed_in)) => {
+                        let src_repo = Self::object_repo(&self.snapshot_dir_path(*compressed_in))?;
+                        object_repo
+                            .replace_with_hardlink_from(&src_repo, &file_id)
+                            .map_err(err_write_object)?;
+                        stats.objects_compressed += 1;
+                        continue;
+                    }
+                    Some(None) => {
+                        stats.objects_skipped += 1;
+                        continue;
+                    }
+                    None => {}
+                }
+
+                let buf = object_repo
+                    .read_entry(&file_id)
+                    .map_err(|cause| SnapshotError::ReadObject {
+                        ty,
+                        source_repo: object_repo.root().to_path_buf(),
+                        cause,
+                    })?;
+                let compressed = (!buf.starts_with(&COMPRESSED_OBJECT_MAGIC))
+                    .then(|| compress_object(&buf))
+                    .filter(|compressed| compressed.len() < buf.len());
+                let Some(compressed) = compressed else {
+                    done.extend(key.map(|key| (key, None)));
+                    stats.objects_skipped += 1;
+                    continue;
+                };
+                object_repo
+                    .replace_entry(&file_id, &compressed)
+                    .map_err(err_write_object)?;
+                done.extend(key.map(|key| (key, Some(tx_offset))));
+                stats.objects_compressed += 1;
+                stats.bytes_before += buf.len() as u64;
+                stats.bytes_after += compressed.len() as u64;
+            }
+            stats.snapshots_compressed += 1;
+            progress(tx_offset, &stats);
+        }
+
+        log::info!(
+            "[{}] SNAPSHOT COMPRESSION: Compressed {} objects of {} snapshots from {} to {} bytes",
+            self.database_identity,
+            stats.objects_compressed,
+            stats.snapshots_compressed,
+            stats.bytes_before,
+            stats.bytes_after,
+        );
+
+        Ok(stats)
+    }
+
+    /// Compare the snapshot `target` against the snapshot `base`,
+    /// listing the objects added and removed and the tables which changed.
+    ///
+    /// Only the snapshot files are read, not the objects.
+    ///
+    /// Fails if either snapshot can't be opened, under the same conditions as [`Self::open_snapshot`].
+    pub fn diff(&self, base: TxOffset, target: TxOffset) -> Result<SnapshotDiff, SnapshotError> {
+        let base_snapshot = self.open_snapshot(base)?.snapshot;
+        let target_snapshot = self.open_snapshot(target)?.snapshot;
+
+        let objects = |snapshot: &Snapshot| {
+            snapshot
+                .object_types()
+                .map(|ty| (object_file_id(ty), ty))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let base_objects = objects(&base_snapshot);
+        let target_objects = objects(&target_snapshot);
+        let only_in = |a: &BTreeMap<[u8; 32], ObjectType>, b: &BTreeMap<[u8; 32], ObjectType>| -> Vec<_> {
+            a.iter()
+                .filter(|(file_id, _)| !b.contains_key(*file_id))
+                .map(|(_, ty)| *ty)
+                .collect()
+        };
+
+        let tables = |snapshot: &Snapshot| {
+            snapshot
+                .tables
+                .iter()
+                .map(|table| (table.table_id, &table.pages))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let base_tables = tables(&base_snapshot);
+        let target_tables = tables(&target_snapshot);
+        let mut changed_tables = base_tables
+            .keys()
+            .chain(target_tables.keys())
+            .copied()
+            .filter(|table_id| base_tables.get(table_id) != target_tables.get(table_id))
+            .collect::<Vec<_>>();
+        changed_tables.sort_unstable();
+        changed_tables.dedup();
+
+        Ok(SnapshotDiff {
+            base,
+            target,
+            added: only_in(&target_objects, &base_objects),
+            removed: only_in(&base_objects, &target_objects),
+            changed_tables,
+        })
+    }
+
+    /// Write an archive of the snapshot `diff.target` to `out`,
+    /// containing its snapshot file and the objects in `diff.added`, compressed.
+    ///
+    /// Applying the archive with [`Self::apply_diff_archive`] to a repository which contains
+    /// the snapshot `diff.base` restores the snapshot `diff.target` in it,
+    /// so only the archive needs to be copied, rather than the whole snapshot.
+    ///
+    /// The objects are verified before they are archived.
+    /// Fails if the snapshot `diff.target` can't be opened, or if any of the objects is missing or corrupted.
+    pub fn write_diff_archive(&self, diff: &SnapshotDiff, mut out: impl Write) -> Result<(), SnapshotError> {
+        let snapshot_dir = self.snapshot_dir_path(diff.target);
+        let reader = self.open_snapshot(diff.target)?;
+        let snapshot_file = std::fs::read(snapshot_dir.snapshot_file(diff.target))?;
+
+        out.write_all(&DIFF_ARCHIVE_MAGIC)?;
+        out.write_all(&[CURRENT_DIFF_ARCHIVE_VERSION])?;
+        out.write_all(&diff.base.to_le_bytes())?;
+        out.write_all(&diff.target.to_le_bytes())?;
+        write_len_prefixed(&mut out, &snapshot_file)?;
+        out.write_all(&(diff.added.len() as u64).to_le_bytes())?;
+        for &ty in &diff.added {
+            let buf = read_object(&reader.object_repo, ty)?;
+            Snapshot::check_object(ty, &buf, reader.object_repo.root())?;
+            out.write_all(&object_file_id(ty))?;
+            write_len_prefixed(&mut out, &compress_object(&buf))?;
+        }
+
+        log::info!(
+            "[{}] SNAPSHOT {:0>20}: Archived {} objects added since snapshot {}",
+            self.database_identity,
+            diff.target,
+            diff.added.len(),
+            diff.base,
+        );
+
+        Ok(())
+    }
+
+    /// Restore a snapshot from an `archive` written by [`Self::write_diff_archive`],
+    /// hardlinking the objects it shares with its base snapshot, which must be in `self`.
+    ///
+    /// The objects in the archive are verified before they are written.
+    /// If applying the archive fails, the partially restored snapshot is invalidated.
+    ///
+    /// Returns the path of the newly-created snapshot directory.
+    ///
+    /// Fails if:
+    /// - The archive is malformed or corrupted.
+    /// - The base snapshot isn't in `self`, or lacks any of the objects shared with the restored snapshot.
+    /// - `self` already contains the restored snapshot.
+    pub fn apply_diff_archive(&self, mut archive: impl Read) -> Result<SnapshotDirPath, SnapshotError> {
+        let mut magic = [0; 4];
+        archive.read_exact(&mut magic)?;
+        if magic != DIFF_ARCHIVE_MAGIC {
+            return Err(SnapshotError::BadDiffArchive(format!("bad magic number {magic:x?}")));
+        }
+        let mut version = [0];
+        archive.read_exact(&mut version)?;
+        if version[0] != CURRENT_DIFF_ARCHIVE_VERSION {
+            return Err(SnapshotError::BadDiffArchive(format!(
+                "unsupported version {}",
+                version[0]
+            )));
+        }
+        let base = read_u64(&mut archive)?;
+        let target = read_u64(&mut archive)?;
+
+        let base_dir = self.snapshot_dir_path(base);
+        if !base_dir.0.is_dir() {
+            return Err(SnapshotError::BadDiffArchive(format!(
+                "base snapshot {base} is not in the repository"
+            )));
+        }
+        let lockfile = Lockfile::lock_path(&base_dir);
+        if lockfile.try_exists()? {
+            return Err(SnapshotError::Incomplete {
+                tx_offset: base,
+                lockfile,
+            });
+        }
+        let base_repo = Self::object_repo(&base_dir)?;
+
+        let snapshot_dir = self.snapshot_dir_path(target);
+        if snapshot_dir.0.exists() {
+            return Err(SnapshotError::BadDiffArchive(format!(
+                "snapshot {target} is already in the repository"
+            )));
+        }
+        let _lock = Lockfile::for_file(&snapshot_dir)?;
+        snapshot_dir.create()?;
+
+        let applied = (|| -> Result<(u64, u64), SnapshotError> {
+            let object_repo = Self::object_repo(&snapshot_dir)?;
+            let snapshot_file = snapshot_dir.snapshot_file(target);
+            snapshot_file
+                .open_file(&o_excl())?
+                .write_all(&read_len_prefixed(&mut archive)?)?;
+            let snapshot = Snapshot::read_from_file(&snapshot_file)?;
+            let objects = snapshot
+                .object_types()
+                .map(|ty| (object_file_id(ty), ty))
+                .collect::<BTreeMap<_, _>>();
+
+            let num_archived = read_u64(&mut archive)?;
+            for _ in 0..num_archived {
+                let mut file_id = [0; 32];
+                archive.read_exact(&mut file_id)?;
+                let Some(&ty) = objects.get(&file_id) else {
+                    return Err(SnapshotError::BadDiffArchive(format!(
+                        "object {} is not referred to by snapshot {target}",
+                        hex::encode(file_id)
+                    )));
+                };
+                let buf = decompress_object(read_len_prefixed(&mut archive)?);
+                Snapshot::check_object(ty, &buf, object_repo.root())?;
+                object_repo
+                    .open_entry(&file_id, &o_excl())
+                    .and_then(|mut file| file.write_all(&buf))
+                    .map_err(|cause| SnapshotError::WriteObject {
+                        ty,
+                        dest_repo: object_repo.root().to_path_buf(),
+                        source_repo: None,
+                        cause,
+                    })?;
+            }
+
+            let mut num_linked = 0;
+            for (file_id, &ty) in &objects {
+                if object_repo.contains_entry(file_id) {
+                    continue;
+                }
+                let linked =
+                    object_repo
+                        .try_hardlink_from(&base_repo, file_id)
+                        .map_err(|cause| SnapshotError::WriteObject {
+                            ty,
+                            dest_repo: object_repo.root().to_path_buf(),
+                            source_repo: Some(base_repo.root().to_path_buf()),
+                            cause,
+                        })?;
+                if !linked {
+                    return Err(SnapshotError::ReadObject {
+                        ty,
+                        source_repo: base_repo.root().to_path_buf(),
+                        cause: std::io::ErrorKind::NotFound.into(),
+                    });
+                }
+                num_linked += 1;
+            }
+            Ok((num_archived, num_linked))
+        })();
+
+        let (num_archived, num_linked) = match applied {
+            Ok(counts) => counts,
+            Err(e) => {
+                // Leave no snapshot which looks valid but isn't.
+                if let Err(rename_err) = snapshot_dir.rename_invalid() {
+                    log::warn!(
+                        "[{}] SNAPSHOT {:0>20}: Failed to invalidate partially applied diff archive: {rename_err}",
+                        self.database_identity,
+                        target,
+                    );
+                }
+                return Err(e);
+            }
+        };
+
+        log::info!(
+            "[{}] SNAPSHOT {:0>20}: Applied diff archive by writing {} objects and hardlinking {} from snapshot {}",
+            self.database_identity,
+            target,
+            num_archived,
+            num_linked,
+            base,
+        );
+
+        Ok(snapshot_dir)
+    }
+
+    /// The directories of the snapshots invalidated by [`Self::invalidate_newer_snapshots`],
+    /// ignoring those whose lockfile still exists.
+    fn invalid_snapshot_dirs(&self) -> Result<Vec<PathBuf>, SnapshotError> {
+        Ok(self
+            .root
+            .read_dir()?
+            .filter_map(Result::ok)
+            .map(|dirent| dirent.path())
+            .filter(|path| path.extension() == Some(OsStr::new(INVALID_SNAPSHOT_DIR_EXT)))
+            .filter(|path| !Lockfile::lock_path(path).exists())
+            .collect())
+    }
+}
+
+/// The key of the object `ty`, which is a blob or a page, in an object repository.
+fn object_file_id(ty: ObjectType) -> [u8; 32] {
+    match ty {
+        ObjectType::Blob(hash) => hash.data,
+        ObjectType::Page(hash) => *hash.as_bytes(),
+        ObjectType::Snapshot => unreachable!("the snapshot file is not an object"),
+    }
+}
+
+/// Read the object `ty`, which is a blob or a page, from `object_repo`,
+/// decompressing it if it was compressed by [`SnapshotRepository::compress_snapshots`].
+fn read_object(object_repo: &DirTrie, ty: ObjectType) -> Result<Vec<u8>, SnapshotError> {
+    let buf = object_repo
+        .read_entry(&object_file_id(ty))
+        .map_err(|cause| SnapshotError::ReadObject {
+            ty,
+            source_repo: object_repo.root().to_path_buf(),
+            cause,
+        })?;
+    Ok(decompress_object(buf))
+}
+
+/// Decompress the contents `buf` of an object if they were compressed by [`compress_object`].
+fn decompress_object(buf: Vec<u8>) -> Vec<u8> {
+    let Some(compressed) = buf.strip_prefix(&COMPRESSED_OBJECT_MAGIC) else {
+        return buf;
+    };
+    let mut decompressed = Vec::new();
+    if DeflateDecoder::new(compressed).read_to_end(&mut decompressed).is_err() {
+        // Not compressed after all, but an object which happens to start with the magic number.
+        return buf;
+    }
+    decompressed
+}
+
+/// Compress the contents `buf` of an object,
+/// to be decompressed again by [`read_object`].
+fn compress_object(buf: &[u8]) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(COMPRESSED_OBJECT_MAGIC.to_vec(), Compression::default());
+    encoder
+        .write_all(buf)
+        .expect("compressing into a `Vec` should never fail");
+    encoder.finish().expect("compressing into a `Vec` should never fail")
+}
+
+/// Write `buf` to `out`, preceded by its length, for [`read_len_prefixed`].
+fn write_len_prefixed(out: &mut impl Write, buf: &[u8]) -> std::io::Result<()> {
+    out.write_all(&(buf.len() as u64).to_le_bytes())?;
+    out.write_all(buf)
+}
+
+/// Read a buffer written by [`write_len_prefixed`] from `input`.
+fn read_len_prefixed(input: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let len = read_u64(input)?;
+    let mut buf = Vec::new();
+    input.take(len).read_to_end(&mut buf)?;
+    if buf.len() as u64 != len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+/// Read a little-endian `u64` from `input`.
+fn read_u64(input: &mut impl Read) -> std::io::Result<u64> {
+    let mut buf = [0; 8];
+    input.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Identifies the file with `metadata`, so that its hardlinks can be recognized,
+/// or `None` on platforms where this isn't supported.
+fn file_key(metadata: &Metadata) -> Option<(u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// The number of bytes freed by removing the object files with `metadata`.
+///
+/// On Unix, a file with several hardlinks only frees its bytes once all of its links are removed.
+fn reclaimed_bytes<'a>(metadata: impl Iterator<Item = &'a Metadata>) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let mut inodes = HashMap::new();
+        for metadata in metadata {
+            let (_, links_removed) = inodes.entry((metadata.dev(), metadata.ino())).or_insert((metadata, 0));
+            *links_removed += 1;
+        }
+        inodes
+            .values()
+            .filter(|(metadata, links_removed)| *links_removed >= metadata.nlink())
+            .map(|(metadata, _)| metadata.len())
+            .sum()
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.map(|metadata| metadata.len()).sum()
+    }
+}
+
+/// A snapshot opened by [`SnapshotRepository::open_snapshot`],
+/// which reads the objects it refers to on demand.
+pub struct SnapshotReader {
+    snapshot: Snapshot,
+    object_repo: DirTrie,
+}
+
+impl ObjectSource for SnapshotReader {
+    fn fetch_object(&self, ty: ObjectType) -> Result<Option<Vec<u8>>, SnapshotError> {
+        let file_id = object_file_id(ty);
+        if !self.object_repo.contains_entry(&file_id) {
+            return Ok(None);
+        }
+        read_object(&self.object_repo, ty).map(Some)
+    }
+}
+
+impl SnapshotReader {
+    /// The address of the snapshotted database.
+    pub fn database_identity(&self) -> Identity {
+        self.snapshot.database_identity
+    }
+
+    /// The transaction offset of the state this snapshot reflects.
+    pub fn tx_offset(&self) -> TxOffset {
+        self.snapshot.tx_offset
+    }
+
+    /// Read the blob store and the tables for which `filter` returns `true`
+    /// into a [`ReconstructedSnapshot`].
+    ///
+    /// Fails if any object file referenced by the blob store or those tables
+    /// is missing or corrupted.
+    pub fn reconstruct(&self, filter: impl Fn(TableId) -> bool) -> Result<ReconstructedSnapshot, SnapshotError> {
+        let blob_store = self.snapshot.reconstruct_blob_store(&self.object_repo)?;
+
+        let tables = self.snapshot.reconstruct_tables(&self.object_repo, filter)?;
+
+        Ok(ReconstructedSnapshot {
+            database_identity: self.snapshot.database_identity,
+            replica_id: self.snapshot.replica_id,
+            tx_offset: self.snapshot.tx_offset,
+            module_abi_version: self.snapshot.module_abi_version,
+            blob_store,
+            tables,
+        })
+    }
+
+    /// Read the pages of the table `table_id` one at a time.
+    ///
+    /// Each item fails if its page file is missing or corrupted.
+    /// Yields no pages if the snapshot doesn't contain `table_id`.
+    pub fn table_pages(&self, table_id: TableId) -> impl Iterator<Item = Result<Box<Page>, SnapshotError>> + '_ {
+        self.snapshot
+            .tables
+            .iter()
+            .filter(move |tbl| tbl.table_id == table_id)
+            .flat_map(|tbl| &tbl.pages)
+            .map(|hash| Snapshot::read_page(&self.object_repo, hash))
+    }
 }
 
 pub struct ReconstructedSnapshot {
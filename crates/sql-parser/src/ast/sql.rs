@@ -36,6 +36,17 @@ impl SqlAst {
                 table: with.clone(),
                 filter: filter.map(|expr| expr.qualify_vars(with)),
             }),
+            Self::Insert(SqlInsert {
+                table: with,
+                fields,
+                values,
+                returning,
+            }) => Self::Insert(SqlInsert {
+                table: with.clone(),
+                fields,
+                values,
+                returning: returning.map(|project| project.qualify_vars(with)),
+            }),
             _ => self,
         }
     }
@@ -79,12 +90,13 @@ impl SqlSelect {
     }
 }
 
-/// INSERT INTO table cols VALUES literals
+/// INSERT INTO table cols VALUES literals [ RETURNING projection ]
 #[derive(Debug)]
 pub struct SqlInsert {
     pub table: SqlIdent,
     pub fields: Vec<SqlIdent>,
     pub values: SqlValues,
+    pub returning: Option<Project>,
 }
 
 /// VALUES literals
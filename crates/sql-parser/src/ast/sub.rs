@@ -1,12 +1,20 @@
 use crate::parser::{errors::SqlUnsupported, SqlParseResult};
 
-use super::{Project, SqlExpr, SqlFrom};
+use super::{Project, ProjectExpr, SqlExpr, SqlFrom};
 
 /// A SELECT statement in the SQL subscription language
 pub struct SqlSelect {
     pub project: Project,
     pub from: SqlFrom,
     pub filter: Option<SqlExpr>,
+    pub top_n: Option<SqlTopN>,
+}
+
+/// ORDER BY field [ ASC | DESC ] LIMIT n
+pub struct SqlTopN {
+    pub field: ProjectExpr,
+    pub desc: bool,
+    pub limit: u64,
 }
 
 impl SqlSelect {
@@ -15,6 +23,11 @@ impl SqlSelect {
             SqlFrom::Expr(_, alias) => Self {
                 project: self.project.qualify_vars(alias.clone()),
                 filter: self.filter.map(|expr| expr.qualify_vars(alias.clone())),
+                top_n: self.top_n.map(|SqlTopN { field, desc, limit }| SqlTopN {
+                    field: field.qualify_vars(alias.clone()),
+                    desc,
+                    limit,
+                }),
                 from: self.from,
             },
             SqlFrom::Join(..) => self,
@@ -14,6 +14,10 @@ pub enum SubscriptionUnsupported {
     Feature(String),
     #[error("Unsupported: Non-SELECT queries")]
     Dml,
+    #[error("Unsupported: ORDER BY and LIMIT must be given together, as ORDER BY a single column")]
+    TopN,
+    #[error("Unsupported: ORDER BY and LIMIT with joins")]
+    TopNJoin,
 }
 
 impl SubscriptionUnsupported {
@@ -11,7 +11,11 @@
 //!     ;
 //!
 //! insert
-//!     = INSERT INTO table [ '(' column { ',' column } ')' ] VALUES '(' literal { ',' literal } ')'
+//!     = INSERT INTO table [ '(' column { ',' column } ')' ] VALUES row { ',' row } [ RETURNING projection ]
+//!     ;
+//!
+//! row
+//!     = '(' literal { ',' literal } ')'
 //!     ;
 //!
 //! delete
@@ -174,12 +178,13 @@ fn parse_statement(stmt: Statement) -> SqlParseResult<SqlAst> {
             after_columns,
             table: false,
             on: None,
-            returning: None,
+            returning,
             ..
         } if after_columns.is_empty() => Ok(SqlAst::Insert(SqlInsert {
             table: parse_ident(table_name)?,
             fields: columns.into_iter().map(SqlIdent::from).collect(),
             values: parse_values(*source)?,
+            returning: returning.map(parse_projection).transpose()?,
         })),
         Statement::Update {
             table:
@@ -430,10 +435,17 @@ mod tests {
         for sql in [
             "select a from t",
             "insert into t values (1, 2)",
+            "insert into t values (1, 2), (3, 4) returning *",
+            "insert into t values (1, 2) returning a, t.b",
             "delete from t",
             "delete from t where a = 1",
             "update t set a = 1, b = 2",
             "update t set a = 1, b = 2 where c = 3",
+            // Quoted identifiers may be keywords
+            "select \"user\" from \"order\"",
+            "insert into \"order\" values (1, 2)",
+            "delete from \"order\" where \"user\" = 1",
+            "update \"order\" set \"user\" = 1",
         ] {
             assert!(parse_sql(sql).is_ok());
         }
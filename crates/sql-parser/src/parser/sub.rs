@@ -2,7 +2,11 @@
 //!
 //! ```ebnf
 //! query
-//!     = SELECT projection FROM relation [ WHERE predicate ]
+//!     = SELECT projection FROM relation [ WHERE predicate ] [ ORDER BY order LIMIT INTEGER ]
+//!     ;
+//!
+//! order
+//!     = ( ident | field ) [ ASC | DESC ]
 //!     ;
 //!
 //! projection
@@ -54,16 +58,19 @@
 //! ```
 
 use sqlparser::{
-    ast::{GroupByExpr, Query, Select, SetExpr, Statement},
+    ast::{Expr, GroupByExpr, OrderByExpr, Query, Select, SetExpr, Statement, Value},
     dialect::PostgreSqlDialect,
     parser::Parser,
 };
 
-use crate::ast::sub::SqlSelect;
+use crate::ast::{
+    sub::{SqlSelect, SqlTopN},
+    SqlFrom,
+};
 
 use super::{
     errors::{SqlUnsupported, SubscriptionUnsupported},
-    parse_expr_opt, parse_projection, RelParser, SqlParseResult,
+    parse_expr_opt, parse_proj, parse_projection, RelParser, SqlParseResult,
 };
 
 /// Parse a SQL string
@@ -97,16 +104,54 @@ impl RelParser for SubParser {
                 with: None,
                 body,
                 order_by,
-                limit: None,
+                limit,
                 offset: None,
                 fetch: None,
                 locks,
-            } if order_by.is_empty() && locks.is_empty() => parse_set_op(*body),
+            } if locks.is_empty() => {
+                let select = parse_set_op(*body)?;
+                match parse_top_n(order_by, limit)? {
+                    Some(_) if matches!(select.from, SqlFrom::Join(..)) => {
+                        Err(SubscriptionUnsupported::TopNJoin.into())
+                    }
+                    top_n => Ok(SqlSelect { top_n, ..select }),
+                }
+            }
             _ => Err(SubscriptionUnsupported::feature(query).into()),
         }
     }
 }
 
+/// Parse the ORDER BY and LIMIT clauses of a top-N subscription
+fn parse_top_n(mut order_by: Vec<OrderByExpr>, limit: Option<Expr>) -> SqlParseResult<Option<SqlTopN>> {
+    match (order_by.len(), limit) {
+        (0, None) => Ok(None),
+        (1, Some(limit)) => match order_by.swap_remove(0) {
+            OrderByExpr {
+                expr,
+                asc,
+                nulls_first: None,
+            } => Ok(Some(SqlTopN {
+                field: parse_proj(expr)?,
+                desc: asc == Some(false),
+                limit: parse_limit(limit)?,
+            })),
+            _ => Err(SubscriptionUnsupported::TopN.into()),
+        },
+        _ => Err(SubscriptionUnsupported::TopN.into()),
+    }
+}
+
+/// Parse a LIMIT, which must be a non-negative integer
+fn parse_limit(expr: Expr) -> SqlParseResult<u64> {
+    if let Expr::Value(Value::Number(n, _)) = &expr {
+        if let Ok(n) = n.parse() {
+            return Ok(n);
+        }
+    }
+    Err(SqlUnsupported::Limit(expr).into())
+}
+
 /// Parse a set operation
 fn parse_set_op(expr: SetExpr) -> SqlParseResult<SqlSelect> {
     match expr {
@@ -144,6 +189,7 @@ fn parse_select(select: Select) -> SqlParseResult<SqlSelect> {
                 from: SubParser::parse_from(from)?,
                 filter: parse_expr_opt(selection)?,
                 project: parse_projection(projection)?,
+                top_n: None,
             })
         }
         _ => Err(SubscriptionUnsupported::Select(select).into()),
@@ -162,6 +208,12 @@ mod tests {
             "",
             "select distinct a from t",
             "select * from (select * from t) join (select * from s) on a = b",
+            "select * from t order by a",
+            "select * from t limit 10",
+            "select * from t order by a, b limit 10",
+            "select * from t order by a limit b",
+            "select * from t order by a limit -1",
+            "select t.* from t join s on t.c = s.d order by t.a limit 10",
         ] {
             assert!(parse_subscription(sql).is_err());
         }
@@ -177,6 +229,13 @@ mod tests {
             "select t.* from t join s",
             "select t.* from t join s on t.c = s.d",
             "select a.* from t as a join s as b on a.c = b.d",
+            // Quoted identifiers may be keywords
+            "select * from \"order\"",
+            "select * from \"order\" where \"user\" = 1",
+            "select \"order\".* from \"order\" join \"user\" on \"order\".\"user\" = \"user\".id",
+            "select * from t order by a limit 10",
+            "select * from t where a > 1 order by t.a desc limit 10",
+            "select * from t as r order by r.a asc limit 10",
         ] {
             assert!(parse_subscription(sql).is_ok());
         }
@@ -0,0 +1,21 @@
+#!/bin/sh
+# Runs the given command with the databases' commitlogs and snapshots
+# on a small filesystem of their own, backed by an image file in the data volume.
+#
+# The docker smoketests (`smoketests/tests/zz_docker.py`) freeze or fill up this filesystem
+# to inject durability faults, without touching the host's disk.
+set -e
+
+image=/stdb/replicas.img
+replicas=/stdb/data/replicas
+
+if ! mountpoint -q "$replicas"; then
+    if [ ! -f "$image" ]; then
+        truncate -s 1G "$image"
+        mkfs.ext4 -q -m 0 "$image"
+    fi
+    mkdir -p "$replicas"
+    mount -o loop "$image" "$replicas"
+fi
+
+exec "$@"
@@ -1,6 +1,6 @@
-use spacetimedb::energy;
+use spacetimedb::energy::{self, EnergyLedgerEntry, EnergyResource, LedgerDay};
 use spacetimedb::identity::Identity;
-use spacetimedb::messages::control_db::{Database, EnergyBalance, Node, Replica};
+use spacetimedb::messages::control_db::{Database, EnergyBalance, ModuleVersion, Node, Replica};
 
 use spacetimedb_client_api_messages::name::{
     DomainName, DomainParsingError, InsertDomainResult, RegisterTldResult, Tld, TldRef,
@@ -266,12 +266,47 @@ impl ControlDb {
 
             tree_by_identity.remove(&key[..])?;
             tree.remove(id.to_be_bytes())?;
+
+            let versions = self.db.open_tree("module_version")?;
+            for entry in versions.scan_prefix(id.to_be_bytes()) {
+                let (key, _) = entry?;
+                versions.remove(key)?;
+            }
             return Ok(Some(id));
         }
 
         Ok(None)
     }
 
+    /// Append `version` to the version history of its database,
+    /// assigning it the version number following the latest one, which is returned.
+    pub fn insert_module_version(&self, mut version: ModuleVersion) -> Result<u64> {
+        let tree = self.db.open_tree("module_version")?;
+        let prefix = version.database_id.to_be_bytes();
+        let latest = match tree.scan_prefix(prefix).next_back().transpose()? {
+            Some((_, value)) => bsatn::from_slice::<ModuleVersion>(&value)?.version,
+            None => 0,
+        };
+        version.version = latest + 1;
+
+        let mut key = prefix.to_vec();
+        key.extend_from_slice(&version.version.to_be_bytes());
+        tree.insert(key, bsatn::to_vec(&version).unwrap())?;
+
+        Ok(version.version)
+    }
+
+    /// Return the version history of the database `database_id`, oldest first.
+    pub fn get_module_versions(&self, database_id: u64) -> Result<Vec<ModuleVersion>> {
+        let tree = self.db.open_tree("module_version")?;
+        let mut versions = Vec::new();
+        for entry in tree.scan_prefix(database_id.to_be_bytes()) {
+            let (_key, value) = entry?;
+            versions.push(bsatn::from_slice(&value)?);
+        }
+        Ok(versions)
+    }
+
     pub fn get_replicas(&self) -> Result<Vec<Replica>> {
         let tree = self.db.open_tree("replica")?;
         let mut replicas = Vec::new();
@@ -447,6 +482,119 @@ impl ControlDb {
 
         Ok(())
     }
+
+    /// Add the calls and energy of `usage` to the energy ledger entry
+    /// for the same day, identity, database, resource, and reducer.
+    pub fn add_energy_usage(&self, usage: &EnergyLedgerEntry) -> Result<()> {
+        let tree = self.db.open_tree("energy_ledger")?;
+        let key = ledger_key(usage);
+        tree.update_and_fetch(key, |value| {
+            let (calls, energy_used) = value.map_or((0, 0), decode_ledger_value);
+            let calls = calls.saturating_add(usage.calls);
+            let energy_used = energy_used.saturating_add(usage.energy_used.get());
+            let mut value = [0; 24];
+            value[..8].copy_from_slice(&calls.to_be_bytes());
+            value[8..].copy_from_slice(&energy_used.to_be_bytes());
+            Some(value.to_vec())
+        })?;
+        Ok(())
+    }
+
+    /// Return the energy ledger entries billed to `identity`
+    /// for the days from `from` up to and including `to`, ordered by day.
+    pub fn get_energy_ledger(
+        &self,
+        identity: &Identity,
+        from: Option<LedgerDay>,
+        to: Option<LedgerDay>,
+    ) -> Result<Vec<EnergyLedgerEntry>> {
+        let tree = self.db.open_tree("energy_ledger")?;
+        let prefix = identity.to_byte_array();
+        let mut start = prefix.to_vec();
+        start.extend_from_slice(&from.map_or(0, LedgerDay::days).to_be_bytes());
+
+        let mut entries = vec![];
+        for entry in tree.range(start..) {
+            let (key, value) = entry?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let entry = decode_ledger_entry(&key, &value)?;
+            if to.is_some_and(|to| entry.day > to) {
+                break;
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+/// The key of an energy ledger entry, ordered so that the entries billed to an identity
+/// are contiguous and sorted by day:
+/// `identity (32 bytes) ++ day (4 bytes, big-endian) ++ database identity (32 bytes) ++ resource (1 byte) ++ reducer`.
+fn ledger_key(entry: &EnergyLedgerEntry) -> Vec<u8> {
+    let reducer = entry.reducer.as_deref().unwrap_or_default();
+    let mut key = Vec::with_capacity(69 + reducer.len());
+    key.extend_from_slice(&entry.identity.to_byte_array());
+    key.extend_from_slice(&entry.day.days().to_be_bytes());
+    key.extend_from_slice(&entry.database_identity.to_byte_array());
+    key.push(match entry.resource {
+        EnergyResource::Reducer => 0,
+        EnergyResource::Memory => 1,
+        EnergyResource::Disk => 2,
+    });
+    key.extend_from_slice(reducer.as_bytes());
+    key
+}
+
+/// Decode the number of calls and the energy used from the value of an energy ledger entry.
+fn decode_ledger_value(value: &[u8]) -> (u64, u128) {
+    let calls = value
+        .get(..8)
+        .and_then(|b| b.try_into().ok())
+        .map_or(0, u64::from_be_bytes);
+    let energy_used = value
+        .get(8..24)
+        .and_then(|b| b.try_into().ok())
+        .map_or(0, u128::from_be_bytes);
+    (calls, energy_used)
+}
+
+fn decode_ledger_entry(key: &[u8], value: &[u8]) -> Result<EnergyLedgerEntry> {
+    if key.len() < 69 {
+        return Err(bsatn::DecodeError::BufferLength {
+            for_type: "EnergyLedgerEntry",
+            expected: 69,
+            given: key.len(),
+        }
+        .into());
+    }
+    let resource = match key[68] {
+        0 => EnergyResource::Reducer,
+        1 => EnergyResource::Memory,
+        2 => EnergyResource::Disk,
+        tag => {
+            return Err(bsatn::DecodeError::InvalidTag {
+                tag,
+                sum_name: Some("EnergyResource".into()),
+            }
+            .into())
+        }
+    };
+    let reducer = (resource == EnergyResource::Reducer)
+        .then(|| String::from_utf8(key[69..].to_vec()))
+        .transpose()
+        .map_err(|_| bsatn::DecodeError::InvalidUtf8)?;
+    let (calls, energy_used) = decode_ledger_value(value);
+    Ok(EnergyLedgerEntry {
+        day: LedgerDay::new(u32::from_be_bytes(key[32..36].try_into().unwrap())),
+        identity: Identity::from_byte_array(key[..32].try_into().unwrap()),
+        database_identity: Identity::from_byte_array(key[36..68].try_into().unwrap()),
+        resource,
+        reducer,
+        calls,
+        energy_used: energy::EnergyQuanta::new(energy_used),
+    })
 }
 
 mod compat {
@@ -2,8 +2,9 @@ use std::str::FromStr;
 
 use once_cell::sync::Lazy;
 use spacetimedb::messages::control_db::HostType;
+use spacetimedb_client_api_messages::timestamp::Timestamp;
 use spacetimedb_lib::error::ResultTest;
-use spacetimedb_lib::Hash;
+use spacetimedb_lib::{hash_bytes, Hash};
 use tempfile::TempDir;
 
 use super::*;
@@ -127,3 +128,76 @@ fn test_decode() -> ResultTest<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_energy_ledger() -> anyhow::Result<()> {
+    let tmp = TempDir::with_prefix("energy-ledger")?;
+    let cdb = ControlDb::at(tmp.path())?;
+
+    let database_identity = Identity::from_hashing_bytes("database");
+    let usage = |day, identity, resource, reducer: Option<&str>, calls, energy_used| EnergyLedgerEntry {
+        day: LedgerDay::new(day),
+        identity,
+        database_identity,
+        resource,
+        reducer: reducer.map(Into::into),
+        calls,
+        energy_used: energy::EnergyQuanta::new(energy_used),
+    };
+
+    cdb.add_energy_usage(&usage(1, *ALICE, EnergyResource::Reducer, Some("send"), 1, 10))?;
+    cdb.add_energy_usage(&usage(1, *ALICE, EnergyResource::Reducer, Some("send"), 1, 15))?;
+    cdb.add_energy_usage(&usage(1, *ALICE, EnergyResource::Memory, None, 0, 100))?;
+    cdb.add_energy_usage(&usage(2, *ALICE, EnergyResource::Reducer, Some("send"), 1, 20))?;
+    cdb.add_energy_usage(&usage(1, *BOB, EnergyResource::Reducer, Some("send"), 1, 1000))?;
+
+    // Usage on the same day is rolled up into one entry.
+    let ledger = cdb.get_energy_ledger(&ALICE, None, None)?;
+    assert_eq!(
+        ledger,
+        [
+            usage(1, *ALICE, EnergyResource::Reducer, Some("send"), 2, 25),
+            usage(1, *ALICE, EnergyResource::Memory, None, 0, 100),
+            usage(2, *ALICE, EnergyResource::Reducer, Some("send"), 1, 20),
+        ]
+    );
+
+    let ledger = cdb.get_energy_ledger(&ALICE, Some(LedgerDay::new(2)), None)?;
+    assert_eq!(ledger, [usage(2, *ALICE, EnergyResource::Reducer, Some("send"), 1, 20)]);
+    let ledger = cdb.get_energy_ledger(&ALICE, None, Some(LedgerDay::new(1)))?;
+    assert_eq!(ledger.len(), 2);
+    let ledger = cdb.get_energy_ledger(&BOB, None, None)?;
+    assert_eq!(ledger, [usage(1, *BOB, EnergyResource::Reducer, Some("send"), 1, 1000)]);
+
+    Ok(())
+}
+
+#[test]
+fn test_module_versions() -> anyhow::Result<()> {
+    let tmp = TempDir::with_prefix("module-versions")?;
+    let cdb = ControlDb::at(tmp.path())?;
+
+    let version = |database_id, program: &str| ModuleVersion {
+        database_id,
+        version: 0,
+        program_hash: hash_bytes(program),
+        host_type: HostType::Wasm,
+        module_def: Default::default(),
+        published_at: Timestamp::from_microseconds(0),
+        publisher: *ALICE,
+    };
+
+    assert_eq!(cdb.insert_module_version(version(1, "a"))?, 1);
+    assert_eq!(cdb.insert_module_version(version(2, "b"))?, 1);
+    assert_eq!(cdb.insert_module_version(version(1, "c"))?, 2);
+
+    let versions = cdb.get_module_versions(1)?;
+    assert_eq!(
+        versions.iter().map(|v| (v.version, v.program_hash)).collect::<Vec<_>>(),
+        [(1, version(1, "a").program_hash), (2, version(1, "c").program_hash)]
+    );
+    assert_eq!(cdb.get_module_versions(2)?.len(), 1);
+    assert!(cdb.get_module_versions(3)?.is_empty());
+
+    Ok(())
+}
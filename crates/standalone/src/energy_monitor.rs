@@ -1,5 +1,8 @@
 use crate::control_db::ControlDb;
-use spacetimedb::energy::{EnergyBalance, EnergyMonitor, EnergyQuanta, ReducerBudget, ReducerFingerprint};
+use spacetimedb::energy::{
+    EnergyBalance, EnergyLedgerEntry, EnergyMonitor, EnergyQuanta, EnergyResource, LedgerDay, ReducerBudget,
+    ReducerFingerprint,
+};
 use spacetimedb::messages::control_db::Database;
 use spacetimedb_lib::Identity;
 use std::time::Duration;
@@ -19,6 +22,31 @@ impl StandaloneEnergyMonitor {
         }
         crate::withdraw_energy(&self.control_db, &identity, amount).unwrap();
     }
+
+    /// Record `usage` in the energy ledger.
+    ///
+    /// The ledger is only used for reporting, so failing to record usage is logged rather than fatal.
+    fn record_usage(&self, usage: EnergyLedgerEntry) {
+        if let Err(e) = self.control_db.add_energy_usage(&usage) {
+            log::error!("Failed to record energy usage of {}: {e}", usage.database_identity);
+        }
+    }
+
+    fn record_storage(&self, database: &Database, resource: EnergyResource, amount: EnergyQuanta) {
+        self.withdraw_energy(database.owner_identity, amount);
+        if amount.get() == 0 {
+            return;
+        }
+        self.record_usage(EnergyLedgerEntry {
+            day: LedgerDay::today(),
+            identity: database.owner_identity,
+            database_identity: database.database_identity,
+            resource,
+            reducer: None,
+            calls: 0,
+            energy_used: amount,
+        });
+    }
 }
 
 impl EnergyMonitor for StandaloneEnergyMonitor {
@@ -33,17 +61,26 @@ impl EnergyMonitor for StandaloneEnergyMonitor {
         energy_used: EnergyQuanta,
         _execution_duration: Duration,
     ) {
-        self.withdraw_energy(fingerprint.module_identity, energy_used)
+        self.withdraw_energy(fingerprint.module_identity, energy_used);
+        self.record_usage(EnergyLedgerEntry {
+            day: LedgerDay::today(),
+            identity: fingerprint.module_identity,
+            database_identity: fingerprint.database_identity,
+            resource: EnergyResource::Reducer,
+            reducer: Some(fingerprint.reducer_name.into()),
+            calls: 1,
+            energy_used,
+        });
     }
 
     fn record_disk_usage(&self, database: &Database, _replica_id: u64, disk_usage: u64, period: Duration) {
         let amount = EnergyQuanta::from_disk_usage(disk_usage, period);
-        self.withdraw_energy(database.owner_identity, amount)
+        self.record_storage(database, EnergyResource::Disk, amount)
     }
 
     fn record_memory_usage(&self, database: &Database, _instance_id: u64, mem_usage: u64, period: Duration) {
         let amount = EnergyQuanta::from_memory_usage(mem_usage, period);
-        self.withdraw_energy(database.owner_identity, amount)
+        self.record_storage(database, EnergyResource::Memory, amount)
     }
 }
 
@@ -1,7 +1,9 @@
 mod control_db;
 mod energy_monitor;
+mod listen;
 pub mod routes;
 pub mod subcommands;
+mod tls;
 pub mod util;
 
 use crate::control_db::ControlDb;
@@ -10,21 +12,28 @@ use anyhow::{ensure, Context};
 use async_trait::async_trait;
 use clap::{ArgMatches, Command};
 use energy_monitor::StandaloneEnergyMonitor;
+use futures::StreamExt;
 use spacetimedb::client::ClientActorIndex;
-use spacetimedb::config::{CertificateAuthority, MetadataFile};
-use spacetimedb::db::relational_db::{self, Durability, Txdata};
+use spacetimedb::config::{AuthConfig, CertificateAuthority, ConfigFile, DurabilityConfig, MetadataFile};
+use spacetimedb::db::relational_db::{self, ArchiveOptions, Durability, LocalDurabilityOptions, Txdata};
 use spacetimedb::db::{db_metrics::DB_METRICS, Config};
-use spacetimedb::energy::{EnergyBalance, EnergyQuanta};
-use spacetimedb::host::{DiskStorage, DurabilityProvider, ExternalDurability, HostController, UpdateDatabaseResult};
+use spacetimedb::energy::{EnergyBalance, EnergyLedgerEntry, EnergyQuanta, LedgerDay};
+use spacetimedb::host::{
+    DiskStorage, DurabilityBackends, DurabilityProvider, ExternalDurability, HostController, ReplicaStatus,
+    UpdateDatabaseResult,
+};
 use spacetimedb::identity::Identity;
-use spacetimedb::messages::control_db::{Database, Node, Replica};
+use spacetimedb::messages::control_db::{Database, HostType, ModuleVersion, Node, Replica};
 use spacetimedb::worker_metrics::WORKER_METRICS;
-use spacetimedb_client_api::auth::{self, LOCALHOST};
+use spacetimedb_client_api::auth::{self, JwtAuthProvider, LOCALHOST};
 use spacetimedb_client_api::{Host, NodeDelegate};
 use spacetimedb_client_api_messages::name::{DomainName, InsertDomainResult, RegisterTldResult, Tld};
+use spacetimedb_client_api_messages::timestamp::Timestamp;
 use spacetimedb_paths::server::{ModuleLogsDir, PidFile, ServerDataDir};
 use spacetimedb_paths::standalone::StandaloneDataDirExt;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 pub use spacetimedb_client_api::routes::subscribe::{BIN_PROTOCOL, TEXT_PROTOCOL};
 
@@ -36,8 +45,13 @@ pub struct StandaloneEnv {
     metrics_registry: prometheus::Registry,
     _pid_file: PidFile,
     auth_provider: auth::DefaultJwtAuthProvider,
+    /// Set once [`Self::launch_databases`] has finished.
+    ready: AtomicBool,
 }
 
+/// How many databases [`StandaloneEnv::launch_databases`] launches at the same time.
+const MAX_CONCURRENT_LAUNCHES: usize = 8;
+
 impl StandaloneEnv {
     pub async fn init(
         config: Config,
@@ -64,15 +78,27 @@ impl StandaloneEnv {
         let energy_monitor = Arc::new(StandaloneEnergyMonitor::new(control_db.clone()));
         let program_store = Arc::new(DiskStorage::new(data_dir.program_bytes().0).await?);
 
-        let durability_provider = Arc::new(StandaloneDurabilityProvider {
-            data_dir: data_dir.clone(),
-        });
+        let durability_backends = DurabilityBackends::default()
+            .with(
+                DurabilityConfig::DEFAULT_BACKEND,
+                Arc::new(StandaloneDurabilityProvider {
+                    data_dir: data_dir.clone(),
+                    defaults: LocalDurabilityOptions::default(),
+                }),
+            )
+            .with(
+                "local-nfs",
+                Arc::new(StandaloneDurabilityProvider {
+                    data_dir: data_dir.clone(),
+                    defaults: LocalDurabilityOptions::network_fs(),
+                }),
+            );
         let host_controller = HostController::new(
             data_dir,
             config,
             program_store.clone(),
             energy_monitor,
-            durability_provider,
+            durability_backends,
         );
         let client_actor_index = ClientActorIndex::new();
         let jwt_keys = certs.get_or_create_keys()?;
@@ -91,23 +117,113 @@ impl StandaloneEnv {
             metrics_registry,
             _pid_file,
             auth_provider: auth_env,
+            ready: AtomicBool::new(false),
         }))
     }
 
     pub fn data_dir(&self) -> &Arc<ServerDataDir> {
         &self.host_controller.data_dir
     }
+
+    /// Apply the settings of `config` which can change while the server runs,
+    /// as described in [`ConfigFile`].
+    pub async fn reload_config(&self, config: ConfigFile) {
+        self.set_auth_config(config.auth);
+        self.host_controller
+            .reload_config(|db_config| {
+                db_config.quotas = config.quotas;
+                db_config.durability = config.durability;
+                db_config.memory = config.memory;
+            })
+            .await;
+    }
+
+    /// Launch the leaders of all databases, so that they are recovered before clients ask for them,
+    /// and report the server as ready once each has been launched or failed to launch.
+    pub async fn launch_databases(&self) {
+        let databases = self.control_db.get_databases().unwrap_or_else(|e| {
+            log::error!("failed to list databases to launch: {e:#}");
+            vec![]
+        });
+        let launches = databases.into_iter().filter_map(|database| {
+            let leader = self.control_db.get_leader_replica_by_database(database.id)?;
+            Some(async move {
+                let database_identity = database.database_identity;
+                if let Err(e) = self
+                    .host_controller
+                    .get_or_launch_module_host(database, leader.id)
+                    .await
+                {
+                    log::error!("failed to launch database {database_identity}: {e:#}");
+                }
+            })
+        });
+        futures::stream::iter(launches)
+            .buffer_unordered(MAX_CONCURRENT_LAUNCHES)
+            .collect::<()>()
+            .await;
+        log::info!("launched all databases");
+        self.ready.store(true, Ordering::Release);
+    }
+
+    /// Shut down the databases for the server to exit, after its clients have disconnected.
+    ///
+    /// See [`HostController::shutdown`].
+    pub async fn shutdown(&self, flush_timeout: Duration, snapshot: bool) {
+        self.ready.store(false, Ordering::Release);
+        self.host_controller.shutdown(flush_timeout, snapshot).await;
+    }
+
+    /// Accept tokens from the clients allowed by `auth`.
+    pub fn set_auth_config(&self, auth: AuthConfig) {
+        self.auth_provider.validator().allowed_issuers.set(auth.allowed_issuers);
+    }
+
+    /// Append the module now running on the leader of the database `database_id`
+    /// to the database's version history.
+    async fn record_module_version(
+        &self,
+        database_id: u64,
+        host_type: HostType,
+        publisher: &Identity,
+    ) -> anyhow::Result<()> {
+        let module = self
+            .leader(database_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("No leader for database"))?
+            .module()
+            .await?;
+        let info = module.info();
+        self.control_db.insert_module_version(ModuleVersion {
+            database_id,
+            version: 0,
+            program_hash: info.module_hash,
+            host_type,
+            module_def: info.module_def.clone().into(),
+            published_at: Timestamp::now(),
+            publisher: *publisher,
+        })?;
+        Ok(())
+    }
 }
 
+/// Keeps the commitlog of each replica in its replica directory.
 struct StandaloneDurabilityProvider {
     data_dir: Arc<ServerDataDir>,
+    /// The parameters of the commitlog, unless overridden by the database's config.
+    defaults: LocalDurabilityOptions,
 }
 
 #[async_trait]
 impl DurabilityProvider for StandaloneDurabilityProvider {
-    async fn durability(&self, replica_id: u64) -> anyhow::Result<ExternalDurability> {
+    async fn durability(
+        &self,
+        replica_id: u64,
+        group_commit_window: Option<Duration>,
+        archive: Option<ArchiveOptions>,
+    ) -> anyhow::Result<ExternalDurability> {
         let commitlog_dir = self.data_dir.replica(replica_id).commit_log();
-        relational_db::local_durability(commitlog_dir)
+        relational_db::local_durability_with(commitlog_dir, self.defaults.clone(), group_commit_window, archive)
             .await
             .map(|(durability, disk_size)| (durability as Arc<dyn Durability<TxData = Txdata>>, disk_size))
             .map_err(Into::into)
@@ -148,9 +264,24 @@ impl NodeDelegate for StandaloneEnv {
 
         Ok(Some(Host::new(leader.id, self.host_controller.clone())))
     }
+    async fn leader_status(&self, database: &Database) -> anyhow::Result<Option<ReplicaStatus>> {
+        let Some(leader) = self.control_db.get_leader_replica_by_database(database.id) else {
+            return Ok(None);
+        };
+        let status = self
+            .host_controller
+            .replica_status(&database.database_identity, leader.id)
+            .await?;
+        Ok(Some(status))
+    }
+
     fn module_logs_dir(&self, replica_id: u64) -> ModuleLogsDir {
         self.data_dir().replica(replica_id).module_logs()
     }
+
+    fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
 }
 
 impl spacetimedb_client_api::ControlStateReadAccess for StandaloneEnv {
@@ -187,6 +318,10 @@ impl spacetimedb_client_api::ControlStateReadAccess for StandaloneEnv {
         Ok(self.control_db.get_databases()?)
     }
 
+    fn get_module_versions(&self, database_id: u64) -> anyhow::Result<Vec<ModuleVersion>> {
+        Ok(self.control_db.get_module_versions(database_id)?)
+    }
+
     // Replicas
     fn get_replica_by_id(&self, id: u64) -> anyhow::Result<Option<Replica>> {
         Ok(self.control_db.get_replica_by_id(id)?)
@@ -203,6 +338,14 @@ impl spacetimedb_client_api::ControlStateReadAccess for StandaloneEnv {
     fn get_energy_balance(&self, identity: &Identity) -> anyhow::Result<Option<EnergyBalance>> {
         Ok(self.control_db.get_energy_balance(identity)?)
     }
+    fn get_energy_ledger(
+        &self,
+        identity: &Identity,
+        from: Option<LedgerDay>,
+        to: Option<LedgerDay>,
+    ) -> anyhow::Result<Vec<EnergyLedgerEntry>> {
+        Ok(self.control_db.get_energy_ledger(identity, from, to)?)
+    }
 
     // DNS
     fn lookup_identity(&self, domain: &DomainName) -> anyhow::Result<Option<Identity>> {
@@ -238,6 +381,8 @@ impl spacetimedb_client_api::ControlStateWriteAccess for StandaloneEnv {
                 database.id = database_id;
 
                 self.schedule_replicas(database_id, spec.num_replicas).await?;
+                self.record_module_version(database_id, spec.host_type, publisher)
+                    .await?;
 
                 Ok(None)
             }
@@ -255,6 +400,8 @@ impl spacetimedb_client_api::ControlStateWriteAccess for StandaloneEnv {
                 let database_identity = database.database_identity;
 
                 let num_replicas = spec.num_replicas;
+                // Keep the program, so that this version can be returned to later.
+                self.program_store.put(&spec.program_bytes).await?;
                 let leader = self
                     .leader(database_id)
                     .await?
@@ -262,6 +409,10 @@ impl spacetimedb_client_api::ControlStateWriteAccess for StandaloneEnv {
                 let update_result = leader
                     .update(database, spec.host_type, spec.program_bytes.into())
                     .await?;
+                if let UpdateDatabaseResult::UpdatePerformed = update_result {
+                    self.record_module_version(database_id, spec.host_type, publisher)
+                        .await?;
+                }
                 if update_result.was_successful() {
                     let replicas = self.control_db.get_replicas_by_database(database_id)?;
                     let desired_replicas = num_replicas as usize;
@@ -333,6 +484,69 @@ impl spacetimedb_client_api::ControlStateWriteAccess for StandaloneEnv {
         Ok(())
     }
 
+    async fn fork_database(
+        &self,
+        caller_identity: &Identity,
+        source_identity: &Identity,
+        target_identity: Identity,
+    ) -> anyhow::Result<()> {
+        let source = self
+            .control_db
+            .get_database_by_identity(source_identity)?
+            .with_context(|| format!("No such database: `{}`", source_identity.to_abbreviated_hex()))?;
+        ensure!(
+            &source.owner_identity == caller_identity,
+            "Permission denied: `{caller_identity}` does not own database `{}`",
+            source_identity.to_abbreviated_hex()
+        );
+        ensure!(
+            self.control_db.get_database_by_identity(&target_identity)?.is_none(),
+            "Database `{}` already exists",
+            target_identity.to_abbreviated_hex()
+        );
+        let source_leader = self
+            .control_db
+            .get_leader_replica_by_database(source.id)
+            .with_context(|| format!("No leader for database `{}`", source_identity.to_abbreviated_hex()))?;
+
+        let mut target = Database {
+            id: 0,
+            database_identity: target_identity,
+            owner_identity: *caller_identity,
+            host_type: source.host_type,
+            initial_program: source.initial_program,
+        };
+        target.id = self.control_db.insert_database(target.clone())?;
+        // Register the leader without launching it,
+        // so that its directory holds the fork by the time it is launched.
+        let target_replica_id = self.control_db.insert_replica(Replica {
+            id: 0,
+            database_id: target.id,
+            node_id: 0,
+            leader: true,
+        })?;
+
+        let forked = self
+            .host_controller
+            .fork_database(&source, source_leader.id, &target, target_replica_id)
+            .await;
+        if let Err(e) = forked {
+            self.control_db.delete_replica(target_replica_id)?;
+            self.control_db.delete_database(target.id)?;
+            return Err(e);
+        }
+
+        self.leader(target.id).await?;
+        // The fork inherits the version history of its source.
+        for version in self.control_db.get_module_versions(source.id)? {
+            self.control_db.insert_module_version(ModuleVersion {
+                database_id: target.id,
+                ..version
+            })?;
+        }
+        Ok(())
+    }
+
     async fn add_energy(&self, identity: &Identity, amount: EnergyQuanta) -> anyhow::Result<()> {
         let balance = self
             .control_db
@@ -460,6 +674,7 @@ pub async fn start_server(data_dir: &ServerDataDir, cert_dir: Option<&std::path:
 mod tests {
     use super::*;
     use anyhow::Result;
+    use spacetimedb::config::{DurabilityConfig, MemoryConfig, QuotaConfig, ShutdownConfig};
     use spacetimedb::db::Storage;
     use spacetimedb_paths::{cli::*, FromPathUnchecked};
     use std::fs;
@@ -487,12 +702,65 @@ mod tests {
         ca.get_or_create_keys()?;
         let config = Config {
             storage: Storage::Memory,
+            quotas: QuotaConfig::UNLIMITED,
+            durability: DurabilityConfig::DEFAULT,
+            memory: MemoryConfig::DEFAULT,
         };
 
-        let _env = StandaloneEnv::init(config, &ca, data_dir.clone()).await?;
+        let _env = StandaloneEnv::init(config.clone(), &ca, data_dir.clone()).await?;
         // Ensure that we have a lock.
         assert!(StandaloneEnv::init(config, &ca, data_dir.clone()).await.is_err());
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn ready_once_databases_are_launched() -> Result<()> {
+        use axum::extract::State;
+        use axum::response::IntoResponse;
+        use futures::FutureExt;
+        use http::StatusCode;
+        use spacetimedb_client_api::routes::health::{health, ready};
+
+        let tempdir = TempDir::new()?;
+        let keys = tempdir.path().join("keys");
+        let data_dir = Arc::new(ServerDataDir::from_path_unchecked(tempdir.path().join("data")));
+        fs::create_dir(&keys)?;
+        data_dir.create()?;
+        let ca = CertificateAuthority {
+            jwt_pub_key_path: PubKeyPath(keys.join("public")),
+            jwt_priv_key_path: PrivKeyPath(keys.join("private")),
+        };
+        let config = Config {
+            storage: Storage::Memory,
+            quotas: QuotaConfig::UNLIMITED,
+            durability: DurabilityConfig::DEFAULT,
+            memory: MemoryConfig::DEFAULT,
+        };
+        let env = StandaloneEnv::init(config, &ca, data_dir).await?;
+        let ready_status = || ready(State(env.clone())).map(|response| response.into_response().status());
+
+        // The server is alive, but not ready, while it launches its databases.
+        assert_eq!(health().await.into_response().status(), StatusCode::OK);
+        assert_eq!(ready_status().await, StatusCode::SERVICE_UNAVAILABLE);
+
+        env.launch_databases().await;
+        assert_eq!(ready_status().await, StatusCode::OK);
+
+        env.shutdown(Duration::ZERO, false).await;
+        assert_eq!(ready_status().await, StatusCode::SERVICE_UNAVAILABLE);
+
+        Ok(())
+    }
+
+    #[test]
+    fn default_config_parses() -> Result<()> {
+        let config: ConfigFile = toml::from_str(include_str!("../config.toml"))?;
+        assert!(config.server.listen_addr.is_none());
+        assert!(config.auth.allowed_issuers.is_none());
+        assert!(config.tls.is_none());
+        assert_eq!(config.shutdown.timeout_secs, ShutdownConfig::default().timeout_secs);
+        assert!(!config.shutdown.snapshot);
+        Ok(())
+    }
 }
@@ -0,0 +1,395 @@
+//! Listening for connections on a TCP or unix socket, and serving the HTTP API to them.
+//!
+//! To restart the server, e.g. to upgrade it, without refusing new connections,
+//! a running server can hand its listening socket to a new server process, see [`Handoff`].
+//! This is a graceful restart rather than a takeover:
+//! the connections the old server is serving, including WebSockets, are closed as it shuts down,
+//! and their clients have to reconnect.
+
+use std::convert::Infallible;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::process::Child;
+use std::time::Duration;
+use std::{env, io};
+
+use anyhow::Context;
+use axum::extract::ConnectInfo;
+use axum::response::Response;
+use hyper::body::Incoming;
+use hyper::Request;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use hyper_util::service::TowerToHyperService;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tower_http::add_extension::AddExtension;
+
+use crate::tls::TlsAcceptor;
+
+/// Where the server listens for connections.
+#[derive(Clone, Debug)]
+pub enum ListenAddr {
+    /// A TCP address and port, e.g. `0.0.0.0:3000`.
+    Tcp(String),
+    /// The path of a unix socket, written as `unix:/run/spacetimedb.sock`.
+    Unix(PathBuf),
+}
+
+impl ListenAddr {
+    pub fn parse(addr: &str) -> Self {
+        match addr.strip_prefix("unix:") {
+            Some(path) => Self::Unix(path.into()),
+            None => Self::Tcp(addr.to_owned()),
+        }
+    }
+}
+
+/// The environment variable through which a server hands its listening socket
+/// to the server process replacing it, see [`Handoff`].
+const LISTEN_FD_VAR: &str = "SPACETIMEDB_LISTEN_FD";
+
+/// A socket listening for connections.
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl Listener {
+    /// Listens for connections on `addr`.
+    pub async fn bind(addr: &ListenAddr) -> anyhow::Result<Self> {
+        match addr {
+            ListenAddr::Tcp(addr) => {
+                let tcp = TcpListener::bind(addr).await?;
+                socket2::SockRef::from(&tcp).set_nodelay(true)?;
+                Ok(Self::Tcp(tcp))
+            }
+            ListenAddr::Unix(path) => bind_unix(path),
+        }
+    }
+
+    /// Takes over the socket listening on `addr` which the server that started this process handed to it,
+    /// or returns `None` if this process wasn't started by [`Handoff::spawn`].
+    pub fn inherit(addr: &ListenAddr) -> anyhow::Result<Option<Self>> {
+        let Some(fd) = env::var_os(LISTEN_FD_VAR) else {
+            return Ok(None);
+        };
+        // Don't pass the socket on to processes we start, other than by handing it off ourselves.
+        env::remove_var(LISTEN_FD_VAR);
+        let fd = fd
+            .to_str()
+            .and_then(|fd| fd.parse().ok())
+            .with_context(|| format!("{LISTEN_FD_VAR} must be a file descriptor, not {fd:?}"))?;
+        inherit_fd(fd, addr).map(Some)
+    }
+}
+
+#[cfg(unix)]
+fn inherit_fd(fd: std::os::fd::RawFd, addr: &ListenAddr) -> anyhow::Result<Listener> {
+    use std::os::fd::{FromRawFd, OwnedFd};
+
+    // SAFETY: the server handing off the socket passed its descriptor to us in `LISTEN_FD_VAR`,
+    // and we removed the variable, so nothing else in this process takes ownership of it.
+    let socket = socket2::Socket::from(unsafe { OwnedFd::from_raw_fd(fd) });
+    socket.set_cloexec(true)?;
+    socket.set_nonblocking(true)?;
+    let is_tcp = socket.local_addr()?.as_socket().is_some();
+    match addr {
+        ListenAddr::Tcp(_) => {
+            anyhow::ensure!(
+                is_tcp,
+                "the handed off socket is not a TCP socket, but listen-addr is a TCP address"
+            );
+            Ok(Listener::Tcp(TcpListener::from_std(socket.into())?))
+        }
+        ListenAddr::Unix(_) => {
+            anyhow::ensure!(
+                !is_tcp,
+                "the handed off socket is a TCP socket, but listen-addr is a unix socket"
+            );
+            let unix = std::os::unix::net::UnixListener::from(OwnedFd::from(socket));
+            Ok(Listener::Unix(tokio::net::UnixListener::from_std(unix)?))
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn inherit_fd(_fd: u64, _addr: &ListenAddr) -> anyhow::Result<Listener> {
+    anyhow::bail!("taking over a listening socket is not supported on this platform")
+}
+
+/// A copy of a [`Listener`]'s socket, to hand over to a new server process
+/// so that restarting the server doesn't refuse new connections.
+pub struct Handoff(socket2::Socket);
+
+impl Handoff {
+    pub fn new(listener: &Listener) -> io::Result<Self> {
+        let socket = match listener {
+            Listener::Tcp(tcp) => socket2::SockRef::from(tcp).try_clone()?,
+            #[cfg(unix)]
+            Listener::Unix(unix) => socket2::SockRef::from(unix).try_clone()?,
+        };
+        Ok(Self(socket))
+    }
+
+    /// Starts a new server process with the same arguments as this one, which listens on this socket.
+    ///
+    /// The new process runs the executable at the path this one was started from,
+    /// i.e. the upgraded server if it was replaced there.
+    /// It waits for this process to release the data directory before opening the databases,
+    /// while connections made in the meantime wait in the socket's backlog instead of being refused.
+    /// The connections this process is serving are not handed over.
+    #[cfg(unix)]
+    pub fn spawn(&self) -> anyhow::Result<Child> {
+        use std::os::fd::AsRawFd;
+
+        let mut args = env::args_os();
+        let exe = args
+            .next()
+            .context("cannot hand off the listening socket: no executable path in argv")?;
+        // Let the new process inherit the socket, but only while starting it.
+        self.0.set_cloexec(false)?;
+        let child = std::process::Command::new(&exe)
+            .args(args)
+            .env(LISTEN_FD_VAR, self.0.as_raw_fd().to_string())
+            .spawn()
+            .with_context(|| format!("failed to start {}", Path::new(&exe).display()));
+        self.0.set_cloexec(true)?;
+        child
+    }
+
+    #[cfg(not(unix))]
+    pub fn spawn(&self) -> anyhow::Result<Child> {
+        anyhow::bail!("handing off the listening socket is not supported on this platform")
+    }
+}
+
+/// Serves `router` to the connections made to `listener`, over TLS if `tls` is set.
+///
+/// Once `shutdown` is cancelled, stops accepting connections,
+/// and returns when the requests in progress have been answered.
+/// Connections upgraded to WebSockets are no longer tracked by then, and must be closed separately.
+pub async fn serve(
+    listener: Listener,
+    router: axum::Router,
+    tls: Option<TlsAcceptor>,
+    shutdown: CancellationToken,
+) -> anyhow::Result<()> {
+    match listener {
+        Listener::Tcp(tcp) => {
+            let local_addr = tcp.local_addr()?;
+            match tls {
+                Some(tls) => {
+                    log::debug!("Starting SpacetimeDB listening with TLS on {local_addr}");
+                    serve_tls(tcp, tls, router, shutdown).await
+                }
+                None => {
+                    log::debug!("Starting SpacetimeDB listening on {local_addr}");
+                    let service = router.into_make_service_with_connect_info::<SocketAddr>();
+                    Ok(axum::serve(tcp, service)
+                        .with_graceful_shutdown(shutdown.cancelled_owned())
+                        .await?)
+                }
+            }
+        }
+        #[cfg(unix)]
+        Listener::Unix(unix) => {
+            anyhow::ensure!(tls.is_none(), "TLS cannot be used when listening on a unix socket");
+            serve_unix(unix, router, shutdown).await
+        }
+    }
+}
+
+async fn serve_tls(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    router: axum::Router,
+    shutdown: CancellationToken,
+) -> anyhow::Result<()> {
+    let connections = TaskTracker::new();
+    loop {
+        let (tcp, remote_addr) = tokio::select! {
+            conn = listener.accept() => match conn {
+                Ok(conn) => conn,
+                Err(e) => {
+                    accept_failed(e).await;
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => break,
+        };
+        let _ = tcp.set_nodelay(true);
+        let acceptor = acceptor.clone();
+        let service = AddExtension::new(router.clone(), ConnectInfo(remote_addr));
+        let shutdown = shutdown.clone();
+        connections.spawn(async move {
+            match acceptor.handshake(tcp).await {
+                Ok(stream) => {
+                    let service = TowerToHyperService::new(service);
+                    serve_connection(stream, service, remote_addr, shutdown).await
+                }
+                Err(e) => log::debug!("TLS handshake with {remote_addr} failed: {e:#}"),
+            }
+        });
+    }
+    drain(listener, connections).await;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn bind_unix(path: &Path) -> anyhow::Result<Listener> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // A socket left behind by a previous run would keep us from binding to the path.
+    if std::fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("could not listen on unix socket {}", path.display()))?;
+    Ok(Listener::Unix(listener))
+}
+
+#[cfg(not(unix))]
+fn bind_unix(_path: &Path) -> anyhow::Result<Listener> {
+    anyhow::bail!("listening on a unix socket is not supported on this platform")
+}
+
+#[cfg(unix)]
+async fn serve_unix(
+    listener: tokio::net::UnixListener,
+    router: axum::Router,
+    shutdown: CancellationToken,
+) -> anyhow::Result<()> {
+    match listener.local_addr()?.as_pathname() {
+        Some(path) => log::debug!("Starting SpacetimeDB listening on unix socket {}", path.display()),
+        None => log::debug!("Starting SpacetimeDB listening on an unnamed unix socket"),
+    }
+    let connections = TaskTracker::new();
+    loop {
+        let stream = tokio::select! {
+            conn = listener.accept() => match conn {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    accept_failed(e).await;
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => break,
+        };
+        let service = TowerToHyperService::new(router.clone());
+        connections.spawn(serve_connection(stream, service, "unix socket", shutdown.clone()));
+    }
+    drain(listener, connections).await;
+    Ok(())
+}
+
+/// Closes `listener`, and waits for the `connections` served from it to finish.
+async fn drain<L>(listener: L, connections: TaskTracker) {
+    drop(listener);
+    connections.close();
+    connections.wait().await;
+}
+
+/// Logs an error accepting a connection, and backs off for a moment,
+/// as errors such as running out of file descriptors are transient.
+async fn accept_failed(e: std::io::Error) {
+    log::error!("failed to accept connection: {e}");
+    tokio::time::sleep(Duration::from_secs(1)).await;
+}
+
+/// Serves HTTP, including WebSocket upgrades, on the connection `io` from `peer` until it is closed.
+///
+/// Once `shutdown` is cancelled, the connection is closed after answering the request in progress.
+async fn serve_connection<I, S>(io: I, service: S, peer: impl fmt::Display, shutdown: CancellationToken)
+where
+    I: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    S: hyper::service::Service<Request<Incoming>, Response = Response, Error = Infallible> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    let builder = auto::Builder::new(TokioExecutor::new());
+    let mut conn = pin!(builder.serve_connection_with_upgrades(TokioIo::new(io), service));
+    let result = tokio::select! {
+        result = conn.as_mut() => result,
+        _ = shutdown.cancelled() => {
+            conn.as_mut().graceful_shutdown();
+            conn.await
+        }
+    };
+    if let Err(e) = result {
+        log::debug!("error serving connection from {peer}: {e}");
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::os::fd::IntoRawFd;
+    use tempfile::TempDir;
+
+    /// Returns the descriptor of a copy of `handoff`'s socket, as a new server process would inherit it.
+    fn inherited_fd(handoff: &Handoff) -> Result<std::os::fd::RawFd> {
+        Ok(handoff.0.try_clone()?.into_raw_fd())
+    }
+
+    #[tokio::test]
+    async fn handed_off_tcp_socket_accepts_connections_made_meanwhile() -> Result<()> {
+        let addr = ListenAddr::Tcp("127.0.0.1:0".into());
+        let listener = Listener::bind(&addr).await?;
+        let Listener::Tcp(tcp) = &listener else { unreachable!() };
+        let local_addr = tcp.local_addr()?;
+        let handoff = Handoff::new(&listener)?;
+
+        // The old server stops listening before the new one takes over,
+        // but the socket stays open, so connections are queued rather than refused.
+        drop(listener);
+        let connecting = tokio::spawn(tokio::net::TcpStream::connect(local_addr));
+
+        let Listener::Tcp(tcp) = inherit_fd(inherited_fd(&handoff)?, &addr)? else {
+            panic!("expected a TCP listener");
+        };
+        drop(handoff);
+        let (_stream, peer) = tcp.accept().await?;
+        assert_eq!(peer, connecting.await??.local_addr()?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn handed_off_unix_socket_accepts_connections_made_meanwhile() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("spacetimedb.sock");
+        let addr = ListenAddr::Unix(path.clone());
+        let listener = Listener::bind(&addr).await?;
+        let handoff = Handoff::new(&listener)?;
+
+        drop(listener);
+        let connecting = tokio::spawn(tokio::net::UnixStream::connect(path));
+
+        let Listener::Unix(unix) = inherit_fd(inherited_fd(&handoff)?, &addr)? else {
+            panic!("expected a unix listener");
+        };
+        drop(handoff);
+        unix.accept().await?;
+        connecting.await??;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn inheriting_a_socket_of_another_kind_fails() -> Result<()> {
+        let dir = TempDir::new()?;
+        let tcp_addr = ListenAddr::Tcp("127.0.0.1:0".into());
+        let unix_addr = ListenAddr::Unix(dir.path().join("spacetimedb.sock"));
+
+        let tcp = Handoff::new(&Listener::bind(&tcp_addr).await?)?;
+        assert!(inherit_fd(inherited_fd(&tcp)?, &unix_addr).is_err());
+
+        let unix = Handoff::new(&Listener::bind(&unix_addr).await?)?;
+        assert!(inherit_fd(inherited_fd(&unix)?, &tcp_addr).is_err());
+        Ok(())
+    }
+}
@@ -1,30 +1,38 @@
+use std::sync::Arc;
+
 use http::header::{ACCEPT, AUTHORIZATION};
 use tower_http::cors::{Any, CorsLayer};
 
 use spacetimedb_client_api::{
-    routes::{database, energy, identity, metrics, prometheus},
+    routes::{database, energy, health, identity, metrics, prometheus},
+    util::client_ip::{resolve_client_ip, TrustedProxies},
     ControlStateDelegate, NodeDelegate,
 };
 
 #[allow(clippy::let_and_return)]
-pub fn router<S>(ctx: S) -> axum::Router<()>
+pub fn router<S>(ctx: S, test_mode: bool, trusted_proxies: TrustedProxies) -> axum::Router<()>
 where
     S: NodeDelegate + ControlStateDelegate + Clone + 'static,
 {
+    let mut database_routes = database::control_routes(ctx.clone()).merge(database::worker_routes(ctx.clone()));
+    if test_mode {
+        database_routes = database_routes.merge(database::test_routes(ctx.clone()));
+    }
+
     let router = axum::Router::new()
-        .nest(
-            "/database",
-            database::control_routes(ctx.clone()).merge(database::worker_routes(ctx.clone())),
-        )
+        .nest("/database", database_routes)
         .nest("/identity", identity::router(ctx.clone()))
         .nest("/energy", energy::router())
         .nest("/prometheus", prometheus::router())
-        .nest("/metrics", metrics::router());
+        .nest("/metrics", metrics::router())
+        .merge(health::router());
 
     let cors = CorsLayer::new()
         .allow_headers([AUTHORIZATION, ACCEPT])
         .allow_methods(Any)
         .allow_origin(Any);
 
-    router.layer(cors).with_state(ctx)
+    let client_ip = axum::middleware::from_fn_with_state(Arc::new(trusted_proxies), resolve_client_ip);
+
+    router.layer(cors).layer(client_ip).with_state(ctx)
 }
@@ -1,16 +1,23 @@
+use std::mem;
+use std::pin::pin;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::listen::{self, Handoff, ListenAddr, Listener};
 use crate::routes::router;
+use crate::tls::TlsAcceptor;
 use crate::StandaloneEnv;
 use anyhow::Context;
 use clap::ArgAction::SetTrue;
 use clap::{Arg, ArgMatches};
-use spacetimedb::config::{CertificateAuthority, ConfigFile};
+use spacetimedb::config::{CertificateAuthority, ConfigFile, ShutdownConfig};
 use spacetimedb::db::{Config, Storage};
 use spacetimedb::startup::{self, TracingOptions};
+use spacetimedb_client_api::util::client_ip::TrustedProxies;
+use spacetimedb_client_api::NodeDelegate;
 use spacetimedb_paths::cli::{PrivKeyPath, PubKeyPath};
-use spacetimedb_paths::server::ServerDataDir;
-use tokio::net::TcpListener;
+use spacetimedb_paths::server::{ConfigToml, PidFileError, ServerDataDir};
+use tokio_util::sync::CancellationToken;
 
 #[cfg(feature = "string")]
 impl From<std::string::String> for OsStr {
@@ -28,10 +35,11 @@ pub fn cli() -> clap::Command {
             Arg::new("listen_addr")
                 .long("listen-addr")
                 .short('l')
-                .default_value("0.0.0.0:3000")
                 .help(
-                    "The address and port where SpacetimeDB should listen for connections. \
-                     This defaults to to listen on all IP addresses on port 80.",
+                    "The address and port where SpacetimeDB should listen for connections, \
+                     or `unix:` followed by the path of a unix socket to listen on. \
+                     This defaults to `server.listen-addr` in config.toml, \
+                     or to listening on all IP addresses on port 3000.",
                 ),
         )
         .arg(
@@ -41,6 +49,15 @@ pub fn cli() -> clap::Command {
                 .required(true)
                 .value_parser(clap::value_parser!(ServerDataDir)),
         )
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .help(
+                    "The path to the server's config file. \
+                     This defaults to config.toml in the data directory, which is created if it doesn't exist.",
+                )
+                .value_parser(clap::value_parser!(ConfigToml)),
+        )
         .arg(
             Arg::new("enable_tracy")
                 .long("enable-tracy")
@@ -71,11 +88,16 @@ pub fn cli() -> clap::Command {
         .arg(Arg::new("in_memory").long("in-memory").action(SetTrue).help(
             "If specified the database will run entirely in memory. After the process exits all data will be lost.",
         ))
+        .arg(Arg::new("test_mode").long("test-mode").action(SetTrue).help(
+            "Enable endpoints which let tests manipulate databases, e.g. advancing the scheduler clock. Never use this in production.",
+        ))
     // .after_help("Run `spacetime help start` for more detailed information.")
 }
 
+/// The address to listen on if neither `--listen-addr` nor config.toml specify one.
+const DEFAULT_LISTEN_ADDR: &str = "0.0.0.0:3000";
+
 pub async fn exec(args: &ArgMatches) -> anyhow::Result<()> {
-    let listen_addr = args.get_one::<String>("listen_addr").unwrap();
     let cert_dir = args.get_one::<spacetimedb_paths::cli::ConfigDir>("jwt_key_dir");
     let certs = Option::zip(
         args.get_one::<PubKeyPath>("jwt_pub_key_path").cloned(),
@@ -92,7 +114,6 @@ pub async fn exec(args: &ArgMatches) -> anyhow::Result<()> {
     } else {
         Storage::Disk
     };
-    let db_config = Config { storage };
 
     banner();
     let exe_name = std::env::current_exe()?;
@@ -101,9 +122,16 @@ pub async fn exec(args: &ArgMatches) -> anyhow::Result<()> {
     println!("{} path: {}", exe_name, std::env::current_exe()?.display());
     println!("database running in data directory {}", data_dir.display());
 
-    let config_path = data_dir.config_toml();
-    let config = match ConfigFile::read(&data_dir.config_toml())? {
+    let config_path = match args.get_one::<ConfigToml>("config") {
+        Some(config_path) => {
+            println!("reading config from {}", config_path.display());
+            config_path.clone()
+        }
+        None => data_dir.config_toml(),
+    };
+    let config = match ConfigFile::read(&config_path)? {
         Some(config) => config,
+        None if args.contains_id("config") => anyhow::bail!("config file {} not found", config_path.display()),
         None => {
             let default_config = include_str!("../../config.toml");
             data_dir.create()?;
@@ -111,11 +139,16 @@ pub async fn exec(args: &ArgMatches) -> anyhow::Result<()> {
             toml::from_str(default_config).unwrap()
         }
     };
+    let listen_addr = args
+        .get_one::<String>("listen_addr")
+        .cloned()
+        .or(config.server.listen_addr)
+        .unwrap_or_else(|| DEFAULT_LISTEN_ADDR.to_owned());
 
     startup::StartupOptions {
         tracing: Some(TracingOptions {
             config: config.logs,
-            reload_config: cfg!(debug_assertions).then_some(config_path),
+            reload_config: cfg!(debug_assertions).then(|| config_path.clone()),
             disk_logging: std::env::var_os("SPACETIMEDB_DISABLE_DISK_LOGGING")
                 .is_none()
                 .then(|| data_dir.logs()),
@@ -136,15 +169,190 @@ pub async fn exec(args: &ArgMatches) -> anyhow::Result<()> {
         .or_else(|| cert_dir.map(CertificateAuthority::in_cli_config_dir))
         .context("cannot omit --jwt-{pub,priv}-key-path when those options are not specified in config.toml")?;
 
+    let db_config = Config {
+        storage,
+        quotas: config.quotas,
+        durability: config.durability,
+        memory: config.memory,
+    };
+    let listen_addr = ListenAddr::parse(&listen_addr);
+    let inherited = Listener::inherit(&listen_addr)?;
+    if inherited.is_some() {
+        wait_for_previous_server(data_dir).await?;
+    }
+
     let data_dir = Arc::new(data_dir.clone());
-    let ctx = StandaloneEnv::init(db_config, &certs, data_dir).await?;
+    let ctx = StandaloneEnv::init(db_config, &certs, data_dir.clone()).await?;
+    ctx.set_auth_config(config.auth);
+    let tls = match &config.tls {
+        Some(tls) => Some(TlsAcceptor::from_config(tls, &data_dir).await?),
+        None => None,
+    };
+    reload_config_on_sighup(ctx.clone(), config_path, tls.clone())?;
+    // Recover the databases in the background, so `/health` responds in the meantime.
+    tokio::spawn({
+        let ctx = ctx.clone();
+        async move { ctx.launch_databases().await }
+    });
+
+    let listener = match inherited {
+        Some(listener) => listener,
+        None => Listener::bind(&listen_addr).await?,
+    };
+    let shutdown = CancellationToken::new();
+    shut_down_on_signal(shutdown.clone(), Handoff::new(&listener)?)?;
+
+    let trusted_proxies = TrustedProxies(config.server.trusted_proxies);
+    let service = router(ctx.clone(), args.get_flag("test_mode"), trusted_proxies);
+    let serving = listen::serve(listener, service, tls, shutdown.clone());
 
-    let service = router(ctx);
+    serve_until_shutdown(serving, shutdown, &ctx, config.shutdown).await
+}
 
-    let tcp = TcpListener::bind(listen_addr).await?;
-    socket2::SockRef::from(&tcp).set_nodelay(true)?;
-    log::debug!("Starting SpacetimeDB listening on {}", tcp.local_addr().unwrap());
-    axum::serve(tcp, service).await?;
+/// Runs the server until `shutdown` is cancelled, then shuts it down gracefully:
+/// stops accepting connections, closes the WebSocket connections, lets the requests in progress finish,
+/// and shuts down the databases.
+async fn serve_until_shutdown(
+    serving: impl std::future::Future<Output = anyhow::Result<()>>,
+    shutdown: CancellationToken,
+    ctx: &StandaloneEnv,
+    config: ShutdownConfig,
+) -> anyhow::Result<()> {
+    let mut serving = pin!(serving);
+    tokio::select! {
+        // Serving only ends by itself if it fails.
+        result = serving.as_mut() => return result,
+        _ = shutdown.cancelled() => {}
+    }
+
+    log::info!("shutting down");
+    let timeout = config.timeout();
+    let clients_closed = async {
+        let (served, ()) = tokio::join!(serving, ctx.client_actor_index().shut_down());
+        served
+    };
+    match tokio::time::timeout(timeout, clients_closed).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => log::error!("error while closing connections: {e:#}"),
+        Err(_) => log::warn!("clients still connected after {timeout:?}, shutting down anyway"),
+    }
+    ctx.shutdown(timeout, config.snapshot).await;
+    log::info!("shut down");
+    Ok(())
+}
+
+/// Waits until the server which handed its listening socket to this process has released `data_dir`,
+/// which it does once it has shut down.
+async fn wait_for_previous_server(data_dir: &ServerDataDir) -> anyhow::Result<()> {
+    let mut logged = false;
+    loop {
+        match data_dir.pid_file() {
+            // Release the lock right away, for `StandaloneEnv::init` to take it.
+            Ok(_pid_file) => return Ok(()),
+            Err(PidFileError::Exists { pid }) => {
+                if !mem::replace(&mut logged, true) {
+                    let pid = pid.map_or("unknown".to_owned(), |pid| pid.to_string());
+                    log::info!("took over the listening socket, waiting for the previous server (pid {pid}) to exit");
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Cancels `shutdown` when the process receives `SIGTERM` or `SIGINT`,
+/// and exits right away if it receives either again.
+///
+/// On `SIGUSR2`, first starts a new server process and hands it the listening socket through `handoff`,
+/// so that the server can be restarted, e.g. to upgrade it, without refusing new connections.
+/// Clients connected to this process are still disconnected as it shuts down.
+#[cfg(unix)]
+fn shut_down_on_signal(shutdown: CancellationToken, handoff: Handoff) -> anyhow::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut sigusr2 = signal(SignalKind::user_defined2())?;
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = sigterm.recv() => log::info!("received SIGTERM"),
+                _ = sigint.recv() => log::info!("received SIGINT"),
+                _ = sigusr2.recv() => match handoff.spawn() {
+                    Ok(child) => {
+                        let pid = child.id();
+                        log::info!("received SIGUSR2, handed the listening socket to new server process {pid}");
+                    }
+                    Err(e) => {
+                        log::error!("not shutting down on SIGUSR2: {e:#}");
+                        continue;
+                    }
+                },
+            }
+            break;
+        }
+        drop(handoff);
+        shutdown.cancel();
+        tokio::select! {
+            _ = sigterm.recv() => {}
+            _ = sigint.recv() => {}
+        }
+        log::warn!("received a second signal, exiting without shutting down gracefully");
+        std::process::exit(1);
+    });
+    Ok(())
+}
+
+/// Cancels `shutdown` when the process receives Ctrl-C.
+#[cfg(not(unix))]
+fn shut_down_on_signal(shutdown: CancellationToken, _handoff: Handoff) -> anyhow::Result<()> {
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            shutdown.cancel();
+        }
+    });
+    Ok(())
+}
+
+/// Reloads the settings in `config_path` which can change while the server runs
+/// whenever the process receives `SIGHUP`.
+#[cfg(unix)]
+fn reload_config_on_sighup(
+    ctx: Arc<StandaloneEnv>,
+    config_path: ConfigToml,
+    tls: Option<TlsAcceptor>,
+) -> anyhow::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sighup = signal(SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while sighup.recv().await.is_some() {
+            log::info!("reloading config from {}", config_path.display());
+            match ConfigFile::read(&config_path) {
+                Ok(Some(mut config)) => {
+                    startup::reload_log_config(std::mem::take(&mut config.logs));
+                    if let (Some(tls), Some(tls_config)) = (&tls, &config.tls) {
+                        if let Err(e) = tls.reload(tls_config) {
+                            log::error!("not reloading TLS certificate: {e:#}");
+                        }
+                    }
+                    ctx.reload_config(config).await;
+                }
+                Ok(None) => log::error!("not reloading config: {} not found", config_path.display()),
+                Err(e) => log::error!("not reloading config: {e:#}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn reload_config_on_sighup(
+    _ctx: Arc<StandaloneEnv>,
+    _config_path: ConfigToml,
+    _tls: Option<TlsAcceptor>,
+) -> anyhow::Result<()> {
     Ok(())
 }
 
@@ -193,3 +401,30 @@ fn banner() {
     "#
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spacetimedb_paths::FromPathUnchecked;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn waits_for_previous_server_to_release_data_dir() -> anyhow::Result<()> {
+        let tempdir = TempDir::new()?;
+        let data_dir = ServerDataDir::from_path_unchecked(tempdir.path());
+        let previous = data_dir.pid_file()?;
+
+        let waiting = tokio::spawn({
+            let data_dir = data_dir.clone();
+            async move { wait_for_previous_server(&data_dir).await }
+        });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(!waiting.is_finished());
+
+        drop(previous);
+        tokio::time::timeout(Duration::from_secs(5), waiting).await???;
+        // The lock was released for the new server to take it.
+        data_dir.pid_file()?;
+        Ok(())
+    }
+}
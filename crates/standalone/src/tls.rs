@@ -0,0 +1,104 @@
+//! Serving HTTPS and secure WebSockets directly, without a reverse proxy in front of the server.
+
+mod acme;
+
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::Context;
+use openssl::pkey::PKey;
+use openssl::ssl::{Ssl, SslAcceptor, SslMethod};
+use openssl::x509::X509;
+use spacetimedb::config::TlsConfig;
+use spacetimedb_paths::server::ServerDataDir;
+use tokio::net::TcpStream;
+use tokio_openssl::SslStream;
+
+/// How long a client may take to complete the TLS handshake before its connection is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Accepts TLS connections with the server's current certificate,
+/// which can be replaced while the server runs.
+#[derive(Clone)]
+pub struct TlsAcceptor(Arc<RwLock<Arc<SslAcceptor>>>);
+
+impl TlsAcceptor {
+    /// Sets up TLS as described by `config`,
+    /// either reading the certificate from the configured files,
+    /// or obtaining it from an ACME certificate authority and renewing it in the background.
+    pub async fn from_config(config: &TlsConfig, data_dir: &ServerDataDir) -> anyhow::Result<Self> {
+        match &config.acme {
+            Some(acme) => acme::start(acme.clone(), data_dir.acme()).await,
+            None => {
+                let (cert_chain, key) = read_cert_files(config)?;
+                Self::new(&cert_chain, &key)
+            }
+        }
+    }
+
+    /// Returns an acceptor serving the PEM-encoded certificate chain `cert_chain` with private key `key`.
+    fn new(cert_chain: &[u8], key: &[u8]) -> anyhow::Result<Self> {
+        let acceptor = build_acceptor(cert_chain, key)?;
+        Ok(Self(Arc::new(RwLock::new(Arc::new(acceptor)))))
+    }
+
+    /// Serves the PEM-encoded certificate chain `cert_chain` with private key `key` to new connections.
+    fn set_certificate(&self, cert_chain: &[u8], key: &[u8]) -> anyhow::Result<()> {
+        let acceptor = build_acceptor(cert_chain, key)?;
+        *self.0.write().unwrap() = Arc::new(acceptor);
+        Ok(())
+    }
+
+    /// Reads the certificate files named in `config` again, e.g. after they were renewed.
+    ///
+    /// Certificates obtained through ACME are renewed by the server itself,
+    /// so this does nothing for them.
+    pub fn reload(&self, config: &TlsConfig) -> anyhow::Result<()> {
+        if config.acme.is_some() {
+            return Ok(());
+        }
+        let (cert_chain, key) = read_cert_files(config)?;
+        self.set_certificate(&cert_chain, &key)
+    }
+
+    fn current(&self) -> Arc<SslAcceptor> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Performs the TLS handshake with the client connected on `tcp`.
+    pub async fn handshake(&self, tcp: TcpStream) -> anyhow::Result<SslStream<TcpStream>> {
+        let ssl = Ssl::new(self.current().context())?;
+        let mut stream = SslStream::new(ssl, tcp)?;
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, Pin::new(&mut stream).accept())
+            .await
+            .context("timed out")??;
+        Ok(stream)
+    }
+}
+
+fn read_cert_files(config: &TlsConfig) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let (Some(cert_path), Some(key_path)) = (&config.cert_path, &config.key_path) else {
+        anyhow::bail!("[tls] in config.toml must set either both cert-path and key-path, or [tls.acme]");
+    };
+    let cert_chain =
+        std::fs::read(cert_path).with_context(|| format!("could not read certificate {}", cert_path.display()))?;
+    let key = std::fs::read(key_path).with_context(|| format!("could not read private key {}", key_path.display()))?;
+    Ok((cert_chain, key))
+}
+
+fn build_acceptor(cert_chain: &[u8], key: &[u8]) -> anyhow::Result<SslAcceptor> {
+    let mut chain = X509::stack_from_pem(cert_chain)?.into_iter();
+    let leaf = chain.next().context("the certificate file contains no certificates")?;
+    let key = PKey::private_key_from_pem(key)?;
+    let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server())?;
+    builder.set_private_key(&key)?;
+    builder.set_certificate(&leaf)?;
+    for cert in chain {
+        builder.add_extra_chain_cert(cert)?;
+    }
+    builder
+        .check_private_key()
+        .context("the private key does not match the certificate")?;
+    Ok(builder.build())
+}
@@ -0,0 +1,581 @@
+//! Obtaining and renewing certificates from an ACME certificate authority, such as Let's Encrypt.
+//!
+//! This implements the parts of [RFC 8555] the server needs to get a certificate for its domains,
+//! proving control of them by answering `http-01` challenges.
+//!
+//! [RFC 8555]: https://www.rfc-editor.org/rfc/rfc8555
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context};
+use axum::extract::{Path, State};
+use axum::routing::get;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use http::header::{CONTENT_TYPE, LOCATION};
+use http::StatusCode;
+use openssl::asn1::Asn1Time;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::stack::Stack;
+use openssl::x509::extension::SubjectAlternativeName;
+use openssl::x509::{X509NameBuilder, X509ReqBuilder, X509};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use spacetimedb::config::AcmeConfig;
+use spacetimedb_paths::server::AcmeDir;
+use tokio::net::TcpListener;
+
+use super::TlsAcceptor;
+
+/// Certificates are renewed once they expire in less than this many days.
+///
+/// Let's Encrypt issues certificates valid for 90 days, and recommends renewing them 30 days before they expire.
+const RENEW_BEFORE_DAYS: i32 = 30;
+
+/// How often to check whether the certificate needs to be renewed.
+const CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// How long to wait before checking again on an order or authorization the certificate authority is working on.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How many times to check on an order or authorization before giving up on it.
+const POLL_ATTEMPTS: usize = 60;
+
+/// The key authorizations for the pending `http-01` challenges, by token.
+type Challenges = Arc<Mutex<HashMap<String, String>>>;
+
+/// Starts answering challenges on `config.challenge_listen_addr`,
+/// and returns an acceptor for a certificate for `config.domains`,
+/// which is read from `dir` if it's there and still valid, and obtained from the certificate authority otherwise.
+///
+/// The certificate is renewed in the background before it expires.
+pub(super) async fn start(config: AcmeConfig, dir: AcmeDir) -> anyhow::Result<TlsAcceptor> {
+    ensure!(!config.domains.is_empty(), "[tls.acme] in config.toml must set domains");
+
+    let challenges = Challenges::default();
+    let listener = TcpListener::bind(&config.challenge_listen_addr)
+        .await
+        .with_context(|| {
+            format!(
+                "could not listen for ACME challenges on {}",
+                config.challenge_listen_addr
+            )
+        })?;
+    let router = axum::Router::new()
+        .route("/.well-known/acme-challenge/:token", get(answer_challenge))
+        .with_state(challenges.clone());
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            log::error!("stopped answering ACME challenges: {e}");
+        }
+    });
+
+    if !needs_renewal(&config, &dir)? {
+        log::info!("using the certificate in {}", dir.display());
+    } else {
+        renew(&config, &dir, &challenges).await?;
+    }
+    let acceptor = TlsAcceptor::new(&dir.cert_chain().read()?, &dir.cert_key().read()?)?;
+
+    let renewed = acceptor.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            let result = async {
+                if needs_renewal(&config, &dir)? {
+                    renew(&config, &dir, &challenges).await?;
+                    renewed.set_certificate(&dir.cert_chain().read()?, &dir.cert_key().read()?)?;
+                }
+                anyhow::Ok(())
+            };
+            // If renewal fails, it's retried at the next check, long before the certificate expires.
+            if let Err(e) = result.await {
+                log::error!("failed to renew the certificate for {:?}: {e:#}", config.domains);
+            }
+        }
+    });
+
+    Ok(acceptor)
+}
+
+async fn answer_challenge(
+    State(challenges): State<Challenges>,
+    Path(token): Path<String>,
+) -> Result<String, StatusCode> {
+    challenges
+        .lock()
+        .unwrap()
+        .get(&token)
+        .cloned()
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Returns whether there is no certificate in `dir` for exactly `config.domains`
+/// which is valid for at least [`RENEW_BEFORE_DAYS`].
+fn needs_renewal(config: &AcmeConfig, dir: &AcmeDir) -> anyhow::Result<bool> {
+    match dir.cert_chain().read() {
+        Ok(cert_chain) => cert_needs_renewal(&cert_chain, &config.domains),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(true),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Returns whether the leaf of the PEM-encoded `cert_chain` is not for exactly `domains`
+/// or is valid for less than [`RENEW_BEFORE_DAYS`].
+fn cert_needs_renewal(cert_chain: &[u8], domains: &[String]) -> anyhow::Result<bool> {
+    let cert = X509::from_pem(cert_chain)?;
+    let mut cert_domains: Vec<_> = cert
+        .subject_alt_names()
+        .into_iter()
+        .flatten()
+        .filter_map(|name| name.dnsname().map(str::to_owned))
+        .collect();
+    cert_domains.sort();
+    let mut wanted = domains.to_vec();
+    wanted.sort();
+    let days_left = Asn1Time::days_from_now(0)?.diff(cert.not_after())?.days;
+    Ok(cert_domains != wanted || days_left < RENEW_BEFORE_DAYS)
+}
+
+/// Obtains a new certificate for `config.domains` and stores it in `dir`.
+async fn renew(config: &AcmeConfig, dir: &AcmeDir, challenges: &Challenges) -> anyhow::Result<()> {
+    log::info!(
+        "obtaining a certificate for {:?} from {}",
+        config.domains,
+        config.directory_url
+    );
+    let mut client = Client::new(&config.directory_url, account_key(dir)?).await?;
+    client.register(&config.contact).await?;
+    let (cert_chain, cert_key) = client.order(&config.domains, challenges).await?;
+    dir.cert_key().write(cert_key)?;
+    dir.cert_chain().write(cert_chain)?;
+    log::info!("obtained a certificate for {:?}", config.domains);
+    Ok(())
+}
+
+/// Returns the key of the server's account with the certificate authority, creating it if need be.
+fn account_key(dir: &AcmeDir) -> anyhow::Result<EcKey<Private>> {
+    let path = dir.account_key();
+    match path.read() {
+        Ok(pem) => Ok(EcKey::private_key_from_pem(&pem)?),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let key = generate_key()?;
+            path.write(key.private_key_to_pem()?)?;
+            Ok(key)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn generate_key() -> anyhow::Result<EcKey<Private>> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    Ok(EcKey::generate(&group)?)
+}
+
+fn b64(data: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(data)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+    new_nonce: String,
+    new_account: String,
+    new_order: String,
+}
+
+#[derive(Deserialize)]
+struct Order {
+    status: String,
+    authorizations: Vec<String>,
+    finalize: String,
+    certificate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Authorization {
+    status: String,
+    identifier: Identifier,
+    challenges: Vec<Challenge>,
+}
+
+#[derive(Deserialize)]
+struct Identifier {
+    value: String,
+}
+
+#[derive(Deserialize)]
+struct Challenge {
+    #[serde(rename = "type")]
+    kind: String,
+    url: String,
+    #[serde(default)]
+    token: String,
+}
+
+/// An error reported by the certificate authority.
+#[derive(Deserialize, Default)]
+struct Problem {
+    #[serde(rename = "type", default)]
+    kind: String,
+    #[serde(default)]
+    detail: String,
+}
+
+/// The key of an account with a certificate authority, which signs the requests made on its behalf.
+struct AccountKey {
+    key: EcKey<Private>,
+    /// The public key, as a JSON Web Key.
+    jwk: serde_json::Value,
+    /// The SHA-256 thumbprint of `jwk`, as defined by RFC 7638.
+    thumbprint: String,
+}
+
+impl AccountKey {
+    fn new(key: EcKey<Private>) -> anyhow::Result<Self> {
+        let mut ctx = BigNumContext::new()?;
+        let (mut x, mut y) = (BigNum::new()?, BigNum::new()?);
+        key.public_key()
+            .affine_coordinates(key.group(), &mut x, &mut y, &mut ctx)?;
+        let (x, y) = (b64(&x.to_vec_padded(32)?), b64(&y.to_vec_padded(32)?));
+        let jwk = json!({ "crv": "P-256", "kty": "EC", "x": x, "y": y });
+        let thumbprint = thumbprint(&jwk)?;
+        Ok(Self { key, jwk, thumbprint })
+    }
+
+    /// Signs `data` with ES256, whose signatures are the two 32-byte integers `r` and `s`, concatenated.
+    fn sign(&self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let signature = EcdsaSig::sign(&openssl::sha::sha256(data), &self.key)?;
+        let mut bytes = signature.r().to_vec_padded(32)?;
+        bytes.extend(signature.s().to_vec_padded(32)?);
+        Ok(bytes)
+    }
+
+    /// Returns the flattened JSON Web Signature of the base64url-encoded `payload`
+    /// with the `protected` header, to which the algorithm is added.
+    fn jws(&self, mut protected: serde_json::Value, payload: &str) -> anyhow::Result<serde_json::Value> {
+        protected["alg"] = "ES256".into();
+        let protected = b64(&serde_json::to_vec(&protected)?);
+        let signature = b64(&self.sign(format!("{protected}.{payload}").as_bytes())?);
+        Ok(json!({ "protected": protected, "payload": payload, "signature": signature }))
+    }
+}
+
+/// Returns the RFC 7638 thumbprint of `jwk`:
+/// the SHA-256 hash of its required members, in lexicographic order and without whitespace.
+fn thumbprint(jwk: &serde_json::Value) -> anyhow::Result<String> {
+    let members: &[&str] = match jwk["kty"].as_str() {
+        Some("EC") => &["crv", "kty", "x", "y"],
+        Some("RSA") => &["e", "kty", "n"],
+        kty => bail!("unsupported JSON Web Key type {kty:?}"),
+    };
+    // `Value`'s `Display` writes compact JSON.
+    let members: Vec<_> = members
+        .iter()
+        .map(|&name| format!("{}:{}", json!(name), jwk[name]))
+        .collect();
+    let canonical = format!("{{{}}}", members.join(","));
+    Ok(b64(&openssl::sha::sha256(canonical.as_bytes())))
+}
+
+/// A session with an ACME certificate authority, on behalf of an account.
+struct Client {
+    http: reqwest::Client,
+    directory: Directory,
+    key: AccountKey,
+    /// The URL of the account, once registered, which identifies it in requests.
+    account_url: Option<String>,
+    /// The nonce for the next request, if the last response provided one.
+    nonce: Option<String>,
+}
+
+impl Client {
+    async fn new(directory_url: &str, key: EcKey<Private>) -> anyhow::Result<Self> {
+        let http = reqwest::Client::new();
+        let directory = http
+            .get(directory_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("could not fetch the ACME directory {directory_url}"))?;
+
+        Ok(Self {
+            http,
+            directory,
+            key: AccountKey::new(key)?,
+            account_url: None,
+            nonce: None,
+        })
+    }
+
+    /// Signs `payload` as a JSON Web Signature and posts it to `url`,
+    /// or posts an empty payload, to fetch the resource at `url`, if `payload` is `None`.
+    async fn post(&mut self, url: &str, payload: Option<&serde_json::Value>) -> anyhow::Result<reqwest::Response> {
+        let payload = match payload {
+            Some(payload) => b64(&serde_json::to_vec(payload)?),
+            None => String::new(),
+        };
+        let mut retried = false;
+        loop {
+            let nonce = match self.nonce.take() {
+                Some(nonce) => nonce,
+                None => self.new_nonce().await?,
+            };
+            let mut protected = json!({ "nonce": nonce, "url": url });
+            match &self.account_url {
+                Some(account_url) => protected["kid"] = account_url.clone().into(),
+                None => protected["jwk"] = self.key.jwk.clone(),
+            }
+            let body = self.key.jws(protected, &payload)?;
+
+            let res = self
+                .http
+                .post(url)
+                .header(CONTENT_TYPE, "application/jose+json")
+                .body(serde_json::to_vec(&body)?)
+                .send()
+                .await?;
+            self.nonce = res
+                .headers()
+                .get("replay-nonce")
+                .and_then(|nonce| nonce.to_str().ok())
+                .map(str::to_owned);
+            if res.status().is_success() {
+                return Ok(res);
+            }
+            let status = res.status();
+            let problem: Problem = res.json().await.unwrap_or_default();
+            // The certificate authority may reject a nonce at any time, and the request is then to be retried.
+            if problem.kind == "urn:ietf:params:acme:error:badNonce" && !retried {
+                retried = true;
+                continue;
+            }
+            bail!(
+                "request to {url} failed with {status}: {} {}",
+                problem.kind,
+                problem.detail
+            );
+        }
+    }
+
+    async fn post_as_get<T: DeserializeOwned>(&mut self, url: &str) -> anyhow::Result<T> {
+        Ok(self.post(url, None).await?.json().await?)
+    }
+
+    async fn new_nonce(&self) -> anyhow::Result<String> {
+        let res = self.http.head(&self.directory.new_nonce).send().await?;
+        let nonce = res
+            .headers()
+            .get("replay-nonce")
+            .context("the ACME server sent no nonce")?;
+        Ok(nonce.to_str()?.to_owned())
+    }
+
+    /// Registers the account, or looks it up if it's registered already.
+    async fn register(&mut self, contact: &[String]) -> anyhow::Result<()> {
+        let url = self.directory.new_account.clone();
+        let payload = json!({ "termsOfServiceAgreed": true, "contact": contact });
+        let res = self.post(&url, Some(&payload)).await?;
+        self.account_url = Some(location(&res)?);
+        Ok(())
+    }
+
+    /// Orders a certificate for `domains`,
+    /// returning its PEM-encoded certificate chain and private key.
+    async fn order(&mut self, domains: &[String], challenges: &Challenges) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+        let url = self.directory.new_order.clone();
+        let identifiers: Vec<_> = domains.iter().map(|d| json!({ "type": "dns", "value": d })).collect();
+        let res = self.post(&url, Some(&json!({ "identifiers": identifiers }))).await?;
+        let order_url = location(&res)?;
+        let order: Order = res.json().await?;
+
+        for authorization_url in &order.authorizations {
+            self.authorize(authorization_url, challenges).await?;
+        }
+
+        let key = PKey::from_ec_key(generate_key()?)?;
+        let csr = csr(domains, &key)?;
+        self.post(&order.finalize, Some(&json!({ "csr": b64(&csr) }))).await?;
+        let order: Order = self.poll(&order_url, |order: &Order| order.status.as_str()).await?;
+        ensure!(order.status == "valid", "the ACME server did not issue the certificate");
+        let certificate_url = order.certificate.context("the ACME server sent no certificate")?;
+        let cert_chain = self.post(&certificate_url, None).await?.bytes().await?;
+        Ok((cert_chain.to_vec(), key.private_key_to_pem_pkcs8()?))
+    }
+
+    /// Proves control of the domain of the authorization at `url` by answering its `http-01` challenge.
+    async fn authorize(&mut self, url: &str, challenges: &Challenges) -> anyhow::Result<()> {
+        let authorization: Authorization = self.post_as_get(url).await?;
+        if authorization.status == "valid" {
+            return Ok(());
+        }
+        let domain = authorization.identifier.value;
+        let challenge = authorization
+            .challenges
+            .into_iter()
+            .find(|challenge| challenge.kind == "http-01")
+            .with_context(|| format!("the ACME server offered no http-01 challenge for {domain}"))?;
+
+        let key_authorization = format!("{}.{}", challenge.token, self.key.thumbprint);
+        challenges
+            .lock()
+            .unwrap()
+            .insert(challenge.token.clone(), key_authorization);
+        let result = async {
+            self.post(&challenge.url, Some(&json!({}))).await?;
+            let authorization: Authorization = self
+                .poll(url, |authorization: &Authorization| authorization.status.as_str())
+                .await?;
+            ensure!(
+                authorization.status == "valid",
+                "the ACME server could not verify control of {domain}; \
+                 is port 80 on it forwarded to the ACME challenge-listen-addr?"
+            );
+            anyhow::Ok(())
+        }
+        .await;
+        challenges.lock().unwrap().remove(&challenge.token);
+        result
+    }
+
+    /// Fetches the resource at `url` until the certificate authority is done processing it.
+    async fn poll<T: DeserializeOwned>(&mut self, url: &str, status: impl Fn(&T) -> &str) -> anyhow::Result<T> {
+        for _ in 0..POLL_ATTEMPTS {
+            let resource: T = self.post_as_get(url).await?;
+            if !matches!(status(&resource), "pending" | "processing") {
+                return Ok(resource);
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        bail!("the ACME server took too long to process {url}")
+    }
+}
+
+fn location(res: &reqwest::Response) -> anyhow::Result<String> {
+    let location = res
+        .headers()
+        .get(LOCATION)
+        .context("the ACME server sent no location")?;
+    Ok(location.to_str()?.to_owned())
+}
+
+/// Returns a DER-encoded certificate signing request for `domains`, with `key`.
+fn csr(domains: &[String], key: &PKey<Private>) -> anyhow::Result<Vec<u8>> {
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_nid(Nid::COMMONNAME, &domains[0])?;
+    let mut builder = X509ReqBuilder::new()?;
+    builder.set_subject_name(&name.build())?;
+    let mut san = SubjectAlternativeName::new();
+    for domain in domains {
+        san.dns(domain);
+    }
+    let mut extensions = Stack::new()?;
+    extensions.push(san.build(&builder.x509v3_context(None))?)?;
+    builder.add_extensions(&extensions)?;
+    builder.set_pubkey(key)?;
+    builder.sign(key, MessageDigest::sha256())?;
+    Ok(builder.build().to_der()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::x509::X509Builder;
+
+    /// Returns a PEM-encoded self-signed certificate for `domains`, valid for `days` from now.
+    fn self_signed(domains: &[&str], days: u32) -> Vec<u8> {
+        let key = PKey::from_ec_key(generate_key().unwrap()).unwrap();
+        let mut builder = X509Builder::new().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(days).unwrap()).unwrap();
+        let mut san = SubjectAlternativeName::new();
+        for domain in domains {
+            san.dns(domain);
+        }
+        let san = san.build(&builder.x509v3_context(None, None)).unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        builder.build().to_pem().unwrap()
+    }
+
+    #[test]
+    fn renews_certificates_close_to_expiry_or_for_other_domains() {
+        let domains = ["b.example.com".to_owned(), "a.example.com".to_owned()];
+        let renew_before = RENEW_BEFORE_DAYS as u32;
+
+        let fresh = self_signed(&["a.example.com", "b.example.com"], renew_before + 60);
+        assert!(!cert_needs_renewal(&fresh, &domains).unwrap());
+        let almost_due = self_signed(&["a.example.com", "b.example.com"], renew_before + 1);
+        assert!(!cert_needs_renewal(&almost_due, &domains).unwrap());
+        let due = self_signed(&["a.example.com", "b.example.com"], renew_before - 1);
+        assert!(cert_needs_renewal(&due, &domains).unwrap());
+
+        let fewer_domains = self_signed(&["a.example.com"], renew_before + 60);
+        assert!(cert_needs_renewal(&fewer_domains, &domains).unwrap());
+        let other_domains = self_signed(&["a.example.com", "c.example.com"], renew_before + 60);
+        assert!(cert_needs_renewal(&other_domains, &domains).unwrap());
+    }
+
+    /// The example of RFC 7638, section 3.1.
+    #[test]
+    fn thumbprint_matches_rfc_7638() {
+        let jwk = json!({
+            "kty": "RSA",
+            "n": "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+            "e": "AQAB",
+            "alg": "RS256",
+            "kid": "2011-04-29",
+        });
+        assert_eq!(thumbprint(&jwk).unwrap(), "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs");
+    }
+
+    #[test]
+    fn account_key_thumbprint_covers_the_ec_members() {
+        let key = AccountKey::new(generate_key().unwrap()).unwrap();
+        let (x, y) = (key.jwk["x"].as_str().unwrap(), key.jwk["y"].as_str().unwrap());
+        // Both coordinates are 32 bytes.
+        assert_eq!((x.len(), y.len()), (43, 43));
+        let canonical = format!(r#"{{"crv":"P-256","kty":"EC","x":"{x}","y":"{y}"}}"#);
+        assert_eq!(key.thumbprint, b64(&openssl::sha::sha256(canonical.as_bytes())));
+    }
+
+    #[test]
+    fn jws_is_signed_with_the_account_key() {
+        let key = AccountKey::new(generate_key().unwrap()).unwrap();
+        let payload = b64(br#"{"termsOfServiceAgreed":true}"#);
+        let jws = key
+            .jws(
+                json!({ "nonce": "abc", "url": "https://ca.example.com/new-account" }),
+                &payload,
+            )
+            .unwrap();
+        let (protected, signature) = (jws["protected"].as_str().unwrap(), jws["signature"].as_str().unwrap());
+
+        let header: serde_json::Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(protected).unwrap()).unwrap();
+        assert_eq!(
+            header,
+            json!({ "alg": "ES256", "nonce": "abc", "url": "https://ca.example.com/new-account" })
+        );
+        assert_eq!(jws["payload"], payload);
+
+        let signature = URL_SAFE_NO_PAD.decode(signature).unwrap();
+        assert_eq!(signature.len(), 64);
+        let (r, s) = signature.split_at(32);
+        let signature =
+            EcdsaSig::from_private_components(BigNum::from_slice(r).unwrap(), BigNum::from_slice(s).unwrap()).unwrap();
+        let signed = openssl::sha::sha256(format!("{protected}.{payload}").as_bytes());
+        assert!(signature.verify(&signed, &key.key).unwrap());
+    }
+}
@@ -7,8 +7,13 @@ use super::indexes::{Bytes, PageIndex, PageOffset, RowPointer, Size};
 use super::page::Page;
 use super::table::BlobNumBytes;
 use super::var_len::VarLenMembers;
+use core::fmt;
 use core::ops::{ControlFlow, Deref, Index, IndexMut};
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use spacetimedb_sats::bsatn;
+use std::io::{Read, Write};
 use std::ops::DerefMut;
+use std::sync::{Mutex, OnceLock, PoisonError};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq, Eq)]
@@ -33,11 +38,126 @@ impl IndexMut<PageIndex> for Pages {
     }
 }
 
+/// A [`Page`] managed by [`Pages`], which may be held compressed while it is cold.
+///
+/// A compressed page is transparently decompressed when it is next accessed,
+/// and the compressed bytes are then discarded,
+/// so that a page is never held both compressed and decompressed.
+pub struct PageSlot {
+    /// The page, unless it is compressed and has not been accessed since.
+    page: OnceLock<Box<Page>>,
+    /// The compressed page, present exactly when `page` isn't.
+    ///
+    /// Only taken while initializing `page`, which happens once, behind a shared reference,
+    /// so the lock is never contended.
+    compressed: Mutex<Option<Box<[u8]>>>,
+}
+
+impl PageSlot {
+    /// Returns whether the page is held compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.page.get().is_none()
+    }
+
+    /// Compresses the page, unless it already is,
+    /// and discards the decompressed page.
+    fn compress(&mut self) {
+        if let Some(mut page) = self.page.take() {
+            // Save the content hash first, so that the page needn't be rehashed
+            // when it is decompressed for a snapshot.
+            page.save_or_get_content_hash();
+            let compressed = self.compressed.get_mut().unwrap_or_else(PoisonError::into_inner);
+            *compressed = Some(compress_page(&page));
+        }
+    }
+
+    /// Decompresses the page, discarding the compressed bytes.
+    fn take_decompressed(&self) -> Box<Page> {
+        let compressed = self
+            .compressed
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .expect("a page which isn't present should be compressed");
+        decompress_page(&compressed)
+    }
+}
+
+/// Compresses `page`, which is cheaper to hold than the page itself
+/// when the page is mostly empty or its rows are repetitive.
+fn compress_page(page: &Page) -> Box<[u8]> {
+    let bytes = bsatn::to_vec(page).expect("serializing a page should never fail");
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+    encoder
+        .write_all(&bytes)
+        .expect("compressing into a `Vec` should never fail");
+    let compressed = encoder.finish().expect("compressing into a `Vec` should never fail");
+    compressed.into_boxed_slice()
+}
+
+/// Decompresses a page compressed by [`compress_page`].
+fn decompress_page(compressed: &[u8]) -> Box<Page> {
+    let mut bytes = Vec::new();
+    DeflateDecoder::new(compressed)
+        .read_to_end(&mut bytes)
+        .expect("decompressing a compressed page should never fail");
+    bsatn::from_slice(&bytes).expect("deserializing a compressed page should never fail")
+}
+
+impl From<Box<Page>> for PageSlot {
+    fn from(page: Box<Page>) -> Self {
+        Self {
+            page: OnceLock::from(page),
+            compressed: Mutex::new(None),
+        }
+    }
+}
+
+impl Deref for PageSlot {
+    type Target = Page;
+
+    fn deref(&self) -> &Page {
+        self.page.get_or_init(|| self.take_decompressed())
+    }
+}
+
+impl DerefMut for PageSlot {
+    fn deref_mut(&mut self) -> &mut Page {
+        if self.page.get().is_none() {
+            let page = self.take_decompressed();
+            let _ = self.page.set(page);
+        }
+        self.page.get_mut().expect("the page was just decompressed")
+    }
+}
+
+impl PartialEq for PageSlot {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for PageSlot {}
+
+impl fmt::Debug for PageSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl MemoryUsage for PageSlot {
+    fn heap_usage(&self) -> usize {
+        let Self { page, compressed } = self;
+        let compressed = compressed.lock().unwrap_or_else(PoisonError::into_inner);
+        page.get().map_or(0, |page| page.heap_usage()) + compressed.heap_usage()
+    }
+}
+
 /// A manager of [`Page`]s.
 #[derive(Default, Debug, PartialEq, Eq)]
 pub struct Pages {
     /// The collection of pages under management.
-    pages: Vec<Box<Page>>,
+    pages: Vec<PageSlot>,
     /// The set of pages that aren't yet full.
     non_full_pages: Vec<PageIndex>,
 }
@@ -97,7 +217,7 @@ impl Pages {
     fn allocate_new_page(&mut self, fixed_row_size: Size) -> Result<PageIndex, Error> {
         let new_idx = self.can_allocate_new_page()?;
 
-        self.pages.push(Page::new(fixed_row_size));
+        self.pages.push(Page::new(fixed_row_size).into());
 
         Ok(new_idx)
     }
@@ -329,7 +449,7 @@ impl Pages {
                 if copy_starting_from.is_none() {
                     partial_page = Some(to_page);
                 } else {
-                    partial_copied_pages.pages.push(to_page);
+                    partial_copied_pages.pages.push(to_page.into());
                 }
             }
         }
@@ -354,12 +474,29 @@ impl Pages {
             .enumerate()
             .filter_map(|(idx, page)| (!page.is_full(fixed_row_size)).then_some(PageIndex(idx as _)))
             .collect();
-        self.pages = pages;
+        self.pages = pages.into_iter().map(PageSlot::from).collect();
+    }
+
+    /// Compresses every page in `self` which isn't already compressed.
+    ///
+    /// Pages are transparently decompressed when they are next accessed,
+    /// so this trades the CPU time to do so for the memory saved while the pages are cold.
+    pub fn compress(&mut self) {
+        for page in &mut self.pages {
+            page.compress();
+        }
+    }
+
+    /// Returns the number of pages in `self` which are held compressed.
+    ///
+    /// Reading from a page decompresses it, so this decreases as compressed pages are read.
+    pub fn num_compressed_pages(&self) -> usize {
+        self.pages.iter().filter(|page| page.is_compressed()).count()
     }
 }
 
 impl Deref for Pages {
-    type Target = [Box<Page>];
+    type Target = [PageSlot];
 
     fn deref(&self) -> &Self::Target {
         &self.pages
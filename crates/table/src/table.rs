@@ -927,6 +927,33 @@ impl Table {
         Some(ret)
     }
 
+    /// Deletes every row from the table.
+    ///
+    /// Unlike calling [`Table::delete`] for each row,
+    /// this resets the pages, indices, and pointer map wholesale.
+    /// Rows are only visited individually when some of them hold blobs,
+    /// which must be released from `blob_store`.
+    pub fn clear(&mut self, blob_store: &mut dyn BlobStore) {
+        if self.blob_store_bytes != BlobNumBytes::default() {
+            let ptrs = self.scan_rows(blob_store).map(|row| row.pointer()).collect::<Vec<_>>();
+            for ptr in ptrs {
+                // SAFETY: `ptr` was just yielded by `scan_rows`, so it points to a valid, live row.
+                unsafe { self.delete_internal_skip_pointer_map(blob_store, ptr) };
+            }
+        }
+        self.inner.pages.clear();
+
+        for index in self.indexes.values_mut() {
+            index.clear();
+        }
+        if let Some(pointer_map) = &mut self.pointer_map {
+            *pointer_map = PointerMap::default();
+        }
+
+        self.row_count = 0;
+        self.blob_store_bytes = BlobNumBytes::default();
+    }
+
     /// If a row exists in `self` which matches `row`
     /// by [`Table::find_same_row`],
     /// delete that row.
@@ -1667,6 +1694,24 @@ impl Table {
         })
     }
 
+    /// Compresses the pages storing the physical rows of this table,
+    /// which are transparently decompressed when next accessed.
+    ///
+    /// Used to save memory on tables which have not been modified recently.
+    pub fn compress_pages(&mut self) {
+        self.inner.pages.compress();
+    }
+
+    /// Returns the number of pages storing the physical rows of this table which are held compressed.
+    pub fn num_compressed_pages(&self) -> usize {
+        self.inner.pages.num_compressed_pages()
+    }
+
+    /// Returns whether every page storing the physical rows of this table is held compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.num_compressed_pages() == self.num_pages()
+    }
+
     /// Returns the number of pages storing the physical rows of this table.
     fn num_pages(&self) -> usize {
         self.inner.pages.len()
@@ -1912,6 +1957,55 @@ pub(crate) mod test {
             prop_assert_eq!(&table.scan_rows(&blob_store).map(|r| r.pointer()).collect::<Vec<_>>(), &[ptr]);
         }
 
+        #[test]
+        fn compressed_pages_are_transparent((ty, val) in generate_typed_row()) {
+            let mut blob_store = HashMapBlobStore::default();
+            let mut table = table(ty);
+            let (_, row) = table.insert(&mut blob_store, &val).unwrap();
+            let ptr = row.pointer();
+            let hash = hash_unmodified_save_get(&mut table.inner.pages[ptr.page_index()]);
+
+            table.compress_pages();
+            prop_assert_eq!(table.num_compressed_pages(), 1);
+            prop_assert!(table.is_compressed());
+
+            // Reading rows decompresses the page and discards the compressed bytes.
+            prop_assert_eq!(table.get_row_ref(&blob_store, ptr).unwrap().to_product_value(), val.clone());
+            prop_assert_eq!(table.num_compressed_pages(), 0);
+            prop_assert_eq!(table.inner.pages[PageIndex(0)].unmodified_hash(), Some(&hash));
+
+            // Snapshots see the hash saved before compressing.
+            table.compress_pages();
+            prop_assert_eq!(table.iter_pages_with_hashes().map(|(hash, _)| hash).collect::<Vec<_>>(), [hash]);
+            prop_assert_eq!(table.num_compressed_pages(), 0);
+
+            // Modifying rows decompresses the page.
+            table.compress_pages();
+            table.delete(&mut blob_store, ptr, |_| ());
+            prop_assert_eq!(table.num_compressed_pages(), 0);
+            prop_assert!(table.scan_rows(&blob_store).next().is_none());
+
+            table.compress_pages();
+            let (_, row) = table.insert(&mut blob_store, &val).unwrap();
+            prop_assert_eq!(row.to_product_value(), val);
+            prop_assert_eq!(table.num_compressed_pages(), 0);
+            prop_assert_eq!(table.inner.pages.len(), 1);
+        }
+
+        #[test]
+        fn compressing_pages_twice_is_a_no_op((ty, val) in generate_typed_row()) {
+            let mut blob_store = HashMapBlobStore::default();
+            let mut table = table(ty);
+            let (_, row) = table.insert(&mut blob_store, &val).unwrap();
+            let ptr = row.pointer();
+
+            table.compress_pages();
+            let usage = table.inner.pages.heap_usage();
+            table.compress_pages();
+            prop_assert_eq!(table.inner.pages.heap_usage(), usage);
+            prop_assert_eq!(table.get_row_ref(&blob_store, ptr).unwrap().to_product_value(), val);
+        }
+
         #[test]
         fn insert_bsatn_same_as_pv((ty, val) in generate_typed_row()) {
             let mut bs_pv = HashMapBlobStore::default();
@@ -2035,6 +2129,30 @@ pub(crate) mod test {
         assert_eq!(table1.blob_store_bytes, 0.into());
     }
 
+    #[test]
+    fn clear_frees_rows_and_blobs() {
+        let blob_store = &mut HashMapBlobStore::default();
+        let mut table = table([AlgebraicType::String, AlgebraicType::I32].into());
+
+        let long_str = "b".repeat(VarLenGranule::OBJECT_SIZE_BLOB_THRESHOLD + 1);
+        for i in 0..10 {
+            table.insert(blob_store, &product![long_str.clone(), i]).unwrap();
+            table.insert(blob_store, &product!["short", i]).unwrap();
+        }
+        assert_eq!(table.row_count, 20);
+        assert!(!blob_store.usage_counter().is_empty());
+
+        table.clear(blob_store);
+        assert_eq!(table.row_count, 0);
+        assert_eq!(table.blob_store_bytes, 0.into());
+        assert_eq!(table.scan_rows(blob_store).count(), 0);
+        assert!(blob_store.usage_counter().is_empty());
+
+        // The table is still usable after being cleared, including re-inserting the same rows.
+        table.insert(blob_store, &product!["short", 0]).unwrap();
+        assert_eq!(table.scan_rows(blob_store).count(), 1);
+    }
+
     /// Assert that calling `get_row_ref` to get a row ref to a non-existent `RowPointer`
     /// does not panic.
     #[test]
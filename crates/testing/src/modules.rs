@@ -1,22 +1,25 @@
 use std::env;
 use std::future::Future;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::OnceLock;
 use std::time::Instant;
 
-use spacetimedb::config::CertificateAuthority;
+use spacetimedb::config::{CertificateAuthority, ConfigFile, DurabilityConfig, MemoryConfig, QuotaConfig};
 use spacetimedb::messages::control_db::HostType;
 use spacetimedb::Identity;
 use spacetimedb_client_api::auth::SpacetimeAuth;
 use spacetimedb_client_api::routes::subscribe::generate_random_address;
 use spacetimedb_paths::{RootDir, SpacetimePaths};
 use tokio::runtime::{Builder, Runtime};
+use tokio::sync::{mpsc, watch};
 
+use spacetimedb::client::messages::SerializableMessage;
 use spacetimedb::client::{ClientActorId, ClientConfig, ClientConnection, DataMessage};
 use spacetimedb::database_logger::DatabaseLogger;
 use spacetimedb::db::{Config, Storage};
-use spacetimedb::host::ReducerArgs;
+use spacetimedb::host::{ModuleHost, ReducerArgs};
 use spacetimedb::messages::websocket::CallReducerFlags;
 use spacetimedb_client_api::{ControlStateReadAccess, ControlStateWriteAccess, DatabaseDef, NodeDelegate};
 use spacetimedb_lib::{bsatn, sats};
@@ -49,6 +52,7 @@ pub struct ModuleHandle {
     _env: Arc<StandaloneEnv>,
     pub client: ClientConnection,
     pub db_identity: Identity,
+    module_rx: watch::Receiver<ModuleHost>,
 }
 
 impl ModuleHandle {
@@ -82,6 +86,38 @@ impl ModuleHandle {
         self.client.handle_message(message, timer).await.map_err(Into::into)
     }
 
+    /// Connects another client to the module,
+    /// returning it along with the receiving end of the messages the host sends it.
+    pub fn connect(&self) -> (ClientConnection, mpsc::Receiver<SerializableMessage>) {
+        let client_id = ClientActorId {
+            identity: Identity::ZERO,
+            address: generate_random_address(),
+            name: self._env.client_actor_index().next_client_name(),
+        };
+        ClientConnection::dummy_with_channel(
+            client_id,
+            ClientConfig::for_test(),
+            self.client.replica_id,
+            self.module_rx.clone(),
+        )
+    }
+
+    /// Applies the settings of `config` which can change while the server runs,
+    /// as the server does when its config file is reloaded.
+    pub async fn reload_config(&self, config: ConfigFile) {
+        self._env.reload_config(config).await
+    }
+
+    /// Serves the HTTP API of the server on a free local port, returning its base URL.
+    pub async fn serve_http(&self) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let router = spacetimedb_standalone::routes::router(self._env.clone(), false, <_>::default());
+        let service = router.into_make_service_with_connect_info::<SocketAddr>();
+        tokio::spawn(async move { axum::serve(listener, service).await });
+        format!("http://{addr}")
+    }
+
     pub async fn read_log(&self, size: Option<u32>) -> String {
         let logs_dir = self._env.data_dir().replica(self.client.replica_id).module_logs();
         DatabaseLogger::read_latest(logs_dir, size).await
@@ -210,18 +246,29 @@ impl CompiledModule {
         // for stuff like "get logs" or "get message log"
         ModuleHandle {
             _env: env,
-            client: ClientConnection::dummy(client_id, ClientConfig::for_test(), instance.id, module_rx),
+            client: ClientConnection::dummy(client_id, ClientConfig::for_test(), instance.id, module_rx.clone()),
             db_identity,
+            module_rx,
         }
     }
 }
 
 /// For testing, persist to disk by default, as many tests
 /// exercise functionality like restarting the database.
-pub static DEFAULT_CONFIG: Config = Config { storage: Storage::Disk };
+pub const DEFAULT_CONFIG: Config = Config {
+    storage: Storage::Disk,
+    quotas: QuotaConfig::UNLIMITED,
+    durability: DurabilityConfig::DEFAULT,
+    memory: MemoryConfig::DEFAULT,
+};
 
 /// For performance tests, do not persist to disk.
-pub static IN_MEMORY_CONFIG: Config = Config { storage: Storage::Disk };
+pub const IN_MEMORY_CONFIG: Config = Config {
+    storage: Storage::Disk,
+    quotas: QuotaConfig::UNLIMITED,
+    durability: DurabilityConfig::DEFAULT,
+    memory: MemoryConfig::DEFAULT,
+};
 
 /// Used to parse output from module logs.
 ///
@@ -1,5 +1,11 @@
 use serial_test::serial;
+use spacetimedb::client::messages::{serialize, SerializableMessage, ViewUpdateMessage};
+use spacetimedb::client::{ClientConfig, DataMessage, MessageHandleError, Protocol};
+use spacetimedb::config::{ConfigFile, DatabaseQuotaConfig, QuotaConfig};
+use spacetimedb::host::HttpRouteCallError;
+use spacetimedb_lib::db::raw_def::v9::HttpMethod;
 use spacetimedb_lib::sats::{product, AlgebraicValue};
+use spacetimedb_lib::{Address, Identity};
 use spacetimedb_testing::modules::{
     CompilationMode, CompiledModule, Csharp, LogLevel, LoggerRecord, ModuleHandle, ModuleLanguage, Rust,
     DEFAULT_CONFIG, IN_MEMORY_CONFIG,
@@ -8,6 +14,7 @@ use std::{
     future::Future,
     time::{Duration, Instant},
 };
+use tokio::sync::mpsc;
 
 fn init() {
     let _ = env_logger::builder()
@@ -99,6 +106,104 @@ fn test_calling_a_reducer_csharp() {
     test_calling_a_reducer_in_module("spacetimedb-quickstart-cs");
 }
 
+#[test]
+#[serial]
+fn test_reducer_queue_quota() {
+    init();
+
+    CompiledModule::compile("spacetimedb-quickstart", CompilationMode::Debug).with_module_async(
+        DEFAULT_CONFIG,
+        |module| async move {
+            let call = |request_id: u32| {
+                format!(r#"{{"CallReducer": {{"reducer": "say_hello", "args": "[]", "request_id": {request_id}, "flags": 0 }}}}"#)
+            };
+            let refuse_all = DatabaseQuotaConfig {
+                max_reducer_queue_depth: Some(0),
+                ..<_>::default()
+            };
+            let mut quotas = QuotaConfig {
+                max_reducer_queue_depth: Some(1),
+                ..QuotaConfig::UNLIMITED
+            };
+
+            // The limits of another database don't apply to this one.
+            quotas.databases.insert(Identity::ZERO, refuse_all);
+            let config = ConfigFile {
+                quotas: quotas.clone(),
+                ..<_>::default()
+            };
+            module.reload_config(config).await;
+            module.send(call(0)).await.unwrap();
+
+            // The limits of this database take precedence over those of every database.
+            quotas.databases.insert(module.db_identity, refuse_all);
+            module.reload_config(ConfigFile { quotas, ..<_>::default() }).await;
+            let err = module.client.handle_message(call(1), Instant::now()).await.unwrap_err();
+            let MessageHandleError::Execution(err) = err else {
+                panic!("expected the call to fail, got {err:?}");
+            };
+
+            // The caller is told that its call failed.
+            let config = ClientConfig {
+                protocol: Protocol::Text,
+                ..ClientConfig::for_test()
+            };
+            let DataMessage::Text(update) = serialize(err, config) else {
+                panic!("expected a text message");
+            };
+            let update: serde_json::Value = serde_json::from_str(&update).unwrap();
+            let update = &update["TransactionUpdate"];
+            assert_eq!(update["reducer_call"]["reducer_name"], "say_hello");
+            assert_eq!(update["reducer_call"]["request_id"], 1);
+            let status = update["status"]["Failed"].as_str().unwrap();
+            assert!(status.contains("limit of 0 pending reducer calls"), "{status}");
+
+            // Only the first call ran.
+            assert_eq!(read_logs(&module).await, ["Hello, World!"]);
+        },
+    );
+}
+
+#[test]
+#[serial]
+fn test_cpu_quota() {
+    init();
+
+    CompiledModule::compile("spacetimedb-quickstart", CompilationMode::Debug).with_module_async(
+        DEFAULT_CONFIG,
+        |module| async move {
+            let call = |request_id: u32| {
+                format!(r#"{{"CallReducer": {{"reducer": "say_hello", "args": "[]", "request_id": {request_id}, "flags": 0 }}}}"#)
+            };
+            let quotas = QuotaConfig {
+                max_cpu_ms_per_minute: Some(0),
+                ..QuotaConfig::UNLIMITED
+            };
+            module.reload_config(ConfigFile { quotas, ..<_>::default() }).await;
+
+            // The first call runs, as the database hasn't used any CPU time yet,
+            // but it uses up all the time the database may use.
+            module.send(call(0)).await.unwrap();
+            let err = module.client.handle_message(call(1), Instant::now()).await.unwrap_err();
+            let MessageHandleError::Execution(err) = err else {
+                panic!("expected the call to fail, got {err:?}");
+            };
+            let config = ClientConfig {
+                protocol: Protocol::Text,
+                ..ClientConfig::for_test()
+            };
+            let DataMessage::Text(update) = serialize(err, config) else {
+                panic!("expected a text message");
+            };
+            let update: serde_json::Value = serde_json::from_str(&update).unwrap();
+            let status = update["TransactionUpdate"]["status"]["Failed"].as_str().unwrap();
+            assert!(status.contains("milliseconds of CPU time per minute"), "{status}");
+
+            assert_eq!(read_logs(&module).await, ["Hello, World!"]);
+        },
+    );
+}
+
 #[test]
 #[serial]
 fn test_calling_a_reducer_with_private_table() {
@@ -124,6 +229,300 @@ fn test_calling_a_reducer_with_private_table() {
     );
 }
 
+async fn next_view_update(messages: &mut mpsc::Receiver<SerializableMessage>) -> ViewUpdateMessage {
+    let message = tokio::time::timeout(Duration::from_secs(10), messages.recv())
+        .await
+        .expect("timed out waiting for a view update")
+        .expect("the client was disconnected");
+    match message {
+        SerializableMessage::ViewUpdate(update) => update,
+        message => panic!("expected a view update, got {message:?}"),
+    }
+}
+
+#[test]
+#[serial]
+fn test_views_refresh_only_when_tables_they_read_change() {
+    init();
+
+    CompiledModule::compile("views-test-cs", CompilationMode::Debug).with_module_async(
+        DEFAULT_CONFIG,
+        |module| async move {
+            let (client, mut messages) = module.connect();
+            let json = r#"{"SubscribeView": {"view": "adults", "args": "[]", "request_id": 0, "query_id": {"id": 0}, "page": {"order_by": [], "offset": 0, "limit": {"none": []}}, "min_tx_offset": {"none": []}}}"#
+                .to_string();
+            client.handle_message(json, Instant::now()).await.unwrap();
+
+            let update = next_view_update(&mut messages).await;
+            assert!(update.inserts.is_empty() && update.deletes.is_empty());
+
+            module
+                .call_reducer_binary("add_person", &product!["Arya", 18u8])
+                .await
+                .unwrap();
+            let update = next_view_update(&mut messages).await;
+            assert_eq!(update.inserts, [product!["Arya", 18u8]]);
+            assert!(update.deletes.is_empty());
+
+            // `adults` doesn't read `pet`, so the host must not call it again.
+            // Give the host time to refresh views, so that a wrong refresh isn't coalesced with the next one.
+            module.call_reducer_binary("add_pet", &product!["Nymeria"]).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(500)).await;
+
+            module
+                .call_reducer_binary("add_person", &product!["Sansa", 20u8])
+                .await
+                .unwrap();
+            let update = next_view_update(&mut messages).await;
+            assert_eq!(update.inserts, [product!["Sansa", 20u8]]);
+            assert!(update.deletes.is_empty());
+
+            // Once for the subscription, and once for each change to `person`.
+            assert_eq!(read_logs(&module).await, ["Computing adults"; 3].map(String::from));
+        },
+    );
+}
+
+#[test]
+#[serial]
+fn test_http_route_served_by_view() {
+    init();
+
+    CompiledModule::compile("views-test-cs", CompilationMode::Debug).with_module_async(
+        DEFAULT_CONFIG,
+        |module| async move {
+            module
+                .call_reducer_binary("add_pet", &product!["Nymeria"])
+                .await
+                .unwrap();
+            module.call_reducer_binary("add_pet", &product!["Ghost"]).await.unwrap();
+
+            let host = &module.client.module;
+            let query = vec![("name".to_owned(), "Ghost".to_owned())];
+            let rows = host
+                .call_http_route(Identity::ZERO, Address::ZERO, HttpMethod::Get, "/pets", query)
+                .await
+                .unwrap();
+            assert_eq!(rows, [product!["Ghost"]]);
+
+            // `adults` is a view, but doesn't serve any endpoint.
+            let err = host
+                .call_http_route(Identity::ZERO, Address::ZERO, HttpMethod::Get, "/adults", vec![])
+                .await
+                .unwrap_err();
+            assert!(matches!(err, HttpRouteCallError::NoSuchRoute));
+
+            let err = host
+                .call_http_route(Identity::ZERO, Address::ZERO, HttpMethod::Get, "/pets", vec![])
+                .await
+                .unwrap_err();
+            assert!(matches!(err, HttpRouteCallError::Args(_)));
+        },
+    );
+}
+
+#[test]
+#[serial]
+fn test_calling_a_view_over_http() {
+    init();
+
+    CompiledModule::compile("views-test-cs", CompilationMode::Debug).with_module_async(
+        DEFAULT_CONFIG,
+        |module| async move {
+            module.call_reducer_binary("add_pet", &product!["Ghost"]).await.unwrap();
+
+            let url = module.serve_http().await;
+            let client = reqwest::Client::new();
+            let call_view = |view: &str, args: &'static str| {
+                let url = format!("{url}/database/view/{}/{view}", module.db_identity);
+                client.post(url).body(args).send()
+            };
+
+            let response = call_view("pets_named", r#"["Ghost"]"#).await.unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::OK);
+            let rows: serde_json::Value = response.json().await.unwrap();
+            assert_eq!(rows, serde_json::json!([["Ghost"]]));
+
+            let response = call_view("pets_named", "[]").await.unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+            let response = call_view("no_such_view", "[]").await.unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        },
+    );
+}
+
+/// Returns the next message the host broadcasts to the client on a topic,
+/// skipping the updates of the reducers it calls on the client's behalf.
+async fn next_topic_message(messages: &mut mpsc::Receiver<SerializableMessage>) -> (Box<str>, Vec<u8>) {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(10), messages.recv())
+            .await
+            .expect("timed out waiting for a topic message")
+            .expect("client disconnected");
+        if let SerializableMessage::Topic(msg) = message {
+            return (msg.topic, msg.payload.to_vec());
+        }
+    }
+}
+
+#[test]
+#[serial]
+fn test_reading_system_tables() {
+    init();
+
+    CompiledModule::compile("system-tables-test", CompilationMode::Debug).with_module_async(
+        DEFAULT_CONFIG,
+        |module| async move {
+            module.call_reducer_json("list_clients", &product![]).await.unwrap();
+
+            let client = &module.client;
+            client
+                .module
+                .call_identity_connected_disconnected(client.id.identity, client.id.address, true, None, None)
+                .await
+                .unwrap();
+            module.call_reducer_json("list_clients", &product![]).await.unwrap();
+
+            assert_eq!(
+                read_logs(&module).await,
+                [
+                    "0 clients, 0 subscriptions".to_owned(),
+                    format!("Client {} is connected", client.id.identity),
+                    "1 clients, 0 subscriptions".to_owned(),
+                ]
+            );
+        },
+    );
+}
+
+#[test]
+#[serial]
+fn test_triggers() {
+    init();
+
+    CompiledModule::compile("triggers-test", CompilationMode::Debug).with_module_async(
+        DEFAULT_CONFIG,
+        |module| async move {
+            module
+                .call_reducer_binary("insert_element", &product![1u32, "a"])
+                .await
+                .unwrap();
+            module
+                .call_reducer_binary("update_element", &product![1u32, "b"])
+                .await
+                .unwrap();
+            module
+                .call_reducer_binary("delete_element", &product![1u32])
+                .await
+                .unwrap();
+            module
+                .call_reducer_binary("log_audit_entries", &product![])
+                .await
+                .unwrap();
+
+            assert_eq!(
+                read_logs(&module).await,
+                [
+                    "inserted 1 = a",
+                    "deleted 1 = a",
+                    "inserted 1 = b",
+                    "deleted 1 = b",
+                    "4 audit log entries",
+                ]
+            );
+        },
+    );
+}
+
+#[test]
+#[serial]
+fn test_undo_journal() {
+    init();
+
+    CompiledModule::compile("journal-test", CompilationMode::Debug).with_module_async(
+        DEFAULT_CONFIG,
+        |module| async move {
+            let call = |reducer: &'static str, args| {
+                let module = &module;
+                async move { module.call_reducer_binary(reducer, &args).await.unwrap() }
+            };
+            for text in ["a", "b", "c"] {
+                call("set_note", product![1u32, text]).await;
+            }
+            call("log_notes", product![]).await;
+
+            // The journal keeps two steps, so the first can't be undone.
+            for _ in 0..3 {
+                call("undo", product![]).await;
+                call("log_notes", product![]).await;
+            }
+
+            // Recording a step after redoing one discards the step still undone.
+            call("redo", product![]).await;
+            call("set_note", product![1u32, "d"]).await;
+            call("redo", product![]).await;
+            call("log_notes", product![]).await;
+            call("undo", product![]).await;
+            call("log_notes", product![]).await;
+
+            assert_eq!(
+                read_logs(&module).await,
+                [
+                    "1 = c",
+                    "undone: true",
+                    "1 = b",
+                    "undone: true",
+                    "1 = a",
+                    "undone: false",
+                    "1 = a",
+                    "redone: true",
+                    "redone: false",
+                    "1 = d",
+                    "undone: true",
+                    "1 = b",
+                ]
+            );
+        },
+    );
+}
+
+#[test]
+#[serial]
+fn test_topic_subscriptions_are_authorized() {
+    init();
+
+    CompiledModule::compile("topics-test", CompilationMode::Debug).with_module_async(
+        DEFAULT_CONFIG,
+        |module| async move {
+            let (client, mut messages) = module.connect();
+            for topic in ["private/secrets", "public/chat"] {
+                let json = format!(r#"{{"SubscribeTopic": {{"topic": "{topic}"}}}}"#);
+                client.handle_message(json, Instant::now()).await.unwrap();
+            }
+
+            // The authorizer refused `private/secrets`, so the client only receives the second message.
+            module
+                .call_reducer_binary("say", &product!["private/secrets", "psst"])
+                .await
+                .unwrap();
+            module
+                .call_reducer_binary("say", &product!["public/chat", "hello"])
+                .await
+                .unwrap();
+            let (topic, payload) = next_topic_message(&mut messages).await;
+            assert_eq!(&*topic, "public/chat");
+            assert_eq!(payload, b"hello");
+
+            // Clients can't call the authorizer themselves.
+            module
+                .call_reducer_binary("authorize_topic", &product!["private/secrets"])
+                .await
+                .unwrap_err();
+        },
+    );
+}
+
 /// Invoke the `rust-wasm-test` module,
 /// use `caller` to invoke its `test` reducer,
 /// and assert that its logs look right.
@@ -250,6 +649,12 @@ fn test_index_scans() {
                 .await
                 .unwrap();
 
+            // This one only checks that the rows scanned don't depend on how they are fetched.
+            module
+                .call_reducer_binary("test_iter_chunk_sizes", no_args)
+                .await
+                .unwrap();
+
             let logs = read_logs(&module).await;
 
             // Each timing span should be < 1ms
@@ -345,6 +345,152 @@ impl ColumnOp {
             OpLogic::Or => opers.iter().any(|o| o.eval_bool(row)),
         }
     }
+
+    /// Collects the columns read by `self` into `cols`, without duplicates.
+    pub fn read_columns(&self, cols: &mut Vec<ColId>) {
+        match self {
+            Self::Col(col) | Self::ColCmpVal { lhs: col, .. } => {
+                if !cols.contains(col) {
+                    cols.push(*col);
+                }
+            }
+            Self::Val(_) => {}
+            Self::Cmp { lhs, rhs, .. } => {
+                lhs.read_columns(cols);
+                rhs.read_columns(cols);
+            }
+            Self::Log { operands, .. } => operands.iter().for_each(|op| op.read_columns(cols)),
+        }
+    }
+
+    /// Evaluates `self` for every row of `batch`,
+    /// returning whether each row satisfies it.
+    ///
+    /// Rather than walking the expression tree once per row, as [`ColumnOp::eval_bool`] does,
+    /// each operator is applied to an entire column of the batch at once.
+    ///
+    /// Panics if `batch` lacks any of the [`read_columns`](Self::read_columns) of `self`.
+    pub fn eval_batch(&self, batch: &ColumnBatch) -> Vec<bool> {
+        let as_bool = |val: &AlgebraicValue| *val.as_bool().unwrap();
+        match self {
+            Self::Col(col) => batch.column(*col).iter().map(as_bool).collect(),
+            Self::Val(val) => vec![as_bool(val); batch.len()],
+            Self::ColCmpVal { lhs, cmp, rhs } => Self::eval_cmp_batch(*cmp, batch.column(*lhs), rhs),
+            Self::Cmp { lhs, cmp, rhs } => {
+                let lhs = lhs.eval_values(batch);
+                let rhs = rhs.eval_values(batch);
+                (0..batch.len())
+                    .map(|i| Self::eval_op_cmp(*cmp, &lhs.get(i), &rhs.get(i)))
+                    .collect()
+            }
+            Self::Log { op, operands } => {
+                let (init, combine): (bool, fn(bool, bool) -> bool) = match op {
+                    OpLogic::And => (true, |a, b| a && b),
+                    OpLogic::Or => (false, |a, b| a || b),
+                };
+                let mut result = vec![init; batch.len()];
+                for operand in operands.iter() {
+                    for (acc, x) in result.iter_mut().zip(operand.eval_batch(batch)) {
+                        *acc = combine(*acc, x);
+                    }
+                }
+                result
+            }
+        }
+    }
+
+    /// Runs the comparison `value cmp rhs` for every `value` of `column`.
+    ///
+    /// Matching on `cmp` once per column, rather than once per value,
+    /// leaves a tight loop for each comparison.
+    fn eval_cmp_batch(cmp: OpCmp, column: &[AlgebraicValue], rhs: &AlgebraicValue) -> Vec<bool> {
+        let cmp: fn(&AlgebraicValue, &AlgebraicValue) -> bool = match cmp {
+            OpCmp::Eq => |lhs, rhs| lhs == rhs,
+            OpCmp::NotEq => |lhs, rhs| lhs != rhs,
+            OpCmp::Lt => |lhs, rhs| lhs < rhs,
+            OpCmp::LtEq => |lhs, rhs| lhs <= rhs,
+            OpCmp::Gt => |lhs, rhs| lhs > rhs,
+            OpCmp::GtEq => |lhs, rhs| lhs >= rhs,
+        };
+        column.iter().map(|lhs| cmp(lhs, rhs)).collect()
+    }
+
+    /// Evaluates `self` to a value for every row of `batch`.
+    fn eval_values<'a>(&'a self, batch: &'a ColumnBatch) -> BatchValues<'a> {
+        match self {
+            Self::Col(col) => BatchValues::Column(batch.column(*col)),
+            Self::Val(val) => BatchValues::Const(val),
+            _ => BatchValues::Bools(self.eval_batch(batch)),
+        }
+    }
+}
+
+/// The values of the columns read by a [`ColumnOp`], for each row in a batch of rows.
+///
+/// Each column is decoded from the rows once, up front,
+/// so that the operators of a predicate can be evaluated over whole columns.
+#[derive(Debug)]
+pub struct ColumnBatch {
+    len: usize,
+    columns: Vec<(ColId, Vec<AlgebraicValue>)>,
+}
+
+impl ColumnBatch {
+    /// Decodes the columns `cols` of each of `rows`.
+    pub fn new(rows: &[RelValue<'_>], cols: &[ColId]) -> Self {
+        let columns = cols
+            .iter()
+            .map(|&col| {
+                let values = rows
+                    .iter()
+                    .map(|row| row.read_column(col.idx()).unwrap().into_owned())
+                    .collect();
+                (col, values)
+            })
+            .collect();
+        Self {
+            len: rows.len(),
+            columns,
+        }
+    }
+
+    /// Returns the number of rows in the batch.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns whether the batch holds no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the values of the column `col`, one for each row.
+    ///
+    /// Panics if the column wasn't decoded.
+    fn column(&self, col: ColId) -> &[AlgebraicValue] {
+        self.columns
+            .iter()
+            .find(|(c, _)| *c == col)
+            .map(|(_, values)| &**values)
+            .unwrap_or_else(|| panic!("column {col} is not part of the batch"))
+    }
+}
+
+/// The operands of a comparison, evaluated for each row in a [`ColumnBatch`].
+enum BatchValues<'a> {
+    Column(&'a [AlgebraicValue]),
+    Const(&'a AlgebraicValue),
+    Bools(Vec<bool>),
+}
+
+impl BatchValues<'_> {
+    fn get(&self, i: usize) -> Cow<'_, AlgebraicValue> {
+        match self {
+            Self::Column(values) => Cow::Borrowed(&values[i]),
+            Self::Const(val) => Cow::Borrowed(val),
+            Self::Bools(values) => Cow::Owned(AlgebraicValue::Bool(values[i])),
+        }
+    }
 }
 
 impl fmt::Display for ColumnOp {
@@ -821,6 +967,8 @@ pub enum CrudExpr {
     Insert {
         table: DbTable,
         rows: Vec<ProductValue>,
+        /// The columns to report for each inserted row, if `RETURNING` was given.
+        returning: Option<Arc<Header>>,
     },
     Update {
         delete: QueryExpr,
@@ -2549,7 +2697,11 @@ mod tests {
     #[test]
     fn test_auth_crud_code_insert() {
         for table in tables().into_iter().filter_map(|s| s.get_db_table().cloned()) {
-            let crud = CrudExpr::Insert { table, rows: vec![] };
+            let crud = CrudExpr::Insert {
+                table,
+                rows: vec![],
+                returning: None,
+            };
             assert_owner_required(crud);
         }
     }
@@ -2666,4 +2818,48 @@ mod tests {
         let optimized = q.clone().optimize(&|_, _| 0);
         assert_eq!(q, optimized);
     }
+
+    #[test]
+    /// Tests that selecting rows in batches agrees with evaluating the predicate row by row,
+    /// across several batches.
+    fn select_batched_matches_eval_bool() {
+        use crate::iterators::RelIter;
+        use crate::rel_ops::RelOps;
+
+        let rows = (0..3000u64)
+            .map(|i| RelValue::Projection(product![i, i % 7 == 0, i % 3]))
+            .collect::<Vec<_>>();
+        let ops = [
+            ColumnOp::cmp(0, OpCmp::Lt, 100u64),
+            ColumnOp::cmp(2, OpCmp::NotEq, 1u64),
+            ColumnOp::Col(1.into()),
+            ColumnOp::new(
+                OpQuery::Logic(OpLogic::Or),
+                ColumnOp::cmp(2, OpCmp::Eq, 1u64),
+                ColumnOp::Col(1.into()),
+            ),
+            ColumnOp::new(
+                OpQuery::Logic(OpLogic::And),
+                ColumnOp::cmp(0, OpCmp::GtEq, 1500u64),
+                ColumnOp::new(
+                    OpQuery::Cmp(OpCmp::Eq),
+                    ColumnOp::Col(1.into()),
+                    ColumnOp::Val(true.into()),
+                ),
+            ),
+            ColumnOp::new(
+                OpQuery::Cmp(OpCmp::Lt),
+                ColumnOp::Col(0.into()),
+                ColumnOp::Col(2.into()),
+            ),
+        ];
+
+        for op in &ops {
+            let expected = rows.iter().filter(|row| op.eval_bool(row)).cloned().collect::<Vec<_>>();
+            let actual = RelIter::new(rows.iter().cloned())
+                .select_batched(op)
+                .collect_vec(|row| row);
+            assert_eq!(actual, expected, "{op}");
+        }
+    }
 }
@@ -1,10 +1,15 @@
 use core::iter;
 
+use crate::expr::{ColumnBatch, ColumnOp};
 use crate::relation::RelValue;
 use spacetimedb_data_structures::map::HashMap;
 use spacetimedb_lib::relation::ColExpr;
+use spacetimedb_primitives::ColId;
 use spacetimedb_sats::AlgebraicValue;
 
+/// The number of rows [`SelectBatch`] reads from its input at a time.
+pub const BATCH_SIZE: usize = 1024;
+
 /// A trait for dealing with fallible iterators for the database.
 pub trait RelOps<'a> {
     /// Advances the `iterator` and returns the next [RelValue].
@@ -27,6 +32,20 @@ pub trait RelOps<'a> {
         Select::new(self, predicate)
     }
 
+    /// Creates an `Iterator` which yields only the [RelValue]s satisfying `predicate`,
+    /// like [`RelOps::select`], but evaluates it over batches of [`BATCH_SIZE`] rows at a time.
+    ///
+    /// Note:
+    ///
+    /// It is the equivalent of a `WHERE` clause on SQL.
+    #[inline]
+    fn select_batched(self, predicate: &ColumnOp) -> SelectBatch<'a, '_, Self>
+    where
+        Self: Sized,
+    {
+        SelectBatch::new(self, predicate)
+    }
+
     /// Creates an `Iterator` which uses a closure that projects to a new [RelValue] extracted from the current.
     ///
     /// Given a [RelValue] the closure must return a subset of the current one.
@@ -142,6 +161,65 @@ where
     }
 }
 
+/// A [`Select`] over a [`ColumnOp`], which evaluates its predicate vectorized.
+///
+/// Rows are read from the input [`BATCH_SIZE`] at a time.
+/// The columns the predicate reads are decoded from the batch into a [`ColumnBatch`],
+/// over which the predicate is evaluated an operator at a time,
+/// rather than a row at a time.
+/// The rows of the batch which satisfy it are then yielded, in order.
+#[derive(Debug)]
+pub struct SelectBatch<'a, 'p, I> {
+    iter: I,
+    predicate: &'p ColumnOp,
+    /// The columns read by `predicate`.
+    cols: Vec<ColId>,
+    /// The rows of the current batch which satisfy `predicate`.
+    selected: std::vec::IntoIter<RelValue<'a>>,
+}
+
+impl<'a, 'p, I> SelectBatch<'a, 'p, I> {
+    pub fn new(iter: I, predicate: &'p ColumnOp) -> Self {
+        let mut cols = Vec::new();
+        predicate.read_columns(&mut cols);
+        Self {
+            iter,
+            predicate,
+            cols,
+            selected: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<'a, I: RelOps<'a>> RelOps<'a> for SelectBatch<'a, '_, I> {
+    fn next(&mut self) -> Option<RelValue<'a>> {
+        loop {
+            if let Some(row) = self.selected.next() {
+                return Some(row);
+            }
+
+            let mut rows = Vec::with_capacity(BATCH_SIZE);
+            while rows.len() < BATCH_SIZE {
+                let Some(row) = self.iter.next() else {
+                    break;
+                };
+                rows.push(row);
+            }
+            if rows.is_empty() {
+                return None;
+            }
+
+            let mask = self.predicate.eval_batch(&ColumnBatch::new(&rows, &self.cols));
+            self.selected = rows
+                .into_iter()
+                .zip(mask)
+                .filter_map(|(row, keep)| keep.then_some(row))
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Project<'a, I, P> {
     pub(crate) cols: &'a [ColExpr],
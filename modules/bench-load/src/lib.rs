@@ -0,0 +1,75 @@
+//! The standard module driven by the workloads of `spacetime bench`.
+//!
+//! The reducers take the size in bytes of the rows they write,
+//! so that the row size can be varied independently of the workload.
+
+use spacetimedb::{ReducerContext, Table};
+
+/// The rows appended by `insert_row`.
+#[spacetimedb::table(name = log_row, public)]
+pub struct LogRow {
+    #[primary_key]
+    #[auto_inc]
+    id: u64,
+    payload: Vec<u8>,
+}
+
+/// The rows overwritten by `update_row` and read by `read_row`.
+#[spacetimedb::table(name = keyed_row, public)]
+pub struct KeyedRow {
+    #[primary_key]
+    id: u64,
+    payload: Vec<u8>,
+}
+
+fn payload(size: u32) -> Vec<u8> {
+    vec![0xab; size as usize]
+}
+
+fn upsert(ctx: &ReducerContext, id: u64, size: u32) {
+    let row = KeyedRow {
+        id,
+        payload: payload(size),
+    };
+    if ctx.db.keyed_row().id().find(id).is_some() {
+        ctx.db.keyed_row().id().update(row);
+    } else {
+        ctx.db.keyed_row().insert(row);
+    }
+}
+
+/// Sets the rows `0..rows` of `keyed_row` to payloads of `size` bytes,
+/// before a workload updating or reading them.
+#[spacetimedb::reducer]
+pub fn fill_rows(ctx: &ReducerContext, rows: u64, size: u32) {
+    for id in 0..rows {
+        upsert(ctx, id, size);
+    }
+}
+
+/// Appends a row with a payload of `size` bytes.
+#[spacetimedb::reducer]
+pub fn insert_row(ctx: &ReducerContext, size: u32) {
+    ctx.db.log_row().insert(LogRow {
+        id: 0,
+        payload: payload(size),
+    });
+}
+
+/// Sets the row `id` to a payload of `size` bytes.
+#[spacetimedb::reducer]
+pub fn update_row(ctx: &ReducerContext, id: u64, size: u32) {
+    upsert(ctx, id, size);
+}
+
+/// Reads the row `id`, failing if there is none.
+#[spacetimedb::reducer]
+pub fn read_row(ctx: &ReducerContext, id: u64) -> Result<(), String> {
+    match ctx.db.keyed_row().id().find(id) {
+        Some(row) => {
+            std::hint::black_box(row.payload);
+            Ok(())
+        }
+        None => Err(format!("no row {id}")),
+    }
+}
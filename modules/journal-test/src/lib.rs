@@ -0,0 +1,42 @@
+//! A module whose changes are recorded in an undo journal, to test it.
+
+use spacetimedb::{ReducerContext, Table};
+
+spacetimedb::undo_journal_table!();
+
+/// The journal recording the changes to `note`.
+const JOURNAL_ID: u64 = 0;
+
+#[spacetimedb::table(name = note)]
+pub struct Note {
+    #[primary_key]
+    id: u32,
+    text: String,
+}
+
+/// Sets the text of the note `id`, as one step of a journal keeping two steps.
+#[spacetimedb::reducer]
+pub fn set_note(ctx: &ReducerContext, id: u32, text: String) {
+    let journal = ctx.journal(JOURNAL_ID).with_capacity(2);
+    if let Some(note) = ctx.db.note().id().find(id) {
+        journal.delete(&ctx.db.note(), note);
+    }
+    journal.insert(&ctx.db.note(), Note { id, text });
+}
+
+#[spacetimedb::reducer]
+pub fn undo(ctx: &ReducerContext) {
+    log::info!("undone: {}", ctx.undo(JOURNAL_ID));
+}
+
+#[spacetimedb::reducer]
+pub fn redo(ctx: &ReducerContext) {
+    log::info!("redone: {}", ctx.redo(JOURNAL_ID));
+}
+
+#[spacetimedb::reducer]
+pub fn log_notes(ctx: &ReducerContext) {
+    for note in ctx.db.note().iter() {
+        log::info!("{} = {}", note.id, note.text);
+    }
+}
@@ -1,4 +1,4 @@
-use spacetimedb::{log_stopwatch::LogStopwatch, ReducerContext, Table};
+use spacetimedb::{log_stopwatch::LogStopwatch, IterOptions, ReducerContext, Table};
 
 #[spacetimedb::table(name = location, index(name = coordinates, btree(columns = [x, z, dimension])))]
 #[derive(Debug, PartialEq, Eq)]
@@ -77,3 +77,25 @@ pub fn test_index_scan_on_x_z(ctx: &ReducerContext) {
     span.end();
     assert_eq!(n as u64, ROWS_PER_CHUNK);
 }
+
+#[spacetimedb::reducer]
+/// Fetching rows in chunks of any size should yield the same rows,
+/// including chunks smaller than a single row.
+pub fn test_iter_chunk_sizes(ctx: &ReducerContext) {
+    let rows = ctx.db.location().chunk().filter(&CHUNK).collect::<Vec<_>>();
+    assert_eq!(rows.len() as u64, ROWS_PER_CHUNK);
+    for chunk_size in [1, 64, 1024 * 1024] {
+        let options = IterOptions { chunk_size };
+        let chunked = ctx
+            .db
+            .location()
+            .chunk()
+            .filter_with(&CHUNK, options)
+            .collect::<Vec<_>>();
+        assert!(chunked == rows, "rows differ with a chunk size of {chunk_size}");
+        assert_eq!(
+            ctx.db.location().iter_with(options).count() as u64,
+            NUM_CHUNKS * ROWS_PER_CHUNK
+        );
+    }
+}
@@ -0,0 +1,15 @@
+//! A module which reads the system tables that modules may read.
+
+use spacetimedb::{ReducerContext, SystemTable};
+
+#[spacetimedb::reducer]
+pub fn list_clients(ctx: &ReducerContext) {
+    for client in ctx.db.st_connection().iter() {
+        log::info!("Client {} is connected", client.identity);
+    }
+    log::info!(
+        "{} clients, {} subscriptions",
+        ctx.db.st_connection().count(),
+        ctx.db.st_subscription().count()
+    );
+}
@@ -0,0 +1,17 @@
+//! A module which broadcasts on topics, and refuses subscriptions to the `private/` ones.
+
+use spacetimedb::ReducerContext;
+
+#[spacetimedb::reducer(authorize_topic)]
+pub fn authorize_topic(_ctx: &ReducerContext, topic: String) -> Result<(), String> {
+    if topic.starts_with("private/") {
+        return Err(format!("`{topic}` is private"));
+    }
+    Ok(())
+}
+
+#[spacetimedb::reducer]
+pub fn say(ctx: &ReducerContext, topic: String, message: String) {
+    log::info!("Saying {message:?} on {topic}");
+    ctx.broadcast(&topic, message);
+}
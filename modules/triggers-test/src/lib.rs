@@ -0,0 +1,55 @@
+//! A module whose triggers record the writes to its tables, to test them.
+
+use spacetimedb::{ReducerContext, Table};
+
+#[spacetimedb::table(name = element)]
+pub struct Element {
+    #[primary_key]
+    id: u32,
+    value: String,
+}
+
+#[spacetimedb::table(name = audit_log)]
+pub struct AuditLog {
+    entry: String,
+}
+
+#[spacetimedb::trigger(on_insert = element)]
+fn element_inserted(ctx: &ReducerContext, row: &Element) {
+    ctx.db.audit_log().insert(AuditLog {
+        entry: format!("inserted {} = {}", row.id, row.value),
+    });
+}
+
+#[spacetimedb::trigger(on_delete = element)]
+fn element_deleted(ctx: &ReducerContext, row: &Element) {
+    ctx.db.audit_log().insert(AuditLog {
+        entry: format!("deleted {} = {}", row.id, row.value),
+    });
+}
+
+/// Runs within the triggers above, as they insert into `audit_log`.
+#[spacetimedb::trigger(on_insert = audit_log)]
+fn entry_logged(_ctx: &ReducerContext, row: &AuditLog) {
+    log::info!("{}", row.entry);
+}
+
+#[spacetimedb::reducer]
+pub fn insert_element(ctx: &ReducerContext, id: u32, value: String) {
+    ctx.db.element().insert(Element { id, value });
+}
+
+#[spacetimedb::reducer]
+pub fn update_element(ctx: &ReducerContext, id: u32, value: String) {
+    ctx.db.element().id().update(Element { id, value });
+}
+
+#[spacetimedb::reducer]
+pub fn delete_element(ctx: &ReducerContext, id: u32) {
+    ctx.db.element().id().delete(id);
+}
+
+#[spacetimedb::reducer]
+pub fn log_audit_entries(ctx: &ReducerContext) {
+    log::info!("{} audit log entries", ctx.db.audit_log().count());
+}
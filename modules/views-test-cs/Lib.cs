@@ -0,0 +1,46 @@
+namespace SpacetimeDB.Views.Test;
+
+using SpacetimeDB;
+
+[Table(Name = "person", Public = true)]
+public partial struct Person
+{
+    public string name;
+    public byte age;
+}
+
+[Table(Name = "pet", Public = true)]
+public partial struct Pet
+{
+    public string name;
+}
+
+static partial class Module
+{
+    [SpacetimeDB.Reducer]
+    public static void add_person(ReducerContext ctx, string name, byte age)
+    {
+        ctx.Db.person.Insert(new Person { name = name, age = age });
+    }
+
+    [SpacetimeDB.Reducer]
+    public static void add_pet(ReducerContext ctx, string name)
+    {
+        ctx.Db.pet.Insert(new Pet { name = name });
+    }
+
+    // Reads only the `person` table, so changes to `pet` must not cause it to be called again.
+    [SpacetimeDB.View]
+    public static List<Person> adults(ViewContext ctx)
+    {
+        Log.Info("Computing adults");
+        return ctx.Db.person.Iter().Where(person => person.age >= 18).ToList();
+    }
+
+    // Served over HTTP at `/pets?name=...`.
+    [SpacetimeDB.View(HttpGet = "/pets")]
+    public static List<Pet> pets_named(ViewContext ctx, string name)
+    {
+        return ctx.Db.pet.Iter().Where(pet => pet.name == name).ToList();
+    }
+}
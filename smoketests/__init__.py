@@ -1,17 +1,28 @@
 from pathlib import Path
+import atexit
+import base64
 import contextlib
+import dataclasses
 import json
 import os
+import queue
 import random
 import re
 import shutil
+import socket
+import ssl
 import string
 import string
+import struct
 import subprocess
 import sys
 import tempfile
 import threading
+import time
+import typing
 import unittest
+import urllib.parse
+import urllib.request
 import logging
 
 # miscellaneous file paths
@@ -35,6 +46,9 @@ HAVE_DOCKER = False
 # this is set to true when the --skip-dotnet flag is not passed to the cli,
 # and a dotnet installation is detected
 HAVE_DOTNET = False
+# this is set to true when the server runs with `--test-mode`,
+# i.e. when the --start-server or --test-mode flag is passed to the cli
+HAVE_TEST_MODE = False
 
 # we need to late-bind the output stream to allow unittests to capture stdout/stderr.
 class CapturableHandler(logging.StreamHandler):
@@ -76,6 +90,11 @@ def requires_docker(item):
         return item
     return unittest.skip("docker not available")(item)
 
+def requires_test_mode(item):
+    if HAVE_TEST_MODE:
+        return item
+    return unittest.skip("server not running with --test-mode")(item)
+
 def random_string(k=20):
     return ''.join(random.choices(string.ascii_letters, k=k))
 
@@ -145,6 +164,33 @@ def new_identity(config_path):
     spacetime("--config-path", str(config_path), "logout")
     spacetime("--config-path", str(config_path), "login", "--server-issued-login", "localhost", full_output=False)
 
+def start_server(data_dir, listen_addr="127.0.0.1:3000"):
+    """
+    Start a standalone server in the background, to be shared by all the tests in this run.
+
+    Every test class already publishes its own database with its own cli config,
+    so tests are isolated from each other without needing a server of their own.
+    The server is killed when the test run exits.
+    """
+    args = [SPACETIME_BIN, "start", "--data-dir", str(data_dir), "--listen-addr", listen_addr, "--test-mode"]
+    log_cmd(["spacetime", *args[1:]])
+    proc = subprocess.Popen(args, stdout=subprocess.DEVNULL, stderr=subprocess.DEVNULL)
+    atexit.register(proc.kill)
+
+    for _ in range(60):
+        if proc.poll() is not None:
+            raise Exception(f"server exited with code {proc.returncode}")
+        try:
+            urllib.request.urlopen(f"http://{listen_addr}/database/ping").close()
+            break
+        except OSError:
+            time.sleep(1)
+    else:
+        raise Exception(f"server at {listen_addr} not responding")
+    logging.info(f"Server started at {listen_addr}")
+    return proc
+
+
 class Smoketest(unittest.TestCase):
     MODULE_CODE = TEMPLATE_LIB_RS
     AUTOPUBLISH = True
@@ -242,6 +288,73 @@ class Smoketest(unittest.TestCase):
         # and **not raise any exceptions to the caller**.
         return ReturnThread(run).join
 
+    @classmethod
+    def server_url(cls):
+        """The base http(s) url of the default server in this test's cli config."""
+        config = open(cls.config_path).read()
+        default_server = re.search(r'^default_server\s*=\s*"(.*)"', config, re.M)[1]
+        for server_config in config.split("[[server_configs]]")[1:]:
+            fields = dict(re.findall(r'^(\w+)\s*=\s*"(.*)"', server_config, re.M))
+            if default_server in (fields.get("nickname"), fields.get("host")):
+                return f"{fields.get('protocol', 'http')}://{fields['host']}"
+        return f"http://{default_server}"
+
+    def _test_route(self, method, route, **query):
+        """Call one of the server's test-mode routes for the published database, returning the decoded json response."""
+        self._check_published()
+        token = re.search(r'^spacetimedb_token\s*=\s*"(.*)"', open(self.config_path).read(), re.M)[1]
+        query = f"?{urllib.parse.urlencode(query)}" if query else ""
+        request = urllib.request.Request(
+            f"{self.server_url()}/database/{route}/{self.database_identity}{query}",
+            method=method,
+            headers={"Authorization": f"Bearer {token}"},
+        )
+        with urllib.request.urlopen(request) as response:
+            body = response.read()
+        return json.loads(body) if body else None
+
+    def advance_time(self, seconds):
+        """
+        Move the published database's clock forward by `seconds`,
+        returning once the scheduled reducers which come due in that window have run.
+
+        Requires the server to be started with `--test-mode`.
+        """
+        self._test_route("POST", "advance_time", micros=int(seconds * 1_000_000))
+
+    def force_snapshot(self):
+        """Capture a snapshot of the published database right away, and return its tx offset. Requires `--test-mode`."""
+        return self._test_route("POST", "snapshot")["tx_offset"]
+
+    def latest_snapshot_offset(self):
+        """The tx offset of the published database's latest snapshot, or `None`. Requires `--test-mode`."""
+        return self._test_route("GET", "snapshot")["tx_offset"]
+
+    def restore_from_snapshot(self, offset):
+        """
+        Restart the published database from its latest snapshot at or before `offset`,
+        replaying the transactions after it from the commitlog. Requires `--test-mode`.
+
+        Snapshots newer than `offset` are discarded.
+        """
+        self._test_route("POST", "restore_snapshot", tx_offset=offset)
+
+    def spawn_clients(self, n):
+        """
+        Open `n` websocket connections to the published database, each authenticated with its own fresh identity.
+
+        Unlike `subscribe`, the clients speak the websocket protocol directly rather than going through the cli,
+        so a test can drive concurrent reducer calls and inspect each client's subscription updates separately.
+        The connections are closed when the test finishes.
+        """
+        self._check_published()
+        assert isinstance(n, int)
+
+        clients = [Client.connect(self.server_url(), self.database_identity) for _ in range(n)]
+        for client in clients:
+            self.addCleanup(client.close)
+        return clients
+
     @classmethod
     def write_module_code(cls, module_code):
         open(cls.project_path / "src/lib.rs", "w").write(module_code)
@@ -317,3 +430,298 @@ class ReturnThread:
             raise self._exception
         return self._result
 
+
+
+# A minimal, synchronous websocket client (RFC 6455), just enough to speak the text protocol to the server.
+# We implement this ourselves rather than pulling in a dependency, since the smoketests only rely on the stdlib.
+class WebSocket:
+    OP_CONTINUATION = 0x0
+    OP_TEXT = 0x1
+    OP_CLOSE = 0x8
+    OP_PING = 0x9
+    OP_PONG = 0xA
+
+    def __init__(self, url, *, protocol, headers=None):
+        headers = headers or {}
+        url = urllib.parse.urlsplit(url)
+        secure = url.scheme in ("wss", "https")
+        sock = socket.create_connection((url.hostname, url.port or (443 if secure else 80)))
+        if secure:
+            sock = ssl.create_default_context().wrap_socket(sock, server_hostname=url.hostname)
+
+        key = base64.b64encode(os.urandom(16)).decode()
+        path = url.path + (f"?{url.query}" if url.query else "")
+        request = [
+            f"GET {path} HTTP/1.1",
+            f"Host: {url.netloc}",
+            "Upgrade: websocket",
+            "Connection: Upgrade",
+            f"Sec-WebSocket-Key: {key}",
+            "Sec-WebSocket-Version: 13",
+            f"Sec-WebSocket-Protocol: {protocol}",
+            *(f"{name}: {value}" for name, value in headers.items()),
+        ]
+        sock.sendall(("\r\n".join(request) + "\r\n\r\n").encode())
+
+        self._sock = sock
+        self._file = sock.makefile("rb")
+        self._send_lock = threading.Lock()
+
+        status = self._file.readline().decode().strip()
+        if status.split()[1:2] != ["101"]:
+            sock.close()
+            raise ConnectionError(f"websocket upgrade failed: {status}")
+        # Skip the rest of the response headers.
+        while self._file.readline() not in (b"\r\n", b""):
+            pass
+
+    def _read_exact(self, n):
+        data = self._file.read(n)
+        if len(data) < n:
+            raise EOFError
+        return data
+
+    def _send_frame(self, opcode, payload):
+        # Client-to-server frames must always be masked.
+        header = bytes([0x80 | opcode])
+        n = len(payload)
+        if n < 126:
+            header += bytes([0x80 | n])
+        elif n < (1 << 16):
+            header += struct.pack("!BH", 0x80 | 126, n)
+        else:
+            header += struct.pack("!BQ", 0x80 | 127, n)
+        mask = os.urandom(4)
+        payload = bytes(b ^ mask[i % 4] for i, b in enumerate(payload))
+        with self._send_lock:
+            self._sock.sendall(header + mask + payload)
+
+    def send(self, text):
+        self._send_frame(self.OP_TEXT, text.encode())
+
+    def recv(self):
+        """Receive the next text message, or `None` if the connection was closed."""
+        message = b""
+        try:
+            while True:
+                b0, b1 = self._read_exact(2)
+                opcode = b0 & 0x0F
+                n = b1 & 0x7F
+                if n == 126:
+                    n, = struct.unpack("!H", self._read_exact(2))
+                elif n == 127:
+                    n, = struct.unpack("!Q", self._read_exact(8))
+                mask = self._read_exact(4) if b1 & 0x80 else None
+                payload = self._read_exact(n)
+                if mask is not None:
+                    payload = bytes(b ^ mask[i % 4] for i, b in enumerate(payload))
+
+                if opcode == self.OP_CLOSE:
+                    return None
+                elif opcode == self.OP_PING:
+                    self._send_frame(self.OP_PONG, payload)
+                elif opcode in (self.OP_TEXT, self.OP_CONTINUATION):
+                    message += payload
+                    if b0 & 0x80:
+                        return message.decode()
+        except (EOFError, OSError):
+            return None
+
+    def close(self):
+        try:
+            self._send_frame(self.OP_CLOSE, b"")
+        except OSError:
+            pass
+        try:
+            self._sock.shutdown(socket.SHUT_RDWR)
+        except OSError:
+            pass
+        self._sock.close()
+
+
+def reformat_update(database_update):
+    """
+    Reformat a `DatabaseUpdate` from the json protocol into the shape printed by `spacetime subscribe`:
+    `{table_name: {"deletes": [...], "inserts": [...]}}`.
+    """
+    out = {}
+    for table in database_update["tables"]:
+        table_out = out.setdefault(table["table_name"], {"deletes": [], "inserts": []})
+        for update in table["updates"]:
+            table_out["deletes"] += map(json.loads, update["deletes"])
+            table_out["inserts"] += map(json.loads, update["inserts"])
+    return out
+
+
+@dataclasses.dataclass
+class InitialSubscription:
+    request_id: int
+    # The matching rows, formatted by `reformat_update`.
+    tables: dict
+    total_host_execution_duration_micros: int
+
+    @classmethod
+    def from_json(cls, body):
+        return cls(
+            request_id=body["request_id"],
+            tables=reformat_update(body["database_update"]),
+            total_host_execution_duration_micros=body["total_host_execution_duration_micros"],
+        )
+
+
+@dataclasses.dataclass
+class TransactionUpdate:
+    # One of "Committed", "Failed" or "OutOfEnergy".
+    status: str
+    request_id: int
+    # The table updates, formatted by `reformat_update`. Empty unless the transaction committed.
+    tables: dict
+    # The error message if the reducer failed.
+    error: typing.Optional[str] = None
+    # The fields below are only sent to the caller of the reducer, i.e. in a full `TransactionUpdate`,
+    # and are `None` in a `TransactionUpdateLight`.
+    reducer: typing.Optional[str] = None
+    reducer_args: typing.Optional[list] = None
+    caller_identity: typing.Optional[str] = None
+    caller_address: typing.Optional[str] = None
+    timestamp: typing.Optional[int] = None
+    energy_quanta_used: typing.Optional[int] = None
+    host_execution_duration_micros: typing.Optional[int] = None
+
+    @classmethod
+    def from_json(cls, kind, body):
+        if kind == "TransactionUpdateLight":
+            return cls(status="Committed", request_id=body["request_id"], tables=reformat_update(body["update"]))
+
+        (status, value), = body["status"].items()
+        reducer_call = body["reducer_call"]
+        return cls(
+            status=status,
+            request_id=reducer_call["request_id"],
+            tables=reformat_update(value) if status == "Committed" else {},
+            error=value if status == "Failed" else None,
+            reducer=reducer_call["reducer_name"],
+            reducer_args=json.loads(reducer_call["args"]),
+            caller_identity=body["caller_identity"]["__identity__"],
+            caller_address=body["caller_address"]["__address__"],
+            timestamp=body["timestamp"]["microseconds"],
+            energy_quanta_used=body["energy_quanta_used"]["quanta"],
+            host_execution_duration_micros=body["host_execution_duration_micros"],
+        )
+
+
+# A client connected to a database over the websocket json protocol, with its own identity.
+# Created with `Smoketest.spawn_clients`.
+class Client:
+    # Version 1 of the protocol, which the host keeps serving to existing clients.
+    PROTOCOL = "v1.json.spacetimedb"
+
+    def __init__(self, ws, identity, token):
+        self.identity = identity
+        self.token = token
+        self._ws = ws
+        self._messages = queue.Queue()
+        self._next_request_id = 0
+        self._thread = threading.Thread(target=self._read_task, daemon=True)
+        self._thread.start()
+
+    @classmethod
+    def connect(cls, server_url, database_identity, timeout=10):
+        request = urllib.request.Request(f"{server_url}/identity", method="POST")
+        with urllib.request.urlopen(request, timeout=timeout) as response:
+            credentials = json.load(response)
+
+        ws_url = re.sub(r"^http", "ws", server_url)
+        ws = WebSocket(
+            f"{ws_url}/database/subscribe/{database_identity}",
+            protocol=cls.PROTOCOL,
+            headers={"Authorization": f"Bearer {credentials['token']}"},
+        )
+        client = cls(ws, credentials["identity"], credentials["token"])
+        # The server sends our identity once the connection is established,
+        # i.e. after the `client_connected` reducer has run.
+        client._recv_matching("IdentityToken", timeout)
+        return client
+
+    def _read_task(self):
+        try:
+            while (message := self._ws.recv()) is not None:
+                self._messages.put(json.loads(message))
+        finally:
+            self._messages.put(None)
+
+    def _send(self, message):
+        self._ws.send(json.dumps(message))
+
+    def _recv(self, timeout):
+        message = self._messages.get(timeout=timeout)
+        if message is None:
+            raise ConnectionError("websocket connection closed")
+        (kind, body), = message.items()
+        return kind, body
+
+    def _recv_matching(self, kind, timeout):
+        while True:
+            got, body = self._recv(timeout)
+            if got == kind:
+                return body
+            if got != "IdentityToken":
+                raise Exception(f"protocol error: expected {kind}, received {got}")
+
+    def subscribe(self, *queries, timeout=10):
+        """Subscribe to `queries` and wait for the initial update."""
+        request_id = self._request_id()
+        self._send({"Subscribe": {"query_strings": list(queries), "request_id": request_id}})
+        initial = InitialSubscription.from_json(self._recv_matching("InitialSubscription", timeout))
+        assert initial.request_id == request_id, f"expected request id {request_id}, got {initial.request_id}"
+        return initial
+
+    def call(self, reducer, *args):
+        """Request a call to `reducer` without waiting for it to complete. Returns the request id."""
+        request_id = self._request_id()
+        self._send({
+            "CallReducer": {
+                "reducer": reducer,
+                "args": json.dumps(args),
+                "request_id": request_id,
+                "flags": 0,
+            }
+        })
+        return request_id
+
+    def transaction_updates(self, n, timeout=10):
+        """
+        Wait for the next `n` transaction updates.
+
+        Note that a client always receives a full `TransactionUpdate` for its own reducer calls,
+        even if none of its subscribed rows changed or the reducer failed,
+        while other clients only receive a light update when their subscriptions are touched.
+        """
+        updates = []
+        while len(updates) < n:
+            kind, body = self._recv(timeout)
+            if kind in ("TransactionUpdate", "TransactionUpdateLight"):
+                updates.append(TransactionUpdate.from_json(kind, body))
+            elif kind != "IdentityToken":
+                raise Exception(f"protocol error: expected a transaction update, received {kind}")
+        return updates
+
+    def updates(self, n, timeout=10):
+        """
+        Wait for the next `n` transaction updates, and return their table updates.
+
+        Raises an exception if one of this client's own reducer calls did not commit.
+        """
+        updates = self.transaction_updates(n, timeout)
+        for update in updates:
+            if update.status != "Committed":
+                raise Exception(f"reducer {update.reducer} did not commit: {update.status} {update.error or ''}".strip())
+        return [update.tables for update in updates]
+
+    def _request_id(self):
+        self._next_request_id += 1
+        return self._next_request_id
+
+    def close(self):
+        self._ws.close()
+        self._thread.join(5)
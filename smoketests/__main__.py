@@ -1,6 +1,9 @@
 #!/usr/bin/env python
 
+import atexit
+import shutil
 import subprocess
+import tempfile
 import unittest
 import argparse
 import os
@@ -67,11 +70,16 @@ def main():
                         help='Only run tests which match the given substring')
     parser.add_argument("-x", dest="exclude", nargs="*", default=[])
     parser.add_argument("--no-build-cli", action="store_true", help="don't cargo build the cli")
+    parser.add_argument("--start-server", action="store_true", help="start a server for this test run, shared by all tests, instead of using one that's already running")
+    parser.add_argument("--test-mode", action="store_true", help="the server was started with --test-mode, so run the tests which require it")
     args = parser.parse_args()
 
     if not args.no_build_cli:
         logging.info("Compiling spacetime cli...")
-        smoketests.run_cmd("cargo", "build", "-pspacetimedb-cli", "-pspacetimedb-update", cwd=TEST_DIR.parent, capture_stderr=False)
+        packages = ["-pspacetimedb-cli", "-pspacetimedb-update"]
+        if args.start_server:
+            packages.append("-pspacetimedb-standalone")
+        smoketests.run_cmd("cargo", "build", *packages, cwd=TEST_DIR.parent, capture_stderr=False)
 
     update_bin_name = "spacetimedb-update" + exe_suffix
     try:
@@ -99,6 +107,13 @@ def main():
         subprocess.Popen(["docker", "logs", "-f", docker_container])
         smoketests.HAVE_DOCKER = True
 
+    if args.start_server:
+        data_dir = tempfile.mkdtemp(prefix="stdb-smoketests-")
+        atexit.register(shutil.rmtree, data_dir, ignore_errors=True)
+        smoketests.start_server(data_dir)
+
+    smoketests.HAVE_TEST_MODE = args.start_server or args.test_mode
+
     smoketests.new_identity(TEST_DIR / 'config.toml')
 
     if not args.skip_dotnet:
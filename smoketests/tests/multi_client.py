@@ -0,0 +1,72 @@
+from .. import Smoketest
+import threading
+
+class MultiClient(Smoketest):
+    MODULE_CODE = """
+use spacetimedb::{ReducerContext, Identity, Table};
+
+#[spacetimedb::table(name = message, public)]
+pub struct Message {
+    sender: Identity,
+    text: String,
+}
+
+#[spacetimedb::reducer]
+pub fn send(ctx: &ReducerContext, text: String) {
+    ctx.db.message().insert(Message { sender: ctx.sender, text });
+}
+
+#[spacetimedb::reducer]
+pub fn fail(_ctx: &ReducerContext) -> Result<(), String> {
+    Err("oops".into())
+}
+"""
+
+    def test_concurrent_calls(self):
+        """Check that concurrent reducer calls from several clients are seen by every subscriber"""
+
+        clients = self.spawn_clients(3)
+        self.assertEqual(len({client.identity for client in clients}), 3)
+
+        for client in clients:
+            self.assertEqual(client.subscribe("SELECT * FROM message").tables, {})
+
+        threads = [threading.Thread(target=client.call, args=("send", f"hello from {i}")) for i, client in enumerate(clients)]
+        for thread in threads:
+            thread.start()
+        for thread in threads:
+            thread.join()
+
+        expected = sorted(f"hello from {i}" for i in range(len(clients)))
+        for client in clients:
+            updates = client.updates(len(clients))
+            texts = sorted(row["text"] for update in updates for row in update["message"]["inserts"])
+            self.assertEqual(texts, expected)
+
+    def test_transaction_update_fields(self):
+        """Check the request ids, caller and energy fields of the transaction updates sent to the caller and to other subscribers"""
+
+        caller, observer = self.spawn_clients(2)
+        observer.subscribe("SELECT * FROM message")
+
+        request_id = caller.call("send", "hi")
+        update, = caller.transaction_updates(1)
+        self.assertEqual(update.status, "Committed")
+        self.assertEqual(update.request_id, request_id)
+        self.assertEqual(update.reducer, "send")
+        self.assertEqual(update.reducer_args, ["hi"])
+        self.assertEqual(int(update.caller_identity, 16), int(caller.identity, 16))
+        self.assertGreater(update.energy_quanta_used, 0)
+        # The caller isn't subscribed, so it doesn't see the inserted row.
+        self.assertEqual(update.tables, {})
+
+        light, = observer.transaction_updates(1)
+        self.assertEqual(light.status, "Committed")
+        self.assertIsNone(light.energy_quanta_used)
+        self.assertEqual([row["text"] for row in light.tables["message"]["inserts"]], ["hi"])
+
+        request_id = caller.call("fail")
+        update, = caller.transaction_updates(1)
+        self.assertEqual(update.status, "Failed")
+        self.assertEqual(update.request_id, request_id)
+        self.assertIn("oops", update.error)
@@ -1,4 +1,4 @@
-from .. import Smoketest
+from .. import Smoketest, requires_test_mode
 import time
 
 class CancelReducer(Smoketest):
@@ -148,3 +148,47 @@ fn do_insert(ctx: &ReducerContext, x: String) {
         self.call("do_schedule")
 
         self.assertEqual(sub(), [{'my_table': {'deletes': [], 'inserts': [{'x': 'yay!'}]}}, {'my_table': {'deletes': [], 'inserts': [{'x': 'hello'}]}}])
+
+
+@requires_test_mode
+class AdvanceTime(Smoketest):
+    MODULE_CODE = """
+use spacetimedb::{log, ReducerContext, Table, Timestamp};
+use std::time::Duration;
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[spacetimedb::table(name = cleanup, scheduled(do_cleanup))]
+pub struct Cleanup {
+    #[primary_key]
+    #[auto_inc]
+    scheduled_id: u64,
+    scheduled_at: spacetimedb::ScheduleAt,
+    scheduled_on: Timestamp,
+}
+
+#[spacetimedb::reducer]
+fn schedule_cleanup(ctx: &ReducerContext) {
+    ctx.db.cleanup().insert(Cleanup {
+        scheduled_id: 0,
+        scheduled_at: (ctx.timestamp + 30 * DAY).into(),
+        scheduled_on: ctx.timestamp,
+    });
+}
+
+#[spacetimedb::reducer]
+fn do_cleanup(ctx: &ReducerContext, args: Cleanup) {
+    let days = ctx.timestamp.duration_since(args.scheduled_on).unwrap().as_secs() / DAY.as_secs();
+    log::info!("cleaned up after {days} days");
+}
+"""
+
+    def test_advance_time(self):
+        """Ensure that advancing the clock runs reducers scheduled far in the future, and that they see the time pass"""
+
+        self.call("schedule_cleanup")
+        self.advance_time(29 * 24 * 60 * 60)
+        self.assertFalse(any("cleaned up" in line for line in self.logs(5)))
+
+        self.advance_time(24 * 60 * 60)
+        self.assertIn("cleaned up after 30 days", self.logs(5))
@@ -0,0 +1,51 @@
+from .. import Smoketest, requires_test_mode
+
+@requires_test_mode
+class Snapshot(Smoketest):
+    MODULE_CODE = """
+use spacetimedb::{ReducerContext, Table};
+
+#[spacetimedb::table(name = person, index(name = name_idx, btree(columns = [name])))]
+pub struct Person {
+    #[primary_key]
+    #[auto_inc]
+    id: u32,
+    name: String,
+}
+
+#[spacetimedb::reducer]
+pub fn add(ctx: &ReducerContext, name: String) {
+    ctx.db.person().insert(Person { id: 0, name });
+}
+"""
+
+    def names(self):
+        sql_out = self.spacetime("sql", self.database_identity, "SELECT name FROM person")
+        return sorted(line.strip() for line in sql_out.splitlines()[2:] if line.strip())
+
+    def test_restore_from_snapshot(self):
+        """Check that a database restored from a snapshot and the commitlog suffix matches its state before the restart"""
+
+        self.call("add", "Robert")
+        self.call("add", "Julie")
+
+        offset = self.force_snapshot()
+        self.assertIsNotNone(offset)
+        self.assertEqual(self.latest_snapshot_offset(), offset)
+
+        self.call("add", "Samantha")
+        later_offset = self.force_snapshot()
+        self.assertGreater(later_offset, offset)
+
+        before = self.names()
+        self.assertEqual(before, ['"Julie"', '"Robert"', '"Samantha"'])
+
+        self.restore_from_snapshot(offset)
+
+        self.assertEqual(self.names(), before)
+        self.assertEqual(self.latest_snapshot_offset(), offset)
+
+        # The restored database keeps working, including its indexes and sequences.
+        self.call("add", "Tom")
+        sql_out = self.spacetime("sql", self.database_identity, "SELECT id FROM person WHERE name = 'Tom'")
+        self.assertIn("4", sql_out)
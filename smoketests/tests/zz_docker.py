@@ -1,5 +1,7 @@
+import contextlib
+import subprocess
 import time
-from .. import Smoketest, run_cmd, requires_docker
+from .. import Smoketest, run_cmd, requires_docker, ReturnThread
 from urllib.request import urlopen
 from .add_remove_index import AddRemoveIndex
 
@@ -22,6 +24,75 @@ def restart_docker():
     # reach it from outside. Ping until we get through.
     ping()
 
+def kill_docker():
+    """Kill the server with SIGKILL, without giving it a chance to shut down cleanly, and bring it back up."""
+    run_cmd("docker", "compose", "kill", "--signal", "SIGKILL")
+    run_cmd("docker", "compose", "up", "--no-recreate", "--detach", "--wait-timeout", "60")
+    ping()
+
+def kill_during_reducer(test, reducer, *args, delay=1):
+    """
+    Call `reducer` and kill the server `delay` seconds into the call.
+
+    The reducer should run for longer than `delay`, so that it is interrupted before it commits.
+    Asserts that the call did not complete successfully.
+    """
+    call = ReturnThread(lambda: test.call(reducer, *args)).join
+    time.sleep(delay)
+    kill_docker()
+    with test.assertRaises(subprocess.CalledProcessError):
+        call()
+
+@contextlib.contextmanager
+def paused_docker():
+    """
+    Freeze all of the server's processes for the duration of the `with` block.
+
+    Nothing the server has not yet written or fsynced to disk makes progress while paused,
+    which lets tests observe clients and durability while the commitlog is stalled.
+    """
+    run_cmd("docker", "compose", "pause")
+    try:
+        yield
+    finally:
+        run_cmd("docker", "compose", "unpause")
+        ping()
+
+# The filesystem holding the databases' commitlogs and snapshots,
+# mounted by `crates/standalone/docker-dev-entrypoint.sh`.
+REPLICAS_DIR = "/stdb/data/replicas"
+
+def docker_exec(*args, **kwargs):
+    return run_cmd("docker", "compose", "exec", "node", *args, **kwargs)
+
+@contextlib.contextmanager
+def pause_commitlog_fsync():
+    """
+    Freeze the filesystem holding the commitlogs for the duration of the `with` block.
+
+    The server keeps running, but its writes and fsyncs to the commitlog block until the block exits,
+    so that transactions committed in the meantime are not yet durable.
+    """
+    docker_exec("fsfreeze", "--freeze", REPLICAS_DIR)
+    try:
+        yield
+    finally:
+        docker_exec("fsfreeze", "--unfreeze", REPLICAS_DIR)
+
+@contextlib.contextmanager
+def full_disk():
+    """
+    Fill up the filesystem holding the commitlogs and snapshots for the duration of the `with` block,
+    so that the server's writes to them fail with `ENOSPC`.
+    """
+    filler = f"{REPLICAS_DIR}/disk-full"
+    # `dd` exits with an error once the disk is full, which is the point.
+    docker_exec("dd", "if=/dev/zero", f"of={filler}", "bs=1M", "status=none", check=False)
+    try:
+        yield
+    finally:
+        docker_exec("rm", "-f", filler)
+
 def ping():
     tries = 0
     host = "127.0.0.1:3000"
@@ -209,3 +280,97 @@ class AddRemoveIndexAfterRestart(AddRemoveIndex):
     """
     def between_publishes(self):
         restart_docker()
+
+
+@requires_docker
+class DockerKillDuringReducer(Smoketest):
+    MODULE_CODE = """
+use spacetimedb::{log, ReducerContext, Table};
+
+#[spacetimedb::table(name = person)]
+pub struct Person {
+    name: String,
+}
+
+#[spacetimedb::reducer]
+pub fn add(ctx: &ReducerContext, name: String) {
+    ctx.db.person().insert(Person { name });
+}
+
+#[spacetimedb::reducer]
+pub fn add_many(ctx: &ReducerContext, n: u32) {
+    for i in 0..n {
+        ctx.db.person().insert(Person { name: format!("Clone {i}") });
+    }
+}
+
+#[spacetimedb::reducer]
+pub fn count(ctx: &ReducerContext) {
+    log::info!("PEOPLE: {}", ctx.db.person().count());
+}
+"""
+
+    def test_kill_during_reducer(self):
+        """Check that a transaction interrupted by a crash is rolled back, and committed ones are replayed"""
+
+        self.call("count")
+        before = int(self.logs(1)[0].removeprefix("PEOPLE: "))
+        self.call("add", "Robert")
+
+        kill_during_reducer(self, "add_many", 100_000_000)
+
+        self.call("count")
+        self.assertEqual(self.logs(1), [f"PEOPLE: {before + 1}"])
+
+    def test_paused_server(self):
+        """Check that calls made while the server is frozen complete once it resumes"""
+
+        self.call("count")
+        before = int(self.logs(1)[0].removeprefix("PEOPLE: "))
+
+        with paused_docker():
+            call = ReturnThread(lambda: self.call("add", "Julie")).join
+            time.sleep(1)
+        call()
+
+        kill_docker()
+
+        self.call("count")
+        self.assertEqual(self.logs(1), [f"PEOPLE: {before + 1}"])
+
+    def test_paused_commitlog_fsync(self):
+        """Check that calls made while the commitlog can't be written to are durable once it can"""
+
+        self.call("count")
+        before = int(self.logs(1)[0].removeprefix("PEOPLE: "))
+
+        with pause_commitlog_fsync():
+            call = ReturnThread(lambda: self.call("add", "Julie")).join
+            time.sleep(1)
+        call()
+
+        kill_docker()
+
+        self.call("count")
+        self.assertEqual(self.logs(1), [f"PEOPLE: {before + 1}"])
+
+    def test_full_disk(self):
+        """Check that the commitlog stays consistent when the disk fills up, and the server recovers once it frees up"""
+
+        self.call("count")
+        before = int(self.logs(1)[0].removeprefix("PEOPLE: "))
+        self.call("add", "Robert")
+
+        with full_disk():
+            # The call may fail, or commit without its transaction ever reaching the commitlog.
+            with contextlib.suppress(subprocess.CalledProcessError):
+                self.call("add", "Julie")
+
+        kill_docker()
+
+        self.call("count")
+        after = int(self.logs(1)[0].removeprefix("PEOPLE: "))
+        self.assertIn(after, [before + 1, before + 2])
+        self.call("add", "Samantha")
+        self.call("count")
+        self.assertEqual(self.logs(1), [f"PEOPLE: {after + 1}"])